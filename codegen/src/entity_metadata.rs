@@ -4,6 +4,7 @@ use proc_macro2::Span;
 use quote::quote;
 use std::collections::HashMap;
 use syn::braced;
+use syn::bracketed;
 use syn::parenthesized;
 use syn::parse::{Parse, ParseBuffer};
 use syn::Error;
@@ -38,6 +39,9 @@ struct Variant {
     ident: Ident,
     extends: Option<Ident>,
     entries: Vec<Entry>,
+    /// Indices in later versions of entries of the variant
+    /// it extends, which differ for this variant.
+    overrides: Vec<(Ident, LaterIndices)>,
 }
 
 impl Parse for Variant {
@@ -55,15 +59,25 @@ impl Parse for Variant {
         braced!(content in input);
 
         let mut entries = vec![];
-
-        while let Ok(entry) = content.parse::<Entry>() {
-            entries.push(entry);
+        let mut overrides = vec![];
+
+        while !content.is_empty() {
+            if content.peek2(Token![=]) {
+                let name = content.parse()?;
+                content.parse::<Token![=]>()?;
+                let later = content.parse()?;
+                content.parse::<Token![,]>()?;
+                overrides.push((name, later));
+            } else {
+                entries.push(content.parse::<Entry>()?);
+            }
         }
 
         Ok(Self {
             ident,
             extends,
             entries,
+            overrides,
         })
     }
 }
@@ -73,7 +87,9 @@ struct Entry {
     ty: EntryType,
     name: Ident,
     index: u8,
+    later: LaterIndices,
     default: Option<Lit>,
+    negative: bool,
 }
 
 impl Parse for Entry {
@@ -84,6 +100,7 @@ impl Parse for Entry {
 
         let paren;
         parenthesized!(paren in input);
+        let negative = paren.parse::<Option<Token![-]>>()?.is_some();
         let default = match paren.parse() {
             Ok(val) => Some(val),
             Err(_) => None,
@@ -95,6 +112,7 @@ impl Parse for Entry {
             Lit::Int(val) => val.base10_parse()?,
             _ => panic!("Index not a `u8`"),
         };
+        let later = input.parse()?;
 
         let _ = input.parse::<Token![,]>()?;
 
@@ -102,11 +120,54 @@ impl Parse for Entry {
             ty,
             name,
             index,
+            later,
             default,
+            negative,
         })
     }
 }
 
+/// The indices of an entry in each protocol version after 1.13.2,
+/// e.g. `[7, 8]`, with `_` for versions without the entry.
+#[derive(Clone)]
+struct LaterIndices(Vec<Option<u8>>);
+
+impl Parse for LaterIndices {
+    fn parse(input: &ParseBuffer) -> Result<Self, Error> {
+        let content;
+        bracketed!(content in input);
+
+        let mut indices = vec![];
+        while !content.is_empty() {
+            if content.parse::<Option<Token![_]>>()?.is_some() {
+                indices.push(None);
+            } else {
+                let index = match content.parse::<Lit>()? {
+                    Lit::Int(val) => val.base10_parse()?,
+                    _ => panic!("Index not a `u8`"),
+                };
+                indices.push(Some(index));
+            }
+
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self(indices))
+    }
+}
+
+impl LaterIndices {
+    fn to_tokens(&self) -> proc_macro2::TokenStream {
+        let indices = self.0.iter().map(|index| match index {
+            Some(index) => quote! { Some(#index) },
+            None => quote! { None },
+        });
+        quote! { [#(#indices),*] }
+    }
+}
+
 #[derive(PartialEq, Debug, Display, EnumString, Copy, Clone)]
 enum EntryType {
     Byte,
@@ -117,6 +178,9 @@ enum EntryType {
     Boolean,
     OptUuid,
     Position,
+    OptPosition,
+    OptChat,
+    OptBlockId,
 }
 
 impl Parse for EntryType {
@@ -138,6 +202,9 @@ impl EntryType {
             EntryType::Boolean => "bool",
             EntryType::OptUuid => "OptUuid",
             EntryType::Position => "BlockPosition",
+            EntryType::OptPosition => "OptPosition",
+            EntryType::OptChat => "OptChat",
+            EntryType::OptBlockId => "OptBlockId",
         }
     }
 
@@ -151,6 +218,9 @@ impl EntryType {
             "Slot" => EntryType::Slot,
             "OptUuid" => EntryType::OptUuid,
             "BlockPosition" => EntryType::Position,
+            "OptPosition" => EntryType::OptPosition,
+            "OptChat" => EntryType::OptChat,
+            "OptBlockId" => EntryType::OptBlockId,
             _ => panic!("Invalid entry type {}", ty),
         }
     }
@@ -182,6 +252,7 @@ pub fn entity_metadata(input: TokenStream) -> TokenStream {
         let mut new_fn_contents = vec![];
 
         let mut default_entries = vec![];
+        let mut layout = vec![];
 
         for entry in entries {
             let entry_ident = entry.name;
@@ -211,9 +282,15 @@ pub fn entity_metadata(input: TokenStream) -> TokenStream {
                 }
             });
 
-            let pass_reference = ty_enum == EntryType::Slot;
+            let pass_reference = match ty_enum {
+                EntryType::String | EntryType::OptChat => true,
+                _ => false,
+            };
 
             let index = entry.index;
+            let later = entry.later.to_tokens();
+            layout.push(quote! { (#index, #later), });
+
             let set_expr = if pass_reference {
                 quote! { meta.set(#index, self.#entry_ident.clone()); }
             } else {
@@ -238,21 +315,29 @@ pub fn entity_metadata(input: TokenStream) -> TokenStream {
                 #is_dirty_ident: true,
             });
 
-            to_raw_metadata_arms.push(quote! {
-                #enum_ident::#variant_ident(meta) => meta.to_raw_metadata(),
+            default_entries.push(match (entry.default, entry.negative) {
+                (Some(default), false) => {
+                    quote! { #entry_ident: #default, #is_dirty_ident: false, }
+                }
+                (Some(default), true) => {
+                    quote! { #entry_ident: -#default, #is_dirty_ident: false, }
+                }
+                (None, _) => quote! { #entry_ident: Default::default(), #is_dirty_ident: false, },
             });
+        }
 
-            to_full_raw_metadata_arms.push(quote! {
-                #enum_ident::#variant_ident(meta) => meta.to_full_raw_metadata(),
-            });
+        to_raw_metadata_arms.push(quote! {
+            #enum_ident::#variant_ident(meta) => meta.to_raw_metadata(),
+        });
 
-            default_entries.push(match entry.default {
-                Some(default) => quote! { #entry_ident: #default, #is_dirty_ident: false, },
-                None => quote! { #entry_ident: Default::default(), #is_dirty_ident: false, },
-            });
-        }
+        to_full_raw_metadata_arms.push(quote! {
+            #enum_ident::#variant_ident(meta) => meta.to_full_raw_metadata(),
+        });
 
         struct_impl.push(quote! {
+            /// The indices of the entries in later protocol versions.
+            pub const LAYOUT: MetadataLayout = MetadataLayout(&[#(#layout)*]);
+
             pub fn new(#(#new_fn_parameters),*) -> Self {
                 Self {
                     #(#new_fn_contents)*
@@ -260,13 +345,13 @@ pub fn entity_metadata(input: TokenStream) -> TokenStream {
             }
 
             fn to_raw_metadata(&mut self) -> EntityMetadata {
-                let mut meta = EntityMetadata::new();
+                let mut meta = EntityMetadata::new().with_layout(Self::LAYOUT);
                 #(#to_raw_metadata)*
                 meta
             }
 
             fn to_full_raw_metadata(&self) -> EntityMetadata {
-                let mut meta = EntityMetadata::new();
+                let mut meta = EntityMetadata::new().with_layout(Self::LAYOUT);
                 #(#to_full_raw_metadata)*
                 meta
             }
@@ -296,6 +381,46 @@ pub fn entity_metadata(input: TokenStream) -> TokenStream {
         })
     }
 
+    // The entries of the variant which all others extend
+    // can be accessed without matching on the variant.
+    let mut common_fns = vec![];
+    if let Some(base) = input
+        .variants
+        .values()
+        .find(|variant| variant.extends.is_none())
+    {
+        for entry in &base.entries {
+            let entry_ident = &entry.name;
+            let ty_ident = Ident::new(entry.ty.rust_type(), Span::call_site());
+            let set_fn_ident = Ident::new(&format!("set_{}", entry_ident), Span::call_site());
+
+            let mut set_arms = vec![];
+            let mut get_arms = vec![];
+            for variant_ident in input.variants.keys() {
+                set_arms.push(quote! {
+                    #enum_ident::#variant_ident(meta) => meta.#set_fn_ident(val),
+                });
+                get_arms.push(quote! {
+                    #enum_ident::#variant_ident(meta) => meta.#entry_ident(),
+                });
+            }
+
+            common_fns.push(quote! {
+                pub fn #set_fn_ident(&mut self, val: #ty_ident) {
+                    match self {
+                        #(#set_arms)*
+                    }
+                }
+
+                pub fn #entry_ident(&self) -> #ty_ident {
+                    match self {
+                        #(#get_arms)*
+                    }
+                }
+            });
+        }
+    }
+
     let result = quote! {
         #[derive(Clone, Debug)]
         pub enum #enum_ident {
@@ -314,6 +439,8 @@ pub fn entity_metadata(input: TokenStream) -> TokenStream {
                     #(#to_full_raw_metadata_arms)*
                 }
             }
+
+            #(#common_fns)*
         }
 
         #(#structs)*
@@ -330,6 +457,13 @@ fn get_metadata_entries(metadata: &EntityMetadata, variant: Variant) -> Vec<Entr
         entries.extend(get_metadata_entries(metadata, inherits_from.clone()).into_iter());
     }
 
+    for (name, later) in variant.overrides {
+        match entries.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => entry.later = later,
+            None => panic!("No entry {} to override in {}", name, variant.ident),
+        }
+    }
+
     entries.extend(variant.entries.into_iter());
     entries
 }
@@ -163,9 +163,30 @@ pub fn derive_packet(_item: TokenStream) -> TokenStream {
             .contains(parameter_type)
         };
 
+        // Types whose encoding differs between protocol versions.
+        let versioned_write = [
+            PacketParameterType::Position,
+            PacketParameterType::Slot,
+            PacketParameterType::EntityMetadata,
+        ]
+        .contains(parameter_type);
+        let versioned_read =
+            [PacketParameterType::Position, PacketParameterType::Slot].contains(parameter_type);
+
         let write;
 
-        if use_ref {
+        if versioned_write {
+            let write_fn_ident = Ident::new(&format!("{}_for", write_fn_ident), Span::call_site());
+            if use_ref {
+                write = quote! {
+                    buf.#write_fn_ident(&self.#field_name, version);
+                };
+            } else {
+                write = quote! {
+                    buf.#write_fn_ident(self.#field_name, version);
+                };
+            }
+        } else if use_ref {
             write = quote! {
                 buf.#write_fn_ident(&self.#field_name);
             };
@@ -177,9 +198,16 @@ pub fn derive_packet(_item: TokenStream) -> TokenStream {
 
         let read;
 
-        read = quote! {
-            self.#field_name = buf.#read_fn_ident()?;
-        };
+        if versioned_read {
+            let read_fn_ident = Ident::new(&format!("{}_for", read_fn_ident), Span::call_site());
+            read = quote! {
+                self.#field_name = buf.#read_fn_ident(version)?;
+            };
+        } else {
+            read = quote! {
+                self.#field_name = buf.#read_fn_ident()?;
+            };
+        }
 
         write_code.push(write);
         read_code.push(read);
@@ -187,12 +215,26 @@ pub fn derive_packet(_item: TokenStream) -> TokenStream {
 
     let r = quote! {
         impl Packet for #ident {
-            fn read_from(&mut self, mut buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+            fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+                self.read_from_version(buf, ProtocolVersion::V1_13_2)
+            }
+
+            fn write_to(&self, buf: &mut BytesMut) {
+                self.write_to_version(buf, ProtocolVersion::V1_13_2)
+            }
+
+            #[allow(unused_variables)]
+            fn read_from_version(
+                &mut self,
+                mut buf: &mut Cursor<&[u8]>,
+                version: ProtocolVersion,
+            ) -> anyhow::Result<()> {
                 #(#read_code)*
                 Ok(())
             }
 
-            fn write_to(&self, buf: &mut BytesMut) {
+            #[allow(unused_variables)]
+            fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
                 #(#write_code)*
             }
 
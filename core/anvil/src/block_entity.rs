@@ -1,4 +1,5 @@
 use crate::player::InventorySlot;
+use crate::region::LevelPaletteEntry;
 use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -79,6 +80,9 @@ pub enum BlockEntityKind {
         update_last_execution: bool,
         last_execution: i64,
     },
+    #[serde(rename = "minecraft:conduit")]
+    #[serde(rename_all = "PascalCase")]
+    Conduit, // the target isn't saved
     #[serde(rename = "minecraft:daylight_detector")]
     #[serde(rename_all = "PascalCase")]
     DaylightDetector, // empty
@@ -103,6 +107,7 @@ pub enum BlockEntityKind {
     #[serde(rename = "minecraft:furnace")]
     #[serde(rename_all = "PascalCase")]
     Furnace {
+        #[serde(default)]
         items: Vec<InventorySlot>,
         burn_time: i16,
         cook_time: i16,
@@ -125,7 +130,53 @@ pub enum BlockEntityKind {
     },
     #[serde(rename = "minecraft:jukebox")]
     #[serde(rename_all = "PascalCase")]
-    Jukebox { record_item: InventorySlot },
+    Jukebox { record_item: Option<InventorySlot> },
+    #[serde(rename = "minecraft:mob_spawner")]
+    #[serde(rename_all = "PascalCase")]
+    MobSpawner {
+        /// The entity to spawn next.
+        #[serde(default)]
+        spawn_data: Option<SpawnData>,
+        /// Ticks until the next spawn.
+        delay: i16,
+        min_spawn_delay: i16,
+        max_spawn_delay: i16,
+        spawn_count: i16,
+        max_nearby_entities: i16,
+        required_player_range: i16,
+        spawn_range: i16,
+    },
+    #[serde(rename = "minecraft:piston")]
+    #[serde(rename_all = "camelCase")]
+    Piston {
+        /// The block being moved.
+        block_state: LevelPaletteEntry,
+        /// The direction the piston faces, as a 3D data value.
+        facing: i32,
+        /// How far the block has moved, from 0 to 1.
+        progress: f32,
+        extending: bool,
+        /// Whether this is the piston or its head
+        /// rather than a block it moves.
+        source: bool,
+    },
+    #[serde(rename = "minecraft:shulker_box")]
+    #[serde(rename_all = "PascalCase")]
+    ShulkerBox {
+        #[serde(default)]
+        items: Vec<InventorySlot>,
+        loot_table: Option<String>,
+        loot_table_seed: Option<i64>,
+    },
+    #[serde(rename = "minecraft:sign")]
+    #[serde(rename_all = "PascalCase")]
+    Sign {
+        /// Each line of text, as a JSON text component.
+        text1: String,
+        text2: String,
+        text3: String,
+        text4: String,
+    },
     // TODO: a few more
     /// Fallback type for unknown block entities
     #[serde(other, serialize_with = "BlockEntityKind::serialize_unknown")]
@@ -145,6 +196,7 @@ impl BlockEntityKind {
             BlockEntityKind::Cauldron { .. } => BlockEntityVariant::Cauldron,
             BlockEntityKind::Comparator { .. } => BlockEntityVariant::Comparator,
             BlockEntityKind::CommandBlock { .. } => BlockEntityVariant::CommandBlock,
+            BlockEntityKind::Conduit { .. } => BlockEntityVariant::Conduit,
             BlockEntityKind::Chest { .. } => BlockEntityVariant::Chest,
             BlockEntityKind::DaylightDetector { .. } => BlockEntityVariant::DaylightDetector,
             BlockEntityKind::Dispenser { .. } => BlockEntityVariant::Dispenser,
@@ -157,6 +209,10 @@ impl BlockEntityKind {
             BlockEntityKind::Hopper { .. } => BlockEntityVariant::Hopper,
             BlockEntityKind::Jigsaw { .. } => BlockEntityVariant::Jigsaw,
             BlockEntityKind::Jukebox { .. } => BlockEntityVariant::Jukebox,
+            BlockEntityKind::MobSpawner { .. } => BlockEntityVariant::MobSpawner,
+            BlockEntityKind::Piston { .. } => BlockEntityVariant::Piston,
+            BlockEntityKind::ShulkerBox { .. } => BlockEntityVariant::ShulkerBox,
+            BlockEntityKind::Sign { .. } => BlockEntityVariant::Sign,
             BlockEntityKind::Unknown { .. } => BlockEntityVariant::Unknown,
         }
     }
@@ -172,6 +228,7 @@ pub enum BlockEntityVariant {
     Chest,
     Comparator,
     CommandBlock,
+    Conduit,
     DaylightDetector,
     Dispenser,
     Dropper,
@@ -183,5 +240,16 @@ pub enum BlockEntityVariant {
     Hopper,
     Jigsaw,
     Jukebox,
+    MobSpawner,
+    Piston,
+    ShulkerBox,
+    Sign,
     Unknown,
 }
+
+/// The entity a spawner spawns next.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnData {
+    /// The namespaced ID of the entity.
+    pub id: String,
+}
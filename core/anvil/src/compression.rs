@@ -0,0 +1,319 @@
+//! Compression of chunks in region files.
+//!
+//! Each chunk in a region file starts with a byte indicating
+//! its compression type: 1 for gzip, 2 for zlib, 3 for no
+//! compression, and 4 for LZ4. Type 127 is reserved for custom
+//! algorithms, whose name precedes the compressed data; Feather
+//! uses it for zstd.
+
+use crate::region::Error;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use flate2::read::{GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder};
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::str::FromStr;
+use thiserror::Error;
+use twox_hash::XxHash32;
+
+/// Compression type of chunks compressed with a custom algorithm.
+const CUSTOM: u8 = 127;
+
+/// Name of the zstd algorithm in chunks of the custom type.
+const ZSTD_NAME: &str = "zstd";
+
+/// The compression used when writing chunks to region files.
+///
+/// Chunks are read regardless of how they were compressed.
+/// zlib is what vanilla writes; LZ4 and zstd are much faster
+/// to compress, but only LZ4 can be read by vanilla servers
+/// (1.20.5 and newer).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RegionCompression {
+    Gzip,
+    Zlib,
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Default for RegionCompression {
+    fn default() -> Self {
+        RegionCompression::Zlib
+    }
+}
+
+impl RegionCompression {
+    /// Returns the compression type byte of chunks
+    /// compressed with this compression.
+    pub fn id(self) -> u8 {
+        match self {
+            RegionCompression::Gzip => 1,
+            RegionCompression::Zlib => 2,
+            RegionCompression::None => 3,
+            RegionCompression::Lz4 => 4,
+            RegionCompression::Zstd => CUSTOM,
+        }
+    }
+
+    /// Returns the name of this compression, as used in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            RegionCompression::Gzip => "gzip",
+            RegionCompression::Zlib => "zlib",
+            RegionCompression::None => "none",
+            RegionCompression::Lz4 => "lz4",
+            RegionCompression::Zstd => "zstd",
+        }
+    }
+
+    /// Compresses the data of a chunk. The result follows
+    /// the compression type byte in the region file.
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(data.len() / 2);
+        match self {
+            RegionCompression::Gzip => {
+                GzEncoder::new(data, flate2::Compression::default()).read_to_end(&mut buf)?;
+            }
+            RegionCompression::Zlib => {
+                ZlibEncoder::new(data, flate2::Compression::default()).read_to_end(&mut buf)?;
+            }
+            RegionCompression::None => buf.extend_from_slice(data),
+            RegionCompression::Lz4 => lz4::encode(data, &mut buf),
+            RegionCompression::Zstd => {
+                write_custom_name(&mut buf, ZSTD_NAME);
+                buf.extend(zstd::stream::encode_all(data, 0)?);
+            }
+        }
+        Ok(buf)
+    }
+}
+
+impl Display for RegionCompression {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid region compression {0} (expected gzip, zlib, none, lz4 or zstd)")]
+pub struct RegionCompressionParseError(String);
+
+impl FromStr for RegionCompression {
+    type Err = RegionCompressionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(RegionCompression::Gzip),
+            "zlib" => Ok(RegionCompression::Zlib),
+            "none" => Ok(RegionCompression::None),
+            "lz4" => Ok(RegionCompression::Lz4),
+            "zstd" => Ok(RegionCompression::Zstd),
+            _ => Err(RegionCompressionParseError(s.to_owned())),
+        }
+    }
+}
+
+/// Decompresses the data of a chunk with the given compression type.
+pub fn decompress(compression_type: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(data.len() * 4);
+    match compression_type {
+        1 => GzDecoder::new(data).read_to_end(&mut buf).map(|_| ()),
+        2 => ZlibDecoder::new(data).read_to_end(&mut buf).map(|_| ()),
+        3 => return Ok(data.to_vec()),
+        4 => lz4::decode(data, &mut buf),
+        CUSTOM => {
+            let (name, data) = read_custom_name(data).map_err(Error::Io)?;
+            if name != ZSTD_NAME {
+                return Err(Error::InvalidCustomCompression(name.to_owned()));
+            }
+            return zstd::stream::decode_all(data).map_err(Error::Io);
+        }
+        _ => return Err(Error::InvalidCompression(compression_type)),
+    }
+    .map_err(Error::Io)?;
+
+    Ok(buf)
+}
+
+/// Writes the name of a custom compression algorithm,
+/// prefixed by its length like Java's `writeUTF`.
+fn write_custom_name(buf: &mut Vec<u8>, name: &str) {
+    let mut len = [0; 2];
+    BigEndian::write_u16(&mut len, name.len() as u16);
+    buf.extend_from_slice(&len);
+    buf.extend_from_slice(name.as_bytes());
+}
+
+/// Reads the name of a custom compression algorithm,
+/// returning it and the compressed data following it.
+fn read_custom_name(data: &[u8]) -> io::Result<(&str, &[u8])> {
+    if data.len() < 2 {
+        return Err(invalid_data("missing custom compression name"));
+    }
+    let len = BigEndian::read_u16(data) as usize;
+    let data = &data[2..];
+    if data.len() < len {
+        return Err(invalid_data("missing custom compression name"));
+    }
+
+    let name = std::str::from_utf8(&data[..len])
+        .map_err(|_| invalid_data("custom compression name is not valid UTF-8"))?;
+    Ok((name, &data[len..]))
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// The LZ4 block stream format of lz4-java's `LZ4BlockOutputStream`,
+/// which vanilla uses for chunks of compression type 4.
+///
+/// The stream consists of blocks of at most 64 KiB of data,
+/// each with a header containing its lengths and a checksum,
+/// and ends with an empty block.
+mod lz4 {
+    use super::*;
+
+    const MAGIC: &[u8; 8] = b"LZ4Block";
+    const HEADER_LEN: usize = MAGIC.len() + 13;
+
+    const METHOD_RAW: u8 = 0x10;
+    const METHOD_LZ4: u8 = 0x20;
+
+    const BLOCK_SIZE: usize = 1 << 16;
+    /// The largest block lz4-java writes.
+    const MAX_BLOCK_SIZE: usize = 1 << 25;
+    /// The compression level lz4-java derives from the block size,
+    /// stored in the low bits of the header token.
+    const LEVEL: u8 = 6;
+
+    const CHECKSUM_SEED: u32 = 0x9747_b28c;
+
+    pub fn encode(data: &[u8], buf: &mut Vec<u8>) {
+        for block in data.chunks(BLOCK_SIZE) {
+            let compressed = lz4_flex::compress(block);
+            // Blocks which don't compress well are stored as is.
+            let (method, payload) = if compressed.len() < block.len() {
+                (METHOD_LZ4, &compressed[..])
+            } else {
+                (METHOD_RAW, block)
+            };
+
+            write_header(buf, method, payload.len(), block.len(), checksum(block));
+            buf.extend_from_slice(payload);
+        }
+
+        write_header(buf, METHOD_RAW, 0, 0, 0);
+    }
+
+    pub fn decode(mut data: &[u8], buf: &mut Vec<u8>) -> io::Result<()> {
+        loop {
+            if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+                return Err(invalid_data("invalid LZ4 block header"));
+            }
+            let token = data[8];
+            let compressed_len = LittleEndian::read_u32(&data[9..]) as usize;
+            let original_len = LittleEndian::read_u32(&data[13..]) as usize;
+            let expected_checksum = LittleEndian::read_u32(&data[17..]);
+            data = &data[HEADER_LEN..];
+
+            // The stream ends with an empty block.
+            if original_len == 0 {
+                return Ok(());
+            }
+            if compressed_len > data.len() || original_len > MAX_BLOCK_SIZE {
+                return Err(invalid_data("invalid LZ4 block length"));
+            }
+            let (payload, rest) = data.split_at(compressed_len);
+            data = rest;
+
+            let block = match token & 0xF0 {
+                METHOD_RAW => payload.to_vec(),
+                METHOD_LZ4 => lz4_flex::decompress(payload, original_len)
+                    .map_err(|_| invalid_data("invalid LZ4 block"))?,
+                _ => return Err(invalid_data("invalid LZ4 compression method")),
+            };
+            if block.len() != original_len || checksum(&block) != expected_checksum {
+                return Err(invalid_data("LZ4 block checksum mismatch"));
+            }
+
+            buf.extend_from_slice(&block);
+        }
+    }
+
+    fn write_header(
+        buf: &mut Vec<u8>,
+        method: u8,
+        compressed_len: usize,
+        original_len: usize,
+        checksum: u32,
+    ) {
+        let mut header = [0; HEADER_LEN];
+        header[..MAGIC.len()].copy_from_slice(MAGIC);
+        header[8] = method | LEVEL;
+        LittleEndian::write_u32(&mut header[9..], compressed_len as u32);
+        LittleEndian::write_u32(&mut header[13..], original_len as u32);
+        LittleEndian::write_u32(&mut header[17..], checksum);
+        buf.extend_from_slice(&header);
+    }
+
+    /// The XXHash32 of a block, truncated to 28 bits like
+    /// lz4-java's checksums.
+    fn checksum(block: &[u8]) -> u32 {
+        let mut hasher = XxHash32::with_seed(CHECKSUM_SEED);
+        hasher.write(block);
+        hasher.finish() as u32 & 0x0FFF_FFFF
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        // Large enough to span multiple LZ4 blocks.
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+        for &compression in &[
+            RegionCompression::Gzip,
+            RegionCompression::Zlib,
+            RegionCompression::None,
+            RegionCompression::Lz4,
+            RegionCompression::Zstd,
+        ] {
+            let compressed = compression.compress(&data).unwrap();
+            let decompressed = decompress(compression.id(), &compressed).unwrap();
+            assert_eq!(decompressed, data, "{}", compression);
+        }
+    }
+
+    #[test]
+    fn invalid_chunks() {
+        assert!(matches!(
+            decompress(5, &[]),
+            Err(Error::InvalidCompression(5))
+        ));
+
+        let mut custom = Vec::new();
+        write_custom_name(&mut custom, "brotli");
+        assert!(matches!(
+            decompress(CUSTOM, &custom),
+            Err(Error::InvalidCustomCompression(_))
+        ));
+
+        let mut lz4 = RegionCompression::Lz4.compress(b"some chunk data").unwrap();
+        lz4[17] ^= 1;
+        assert!(decompress(4, &lz4).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "lz4".parse::<RegionCompression>().unwrap(),
+            RegionCompression::Lz4
+        );
+        assert!("deflate".parse::<RegionCompression>().is_err());
+    }
+}
@@ -1,9 +1,12 @@
+use crate::player::InventorySlot;
 use arrayvec::ArrayVec;
-use feather_items::{Item, ItemStack};
-use feather_util::{vec3, Position, Vec3d};
+use feather_items::{Enchantment, Enchantments, Item, ItemName, ItemStack, Potion};
+use feather_text::{Text, TextRoot};
+use feather_util::{vec3, BlockPosition, Position, Vec3d};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EntityDataKind {
@@ -19,6 +22,14 @@ pub enum EntityDataKind {
     Rabbit,
     Squid,
     Donkey,
+    Villager,
+    ItemFrame,
+    Painting,
+    ArmorStand,
+    Boat,
+    Minecart,
+    ChestMinecart,
+    HopperMinecart,
     Unknown,
 }
 
@@ -37,6 +48,14 @@ impl<'a> From<&'a EntityData> for EntityDataKind {
             EntityData::Rabbit(_) => EntityDataKind::Rabbit,
             EntityData::Squid(_) => EntityDataKind::Squid,
             EntityData::Donkey(_) => EntityDataKind::Donkey,
+            EntityData::Villager(_) => EntityDataKind::Villager,
+            EntityData::ItemFrame(_) => EntityDataKind::ItemFrame,
+            EntityData::Painting(_) => EntityDataKind::Painting,
+            EntityData::ArmorStand(_) => EntityDataKind::ArmorStand,
+            EntityData::Boat(_) => EntityDataKind::Boat,
+            EntityData::Minecart(_) => EntityDataKind::Minecart,
+            EntityData::ChestMinecart(_) => EntityDataKind::ChestMinecart,
+            EntityData::HopperMinecart(_) => EntityDataKind::HopperMinecart,
             EntityData::Unknown => EntityDataKind::Unknown,
         }
     }
@@ -52,23 +71,39 @@ pub enum EntityData {
     #[serde(rename = "minecraft:cow")]
     Cow(AnimalData),
     #[serde(rename = "minecraft:pig")]
-    Pig(AnimalData),
+    Pig(PigData),
     #[serde(rename = "minecraft:chicken")]
     Chicken(AnimalData),
     #[serde(rename = "minecraft:sheep")]
-    Sheep(AnimalData),
+    Sheep(SheepData),
     #[serde(rename = "minecraft:horse")]
-    Horse(AnimalData),
+    Horse(HorseData),
     #[serde(rename = "minecraft:llama")]
     Llama(AnimalData),
-    #[serde(rename = "minectaft:mooshroom")]
+    #[serde(rename = "minecraft:mooshroom")]
     Mooshroom(AnimalData),
-    #[serde(rename = "minecraft:rabbit}")]
+    #[serde(rename = "minecraft:rabbit")]
     Rabbit(AnimalData),
     #[serde(rename = "minecraft:squid")]
     Squid(AnimalData),
     #[serde(rename = "minecraft:donkey")]
     Donkey(AnimalData),
+    #[serde(rename = "minecraft:villager")]
+    Villager(VillagerData),
+    #[serde(rename = "minecraft:item_frame")]
+    ItemFrame(ItemFrameEntityData),
+    #[serde(rename = "minecraft:painting")]
+    Painting(PaintingEntityData),
+    #[serde(rename = "minecraft:armor_stand")]
+    ArmorStand(ArmorStandEntityData),
+    #[serde(rename = "minecraft:boat")]
+    Boat(BoatEntityData),
+    #[serde(rename = "minecraft:minecart")]
+    Minecart(MinecartEntityData),
+    #[serde(rename = "minecraft:chest_minecart")]
+    ChestMinecart(ChestMinecartEntityData),
+    #[serde(rename = "minecraft:hopper_minecart")]
+    HopperMinecart(HopperMinecartEntityData),
 
     /// Fallback type for unknown entities
     #[serde(other, serialize_with = "EntityData::serialize_unknown")]
@@ -150,22 +185,238 @@ pub struct AnimalData {
     pub base: BaseEntityData,
     #[serde(rename = "Health")]
     pub health: f32,
+    /// Most significant bits of the entity's UUID.
+    #[serde(rename = "UUIDMost", default)]
+    pub uuid_most: Option<i64>,
+    /// Least significant bits of the entity's UUID.
+    #[serde(rename = "UUIDLeast", default)]
+    pub uuid_least: Option<i64>,
 }
 
 impl AnimalData {
     /// Creates an `AnimalData` from its parameters.
     pub fn new(base: BaseEntityData, health: f32) -> Self {
-        Self { base, health }
+        Self {
+            base,
+            health,
+            uuid_most: None,
+            uuid_least: None,
+        }
+    }
+
+    /// Sets the UUID of the entity.
+    pub fn with_uuid(mut self, uuid: Uuid) -> Self {
+        let bits = uuid.as_u128();
+        self.uuid_most = Some((bits >> 64) as i64);
+        self.uuid_least = Some(bits as i64);
+        self
+    }
+
+    /// Returns the UUID of the entity, if it is stored.
+    pub fn uuid(&self) -> Option<Uuid> {
+        let most = u128::from(self.uuid_most? as u64);
+        let least = u128::from(self.uuid_least? as u64);
+        Some(Uuid::from_u128(most << 64 | least))
     }
 }
 
 impl Default for AnimalData {
     fn default() -> Self {
-        AnimalData {
-            base: Default::default(),
-            health: 20.0,
+        AnimalData::new(Default::default(), 20.0)
+    }
+}
+
+/// Data for a pig (`minecraft:pig`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PigData {
+    #[serde(flatten)]
+    pub animal: AnimalData,
+    // TODO: Change this field to `bool` when issue with hematite_nbt is resolved.
+    // See: https://github.com/PistonDevelopers/hematite_nbt/issues/43
+    #[serde(rename = "Saddle", default)]
+    pub saddle: i8,
+}
+
+/// Data for a horse (`minecraft:horse`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HorseData {
+    #[serde(flatten)]
+    pub animal: AnimalData,
+    #[serde(rename = "Tame", default)]
+    pub tame: i8,
+    /// Raised each time the horse throws off a player
+    /// trying to tame it, up to 100.
+    #[serde(rename = "Temper", default)]
+    pub temper: i32,
+    /// UUID of the player who tamed the horse, with hyphens.
+    #[serde(rename = "OwnerUUID", default)]
+    pub owner_uuid: Option<String>,
+    #[serde(rename = "SaddleItem", default)]
+    pub saddle_item: Option<ItemData>,
+}
+
+impl HorseData {
+    /// Returns the UUID of the player who tamed the horse, if any.
+    pub fn owner(&self) -> Option<Uuid> {
+        Uuid::parse_str(self.owner_uuid.as_deref()?).ok()
+    }
+}
+
+/// Data for a sheep (`minecraft:sheep`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SheepData {
+    #[serde(flatten)]
+    pub animal: AnimalData,
+    /// The dye color of the wool, from 0 (white) to 15 (black).
+    #[serde(rename = "Color", default)]
+    pub color: i8,
+    #[serde(rename = "Sheared", default)]
+    pub sheared: i8,
+}
+
+/// Data for a villager (`minecraft:villager`), in the
+/// format of 1.14 and later.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VillagerData {
+    #[serde(flatten)]
+    pub animal: AnimalData,
+    #[serde(rename = "VillagerData", default)]
+    pub kind: VillagerKindData,
+    /// Experience gained from trading.
+    #[serde(rename = "Xp", default)]
+    pub xp: i32,
+    #[serde(rename = "Offers", default)]
+    pub offers: Option<TradeOffersData>,
+    #[serde(rename = "Gossips", default)]
+    pub gossips: Vec<GossipData>,
+    /// The game time of the last restock.
+    #[serde(rename = "LastRestock", default)]
+    pub last_restock: i64,
+    #[serde(rename = "RestocksToday", default)]
+    pub restocks_today: i32,
+    #[serde(rename = "Brain", default)]
+    pub brain: BrainData,
+}
+
+/// The profession, level and biome type of a villager.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VillagerKindData {
+    /// The namespaced identifier of the profession,
+    /// e.g. `minecraft:librarian`.
+    pub profession: String,
+    pub level: i32,
+    #[serde(rename = "type")]
+    pub biome_type: String,
+}
+
+impl Default for VillagerKindData {
+    fn default() -> Self {
+        Self {
+            profession: String::from("minecraft:none"),
+            level: 1,
+            biome_type: String::from("minecraft:plains"),
+        }
+    }
+}
+
+/// What a mob remembers. Only the memories
+/// used by the server are kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BrainData {
+    #[serde(default)]
+    pub memories: MemoriesData,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoriesData {
+    /// The job site claimed by a villager.
+    #[serde(
+        rename = "minecraft:job_site",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub job_site: Option<MemoryData<GlobalPositionData>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryData<T> {
+    pub value: T,
+}
+
+/// A block position in a dimension.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalPositionData {
+    #[serde(with = "crate::poi::block_position")]
+    pub pos: BlockPosition,
+    /// The identifier of the dimension's type,
+    /// e.g. `minecraft:overworld`.
+    pub dimension: String,
+}
+
+/// The trades offered by a merchant.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeOffersData {
+    #[serde(rename = "Recipes", default)]
+    pub recipes: Vec<TradeOfferData>,
+}
+
+/// A trade offered by a merchant.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeOfferData {
+    pub buy: ItemData,
+    #[serde(rename = "buyB", default)]
+    pub buy_b: Option<ItemData>,
+    pub sell: ItemData,
+    #[serde(default)]
+    pub uses: i32,
+    #[serde(rename = "maxUses", default)]
+    pub max_uses: i32,
+    /// Whether the trade gives experience orbs to players.
+    #[serde(rename = "rewardExp", default)]
+    pub reward_exp: i8,
+    /// Experience the merchant gains from the trade.
+    #[serde(default)]
+    pub xp: i32,
+    #[serde(rename = "priceMultiplier", default)]
+    pub price_multiplier: f32,
+    #[serde(rename = "specialPrice", default)]
+    pub special_price: i32,
+    #[serde(default)]
+    pub demand: i32,
+}
+
+/// A villager's gossip about a player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GossipData {
+    /// The kind of gossip, e.g. `trading`.
+    #[serde(rename = "Type")]
+    pub kind: String,
+    #[serde(rename = "Value")]
+    pub value: i32,
+    #[serde(rename = "TargetMost")]
+    pub target_most: i64,
+    #[serde(rename = "TargetLeast")]
+    pub target_least: i64,
+}
+
+impl GossipData {
+    /// Creates gossip about the player with the given UUID.
+    pub fn new(kind: impl Into<String>, value: i32, target: Uuid) -> Self {
+        let bits = target.as_u128();
+        Self {
+            kind: kind.into(),
+            value,
+            target_most: (bits >> 64) as i64,
+            target_least: bits as i64,
         }
     }
+
+    /// Returns the UUID of the player the gossip is about.
+    pub fn target(&self) -> Uuid {
+        let most = u128::from(self.target_most as u64);
+        let least = u128::from(self.target_least as u64);
+        Uuid::from_u128(most << 64 | least)
+    }
 }
 
 /// Represents a single item, without slot information.
@@ -231,17 +482,83 @@ where
 pub struct ItemNbt {
     #[serde(rename = "Damage")]
     pub damage: Option<i32>,
-    // TODO enchantments, display name, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<i32>,
+    #[serde(rename = "Potion", default, skip_serializing_if = "Option::is_none")]
+    pub potion: Option<String>,
+    #[serde(
+        rename = "Enchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub enchantments: Vec<EnchantmentNbt>,
+    /// Enchantments stored in an enchanted book.
+    #[serde(
+        rename = "StoredEnchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub stored_enchantments: Vec<EnchantmentNbt>,
+    #[serde(
+        rename = "RepairCost",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub repair_cost: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<ItemDisplayNbt>,
+    // TODO more
+}
+
+/// An enchantment in the `Enchantments` or
+/// `StoredEnchantments` tag of an item.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnchantmentNbt {
+    pub id: String,
+    pub lvl: i16,
+}
+
+/// The `display` tag of an item.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ItemDisplayNbt {
+    /// The custom name of the item, as JSON text.
+    #[serde(rename = "Name", default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 impl ItemNbt {
     /// Create an `ItemStack` of the specified item and amount, setting any nbt present.
     pub fn item_stack(nbt: &Option<Self>, item: Item, amount: u8) -> ItemStack {
-        ItemStack {
-            ty: item,
-            amount,
-            damage: nbt.as_ref().map(|n| n.damage).flatten(),
+        let mut stack = ItemStack::new(item, amount);
+        let nbt = match nbt {
+            Some(nbt) => nbt,
+            None => return stack,
+        };
+
+        stack.damage = nbt.damage;
+        stack.map = nbt.map;
+        stack.potion = nbt.potion.as_deref().and_then(Potion::from_identifier);
+        let enchantments = if item == Item::EnchantedBook {
+            &nbt.stored_enchantments
+        } else {
+            &nbt.enchantments
+        };
+        for enchantment in enchantments {
+            // Unknown enchantments are dropped.
+            if let Some(kind) = Enchantment::from_identifier(&enchantment.id) {
+                stack.enchantments.set(kind, enchantment.lvl);
+            }
         }
+        stack.repair_cost = nbt.repair_cost;
+        stack.name = nbt
+            .display
+            .as_ref()
+            .and_then(|display| display.name.as_deref())
+            .map(|json| match serde_json::from_str::<Text>(json) {
+                Ok(text) => ItemName::new(&text.to_plain()),
+                Err(_) => ItemName::new(json),
+            });
+        stack
     }
 }
 
@@ -251,12 +568,36 @@ where
 {
     fn from(s: S) -> Self {
         let stack = s.borrow();
+        let enchantments = enchantments_nbt(&stack.enchantments);
+        let (enchantments, stored_enchantments) = if stack.ty == Item::EnchantedBook {
+            (vec![], enchantments)
+        } else {
+            (enchantments, vec![])
+        };
         Self {
             damage: stack.damage,
+            map: stack.map,
+            potion: stack.potion.map(|potion| potion.identifier().to_owned()),
+            enchantments,
+            stored_enchantments,
+            repair_cost: stack.repair_cost,
+            display: stack.name.map(|name| ItemDisplayNbt {
+                name: Some(TextRoot::from(name.as_str().to_owned()).into()),
+            }),
         }
     }
 }
 
+fn enchantments_nbt(enchantments: &Enchantments) -> Vec<EnchantmentNbt> {
+    enchantments
+        .iter()
+        .map(|(enchantment, level)| EnchantmentNbt {
+            id: enchantment.identifier().to_owned(),
+            lvl: level,
+        })
+        .collect()
+}
+
 /// Data for an Item entity (`minecraft:item`).
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
 pub struct ItemEntityData {
@@ -290,6 +631,148 @@ pub struct ArrowEntityData {
     pub critical: i8,
 }
 
+/// Data common to entities hanging on a block,
+/// such as item frames and paintings.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HangingEntityData {
+    // Inherit base entity data
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    /// Coordinates of the block the entity occupies,
+    /// in front of the block it hangs on.
+    #[serde(rename = "TileX")]
+    pub tile_x: i32,
+    #[serde(rename = "TileY")]
+    pub tile_y: i32,
+    #[serde(rename = "TileZ")]
+    pub tile_z: i32,
+    /// The direction the entity faces. Item frames use
+    /// 3D data values, and paintings 2D ones.
+    #[serde(rename = "Facing")]
+    pub facing: i8,
+}
+
+impl HangingEntityData {
+    /// Returns the position of the block the entity occupies.
+    pub fn tile(&self) -> BlockPosition {
+        BlockPosition::new(self.tile_x, self.tile_y, self.tile_z)
+    }
+}
+
+/// Data for an Item Frame entity (`minecraft:item_frame`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ItemFrameEntityData {
+    #[serde(flatten)]
+    pub hanging: HangingEntityData,
+
+    #[serde(rename = "Item", default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<ItemData>,
+    /// Number of times the item has been rotated by 45 degrees.
+    #[serde(rename = "ItemRotation", default)]
+    pub item_rotation: i8,
+}
+
+/// Data for a Painting entity (`minecraft:painting`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PaintingEntityData {
+    #[serde(flatten)]
+    pub hanging: HangingEntityData,
+
+    /// The namespaced ID of the painting shown.
+    #[serde(rename = "Motive")]
+    pub motive: String,
+}
+
+/// Data for an Armor Stand entity (`minecraft:armor_stand`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ArmorStandEntityData {
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    /// Items worn on the feet, legs, chest and head, in that order.
+    #[serde(rename = "ArmorItems", default)]
+    pub armor_items: Vec<ItemData>,
+    /// Items held in the main hand and the offhand, in that order.
+    #[serde(rename = "HandItems", default)]
+    pub hand_items: Vec<ItemData>,
+    #[serde(rename = "Pose", default)]
+    pub pose: ArmorStandPoseData,
+
+    // TODO: Change these fields to `bool` when issue with hematite_nbt is resolved.
+    // See: https://github.com/PistonDevelopers/hematite_nbt/issues/43
+    #[serde(rename = "Invisible", default)]
+    pub invisible: i8,
+    #[serde(rename = "Marker", default)]
+    pub marker: i8,
+    #[serde(rename = "NoBasePlate", default)]
+    pub no_base_plate: i8,
+    #[serde(rename = "ShowArms", default)]
+    pub show_arms: i8,
+    #[serde(rename = "Small", default)]
+    pub small: i8,
+}
+
+/// Rotations of the parts of an armor stand, in degrees around
+/// the X, Y and Z axes. Missing parts have their default pose.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ArmorStandPoseData {
+    #[serde(rename = "Head", default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<Vec<f32>>,
+    #[serde(rename = "Body", default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Vec<f32>>,
+    #[serde(rename = "LeftArm", default, skip_serializing_if = "Option::is_none")]
+    pub left_arm: Option<Vec<f32>>,
+    #[serde(rename = "RightArm", default, skip_serializing_if = "Option::is_none")]
+    pub right_arm: Option<Vec<f32>>,
+    #[serde(rename = "LeftLeg", default, skip_serializing_if = "Option::is_none")]
+    pub left_leg: Option<Vec<f32>>,
+    #[serde(rename = "RightLeg", default, skip_serializing_if = "Option::is_none")]
+    pub right_leg: Option<Vec<f32>>,
+}
+
+/// Data for a Boat entity (`minecraft:boat`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BoatEntityData {
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    /// The wood the boat is made of, such as `oak` or `dark_oak`.
+    #[serde(rename = "Type")]
+    pub ty: String,
+}
+
+/// Data for a rideable Minecart entity (`minecraft:minecart`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MinecartEntityData {
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+}
+
+/// Data for a Minecart with Chest entity (`minecraft:chest_minecart`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChestMinecartEntityData {
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    #[serde(rename = "Items", default)]
+    pub items: Vec<InventorySlot>,
+}
+
+/// Data for a Minecart with Hopper entity (`minecraft:hopper_minecart`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HopperMinecartEntityData {
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    #[serde(rename = "Items", default)]
+    pub items: Vec<InventorySlot>,
+    /// Whether the hopper picks up items. Missing
+    /// for minecarts which have never been disabled.
+    #[serde(rename = "Enabled", default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<i8>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -335,4 +818,56 @@ mod tests {
         assert_eq!(data.read_position(), Ok(pos));
         assert_eq!(data.read_velocity(), Ok(vel));
     }
+
+    #[test]
+    fn animal_uuid() {
+        let uuid = Uuid::parse_str("f7c77d99-9f15-4a66-a87d-c4a51ef30d19").unwrap();
+        let data = AnimalData::default().with_uuid(uuid);
+        assert_eq!(data.uuid(), Some(uuid));
+        assert_eq!(AnimalData::default().uuid(), None);
+    }
+
+    #[test]
+    fn horse_owner() {
+        let uuid = Uuid::parse_str("f7c77d99-9f15-4a66-a87d-c4a51ef30d19").unwrap();
+        let data = HorseData {
+            owner_uuid: Some(uuid.to_string()),
+            ..HorseData::default()
+        };
+        assert_eq!(data.owner(), Some(uuid));
+        assert_eq!(HorseData::default().owner(), None);
+    }
+
+    #[test]
+    fn gossip_target() {
+        let uuid = Uuid::parse_str("f7c77d99-9f15-4a66-a87d-c4a51ef30d19").unwrap();
+        let gossip = GossipData::new("trading", 2, uuid);
+        assert_eq!(gossip.target(), uuid);
+        assert_eq!(gossip.kind, "trading");
+    }
+
+    #[test]
+    fn item_nbt_roundtrip() {
+        let mut stack = ItemStack::new(Item::DiamondSword, 1);
+        stack.enchantments.set(Enchantment::Sharpness, 5);
+        stack.enchantments.set(Enchantment::Mending, 1);
+        stack.repair_cost = Some(3);
+        stack.name = Some(ItemName::new("Excalibur"));
+
+        let nbt = ItemNbt::from(stack);
+        assert_eq!(nbt.enchantments.len(), 2);
+        assert!(nbt.stored_enchantments.is_empty());
+        assert_eq!(
+            nbt.display.as_ref().unwrap().name.as_deref(),
+            Some(r#"{"text":"Excalibur"}"#)
+        );
+        assert_eq!(ItemNbt::item_stack(&Some(nbt), stack.ty, 1), stack);
+
+        let mut book = ItemStack::new(Item::EnchantedBook, 1);
+        book.enchantments.set(Enchantment::Unbreaking, 3);
+        let nbt = ItemNbt::from(book);
+        assert!(nbt.enchantments.is_empty());
+        assert_eq!(nbt.stored_enchantments.len(), 1);
+        assert_eq!(ItemNbt::item_stack(&Some(nbt), book.ty, 1), book);
+    }
 }
@@ -1,10 +1,12 @@
 //! Implements level.dat file loading.
 
 use feather_biomes::Biome;
+use feather_blocks::BlockId;
 use feather_items::Item;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::io::Cursor;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
@@ -15,24 +17,42 @@ pub struct Root {
     pub data: LevelData,
 }
 
+/// The data version of 1.13.2, written to new worlds.
+pub const DATA_VERSION: i32 = 1631;
+/// The name of the game version which writes new worlds.
+pub const VERSION_NAME: &str = "1.13.2";
+/// Version of the Anvil format.
+const ANVIL_VERSION: i32 = 19133;
+
 /// Represents the contents of a level file.
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+///
+/// Fields missing from older or hand-written files take
+/// the values vanilla uses for new worlds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LevelData {
     #[serde(rename = "allowCommands")]
     pub allow_commands: bool,
-    #[serde(default)]
     #[serde(rename = "BorderCenterX")]
     pub border_center_x: f64,
-    #[serde(default)]
     #[serde(rename = "BorderCenterZ")]
     pub border_center_z: f64,
-    #[serde(default)]
     #[serde(rename = "BorderDamagePerBlock")]
     pub border_damage_per_block: f64,
     #[serde(rename = "BorderSafeZone")]
     pub border_safe_zone: f64,
     #[serde(rename = "BorderSize")]
     pub border_size: f64,
+    /// The diameter the border is moving towards.
+    #[serde(rename = "BorderSizeLerpTarget")]
+    pub border_size_lerp_target: f64,
+    /// Milliseconds until the border reaches `border_size_lerp_target`.
+    #[serde(rename = "BorderSizeLerpTime")]
+    pub border_size_lerp_time: i64,
+    #[serde(rename = "BorderWarningBlocks")]
+    pub border_warning_blocks: f64,
+    #[serde(rename = "BorderWarningTime")]
+    pub border_warning_time: f64,
 
     #[serde(rename = "clearWeatherTime")]
     pub clear_weather_time: i32,
@@ -44,6 +64,8 @@ pub struct LevelData {
     pub difficulty: i8,
     #[serde(rename = "DifficultyLocked")]
     pub difficulty_locked: i8,
+    #[serde(rename = "GameRules")]
+    pub game_rules: HashMap<String, String>,
     #[serde(rename = "GameType")]
     pub game_type: i32,
 
@@ -52,6 +74,10 @@ pub struct LevelData {
     pub initialized: bool,
     #[serde(rename = "LastPlayed")]
     pub last_played: i64,
+    #[serde(rename = "LevelName")]
+    pub level_name: String,
+    #[serde(rename = "MapFeatures")]
+    pub map_features: bool,
     pub raining: bool,
     #[serde(rename = "rainTime")]
     pub rain_time: i32,
@@ -72,14 +98,75 @@ pub struct LevelData {
 
     #[serde(rename = "Version")]
     pub version: LevelVersion,
+    /// Version of the Anvil format, always 19133.
+    #[serde(rename = "version")]
+    pub anvil_version: i32,
 
     #[serde(rename = "generatorName")]
     pub generator_name: String,
+    #[serde(rename = "generatorVersion")]
+    pub generator_version: i32,
+    /// Superflat settings. Before 1.13, these were stored as a
+    /// preset string, which is converted when loading.
     #[serde(rename = "generatorOptions")]
+    #[serde(deserialize_with = "deserialize_generator_options")]
     pub generator_options: Option<SuperflatGeneratorOptions>,
 }
 
+impl Default for LevelData {
+    fn default() -> Self {
+        Self {
+            allow_commands: false,
+            border_center_x: 0.0,
+            border_center_z: 0.0,
+            border_damage_per_block: 0.2,
+            border_safe_zone: 5.0,
+            border_size: 60_000_000.0,
+            border_size_lerp_target: 60_000_000.0,
+            border_size_lerp_time: 0,
+            border_warning_blocks: 5.0,
+            border_warning_time: 15.0,
+            clear_weather_time: 0,
+            data_version: DATA_VERSION,
+            day_time: 0,
+            difficulty: 2,
+            difficulty_locked: 0,
+            game_rules: HashMap::new(),
+            game_type: 0,
+            hardcore: false,
+            initialized: true,
+            last_played: 0,
+            level_name: String::from("world"),
+            map_features: true,
+            raining: false,
+            rain_time: 0,
+            seed: 0,
+            spawn_x: 0,
+            spawn_y: 64,
+            spawn_z: 0,
+            thundering: false,
+            thunder_time: 0,
+            time: 0,
+            version: LevelVersion::default(),
+            anvil_version: ANVIL_VERSION,
+            generator_name: String::from("default"),
+            generator_version: 1,
+            generator_options: None,
+        }
+    }
+}
+
 impl LevelData {
+    /// Creates the level data of a new world.
+    pub fn new(level_name: &str, seed: i64, generator_name: &str) -> Self {
+        Self {
+            level_name: level_name.to_owned(),
+            seed,
+            generator_name: generator_name.to_owned(),
+            ..Default::default()
+        }
+    }
+
     pub async fn load_from_file(file: &mut File) -> anyhow::Result<Self> {
         let mut buf = vec![];
         file.read_to_end(&mut buf).await?;
@@ -89,22 +176,55 @@ impl LevelData {
             .map(|root| root.data)
     }
 
+    /// Writes the level data to a file, marking
+    /// it as last played at the current time.
     pub async fn save_to_file(&self, file: &mut File) -> anyhow::Result<()> {
+        let mut data = self.clone();
+        data.last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as i64);
+
         let mut buf = vec![];
-        nbt::to_gzip_writer(&mut buf, &Root { data: self.clone() }, None)?;
+        nbt::to_gzip_writer(&mut buf, &Root { data }, None)?;
 
         file.write_all(&buf).await?;
         Ok(())
     }
 }
 
+fn deserialize_generator_options<'de, D>(
+    deserializer: D,
+) -> Result<Option<SuperflatGeneratorOptions>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // Default worlds store an empty compound or string,
+    // which is ignored along with invalid options.
+    Ok(match nbt::Value::deserialize(deserializer)? {
+        nbt::Value::String(preset) => SuperflatGeneratorOptions::from_legacy_preset(&preset),
+        value => SuperflatGeneratorOptions::from_nbt(&value),
+    })
+}
+
 /// Represents level version data.
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LevelVersion {
     #[serde(rename = "Id")]
-    id: i32,
+    pub id: i32,
     #[serde(rename = "Name")]
-    name: String,
+    pub name: String,
+    #[serde(rename = "Snapshot", default)]
+    pub snapshot: bool,
+}
+
+impl Default for LevelVersion {
+    fn default() -> Self {
+        Self {
+            id: DATA_VERSION,
+            name: VERSION_NAME.to_owned(),
+            snapshot: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +264,143 @@ impl Default for SuperflatGeneratorOptions {
     }
 }
 
+impl SuperflatGeneratorOptions {
+    /// Reads the options from NBT, as stored since 1.13.
+    /// Returns `None` if they aren't valid superflat options.
+    pub fn from_nbt(value: &nbt::Value) -> Option<Self> {
+        let compound = match value {
+            nbt::Value::Compound(compound) => compound,
+            _ => return None,
+        };
+
+        let layers = match compound.get("layers")? {
+            nbt::Value::List(layers) => layers
+                .iter()
+                .map(|layer| {
+                    let layer = match layer {
+                        nbt::Value::Compound(layer) => layer,
+                        _ => return None,
+                    };
+                    let block = match layer.get("block")? {
+                        nbt::Value::String(block) => block.clone(),
+                        _ => return None,
+                    };
+                    let height = match layer.get("height")? {
+                        nbt::Value::Byte(height) => i32::from(*height),
+                        nbt::Value::Short(height) => i32::from(*height),
+                        nbt::Value::Int(height) => *height,
+                        _ => return None,
+                    };
+                    Some(SuperflatLayer {
+                        block,
+                        height: height.clamp(0, 255) as u8,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+        let biome = match compound.get("biome") {
+            Some(nbt::Value::String(biome)) => biome.clone(),
+            _ => Biome::Plains.identifier().to_owned(),
+        };
+        let structures = match compound.get("structures") {
+            Some(nbt::Value::Compound(structures)) => structures.clone(),
+            _ => HashMap::new(),
+        };
+
+        Some(Self {
+            structures,
+            layers,
+            biome,
+        })
+    }
+
+    /// Parses a superflat preset string as stored before 1.13,
+    /// e.g. `3;minecraft:bedrock,2*minecraft:dirt,minecraft:grass;1;village`.
+    /// Blocks may be given by name or by numeric ID and metadata.
+    /// Returns `None` if the preset is invalid.
+    pub fn from_legacy_preset(preset: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = preset.split(';').collect();
+        // Presets from 1.5 on start with a version number.
+        if parts.len() > 1 && parts[0].parse::<u32>().is_ok() && parts[1].parse::<u32>().is_err() {
+            parts.remove(0);
+        }
+
+        let layers = parts
+            .first()?
+            .split(',')
+            .map(parse_legacy_layer)
+            .collect::<Option<Vec<_>>>()?;
+        let biome = parts
+            .get(1)
+            .and_then(|id| id.parse().ok())
+            .and_then(Biome::from_protocol_id)
+            .unwrap_or(Biome::Plains);
+        let structures = parts
+            .get(2)
+            .map(|structures| {
+                structures
+                    .split(',')
+                    .filter(|structure| !structure.is_empty())
+                    // Structure parameters, e.g. `village(size=1)`, are dropped.
+                    .map(|structure| structure.split('(').next().unwrap_or_default())
+                    .map(|name| (name.to_owned(), nbt::Value::Compound(HashMap::new())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            structures,
+            layers,
+            biome: biome.identifier().to_owned(),
+        })
+    }
+}
+
+/// Parses a layer of a legacy superflat preset,
+/// e.g. `2*minecraft:dirt`, `minecraft:wool:14` or `3x7`.
+fn parse_legacy_layer(layer: &str) -> Option<SuperflatLayer> {
+    let (height, block) = match layer.find(|c| c == '*' || c == 'x') {
+        Some(index) if layer[..index].parse::<u8>().is_ok() => {
+            (layer[..index].parse().ok()?, &layer[index + 1..])
+        }
+        _ => (1, layer),
+    };
+
+    let mut segments: Vec<&str> = block.split(':').collect();
+    let meta = match segments.last()?.parse::<u8>() {
+        Ok(meta) if segments.len() > 1 => {
+            segments.pop();
+            meta
+        }
+        _ => 0,
+    };
+
+    let block = match segments.as_slice() {
+        [id] if id.parse::<u16>().is_ok() => crate::upgrade::legacy_block(id.parse().ok()?, meta),
+        [name] => legacy_block_by_name(&format!("minecraft:{}", name))?,
+        _ => legacy_block_by_name(&segments.join(":"))?,
+    };
+
+    Some(SuperflatLayer {
+        block: block.identifier().to_owned(),
+        height,
+    })
+}
+
+/// Returns the block with the given pre-1.13 name, for the
+/// blocks which kept their name and those common in presets.
+fn legacy_block_by_name(name: &str) -> Option<BlockId> {
+    let name = match name {
+        "minecraft:grass" => "minecraft:grass_block",
+        "minecraft:snow_layer" => "minecraft:snow",
+        "minecraft:wool" => "minecraft:white_wool",
+        "minecraft:stonebrick" => "minecraft:stone_bricks",
+        name => name,
+    };
+    BlockId::from_identifier(name)
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct SuperflatLayer {
     pub block: String, // TODO: Use "Block" enum and implement (de)serialization
@@ -166,7 +423,7 @@ impl LevelData {
         match self.generator_name.to_lowercase().as_str() {
             "default" => LevelGeneratorType::Default,
             "flat" => LevelGeneratorType::Flat,
-            "largeBiomes" => LevelGeneratorType::LargeBiomes,
+            "largebiomes" | "large_biomes" => LevelGeneratorType::LargeBiomes,
             "amplified" => LevelGeneratorType::Amplified,
             "buffet" => LevelGeneratorType::Buffet,
             "debug_all_block_states" => LevelGeneratorType::Debug,
@@ -205,5 +462,78 @@ mod tests {
         assert_eq!(level.thunder_time, 5252);
         assert_eq!(level.generator_name, "default");
         assert!(level.generator_options.is_none());
+        assert_eq!(level.version.id, 1631);
+        assert_eq!(level.version.name, "1.13.2");
+        assert_eq!(
+            level.game_rules.get("doFireTick").map(String::as_str),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut level = LevelData::new("test", -42, "flat");
+        level
+            .game_rules
+            .insert("keepInventory".to_owned(), "true".to_owned());
+        level.generator_options = Some(SuperflatGeneratorOptions::default());
+
+        let mut buf = Vec::new();
+        nbt::to_gzip_writer(&mut buf, &Root { data: level }, None).unwrap();
+        let level = nbt::from_gzip_reader::<_, Root>(Cursor::new(buf))
+            .unwrap()
+            .data;
+
+        assert_eq!(level.level_name, "test");
+        assert_eq!(level.seed, -42);
+        assert_eq!(level.data_version, DATA_VERSION);
+        assert_eq!(level.border_size, 60_000_000.0);
+        assert_eq!(level.game_rules["keepInventory"], "true");
+        let options = level.generator_options.unwrap();
+        assert_eq!(options.layers.len(), 3);
+        assert_eq!(options.layers[2].block, "minecraft:grass_block");
+    }
+
+    #[test]
+    fn legacy_preset() {
+        let options = SuperflatGeneratorOptions::from_legacy_preset(
+            "3;minecraft:bedrock,2*minecraft:dirt,minecraft:grass;1;village,biome_1",
+        )
+        .unwrap();
+        let layers: Vec<_> = options
+            .layers
+            .iter()
+            .map(|layer| (layer.block.as_str(), layer.height))
+            .collect();
+        assert_eq!(
+            layers,
+            vec![
+                ("minecraft:bedrock", 1),
+                ("minecraft:dirt", 2),
+                ("minecraft:grass_block", 1)
+            ]
+        );
+        assert_eq!(options.biome, "minecraft:plains");
+        assert!(options.structures.contains_key("village"));
+
+        // Version 2 presets use numeric IDs.
+        let options = SuperflatGeneratorOptions::from_legacy_preset("2;7,3x1:1,2;4").unwrap();
+        assert_eq!(options.layers[1].block, "minecraft:granite");
+        assert_eq!(options.layers[1].height, 3);
+        assert_eq!(options.biome, "minecraft:forest");
+
+        assert!(SuperflatGeneratorOptions::from_legacy_preset("1;minecraft:nonsense").is_none());
+    }
+
+    #[test]
+    fn generator_types() {
+        let cursor = Cursor::new(include_bytes!("level.dat").to_vec());
+        let mut level = nbt::from_gzip_reader::<_, Root>(cursor).unwrap().data;
+        assert_eq!(level.generator_type(), LevelGeneratorType::Default);
+
+        level.generator_name = "largeBiomes".to_owned();
+        assert_eq!(level.generator_type(), LevelGeneratorType::LargeBiomes);
+        level.generator_name = "amplified".to_owned();
+        assert_eq!(level.generator_type(), LevelGeneratorType::Amplified);
     }
 }
@@ -3,8 +3,12 @@
 //! player data loading, and level data loading.
 
 pub mod block_entity;
+pub mod compression;
 pub mod entity;
 pub mod level;
 pub mod player;
+pub mod poi;
 pub mod region;
 mod serialization_helper;
+pub mod structure;
+pub mod upgrade;
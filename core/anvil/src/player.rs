@@ -1,4 +1,4 @@
-use crate::entity::{AnimalData, ItemNbt};
+use crate::entity::{AnimalData, EnchantmentNbt, ItemNbt};
 use feather_inventory::player_constants::{
     HOTBAR_SIZE, INVENTORY_SIZE, SLOT_ARMOR_MAX, SLOT_ARMOR_MIN, SLOT_HOTBAR_OFFSET,
     SLOT_INVENTORY_OFFSET, SLOT_OFFHAND,
@@ -23,10 +23,73 @@ pub struct PlayerData {
 
     #[serde(rename = "playerGameType")]
     pub gamemode: i32,
+    /// The dimension the player is in.
+    #[serde(rename = "Dimension", default)]
+    pub dimension: i32,
     #[serde(rename = "Inventory")]
     pub inventory: Vec<InventorySlot>,
     #[serde(rename = "SelectedItemSlot")]
     pub held_item: i32,
+    #[serde(flatten)]
+    pub food: FoodData,
+    #[serde(flatten)]
+    pub experience: ExperienceData,
+    #[serde(flatten)]
+    pub spawn: SpawnData,
+}
+
+/// The hunger of a player, stored alongside their other data.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodData {
+    #[serde(rename = "foodLevel")]
+    pub food_level: i32,
+    #[serde(rename = "foodSaturationLevel")]
+    pub saturation: f32,
+    #[serde(rename = "foodExhaustionLevel")]
+    pub exhaustion: f32,
+    #[serde(rename = "foodTickTimer")]
+    pub tick_timer: i32,
+}
+
+impl Default for FoodData {
+    fn default() -> Self {
+        Self {
+            food_level: 20,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            tick_timer: 0,
+        }
+    }
+}
+
+/// The experience of a player, stored alongside their other data.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExperienceData {
+    #[serde(rename = "XpLevel")]
+    pub level: i32,
+    /// Progress towards the next level, between 0 and 1.
+    #[serde(rename = "XpP")]
+    pub progress: f32,
+    #[serde(rename = "XpTotal")]
+    pub total: i32,
+    /// Seed of the enchantments offered by enchanting tables.
+    #[serde(rename = "XpSeed")]
+    pub seed: i32,
+}
+
+/// The bed a player respawns at, stored alongside their other data.
+/// Players who never slept in a bed have no spawn point.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnData {
+    #[serde(rename = "SpawnX", skip_serializing_if = "Option::is_none")]
+    pub x: Option<i32>,
+    #[serde(rename = "SpawnY", skip_serializing_if = "Option::is_none")]
+    pub y: Option<i32>,
+    #[serde(rename = "SpawnZ", skip_serializing_if = "Option::is_none")]
+    pub z: Option<i32>,
 }
 
 /// Represents a single inventory slot (including position index).
@@ -34,7 +97,9 @@ pub struct PlayerData {
 pub struct InventorySlot {
     #[serde(rename = "Count")]
     pub count: i8,
-    #[serde(rename = "Slot")]
+    /// Missing for items stored outside of an inventory,
+    /// such as the record in a jukebox.
+    #[serde(rename = "Slot", default)]
     pub slot: i8,
     #[serde(rename = "id")]
     pub item: String,
@@ -112,12 +177,52 @@ impl InventorySlot {
             if let Some(damage) = nbt.damage {
                 tags_compound.insert(String::from("Damage"), Value::Int(damage));
             }
+            if let Some(map) = nbt.map {
+                tags_compound.insert(String::from("map"), Value::Int(map));
+            }
+            if let Some(potion) = nbt.potion {
+                tags_compound.insert(String::from("Potion"), Value::String(potion));
+            }
+            if !nbt.enchantments.is_empty() {
+                tags_compound.insert(
+                    String::from("Enchantments"),
+                    enchantments_value(nbt.enchantments),
+                );
+            }
+            if !nbt.stored_enchantments.is_empty() {
+                tags_compound.insert(
+                    String::from("StoredEnchantments"),
+                    enchantments_value(nbt.stored_enchantments),
+                );
+            }
+            if let Some(repair_cost) = nbt.repair_cost {
+                tags_compound.insert(String::from("RepairCost"), Value::Int(repair_cost));
+            }
+            if let Some(name) = nbt.display.and_then(|display| display.name) {
+                let mut display = HashMap::new();
+                display.insert(String::from("Name"), Value::String(name));
+                tags_compound.insert(String::from("display"), Value::Compound(display));
+            }
         }
         compound.insert(String::from("tag"), Value::Compound(tags_compound));
         Value::Compound(compound)
     }
 }
 
+fn enchantments_value(enchantments: Vec<EnchantmentNbt>) -> Value {
+    Value::List(
+        enchantments
+            .into_iter()
+            .map(|enchantment| {
+                let mut compound = HashMap::new();
+                compound.insert(String::from("id"), Value::String(enchantment.id));
+                compound.insert(String::from("lvl"), Value::Short(enchantment.lvl));
+                Value::Compound(compound)
+            })
+            .collect(),
+    )
+}
+
 impl From<InventorySlot> for ItemStack {
     fn from(slot: InventorySlot) -> Self {
         ItemStack::from(&slot)
@@ -190,7 +295,15 @@ mod tests {
         let player = load_from_file(cursor).await.unwrap();
         assert_eq!(player.gamemode, i32::from(Gamemode::Creative.id()));
         assert_eq!(player.inventory[0].item, "minecraft:diamond_shovel");
-        assert_eq!(player.inventory[0].nbt, Some(ItemNbt { damage: Some(3) }));
+        assert_eq!(
+            player.inventory[0].nbt,
+            Some(ItemNbt {
+                damage: Some(3),
+                map: None,
+                potion: None,
+                ..Default::default()
+            })
+        );
     }
 
     #[test]
@@ -213,7 +326,12 @@ mod tests {
             count: 1,
             slot: 2,
             item: String::from(Item::DiamondAxe.identifier()),
-            nbt: Some(ItemNbt { damage: Some(42) }),
+            nbt: Some(ItemNbt {
+                damage: Some(42),
+                map: None,
+                potion: None,
+                ..Default::default()
+            }),
         };
 
         let item_stack: ItemStack = slot.into();
@@ -0,0 +1,198 @@
+//! Loading and saving of points of interest, such as beds,
+//! job sites and nether portals.
+//!
+//! Points of interest are stored per chunk in region files
+//! in the `poi` directory of a world, separately from the
+//! chunks themselves. Each chunk lists the points of interest
+//! in each of its sections. A section which is missing or not
+//! marked as valid has to be scanned for points of interest.
+
+use crate::region::{Error, RegionHandle};
+use feather_util::{BlockPosition, ChunkPosition};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The subdirectory of a world containing the
+/// region files storing points of interest.
+pub const POI_DIR: &str = "poi";
+
+/// The data version written with points of interest.
+/// They were added in 1.14; this corresponds to 1.16.5,
+/// whose format is written.
+const DATA_VERSION: i32 = 2586;
+
+/// The points of interest in a chunk.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PoiChunk {
+    #[serde(default)]
+    data_version: i32,
+    /// Sections keyed by their Y coordinate.
+    #[serde(default)]
+    sections: BTreeMap<String, PoiSection>,
+}
+
+/// The points of interest in a chunk section.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PoiSection {
+    /// Whether the records are up to date with the section's blocks.
+    #[serde(default)]
+    pub valid: bool,
+    #[serde(default)]
+    pub records: Vec<PoiRecord>,
+}
+
+/// A point of interest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PoiRecord {
+    #[serde(with = "block_position")]
+    pub pos: BlockPosition,
+    /// The identifier of the point of interest type,
+    /// e.g. `minecraft:home`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The number of entities which may still claim this
+    /// point of interest, e.g. villagers claiming a bed.
+    pub free_tickets: i32,
+}
+
+impl Default for PoiChunk {
+    fn default() -> Self {
+        Self {
+            data_version: DATA_VERSION,
+            sections: BTreeMap::new(),
+        }
+    }
+}
+
+impl PoiChunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the records of the section at the given Y coordinate,
+    /// or `None` if the section has to be scanned for points of
+    /// interest instead.
+    pub fn section(&self, y: i32) -> Option<&[PoiRecord]> {
+        self.sections
+            .get(&y.to_string())
+            .filter(|section| section.valid)
+            .map(|section| section.records.as_slice())
+    }
+
+    /// Sets the records of the section at the given Y
+    /// coordinate, marking the section as valid.
+    pub fn set_section(&mut self, y: i32, records: Vec<PoiRecord>) {
+        self.sections.insert(
+            y.to_string(),
+            PoiSection {
+                valid: true,
+                records,
+            },
+        );
+    }
+}
+
+impl RegionHandle {
+    /// Loads the points of interest in the chunk at the given
+    /// position (global, not region-relative). Returns `None`
+    /// if none have been saved for the chunk.
+    ///
+    /// The region must have been loaded from the `poi` directory.
+    pub fn load_poi(&mut self, pos: ChunkPosition) -> Result<Option<PoiChunk>, Error> {
+        let data = match self.load_data(pos) {
+            Ok(data) => data,
+            Err(Error::ChunkNotExist) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        nbt::from_reader(&data[..]).map(Some).map_err(Error::Nbt)
+    }
+
+    /// Saves the points of interest in the chunk at the given
+    /// position, replacing any existing ones.
+    ///
+    /// The region must have been loaded from the `poi` directory.
+    pub fn save_poi(&mut self, pos: ChunkPosition, poi: &PoiChunk) -> Result<(), Error> {
+        let mut data = Vec::new();
+        nbt::to_writer(&mut data, poi, None).map_err(Error::Nbt)?;
+        self.save_data(pos, &data)
+    }
+}
+
+/// Stores a block position as an array of three ints.
+pub(crate) mod block_position {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(pos: &BlockPosition, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        nbt::i32_array([pos.x, pos.y, pos.z].iter(), serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BlockPosition, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Vec::<i32>::deserialize(deserializer)?.as_slice() {
+            &[x, y, z] => Ok(BlockPosition::new(x, y, z)),
+            _ => Err(D::Error::custom(
+                "block position must have three coordinates",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::{self, RegionPosition};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn round_trip() {
+        let dir = env::temp_dir().join(format!("feather-poi-{}", std::process::id()));
+        let pos = ChunkPosition::new(-1, 2);
+        let mut handle =
+            region::create_region_in(&dir, POI_DIR, RegionPosition::from_chunk(pos)).unwrap();
+        assert_eq!(handle.load_poi(pos).unwrap(), None);
+
+        let mut poi = PoiChunk::new();
+        let bed = PoiRecord {
+            pos: BlockPosition::new(-10, 70, 33),
+            kind: String::from("minecraft:home"),
+            free_tickets: 1,
+        };
+        poi.set_section(4, vec![bed.clone()]);
+        poi.set_section(5, vec![]);
+        handle.save_poi(pos, &poi).unwrap();
+
+        let loaded = handle.load_poi(pos).unwrap().unwrap();
+        let exists = region::region_exists_in(&dir, POI_DIR, RegionPosition::from_chunk(pos));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(exists);
+        assert_eq!(loaded, poi);
+        assert_eq!(loaded.section(4), Some(&[bed][..]));
+        assert_eq!(loaded.section(5), Some(&[][..]));
+        assert_eq!(loaded.section(6), None);
+    }
+
+    #[test]
+    fn invalid_sections() {
+        let mut poi = PoiChunk::new();
+        poi.sections.insert(
+            String::from("3"),
+            PoiSection {
+                valid: false,
+                records: vec![],
+            },
+        );
+        assert_eq!(poi.section(3), None);
+    }
+}
@@ -2,12 +2,17 @@
 //! of Anvil region files.
 
 use super::serialization_helper::packed_u9;
+use crate::compression::{self, RegionCompression};
+use crate::upgrade::{self, FLATTENING_DATA_VERSION};
 use crate::{block_entity::BlockEntityData, entity::EntityData};
 use bitvec::{bitvec, vec::BitVec};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use feather_biomes::Biome;
+use feather_biomes::{registry, BIOMES_2D_LEN, BIOMES_3D_LEN};
 use feather_blocks::BlockId;
-use feather_chunk::{BitArray, Chunk, ChunkSection};
+use feather_chunk::{
+    BiomeStorage, BitArray, Chunk, ChunkSection, PalettedContainer, MAX_NUM_SECTIONS, NUM_SECTIONS,
+    SECTION_HEIGHT,
+};
 use feather_util::ChunkPosition;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -15,21 +20,42 @@ use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
 use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
-use std::io::{Cursor, SeekFrom};
+use std::io::SeekFrom;
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io, iter};
 
 /// The length and width of a region, in chunks.
 const REGION_SIZE: usize = 32;
 
+/// The subdirectory of a world containing the region files of its chunks.
+pub const REGION_DIR: &str = "region";
+
 /// The data version supported by this code, currently corresponding
 /// to 1.13.2.
 const DATA_VERSION: i32 = 1631;
 
+/// The oldest data version which is loaded without an upgrade,
+/// corresponding to 1.13. Older chunks from before the flattening
+/// are upgraded by the `upgrade` module.
+const MIN_DATA_VERSION: i32 = 1519;
+
 /// Length, in bytes, of a sector.
 const SECTOR_BYTES: usize = 4096;
 
+/// Maximum number of sectors a chunk may occupy. Larger
+/// chunks are stored in a separate file.
+const MAX_CHUNK_SECTORS: usize = 255;
+
+/// Set in a chunk's compression type if its data is stored
+/// in a separate `.mcc` file instead of the region file.
+const EXTERNAL_FLAG: u8 = 0x80;
+
+/// Worldgen statuses of chunks which have finished generating.
+/// Chunks in any other status are regenerated.
+const COMPLETE_STATUSES: &[&str] = &["fullchunk", "postprocessed", "full"];
+
 /// Represents the data for a chunk after the "Chunk [x, y]" tag.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -38,6 +64,16 @@ pub struct ChunkRoot {
     data_version: i32,
 }
 
+/// The part of a chunk's root tag which is read
+/// to determine its format.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct ChunkVersion {
+    /// Missing in chunks saved before 1.9.
+    #[serde(default)]
+    data_version: i32,
+}
+
 /// Represents the level data for a chunk.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -47,30 +83,38 @@ pub struct ChunkLevel {
     x_pos: i32,
     #[serde(rename = "zPos")]
     z_pos: i32,
+    // Vanilla omits empty lists, so most
+    // fields are optional when reading.
+    #[serde(default)]
     last_update: i64,
+    #[serde(default)]
     inhabited_time: i64,
+    #[serde(default)]
     sections: Vec<LevelSection>,
-    #[serde(serialize_with = "nbt::i32_array")]
+    #[serde(serialize_with = "nbt::i32_array", default)]
     biomes: Vec<i32>,
+    #[serde(default)]
     entities: Vec<EntityData>,
-    #[serde(rename = "TileEntities")]
+    #[serde(rename = "TileEntities", default)]
     block_entities: Vec<BlockEntityData>,
+    #[serde(default)]
     heightmaps: Heightmaps,
-    #[serde(rename = "ToBeTicked")]
+    #[serde(rename = "ToBeTicked", default)]
     awaiting_block_updates: Vec<Vec<i16>>,
-    #[serde(rename = "LiquidsToBeTicked")]
+    #[serde(rename = "LiquidsToBeTicked", default)]
     awaiting_liquid_updates: Vec<Vec<i16>>,
+    #[serde(default)]
     post_processing: Vec<Vec<i16>>,
-    #[serde(rename = "TileTicks")]
+    #[serde(rename = "TileTicks", default)]
     scheduled_block_updates: Vec<ScheduledBlockUpdate>,
-    #[serde(rename = "LiquidTicks")]
+    #[serde(rename = "LiquidTicks", default)]
     scheduled_liquid_updates: Vec<ScheduledBlockUpdate>,
     #[serde(rename = "Status")]
     worldgen_status: Cow<'static, str>,
 }
 
 /// Represents the heightmap data of a chunk.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Heightmaps {
     // sometimes a few of those are missing, but we regenerate them anyways
@@ -102,21 +146,26 @@ pub struct Heightmaps {
 }
 
 /// Represents a chunk section in a region file.
+///
+/// Vanilla also stores sections containing only light data,
+/// including ones just above and below the world, which
+/// have neither block states nor a palette.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct LevelSection {
     y: i8,
-    #[serde(serialize_with = "nbt::i64_array", rename = "BlockStates")]
+    #[serde(serialize_with = "nbt::i64_array", rename = "BlockStates", default)]
     states: Vec<i64>,
+    #[serde(default)]
     palette: Vec<LevelPaletteEntry>,
-    #[serde(serialize_with = "nbt::i8_array")]
+    #[serde(serialize_with = "nbt::i8_array", default)]
     block_light: Vec<i8>,
-    #[serde(serialize_with = "nbt::i8_array")]
+    #[serde(serialize_with = "nbt::i8_array", default)]
     sky_light: Vec<i8>,
 }
 
 /// Represents a palette entry in a region file.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct LevelPaletteEntry {
     /// The identifier of the type of this block
@@ -126,13 +175,49 @@ pub struct LevelPaletteEntry {
 }
 
 /// Represents the properties for a palette entry.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LevelProperties {
     /// Map containing a list of property names to values.
     #[serde(flatten)]
     props: BTreeMap<Cow<'static, str>, Cow<'static, str>>,
 }
 
+impl LevelPaletteEntry {
+    pub fn from_block(block: BlockId) -> Self {
+        Self {
+            name: block.identifier().into(),
+            properties: Some(LevelProperties {
+                props: block
+                    .to_properties_map()
+                    .into_iter()
+                    .map(|(k, v)| (Cow::from(k), Cow::from(v)))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Returns the block this entry refers to, or `None`
+    /// if the name or properties are invalid.
+    pub fn to_block(&self) -> Option<BlockId> {
+        let mut props = BTreeMap::new();
+        if let Some(entry_props) = self.properties.as_ref() {
+            props.extend(
+                entry_props
+                    .props
+                    .iter()
+                    .map(|(k, v)| (k.clone().into_owned(), v.clone().into_owned())),
+            );
+        }
+
+        BlockId::from_identifier_and_properties(&self.name, &props)
+    }
+
+    /// Returns the identifier of the block.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 /// Represents a block update scheduled for a specific time.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ScheduledBlockUpdate {
@@ -143,7 +228,7 @@ pub struct ScheduledBlockUpdate {
     #[serde(rename = "t")]
     ticks_from_now: i32,
     /// Lower priority is handled first when happening on the same tick
-    #[serde(rename = "t")]
+    #[serde(rename = "p")]
     priority: i32,
     // TODO are these global or chunk coordinates?
     /// X coordinate
@@ -167,6 +252,11 @@ pub struct SectorBlock {
 pub struct RegionHandle {
     /// The region file.
     file: File,
+    /// The directory containing the region file and
+    /// the files of chunks too large to fit in it.
+    dir: PathBuf,
+    /// The compression used for saved chunks.
+    compression: RegionCompression,
     /// The region file's header, pre-loaded into memory.
     header: RegionHeader,
     /// Sector allocator to allocate sectors where we can store chunks.
@@ -177,28 +267,104 @@ impl RegionHandle {
     /// Loads the chunk at the given position (global, not region-relative).
     ///
     /// The specified chunk is expected to be contained within this region.
+    /// Returns `Error::ChunkNotExist` if the chunk isn't in the region
+    /// file or hasn't finished generating.
     ///
     /// # Panics
     /// Panics if the specified chunk position is not within this
     /// region file.
     pub fn load_chunk(
         &mut self,
-        mut pos: ChunkPosition,
+        pos: ChunkPosition,
     ) -> Result<(Chunk, Vec<EntityData>, Vec<BlockEntityData>), Error> {
+        let data = self.load_data(pos)?;
+
+        // Chunks from before the flattening are upgraded
+        // to the current format.
+        let version: ChunkVersion = nbt::from_reader(&data[..]).map_err(Error::Nbt)?;
+        if version.data_version < FLATTENING_DATA_VERSION {
+            let root = nbt::from_reader(&data[..]).map_err(Error::Nbt)?;
+            return upgrade::upgrade_chunk(root, pos)?.ok_or(Error::ChunkNotExist);
+        }
+
+        // Check data version
+        if !(MIN_DATA_VERSION..=DATA_VERSION).contains(&version.data_version) {
+            return Err(Error::UnsupportedDataVersion(version.data_version));
+        }
+
+        let root: ChunkRoot = nbt::from_reader(&data[..]).map_err(Error::Nbt)?;
+
+        let level = &root.level;
+
+        // Chunks which were only partially generated
+        // are treated as missing, so they're generated again.
+        if !COMPLETE_STATUSES.contains(&level.worldgen_status.as_ref()) {
+            return Err(Error::ChunkNotExist);
+        }
+
+        // Chunks span at least Y 0 to 255, extended
+        // to fit any sections with blocks outside that.
+        let (min_section, max_section) = level
+            .sections
+            .iter()
+            .filter(|section| has_blocks(section))
+            .fold((0, NUM_SECTIONS as i32), |(min, max), section| {
+                let y = i32::from(section.y);
+                (min.min(y), max.max(y + 1))
+            });
+        if (max_section - min_section) as usize > MAX_NUM_SECTIONS {
+            return Err(Error::IndexOutOfBounds);
+        }
+        let mut chunk = Chunk::with_bounds(
+            pos,
+            min_section * SECTION_HEIGHT as i32,
+            (max_section - min_section) as usize * SECTION_HEIGHT,
+        );
+
+        // Read sections
+        for section in &level.sections {
+            read_section_into_chunk(section, &mut chunk)?;
+        }
+
+        // Read biomes, which are 2D before 1.15 and 3D since
+        if level.biomes.len() != BIOMES_2D_LEN && level.biomes.len() != BIOMES_3D_LEN {
+            return Err(Error::IndexOutOfBounds);
+        }
+        if let Some(&id) = level.biomes.iter().find(|&&id| !registry().contains(id)) {
+            return Err(Error::InvalidBiomeId(id));
+        }
+        chunk.set_biomes(BiomeStorage::decode(&level.biomes).unwrap());
+
+        // Chunk was not modified, but it thinks it was: disable this
+        chunk.check_modified();
+
+        chunk.recalculate_heightmap();
+
+        Ok((chunk, level.entities.clone(), level.block_entities.clone()))
+    }
+
+    /// Loads the uncompressed NBT data stored for the chunk at
+    /// the given position (global, not region-relative). This is
+    /// also used for region files which don't contain chunks,
+    /// such as those storing points of interest.
+    ///
+    /// Returns `Error::ChunkNotExist` if there is no data for the chunk.
+    pub fn load_data(&mut self, mut pos: ChunkPosition) -> Result<Vec<u8>, Error> {
         // Get a copy of the original position before clipping
         let original_pos = pos;
         // Clip chunk position to region-local coordinates.
         pos.x %= 32;
         pos.z %= 32;
 
-        // Get the offset of the chunk within the file
+        // Get the location of the chunk within the file
         // so that it can be read.
-        let offset = self.header.location_for_chunk(pos).0.offset;
+        let location = self.header.location_for_chunk(pos);
 
         // If the chunk doesn't exist, return early
-        if !self.header.location_for_chunk(pos).exists() {
+        if !location.exists() {
             return Err(Error::ChunkNotExist);
         }
+        let offset = location.0.offset;
 
         // Seek to the offset position. Note that since the offset in the header
         // is in "sectors" of 4KiB each, the value needs to be multiplied by SECTOR_BYTES
@@ -221,58 +387,35 @@ impl RegionHandle {
             return Err(Error::ChunkTooLarge(0));
         }
 
+        // The data must fit in the sectors allocated to the chunk.
+        if len as usize + 4 > location.0.count as usize * SECTOR_BYTES {
+            return Err(Error::Header("Chunk length exceeds its allocated sectors."));
+        }
+
         // Read `len` bytes into memory.
         let mut buf = vec![0u8; len as usize];
         self.file.read_exact(&mut buf).map_err(Error::Io)?;
 
-        // The compression type is indicated by a byte.
-        // 1 corresponds to gzip compression, while 2
-        // corresponds to zlib.
+        // The compression type is indicated by a byte,
+        // which also flags chunks stored in a separate file.
         let compression_type = buf[0];
 
-        // Parse NBT data
-        let cursor = Cursor::new(&buf[1..]);
-        let root: ChunkRoot = match compression_type {
-            1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
-            2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
-            _ => return Err(Error::InvalidCompression(compression_type)),
+        let data = if compression_type & EXTERNAL_FLAG != 0 {
+            let external = fs::read(self.external_chunk_path(original_pos)).map_err(Error::Io)?;
+            compression::decompress(compression_type & !EXTERNAL_FLAG, &external)?
+        } else {
+            compression::decompress(compression_type, &buf[1..])?
         };
 
-        // Check data version
-        if root.data_version != DATA_VERSION {
-            return Err(Error::UnsupportedDataVersion(root.data_version));
-        }
-
-        let level = &root.level;
-
-        let mut chunk = Chunk::new(original_pos);
-
-        // Read sections
-        for section in &level.sections {
-            read_section_into_chunk(section, &mut chunk)?;
-        }
-
-        // Read biomes
-        if level.biomes.len() != 256 {
-            return Err(Error::IndexOutOfBounds);
-        }
-        for index in 0..256 {
-            let id = level.biomes[index];
-            chunk.biomes_mut()[index] =
-                Biome::from_protocol_id(id).ok_or_else(|| Error::InvalidBiomeId(id))?;
-        }
-
-        // Chunk was not modified, but it thinks it was: disable this
-        chunk.check_modified();
-
-        chunk.recalculate_heightmap();
-
-        Ok((chunk, level.entities.clone(), level.block_entities.clone()))
+        Ok(data)
     }
 
     /// Saves the given chunk to this region file. The header will be updated
     /// accordingly and saved as well.
     ///
+    /// Chunks too large for the region file are written to
+    /// a separate file next to it, as vanilla does.
+    ///
     /// Behavior may be unexpected if this region file does not contain the given
     /// chunk position.
     pub fn save_chunk(
@@ -281,10 +424,41 @@ impl RegionHandle {
         entities: &[EntityData],
         block_entities: &[BlockEntityData],
     ) -> Result<(), Error> {
-        let chunk_pos = chunk.position();
+        // Write chunk to `ChunkRoot` tag.
+        let root = chunk_to_chunk_root(chunk, entities, block_entities);
 
+        let mut data = Vec::with_capacity(16384);
+        nbt::to_writer(&mut data, &root, None).map_err(Error::Nbt)?;
+        self.save_data(chunk.position(), &data)
+    }
+
+    /// Saves uncompressed NBT data for the chunk at the given
+    /// position, replacing any existing data for the chunk.
+    /// See `load_data`.
+    pub fn save_data(&mut self, chunk_pos: ChunkPosition, data: &[u8]) -> Result<(), Error> {
         let (local_x, local_z) = (chunk_pos.x % 32, chunk_pos.z % 32);
 
+        let compressed = self.compression.compress(data).map_err(Error::Io)?;
+
+        // Write to intermediate buffer, because we need to know the length.
+        let mut buf = Vec::with_capacity(compressed.len() + 1);
+        buf.push(self.compression.id());
+        buf.extend_from_slice(&compressed);
+
+        // The header stores sector counts in a single byte,
+        // so larger chunks are stored in their own file.
+        let external_path = self.external_chunk_path(chunk_pos);
+        if (buf.len() + 4 + SECTOR_BYTES - 1) / SECTOR_BYTES > MAX_CHUNK_SECTORS {
+            fs::write(&external_path, &compressed).map_err(Error::Io)?;
+            buf = vec![self.compression.id() | EXTERNAL_FLAG];
+        } else if external_path.exists() {
+            fs::remove_file(&external_path).map_err(Error::Io)?;
+        }
+
+        let total_len = buf.len() + 4; // 4 bytes for length header
+
+        let sectors = (total_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
+
         // Find position in header and deallocate it if it currently exists.
         let location = self
             .header
@@ -293,19 +467,6 @@ impl RegionHandle {
             self.allocator.free(location.0);
         }
 
-        // Write chunk to `ChunkRoot` tag.
-        let root = chunk_to_chunk_root(chunk, entities, block_entities);
-
-        // Write to intermediate buffer, because we need to know the length.
-        let mut buf = Vec::with_capacity(4096);
-        buf.write_u8(2).map_err(Error::Io)?; // Compression type: zlib
-
-        nbt::to_zlib_writer(&mut buf, &root, None).map_err(Error::Nbt)?;
-
-        let total_len = buf.len() + 4; // 4 bytes for length header
-
-        let sectors = (total_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
-
         let block = self.allocator.allocate(sectors as u32);
 
         // Write to file
@@ -318,21 +479,55 @@ impl RegionHandle {
             .map_err(Error::Io)?;
         self.file.write_all(&buf).map_err(Error::Io)?;
 
-        // Write padding to align to sector count
-        let padding_count = SECTOR_BYTES - total_len % SECTOR_BYTES;
-
-        for _ in 0..padding_count {
-            self.file.write_u8(0).map_err(Error::Io)?;
-        }
+        // Write padding to align to sector count. The padding must
+        // not extend past the allocated block, or it would overwrite
+        // the chunk stored after it.
+        let padding_count = sectors * SECTOR_BYTES - total_len;
+        self.file
+            .write_all(&vec![0; padding_count])
+            .map_err(Error::Io)?;
 
         // Update header
+        let local_pos = ChunkPosition::new(local_x, local_z);
+        self.header
+            .set_location_for_chunk(local_pos, ChunkLocation(block));
         self.header
-            .set_location_for_chunk(ChunkPosition::new(local_x, local_z), ChunkLocation(block));
+            .set_timestamp_for_chunk(local_pos, current_timestamp());
         self.save_header().map_err(Error::Io)?;
 
         Ok(())
     }
 
+    /// Sets the compression used for chunks saved from now on.
+    /// Existing chunks are read regardless of their compression.
+    pub fn set_compression(&mut self, compression: RegionCompression) {
+        self.compression = compression;
+    }
+
+    /// Returns the UNIX timestamp at which the chunk at the given
+    /// position was last saved, or 0 if it has never been saved.
+    pub fn timestamp(&self, pos: ChunkPosition) -> u32 {
+        self.header.timestamps[RegionHeader::index(pos)]
+    }
+
+    /// Returns whether there is data for the chunk at the
+    /// given position (global, not region-relative).
+    pub fn contains_chunk(&self, pos: ChunkPosition) -> bool {
+        self.header.location_for_chunk(pos).exists()
+    }
+
+    /// Flushes all data written to this region file to disk.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.file.sync_data().map_err(Error::Io)
+    }
+
+    /// Returns the path of the file storing the chunk at the
+    /// given position (global, not region-relative) if it is
+    /// too large for the region file.
+    fn external_chunk_path(&self, pos: ChunkPosition) -> PathBuf {
+        self.dir.join(format!("c.{}.{}.mcc", pos.x, pos.z))
+    }
+
     fn save_header(&mut self) -> Result<(), io::Error> {
         self.file.seek(SeekFrom::Start(0))?;
 
@@ -340,77 +535,78 @@ impl RegionHandle {
     }
 }
 
+/// Returns whether a section contains blocks. Sections without
+/// blocks only contain light data, which is recalculated anyway.
+fn has_blocks(section: &LevelSection) -> bool {
+    !section.palette.is_empty() && !section.states.is_empty()
+}
+
 fn read_section_into_chunk(section: &LevelSection, chunk: &mut Chunk) -> Result<(), Error> {
+    if !has_blocks(section) {
+        return Ok(());
+    }
+
+    let index = chunk
+        .local_y(i32::from(section.y) * SECTION_HEIGHT as i32)
+        .ok_or(Error::IndexOutOfBounds)?
+        / SECTION_HEIGHT;
+
     let data = &section.states;
 
     // Create palette
     let mut palette = vec![];
     for entry in &section.palette {
-        // Construct properties map
-        let mut props = BTreeMap::new();
-        if let Some(entry_props) = entry.properties.as_ref() {
-            props.extend(
-                entry_props
-                    .props
-                    .iter()
-                    .map(|(k, v)| (k.clone().into_owned(), v.clone().into_owned())),
-            );
-        }
-
-        // Attempt to get block from the given values
-        let block = BlockId::from_identifier_and_properties(&entry.name, &props)
+        let block = entry
+            .to_block()
             .ok_or_else(|| Error::InvalidBlock(entry.name.deref().to_owned()))?;
         palette.push(block);
     }
 
     // Create section
     // TODO don't clone data - need way around this
+    let bits_per_block = data.len() * 64 / 4096;
+    if data.len() * 64 % 4096 != 0 || bits_per_block == 0 || bits_per_block > 64 {
+        return Err(Error::InvalidBlockType);
+    }
     let data = BitArray::from_raw(
         data.iter().map(|x| *x as u64).collect(),
-        ((data.len() as f32 * 64.0) / 4096.0).ceil() as u8,
+        bits_per_block as u8,
         4096,
     );
 
-    // Light
-    // convert raw lighting data (4bits / block) into a BitArray
-    let convert_light_data = |light_data: &Vec<i8>| {
-        let data = light_data
-            .chunks(8)
-            .map(|chunk| {
-                // not sure if there's a better (safe) way of doing this..
-                let chunk: [u8; 8] = [
-                    chunk[0] as u8,
-                    chunk[1] as u8,
-                    chunk[2] as u8,
-                    chunk[3] as u8,
-                    chunk[4] as u8,
-                    chunk[5] as u8,
-                    chunk[6] as u8,
-                    chunk[7] as u8,
-                ];
-                u64::from_le_bytes(chunk)
-            })
-            .collect();
-        BitArray::from_raw(data, 4, 4096)
-    };
-
-    if section.block_light.len() != 2048 || section.sky_light.len() != 2048 {
-        return Err(Error::IndexOutOfBounds);
-    }
+    let block_light = read_light(&section.block_light, 0)?;
+    let sky_light = read_light(&section.sky_light, 15)?;
 
-    let block_light = convert_light_data(&section.block_light);
-    let sky_light = convert_light_data(&section.sky_light);
+    let blocks = PalettedContainer::from_raw(data, Some(palette)).ok_or(Error::InvalidBlockType)?;
+    let chunk_section = ChunkSection::from_blocks(blocks, block_light, sky_light);
 
-    let chunk_section = ChunkSection::new(data, Some(palette), block_light, sky_light);
+    chunk.set_section_at(index, Some(chunk_section));
 
-    if section.y >= 16 {
-        // Haha... nope.
-        return Err(Error::IndexOutOfBounds);
-    }
+    Ok(())
+}
 
-    chunk.set_section_at(usize::from(section.y as u8), Some(chunk_section));
+/// Converts a light array of a region file section, with
+/// two values per byte, to a `BitArray`. Light arrays are
+/// omitted when the chunk hasn't been lit, or has no sky
+/// light (as in the Nether), in which case all values are
+/// set to `default`.
+pub(crate) fn read_light(light: &[i8], default: u8) -> Result<BitArray, Error> {
+    let data = match light.len() {
+        0 => vec![u64::from_le_bytes([default * 0x11; 8]); 256],
+        2048 => light
+            .chunks(8)
+            .map(|chunk| {
+                let mut bytes = [0; 8];
+                for (byte, &value) in bytes.iter_mut().zip(chunk) {
+                    *byte = value as u8;
+                }
+                u64::from_le_bytes(bytes)
+            })
+            .collect(),
+        _ => return Err(Error::IndexOutOfBounds),
+    };
 
-    Ok(())
+    Ok(BitArray::from_raw(data, 4, 4096))
 }
 
 fn chunk_to_chunk_root(
@@ -430,10 +626,10 @@ fn chunk_to_chunk_root(
                 .iter()
                 .enumerate()
                 .filter_map(|(y, sec)| sec.map(|sec| (y, sec.clone())))
-                .map(|(y, mut section)| {
+                .map(|(index, mut section)| {
                     let palette = convert_palette(&mut section);
                     LevelSection {
-                        y: y as i8,
+                        y: (chunk.min_y() / SECTION_HEIGHT as i32 + index as i32) as i8,
                         states: section.data().inner().iter().map(|x| *x as i64).collect(),
                         palette,
                         block_light: slice_u64_to_i8(section.block_light().inner()).to_vec(),
@@ -441,11 +637,7 @@ fn chunk_to_chunk_root(
                     }
                 })
                 .collect(),
-            biomes: chunk
-                .biomes()
-                .iter()
-                .map(|biome| biome.protocol_id())
-                .collect(),
+            biomes: chunk.biomes().encode(chunk.biomes().format()),
             entities: entities.into(),
             heightmaps: Heightmaps {
                 light_blocking: chunk
@@ -489,20 +681,7 @@ fn convert_palette(section: &mut ChunkSection) -> Vec<LevelPaletteEntry> {
 fn raw_palette_to_palette_entries(palette: &[BlockId]) -> Vec<LevelPaletteEntry> {
     palette
         .iter()
-        .map(|block| {
-            let props = block.to_properties_map();
-            let identifier = block.identifier();
-
-            LevelPaletteEntry {
-                name: identifier.into(),
-                properties: Some(LevelProperties {
-                    props: props
-                        .into_iter()
-                        .map(|(k, v)| (Cow::from(k), Cow::from(v)))
-                        .collect(),
-                }),
-            }
-        })
+        .map(|&block| LevelPaletteEntry::from_block(block))
         .collect()
 }
 
@@ -617,6 +796,8 @@ pub enum Error {
     ChunkTooLarge(usize),
     /// The chunk contained an invalid compression type
     InvalidCompression(u8),
+    /// The chunk was compressed with an unknown custom algorithm
+    InvalidCustomCompression(String),
     /// An IO error occurred
     Io(io::Error),
     /// There was an invalid block in the chunk
@@ -647,9 +828,12 @@ impl Display for Error {
             Error::InvalidCompression(id) => {
                 f.write_str(&format!("Chunk uses invalid compression type {}", id))?
             }
+            Error::InvalidCustomCompression(name) => {
+                write!(f, "Chunk uses unknown custom compression {}", name)?
+            }
             Error::InvalidBlock(name) => f.write_str(&format!("Chunk contains invalid block {}", name))?,
             Error::ChunkNotExist => f.write_str("The chunk does not exist")?,
-            Error::UnsupportedDataVersion(_) => f.write_str("The chunk uses an unsupported data version. Feather currently only supports 1.13.2 region files.")?,
+            Error::UnsupportedDataVersion(_) => f.write_str("The chunk uses an unsupported data version. Feather currently supports region files from 1.13 to 1.13.2, and upgrades older ones.")?,
             Error::InvalidBlockType => f.write_str("Chunk contains invalid block type")?,
             Error::MissingRootTag => f.write_str("Chunk is missing a root NBT tag")?,
             Error::IndexOutOfBounds => f.write_str("Section index out of bounds")?,
@@ -673,8 +857,18 @@ impl std::error::Error for Error {}
 /// in the region into memory; it only reads the file's
 /// header so that chunks can be retrieved later.
 pub fn load_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle, Error> {
+    load_region_in(dir, REGION_DIR, pos)
+}
+
+/// Like `load_region`, but loads the region file from the given
+/// subdirectory of the world directory instead of `region`.
+pub fn load_region_in(
+    dir: &PathBuf,
+    subdir: &str,
+    pos: RegionPosition,
+) -> Result<RegionHandle, Error> {
     let mut file = {
-        let buf = region_file_path(dir, pos);
+        let buf = region_file_path(dir, subdir, pos);
 
         open_opts()
             .create(false)
@@ -690,11 +884,25 @@ pub fn load_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle, E
 
     Ok(RegionHandle {
         file,
+        dir: dir.join(subdir),
+        compression: RegionCompression::default(),
         header,
         allocator,
     })
 }
 
+/// Returns whether the region file at the given
+/// position exists in the given world directory.
+pub fn region_exists(dir: &PathBuf, pos: RegionPosition) -> bool {
+    region_exists_in(dir, REGION_DIR, pos)
+}
+
+/// Like `region_exists`, but checks the given
+/// subdirectory of the world directory.
+pub fn region_exists_in(dir: &PathBuf, subdir: &str, pos: RegionPosition) -> bool {
+    region_file_path(dir, subdir, pos).is_file()
+}
+
 /// Creates the region file at the given region position and initializes
 /// a handle.
 ///
@@ -707,9 +915,19 @@ pub fn load_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle, E
 /// Care must be taken to ensure that this function is only called
 /// for nonexistent regions.
 pub fn create_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle, Error> {
-    create_region_dir(dir).map_err(Error::Io)?;
+    create_region_in(dir, REGION_DIR, pos)
+}
+
+/// Like `create_region`, but creates the region file in the
+/// given subdirectory of the world directory instead of `region`.
+pub fn create_region_in(
+    dir: &PathBuf,
+    subdir: &str,
+    pos: RegionPosition,
+) -> Result<RegionHandle, Error> {
+    create_region_dir(dir, subdir).map_err(Error::Io)?;
     let mut file = {
-        let buf = region_file_path(dir, pos);
+        let buf = region_file_path(dir, subdir, pos);
 
         open_opts().create(true).open(buf.as_path())
     }
@@ -721,11 +939,21 @@ pub fn create_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle,
     let allocator = SectorAllocator::new(&header, 2);
     Ok(RegionHandle {
         file,
+        dir: dir.join(subdir),
+        compression: RegionCompression::default(),
         header,
         allocator,
     })
 }
 
+/// Returns the current UNIX timestamp, as stored in region headers.
+fn current_timestamp() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as u32)
+        .unwrap_or_default()
+}
+
 fn open_opts() -> OpenOptions {
     OpenOptions::new()
         .read(true)
@@ -734,15 +962,16 @@ fn open_opts() -> OpenOptions {
         .clone()
 }
 
-fn region_file_path(dir: &PathBuf, pos: RegionPosition) -> PathBuf {
+fn region_file_path(dir: &PathBuf, subdir: &str, pos: RegionPosition) -> PathBuf {
     let mut buf = dir.clone();
-    buf.push(format!("region/r.{}.{}.mca", pos.x, pos.z));
+    buf.push(subdir);
+    buf.push(format!("r.{}.{}.mca", pos.x, pos.z));
     buf
 }
 
-fn create_region_dir(dir: &PathBuf) -> Result<(), io::Error> {
+fn create_region_dir(dir: &PathBuf, subdir: &str) -> Result<(), io::Error> {
     let mut dir = dir.clone();
-    dir.push("region");
+    dir.push(subdir);
     fs::create_dir_all(dir.as_path())
 }
 
@@ -832,6 +1061,12 @@ impl RegionHeader {
         self.locations[index] = location;
     }
 
+    /// Sets the last modification time for the given chunk position.
+    fn set_timestamp_for_chunk(&mut self, pos: ChunkPosition, timestamp: u32) {
+        let index = Self::index(pos);
+        self.timestamps[index] = timestamp;
+    }
+
     /// Writes this header to the given writer.
     fn write_to<W>(&self, w: &mut W) -> Result<(), io::Error>
     where
@@ -890,6 +1125,175 @@ impl RegionPosition {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct VanillaRoot {
+        level: VanillaLevel,
+        data_version: i32,
+    }
+
+    /// A chunk with only the tags vanilla always writes.
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct VanillaLevel {
+        #[serde(rename = "xPos")]
+        x_pos: i32,
+        #[serde(rename = "zPos")]
+        z_pos: i32,
+        status: &'static str,
+        #[serde(serialize_with = "nbt::i32_array")]
+        biomes: Vec<i32>,
+        sections: Vec<LightSection>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct LightSection {
+        y: i8,
+        #[serde(serialize_with = "nbt::i8_array")]
+        sky_light: Vec<i8>,
+    }
+
+    /// Writes an uncompressed chunk to the first free sectors of a region.
+    fn write_uncompressed(handle: &mut RegionHandle, pos: ChunkPosition, root: &VanillaRoot) {
+        let mut buf = vec![3];
+        nbt::to_writer(&mut buf, root, None).unwrap();
+
+        let sectors = (buf.len() + 4 + SECTOR_BYTES - 1) / SECTOR_BYTES;
+        let block = handle.allocator.allocate(sectors as u32);
+        handle
+            .file
+            .seek(SeekFrom::Start(
+                u64::from(block.offset) * SECTOR_BYTES as u64,
+            ))
+            .unwrap();
+        handle
+            .file
+            .write_u32::<BigEndian>(buf.len() as u32)
+            .unwrap();
+        handle.file.write_all(&buf).unwrap();
+        handle
+            .file
+            .write_all(&vec![0; sectors * SECTOR_BYTES - buf.len() - 4])
+            .unwrap();
+
+        handle
+            .header
+            .set_location_for_chunk(pos, ChunkLocation(block));
+        handle.save_header().unwrap();
+    }
+
+    #[test]
+    fn save_chunks() {
+        let dir = env::temp_dir().join(format!("feather-region-save-{}", std::process::id()));
+        let rpos = RegionPosition::from_chunk(ChunkPosition::new(0, 0));
+        let mut handle = create_region(&dir, rpos).unwrap();
+
+        let first = Chunk::new(ChunkPosition::new(0, 0));
+        let second = Chunk::new(ChunkPosition::new(1, 0));
+        handle.save_chunk(&first, &[], &[]).unwrap();
+        // Chunks are read regardless of their compression.
+        handle.set_compression(RegionCompression::Lz4);
+        handle.save_chunk(&second, &[], &[]).unwrap();
+        let location = handle.header.location_for_chunk(first.position());
+
+        // Saving again reuses the freed sectors.
+        handle.save_chunk(&first, &[], &[]).unwrap();
+        assert_eq!(
+            handle.header.location_for_chunk(first.position()).0,
+            location.0
+        );
+        assert!(handle.timestamp(first.position()) > 0);
+        handle.flush().unwrap();
+
+        let mut handle = load_region(&dir, rpos).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(handle.timestamp(second.position()) > 0);
+        assert_eq!(handle.timestamp(ChunkPosition::new(2, 0)), 0);
+        assert!(handle.load_chunk(first.position()).is_ok());
+        assert!(handle.load_chunk(second.position()).is_ok());
+    }
+
+    #[test]
+    fn load_vanilla_chunks() {
+        let dir = env::temp_dir().join(format!("feather-region-test-{}", std::process::id()));
+        let rpos = RegionPosition::from_chunk(ChunkPosition::new(-1, -1));
+        let mut handle = create_region(&dir, rpos).unwrap();
+
+        let vanilla_chunk = |x, z, status| VanillaRoot {
+            level: VanillaLevel {
+                x_pos: x,
+                z_pos: z,
+                status,
+                biomes: vec![2; BIOMES_2D_LEN],
+                // Light-only sections above and below the world.
+                sections: vec![
+                    LightSection {
+                        y: -1,
+                        sky_light: vec![-1; 2048],
+                    },
+                    LightSection {
+                        y: 16,
+                        sky_light: vec![-1; 2048],
+                    },
+                ],
+            },
+            data_version: DATA_VERSION,
+        };
+        write_uncompressed(
+            &mut handle,
+            ChunkPosition::new(-1, -1),
+            &vanilla_chunk(-1, -1, "postprocessed"),
+        );
+        write_uncompressed(
+            &mut handle,
+            ChunkPosition::new(-2, -1),
+            &vanilla_chunk(-2, -1, "carved"),
+        );
+
+        // Reopen to read the header from disk.
+        let mut handle = load_region(&dir, rpos).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (chunk, entities, block_entities) =
+            handle.load_chunk(ChunkPosition::new(-1, -1)).unwrap();
+        assert_eq!(chunk.position(), ChunkPosition::new(-1, -1));
+        assert!(chunk.sections().iter().all(Option::is_none));
+        assert_eq!(chunk.biomes().biome_id_at(5, 5), 2);
+        assert!(entities.is_empty() && block_entities.is_empty());
+
+        // Partially generated chunks are regenerated.
+        assert!(matches!(
+            handle.load_chunk(ChunkPosition::new(-2, -1)),
+            Err(Error::ChunkNotExist)
+        ));
+        assert!(matches!(
+            handle.load_chunk(ChunkPosition::new(-3, -1)),
+            Err(Error::ChunkNotExist)
+        ));
+    }
+
+    #[test]
+    fn save_chunk_below_zero() {
+        let dir = env::temp_dir().join(format!("feather-region-height-{}", std::process::id()));
+        let rpos = RegionPosition::from_chunk(ChunkPosition::new(0, 0));
+        let mut handle = create_region(&dir, rpos).unwrap();
+
+        let mut chunk = Chunk::with_bounds(ChunkPosition::new(0, 0), -64, 384);
+        chunk.set_block_at(1, 2, 3, BlockId::stone());
+        chunk.set_block_at(1, 300, 3, BlockId::dirt());
+        handle.save_chunk(&chunk, &[], &[]).unwrap();
+
+        let (loaded, _, _) = handle.load_chunk(chunk.position()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        // The chunk is only as tall as needed for its sections.
+        assert_eq!(loaded.min_y(), -64);
+        assert_eq!(loaded.max_y(), 256);
+        assert_eq!(loaded.block_at(1, 2, 3), BlockId::stone());
+        assert_eq!(loaded.block_at(1, 300, 3), BlockId::dirt());
+    }
 
     #[test]
     fn test_sector_allocator() {
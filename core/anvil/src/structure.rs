@@ -0,0 +1,559 @@
+//! Structures: boxes of blocks, with their block entities, which
+//! can be placed in a world in any orientation.
+//!
+//! Structures are read from and written to the two common formats:
+//! the templates saved by structure blocks (`.nbt`), and Sponge
+//! schematics (`.schem`, version 2) as used by world editors.
+//! Entities stored in either format are not supported and skipped.
+
+use crate::block_entity::BlockEntityData;
+use crate::level::DATA_VERSION;
+use crate::region::LevelPaletteEntry;
+use feather_blocks::{BlockId, BlockKind, Mirror, Rotation};
+use feather_util::BlockPosition;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// The version of the Sponge schematic format written.
+const SCHEMATIC_VERSION: i32 = 2;
+
+#[derive(Debug, Error)]
+pub enum StructureError {
+    #[error("invalid NBT: {0}")]
+    Nbt(#[from] nbt::Error),
+    #[error("invalid block {0}")]
+    InvalidBlock(String),
+    #[error("invalid structure size")]
+    InvalidSize,
+    #[error("block palette index {0} out of bounds")]
+    InvalidPaletteIndex(i32),
+    #[error("unsupported schematic version {0}")]
+    UnsupportedVersion(i32),
+    #[error("block position {0:?} outside of the structure")]
+    OutOfBounds(BlockPosition),
+}
+
+/// A box of blocks.
+///
+/// Positions are relative to the structure's origin, its
+/// corner with the lowest coordinates. Positions without
+/// a block, like those filled with structure voids, are
+/// left unchanged when placing the structure.
+#[derive(Debug, Clone, Default)]
+pub struct Structure {
+    size: BlockPosition,
+    blocks: Vec<StructureBlock>,
+}
+
+/// A block in a structure.
+#[derive(Debug, Clone)]
+pub struct StructureBlock {
+    pub pos: BlockPosition,
+    pub block: BlockId,
+    /// The block entity's tags, without its position.
+    nbt: Option<nbt::Value>,
+}
+
+impl StructureBlock {
+    /// Returns the block entity of this block, moved to
+    /// `pos`, or `None` if it has no block entity.
+    pub fn block_entity(&self, pos: BlockPosition) -> Option<BlockEntityData> {
+        let mut tags = match self.nbt.clone()? {
+            nbt::Value::Compound(tags) => tags,
+            _ => return None,
+        };
+        tags.insert("x".to_owned(), nbt::Value::Int(pos.x));
+        tags.insert("y".to_owned(), nbt::Value::Int(pos.y));
+        tags.insert("z".to_owned(), nbt::Value::Int(pos.z));
+
+        convert(&nbt::Value::Compound(tags))
+    }
+}
+
+impl Structure {
+    /// Creates an empty structure with the given size
+    /// along each axis.
+    pub fn new(size: BlockPosition) -> Self {
+        Self {
+            size,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Returns the size of the structure along each axis.
+    pub fn size(&self) -> BlockPosition {
+        self.size
+    }
+
+    pub fn blocks(&self) -> &[StructureBlock] {
+        &self.blocks
+    }
+
+    /// Adds a block, with an optional block entity, to the structure.
+    pub fn add_block(
+        &mut self,
+        pos: BlockPosition,
+        block: BlockId,
+        block_entity: Option<&BlockEntityData>,
+    ) -> Result<(), StructureError> {
+        if !self.contains(pos) {
+            return Err(StructureError::OutOfBounds(pos));
+        }
+
+        let nbt = match block_entity {
+            Some(data) => {
+                let mut value: nbt::Value = convert(data)
+                    .ok_or_else(|| StructureError::InvalidBlock(block.identifier().to_owned()))?;
+                if let nbt::Value::Compound(tags) = &mut value {
+                    for tag in &["x", "y", "z"] {
+                        tags.remove(*tag);
+                    }
+                }
+                Some(value)
+            }
+            None => None,
+        };
+
+        self.blocks.push(StructureBlock { pos, block, nbt });
+        Ok(())
+    }
+
+    fn contains(&self, pos: BlockPosition) -> bool {
+        (0..self.size.x).contains(&pos.x)
+            && (0..self.size.y).contains(&pos.y)
+            && (0..self.size.z).contains(&pos.z)
+    }
+
+    /// Returns this structure mirrored, then rotated around
+    /// the Y axis. The result again has its origin at its
+    /// lowest corner.
+    pub fn transformed(&self, mirror: Mirror, rotation: Rotation) -> Structure {
+        let transform = |pos| rotation.apply(mirror.apply(pos));
+
+        let far_corner = BlockPosition::new(self.size.x - 1, self.size.y - 1, self.size.z - 1);
+        let (a, b) = (
+            transform(BlockPosition::new(0, 0, 0)),
+            transform(far_corner),
+        );
+        let min = BlockPosition::new(a.x.min(b.x), 0, a.z.min(b.z));
+        let size = BlockPosition::new((a.x - b.x).abs() + 1, self.size.y, (a.z - b.z).abs() + 1);
+
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let pos = transform(block.pos);
+                StructureBlock {
+                    pos: BlockPosition::new(pos.x - min.x, pos.y, pos.z - min.z),
+                    block: block.block.transformed(mirror, rotation),
+                    nbt: block.nbt.clone(),
+                }
+            })
+            .collect();
+
+        Structure { size, blocks }
+    }
+
+    /// Reads a gzipped structure block template.
+    pub fn read_template<R: Read>(reader: R) -> Result<Self, StructureError> {
+        let root: TemplateRoot = nbt::from_gzip_reader(reader)?;
+
+        let size = match root.size.as_slice() {
+            &[x, y, z] if x >= 0 && y >= 0 && z >= 0 => BlockPosition::new(x, y, z),
+            _ => return Err(StructureError::InvalidSize),
+        };
+
+        let palette = root
+            .palette
+            .iter()
+            .map(|entry| {
+                entry
+                    .to_block()
+                    .ok_or_else(|| StructureError::InvalidBlock(entry.name().to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut structure = Structure::new(size);
+        for block in root.blocks {
+            let pos = match block.pos.as_slice() {
+                &[x, y, z] => BlockPosition::new(x, y, z),
+                _ => return Err(StructureError::InvalidSize),
+            };
+            if !structure.contains(pos) {
+                return Err(StructureError::OutOfBounds(pos));
+            }
+            let id = palette
+                .get(block.state as usize)
+                .copied()
+                .ok_or(StructureError::InvalidPaletteIndex(block.state))?;
+
+            structure.blocks.push(StructureBlock {
+                pos,
+                block: id,
+                nbt: block.nbt,
+            });
+        }
+
+        Ok(structure)
+    }
+
+    /// Writes the structure as a gzipped structure block template.
+    pub fn write_template<W: Write>(&self, mut writer: W) -> Result<(), StructureError> {
+        let mut palette = Vec::new();
+        let mut indices = HashMap::new();
+
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let state = *indices.entry(block.block).or_insert_with(|| {
+                    palette.push(LevelPaletteEntry::from_block(block.block));
+                    palette.len() as i32 - 1
+                });
+                TemplateBlock {
+                    state,
+                    pos: vec![block.pos.x, block.pos.y, block.pos.z],
+                    nbt: block.nbt.clone(),
+                }
+            })
+            .collect();
+
+        let root = TemplateRoot {
+            data_version: DATA_VERSION,
+            size: vec![self.size.x, self.size.y, self.size.z],
+            palette,
+            blocks,
+        };
+        nbt::to_gzip_writer(&mut writer, &root, None)?;
+        Ok(())
+    }
+
+    /// Reads a gzipped Sponge schematic of version 1 or 2.
+    pub fn read_schematic<R: Read>(reader: R) -> Result<Self, StructureError> {
+        let root: SchematicRoot = nbt::from_gzip_reader(reader)?;
+        if root.version != 1 && root.version != 2 {
+            return Err(StructureError::UnsupportedVersion(root.version));
+        }
+
+        let size = BlockPosition::new(
+            i32::from(root.width as u16),
+            i32::from(root.height as u16),
+            i32::from(root.length as u16),
+        );
+
+        let mut palette = vec![None; root.palette.len()];
+        for (state, &index) in &root.palette {
+            let block = parse_block_state(state)
+                .ok_or_else(|| StructureError::InvalidBlock(state.clone()))?;
+            *palette
+                .get_mut(index as usize)
+                .ok_or(StructureError::InvalidPaletteIndex(index))? = Some(block);
+        }
+
+        let mut block_entities = HashMap::new();
+        for value in root.block_entities {
+            if let Some((pos, nbt)) = schematic_block_entity(value) {
+                block_entities.insert(pos, nbt);
+            }
+        }
+
+        let mut structure = Structure::new(size);
+        let mut data = root.block_data.iter().map(|&byte| byte as u8);
+        for y in 0..size.y {
+            for z in 0..size.z {
+                for x in 0..size.x {
+                    let index = read_var_int(&mut data).ok_or(StructureError::InvalidSize)?;
+                    let block = palette
+                        .get(index as usize)
+                        .copied()
+                        .flatten()
+                        .ok_or(StructureError::InvalidPaletteIndex(index))?;
+
+                    if block.kind() == BlockKind::StructureVoid {
+                        continue;
+                    }
+
+                    let pos = BlockPosition::new(x, y, z);
+                    structure.blocks.push(StructureBlock {
+                        pos,
+                        block,
+                        nbt: block_entities.remove(&pos),
+                    });
+                }
+            }
+        }
+
+        Ok(structure)
+    }
+
+    /// Writes the structure as a gzipped Sponge schematic.
+    /// Positions without a block are filled with structure voids.
+    pub fn write_schematic<W: Write>(&self, mut writer: W) -> Result<(), StructureError> {
+        let (width, height, length) = (self.size.x, self.size.y, self.size.z);
+        let index = |pos: BlockPosition| (pos.x + pos.z * width + pos.y * width * length) as usize;
+
+        let mut blocks = vec![BlockId::structure_void(); (width * height * length) as usize];
+        let mut block_entities = Vec::new();
+        for block in &self.blocks {
+            blocks[index(block.pos)] = block.block;
+            if let Some(nbt::Value::Compound(tags)) = &block.nbt {
+                let mut tags = tags.clone();
+                if let Some(id) = tags.remove("id") {
+                    tags.insert("Id".to_owned(), id);
+                }
+                tags.insert(
+                    "Pos".to_owned(),
+                    nbt::Value::IntArray(vec![block.pos.x, block.pos.y, block.pos.z]),
+                );
+                block_entities.push(nbt::Value::Compound(tags));
+            }
+        }
+
+        let mut palette = BTreeMap::new();
+        let mut block_data = Vec::new();
+        for block in blocks {
+            let next = palette.len() as i32;
+            let id = *palette.entry(block_state_string(block)).or_insert(next);
+            write_var_int(&mut block_data, id);
+        }
+
+        let root = SchematicRoot {
+            version: SCHEMATIC_VERSION,
+            data_version: DATA_VERSION,
+            width: width as i16,
+            height: height as i16,
+            length: length as i16,
+            palette_max: palette.len() as i32,
+            palette,
+            block_data,
+            block_entities,
+        };
+        nbt::to_gzip_writer(&mut writer, &root, Some("Schematic"))?;
+        Ok(())
+    }
+}
+
+/// The root of a structure block template.
+#[derive(Serialize, Deserialize)]
+struct TemplateRoot {
+    #[serde(rename = "DataVersion", default)]
+    data_version: i32,
+    size: Vec<i32>,
+    palette: Vec<LevelPaletteEntry>,
+    blocks: Vec<TemplateBlock>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TemplateBlock {
+    /// Index into the palette.
+    state: i32,
+    pos: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nbt: Option<nbt::Value>,
+}
+
+/// The root of a Sponge schematic.
+///
+/// https://github.com/SpongePowered/Schematic-Specification
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SchematicRoot {
+    version: i32,
+    #[serde(default)]
+    data_version: i32,
+    width: i16,
+    height: i16,
+    length: i16,
+    #[serde(default)]
+    palette_max: i32,
+    /// Maps block states, e.g. `minecraft:oak_log[axis=x]`,
+    /// to indices used in `block_data`.
+    palette: BTreeMap<String, i32>,
+    /// The palette index of each block as a `VarInt`,
+    /// ordered by Y, then Z, then X.
+    #[serde(serialize_with = "nbt::i8_array")]
+    block_data: Vec<i8>,
+    #[serde(default, alias = "TileEntities", skip_serializing_if = "Vec::is_empty")]
+    block_entities: Vec<nbt::Value>,
+}
+
+/// Converts a schematic block entity, which stores its position
+/// as `Pos` and ID as `Id`, to the template format.
+fn schematic_block_entity(value: nbt::Value) -> Option<(BlockPosition, nbt::Value)> {
+    let mut tags = match value {
+        nbt::Value::Compound(tags) => tags,
+        _ => return None,
+    };
+
+    let pos = match tags.remove("Pos")? {
+        nbt::Value::IntArray(pos) if pos.len() == 3 => BlockPosition::new(pos[0], pos[1], pos[2]),
+        _ => return None,
+    };
+    if let Some(id) = tags.remove("Id") {
+        tags.insert("id".to_owned(), id);
+    }
+
+    Some((pos, nbt::Value::Compound(tags)))
+}
+
+/// Parses a block state such as `minecraft:oak_log[axis=x]`.
+fn parse_block_state(state: &str) -> Option<BlockId> {
+    let (identifier, properties) = match state.find('[') {
+        Some(start) => (&state[..start], state[start + 1..].strip_suffix(']')?),
+        None => (state, ""),
+    };
+
+    let properties = properties
+        .split(',')
+        .filter(|property| !property.is_empty())
+        .map(|property| {
+            let mut parts = property.splitn(2, '=');
+            Some((parts.next()?.to_owned(), parts.next()?.to_owned()))
+        })
+        .collect::<Option<BTreeMap<_, _>>>()?;
+
+    BlockId::from_identifier_and_properties(identifier, &properties)
+}
+
+/// Formats a block state as parsed by `parse_block_state`.
+fn block_state_string(block: BlockId) -> String {
+    let properties = block.to_properties_map();
+    if properties.is_empty() {
+        return block.identifier().to_owned();
+    }
+
+    let properties: Vec<_> = properties
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    format!("{}[{}]", block.identifier(), properties.join(","))
+}
+
+fn read_var_int(bytes: &mut impl Iterator<Item = u8>) -> Option<i32> {
+    let mut result = 0;
+    for i in 0..5 {
+        let byte = bytes.next()?;
+        result |= i32::from(byte & 0b0111_1111) << (7 * i);
+        if byte & 0b1000_0000 == 0 {
+            return Some(result);
+        }
+    }
+    None
+}
+
+fn write_var_int(bytes: &mut Vec<i8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        let mut byte = (value & 0b0111_1111) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0b1000_0000;
+        }
+        bytes.push(byte as i8);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+/// Converts between NBT representations by writing
+/// and reading back the NBT.
+fn convert<T: Serialize, U: DeserializeOwned>(value: &T) -> Option<U> {
+    let mut buf = Vec::new();
+    nbt::to_writer(&mut buf, value, None).ok()?;
+    nbt::from_reader(&buf[..]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_blocks::{AxisXyz, FacingCardinal};
+
+    fn sample() -> Structure {
+        let mut structure = Structure::new(BlockPosition::new(3, 2, 1));
+        structure
+            .add_block(BlockPosition::new(0, 0, 0), BlockId::stone(), None)
+            .unwrap();
+        structure
+            .add_block(
+                BlockPosition::new(2, 1, 0),
+                BlockId::oak_log().with_axis_xyz(AxisXyz::X),
+                None,
+            )
+            .unwrap();
+        structure
+    }
+
+    fn sorted_blocks(structure: &Structure) -> Vec<(BlockPosition, BlockId)> {
+        let mut blocks: Vec<_> = structure
+            .blocks()
+            .iter()
+            .map(|block| (block.pos, block.block))
+            .collect();
+        blocks.sort();
+        blocks
+    }
+
+    #[test]
+    fn template_round_trip() {
+        let structure = sample();
+        let mut buf = Vec::new();
+        structure.write_template(&mut buf).unwrap();
+
+        let read = Structure::read_template(&buf[..]).unwrap();
+        assert_eq!(read.size(), structure.size());
+        assert_eq!(sorted_blocks(&read), sorted_blocks(&structure));
+    }
+
+    #[test]
+    fn schematic_round_trip() {
+        let structure = sample();
+        let mut buf = Vec::new();
+        structure.write_schematic(&mut buf).unwrap();
+
+        // Positions without blocks are written as structure
+        // voids, which are skipped again when reading.
+        let read = Structure::read_schematic(&buf[..]).unwrap();
+        assert_eq!(read.size(), structure.size());
+        assert_eq!(sorted_blocks(&read), sorted_blocks(&structure));
+    }
+
+    #[test]
+    fn block_states() {
+        let block = BlockId::oak_stairs().with_facing_cardinal(FacingCardinal::East);
+        let state = block_state_string(block);
+        assert!(state.starts_with("minecraft:oak_stairs[facing=east,"));
+        assert_eq!(parse_block_state(&state), Some(block));
+        assert_eq!(parse_block_state("minecraft:stone"), Some(BlockId::stone()));
+        assert_eq!(parse_block_state("minecraft:stone[oops"), None);
+    }
+
+    #[test]
+    fn transform() {
+        let rotated = sample().transformed(Mirror::None, Rotation::Clockwise90);
+        assert_eq!(rotated.size(), BlockPosition::new(1, 2, 3));
+        assert_eq!(
+            sorted_blocks(&rotated),
+            vec![
+                (BlockPosition::new(0, 0, 0), BlockId::stone()),
+                (
+                    BlockPosition::new(0, 1, 2),
+                    BlockId::oak_log().with_axis_xyz(AxisXyz::Z)
+                ),
+            ]
+        );
+
+        let mirrored = sample().transformed(Mirror::FrontBack, Rotation::None);
+        assert_eq!(mirrored.size(), BlockPosition::new(3, 2, 1));
+        assert_eq!(
+            sorted_blocks(&mirrored)[0],
+            (
+                BlockPosition::new(0, 1, 0),
+                BlockId::oak_log().with_axis_xyz(AxisXyz::X)
+            )
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! Upgrading of chunks saved before 1.13 to the current format,
+//! a minimal version of vanilla's DataFixer.
+//!
+//! Before 1.13 ("the flattening"), blocks were stored as a numeric
+//! ID and four bits of metadata. These are converted to block states
+//! using the vanilla mappings listed in `legacy_blocks.txt`. Entity
+//! and block entity IDs are renamed to their namespaced versions;
+//! entities and block entities which still don't match the current
+//! format, such as chests holding items with numeric IDs, are dropped.
+//!
+//! Upgraded chunks remain marked as modified, so they're
+//! written back in the current format when saved.
+
+use crate::block_entity::BlockEntityData;
+use crate::entity::EntityData;
+use crate::region::{read_light, Error};
+use feather_biomes::{registry, Biome, BIOMES_2D_LEN};
+use feather_blocks::{BlockId, HalfUpperLower};
+use feather_chunk::{BiomeStorage, Chunk, ChunkSection, PalettedContainer};
+use feather_util::ChunkPosition;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Data version of the first snapshot storing block states, 17w47a.
+/// Chunks with an older (or no) data version are upgraded.
+pub const FLATTENING_DATA_VERSION: i32 = 1451;
+
+/// Number of legacy block IDs. IDs above 255 are only
+/// used by mods, through a section's `Add` array.
+const LEGACY_IDS: usize = 4096;
+
+/// Legacy entity IDs, used before 1.11, and their current versions.
+const ENTITY_IDS: &[(&str, &str)] = &[
+    ("Item", "minecraft:item"),
+    ("Arrow", "minecraft:arrow"),
+    ("Cow", "minecraft:cow"),
+    ("Pig", "minecraft:pig"),
+    ("Chicken", "minecraft:chicken"),
+    ("Sheep", "minecraft:sheep"),
+    ("EntityHorse", "minecraft:horse"),
+    ("MushroomCow", "minecraft:mooshroom"),
+    ("Rabbit", "minecraft:rabbit"),
+    ("Squid", "minecraft:squid"),
+];
+
+/// Legacy block entity IDs, used before 1.11, and their current versions.
+const BLOCK_ENTITY_IDS: &[(&str, &str)] = &[
+    ("Airportal", "minecraft:end_portal"),
+    ("Banner", "minecraft:banner"),
+    ("Beacon", "minecraft:beacon"),
+    ("Cauldron", "minecraft:brewing_stand"),
+    ("Chest", "minecraft:chest"),
+    ("Comparator", "minecraft:comparator"),
+    ("Control", "minecraft:command_block"),
+    ("DLDetector", "minecraft:daylight_detector"),
+    ("Dropper", "minecraft:dropper"),
+    ("EnchantTable", "minecraft:enchanting_table"),
+    ("EnderChest", "minecraft:ender_chest"),
+    ("EndGateway", "minecraft:end_gateway"),
+    ("FlowerPot", "minecraft:flower_pot"),
+    ("Furnace", "minecraft:furnace"),
+    ("Hopper", "minecraft:hopper"),
+    ("MobSpawner", "minecraft:mob_spawner"),
+    ("Music", "minecraft:noteblock"),
+    ("Piston", "minecraft:piston"),
+    ("RecordPlayer", "minecraft:jukebox"),
+    ("Sign", "minecraft:sign"),
+    ("Skull", "minecraft:skull"),
+    ("Structure", "minecraft:structure_block"),
+    ("Trap", "minecraft:dispenser"),
+];
+
+/// Block states of all legacy IDs and metadata values, indexed
+/// by `id << 4 | meta`. Metadata values without a mapping use
+/// the block of metadata 0, and unknown IDs become air.
+static LEGACY_BLOCKS: Lazy<Vec<BlockId>> = Lazy::new(|| {
+    let mut table = vec![None; LEGACY_IDS << 4];
+    for line in include_str!("legacy_blocks.txt").lines() {
+        let (index, block) = parse_legacy_entry(line)
+            .unwrap_or_else(|| panic!("invalid legacy block mapping `{}`", line));
+        table[index] = Some(block);
+    }
+
+    (0..table.len())
+        .map(|index| {
+            table[index]
+                .or(table[index & !0xF])
+                .unwrap_or_else(BlockId::air)
+        })
+        .collect()
+});
+
+/// Parses a line of the legacy block table, such as
+/// `17:4 minecraft:oak_log[axis=x]`. Properties not
+/// listed take their default values.
+fn parse_legacy_entry(line: &str) -> Option<(usize, BlockId)> {
+    let mut parts = line.splitn(2, ' ');
+    let mut legacy = parts.next()?.splitn(2, ':');
+    let id: usize = legacy.next()?.parse().ok()?;
+    let meta: usize = legacy.next()?.parse().ok()?;
+
+    let state = parts.next()?;
+    let (name, props) = match state.find('[') {
+        Some(start) => (&state[..start], state[start + 1..].trim_end_matches(']')),
+        None => (state, ""),
+    };
+
+    let block = BlockId::from_identifier(name)?;
+    let mut properties: BTreeMap<String, String> = block
+        .to_properties_map()
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+    for prop in props.split(',').filter(|prop| !prop.is_empty()) {
+        let mut prop = prop.splitn(2, '=');
+        let key = prop.next()?;
+        properties.insert(key.to_owned(), prop.next()?.to_owned());
+    }
+
+    let block = BlockId::from_identifier_and_properties(name, &properties)?;
+    Some((id << 4 | meta, block))
+}
+
+/// Returns the block state corresponding to a legacy block ID and
+/// metadata value, as stored in chunks before 1.13 and in MCEdit
+/// schematics.
+///
+/// The upper halves of doors and tall plants only store
+/// part of their state; see `fix_upper_halves`.
+pub fn legacy_block(id: u16, meta: u8) -> BlockId {
+    LEGACY_BLOCKS
+        .get(usize::from(id) << 4 | usize::from(meta & 0xF))
+        .copied()
+        .unwrap_or_else(BlockId::air)
+}
+
+/// Represents the data for a chunk saved before the flattening.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacyChunkRoot {
+    level: LegacyChunkLevel,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct LegacyChunkLevel {
+    #[serde(default)]
+    terrain_populated: i8,
+    #[serde(default)]
+    sections: Vec<LegacySection>,
+    /// One byte per column. Missing in chunks which
+    /// were saved before their biomes were generated.
+    #[serde(default)]
+    biomes: Vec<i8>,
+    #[serde(default)]
+    entities: Vec<nbt::Value>,
+    #[serde(default)]
+    tile_entities: Vec<nbt::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct LegacySection {
+    y: i8,
+    /// The lower 8 bits of each block ID.
+    blocks: Vec<i8>,
+    /// The metadata of each block, two per byte.
+    data: Vec<i8>,
+    /// The upper 4 bits of each block ID, two per byte.
+    #[serde(default)]
+    add: Vec<i8>,
+    #[serde(default)]
+    block_light: Vec<i8>,
+    #[serde(default)]
+    sky_light: Vec<i8>,
+}
+
+/// Upgrades a chunk saved before the flattening. Returns `None`
+/// if the chunk hadn't finished generating, so it should be
+/// generated again.
+pub(crate) fn upgrade_chunk(
+    root: LegacyChunkRoot,
+    pos: ChunkPosition,
+) -> Result<Option<(Chunk, Vec<EntityData>, Vec<BlockEntityData>)>, Error> {
+    let level = root.level;
+
+    // Before 1.13, chunks were saved before trees, ores
+    // and other features were added.
+    if level.terrain_populated == 0 {
+        return Ok(None);
+    }
+
+    let mut chunk = Chunk::new(pos);
+    for section in &level.sections {
+        upgrade_section(section, &mut chunk)?;
+    }
+    fix_upper_halves(&mut chunk);
+
+    // Biome IDs haven't changed, but unset columns were stored as 255.
+    let biomes: Vec<i32> = if level.biomes.len() == BIOMES_2D_LEN {
+        level
+            .biomes
+            .iter()
+            .map(|&id| i32::from(id as u8))
+            .map(|id| {
+                if registry().contains(id) {
+                    id
+                } else {
+                    Biome::Plains.id()
+                }
+            })
+            .collect()
+    } else {
+        vec![Biome::Plains.id(); BIOMES_2D_LEN]
+    };
+    chunk.set_biomes(BiomeStorage::decode(&biomes).ok_or(Error::IndexOutOfBounds)?);
+
+    chunk.recalculate_heightmap();
+
+    let entities = level
+        .entities
+        .into_iter()
+        .filter_map(|entity| upgrade_entity(entity, ENTITY_IDS))
+        .collect();
+    let block_entities = level
+        .tile_entities
+        .into_iter()
+        .filter_map(|block_entity| upgrade_entity(block_entity, BLOCK_ENTITY_IDS))
+        .collect();
+
+    Ok(Some((chunk, entities, block_entities)))
+}
+
+fn upgrade_section(section: &LegacySection, chunk: &mut Chunk) -> Result<(), Error> {
+    if section.y < 0 || section.y >= 16 {
+        return Err(Error::IndexOutOfBounds);
+    }
+    if section.blocks.len() != 4096
+        || section.data.len() != 2048
+        || !(section.add.is_empty() || section.add.len() == 2048)
+    {
+        return Err(Error::IndexOutOfBounds);
+    }
+
+    let nibble = |array: &[i8], index: usize| (array[index / 2] as u8 >> (index % 2 * 4)) & 0xF;
+
+    // Indices are in the same YZX order as in the current format.
+    let mut blocks = PalettedContainer::new();
+    for index in 0..4096 {
+        let mut id = u16::from(section.blocks[index] as u8);
+        if !section.add.is_empty() {
+            id |= u16::from(nibble(&section.add, index)) << 8;
+        }
+        blocks.set(index, legacy_block(id, nibble(&section.data, index)));
+    }
+    blocks.optimize();
+
+    let block_light = read_light(&section.block_light, 0)?;
+    let sky_light = read_light(&section.sky_light, 15)?;
+
+    chunk.set_section_at(
+        usize::from(section.y as u8),
+        Some(ChunkSection::from_blocks(blocks, block_light, sky_light)),
+    );
+
+    Ok(())
+}
+
+/// Completes the state of the upper halves of doors and tall
+/// plants, which is partly stored in their lower halves.
+fn fix_upper_halves(chunk: &mut Chunk) {
+    for y in 1..256 {
+        if chunk.section(y / 16).is_none() {
+            continue;
+        }
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let upper = chunk.block_at(x, y, z);
+                let lower = chunk.block_at(x, y - 1, z);
+                if upper.half_upper_lower() != Some(HalfUpperLower::Upper)
+                    || lower.half_upper_lower() != Some(HalfUpperLower::Lower)
+                {
+                    continue;
+                }
+
+                let fixed = match upper.hinge() {
+                    // Doors store their hinge and whether
+                    // they're powered in the upper half.
+                    Some(hinge) if upper.kind() == lower.kind() => lower
+                        .with_half_upper_lower(HalfUpperLower::Upper)
+                        .with_hinge(hinge)
+                        .with_powered(upper.powered().unwrap_or(false)),
+                    Some(_) => continue,
+                    // The kind of a tall plant is only stored in the lower half.
+                    None => lower.with_half_upper_lower(HalfUpperLower::Upper),
+                };
+                chunk.set_block_at(x, y, z, fixed);
+            }
+        }
+    }
+}
+
+/// Renames the ID of an entity or block entity using the
+/// given mappings, then converts it to the current format.
+/// Returns `None` if the conversion fails.
+fn upgrade_entity<T: DeserializeOwned>(mut value: nbt::Value, ids: &[(&str, &str)]) -> Option<T> {
+    if let nbt::Value::Compound(tags) = &mut value {
+        if let Some(nbt::Value::String(id)) = tags.get_mut("id") {
+            if let Some((_, new_id)) = ids.iter().find(|(old_id, _)| old_id == id) {
+                *id = (*new_id).to_owned();
+            }
+        }
+    }
+
+    let mut buf = Vec::new();
+    nbt::to_writer(&mut buf, &value, None).ok()?;
+    nbt::from_reader(&buf[..]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_blocks::{AxisXyz, FacingCardinal, Hinge};
+
+    #[test]
+    fn legacy_table_is_valid() {
+        for line in include_str!("legacy_blocks.txt").lines() {
+            assert!(parse_legacy_entry(line).is_some(), "{}", line);
+        }
+
+        assert_eq!(legacy_block(1, 0), BlockId::stone());
+        assert_eq!(legacy_block(1, 3), BlockId::diorite());
+        assert_eq!(
+            legacy_block(17, 6),
+            BlockId::birch_log().with_axis_xyz(AxisXyz::X)
+        );
+        assert_eq!(legacy_block(35, 14), BlockId::red_wool());
+        // Unmapped metadata falls back to the default block.
+        assert_eq!(legacy_block(4, 7), BlockId::cobblestone());
+        assert_eq!(legacy_block(300, 0), BlockId::air());
+    }
+
+    fn legacy_section(y: i8, blocks: &[(usize, u8, u8)]) -> LegacySection {
+        let mut section = LegacySection {
+            y,
+            blocks: vec![0; 4096],
+            data: vec![0; 2048],
+            add: vec![],
+            block_light: vec![],
+            sky_light: vec![],
+        };
+        for &(index, id, meta) in blocks {
+            section.blocks[index] = id as i8;
+            section.data[index / 2] |= (meta << (index % 2 * 4)) as i8;
+        }
+        section
+    }
+
+    #[test]
+    fn upgrade_legacy_chunk() {
+        let index = |x: usize, y: usize, z: usize| (y << 8) | (z << 4) | x;
+        let root = LegacyChunkRoot {
+            level: LegacyChunkLevel {
+                terrain_populated: 1,
+                sections: vec![
+                    legacy_section(0, &[(index(0, 0, 0), 7, 0), (index(1, 5, 2), 3, 2)]),
+                    // A door facing north with its hinge on the right,
+                    // crossing the border between two sections.
+                    legacy_section(1, &[(index(4, 15, 4), 64, 3 | 4)]),
+                    legacy_section(2, &[(index(4, 0, 4), 64, 8 | 1)]),
+                ],
+                biomes: vec![6; 256],
+                entities: vec![],
+                tile_entities: vec![],
+            },
+        };
+
+        let (chunk, entities, block_entities) = upgrade_chunk(root, ChunkPosition::new(3, -2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk.position(), ChunkPosition::new(3, -2));
+        assert_eq!(chunk.block_at(0, 0, 0), BlockId::bedrock());
+        assert_eq!(chunk.block_at(1, 5, 2), BlockId::podzol());
+        assert_eq!(chunk.block_at(2, 5, 1), BlockId::air());
+        assert_eq!(chunk.biomes().biome_id_at(3, 3), Biome::Swamp.id());
+        assert!(entities.is_empty() && block_entities.is_empty());
+
+        let lower = chunk.block_at(4, 31, 4);
+        let upper = chunk.block_at(4, 32, 4);
+        assert_eq!(lower.half_upper_lower(), Some(HalfUpperLower::Lower));
+        assert_eq!(upper.half_upper_lower(), Some(HalfUpperLower::Upper));
+        for door in &[lower, upper] {
+            assert_eq!(door.kind(), BlockId::oak_door().kind());
+            assert_eq!(door.facing_cardinal(), Some(FacingCardinal::North));
+            assert_eq!(door.open(), Some(true));
+            assert_eq!(door.hinge(), Some(Hinge::Right));
+        }
+
+        // Chunks without their features are generated again.
+        let root = LegacyChunkRoot {
+            level: LegacyChunkLevel {
+                terrain_populated: 0,
+                sections: vec![],
+                biomes: vec![],
+                entities: vec![],
+                tile_entities: vec![],
+            },
+        };
+        assert!(upgrade_chunk(root, ChunkPosition::new(0, 0))
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! Biome categories and relationships between biomes.
+
+use crate::Biome;
+
+/// Broad category a biome belongs to. Biomes in the
+/// same category are considered similar by world generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BiomeCategory {
+    None,
+    Taiga,
+    ExtremeHills,
+    Jungle,
+    Mesa,
+    Plains,
+    Savanna,
+    Icy,
+    TheEnd,
+    Beach,
+    Forest,
+    Ocean,
+    Desert,
+    River,
+    Swamp,
+    Mushroom,
+    Nether,
+}
+
+/// Temperature class of a biome, derived from its temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TempCategory {
+    Ocean,
+    Cold,
+    Medium,
+    Warm,
+}
+
+/// Type of precipitation which falls in a biome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
+impl Biome {
+    /// Returns the category of this biome.
+    pub fn category(self) -> BiomeCategory {
+        match self {
+            Biome::Ocean
+            | Biome::DeepOcean
+            | Biome::FrozenOcean
+            | Biome::DeepFrozenOcean
+            | Biome::ColdOcean
+            | Biome::DeepColdOcean
+            | Biome::LukewarmOcean
+            | Biome::DeepLukewarmOcean
+            | Biome::WarmOcean
+            | Biome::DeepWarmOcean => BiomeCategory::Ocean,
+            Biome::Plains | Biome::SunflowerPlains => BiomeCategory::Plains,
+            Biome::Desert | Biome::DesertHills | Biome::DesertLakes => BiomeCategory::Desert,
+            Biome::Mountains
+            | Biome::MountainEdge
+            | Biome::GravellyMountains
+            | Biome::ModifiedGravellyMountains
+            | Biome::WoodedMountains => BiomeCategory::ExtremeHills,
+            Biome::Forest
+            | Biome::WoodedHills
+            | Biome::FlowerForest
+            | Biome::BirchForest
+            | Biome::BirchForestHills
+            | Biome::TallBirchForest
+            | Biome::TallBirchHills
+            | Biome::DarkForest
+            | Biome::DarkForestHills => BiomeCategory::Forest,
+            Biome::Taiga
+            | Biome::TaigaHills
+            | Biome::TaigaMountains
+            | Biome::SnowyTaiga
+            | Biome::SnowyTaigaHills
+            | Biome::SnowyTaigaMountains
+            | Biome::GiantTreeTaiga
+            | Biome::GiantTreeTaigaHills
+            | Biome::GiantSpruceTaiga
+            | Biome::GiantSpruceTaigaHills => BiomeCategory::Taiga,
+            Biome::Swamp | Biome::SwampHills => BiomeCategory::Swamp,
+            Biome::River | Biome::FrozenRiver => BiomeCategory::River,
+            Biome::Nether => BiomeCategory::Nether,
+            Biome::TheEnd
+            | Biome::SmallEndIslands
+            | Biome::EndMidlands
+            | Biome::EndHighlands
+            | Biome::EndBarrens => BiomeCategory::TheEnd,
+            Biome::SnowyTundra | Biome::SnowyMountains | Biome::IceSpikes => BiomeCategory::Icy,
+            Biome::MushroomFields | Biome::MushroomFieldShore => BiomeCategory::Mushroom,
+            Biome::Beach | Biome::SnowyBeach => BiomeCategory::Beach,
+            Biome::Jungle
+            | Biome::JungleHills
+            | Biome::JungleEdge
+            | Biome::ModifiedJungle
+            | Biome::ModifiedJungleEdge => BiomeCategory::Jungle,
+            Biome::Savanna
+            | Biome::SavannaPlateau
+            | Biome::ShatteredSavanna
+            | Biome::ShatteredSavannaPlateau => BiomeCategory::Savanna,
+            Biome::Badlands
+            | Biome::BadlandsPlateau
+            | Biome::ErodedBadlands
+            | Biome::WoodedBadlandsPlateau
+            | Biome::ModifiedBadlandsPlateau
+            | Biome::ModifiedWoodedBadlandsPlateau => BiomeCategory::Mesa,
+            Biome::StoneShore | Biome::TheVoid => BiomeCategory::None,
+        }
+    }
+
+    /// Returns the temperature category of this biome.
+    pub fn temp_category(self) -> TempCategory {
+        if self.category() == BiomeCategory::Ocean {
+            TempCategory::Ocean
+        } else if self.temperature() < 0.2 {
+            TempCategory::Cold
+        } else if self.temperature() < 1.0 {
+            TempCategory::Medium
+        } else {
+            TempCategory::Warm
+        }
+    }
+
+    /// Returns the type of precipitation in this biome.
+    pub fn precipitation(self) -> Precipitation {
+        match self {
+            Biome::FrozenOcean
+            | Biome::FrozenRiver
+            | Biome::SnowyTundra
+            | Biome::SnowyMountains
+            | Biome::IceSpikes
+            | Biome::SnowyBeach
+            | Biome::SnowyTaiga
+            | Biome::SnowyTaigaHills
+            | Biome::SnowyTaigaMountains => Precipitation::Snow,
+            _ if self.downfall() == 0.0 => Precipitation::None,
+            Biome::TheEnd
+            | Biome::SmallEndIslands
+            | Biome::EndMidlands
+            | Biome::EndHighlands
+            | Biome::EndBarrens
+            | Biome::TheVoid => Precipitation::None,
+            _ => Precipitation::Rain,
+        }
+    }
+
+    /// Returns whether this biome is an ocean of any kind.
+    pub fn is_ocean(self) -> bool {
+        self.category() == BiomeCategory::Ocean
+    }
+
+    /// Returns the mutated ("M") variant of this biome, if it has one.
+    pub fn mutation(self) -> Option<Biome> {
+        Some(match self {
+            Biome::Plains => Biome::SunflowerPlains,
+            Biome::Desert => Biome::DesertLakes,
+            Biome::Mountains => Biome::GravellyMountains,
+            Biome::Forest => Biome::FlowerForest,
+            Biome::Taiga => Biome::TaigaMountains,
+            Biome::Swamp => Biome::SwampHills,
+            Biome::SnowyTundra => Biome::IceSpikes,
+            Biome::Jungle => Biome::ModifiedJungle,
+            Biome::JungleEdge => Biome::ModifiedJungleEdge,
+            Biome::BirchForest => Biome::TallBirchForest,
+            Biome::BirchForestHills => Biome::TallBirchHills,
+            Biome::DarkForest => Biome::DarkForestHills,
+            Biome::SnowyTaiga => Biome::SnowyTaigaMountains,
+            Biome::GiantTreeTaiga => Biome::GiantSpruceTaiga,
+            Biome::GiantTreeTaigaHills => Biome::GiantSpruceTaigaHills,
+            Biome::WoodedMountains => Biome::ModifiedGravellyMountains,
+            Biome::Savanna => Biome::ShatteredSavanna,
+            Biome::SavannaPlateau => Biome::ShatteredSavannaPlateau,
+            Biome::Badlands => Biome::ErodedBadlands,
+            Biome::WoodedBadlandsPlateau => Biome::ModifiedWoodedBadlandsPlateau,
+            Biome::BadlandsPlateau => Biome::ModifiedBadlandsPlateau,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether this biome is the mutated variant of another biome.
+    pub fn is_mutation(self) -> bool {
+        // Mutated biomes have numeric IDs offset by 128 from their parents.
+        self.id() >= 128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn mutation_ids() {
+        for biome in Biome::iter() {
+            if let Some(mutation) = biome.mutation() {
+                assert_eq!(mutation.id(), biome.id() + 128);
+                assert!(mutation.is_mutation());
+                assert!(!biome.is_mutation());
+            }
+        }
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(Biome::DeepWarmOcean.temp_category(), TempCategory::Ocean);
+        assert_eq!(Biome::SnowyTaiga.temp_category(), TempCategory::Cold);
+        assert_eq!(Biome::Plains.temp_category(), TempCategory::Medium);
+        assert_eq!(Biome::Desert.temp_category(), TempCategory::Warm);
+        assert_eq!(Biome::Desert.precipitation(), Precipitation::None);
+        assert_eq!(Biome::SnowyBeach.precipitation(), Precipitation::Snow);
+    }
+}
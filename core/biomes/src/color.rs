@@ -0,0 +1,298 @@
+//! Biome climate and color data.
+//!
+//! Grass and foliage colors are computed from the biome's
+//! temperature and downfall using the same triangular
+//! colormap the vanilla client uses. Since we don't ship the
+//! colormap textures, the map is reconstructed from the colors
+//! at its three corners, which is accurate to within a few
+//! units per channel.
+
+use crate::Biome;
+
+/// A color packed as `0xRRGGBB`.
+pub type Rgb = u32;
+
+/// Default water color used by most biomes.
+pub const DEFAULT_WATER_COLOR: Rgb = 0x3F_76_E4;
+/// Default underwater fog color used by most biomes.
+pub const DEFAULT_WATER_FOG_COLOR: Rgb = 0x05_05_33;
+/// Fog color used by overworld biomes.
+pub const OVERWORLD_FOG_COLOR: Rgb = 0xC0_D8_FF;
+
+/// Corners of the grass colormap: hot and wet, hot and dry, cold.
+const GRASS_CORNERS: [Rgb; 3] = [0x47_CD_33, 0xBF_B7_55, 0x80_B4_97];
+/// Corners of the foliage colormap: hot and wet, hot and dry, cold.
+const FOLIAGE_CORNERS: [Rgb; 3] = [0x1A_BF_00, 0xAE_A4_2A, 0x60_A1_7B];
+
+impl Biome {
+    /// Returns the temperature of this biome.
+    pub fn temperature(self) -> f32 {
+        match self {
+            Biome::Desert
+            | Biome::DesertHills
+            | Biome::DesertLakes
+            | Biome::Nether
+            | Biome::Badlands
+            | Biome::BadlandsPlateau
+            | Biome::ErodedBadlands
+            | Biome::ModifiedBadlandsPlateau
+            | Biome::ModifiedWoodedBadlandsPlateau
+            | Biome::WoodedBadlandsPlateau => 2.0,
+            Biome::Savanna => 1.2,
+            Biome::ShatteredSavanna => 1.1,
+            Biome::SavannaPlateau | Biome::ShatteredSavannaPlateau => 1.0,
+            Biome::Jungle
+            | Biome::JungleHills
+            | Biome::JungleEdge
+            | Biome::ModifiedJungle
+            | Biome::ModifiedJungleEdge => 0.95,
+            Biome::MushroomFields | Biome::MushroomFieldShore => 0.9,
+            Biome::Plains
+            | Biome::SunflowerPlains
+            | Biome::Beach
+            | Biome::Swamp
+            | Biome::SwampHills => 0.8,
+            Biome::Forest
+            | Biome::WoodedHills
+            | Biome::FlowerForest
+            | Biome::DarkForest
+            | Biome::DarkForestHills => 0.7,
+            Biome::BirchForest
+            | Biome::BirchForestHills
+            | Biome::TallBirchForest
+            | Biome::TallBirchHills => 0.6,
+            Biome::GiantTreeTaiga | Biome::GiantTreeTaigaHills => 0.3,
+            Biome::Taiga
+            | Biome::TaigaHills
+            | Biome::TaigaMountains
+            | Biome::GiantSpruceTaiga
+            | Biome::GiantSpruceTaigaHills => 0.25,
+            Biome::Mountains
+            | Biome::MountainEdge
+            | Biome::GravellyMountains
+            | Biome::ModifiedGravellyMountains
+            | Biome::WoodedMountains
+            | Biome::StoneShore => 0.2,
+            Biome::SnowyBeach => 0.05,
+            Biome::FrozenOcean
+            | Biome::FrozenRiver
+            | Biome::SnowyTundra
+            | Biome::SnowyMountains
+            | Biome::IceSpikes => 0.0,
+            Biome::SnowyTaiga | Biome::SnowyTaigaHills | Biome::SnowyTaigaMountains => -0.5,
+            _ => 0.5,
+        }
+    }
+
+    /// Returns the downfall (humidity) of this biome.
+    pub fn downfall(self) -> f32 {
+        match self {
+            Biome::MushroomFields | Biome::MushroomFieldShore => 1.0,
+            Biome::Swamp
+            | Biome::SwampHills
+            | Biome::Jungle
+            | Biome::JungleHills
+            | Biome::ModifiedJungle => 0.9,
+            Biome::Forest
+            | Biome::WoodedHills
+            | Biome::FlowerForest
+            | Biome::DarkForest
+            | Biome::DarkForestHills
+            | Biome::Taiga
+            | Biome::TaigaHills
+            | Biome::TaigaMountains
+            | Biome::GiantTreeTaiga
+            | Biome::GiantTreeTaigaHills
+            | Biome::GiantSpruceTaiga
+            | Biome::GiantSpruceTaigaHills
+            | Biome::JungleEdge
+            | Biome::ModifiedJungleEdge => 0.8,
+            Biome::BirchForest
+            | Biome::BirchForestHills
+            | Biome::TallBirchForest
+            | Biome::TallBirchHills => 0.6,
+            Biome::Plains
+            | Biome::SunflowerPlains
+            | Biome::Beach
+            | Biome::SnowyTaiga
+            | Biome::SnowyTaigaHills
+            | Biome::SnowyTaigaMountains => 0.4,
+            Biome::Mountains
+            | Biome::MountainEdge
+            | Biome::GravellyMountains
+            | Biome::ModifiedGravellyMountains
+            | Biome::WoodedMountains
+            | Biome::StoneShore
+            | Biome::SnowyBeach => 0.3,
+            Biome::Desert
+            | Biome::DesertHills
+            | Biome::DesertLakes
+            | Biome::Nether
+            | Biome::Savanna
+            | Biome::SavannaPlateau
+            | Biome::ShatteredSavanna
+            | Biome::ShatteredSavannaPlateau
+            | Biome::Badlands
+            | Biome::BadlandsPlateau
+            | Biome::ErodedBadlands
+            | Biome::ModifiedBadlandsPlateau
+            | Biome::ModifiedWoodedBadlandsPlateau
+            | Biome::WoodedBadlandsPlateau => 0.0,
+            _ => 0.5,
+        }
+    }
+
+    /// Returns the color of water in this biome.
+    pub fn water_color(self) -> Rgb {
+        match self {
+            Biome::Swamp | Biome::SwampHills => 0x61_7B_64,
+            Biome::WarmOcean | Biome::DeepWarmOcean => 0x43_D5_EE,
+            Biome::LukewarmOcean | Biome::DeepLukewarmOcean => 0x45_AD_F2,
+            Biome::ColdOcean | Biome::DeepColdOcean | Biome::SnowyTaiga => 0x3D_57_D6,
+            Biome::FrozenOcean | Biome::DeepFrozenOcean | Biome::FrozenRiver => 0x39_38_C9,
+            _ => DEFAULT_WATER_COLOR,
+        }
+    }
+
+    /// Returns the color of the fog seen while underwater in this biome.
+    pub fn water_fog_color(self) -> Rgb {
+        match self {
+            Biome::Swamp | Biome::SwampHills => 0x23_23_17,
+            Biome::WarmOcean | Biome::DeepWarmOcean => 0x04_1F_33,
+            Biome::LukewarmOcean | Biome::DeepLukewarmOcean => 0x04_16_33,
+            _ => DEFAULT_WATER_FOG_COLOR,
+        }
+    }
+
+    /// Returns the sky color of this biome, which vanilla
+    /// derives from its temperature.
+    pub fn sky_color(self) -> Rgb {
+        let temp = (self.temperature() / 3.0).max(-1.0).min(1.0);
+        hsb_to_rgb(0.622_222_24 - temp * 0.05, 0.5 + temp * 0.1, 1.0)
+    }
+
+    /// Returns the fog color of this biome.
+    pub fn fog_color(self) -> Rgb {
+        match self {
+            Biome::Nether => 0x33_08_08,
+            Biome::TheEnd
+            | Biome::SmallEndIslands
+            | Biome::EndMidlands
+            | Biome::EndHighlands
+            | Biome::EndBarrens => 0xA0_80_A0,
+            _ => OVERWORLD_FOG_COLOR,
+        }
+    }
+
+    /// Returns the grass color of this biome.
+    pub fn grass_color(self) -> Rgb {
+        match self {
+            Biome::Swamp | Biome::SwampHills => 0x6A_70_39,
+            Biome::Badlands
+            | Biome::BadlandsPlateau
+            | Biome::ErodedBadlands
+            | Biome::ModifiedBadlandsPlateau
+            | Biome::ModifiedWoodedBadlandsPlateau
+            | Biome::WoodedBadlandsPlateau => 0x90_81_4D,
+            Biome::DarkForest | Biome::DarkForestHills => {
+                let base = grass_colormap(self.temperature(), self.downfall());
+                ((base & 0xFE_FE_FE) + 0x28_34_0A) >> 1
+            }
+            _ => grass_colormap(self.temperature(), self.downfall()),
+        }
+    }
+
+    /// Returns the foliage (leaves and vines) color of this biome.
+    pub fn foliage_color(self) -> Rgb {
+        match self {
+            Biome::Swamp | Biome::SwampHills => 0x6A_70_39,
+            Biome::Badlands
+            | Biome::BadlandsPlateau
+            | Biome::ErodedBadlands
+            | Biome::ModifiedBadlandsPlateau
+            | Biome::ModifiedWoodedBadlandsPlateau
+            | Biome::WoodedBadlandsPlateau => 0x9E_81_4D,
+            _ => foliage_colormap(self.temperature(), self.downfall()),
+        }
+    }
+}
+
+/// Samples the grass colormap at the given temperature and downfall.
+pub fn grass_colormap(temperature: f32, downfall: f32) -> Rgb {
+    sample_colormap(&GRASS_CORNERS, temperature, downfall)
+}
+
+/// Samples the foliage colormap at the given temperature and downfall.
+pub fn foliage_colormap(temperature: f32, downfall: f32) -> Rgb {
+    sample_colormap(&FOLIAGE_CORNERS, temperature, downfall)
+}
+
+fn sample_colormap(corners: &[Rgb; 3], temperature: f32, downfall: f32) -> Rgb {
+    // Vanilla scales downfall by temperature, which is what
+    // makes the colormap triangular.
+    let temperature = temperature.max(0.0).min(1.0);
+    let downfall = downfall.max(0.0).min(1.0) * temperature;
+
+    let weights = [downfall, temperature - downfall, 1.0 - temperature];
+
+    let channel = |shift: u32| {
+        let value: f32 = corners
+            .iter()
+            .zip(weights.iter())
+            .map(|(corner, weight)| ((corner >> shift) & 0xFF) as f32 * weight)
+            .sum();
+        (value.round().max(0.0).min(255.0) as u32) << shift
+    };
+
+    channel(16) | channel(8) | channel(0)
+}
+
+/// HSV to RGB conversion matching the one vanilla uses
+/// for sky colors (channels are truncated, not rounded).
+fn hsb_to_rgb(hue: f32, saturation: f32, brightness: f32) -> Rgb {
+    let h = (hue - hue.floor()) * 6.0;
+    let f = h - h.floor();
+    let p = brightness * (1.0 - saturation);
+    let q = brightness * (1.0 - saturation * f);
+    let t = brightness * (1.0 - saturation * (1.0 - f));
+
+    let (r, g, b) = match h as u32 {
+        0 => (brightness, t, p),
+        1 => (q, brightness, p),
+        2 => (p, brightness, t),
+        3 => (p, q, brightness),
+        4 => (t, p, brightness),
+        _ => (brightness, p, q),
+    };
+
+    let to_u8 = |x: f32| (x * 255.0) as u32;
+    (to_u8(r) << 16) | (to_u8(g) << 8) | to_u8(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colormap_corners() {
+        assert_eq!(grass_colormap(1.0, 1.0), 0x47_CD_33);
+        assert_eq!(grass_colormap(1.0, 0.0), 0xBF_B7_55);
+        assert_eq!(grass_colormap(0.0, 0.5), 0x80_B4_97);
+        assert_eq!(foliage_colormap(2.0, 0.0), 0xAE_A4_2A);
+    }
+
+    #[test]
+    fn sky_colors() {
+        // Values taken from the vanilla biome definitions.
+        assert_eq!(Biome::Plains.sky_color(), 0x78_A7_FF);
+        assert_eq!(Biome::Desert.sky_color(), 0x6E_B1_FF);
+        assert_eq!(Biome::SnowyTundra.sky_color(), 0x7F_A1_FF);
+    }
+
+    #[test]
+    fn overrides() {
+        assert_eq!(Biome::Swamp.water_color(), 0x61_7B_64);
+        assert_eq!(Biome::Badlands.grass_color(), 0x90_81_4D);
+        assert_eq!(Biome::Plains.water_color(), DEFAULT_WATER_COLOR);
+    }
+}
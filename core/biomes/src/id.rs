@@ -0,0 +1,197 @@
+//! Numeric and namespaced biome IDs, plus encoding of
+//! the biome arrays sent in chunk packets.
+
+use crate::Biome;
+
+/// Namespace used by all vanilla biomes.
+pub const NAMESPACE: &str = "minecraft";
+
+/// Number of biome columns in a 2D (1.13-1.14) biome array.
+pub const BIOMES_2D_LEN: usize = 16 * 16;
+/// Number of biome cells in a 3D (1.15+) biome array.
+/// Each cell covers a 4x4x4 volume of blocks.
+pub const BIOMES_3D_LEN: usize = 4 * 4 * 64;
+
+/// First protocol version (1.15) using 3D biome arrays.
+const FIRST_3D_PROTOCOL_VERSION: u32 = 573;
+
+/// Vertical cell sampled when a 3D biome array
+/// is flattened into a 2D one. This corresponds to sea level.
+const FLATTEN_CELL_Y: usize = 64 / 4;
+
+/// The layout of the biome array in a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BiomeArrayFormat {
+    /// One biome per column, indexed by `(z << 4) | x`.
+    TwoDimensional,
+    /// One biome per 4x4x4 cell, indexed by `(y << 4) | (z << 2) | x`.
+    ThreeDimensional,
+}
+
+impl BiomeArrayFormat {
+    /// Returns the biome array format used by the given protocol version.
+    pub fn for_protocol_version(version: u32) -> Self {
+        if version >= FIRST_3D_PROTOCOL_VERSION {
+            BiomeArrayFormat::ThreeDimensional
+        } else {
+            BiomeArrayFormat::TwoDimensional
+        }
+    }
+
+    /// Returns the number of entries in a biome array of this format.
+    pub fn array_len(self) -> usize {
+        match self {
+            BiomeArrayFormat::TwoDimensional => BIOMES_2D_LEN,
+            BiomeArrayFormat::ThreeDimensional => BIOMES_3D_LEN,
+        }
+    }
+}
+
+impl Biome {
+    /// Returns the numeric ID of this biome as used
+    /// in chunk packets and region files.
+    pub fn id(self) -> i32 {
+        self.protocol_id()
+    }
+
+    /// Returns the biome with the given numeric ID.
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::from_protocol_id(id)
+    }
+
+    /// Returns the namespaced ID of this biome, e.g. `minecraft:plains`.
+    pub fn namespaced_id(self) -> &'static str {
+        self.identifier()
+    }
+
+    /// Returns the ID of this biome without its namespace, e.g. `plains`.
+    pub fn name(self) -> &'static str {
+        let identifier = self.identifier();
+        &identifier[NAMESPACE.len() + 1..]
+    }
+
+    /// Returns the biome with the given namespaced ID.
+    /// IDs without a namespace are assumed to be in the
+    /// `minecraft` namespace.
+    pub fn from_namespaced_id(id: &str) -> Option<Self> {
+        if id.contains(':') {
+            Self::from_identifier(id)
+        } else {
+            Self::from_identifier(&format!("{}:{}", NAMESPACE, id))
+        }
+    }
+}
+
+/// Encodes a chunk's 2D biome array (as stored in `Chunk`)
+/// into numeric IDs using the given format.
+///
+/// # Panics
+/// Panics if `biomes.len() != 256`.
+pub fn encode_biomes(biomes: &[Biome], format: BiomeArrayFormat) -> Vec<i32> {
+    assert_eq!(biomes.len(), BIOMES_2D_LEN);
+
+    match format {
+        BiomeArrayFormat::TwoDimensional => biomes.iter().map(|biome| biome.id()).collect(),
+        BiomeArrayFormat::ThreeDimensional => {
+            let mut result = Vec::with_capacity(BIOMES_3D_LEN);
+            for _y in 0..64 {
+                for z in 0..4 {
+                    for x in 0..4 {
+                        // Sample the column in the center of the cell.
+                        let column = ((z * 4 + 2) << 4) | (x * 4 + 2);
+                        result.push(biomes[column].id());
+                    }
+                }
+            }
+            result
+        }
+    }
+}
+
+/// Decodes an array of numeric biome IDs in the given format
+/// into a 2D biome array as stored in `Chunk`.
+///
+/// Returns `None` if the array has the wrong length
+/// or contains an unknown biome ID.
+pub fn decode_biomes(ids: &[i32], format: BiomeArrayFormat) -> Option<Vec<Biome>> {
+    if ids.len() != format.array_len() {
+        return None;
+    }
+
+    match format {
+        BiomeArrayFormat::TwoDimensional => ids.iter().map(|id| Biome::from_id(*id)).collect(),
+        BiomeArrayFormat::ThreeDimensional => (0..BIOMES_2D_LEN)
+            .map(|column| {
+                let x = (column & 0xF) >> 2;
+                let z = (column >> 4) >> 2;
+                Biome::from_id(ids[(FLATTEN_CELL_Y << 4) | (z << 2) | x])
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn id_round_trip() {
+        for biome in Biome::iter() {
+            assert_eq!(Biome::from_id(biome.id()), Some(biome));
+            assert_eq!(
+                Biome::from_namespaced_id(biome.namespaced_id()),
+                Some(biome)
+            );
+            assert_eq!(Biome::from_namespaced_id(biome.name()), Some(biome));
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Biome::Plains.name(), "plains");
+        assert_eq!(Biome::Plains.namespaced_id(), "minecraft:plains");
+        assert_eq!(Biome::from_namespaced_id("minecraft:not_a_biome"), None);
+    }
+
+    #[test]
+    fn format_for_version() {
+        assert_eq!(
+            BiomeArrayFormat::for_protocol_version(404),
+            BiomeArrayFormat::TwoDimensional
+        );
+        assert_eq!(
+            BiomeArrayFormat::for_protocol_version(754),
+            BiomeArrayFormat::ThreeDimensional
+        );
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut biomes = vec![Biome::Plains; BIOMES_2D_LEN];
+        // Fill the 4x4 region at the origin with desert.
+        for z in 0..4 {
+            for x in 0..4 {
+                biomes[(z << 4) | x] = Biome::Desert;
+            }
+        }
+
+        let flat = encode_biomes(&biomes, BiomeArrayFormat::TwoDimensional);
+        assert_eq!(flat.len(), BIOMES_2D_LEN);
+        assert_eq!(
+            decode_biomes(&flat, BiomeArrayFormat::TwoDimensional).unwrap(),
+            biomes
+        );
+
+        let cells = encode_biomes(&biomes, BiomeArrayFormat::ThreeDimensional);
+        assert_eq!(cells.len(), BIOMES_3D_LEN);
+        assert_eq!(cells[0], Biome::Desert.id());
+        assert_eq!(cells[1], Biome::Plains.id());
+        assert_eq!(
+            decode_biomes(&cells, BiomeArrayFormat::ThreeDimensional).unwrap(),
+            biomes
+        );
+
+        assert!(decode_biomes(&[0; 3], BiomeArrayFormat::TwoDimensional).is_none());
+    }
+}
@@ -1,6 +1,23 @@
 use num_derive::{FromPrimitive, ToPrimitive};
 use strum_macros::*;
 
+mod category;
+mod color;
+mod id;
+mod registry;
+
+pub use category::{BiomeCategory, Precipitation, TempCategory};
+pub use color::{
+    foliage_colormap, grass_colormap, Rgb, DEFAULT_WATER_COLOR, DEFAULT_WATER_FOG_COLOR,
+    OVERWORLD_FOG_COLOR,
+};
+pub use id::{
+    decode_biomes, encode_biomes, BiomeArrayFormat, BIOMES_2D_LEN, BIOMES_3D_LEN, NAMESPACE,
+};
+pub use registry::{
+    registry, BiomeRegistry, CustomBiome, RegisterError, SpawnCategory, SpawnEntry, FIRST_CUSTOM_ID,
+};
+
 #[derive(
     Debug,
     Clone,
@@ -0,0 +1,224 @@
+//! Registration of custom biomes.
+//!
+//! Custom biomes are registered with a `BiomeRegistry` at startup,
+//! which is then installed as the global registry. Chunks store
+//! numeric biome IDs, so custom biomes can appear in chunks
+//! alongside vanilla ones. Code which needs a `Biome` (such as
+//! world generation) sees a custom biome as its `parent`.
+
+use crate::{Biome, BiomeCategory, Precipitation, Rgb};
+use once_cell::sync::OnceCell;
+use thiserror::Error;
+
+/// Numeric ID of the first custom biome.
+/// All vanilla biome IDs are lower.
+pub const FIRST_CUSTOM_ID: i32 = 256;
+
+static REGISTRY: OnceCell<BiomeRegistry> = OnceCell::new();
+
+/// Category of naturally spawning entities, each
+/// of which has its own spawn cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpawnCategory {
+    Monster,
+    Creature,
+    Ambient,
+    WaterCreature,
+    WaterAmbient,
+    Misc,
+}
+
+impl SpawnCategory {
+    /// Returns the category with the given name,
+    /// as used in data packs, e.g. `water_creature`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "monster" => SpawnCategory::Monster,
+            "creature" => SpawnCategory::Creature,
+            "ambient" => SpawnCategory::Ambient,
+            "water_creature" => SpawnCategory::WaterCreature,
+            "water_ambient" => SpawnCategory::WaterAmbient,
+            "misc" => SpawnCategory::Misc,
+            _ => return None,
+        })
+    }
+}
+
+/// An entity which may spawn naturally in a biome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnEntry {
+    pub category: SpawnCategory,
+    /// Namespaced ID of the entity type, e.g. `minecraft:zombie`.
+    pub entity: String,
+    /// Weight relative to the other entries of the biome.
+    pub weight: u32,
+    pub min_count: u32,
+    pub max_count: u32,
+}
+
+/// A biome which isn't part of vanilla.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomBiome {
+    /// Namespaced ID, e.g. `myplugin:crystal_fields`.
+    pub name: String,
+    /// Vanilla biome standing in for this one wherever
+    /// only vanilla biomes are supported.
+    pub parent: Biome,
+    pub temperature: f32,
+    pub downfall: f32,
+    pub precipitation: Precipitation,
+    pub category: BiomeCategory,
+    pub sky_color: Rgb,
+    pub fog_color: Rgb,
+    pub water_color: Rgb,
+    pub water_fog_color: Rgb,
+    /// Overrides the grass color computed from the climate.
+    pub grass_color: Option<Rgb>,
+    /// Overrides the foliage color computed from the climate.
+    pub foliage_color: Option<Rgb>,
+    /// Entities which spawn naturally in this biome.
+    pub spawns: Vec<SpawnEntry>,
+}
+
+impl CustomBiome {
+    /// Creates a custom biome with the climate
+    /// and colors of its parent.
+    pub fn based_on(name: impl Into<String>, parent: Biome) -> Self {
+        Self {
+            name: name.into(),
+            parent,
+            temperature: parent.temperature(),
+            downfall: parent.downfall(),
+            precipitation: parent.precipitation(),
+            category: parent.category(),
+            sky_color: parent.sky_color(),
+            fog_color: parent.fog_color(),
+            water_color: parent.water_color(),
+            water_fog_color: parent.water_fog_color(),
+            grass_color: None,
+            foliage_color: None,
+            spawns: Vec::new(),
+        }
+    }
+}
+
+/// Error returned when a biome cannot be registered.
+#[derive(Debug, Error)]
+pub enum RegisterError {
+    #[error("a biome named {0} already exists")]
+    DuplicateName(String),
+    #[error("biome name {0} has no namespace")]
+    MissingNamespace(String),
+}
+
+/// The set of custom biomes.
+#[derive(Debug, Clone, Default)]
+pub struct BiomeRegistry {
+    /// Custom biomes, with IDs starting at `FIRST_CUSTOM_ID`.
+    custom: Vec<CustomBiome>,
+}
+
+impl BiomeRegistry {
+    /// Creates a registry containing only the vanilla biomes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a custom biome, returning its numeric ID.
+    pub fn register(&mut self, biome: CustomBiome) -> Result<i32, RegisterError> {
+        if !biome.name.contains(':') {
+            return Err(RegisterError::MissingNamespace(biome.name));
+        }
+        if self.id_of(&biome.name).is_some() {
+            return Err(RegisterError::DuplicateName(biome.name));
+        }
+
+        self.custom.push(biome);
+        Ok(FIRST_CUSTOM_ID + self.custom.len() as i32 - 1)
+    }
+
+    /// Returns the custom biome with the given ID.
+    pub fn get(&self, id: i32) -> Option<&CustomBiome> {
+        if id < FIRST_CUSTOM_ID {
+            return None;
+        }
+        self.custom.get((id - FIRST_CUSTOM_ID) as usize)
+    }
+
+    /// Returns the numeric ID of the vanilla or
+    /// custom biome with the given namespaced ID.
+    pub fn id_of(&self, name: &str) -> Option<i32> {
+        if let Some(biome) = Biome::from_namespaced_id(name) {
+            return Some(biome.id());
+        }
+        self.custom
+            .iter()
+            .position(|biome| biome.name == name)
+            .map(|index| FIRST_CUSTOM_ID + index as i32)
+    }
+
+    /// Returns whether the given ID belongs to a vanilla or custom biome.
+    pub fn contains(&self, id: i32) -> bool {
+        Biome::from_id(id).is_some() || self.get(id).is_some()
+    }
+
+    /// Returns the vanilla biome with the given ID,
+    /// or the parent of the custom biome with the given ID.
+    pub fn resolve(&self, id: i32) -> Option<Biome> {
+        Biome::from_id(id).or_else(|| self.get(id).map(|biome| biome.parent))
+    }
+
+    /// Iterates over the custom biomes and their IDs.
+    pub fn custom_biomes(&self) -> impl Iterator<Item = (i32, &CustomBiome)> {
+        self.custom
+            .iter()
+            .enumerate()
+            .map(|(index, biome)| (FIRST_CUSTOM_ID + index as i32, biome))
+    }
+
+    /// Installs this registry as the global registry returned
+    /// by `registry()`. This must happen at startup, before
+    /// chunks are loaded or generated.
+    ///
+    /// Returns the registry back if the global registry
+    /// has already been installed or accessed.
+    pub fn install(self) -> Result<(), Self> {
+        REGISTRY.set(self)
+    }
+}
+
+/// Returns the global biome registry. If none
+/// was installed, only vanilla biomes are known.
+pub fn registry() -> &'static BiomeRegistry {
+    REGISTRY.get_or_init(BiomeRegistry::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_custom_biomes() {
+        let mut registry = BiomeRegistry::new();
+
+        let mut crystal = CustomBiome::based_on("test:crystal_fields", Biome::Plains);
+        crystal.grass_color = Some(0xFF_00_FF);
+        let id = registry.register(crystal.clone()).unwrap();
+        assert_eq!(id, FIRST_CUSTOM_ID);
+        assert_eq!(registry.get(id), Some(&crystal));
+        assert_eq!(registry.id_of("test:crystal_fields"), Some(id));
+        assert_eq!(registry.resolve(id), Some(Biome::Plains));
+        assert!(registry.contains(id));
+
+        assert!(registry
+            .register(CustomBiome::based_on("minecraft:plains", Biome::Plains))
+            .is_err());
+        assert!(registry.register(crystal).is_err());
+        assert!(registry
+            .register(CustomBiome::based_on("no_namespace", Biome::Plains))
+            .is_err());
+
+        assert_eq!(registry.resolve(Biome::Desert.id()), Some(Biome::Desert));
+        assert_eq!(registry.resolve(FIRST_CUSTOM_ID + 1), None);
+    }
+}
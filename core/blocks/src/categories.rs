@@ -104,6 +104,31 @@ impl BlockId {
         }
     }
 
+    /// Returns how much light passing through this block is reduced,
+    /// from 0 (transparent) to 15 (opaque). Light is reduced by at
+    /// least one level per block regardless, except sky light
+    /// travelling straight down through fully transparent blocks.
+    #[inline]
+    pub fn light_opacity(self) -> u8 {
+        if self.is_opaque() {
+            return 15;
+        }
+
+        match self.kind() {
+            BlockKind::Water
+            | BlockKind::Ice
+            | BlockKind::FrostedIce
+            | BlockKind::Cobweb
+            | BlockKind::OakLeaves
+            | BlockKind::SpruceLeaves
+            | BlockKind::BirchLeaves
+            | BlockKind::JungleLeaves
+            | BlockKind::AcaciaLeaves
+            | BlockKind::DarkOakLeaves => 1,
+            _ => 0,
+        }
+    }
+
     #[inline]
     pub fn can_fall(self) -> bool {
         matches!(
@@ -200,7 +225,7 @@ impl BlockId {
             | SimplifiedBlockKind::Fence
             | SimplifiedBlockKind::FenceGate
             | SimplifiedBlockKind::IronDoor
-            | SimplifiedBlockKind::Stairs 
+            | SimplifiedBlockKind::Stairs
             | SimplifiedBlockKind::WoodenDoor => Some(PlacementType::PlayerDirection),
             SimplifiedBlockKind::Anvil => Some(PlacementType::PlayerDirectionRightAngle),
             _ => None,
@@ -211,4 +236,84 @@ impl BlockId {
     pub fn is_full_block(self) -> bool {
         self.kind().full_block()
     }
+
+    /// Whether this block may change when randomly ticked,
+    /// e.g. crops growing or ice melting.
+    #[inline]
+    pub fn is_randomly_ticked(self) -> bool {
+        match self.simplified_kind() {
+            SimplifiedBlockKind::Leaves => self.persistent() == Some(false),
+            kind => matches!(
+                kind,
+                SimplifiedBlockKind::GrassBlock
+                    | SimplifiedBlockKind::Mycelium
+                    | SimplifiedBlockKind::Farmland
+                    | SimplifiedBlockKind::Sapling
+                    | SimplifiedBlockKind::Wheat
+                    | SimplifiedBlockKind::Carrots
+                    | SimplifiedBlockKind::Potatoes
+                    | SimplifiedBlockKind::Beetroots
+                    | SimplifiedBlockKind::PumpkinStem
+                    | SimplifiedBlockKind::MelonStem
+                    | SimplifiedBlockKind::NetherWart
+                    | SimplifiedBlockKind::Cocoa
+                    | SimplifiedBlockKind::SugarCane
+                    | SimplifiedBlockKind::Cactus
+                    | SimplifiedBlockKind::Vine
+                    | SimplifiedBlockKind::Kelp
+                    | SimplifiedBlockKind::ChorusFlower
+                    | SimplifiedBlockKind::TurtleEgg
+                    | SimplifiedBlockKind::Fire
+                    | SimplifiedBlockKind::Lava
+                    | SimplifiedBlockKind::Ice
+                    | SimplifiedBlockKind::Snow
+            ),
+        }
+    }
+
+    /// The growth stage of a plant which grows over time,
+    /// such as crops, stems, sugar cane and cactus, along
+    /// with its greatest stage.
+    pub fn growth_stage(self) -> Option<(i32, i32)> {
+        match self.simplified_kind() {
+            SimplifiedBlockKind::Wheat
+            | SimplifiedBlockKind::Carrots
+            | SimplifiedBlockKind::Potatoes
+            | SimplifiedBlockKind::PumpkinStem
+            | SimplifiedBlockKind::MelonStem => Some((self.age_0_7()?, 7)),
+            SimplifiedBlockKind::Beetroots | SimplifiedBlockKind::NetherWart => {
+                Some((self.age_0_3()?, 3))
+            }
+            SimplifiedBlockKind::Cocoa => Some((self.age_0_2()?, 2)),
+            SimplifiedBlockKind::SugarCane | SimplifiedBlockKind::Cactus => {
+                Some((self.age_0_15()?, 15))
+            }
+            SimplifiedBlockKind::Sapling => Some((self.stage()?, 1)),
+            _ => None,
+        }
+    }
+
+    /// Returns this plant at the given growth stage, clamped
+    /// to its greatest stage. Blocks which don't grow are
+    /// returned unchanged.
+    pub fn with_growth_stage(self, stage: i32) -> Self {
+        let max_stage = match self.growth_stage() {
+            Some((_, max_stage)) => max_stage,
+            None => return self,
+        };
+        let stage = stage.max(0).min(max_stage);
+        match max_stage {
+            7 => self.with_age_0_7(stage),
+            3 => self.with_age_0_3(stage),
+            2 => self.with_age_0_2(stage),
+            15 => self.with_age_0_15(stage),
+            _ => self.with_stage(stage),
+        }
+    }
+
+    /// Whether this plant has reached its greatest growth stage.
+    pub fn is_fully_grown(self) -> bool {
+        self.growth_stage()
+            .map_or(false, |(stage, max_stage)| stage >= max_stage)
+    }
 }
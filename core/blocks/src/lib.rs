@@ -3,6 +3,7 @@ use std::convert::TryFrom;
 use thiserror::Error;
 
 pub use feather_definitions::BlockKind;
+pub use feather_definitions::MapColor;
 pub use feather_definitions::SimplifiedBlockKind;
 
 pub mod categories;
@@ -10,6 +11,7 @@ mod directions;
 #[allow(warnings)]
 #[allow(clippy::all)]
 mod generated;
+mod transform;
 mod wall_blocks;
 
 static BLOCK_TABLE: Lazy<BlockTable> = Lazy::new(|| {
@@ -22,7 +24,7 @@ static VANILLA_ID_TABLE: Lazy<Vec<Vec<u16>>> = Lazy::new(|| {
     bincode::deserialize(bytes).expect("failed to deserialize generated vanilla ID table (bincode)")
 });
 
-const HIGHEST_ID: u16 = 8596;
+const HIGHEST_ID: u16 = 8598;
 
 static FROM_VANILLA_ID_TABLE: Lazy<Vec<BlockId>> = Lazy::new(|| {
     let mut res = vec![BlockId::default(); u16::max_value() as usize];
@@ -57,6 +59,7 @@ pub fn init() {
 use once_cell::sync::Lazy;
 
 pub use crate::generated::table::*;
+pub use transform::{Mirror, MirrorParseError, Rotation, RotationParseError};
 
 use std::collections::HashSet;
 
@@ -110,6 +113,11 @@ impl BlockId {
     pub fn from_vanilla_id(id: u16) -> Self {
         FROM_VANILLA_ID_TABLE[id as usize]
     }
+
+    /// Returns an iterator over all block states, in order of vanilla ID.
+    pub fn all() -> impl Iterator<Item = BlockId> {
+        (0..=HIGHEST_ID).map(Self::from_vanilla_id)
+    }
 }
 
 impl From<BlockId> for u32 {
@@ -161,6 +169,16 @@ mod tests {
         assert_eq!(block.instrument(), Some(Instrument::Basedrum));
     }
 
+    #[test]
+    fn all_states() {
+        assert_eq!(BlockId::all().count(), HIGHEST_ID as usize + 1);
+        assert_eq!(BlockId::all().next(), Some(BlockId::air()));
+        assert_eq!(
+            BlockId::all().last(),
+            Some(BlockId::structure_block().with_structure_block_mode(StructureBlockMode::Data))
+        );
+    }
+
     #[test]
     fn vanilla_ids() {
         let block = BlockId::rose_bush().with_half_upper_lower(HalfUpperLower::Lower);
@@ -202,6 +220,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn growth_stage() {
+        let wheat = BlockId::wheat().with_age_0_7(3);
+        assert_eq!(wheat.growth_stage(), Some((3, 7)));
+        assert_eq!(wheat.with_growth_stage(9).growth_stage(), Some((7, 7)));
+        assert!(wheat.with_growth_stage(7).is_fully_grown());
+
+        let cane = BlockId::sugar_cane().with_growth_stage(15);
+        assert_eq!(cane.age_0_15(), Some(15));
+        assert_eq!(BlockId::stone().growth_stage(), None);
+        assert_eq!(BlockId::stone().with_growth_stage(1), BlockId::stone());
+    }
+
     #[test]
     fn property_starting_at_1() {
         let block = BlockId::snow().with_layers(1);
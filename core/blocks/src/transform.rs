@@ -0,0 +1,299 @@
+//! Rotation and mirroring of blocks, used when
+//! placing structures in a different orientation.
+
+use crate::BlockId;
+use feather_util::BlockPosition;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A rotation around the Y axis, as seen from above.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    None,
+    Clockwise90,
+    Clockwise180,
+    CounterClockwise90,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Rotation::None
+    }
+}
+
+impl Rotation {
+    /// Returns the number of clockwise quarter turns.
+    fn quarter_turns(self) -> usize {
+        match self {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::CounterClockwise90 => 3,
+        }
+    }
+
+    /// Rotates a position around the origin.
+    pub fn apply(self, pos: BlockPosition) -> BlockPosition {
+        match self {
+            Rotation::None => pos,
+            Rotation::Clockwise90 => BlockPosition::new(-pos.z, pos.y, pos.x),
+            Rotation::Clockwise180 => BlockPosition::new(-pos.x, pos.y, -pos.z),
+            Rotation::CounterClockwise90 => BlockPosition::new(pos.z, pos.y, -pos.x),
+        }
+    }
+
+    /// Rotates a horizontal direction. Other values are returned unchanged.
+    fn direction(self, direction: &str) -> &str {
+        match HORIZONTAL.iter().position(|&d| d == direction) {
+            Some(index) => HORIZONTAL[(index + self.quarter_turns()) % 4],
+            None => direction,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid rotation {0}")]
+pub struct RotationParseError(String);
+
+impl FromStr for Rotation {
+    type Err = RotationParseError;
+
+    /// Parses the names used by structure blocks,
+    /// e.g. `clockwise_90`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Rotation::None),
+            "clockwise_90" => Ok(Rotation::Clockwise90),
+            "180" => Ok(Rotation::Clockwise180),
+            "counterclockwise_90" => Ok(Rotation::CounterClockwise90),
+            _ => Err(RotationParseError(s.to_owned())),
+        }
+    }
+}
+
+/// A reflection across a vertical plane through the origin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Mirror {
+    None,
+    /// Swaps north and south.
+    LeftRight,
+    /// Swaps east and west.
+    FrontBack,
+}
+
+impl Default for Mirror {
+    fn default() -> Self {
+        Mirror::None
+    }
+}
+
+impl Mirror {
+    /// Mirrors a position across the plane through the origin.
+    pub fn apply(self, pos: BlockPosition) -> BlockPosition {
+        match self {
+            Mirror::None => pos,
+            Mirror::LeftRight => BlockPosition::new(pos.x, pos.y, -pos.z),
+            Mirror::FrontBack => BlockPosition::new(-pos.x, pos.y, pos.z),
+        }
+    }
+
+    /// Mirrors a horizontal direction. Other values are returned unchanged.
+    fn direction(self, direction: &str) -> &str {
+        match (self, direction) {
+            (Mirror::LeftRight, "north") => "south",
+            (Mirror::LeftRight, "south") => "north",
+            (Mirror::FrontBack, "east") => "west",
+            (Mirror::FrontBack, "west") => "east",
+            _ => direction,
+        }
+    }
+
+    /// Mirrors a 16-step rotation as used by signs and banners,
+    /// where 0 faces south and values increase clockwise.
+    fn rotation_16(self, rotation: u8) -> u8 {
+        match self {
+            Mirror::None => rotation,
+            Mirror::LeftRight => (24 - rotation) % 16,
+            Mirror::FrontBack => (16 - rotation) % 16,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("invalid mirror {0}")]
+pub struct MirrorParseError(String);
+
+impl FromStr for Mirror {
+    type Err = MirrorParseError;
+
+    /// Parses the names used by structure blocks,
+    /// e.g. `left_right`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Mirror::None),
+            "left_right" => Ok(Mirror::LeftRight),
+            "front_back" => Ok(Mirror::FrontBack),
+            _ => Err(MirrorParseError(s.to_owned())),
+        }
+    }
+}
+
+/// Horizontal directions in clockwise order.
+const HORIZONTAL: [&str; 4] = ["north", "east", "south", "west"];
+
+impl BlockId {
+    /// Returns this block rotated around the Y axis.
+    pub fn rotated(self, rotation: Rotation) -> BlockId {
+        self.transformed(Mirror::None, rotation)
+    }
+
+    /// Returns this block mirrored.
+    pub fn mirrored(self, mirror: Mirror) -> BlockId {
+        self.transformed(mirror, Rotation::None)
+    }
+
+    /// Returns this block mirrored, then rotated. This is how
+    /// blocks are transformed when placing a structure.
+    ///
+    /// Transforms the properties which depend on the block's
+    /// orientation: facing, axis, sign rotation, the sides
+    /// of connecting blocks, rail shapes, and the handedness
+    /// of stairs, doors and chests.
+    pub fn transformed(self, mirror: Mirror, rotation: Rotation) -> BlockId {
+        if mirror == Mirror::None && rotation == Rotation::None {
+            return self;
+        }
+
+        let mut properties = BTreeMap::new();
+        for (name, value) in self.to_properties_map() {
+            let (name, value) = transform_property(name, value, mirror, rotation);
+            properties.insert(name.to_owned(), value);
+        }
+
+        BlockId::from_identifier_and_properties(self.identifier(), &properties).unwrap_or(self)
+    }
+}
+
+fn transform_property<'a>(
+    name: &'a str,
+    value: &'a str,
+    mirror: Mirror,
+    rotation: Rotation,
+) -> (&'a str, String) {
+    let direction = |direction| rotation.direction(mirror.direction(direction));
+
+    match name {
+        // Sides of fences, walls, panes, redstone wire, etc.
+        "north" | "east" | "south" | "west" => (direction(name), value.to_owned()),
+        "facing" => (name, direction(value).to_owned()),
+        "axis" if rotation.quarter_turns() % 2 == 1 => {
+            let axis = match value {
+                "x" => "z",
+                "z" => "x",
+                axis => axis,
+            };
+            (name, axis.to_owned())
+        }
+        "rotation" => {
+            let transformed = value.parse::<u8>().map(|value| {
+                let value = mirror.rotation_16(value);
+                (value + rotation.quarter_turns() as u8 * 4) % 16
+            });
+            match transformed {
+                Ok(value) => (name, value.to_string()),
+                Err(_) => (name, value.to_owned()),
+            }
+        }
+        "shape" if value.contains('_') && !value.contains("left") && !value.contains("right") => {
+            (name, rail_shape(value, direction))
+        }
+        "shape" | "hinge" | "type" if mirror != Mirror::None => {
+            let value = if value.ends_with("left") {
+                value.replace("left", "right")
+            } else {
+                value.replace("right", "left")
+            };
+            (name, value)
+        }
+        _ => (name, value.to_owned()),
+    }
+}
+
+/// Transforms a rail shape such as `south_east` or
+/// `ascending_north`, keeping the vanilla word order.
+fn rail_shape<'a>(shape: &'a str, direction: impl Fn(&'a str) -> &'a str) -> String {
+    let mut parts = shape.split('_');
+    match (parts.next(), parts.next()) {
+        (Some("ascending"), Some(dir)) => format!("ascending_{}", direction(dir)),
+        (Some(a), Some(b)) => {
+            let (a, b) = (direction(a), direction(b));
+            // Straight shapes start with north, corners
+            // with north or south.
+            if a == "east" || a == "west" {
+                format!("{}_{}", b, a)
+            } else {
+                format!("{}_{}", a, b)
+            }
+        }
+        _ => shape.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AxisXyz, FacingCardinal, RailShape, StairsShape};
+
+    #[test]
+    fn positions() {
+        let pos = BlockPosition::new(1, 2, 3);
+        assert_eq!(
+            Rotation::Clockwise90.apply(pos),
+            BlockPosition::new(-3, 2, 1)
+        );
+        assert_eq!(
+            Rotation::CounterClockwise90.apply(Rotation::Clockwise90.apply(pos)),
+            pos
+        );
+        assert_eq!(Mirror::FrontBack.apply(pos), BlockPosition::new(-1, 2, 3));
+    }
+
+    #[test]
+    fn rotate_blocks() {
+        let stairs = BlockId::oak_stairs().with_facing_cardinal(FacingCardinal::North);
+        assert_eq!(
+            stairs.rotated(Rotation::Clockwise90).facing_cardinal(),
+            Some(FacingCardinal::East)
+        );
+
+        let log = BlockId::oak_log().with_axis_xyz(AxisXyz::X);
+        assert_eq!(
+            log.rotated(Rotation::Clockwise90).axis_xyz(),
+            Some(AxisXyz::Z)
+        );
+
+        let rail = BlockId::rail().with_rail_shape(RailShape::SouthEast);
+        assert_eq!(
+            rail.rotated(Rotation::Clockwise90).rail_shape(),
+            Some(RailShape::SouthWest)
+        );
+
+        let sign = BlockId::sign().with_rotation(1);
+        assert_eq!(sign.rotated(Rotation::Clockwise180).rotation(), Some(9));
+    }
+
+    #[test]
+    fn mirror_blocks() {
+        let stairs = BlockId::oak_stairs()
+            .with_facing_cardinal(FacingCardinal::East)
+            .with_stairs_shape(StairsShape::InnerLeft);
+        let mirrored = stairs.mirrored(Mirror::FrontBack);
+        assert_eq!(mirrored.facing_cardinal(), Some(FacingCardinal::West));
+        assert_eq!(mirrored.stairs_shape(), Some(StairsShape::InnerRight));
+
+        let sign = BlockId::sign().with_rotation(4);
+        assert_eq!(sign.mirrored(Mirror::FrontBack).rotation(), Some(12));
+        assert_eq!(sign.mirrored(Mirror::LeftRight).rotation(), Some(4));
+    }
+}
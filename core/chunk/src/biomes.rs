@@ -0,0 +1,249 @@
+//! Biome storage for chunks, in either the 2D format
+//! used before 1.15 or the 3D format used since.
+//!
+//! Biomes are stored as numeric IDs so that custom biomes
+//! from the global `BiomeRegistry` can be stored too.
+
+use feather_biomes::{registry, Biome, BiomeArrayFormat, BIOMES_2D_LEN, BIOMES_3D_LEN};
+
+/// Vertical cell sampled when 3D biomes are
+/// accessed as 2D ones. This corresponds to sea level.
+const FLATTEN_CELL_Y: usize = 64 / 4;
+
+/// The biomes of a chunk.
+///
+/// Chunks loaded from legacy worlds or generated by 2D
+/// biome generators store one biome per column. Setting
+/// a biome at a specific height converts the storage to
+/// the 3D format, in which each 4x4x4 cell has its own biome.
+///
+/// Methods taking or returning a `Biome` see custom
+/// biomes as their parent; use the `*_id` methods to
+/// access custom biomes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BiomeStorage {
+    /// One biome ID per column, indexed by `(z << 4) | x`.
+    TwoDimensional(Box<[i32; BIOMES_2D_LEN]>),
+    /// One biome ID per 4x4x4 cell, indexed by `(y << 4) | (z << 2) | x`
+    /// in cell coordinates.
+    ThreeDimensional(Box<[i32; BIOMES_3D_LEN]>),
+}
+
+impl BiomeStorage {
+    /// Creates 2D biome storage filled with the given biome.
+    pub fn new(biome: Biome) -> Self {
+        BiomeStorage::TwoDimensional(Box::new([biome.id(); BIOMES_2D_LEN]))
+    }
+
+    /// Returns the format of this storage.
+    pub fn format(&self) -> BiomeArrayFormat {
+        match self {
+            BiomeStorage::TwoDimensional(_) => BiomeArrayFormat::TwoDimensional,
+            BiomeStorage::ThreeDimensional(_) => BiomeArrayFormat::ThreeDimensional,
+        }
+    }
+
+    /// Returns the biome of the given column. For 3D
+    /// storage, this is the biome at sea level.
+    pub fn biome_at(&self, x: usize, z: usize) -> Biome {
+        resolve(self.biome_id_at(x, z))
+    }
+
+    /// Sets the biome of the given column. For 3D storage,
+    /// this sets every cell in the column containing it.
+    pub fn set_biome_at(&mut self, x: usize, z: usize, biome: Biome) {
+        self.set_biome_id_at(x, z, biome.id());
+    }
+
+    /// Returns the biome at the given block, in
+    /// the chunk's local coordinate space.
+    pub fn biome_at_3d(&self, x: usize, y: usize, z: usize) -> Biome {
+        resolve(self.biome_id_at_3d(x, y, z))
+    }
+
+    /// Sets the biome of the 4x4x4 cell containing the given
+    /// block, converting this storage to 3D if necessary.
+    pub fn set_biome_at_3d(&mut self, x: usize, y: usize, z: usize, biome: Biome) {
+        self.set_biome_id_at_3d(x, y, z, biome.id());
+    }
+
+    /// Returns the ID of the biome of the given column.
+    pub fn biome_id_at(&self, x: usize, z: usize) -> i32 {
+        match self {
+            BiomeStorage::TwoDimensional(biomes) => biomes[index_2d(x, z)],
+            BiomeStorage::ThreeDimensional(biomes) => {
+                biomes[index_3d(x >> 2, FLATTEN_CELL_Y, z >> 2)]
+            }
+        }
+    }
+
+    /// Sets the ID of the biome of the given column.
+    pub fn set_biome_id_at(&mut self, x: usize, z: usize, id: i32) {
+        match self {
+            BiomeStorage::TwoDimensional(biomes) => biomes[index_2d(x, z)] = id,
+            BiomeStorage::ThreeDimensional(biomes) => {
+                for cell_y in 0..64 {
+                    biomes[index_3d(x >> 2, cell_y, z >> 2)] = id;
+                }
+            }
+        }
+    }
+
+    /// Returns the ID of the biome at the given block.
+    pub fn biome_id_at_3d(&self, x: usize, y: usize, z: usize) -> i32 {
+        match self {
+            BiomeStorage::TwoDimensional(biomes) => biomes[index_2d(x, z)],
+            BiomeStorage::ThreeDimensional(biomes) => biomes[index_3d(x >> 2, y >> 2, z >> 2)],
+        }
+    }
+
+    /// Sets the ID of the biome of the 4x4x4 cell containing
+    /// the given block, converting this storage to 3D if necessary.
+    pub fn set_biome_id_at_3d(&mut self, x: usize, y: usize, z: usize, id: i32) {
+        self.make_3d()[index_3d(x >> 2, y >> 2, z >> 2)] = id;
+    }
+
+    /// Converts this storage to the 3D format, returning the cells.
+    ///
+    /// Each cell takes the biome of the column at the center
+    /// of its 4x4 area, as vanilla does when upgrading worlds.
+    pub fn make_3d(&mut self) -> &mut [i32; BIOMES_3D_LEN] {
+        if let BiomeStorage::TwoDimensional(columns) = self {
+            let mut cells = Box::new([0; BIOMES_3D_LEN]);
+            for (index, cell) in cells.iter_mut().enumerate() {
+                let (x, z) = (index & 0x3, (index >> 2) & 0x3);
+                *cell = columns[index_2d(x * 4 + 2, z * 4 + 2)];
+            }
+            *self = BiomeStorage::ThreeDimensional(cells);
+        }
+
+        match self {
+            BiomeStorage::ThreeDimensional(cells) => cells,
+            BiomeStorage::TwoDimensional(_) => unreachable!(),
+        }
+    }
+
+    /// Encodes the biomes as numeric IDs in the given format.
+    pub fn encode(&self, format: BiomeArrayFormat) -> Vec<i32> {
+        match (self, format) {
+            (BiomeStorage::TwoDimensional(biomes), BiomeArrayFormat::TwoDimensional) => {
+                biomes.to_vec()
+            }
+            (BiomeStorage::ThreeDimensional(biomes), BiomeArrayFormat::ThreeDimensional) => {
+                biomes.to_vec()
+            }
+            (BiomeStorage::TwoDimensional(_), BiomeArrayFormat::ThreeDimensional) => {
+                let mut converted = self.clone();
+                converted.make_3d().to_vec()
+            }
+            (BiomeStorage::ThreeDimensional(_), BiomeArrayFormat::TwoDimensional) => {
+                let columns = 0..BIOMES_2D_LEN;
+                columns
+                    .map(|column| self.biome_id_at(column & 0xF, column >> 4))
+                    .collect()
+            }
+        }
+    }
+
+    /// Decodes numeric biome IDs, detecting the
+    /// format from the length of the array.
+    ///
+    /// Returns `None` if the array has the wrong length or
+    /// contains an ID unknown to the global biome registry.
+    pub fn decode(ids: &[i32]) -> Option<Self> {
+        if !ids.iter().all(|id| registry().contains(*id)) {
+            return None;
+        }
+
+        match ids.len() {
+            BIOMES_2D_LEN => {
+                let mut columns = Box::new([0; BIOMES_2D_LEN]);
+                columns.copy_from_slice(ids);
+                Some(BiomeStorage::TwoDimensional(columns))
+            }
+            BIOMES_3D_LEN => {
+                let mut cells = Box::new([0; BIOMES_3D_LEN]);
+                cells.copy_from_slice(ids);
+                Some(BiomeStorage::ThreeDimensional(cells))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for BiomeStorage {
+    fn default() -> Self {
+        Self::new(Biome::Plains)
+    }
+}
+
+/// Returns the biome with the given ID, or the parent of
+/// the custom biome with the given ID.
+fn resolve(id: i32) -> Biome {
+    registry().resolve(id).unwrap_or(Biome::Plains)
+}
+
+fn index_2d(x: usize, z: usize) -> usize {
+    assert!(x < 16);
+    assert!(z < 16);
+
+    (z << 4) | x
+}
+
+fn index_3d(cell_x: usize, cell_y: usize, cell_z: usize) -> usize {
+    assert!(cell_x < 4);
+    assert!(cell_y < 64);
+    assert!(cell_z < 4);
+
+    (cell_y << 4) | (cell_z << 2) | cell_x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrade_to_3d() {
+        let mut storage = BiomeStorage::default();
+        storage.set_biome_at(6, 10, Biome::Desert);
+        assert_eq!(storage.biome_at_3d(6, 200, 10), Biome::Desert);
+
+        storage.set_biome_at_3d(6, 100, 10, Biome::Ocean);
+        assert_eq!(storage.format(), BiomeArrayFormat::ThreeDimensional);
+        // The whole 4x4x4 cell changes.
+        assert_eq!(storage.biome_at_3d(4, 102, 11), Biome::Ocean);
+        // Other cells in the column keep the converted biome.
+        assert_eq!(storage.biome_at_3d(6, 0, 10), Biome::Desert);
+        assert_eq!(storage.biome_at_3d(0, 100, 0), Biome::Plains);
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let mut storage = BiomeStorage::default();
+        storage.set_biome_at_3d(0, 0, 0, Biome::Forest);
+
+        let ids = storage.encode(BiomeArrayFormat::ThreeDimensional);
+        assert_eq!(ids.len(), BIOMES_3D_LEN);
+        assert_eq!(BiomeStorage::decode(&ids), Some(storage.clone()));
+
+        let ids = storage.encode(BiomeArrayFormat::TwoDimensional);
+        assert_eq!(ids.len(), BIOMES_2D_LEN);
+        let flattened = BiomeStorage::decode(&ids).unwrap();
+        assert_eq!(flattened.format(), BiomeArrayFormat::TwoDimensional);
+
+        assert_eq!(BiomeStorage::decode(&[0; 10]), None);
+    }
+
+    #[test]
+    fn custom_biome_ids() {
+        let mut storage = BiomeStorage::default();
+        storage.set_biome_id_at(3, 3, 1000);
+        assert_eq!(storage.biome_id_at(3, 3), 1000);
+        // Unknown IDs are seen as plains.
+        assert_eq!(storage.biome_at(3, 3), Biome::Plains);
+        assert_eq!(
+            storage.encode(BiomeArrayFormat::TwoDimensional)[(3 << 4) | 3],
+            1000
+        );
+    }
+}
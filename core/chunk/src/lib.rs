@@ -1,9 +1,14 @@
-use ahash::AHashMap;
+mod biomes;
+pub mod light;
+mod paletted;
+
+pub use biomes::BiomeStorage;
+pub use paletted::PalettedContainer;
+
 use bitflags::bitflags;
 use feather_biomes::Biome;
 use feather_blocks::{BlockId, SimplifiedBlockKind};
-use feather_util::ChunkPosition;
-use smallvec::SmallVec;
+use feather_util::{BlockPosition, ChunkPosition};
 
 /// The number of bits used for each block
 /// in the global palette.
@@ -21,7 +26,8 @@ pub const MIN_BITS_PER_BLOCK: u8 = 4;
 /// instead.
 pub const MAX_BITS_PER_BLOCK: u8 = 8;
 
-/// The height in blocks of a chunk column.
+/// The height in blocks of a chunk column, unless
+/// created with other bounds by `Chunk::with_bounds`.
 pub const CHUNK_HEIGHT: usize = 256;
 /// The width in blocks of a chunk column.
 pub const CHUNK_WIDTH: usize = 16;
@@ -35,30 +41,45 @@ pub const SECTION_WIDTH: usize = CHUNK_WIDTH;
 /// The volume in blocks of a chunk section.
 pub const SECTION_VOLUME: usize = (SECTION_HEIGHT * SECTION_WIDTH * SECTION_WIDTH) as usize;
 
-/// The number of chunk sections in a column.
+/// The number of chunk sections in a column of the default height.
 pub const NUM_SECTIONS: usize = 16;
 
+/// The maximum number of sections in a chunk column, so that
+/// the light masks of a chunk, which cover one section below
+/// and one above it, fit in 64 bits.
+pub const MAX_NUM_SECTIONS: usize = 62;
+
+/// The maximum height in blocks of a chunk column.
+pub const MAX_CHUNK_HEIGHT: usize = MAX_NUM_SECTIONS * SECTION_HEIGHT;
+
 /// A chunk column consisting
-/// of a 16x256x16 section of blocks.
+/// of a 16x256x16 section of blocks,
+/// or of another height since 1.17.
 /// A chunk column maintains an array
-/// of up to 16 chunk sections, each corresponding
+/// of chunk sections, each corresponding
 /// to a 16x16x16 section of blocks in the chunk.
+///
+/// Positions within a chunk are in its local coordinate
+/// space, in which Y coordinates are relative to the
+/// bottom of the chunk, `min_y`.
 #[derive(Clone)]
 pub struct Chunk {
     /// The location of this chunk, in chunk
     /// coordinates.
     location: ChunkPosition,
-    /// An array of the sections in this chunk.
-    /// A section with Y value `y` can be found at
-    /// index `y` in this array.
+    /// The Y coordinate of the bottom of this
+    /// chunk, which is a multiple of 16.
+    min_y: i32,
+    /// The sections in this chunk, from bottom to top.
+    /// The section at index `i` starts at Y coordinate
+    /// `min_y + 16 * i`.
     /// When an entry in this array is set to `None`,
     /// the section at the entry's Y coordinate
     /// is assumed to empty, meaning that it consists
     /// of only air.
-    sections: [Option<ChunkSection>; NUM_SECTIONS],
-    /// The biomes in this section, indexable by
-    /// ((z << 4) | x).
-    biomes: [Biome; SECTION_WIDTH * SECTION_WIDTH],
+    sections: Vec<Option<ChunkSection>>,
+    /// The biomes in this chunk.
+    biomes: BiomeStorage,
     /// Whether this chunk has been modified since the most recent
     /// call to `check_modified`().
     modified: bool,
@@ -82,8 +103,8 @@ impl HeightMap {
     }
 
     pub fn set_light_blocking(&mut self, mut light_blocking: u16) {
-        if light_blocking > 256 {
-            light_blocking = 256;
+        if light_blocking > MAX_CHUNK_HEIGHT as u16 {
+            light_blocking = MAX_CHUNK_HEIGHT as u16;
         }
         self.light_blocking = light_blocking;
     }
@@ -94,8 +115,8 @@ impl HeightMap {
     }
 
     pub fn set_motion_blocking(&mut self, mut motion_blocking: u16) {
-        if motion_blocking > 256 {
-            motion_blocking = 256;
+        if motion_blocking > MAX_CHUNK_HEIGHT as u16 {
+            motion_blocking = MAX_CHUNK_HEIGHT as u16;
         }
         self.motion_blocking = motion_blocking;
     }
@@ -106,8 +127,8 @@ impl HeightMap {
     }
 
     pub fn set_motion_blocking_no_leaves(&mut self, mut motion_blocking_no_leaves: u16) {
-        if motion_blocking_no_leaves > 256 {
-            motion_blocking_no_leaves = 256;
+        if motion_blocking_no_leaves > MAX_CHUNK_HEIGHT as u16 {
+            motion_blocking_no_leaves = MAX_CHUNK_HEIGHT as u16;
         }
         self.motion_blocking_no_leaves = motion_blocking_no_leaves;
     }
@@ -118,8 +139,8 @@ impl HeightMap {
     }
 
     pub fn set_ocean_floor(&mut self, mut ocean_floor: u16) {
-        if ocean_floor > 256 {
-            ocean_floor = 256;
+        if ocean_floor > MAX_CHUNK_HEIGHT as u16 {
+            ocean_floor = MAX_CHUNK_HEIGHT as u16;
         }
         self.ocean_floor = ocean_floor;
     }
@@ -130,8 +151,8 @@ impl HeightMap {
     }
 
     pub fn set_world_surface(&mut self, mut world_surface: u16) {
-        if world_surface > 256 {
-            world_surface = 256;
+        if world_surface > MAX_CHUNK_HEIGHT as u16 {
+            world_surface = MAX_CHUNK_HEIGHT as u16;
         }
         self.world_surface = world_surface;
     }
@@ -149,20 +170,12 @@ bitflags! {
 
 impl Default for Chunk {
     fn default() -> Self {
-        // Rust apparently forces you to implement
-        // `Copy` on types if you want to use the
-        // `[ChunkSection::new(); 16]` syntax,
-        // so I had to do this.
-        let sections = [
-            None, None, None, None, None, None, None, None, None, None, None, None, None, None,
-            None, None,
-        ];
-
         Self {
             location: ChunkPosition::new(0, 0),
             modified: true,
-            sections,
-            biomes: [Biome::Plains; SECTION_WIDTH * SECTION_WIDTH],
+            min_y: 0,
+            sections: vec![None; NUM_SECTIONS],
+            biomes: BiomeStorage::default(),
             heightmaps: vec![HeightMap::default(); CHUNK_WIDTH * CHUNK_WIDTH].into_boxed_slice(),
         }
     }
@@ -187,11 +200,95 @@ impl Chunk {
         Self {
             location,
             modified: true,
-            biomes: [default_biome; SECTION_WIDTH * SECTION_HEIGHT],
+            biomes: BiomeStorage::new(default_biome),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a new empty chunk with the specified
+    /// location, spanning `height` blocks upwards
+    /// from the Y coordinate `min_y`.
+    ///
+    /// # Panics
+    /// Panics if `min_y` or `height` isn't a multiple of 16,
+    /// or if `height` is 0 or above `MAX_CHUNK_HEIGHT`.
+    pub fn with_bounds(location: ChunkPosition, min_y: i32, height: usize) -> Self {
+        check_bounds(min_y, height);
+        Self {
+            location,
+            min_y,
+            sections: vec![None; height / SECTION_HEIGHT],
             ..Default::default()
         }
     }
 
+    /// Changes the vertical bounds of this chunk, keeping
+    /// blocks at the same Y coordinates. Sections outside the
+    /// new bounds are discarded, while added sections are empty.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `with_bounds`.
+    pub fn resize(&mut self, min_y: i32, height: usize) {
+        check_bounds(min_y, height);
+        if min_y == self.min_y && height == self.height() {
+            return;
+        }
+
+        // The number of sections the old sections move up by.
+        let shift = (self.min_y - min_y) / SECTION_HEIGHT as i32;
+        let mut sections = vec![None; height / SECTION_HEIGHT];
+        for (i, section) in self.sections.drain(..).enumerate() {
+            let index = i as i32 + shift;
+            if index >= 0 && (index as usize) < sections.len() {
+                sections[index as usize] = section;
+            }
+        }
+
+        self.sections = sections;
+        self.min_y = min_y;
+        self.modified = true;
+        // Heightmaps are relative to the bottom of the chunk.
+        self.recalculate_heightmap();
+    }
+
+    /// Returns the Y coordinate of the bottom of this chunk.
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    /// Returns the Y coordinate just above the top of this chunk.
+    pub fn max_y(&self) -> i32 {
+        self.min_y + self.height() as i32
+    }
+
+    /// Returns the height in blocks of this chunk.
+    pub fn height(&self) -> usize {
+        self.sections.len() * SECTION_HEIGHT
+    }
+
+    /// Returns the number of sections in this chunk.
+    pub fn num_sections(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Converts a Y coordinate to this chunk's local coordinate
+    /// space, returning `None` if it's outside the chunk.
+    pub fn local_y(&self, y: i32) -> Option<usize> {
+        if y >= self.min_y && y < self.max_y() {
+            Some((y - self.min_y) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Converts a block position to this chunk's local coordinate
+    /// space, returning `None` if its Y coordinate is outside the
+    /// chunk. The X and Z coordinates are assumed to be inside it.
+    pub fn local_pos(&self, pos: BlockPosition) -> Option<(usize, usize, usize)> {
+        let y = self.local_y(pos.y)?;
+        Some((pos.x as usize & 0xf, y, pos.z as usize & 0xf))
+    }
+
     /// Gets the block at the specified
     /// position in this chunk. The position
     /// is in the chunk's local coordinate
@@ -199,9 +296,9 @@ impl Chunk {
     ///
     /// The specified coordinates must be inside
     /// this chunk, so the function will panic
-    /// if `x >= 16 || y >= 256 || z >= 16`.
+    /// if `x >= 16 || y >= self.height() || z >= 16`.
     pub fn block_at(&self, x: usize, y: usize, z: usize) -> BlockId {
-        Self::check_coords(x, y, z);
+        self.check_coords(x, y, z);
         let chunk_section = &self.sections[(y / 16) as usize];
         match chunk_section {
             Some(section) => section.block_at(x, y % 16, z),
@@ -216,9 +313,9 @@ impl Chunk {
     ///
     /// The specified coordinates must be inside
     /// this chunk, so the function will panic
-    /// if `x >= 16 || y >= 256 || z >= 16`.
+    /// if `x >= 16 || y >= self.height() || z >= 16`.
     pub fn set_block_at(&mut self, x: usize, y: usize, z: usize, block: BlockId) {
-        Self::check_coords(x, y, z);
+        self.check_coords(x, y, z);
         self.modified = true;
 
         let chunk_section = &mut self.sections[y / 16];
@@ -232,8 +329,7 @@ impl Chunk {
                 return; // Nothing to do - section already empty
             }
 
-            let new_section = ChunkSection::default();
-            self.set_section_at(y / 16, Some(new_section));
+            self.create_section(y / 16);
             section = self.section_mut(y / 16).unwrap();
         }
 
@@ -244,12 +340,12 @@ impl Chunk {
     }
 
     pub fn heightmap(&self, x: usize, z: usize) -> &HeightMap {
-        Self::check_coords(x, 0, z);
+        self.check_coords(x, 0, z);
         &self.heightmaps[x + z * CHUNK_WIDTH]
     }
 
     pub fn heightmap_mut(&mut self, x: usize, z: usize) -> &mut HeightMap {
-        Self::check_coords(x, 0, z);
+        self.check_coords(x, 0, z);
         &mut self.heightmaps[x + z * CHUNK_WIDTH]
     }
 
@@ -318,24 +414,20 @@ impl Chunk {
 
         for HeightMapCheckContext(valid_block, check_mask, map_getter, map_setter) in checks.iter()
         {
-            // Check heightmap type
-            if valid_block(old_block) && map_getter(self.heightmap_mut(x, z)) == y {
-                // This was the highest block
-                self.heightmap_mut(x, z).set_light_blocking(0);
-
-                for i in (0..y).rev() {
-                    let block = self.block_at(x, i as usize, z);
-
-                    if valid_block(block) {
-                        map_setter(self.heightmap_mut(x, z), i + 1);
-                        break;
-                    }
+            let height = map_getter(self.heightmap(x, z));
+            if valid_block(new_block) {
+                if height <= y {
+                    // This is the new highest block
+                    map_setter(self.heightmap_mut(x, z), y + 1);
+                    mask |= *check_mask;
                 }
-                mask |= *check_mask;
-            }
-            if valid_block(new_block) && map_getter(self.heightmap_mut(x, z)) < y {
-                // This is the new highest block
-                map_setter(self.heightmap_mut(x, z), y);
+            } else if valid_block(old_block) && height == y + 1 {
+                // This was the highest block: find the next one below.
+                let below = (0..y)
+                    .rev()
+                    .find(|&i| valid_block(self.block_at(x, i as usize, z)))
+                    .map_or(0, |i| i + 1);
+                map_setter(self.heightmap_mut(x, z), below);
                 mask |= *check_mask;
             }
         }
@@ -343,35 +435,52 @@ impl Chunk {
         mask
     }
 
-    /// Recalculate the heightmap for the chunk
+    /// Recalculates the heightmaps of the chunk from its blocks.
+    ///
+    /// Heightmaps are kept up to date by `set_block_at`, so this
+    /// is only needed after sections are replaced directly,
+    /// e.g. when generating or loading a chunk.
     pub fn recalculate_heightmap(&mut self) {
-        // This function can be optimized, instead of
-        // fetching heightmap every time, and sections
+        for heightmap in self.heightmaps.iter_mut() {
+            *heightmap = HeightMap::default();
+        }
+
+        // Blocks above the highest section are all air.
+        let top = match self.sections.iter().rposition(Option::is_some) {
+            Some(index) => (index + 1) * SECTION_HEIGHT,
+            None => return,
+        };
+
         for x in 0..CHUNK_WIDTH {
             for z in 0..CHUNK_WIDTH {
                 let mut mask: HeightMapMask = HeightMapMask::empty();
-                for y in (0..CHUNK_HEIGHT).rev() {
+                for y in (0..top).rev() {
                     if mask.is_all() {
                         break;
                     }
                     let block = self.block_at(x, y, z);
-                    mask |= self.update_heightmap(x, y, z, BlockId::air(), block);
+                    if !block.is_air() {
+                        mask |= self.update_heightmap(x, y, z, BlockId::air(), block);
+                    }
                 }
             }
         }
     }
 
+    /// Returns the sky light at the given position. Missing
+    /// sections above all others are open to the sky, so
+    /// they have full sky light.
     pub fn sky_light_at(&self, x: usize, y: usize, z: usize) -> u8 {
-        Self::check_coords(x, y, z);
+        self.check_coords(x, y, z);
         let chunk_section = self.section_for_y(y);
         match chunk_section {
             Some(chunk_section) => chunk_section.sky_light_at(x, y % 16, z),
-            None => 0,
+            None => self.implicit_sky_light(y / 16),
         }
     }
 
     pub fn block_light_at(&self, x: usize, y: usize, z: usize) -> u8 {
-        Self::check_coords(x, y, z);
+        self.check_coords(x, y, z);
         let chunk_section = self.section_for_y(y);
         match chunk_section {
             Some(chunk_section) => chunk_section.block_light_at(x, y % 16, z),
@@ -380,13 +489,13 @@ impl Chunk {
     }
 
     pub fn set_sky_light_at(&mut self, x: usize, y: usize, z: usize, value: u8) {
-        Self::check_coords(x, y, z);
+        self.check_coords(x, y, z);
         let chunk_section = self.section_for_y_mut(y);
         chunk_section.set_sky_light_at(x, y % 16, z, value);
     }
 
     pub fn set_block_light_at(&mut self, x: usize, y: usize, z: usize, value: u8) {
-        Self::check_coords(x, y, z);
+        self.check_coords(x, y, z);
         let chunk_section = self.section_for_y_mut(y);
         chunk_section.set_block_light_at(x, y % 16, z, value);
     }
@@ -396,22 +505,69 @@ impl Chunk {
     }
 
     fn section_for_y_mut(&mut self, y: usize) -> &mut ChunkSection {
-        self.sections[y / 16].get_or_insert_with(ChunkSection::default)
+        if self.sections[y / 16].is_none() {
+            self.create_section(y / 16);
+        }
+        self.sections[y / 16].as_mut().unwrap()
+    }
+
+    /// Returns the sky light of the missing section
+    /// at the given index: full if it is open to the sky,
+    /// i.e. no section above it exists, and zero otherwise.
+    fn implicit_sky_light(&self, index: usize) -> u8 {
+        if self.sections[index + 1..].iter().all(Option::is_none) {
+            15
+        } else {
+            0
+        }
+    }
+
+    /// Creates the missing section at the given index.
+    ///
+    /// Creating a section above all others closes the missing
+    /// sections below it off from the sky, so those are
+    /// created too, keeping their full sky light.
+    fn create_section(&mut self, index: usize) {
+        let open_to_sky = self.implicit_sky_light(index) == 15;
+        for i in (0..=index).rev() {
+            if self.sections[i].is_some() {
+                break;
+            }
+
+            let mut section = ChunkSection::default();
+            if open_to_sky {
+                for value in 0..SECTION_VOLUME {
+                    section.sky_light.set(value, 15);
+                }
+            }
+            self.set_section_at(i, Some(section));
+
+            if !open_to_sky {
+                break;
+            }
+        }
     }
 
-    fn check_coords(x: usize, y: usize, z: usize) {
+    fn check_coords(&self, x: usize, y: usize, z: usize) {
         assert!(x < CHUNK_WIDTH);
-        assert!(y < CHUNK_HEIGHT);
+        assert!(y < self.height());
         assert!(z < CHUNK_WIDTH);
     }
 
-    /// Returns a slice of the 16
+    /// Returns the biome storage Y coordinate of a block. Biomes
+    /// are only stored between Y coordinates 0 and 256, so blocks
+    /// outside use the biome of the nearest stored cell.
+    fn biome_y(&self, y: usize) -> usize {
+        (self.min_y + y as i32).clamp(0, CHUNK_HEIGHT as i32 - 1) as usize
+    }
+
+    /// Returns a slice of the
     /// chunk sections in the chunk.
     pub fn sections(&self) -> Vec<Option<&ChunkSection>> {
         self.sections.iter().map(|sec| sec.as_ref()).collect()
     }
 
-    /// Returns a mutable slice of the 16 sections
+    /// Returns a mutable slice of the sections
     /// in this chunk.
     pub fn sections_mut(&mut self) -> Vec<Option<&mut ChunkSection>> {
         self.modified = true;
@@ -430,31 +586,31 @@ impl Chunk {
     }
 
     /// Returns a reference to the chunk section at the given
-    /// Y offset. The Y offset must be between 0 and 15, inclusive;
+    /// Y offset. The Y offset must be less than `num_sections()`;
     /// each Y offset value corresponds to 16 blocks vertically.
     ///
     /// If this function returns `None`, the section is assumed
     /// to be empty, meaning it consists only of air.
     pub fn section(&self, index: usize) -> Option<&ChunkSection> {
-        assert!(index < NUM_SECTIONS);
+        assert!(index < self.sections.len());
         self.sections[index].as_ref()
     }
 
     /// Returns a mutable reference to the chunk section at the given
-    /// Y offset. The Y offset must be between 0 and 15, inclusive;
+    /// Y offset. The Y offset must be less than `num_sections()`;
     /// each Y offset value corresponds to 16 blocks vertically.
     ///
     /// If this function returns `None`, the section is assumed
     /// to be empty, meaning it consists only of air.
     pub fn section_mut(&mut self, index: usize) -> Option<&mut ChunkSection> {
-        assert!(index < NUM_SECTIONS);
+        assert!(index < self.sections.len());
         self.modified = true;
         self.sections[index].as_mut()
     }
 
     /// Sets the section at the given section index.
     pub fn set_section_at(&mut self, index: usize, section: Option<ChunkSection>) {
-        assert!(index < NUM_SECTIONS);
+        assert!(index < self.sections.len());
         self.sections[index] = section;
         self.modified = true;
     }
@@ -465,24 +621,29 @@ impl Chunk {
     /// optimized - sections which have not been
     /// modified since the last time they were optimized
     /// are not optimized.
+    ///
+    /// Empty sections at the top of the chunk are removed
+    /// unless they contain block light. Missing sections are
+    /// assumed to be open to the sky, so empty sections
+    /// below other sections are kept to store their light.
     pub fn optimize(&mut self) -> u32 {
         let modified = self.modified;
         let mut count = 0;
-        let mut to_remove = vec![];
-        for (i, s) in self.sections.iter_mut().enumerate() {
-            if let Some(section) = s {
-                if section.optimize() {
-                    // Section was optimized - increment count
-                    count += 1;
-                }
-
-                if section.empty() {
-                    to_remove.push(i);
-                }
+        for section in self.sections.iter_mut().flatten() {
+            if section.optimize() {
+                // Section was optimized - increment count
+                count += 1;
             }
         }
 
-        for i in to_remove {
+        for i in (0..self.sections.len()).rev() {
+            let removable = match &self.sections[i] {
+                None => continue,
+                Some(section) => section.empty() && !section.has_block_light(),
+            };
+            if !removable {
+                break;
+            }
             self.set_section_at(i, None);
         }
 
@@ -492,33 +653,59 @@ impl Chunk {
     }
 
     /// Returns the biomes of this chunk.
-    pub fn biomes(&self) -> &[Biome] {
+    pub fn biomes(&self) -> &BiomeStorage {
         &self.biomes
     }
 
     /// Returns a mutable reference to the biomes of this chunk.
-    pub fn biomes_mut(&mut self) -> &mut [Biome] {
+    pub fn biomes_mut(&mut self) -> &mut BiomeStorage {
         self.modified = true;
         &mut self.biomes
     }
 
+    /// Replaces the biomes of this chunk.
+    pub fn set_biomes(&mut self, biomes: BiomeStorage) {
+        self.modified = true;
+        self.biomes = biomes;
+    }
+
     /// Gets the biome for the specified column.
     ///
     /// # Panics
-    /// Panics if `x < 16` or `z < 16`.
+    /// Panics if `x >= 16` or `z >= 16`.
     pub fn biome_at(&self, x: usize, z: usize) -> Biome {
-        let index = Self::biome_index(x, z);
-        self.biomes[index]
+        self.biomes.biome_at(x, z)
     }
 
     /// Sets the biome for the specified column.
     ///
     /// # Panics
-    /// Panics if `x < 16` or `z < 16`.
+    /// Panics if `x >= 16` or `z >= 16`.
     pub fn set_biome_at(&mut self, x: usize, z: usize, biome: Biome) {
-        let index = Self::biome_index(x, z);
         self.modified = true;
-        self.biomes[index] = biome;
+        self.biomes.set_biome_at(x, z, biome);
+    }
+
+    /// Gets the biome at the specified block. Blocks below
+    /// Y 0 or above 255 take the biome at that height.
+    ///
+    /// # Panics
+    /// Panics if the position is outside the chunk.
+    pub fn biome_at_3d(&self, x: usize, y: usize, z: usize) -> Biome {
+        self.check_coords(x, y, z);
+        self.biomes.biome_at_3d(x, self.biome_y(y), z)
+    }
+
+    /// Sets the biome of the 4x4x4 cell containing the specified
+    /// block. This converts the chunk's biomes to the 3D format.
+    ///
+    /// # Panics
+    /// Panics if the position is outside the chunk.
+    pub fn set_biome_at_3d(&mut self, x: usize, y: usize, z: usize, biome: Biome) {
+        self.check_coords(x, y, z);
+        self.modified = true;
+        let y = self.biome_y(y);
+        self.biomes.set_biome_at_3d(x, y, z, biome);
     }
 
     /// Checks whether this chunk has been modified since the last
@@ -534,25 +721,28 @@ impl Chunk {
     pub fn set_modified(&mut self) {
         self.modified = true;
     }
+}
 
-    fn biome_index(x: usize, z: usize) -> usize {
-        assert!(x < 16);
-        assert!(z < 16);
-
-        (z << 4) | x
-    }
+fn check_bounds(min_y: i32, height: usize) {
+    assert_eq!(
+        min_y % SECTION_HEIGHT as i32,
+        0,
+        "chunk bottom must be a multiple of 16"
+    );
+    assert_eq!(
+        height % SECTION_HEIGHT,
+        0,
+        "chunk height must be a multiple of 16"
+    );
+    assert!(height > 0 && height <= MAX_CHUNK_HEIGHT);
 }
 
 /// A chunk section consisting of a 16x16x16
 /// cube of blocks.
 #[derive(Clone, Debug)]
 pub struct ChunkSection {
-    /// The block state data for this chunk section.
-    data: BitArray,
-    /// This section's palette. `None` if using the global palette.
-    /// The palette should always remain sorted so that a binary
-    /// search can be performed on it.
-    palette: Option<Vec<BlockId>>,
+    /// The blocks in this chunk section.
+    blocks: PalettedContainer,
     /// The number of solid blocks in this chunk, i.e. those
     /// that are not air. This value is used to figure out when
     /// the section becomes empty.
@@ -567,70 +757,40 @@ pub struct ChunkSection {
 }
 
 impl ChunkSection {
-    /// Creates a new, empty `ChunkSection`.
+    /// Creates a `ChunkSection` from a raw data array and palette,
+    /// as stored by external sources (e.g. Vanilla). The palette
+    /// may be in any order.
+    ///
+    /// # Panics
+    /// Panics if the data array refers to entries missing
+    /// from the palette. Use `PalettedContainer::from_raw`
+    /// and `from_blocks` to handle invalid data.
     pub fn new(
-        mut data: BitArray,
-        mut palette: Option<Vec<BlockId>>,
+        data: BitArray,
+        palette: Option<Vec<BlockId>>,
         block_light: BitArray,
         sky_light: BitArray,
     ) -> Self {
-        // Correct palette if not using the global palette
-        if let Some(palette) = palette.as_mut() {
-            Self::correct_data_and_palette(&mut data, palette);
-        }
-
-        // Count solid blocks
-        let mut solid_block_count = 0;
-        for x in 0..16 {
-            for y in 0..16 {
-                for z in 0..16 {
-                    if data.get(block_index(x, y, z)) != 0 {
-                        solid_block_count += 1;
-                    }
-                }
-            }
-        }
+        let blocks = PalettedContainer::from_raw(data, palette)
+            .expect("block data refers to entries missing from the palette");
+        Self::from_blocks(blocks, block_light, sky_light)
+    }
 
+    /// Creates a `ChunkSection` containing the given blocks.
+    pub fn from_blocks(
+        blocks: PalettedContainer,
+        block_light: BitArray,
+        sky_light: BitArray,
+    ) -> Self {
         Self {
-            data,
-            palette,
-            solid_block_count,
+            solid_block_count: blocks.count_non_air(),
+            blocks,
             dirty: false,
             block_light,
             sky_light,
         }
     }
 
-    /// Corrects a given raw palette and data array.
-    ///
-    /// Since chunk data stored by external sources
-    /// (e.g. Vanilla) might not require a sorted palette
-    /// like Feather does, we need to sort the palette and
-    /// correct data in the array when reading from external
-    /// sources.
-    ///
-    /// The correction is done in-place.
-    fn correct_data_and_palette(data: &mut BitArray, palette: &mut Vec<BlockId>) {
-        let original_palette = palette.clone(); // Palette without sorting guarantees
-
-        palette.sort_unstable();
-
-        for x in 0..16 {
-            for y in 0..16 {
-                for z in 0..16 {
-                    // Replace index into palette of each block with
-                    // new index into the sorted palette.
-                    let block_index = block_index(x, y, z);
-                    let old_index = data.get(block_index);
-                    let new_index = palette
-                        .binary_search(&original_palette[old_index as usize])
-                        .unwrap();
-                    data.set(block_index, new_index as u64);
-                }
-            }
-        }
-    }
-
     /// Returns whether this chunk section is empty.
     pub fn empty(&self) -> bool {
         self.solid_block_count == 0
@@ -639,13 +799,7 @@ impl ChunkSection {
     /// Retrieves the block at the given position in this chunk section.
     /// The position is local to this section.
     pub fn block_at(&self, x: usize, y: usize, z: usize) -> BlockId {
-        let index = block_index(x, y, z);
-        let block_id = self.data.get(index);
-
-        match &self.palette {
-            Some(palette) => palette[block_id as usize],
-            None => BlockId::from_vanilla_id(block_id as u16),
-        }
+        self.blocks.get(block_index(x, y, z))
     }
 
     /// Sets the block at the given position in this chunk section.
@@ -653,77 +807,13 @@ impl ChunkSection {
     pub fn set_block_at(&mut self, x: usize, y: usize, z: usize, block: BlockId) {
         self.dirty = true;
 
-        let index = block_index(x, y, z);
-
-        // The value that will be put into the data array.
-        let mut paletted_index;
-        if let Some(palette) = self.palette.as_mut() {
-            // Retrieve the block index from the palette.
-
-            // If necessary, add the block to the palette.
-            match palette.binary_search(&block) {
-                Ok(index) => paletted_index = index,
-                Err(insertion_index) => {
-                    palette.insert(insertion_index, block);
-                    paletted_index = insertion_index;
-
-                    // Resize if necessary
-                    if needed_bits((palette.len() - 1) as u64) > self.data.bits_per_value {
-                        let new_bits_per_value = self.data.bits_per_value + 1;
-                        if new_bits_per_value <= MAX_BITS_PER_BLOCK {
-                            self.data = self.data.resize_to(self.data.bits_per_value + 1).unwrap();
-                            paletted_index = insertion_index;
-                        } else {
-                            // Switch to the global palette
-                            let mut new_data = BitArray::new(GLOBAL_BITS_PER_BLOCK, SECTION_VOLUME);
-                            for _x in 0..16 {
-                                for _y in 0..16 {
-                                    for _z in 0..16 {
-                                        let block = self.block_at(_x, _y, _z);
-                                        new_data.set(
-                                            block_index(_x, _y, _z),
-                                            block.vanilla_id() as u64,
-                                        );
-                                    }
-                                }
-                            }
-
-                            self.palette = None;
-                            paletted_index = block.vanilla_id() as usize;
-                            self.data = new_data;
-                        }
-                    }
-
-                    // Correct data, since palette entries after
-                    // the one which was inserted will be offsetted
-                    // by one.
-                    for x in 0..16 {
-                        for y in 0..16 {
-                            for z in 0..16 {
-                                let index = block_index(x, y, z);
-
-                                let entry = self.data.get(index);
-                                if entry >= insertion_index as u64 {
-                                    self.data.set(index, entry + 1);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            // Use the global palette.
-            paletted_index = block.vanilla_id() as usize;
-        }
-
-        let old_block = self.block_at(x, y, z);
+        let old_block = self.blocks.set(block_index(x, y, z), block);
         if block.is_air() && !old_block.is_air() {
             self.solid_block_count -= 1;
         } else if !block.is_air() && old_block.is_air() {
             self.solid_block_count += 1;
         }
 
-        self.data.set(index, paletted_index as u64);
         debug_assert_eq!(self.block_at(x, y, z), block);
     }
 
@@ -742,48 +832,7 @@ impl ChunkSection {
         }
 
         self.dirty = false;
-
-        // Replace palette with new one.
-        let mut new_palette = vec![];
-        for x in 0..16 {
-            for y in 0..16 {
-                for z in 0..16 {
-                    let block = self.block_at(x, y, z);
-                    match new_palette.binary_search(&block) {
-                        Ok(_) => (),
-                        Err(insert_index) => {
-                            new_palette.insert(insert_index, block);
-                        }
-                    }
-                }
-            }
-        }
-
-        // Recalculate all block IDs to match with the new palette.
-        for x in 0..16 {
-            for y in 0..16 {
-                for z in 0..16 {
-                    let block = self.block_at(x, y, z);
-                    self.data.set(
-                        block_index(x, y, z),
-                        new_palette.binary_search(&block).unwrap() as u64,
-                    );
-                }
-            }
-        }
-
-        self.palette = Some(new_palette);
-
-        // Recalculate bits per block value.
-        let mut new_bits_per_block = needed_bits(self.palette.as_ref().unwrap().len() as u64);
-        if new_bits_per_block > MAX_BITS_PER_BLOCK {
-            self.palette = None;
-        } else {
-            if new_bits_per_block < MIN_BITS_PER_BLOCK {
-                new_bits_per_block = MIN_BITS_PER_BLOCK;
-            }
-            self.data = self.data.resize_to(new_bits_per_block).unwrap();
-        }
+        self.blocks.optimize();
 
         true // Chunk was optimized
     }
@@ -791,58 +840,38 @@ impl ChunkSection {
     /// If the global palette is in use, convert it to a section palette.
     /// This is used for chunk saving.
     pub fn convert_palette_to_section(&mut self) {
-        if self.palette.is_some() {
-            // Nothing to do: section palette already in use.
-            return;
-        }
-
-        type TuplePos = (usize, usize, usize);
-        let mut blocks: AHashMap<BlockId, SmallVec<[TuplePos; 4]>> = AHashMap::with_capacity(512);
-        for x in 0..16 {
-            for y in 0..16 {
-                for z in 0..16 {
-                    blocks
-                        .entry(self.block_at(x, y, z))
-                        .or_default()
-                        .push((x, y, z));
-                }
-            }
-        }
-
-        // Create a palette based on the blocks in the chunk.
-        // We also have to modify the data array based on the new palette.
-        let mut palette = Vec::with_capacity(512);
-        for (block, positions) in blocks.into_iter() {
-            palette.push(block);
-
-            for (x, y, z) in positions {
-                let index = block_index(x, y, z);
-                self.data.set(index, (palette.len() - 1) as u64);
-            }
-        }
+        self.blocks.convert_to_section_palette();
+    }
 
-        self.palette = Some(palette);
+    /// Returns the blocks in this section.
+    pub fn blocks(&self) -> &PalettedContainer {
+        &self.blocks
     }
 
     /// Returns the internal data array for this section.
     pub fn data(&self) -> &BitArray {
-        &self.data
+        self.blocks.data()
     }
 
     /// Returns the palette for this section.
     pub fn palette(&self) -> Option<&[BlockId]> {
-        self.palette.as_deref()
+        self.blocks.palette()
     }
 
     /// Returns the number of bits used to store each block.
     pub fn bits_per_block(&self) -> u8 {
-        self.data.bits_per_value
+        self.blocks.bits_per_block()
     }
 
     pub fn sky_light(&self) -> &BitArray {
         &self.sky_light
     }
 
+    /// Returns whether any block in this section has block light.
+    pub fn has_block_light(&self) -> bool {
+        self.block_light.inner().iter().any(|&value| value != 0)
+    }
+
     pub fn block_light(&self) -> &BitArray {
         &self.block_light
     }
@@ -880,10 +909,8 @@ impl ChunkSection {
 
 impl Default for ChunkSection {
     fn default() -> Self {
-        let air = BlockId::air();
         Self {
-            data: BitArray::new(4, SECTION_VOLUME),
-            palette: Some(vec![air]),
+            blocks: PalettedContainer::new(),
             solid_block_count: 0,
             dirty: false,
             block_light: BitArray::new(4, SECTION_VOLUME),
@@ -1345,24 +1372,6 @@ mod tests {
         assert_eq!(block_index(1, 1, 1), 256 + 16 + 1);
     }
 
-    #[test]
-    fn test_correct_data_and_palette() {
-        let mut data = BitArray::new(4, 4096);
-        let mut palette = vec![
-            BlockId::redstone_wire(),
-            BlockId::stone(),
-            BlockId::stone_brick_slab(),
-            BlockId::sea_pickle(),
-            BlockId::acacia_button(),
-        ];
-        ChunkSection::correct_data_and_palette(&mut data, &mut palette);
-        assert_eq!(palette.len(), 5);
-
-        let mut sorted = palette.clone();
-        sorted.sort();
-        assert_eq!(sorted, palette);
-    }
-
     #[test]
     fn test_palette_insertion_in_middle() {
         let mut chunk = ChunkSection::default();
@@ -1445,4 +1454,101 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_heightmap_updates() {
+        let mut chunk = Chunk::default();
+        assert_eq!(chunk.heightmap(1, 2).world_surface(), 0);
+
+        chunk.set_block_at(1, 10, 2, BlockId::stone());
+        chunk.set_block_at(1, 40, 2, BlockId::oak_leaves());
+        chunk.set_block_at(1, 20, 2, BlockId::water());
+
+        let heightmap = *chunk.heightmap(1, 2);
+        assert_eq!(heightmap.world_surface(), 41);
+        assert_eq!(heightmap.motion_blocking(), 41);
+        assert_eq!(heightmap.motion_blocking_no_leaves(), 21);
+        assert_eq!(heightmap.ocean_floor(), 41);
+        assert_eq!(chunk.heightmap(2, 2).world_surface(), 0);
+
+        // Removing the highest block falls back to the next one.
+        chunk.set_block_at(1, 40, 2, BlockId::air());
+        assert_eq!(chunk.heightmap(1, 2).world_surface(), 21);
+        assert_eq!(chunk.heightmap(1, 2).motion_blocking(), 21);
+        assert_eq!(chunk.heightmap(1, 2).ocean_floor(), 11);
+
+        // Removing a lower block leaves the heightmap unchanged.
+        chunk.set_block_at(1, 10, 2, BlockId::air());
+        assert_eq!(chunk.heightmap(1, 2).world_surface(), 21);
+        assert_eq!(chunk.heightmap(1, 2).ocean_floor(), 0);
+
+        chunk.set_block_at(1, 20, 2, BlockId::air());
+        assert_eq!(chunk.heightmap(1, 2).world_surface(), 0);
+
+        chunk.set_block_at(1, 255, 2, BlockId::stone());
+        assert_eq!(chunk.heightmap(1, 2).world_surface(), 256);
+    }
+
+    #[test]
+    fn test_recalculate_heightmap() {
+        let mut chunk = Chunk::default();
+        chunk.set_block_at(3, 70, 4, BlockId::stone());
+        chunk.set_block_at(3, 90, 4, BlockId::oak_leaves());
+
+        // Heightmaps from before are discarded.
+        chunk.heightmap_mut(5, 5).set_world_surface(100);
+        chunk.recalculate_heightmap();
+
+        assert_eq!(chunk.heightmap(3, 4).world_surface(), 91);
+        assert_eq!(chunk.heightmap(3, 4).motion_blocking_no_leaves(), 71);
+        assert_eq!(chunk.heightmap(5, 5).world_surface(), 0);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let pos = ChunkPosition::new(0, 0);
+        let mut chunk = Chunk::with_bounds(pos, -64, 384);
+        assert_eq!(chunk.num_sections(), 24);
+        assert_eq!(chunk.max_y(), 320);
+        assert_eq!(chunk.local_y(-64), Some(0));
+        assert_eq!(chunk.local_y(319), Some(383));
+        assert_eq!(chunk.local_y(-65), None);
+        assert_eq!(chunk.local_y(320), None);
+        assert_eq!(
+            chunk.local_pos(BlockPosition::new(-1, 0, 17)),
+            Some((15, 64, 1))
+        );
+
+        chunk.set_block_at(0, 0, 0, BlockId::bedrock());
+        chunk.set_block_at(0, 383, 0, BlockId::stone());
+        assert_eq!(chunk.heightmap(0, 0).world_surface(), 384);
+        assert!(chunk.section(23).is_some());
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(0, 10, 0, BlockId::stone());
+        chunk.set_block_at(0, 250, 0, BlockId::dirt());
+
+        // Blocks keep their Y coordinates.
+        chunk.resize(-64, 384);
+        assert_eq!(chunk.min_y(), -64);
+        assert_eq!(chunk.block_at(0, 74, 0), BlockId::stone());
+        assert_eq!(chunk.block_at(0, 314, 0), BlockId::dirt());
+        assert_eq!(chunk.block_at(0, 10, 0), BlockId::air());
+        assert_eq!(chunk.heightmap(0, 0).world_surface(), 315);
+
+        // Sections outside the new bounds are discarded.
+        chunk.resize(0, 128);
+        assert_eq!(chunk.num_sections(), 8);
+        assert_eq!(chunk.block_at(0, 10, 0), BlockId::stone());
+        assert_eq!(chunk.heightmap(0, 0).world_surface(), 11);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bounds_not_section_aligned() {
+        Chunk::with_bounds(ChunkPosition::new(0, 0), -8, 256);
+    }
 }
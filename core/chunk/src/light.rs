@@ -0,0 +1,338 @@
+//! Calculation of sky and block light within a chunk,
+//! and the light data sent to clients.
+//!
+//! Light spreads from its sources to adjacent blocks, losing
+//! at least one level per block, or more when passing through
+//! blocks with a higher `light_opacity`. Sky light is the
+//! exception: it travels straight down through transparent
+//! blocks without losing any light.
+
+use crate::{BitArray, Chunk, ChunkSection, NUM_SECTIONS, SECTION_HEIGHT, SECTION_VOLUME};
+use feather_blocks::BlockId;
+use std::collections::VecDeque;
+
+/// The highest light level.
+pub const MAX_LIGHT: u8 = 15;
+
+/// Number of sections in the light data of a chunk of the
+/// default height: the sections of the chunk, plus one
+/// below and one above it.
+pub const LIGHT_SECTIONS: usize = NUM_SECTIONS + 2;
+
+/// Number of bytes in a section's light array.
+const LIGHT_ARRAY_LEN: usize = SECTION_VOLUME / 2;
+
+/// The kind of a light value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LightKind {
+    /// Light from the sky.
+    Sky,
+    /// Light from light-emitting blocks.
+    Block,
+}
+
+/// Returns the light which a block emits.
+pub fn emitted_light(kind: LightKind, block: BlockId) -> u8 {
+    match kind {
+        LightKind::Sky => 0,
+        LightKind::Block => block.light_emission(),
+    }
+}
+
+/// Returns the light level of a block next to a block with the
+/// given light level. `downward` is set if `into` is directly
+/// below the block the light comes from.
+pub fn propagated_light(kind: LightKind, level: u8, into: BlockId, downward: bool) -> u8 {
+    let opacity = into.light_opacity();
+    if kind == LightKind::Sky && downward && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Calculates the sky and block light of a chunk from scratch,
+/// as if it were surrounded by chunks without any light.
+///
+/// Light from neighbouring chunks, which may spread across the
+/// borders, is handled by the lighting worker once they are loaded.
+pub fn compute_light(chunk: &mut Chunk) {
+    let top = match (0..chunk.num_sections())
+        .rev()
+        .find(|&i| chunk.section(i).is_some())
+    {
+        Some(top) => top,
+        // Missing sections are open to the sky and contain no light sources.
+        None => return,
+    };
+
+    for i in 0..=top {
+        if chunk.section(i).is_none() {
+            chunk.set_section_at(i, Some(ChunkSection::default()));
+        }
+        let section = chunk.section_mut(i).unwrap();
+        *section.sky_light_mut() = BitArray::new(4, SECTION_VOLUME);
+        *section.block_light_mut() = BitArray::new(4, SECTION_VOLUME);
+    }
+
+    let height = (top + 1) * SECTION_HEIGHT;
+
+    let mut queue = VecDeque::new();
+    for x in 0..16 {
+        for z in 0..16 {
+            let mut level = MAX_LIGHT;
+            for y in (0..height).rev() {
+                level = propagated_light(LightKind::Sky, level, chunk.block_at(x, y, z), true);
+                if level == 0 {
+                    break;
+                }
+
+                chunk.set_sky_light_at(x, y, z, level);
+                if level > 1 {
+                    queue.push_back((x, y, z));
+                }
+            }
+        }
+    }
+    spread_light(chunk, LightKind::Sky, queue, height);
+
+    let mut queue = VecDeque::new();
+    for x in 0..16 {
+        for y in 0..height {
+            for z in 0..16 {
+                let emission = chunk.block_at(x, y, z).light_emission();
+                if emission > 0 {
+                    chunk.set_block_light_at(x, y, z, emission);
+                    queue.push_back((x, y, z));
+                }
+            }
+        }
+    }
+    // Block light may spread into the section above the top one.
+    let height = (height + SECTION_HEIGHT).min(chunk.height());
+    spread_light(chunk, LightKind::Block, queue, height);
+}
+
+/// Spreads light from the queued blocks to all blocks
+/// below `height` which it makes brighter.
+fn spread_light(
+    chunk: &mut Chunk,
+    kind: LightKind,
+    mut queue: VecDeque<(usize, usize, usize)>,
+    height: usize,
+) {
+    const OFFSETS: [(isize, isize, isize); 6] = [
+        (-1, 0, 0),
+        (1, 0, 0),
+        (0, -1, 0),
+        (0, 1, 0),
+        (0, 0, -1),
+        (0, 0, 1),
+    ];
+
+    while let Some((x, y, z)) = queue.pop_front() {
+        let level = light_at(chunk, kind, x, y, z);
+
+        for &(dx, dy, dz) in &OFFSETS {
+            let (nx, ny, nz) = (x as isize + dx, y as isize + dy, z as isize + dz);
+            if !(0..16).contains(&nx)
+                || !(0..16).contains(&nz)
+                || !(0..height as isize).contains(&ny)
+            {
+                continue;
+            }
+            let (nx, ny, nz) = (nx as usize, ny as usize, nz as usize);
+
+            let new_level = propagated_light(kind, level, chunk.block_at(nx, ny, nz), dy == -1);
+            if new_level > light_at(chunk, kind, nx, ny, nz) {
+                match kind {
+                    LightKind::Sky => chunk.set_sky_light_at(nx, ny, nz, new_level),
+                    LightKind::Block => chunk.set_block_light_at(nx, ny, nz, new_level),
+                }
+                if new_level > 1 {
+                    queue.push_back((nx, ny, nz));
+                }
+            }
+        }
+    }
+}
+
+fn light_at(chunk: &Chunk, kind: LightKind, x: usize, y: usize, z: usize) -> u8 {
+    match kind {
+        LightKind::Sky => chunk.sky_light_at(x, y, z),
+        LightKind::Block => chunk.block_light_at(x, y, z),
+    }
+}
+
+/// The light of a chunk in the format of the Update Light
+/// packet introduced in 1.14.
+///
+/// Light is sent for the sections of the chunk and the sections
+/// directly below and above it; bit `i + 1` of each mask refers to
+/// section `i`. Sections in the empty masks have no light at all,
+/// so no array is sent for them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LightData {
+    pub sky_light_mask: u64,
+    pub block_light_mask: u64,
+    pub empty_sky_light_mask: u64,
+    pub empty_block_light_mask: u64,
+    /// A 2048-byte array for each section in `sky_light_mask`,
+    /// from bottom to top.
+    pub sky_light: Vec<Vec<u8>>,
+    /// A 2048-byte array for each section in `block_light_mask`,
+    /// from bottom to top.
+    pub block_light: Vec<Vec<u8>>,
+}
+
+impl LightData {
+    /// Collects the light data of a chunk. If `has_sky_light`
+    /// is unset, as in the Nether, no sky light is included.
+    pub fn from_chunk(chunk: &Chunk, has_sky_light: bool) -> Self {
+        let mut data = Self::default();
+
+        for index in 0..chunk.num_sections() + 2 {
+            let bit = 1 << index;
+            let section = index
+                .checked_sub(1)
+                .filter(|&i| i < chunk.num_sections())
+                .map(|i| (i, chunk.section(i)));
+
+            if has_sky_light {
+                let sky_light = match section {
+                    Some((_, Some(section))) => light_array(section.sky_light()),
+                    Some((i, None)) if chunk.sky_light_at(0, i * SECTION_HEIGHT, 0) == 0 => None,
+                    // Below the world, there's no sky light.
+                    None if index == 0 => None,
+                    // Missing sections open to the sky, and the section above the chunk.
+                    _ => Some(vec![0xFF; LIGHT_ARRAY_LEN]),
+                };
+                match sky_light {
+                    Some(array) => {
+                        data.sky_light_mask |= bit;
+                        data.sky_light.push(array);
+                    }
+                    None => data.empty_sky_light_mask |= bit,
+                }
+            }
+
+            match section.and_then(|(_, section)| light_array(section?.block_light())) {
+                Some(array) => {
+                    data.block_light_mask |= bit;
+                    data.block_light.push(array);
+                }
+                None => data.empty_block_light_mask |= bit,
+            }
+        }
+
+        data
+    }
+}
+
+/// Converts a light array to bytes, two values per byte with
+/// the first in the low nibble. Returns `None` if all values are 0.
+fn light_array(light: &BitArray) -> Option<Vec<u8>> {
+    if light.inner().iter().all(|&value| value == 0) {
+        return None;
+    }
+
+    Some(
+        light
+            .inner()
+            .iter()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_util::ChunkPosition;
+
+    fn chunk_with_floor(y: usize) -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, y, z, BlockId::stone());
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn sky_light() {
+        let mut chunk = chunk_with_floor(40);
+        // A roof with a hole in it.
+        for x in 2..10 {
+            for z in 2..10 {
+                chunk.set_block_at(x, 50, z, BlockId::stone());
+            }
+        }
+        chunk.set_block_at(5, 50, 5, BlockId::air());
+        chunk.set_block_at(12, 45, 12, BlockId::water());
+        compute_light(&mut chunk);
+
+        assert_eq!(chunk.sky_light_at(0, 41, 0), 15);
+        assert_eq!(chunk.sky_light_at(0, 200, 0), 15);
+        assert_eq!(chunk.sky_light_at(0, 40, 0), 0);
+        assert_eq!(chunk.sky_light_at(0, 10, 0), 0);
+
+        // Light through the hole travels straight down,
+        // then spreads out below the roof.
+        assert_eq!(chunk.sky_light_at(5, 41, 5), 15);
+        assert_eq!(chunk.sky_light_at(6, 41, 5), 14);
+        assert_eq!(chunk.sky_light_at(7, 41, 7), 11);
+        // Light also reaches below the roof from its edges.
+        assert_eq!(chunk.sky_light_at(2, 49, 2), 14);
+        assert_eq!(chunk.sky_light_at(4, 49, 4), 13);
+
+        // Sky light doesn't pass through water unchanged.
+        assert_eq!(chunk.sky_light_at(12, 45, 12), 14);
+    }
+
+    #[test]
+    fn block_light() {
+        let mut chunk = chunk_with_floor(40);
+        chunk.set_block_at(0, 41, 0, BlockId::glowstone());
+        chunk.set_block_at(0, 41, 2, BlockId::stone());
+        compute_light(&mut chunk);
+
+        assert_eq!(chunk.block_light_at(0, 41, 0), 15);
+        assert_eq!(chunk.block_light_at(1, 41, 0), 14);
+        assert_eq!(chunk.block_light_at(0, 45, 0), 11);
+        assert_eq!(chunk.block_light_at(0, 41, 3), 10);
+        assert_eq!(chunk.block_light_at(0, 41, 2), 0);
+        assert_eq!(chunk.block_light_at(0, 40, 0), 0);
+        assert_eq!(chunk.block_light_at(15, 41, 0), 0);
+        // Light reaches the section above the top one.
+        assert_eq!(chunk.block_light_at(0, 48, 0), 8);
+    }
+
+    #[test]
+    fn light_data() {
+        let mut chunk = chunk_with_floor(40);
+        chunk.set_block_at(0, 41, 0, BlockId::glowstone());
+        compute_light(&mut chunk);
+
+        let data = LightData::from_chunk(&chunk, true);
+        // Sections 0 and 1 are dark, while section 2 has light
+        // and sections 3 and above are open to the sky.
+        assert_eq!(data.empty_sky_light_mask, 0b111);
+        assert_eq!(data.sky_light_mask, 0x3FFFF & !0b111);
+        assert_eq!(data.sky_light.len(), LIGHT_SECTIONS - 3);
+        assert!(data.sky_light.iter().all(|array| array.len() == 2048));
+        assert_eq!(data.block_light_mask, 0b1000 | 0b10000);
+        assert_eq!(data.block_light.len(), 2);
+
+        // Blocks (0, 9, 0) and (1, 9, 0) of section 2 share a byte.
+        let byte = (9 << 8) / 2;
+        assert_eq!(data.sky_light[0][byte] >> 4, 15);
+        assert_eq!(data.block_light[0][byte] & 0x0F, 15);
+        assert_eq!(data.block_light[0][byte] >> 4, 14);
+
+        let data = LightData::from_chunk(&chunk, false);
+        assert_eq!(data.sky_light_mask | data.empty_sky_light_mask, 0);
+        assert!(data.sky_light.is_empty());
+    }
+}
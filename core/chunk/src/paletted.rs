@@ -0,0 +1,268 @@
+//! Paletted, bit-packed storage for the blocks of a chunk section.
+
+use crate::{
+    needed_bits, BitArray, GLOBAL_BITS_PER_BLOCK, MAX_BITS_PER_BLOCK, MIN_BITS_PER_BLOCK,
+    SECTION_VOLUME,
+};
+use ahash::AHashMap;
+use feather_blocks::BlockId;
+
+/// The blocks of a chunk section, stored as indices
+/// into a palette of the blocks the section contains.
+///
+/// Indices use as few bits as the palette allows, and
+/// at least `MIN_BITS_PER_BLOCK`, so a section containing
+/// only a handful of blocks takes half a byte per block.
+/// Once a section needs more than `MAX_BITS_PER_BLOCK`,
+/// it switches to the global palette and stores vanilla
+/// block IDs directly.
+///
+/// The palette and data array use the layout of chunk
+/// packets and region files, so they can be written as-is.
+#[derive(Clone, Debug)]
+pub struct PalettedContainer {
+    /// The section palette, in insertion order.
+    /// `None` if using the global palette.
+    palette: Option<Vec<BlockId>>,
+    /// Palette index (or vanilla ID) of each block.
+    data: BitArray,
+}
+
+impl PalettedContainer {
+    /// Creates a container filled with air.
+    pub fn new() -> Self {
+        Self::filled(BlockId::air())
+    }
+
+    /// Creates a container filled with the given block.
+    pub fn filled(block: BlockId) -> Self {
+        Self {
+            palette: Some(vec![block]),
+            data: BitArray::new(MIN_BITS_PER_BLOCK, SECTION_VOLUME),
+        }
+    }
+
+    /// Creates a container from a raw data array and palette,
+    /// which may be in any order. A palette of `None` means
+    /// the data array contains vanilla block IDs.
+    ///
+    /// Returns `None` if the data array has the wrong length
+    /// or refers to entries missing from the palette.
+    pub fn from_raw(data: BitArray, palette: Option<Vec<BlockId>>) -> Option<Self> {
+        if data.capacity != SECTION_VOLUME
+            || data.data.len() * 64 < SECTION_VOLUME * data.bits_per_value as usize
+        {
+            return None;
+        }
+
+        if let Some(palette) = &palette {
+            if (0..SECTION_VOLUME).any(|index| data.get(index) as usize >= palette.len()) {
+                return None;
+            }
+        }
+
+        Some(Self { palette, data })
+    }
+
+    /// Returns the block at the given index.
+    pub fn get(&self, index: usize) -> BlockId {
+        let value = self.data.get(index);
+
+        match &self.palette {
+            Some(palette) => palette[value as usize],
+            None => BlockId::from_vanilla_id(value as u16),
+        }
+    }
+
+    /// Sets the block at the given index,
+    /// returning the block which was replaced.
+    pub fn set(&mut self, index: usize, block: BlockId) -> BlockId {
+        let old_block = self.get(index);
+        if old_block != block {
+            let value = self.index_or_insert(block);
+            self.data.set(index, value);
+        }
+        old_block
+    }
+
+    /// Returns the number of blocks which aren't air.
+    pub fn count_non_air(&self) -> u16 {
+        (0..SECTION_VOLUME)
+            .filter(|&index| !self.get(index).is_air())
+            .count() as u16
+    }
+
+    /// Returns the section palette, or `None` if
+    /// the global palette is in use.
+    pub fn palette(&self) -> Option<&[BlockId]> {
+        self.palette.as_deref()
+    }
+
+    /// Returns the data array.
+    pub fn data(&self) -> &BitArray {
+        &self.data
+    }
+
+    /// Returns the number of bits used to store each block.
+    pub fn bits_per_block(&self) -> u8 {
+        self.data.bits_per_value
+    }
+
+    /// Rebuilds the palette from the blocks in use, removing
+    /// unused entries and reducing the bits per block as much
+    /// as possible. This can switch back from the global palette.
+    pub fn optimize(&mut self) {
+        let (palette, indices) = self.collect_palette();
+
+        let bits_per_block = needed_bits(palette.len() as u64 - 1).max(MIN_BITS_PER_BLOCK);
+        if bits_per_block > MAX_BITS_PER_BLOCK {
+            if self.palette.is_some() {
+                self.switch_to_global();
+            }
+            return;
+        }
+
+        self.set_palette(palette, &indices, bits_per_block);
+    }
+
+    /// Converts the global palette, if in use, to a section palette,
+    /// regardless of its size. Region files always use section palettes.
+    pub fn convert_to_section_palette(&mut self) {
+        if self.palette.is_some() {
+            return;
+        }
+
+        let (palette, indices) = self.collect_palette();
+        let bits_per_block = needed_bits(palette.len() as u64 - 1).max(MIN_BITS_PER_BLOCK);
+        self.set_palette(palette, &indices, bits_per_block);
+    }
+
+    /// Returns the palette index of the given block,
+    /// adding it to the palette if necessary.
+    fn index_or_insert(&mut self, block: BlockId) -> u64 {
+        let palette = match &mut self.palette {
+            Some(palette) => palette,
+            None => return u64::from(block.vanilla_id()),
+        };
+
+        if let Some(index) = palette.iter().position(|&entry| entry == block) {
+            return index as u64;
+        }
+
+        palette.push(block);
+        let index = palette.len() - 1;
+
+        let bits_per_block = needed_bits(index as u64);
+        if bits_per_block > self.data.bits_per_value {
+            if bits_per_block > MAX_BITS_PER_BLOCK {
+                self.switch_to_global();
+                return u64::from(block.vanilla_id());
+            }
+            self.data = self
+                .data
+                .resize_to(bits_per_block)
+                .expect("existing indices fit in more bits");
+        }
+
+        index as u64
+    }
+
+    fn switch_to_global(&mut self) {
+        let mut data = BitArray::new(GLOBAL_BITS_PER_BLOCK, SECTION_VOLUME);
+        for index in 0..SECTION_VOLUME {
+            data.set(index, u64::from(self.get(index).vanilla_id()));
+        }
+
+        self.palette = None;
+        self.data = data;
+    }
+
+    /// Returns the distinct blocks in this container, in
+    /// order of appearance, and the index of each block
+    /// into that palette.
+    fn collect_palette(&self) -> (Vec<BlockId>, Vec<u16>) {
+        let mut palette = Vec::new();
+        let mut lookup = AHashMap::new();
+
+        let indices = (0..SECTION_VOLUME)
+            .map(|index| {
+                let block = self.get(index);
+                *lookup.entry(block).or_insert_with(|| {
+                    palette.push(block);
+                    (palette.len() - 1) as u16
+                })
+            })
+            .collect();
+
+        (palette, indices)
+    }
+
+    fn set_palette(&mut self, palette: Vec<BlockId>, indices: &[u16], bits_per_block: u8) {
+        let mut data = BitArray::new(bits_per_block, SECTION_VOLUME);
+        for (index, &value) in indices.iter().enumerate() {
+            data.set(index, u64::from(value));
+        }
+
+        self.palette = Some(palette);
+        self.data = data;
+    }
+}
+
+impl Default for PalettedContainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_and_switches_to_global() {
+        let mut container = PalettedContainer::new();
+        assert_eq!(container.bits_per_block(), MIN_BITS_PER_BLOCK);
+
+        assert_eq!(container.set(10, BlockId::stone()), BlockId::air());
+        assert_eq!(container.get(10), BlockId::stone());
+        assert_eq!(container.palette().unwrap().len(), 2);
+
+        // 17 distinct blocks need 5 bits.
+        for id in 1..=16 {
+            container.set(id as usize, BlockId::from_vanilla_id(id * 20));
+        }
+        assert_eq!(container.bits_per_block(), 5);
+
+        for id in 0..300 {
+            container.set(id as usize, BlockId::from_vanilla_id(id + 1));
+        }
+        assert!(container.palette().is_none());
+        assert_eq!(container.bits_per_block(), GLOBAL_BITS_PER_BLOCK);
+        for id in 0..300 {
+            assert_eq!(container.get(id as usize), BlockId::from_vanilla_id(id + 1));
+        }
+
+        // Removing most blocks allows a section palette again.
+        for index in 0..300 {
+            container.set(index, BlockId::air());
+        }
+        container.optimize();
+        assert_eq!(container.palette(), Some(&[BlockId::air()][..]));
+        assert_eq!(container.bits_per_block(), MIN_BITS_PER_BLOCK);
+        assert_eq!(container.count_non_air(), 0);
+    }
+
+    #[test]
+    fn from_raw_checks_palette() {
+        let mut data = BitArray::new(4, SECTION_VOLUME);
+        data.set(5, 1);
+
+        let container =
+            PalettedContainer::from_raw(data.clone(), Some(vec![BlockId::air(), BlockId::stone()]))
+                .unwrap();
+        assert_eq!(container.get(5), BlockId::stone());
+        assert_eq!(container.count_non_air(), 1);
+
+        assert!(PalettedContainer::from_raw(data, Some(vec![BlockId::air()])).is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use feather_blocks::BlockId;
-use feather_chunk::{Chunk, CHUNK_HEIGHT};
+use feather_chunk::Chunk;
 use feather_util::{BlockPosition, ChunkPosition};
 use hashbrown::HashMap;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -48,10 +48,9 @@ impl ChunkMap {
     /// exists is not loaded or the coordinates
     /// are out of bounds, `None` is returned.
     pub fn block_at(&self, pos: BlockPosition) -> Option<BlockId> {
-        check_coords(pos)?;
-        let (x, y, z) = chunk_relative_pos(pos);
-        self.chunk_at(pos.into())
-            .map(|chunk| chunk.block_at(x, y, z))
+        let chunk = self.chunk_at(pos.into())?;
+        let (x, y, z) = chunk.local_pos(pos)?;
+        Some(chunk.block_at(x, y, z))
     }
 
     /// Sets the block at the given position.
@@ -61,13 +60,12 @@ impl ChunkMap {
     /// are out of bounds and thus no operation
     /// was performed.
     pub fn set_block_at(&self, pos: BlockPosition, block: BlockId) -> bool {
-        if check_coords(pos).is_none() {
-            return false;
-        }
-        let (x, y, z) = chunk_relative_pos(pos);
-
         self.chunk_at_mut(pos.into())
-            .map(|mut chunk| chunk.set_block_at(x, y, z, block))
+            .and_then(|mut chunk| {
+                let (x, y, z) = chunk.local_pos(pos)?;
+                chunk.set_block_at(x, y, z, block);
+                Some(())
+            })
             .is_some()
     }
 
@@ -88,22 +86,6 @@ impl ChunkMap {
     }
 }
 
-fn check_coords(pos: BlockPosition) -> Option<()> {
-    if pos.y >= 0 && pos.y < CHUNK_HEIGHT as i32 {
-        Some(())
-    } else {
-        None
-    }
-}
-
-pub fn chunk_relative_pos(block_pos: BlockPosition) -> (usize, usize, usize) {
-    (
-        block_pos.x as usize & 0xf,
-        block_pos.y as usize,
-        block_pos.z as usize & 0xf,
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,5 +97,21 @@ mod tests {
 
         assert!(map.block_at(BlockPosition::new(0, -1, 0)).is_none());
         assert!(map.block_at(BlockPosition::new(0, 0, 0)).is_some());
+        assert!(map.block_at(BlockPosition::new(0, 256, 0)).is_none());
+    }
+
+    #[test]
+    fn chunk_map_extended_height() {
+        let mut map = ChunkMap::new();
+        map.insert(Chunk::with_bounds(ChunkPosition::new(0, 0), -64, 384));
+
+        let pos = BlockPosition::new(3, -64, 4);
+        assert!(map.set_block_at(pos, BlockId::bedrock()));
+        assert_eq!(map.block_at(pos), Some(BlockId::bedrock()));
+        assert_eq!(
+            map.block_at(BlockPosition::new(3, 319, 4)),
+            Some(BlockId::air())
+        );
+        assert!(!map.set_block_at(BlockPosition::new(3, 320, 4), BlockId::stone()));
     }
 }
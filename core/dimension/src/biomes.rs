@@ -0,0 +1,168 @@
+//! Loading of custom biomes from data packs.
+
+use crate::codec::{category_from_name, precipitation_from_name};
+use crate::{datapack_files, LoadError};
+use feather_biomes::{Biome, BiomeRegistry, CustomBiome, SpawnCategory, SpawnEntry};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// A biome in the data pack format.
+#[derive(Debug, Deserialize)]
+struct BiomeDefinition {
+    precipitation: String,
+    category: String,
+    temperature: f32,
+    downfall: f32,
+    effects: EffectsDefinition,
+    #[serde(default)]
+    spawners: BTreeMap<String, Vec<SpawnerDefinition>>,
+    /// The vanilla biome standing in for this one. Not part of the
+    /// vanilla format; defaults to a biome of the same category.
+    #[serde(default, rename = "feather:parent")]
+    parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EffectsDefinition {
+    sky_color: u32,
+    fog_color: u32,
+    water_color: u32,
+    water_fog_color: u32,
+    #[serde(default)]
+    grass_color: Option<u32>,
+    #[serde(default)]
+    foliage_color: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpawnerDefinition {
+    #[serde(rename = "type")]
+    entity: String,
+    weight: u32,
+    #[serde(rename = "minCount")]
+    min_count: u32,
+    #[serde(rename = "maxCount")]
+    max_count: u32,
+}
+
+impl BiomeDefinition {
+    fn into_custom_biome(self, name: String) -> Result<CustomBiome, String> {
+        let category = category_from_name(&self.category)
+            .ok_or_else(|| format!("unknown category {}", self.category))?;
+        let precipitation = precipitation_from_name(&self.precipitation)
+            .ok_or_else(|| format!("unknown precipitation {}", self.precipitation))?;
+
+        let parent = match &self.parent {
+            Some(parent) => Biome::from_namespaced_id(parent)
+                .ok_or_else(|| format!("unknown parent biome {}", parent))?,
+            None => Biome::iter()
+                .find(|biome| biome.category() == category)
+                .unwrap_or(Biome::Plains),
+        };
+
+        let mut spawns = Vec::new();
+        for (category, spawners) in self.spawners {
+            let category = SpawnCategory::from_name(&category)
+                .ok_or_else(|| format!("unknown spawn category {}", category))?;
+            spawns.extend(spawners.into_iter().map(|spawner| SpawnEntry {
+                category,
+                entity: spawner.entity,
+                weight: spawner.weight,
+                min_count: spawner.min_count,
+                max_count: spawner.max_count,
+            }));
+        }
+
+        Ok(CustomBiome {
+            name,
+            parent,
+            temperature: self.temperature,
+            downfall: self.downfall,
+            precipitation,
+            category,
+            sky_color: self.effects.sky_color,
+            fog_color: self.effects.fog_color,
+            water_color: self.effects.water_color,
+            water_fog_color: self.effects.water_fog_color,
+            grass_color: self.effects.grass_color,
+            foliage_color: self.effects.foliage_color,
+            spawns,
+        })
+    }
+}
+
+/// Registers the custom biomes from all data packs in the given
+/// directory, usually `<world>/datapacks`. Each biome is read from
+/// `<pack>/data/<namespace>/worldgen/biome/<name>.json`.
+///
+/// Vanilla biomes can't be overridden; such files,
+/// and duplicates in later packs, are skipped.
+pub fn load_biome_datapacks(
+    registry: &mut BiomeRegistry,
+    datapacks: &Path,
+) -> Result<(), LoadError> {
+    for (id, file) in datapack_files(datapacks, "worldgen/biome")? {
+        let bytes = fs::read(&file).map_err(|e| LoadError::Io(file.clone(), e))?;
+        let definition: BiomeDefinition =
+            serde_json::from_slice(&bytes).map_err(|e| LoadError::Json(file.clone(), e))?;
+        let biome = definition
+            .into_custom_biome(id.clone())
+            .map_err(|e| LoadError::InvalidBiome(file.clone(), e))?;
+
+        match registry.register(biome) {
+            Ok(numeric_id) => {
+                log::debug!("Registered biome {} with ID {}", id, numeric_id);
+            }
+            Err(e) => log::warn!("Skipping biome {}: {}", file.display(), e),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn load_biome() {
+        let datapacks = env::temp_dir().join(format!(
+            "feather-dimension-biome-test-{}",
+            std::process::id()
+        ));
+        let directory = datapacks.join("pack/data/custom/worldgen/biome");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("crystal_fields.json"),
+            r#"{
+                "precipitation": "snow", "category": "icy", "temperature": -0.5,
+                "downfall": 0.5, "depth": 0.1, "scale": 0.2,
+                "effects": {
+                    "sky_color": 8103167, "fog_color": 12638463,
+                    "water_color": 4159204, "water_fog_color": 329011,
+                    "grass_color": 16711935
+                },
+                "spawners": {
+                    "monster": [{ "type": "minecraft:stray", "weight": 80, "minCount": 4, "maxCount": 4 }]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut registry = BiomeRegistry::new();
+        load_biome_datapacks(&mut registry, &datapacks).unwrap();
+        fs::remove_dir_all(&datapacks).unwrap();
+
+        let id = registry.id_of("custom:crystal_fields").unwrap();
+        let biome = registry.get(id).unwrap();
+        assert_eq!(biome.category, feather_biomes::BiomeCategory::Icy);
+        assert_eq!(biome.parent.category(), feather_biomes::BiomeCategory::Icy);
+        assert_eq!(biome.grass_color, Some(0xFF_00_FF));
+        assert_eq!(biome.spawns.len(), 1);
+        assert_eq!(biome.spawns[0].category, SpawnCategory::Monster);
+    }
+}
@@ -0,0 +1,266 @@
+//! The dimension codec: an NBT registry of dimension types
+//! and biomes sent to 1.16+ clients when they join.
+
+use crate::{DimensionType, DimensionTypes};
+use feather_biomes::{registry, Biome, BiomeCategory, CustomBiome, Precipitation};
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+/// The root of the dimension codec.
+#[derive(Debug, Clone, Serialize)]
+pub struct DimensionCodec {
+    #[serde(rename = "minecraft:dimension_type")]
+    pub dimension_types: Registry<DimensionType>,
+    #[serde(rename = "minecraft:worldgen/biome")]
+    pub biomes: Registry<BiomeElement>,
+}
+
+/// A registry within the codec.
+#[derive(Debug, Clone, Serialize)]
+pub struct Registry<T> {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Vec<RegistryEntry<T>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryEntry<T> {
+    pub name: String,
+    pub id: i32,
+    pub element: T,
+}
+
+/// A biome, as described to the client.
+#[derive(Debug, Clone, Serialize)]
+pub struct BiomeElement {
+    pub precipitation: &'static str,
+    /// Terrain depth and scale. The client doesn't use these,
+    /// so the same values are sent for every biome.
+    pub depth: f32,
+    pub scale: f32,
+    pub temperature: f32,
+    pub downfall: f32,
+    pub category: &'static str,
+    pub effects: BiomeEffects,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BiomeEffects {
+    pub sky_color: i32,
+    pub water_fog_color: i32,
+    pub fog_color: i32,
+    pub water_color: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grass_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foliage_color: Option<i32>,
+}
+
+impl BiomeElement {
+    fn for_biome(biome: Biome) -> Self {
+        Self {
+            precipitation: precipitation_name(biome.precipitation()),
+            depth: 0.1,
+            scale: 0.2,
+            temperature: biome.temperature(),
+            downfall: biome.downfall(),
+            category: category_name(biome.category()),
+            effects: BiomeEffects {
+                sky_color: biome.sky_color() as i32,
+                water_fog_color: biome.water_fog_color() as i32,
+                fog_color: biome.fog_color() as i32,
+                water_color: biome.water_color() as i32,
+                grass_color: None,
+                foliage_color: None,
+            },
+        }
+    }
+
+    fn for_custom_biome(biome: &CustomBiome) -> Self {
+        Self {
+            precipitation: precipitation_name(biome.precipitation),
+            depth: 0.1,
+            scale: 0.2,
+            temperature: biome.temperature,
+            downfall: biome.downfall,
+            category: category_name(biome.category),
+            effects: BiomeEffects {
+                sky_color: biome.sky_color as i32,
+                water_fog_color: biome.water_fog_color as i32,
+                fog_color: biome.fog_color as i32,
+                water_color: biome.water_color as i32,
+                grass_color: biome.grass_color.map(|color| color as i32),
+                foliage_color: biome.foliage_color.map(|color| color as i32),
+            },
+        }
+    }
+}
+
+fn precipitation_name(precipitation: Precipitation) -> &'static str {
+    match precipitation {
+        Precipitation::None => "none",
+        Precipitation::Rain => "rain",
+        Precipitation::Snow => "snow",
+    }
+}
+
+pub(crate) fn precipitation_from_name(name: &str) -> Option<Precipitation> {
+    Some(match name {
+        "none" => Precipitation::None,
+        "rain" => Precipitation::Rain,
+        "snow" => Precipitation::Snow,
+        _ => return None,
+    })
+}
+
+const CATEGORIES: [BiomeCategory; 17] = [
+    BiomeCategory::None,
+    BiomeCategory::Taiga,
+    BiomeCategory::ExtremeHills,
+    BiomeCategory::Jungle,
+    BiomeCategory::Mesa,
+    BiomeCategory::Plains,
+    BiomeCategory::Savanna,
+    BiomeCategory::Icy,
+    BiomeCategory::TheEnd,
+    BiomeCategory::Beach,
+    BiomeCategory::Forest,
+    BiomeCategory::Ocean,
+    BiomeCategory::Desert,
+    BiomeCategory::River,
+    BiomeCategory::Swamp,
+    BiomeCategory::Mushroom,
+    BiomeCategory::Nether,
+];
+
+pub(crate) fn category_from_name(name: &str) -> Option<BiomeCategory> {
+    CATEGORIES
+        .iter()
+        .copied()
+        .find(|&category| category_name(category) == name)
+}
+
+fn category_name(category: BiomeCategory) -> &'static str {
+    match category {
+        BiomeCategory::None => "none",
+        BiomeCategory::Taiga => "taiga",
+        BiomeCategory::ExtremeHills => "extreme_hills",
+        BiomeCategory::Jungle => "jungle",
+        BiomeCategory::Mesa => "mesa",
+        BiomeCategory::Plains => "plains",
+        BiomeCategory::Savanna => "savanna",
+        BiomeCategory::Icy => "icy",
+        BiomeCategory::TheEnd => "the_end",
+        BiomeCategory::Beach => "beach",
+        BiomeCategory::Forest => "forest",
+        BiomeCategory::Ocean => "ocean",
+        BiomeCategory::Desert => "desert",
+        BiomeCategory::River => "river",
+        BiomeCategory::Swamp => "swamp",
+        BiomeCategory::Mushroom => "mushroom",
+        BiomeCategory::Nether => "nether",
+    }
+}
+
+impl DimensionCodec {
+    /// Creates the codec for the given dimension types and all
+    /// biomes, including those in the global biome registry.
+    pub fn new(dimension_types: &DimensionTypes) -> Self {
+        Self {
+            dimension_types: Registry {
+                kind: "minecraft:dimension_type".to_owned(),
+                value: dimension_types
+                    .iter()
+                    .enumerate()
+                    .map(|(id, (name, dimension_type))| RegistryEntry {
+                        name: name.to_owned(),
+                        id: id as i32,
+                        element: dimension_type.clone(),
+                    })
+                    .collect(),
+            },
+            biomes: Registry {
+                kind: "minecraft:worldgen/biome".to_owned(),
+                value: Biome::iter()
+                    .map(|biome| RegistryEntry {
+                        name: biome.namespaced_id().to_owned(),
+                        id: biome.id(),
+                        element: BiomeElement::for_biome(biome),
+                    })
+                    .chain(registry().custom_biomes().map(|(id, biome)| RegistryEntry {
+                        name: biome.name.clone(),
+                        id,
+                        element: BiomeElement::for_custom_biome(biome),
+                    }))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Writes the codec as uncompressed NBT, as sent in the Join Game packet.
+    pub fn to_nbt(&self) -> Result<Vec<u8>, nbt::Error> {
+        let mut buf = Vec::new();
+        nbt::to_writer(&mut buf, self, None)?;
+        Ok(buf)
+    }
+}
+
+/// Returns the encoded dimension codec for the given dimension types.
+pub fn dimension_codec(dimension_types: &DimensionTypes) -> Result<Vec<u8>, nbt::Error> {
+    DimensionCodec::new(dimension_types).to_nbt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Codec {
+        #[serde(rename = "minecraft:dimension_type")]
+        dimension_types: DecodedRegistry,
+        #[serde(rename = "minecraft:worldgen/biome")]
+        biomes: DecodedRegistry,
+    }
+
+    #[derive(Deserialize)]
+    struct DecodedRegistry {
+        #[serde(rename = "type")]
+        kind: String,
+        value: Vec<DecodedEntry>,
+    }
+
+    #[derive(Deserialize)]
+    struct DecodedEntry {
+        name: String,
+        id: i32,
+    }
+
+    #[test]
+    fn codec_roundtrip() {
+        let bytes = dimension_codec(&DimensionTypes::vanilla()).unwrap();
+        let codec: Codec = nbt::from_reader(bytes.as_slice()).unwrap();
+
+        assert_eq!(codec.dimension_types.kind, "minecraft:dimension_type");
+        assert_eq!(codec.dimension_types.value.len(), 4);
+        assert_eq!(codec.dimension_types.value[2].name, "minecraft:the_nether");
+        assert_eq!(codec.dimension_types.value[2].id, 2);
+
+        assert_eq!(codec.biomes.kind, "minecraft:worldgen/biome");
+        let plains = codec
+            .biomes
+            .value
+            .iter()
+            .find(|entry| entry.name == "minecraft:plains")
+            .unwrap();
+        assert_eq!(plains.id, Biome::Plains.id());
+    }
+
+    #[test]
+    fn category_names() {
+        for &category in CATEGORIES.iter() {
+            assert_eq!(category_from_name(category_name(category)), Some(category));
+        }
+        assert_eq!(category_from_name("unknown"), None);
+    }
+}
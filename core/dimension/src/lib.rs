@@ -0,0 +1,441 @@
+//! Dimension types: the properties of a dimension, such as
+//! its height and lighting, which the client needs to render it.
+//!
+//! Besides the vanilla dimension types, additional types
+//! (or overrides of the vanilla ones) can be loaded from the
+//! `dimension_type` directories of data packs.
+
+mod biomes;
+mod codec;
+
+pub use biomes::load_biome_datapacks;
+pub use codec::{
+    dimension_codec, BiomeEffects, BiomeElement, DimensionCodec, Registry, RegistryEntry,
+};
+
+use feather_chunk::{MAX_CHUNK_HEIGHT, SECTION_HEIGHT};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The namespace of vanilla dimension types.
+pub const NAMESPACE: &str = "minecraft";
+
+/// The properties of a dimension, in the format
+/// used by data packs and the dimension codec.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DimensionType {
+    /// Whether piglins can live in this dimension without zombifying.
+    pub piglin_safe: bool,
+    /// Whether compasses and clocks work, and whether
+    /// portals spawn zombified piglins.
+    pub natural: bool,
+    /// Minimum light level, from 0.0 to 1.0.
+    pub ambient_light: f32,
+    /// If set, the time of day is fixed to this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed_time: Option<i64>,
+    /// Block tag of blocks on which fire burns forever.
+    pub infiniburn: String,
+    pub respawn_anchor_works: bool,
+    pub has_skylight: bool,
+    pub bed_works: bool,
+    /// Sky effects used by the client, e.g. `minecraft:the_nether`.
+    pub effects: String,
+    pub has_raids: bool,
+    /// Maximum height to which portals and chorus fruit can teleport.
+    pub logical_height: i32,
+    /// Scale of coordinates relative to other dimensions,
+    /// e.g. 8.0 in the Nether.
+    pub coordinate_scale: f64,
+    /// Whether water evaporates and lava spreads faster.
+    pub ultrawarm: bool,
+    /// Whether the dimension has a bedrock ceiling.
+    pub has_ceiling: bool,
+    /// Lowest Y coordinate at which blocks may exist.
+    #[serde(default)]
+    pub min_y: i32,
+    /// Total height in which blocks may exist.
+    #[serde(default = "default_height")]
+    pub height: i32,
+}
+
+fn default_height() -> i32 {
+    256
+}
+
+impl DimensionType {
+    /// Returns whether the vertical bounds of the dimension can be
+    /// stored in chunks: both must be aligned to chunk sections,
+    /// and the height at most `MAX_CHUNK_HEIGHT`.
+    pub fn has_valid_bounds(&self) -> bool {
+        let section = SECTION_HEIGHT as i32;
+        self.min_y % section == 0
+            && self.height % section == 0
+            && self.height > 0
+            && self.height <= MAX_CHUNK_HEIGHT as i32
+    }
+
+    /// The overworld's dimension type.
+    pub fn overworld() -> Self {
+        Self {
+            piglin_safe: false,
+            natural: true,
+            ambient_light: 0.0,
+            fixed_time: None,
+            infiniburn: "minecraft:infiniburn_overworld".to_owned(),
+            respawn_anchor_works: false,
+            has_skylight: true,
+            bed_works: true,
+            effects: "minecraft:overworld".to_owned(),
+            has_raids: true,
+            logical_height: 256,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false,
+            min_y: 0,
+            height: 256,
+        }
+    }
+
+    /// The type of overworlds using the "caves" generator,
+    /// which have a bedrock ceiling.
+    pub fn overworld_caves() -> Self {
+        Self {
+            has_ceiling: true,
+            ..Self::overworld()
+        }
+    }
+
+    /// The Nether's dimension type.
+    pub fn nether() -> Self {
+        Self {
+            piglin_safe: true,
+            natural: false,
+            ambient_light: 0.1,
+            fixed_time: Some(18000),
+            infiniburn: "minecraft:infiniburn_nether".to_owned(),
+            respawn_anchor_works: true,
+            has_skylight: false,
+            bed_works: false,
+            effects: "minecraft:the_nether".to_owned(),
+            has_raids: false,
+            logical_height: 128,
+            coordinate_scale: 8.0,
+            ultrawarm: true,
+            has_ceiling: true,
+            min_y: 0,
+            height: 256,
+        }
+    }
+
+    /// The End's dimension type.
+    pub fn end() -> Self {
+        Self {
+            piglin_safe: false,
+            natural: false,
+            ambient_light: 0.0,
+            fixed_time: Some(6000),
+            infiniburn: "minecraft:infiniburn_end".to_owned(),
+            respawn_anchor_works: false,
+            has_skylight: false,
+            bed_works: false,
+            effects: "minecraft:the_end".to_owned(),
+            has_raids: true,
+            logical_height: 256,
+            coordinate_scale: 1.0,
+            ultrawarm: false,
+            has_ceiling: false,
+            min_y: 0,
+            height: 256,
+        }
+    }
+
+    /// Writes this dimension type as uncompressed NBT,
+    /// as sent in the Join Game and Respawn packets.
+    pub fn to_nbt(&self) -> Result<Vec<u8>, nbt::Error> {
+        let mut buf = Vec::new();
+        nbt::to_writer(&mut buf, self, None)?;
+        Ok(buf)
+    }
+}
+
+/// Error returned when loading dimension types from data packs fails.
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, #[source] io::Error),
+    #[error("invalid JSON in {0}: {1}")]
+    Json(PathBuf, #[source] serde_json::Error),
+    #[error("invalid biome {0}: {1}")]
+    InvalidBiome(PathBuf, String),
+    #[error("invalid vertical bounds in {0}: min_y {1}, height {2}")]
+    InvalidHeight(PathBuf, i32, i32),
+}
+
+/// The set of known dimension types, keyed by namespaced ID.
+///
+/// Types keep the order in which they were first added,
+/// which determines their numeric IDs in the dimension codec.
+#[derive(Debug, Clone, Default)]
+pub struct DimensionTypes {
+    types: Vec<(String, DimensionType)>,
+}
+
+impl DimensionTypes {
+    /// Creates an empty set of dimension types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the vanilla dimension types.
+    pub fn vanilla() -> Self {
+        let mut types = Self::new();
+        types.insert("minecraft:overworld", DimensionType::overworld());
+        types.insert(
+            "minecraft:overworld_caves",
+            DimensionType::overworld_caves(),
+        );
+        types.insert("minecraft:the_nether", DimensionType::nether());
+        types.insert("minecraft:the_end", DimensionType::end());
+        types
+    }
+
+    /// Adds a dimension type, replacing any existing
+    /// type with the same ID.
+    pub fn insert(&mut self, id: impl Into<String>, dimension_type: DimensionType) {
+        let id = id.into();
+        match self.types.iter_mut().find(|(existing, _)| *existing == id) {
+            Some((_, existing)) => *existing = dimension_type,
+            None => self.types.push((id, dimension_type)),
+        }
+    }
+
+    /// Returns the dimension type with the given ID. IDs without
+    /// a namespace are assumed to be in the `minecraft` namespace.
+    pub fn get(&self, id: &str) -> Option<&DimensionType> {
+        let id = namespaced(id);
+        self.types
+            .iter()
+            .find(|(existing, _)| *existing == id)
+            .map(|(_, dimension_type)| dimension_type)
+    }
+
+    /// Returns the numeric ID of the dimension type with the given ID.
+    pub fn numeric_id(&self, id: &str) -> Option<i32> {
+        let id = namespaced(id);
+        self.types
+            .iter()
+            .position(|(existing, _)| *existing == id)
+            .map(|index| index as i32)
+    }
+
+    /// Iterates over the dimension types and their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DimensionType)> {
+        self.types
+            .iter()
+            .map(|(id, dimension_type)| (id.as_str(), dimension_type))
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Loads the dimension types from all data packs in the given
+    /// directory, usually `<world>/datapacks`. Each type is read from
+    /// `<pack>/data/<namespace>/dimension_type/<name>.json`.
+    ///
+    /// Packs are loaded in alphabetical order, so later packs
+    /// override earlier ones. Zipped packs are not supported.
+    /// A missing `datapacks` directory is not an error.
+    pub fn load_datapacks(&mut self, datapacks: &Path) -> Result<(), LoadError> {
+        for (id, file) in datapack_files(datapacks, "dimension_type")? {
+            let bytes = fs::read(&file).map_err(|e| LoadError::Io(file.clone(), e))?;
+            let dimension_type: DimensionType =
+                serde_json::from_slice(&bytes).map_err(|e| LoadError::Json(file.clone(), e))?;
+            if !dimension_type.has_valid_bounds() {
+                return Err(LoadError::InvalidHeight(
+                    file,
+                    dimension_type.min_y,
+                    dimension_type.height,
+                ));
+            }
+
+            log::debug!("Loaded dimension type {} from {}", id, file.display());
+            self.insert(id, dimension_type);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the namespaced IDs and paths of the JSON files in the
+/// `<pack>/data/<namespace>/<directory>` directories of all
+/// data packs in `datapacks`, in the order packs should be applied.
+fn datapack_files(datapacks: &Path, directory: &str) -> Result<Vec<(String, PathBuf)>, LoadError> {
+    let mut files = Vec::new();
+    if !datapacks.is_dir() {
+        return Ok(files);
+    }
+
+    for pack in sorted_entries(datapacks)? {
+        let data = pack.join("data");
+        if !data.is_dir() {
+            continue;
+        }
+
+        for namespace_dir in sorted_entries(&data)? {
+            let dir = namespace_dir.join(directory);
+            if !dir.is_dir() {
+                continue;
+            }
+            let namespace = file_name(&namespace_dir);
+
+            for file in sorted_entries(&dir)? {
+                if file.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                    continue;
+                }
+
+                let name = file
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default();
+                files.push((format!("{}:{}", namespace, name), file.clone()));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn namespaced(id: &str) -> String {
+    if id.contains(':') {
+        id.to_owned()
+    } else {
+        format!("{}:{}", NAMESPACE, id)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn sorted_entries(directory: &Path) -> Result<Vec<PathBuf>, LoadError> {
+    let mut entries = fs::read_dir(directory)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| LoadError::Io(directory.to_owned(), e))?;
+    entries.sort();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn vanilla_types() {
+        let types = DimensionTypes::vanilla();
+        assert_eq!(types.len(), 4);
+        assert_eq!(types.get("the_nether"), Some(&DimensionType::nether()));
+        assert_eq!(types.numeric_id("minecraft:the_end"), Some(3));
+        assert_eq!(types.get("minecraft:unknown"), None);
+    }
+
+    #[test]
+    fn dimension_type_nbt() {
+        #[derive(Deserialize)]
+        struct Decoded {
+            effects: String,
+            logical_height: i32,
+        }
+
+        let bytes = DimensionType::nether().to_nbt().unwrap();
+        let decoded: Decoded = nbt::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.effects, "minecraft:the_nether");
+        assert_eq!(decoded.logical_height, 128);
+    }
+
+    #[test]
+    fn load_from_datapack() {
+        let datapacks =
+            env::temp_dir().join(format!("feather-dimension-test-{}", std::process::id()));
+        let directory = datapacks.join("pack/data/custom/dimension_type");
+        fs::create_dir_all(&directory).unwrap();
+
+        let mut tall = DimensionType::overworld();
+        tall.height = 384;
+        tall.min_y = -64;
+        tall.coordinate_scale = 2.0;
+        fs::write(
+            directory.join("tall.json"),
+            serde_json::to_vec(&tall).unwrap(),
+        )
+        .unwrap();
+        // Fields added in later versions are optional.
+        fs::write(
+            directory.join("dark.json"),
+            r#"{
+                "piglin_safe": false, "natural": true, "ambient_light": 0.5,
+                "infiniburn": "minecraft:infiniburn_overworld", "respawn_anchor_works": false,
+                "has_skylight": false, "bed_works": true, "effects": "minecraft:overworld",
+                "has_raids": false, "logical_height": 256, "coordinate_scale": 1.0,
+                "ultrawarm": false, "has_ceiling": true
+            }"#,
+        )
+        .unwrap();
+
+        let mut types = DimensionTypes::vanilla();
+        types.load_datapacks(&datapacks).unwrap();
+        fs::remove_dir_all(&datapacks).unwrap();
+
+        assert_eq!(types.get("custom:tall"), Some(&tall));
+        let dark = types.get("custom:dark").unwrap();
+        assert_eq!(dark.height, 256);
+        assert!((dark.ambient_light - 0.5).abs() < f32::EPSILON);
+        assert_eq!(types.len(), 6);
+    }
+
+    #[test]
+    fn invalid_height() {
+        let datapacks =
+            env::temp_dir().join(format!("feather-dimension-height-{}", std::process::id()));
+        let directory = datapacks.join("pack/data/custom/dimension_type");
+        fs::create_dir_all(&directory).unwrap();
+
+        let mut misaligned = DimensionType::overworld();
+        misaligned.min_y = -60;
+        assert!(!misaligned.has_valid_bounds());
+        assert!(DimensionType::overworld().has_valid_bounds());
+        fs::write(
+            directory.join("misaligned.json"),
+            serde_json::to_vec(&misaligned).unwrap(),
+        )
+        .unwrap();
+
+        let result = DimensionTypes::vanilla().load_datapacks(&datapacks);
+        fs::remove_dir_all(&datapacks).unwrap();
+        assert!(matches!(result, Err(LoadError::InvalidHeight(_, -60, 256))));
+    }
+
+    #[test]
+    fn missing_datapacks_directory() {
+        let mut types = DimensionTypes::vanilla();
+        types
+            .load_datapacks(Path::new("/nonexistent/datapacks"))
+            .unwrap();
+        assert_eq!(types.len(), 4);
+    }
+}
@@ -8,8 +8,14 @@ use feather_util::{BlockPosition, Direction};
 use std::collections::BTreeMap;
 use uuid::Uuid;
 
+pub mod model;
+
+pub use model::Metadata;
+
 type OptUuid = Option<Uuid>;
 type OptChat = Option<String>;
+type OptPosition = Option<BlockPosition>;
+type OptBlockId = Option<i32>;
 
 // Meta index constants.
 pub const META_INDEX_ENTITY_BITMASK: u8 = 0;
@@ -21,8 +27,139 @@ pub const META_INDEX_NO_GRAVITY: u8 = 5;
 
 pub const META_INDEX_ITEM_SLOT: u8 = 6;
 
+pub const META_INDEX_LIVING_HAND_STATE: u8 = 6;
+
 pub const META_INDEX_FALLING_BLOCK_SPAWN_POSITION: u8 = 7;
 
+pub const META_INDEX_ARROW_FLAGS: u8 = 6;
+
+pub const META_INDEX_FIREWORK_ROCKET_ITEM: u8 = 6;
+pub const META_INDEX_FIREWORK_ROCKET_BOOSTED_ENTITY: u8 = 7;
+
+pub const META_INDEX_PRIMED_TNT_FUSE: u8 = 6;
+
+pub const META_INDEX_END_CRYSTAL_BEAM_TARGET: u8 = 6;
+pub const META_INDEX_END_CRYSTAL_SHOW_BOTTOM: u8 = 7;
+
+pub const META_INDEX_ITEM_FRAME_ITEM: u8 = 6;
+pub const META_INDEX_ITEM_FRAME_ROTATION: u8 = 7;
+
+pub const META_INDEX_BOAT_TIME_SINCE_HIT: u8 = 6;
+pub const META_INDEX_BOAT_FORWARD_DIRECTION: u8 = 7;
+pub const META_INDEX_BOAT_DAMAGE_TAKEN: u8 = 8;
+pub const META_INDEX_BOAT_TYPE: u8 = 9;
+pub const META_INDEX_BOAT_RIGHT_PADDLE_TURNING: u8 = 10;
+pub const META_INDEX_BOAT_LEFT_PADDLE_TURNING: u8 = 11;
+pub const META_INDEX_BOAT_SPLASH_TIMER: u8 = 12;
+
+pub const META_INDEX_MINECART_SHAKING_POWER: u8 = 6;
+pub const META_INDEX_MINECART_SHAKING_DIRECTION: u8 = 7;
+pub const META_INDEX_MINECART_SHAKING_MULTIPLIER: u8 = 8;
+
+pub const META_INDEX_ARMOR_STAND_BITMASK: u8 = 11;
+pub const META_INDEX_ARMOR_STAND_HEAD_ROTATION: u8 = 12;
+pub const META_INDEX_ARMOR_STAND_BODY_ROTATION: u8 = 13;
+pub const META_INDEX_ARMOR_STAND_LEFT_ARM_ROTATION: u8 = 14;
+pub const META_INDEX_ARMOR_STAND_RIGHT_ARM_ROTATION: u8 = 15;
+pub const META_INDEX_ARMOR_STAND_LEFT_LEG_ROTATION: u8 = 16;
+pub const META_INDEX_ARMOR_STAND_RIGHT_LEG_ROTATION: u8 = 17;
+
+/// The indices of the metadata fields of a kind of entity in the
+/// protocol versions after 1.13.2, 1.16.5 and 1.18.1, by their
+/// 1.13.2 index.
+///
+/// A field has no index in a version which removed it or changed
+/// its type, so that it isn't sent to clients of that version.
+/// The fields of the base entity keep their indices in all versions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MetadataLayout(pub &'static [(u8, [Option<u8>; 2])]);
+
+impl MetadataLayout {
+    /// The layout of an entity with only the base entity's fields.
+    pub const BASE: MetadataLayout = MetadataLayout(&[]);
+
+    /// Returns the indices of a field in the versions after 1.13.2.
+    pub fn later_indices(self, index: u8) -> [Option<u8>; 2] {
+        match self.0.iter().find(|(other, _)| *other == index) {
+            Some((_, later)) => *later,
+            None if index <= META_INDEX_NO_GRAVITY => [Some(index); 2],
+            None => [None; 2],
+        }
+    }
+}
+
+// Layouts of the entities whose metadata is built from the
+// indices above rather than from the model. Since 1.14, the
+// pose follows the base entity's fields, and since 1.17, the
+// ticks an entity has been frozen for. Living entities gained
+// the bed they sleep in (1.14) and their bee stingers (1.15).
+
+pub const BOAT_LAYOUT: MetadataLayout = MetadataLayout(&[
+    (6, [Some(7), Some(8)]),
+    (7, [Some(8), Some(9)]),
+    (8, [Some(9), Some(10)]),
+    (9, [Some(10), Some(11)]),
+    (10, [Some(11), Some(12)]),
+    (11, [Some(12), Some(13)]),
+    (12, [Some(13), Some(14)]),
+]);
+
+pub const MINECART_LAYOUT: MetadataLayout = MetadataLayout(&[
+    (6, [Some(7), Some(8)]),
+    (7, [Some(8), Some(9)]),
+    (8, [Some(9), Some(10)]),
+    (9, [Some(10), Some(11)]),
+    (10, [Some(11), Some(12)]),
+    (11, [Some(12), Some(13)]),
+]);
+
+pub const PRIMED_TNT_LAYOUT: MetadataLayout = MetadataLayout(&[(6, [Some(7), Some(8)])]);
+
+pub const FALLING_BLOCK_LAYOUT: MetadataLayout = MetadataLayout(&[(7, [Some(8), Some(9)])]);
+
+/// The boosted entity became an optional entity ID in 1.14.
+pub const FIREWORK_ROCKET_LAYOUT: MetadataLayout =
+    MetadataLayout(&[(6, [Some(7), Some(8)]), (7, [None, None])]);
+
+pub const END_CRYSTAL_LAYOUT: MetadataLayout =
+    MetadataLayout(&[(6, [Some(7), Some(8)]), (7, [Some(8), Some(9)])]);
+
+pub const ITEM_FRAME_LAYOUT: MetadataLayout =
+    MetadataLayout(&[(6, [Some(7), Some(8)]), (7, [Some(8), Some(9)])]);
+
+/// The shooter was replaced by the piercing level in 1.14.
+pub const ARROW_LAYOUT: MetadataLayout =
+    MetadataLayout(&[(6, [Some(7), Some(8)]), (7, [None, None])]);
+
+pub const ARMOR_STAND_LAYOUT: MetadataLayout = MetadataLayout(&[
+    (6, [Some(7), Some(8)]),
+    (7, [Some(8), Some(9)]),
+    (8, [Some(9), Some(10)]),
+    (9, [Some(10), Some(11)]),
+    (10, [Some(11), Some(12)]),
+    (11, [Some(14), Some(15)]),
+    (12, [Some(15), Some(16)]),
+    (13, [Some(16), Some(17)]),
+    (14, [Some(17), Some(18)]),
+    (15, [Some(18), Some(19)]),
+    (16, [Some(19), Some(20)]),
+    (17, [Some(20), Some(21)]),
+]);
+
+pub const PLAYER_LAYOUT: MetadataLayout = MetadataLayout(&[
+    (6, [Some(7), Some(8)]),
+    (7, [Some(8), Some(9)]),
+    (8, [Some(9), Some(10)]),
+    (9, [Some(10), Some(11)]),
+    (10, [Some(11), Some(12)]),
+    (11, [Some(14), Some(15)]),
+    (12, [Some(15), Some(16)]),
+    (13, [Some(16), Some(17)]),
+    (14, [Some(17), Some(18)]),
+    (15, [Some(18), Some(19)]),
+    (16, [Some(19), Some(20)]),
+]);
+
 bitflags! {
     pub struct EntityBitMask: u8 {
         const ON_FIRE = 0x01;
@@ -35,6 +172,37 @@ bitflags! {
     }
 }
 
+bitflags! {
+    pub struct HandState: u8 {
+        const ACTIVE = 0x01;
+        const OFFHAND = 0x02;
+    }
+}
+
+bitflags! {
+    pub struct ArmorStandBitMask: u8 {
+        const SMALL = 0x01;
+        const HAS_ARMS = 0x04;
+        const NO_BASE_PLATE = 0x08;
+        const MARKER = 0x10;
+    }
+}
+
+/// Rotation around the X, Y and Z axes, in degrees,
+/// such as that of the limbs of an armor stand.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rotation {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Rotation {
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum MetaEntry {
     Byte(i8),
@@ -118,6 +286,12 @@ impl ToMetaEntry for f32 {
     }
 }
 
+impl ToMetaEntry for Rotation {
+    fn to_meta_entry(&self) -> MetaEntry {
+        MetaEntry::Rotation(self.x, self.y, self.z)
+    }
+}
+
 impl ToMetaEntry for OptUuid {
     fn to_meta_entry(&self) -> MetaEntry {
         MetaEntry::OptUuid(*self)
@@ -136,18 +310,39 @@ impl ToMetaEntry for OptChat {
     }
 }
 
+impl ToMetaEntry for OptPosition {
+    fn to_meta_entry(&self) -> MetaEntry {
+        MetaEntry::OptPosition(*self)
+    }
+}
+
+impl ToMetaEntry for OptBlockId {
+    fn to_meta_entry(&self) -> MetaEntry {
+        MetaEntry::OptBlockId(*self)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct EntityMetadata {
     pub values: BTreeMap<u8, MetaEntry>,
+    /// The layout of the entity's fields, used
+    /// to translate them to other protocol versions.
+    pub layout: MetadataLayout,
 }
 
 impl EntityMetadata {
     pub fn new() -> Self {
         Self {
             values: BTreeMap::new(),
+            layout: MetadataLayout::BASE,
         }
     }
 
+    pub fn with_layout(mut self, layout: MetadataLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
     /// Returns an entity metadata with the defaults for an `Entity`.
     pub fn entity_base() -> Self {
         Self::new()
@@ -183,6 +378,10 @@ impl EntityMetadata {
     pub fn iter(&self) -> impl Iterator<Item = (u8, &MetaEntry)> {
         self.values.iter().map(|(key, entry)| (*key, entry))
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
 }
 
 impl Default for EntityMetadata {
@@ -0,0 +1,285 @@
+//! The metadata model of each kind of entity, generated by
+//! `entity_metadata!`.
+//!
+//! Each variant of `Metadata` has the fields of the variant it extends,
+//! followed by its own. Setting a field marks it as dirty, so that
+//! `Metadata::to_raw_metadata` returns only the fields which changed
+//! since it was last called, while `Metadata::to_full_raw_metadata`
+//! returns all of them, e.g. for spawning the entity.
+//!
+//! Indices are those of 1.13.2, each followed by the indices in 1.16.5
+//! and 1.18.1, which the protocol layer uses to translate them to the
+//! version of each client. A field which was removed or changed its
+//! type in a version has no index (`_`) there and isn't sent. A variant
+//! may override the later indices of the fields of the variant it extends.
+//! See https://wiki.vg/index.php?title=Entity_metadata&oldid=14800.
+
+use crate::{EntityMetadata, MetadataLayout, OptBlockId, OptChat, OptPosition, OptUuid};
+use feather_codegen::entity_metadata;
+use feather_inventory::Slot;
+use feather_util::BlockPosition;
+
+entity_metadata! {
+    Metadata,
+    Entity {
+        entity_bitmask: u8 () = 0 [0, 0],
+        air: VarInt (300) = 1 [1, 1],
+        custom_name: OptChat () = 2 [2, 2],
+        custom_name_visible: bool (false) = 3 [3, 3],
+        silent: bool (false) = 4 [4, 4],
+        no_gravity: bool (false) = 5 [5, 5],
+    },
+    Item: Entity {
+        item: Slot () = 6 [7, 8],
+    },
+    Living: Entity {
+        hand_states: u8 () = 6 [7, 8],
+        health: f32 (1.0) = 7 [8, 9],
+        potion_effect_color: VarInt () = 8 [9, 10],
+        potion_effect_ambient: bool (false) = 9 [10, 11],
+        arrows: VarInt () = 10 [11, 12],
+    },
+    Insentient: Living {
+        insentient_bitmask: u8 () = 11 [14, 15],
+    },
+    Ambient: Insentient {},
+    Bat: Ambient {
+        hanging: u8 () = 12 [15, 16],
+    },
+    WaterMob: Insentient {},
+    Squid: WaterMob {},
+    Dolphin: WaterMob {
+        treasure_position: BlockPosition () = 12 [15, 16],
+        can_find_treasure: bool (false) = 13 [16, 17],
+        has_fish: bool (false) = 14 [17, 18],
+    },
+    AbstractFish: WaterMob {
+        from_bucket: bool (false) = 12 [15, 16],
+    },
+    Cod: AbstractFish {},
+    Salmon: AbstractFish {},
+    Pufferfish: AbstractFish {
+        puff_state: VarInt () = 13 [16, 17],
+    },
+    TropicalFish: AbstractFish {
+        tropical_fish_variant: VarInt () = 13 [16, 17],
+    },
+    Ageable: Insentient {
+        baby: bool (false) = 12 [15, 16],
+    },
+    Animal: Ageable {},
+    AbstractHorse: Animal {
+        horse_bitmask: u8 () = 13 [16, 17],
+        owner: OptUuid () = 14 [17, 18],
+    },
+    Horse: AbstractHorse {
+        horse_variant: VarInt () = 15 [18, 19],
+        // Horse armor is equipment since 1.14.
+        armor: VarInt () = 16 [_, _],
+    },
+    ZombieHorse: AbstractHorse {},
+    SkeletonHorse: AbstractHorse {},
+    ChestedHorse: AbstractHorse {
+        has_chest: bool (false) = 15 [18, 19],
+    },
+    Donkey: ChestedHorse {},
+    Mule: ChestedHorse {},
+    Llama: ChestedHorse {
+        strength: VarInt () = 16 [19, 20],
+        carpet_color: VarInt (-1) = 17 [20, 21],
+        llama_variant: VarInt () = 18 [21, 22],
+    },
+    Pig: Animal {
+        has_saddle: bool (false) = 13 [16, 17],
+        boost_time: VarInt () = 14 [17, 18],
+    },
+    Rabbit: Animal {
+        rabbit_type: VarInt () = 13 [16, 17],
+    },
+    Turtle: Animal {
+        home_position: BlockPosition () = 13 [16, 17],
+        has_egg: bool (false) = 14 [17, 18],
+        laying_egg: bool (false) = 15 [18, 19],
+        travel_position: BlockPosition () = 16 [19, 20],
+        going_home: bool (false) = 17 [20, 21],
+        travelling: bool (false) = 18 [21, 22],
+    },
+    PolarBear: Animal {
+        standing_up: bool (false) = 13 [16, 17],
+    },
+    Chicken: Animal {},
+    Cow: Animal {},
+    MushroomCow: Cow {},
+    Sheep: Animal {
+        sheep_bitmask: u8 () = 13 [16, 17],
+    },
+    TameableAnimal: Animal {
+        tameable_bitmask: u8 () = 13 [16, 17],
+        owner: OptUuid () = 14 [17, 18],
+    },
+    // Ocelots can't be tamed since 1.14, when cats were split from them.
+    Ocelot: TameableAnimal {
+        tameable_bitmask = [_, _],
+        owner = [_, _],
+        ocelot_type: VarInt () = 15 [_, _],
+    },
+    // The damage taken by wolves was removed in 1.15.
+    Wolf: TameableAnimal {
+        damage_taken: f32 (8.0) = 15 [_, _],
+        begging: bool (false) = 16 [18, 19],
+        collar_color: VarInt (14) = 17 [19, 20],
+    },
+    Parrot: TameableAnimal {
+        parrot_variant: VarInt () = 15 [18, 19],
+    },
+    // The profession is part of the villager data since 1.14.
+    Villager: Ageable {
+        profession: VarInt () = 13 [_, _],
+    },
+    Golem: Insentient {},
+    IronGolem: Golem {
+        player_created: u8 () = 12 [15, 16],
+    },
+    SnowGolem: Golem {
+        snow_golem_bitmask: u8 (0x10) = 12 [15, 16],
+    },
+    // The facing of shulkers, at index 12, is left to the client's default.
+    Shulker: Golem {
+        attachment_position: OptPosition () = 13 [16, 17],
+        shield_height: u8 () = 14 [17, 18],
+        color: u8 (10) = 15 [18, 19],
+    },
+    Monster: Insentient {},
+    Blaze: Monster {
+        on_fire: u8 () = 12 [15, 16],
+    },
+    Creeper: Monster {
+        state: VarInt (-1) = 12 [15, 16],
+        charged: bool (false) = 13 [16, 17],
+        ignited: bool (false) = 14 [17, 18],
+    },
+    Endermite: Monster {},
+    Giant: Monster {},
+    Silverfish: Monster {},
+    Guardian: Monster {
+        retracting_spikes: bool (false) = 12 [15, 16],
+        target: VarInt () = 13 [16, 17],
+    },
+    ElderGuardian: Guardian {},
+    // Since 1.14, illagers are raiders, which have
+    // whether they are celebrating instead of a bitmask.
+    AbstractIllager: Monster {
+        illager_bitmask: u8 () = 12 [_, _],
+    },
+    VindicationIllager: AbstractIllager {},
+    SpellcasterIllager: AbstractIllager {
+        spell: u8 () = 13 [16, 17],
+    },
+    EvocationIllager: SpellcasterIllager {},
+    IllusionIllager: SpellcasterIllager {},
+    Vex: Monster {
+        vex_bitmask: u8 () = 12 [15, 16],
+    },
+    // Removed in 1.14.
+    AbstractSkeleton: Monster {
+        swinging_arms: bool (false) = 12 [_, _],
+    },
+    Skeleton: AbstractSkeleton {},
+    WitherSkeleton: AbstractSkeleton {},
+    Stray: AbstractSkeleton {},
+    Spider: Monster {
+        climbing: u8 () = 12 [15, 16],
+    },
+    CaveSpider: Spider {},
+    // Witches are raiders since 1.14.
+    Witch: Monster {
+        drinking_potion: bool (false) = 12 [16, 17],
+    },
+    Wither: Monster {
+        center_head_target: VarInt () = 12 [15, 16],
+        left_head_target: VarInt () = 13 [16, 17],
+        right_head_target: VarInt () = 14 [17, 18],
+        invulnerable_time: VarInt () = 15 [18, 19],
+    },
+    Zombie: Monster {
+        zombie_baby: bool (false) = 12 [15, 16],
+        zombie_type: VarInt () = 13 [16, 17],
+        // Removed in 1.14.
+        hands_held_up: bool (false) = 14 [_, _],
+        becoming_drowned: bool (false) = 15 [17, 18],
+    },
+    ZombieVillager: Zombie {
+        converting: bool (false) = 16 [18, 19],
+        zombie_villager_profession: VarInt () = 17 [_, _],
+    },
+    Husk: Zombie {},
+    Drowned: Zombie {},
+    PigZombie: Zombie {},
+    Enderman: Monster {
+        carried_block: OptBlockId () = 12 [15, 16],
+        screaming: bool (false) = 13 [16, 17],
+    },
+    Flying: Insentient {},
+    Ghast: Flying {
+        attacking: bool (false) = 12 [15, 16],
+    },
+    Phantom: Flying {
+        phantom_size: VarInt () = 12 [15, 16],
+    },
+    Slime: Insentient {
+        size: VarInt (1) = 12 [15, 16],
+    },
+    MagmaCube: Slime {},
+    EnderDragon: Insentient {
+        phase: VarInt (10) = 12 [15, 16],
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MetaEntry;
+
+    #[test]
+    fn defaults_and_changes() {
+        let mut meta = Metadata::Llama(Llama::default());
+
+        // Llamas have all fields up to their variant at index 18.
+        let full = meta.to_full_raw_metadata();
+        let indices: Vec<u8> = full.iter().map(|(index, _)| index).collect();
+        assert_eq!(indices, (0..=18).collect::<Vec<u8>>());
+        assert_eq!(full.get(1), Some(MetaEntry::VarInt(300)));
+        assert_eq!(full.get(7), Some(MetaEntry::Float(1.0)));
+        assert_eq!(full.get(17), Some(MetaEntry::VarInt(-1)));
+
+        assert!(meta.to_raw_metadata().is_empty());
+
+        if let Metadata::Llama(llama) = &mut meta {
+            llama.set_health(4.0);
+            llama.set_custom_name(Some(String::from("Spit")));
+        }
+        let changes = meta.to_raw_metadata();
+        assert_eq!(changes.iter().count(), 2);
+        assert_eq!(changes.get(7), Some(MetaEntry::Float(4.0)));
+        assert_eq!(
+            changes.get(2),
+            Some(MetaEntry::OptChat(Some(String::from("Spit"))))
+        );
+
+        assert!(meta.to_raw_metadata().is_empty());
+    }
+
+    #[test]
+    fn later_indices() {
+        assert_eq!(Llama::LAYOUT.later_indices(2), [Some(2), Some(2)]);
+        // Living entities gained the bed and stingers.
+        assert_eq!(Llama::LAYOUT.later_indices(7), [Some(8), Some(9)]);
+        assert_eq!(Llama::LAYOUT.later_indices(18), [Some(21), Some(22)]);
+        // Fields of the extended variant may be overridden.
+        assert_eq!(Wolf::LAYOUT.later_indices(13), [Some(16), Some(17)]);
+        assert_eq!(Ocelot::LAYOUT.later_indices(13), [None, None]);
+
+        let meta = Metadata::Ocelot(Ocelot::default()).to_full_raw_metadata();
+        assert_eq!(meta.layout, Ocelot::LAYOUT);
+    }
+}
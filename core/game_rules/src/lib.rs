@@ -1,6 +1,6 @@
 macro_rules! gamerules {
-    {$($name:ident: $value:ty = $default:literal),*$(,)*} => {
-        #[derive(Debug)]
+    {$($name:ident: $value:ty = $default:literal => $key:literal),*$(,)*} => {
+        #[derive(Debug, Clone)]
         pub struct GameRules {
             $(
                 pub $name: $value
@@ -16,39 +16,90 @@ macro_rules! gamerules {
                 }
             }
         }
+
+        impl GameRules {
+            /// Returns each game rule's vanilla name, e.g. `doDaylightCycle`,
+            /// and its value as a string, as stored in `level.dat`.
+            pub fn to_strings(&self) -> Vec<(&'static str, String)> {
+                vec![
+                    $(
+                        ($key, self.$name.to_string())
+                    ),*
+                ]
+            }
+
+            /// Sets the game rule with the given vanilla name from a string.
+            /// Returns `false` if the game rule is unknown or the value is invalid.
+            pub fn set(&mut self, name: &str, value: &str) -> bool {
+                match name {
+                    $(
+                        $key => match value.parse() {
+                            Ok(value) => {
+                                self.$name = value;
+                                true
+                            }
+                            Err(_) => false,
+                        },
+                    )*
+                    _ => false,
+                }
+            }
+        }
     };
 }
 
 gamerules! {
-    announce_advancements: bool = true,
-    command_block_output: bool = true,
-    disable_elytra_movement_check: bool = false,
-    disable_raids: bool = false,
-    do_daylight_cycle: bool = true,
-    do_entity_drops: bool = true,
-    do_fire_tick: bool = true,
-    do_insomnia: bool = true,
-    do_immediate_respawn: bool = false,
-    do_limited_crafting: bool = false,
-    do_mob_loot: bool = true,
-    do_mob_spawning: bool = true,
-    do_patrol_spawning: bool = true,
-    do_tile_drops: bool = true,
-    do_trader_spawning: bool = true,
-    do_weather_cycle: bool = true,
-    drowning_damage: bool = true,
-    fall_damage: bool = true,
-    fire_damage: bool = true,
-    keep_inventory: bool = false,
-    log_admin_commands: bool = true,
-    max_command_chain_length: u32 = 65536,
-    max_entity_cramming: u32 = 24,
-    mob_griefing: bool = true,
-    natural_regeneration: bool = true,
-    random_tick_speed: u32 = 3,
-    reduced_debug_info: bool = false,
-    send_command_feedback: bool = true,
-    show_death_messages: bool = true,
-    spawn_radius: u32 = 10,
-    spectators_generate_chunks: bool = true,
+    announce_advancements: bool = true => "announceAdvancements",
+    command_block_output: bool = true => "commandBlockOutput",
+    disable_elytra_movement_check: bool = false => "disableElytraMovementCheck",
+    disable_raids: bool = false => "disableRaids",
+    do_daylight_cycle: bool = true => "doDaylightCycle",
+    do_entity_drops: bool = true => "doEntityDrops",
+    do_fire_tick: bool = true => "doFireTick",
+    do_insomnia: bool = true => "doInsomnia",
+    do_immediate_respawn: bool = false => "doImmediateRespawn",
+    do_limited_crafting: bool = false => "doLimitedCrafting",
+    do_mob_loot: bool = true => "doMobLoot",
+    do_mob_spawning: bool = true => "doMobSpawning",
+    do_patrol_spawning: bool = true => "doPatrolSpawning",
+    do_tile_drops: bool = true => "doTileDrops",
+    do_trader_spawning: bool = true => "doTraderSpawning",
+    do_weather_cycle: bool = true => "doWeatherCycle",
+    drowning_damage: bool = true => "drowningDamage",
+    fall_damage: bool = true => "fallDamage",
+    fire_damage: bool = true => "fireDamage",
+    keep_inventory: bool = false => "keepInventory",
+    log_admin_commands: bool = true => "logAdminCommands",
+    max_command_chain_length: u32 = 65536 => "maxCommandChainLength",
+    max_entity_cramming: u32 = 24 => "maxEntityCramming",
+    mob_griefing: bool = true => "mobGriefing",
+    natural_regeneration: bool = true => "naturalRegeneration",
+    players_sleeping_percentage: u32 = 100 => "playersSleepingPercentage",
+    random_tick_speed: u32 = 3 => "randomTickSpeed",
+    reduced_debug_info: bool = false => "reducedDebugInfo",
+    send_command_feedback: bool = true => "sendCommandFeedback",
+    show_death_messages: bool = true => "showDeathMessages",
+    spawn_radius: u32 = 10 => "spawnRadius",
+    spectators_generate_chunks: bool = true => "spectatorsGenerateChunks",
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_round_trip() {
+        let mut rules = GameRules::default();
+        assert!(rules.set("doDaylightCycle", "false"));
+        assert!(rules.set("randomTickSpeed", "10"));
+        assert!(!rules.set("randomTickSpeed", "fast"));
+        assert!(!rules.set("notAGameRule", "true"));
+
+        let mut copy = GameRules::default();
+        for (name, value) in rules.to_strings() {
+            assert!(copy.set(name, &value));
+        }
+        assert!(!copy.do_daylight_cycle);
+        assert_eq!(copy.random_tick_speed, 10);
+    }
 }
@@ -65,6 +65,37 @@ pub enum Area {
     ///
     /// Note that this is not the chestplate slot; use `Torso` instead.
     Chest,
+
+    /// The item being smelted in a furnace. (1 slot total)
+    FurnaceIngredient,
+    /// A furnace's fuel. (1 slot total)
+    FurnaceFuel,
+    /// The result of smelting in a furnace. (1 slot total)
+    FurnaceOutput,
+
+    /// The bottles being brewed in a brewing stand. (3 slots total)
+    BrewingBottle,
+    /// The item brewed into the bottles of a brewing stand. (1 slot total)
+    BrewingIngredient,
+    /// A brewing stand's fuel. (1 slot total)
+    BrewingFuel,
+
+    /// The item being worked on in an anvil, followed by the
+    /// item or material combined with it. (2 slots total)
+    AnvilInput,
+    /// The result of working on an item in an anvil. (1 slot total)
+    AnvilOutput,
+
+    /// The items paid for a trade with a merchant. (2 slots total)
+    MerchantInput,
+    /// The result of a trade with a merchant. (1 slot total)
+    MerchantOutput,
+
+    /// Hopper storage. (5 slots total)
+    Hopper,
+
+    /// The item paid to select the effects of a beacon. (1 slot total)
+    BeaconPayment,
 }
 
 /// Index into a slot.
@@ -151,13 +182,31 @@ impl Inventory {
         Self { slots }
     }
 
+    /// Creates an inventory holding the equipment of an
+    /// entity other than a player, such as an armor stand.
+    /// Contains a single hotbar slot for the main hand,
+    /// armor and offhand slots.
+    pub fn equipment() -> Self {
+        let slots = btreemap! {
+            Area::Hotbar => empty(1),
+
+            Area::Head => empty(1),
+            Area::Torso => empty(1),
+            Area::Legs => empty(1),
+            Area::Feet => empty(1),
+            Area::Offhand => empty(1),
+        };
+
+        Self { slots }
+    }
+
     /// Creates an inventory for a crafting table.
     /// Contains `CraftingInput` and `CraftingOutput`
     /// areas.
     pub fn crafting_table() -> Self {
         let slots = btreemap! {
             Area::CraftingInput => empty(9),
-            Area::CraftingOutput => empty(9),
+            Area::CraftingOutput => empty(1),
         };
 
         Self { slots }
@@ -173,6 +222,74 @@ impl Inventory {
         Self { slots }
     }
 
+    /// Creates an inventory for a furnace.
+    /// Contains the `FurnaceIngredient`, `FurnaceFuel`
+    /// and `FurnaceOutput` areas.
+    pub fn furnace() -> Self {
+        let slots = btreemap! {
+            Area::FurnaceIngredient => empty(1),
+            Area::FurnaceFuel => empty(1),
+            Area::FurnaceOutput => empty(1),
+        };
+
+        Self { slots }
+    }
+
+    /// Creates an inventory for a brewing stand.
+    /// Contains the `BrewingBottle`, `BrewingIngredient`
+    /// and `BrewingFuel` areas.
+    pub fn brewing_stand() -> Self {
+        let slots = btreemap! {
+            Area::BrewingBottle => empty(3),
+            Area::BrewingIngredient => empty(1),
+            Area::BrewingFuel => empty(1),
+        };
+
+        Self { slots }
+    }
+
+    /// Creates an inventory for an anvil.
+    /// Contains the `AnvilInput` and `AnvilOutput` areas.
+    pub fn anvil() -> Self {
+        let slots = btreemap! {
+            Area::AnvilInput => empty(2),
+            Area::AnvilOutput => empty(1),
+        };
+
+        Self { slots }
+    }
+
+    /// Creates an inventory for trading with a merchant.
+    /// Contains the `MerchantInput` and `MerchantOutput` areas.
+    pub fn merchant() -> Self {
+        let slots = btreemap! {
+            Area::MerchantInput => empty(2),
+            Area::MerchantOutput => empty(1),
+        };
+
+        Self { slots }
+    }
+
+    /// Creates an inventory for a hopper.
+    /// Contains a single `Hopper` area with 5 slots.
+    pub fn hopper() -> Self {
+        let slots = btreemap! {
+            Area::Hopper => empty(5),
+        };
+
+        Self { slots }
+    }
+
+    /// Creates an inventory for a beacon.
+    /// Contains a single `BeaconPayment` slot.
+    pub fn beacon() -> Self {
+        let slots = btreemap! {
+            Area::BeaconPayment => empty(1),
+        };
+
+        Self { slots }
+    }
+
     /// Returns the item at the given
     /// index inside some area.
     pub fn item_at(&self, area: Area, index: usize) -> Result<Slot, Error> {
@@ -300,6 +417,46 @@ impl Inventory {
         (affected_slots, item.amount)
     }
 
+    /// Attempts to insert the given item into an area
+    /// of the inventory, filling stacks of the same item
+    /// before empty slots.
+    ///
+    /// Returns the affected slots and the number of remaining
+    /// items which were not added to the inventory.
+    pub fn insert_item(
+        &self,
+        area: Area,
+        mut item: ItemStack,
+    ) -> Result<(SmallVec<[SlotIndex; 2]>, u8), Error> {
+        let mut affected_slots = SmallVec::new();
+        let size = self.slots(area)?.len();
+
+        for slot in 0..size {
+            if item.amount == 0 {
+                break;
+            }
+            if let Some(slot_item) = self.item_at(area, slot)? {
+                if slot_item.eq_ignore_amount(item) && slot_item.amount < item.ty.stack_size() as u8
+                {
+                    let index = SlotIndex { area, slot };
+                    self.add_to_stack(&mut item, slot_item, index, &mut affected_slots);
+                }
+            }
+        }
+
+        for slot in 0..size {
+            if item.amount == 0 {
+                break;
+            }
+            if self.item_at(area, slot)?.is_none() {
+                let index = SlotIndex { area, slot };
+                self.add_to_stack(&mut item, item.of_amount(0), index, &mut affected_slots);
+            }
+        }
+
+        Ok((affected_slots, item.amount))
+    }
+
     /// Adds an item to a stack.
     fn add_to_stack<A: Array<Item = SlotIndex>>(
         &self,
@@ -133,6 +133,60 @@ impl Window {
         }
     }
 
+    /// Creates a new `Window` for an opened furnace.
+    pub fn furnace(player: Entity, furnace: Entity) -> Self {
+        Self {
+            protocol_to_slot: furnace_to_slot,
+            slot_to_protocol: furnace_from_slot,
+            inventories: smallvec![player, furnace],
+        }
+    }
+
+    /// Creates a new `Window` for an opened brewing stand.
+    pub fn brewing_stand(player: Entity, brewing_stand: Entity) -> Self {
+        Self {
+            protocol_to_slot: brewing_stand_to_slot,
+            slot_to_protocol: brewing_stand_from_slot,
+            inventories: smallvec![player, brewing_stand],
+        }
+    }
+
+    /// Creates a new `Window` for an opened anvil.
+    pub fn anvil(player: Entity, anvil: Entity) -> Self {
+        Self {
+            protocol_to_slot: anvil_to_slot,
+            slot_to_protocol: anvil_from_slot,
+            inventories: smallvec![player, anvil],
+        }
+    }
+
+    /// Creates a new `Window` for trading with a merchant.
+    pub fn merchant(player: Entity, merchant: Entity) -> Self {
+        Self {
+            protocol_to_slot: merchant_to_slot,
+            slot_to_protocol: merchant_from_slot,
+            inventories: smallvec![player, merchant],
+        }
+    }
+
+    /// Creates a new `Window` for an opened hopper.
+    pub fn hopper(player: Entity, hopper: Entity) -> Self {
+        Self {
+            protocol_to_slot: hopper_to_slot,
+            slot_to_protocol: hopper_from_slot,
+            inventories: smallvec![player, hopper],
+        }
+    }
+
+    /// Creates a new `Window` for an opened beacon.
+    pub fn beacon(player: Entity, beacon: Entity) -> Self {
+        Self {
+            protocol_to_slot: beacon_to_slot,
+            slot_to_protocol: beacon_from_slot,
+            inventories: smallvec![player, beacon],
+        }
+    }
+
     /// Returns the entities other than the player
     /// which this window wraps over. For example,
     /// for `Window::chest(),` this will return the chest.
@@ -321,6 +375,132 @@ fn large_chest_from_slot(slot: Index) -> usize {
     }
 }
 
+fn furnace_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0 => index(1, Area::FurnaceIngredient, 0),
+        1 => index(1, Area::FurnaceFuel, 0),
+        2 => index(1, Area::FurnaceOutput, 0),
+        3..=29 => index(0, Area::Main, x - 3),
+        30..=38 => index(0, Area::Hotbar, x - 30),
+        _ => return None,
+    })
+}
+
+fn furnace_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        FurnaceIngredient => 0,
+        FurnaceFuel => 1,
+        FurnaceOutput => 2,
+        Main => slot.slot + 3,
+        Hotbar => slot.slot + 30,
+        x => panic!("unreachable area {:?} for furnace window", x),
+    }
+}
+
+fn brewing_stand_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0..=2 => index(1, Area::BrewingBottle, x),
+        3 => index(1, Area::BrewingIngredient, 0),
+        4 => index(1, Area::BrewingFuel, 0),
+        5..=31 => index(0, Area::Main, x - 5),
+        32..=40 => index(0, Area::Hotbar, x - 32),
+        _ => return None,
+    })
+}
+
+fn brewing_stand_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        BrewingBottle => slot.slot,
+        BrewingIngredient => 3,
+        BrewingFuel => 4,
+        Main => slot.slot + 5,
+        Hotbar => slot.slot + 32,
+        x => panic!("unreachable area {:?} for brewing stand window", x),
+    }
+}
+
+fn anvil_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0..=1 => index(1, Area::AnvilInput, x),
+        2 => index(1, Area::AnvilOutput, 0),
+        3..=29 => index(0, Area::Main, x - 3),
+        30..=38 => index(0, Area::Hotbar, x - 30),
+        _ => return None,
+    })
+}
+
+fn anvil_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        AnvilInput => slot.slot,
+        AnvilOutput => 2,
+        Main => slot.slot + 3,
+        Hotbar => slot.slot + 30,
+        x => panic!("unreachable area {:?} for anvil window", x),
+    }
+}
+
+fn merchant_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0..=1 => index(1, Area::MerchantInput, x),
+        2 => index(1, Area::MerchantOutput, 0),
+        3..=29 => index(0, Area::Main, x - 3),
+        30..=38 => index(0, Area::Hotbar, x - 30),
+        _ => return None,
+    })
+}
+
+fn merchant_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        MerchantInput => slot.slot,
+        MerchantOutput => 2,
+        Main => slot.slot + 3,
+        Hotbar => slot.slot + 30,
+        x => panic!("unreachable area {:?} for merchant window", x),
+    }
+}
+
+fn hopper_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0..=4 => index(1, Area::Hopper, x),
+        5..=31 => index(0, Area::Main, x - 5),
+        32..=40 => index(0, Area::Hotbar, x - 32),
+        _ => return None,
+    })
+}
+
+fn hopper_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        Hopper => slot.slot,
+        Main => slot.slot + 5,
+        Hotbar => slot.slot + 32,
+        x => panic!("unreachable area {:?} for hopper window", x),
+    }
+}
+
+fn beacon_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0 => index(1, Area::BeaconPayment, 0),
+        1..=27 => index(0, Area::Main, x - 1),
+        28..=36 => index(0, Area::Hotbar, x - 28),
+        _ => return None,
+    })
+}
+
+fn beacon_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        BeaconPayment => 0,
+        Main => slot.slot + 1,
+        Hotbar => slot.slot + 28,
+        x => panic!("unreachable area {:?} for beacon window", x),
+    }
+}
+
 fn index(inventory: usize, area: Area, slot: usize) -> Index {
     Index {
         inventory,
@@ -347,4 +527,45 @@ mod tests {
     fn large_chest_roundtrip() {
         (0..89).for_each(|i| assert_eq!(i, large_chest_from_slot(large_chest_to_slot(i).unwrap())));
     }
+
+    #[test]
+    fn furnace_roundtrip() {
+        (0..=38).for_each(|i| assert_eq!(i, furnace_from_slot(furnace_to_slot(i).unwrap())));
+        assert_eq!(furnace_to_slot(39), None);
+    }
+
+    #[test]
+    fn brewing_stand_roundtrip() {
+        (0..=40).for_each(|i| {
+            assert_eq!(
+                i,
+                brewing_stand_from_slot(brewing_stand_to_slot(i).unwrap())
+            )
+        });
+        assert_eq!(brewing_stand_to_slot(41), None);
+    }
+
+    #[test]
+    fn anvil_roundtrip() {
+        (0..=38).for_each(|i| assert_eq!(i, anvil_from_slot(anvil_to_slot(i).unwrap())));
+        assert_eq!(anvil_to_slot(39), None);
+    }
+
+    #[test]
+    fn merchant_roundtrip() {
+        (0..=38).for_each(|i| assert_eq!(i, merchant_from_slot(merchant_to_slot(i).unwrap())));
+        assert_eq!(merchant_to_slot(39), None);
+    }
+
+    #[test]
+    fn hopper_roundtrip() {
+        (0..=40).for_each(|i| assert_eq!(i, hopper_from_slot(hopper_to_slot(i).unwrap())));
+        assert_eq!(hopper_to_slot(41), None);
+    }
+
+    #[test]
+    fn beacon_roundtrip() {
+        (0..=36).for_each(|i| assert_eq!(i, beacon_from_slot(beacon_to_slot(i).unwrap())));
+        assert_eq!(beacon_to_slot(37), None);
+    }
 }
@@ -58,12 +58,12 @@ pub fn item_to_block(item: Item) -> Option<BlockId> {
         Item::JungleWood => Some(BlockId::jungle_wood()),
         Item::AcaciaWood => Some(BlockId::acacia_wood()),
         Item::DarkOakWood => Some(BlockId::dark_oak_wood()),
-        Item::OakLeaves => Some(BlockId::oak_leaves()),
-        Item::SpruceLeaves => Some(BlockId::spruce_leaves()),
-        Item::BirchLeaves => Some(BlockId::birch_leaves()),
-        Item::JungleLeaves => Some(BlockId::jungle_leaves()),
-        Item::AcaciaLeaves => Some(BlockId::acacia_leaves()),
-        Item::DarkOakLeaves => Some(BlockId::dark_oak_leaves()),
+        Item::OakLeaves => Some(BlockId::oak_leaves().with_persistent(true)),
+        Item::SpruceLeaves => Some(BlockId::spruce_leaves().with_persistent(true)),
+        Item::BirchLeaves => Some(BlockId::birch_leaves().with_persistent(true)),
+        Item::JungleLeaves => Some(BlockId::jungle_leaves().with_persistent(true)),
+        Item::AcaciaLeaves => Some(BlockId::acacia_leaves().with_persistent(true)),
+        Item::DarkOakLeaves => Some(BlockId::dark_oak_leaves().with_persistent(true)),
         Item::Sponge => Some(BlockId::sponge()),
         Item::WetSponge => Some(BlockId::wet_sponge()),
         Item::Glass => Some(BlockId::glass()),
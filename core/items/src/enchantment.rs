@@ -0,0 +1,409 @@
+//! Enchantments and the enchantments on items.
+
+use crate::Item;
+use feather_definitions::Tool;
+
+/// An enchantment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Enchantment {
+    Protection,
+    FireProtection,
+    FeatherFalling,
+    BlastProtection,
+    ProjectileProtection,
+    Respiration,
+    AquaAffinity,
+    Thorns,
+    DepthStrider,
+    FrostWalker,
+    BindingCurse,
+    Sharpness,
+    Smite,
+    BaneOfArthropods,
+    Knockback,
+    FireAspect,
+    Looting,
+    Sweeping,
+    Efficiency,
+    SilkTouch,
+    Unbreaking,
+    Fortune,
+    Power,
+    Punch,
+    Flame,
+    Infinity,
+    LuckOfTheSea,
+    Lure,
+    Loyalty,
+    Impaling,
+    Riptide,
+    Channeling,
+    Mending,
+    VanishingCurse,
+}
+
+/// How rare an enchantment is, which determines how likely
+/// it is to be picked when enchanting and how much it costs
+/// to combine in an anvil.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnchantmentRarity {
+    Common,
+    Uncommon,
+    Rare,
+    VeryRare,
+}
+
+impl Enchantment {
+    /// Returns the namespaced identifier of the enchantment,
+    /// e.g. `minecraft:silk_touch`.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            Enchantment::Protection => "minecraft:protection",
+            Enchantment::FireProtection => "minecraft:fire_protection",
+            Enchantment::FeatherFalling => "minecraft:feather_falling",
+            Enchantment::BlastProtection => "minecraft:blast_protection",
+            Enchantment::ProjectileProtection => "minecraft:projectile_protection",
+            Enchantment::Respiration => "minecraft:respiration",
+            Enchantment::AquaAffinity => "minecraft:aqua_affinity",
+            Enchantment::Thorns => "minecraft:thorns",
+            Enchantment::DepthStrider => "minecraft:depth_strider",
+            Enchantment::FrostWalker => "minecraft:frost_walker",
+            Enchantment::BindingCurse => "minecraft:binding_curse",
+            Enchantment::Sharpness => "minecraft:sharpness",
+            Enchantment::Smite => "minecraft:smite",
+            Enchantment::BaneOfArthropods => "minecraft:bane_of_arthropods",
+            Enchantment::Knockback => "minecraft:knockback",
+            Enchantment::FireAspect => "minecraft:fire_aspect",
+            Enchantment::Looting => "minecraft:looting",
+            Enchantment::Sweeping => "minecraft:sweeping",
+            Enchantment::Efficiency => "minecraft:efficiency",
+            Enchantment::SilkTouch => "minecraft:silk_touch",
+            Enchantment::Unbreaking => "minecraft:unbreaking",
+            Enchantment::Fortune => "minecraft:fortune",
+            Enchantment::Power => "minecraft:power",
+            Enchantment::Punch => "minecraft:punch",
+            Enchantment::Flame => "minecraft:flame",
+            Enchantment::Infinity => "minecraft:infinity",
+            Enchantment::LuckOfTheSea => "minecraft:luck_of_the_sea",
+            Enchantment::Lure => "minecraft:lure",
+            Enchantment::Loyalty => "minecraft:loyalty",
+            Enchantment::Impaling => "minecraft:impaling",
+            Enchantment::Riptide => "minecraft:riptide",
+            Enchantment::Channeling => "minecraft:channeling",
+            Enchantment::Mending => "minecraft:mending",
+            Enchantment::VanishingCurse => "minecraft:vanishing_curse",
+        }
+    }
+
+    /// Returns the enchantment with the given identifier. The
+    /// `minecraft:` namespace may be omitted.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        let name = identifier.trim_start_matches("minecraft:");
+        Self::values()
+            .iter()
+            .copied()
+            .find(|enchantment| enchantment.identifier()["minecraft:".len()..] == *name)
+    }
+
+    /// Returns all enchantments.
+    pub fn values() -> &'static [Enchantment] {
+        &[
+            Enchantment::Protection,
+            Enchantment::FireProtection,
+            Enchantment::FeatherFalling,
+            Enchantment::BlastProtection,
+            Enchantment::ProjectileProtection,
+            Enchantment::Respiration,
+            Enchantment::AquaAffinity,
+            Enchantment::Thorns,
+            Enchantment::DepthStrider,
+            Enchantment::FrostWalker,
+            Enchantment::BindingCurse,
+            Enchantment::Sharpness,
+            Enchantment::Smite,
+            Enchantment::BaneOfArthropods,
+            Enchantment::Knockback,
+            Enchantment::FireAspect,
+            Enchantment::Looting,
+            Enchantment::Sweeping,
+            Enchantment::Efficiency,
+            Enchantment::SilkTouch,
+            Enchantment::Unbreaking,
+            Enchantment::Fortune,
+            Enchantment::Power,
+            Enchantment::Punch,
+            Enchantment::Flame,
+            Enchantment::Infinity,
+            Enchantment::LuckOfTheSea,
+            Enchantment::Lure,
+            Enchantment::Loyalty,
+            Enchantment::Impaling,
+            Enchantment::Riptide,
+            Enchantment::Channeling,
+            Enchantment::Mending,
+            Enchantment::VanishingCurse,
+        ]
+    }
+
+    /// Returns the highest level of the enchantment
+    /// obtainable in survival.
+    pub fn max_level(self) -> i16 {
+        match self {
+            Enchantment::Protection
+            | Enchantment::FireProtection
+            | Enchantment::FeatherFalling
+            | Enchantment::BlastProtection
+            | Enchantment::ProjectileProtection => 4,
+            Enchantment::Sharpness
+            | Enchantment::Smite
+            | Enchantment::BaneOfArthropods
+            | Enchantment::Efficiency
+            | Enchantment::Power
+            | Enchantment::Impaling => 5,
+            Enchantment::Respiration
+            | Enchantment::Thorns
+            | Enchantment::DepthStrider
+            | Enchantment::Looting
+            | Enchantment::Sweeping
+            | Enchantment::Unbreaking
+            | Enchantment::Fortune
+            | Enchantment::LuckOfTheSea
+            | Enchantment::Lure
+            | Enchantment::Loyalty
+            | Enchantment::Riptide => 3,
+            Enchantment::FrostWalker
+            | Enchantment::Knockback
+            | Enchantment::FireAspect
+            | Enchantment::Punch => 2,
+            Enchantment::AquaAffinity
+            | Enchantment::BindingCurse
+            | Enchantment::SilkTouch
+            | Enchantment::Flame
+            | Enchantment::Infinity
+            | Enchantment::Channeling
+            | Enchantment::Mending
+            | Enchantment::VanishingCurse => 1,
+        }
+    }
+
+    /// Returns the rarity of the enchantment.
+    pub fn rarity(self) -> EnchantmentRarity {
+        match self {
+            Enchantment::Protection
+            | Enchantment::Sharpness
+            | Enchantment::Efficiency
+            | Enchantment::Power => EnchantmentRarity::Common,
+            Enchantment::FireProtection
+            | Enchantment::FeatherFalling
+            | Enchantment::ProjectileProtection
+            | Enchantment::Smite
+            | Enchantment::BaneOfArthropods
+            | Enchantment::Knockback
+            | Enchantment::Unbreaking
+            | Enchantment::Loyalty => EnchantmentRarity::Uncommon,
+            Enchantment::BlastProtection
+            | Enchantment::Respiration
+            | Enchantment::AquaAffinity
+            | Enchantment::DepthStrider
+            | Enchantment::FrostWalker
+            | Enchantment::FireAspect
+            | Enchantment::Looting
+            | Enchantment::Sweeping
+            | Enchantment::Fortune
+            | Enchantment::Punch
+            | Enchantment::Flame
+            | Enchantment::LuckOfTheSea
+            | Enchantment::Lure
+            | Enchantment::Impaling
+            | Enchantment::Riptide
+            | Enchantment::Mending => EnchantmentRarity::Rare,
+            Enchantment::Thorns
+            | Enchantment::BindingCurse
+            | Enchantment::SilkTouch
+            | Enchantment::Infinity
+            | Enchantment::Channeling
+            | Enchantment::VanishingCurse => EnchantmentRarity::VeryRare,
+        }
+    }
+
+    /// Returns whether the enchantment is a curse.
+    pub fn is_curse(self) -> bool {
+        matches!(
+            self,
+            Enchantment::BindingCurse | Enchantment::VanishingCurse
+        )
+    }
+
+    /// Returns whether the enchantment can be on
+    /// the same item as another enchantment.
+    pub fn is_compatible_with(self, other: Enchantment) -> bool {
+        self != other && self.allows(other) && other.allows(self)
+    }
+
+    fn allows(self, other: Enchantment) -> bool {
+        use Enchantment::*;
+        match self {
+            Protection | FireProtection | BlastProtection | ProjectileProtection => !matches!(
+                other,
+                Protection | FireProtection | BlastProtection | ProjectileProtection
+            ),
+            Sharpness | Smite | BaneOfArthropods => {
+                !matches!(other, Sharpness | Smite | BaneOfArthropods)
+            }
+            DepthStrider => other != FrostWalker,
+            SilkTouch => other != Fortune && other != Looting && other != LuckOfTheSea,
+            Infinity => other != Mending,
+            Riptide => other != Loyalty && other != Channeling,
+            _ => true,
+        }
+    }
+
+    /// Returns whether the enchantment can be applied to an item,
+    /// e.g. in an enchanting table or by combining it with an
+    /// enchanted book in an anvil.
+    pub fn can_apply_to(self, item: Item) -> bool {
+        use Enchantment::*;
+        let armor = armor_piece(item);
+        match self {
+            Protection | FireProtection | BlastProtection | ProjectileProtection | Thorns => {
+                armor.is_some()
+            }
+            FeatherFalling | DepthStrider | FrostWalker => armor == Some(ArmorPiece::Boots),
+            Respiration | AquaAffinity => armor == Some(ArmorPiece::Helmet),
+            BindingCurse => is_wearable(item),
+            Sharpness | Smite | BaneOfArthropods => {
+                item.tool() == Some(Tool::Sword) || item.tool() == Some(Tool::Axe)
+            }
+            Knockback | FireAspect | Looting | Sweeping => item.tool() == Some(Tool::Sword),
+            Efficiency => is_digger(item) || item == Item::Shears,
+            SilkTouch | Fortune => is_digger(item),
+            Unbreaking | Mending => item.durability().is_some(),
+            Power | Punch | Flame | Infinity => item == Item::Bow,
+            LuckOfTheSea | Lure => item == Item::FishingRod,
+            Loyalty | Impaling | Riptide | Channeling => item == Item::Trident,
+            VanishingCurse => Self::values().iter().any(|enchantment| {
+                *enchantment != VanishingCurse && enchantment.can_apply_to(item)
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArmorPiece {
+    Helmet,
+    Chestplate,
+    Leggings,
+    Boots,
+}
+
+fn armor_piece(item: Item) -> Option<ArmorPiece> {
+    let identifier = item.identifier();
+    if identifier.ends_with("_helmet") {
+        Some(ArmorPiece::Helmet)
+    } else if identifier.ends_with("_chestplate") {
+        Some(ArmorPiece::Chestplate)
+    } else if identifier.ends_with("_leggings") {
+        Some(ArmorPiece::Leggings)
+    } else if identifier.ends_with("_boots") {
+        Some(ArmorPiece::Boots)
+    } else {
+        None
+    }
+}
+
+/// Returns whether an item can be worn on the head or body.
+fn is_wearable(item: Item) -> bool {
+    match item {
+        Item::Elytra
+        | Item::CarvedPumpkin
+        | Item::SkeletonSkull
+        | Item::WitherSkeletonSkull
+        | Item::PlayerHead
+        | Item::ZombieHead
+        | Item::CreeperHead
+        | Item::DragonHead => true,
+        _ => armor_piece(item).is_some(),
+    }
+}
+
+/// Returns whether an item is a tool used to mine blocks
+/// which mining enchantments apply to. Hoes aren't.
+fn is_digger(item: Item) -> bool {
+    matches!(
+        item.tool(),
+        Some(Tool::Pickaxe) | Some(Tool::Shovel) | Some(Tool::Axe)
+    )
+}
+
+/// The enchantments on an item and their levels,
+/// in the order they were added.
+///
+/// Enchantments are stored inline so that `ItemStack` stays
+/// `Copy`. An item can have at most `Enchantments::CAPACITY`
+/// enchantments, which is more than survival allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Enchantments {
+    entries: [Option<(Enchantment, i16)>; Enchantments::CAPACITY],
+}
+
+impl Enchantments {
+    pub const CAPACITY: usize = 16;
+
+    /// Creates an empty list of enchantments.
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; Enchantments::CAPACITY],
+        }
+    }
+
+    /// Returns the level of an enchantment,
+    /// or `None` if it isn't in the list.
+    pub fn level(&self, enchantment: Enchantment) -> Option<i16> {
+        self.iter()
+            .find(|(e, _)| *e == enchantment)
+            .map(|(_, level)| level)
+    }
+
+    /// Sets the level of an enchantment, adding it at the end if it
+    /// isn't in the list yet. Returns `false` if the list is full.
+    pub fn set(&mut self, enchantment: Enchantment, level: i16) -> bool {
+        for entry in self.entries.iter_mut() {
+            match entry {
+                Some((e, l)) if *e == enchantment => {
+                    *l = level;
+                    return true;
+                }
+                Some(_) => (),
+                None => {
+                    *entry = Some((enchantment, level));
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Removes an enchantment, returning its level.
+    pub fn remove(&mut self, enchantment: Enchantment) -> Option<i16> {
+        let index = self.iter().position(|(e, _)| e == enchantment)?;
+        let level = self.entries[index].map(|(_, level)| level);
+        for i in index..Self::CAPACITY - 1 {
+            self.entries[i] = self.entries[i + 1];
+        }
+        self.entries[Self::CAPACITY - 1] = None;
+        level
+    }
+
+    /// Returns an iterator over the enchantments and their levels.
+    pub fn iter(&self) -> impl Iterator<Item = (Enchantment, i16)> + '_ {
+        self.entries.iter().filter_map(|entry| *entry)
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries[0].is_none()
+    }
+}
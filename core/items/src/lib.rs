@@ -1,6 +1,14 @@
 #![forbid(unsafe_code, warnings)]
 
-pub use feather_definitions::Item;
+pub use feather_definitions::{Item, Tool, ToolMaterial};
+
+mod enchantment;
+mod name;
+mod potion;
+
+pub use enchantment::{Enchantment, EnchantmentRarity, Enchantments};
+pub use name::ItemName;
+pub use potion::Potion;
 
 /// Represents an item stack.
 ///
@@ -13,7 +21,18 @@ pub struct ItemStack {
     pub amount: u8,
     /// Amount of damage taken on tools/equipment (how much durability expended).
     pub damage: Option<i32>,
-    // TODO enchantments, more
+    /// ID of the map drawn on a filled map.
+    pub map: Option<i32>,
+    /// The potion in a potion item.
+    pub potion: Option<Potion>,
+    /// Enchantments on the item, or stored in an enchanted book.
+    pub enchantments: Enchantments,
+    /// Experience levels added to the cost of working
+    /// on the item in an anvil.
+    pub repair_cost: Option<i32>,
+    /// The custom name of the item.
+    pub name: Option<ItemName>,
+    // TODO more
 }
 
 impl Default for ItemStack {
@@ -28,6 +47,11 @@ impl ItemStack {
             ty,
             amount,
             damage: None,
+            map: None,
+            potion: None,
+            enchantments: Enchantments::new(),
+            repair_cost: None,
+            name: None,
         }
     }
 
@@ -53,4 +77,68 @@ mod tests {
         assert_eq!(item.vanilla_id(), 0);
         assert_eq!(Item::from_vanilla_id(0), Some(item));
     }
+
+    #[test]
+    fn potion_identifiers() {
+        let potion = Potion::StrongTurtleMaster;
+        assert_eq!(potion.identifier(), "minecraft:strong_turtle_master");
+        assert_eq!(Potion::from_identifier(potion.identifier()), Some(potion));
+        assert_eq!(Potion::from_identifier("minecraft:stone"), None);
+    }
+
+    #[test]
+    fn enchantments() {
+        let mut enchantments = Enchantments::new();
+        assert!(enchantments.set(Enchantment::Sharpness, 3));
+        assert!(enchantments.set(Enchantment::Unbreaking, 1));
+        assert!(enchantments.set(Enchantment::Sharpness, 4));
+        assert_eq!(
+            enchantments.iter().collect::<Vec<_>>(),
+            vec![(Enchantment::Sharpness, 4), (Enchantment::Unbreaking, 1)]
+        );
+
+        assert_eq!(enchantments.remove(Enchantment::Sharpness), Some(4));
+        assert_eq!(enchantments.level(Enchantment::Sharpness), None);
+        assert_eq!(enchantments.level(Enchantment::Unbreaking), Some(1));
+        assert_eq!(enchantments.len(), 1);
+
+        for enchantment in Enchantment::values().iter().take(Enchantments::CAPACITY) {
+            enchantments.set(*enchantment, 1);
+        }
+        assert!(!enchantments.set(Enchantment::VanishingCurse, 1));
+    }
+
+    #[test]
+    fn enchantment_compatibility() {
+        assert!(!Enchantment::Sharpness.is_compatible_with(Enchantment::Smite));
+        assert!(!Enchantment::Fortune.is_compatible_with(Enchantment::SilkTouch));
+        assert!(Enchantment::FeatherFalling.is_compatible_with(Enchantment::Protection));
+        assert!(!Enchantment::Protection.is_compatible_with(Enchantment::BlastProtection));
+        assert!(!Enchantment::Mending.is_compatible_with(Enchantment::Infinity));
+
+        assert!(Enchantment::Sharpness.can_apply_to(Item::IronAxe));
+        assert!(!Enchantment::Looting.can_apply_to(Item::IronAxe));
+        assert!(Enchantment::Efficiency.can_apply_to(Item::Shears));
+        assert!(Enchantment::FeatherFalling.can_apply_to(Item::LeatherBoots));
+        assert!(!Enchantment::FeatherFalling.can_apply_to(Item::LeatherHelmet));
+        assert!(Enchantment::VanishingCurse.can_apply_to(Item::CarvedPumpkin));
+        assert!(!Enchantment::Unbreaking.can_apply_to(Item::Stone));
+        assert_eq!(
+            Enchantment::from_identifier("minecraft:luck_of_the_sea"),
+            Some(Enchantment::LuckOfTheSea)
+        );
+    }
+
+    #[test]
+    fn item_names() {
+        assert_eq!(ItemName::new("Excalibur").as_str(), "Excalibur");
+
+        let long = "é".repeat(ItemName::CAPACITY);
+        let name = ItemName::new(&long);
+        assert_eq!(name.as_str().len(), ItemName::CAPACITY);
+        assert!(long.starts_with(name.as_str()));
+
+        let odd = format!("a{}", long);
+        assert_eq!(ItemName::new(&odd).as_str().len(), ItemName::CAPACITY - 1);
+    }
 }
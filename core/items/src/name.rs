@@ -0,0 +1,67 @@
+//! Custom names of items.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A custom name given to an item, e.g. in an anvil.
+///
+/// Names are plain text stored inline so that `ItemStack`
+/// stays `Copy`. Longer names are truncated to
+/// `ItemName::CAPACITY` bytes, which fits the 35 characters
+/// allowed by anvils in most scripts.
+#[derive(Clone, Copy)]
+pub struct ItemName {
+    len: u8,
+    bytes: [u8; ItemName::CAPACITY],
+}
+
+impl ItemName {
+    pub const CAPACITY: usize = 128;
+
+    /// Creates a name, truncating it to
+    /// `ItemName::CAPACITY` bytes.
+    pub fn new(name: &str) -> Self {
+        let mut len = name.len().min(Self::CAPACITY);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut bytes = [0; Self::CAPACITY];
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Self {
+            len: len as u8,
+            bytes,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize])
+            .expect("item name is truncated at a char boundary")
+    }
+}
+
+impl PartialEq for ItemName {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ItemName {}
+
+impl Hash for ItemName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for ItemName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ItemName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,149 @@
+//! The potions in potion items.
+
+/// The potion in a potion, splash potion or lingering potion,
+/// or on a tipped arrow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Potion {
+    Empty,
+    Water,
+    Mundane,
+    Thick,
+    Awkward,
+    NightVision,
+    LongNightVision,
+    Invisibility,
+    LongInvisibility,
+    Leaping,
+    LongLeaping,
+    StrongLeaping,
+    FireResistance,
+    LongFireResistance,
+    Swiftness,
+    LongSwiftness,
+    StrongSwiftness,
+    Slowness,
+    LongSlowness,
+    TurtleMaster,
+    LongTurtleMaster,
+    StrongTurtleMaster,
+    WaterBreathing,
+    LongWaterBreathing,
+    Healing,
+    StrongHealing,
+    Harming,
+    StrongHarming,
+    Poison,
+    LongPoison,
+    StrongPoison,
+    Regeneration,
+    LongRegeneration,
+    StrongRegeneration,
+    Strength,
+    LongStrength,
+    StrongStrength,
+    Weakness,
+    LongWeakness,
+    Luck,
+    SlowFalling,
+    LongSlowFalling,
+}
+
+impl Potion {
+    /// Returns the namespaced identifier of the potion,
+    /// e.g. `minecraft:long_swiftness`.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            Potion::Empty => "minecraft:empty",
+            Potion::Water => "minecraft:water",
+            Potion::Mundane => "minecraft:mundane",
+            Potion::Thick => "minecraft:thick",
+            Potion::Awkward => "minecraft:awkward",
+            Potion::NightVision => "minecraft:night_vision",
+            Potion::LongNightVision => "minecraft:long_night_vision",
+            Potion::Invisibility => "minecraft:invisibility",
+            Potion::LongInvisibility => "minecraft:long_invisibility",
+            Potion::Leaping => "minecraft:leaping",
+            Potion::LongLeaping => "minecraft:long_leaping",
+            Potion::StrongLeaping => "minecraft:strong_leaping",
+            Potion::FireResistance => "minecraft:fire_resistance",
+            Potion::LongFireResistance => "minecraft:long_fire_resistance",
+            Potion::Swiftness => "minecraft:swiftness",
+            Potion::LongSwiftness => "minecraft:long_swiftness",
+            Potion::StrongSwiftness => "minecraft:strong_swiftness",
+            Potion::Slowness => "minecraft:slowness",
+            Potion::LongSlowness => "minecraft:long_slowness",
+            Potion::TurtleMaster => "minecraft:turtle_master",
+            Potion::LongTurtleMaster => "minecraft:long_turtle_master",
+            Potion::StrongTurtleMaster => "minecraft:strong_turtle_master",
+            Potion::WaterBreathing => "minecraft:water_breathing",
+            Potion::LongWaterBreathing => "minecraft:long_water_breathing",
+            Potion::Healing => "minecraft:healing",
+            Potion::StrongHealing => "minecraft:strong_healing",
+            Potion::Harming => "minecraft:harming",
+            Potion::StrongHarming => "minecraft:strong_harming",
+            Potion::Poison => "minecraft:poison",
+            Potion::LongPoison => "minecraft:long_poison",
+            Potion::StrongPoison => "minecraft:strong_poison",
+            Potion::Regeneration => "minecraft:regeneration",
+            Potion::LongRegeneration => "minecraft:long_regeneration",
+            Potion::StrongRegeneration => "minecraft:strong_regeneration",
+            Potion::Strength => "minecraft:strength",
+            Potion::LongStrength => "minecraft:long_strength",
+            Potion::StrongStrength => "minecraft:strong_strength",
+            Potion::Weakness => "minecraft:weakness",
+            Potion::LongWeakness => "minecraft:long_weakness",
+            Potion::Luck => "minecraft:luck",
+            Potion::SlowFalling => "minecraft:slow_falling",
+            Potion::LongSlowFalling => "minecraft:long_slow_falling",
+        }
+    }
+
+    /// Returns the potion with the given namespaced identifier.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        Some(match identifier {
+            "minecraft:empty" => Potion::Empty,
+            "minecraft:water" => Potion::Water,
+            "minecraft:mundane" => Potion::Mundane,
+            "minecraft:thick" => Potion::Thick,
+            "minecraft:awkward" => Potion::Awkward,
+            "minecraft:night_vision" => Potion::NightVision,
+            "minecraft:long_night_vision" => Potion::LongNightVision,
+            "minecraft:invisibility" => Potion::Invisibility,
+            "minecraft:long_invisibility" => Potion::LongInvisibility,
+            "minecraft:leaping" => Potion::Leaping,
+            "minecraft:long_leaping" => Potion::LongLeaping,
+            "minecraft:strong_leaping" => Potion::StrongLeaping,
+            "minecraft:fire_resistance" => Potion::FireResistance,
+            "minecraft:long_fire_resistance" => Potion::LongFireResistance,
+            "minecraft:swiftness" => Potion::Swiftness,
+            "minecraft:long_swiftness" => Potion::LongSwiftness,
+            "minecraft:strong_swiftness" => Potion::StrongSwiftness,
+            "minecraft:slowness" => Potion::Slowness,
+            "minecraft:long_slowness" => Potion::LongSlowness,
+            "minecraft:turtle_master" => Potion::TurtleMaster,
+            "minecraft:long_turtle_master" => Potion::LongTurtleMaster,
+            "minecraft:strong_turtle_master" => Potion::StrongTurtleMaster,
+            "minecraft:water_breathing" => Potion::WaterBreathing,
+            "minecraft:long_water_breathing" => Potion::LongWaterBreathing,
+            "minecraft:healing" => Potion::Healing,
+            "minecraft:strong_healing" => Potion::StrongHealing,
+            "minecraft:harming" => Potion::Harming,
+            "minecraft:strong_harming" => Potion::StrongHarming,
+            "minecraft:poison" => Potion::Poison,
+            "minecraft:long_poison" => Potion::LongPoison,
+            "minecraft:strong_poison" => Potion::StrongPoison,
+            "minecraft:regeneration" => Potion::Regeneration,
+            "minecraft:long_regeneration" => Potion::LongRegeneration,
+            "minecraft:strong_regeneration" => Potion::StrongRegeneration,
+            "minecraft:strength" => Potion::Strength,
+            "minecraft:long_strength" => Potion::LongStrength,
+            "minecraft:strong_strength" => Potion::StrongStrength,
+            "minecraft:weakness" => Potion::Weakness,
+            "minecraft:long_weakness" => Potion::LongWeakness,
+            "minecraft:luck" => Potion::Luck,
+            "minecraft:slow_falling" => Potion::SlowFalling,
+            "minecraft:long_slow_falling" => Potion::LongSlowFalling,
+            _ => return None,
+        })
+    }
+}
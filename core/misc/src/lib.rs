@@ -87,3 +87,75 @@ impl Default for ParticleData {
         }
     }
 }
+
+impl ParticleData {
+    /// Returns the namespaced identifier of this particle,
+    /// by which it is looked up in each protocol version.
+    pub fn identifier(&self) -> &'static str {
+        use ParticleData::*;
+        match self {
+            AmbientEntityEffect => "minecraft:ambient_entity_effect",
+            AngryVillager => "minecraft:angry_villager",
+            Barrier => "minecraft:barrier",
+            Block(_) => "minecraft:block",
+            Bubble => "minecraft:bubble",
+            Cloud => "minecraft:cloud",
+            Crit => "minecraft:crit",
+            DamageIndicator => "minecraft:damage_indicator",
+            DragonBreath => "minecraft:dragon_breath",
+            DrippingLava => "minecraft:dripping_lava",
+            FallingLava => "minecraft:falling_lava",
+            LandingLava => "minecraft:landing_lava",
+            DrippingWater => "minecraft:dripping_water",
+            FallingWater => "minecraft:falling_water",
+            Dust { .. } => "minecraft:dust",
+            Effect => "minecraft:effect",
+            ElderGuardian => "minecraft:elder_guardian",
+            EnchantedHit => "minecraft:enchanted_hit",
+            Enchant => "minecraft:enchant",
+            EndRod => "minecraft:end_rod",
+            EntityEffect => "minecraft:entity_effect",
+            ExplosionEmitter => "minecraft:explosion_emitter",
+            Explosion => "minecraft:explosion",
+            FallingDust(_) => "minecraft:falling_dust",
+            Firework => "minecraft:firework",
+            Fishing => "minecraft:fishing",
+            Flame => "minecraft:flame",
+            Flash => "minecraft:flash",
+            HappyVillager => "minecraft:happy_villager",
+            Composter => "minecraft:composter",
+            Heart => "minecraft:heart",
+            InstantEffect => "minecraft:instant_effect",
+            Item(_) => "minecraft:item",
+            ItemSlime => "minecraft:item_slime",
+            ItemSnowball => "minecraft:item_snowball",
+            LargeSmoke => "minecraft:large_smoke",
+            Lava => "minecraft:lava",
+            Mycelium => "minecraft:mycelium",
+            Note => "minecraft:note",
+            Poof => "minecraft:poof",
+            Portal => "minecraft:portal",
+            Rain => "minecraft:rain",
+            Smoke => "minecraft:smoke",
+            Sneeze => "minecraft:sneeze",
+            Spit => "minecraft:spit",
+            SquidInk => "minecraft:squid_ink",
+            SweepAttack => "minecraft:sweep_attack",
+            TotemOfUndying => "minecraft:totem_of_undying",
+            Underwater => "minecraft:underwater",
+            Splash => "minecraft:splash",
+            Witch => "minecraft:witch",
+            BublePop => "minecraft:bubble_pop",
+            CurrentDown => "minecraft:current_down",
+            BubbleColumnUp => "minecraft:bubble_column_up",
+            Nautilus => "minecraft:nautilus",
+            Dolphin => "minecraft:dolphin",
+            CampfireCosySmoke => "minecraft:campfire_cosy_smoke",
+            CampfireSignalSmoke => "minecraft:campfire_signal_smoke",
+            DrippingHoney => "minecraft:dripping_honey",
+            FallingHoney => "minecraft:falling_honey",
+            LandingHoney => "minecraft:landing_honey",
+            FallingNectar => "minecraft:falling_nectar",
+        }
+    }
+}
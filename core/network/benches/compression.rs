@@ -0,0 +1,99 @@
+#[macro_use]
+extern crate criterion;
+
+use bytes::BytesMut;
+use criterion::{BenchmarkId, Criterion, Throughput};
+use feather_blocks::BlockId;
+use feather_chunk::Chunk;
+use feather_network::packets::{ChunkData, EntityLook};
+use feather_network::{MinecraftCodec, Packet, PacketDirection, PacketStage};
+use feather_util::ChunkPosition;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use tokio_util::codec::Encoder;
+
+// Measures the cost of compressing packets against the bytes it saves.
+// The size of each packet is printed before it is benchmarked.
+
+/// Compression settings to compare: a zlib level, or `None` for no compression.
+const LEVELS: [Option<u32>; 4] = [None, Some(1), Some(6), Some(9)];
+
+/// Returns a chunk resembling generated terrain: stone with
+/// scattered ores up to Y 60, then dirt and a grass surface.
+fn terrain_chunk() -> Chunk {
+    let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+    let mut seed = 0x2545_f491_u32;
+    for x in 0..16 {
+        for z in 0..16 {
+            chunk.set_block_at(x, 0, z, BlockId::bedrock());
+            for y in 1..64 {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let block = match (y, seed >> 24) {
+                    (60..=62, _) => BlockId::dirt(),
+                    (63, _) => BlockId::grass_block(),
+                    (_, 0..=2) => BlockId::coal_ore(),
+                    (_, 3) => BlockId::iron_ore(),
+                    (_, 4..=5) => BlockId::gravel(),
+                    _ => BlockId::stone(),
+                };
+                chunk.set_block_at(x, y, z, block);
+            }
+        }
+    }
+    chunk
+}
+
+fn codec(level: Option<u32>) -> MinecraftCodec {
+    let mut codec = MinecraftCodec::new(PacketDirection::Clientbound);
+    codec.set_stage(PacketStage::Play);
+    if let Some(level) = level {
+        codec.enable_compression(256, level);
+    }
+    codec
+}
+
+/// Benchmarks encoding packets created by `packet` at each compression level.
+fn bench_packet<P: Packet>(c: &mut Criterion, name: &str, packet: impl Fn() -> P) {
+    let mut group = c.benchmark_group(name);
+    for &level in &LEVELS {
+        let mut codec = codec(level);
+        let mut buf = BytesMut::new();
+        codec.encode(Box::new(packet()), &mut buf).unwrap();
+        let label = match level {
+            Some(level) => format!("level_{}", level),
+            None => String::from("uncompressed"),
+        };
+        println!("{}/{}: {} bytes", name, label, buf.len());
+
+        group.throughput(Throughput::Bytes(buf.len() as u64));
+        group.bench_function(BenchmarkId::from_parameter(label), |b| {
+            b.iter(|| {
+                buf.clear();
+                codec.encode(Box::new(packet()), &mut buf).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+fn encode_chunk(c: &mut Criterion) {
+    feather_blocks::init();
+    let chunk = Arc::new(RwLock::new(terrain_chunk()));
+    bench_packet(c, "encode_chunk", || ChunkData {
+        chunk: Arc::clone(&chunk),
+        section_mask: None,
+    });
+}
+
+fn encode_small_packet(c: &mut Criterion) {
+    // Below the threshold, compression only adds a byte.
+    bench_packet(c, "encode_small_packet", || EntityLook {
+        entity_id: 1,
+        yaw: 64,
+        pitch: 0,
+        on_ground: true,
+    });
+}
+
+criterion_group!(benches, encode_chunk, encode_small_packet);
+criterion_main!(benches);
@@ -0,0 +1,560 @@
+//! Generates the mappings from 1.13.2 data to the data of later
+//! versions needed to speak their protocols: block, item, entity
+//! type, menu, particle and block entity type IDs, and the vanilla tags, which clients since
+//! 1.16.5 require on join.
+//!
+//! The mappings are dumped into `${OUT_DIR}/mappings_<version>.json`
+//! for inclusion in `feather-network`.
+
+use anyhow::Context;
+use feather_items::Item;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::{env, fs};
+use walkdir::WalkDir;
+
+/// Blocks renamed since 1.13.2, by their old name. Names
+/// missing in a version are looked up by their old name.
+const RENAMED_BLOCKS: &[(&str, &str)] = &[
+    ("minecraft:sign", "minecraft:oak_sign"),
+    ("minecraft:wall_sign", "minecraft:oak_wall_sign"),
+    ("minecraft:stone_slab", "minecraft:smooth_stone_slab"),
+    // Renamed in 1.17.
+    ("minecraft:grass_path", "minecraft:dirt_path"),
+];
+
+/// Items renamed since 1.13.2, by their old name.
+const RENAMED_ITEMS: &[(&str, &str)] = &[
+    ("minecraft:sign", "minecraft:oak_sign"),
+    ("minecraft:grass_path", "minecraft:dirt_path"),
+    ("minecraft:stone_slab", "minecraft:smooth_stone_slab"),
+    ("minecraft:rose_red", "minecraft:red_dye"),
+    ("minecraft:dandelion_yellow", "minecraft:yellow_dye"),
+    ("minecraft:cactus_green", "minecraft:green_dye"),
+    (
+        "minecraft:zombie_pigman_spawn_egg",
+        "minecraft:zombified_piglin_spawn_egg",
+    ),
+];
+
+/// Entity types renamed since 1.13.2, by their old name.
+const RENAMED_ENTITY_TYPES: &[(&str, &str)] =
+    &[("minecraft:zombie_pigman", "minecraft:zombified_piglin")];
+
+/// The 1.13.2 entity types, indexed by ID. The 1.13.2
+/// reports don't include the registries, so they are listed here.
+const ENTITY_TYPES_1_13: &[&str] = &[
+    "area_effect_cloud",
+    "armor_stand",
+    "arrow",
+    "bat",
+    "blaze",
+    "boat",
+    "cave_spider",
+    "chicken",
+    "cod",
+    "cow",
+    "creeper",
+    "donkey",
+    "dolphin",
+    "dragon_fireball",
+    "drowned",
+    "elder_guardian",
+    "end_crystal",
+    "ender_dragon",
+    "enderman",
+    "endermite",
+    "evoker_fangs",
+    "evoker",
+    "experience_orb",
+    "eye_of_ender",
+    "falling_block",
+    "firework_rocket",
+    "ghast",
+    "giant",
+    "guardian",
+    "horse",
+    "husk",
+    "illusioner",
+    "item",
+    "item_frame",
+    "fireball",
+    "leash_knot",
+    "llama",
+    "llama_spit",
+    "magma_cube",
+    "minecart",
+    "chest_minecart",
+    "command_block_minecart",
+    "furnace_minecart",
+    "hopper_minecart",
+    "spawner_minecart",
+    "tnt_minecart",
+    "mule",
+    "mooshroom",
+    "ocelot",
+    "painting",
+    "parrot",
+    "pig",
+    "pufferfish",
+    "zombie_pigman",
+    "polar_bear",
+    "tnt",
+    "rabbit",
+    "salmon",
+    "sheep",
+    "shulker",
+    "shulker_bullet",
+    "silverfish",
+    "skeleton",
+    "skeleton_horse",
+    "slime",
+    "small_fireball",
+    "snow_golem",
+    "snowball",
+    "spectral_arrow",
+    "spider",
+    "squid",
+    "stray",
+    "tropical_fish",
+    "turtle",
+    "egg",
+    "ender_pearl",
+    "experience_bottle",
+    "potion",
+    "vex",
+    "villager",
+    "iron_golem",
+    "vindicator",
+    "witch",
+    "wither",
+    "wither_skeleton",
+    "wither_skull",
+    "wolf",
+    "zombie",
+    "zombie_horse",
+    "zombie_villager",
+    "phantom",
+    "lightning_bolt",
+    "player",
+    "fishing_bobber",
+    "trident",
+];
+
+/// The 1.13.2 object types used by the Spawn Object packet,
+/// which 1.14 replaced with entity types.
+const OBJECT_TYPES_1_13: &[(i8, &str)] = &[
+    (1, "boat"),
+    (2, "item"),
+    (3, "area_effect_cloud"),
+    (10, "minecart"),
+    (50, "tnt"),
+    (51, "end_crystal"),
+    (60, "arrow"),
+    (61, "snowball"),
+    (62, "egg"),
+    (63, "fireball"),
+    (64, "small_fireball"),
+    (65, "ender_pearl"),
+    (66, "wither_skull"),
+    (67, "shulker_bullet"),
+    (68, "llama_spit"),
+    (70, "falling_block"),
+    (71, "item_frame"),
+    (72, "eye_of_ender"),
+    (73, "potion"),
+    (75, "experience_bottle"),
+    (76, "firework_rocket"),
+    (77, "leash_knot"),
+    (78, "armor_stand"),
+    (79, "evoker_fangs"),
+    (90, "fishing_bobber"),
+    (91, "spectral_arrow"),
+    (93, "dragon_fireball"),
+    (94, "trident"),
+];
+
+/// The tag directories sent in the Tags packet,
+/// along with the registry their entries are in.
+const TAG_KINDS_1_16: &[(&str, &str)] = &[
+    ("blocks", "minecraft:block"),
+    ("items", "minecraft:item"),
+    ("fluids", "minecraft:fluid"),
+    ("entity_types", "minecraft:entity_type"),
+];
+
+/// 1.17 added game event tags.
+const TAG_KINDS_1_18: &[(&str, &str)] = &[
+    ("blocks", "minecraft:block"),
+    ("items", "minecraft:item"),
+    ("fluids", "minecraft:fluid"),
+    ("entity_types", "minecraft:entity_type"),
+    ("game_events", "minecraft:game_event"),
+];
+
+/// A version to generate mappings for.
+struct Target {
+    /// Name of the directory containing the version's data.
+    dir: &'static str,
+    /// Name of the mappings dump in `OUT_DIR`.
+    dump: &'static str,
+    tag_kinds: &'static [(&'static str, &'static str)],
+}
+
+const TARGETS: &[Target] = &[
+    Target {
+        dir: "minecraft-1.16.5",
+        dump: "mappings_1_16_5.json",
+        tag_kinds: TAG_KINDS_1_16,
+    },
+    Target {
+        dir: "minecraft-1.18.1",
+        dump: "mappings_1_18_1.json",
+        tag_kinds: TAG_KINDS_1_18,
+    },
+];
+
+#[derive(Deserialize)]
+struct BlockReport {
+    states: Vec<BlockState>,
+}
+
+#[derive(Deserialize)]
+struct BlockState {
+    id: u16,
+    #[serde(default)]
+    default: bool,
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Registry {
+    entries: HashMap<String, RegistryEntry>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntry {
+    protocol_id: i32,
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    values: Vec<TagValue>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagValue {
+    Id(String),
+    Entry { id: String },
+}
+
+#[derive(Serialize)]
+struct Mappings {
+    /// New block state IDs, indexed by 1.13.2 block state ID.
+    block_ids: Vec<u16>,
+    /// New block IDs, indexed by 1.13.2 block ID.
+    block_kinds: Vec<i32>,
+    /// New item IDs, indexed by 1.13.2 item ID.
+    items: Vec<i32>,
+    /// New entity type IDs, indexed by 1.13.2 entity type ID.
+    entity_types: Vec<Option<i32>>,
+    /// New entity type IDs, by 1.13.2 object type.
+    object_types: BTreeMap<i8, i32>,
+    /// New menu (window type) IDs, by name.
+    menus: BTreeMap<String, i32>,
+    /// New particle type IDs, by name.
+    particles: BTreeMap<String, i32>,
+    /// New block entity type IDs, by name.
+    block_entity_types: BTreeMap<String, i32>,
+    /// The registries of each kind of tag.
+    tag_registries: Vec<String>,
+    /// Tags by kind, then name.
+    tags: Vec<BTreeMap<String, Vec<i32>>>,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        panic!("{:?}", e);
+    }
+
+    println!(
+        "cargo:rerun-if-changed={}",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/build.rs")
+    );
+}
+
+fn run() -> anyhow::Result<()> {
+    let path = feather_data::minecraft::PATH;
+    let old_blocks: HashMap<String, BlockReport> =
+        read_json(format!("{}/minecraft/generated/reports/blocks.json", path))?;
+
+    for target in TARGETS {
+        let dir = format!("{}/{}", path, target.dir);
+        let mappings = generate(&old_blocks, &dir, target)
+            .with_context(|| format!("failed to generate mappings for {}", target.dir))?;
+
+        let dump_path = format!("{}/{}", env::var("OUT_DIR")?, target.dump);
+        let mut dump = File::create(&dump_path)?;
+        dump.write_all(&serde_json::to_vec(&mappings)?)?;
+    }
+
+    Ok(())
+}
+
+fn generate(
+    old_blocks: &HashMap<String, BlockReport>,
+    dir: &str,
+    target: &Target,
+) -> anyhow::Result<Mappings> {
+    let new_blocks: HashMap<String, BlockReport> =
+        read_json(format!("{}/generated/reports/blocks.json", dir))?;
+    let registries: HashMap<String, Registry> =
+        read_json(format!("{}/generated/reports/registries.json", dir))?;
+
+    let registry = |name: &str| {
+        registries
+            .get(name)
+            .with_context(|| format!("missing registry `{}`", name))
+    };
+    let entity_types = registry("minecraft:entity_type")?;
+
+    let mappings = Mappings {
+        block_ids: map_block_ids(old_blocks, &new_blocks),
+        block_kinds: map_block_kinds(old_blocks, &new_blocks),
+        items: map_items(registry("minecraft:item")?),
+        entity_types: ENTITY_TYPES_1_13
+            .iter()
+            .map(|name| entity_type_id(entity_types, name))
+            .collect(),
+        object_types: OBJECT_TYPES_1_13
+            .iter()
+            .filter_map(|(object, name)| Some((*object, entity_type_id(entity_types, name)?)))
+            .collect(),
+        menus: registry("minecraft:menu")?
+            .entries
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.protocol_id))
+            .collect(),
+        particles: registry("minecraft:particle_type")?
+            .entries
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.protocol_id))
+            .collect(),
+        block_entity_types: registry("minecraft:block_entity_type")?
+            .entries
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.protocol_id))
+            .collect(),
+        tag_registries: target
+            .tag_kinds
+            .iter()
+            .map(|(_, name)| (*name).to_owned())
+            .collect(),
+        tags: target
+            .tag_kinds
+            .iter()
+            .map(|(tags_dir, name)| {
+                load_tags(
+                    format!("{}/data/minecraft/tags/{}", dir, tags_dir),
+                    registry(*name)?,
+                )
+            })
+            .collect::<anyhow::Result<_>>()?,
+    };
+
+    Ok(mappings)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: String) -> anyhow::Result<T> {
+    let bytes = fs::read(&path).with_context(|| format!("failed to read `{}`", path))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("failed to parse `{}`", path))
+}
+
+/// Maps each 1.13.2 block state to the new state of the same
+/// block with the most matching properties. Blocks which no
+/// longer exist are mapped to air.
+fn map_block_ids(
+    old_blocks: &HashMap<String, BlockReport>,
+    new_blocks: &HashMap<String, BlockReport>,
+) -> Vec<u16> {
+    let highest_id = old_blocks
+        .values()
+        .flat_map(|block| block.states.iter().map(|state| state.id))
+        .max()
+        .unwrap_or(0);
+    let mut ids = vec![0; highest_id as usize + 1];
+
+    for (name, block) in old_blocks {
+        let new_block = match lookup(new_blocks, RENAMED_BLOCKS, name) {
+            Some(block) => block,
+            None => continue,
+        };
+
+        for state in &block.states {
+            let best = new_block
+                .states
+                .iter()
+                .max_by_key(|new_state| {
+                    let matching = state
+                        .properties
+                        .iter()
+                        .filter(|(property, value)| {
+                            new_state
+                                .properties
+                                .get(*property)
+                                .map_or(false, |new_value| property_matches(value, new_value))
+                        })
+                        .count();
+                    (matching, new_state.default)
+                })
+                .expect("block has no states");
+            ids[state.id as usize] = best.id;
+        }
+    }
+
+    ids
+}
+
+/// Maps each 1.13.2 block to the new block with the same name.
+///
+/// Block IDs aren't in the reports, but are assigned in
+/// the same order as the IDs of the blocks' states.
+fn map_block_kinds(
+    old_blocks: &HashMap<String, BlockReport>,
+    new_blocks: &HashMap<String, BlockReport>,
+) -> Vec<i32> {
+    let new_ids = block_kind_ids(new_blocks);
+    let mut old_names = old_blocks.iter().collect::<Vec<_>>();
+    old_names.sort_unstable_by_key(|(_, block)| first_state_id(block));
+
+    old_names
+        .into_iter()
+        .map(|(name, _)| lookup(&new_ids, RENAMED_BLOCKS, name).copied().unwrap_or(0))
+        .collect()
+}
+
+fn block_kind_ids(blocks: &HashMap<String, BlockReport>) -> HashMap<String, i32> {
+    let mut names = blocks.iter().collect::<Vec<_>>();
+    names.sort_unstable_by_key(|(_, block)| first_state_id(block));
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(id, (name, _))| (name.clone(), id as i32))
+        .collect()
+}
+
+fn first_state_id(block: &BlockReport) -> u16 {
+    block
+        .states
+        .iter()
+        .map(|state| state.id)
+        .min()
+        .unwrap_or(u16::MAX)
+}
+
+/// Maps each 1.13.2 item to the new item with the same
+/// name. Items which no longer exist are mapped to air.
+fn map_items(registry: &Registry) -> Vec<i32> {
+    (0..)
+        .map(Item::from_vanilla_id)
+        .take_while(Option::is_some)
+        .flatten()
+        .map(|item| {
+            lookup(&registry.entries, RENAMED_ITEMS, item.identifier())
+                .map_or(0, |entry| entry.protocol_id)
+        })
+        .collect()
+}
+
+fn entity_type_id(registry: &Registry, name: &str) -> Option<i32> {
+    let name = format!("minecraft:{}", name);
+    lookup(&registry.entries, RENAMED_ENTITY_TYPES, &name).map(|entry| entry.protocol_id)
+}
+
+/// Looks up something by its 1.13.2 name, using its new name if
+/// it has been renamed and the new name exists in the version.
+fn lookup<'a, T>(
+    map: &'a HashMap<String, T>,
+    renames: &[(&'static str, &'static str)],
+    name: &str,
+) -> Option<&'a T> {
+    renames
+        .iter()
+        .find(|(old, _)| *old == name)
+        .and_then(|(_, new)| map.get(*new))
+        .or_else(|| map.get(name))
+}
+
+fn property_matches(old: &str, new: &str) -> bool {
+    // Wall sides changed from booleans to heights in 1.16.
+    match (old, new) {
+        ("true", "low") | ("false", "none") => true,
+        _ => old == new,
+    }
+}
+
+/// Loads the tags in a directory, resolving references to
+/// other tags and mapping entries to their protocol IDs.
+fn load_tags(dir: String, registry: &Registry) -> anyhow::Result<BTreeMap<String, Vec<i32>>> {
+    let mut raw = HashMap::new();
+    for entry in WalkDir::new(&dir) {
+        let entry = entry.context("entry access failed")?;
+        if entry.metadata()?.is_dir() {
+            continue;
+        }
+
+        let relative_path = entry
+            .path()
+            .strip_prefix(&dir)
+            .with_context(|| format!("failed to strip prefix for `{}`", entry.path().display()))?
+            .to_str()
+            .context("path contains invalid UTF-8")?
+            .replace("\\", "/");
+        let name = format!("minecraft:{}", relative_path.trim_end_matches(".json"));
+        let tag: Tag = read_json(entry.path().display().to_string())?;
+        raw.insert(name, tag);
+    }
+
+    let mut tags = BTreeMap::new();
+    for name in raw.keys() {
+        let mut entries = Vec::new();
+        resolve_tag(name, &raw, registry, &mut entries, 0)?;
+        entries.sort_unstable();
+        entries.dedup();
+        tags.insert(name.clone(), entries);
+    }
+    Ok(tags)
+}
+
+fn resolve_tag(
+    name: &str,
+    raw: &HashMap<String, Tag>,
+    registry: &Registry,
+    entries: &mut Vec<i32>,
+    depth: usize,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(depth < 32, "tag `{}` references itself", name);
+    let tag = raw
+        .get(name)
+        .with_context(|| format!("missing tag `{}`", name))?;
+
+    for value in &tag.values {
+        let id = match value {
+            TagValue::Id(id) | TagValue::Entry { id } => id,
+        };
+        if let Some(referenced) = id.strip_prefix('#') {
+            resolve_tag(referenced, raw, registry, entries, depth + 1)?;
+        } else {
+            let entry = registry
+                .entries
+                .get(id)
+                .with_context(|| format!("unknown entry `{}` in tag `{}`", id, name))?;
+            entries.push(entry.protocol_id);
+        }
+    }
+    Ok(())
+}
@@ -1,28 +1,12 @@
-use crate::bytes_ext::TryGetError;
+use crate::frame::{FrameCodec, VanillaFrameCodec};
 use crate::mctypes::{McTypeRead, McTypeWrite};
 use crate::packet::{PacketDirection, PacketId, PacketStage};
-use crate::{Packet, PacketType};
-use aes::Aes128;
-use bytes::buf::BufMutExt;
-use bytes::{Buf, BytesMut};
-use cfb8::stream_cipher::{NewStreamCipher, StreamCipher};
-use cfb8::Cfb8;
-use flate2::read::ZlibDecoder;
-use flate2::write::ZlibEncoder;
-use flate2::Compression;
-use std::io::{Cursor, Read, Write};
+use crate::{Packet, PacketType, ProtocolVersion};
+use bytes::BytesMut;
+use std::io::Cursor;
 use thiserror::Error;
 use tokio_util::codec::{Decoder, Encoder};
 
-type AesCfb8 = Cfb8<Aes128>;
-
-/// Maximum possible size of a varint.
-const MAX_VAR_INT_SIZE: usize = 5;
-/// Maximum allowed length of a received packet.
-const MAX_PACKET_LEN: usize = 1_048_576; // One MB
-/// Maximum possible size of a packet header.
-const HEADER_SIZE: usize = MAX_VAR_INT_SIZE * 2;
-
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Packet of length {0} (under compression threshold {1}) was sent compressed")]
@@ -34,245 +18,286 @@ pub enum Error {
 }
 
 /// Codec for encoding and decoding Minecraft packets.
-pub struct MinecraftCodec {
+///
+/// Packets are sent in frames encoded by `F`, which
+/// defaults to the frames of the vanilla protocol.
+pub struct MinecraftCodec<F = VanillaFrameCodec> {
     /// Direction of incoming packets.
     incoming_direction: PacketDirection,
     /// The current stage of this codec.
     stage: PacketStage,
-    /// The encrypter, if encryption is enabled.
-    encrypter: Option<AesCfb8>,
-    /// The decrypter, if encryption is enabled.
-    decrypter: Option<AesCfb8>,
-    /// The compression threshold, if compression is enabled.
-    compression_threshold: Option<usize>,
-    /// Cached buffer for writing header data.
-    /// Using this avoids reallocations.
-    header_buffer: BytesMut,
-    /// Cached buffer into which we write decompressed
-    /// data. Using this avoids reallocations.
-    decompressed_buffer: Vec<u8>,
-    /// Index into `src` of next byte to decrypt.
-    decrypt_index: usize,
+    /// The protocol version packets are
+    /// read and written in.
+    protocol_version: ProtocolVersion,
+    /// The codec of the frames packets are sent in.
+    frames: F,
+    /// Cached buffer for writing packet data before
+    /// it is framed. Using this avoids reallocations.
+    packet_buffer: BytesMut,
+    /// The number of bytes of incoming packets decoded so far,
+    /// including those of packets which were skipped.
+    bytes_received: u64,
+    /// The number of bytes of outgoing packets encoded so far.
+    bytes_sent: u64,
 }
 
 impl MinecraftCodec {
     pub fn new(incoming_direction: PacketDirection) -> Self {
+        Self::with_frame_codec(incoming_direction, VanillaFrameCodec::new())
+    }
+}
+
+impl<F: FrameCodec> MinecraftCodec<F> {
+    /// Creates a codec sending packets in the frames of `frames`.
+    pub fn with_frame_codec(incoming_direction: PacketDirection, frames: F) -> Self {
         Self {
             incoming_direction,
             stage: PacketStage::Handshake,
-            encrypter: None,
-            decrypter: None,
-            compression_threshold: None,
-            header_buffer: BytesMut::with_capacity(HEADER_SIZE),
-            decompressed_buffer: vec![],
-            decrypt_index: 0,
+            protocol_version: ProtocolVersion::default(),
+            frames,
+            packet_buffer: BytesMut::new(),
+            bytes_received: 0,
+            bytes_sent: 0,
         }
     }
 
-    pub fn enable_compression(&mut self, threshold: usize) {
-        log::trace!("Enabling compression with threshold {}", threshold);
-        self.compression_threshold = Some(threshold);
+    /// Enables compression of packets whose uncompressed size is at
+    /// least `threshold`. Outgoing packets are compressed with the
+    /// given zlib level, from 0 (no compression) to 9 (best).
+    pub fn enable_compression(&mut self, threshold: usize, level: u32) {
+        self.frames.enable_compression(threshold, level);
     }
 
     pub fn enable_encryption(&mut self, key: [u8; 16]) {
-        log::trace!("Enabling encryption");
-        // This is the toppoint of security: using the same IV
-        // for every packet. Typical for Mojang.
-        self.encrypter = Some(AesCfb8::new_var(&key, &key).unwrap());
-        self.decrypter = Some(AesCfb8::new_var(&key, &key).unwrap());
+        self.frames.enable_encryption(key);
     }
 
     pub fn set_stage(&mut self, stage: PacketStage) {
         log::trace!("Setting packet stage to {:?}", stage);
         self.stage = stage;
     }
-}
-
-impl Encoder<Box<dyn Packet>> for MinecraftCodec {
-    type Error = anyhow::Error;
 
-    fn encode(&mut self, packet: Box<dyn Packet>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        // Reserve space for the packet header (at most 2 * 5 bytes, for length + data length).
-        // `header` will contain the first 10 bytes of the buffer, while `dst`
-        // still contains the rest.
-        // "Data length" refers to the uncompressed size of the packet.
-        // Since we cannot know the size of the header in advance, thanks to varints,
-        // we reserve the maximum size and copy the header in with a correct offset.
-        assert!(dst.is_empty());
-        dst.reserve(HEADER_SIZE);
-
-        // Zero out the header.
-        dst.extend_from_slice(&[0u8; HEADER_SIZE]);
-
-        let mut header = dst.split_to(HEADER_SIZE);
-        assert!(dst.is_empty());
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        log::trace!("Setting protocol version to {}", version);
+        self.protocol_version = version;
+    }
 
-        // Write raw packet data to `dst`.
-        let ty = packet.ty();
-        log::trace!("Sending packet with type {:?}", ty);
-        dst.push_var_int(ty.get_id().0 as i32);
-        packet.write_to(dst);
-
-        // If compression is enabled, we follow a more complex course of action:
-        // * Write the raw packet data to `dst`.
-        // * If the data is less than the compression threshold, proceed as usual.
-        // * Otherwise, we move forward into the buffer, allocating
-        // another header and then writing the compressed bytes
-        // to the capacity after that.
-        let data_len: Option<usize> = if let Some(threshold) = self.compression_threshold {
-            let data_len = dst.len();
-            if data_len >= threshold {
-                // Allocate new header
-                dst.reserve(HEADER_SIZE);
-
-                let uncompressed = dst.split_to(data_len);
-                dst.extend_from_slice(&[0u8; HEADER_SIZE]);
-                header = dst.split_to(HEADER_SIZE);
-
-                assert!(dst.is_empty());
-                // Compress data into `compressed`.
-                let mut encoder = ZlibEncoder::new(dst.writer(), Compression::default());
-                encoder.write_all(uncompressed.as_ref()).unwrap();
-
-                Some(data_len)
-            } else {
-                Some(0) // Not compressed
-            }
-        } else {
-            None
-        };
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
 
-        // Figure out the length of `data_length` encoded.
-        let length_of_data_length = match data_len {
-            Some(data_len) => {
-                let mut temp = BytesMut::with_capacity(MAX_VAR_INT_SIZE);
-                temp.push_var_int(data_len as i32);
-                temp.len()
-            }
-            None => 0,
-        };
+    pub fn frame_codec(&self) -> &F {
+        &self.frames
+    }
 
-        // Write header. We first write to a temporary buffer,
-        // then copy this to the correct position in `header`,
-        // trimming off the unused bytes.
-        self.header_buffer
-            .push_var_int((length_of_data_length + dst.len()) as i32);
-        if let Some(data_len) = data_len {
-            self.header_buffer.push_var_int(data_len as i32);
-        }
+    pub fn frame_codec_mut(&mut self) -> &mut F {
+        &mut self.frames
+    }
 
-        // Offset into `header` to write to.
-        let header_offset = HEADER_SIZE - self.header_buffer.len();
-        // Discard unused header bytes.
-        header.advance(header_offset);
-        header.clear();
+    /// Returns the number of bytes of incoming packets decoded so far.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
 
-        // Write into header.
-        header.extend_from_slice(&self.header_buffer);
-        self.header_buffer.clear();
+    /// Returns the number of bytes of outgoing packets encoded so far.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+}
 
-        // Finally, merge `header` and `dst`.
-        std::mem::swap(dst, &mut header);
-        dst.unsplit(header);
+impl<F: FrameCodec> Encoder<Box<dyn Packet>> for MinecraftCodec<F> {
+    type Error = anyhow::Error;
 
-        // If encryption is enabled, encrypt data in place.
-        if let Some(crypter) = self.encrypter.as_mut() {
-            crypter.encrypt(dst);
-        }
+    fn encode(&mut self, packet: Box<dyn Packet>, buf: &mut BytesMut) -> Result<(), Self::Error> {
+        // Packets which don't exist in the
+        // codec's protocol version are dropped.
+        let ty = packet.ty();
+        let id = match ty.get_id_for(self.protocol_version) {
+            Some(id) => id,
+            None => {
+                log::trace!(
+                    "Dropping packet with type {:?}, which doesn't exist in {}",
+                    ty,
+                    self.protocol_version
+                );
+                return Ok(());
+            }
+        };
 
+        log::trace!("Sending packet with type {:?}", ty);
+        self.packet_buffer.clear();
+        self.packet_buffer.push_var_int(id.0 as i32);
+        packet.write_to_version(&mut self.packet_buffer, self.protocol_version);
+
+        // `buf` holds the packets written since the last
+        // flush, so the frame is appended to it.
+        let len = buf.len();
+        self.frames.encode_frame(&self.packet_buffer, buf)?;
+        self.bytes_sent += (buf.len() - len) as u64;
         Ok(())
     }
 }
 
-impl Decoder for MinecraftCodec {
+impl<F: FrameCodec> Decoder for MinecraftCodec<F> {
     type Item = Box<dyn Packet>;
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // If encryption is enabled, decrypt undecrypted data.
-        if let Some(crypter) = self.decrypter.as_mut() {
-            crypter.decrypt(&mut src[self.decrypt_index..]);
-            self.decrypt_index = src.len();
-        }
+        loop {
+            let len = src.len();
+            let frame = match self.frames.decode_frame(src)? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            self.bytes_received += (len - src.len()) as u64;
+
+            let mut cursor = Cursor::new(frame.as_ref());
+
+            // Read packet.
+            let id = cursor.try_get_var_int()? as u32;
+            // If we don't know this packet type, skip the packet.
+            let id = PacketId(id, self.incoming_direction, self.stage);
+            let packet_type = match PacketType::get_from_id_for(id, self.protocol_version) {
+                Ok(ty) => ty,
+                Err(_) => {
+                    log::trace!(
+                        "Received packet type with unknown ID 0x{:x}; skipping",
+                        id.0
+                    );
+                    continue;
+                }
+            };
 
-        // Conversion to `Cursor` is required because `Bytes` does
-        // not implement `Buf`.
-        let mut cursor = Cursor::new(src.as_ref());
+            log::trace!("Decoding packet with type {:?}", packet_type);
 
-        // Read header.
-        let length = match cursor.try_get_var_int() {
-            Ok(length) => length as usize,
-            Err(TryGetError::NotEnoughBytes) => return Ok(None),
-            Err(e) => return Err(e.into()),
-        };
+            let mut packet = packet_type.get_implementation();
+            packet.read_from_version(&mut cursor, self.protocol_version)?;
 
-        if length > cursor.remaining() {
-            // Full packet has not been read yet.
-            return Ok(None);
-        }
+            log::trace!("Received packet with type {:?}", packet_type);
 
-        // Prevent malicious clients from causing huge allocations.
-        if length > MAX_PACKET_LEN {
-            return Err(Error::PacketTooLarge(length).into());
+            return Ok(Some(packet));
         }
+    }
+}
 
-        // At this point, we know a full packet has been received.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cast_packet;
+    use crate::packets::DisconnectLogin;
+    use bytes::{Buf, BufMut};
+
+    fn codecs(threshold: Option<usize>) -> (MinecraftCodec, MinecraftCodec) {
+        let mut server = MinecraftCodec::new(PacketDirection::Serverbound);
+        let mut client = MinecraftCodec::new(PacketDirection::Clientbound);
+        for codec in &mut [&mut server, &mut client] {
+            codec.set_stage(PacketStage::Login);
+            if let Some(threshold) = threshold {
+                codec.enable_compression(threshold, 6);
+            }
+        }
+        (server, client)
+    }
 
-        // Trim `cursor` and `src` to length of packet.
-        let position = cursor.position() as usize;
-        src.advance(position);
-        cursor = Cursor::new(&src[..length]);
+    fn round_trip(threshold: Option<usize>, reason: &str) -> usize {
+        let (mut server, mut client) = codecs(threshold);
+        let packet = DisconnectLogin {
+            reason: reason.to_owned(),
+        };
 
-        // If compression is enabled:
-        // * Read the data length field. If 0, continue as normal: the packet is not compressed.
-        // * Decompress remaining bytes into `self.decompressed_buffer`.
-        // * Update `cursor` to read from `self.decompressed_buffer`.
-        if let Some(threshold) = self.compression_threshold {
-            let data_length = cursor.try_get_var_int()?;
+        let mut buf = BytesMut::new();
+        server.encode(Box::new(packet), &mut buf).unwrap();
+        let len = buf.len();
 
-            if data_length != 0 {
-                self.decompressed_buffer.clear();
+        let packet = client.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(cast_packet::<DisconnectLogin>(packet).reason, reason);
+        assert!(buf.is_empty());
+        len
+    }
 
-                let mut decoder = ZlibDecoder::new(cursor);
-                decoder.read_to_end(&mut self.decompressed_buffer)?;
+    #[test]
+    fn compression() {
+        let reason = "a".repeat(1000);
+        let uncompressed = round_trip(None, &reason);
+        // Packets above the threshold are compressed.
+        assert!(round_trip(Some(256), &reason) < uncompressed / 2);
+        // Those below it are sent as they are, after
+        // a data length of 0, which takes one byte.
+        assert_eq!(round_trip(Some(2048), &reason), uncompressed + 1);
+        // A threshold of 0 compresses every packet.
+        assert!(round_trip(Some(0), "short") > round_trip(None, "short") + 1);
+    }
 
-                let actual_data_length = self.decompressed_buffer.len();
-                if actual_data_length < threshold {
-                    return Err(
-                        Error::CompressedPacketTooSmall(actual_data_length, threshold).into(),
-                    );
-                }
+    #[test]
+    fn batched() {
+        let (mut server, mut client) = codecs(Some(256));
+
+        // Packets are appended to the buffer until it is flushed.
+        let mut buf = BytesMut::new();
+        for reason in &["first", "second"] {
+            let packet = DisconnectLogin {
+                reason: reason.to_string(),
+            };
+            server.encode(Box::new(packet), &mut buf).unwrap();
+        }
+        assert_eq!(server.bytes_sent(), buf.len() as u64);
 
-                cursor = Cursor::new(&self.decompressed_buffer);
-            }
+        for reason in &["first", "second"] {
+            let packet = client.decode(&mut buf).unwrap().unwrap();
+            assert_eq!(cast_packet::<DisconnectLogin>(packet).reason, *reason);
         }
+        assert!(buf.is_empty());
+    }
 
-        // Read packet.
-        let id = cursor.try_get_var_int()? as u32;
-        // If we don't know this packet type, skip the packet.
-        let packet_type = {
-            match PacketType::get_from_id(PacketId(id, self.incoming_direction, self.stage)) {
-                Ok(ty) => ty,
-                Err(_) => {
-                    // Advance buffer and stop.
-                    log::trace!("Received packet type with unknown ID 0x{:x}; skipping", id);
-                    src.advance(length);
-                    self.decrypt_index = src.len();
-                    return Ok(None);
-                }
-            }
-        };
+    /// Frames prefixed by their length as a `u32`,
+    /// as an alternative transport might use.
+    struct FixedLengthFrames;
 
-        log::trace!("Decoding packet with type {:?}", packet_type);
+    impl FrameCodec for FixedLengthFrames {
+        fn encode_frame(&mut self, data: &[u8], dst: &mut BytesMut) -> anyhow::Result<()> {
+            dst.put_u32(data.len() as u32);
+            dst.extend_from_slice(data);
+            Ok(())
+        }
 
-        let mut packet = packet_type.get_implementation();
-        packet.read_from(&mut cursor)?;
+        fn decode_frame(&mut self, src: &mut BytesMut) -> anyhow::Result<Option<BytesMut>> {
+            if src.len() < 4 {
+                return Ok(None);
+            }
+            let len = (&src[..4]).get_u32() as usize;
+            if src.len() < 4 + len {
+                return Ok(None);
+            }
+            src.advance(4);
+            Ok(Some(src.split_to(len)))
+        }
 
-        log::trace!("Received packet with type {:?}", packet_type);
+        fn enable_compression(&mut self, _threshold: usize, _level: u32) {}
 
-        src.advance(length);
-        self.decrypt_index = src.len();
+        fn enable_encryption(&mut self, _key: [u8; 16]) {}
+    }
 
-        Ok(Some(packet))
+    #[test]
+    fn custom_frames() {
+        let mut server = MinecraftCodec::with_frame_codec(
+            PacketDirection::Serverbound,
+            Box::new(FixedLengthFrames) as Box<dyn FrameCodec>,
+        );
+        let mut client =
+            MinecraftCodec::with_frame_codec(PacketDirection::Clientbound, FixedLengthFrames);
+        server.set_stage(PacketStage::Login);
+        client.set_stage(PacketStage::Login);
+
+        let packet = DisconnectLogin {
+            reason: "reason".to_owned(),
+        };
+        let mut buf = BytesMut::new();
+        server.encode(Box::new(packet), &mut buf).unwrap();
+        // The ID and the length-prefixed reason, after the frame length.
+        assert_eq!(buf.len(), 4 + 1 + 1 + 6);
+
+        let packet = client.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(cast_packet::<DisconnectLogin>(packet).reason, "reason");
+        assert_eq!(client.bytes_received(), server.bytes_sent());
     }
 }
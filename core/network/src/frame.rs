@@ -0,0 +1,263 @@
+//! Frames, which packets are sent in over a connection.
+//!
+//! A frame holds the data of one packet: its ID followed by its
+//! fields. How frames are delimited, compressed and encrypted is
+//! up to a `FrameCodec`, so that transports other than vanilla's,
+//! e.g. between a proxy and a backend server, can reuse the rest
+//! of the codec.
+
+use crate::bytes_ext::TryGetError;
+use crate::codec::Error;
+use crate::mctypes::{McTypeRead, McTypeWrite};
+use aes::Aes128;
+use bytes::buf::BufMutExt;
+use bytes::{Buf, BytesMut};
+use cfb8::stream_cipher::{NewStreamCipher, StreamCipher};
+use cfb8::Cfb8;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{self, Cursor, Write};
+
+type AesCfb8 = Cfb8<Aes128>;
+
+/// Maximum allowed length of a received packet.
+const MAX_PACKET_LEN: usize = 1_048_576; // One MB
+
+/// Encodes packet data into frames and decodes it from them.
+pub trait FrameCodec {
+    /// Appends a frame containing the given packet data to `dst`.
+    fn encode_frame(&mut self, data: &[u8], dst: &mut BytesMut) -> anyhow::Result<()>;
+
+    /// Removes the next frame from `src` and returns its packet
+    /// data, or returns `None` if it hasn't been fully received.
+    fn decode_frame(&mut self, src: &mut BytesMut) -> anyhow::Result<Option<BytesMut>>;
+
+    /// Called when the server enables compression during login,
+    /// with the compression threshold and the configured zlib level.
+    fn enable_compression(&mut self, threshold: usize, level: u32);
+
+    /// Called when encryption is enabled during login
+    /// with the shared secret of the connection.
+    fn enable_encryption(&mut self, key: [u8; 16]);
+}
+
+impl<F: FrameCodec + ?Sized> FrameCodec for Box<F> {
+    fn encode_frame(&mut self, data: &[u8], dst: &mut BytesMut) -> anyhow::Result<()> {
+        (**self).encode_frame(data, dst)
+    }
+
+    fn decode_frame(&mut self, src: &mut BytesMut) -> anyhow::Result<Option<BytesMut>> {
+        (**self).decode_frame(src)
+    }
+
+    fn enable_compression(&mut self, threshold: usize, level: u32) {
+        (**self).enable_compression(threshold, level)
+    }
+
+    fn enable_encryption(&mut self, key: [u8; 16]) {
+        (**self).enable_encryption(key)
+    }
+}
+
+/// The frames of the vanilla protocol: packet data prefixed
+/// by its length, with optional zlib compression and AES
+/// encryption.
+pub struct VanillaFrameCodec {
+    /// The encrypter, if encryption is enabled.
+    encrypter: Option<AesCfb8>,
+    /// The decrypter, if encryption is enabled.
+    decrypter: Option<AesCfb8>,
+    /// The compression threshold, if compression is enabled.
+    compression_threshold: Option<usize>,
+    /// The zlib compression level of outgoing packets.
+    compression_level: Compression,
+    /// Cached buffer into which we write compressed data,
+    /// whose length is needed before it is appended to
+    /// the frame. Using this avoids reallocations.
+    compressed_buffer: Vec<u8>,
+    /// Index into `src` of next byte to decrypt.
+    decrypt_index: usize,
+}
+
+impl Default for VanillaFrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VanillaFrameCodec {
+    pub fn new() -> Self {
+        Self {
+            encrypter: None,
+            decrypter: None,
+            compression_threshold: None,
+            compression_level: Compression::default(),
+            compressed_buffer: vec![],
+            decrypt_index: 0,
+        }
+    }
+}
+
+impl FrameCodec for VanillaFrameCodec {
+    fn encode_frame(&mut self, data: &[u8], dst: &mut BytesMut) -> anyhow::Result<()> {
+        let start = dst.len();
+
+        // With compression enabled, the length is followed by the
+        // uncompressed length of the data, or 0 if it is below the
+        // threshold and thus sent uncompressed.
+        match self.compression_threshold {
+            Some(threshold) if data.len() >= threshold => {
+                self.compressed_buffer.clear();
+                let mut encoder =
+                    ZlibEncoder::new(&mut self.compressed_buffer, self.compression_level);
+                encoder.write_all(data)?;
+                encoder.finish()?;
+
+                let length = var_int_len(data.len()) + self.compressed_buffer.len();
+                dst.push_var_int(length as i32);
+                dst.push_var_int(data.len() as i32);
+                dst.extend_from_slice(&self.compressed_buffer);
+            }
+            Some(_) => {
+                dst.push_var_int(data.len() as i32 + 1);
+                dst.push_var_int(0);
+                dst.extend_from_slice(data);
+            }
+            None => {
+                dst.push_var_int(data.len() as i32);
+                dst.extend_from_slice(data);
+            }
+        }
+
+        // If encryption is enabled, encrypt the frame in place.
+        if let Some(crypter) = self.encrypter.as_mut() {
+            crypter.encrypt(&mut dst[start..]);
+        }
+
+        Ok(())
+    }
+
+    fn decode_frame(&mut self, src: &mut BytesMut) -> anyhow::Result<Option<BytesMut>> {
+        // If encryption is enabled, decrypt undecrypted data.
+        if let Some(crypter) = self.decrypter.as_mut() {
+            crypter.decrypt(&mut src[self.decrypt_index..]);
+            self.decrypt_index = src.len();
+        }
+
+        // Conversion to `Cursor` is required because `Bytes` does
+        // not implement `Buf`.
+        let mut cursor = Cursor::new(src.as_ref());
+
+        // Read header.
+        let length = match cursor.try_get_var_int() {
+            Ok(length) => length as usize,
+            Err(TryGetError::NotEnoughBytes) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Prevent malicious clients from causing huge allocations.
+        if length > MAX_PACKET_LEN {
+            return Err(Error::PacketTooLarge(length).into());
+        }
+
+        if length > cursor.remaining() {
+            // Full packet has not been read yet.
+            return Ok(None);
+        }
+
+        let position = cursor.position() as usize;
+        src.advance(position);
+        let mut frame = src.split_to(length);
+        self.decrypt_index = src.len();
+
+        // If compression is enabled, read the data length. If 0,
+        // the packet is not compressed; otherwise, decompress it.
+        if let Some(threshold) = self.compression_threshold {
+            let mut cursor = Cursor::new(frame.as_ref());
+            let data_length = cursor.try_get_var_int()?;
+            let position = cursor.position() as usize;
+
+            if data_length == 0 {
+                frame.advance(position);
+            } else {
+                let mut decoder = ZlibDecoder::new(&frame[position..]);
+                let mut decompressed = BytesMut::new().writer();
+                io::copy(&mut decoder, &mut decompressed)?;
+                let decompressed = decompressed.into_inner();
+
+                if decompressed.len() < threshold {
+                    return Err(
+                        Error::CompressedPacketTooSmall(decompressed.len(), threshold).into(),
+                    );
+                }
+
+                frame = decompressed;
+            }
+        }
+
+        Ok(Some(frame))
+    }
+
+    fn enable_compression(&mut self, threshold: usize, level: u32) {
+        log::trace!(
+            "Enabling compression with threshold {} and level {}",
+            threshold,
+            level
+        );
+        self.compression_threshold = Some(threshold);
+        self.compression_level = Compression::new(level.min(9));
+    }
+
+    fn enable_encryption(&mut self, key: [u8; 16]) {
+        log::trace!("Enabling encryption");
+        // This is the toppoint of security: using the same IV
+        // for every packet. Typical for Mojang.
+        self.encrypter = Some(AesCfb8::new_var(&key, &key).unwrap());
+        self.decrypter = Some(AesCfb8::new_var(&key, &key).unwrap());
+    }
+}
+
+/// Returns the number of bytes `value` takes up as a varint.
+fn var_int_len(value: usize) -> usize {
+    let mut len = 1;
+    let mut value = value >> 7;
+    while value != 0 {
+        len += 1;
+        value >>= 7;
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_encrypted_frames() {
+        let mut server = VanillaFrameCodec::new();
+        let mut client = VanillaFrameCodec::new();
+        for codec in &mut [&mut server, &mut client] {
+            codec.enable_compression(64, 6);
+            codec.enable_encryption([7; 16]);
+        }
+
+        let long = vec![1; 300];
+        let mut buf = BytesMut::new();
+        server.encode_frame(b"short", &mut buf).unwrap();
+        server.encode_frame(&long, &mut buf).unwrap();
+
+        // Frames are decoded once all of their bytes are received,
+        // and bytes are decrypted once only.
+        let mut received = BytesMut::new();
+        let mut frames = vec![];
+        for chunk in buf.chunks(7) {
+            received.extend_from_slice(chunk);
+            while let Some(frame) = client.decode_frame(&mut received).unwrap() {
+                frames.push(frame.to_vec());
+            }
+        }
+        assert_eq!(frames, vec![b"short".to_vec(), long]);
+        assert!(received.is_empty());
+    }
+}
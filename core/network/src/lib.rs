@@ -1,11 +1,18 @@
 mod bytes_ext;
 mod codec;
+mod frame;
+pub mod mappings;
 mod mctypes;
 mod packet;
 pub mod packets;
+mod version;
 
+pub use bytes_ext::{BytesExt, TryGetError};
 pub use codec::{Error, MinecraftCodec};
+pub use frame::{FrameCodec, VanillaFrameCodec};
+pub use mctypes::McTypeRead;
 pub use packet::{Packet, PacketBuilder, PacketDirection, PacketId, PacketStage, PacketType};
+pub use version::ProtocolVersion;
 
 pub fn cast_packet<P: packet::Packet + 'static + Send>(packet: Box<dyn Packet>) -> P {
     *packet.into_any().downcast().unwrap()
@@ -0,0 +1,431 @@
+//! Translation of IDs from 1.13.2, which the server
+//! uses internally, to other protocol versions.
+
+use crate::bytes_ext::BytesMutExt;
+use crate::mctypes::McTypeWrite;
+use crate::ProtocolVersion;
+use ahash::AHashMap;
+use bytes::BytesMut;
+use feather_blocks::BlockId;
+use feather_entity_metadata::MetadataLayout;
+use feather_items::Item;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)]
+struct Mappings {
+    /// Block state IDs, indexed by 1.13.2 block state ID.
+    block_ids: Vec<u16>,
+    /// Block IDs, indexed by 1.13.2 block ID.
+    block_kinds: Vec<i32>,
+    /// Item IDs, indexed by 1.13.2 item ID.
+    items: Vec<i32>,
+    /// Entity type IDs, indexed by 1.13.2 entity type ID.
+    entity_types: Vec<Option<i32>>,
+    /// Entity type IDs, by 1.13.2 object type.
+    object_types: BTreeMap<i8, i32>,
+    /// Menu (window type) IDs, by name.
+    menus: BTreeMap<String, i32>,
+    /// Particle type IDs, by name.
+    particles: BTreeMap<String, i32>,
+    /// Block entity type IDs, by name.
+    block_entity_types: BTreeMap<String, i32>,
+    /// The registry of each kind of tag in `tags`.
+    tag_registries: Vec<String>,
+    /// Block, item, fluid and entity type tags (and game
+    /// event tags since 1.17), mapping tag names to protocol IDs.
+    tags: Vec<BTreeMap<String, Vec<i32>>>,
+}
+
+/// The 1.13.2 particle types, indexed by ID. The 1.13.2
+/// reports don't include the registries, so they are listed here.
+const PARTICLES_1_13: &[&str] = &[
+    "minecraft:ambient_entity_effect",
+    "minecraft:angry_villager",
+    "minecraft:barrier",
+    "minecraft:block",
+    "minecraft:bubble",
+    "minecraft:cloud",
+    "minecraft:crit",
+    "minecraft:damage_indicator",
+    "minecraft:dragon_breath",
+    "minecraft:dripping_lava",
+    "minecraft:dripping_water",
+    "minecraft:dust",
+    "minecraft:effect",
+    "minecraft:elder_guardian",
+    "minecraft:enchanted_hit",
+    "minecraft:enchant",
+    "minecraft:end_rod",
+    "minecraft:entity_effect",
+    "minecraft:explosion_emitter",
+    "minecraft:explosion",
+    "minecraft:falling_dust",
+    "minecraft:firework",
+    "minecraft:fishing",
+    "minecraft:flame",
+    "minecraft:happy_villager",
+    "minecraft:heart",
+    "minecraft:instant_effect",
+    "minecraft:item",
+    "minecraft:item_slime",
+    "minecraft:item_snowball",
+    "minecraft:large_smoke",
+    "minecraft:lava",
+    "minecraft:mycelium",
+    "minecraft:note",
+    "minecraft:poof",
+    "minecraft:portal",
+    "minecraft:rain",
+    "minecraft:smoke",
+    "minecraft:spit",
+    "minecraft:squid_ink",
+    "minecraft:sweep_attack",
+    "minecraft:totem_of_undying",
+    "minecraft:underwater",
+    "minecraft:splash",
+    "minecraft:witch",
+    "minecraft:bubble_pop",
+    "minecraft:current_down",
+    "minecraft:bubble_column_up",
+    "minecraft:nautilus",
+    "minecraft:dolphin",
+];
+
+/// Similar particles shown to 1.13.2 clients
+/// for particles added since, by name.
+const PARTICLE_FALLBACKS_1_13: &[(&str, &str)] = &[
+    ("minecraft:falling_lava", "minecraft:dripping_lava"),
+    ("minecraft:landing_lava", "minecraft:lava"),
+    ("minecraft:falling_water", "minecraft:dripping_water"),
+    ("minecraft:flash", "minecraft:firework"),
+    ("minecraft:composter", "minecraft:happy_villager"),
+    ("minecraft:sneeze", "minecraft:spit"),
+    ("minecraft:campfire_cosy_smoke", "minecraft:large_smoke"),
+    ("minecraft:campfire_signal_smoke", "minecraft:large_smoke"),
+    ("minecraft:dripping_honey", "minecraft:dripping_water"),
+    ("minecraft:falling_honey", "minecraft:dripping_water"),
+    ("minecraft:landing_honey", "minecraft:dripping_water"),
+    ("minecraft:falling_nectar", "minecraft:happy_villager"),
+];
+
+/// The block entity types updated by each action of
+/// the 1.13.2 Update Block Entity packet.
+const BLOCK_ENTITY_ACTIONS_1_13: &[(u8, &str)] = &[
+    (1, "minecraft:mob_spawner"),
+    (2, "minecraft:command_block"),
+    (3, "minecraft:beacon"),
+    (4, "minecraft:skull"),
+    (5, "minecraft:conduit"),
+    (6, "minecraft:banner"),
+    (7, "minecraft:structure_block"),
+    (8, "minecraft:end_gateway"),
+    (9, "minecraft:sign"),
+    (10, "minecraft:shulker_box"),
+    (11, "minecraft:bed"),
+];
+
+/// The 1.16.5 mappings, initialized at runtime from the
+/// embedded mappings dump. (Generated by the build script)
+static MAPPINGS_1_16_5: Lazy<Mappings> = Lazy::new(|| {
+    static BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/mappings_1_16_5.json"));
+
+    serde_json::from_slice(BYTES).expect("invalid 1.16.5 mappings dump")
+});
+
+/// The 1.18.1 mappings.
+static MAPPINGS_1_18_1: Lazy<Mappings> = Lazy::new(|| {
+    static BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/mappings_1_18_1.json"));
+
+    serde_json::from_slice(BYTES).expect("invalid 1.18.1 mappings dump")
+});
+
+/// 1.13.2 item IDs, by 1.16.5 item ID.
+static ITEMS_FROM_1_16_5: Lazy<AHashMap<i32, u32>> = Lazy::new(|| items_from(&MAPPINGS_1_16_5));
+
+/// 1.13.2 item IDs, by 1.18.1 item ID.
+static ITEMS_FROM_1_18_1: Lazy<AHashMap<i32, u32>> = Lazy::new(|| items_from(&MAPPINGS_1_18_1));
+
+fn items_from(mappings: &Mappings) -> AHashMap<i32, u32> {
+    let mut m = AHashMap::new();
+
+    for (old, new) in mappings.items.iter().enumerate() {
+        // Items which no longer exist are mapped to air.
+        if *new != 0 || old == 0 {
+            m.insert(*new, old as u32);
+        }
+    }
+
+    m
+}
+
+/// Returns the mappings to the given version,
+/// or `None` for 1.13.2, which needs none.
+fn mappings(version: ProtocolVersion) -> Option<&'static Mappings> {
+    match version {
+        ProtocolVersion::V1_13_2 => None,
+        ProtocolVersion::V1_16_5 => Some(&MAPPINGS_1_16_5),
+        ProtocolVersion::V1_18_1 => Some(&MAPPINGS_1_18_1),
+    }
+}
+
+/// Returns the block state ID of a block in the given version.
+pub fn block_id(block: BlockId, version: ProtocolVersion) -> u16 {
+    vanilla_block_id(block.vanilla_id(), version)
+}
+
+/// Translates a 1.13.2 block state ID to the given version.
+/// Unknown IDs are translated to air.
+pub fn vanilla_block_id(id: u16, version: ProtocolVersion) -> u16 {
+    match mappings(version) {
+        None => id,
+        Some(mappings) => mappings.block_ids.get(id as usize).copied().unwrap_or(0),
+    }
+}
+
+/// Translates a 1.13.2 block ID (not block state ID),
+/// as used by the Block Action packet, to the given version.
+pub fn block_kind_id(id: i32, version: ProtocolVersion) -> i32 {
+    match mappings(version) {
+        None => id,
+        Some(mappings) => mappings.block_kinds.get(id as usize).copied().unwrap_or(0),
+    }
+}
+
+/// Returns the ID of an item in the given version. Items
+/// which don't exist in that version are translated to air.
+pub fn item_id(item: Item, version: ProtocolVersion) -> i32 {
+    let id = item.vanilla_id();
+    match mappings(version) {
+        None => id as i32,
+        Some(mappings) => mappings.items.get(id as usize).copied().unwrap_or(0),
+    }
+}
+
+/// Returns the item with the given ID in the given version,
+/// or `None` if it doesn't exist in 1.13.2.
+pub fn item_from_id(id: i32, version: ProtocolVersion) -> Option<Item> {
+    let id = match version {
+        ProtocolVersion::V1_13_2 => id as u32,
+        ProtocolVersion::V1_16_5 => *ITEMS_FROM_1_16_5.get(&id)?,
+        ProtocolVersion::V1_18_1 => *ITEMS_FROM_1_18_1.get(&id)?,
+    };
+    Item::from_vanilla_id(id)
+}
+
+/// Translates a 1.13.2 entity type ID to the given version.
+/// Returns `None` if the entity type no longer exists.
+pub fn entity_type_id(id: i32, version: ProtocolVersion) -> Option<i32> {
+    match mappings(version) {
+        None => Some(id),
+        Some(mappings) => mappings.entity_types.get(id as usize).copied().flatten(),
+    }
+}
+
+/// Translates a 1.13.2 object type, as sent in the Spawn Object
+/// packet, to the given version. Objects are identified by their
+/// entity type since 1.14.
+pub fn object_type_id(ty: i8, version: ProtocolVersion) -> Option<i32> {
+    match mappings(version) {
+        None => Some(i32::from(ty)),
+        Some(mappings) => mappings.object_types.get(&ty).copied(),
+    }
+}
+
+/// Returns the ID of the menu with the given window type and
+/// number of slots in a version since 1.14, or `None` if there
+/// is no such menu.
+pub(crate) fn menu_id(window_type: &str, slots: u8, version: ProtocolVersion) -> Option<i32> {
+    let menus = &mappings(version)?.menus;
+    menus
+        .get(window_type)
+        .copied()
+        .or_else(|| match window_type {
+            // Chests were sized by their number of slots before 1.14.
+            "minecraft:chest" | "minecraft:container" => menus
+                .get(&format!("minecraft:generic_9x{}", slots / 9))
+                .copied(),
+            "minecraft:villager" => menus.get("minecraft:merchant").copied(),
+            _ => None,
+        })
+}
+
+/// Returns the ID of the particle type with the given name in the
+/// given version, or `None` if there is no such particle type.
+///
+/// Particles added since 1.13.2 are shown to 1.13.2
+/// clients as the most similar particle they know.
+pub fn particle_id(name: &str, version: ProtocolVersion) -> Option<i32> {
+    match mappings(version) {
+        None => {
+            let name = PARTICLE_FALLBACKS_1_13
+                .iter()
+                .find(|(new, _)| *new == name)
+                .map_or(name, |(_, old)| *old);
+            PARTICLES_1_13
+                .iter()
+                .position(|particle| *particle == name)
+                .map(|id| id as i32)
+        }
+        Some(mappings) => mappings.particles.get(name).copied(),
+    }
+}
+
+/// Returns the ID of the block entity type updated by an action
+/// of the 1.13.2 Update Block Entity packet in the given version,
+/// or `None` if the action is unknown.
+pub fn block_entity_type_id(action: u8, version: ProtocolVersion) -> Option<i32> {
+    let (_, name) = BLOCK_ENTITY_ACTIONS_1_13
+        .iter()
+        .find(|(other, _)| *other == action)?;
+    mappings(version)?.block_entity_types.get(*name).copied()
+}
+
+/// Translates the index of a field of entity metadata with the
+/// given layout to the given version. Returns `None` if the field
+/// doesn't exist in that version or has another type there.
+pub fn metadata_index(layout: MetadataLayout, index: u8, version: ProtocolVersion) -> Option<u8> {
+    let [v1_16_5, v1_18_1] = layout.later_indices(index);
+    match version {
+        ProtocolVersion::V1_13_2 => Some(index),
+        ProtocolVersion::V1_16_5 => v1_16_5,
+        ProtocolVersion::V1_18_1 => v1_18_1,
+    }
+}
+
+/// Writes the vanilla tags of a version since 1.16.5
+/// in the format of the Tags packet.
+///
+/// Clients since 1.16.5 disconnect if any vanilla tag is
+/// missing, so these are sent instead of the server's own tags.
+pub(crate) fn write_tags(buf: &mut BytesMut, version: ProtocolVersion) {
+    let mappings = match mappings(version) {
+        Some(mappings) => mappings,
+        None => return,
+    };
+    // Since 1.17, each kind of tag is preceded by its registry.
+    let named_registries = version >= ProtocolVersion::V1_18_1;
+    if named_registries {
+        buf.push_var_int(mappings.tags.len() as i32);
+    }
+
+    for (registry, tags) in mappings.tag_registries.iter().zip(&mappings.tags) {
+        if named_registries {
+            buf.push_string(registry);
+        }
+        buf.push_var_int(tags.len() as i32);
+        for (name, ids) in tags {
+            buf.push_string(name);
+            buf.push_var_int(ids.len() as i32);
+            for id in ids {
+                buf.push_var_int(*id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_ids() {
+        let version = ProtocolVersion::V1_16_5;
+        assert_eq!(block_id(BlockId::air(), version), 0);
+        assert_eq!(block_id(BlockId::stone(), version), 1);
+        assert_eq!(block_id(BlockId::grass_block(), version), 9);
+        // Renamed to `oak_sign` in 1.14.
+        assert_ne!(block_id(BlockId::sign(), version), 0);
+        assert_eq!(
+            block_id(BlockId::stone(), ProtocolVersion::V1_13_2),
+            BlockId::stone().vanilla_id()
+        );
+    }
+
+    #[test]
+    fn item_ids() {
+        let version = ProtocolVersion::V1_16_5;
+        assert_eq!(item_id(Item::Air, version), 0);
+        assert_eq!(item_id(Item::Stone, version), 1);
+        assert_ne!(item_id(Item::Sign, version), 0);
+        assert_ne!(item_id(Item::RoseRed, version), 0);
+
+        for &item in &[Item::Stone, Item::DiamondSword, Item::OakLog] {
+            assert_eq!(item_from_id(item_id(item, version), version), Some(item));
+        }
+    }
+
+    #[test]
+    fn entity_types() {
+        let version = ProtocolVersion::V1_16_5;
+        // Zombie pigmen became zombified piglins.
+        assert!(entity_type_id(53, version).is_some());
+        assert_eq!(object_type_id(2, version), entity_type_id(32, version));
+        assert_eq!(object_type_id(4, version), None);
+        assert_eq!(metadata_index(MetadataLayout::BASE, 5, version), Some(5));
+        assert_eq!(metadata_index(MetadataLayout::BASE, 6, version), None);
+        assert_eq!(menu_id("minecraft:generic_9x3", 27, version), Some(2));
+        assert_eq!(menu_id("minecraft:chest", 54, version), Some(5));
+        assert_eq!(menu_id("minecraft:villager", 0, version), Some(18));
+        assert_eq!(
+            menu_id("minecraft:chest", 54, ProtocolVersion::V1_13_2),
+            None
+        );
+    }
+
+    #[test]
+    fn particle_ids() {
+        assert_eq!(
+            particle_id("minecraft:dust", ProtocolVersion::V1_13_2),
+            Some(11)
+        );
+        assert_eq!(
+            particle_id("minecraft:dust", ProtocolVersion::V1_16_5),
+            Some(14)
+        );
+        assert_eq!(
+            particle_id("minecraft:dust", ProtocolVersion::V1_18_1),
+            Some(14)
+        );
+        assert_eq!(
+            particle_id("minecraft:falling_lava", ProtocolVersion::V1_13_2),
+            particle_id("minecraft:dripping_lava", ProtocolVersion::V1_13_2)
+        );
+        assert_eq!(block_entity_type_id(9, ProtocolVersion::V1_18_1), Some(7));
+        // Replaced by `block_marker` in 1.18.
+        assert_eq!(
+            particle_id("minecraft:barrier", ProtocolVersion::V1_18_1),
+            None
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let tags = &MAPPINGS_1_16_5.tags;
+        assert_eq!(tags.len(), 4);
+        assert!(!tags[0]["minecraft:logs"].is_empty());
+        assert_eq!(tags[2]["minecraft:water"], vec![1, 2]);
+
+        let mappings = &MAPPINGS_1_18_1;
+        assert_eq!(mappings.tags.len(), 5);
+        assert_eq!(mappings.tag_registries[4], "minecraft:game_event");
+        assert!(!mappings.tags[4]["minecraft:vibrations"].is_empty());
+    }
+
+    #[test]
+    fn ids_1_18_1() {
+        let version = ProtocolVersion::V1_18_1;
+        assert_eq!(block_id(BlockId::stone(), version), 1);
+        // Renamed to `dirt_path` in 1.17.
+        assert_ne!(block_id(BlockId::grass_path(), version), 0);
+        assert_ne!(item_id(Item::GrassPath, version), 0);
+        assert_eq!(
+            item_from_id(item_id(Item::DiamondSword, version), version),
+            Some(Item::DiamondSword)
+        );
+        assert!(entity_type_id(53, version).is_some());
+        assert_eq!(metadata_index(MetadataLayout::BASE, 5, version), Some(5));
+        assert_eq!(metadata_index(MetadataLayout::BASE, 6, version), None);
+    }
+}
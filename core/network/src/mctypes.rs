@@ -1,8 +1,9 @@
 use crate::bytes_ext::{BytesExt, BytesMutExt, TryGetError};
+use crate::{mappings, ProtocolVersion};
 use bytes::{Buf, BytesMut};
 use feather_anvil::entity::ItemNbt;
 use feather_entity_metadata::{EntityMetadata, MetaEntry};
-use feather_items::{Item, ItemStack};
+use feather_items::ItemStack;
 use feather_util::BlockPosition;
 use feather_util::Direction;
 use num_traits::FromPrimitive;
@@ -20,6 +21,9 @@ pub trait McTypeWrite {
     ///
     /// Returns the number of bytes used to encode this integer.
     fn push_var_int(&mut self, x: i32) -> usize;
+    /// Writes a `VarLong` to the object, returning
+    /// the number of bytes used to encode it.
+    fn push_var_long(&mut self, x: i64) -> usize;
     /// Writes a string to the object. This method
     /// will first write the length of the string in bytes
     /// encodes as a `VarInt` and will then write
@@ -28,6 +32,10 @@ pub trait McTypeWrite {
 
     fn push_position(&mut self, x: &BlockPosition);
 
+    /// Writes a position in the encoding used by the given
+    /// protocol version.
+    fn push_position_for(&mut self, x: &BlockPosition, version: ProtocolVersion);
+
     fn push_bool(&mut self, x: bool);
 
     fn push_uuid(&mut self, x: &Uuid);
@@ -35,6 +43,9 @@ pub trait McTypeWrite {
     fn push_nbt<T: Serialize>(&mut self, x: &T);
 
     fn push_slot(&mut self, slot: Option<ItemStack>);
+
+    /// Writes a slot using the item IDs of the given protocol version.
+    fn push_slot_for(&mut self, slot: Option<ItemStack>, version: ProtocolVersion);
 }
 
 /// Identifies a type from which Minecraft-specified
@@ -44,11 +55,20 @@ pub trait McTypeRead {
     /// `Some(x)` if successful or `None` if the object
     /// does not contain a valid `VarInt`.
     fn try_get_var_int(&mut self) -> Result<i32, TryGetError>;
+    /// Reads a `VarLong` from this object.
+    fn try_get_var_long(&mut self) -> Result<i64, TryGetError>;
     /// Reads a string from the object.
     fn try_get_string(&mut self) -> Result<String, TryGetError>;
 
     fn try_get_position(&mut self) -> Result<BlockPosition, TryGetError>;
 
+    /// Reads a position in the encoding used by the given
+    /// protocol version.
+    fn try_get_position_for(
+        &mut self,
+        version: ProtocolVersion,
+    ) -> Result<BlockPosition, TryGetError>;
+
     fn try_get_bool(&mut self) -> Result<bool, TryGetError>;
 
     fn try_get_uuid(&mut self) -> anyhow::Result<Uuid>;
@@ -56,6 +76,12 @@ pub trait McTypeRead {
     fn try_get_nbt<T: DeserializeOwned>(&mut self) -> Result<T, nbt::Error>;
 
     fn try_get_slot(&mut self) -> Result<Option<ItemStack>, TryGetError>;
+
+    /// Reads a slot using the item IDs of the given protocol version.
+    fn try_get_slot_for(
+        &mut self,
+        version: ProtocolVersion,
+    ) -> Result<Option<ItemStack>, TryGetError>;
 }
 
 impl McTypeWrite for BytesMut {
@@ -77,6 +103,26 @@ impl McTypeWrite for BytesMut {
         bytes_written
     }
 
+    fn push_var_long(&mut self, x: i64) -> usize {
+        // Shift as unsigned so negative values terminate.
+        let mut x = x as u64;
+        let mut bytes_written = 0;
+        loop {
+            let mut temp = (x & 0b0111_1111) as u8;
+            x >>= 7;
+            if x != 0 {
+                temp |= 0b1000_0000;
+            }
+            self.push_u8(temp);
+            bytes_written += 1;
+            if x == 0 {
+                break;
+            }
+        }
+
+        bytes_written
+    }
+
     /// Writes a string to the object. This method
     /// will first write the length of the string in bytes
     /// encodes as a `VarInt` and will then write
@@ -96,6 +142,20 @@ impl McTypeWrite for BytesMut {
         self.push_u64(result);
     }
 
+    fn push_position_for(&mut self, x: &BlockPosition, version: ProtocolVersion) {
+        match version {
+            ProtocolVersion::V1_13_2 => self.push_position(x),
+            // 1.14 moved the Y coordinate to the lowest bits.
+            ProtocolVersion::V1_16_5 | ProtocolVersion::V1_18_1 => {
+                let result: u64 = ((x.x as u64 & 0x03FF_FFFF) << 38)
+                    | ((x.z as u64 & 0x03FF_FFFF) << 12)
+                    | (x.y as u64 & 0xFFF);
+
+                self.push_u64(result);
+            }
+        }
+    }
+
     fn push_bool(&mut self, x: bool) {
         if x {
             self.push_u8(1);
@@ -116,10 +176,14 @@ impl McTypeWrite for BytesMut {
     }
 
     fn push_slot(&mut self, slot: Option<ItemStack>) {
+        self.push_slot_for(slot, ProtocolVersion::V1_13_2);
+    }
+
+    fn push_slot_for(&mut self, slot: Option<ItemStack>, version: ProtocolVersion) {
         self.push_bool(slot.is_some());
 
         if let Some(slot) = slot.as_ref() {
-            self.push_var_int(slot.ty.vanilla_id() as i32);
+            self.push_var_int(mappings::item_id(slot.ty, version));
             self.push_i8(slot.amount as i8);
             let tags: ItemNbt = slot.into();
 
@@ -158,6 +222,28 @@ impl<B: Buf + Read> McTypeRead for B {
         Ok(result)
     }
 
+    fn try_get_var_long(&mut self) -> Result<i64, TryGetError> {
+        let mut num_read = 0;
+        let mut result = 0;
+        loop {
+            if self.remaining() == 0 {
+                return Err(TryGetError::NotEnoughBytes);
+            }
+            let read = self.try_get_u8()?;
+            let value = i64::from(read & 0b0111_1111);
+            result |= value.overflowing_shl(7u32 * num_read).0;
+
+            num_read += 1;
+            if num_read > 10 {
+                return Err(TryGetError::NotEnoughBytes);
+            }
+            if read & 0b1000_0000 == 0 {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
     /// Reads a string from the object.
     fn try_get_string(&mut self) -> Result<String, TryGetError> {
         let len = self.try_get_var_int();
@@ -192,6 +278,23 @@ impl<B: Buf + Read> McTypeRead for B {
         Ok(BlockPosition::new(x as i32, y as i32, z as i32))
     }
 
+    fn try_get_position_for(
+        &mut self,
+        version: ProtocolVersion,
+    ) -> Result<BlockPosition, TryGetError> {
+        match version {
+            ProtocolVersion::V1_13_2 => self.try_get_position(),
+            ProtocolVersion::V1_16_5 | ProtocolVersion::V1_18_1 => {
+                let val = self.try_get_i64()?;
+                let x = val >> 38;
+                let y = val << 52 >> 52;
+                let z = val << 26 >> 38;
+
+                Ok(BlockPosition::new(x as i32, y as i32, z as i32))
+            }
+        }
+    }
+
     fn try_get_bool(&mut self) -> Result<bool, TryGetError> {
         let byte = self.try_get_i8()?;
         match byte {
@@ -212,6 +315,13 @@ impl<B: Buf + Read> McTypeRead for B {
     }
 
     fn try_get_slot(&mut self) -> Result<Option<ItemStack>, TryGetError> {
+        self.try_get_slot_for(ProtocolVersion::V1_13_2)
+    }
+
+    fn try_get_slot_for(
+        &mut self,
+        version: ProtocolVersion,
+    ) -> Result<Option<ItemStack>, TryGetError> {
         let present = self.try_get_bool()?;
 
         if !present {
@@ -219,20 +329,21 @@ impl<B: Buf + Read> McTypeRead for B {
         }
 
         let id = self.try_get_var_int()?;
-        let ty = Item::from_vanilla_id(id as u32).ok_or(TryGetError::InvalidValue(id))?;
+        let ty = mappings::item_from_id(id, version).ok_or(TryGetError::InvalidValue(id))?;
         let amount = self.try_get_i8()? as u8;
         let nbt: Option<ItemNbt> = self.try_get_nbt().ok();
 
-        Ok(Some(ItemStack {
-            ty,
-            amount,
-            damage: nbt.map(|t| t.damage).flatten(),
-        }))
+        Ok(Some(ItemNbt::item_stack(&nbt, ty, amount)))
     }
 }
 
 pub trait EntityMetaWrite {
     fn push_metadata(&mut self, meta: &EntityMetadata);
+
+    /// Writes entity metadata with the indices and IDs of the
+    /// given protocol version. Fields which don't exist in that
+    /// version are left out.
+    fn push_metadata_for(&mut self, meta: &EntityMetadata, version: ProtocolVersion);
 }
 
 pub trait EntityMetaRead {
@@ -244,10 +355,18 @@ where
     B: BytesMutExt + McTypeWrite,
 {
     fn push_metadata(&mut self, meta: &EntityMetadata) {
+        self.push_metadata_for(meta, ProtocolVersion::V1_13_2);
+    }
+
+    fn push_metadata_for(&mut self, meta: &EntityMetadata, version: ProtocolVersion) {
         for (index, entry) in meta.iter() {
+            let index = match mappings::metadata_index(meta.layout, index, version) {
+                Some(index) => index,
+                None => continue,
+            };
             self.push_u8(index);
             self.push_var_int(entry.id());
-            write_entry_to_buf(entry, self);
+            write_entry_to_buf(entry, self, version);
         }
 
         self.push_u8(0xff); // End of metadata
@@ -272,11 +391,14 @@ where
             values.insert(index, entry);
         }
 
-        Ok(EntityMetadata { values })
+        Ok(EntityMetadata {
+            values,
+            ..Default::default()
+        })
     }
 }
 
-fn write_entry_to_buf<B>(entry: &MetaEntry, buf: &mut B)
+fn write_entry_to_buf<B>(entry: &MetaEntry, buf: &mut B, version: ProtocolVersion)
 where
     B: BytesMutExt + McTypeWrite,
 {
@@ -297,7 +419,7 @@ where
             }
         }
         MetaEntry::Slot(slot) => {
-            buf.push_slot(*slot);
+            buf.push_slot_for(*slot, version);
         }
         MetaEntry::Boolean(x) => buf.push_bool(*x),
         MetaEntry::Rotation(x, y, z) => {
@@ -305,11 +427,11 @@ where
             buf.push_f32(*y);
             buf.push_f32(*z);
         }
-        MetaEntry::Position(x) => buf.push_position(x),
+        MetaEntry::Position(x) => buf.push_position_for(x, version),
         MetaEntry::OptPosition(ox) => {
             if let Some(x) = ox {
                 buf.push_bool(true);
-                buf.push_position(x);
+                buf.push_position_for(x, version);
             } else {
                 buf.push_bool(false);
             }
@@ -327,7 +449,7 @@ where
         }
         MetaEntry::OptBlockId(ox) => {
             if let Some(x) = ox {
-                buf.push_var_int(*x);
+                buf.push_var_int(i32::from(mappings::vanilla_block_id(*x as u16, version)));
             } else {
                 buf.push_var_int(0); // No value implies air
             }
@@ -384,6 +506,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use feather_entity_metadata::{model, Metadata};
     use std::io::Cursor;
 
     #[test]
@@ -393,4 +516,84 @@ mod tests {
         buf.extend_from_slice(&[0xff, 0x01]);
         assert_eq!(Cursor::new(&buf).try_get_var_int(), Ok(255));
     }
+
+    #[test]
+    fn test_var_long() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x07]);
+        assert_eq!(Cursor::new(&buf).try_get_var_long(), Ok(2_147_483_647));
+
+        let mut buf = BytesMut::new();
+        assert_eq!(buf.push_var_long(-1), 10);
+        assert_eq!(Cursor::new(&buf).try_get_var_long(), Ok(-1));
+    }
+
+    #[test]
+    fn test_position_for() {
+        let pos = BlockPosition::new(-1000, 70, 33_554_431);
+        for &version in ProtocolVersion::ALL.iter() {
+            let mut buf = BytesMut::new();
+            buf.push_position_for(&pos, version);
+            assert_eq!(Cursor::new(&buf).try_get_position_for(version), Ok(pos));
+        }
+
+        // Positions below Y 0 exist since 1.17.
+        let below = BlockPosition::new(5, -60, -5);
+        let mut buf = BytesMut::new();
+        buf.push_position_for(&below, ProtocolVersion::V1_18_1);
+        assert_eq!(
+            Cursor::new(&buf).try_get_position_for(ProtocolVersion::V1_18_1),
+            Ok(below)
+        );
+
+        // Example from wiki.vg
+        let mut buf = BytesMut::new();
+        buf.push_position_for(
+            &BlockPosition::new(18357644, 831, -20882616),
+            ProtocolVersion::V1_16_5,
+        );
+        assert_eq!(
+            buf.as_ref(),
+            &[0x46, 0x07, 0x63, 0x2c, 0x15, 0xb4, 0x83, 0x3f]
+        );
+    }
+
+    #[test]
+    fn test_metadata_for() {
+        // Living entities gained fields since 1.13.2, and
+        // zombies no longer have their hands held up.
+        let meta = Metadata::Zombie(model::Zombie::default()).to_full_raw_metadata();
+        let expected = [
+            (ProtocolVersion::V1_13_2, (0..=15).collect::<Vec<u8>>()),
+            (
+                ProtocolVersion::V1_16_5,
+                vec![0, 1, 2, 3, 4, 5, 7, 8, 9, 10, 11, 14, 15, 16, 17],
+            ),
+            (
+                ProtocolVersion::V1_18_1,
+                vec![0, 1, 2, 3, 4, 5, 8, 9, 10, 11, 12, 15, 16, 17, 18],
+            ),
+        ];
+
+        for (version, indices) in expected.iter() {
+            let mut buf = BytesMut::new();
+            buf.push_metadata_for(&meta, *version);
+            let written = Cursor::new(&buf).try_get_metadata().unwrap();
+
+            let written_indices: Vec<u8> = written.iter().map(|(index, _)| index).collect();
+            assert_eq!(&written_indices, indices, "{:?}", version);
+            for (index, entry) in meta.iter() {
+                if let Some(index) = mappings::metadata_index(meta.layout, index, *version) {
+                    assert_eq!(written.get(index).as_ref(), Some(entry));
+                }
+            }
+        }
+
+        // The mob's bitmask follows the fields added to living entities.
+        let mut buf = BytesMut::new();
+        buf.push_metadata_for(&meta, ProtocolVersion::V1_16_5);
+        let written = Cursor::new(&buf).try_get_metadata().unwrap();
+        assert_eq!(written.get(14), Some(MetaEntry::Byte(0)));
+        assert_eq!(written.get(17), Some(MetaEntry::Boolean(false)));
+    }
 }
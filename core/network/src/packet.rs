@@ -3,6 +3,7 @@ use std::any::Any;
 use std::io::Cursor;
 
 use crate::packets::IMPL_MAP;
+use crate::ProtocolVersion;
 use ahash::AHashMap;
 use num_derive::{FromPrimitive, ToPrimitive};
 use once_cell::sync::Lazy;
@@ -28,6 +29,25 @@ where
 pub trait Packet: AsAny + IntoAny + Send + Sync + Any {
     fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()>;
     fn write_to(&self, buf: &mut BytesMut);
+
+    /// Reads this packet in the format of the given protocol version.
+    ///
+    /// Defaults to `read_from`, i.e. the 1.13.2 format.
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        _version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        self.read_from(buf)
+    }
+
+    /// Writes this packet in the format of the given protocol version.
+    ///
+    /// Defaults to `write_to`, i.e. the 1.13.2 format.
+    fn write_to_version(&self, buf: &mut BytesMut, _version: ProtocolVersion) {
+        self.write_to(buf)
+    }
+
     fn ty(&self) -> PacketType;
     fn ty_sized() -> PacketType
     where
@@ -167,6 +187,7 @@ pub enum PacketType {
     ChunkData,
     Effect,
     Particle,
+    UpdateLight,
     JoinGame,
     MapData,
     Entity,
@@ -192,6 +213,7 @@ pub enum PacketType {
     WorldBorder,
     Camera,
     HeldItemChangeClientbound,
+    UpdateViewPosition,
     DisplayScoreboard,
     EntityMetadata,
     AttachEntity,
@@ -205,6 +227,7 @@ pub enum PacketType {
     UpdateScore,
     SpawnPosition,
     TimeUpdate,
+    Title,
     StopSound,
     SoundEffect,
     PlayerListHeaderAndFooter,
@@ -215,6 +238,16 @@ pub enum PacketType {
     EntityEffect,
     DeclareRecipes,
     Tags,
+    // Since 1.14, replacing the trader list plugin message
+    TradeList,
+    // Since 1.17, replacing Title
+    ActionBar,
+    ClearTitles,
+    SetTitleSubtitle,
+    SetTitleText,
+    SetTitleTimes,
+    // Since 1.17, replacing CombatEvent
+    DeathCombatEvent,
 
     // Status
     Response,
@@ -460,6 +493,11 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::SpawnObject,
     );
 
+    m.insert(
+        PacketId(0x01, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::SpawnExperienceOrb,
+    );
+
     m.insert(
         PacketId(0x02, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::SpawnGlobalEntity,
@@ -485,6 +523,14 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::BlockAction,
     );
 
+    m.insert(
+        PacketId(0x10, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::TabCompleteClientbound,
+    );
+    m.insert(
+        PacketId(0x11, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::DeclareCommands,
+    );
     m.insert(
         PacketId(0x12, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::ConfirmTransactionClientbound,
@@ -501,12 +547,21 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketId(0x0E, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::ChatMessageClientbound,
     );
+    m.insert(
+        PacketId(0x0F, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::MultiBlockChange,
+    );
 
     m.insert(
         PacketId(0x17, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::SetSlot,
     );
 
+    m.insert(
+        PacketId(0x19, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::PluginMessageClientbound,
+    );
+
     m.insert(
         PacketId(0x1A, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::NamedSoundEffect,
@@ -572,6 +627,11 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::JoinGame,
     );
 
+    m.insert(
+        PacketId(0x26, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::MapData,
+    );
+
     m.insert(
         PacketId(0x28, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::EntityRelativeMove,
@@ -587,6 +647,16 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::EntityLook,
     );
 
+    m.insert(
+        PacketId(0x2C, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::OpenSignEditor,
+    );
+
+    m.insert(
+        PacketId(0x2F, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::CombatEvent,
+    );
+
     m.insert(
         PacketId(0x30, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::PlayerInfo,
@@ -602,6 +672,11 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::DestroyEntities,
     );
 
+    m.insert(
+        PacketId(0x36, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::RemoveEntityEffect,
+    );
+
     m.insert(
         PacketId(0x37, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::ResourcePackSend,
@@ -617,16 +692,36 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::EntityHeadLook,
     );
 
+    m.insert(
+        PacketId(0x3A, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::SelectAdvancementTab,
+    );
+
+    m.insert(
+        PacketId(0x3B, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::WorldBorder,
+    );
+
     m.insert(
         PacketId(0x3D, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::HeldItemChangeClientbound,
     );
 
+    m.insert(
+        PacketId(0x3E, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::DisplayScoreboard,
+    );
+
     m.insert(
         PacketId(0x3F, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::EntityMetadata,
     );
 
+    m.insert(
+        PacketId(0x40, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::AttachEntity,
+    );
+
     m.insert(
         PacketId(0x41, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::EntityVelocity,
@@ -637,11 +732,36 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::EntityEquipment,
     );
 
+    m.insert(
+        PacketId(0x43, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::SetExperience,
+    );
+
     m.insert(
         PacketId(0x44, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::UpdateHealth,
     );
 
+    m.insert(
+        PacketId(0x45, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::ScoreboardObjective,
+    );
+
+    m.insert(
+        PacketId(0x46, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::SetPassengers,
+    );
+
+    m.insert(
+        PacketId(0x47, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::Teams,
+    );
+
+    m.insert(
+        PacketId(0x48, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::UpdateScore,
+    );
+
     m.insert(
         PacketId(0x49, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::SpawnPosition,
@@ -652,6 +772,16 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::TimeUpdate,
     );
 
+    m.insert(
+        PacketId(0x4B, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::Title,
+    );
+
+    m.insert(
+        PacketId(0x4E, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::PlayerListHeaderAndFooter,
+    );
+
     m.insert(
         PacketId(0x4F, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::CollectItem,
@@ -662,6 +792,16 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::EntityTeleport,
     );
 
+    m.insert(
+        PacketId(0x51, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::Advancements,
+    );
+
+    m.insert(
+        PacketId(0x53, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::EntityEffect,
+    );
+
     m.insert(
         PacketId(0x55, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::Tags,
@@ -677,6 +817,321 @@ static PACKET_TYPE_MAPPINGS: Lazy<AHashMap<PacketType, PacketId>> = Lazy::new(||
         m.insert(val, key);
     }
 
+    // Trade lists are sent as plugin messages before 1.14. They
+    // aren't decoded by ID, so they only appear in this direction.
+    m.insert(
+        PacketType::TradeList,
+        PacketId(0x19, PacketDirection::Clientbound, PacketStage::Play),
+    );
+
+    m
+});
+
+/// Packet IDs in 1.16.5.
+///
+/// Handshake, status and login packets have the same IDs as in
+/// 1.13.2. Play packets which 1.16.5 removed, or which can't be
+/// converted to 1.16.5 yet, are left out and never sent to or
+/// accepted from 1.16.5 clients.
+static PACKET_ID_MAPPINGS_1_16_5: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
+    let mut m = AHashMap::new();
+
+    for (id, ty) in PACKET_ID_MAPPINGS.iter() {
+        if id.2 != PacketStage::Play {
+            m.insert(*id, *ty);
+        }
+    }
+
+    let serverbound = [
+        (0x00, PacketType::TeleportConfirm),
+        (0x01, PacketType::QueryBlockNBT),
+        (0x03, PacketType::ChatMessageServerbound),
+        (0x04, PacketType::ClientStatus),
+        (0x05, PacketType::ClientSettings),
+        (0x06, PacketType::TabCompleteServerbound),
+        (0x07, PacketType::ConfirmTransactionServerbound),
+        (0x08, PacketType::EnchantItem),
+        (0x09, PacketType::ClickWindow),
+        (0x0A, PacketType::CloseWindowServerbound),
+        (0x0B, PacketType::PluginMessageServerbound),
+        (0x0C, PacketType::EditBook),
+        (0x0D, PacketType::QueryEntityNBT),
+        (0x0E, PacketType::UseEntity),
+        (0x10, PacketType::KeepAliveServerbound),
+        (0x12, PacketType::PlayerPosition),
+        (0x13, PacketType::PlayerPositionAndLookServerbound),
+        (0x14, PacketType::PlayerLook),
+        (0x15, PacketType::Player),
+        (0x16, PacketType::VehicleMoveServerbound),
+        (0x17, PacketType::SteerBoat),
+        (0x18, PacketType::PickItem),
+        (0x19, PacketType::CraftRecipeRequest),
+        (0x1A, PacketType::PlayerAbilitiesServerbound),
+        (0x1B, PacketType::PlayerDigging),
+        (0x1C, PacketType::EntityAction),
+        (0x1D, PacketType::SteerVehicle),
+        (0x20, PacketType::NameItem),
+        (0x21, PacketType::ResourcePackStatus),
+        (0x22, PacketType::AdvancementTab),
+        (0x23, PacketType::SelectTrade),
+        (0x24, PacketType::SetBeaconEffect),
+        (0x25, PacketType::HeldItemChangeServerbound),
+        (0x26, PacketType::UpdateCommandBlock),
+        (0x27, PacketType::UpdateCommandBlockMinecart),
+        (0x28, PacketType::CreativeInventoryAction),
+        (0x2B, PacketType::UpdateSign),
+        (0x2C, PacketType::AnimationServerbound),
+        (0x2D, PacketType::Spectate),
+        (0x2E, PacketType::PlayerBlockPlacement),
+        (0x2F, PacketType::UseItem),
+    ];
+    let clientbound = [
+        (0x00, PacketType::SpawnObject),
+        (0x01, PacketType::SpawnExperienceOrb),
+        (0x02, PacketType::SpawnMob),
+        (0x04, PacketType::SpawnPlayer),
+        (0x05, PacketType::AnimationClientbound),
+        (0x08, PacketType::BlockBreakAnimation),
+        (0x09, PacketType::UpdateBlockEntity),
+        (0x0A, PacketType::BlockAction),
+        (0x0B, PacketType::BlockChange),
+        (0x0E, PacketType::ChatMessageClientbound),
+        (0x0F, PacketType::TabCompleteClientbound),
+        (0x10, PacketType::DeclareCommands),
+        (0x11, PacketType::ConfirmTransactionClientbound),
+        (0x13, PacketType::WindowItems),
+        (0x15, PacketType::SetSlot),
+        (0x17, PacketType::PluginMessageClientbound),
+        (0x18, PacketType::NamedSoundEffect),
+        (0x19, PacketType::DisconnectPlay),
+        (0x1A, PacketType::EntityStatus),
+        (0x1C, PacketType::UnloadChunk),
+        (0x1D, PacketType::ChangeGameState),
+        (0x1F, PacketType::KeepAliveClientbound),
+        (0x20, PacketType::ChunkData),
+        (0x21, PacketType::Effect),
+        (0x22, PacketType::Particle),
+        (0x23, PacketType::UpdateLight),
+        (0x24, PacketType::JoinGame),
+        (0x25, PacketType::MapData),
+        (0x26, PacketType::TradeList),
+        (0x27, PacketType::EntityRelativeMove),
+        (0x28, PacketType::EntityLookAndRelativeMove),
+        (0x29, PacketType::EntityLook),
+        (0x2D, PacketType::OpenWindow),
+        (0x2E, PacketType::OpenSignEditor),
+        (0x31, PacketType::CombatEvent),
+        (0x32, PacketType::PlayerInfo),
+        (0x34, PacketType::PlayerPositionAndLookClientbound),
+        (0x36, PacketType::DestroyEntities),
+        (0x37, PacketType::RemoveEntityEffect),
+        (0x38, PacketType::ResourcePackSend),
+        (0x39, PacketType::Respawn),
+        (0x3A, PacketType::EntityHeadLook),
+        (0x3B, PacketType::MultiBlockChange),
+        (0x3C, PacketType::SelectAdvancementTab),
+        (0x3D, PacketType::WorldBorder),
+        (0x3F, PacketType::HeldItemChangeClientbound),
+        (0x40, PacketType::UpdateViewPosition),
+        (0x42, PacketType::SpawnPosition),
+        (0x43, PacketType::DisplayScoreboard),
+        (0x44, PacketType::EntityMetadata),
+        (0x45, PacketType::AttachEntity),
+        (0x46, PacketType::EntityVelocity),
+        (0x47, PacketType::EntityEquipment),
+        (0x48, PacketType::SetExperience),
+        (0x49, PacketType::UpdateHealth),
+        (0x4A, PacketType::ScoreboardObjective),
+        (0x4B, PacketType::SetPassengers),
+        (0x4C, PacketType::Teams),
+        (0x4D, PacketType::UpdateScore),
+        (0x4E, PacketType::TimeUpdate),
+        (0x4F, PacketType::Title),
+        (0x53, PacketType::PlayerListHeaderAndFooter),
+        (0x55, PacketType::CollectItem),
+        (0x56, PacketType::EntityTeleport),
+        (0x57, PacketType::Advancements),
+        (0x59, PacketType::EntityEffect),
+        (0x5B, PacketType::Tags),
+    ];
+
+    for (id, ty) in serverbound.iter() {
+        m.insert(
+            PacketId(*id, PacketDirection::Serverbound, PacketStage::Play),
+            *ty,
+        );
+    }
+    for (id, ty) in clientbound.iter() {
+        m.insert(
+            PacketId(*id, PacketDirection::Clientbound, PacketStage::Play),
+            *ty,
+        );
+    }
+
+    m
+});
+
+static PACKET_TYPE_MAPPINGS_1_16_5: Lazy<AHashMap<PacketType, PacketId>> = Lazy::new(|| {
+    let mut m = AHashMap::new();
+
+    for (key, val) in PACKET_ID_MAPPINGS_1_16_5.iter() {
+        m.insert(*val, *key);
+    }
+
+    m
+});
+
+/// Packet IDs in 1.18.1.
+///
+/// As for 1.16.5, only play packets differ from 1.13.2, and those
+/// which can't be converted are left out. Among them are confirm
+/// transaction packets, which were removed in 1.17, and world border
+/// packets, which were split into a packet per action.
+static PACKET_ID_MAPPINGS_1_18_1: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
+    let mut m = AHashMap::new();
+
+    for (id, ty) in PACKET_ID_MAPPINGS.iter() {
+        if id.2 != PacketStage::Play {
+            m.insert(*id, *ty);
+        }
+    }
+
+    let serverbound = [
+        (0x00, PacketType::TeleportConfirm),
+        (0x01, PacketType::QueryBlockNBT),
+        (0x03, PacketType::ChatMessageServerbound),
+        (0x04, PacketType::ClientStatus),
+        (0x05, PacketType::ClientSettings),
+        (0x06, PacketType::TabCompleteServerbound),
+        (0x07, PacketType::EnchantItem),
+        (0x08, PacketType::ClickWindow),
+        (0x09, PacketType::CloseWindowServerbound),
+        (0x0A, PacketType::PluginMessageServerbound),
+        (0x0C, PacketType::QueryEntityNBT),
+        (0x0D, PacketType::UseEntity),
+        (0x0F, PacketType::KeepAliveServerbound),
+        (0x11, PacketType::PlayerPosition),
+        (0x12, PacketType::PlayerPositionAndLookServerbound),
+        (0x13, PacketType::PlayerLook),
+        (0x14, PacketType::Player),
+        (0x15, PacketType::VehicleMoveServerbound),
+        (0x16, PacketType::SteerBoat),
+        (0x17, PacketType::PickItem),
+        (0x18, PacketType::CraftRecipeRequest),
+        (0x19, PacketType::PlayerAbilitiesServerbound),
+        (0x1A, PacketType::PlayerDigging),
+        (0x1B, PacketType::EntityAction),
+        (0x1C, PacketType::SteerVehicle),
+        (0x20, PacketType::NameItem),
+        (0x21, PacketType::ResourcePackStatus),
+        (0x22, PacketType::AdvancementTab),
+        (0x23, PacketType::SelectTrade),
+        (0x24, PacketType::SetBeaconEffect),
+        (0x25, PacketType::HeldItemChangeServerbound),
+        (0x26, PacketType::UpdateCommandBlock),
+        (0x27, PacketType::UpdateCommandBlockMinecart),
+        (0x28, PacketType::CreativeInventoryAction),
+        (0x2B, PacketType::UpdateSign),
+        (0x2C, PacketType::AnimationServerbound),
+        (0x2D, PacketType::Spectate),
+        (0x2E, PacketType::PlayerBlockPlacement),
+        (0x2F, PacketType::UseItem),
+    ];
+    let clientbound = [
+        (0x00, PacketType::SpawnObject),
+        (0x01, PacketType::SpawnExperienceOrb),
+        (0x02, PacketType::SpawnMob),
+        (0x04, PacketType::SpawnPlayer),
+        (0x06, PacketType::AnimationClientbound),
+        (0x09, PacketType::BlockBreakAnimation),
+        (0x0A, PacketType::UpdateBlockEntity),
+        (0x0B, PacketType::BlockAction),
+        (0x0C, PacketType::BlockChange),
+        (0x0F, PacketType::ChatMessageClientbound),
+        (0x10, PacketType::ClearTitles),
+        (0x11, PacketType::TabCompleteClientbound),
+        (0x12, PacketType::DeclareCommands),
+        (0x14, PacketType::WindowItems),
+        (0x16, PacketType::SetSlot),
+        (0x18, PacketType::PluginMessageClientbound),
+        (0x19, PacketType::NamedSoundEffect),
+        (0x1A, PacketType::DisconnectPlay),
+        (0x1B, PacketType::EntityStatus),
+        (0x1D, PacketType::UnloadChunk),
+        (0x1E, PacketType::ChangeGameState),
+        (0x21, PacketType::KeepAliveClientbound),
+        (0x22, PacketType::ChunkData),
+        (0x23, PacketType::Effect),
+        (0x24, PacketType::Particle),
+        (0x25, PacketType::UpdateLight),
+        (0x26, PacketType::JoinGame),
+        (0x27, PacketType::MapData),
+        (0x28, PacketType::TradeList),
+        (0x29, PacketType::EntityRelativeMove),
+        (0x2A, PacketType::EntityLookAndRelativeMove),
+        (0x2B, PacketType::EntityLook),
+        (0x2E, PacketType::OpenWindow),
+        (0x2F, PacketType::OpenSignEditor),
+        (0x35, PacketType::DeathCombatEvent),
+        (0x36, PacketType::PlayerInfo),
+        (0x38, PacketType::PlayerPositionAndLookClientbound),
+        (0x3A, PacketType::DestroyEntities),
+        (0x3B, PacketType::RemoveEntityEffect),
+        (0x3C, PacketType::ResourcePackSend),
+        (0x3D, PacketType::Respawn),
+        (0x3E, PacketType::EntityHeadLook),
+        (0x3F, PacketType::MultiBlockChange),
+        (0x40, PacketType::SelectAdvancementTab),
+        (0x41, PacketType::ActionBar),
+        (0x48, PacketType::HeldItemChangeClientbound),
+        (0x49, PacketType::UpdateViewPosition),
+        (0x4B, PacketType::SpawnPosition),
+        (0x4C, PacketType::DisplayScoreboard),
+        (0x4D, PacketType::EntityMetadata),
+        (0x4E, PacketType::AttachEntity),
+        (0x4F, PacketType::EntityVelocity),
+        (0x50, PacketType::EntityEquipment),
+        (0x51, PacketType::SetExperience),
+        (0x52, PacketType::UpdateHealth),
+        (0x53, PacketType::ScoreboardObjective),
+        (0x54, PacketType::SetPassengers),
+        (0x55, PacketType::Teams),
+        (0x56, PacketType::UpdateScore),
+        (0x58, PacketType::SetTitleSubtitle),
+        (0x59, PacketType::TimeUpdate),
+        (0x5A, PacketType::SetTitleText),
+        (0x5B, PacketType::SetTitleTimes),
+        (0x5F, PacketType::PlayerListHeaderAndFooter),
+        (0x61, PacketType::CollectItem),
+        (0x62, PacketType::EntityTeleport),
+        (0x63, PacketType::Advancements),
+        (0x65, PacketType::EntityEffect),
+        (0x67, PacketType::Tags),
+    ];
+
+    for (id, ty) in serverbound.iter() {
+        m.insert(
+            PacketId(*id, PacketDirection::Serverbound, PacketStage::Play),
+            *ty,
+        );
+    }
+    for (id, ty) in clientbound.iter() {
+        m.insert(
+            PacketId(*id, PacketDirection::Clientbound, PacketStage::Play),
+            *ty,
+        );
+    }
+
+    m
+});
+
+static PACKET_TYPE_MAPPINGS_1_18_1: Lazy<AHashMap<PacketType, PacketId>> = Lazy::new(|| {
+    let mut m = AHashMap::new();
+
+    for (key, val) in PACKET_ID_MAPPINGS_1_18_1.iter() {
+        m.insert(*val, *key);
+    }
+
     m
 });
 
@@ -689,6 +1144,25 @@ impl PacketType {
         *PACKET_TYPE_MAPPINGS.get(&self).unwrap_or_else(|| panic!("failed to find packet ID for packet type {:?} (try inserting it into the ID map in core/network/packet.rs)", self))
     }
 
+    /// Returns the packet type with the given ID in the given protocol version.
+    pub fn get_from_id_for(id: PacketId, version: ProtocolVersion) -> Result<PacketType, ()> {
+        match version {
+            ProtocolVersion::V1_13_2 => Self::get_from_id(id),
+            ProtocolVersion::V1_16_5 => PACKET_ID_MAPPINGS_1_16_5.get(&id).copied().ok_or(()),
+            ProtocolVersion::V1_18_1 => PACKET_ID_MAPPINGS_1_18_1.get(&id).copied().ok_or(()),
+        }
+    }
+
+    /// Returns the ID of this packet type in the given protocol
+    /// version, or `None` if the version has no such packet.
+    pub fn get_id_for(self, version: ProtocolVersion) -> Option<PacketId> {
+        match version {
+            ProtocolVersion::V1_13_2 => PACKET_TYPE_MAPPINGS.get(&self).copied(),
+            ProtocolVersion::V1_16_5 => PACKET_TYPE_MAPPINGS_1_16_5.get(&self).copied(),
+            ProtocolVersion::V1_18_1 => PACKET_TYPE_MAPPINGS_1_18_1.get(&self).copied(),
+        }
+    }
+
     pub fn get_implementation(self) -> Box<dyn Packet> {
         IMPL_MAP.get(&self).unwrap().build()
     }
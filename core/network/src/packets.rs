@@ -1,11 +1,13 @@
 use crate::bytes_ext::{BytesExt, BytesMutExt};
 use crate::mctypes::{EntityMetaRead, EntityMetaWrite, McTypeRead, McTypeWrite};
 use crate::packet::{AsAny, PacketBuilder};
-use crate::{Packet, PacketType};
+use crate::{mappings, Packet, PacketType, ProtocolVersion};
 use ahash::AHashMap;
 use bytes::{Buf, BufMut, BytesMut};
-use feather_blocks::{FacingCardinal, FacingCardinalAndDown, FacingCubic};
-use feather_chunk::Chunk;
+use feather_biomes::BiomeArrayFormat;
+use feather_blocks::{BlockId, FacingCardinal, FacingCardinalAndDown, FacingCubic};
+use feather_chunk::light::LightData;
+use feather_chunk::{Chunk, ChunkSection, NUM_SECTIONS, SECTION_HEIGHT, SECTION_VOLUME};
 use feather_codegen::{AsAny, Packet};
 use feather_entity_metadata::EntityMetadata;
 use feather_items::ItemStack;
@@ -60,6 +62,10 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         PacketType::EncryptionResponse,
         PacketBuilder::with(|| Box::new(EncryptionResponse::default())),
     );
+    m.insert(
+        PacketType::LoginPluginResponse,
+        PacketBuilder::with(|| Box::new(LoginPluginResponse::default())),
+    );
 
     m.insert(
         PacketType::Request,
@@ -265,6 +271,7 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         EncryptionRequest,
         LoginSuccess,
         SetCompression,
+        LoginPluginRequest,
         SpawnObject,
         SpawnExperienceOrb,
         SpawnGlobalEntity,
@@ -280,12 +287,16 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         BossBar,
         ServerDifficulty,
         ChatMessageClientbound,
+        MultiBlockChange,
+        TabCompleteClientbound,
+        DeclareCommands,
         OpenWindow,
         WindowItems,
         WindowProperty,
         SetSlot,
         SetCooldown,
         PluginMessageClientbound,
+        TradeList,
         NamedSoundEffect,
         DisconnectPlay,
         EntityStatus,
@@ -295,9 +306,11 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         ChangeGameState,
         KeepAliveClientbound,
         ChunkData,
+        UpdateLight,
         Effect,
         Particle,
         JoinGame,
+        MapData,
         EntityRelativeMove,
         EntityLookAndRelativeMove,
         EntityLook,
@@ -313,14 +326,34 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         ResourcePackSend,
         Respawn,
         EntityHeadLook,
+        WorldBorder,
+        AttachEntity,
         EntityVelocity,
         EntityEquipment,
         HeldItemChangeClientbound,
+        UpdateViewPosition,
+        SetExperience,
         UpdateHealth,
+        DisplayScoreboard,
+        ScoreboardObjective,
+        SetPassengers,
+        Teams,
+        UpdateScore,
+        SelectAdvancementTab,
+        Title,
+        ActionBar,
+        ClearTitles,
+        SetTitleSubtitle,
+        SetTitleText,
+        SetTitleTimes,
+        DeathCombatEvent,
         SpawnPosition,
         TimeUpdate,
+        PlayerListHeaderAndFooter,
         CollectItem,
         EntityTeleport,
+        Advancements,
+        EntityEffect,
         Tags,
         Response,
         Pong,
@@ -351,6 +384,22 @@ pub enum Error {
     InsufficientArrayLength,
     #[error("invalid handshake next state {0}")]
     InvalidHandshakeState(i32),
+    #[error("invalid command node type {0}")]
+    InvalidCommandNodeType(u8),
+    #[error("unsupported command argument parser")]
+    UnsupportedArgumentParser,
+    #[error("invalid world border action {0}")]
+    InvalidWorldBorderAction(i32),
+    #[error("invalid teams mode {0}")]
+    InvalidTeamsMode(i8),
+    #[error("invalid scoreboard objective mode {0}")]
+    InvalidObjectiveMode(i8),
+    #[error("invalid objective render type {0}")]
+    InvalidObjectiveRenderType(i32),
+    #[error("invalid update score action {0}")]
+    InvalidUpdateScoreAction(i8),
+    #[error("invalid title action {0}")]
+    InvalidTitleAction(i32),
 }
 
 // SERVERBOUND
@@ -476,6 +525,48 @@ impl Packet for EncryptionResponse {
     }
 }
 
+/// A client's response to a `LoginPluginRequest`.
+#[derive(Default, AsAny, Clone)]
+pub struct LoginPluginResponse {
+    /// The ID of the request this responds to.
+    pub message_id: VarInt,
+    /// Whether the client understood the request.
+    pub successful: bool,
+    /// The response data, empty if unsuccessful.
+    pub data: Vec<u8>,
+}
+
+impl Packet for LoginPluginResponse {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.message_id = buf.try_get_var_int()?;
+        self.successful = buf.try_get_bool()?;
+        buf.read_to_end(&mut self.data)?;
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.message_id);
+        buf.push_bool(self.successful);
+        buf.put(self.data.as_slice());
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::LoginPluginResponse
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::LoginPluginResponse
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct Request {}
 
@@ -535,16 +626,79 @@ pub struct EnchantItem {
     pub enchantment: u8,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct ClickWindow {
     pub window_id: u8,
     pub slot: i16,
     pub button: u8,
+    /// Unset since 1.17, which removed transactions.
     pub action_number: i16,
     pub mode: VarInt,
+    /// The item in the clicked slot. Since 1.17, this is
+    /// the item carried by the cursor after the click.
     pub clicked_item: Slot,
 }
 
+impl Packet for ClickWindow {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.read_from_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_u8(self.window_id);
+        buf.push_i16(self.slot);
+        buf.push_u8(self.button);
+        buf.push_i16(self.action_number);
+        buf.push_var_int(self.mode);
+        buf.push_slot(self.clicked_item);
+    }
+
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        self.window_id = buf.try_get_u8()?;
+        if version < ProtocolVersion::V1_18_1 {
+            self.slot = buf.try_get_i16()?;
+            self.button = buf.try_get_u8()?;
+            self.action_number = buf.try_get_i16()?;
+            self.mode = buf.try_get_var_int()?;
+            self.clicked_item = buf.try_get_slot_for(version)?;
+            return Ok(());
+        }
+
+        buf.try_get_var_int()?; // State ID
+        self.slot = buf.try_get_i16()?;
+        self.button = buf.try_get_u8()?;
+        self.mode = buf.try_get_var_int()?;
+        // The slots changed by the click, which are
+        // recomputed by the server.
+        let changed = buf.try_get_var_int()?;
+        for _ in 0..changed {
+            buf.try_get_i16()?;
+            buf.try_get_slot_for(version)?;
+        }
+        self.clicked_item = buf.try_get_slot_for(version)?;
+        Ok(())
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::ClickWindow
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::ClickWindow
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct CloseWindowServerbound {
     pub window_id: u8,
@@ -561,7 +715,7 @@ impl Packet for PluginMessageServerbound {
         self.channel = buf.try_get_string()?;
 
         let mut data = Vec::with_capacity(buf.remaining());
-        buf.read(&mut data)
+        buf.read_to_end(&mut data)
             .map_err(|_| Error::InsufficientArrayLength)?;
         self.data = data;
 
@@ -739,13 +893,54 @@ pub struct CraftRecipeRequest {
     pub make_all: bool,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct PlayerAbilitiesServerbound {
     pub flags: u8,
     pub flying_speed: f32,
     pub walking_speed: f32,
 }
 
+impl Packet for PlayerAbilitiesServerbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.read_from_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_u8(self.flags);
+        buf.push_f32(self.flying_speed);
+        buf.push_f32(self.walking_speed);
+    }
+
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        self.flags = buf.try_get_u8()?;
+        // Speeds are no longer sent since 1.16.
+        if version == ProtocolVersion::V1_13_2 {
+            self.flying_speed = buf.try_get_f32()?;
+            self.walking_speed = buf.try_get_f32()?;
+        }
+        Ok(())
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::PlayerAbilitiesServerbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::PlayerAbilitiesServerbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(AsAny, Clone, Default)]
 pub struct PlayerDigging {
     pub status: PlayerDiggingStatus,
@@ -755,6 +950,14 @@ pub struct PlayerDigging {
 
 impl Packet for PlayerDigging {
     fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.read_from_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
         self.status = {
             let id = buf.try_get_var_int()?;
             match id {
@@ -769,7 +972,7 @@ impl Packet for PlayerDigging {
             }
         };
 
-        self.location = buf.try_get_position()?;
+        self.location = buf.try_get_position_for(version)?;
         self.face = buf.try_get_i8()?;
 
         Ok(())
@@ -1100,13 +1303,30 @@ pub struct PlayerBlockPlacement {
 
 impl Packet for PlayerBlockPlacement {
     fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
-        self.location = buf.try_get_position()?;
+        self.read_from_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        // The hand was moved to the front in 1.14.
+        if version != ProtocolVersion::V1_13_2 {
+            self.hand = buf.try_get_var_int()?;
+        }
+        self.location = buf.try_get_position_for(version)?;
         let face_id = buf.try_get_var_int()?;
         self.face = Face::from_i32(face_id).ok_or(Error::InvalidFace(face_id))?;
-        self.hand = buf.try_get_var_int()?;
+        if version == ProtocolVersion::V1_13_2 {
+            self.hand = buf.try_get_var_int()?;
+        }
         self.cursor_position_x = buf.try_get_f32()?;
         self.cursor_position_y = buf.try_get_f32()?;
         self.cursor_position_z = buf.try_get_f32()?;
+        if version != ProtocolVersion::V1_13_2 {
+            let _inside_block = buf.try_get_bool()?;
+        }
         Ok(())
     }
 
@@ -1196,17 +1416,94 @@ impl Packet for EncryptionRequest {
     }
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct LoginSuccess {
     pub uuid: String,
     pub username: String,
 }
 
+impl Packet for LoginSuccess {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.uuid = buf.try_get_string()?;
+        self.username = buf.try_get_string()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        match version {
+            ProtocolVersion::V1_13_2 => buf.push_string(&self.uuid),
+            // Sent as a binary UUID since 1.16.
+            _ => buf.push_uuid(&Uuid::parse_str(&self.uuid).unwrap_or_else(|_| Uuid::nil())),
+        }
+        buf.push_string(&self.username);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::LoginSuccess
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::LoginSuccess
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct SetCompression {
     pub threshold: VarInt,
 }
 
+/// A plugin message sent during login, to which the
+/// client replies with a `LoginPluginResponse`. Used
+/// by proxies to forward player information.
+#[derive(Default, AsAny, Clone)]
+pub struct LoginPluginRequest {
+    pub message_id: VarInt,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl Packet for LoginPluginRequest {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.message_id = buf.try_get_var_int()?;
+        self.channel = buf.try_get_string()?;
+        buf.read_to_end(&mut self.data)?;
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.message_id);
+        buf.push_string(&self.channel);
+        buf.put(self.data.as_slice());
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::LoginPluginRequest
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::LoginPluginRequest
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct Response {
     pub json_response: String,
@@ -1219,7 +1516,7 @@ pub struct Pong {
 
 // PLAY
 #[allow(clippy::too_many_arguments)]
-#[derive(Default, AsAny, Packet, Clone, Debug)]
+#[derive(Default, AsAny, Clone, Debug)]
 pub struct SpawnObject {
     pub entity_id: VarInt,
     pub object_uuid: Uuid,
@@ -1235,6 +1532,62 @@ pub struct SpawnObject {
     pub velocity_z: i16,
 }
 
+impl Packet for SpawnObject {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_id = buf.try_get_var_int()?;
+        self.object_uuid = buf.try_get_uuid()?;
+        self.ty = buf.try_get_i8()?;
+        self.x = buf.try_get_f64()?;
+        self.y = buf.try_get_f64()?;
+        self.z = buf.try_get_f64()?;
+        self.pitch = buf.try_get_u8()?;
+        self.yaw = buf.try_get_u8()?;
+        self.data = buf.try_get_i32()?;
+        self.velocity_x = buf.try_get_i16()?;
+        self.velocity_y = buf.try_get_i16()?;
+        self.velocity_z = buf.try_get_i16()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_var_int(self.entity_id);
+        buf.push_uuid(&self.object_uuid);
+        match version {
+            ProtocolVersion::V1_13_2 => buf.push_i8(self.ty),
+            // Objects are identified by their entity type since 1.14.
+            _ => buf.push_var_int(mappings::object_type_id(self.ty, version).unwrap_or_default()),
+        }
+        buf.push_f64(self.x);
+        buf.push_f64(self.y);
+        buf.push_f64(self.z);
+        buf.push_u8(self.pitch);
+        buf.push_u8(self.yaw);
+        buf.push_i32(self.data);
+        buf.push_i16(self.velocity_x);
+        buf.push_i16(self.velocity_y);
+        buf.push_i16(self.velocity_z);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SpawnObject
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SpawnObject
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct SpawnExperienceOrb {
     pub entity_id: VarInt,
@@ -1254,7 +1607,7 @@ pub struct SpawnGlobalEntity {
 }
 
 #[allow(clippy::too_many_arguments)]
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct SpawnMob {
     pub entity_id: VarInt,
     pub entity_uuid: Uuid,
@@ -1271,6 +1624,63 @@ pub struct SpawnMob {
     pub meta: EntityMetadata,
 }
 
+impl Packet for SpawnMob {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_id = buf.try_get_var_int()?;
+        self.entity_uuid = buf.try_get_uuid()?;
+        self.ty = buf.try_get_var_int()?;
+        self.x = buf.try_get_f64()?;
+        self.y = buf.try_get_f64()?;
+        self.z = buf.try_get_f64()?;
+        self.yaw = buf.try_get_u8()?;
+        self.pitch = buf.try_get_u8()?;
+        self.head_pitch = buf.try_get_u8()?;
+        self.velocity_x = buf.try_get_i16()?;
+        self.velocity_y = buf.try_get_i16()?;
+        self.velocity_z = buf.try_get_i16()?;
+        self.meta = buf.try_get_metadata()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_var_int(self.entity_id);
+        buf.push_uuid(&self.entity_uuid);
+        buf.push_var_int(mappings::entity_type_id(self.ty, version).unwrap_or_default());
+        buf.push_f64(self.x);
+        buf.push_f64(self.y);
+        buf.push_f64(self.z);
+        buf.push_u8(self.yaw);
+        buf.push_u8(self.pitch);
+        buf.push_u8(self.head_pitch);
+        buf.push_i16(self.velocity_x);
+        buf.push_i16(self.velocity_y);
+        buf.push_i16(self.velocity_z);
+        // Metadata is sent separately since 1.15.
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_metadata(&self.meta);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SpawnMob
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SpawnMob
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct SpawnPainting {
     pub entity_id: VarInt,
@@ -1281,7 +1691,7 @@ pub struct SpawnPainting {
 }
 
 #[allow(clippy::too_many_arguments)]
-#[derive(AsAny, Clone, Default, Packet)]
+#[derive(AsAny, Clone, Default)]
 pub struct SpawnPlayer {
     pub entity_id: VarInt,
     pub player_uuid: Uuid,
@@ -1293,6 +1703,53 @@ pub struct SpawnPlayer {
     pub metadata: EntityMetadata,
 }
 
+impl Packet for SpawnPlayer {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_id = buf.try_get_var_int()?;
+        self.player_uuid = buf.try_get_uuid()?;
+        self.x = buf.try_get_f64()?;
+        self.y = buf.try_get_f64()?;
+        self.z = buf.try_get_f64()?;
+        self.yaw = buf.try_get_u8()?;
+        self.pitch = buf.try_get_u8()?;
+        self.metadata = buf.try_get_metadata()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_var_int(self.entity_id);
+        buf.push_uuid(&self.player_uuid);
+        buf.push_f64(self.x);
+        buf.push_f64(self.y);
+        buf.push_f64(self.z);
+        buf.push_u8(self.yaw);
+        buf.push_u8(self.pitch);
+        // Metadata is sent separately since 1.15.
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_metadata(&self.metadata);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SpawnPlayer
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SpawnPlayer
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Clone)]
 pub struct AnimationClientbound {
     pub entity_id: VarInt,
@@ -1382,13 +1839,55 @@ pub struct BlockBreakAnimation {
     pub destroy_stage: i8,
 }
 
-#[derive(AsAny, Packet, Clone)]
+#[derive(AsAny, Clone)]
 pub struct UpdateBlockEntity {
     pub location: BlockPosition,
+    /// The kind of block entity updated, as in 1.13.2, e.g. 9 for signs.
     pub action: u8,
     pub data: Blob,
 }
 
+impl Packet for UpdateBlockEntity {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.location = buf.try_get_position()?;
+        self.action = buf.try_get_u8()?;
+        self.data = buf.try_get_nbt()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_position_for(&self.location, version);
+        // Block entities are identified by their type since 1.18.
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_var_int(
+                mappings::block_entity_type_id(self.action, version).unwrap_or_default(),
+            );
+        } else {
+            buf.push_u8(self.action);
+        }
+        buf.push_nbt(&self.data);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::UpdateBlockEntity
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::UpdateBlockEntity
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 impl Default for UpdateBlockEntity {
     fn default() -> Self {
         Self {
@@ -1399,7 +1898,7 @@ impl Default for UpdateBlockEntity {
     }
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct BlockAction {
     pub location: BlockPosition,
     pub action_id: u8,
@@ -1407,12 +1906,83 @@ pub struct BlockAction {
     pub block_type: VarInt, // NOTE: block type ID, not the block state ID
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+impl Packet for BlockAction {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.location = buf.try_get_position()?;
+        self.action_id = buf.try_get_u8()?;
+        self.action_param = buf.try_get_u8()?;
+        self.block_type = buf.try_get_var_int()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_position_for(&self.location, version);
+        buf.push_u8(self.action_id);
+        buf.push_u8(self.action_param);
+        buf.push_var_int(mappings::block_kind_id(self.block_type, version));
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::BlockAction
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::BlockAction
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
 pub struct BlockChange {
     pub location: BlockPosition,
     pub block_id: VarInt,
 }
 
+impl Packet for BlockChange {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.location = buf.try_get_position()?;
+        self.block_id = buf.try_get_var_int()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_position_for(&self.location, version);
+        buf.push_var_int(i32::from(mappings::vanilla_block_id(
+            self.block_id as u16,
+            version,
+        )));
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::BlockChange
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::BlockChange
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Clone)]
 pub struct BossBar {
     pub uuid: Uuid,
@@ -1535,15 +2105,466 @@ pub struct ServerDifficulty {
     pub difficulty: u8,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct ChatMessageClientbound {
     pub json_data: String,
     pub position: u8,
 }
 
-// TODO MultiBlockChange
-// TODO TabCompleteClientbound
-// TODO DeclareCommands
+impl Packet for ChatMessageClientbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.json_data = buf.try_get_string()?;
+        self.position = buf.try_get_u8()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_string(&self.json_data);
+        buf.push_u8(self.position);
+        // The sender's UUID, used by clients to hide messages
+        // from blocked players. The server never sets one.
+        if version != ProtocolVersion::V1_13_2 {
+            buf.push_uuid(&Uuid::nil());
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::ChatMessageClientbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::ChatMessageClientbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// Changes several blocks in a single chunk at once.
+///
+/// Since 1.16.2, the packet is limited to a single chunk section.
+/// When written in that format, only the records in the section
+/// of the first record are sent.
+#[derive(Default, AsAny, Clone)]
+pub struct MultiBlockChange {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub records: Vec<BlockChangeRecord>,
+}
+
+/// A block changed by a `MultiBlockChange`. Coordinates
+/// are relative to the chunk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BlockChangeRecord {
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+    pub block_id: i32,
+}
+
+impl Packet for MultiBlockChange {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.chunk_x = buf.try_get_i32()?;
+        self.chunk_z = buf.try_get_i32()?;
+
+        let count = buf.try_get_var_int()?;
+        self.records = (0..count)
+            .map(|_| {
+                let horizontal = buf.try_get_u8()?;
+                Ok(BlockChangeRecord {
+                    x: horizontal >> 4,
+                    z: horizontal & 0x0F,
+                    y: buf.try_get_u8()?,
+                    block_id: buf.try_get_var_int()?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_i32(self.chunk_x);
+            buf.push_i32(self.chunk_z);
+
+            buf.push_var_int(self.records.len() as i32);
+            for record in &self.records {
+                buf.push_u8((record.x << 4) | (record.z & 0x0F));
+                buf.push_u8(record.y);
+                buf.push_var_int(record.block_id);
+            }
+            return;
+        }
+
+        let section_y = self.records.first().map_or(0, |record| record.y >> 4);
+        let records = self
+            .records
+            .iter()
+            .filter(|record| record.y >> 4 == section_y)
+            .collect::<Vec<_>>();
+
+        let section_position = ((i64::from(self.chunk_x) & 0x3F_FFFF) << 42)
+            | ((i64::from(self.chunk_z) & 0x3F_FFFF) << 20)
+            | i64::from(section_y);
+        buf.push_i64(section_position);
+        buf.push_bool(true); // Trust edges: light is computed by the client
+
+        buf.push_var_int(records.len() as i32);
+        for record in records {
+            let block_id = mappings::vanilla_block_id(record.block_id as u16, version);
+            let position = (i64::from(record.x) << 8)
+                | (i64::from(record.z) << 4)
+                | i64::from(record.y & 0x0F);
+            buf.push_var_long((i64::from(block_id) << 12) | position);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::MultiBlockChange
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::MultiBlockChange
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// Suggestions completing the command a player is typing,
+/// in response to a `TabCompleteServerbound`.
+#[derive(Default, AsAny, Clone)]
+pub struct TabCompleteClientbound {
+    pub transaction_id: VarInt,
+    /// Index into the typed text of the first character
+    /// replaced by the suggestions.
+    pub start: VarInt,
+    /// Number of characters replaced by the suggestions.
+    pub length: VarInt,
+    pub matches: Vec<TabCompleteMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabCompleteMatch {
+    pub text: String,
+    /// Tooltip shown when hovering the suggestion, as JSON text.
+    pub tooltip: Option<String>,
+}
+
+impl Packet for TabCompleteClientbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.transaction_id = buf.try_get_var_int()?;
+        self.start = buf.try_get_var_int()?;
+        self.length = buf.try_get_var_int()?;
+
+        let count = buf.try_get_var_int()?;
+        self.matches.clear();
+        for _ in 0..count {
+            let text = buf.try_get_string()?;
+            let tooltip = if buf.try_get_bool()? {
+                Some(buf.try_get_string()?)
+            } else {
+                None
+            };
+            self.matches.push(TabCompleteMatch { text, tooltip });
+        }
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.transaction_id);
+        buf.push_var_int(self.start);
+        buf.push_var_int(self.length);
+
+        buf.push_var_int(self.matches.len() as i32);
+        for m in &self.matches {
+            buf.push_string(&m.text);
+            buf.push_bool(m.tooltip.is_some());
+            if let Some(tooltip) = &m.tooltip {
+                buf.push_string(tooltip);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::TabCompleteClientbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::TabCompleteClientbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// The tree of commands, which clients use to
+/// parse and complete the commands players type.
+#[derive(Default, AsAny, Clone)]
+pub struct DeclareCommands {
+    pub nodes: Vec<CommandNode>,
+    /// Index of the root node.
+    pub root_index: VarInt,
+}
+
+const COMMAND_NODE_TYPE_MASK: u8 = 0x03;
+const COMMAND_NODE_EXECUTABLE: u8 = 0x04;
+const COMMAND_NODE_REDIRECT: u8 = 0x08;
+const COMMAND_NODE_SUGGESTIONS: u8 = 0x10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandNode {
+    pub kind: CommandNodeKind,
+    /// Whether the command ending at this node can be executed.
+    pub executable: bool,
+    /// Indices of the children of this node.
+    pub children: Vec<VarInt>,
+    /// Index of the node whose children this node shares.
+    pub redirect: Option<VarInt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandNodeKind {
+    Root,
+    /// A literal word.
+    Literal(String),
+    /// An argument with its name and parser. Arguments with
+    /// a suggestions type, e.g. `minecraft:ask_server`,
+    /// get their suggestions from it instead of the parser.
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+        suggestions: Option<String>,
+    },
+}
+
+/// Parsers used by clients for the arguments of commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentParser {
+    /// A single word (`brigadier:string`).
+    Word,
+    /// The rest of the command (`brigadier:string`).
+    GreedyPhrase,
+    /// An entity selector or player name, with flags for
+    /// selectors of a single entity or only of players.
+    Entity(u8),
+    ItemStack,
+    BlockState,
+    /// Three coordinates, supporting `~` and `^` notation.
+    Vec3,
+    /// An integer (`brigadier:integer`), with an optional minimum.
+    Integer(Option<i32>),
+    /// A floating point number (`brigadier:double`).
+    Double,
+}
+
+const STRING_WORD: i32 = 0;
+const STRING_GREEDY_PHRASE: i32 = 2;
+
+/// Flags of number parsers giving their minimum and maximum.
+const NUMBER_MIN: u8 = 0x01;
+const NUMBER_MAX: u8 = 0x02;
+
+impl ArgumentParser {
+    fn identifier(self) -> &'static str {
+        match self {
+            ArgumentParser::Word | ArgumentParser::GreedyPhrase => "brigadier:string",
+            ArgumentParser::Entity(_) => "minecraft:entity",
+            ArgumentParser::ItemStack => "minecraft:item_stack",
+            ArgumentParser::BlockState => "minecraft:block_state",
+            ArgumentParser::Vec3 => "minecraft:vec3",
+            ArgumentParser::Integer(_) => "brigadier:integer",
+            ArgumentParser::Double => "brigadier:double",
+        }
+    }
+
+    fn read_from(buf: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
+        Ok(match buf.try_get_string()?.as_str() {
+            "brigadier:string" => match buf.try_get_var_int()? {
+                STRING_GREEDY_PHRASE => ArgumentParser::GreedyPhrase,
+                _ => ArgumentParser::Word,
+            },
+            "minecraft:entity" => ArgumentParser::Entity(buf.try_get_u8()?),
+            "minecraft:item_stack" => ArgumentParser::ItemStack,
+            "minecraft:block_state" => ArgumentParser::BlockState,
+            "minecraft:vec3" => ArgumentParser::Vec3,
+            "brigadier:integer" => {
+                let flags = buf.try_get_u8()?;
+                let min = if flags & NUMBER_MIN != 0 {
+                    Some(buf.try_get_i32()?)
+                } else {
+                    None
+                };
+                if flags & NUMBER_MAX != 0 {
+                    buf.try_get_i32()?;
+                }
+                ArgumentParser::Integer(min)
+            }
+            "brigadier:double" => {
+                let flags = buf.try_get_u8()?;
+                if flags & NUMBER_MIN != 0 {
+                    buf.try_get_f64()?;
+                }
+                if flags & NUMBER_MAX != 0 {
+                    buf.try_get_f64()?;
+                }
+                ArgumentParser::Double
+            }
+            _ => return Err(Error::UnsupportedArgumentParser.into()),
+        })
+    }
+
+    fn write_to(self, buf: &mut BytesMut) {
+        buf.push_string(self.identifier());
+        match self {
+            ArgumentParser::Word => buf.push_var_int(STRING_WORD),
+            ArgumentParser::GreedyPhrase => buf.push_var_int(STRING_GREEDY_PHRASE),
+            ArgumentParser::Entity(flags) => buf.push_u8(flags),
+            ArgumentParser::Integer(Some(min)) => {
+                buf.push_u8(NUMBER_MIN);
+                buf.push_i32(min);
+            }
+            ArgumentParser::Integer(None) | ArgumentParser::Double => buf.push_u8(0),
+            _ => (),
+        }
+    }
+}
+
+impl Packet for DeclareCommands {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        let count = buf.try_get_var_int()?;
+        self.nodes.clear();
+        for _ in 0..count {
+            let flags = buf.try_get_u8()?;
+
+            let num_children = buf.try_get_var_int()?;
+            let children: Vec<VarInt> = (0..num_children)
+                .map(|_| buf.try_get_var_int())
+                .collect::<Result<_, _>>()?;
+            let redirect = if flags & COMMAND_NODE_REDIRECT != 0 {
+                Some(buf.try_get_var_int()?)
+            } else {
+                None
+            };
+
+            let kind = match flags & COMMAND_NODE_TYPE_MASK {
+                0 => CommandNodeKind::Root,
+                1 => CommandNodeKind::Literal(buf.try_get_string()?),
+                2 => {
+                    let name = buf.try_get_string()?;
+                    let parser = ArgumentParser::read_from(buf)?;
+                    let suggestions = if flags & COMMAND_NODE_SUGGESTIONS != 0 {
+                        Some(buf.try_get_string()?)
+                    } else {
+                        None
+                    };
+                    CommandNodeKind::Argument {
+                        name,
+                        parser,
+                        suggestions,
+                    }
+                }
+                ty => return Err(Error::InvalidCommandNodeType(ty).into()),
+            };
+
+            self.nodes.push(CommandNode {
+                kind,
+                executable: flags & COMMAND_NODE_EXECUTABLE != 0,
+                children,
+                redirect,
+            });
+        }
+        self.root_index = buf.try_get_var_int()?;
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.nodes.len() as i32);
+        for node in &self.nodes {
+            let mut flags = match &node.kind {
+                CommandNodeKind::Root => 0,
+                CommandNodeKind::Literal(_) => 1,
+                CommandNodeKind::Argument { suggestions, .. } => {
+                    if suggestions.is_some() {
+                        2 | COMMAND_NODE_SUGGESTIONS
+                    } else {
+                        2
+                    }
+                }
+            };
+            if node.executable {
+                flags |= COMMAND_NODE_EXECUTABLE;
+            }
+            if node.redirect.is_some() {
+                flags |= COMMAND_NODE_REDIRECT;
+            }
+            buf.push_u8(flags);
+
+            buf.push_var_int(node.children.len() as i32);
+            for child in &node.children {
+                buf.push_var_int(*child);
+            }
+            if let Some(redirect) = node.redirect {
+                buf.push_var_int(redirect);
+            }
+
+            match &node.kind {
+                CommandNodeKind::Root => (),
+                CommandNodeKind::Literal(name) => buf.push_string(name),
+                CommandNodeKind::Argument {
+                    name,
+                    parser,
+                    suggestions,
+                } => {
+                    buf.push_string(name);
+                    parser.write_to(buf);
+                    if let Some(suggestions) = suggestions {
+                        buf.push_string(suggestions);
+                    }
+                }
+            }
+        }
+        buf.push_var_int(self.root_index);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::DeclareCommands
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::DeclareCommands
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
 
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct ConfirmTransactionClientbound {
@@ -1578,14 +2599,29 @@ impl Packet for OpenWindow {
     }
 
     fn write_to(&self, buf: &mut BytesMut) {
-        buf.push_u8(self.window_id);
-        buf.push_string(&self.window_type);
-        buf.push_string(&self.window_title);
-        buf.push_u8(self.number_of_slots);
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
 
-        if self.window_type == "EntityHorse" {
-            buf.push_i32(self.entity_id.unwrap());
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_u8(self.window_id);
+            buf.push_string(&self.window_type);
+            buf.push_string(&self.window_title);
+            buf.push_u8(self.number_of_slots);
+
+            if self.window_type == "EntityHorse" {
+                buf.push_i32(self.entity_id.unwrap());
+            }
+            return;
         }
+
+        // Windows are identified by menu type, which implies
+        // the number of slots, since 1.14. Horse windows are
+        // opened by a separate packet.
+        let menu = mappings::menu_id(&self.window_type, self.number_of_slots, version);
+        buf.push_var_int(i32::from(self.window_id));
+        buf.push_var_int(menu.unwrap_or_default());
+        buf.push_string(&self.window_title);
     }
 
     fn ty(&self) -> PacketType {
@@ -1623,11 +2659,24 @@ impl Packet for WindowItems {
     }
 
     fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
         buf.push_u8(self.window_id);
-        buf.push_i16(self.slots.len() as i16);
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_var_int(0); // State ID
+            buf.push_var_int(self.slots.len() as i32);
+        } else {
+            buf.push_i16(self.slots.len() as i16);
+        }
 
         for slot in &self.slots {
-            buf.push_slot(*slot);
+            buf.push_slot_for(*slot, version);
+        }
+
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_slot_for(None, version); // Item carried by the cursor
         }
     }
 
@@ -1654,13 +2703,52 @@ pub struct WindowProperty {
     pub value: i16,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct SetSlot {
     pub window_id: i8,
     pub slot: i16,
     pub slot_data: Slot,
 }
 
+impl Packet for SetSlot {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.window_id = buf.try_get_i8()?;
+        self.slot = buf.try_get_i16()?;
+        self.slot_data = buf.try_get_slot()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_i8(self.window_id);
+        // Since 1.17.1, window changes are numbered by a state ID,
+        // which the client sends back when clicking in the window.
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_var_int(0);
+        }
+        buf.push_i16(self.slot);
+        buf.push_slot_for(self.slot_data, version);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SetSlot
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SetSlot
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct SetCooldown {
     pub item_id: VarInt,
@@ -1677,7 +2765,9 @@ impl Packet for PluginMessageClientbound {
     fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
         self.channel = buf.try_get_string()?;
 
-        self.data.extend_from_slice(*buf.get_ref());
+        self.data.clear();
+        buf.read_to_end(&mut self.data)
+            .map_err(|_| Error::InsufficientArrayLength)?;
 
         Ok(())
     }
@@ -1703,7 +2793,197 @@ impl Packet for PluginMessageClientbound {
     }
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+impl PluginMessageClientbound {
+    /// Creates a plugin message on the `minecraft:brand`
+    /// channel, which tells the client the server's brand.
+    pub fn brand(brand: &str) -> Self {
+        let mut data = BytesMut::new();
+        data.push_string(brand);
+        Self {
+            channel: String::from("minecraft:brand"),
+            data: data.to_vec(),
+        }
+    }
+}
+
+/// The trades offered by a merchant, sent after
+/// its window is opened.
+///
+/// Before 1.14, this is a plugin message on the
+/// `minecraft:trader_list` channel, and prices are
+/// adjusted by the server instead of the client.
+#[derive(Default, AsAny, Clone)]
+pub struct TradeList {
+    pub window_id: u8,
+    pub trades: Vec<Trade>,
+    /// The merchant's level, from 1 (novice) to 5 (master).
+    pub villager_level: i32,
+    /// The merchant's total experience.
+    pub experience: i32,
+    /// Whether the merchant is a villager with a level and
+    /// experience bar, unlike wandering traders.
+    pub is_regular_villager: bool,
+    pub can_restock: bool,
+}
+
+/// A trade in a `TradeList`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Trade {
+    pub input_1: ItemStack,
+    pub input_2: Option<ItemStack>,
+    pub output: ItemStack,
+    /// Whether the trade is out of stock.
+    pub disabled: bool,
+    pub uses: i32,
+    pub max_uses: i32,
+    /// Experience the merchant gains from the trade.
+    pub experience: i32,
+    /// The change to the price of the first input, e.g.
+    /// from gossip about the player.
+    pub special_price: i32,
+    pub price_multiplier: f32,
+    pub demand: i32,
+}
+
+impl Trade {
+    /// Returns the first input with its price adjusted by
+    /// demand and the special price, as computed by clients
+    /// since 1.14.
+    pub fn adjusted_input_1(&self) -> ItemStack {
+        let base = i32::from(self.input_1.amount);
+        let demand = ((base * self.demand) as f32 * self.price_multiplier).floor() as i32;
+        let amount = base + demand.max(0) + self.special_price;
+
+        let mut input = self.input_1;
+        input.amount = amount.max(1).min(self.input_1.ty.stack_size() as i32) as u8;
+        input
+    }
+}
+
+impl Packet for TradeList {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.read_from_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        let legacy = version == ProtocolVersion::V1_13_2;
+        if legacy {
+            buf.try_get_string()?;
+            self.window_id = buf.try_get_i32()? as u8;
+        } else {
+            self.window_id = buf.try_get_var_int()? as u8;
+        }
+
+        let count = buf.try_get_u8()?;
+        self.trades.clear();
+        for _ in 0..count {
+            let input_1 = buf
+                .try_get_slot_for(version)?
+                .ok_or(Error::InsufficientArrayLength)?;
+            let output = buf
+                .try_get_slot_for(version)?
+                .ok_or(Error::InsufficientArrayLength)?;
+            let input_2 = if buf.try_get_bool()? {
+                buf.try_get_slot_for(version)?
+            } else {
+                None
+            };
+            let mut trade = Trade {
+                input_1,
+                input_2,
+                output,
+                disabled: buf.try_get_bool()?,
+                uses: buf.try_get_i32()?,
+                max_uses: buf.try_get_i32()?,
+                ..Default::default()
+            };
+            if !legacy {
+                trade.experience = buf.try_get_i32()?;
+                trade.special_price = buf.try_get_i32()?;
+                trade.price_multiplier = buf.try_get_f32()?;
+                trade.demand = buf.try_get_i32()?;
+            }
+            self.trades.push(trade);
+        }
+
+        if !legacy {
+            self.villager_level = buf.try_get_var_int()?;
+            self.experience = buf.try_get_var_int()?;
+            self.is_regular_villager = buf.try_get_bool()?;
+            self.can_restock = buf.try_get_bool()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        let legacy = version == ProtocolVersion::V1_13_2;
+        if legacy {
+            buf.push_string("minecraft:trader_list");
+            buf.push_i32(i32::from(self.window_id));
+        } else {
+            buf.push_var_int(i32::from(self.window_id));
+        }
+
+        buf.push_u8(self.trades.len().min(usize::from(u8::MAX)) as u8);
+        for trade in self.trades.iter().take(usize::from(u8::MAX)) {
+            // Old clients don't know about demand
+            // and special prices.
+            let input_1 = if legacy {
+                trade.adjusted_input_1()
+            } else {
+                trade.input_1
+            };
+            buf.push_slot_for(Some(input_1), version);
+            buf.push_slot_for(Some(trade.output), version);
+            buf.push_bool(trade.input_2.is_some());
+            if let Some(input_2) = trade.input_2 {
+                buf.push_slot_for(Some(input_2), version);
+            }
+            buf.push_bool(trade.disabled);
+            buf.push_i32(trade.uses);
+            buf.push_i32(trade.max_uses);
+            if !legacy {
+                buf.push_i32(trade.experience);
+                buf.push_i32(trade.special_price);
+                buf.push_f32(trade.price_multiplier);
+                buf.push_i32(trade.demand);
+            }
+        }
+
+        if !legacy {
+            buf.push_var_int(self.villager_level);
+            buf.push_var_int(self.experience);
+            buf.push_bool(self.is_regular_villager);
+            buf.push_bool(self.can_restock);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::TradeList
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::TradeList
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
 pub struct NamedSoundEffect {
     pub sound_name: String,
     pub sound_category: VarInt,
@@ -1714,7 +2994,7 @@ pub struct NamedSoundEffect {
     pub pitch: f32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SoundCategory {
     Master = 0,
     Music = 1,
@@ -1818,6 +3098,270 @@ pub struct KeepAliveClientbound {
 #[derive(Default, AsAny, Clone)]
 pub struct ChunkData {
     pub chunk: Arc<RwLock<Chunk>>,
+    /// The sections to send, or `None` to send the
+    /// full chunk, including its biomes. Sections in
+    /// the mask which don't exist are left out.
+    ///
+    /// The mask covers the sections from Y 0 to 255. Since
+    /// 1.17, chunks can only be sent in full, so it is ignored.
+    pub section_mask: Option<u16>,
+}
+
+impl ChunkData {
+    /// Returns the bit mask of the sections to send.
+    fn primary_mask(&self, chunk: &Chunk) -> u16 {
+        let mask = self.section_mask.unwrap_or(u16::MAX);
+        let mut r = 0;
+        for (i, section) in legacy_sections(chunk).iter().enumerate() {
+            if section.is_some() && mask & (1 << i) != 0 {
+                r |= 1 << i;
+            }
+        }
+        r
+    }
+
+    /// Writes the chunk in the 1.16 format. Light is sent
+    /// separately by `UpdateLight` since 1.14.
+    fn write_to_1_16(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        let chunk = self.chunk.read();
+        buf.push_i32(chunk.position().x);
+        buf.push_i32(chunk.position().z);
+        let full = self.section_mask.is_none();
+        buf.push_bool(full);
+
+        let primary_mask = self.primary_mask(&chunk);
+        buf.push_var_int(i32::from(primary_mask));
+
+        push_heightmaps(buf, &chunk, version);
+
+        // Biomes are only sent with full chunks.
+        if full {
+            let ids = chunk
+                .biomes()
+                .encode(BiomeArrayFormat::for_protocol_version(version.id()));
+            buf.push_var_int(ids.len() as i32);
+            for id in ids {
+                buf.push_var_int(id);
+            }
+        }
+
+        let mut temp_buf = BytesMut::new();
+        for (i, section) in legacy_sections(&chunk).into_iter().enumerate() {
+            if primary_mask & (1 << i) == 0 {
+                continue;
+            }
+            if let Some(section) = section {
+                push_section_1_16(&mut temp_buf, section, version);
+            }
+        }
+
+        buf.push_var_int(temp_buf.len() as i32);
+        buf.extend_from_slice(&temp_buf);
+
+        buf.push_var_int(0); // Block entities are sent separately
+    }
+
+    /// Writes the chunk in the 1.18 format, which
+    /// includes all of its sections and their light.
+    fn write_to_1_18(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        let chunk = self.chunk.read();
+        buf.push_i32(chunk.position().x);
+        buf.push_i32(chunk.position().z);
+
+        push_heightmaps(buf, &chunk, version);
+
+        let mut temp_buf = BytesMut::new();
+        for index in 0..chunk.num_sections() {
+            match chunk.section(index) {
+                Some(section) => push_section_1_18(&mut temp_buf, section, version),
+                None => {
+                    temp_buf.push_i16(0); // Non-air blocks
+                    push_paletted_1_16(&mut temp_buf, &[0; SECTION_VOLUME], 4, 8, 15, true);
+                }
+            }
+            push_biomes_1_18(&mut temp_buf, &chunk, index);
+        }
+
+        buf.push_var_int(temp_buf.len() as i32);
+        buf.extend_from_slice(&temp_buf);
+
+        buf.push_var_int(0); // Block entities are sent separately
+        push_light_1_18(buf, &LightData::from_chunk(&chunk, true));
+    }
+}
+
+/// Returns the sections of a chunk from Y 0 to 255,
+/// the only ones known to clients before 1.17.
+fn legacy_sections(chunk: &Chunk) -> Vec<Option<&ChunkSection>> {
+    (0..NUM_SECTIONS)
+        .map(|i| {
+            let y = chunk.local_y((i * SECTION_HEIGHT) as i32)?;
+            chunk.section(y / SECTION_HEIGHT)
+        })
+        .collect()
+}
+
+/// Writes the `MOTION_BLOCKING` heightmap of a chunk as an
+/// NBT compound, which clients use to render rain and snow.
+///
+/// Since 1.17, heights are relative to the bottom of the world
+/// and take as many bits as needed for the world's height.
+fn push_heightmaps(buf: &mut BytesMut, chunk: &Chunk, version: ProtocolVersion) {
+    let (bits, offset, max) = if version >= ProtocolVersion::V1_18_1 {
+        let bits = 64 - (chunk.height() as u64).leading_zeros();
+        (bits as usize, 0, chunk.height() as i32)
+    } else {
+        (9, chunk.min_y(), 256)
+    };
+    let values_per_long = 64 / bits;
+
+    let mut heights = vec![0u64; (256 + values_per_long - 1) / values_per_long];
+    for (i, heightmap) in chunk.heightmaps().iter().enumerate() {
+        let height = (i32::from(heightmap.motion_blocking()) + offset).clamp(0, max);
+        heights[i / values_per_long] |= (height as u64) << ((i % values_per_long) * bits);
+    }
+
+    // The NBT crate can't write long arrays, so the
+    // compound is written by hand.
+    let name = "MOTION_BLOCKING";
+    buf.push_u8(10); // TAG_Compound
+    buf.push_u16(0); // Empty root name
+    buf.push_u8(12); // TAG_Long_Array
+    buf.push_u16(name.len() as u16);
+    buf.extend_from_slice(name.as_bytes());
+    buf.push_i32(heights.len() as i32);
+    for height in heights {
+        buf.push_u64(height);
+    }
+    buf.push_u8(0); // TAG_End
+}
+
+/// Writes a chunk section in the 1.16 format, translating
+/// its blocks to the block state IDs of the given version.
+fn push_section_1_16(buf: &mut BytesMut, section: &ChunkSection, version: ProtocolVersion) {
+    let (non_air, ids) = section_block_ids(section, version);
+    buf.push_i16(non_air);
+    push_paletted_1_16(buf, &ids, 4, 8, 15, false);
+}
+
+/// Writes a chunk section in the 1.18 format, in which
+/// sections of a single block have no block data.
+fn push_section_1_18(buf: &mut BytesMut, section: &ChunkSection, version: ProtocolVersion) {
+    let (non_air, ids) = section_block_ids(section, version);
+    buf.push_i16(non_air);
+    push_paletted_1_16(buf, &ids, 4, 8, 15, true);
+}
+
+/// Returns the number of non-air blocks in a section, and
+/// the block state IDs of its blocks in the given version.
+fn section_block_ids(section: &ChunkSection, version: ProtocolVersion) -> (i16, Vec<u16>) {
+    let blocks = section.blocks();
+
+    let mut non_air = 0;
+    let mut ids = Vec::with_capacity(SECTION_VOLUME);
+    for i in 0..SECTION_VOLUME {
+        let block = blocks.get(i);
+        if !block.is_air() {
+            non_air += 1;
+        }
+        ids.push(mappings::block_id(block, version));
+    }
+    (non_air, ids)
+}
+
+/// Writes the biomes of a section in the 1.18 format, one
+/// for each 4x4x4 cell. The palette may only hold up to
+/// 8 biomes, so any others are replaced by the most
+/// common biome in the section.
+fn push_biomes_1_18(buf: &mut BytesMut, chunk: &Chunk, index: usize) {
+    let mut ids = Vec::with_capacity(64);
+    for y in 0..4 {
+        for z in 0..4 {
+            for x in 0..4 {
+                let biome = chunk.biome_at_3d(x * 4, index * SECTION_HEIGHT + y * 4, z * 4);
+                ids.push(biome.id() as u16);
+            }
+        }
+    }
+
+    let mut counts = AHashMap::new();
+    for &id in &ids {
+        *counts.entry(id).or_insert(0) += 1;
+    }
+    if counts.len() > 8 {
+        let mut by_count = counts.into_iter().collect::<Vec<_>>();
+        by_count.sort_unstable_by_key(|&(id, count)| (std::cmp::Reverse(count), id));
+        let kept = &by_count[..8];
+        for id in &mut ids {
+            if !kept.iter().any(|(kept_id, _)| kept_id == id) {
+                *id = kept[0].0;
+            }
+        }
+    }
+
+    // At most 8 biomes remain, so the palette is never replaced.
+    push_paletted_1_16(buf, &ids, 1, 3, 0, true);
+}
+
+/// Writes a paletted container in the format used since 1.16.
+///
+/// Palettes needing more than `max_bits` bits per value are replaced
+/// by the values themselves, written with `direct_bits` bits. If
+/// `single_value` is set, as since 1.18, containers holding a single
+/// value are written with zero bits per value and no data.
+fn push_paletted_1_16(
+    buf: &mut BytesMut,
+    ids: &[u16],
+    min_bits: u32,
+    max_bits: u32,
+    direct_bits: usize,
+    single_value: bool,
+) {
+    let mut palette = Vec::new();
+    let mut palette_indices = AHashMap::new();
+    for &id in ids {
+        if !palette_indices.contains_key(&id) {
+            palette_indices.insert(id, palette.len() as u64);
+            palette.push(id);
+        }
+    }
+
+    if single_value && palette.len() == 1 {
+        buf.push_u8(0);
+        buf.push_var_int(i32::from(palette[0]));
+        buf.push_var_int(0); // No data
+        return;
+    }
+
+    let bits = (64 - (palette.len() as u64 - 1).leading_zeros()).max(min_bits);
+    let values: Vec<u64> = if bits <= max_bits {
+        buf.push_u8(bits as u8);
+        buf.push_var_int(palette.len() as i32);
+        for id in &palette {
+            buf.push_var_int(i32::from(*id));
+        }
+        ids.iter().map(|id| palette_indices[id]).collect()
+    } else {
+        buf.push_u8(direct_bits as u8);
+        ids.iter().map(|id| u64::from(*id)).collect()
+    };
+    let bits = if bits <= max_bits {
+        bits as usize
+    } else {
+        direct_bits
+    };
+
+    // Values don't span across longs since 1.16.
+    let values_per_long = 64 / bits;
+    let mut data = vec![0u64; (values.len() + values_per_long - 1) / values_per_long];
+    for (i, value) in values.into_iter().enumerate() {
+        data[i / values_per_long] |= value << ((i % values_per_long) * bits);
+    }
+
+    buf.push_var_int(data.len() as i32);
+    for val in data {
+        buf.push_u64(val);
+    }
 }
 
 impl Packet for ChunkData {
@@ -1826,33 +3370,38 @@ impl Packet for ChunkData {
     }
 
     fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        match version {
+            ProtocolVersion::V1_13_2 => (),
+            ProtocolVersion::V1_16_5 => return self.write_to_1_16(buf, version),
+            ProtocolVersion::V1_18_1 => return self.write_to_1_18(buf, version),
+        }
+
         let chunk = self.chunk.read();
         buf.push_i32(chunk.position().x);
         buf.push_i32(chunk.position().z);
-        buf.push_bool(true); // Full chunk - assume true
-
-        // Produce primary bit mask
-        let primary_mask = {
-            let mut r = 0;
-            for (i, section) in chunk.sections().iter().enumerate() {
-                if section.is_some() {
-                    r |= 1 << i;
-                }
-            }
-            r
-        };
+        let full = self.section_mask.is_none();
+        buf.push_bool(full);
 
+        let primary_mask = self.primary_mask(&chunk);
         buf.push_var_int(primary_mask as i32);
 
         // TODO: approximate appropriate capacity
         let mut temp_buf = BytesMut::new();
 
-        for section in chunk.sections() {
+        for (i, section) in legacy_sections(&chunk).into_iter().enumerate() {
+            if primary_mask & (1 << i) == 0 {
+                continue;
+            }
             if let Some(section) = section {
-                temp_buf.push_u8(section.bits_per_block());
+                // Block data is written straight from the section's storage.
+                let blocks = section.blocks();
+                temp_buf.push_u8(blocks.bits_per_block());
 
-                let palette = section.palette();
-                if let Some(palette) = palette {
+                if let Some(palette) = blocks.palette() {
                     let mut palette_buf = BytesMut::with_capacity(palette.len() + 4);
                     for block in palette {
                         palette_buf.push_var_int(i32::from(block.vanilla_id()));
@@ -1862,8 +3411,7 @@ impl Packet for ChunkData {
                     temp_buf.extend_from_slice(&palette_buf);
                 }
 
-                let data = section.data();
-                let data = data.inner();
+                let data = blocks.data().inner();
                 temp_buf.push_var_int(data.len() as i32);
 
                 temp_buf.reserve(data.len());
@@ -1886,13 +3434,15 @@ impl Packet for ChunkData {
             }
         }
 
-        // Biomes
-        temp_buf.reserve(256 * 4);
-        chunk
-            .biomes()
-            .iter()
-            .map(|biome| biome.protocol_id())
-            .for_each(|id| temp_buf.push_i32(id));
+        // Biomes are only sent with full chunks.
+        if full {
+            temp_buf.reserve(256 * 4);
+            chunk
+                .biomes()
+                .encode(BiomeArrayFormat::TwoDimensional)
+                .into_iter()
+                .for_each(|id| temp_buf.push_i32(id));
+        }
 
         buf.push_var_int(temp_buf.len() as i32);
         buf.extend_from_slice(&temp_buf);
@@ -1916,7 +3466,124 @@ impl Packet for ChunkData {
     }
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+/// Sends the light of a chunk's sections, which isn't
+/// part of `ChunkData` from 1.14 until 1.18. Only written
+/// to clients of those versions.
+#[derive(Default, AsAny, Clone)]
+pub struct UpdateLight {
+    pub chunk: Arc<RwLock<Chunk>>,
+}
+
+impl Packet for UpdateLight {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::latest())
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        let chunk = self.chunk.read();
+        buf.push_var_int(chunk.position().x);
+        buf.push_var_int(chunk.position().z);
+        if version >= ProtocolVersion::V1_18_1 {
+            return push_light_1_18(buf, &LightData::from_chunk(&chunk, true));
+        }
+        buf.push_bool(true); // Trust edges
+
+        // The masks cover the sections from one below
+        // the world to one above it. Sky light is sent for
+        // every section, with missing sections fully lit.
+        let sections = legacy_sections(&chunk);
+        let section_at = |i: usize| {
+            if i == 0 || i > sections.len() {
+                None
+            } else {
+                sections[i - 1]
+            }
+        };
+
+        let sky_mask = (1 << (sections.len() + 2)) - 1;
+        let mut block_mask = 0;
+        for i in 0..sections.len() + 2 {
+            if section_at(i).is_some() {
+                block_mask |= 1 << i;
+            }
+        }
+
+        buf.push_var_int(sky_mask);
+        buf.push_var_int(block_mask);
+        buf.push_var_int(0); // Empty sky light mask
+        buf.push_var_int(!block_mask & sky_mask); // Empty block light mask
+
+        for i in 0..sections.len() + 2 {
+            buf.push_var_int(2048);
+            match section_at(i) {
+                Some(section) => {
+                    for data in section.sky_light().inner() {
+                        buf.put_u64_le(*data);
+                    }
+                }
+                None => buf.extend_from_slice(&[0xFF; 2048]),
+            }
+        }
+
+        for section in sections.iter().flatten() {
+            buf.push_var_int(2048);
+            for data in section.block_light().inner() {
+                buf.put_u64_le(*data);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::UpdateLight
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::UpdateLight
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// Writes the light of a chunk in the format used by
+/// `UpdateLight` and `ChunkData` since 1.17, in which
+/// the masks are bit sets of any length.
+fn push_light_1_18(buf: &mut BytesMut, light: &LightData) {
+    buf.push_bool(true); // Trust edges
+
+    for &mask in &[
+        light.sky_light_mask,
+        light.block_light_mask,
+        light.empty_sky_light_mask,
+        light.empty_block_light_mask,
+    ] {
+        // Bit sets are written as an array of longs.
+        if mask == 0 {
+            buf.push_var_int(0);
+        } else {
+            buf.push_var_int(1);
+            buf.push_u64(mask);
+        }
+    }
+
+    for arrays in &[&light.sky_light, &light.block_light] {
+        buf.push_var_int(arrays.len() as i32);
+        for array in arrays.iter() {
+            buf.push_var_int(array.len() as i32);
+            buf.extend_from_slice(array);
+        }
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
 pub struct Effect {
     pub effect_id: i32,
     pub location: BlockPosition,
@@ -1924,12 +3591,56 @@ pub struct Effect {
     pub disable_relative_volume: bool,
 }
 
+impl Packet for Effect {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.effect_id = buf.try_get_i32()?;
+        self.location = buf.try_get_position()?;
+        self.data = buf.try_get_i32()?;
+        self.disable_relative_volume = buf.try_get_bool()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_i32(self.effect_id);
+        buf.push_position_for(&self.location, version);
+        // The data of block break effects is a block state ID.
+        if self.effect_id == 2001 {
+            buf.push_i32(i32::from(mappings::vanilla_block_id(
+                self.data as u16,
+                version,
+            )));
+        } else {
+            buf.push_i32(self.data);
+        }
+        buf.push_bool(self.disable_relative_volume);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::Effect
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::Effect
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Clone)]
 pub struct Particle {
     pub long_distance: bool,
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
     pub offset_x: f32,
     pub offset_y: f32,
     pub offset_z: f32,
@@ -1944,20 +3655,43 @@ impl Packet for Particle {
     }
 
     fn write_to(&self, buf: &mut BytesMut) {
-        buf.push_i32(self.data.ordinal() as i32);
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        // Barrier particles became block markers in 1.17.
+        let (name, block) = match self.data {
+            ParticleData::Barrier if version >= ProtocolVersion::V1_18_1 => {
+                ("minecraft:block_marker", Some(BlockId::barrier()))
+            }
+            ParticleData::Block(block) | ParticleData::FallingDust(block) => {
+                (self.data.identifier(), Some(block))
+            }
+            _ => (self.data.identifier(), None),
+        };
+
+        buf.push_i32(mappings::particle_id(name, version).unwrap_or_default());
         buf.push_bool(self.long_distance);
-        buf.push_f32(self.x);
-        buf.push_f32(self.y);
-        buf.push_f32(self.z);
+        // Coordinates are doubles since 1.15.
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_f32(self.x as f32);
+            buf.push_f32(self.y as f32);
+            buf.push_f32(self.z as f32);
+        } else {
+            buf.push_f64(self.x);
+            buf.push_f64(self.y);
+            buf.push_f64(self.z);
+        }
         buf.push_f32(self.offset_x);
         buf.push_f32(self.offset_y);
         buf.push_f32(self.offset_z);
         buf.push_f32(self.particle_data);
         buf.push_i32(self.particle_count);
+
+        if let Some(block) = block {
+            buf.push_var_int(i32::from(mappings::block_id(block, version)));
+        }
         match self.data {
-            ParticleData::Block(id) => {
-                buf.push_var_int(id.vanilla_id() as i32);
-            }
             ParticleData::Dust {
                 red,
                 green,
@@ -1969,10 +3703,7 @@ impl Packet for Particle {
                 buf.push_f32(blue);
                 buf.push_f32(scale);
             }
-            ParticleData::FallingDust(id) => {
-                buf.push_var_int(id.vanilla_id() as i32);
-            }
-            ParticleData::Item(stack) => buf.push_slot(stack),
+            ParticleData::Item(stack) => buf.push_slot_for(stack, version),
             _ => (),
         }
     }
@@ -1993,7 +3724,7 @@ impl Packet for Particle {
     }
 }
 
-#[derive(Default, AsAny, Packet, Clone, Debug)]
+#[derive(Default, AsAny, Clone, Debug)]
 pub struct JoinGame {
     pub entity_id: i32,
     pub gamemode: u8,
@@ -2002,32 +3733,240 @@ pub struct JoinGame {
     pub max_players: u8,
     pub level_type: String,
     pub reduced_debug_info: bool,
-}
+    // The remaining fields are only sent since 1.16.
+    pub view_distance: i32,
+    /// The NBT-encoded dimension codec.
+    pub dimension_codec: Vec<u8>,
+    /// The NBT-encoded type of the dimension.
+    pub dimension_type: Vec<u8>,
+    pub world_name: String,
+    pub world_names: Vec<String>,
+    pub is_flat: bool,
+}
+
+impl Packet for JoinGame {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_id = buf.try_get_i32()?;
+        self.gamemode = buf.try_get_u8()?;
+        self.dimension = buf.try_get_i32()?;
+        self.difficulty = buf.try_get_u8()?;
+        self.max_players = buf.try_get_u8()?;
+        self.level_type = buf.try_get_string()?;
+        self.reduced_debug_info = buf.try_get_bool()?;
+        Ok(())
+    }
 
-// TODO MapData
-// TODO EntityPacket
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_i32(self.entity_id);
+            buf.push_u8(self.gamemode);
+            buf.push_i32(self.dimension);
+            buf.push_u8(self.difficulty);
+            buf.push_u8(self.max_players);
+            buf.push_string(&self.level_type);
+            buf.push_bool(self.reduced_debug_info);
+            return;
+        }
 
-#[derive(Default, AsAny, Packet, Clone)]
-pub struct EntityRelativeMove {
-    pub entity_id: VarInt,
-    pub delta_x: i16,
-    pub delta_y: i16,
-    pub delta_z: i16,
-    pub on_ground: bool,
+        buf.push_i32(self.entity_id);
+        buf.push_bool(false); // Hardcore
+        buf.push_u8(self.gamemode);
+        buf.push_i8(-1); // Previous gamemode
+        buf.push_var_int(self.world_names.len() as i32);
+        for name in &self.world_names {
+            buf.push_string(name);
+        }
+        buf.extend_from_slice(&self.dimension_codec);
+        buf.extend_from_slice(&self.dimension_type);
+        buf.push_string(&self.world_name);
+        buf.push_i64(0); // Hashed seed
+        buf.push_var_int(i32::from(self.max_players));
+        buf.push_var_int(self.view_distance);
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_var_int(self.view_distance); // Simulation distance
+        }
+        buf.push_bool(self.reduced_debug_info);
+        buf.push_bool(true); // Enable respawn screen
+        buf.push_bool(false); // Debug world
+        buf.push_bool(self.is_flat);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::JoinGame
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::JoinGame
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
-pub struct EntityLookAndRelativeMove {
-    pub entity_id: VarInt,
-    pub delta_x: i16,
-    pub delta_y: i16,
-    pub delta_z: i16,
-    pub yaw: u8,
-    pub pitch: u8,
-    pub on_ground: bool,
+/// An icon drawn on a map, such as a player marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapIcon {
+    /// The kind of icon, e.g. 0 for a player or
+    /// 6 for a player beyond the edges of the map.
+    pub kind: VarInt,
+    /// Position on the map, from -128 to 127, in
+    /// units of half a pixel.
+    pub x: i8,
+    pub z: i8,
+    /// Rotation from 0 to 15, clockwise from north.
+    pub direction: i8,
+    pub display_name: Option<String>,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
+pub struct MapData {
+    pub map_id: VarInt,
+    pub scale: i8,
+    pub tracking_position: bool,
+    pub locked: bool,
+    pub icons: Vec<MapIcon>,
+    /// Width of the updated region of colors. If
+    /// zero, no colors are updated.
+    pub columns: u8,
+    pub rows: u8,
+    pub x: u8,
+    pub z: u8,
+    /// Colors in the updated region, row by row.
+    pub data: Vec<u8>,
+}
+
+impl Packet for MapData {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.map_id = buf.try_get_var_int()?;
+        self.scale = buf.try_get_i8()?;
+        self.tracking_position = buf.try_get_bool()?;
+
+        let num_icons = buf.try_get_var_int()?;
+        self.icons.clear();
+        for _ in 0..num_icons {
+            let kind = buf.try_get_var_int()?;
+            let x = buf.try_get_i8()?;
+            let z = buf.try_get_i8()?;
+            let direction = buf.try_get_i8()?;
+            let display_name = if buf.try_get_bool()? {
+                Some(buf.try_get_string()?)
+            } else {
+                None
+            };
+            self.icons.push(MapIcon {
+                kind,
+                x,
+                z,
+                direction,
+                display_name,
+            });
+        }
+
+        self.columns = buf.try_get_u8()?;
+        if self.columns > 0 {
+            self.rows = buf.try_get_u8()?;
+            self.x = buf.try_get_u8()?;
+            self.z = buf.try_get_u8()?;
+
+            let len = buf.try_get_var_int()?;
+            self.data.clear();
+            for _ in 0..len {
+                self.data.push(buf.try_get_u8()?);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_var_int(self.map_id);
+        buf.push_i8(self.scale);
+        if version < ProtocolVersion::V1_18_1 {
+            buf.push_bool(self.tracking_position);
+        }
+        if version >= ProtocolVersion::V1_16_5 {
+            buf.push_bool(self.locked);
+        }
+        // Since 1.17, the icons are optional, and
+        // present only when the position is tracked.
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_bool(self.tracking_position);
+        }
+        if version < ProtocolVersion::V1_18_1 || self.tracking_position {
+            buf.push_var_int(self.icons.len() as i32);
+            for icon in &self.icons {
+                buf.push_var_int(icon.kind);
+                buf.push_i8(icon.x);
+                buf.push_i8(icon.z);
+                buf.push_i8(icon.direction);
+                buf.push_bool(icon.display_name.is_some());
+                if let Some(display_name) = &icon.display_name {
+                    buf.push_string(display_name);
+                }
+            }
+        }
+
+        buf.push_u8(self.columns);
+        if self.columns > 0 {
+            buf.push_u8(self.rows);
+            buf.push_u8(self.x);
+            buf.push_u8(self.z);
+            buf.push_var_int(self.data.len() as i32);
+            buf.extend_from_slice(&self.data);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::MapData
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::MapData
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+// TODO EntityPacket
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct EntityRelativeMove {
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub on_ground: bool,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct EntityLookAndRelativeMove {
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
 pub struct EntityLook {
     pub entity_id: VarInt,
     pub yaw: u8,
@@ -2074,12 +4013,14 @@ impl Packet for CombatEvent {
 
     fn write_to(&self, buf: &mut BytesMut) {
         match &self.event {
-            CombatEventType::EnterCombat => (),
+            CombatEventType::EnterCombat => buf.push_var_int(0),
             CombatEventType::EndCombat(duration, entity_id) => {
+                buf.push_var_int(1);
                 buf.push_var_int(*duration);
                 buf.push_i32(*entity_id);
             }
             CombatEventType::EntityDead(player_id, entity_id, message) => {
+                buf.push_var_int(2);
                 buf.push_var_int(*player_id);
                 buf.push_i32(*entity_id);
                 buf.push_string(message);
@@ -2148,20 +4089,20 @@ impl Packet for PlayerInfo {
                 let gamemode = Gamemode::from_id(buf.try_get_var_int()? as u8);
                 let ping = buf.try_get_var_int()?;
                 let display_name = if buf.try_get_bool()? {
-                    buf.try_get_string()?
+                    Some(buf.try_get_string()?)
                 } else {
-                    String::default()
+                    None
                 };
 
                 PlayerInfoAction::AddPlayer(name, props, gamemode, ping, display_name)
             }
-            1 => PlayerInfoAction::UpdateGamemode(Gamemode::from_id(buf.try_get_u8()?)),
+            1 => PlayerInfoAction::UpdateGamemode(Gamemode::from_id(buf.try_get_var_int()? as u8)),
             2 => PlayerInfoAction::UpdateLatency(buf.try_get_var_int()?),
             3 => {
                 if buf.try_get_bool()? {
-                    PlayerInfoAction::UpdateDisplayName(buf.try_get_string()?)
+                    PlayerInfoAction::UpdateDisplayName(Some(buf.try_get_string()?))
                 } else {
-                    PlayerInfoAction::UpdateDisplayName(String::default())
+                    PlayerInfoAction::UpdateDisplayName(None)
                 }
             }
             _ => PlayerInfoAction::RemovePlayer,
@@ -2183,14 +4124,16 @@ impl Packet for PlayerInfo {
                 for prop in props {
                     buf.push_string(&prop.0);
                     buf.push_string(&prop.1);
-                    buf.push_bool(true);
-                    buf.push_string(&prop.2);
+                    // Unsigned properties have an empty signature.
+                    buf.push_bool(!prop.2.is_empty());
+                    if !prop.2.is_empty() {
+                        buf.push_string(&prop.2);
+                    }
                 }
 
                 buf.push_var_int(i32::from(gamemode.id()));
                 buf.push_var_int(*ping);
-                buf.push_bool(true);
-                buf.push_string(display_name);
+                push_display_name(buf, display_name);
             }
             PlayerInfoAction::UpdateGamemode(gamemode) => {
                 buf.push_var_int(i32::from(gamemode.id()));
@@ -2199,8 +4142,7 @@ impl Packet for PlayerInfo {
                 buf.push_var_int(*ping);
             }
             PlayerInfoAction::UpdateDisplayName(display_name) => {
-                buf.push_bool(true);
-                buf.push_string(&display_name);
+                push_display_name(buf, display_name);
             }
             PlayerInfoAction::RemovePlayer => (),
         }
@@ -2222,18 +4164,30 @@ impl Packet for PlayerInfo {
     }
 }
 
+/// Writes a display name in the tab list, as JSON text.
+/// Players without one are shown by their name.
+fn push_display_name(buf: &mut BytesMut, display_name: &Option<String>) {
+    buf.push_bool(display_name.is_some());
+    if let Some(display_name) = display_name {
+        buf.push_string(display_name);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PlayerInfoAction {
+    /// Name, profile properties (name, value and signature, which
+    /// is empty if unsigned), gamemode, latency in milliseconds
+    /// and display name.
     AddPlayer(
         String,
         Vec<(String, String, String)>,
         Gamemode,
         VarInt,
-        String,
+        Option<String>,
     ),
     UpdateGamemode(Gamemode),
     UpdateLatency(VarInt),
-    UpdateDisplayName(String),
+    UpdateDisplayName(Option<String>),
     RemovePlayer,
 }
 
@@ -2257,7 +4211,7 @@ impl Default for PlayerInfoAction {
 
 // TODO Face Player
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct PlayerPositionAndLookClientbound {
     pub x: f64,
     pub y: f64,
@@ -2268,6 +4222,51 @@ pub struct PlayerPositionAndLookClientbound {
     pub teleport_id: VarInt,
 }
 
+impl Packet for PlayerPositionAndLookClientbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.x = buf.try_get_f64()?;
+        self.y = buf.try_get_f64()?;
+        self.z = buf.try_get_f64()?;
+        self.yaw = buf.try_get_f32()?;
+        self.pitch = buf.try_get_f32()?;
+        self.flags = buf.try_get_u8()?;
+        self.teleport_id = buf.try_get_var_int()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_f64(self.x);
+        buf.push_f64(self.y);
+        buf.push_f64(self.z);
+        buf.push_f32(self.yaw);
+        buf.push_f32(self.pitch);
+        buf.push_u8(self.flags);
+        buf.push_var_int(self.teleport_id);
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_bool(false); // Dismount vehicle
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::PlayerPositionAndLookClientbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::PlayerPositionAndLookClientbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct UseBed {
     pub entity_id: VarInt,
@@ -2316,18 +4315,130 @@ pub struct RemoveEntityEffect {
     pub effect_id: i8,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct ResourcePackSend {
     pub url: String,
     pub hash: String,
+    // The remaining fields are only sent since 1.17.
+    pub forced: bool,
+    /// JSON text shown in the prompt to download the pack.
+    pub prompt: Option<String>,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+impl Packet for ResourcePackSend {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.read_from_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn read_from_version(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+        version: ProtocolVersion,
+    ) -> anyhow::Result<()> {
+        self.url = buf.try_get_string()?;
+        self.hash = buf.try_get_string()?;
+        if version >= ProtocolVersion::V1_18_1 {
+            self.forced = buf.try_get_bool()?;
+            self.prompt = if buf.try_get_bool()? {
+                Some(buf.try_get_string()?)
+            } else {
+                None
+            };
+        }
+        Ok(())
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_string(&self.url);
+        buf.push_string(&self.hash);
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_bool(self.forced);
+            buf.push_bool(self.prompt.is_some());
+            if let Some(prompt) = &self.prompt {
+                buf.push_string(prompt);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::ResourcePackSend
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::ResourcePackSend
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
 pub struct Respawn {
     pub dimension: i32,
     pub difficulty: u8,
     pub gamemode: u8,
     pub level_type: String,
+    // The remaining fields are only sent since 1.16.
+    /// The NBT-encoded type of the dimension.
+    pub dimension_type: Vec<u8>,
+    pub world_name: String,
+    pub is_flat: bool,
+}
+
+impl Packet for Respawn {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.dimension = buf.try_get_i32()?;
+        self.difficulty = buf.try_get_u8()?;
+        self.gamemode = buf.try_get_u8()?;
+        self.level_type = buf.try_get_string()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        if version == ProtocolVersion::V1_13_2 {
+            buf.push_i32(self.dimension);
+            buf.push_u8(self.difficulty);
+            buf.push_u8(self.gamemode);
+            buf.push_string(&self.level_type);
+            return;
+        }
+
+        buf.extend_from_slice(&self.dimension_type);
+        buf.push_string(&self.world_name);
+        buf.push_i64(0); // Hashed seed
+        buf.push_u8(self.gamemode);
+        buf.push_u8(self.gamemode); // Previous gamemode
+        buf.push_bool(false); // Debug world
+        buf.push_bool(self.is_flat);
+        buf.push_bool(false); // Copy metadata
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::Respawn
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::Respawn
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
 #[derive(Default, AsAny, Packet, Clone)]
@@ -2350,8 +4461,12 @@ impl Packet for PacketEntityMetadata {
     }
 
     fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
         buf.push_var_int(self.entity_id);
-        buf.push_metadata(&self.metadata);
+        buf.push_metadata_for(&self.metadata, version);
     }
 
     fn ty(&self) -> PacketType {
@@ -2370,6 +4485,12 @@ impl Packet for PacketEntityMetadata {
     }
 }
 
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct AttachEntity {
+    pub attached_entity_id: i32,
+    pub holding_entity_id: i32,
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct EntityVelocity {
     pub entity_id: VarInt,
@@ -2378,94 +4499,1128 @@ pub struct EntityVelocity {
     pub velocity_z: i16,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct EntityEquipment {
     pub entity_id: VarInt,
     pub slot: VarInt,
     pub item: Slot,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
-pub struct HeldItemChangeClientbound {
-    pub slot: i8,
+impl Packet for EntityEquipment {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_id = buf.try_get_var_int()?;
+        self.slot = buf.try_get_var_int()?;
+        self.item = buf.try_get_slot()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_var_int(self.entity_id);
+        match version {
+            ProtocolVersion::V1_13_2 => buf.push_var_int(self.slot),
+            // Since 1.16, the packet holds a list of slots, each
+            // slot byte having its top bit set if another follows.
+            _ => buf.push_u8(self.slot as u8 & 0x7F),
+        }
+        buf.push_slot_for(self.item, version);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::EntityEquipment
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::EntityEquipment
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
 #[derive(Default, AsAny, Packet, Clone)]
-pub struct UpdateHealth {
-    pub health: f32,
-    pub food: VarInt,
-    pub saturation: f32,
+pub struct HeldItemChangeClientbound {
+    pub slot: i8,
 }
 
-// TODO Select Advancement Tab
-// TODO World Border
-
+/// Sets the chunk at the center of a player's view, since
+/// 1.14 clients only load chunks within their view distance.
 #[derive(Default, AsAny, Packet, Clone)]
-pub struct SpawnPosition {
-    pub location: BlockPosition,
+pub struct UpdateViewPosition {
+    pub chunk_x: VarInt,
+    pub chunk_z: VarInt,
 }
 
 #[derive(Default, AsAny, Packet, Clone)]
-pub struct TimeUpdate {
-    pub world_age: i64,
-    pub time_of_day: i64,
+pub struct SetExperience {
+    /// Progress towards the next level, between 0 and 1.
+    pub experience_bar: f32,
+    pub level: VarInt,
+    pub total_experience: VarInt,
 }
 
 #[derive(Default, AsAny, Packet, Clone)]
-pub struct CollectItem {
-    pub collected: VarInt,
-    pub collector: VarInt,
-    pub count: VarInt,
+pub struct UpdateHealth {
+    pub health: f32,
+    pub food: VarInt,
+    pub saturation: f32,
 }
 
 #[derive(Default, AsAny, Packet, Clone)]
-pub struct EntityTeleport {
-    pub entity_id: VarInt,
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
-    pub yaw: u8,
-    pub pitch: u8,
-    pub on_ground: bool,
+pub struct DisplayScoreboard {
+    /// 0 for the list, 1 for the sidebar and 2 below names.
+    pub position: i8,
+    /// The objective to display, or an empty
+    /// string to clear the position.
+    pub score_name: String,
 }
 
 #[derive(Default, AsAny, Clone)]
-pub struct Tags {
-    pub block_tags: Vec<(String, Vec<VarInt>)>,
-    pub item_tags: Vec<(String, Vec<VarInt>)>,
-    pub fluid_tags: Vec<(String, Vec<VarInt>)>,
+pub struct ScoreboardObjective {
+    pub objective_name: String,
+    pub action: ObjectiveAction,
 }
 
-impl Packet for Tags {
-    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
-        unimplemented!()
+impl Packet for ScoreboardObjective {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.objective_name = buf.try_get_string()?;
+        self.action = match buf.try_get_i8()? {
+            0 => {
+                let display_name = buf.try_get_string()?;
+                ObjectiveAction::Create(display_name, read_render_type(buf)?)
+            }
+            1 => ObjectiveAction::Remove,
+            2 => {
+                let display_name = buf.try_get_string()?;
+                ObjectiveAction::Update(display_name, read_render_type(buf)?)
+            }
+            x => return Err(Error::InvalidObjectiveMode(x).into()),
+        };
+
+        Ok(())
     }
 
     fn write_to(&self, buf: &mut BytesMut) {
-        for field in [&self.block_tags, &self.item_tags, &self.fluid_tags].iter_mut() {
-            buf.push_var_int(field.len() as i32);
-            for (identifier, entries) in field.iter() {
-                buf.push_string(identifier.as_str());
-                buf.push_var_int(entries.len().try_into().unwrap());
-                for entry in entries {
-                    buf.push_var_int(*entry);
-                }
+        buf.push_string(&self.objective_name);
+
+        match &self.action {
+            ObjectiveAction::Create(display_name, render_type) => {
+                buf.push_i8(0);
+                buf.push_string(display_name);
+                buf.push_var_int(*render_type as i32);
+            }
+            ObjectiveAction::Remove => buf.push_i8(1),
+            ObjectiveAction::Update(display_name, render_type) => {
+                buf.push_i8(2);
+                buf.push_string(display_name);
+                buf.push_var_int(*render_type as i32);
             }
         }
     }
 
     fn ty(&self) -> PacketType {
-        PacketType::Tags
+        PacketType::ScoreboardObjective
     }
 
     fn ty_sized() -> PacketType
     where
         Self: Sized,
     {
-        PacketType::Tags
+        PacketType::ScoreboardObjective
     }
 
     fn box_clone(&self) -> Box<dyn Packet> {
         box_clone_impl!(self);
     }
 }
+
+fn read_render_type(buf: &mut Cursor<&[u8]>) -> anyhow::Result<ObjectiveRenderType> {
+    match buf.try_get_var_int()? {
+        0 => Ok(ObjectiveRenderType::Integer),
+        1 => Ok(ObjectiveRenderType::Hearts),
+        x => Err(Error::InvalidObjectiveRenderType(x).into()),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectiveAction {
+    /// Creates an objective with the given display name, as JSON text.
+    Create(String, ObjectiveRenderType),
+    Remove,
+    Update(String, ObjectiveRenderType),
+}
+
+impl Default for ObjectiveAction {
+    fn default() -> Self {
+        ObjectiveAction::Remove
+    }
+}
+
+/// How the scores of an objective are shown in the list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ObjectiveRenderType {
+    Integer = 0,
+    Hearts = 1,
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct SetPassengers {
+    pub entity_id: VarInt,
+    pub passengers: Vec<VarInt>,
+}
+
+impl Packet for SetPassengers {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.entity_id);
+        buf.push_var_int(self.passengers.len() as i32);
+
+        for passenger in &self.passengers {
+            buf.push_var_int(*passenger);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SetPassengers
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SetPassengers
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct Teams {
+    pub name: String,
+    pub action: TeamsAction,
+}
+
+impl Packet for Teams {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.name = buf.try_get_string()?;
+        self.action = match buf.try_get_i8()? {
+            0 => {
+                let info = read_team_info(buf)?;
+                TeamsAction::Create(info, read_team_entities(buf)?)
+            }
+            1 => TeamsAction::Remove,
+            2 => TeamsAction::UpdateInfo(read_team_info(buf)?),
+            3 => TeamsAction::AddEntities(read_team_entities(buf)?),
+            4 => TeamsAction::RemoveEntities(read_team_entities(buf)?),
+            x => return Err(Error::InvalidTeamsMode(x).into()),
+        };
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_string(&self.name);
+        buf.push_i8(self.action.id());
+
+        match &self.action {
+            TeamsAction::Create(info, entities) => {
+                write_team_info(buf, info);
+                write_team_entities(buf, entities);
+            }
+            TeamsAction::Remove => (),
+            TeamsAction::UpdateInfo(info) => write_team_info(buf, info),
+            TeamsAction::AddEntities(entities) | TeamsAction::RemoveEntities(entities) => {
+                write_team_entities(buf, entities);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::Teams
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::Teams
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+fn read_team_info(buf: &mut Cursor<&[u8]>) -> anyhow::Result<TeamInfo> {
+    Ok(TeamInfo {
+        display_name: buf.try_get_string()?,
+        friendly_flags: buf.try_get_u8()?,
+        name_tag_visibility: buf.try_get_string()?,
+        collision_rule: buf.try_get_string()?,
+        color: buf.try_get_var_int()?,
+        prefix: buf.try_get_string()?,
+        suffix: buf.try_get_string()?,
+    })
+}
+
+fn write_team_info(buf: &mut BytesMut, info: &TeamInfo) {
+    buf.push_string(&info.display_name);
+    buf.push_u8(info.friendly_flags);
+    buf.push_string(&info.name_tag_visibility);
+    buf.push_string(&info.collision_rule);
+    buf.push_var_int(info.color);
+    buf.push_string(&info.prefix);
+    buf.push_string(&info.suffix);
+}
+
+fn read_team_entities(buf: &mut Cursor<&[u8]>) -> anyhow::Result<Vec<String>> {
+    let count = buf.try_get_var_int()?;
+    let mut entities = vec![];
+    for _ in 0..count {
+        entities.push(buf.try_get_string()?);
+    }
+    Ok(entities)
+}
+
+fn write_team_entities(buf: &mut BytesMut, entities: &[String]) {
+    buf.push_var_int(entities.len() as i32);
+    for entity in entities {
+        buf.push_string(entity);
+    }
+}
+
+/// The properties of a scoreboard team. Players are
+/// team members by name, and other entities by UUID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TeamInfo {
+    /// JSON text.
+    pub display_name: String,
+    /// 0x01 allows friendly fire; 0x02 lets members
+    /// see invisible members.
+    pub friendly_flags: u8,
+    /// `always`, `hideForOtherTeams`, `hideForOwnTeam` or `never`.
+    pub name_tag_visibility: String,
+    /// `always`, `pushOtherTeams`, `pushOwnTeam` or `never`.
+    pub collision_rule: String,
+    /// The ID of the formatting code coloring members' names,
+    /// e.g. 0 for black or 21 for reset.
+    pub color: VarInt,
+    /// JSON text shown before members' names.
+    pub prefix: String,
+    /// JSON text shown after members' names.
+    pub suffix: String,
+}
+
+impl Default for TeamInfo {
+    fn default() -> Self {
+        Self {
+            display_name: String::from(r#"{"text":""}"#),
+            friendly_flags: 0x01,
+            name_tag_visibility: String::from("always"),
+            collision_rule: String::from("always"),
+            color: 21,
+            prefix: String::from(r#"{"text":""}"#),
+            suffix: String::from(r#"{"text":""}"#),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TeamsAction {
+    /// Creates a team with the given members.
+    Create(TeamInfo, Vec<String>),
+    Remove,
+    UpdateInfo(TeamInfo),
+    AddEntities(Vec<String>),
+    RemoveEntities(Vec<String>),
+}
+
+impl Default for TeamsAction {
+    fn default() -> Self {
+        TeamsAction::Remove
+    }
+}
+
+impl TeamsAction {
+    fn id(&self) -> i8 {
+        match self {
+            TeamsAction::Create(_, _) => 0,
+            TeamsAction::Remove => 1,
+            TeamsAction::UpdateInfo(_) => 2,
+            TeamsAction::AddEntities(_) => 3,
+            TeamsAction::RemoveEntities(_) => 4,
+        }
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct UpdateScore {
+    /// The player name or, for other entities, the UUID whose score changed.
+    pub entity_name: String,
+    pub objective_name: String,
+    /// The new score, or `None` to remove it.
+    pub value: Option<VarInt>,
+}
+
+impl Packet for UpdateScore {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_name = buf.try_get_string()?;
+        let action = buf.try_get_i8()?;
+        self.objective_name = buf.try_get_string()?;
+        self.value = match action {
+            0 => Some(buf.try_get_var_int()?),
+            1 => None,
+            x => return Err(Error::InvalidUpdateScoreAction(x).into()),
+        };
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_string(&self.entity_name);
+        buf.push_i8(if self.value.is_some() { 0 } else { 1 });
+        buf.push_string(&self.objective_name);
+        if let Some(value) = self.value {
+            buf.push_var_int(value);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::UpdateScore
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::UpdateScore
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct SelectAdvancementTab {
+    /// The tab to switch to, or `None` to close the
+    /// advancements screen.
+    pub identifier: Option<String>,
+}
+
+impl Packet for SelectAdvancementTab {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.identifier = if buf.try_get_bool()? {
+            Some(buf.try_get_string()?)
+        } else {
+            None
+        };
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_bool(self.identifier.is_some());
+        if let Some(identifier) = &self.identifier {
+            buf.push_string(identifier);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SelectAdvancementTab
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SelectAdvancementTab
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct WorldBorder {
+    pub action: WorldBorderAction,
+}
+
+impl Packet for WorldBorder {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.action = match buf.try_get_var_int()? {
+            0 => WorldBorderAction::SetSize(buf.try_get_f64()?),
+            1 => WorldBorderAction::LerpSize(
+                buf.try_get_f64()?,
+                buf.try_get_f64()?,
+                buf.try_get_var_long()?,
+            ),
+            2 => WorldBorderAction::SetCenter(buf.try_get_f64()?, buf.try_get_f64()?),
+            3 => WorldBorderAction::Initialize {
+                x: buf.try_get_f64()?,
+                z: buf.try_get_f64()?,
+                old_diameter: buf.try_get_f64()?,
+                new_diameter: buf.try_get_f64()?,
+                speed: buf.try_get_var_long()?,
+                portal_teleport_boundary: buf.try_get_var_int()?,
+                warning_time: buf.try_get_var_int()?,
+                warning_blocks: buf.try_get_var_int()?,
+            },
+            4 => WorldBorderAction::SetWarningTime(buf.try_get_var_int()?),
+            5 => WorldBorderAction::SetWarningBlocks(buf.try_get_var_int()?),
+            x => return Err(Error::InvalidWorldBorderAction(x).into()),
+        };
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.action.id());
+
+        match self.action {
+            WorldBorderAction::SetSize(diameter) => {
+                buf.push_f64(diameter);
+            }
+            WorldBorderAction::LerpSize(old_diameter, new_diameter, speed) => {
+                buf.push_f64(old_diameter);
+                buf.push_f64(new_diameter);
+                buf.push_var_long(speed);
+            }
+            WorldBorderAction::SetCenter(x, z) => {
+                buf.push_f64(x);
+                buf.push_f64(z);
+            }
+            WorldBorderAction::Initialize {
+                x,
+                z,
+                old_diameter,
+                new_diameter,
+                speed,
+                portal_teleport_boundary,
+                warning_time,
+                warning_blocks,
+            } => {
+                buf.push_f64(x);
+                buf.push_f64(z);
+                buf.push_f64(old_diameter);
+                buf.push_f64(new_diameter);
+                buf.push_var_long(speed);
+                buf.push_var_int(portal_teleport_boundary);
+                buf.push_var_int(warning_time);
+                buf.push_var_int(warning_blocks);
+            }
+            WorldBorderAction::SetWarningTime(time) => {
+                buf.push_var_int(time);
+            }
+            WorldBorderAction::SetWarningBlocks(blocks) => {
+                buf.push_var_int(blocks);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::WorldBorder
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::WorldBorder
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// An update to the world border. Diameters are in blocks;
+/// speeds and warning times are in milliseconds and
+/// seconds respectively.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldBorderAction {
+    SetSize(f64),
+    /// Old diameter, new diameter and the time to move between them.
+    LerpSize(f64, f64, i64),
+    SetCenter(f64, f64),
+    Initialize {
+        x: f64,
+        z: f64,
+        old_diameter: f64,
+        new_diameter: f64,
+        speed: i64,
+        portal_teleport_boundary: i32,
+        warning_time: i32,
+        warning_blocks: i32,
+    },
+    SetWarningTime(i32),
+    SetWarningBlocks(i32),
+}
+
+impl Default for WorldBorderAction {
+    fn default() -> Self {
+        WorldBorderAction::SetSize(0.0)
+    }
+}
+
+impl WorldBorderAction {
+    fn id(&self) -> i32 {
+        match self {
+            WorldBorderAction::SetSize(_) => 0,
+            WorldBorderAction::LerpSize(_, _, _) => 1,
+            WorldBorderAction::SetCenter(_, _) => 2,
+            WorldBorderAction::Initialize { .. } => 3,
+            WorldBorderAction::SetWarningTime(_) => 4,
+            WorldBorderAction::SetWarningBlocks(_) => 5,
+        }
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct SpawnPosition {
+    pub location: BlockPosition,
+}
+
+impl Packet for SpawnPosition {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.location = buf.try_get_position()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_position_for(&self.location, version);
+        if version >= ProtocolVersion::V1_18_1 {
+            buf.push_f32(0.0); // Angle
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SpawnPosition
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SpawnPosition
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct TimeUpdate {
+    pub world_age: i64,
+    pub time_of_day: i64,
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct Title {
+    pub action: TitleAction,
+}
+
+impl Packet for Title {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.action = match buf.try_get_var_int()? {
+            0 => TitleAction::SetTitle(buf.try_get_string()?),
+            1 => TitleAction::SetSubtitle(buf.try_get_string()?),
+            2 => TitleAction::SetActionBar(buf.try_get_string()?),
+            3 => TitleAction::SetTimes {
+                fade_in: buf.try_get_i32()?,
+                stay: buf.try_get_i32()?,
+                fade_out: buf.try_get_i32()?,
+            },
+            4 => TitleAction::Hide,
+            5 => TitleAction::Reset,
+            x => return Err(Error::InvalidTitleAction(x).into()),
+        };
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        match &self.action {
+            TitleAction::SetTitle(text) => {
+                buf.push_var_int(0);
+                buf.push_string(text);
+            }
+            TitleAction::SetSubtitle(text) => {
+                buf.push_var_int(1);
+                buf.push_string(text);
+            }
+            TitleAction::SetActionBar(text) => {
+                buf.push_var_int(2);
+                buf.push_string(text);
+            }
+            TitleAction::SetTimes {
+                fade_in,
+                stay,
+                fade_out,
+            } => {
+                buf.push_var_int(3);
+                buf.push_i32(*fade_in);
+                buf.push_i32(*stay);
+                buf.push_i32(*fade_out);
+            }
+            TitleAction::Hide => {
+                buf.push_var_int(4);
+            }
+            TitleAction::Reset => {
+                buf.push_var_int(5);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::Title
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::Title
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// An action of the `Title` packet, which clients since
+/// 1.17 receive as separate packets instead. Text is JSON,
+/// and times are in ticks.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TitleAction {
+    SetTitle(String),
+    SetSubtitle(String),
+    SetActionBar(String),
+    SetTimes {
+        fade_in: i32,
+        stay: i32,
+        fade_out: i32,
+    },
+    /// Hides the current title, keeping its text and times.
+    Hide,
+    /// Hides the current title and resets its text and times.
+    Reset,
+}
+
+impl Default for TitleAction {
+    fn default() -> Self {
+        TitleAction::Reset
+    }
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct ActionBar {
+    pub text: String,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct ClearTitles {
+    /// Whether to also reset the title's text and times.
+    pub reset: bool,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct SetTitleSubtitle {
+    pub text: String,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct SetTitleText {
+    pub text: String,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct SetTitleTimes {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+/// Shows the death screen with a message. Sent alongside
+/// `CombatEvent::EntityDead`, which it replaces since 1.17.
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct DeathCombatEvent {
+    pub player_id: VarInt,
+    /// The killer's entity ID, or -1 if there is none.
+    pub entity_id: i32,
+    pub message: String,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct PlayerListHeaderAndFooter {
+    pub header: String,
+    pub footer: String,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct CollectItem {
+    pub collected: VarInt,
+    pub collector: VarInt,
+    pub count: VarInt,
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct EntityTeleport {
+    pub entity_id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+}
+
+/// How an advancement is displayed in the advancements
+/// screen and in the toast shown when it is made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvancementDisplay {
+    /// JSON text.
+    pub title: String,
+    /// JSON text.
+    pub description: String,
+    pub icon: Slot,
+    /// 0 for a task, 1 for a challenge and 2 for a goal.
+    pub frame: VarInt,
+    /// Background texture of the tab, only set on root advancements.
+    pub background: Option<String>,
+    pub show_toast: bool,
+    pub hidden: bool,
+    /// Position in the advancements screen.
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvancementData {
+    pub parent: Option<String>,
+    /// `None` for advancements not shown in the advancements screen.
+    pub display: Option<AdvancementDisplay>,
+    pub criteria: Vec<String>,
+    /// The advancement is made once, for each inner list,
+    /// one of its criteria has been achieved.
+    pub requirements: Vec<Vec<String>>,
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct Advancements {
+    /// Whether to clear all advancements before adding these.
+    pub reset: bool,
+    pub advancements: Vec<(String, AdvancementData)>,
+    pub removed: Vec<String>,
+    /// Progress of advancements, as the time each of their criteria
+    /// was achieved in milliseconds since the epoch, if it was.
+    pub progress: Vec<(String, Vec<(String, Option<i64>)>)>,
+}
+
+impl Packet for Advancements {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.reset = buf.try_get_bool()?;
+
+        let num_advancements = buf.try_get_var_int()?;
+        self.advancements.clear();
+        for _ in 0..num_advancements {
+            let id = buf.try_get_string()?;
+            let parent = if buf.try_get_bool()? {
+                Some(buf.try_get_string()?)
+            } else {
+                None
+            };
+            let display = if buf.try_get_bool()? {
+                let title = buf.try_get_string()?;
+                let description = buf.try_get_string()?;
+                let icon = buf.try_get_slot()?;
+                let frame = buf.try_get_var_int()?;
+                let flags = buf.try_get_i32()?;
+                let background = if flags & 0x1 != 0 {
+                    Some(buf.try_get_string()?)
+                } else {
+                    None
+                };
+                Some(AdvancementDisplay {
+                    title,
+                    description,
+                    icon,
+                    frame,
+                    background,
+                    show_toast: flags & 0x2 != 0,
+                    hidden: flags & 0x4 != 0,
+                    x: buf.try_get_f32()?,
+                    y: buf.try_get_f32()?,
+                })
+            } else {
+                None
+            };
+
+            let num_criteria = buf.try_get_var_int()?;
+            let mut criteria = vec![];
+            for _ in 0..num_criteria {
+                criteria.push(buf.try_get_string()?);
+            }
+
+            let num_requirements = buf.try_get_var_int()?;
+            let mut requirements = vec![];
+            for _ in 0..num_requirements {
+                let len = buf.try_get_var_int()?;
+                let mut requirement = vec![];
+                for _ in 0..len {
+                    requirement.push(buf.try_get_string()?);
+                }
+                requirements.push(requirement);
+            }
+
+            self.advancements.push((
+                id,
+                AdvancementData {
+                    parent,
+                    display,
+                    criteria,
+                    requirements,
+                },
+            ));
+        }
+
+        let num_removed = buf.try_get_var_int()?;
+        self.removed.clear();
+        for _ in 0..num_removed {
+            self.removed.push(buf.try_get_string()?);
+        }
+
+        let num_progress = buf.try_get_var_int()?;
+        self.progress.clear();
+        for _ in 0..num_progress {
+            let id = buf.try_get_string()?;
+            let num_criteria = buf.try_get_var_int()?;
+            let mut criteria = vec![];
+            for _ in 0..num_criteria {
+                let criterion = buf.try_get_string()?;
+                let achieved = if buf.try_get_bool()? {
+                    Some(buf.try_get_i64()?)
+                } else {
+                    None
+                };
+                criteria.push((criterion, achieved));
+            }
+            self.progress.push((id, criteria));
+        }
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        self.write_to_version(buf, ProtocolVersion::V1_13_2)
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        buf.push_bool(self.reset);
+
+        buf.push_var_int(self.advancements.len() as i32);
+        for (id, advancement) in &self.advancements {
+            buf.push_string(id);
+            buf.push_bool(advancement.parent.is_some());
+            if let Some(parent) = &advancement.parent {
+                buf.push_string(parent);
+            }
+
+            buf.push_bool(advancement.display.is_some());
+            if let Some(display) = &advancement.display {
+                buf.push_string(&display.title);
+                buf.push_string(&display.description);
+                buf.push_slot_for(display.icon, version);
+                buf.push_var_int(display.frame);
+
+                let mut flags = 0;
+                if display.background.is_some() {
+                    flags |= 0x1;
+                }
+                if display.show_toast {
+                    flags |= 0x2;
+                }
+                if display.hidden {
+                    flags |= 0x4;
+                }
+                buf.push_i32(flags);
+                if let Some(background) = &display.background {
+                    buf.push_string(background);
+                }
+
+                buf.push_f32(display.x);
+                buf.push_f32(display.y);
+            }
+
+            buf.push_var_int(advancement.criteria.len() as i32);
+            for criterion in &advancement.criteria {
+                buf.push_string(criterion);
+            }
+
+            buf.push_var_int(advancement.requirements.len() as i32);
+            for requirement in &advancement.requirements {
+                buf.push_var_int(requirement.len() as i32);
+                for criterion in requirement {
+                    buf.push_string(criterion);
+                }
+            }
+        }
+
+        buf.push_var_int(self.removed.len() as i32);
+        for id in &self.removed {
+            buf.push_string(id);
+        }
+
+        buf.push_var_int(self.progress.len() as i32);
+        for (id, criteria) in &self.progress {
+            buf.push_string(id);
+            buf.push_var_int(criteria.len() as i32);
+            for (criterion, achieved) in criteria {
+                buf.push_string(criterion);
+                buf.push_bool(achieved.is_some());
+                if let Some(time) = achieved {
+                    buf.push_i64(*time);
+                }
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::Advancements
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::Advancements
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct EntityEffect {
+    pub entity_id: VarInt,
+    pub effect_id: i8,
+    pub amplifier: i8,
+    /// Duration in ticks.
+    pub duration: VarInt,
+    /// Bit 0x01 is set for ambient effects, e.g. from beacons,
+    /// 0x02 to show particles and 0x04 to show an icon.
+    pub flags: i8,
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct Tags {
+    pub block_tags: Vec<(String, Vec<VarInt>)>,
+    pub item_tags: Vec<(String, Vec<VarInt>)>,
+    pub fluid_tags: Vec<(String, Vec<VarInt>)>,
+}
+
+impl Packet for Tags {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        for field in [&self.block_tags, &self.item_tags, &self.fluid_tags].iter_mut() {
+            buf.push_var_int(field.len() as i32);
+            for (identifier, entries) in field.iter() {
+                buf.push_string(identifier.as_str());
+                buf.push_var_int(entries.len().try_into().unwrap());
+                for entry in entries {
+                    buf.push_var_int(*entry);
+                }
+            }
+        }
+    }
+
+    fn write_to_version(&self, buf: &mut BytesMut, version: ProtocolVersion) {
+        match version {
+            ProtocolVersion::V1_13_2 => self.write_to(buf),
+            _ => mappings::write_tags(buf, version),
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::Tags
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::Tags
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_info_signatures() {
+        let packet = PlayerInfo {
+            action: PlayerInfoAction::AddPlayer(
+                "player".to_owned(),
+                vec![
+                    ("textures".to_owned(), "unsigned".to_owned(), String::new()),
+                    ("other".to_owned(), "signed".to_owned(), "sig".to_owned()),
+                ],
+                Gamemode::Survival,
+                0,
+                None,
+            ),
+            uuid: Uuid::nil(),
+        };
+        let mut buf = BytesMut::new();
+        packet.write_to(&mut buf);
+
+        let mut expected = BytesMut::new();
+        expected.push_var_int(0);
+        expected.push_var_int(1);
+        expected.push_uuid(&Uuid::nil());
+        expected.push_string("player");
+        expected.push_var_int(2);
+        expected.push_string("textures");
+        expected.push_string("unsigned");
+        expected.push_bool(false);
+        expected.push_string("other");
+        expected.push_string("signed");
+        expected.push_bool(true);
+        expected.push_string("sig");
+        expected.push_var_int(0);
+        expected.push_var_int(0);
+        expected.push_bool(false);
+        assert_eq!(buf, expected);
+
+        let mut read = PlayerInfo::default();
+        read.read_from(&mut Cursor::new(&buf[..])).unwrap();
+        assert_eq!(read.action, packet.action);
+    }
+}
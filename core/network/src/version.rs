@@ -0,0 +1,94 @@
+//! The protocol versions supported by the server.
+
+use std::fmt::{self, Display, Formatter};
+
+/// A version of the protocol spoken by clients.
+///
+/// Packets are defined in their 1.13.2 form. Those which
+/// changed in later versions are converted when read or
+/// written by a codec set to another version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    /// Minecraft 1.13.2, protocol version 404.
+    V1_13_2,
+    /// Minecraft 1.16.5, protocol version 754.
+    V1_16_5,
+    /// Minecraft 1.18.1, protocol version 757.
+    V1_18_1,
+}
+
+impl ProtocolVersion {
+    /// All supported protocol versions, oldest first.
+    pub const ALL: [ProtocolVersion; 3] = [
+        ProtocolVersion::V1_13_2,
+        ProtocolVersion::V1_16_5,
+        ProtocolVersion::V1_18_1,
+    ];
+
+    /// Returns the protocol version sent by clients in the Handshake packet.
+    pub fn id(self) -> u32 {
+        match self {
+            ProtocolVersion::V1_13_2 => 404,
+            ProtocolVersion::V1_16_5 => 754,
+            ProtocolVersion::V1_18_1 => 757,
+        }
+    }
+
+    /// Returns the protocol version with the given ID,
+    /// or `None` if it's not supported.
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|version| version.id() == id)
+    }
+
+    /// Returns the name of the Minecraft version using this protocol.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_13_2 => "1.13.2",
+            ProtocolVersion::V1_16_5 => "1.16.5",
+            ProtocolVersion::V1_18_1 => "1.18.1",
+        }
+    }
+
+    /// Returns the newest supported protocol version.
+    pub fn latest() -> Self {
+        ProtocolVersion::V1_18_1
+    }
+}
+
+impl Default for ProtocolVersion {
+    fn default() -> Self {
+        ProtocolVersion::V1_13_2
+    }
+}
+
+impl Display for ProtocolVersion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids() {
+        for &version in ProtocolVersion::ALL.iter() {
+            assert_eq!(ProtocolVersion::from_id(version.id()), Some(version));
+        }
+        assert_eq!(
+            ProtocolVersion::from_id(404),
+            Some(ProtocolVersion::V1_13_2)
+        );
+        assert_eq!(
+            ProtocolVersion::from_id(754),
+            Some(ProtocolVersion::V1_16_5)
+        );
+        assert_eq!(
+            ProtocolVersion::from_id(757),
+            Some(ProtocolVersion::V1_18_1)
+        );
+        assert_eq!(ProtocolVersion::from_id(578), None);
+        assert_eq!(ProtocolVersion::latest().to_string(), "1.18.1");
+    }
+}
@@ -3,6 +3,7 @@ pub extern crate feather_biomes as biomes;
 pub extern crate feather_blocks as blocks;
 pub extern crate feather_chunk as chunk;
 pub extern crate feather_chunk_map as chunk_map;
+pub extern crate feather_dimension as dimension;
 pub extern crate feather_entity_metadata as entitymeta;
 pub extern crate feather_game_rules as game_rules;
 pub extern crate feather_inventory as inventory;
@@ -311,7 +311,7 @@ impl<'a> From<&Translate> for String {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(tag = "action", content = "value")]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
 // TODO: Accept any json primitive as string
 pub enum Click {
     OpenUrl(Cow<'static, str>),
@@ -322,12 +322,197 @@ pub enum Click {
     CopyToClipboard(Cow<'static, str>),
 }
 
-#[serde_with::skip_serializing_none]
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// An entity shown when hovering over text.
+///
+/// Sent as SNBT, e.g. `{id:"<uuid>",type:"minecraft:pig",name:"{\"text\":\"Pig\"}"}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Entity {
-    id: Uuid,
-    ty: Option<Cow<'static, str>>,
-    name: Cow<'static, str>,
+    pub id: Uuid,
+    /// The entity's type, e.g. `minecraft:pig`.
+    pub ty: Option<Cow<'static, str>>,
+    pub name: Cow<'static, str>,
+}
+
+impl Entity {
+    pub fn new<A: Into<Cow<'static, str>>>(id: Uuid, name: A) -> Self {
+        Self {
+            id,
+            ty: None,
+            name: name.into(),
+        }
+    }
+
+    pub fn with_type<A: Into<Cow<'static, str>>>(mut self, ty: A) -> Self {
+        self.ty = Some(ty.into());
+        self
+    }
+
+    fn to_snbt(&self) -> String {
+        let mut snbt = format!("{{id:{}", snbt_string(&self.id.to_string()));
+        if let Some(ty) = &self.ty {
+            snbt += &format!(",type:{}", snbt_string(ty));
+        }
+        // The name is JSON text.
+        let name: String = Text::from(self.name.clone()).into();
+        snbt += &format!(",name:{}}}", snbt_string(&name));
+        snbt
+    }
+
+    fn from_snbt(snbt: &str) -> Option<Self> {
+        let mut entity = Entity::new(Uuid::nil(), "");
+        for (key, value) in parse_snbt_compound(snbt)? {
+            match key.as_str() {
+                "id" => entity.id = Uuid::parse_str(&value).ok()?,
+                "type" => entity.ty = Some(value.into()),
+                "name" => {
+                    entity.name = match serde_json::from_str::<Text>(&value) {
+                        Ok(name) => name.to_plain().into(),
+                        Err(_) => value.into(),
+                    }
+                }
+                _ => (),
+            }
+        }
+        Some(entity)
+    }
+}
+
+impl Serialize for Entity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_snbt())
+    }
+}
+
+impl<'de> Deserialize<'de> for Entity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snbt = String::deserialize(deserializer)?;
+        Entity::from_snbt(&snbt).ok_or_else(|| serde::de::Error::custom("invalid entity SNBT"))
+    }
+}
+
+/// An item shown when hovering over text.
+///
+/// Sent as SNBT, e.g. `{id:"minecraft:diamond",Count:3b}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    /// The item's identifier, e.g. `minecraft:diamond`.
+    pub id: Cow<'static, str>,
+    pub count: i8,
+    /// The item's NBT tag, as SNBT.
+    pub tag: Option<Cow<'static, str>>,
+}
+
+impl Item {
+    pub fn new<A: Into<Cow<'static, str>>>(id: A, count: i8) -> Self {
+        Self {
+            id: id.into(),
+            count,
+            tag: None,
+        }
+    }
+
+    pub fn with_tag<A: Into<Cow<'static, str>>>(mut self, tag: A) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    fn to_snbt(&self) -> String {
+        let mut snbt = format!("{{id:{},Count:{}b", snbt_string(&self.id), self.count);
+        if let Some(tag) = &self.tag {
+            snbt += &format!(",tag:{}", tag);
+        }
+        snbt.push('}');
+        snbt
+    }
+
+    fn from_snbt(snbt: &str) -> Option<Self> {
+        let mut item = Item::new("", 1);
+        for (key, value) in parse_snbt_compound(snbt)? {
+            match key.as_str() {
+                "id" => item.id = value.into(),
+                "Count" => {
+                    item.count = value
+                        .trim_end_matches(|c| c == 'b' || c == 'B')
+                        .parse()
+                        .ok()?
+                }
+                "tag" => item.tag = Some(value.into()),
+                _ => (),
+            }
+        }
+        Some(item)
+    }
+}
+
+impl Serialize for Item {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_snbt())
+    }
+}
+
+impl<'de> Deserialize<'de> for Item {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snbt = String::deserialize(deserializer)?;
+        Item::from_snbt(&snbt).ok_or_else(|| serde::de::Error::custom("invalid item SNBT"))
+    }
+}
+
+/// Quotes a string for SNBT.
+fn snbt_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parses the entries of an SNBT compound. Strings are unquoted,
+/// while nested compounds and other values are left as SNBT.
+fn parse_snbt_compound(snbt: &str) -> Option<Vec<(String, String)>> {
+    let mut chars = snbt
+        .trim()
+        .strip_prefix('{')?
+        .strip_suffix('}')?
+        .chars()
+        .peekable();
+    let mut entries = vec![];
+
+    loop {
+        let key: String = chars.by_ref().take_while(|c| *c != ':').collect();
+        let key = key.trim();
+        if key.is_empty() {
+            return Some(entries);
+        }
+
+        while let Some(' ') = chars.peek() {
+            chars.next();
+        }
+
+        let mut value = String::new();
+        match chars.peek() {
+            Some('"') => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => value.push(chars.next()?),
+                        '"' => break,
+                        c => value.push(c),
+                    }
+                }
+                // Skip the separator.
+                chars.next();
+            }
+            _ => {
+                let mut depth = 0;
+                for c in chars.by_ref() {
+                    match c {
+                        '{' | '[' => depth += 1,
+                        '}' | ']' => depth -= 1,
+                        ',' if depth == 0 => break,
+                        _ => (),
+                    }
+                    value.push(c);
+                }
+            }
+        }
+        entries.push((key.to_owned(), value));
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -337,8 +522,7 @@ pub enum Hover {
     #[serde(rename = "show_text")]
     ShowText(Box<Text>),
     #[serde(rename = "show_item")]
-    // TODO: Item struct
-    ShowItem(String),
+    ShowItem(Item),
     #[serde(rename = "show_entity")]
     ShowEntity(Entity),
 }
@@ -551,7 +735,7 @@ pub trait TextComponentBuilder {
 
     fn on_hover(self, hover: Hover) -> Self;
     fn on_hover_show_entity<A: Into<Entity>>(self, entity: A) -> Self;
-    fn on_hover_show_item(self, item: String) -> Self;
+    fn on_hover_show_item<A: Into<Item>>(self, item: A) -> Self;
     fn on_hover_show_text<A: Into<Text>>(self, text: A) -> Self;
 
     fn reset_on_hover(self) -> Self;
@@ -809,8 +993,8 @@ where
         self.on_hover(Hover::ShowEntity(entity.into()))
     }
 
-    fn on_hover_show_item(self, item: String) -> Self {
-        self.on_hover(Hover::ShowItem(item))
+    fn on_hover_show_item<A: Into<Item>>(self, item: A) -> Self {
+        self.on_hover(Hover::ShowItem(item.into()))
     }
 
     fn on_hover_show_text<A: Into<Text>>(self, text: A) -> Self {
@@ -961,6 +1145,42 @@ impl Text {
     pub fn nbt<A: Into<nbt::Blob>>(nbt: A) -> Text {
         Text::from(TextValue::nbt(nbt))
     }
+
+    /// Returns the text without formatting, for consoles which
+    /// can't display JSON text. Translated text is written as
+    /// its key followed by its arguments.
+    pub fn to_plain(&self) -> String {
+        let mut plain = String::new();
+        self.write_plain(&mut plain);
+        plain
+    }
+
+    fn write_plain(&self, plain: &mut String) {
+        match self {
+            Text::String(text) => plain.push_str(text),
+            Text::Array(texts) => texts.iter().for_each(|text| text.write_plain(plain)),
+            Text::Component(component) => {
+                match &component.value {
+                    TextValue::Text { text } => plain.push_str(text),
+                    TextValue::Translate { translate, with } => {
+                        plain.push_str(&String::from(translate));
+                        for arg in with {
+                            plain.push(' ');
+                            arg.write_plain(plain);
+                        }
+                    }
+                    TextValue::Score { value, .. } => {
+                        plain.push_str(value.as_deref().unwrap_or_default())
+                    }
+                    TextValue::Selector { selector } => plain.push_str(selector),
+                    TextValue::Keybind { .. } | TextValue::Nbt { .. } => (),
+                }
+                for text in component.extra.iter().flatten() {
+                    text.write_plain(plain);
+                }
+            }
+        }
+    }
 }
 
 impl From<Text> for String {
@@ -1184,4 +1404,53 @@ mod tests {
 
         assert_eq!(root_json, r#"{"text":"hello"}"#);
     }
+
+    #[test]
+    fn text_plain() {
+        let text = Text::from("Kicked ").red()
+            + (Text::from("test") * Color::Yellow)
+            + Translate::MultiplayerPlayerJoined * vec!["The_Defman"];
+
+        assert_eq!(
+            text.to_plain(),
+            "Kicked testmultiplayer.player.joined The_Defman"
+        );
+    }
+
+    #[test]
+    fn text_events() -> Result<(), Box<dyn Error>> {
+        let text = Text::from("here")
+            .on_click_run_command("/spawn")
+            .on_hover_show_item(Item::new("minecraft:diamond", 3));
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&text)?)?;
+
+        assert_eq!(json["clickEvent"]["action"], "run_command");
+        assert_eq!(json["clickEvent"]["value"], "/spawn");
+        assert_eq!(json["hoverEvent"]["action"], "show_item");
+        assert_eq!(
+            json["hoverEvent"]["value"],
+            r#"{id:"minecraft:diamond",Count:3b}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn hover_snbt() {
+        let entity = Entity::new(Uuid::nil(), "Steve").with_type("minecraft:player");
+        let snbt = entity.to_snbt();
+        assert_eq!(
+            snbt,
+            r#"{id:"00000000-0000-0000-0000-000000000000",type:"minecraft:player",name:"{\"text\":\"Steve\"}"}"#
+        );
+        assert_eq!(Entity::from_snbt(&snbt), Some(entity));
+
+        let item = Item::new("minecraft:diamond_sword", 1).with_tag("{Damage:5}");
+        let snbt = item.to_snbt();
+        assert_eq!(
+            snbt,
+            r#"{id:"minecraft:diamond_sword",Count:1b,tag:{Damage:5}}"#
+        );
+        assert_eq!(Item::from_snbt(&snbt), Some(item));
+    }
 }
@@ -100,6 +100,23 @@ impl Dimension {
             Dimension::End => 1,
         }
     }
+
+    pub fn from_id(id: i32) -> Self {
+        match id {
+            -1 => Dimension::Nether,
+            1 => Dimension::End,
+            _ => Dimension::Overwold,
+        }
+    }
+
+    /// Returns the ID of the vanilla dimension type of this dimension.
+    pub fn type_id(self) -> &'static str {
+        match self {
+            Dimension::Nether => "minecraft:the_nether",
+            Dimension::Overwold => "minecraft:overworld",
+            Dimension::End => "minecraft:the_end",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -2,11 +2,21 @@ use anyhow::Context;
 use std::env;
 use std::fs;
 use std::fs::File;
-use std::io::{copy, Write};
+use std::io::{copy, Cursor, Read, Write};
 use std::path::Path;
 use std::process::Command;
 use zip::ZipArchive;
 
+/// How the vanilla server JAR is packaged.
+#[derive(Copy, Clone)]
+enum JarKind {
+    /// The server classes and data are in the JAR itself.
+    Plain,
+    /// Since 1.18, the JAR is a bundler containing the actual
+    /// server JAR at the given path, along with its libraries.
+    Bundler(&'static str),
+}
+
 fn main() {
     match run() {
         Ok(_) => (),
@@ -17,9 +27,13 @@ fn main() {
 fn run() -> anyhow::Result<()> {
     let path = format!("{}/minecraft", env::var("OUT_DIR")?);
     let path_1_15 = format!("{}/minecraft-1.15", env::var("OUT_DIR")?);
+    let path_1_16 = format!("{}/minecraft-1.16.5", env::var("OUT_DIR")?);
+    let path_1_18 = format!("{}/minecraft-1.18.1", env::var("OUT_DIR")?);
 
-    download_version("https://launcher.mojang.com/v1/objects/3737db93722a9e39eeada7c27e7aca28b144ffa7/server.jar", &path, true).context("failed to download 1.13 data")?;
-    download_version("https://launcher.mojang.com/v1/objects/bb2b6b1aefcd70dfd1892149ac3a215f6c636b07/server.jar", &path_1_15, false).context("failed to download 1.15 data")?;
+    download_version("https://launcher.mojang.com/v1/objects/3737db93722a9e39eeada7c27e7aca28b144ffa7/server.jar", &path, true, JarKind::Plain).context("failed to download 1.13 data")?;
+    download_version("https://launcher.mojang.com/v1/objects/bb2b6b1aefcd70dfd1892149ac3a215f6c636b07/server.jar", &path_1_15, false, JarKind::Plain).context("failed to download 1.15 data")?;
+    download_version("https://launcher.mojang.com/v1/objects/1b557e7b033b583cd9f66746b7a9ab1ec1673ced/server.jar", &path_1_16, true, JarKind::Plain).context("failed to download 1.16.5 data")?;
+    download_version("https://launcher.mojang.com/v1/objects/125e5adf40c659fd3bce3e66e67a16bb49ecc1b9/server.jar", &path_1_18, true, JarKind::Bundler("META-INF/versions/1.18.1/server-1.18.1.jar")).context("failed to download 1.18.1 data")?;
 
     clone_minecraft_data().context("failed to clone PrismarineJS/minecraft-data")?;
 
@@ -30,7 +44,7 @@ fn run() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn download_version(url: &str, path: &str, do_generate: bool) -> anyhow::Result<()> {
+fn download_version(url: &str, path: &str, do_generate: bool, kind: JarKind) -> anyhow::Result<()> {
     let path = Path::new(&path);
     let path_server = path.join("server.jar");
 
@@ -54,10 +68,10 @@ fn download_version(url: &str, path: &str, do_generate: bool) -> anyhow::Result<
     );
 
     if do_generate {
-        generate(path).context("failed to generate vanilla server reports.")?;
+        generate(path, kind).context("failed to generate vanilla server reports.")?;
     }
 
-    extract(path).context("failed to extract vanilla assets.")?;
+    extract(path, kind).context("failed to extract vanilla assets.")?;
     println!(
         "after extract: {:?}",
         std::fs::read_dir(path)?.collect::<Vec<_>>()
@@ -82,11 +96,19 @@ fn download<P: AsRef<Path>>(url: &str, server: P) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn generate<P: AsRef<Path>>(working: P) -> anyhow::Result<()> {
-    let status = Command::new("java")
-        .current_dir(working.as_ref())
-        .args(&["-cp", "server.jar", "net.minecraft.data.Main", "--reports"])
-        .status()?;
+fn generate<P: AsRef<Path>>(working: P, kind: JarKind) -> anyhow::Result<()> {
+    let mut command = Command::new("java");
+    command.current_dir(working.as_ref());
+    match kind {
+        JarKind::Plain => command.args(&["-cp", "server.jar", "net.minecraft.data.Main"]),
+        // The bundler sets up the classpath before running the given main class.
+        JarKind::Bundler(_) => command.args(&[
+            "-DbundlerMainClass=net.minecraft.data.Main",
+            "-jar",
+            "server.jar",
+        ]),
+    };
+    let status = command.arg("--reports").status()?;
     if !status.success() {
         anyhow::bail!(
             "process to generate server reports was not successful (exit status {}, JAR path {})",
@@ -97,13 +119,24 @@ fn generate<P: AsRef<Path>>(working: P) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn extract<P: AsRef<Path>>(working: P) -> anyhow::Result<()> {
+fn extract<P: AsRef<Path>>(working: P, kind: JarKind) -> anyhow::Result<()> {
     println!(
         "{:?}",
         std::fs::read_dir(working.as_ref())?.collect::<Vec<_>>()
     );
     let server_jar = working.as_ref().join("server.jar");
-    let mut archive = ZipArchive::new(std::fs::File::open(server_jar)?)?;
+    let mut bytes = Vec::new();
+    File::open(server_jar)?.read_to_end(&mut bytes)?;
+    if let JarKind::Bundler(inner) = kind {
+        let mut bundler = ZipArchive::new(Cursor::new(bytes))?;
+        let mut inner_jar = bundler
+            .by_name(inner)
+            .with_context(|| format!("bundler JAR does not contain `{}`", inner))?;
+        let mut inner_bytes = Vec::new();
+        inner_jar.read_to_end(&mut inner_bytes)?;
+        bytes = inner_bytes;
+    }
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
         if !(file.name().starts_with("assets/") || file.name().starts_with("data/")) {
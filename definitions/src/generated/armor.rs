@@ -0,0 +1,40 @@
+// This file is @generated
+impl crate::Item {
+    pub fn armor_points(self) -> Option<u32> {
+        match self {
+            crate::Item::ChainmailBoots => Some(1u32),
+            crate::Item::ChainmailChestplate => Some(5u32),
+            crate::Item::ChainmailHelmet => Some(2u32),
+            crate::Item::ChainmailLeggings => Some(4u32),
+            crate::Item::DiamondBoots => Some(3u32),
+            crate::Item::DiamondChestplate => Some(8u32),
+            crate::Item::DiamondHelmet => Some(3u32),
+            crate::Item::DiamondLeggings => Some(6u32),
+            crate::Item::GoldenBoots => Some(1u32),
+            crate::Item::GoldenChestplate => Some(5u32),
+            crate::Item::GoldenHelmet => Some(2u32),
+            crate::Item::GoldenLeggings => Some(3u32),
+            crate::Item::IronBoots => Some(2u32),
+            crate::Item::IronChestplate => Some(6u32),
+            crate::Item::IronHelmet => Some(2u32),
+            crate::Item::IronLeggings => Some(5u32),
+            crate::Item::LeatherBoots => Some(1u32),
+            crate::Item::LeatherChestplate => Some(3u32),
+            crate::Item::LeatherHelmet => Some(1u32),
+            crate::Item::LeatherLeggings => Some(2u32),
+            crate::Item::TurtleHelmet => Some(2u32),
+            _ => None,
+        }
+    }
+}
+impl crate::Item {
+    pub fn armor_toughness(self) -> Option<f64> {
+        match self {
+            crate::Item::DiamondBoots => Some(2f64),
+            crate::Item::DiamondChestplate => Some(2f64),
+            crate::Item::DiamondHelmet => Some(2f64),
+            crate::Item::DiamondLeggings => Some(2f64),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// This file is @generated
+impl crate::BlockKind {
+    pub fn fire_encouragement(self) -> Option<u32> {
+        match self {
+            crate::BlockKind::AcaciaFence => Some(5u32),
+            crate::BlockKind::AcaciaFenceGate => Some(5u32),
+            crate::BlockKind::AcaciaLeaves => Some(30u32),
+            crate::BlockKind::AcaciaLog => Some(5u32),
+            crate::BlockKind::AcaciaPlanks => Some(5u32),
+            crate::BlockKind::AcaciaSlab => Some(5u32),
+            crate::BlockKind::AcaciaStairs => Some(5u32),
+            crate::BlockKind::AcaciaWood => Some(5u32),
+            crate::BlockKind::Allium => Some(60u32),
+            crate::BlockKind::AzureBluet => Some(60u32),
+            crate::BlockKind::BirchFence => Some(5u32),
+            crate::BlockKind::BirchFenceGate => Some(5u32),
+            crate::BlockKind::BirchLeaves => Some(30u32),
+            crate::BlockKind::BirchLog => Some(5u32),
+            crate::BlockKind::BirchPlanks => Some(5u32),
+            crate::BlockKind::BirchSlab => Some(5u32),
+            crate::BlockKind::BirchStairs => Some(5u32),
+            crate::BlockKind::BirchWood => Some(5u32),
+            crate::BlockKind::BlackCarpet => Some(60u32),
+            crate::BlockKind::BlackWool => Some(30u32),
+            crate::BlockKind::BlueCarpet => Some(60u32),
+            crate::BlockKind::BlueOrchid => Some(60u32),
+            crate::BlockKind::BlueWool => Some(30u32),
+            crate::BlockKind::Bookshelf => Some(30u32),
+            crate::BlockKind::BrownCarpet => Some(60u32),
+            crate::BlockKind::BrownWool => Some(30u32),
+            crate::BlockKind::CoalBlock => Some(5u32),
+            crate::BlockKind::CyanCarpet => Some(60u32),
+            crate::BlockKind::CyanWool => Some(30u32),
+            crate::BlockKind::Dandelion => Some(60u32),
+            crate::BlockKind::DarkOakFence => Some(5u32),
+            crate::BlockKind::DarkOakFenceGate => Some(5u32),
+            crate::BlockKind::DarkOakLeaves => Some(30u32),
+            crate::BlockKind::DarkOakLog => Some(5u32),
+            crate::BlockKind::DarkOakPlanks => Some(5u32),
+            crate::BlockKind::DarkOakSlab => Some(5u32),
+            crate::BlockKind::DarkOakStairs => Some(5u32),
+            crate::BlockKind::DarkOakWood => Some(5u32),
+            crate::BlockKind::DeadBush => Some(60u32),
+            crate::BlockKind::DriedKelpBlock => Some(30u32),
+            crate::BlockKind::Fern => Some(60u32),
+            crate::BlockKind::Grass => Some(60u32),
+            crate::BlockKind::GrayCarpet => Some(60u32),
+            crate::BlockKind::GrayWool => Some(30u32),
+            crate::BlockKind::GreenCarpet => Some(60u32),
+            crate::BlockKind::GreenWool => Some(30u32),
+            crate::BlockKind::HayBlock => Some(60u32),
+            crate::BlockKind::JungleFence => Some(5u32),
+            crate::BlockKind::JungleFenceGate => Some(5u32),
+            crate::BlockKind::JungleLeaves => Some(30u32),
+            crate::BlockKind::JungleLog => Some(5u32),
+            crate::BlockKind::JunglePlanks => Some(5u32),
+            crate::BlockKind::JungleSlab => Some(5u32),
+            crate::BlockKind::JungleStairs => Some(5u32),
+            crate::BlockKind::JungleWood => Some(5u32),
+            crate::BlockKind::LargeFern => Some(60u32),
+            crate::BlockKind::LightBlueCarpet => Some(60u32),
+            crate::BlockKind::LightBlueWool => Some(30u32),
+            crate::BlockKind::LightGrayCarpet => Some(60u32),
+            crate::BlockKind::LightGrayWool => Some(30u32),
+            crate::BlockKind::Lilac => Some(60u32),
+            crate::BlockKind::LimeCarpet => Some(60u32),
+            crate::BlockKind::LimeWool => Some(30u32),
+            crate::BlockKind::MagentaCarpet => Some(60u32),
+            crate::BlockKind::MagentaWool => Some(30u32),
+            crate::BlockKind::OakFence => Some(5u32),
+            crate::BlockKind::OakFenceGate => Some(5u32),
+            crate::BlockKind::OakLeaves => Some(30u32),
+            crate::BlockKind::OakLog => Some(5u32),
+            crate::BlockKind::OakPlanks => Some(5u32),
+            crate::BlockKind::OakSlab => Some(5u32),
+            crate::BlockKind::OakStairs => Some(5u32),
+            crate::BlockKind::OakWood => Some(5u32),
+            crate::BlockKind::OrangeCarpet => Some(60u32),
+            crate::BlockKind::OrangeTulip => Some(60u32),
+            crate::BlockKind::OrangeWool => Some(30u32),
+            crate::BlockKind::OxeyeDaisy => Some(60u32),
+            crate::BlockKind::Peony => Some(60u32),
+            crate::BlockKind::PinkCarpet => Some(60u32),
+            crate::BlockKind::PinkTulip => Some(60u32),
+            crate::BlockKind::PinkWool => Some(30u32),
+            crate::BlockKind::Poppy => Some(60u32),
+            crate::BlockKind::PurpleCarpet => Some(60u32),
+            crate::BlockKind::PurpleWool => Some(30u32),
+            crate::BlockKind::RedCarpet => Some(60u32),
+            crate::BlockKind::RedTulip => Some(60u32),
+            crate::BlockKind::RedWool => Some(30u32),
+            crate::BlockKind::RoseBush => Some(60u32),
+            crate::BlockKind::SpruceFence => Some(5u32),
+            crate::BlockKind::SpruceFenceGate => Some(5u32),
+            crate::BlockKind::SpruceLeaves => Some(30u32),
+            crate::BlockKind::SpruceLog => Some(5u32),
+            crate::BlockKind::SprucePlanks => Some(5u32),
+            crate::BlockKind::SpruceSlab => Some(5u32),
+            crate::BlockKind::SpruceStairs => Some(5u32),
+            crate::BlockKind::SpruceWood => Some(5u32),
+            crate::BlockKind::StrippedAcaciaLog => Some(5u32),
+            crate::BlockKind::StrippedAcaciaWood => Some(5u32),
+            crate::BlockKind::StrippedBirchLog => Some(5u32),
+            crate::BlockKind::StrippedBirchWood => Some(5u32),
+            crate::BlockKind::StrippedDarkOakLog => Some(5u32),
+            crate::BlockKind::StrippedDarkOakWood => Some(5u32),
+            crate::BlockKind::StrippedJungleLog => Some(5u32),
+            crate::BlockKind::StrippedJungleWood => Some(5u32),
+            crate::BlockKind::StrippedOakLog => Some(5u32),
+            crate::BlockKind::StrippedOakWood => Some(5u32),
+            crate::BlockKind::StrippedSpruceLog => Some(5u32),
+            crate::BlockKind::StrippedSpruceWood => Some(5u32),
+            crate::BlockKind::Sunflower => Some(60u32),
+            crate::BlockKind::TallGrass => Some(60u32),
+            crate::BlockKind::Tnt => Some(15u32),
+            crate::BlockKind::Vine => Some(15u32),
+            crate::BlockKind::WhiteCarpet => Some(60u32),
+            crate::BlockKind::WhiteTulip => Some(60u32),
+            crate::BlockKind::WhiteWool => Some(30u32),
+            crate::BlockKind::YellowCarpet => Some(60u32),
+            crate::BlockKind::YellowWool => Some(30u32),
+            _ => None,
+        }
+    }
+}
+impl crate::BlockKind {
+    pub fn flammability(self) -> Option<u32> {
+        match self {
+            crate::BlockKind::AcaciaFence => Some(20u32),
+            crate::BlockKind::AcaciaFenceGate => Some(20u32),
+            crate::BlockKind::AcaciaLeaves => Some(60u32),
+            crate::BlockKind::AcaciaLog => Some(5u32),
+            crate::BlockKind::AcaciaPlanks => Some(20u32),
+            crate::BlockKind::AcaciaSlab => Some(20u32),
+            crate::BlockKind::AcaciaStairs => Some(20u32),
+            crate::BlockKind::AcaciaWood => Some(5u32),
+            crate::BlockKind::Allium => Some(100u32),
+            crate::BlockKind::AzureBluet => Some(100u32),
+            crate::BlockKind::BirchFence => Some(20u32),
+            crate::BlockKind::BirchFenceGate => Some(20u32),
+            crate::BlockKind::BirchLeaves => Some(60u32),
+            crate::BlockKind::BirchLog => Some(5u32),
+            crate::BlockKind::BirchPlanks => Some(20u32),
+            crate::BlockKind::BirchSlab => Some(20u32),
+            crate::BlockKind::BirchStairs => Some(20u32),
+            crate::BlockKind::BirchWood => Some(5u32),
+            crate::BlockKind::BlackCarpet => Some(20u32),
+            crate::BlockKind::BlackWool => Some(60u32),
+            crate::BlockKind::BlueCarpet => Some(20u32),
+            crate::BlockKind::BlueOrchid => Some(100u32),
+            crate::BlockKind::BlueWool => Some(60u32),
+            crate::BlockKind::Bookshelf => Some(20u32),
+            crate::BlockKind::BrownCarpet => Some(20u32),
+            crate::BlockKind::BrownWool => Some(60u32),
+            crate::BlockKind::CoalBlock => Some(5u32),
+            crate::BlockKind::CyanCarpet => Some(20u32),
+            crate::BlockKind::CyanWool => Some(60u32),
+            crate::BlockKind::Dandelion => Some(100u32),
+            crate::BlockKind::DarkOakFence => Some(20u32),
+            crate::BlockKind::DarkOakFenceGate => Some(20u32),
+            crate::BlockKind::DarkOakLeaves => Some(60u32),
+            crate::BlockKind::DarkOakLog => Some(5u32),
+            crate::BlockKind::DarkOakPlanks => Some(20u32),
+            crate::BlockKind::DarkOakSlab => Some(20u32),
+            crate::BlockKind::DarkOakStairs => Some(20u32),
+            crate::BlockKind::DarkOakWood => Some(5u32),
+            crate::BlockKind::DeadBush => Some(100u32),
+            crate::BlockKind::DriedKelpBlock => Some(60u32),
+            crate::BlockKind::Fern => Some(100u32),
+            crate::BlockKind::Grass => Some(100u32),
+            crate::BlockKind::GrayCarpet => Some(20u32),
+            crate::BlockKind::GrayWool => Some(60u32),
+            crate::BlockKind::GreenCarpet => Some(20u32),
+            crate::BlockKind::GreenWool => Some(60u32),
+            crate::BlockKind::HayBlock => Some(20u32),
+            crate::BlockKind::JungleFence => Some(20u32),
+            crate::BlockKind::JungleFenceGate => Some(20u32),
+            crate::BlockKind::JungleLeaves => Some(60u32),
+            crate::BlockKind::JungleLog => Some(5u32),
+            crate::BlockKind::JunglePlanks => Some(20u32),
+            crate::BlockKind::JungleSlab => Some(20u32),
+            crate::BlockKind::JungleStairs => Some(20u32),
+            crate::BlockKind::JungleWood => Some(5u32),
+            crate::BlockKind::LargeFern => Some(100u32),
+            crate::BlockKind::LightBlueCarpet => Some(20u32),
+            crate::BlockKind::LightBlueWool => Some(60u32),
+            crate::BlockKind::LightGrayCarpet => Some(20u32),
+            crate::BlockKind::LightGrayWool => Some(60u32),
+            crate::BlockKind::Lilac => Some(100u32),
+            crate::BlockKind::LimeCarpet => Some(20u32),
+            crate::BlockKind::LimeWool => Some(60u32),
+            crate::BlockKind::MagentaCarpet => Some(20u32),
+            crate::BlockKind::MagentaWool => Some(60u32),
+            crate::BlockKind::OakFence => Some(20u32),
+            crate::BlockKind::OakFenceGate => Some(20u32),
+            crate::BlockKind::OakLeaves => Some(60u32),
+            crate::BlockKind::OakLog => Some(5u32),
+            crate::BlockKind::OakPlanks => Some(20u32),
+            crate::BlockKind::OakSlab => Some(20u32),
+            crate::BlockKind::OakStairs => Some(20u32),
+            crate::BlockKind::OakWood => Some(5u32),
+            crate::BlockKind::OrangeCarpet => Some(20u32),
+            crate::BlockKind::OrangeTulip => Some(100u32),
+            crate::BlockKind::OrangeWool => Some(60u32),
+            crate::BlockKind::OxeyeDaisy => Some(100u32),
+            crate::BlockKind::Peony => Some(100u32),
+            crate::BlockKind::PinkCarpet => Some(20u32),
+            crate::BlockKind::PinkTulip => Some(100u32),
+            crate::BlockKind::PinkWool => Some(60u32),
+            crate::BlockKind::Poppy => Some(100u32),
+            crate::BlockKind::PurpleCarpet => Some(20u32),
+            crate::BlockKind::PurpleWool => Some(60u32),
+            crate::BlockKind::RedCarpet => Some(20u32),
+            crate::BlockKind::RedTulip => Some(100u32),
+            crate::BlockKind::RedWool => Some(60u32),
+            crate::BlockKind::RoseBush => Some(100u32),
+            crate::BlockKind::SpruceFence => Some(20u32),
+            crate::BlockKind::SpruceFenceGate => Some(20u32),
+            crate::BlockKind::SpruceLeaves => Some(60u32),
+            crate::BlockKind::SpruceLog => Some(5u32),
+            crate::BlockKind::SprucePlanks => Some(20u32),
+            crate::BlockKind::SpruceSlab => Some(20u32),
+            crate::BlockKind::SpruceStairs => Some(20u32),
+            crate::BlockKind::SpruceWood => Some(5u32),
+            crate::BlockKind::StrippedAcaciaLog => Some(5u32),
+            crate::BlockKind::StrippedAcaciaWood => Some(5u32),
+            crate::BlockKind::StrippedBirchLog => Some(5u32),
+            crate::BlockKind::StrippedBirchWood => Some(5u32),
+            crate::BlockKind::StrippedDarkOakLog => Some(5u32),
+            crate::BlockKind::StrippedDarkOakWood => Some(5u32),
+            crate::BlockKind::StrippedJungleLog => Some(5u32),
+            crate::BlockKind::StrippedJungleWood => Some(5u32),
+            crate::BlockKind::StrippedOakLog => Some(5u32),
+            crate::BlockKind::StrippedOakWood => Some(5u32),
+            crate::BlockKind::StrippedSpruceLog => Some(5u32),
+            crate::BlockKind::StrippedSpruceWood => Some(5u32),
+            crate::BlockKind::Sunflower => Some(100u32),
+            crate::BlockKind::TallGrass => Some(100u32),
+            crate::BlockKind::Tnt => Some(100u32),
+            crate::BlockKind::Vine => Some(100u32),
+            crate::BlockKind::WhiteCarpet => Some(20u32),
+            crate::BlockKind::WhiteTulip => Some(100u32),
+            crate::BlockKind::WhiteWool => Some(60u32),
+            crate::BlockKind::YellowCarpet => Some(20u32),
+            crate::BlockKind::YellowWool => Some(60u32),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// This file is @generated
+impl crate::Item {
+    pub fn food_points(self) -> Option<u32> {
+        match self {
+            crate::Item::Apple => Some(4u32),
+            crate::Item::BakedPotato => Some(5u32),
+            crate::Item::Beef => Some(3u32),
+            crate::Item::Beetroot => Some(1u32),
+            crate::Item::BeetrootSoup => Some(6u32),
+            crate::Item::Bread => Some(5u32),
+            crate::Item::Carrot => Some(3u32),
+            crate::Item::Chicken => Some(2u32),
+            crate::Item::ChorusFruit => Some(4u32),
+            crate::Item::Cod => Some(2u32),
+            crate::Item::CookedBeef => Some(8u32),
+            crate::Item::CookedChicken => Some(6u32),
+            crate::Item::CookedCod => Some(5u32),
+            crate::Item::CookedMutton => Some(6u32),
+            crate::Item::CookedPorkchop => Some(8u32),
+            crate::Item::CookedRabbit => Some(5u32),
+            crate::Item::CookedSalmon => Some(6u32),
+            crate::Item::Cookie => Some(2u32),
+            crate::Item::DriedKelp => Some(1u32),
+            crate::Item::EnchantedGoldenApple => Some(4u32),
+            crate::Item::GoldenApple => Some(4u32),
+            crate::Item::GoldenCarrot => Some(6u32),
+            crate::Item::MelonSlice => Some(2u32),
+            crate::Item::MushroomStew => Some(6u32),
+            crate::Item::Mutton => Some(2u32),
+            crate::Item::PoisonousPotato => Some(2u32),
+            crate::Item::Porkchop => Some(3u32),
+            crate::Item::Potato => Some(1u32),
+            crate::Item::Pufferfish => Some(1u32),
+            crate::Item::PumpkinPie => Some(8u32),
+            crate::Item::Rabbit => Some(3u32),
+            crate::Item::RabbitStew => Some(10u32),
+            crate::Item::RottenFlesh => Some(4u32),
+            crate::Item::Salmon => Some(2u32),
+            crate::Item::SpiderEye => Some(2u32),
+            crate::Item::TropicalFish => Some(1u32),
+            _ => None,
+        }
+    }
+}
+impl crate::Item {
+    pub fn saturation_modifier(self) -> Option<f64> {
+        match self {
+            crate::Item::Apple => Some(0.3f64),
+            crate::Item::BakedPotato => Some(0.6f64),
+            crate::Item::Beef => Some(0.3f64),
+            crate::Item::Beetroot => Some(0.6f64),
+            crate::Item::BeetrootSoup => Some(0.6f64),
+            crate::Item::Bread => Some(0.6f64),
+            crate::Item::Carrot => Some(0.6f64),
+            crate::Item::Chicken => Some(0.3f64),
+            crate::Item::ChorusFruit => Some(0.3f64),
+            crate::Item::Cod => Some(0.1f64),
+            crate::Item::CookedBeef => Some(0.8f64),
+            crate::Item::CookedChicken => Some(0.6f64),
+            crate::Item::CookedCod => Some(0.6f64),
+            crate::Item::CookedMutton => Some(0.8f64),
+            crate::Item::CookedPorkchop => Some(0.8f64),
+            crate::Item::CookedRabbit => Some(0.6f64),
+            crate::Item::CookedSalmon => Some(0.8f64),
+            crate::Item::Cookie => Some(0.1f64),
+            crate::Item::DriedKelp => Some(0.3f64),
+            crate::Item::EnchantedGoldenApple => Some(1.2f64),
+            crate::Item::GoldenApple => Some(1.2f64),
+            crate::Item::GoldenCarrot => Some(1.2f64),
+            crate::Item::MelonSlice => Some(0.3f64),
+            crate::Item::MushroomStew => Some(0.6f64),
+            crate::Item::Mutton => Some(0.3f64),
+            crate::Item::PoisonousPotato => Some(0.3f64),
+            crate::Item::Porkchop => Some(0.3f64),
+            crate::Item::Potato => Some(0.3f64),
+            crate::Item::Pufferfish => Some(0.1f64),
+            crate::Item::PumpkinPie => Some(0.3f64),
+            crate::Item::Rabbit => Some(0.3f64),
+            crate::Item::RabbitStew => Some(0.6f64),
+            crate::Item::RottenFlesh => Some(0.1f64),
+            crate::Item::Salmon => Some(0.1f64),
+            crate::Item::SpiderEye => Some(0.8f64),
+            crate::Item::TropicalFish => Some(0.1f64),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// This file is @generated
+impl crate::Item {
+    pub fn fuel_time(self) -> Option<u32> {
+        match self {
+            crate::Item::AcaciaBoat => Some(400u32),
+            crate::Item::AcaciaButton => Some(100u32),
+            crate::Item::AcaciaDoor => Some(200u32),
+            crate::Item::AcaciaFence => Some(300u32),
+            crate::Item::AcaciaFenceGate => Some(300u32),
+            crate::Item::AcaciaLog => Some(300u32),
+            crate::Item::AcaciaPlanks => Some(300u32),
+            crate::Item::AcaciaPressurePlate => Some(300u32),
+            crate::Item::AcaciaSapling => Some(100u32),
+            crate::Item::AcaciaSlab => Some(150u32),
+            crate::Item::AcaciaStairs => Some(300u32),
+            crate::Item::AcaciaTrapdoor => Some(300u32),
+            crate::Item::AcaciaWood => Some(300u32),
+            crate::Item::BirchBoat => Some(400u32),
+            crate::Item::BirchButton => Some(100u32),
+            crate::Item::BirchDoor => Some(200u32),
+            crate::Item::BirchFence => Some(300u32),
+            crate::Item::BirchFenceGate => Some(300u32),
+            crate::Item::BirchLog => Some(300u32),
+            crate::Item::BirchPlanks => Some(300u32),
+            crate::Item::BirchPressurePlate => Some(300u32),
+            crate::Item::BirchSapling => Some(100u32),
+            crate::Item::BirchSlab => Some(150u32),
+            crate::Item::BirchStairs => Some(300u32),
+            crate::Item::BirchTrapdoor => Some(300u32),
+            crate::Item::BirchWood => Some(300u32),
+            crate::Item::BlackBanner => Some(300u32),
+            crate::Item::BlackCarpet => Some(67u32),
+            crate::Item::BlackWool => Some(100u32),
+            crate::Item::BlazeRod => Some(2400u32),
+            crate::Item::BlueBanner => Some(300u32),
+            crate::Item::BlueCarpet => Some(67u32),
+            crate::Item::BlueWool => Some(100u32),
+            crate::Item::Bookshelf => Some(300u32),
+            crate::Item::Bow => Some(300u32),
+            crate::Item::Bowl => Some(100u32),
+            crate::Item::BrownBanner => Some(300u32),
+            crate::Item::BrownCarpet => Some(67u32),
+            crate::Item::BrownWool => Some(100u32),
+            crate::Item::Charcoal => Some(1600u32),
+            crate::Item::Chest => Some(300u32),
+            crate::Item::Coal => Some(1600u32),
+            crate::Item::CoalBlock => Some(16000u32),
+            crate::Item::CraftingTable => Some(300u32),
+            crate::Item::CyanBanner => Some(300u32),
+            crate::Item::CyanCarpet => Some(67u32),
+            crate::Item::CyanWool => Some(100u32),
+            crate::Item::DarkOakBoat => Some(400u32),
+            crate::Item::DarkOakButton => Some(100u32),
+            crate::Item::DarkOakDoor => Some(200u32),
+            crate::Item::DarkOakFence => Some(300u32),
+            crate::Item::DarkOakFenceGate => Some(300u32),
+            crate::Item::DarkOakLog => Some(300u32),
+            crate::Item::DarkOakPlanks => Some(300u32),
+            crate::Item::DarkOakPressurePlate => Some(300u32),
+            crate::Item::DarkOakSapling => Some(100u32),
+            crate::Item::DarkOakSlab => Some(150u32),
+            crate::Item::DarkOakStairs => Some(300u32),
+            crate::Item::DarkOakTrapdoor => Some(300u32),
+            crate::Item::DarkOakWood => Some(300u32),
+            crate::Item::DaylightDetector => Some(300u32),
+            crate::Item::DriedKelpBlock => Some(4001u32),
+            crate::Item::FishingRod => Some(300u32),
+            crate::Item::GrayBanner => Some(300u32),
+            crate::Item::GrayCarpet => Some(67u32),
+            crate::Item::GrayWool => Some(100u32),
+            crate::Item::GreenBanner => Some(300u32),
+            crate::Item::GreenCarpet => Some(67u32),
+            crate::Item::GreenWool => Some(100u32),
+            crate::Item::Jukebox => Some(300u32),
+            crate::Item::JungleBoat => Some(400u32),
+            crate::Item::JungleButton => Some(100u32),
+            crate::Item::JungleDoor => Some(200u32),
+            crate::Item::JungleFence => Some(300u32),
+            crate::Item::JungleFenceGate => Some(300u32),
+            crate::Item::JungleLog => Some(300u32),
+            crate::Item::JunglePlanks => Some(300u32),
+            crate::Item::JunglePressurePlate => Some(300u32),
+            crate::Item::JungleSapling => Some(100u32),
+            crate::Item::JungleSlab => Some(150u32),
+            crate::Item::JungleStairs => Some(300u32),
+            crate::Item::JungleTrapdoor => Some(300u32),
+            crate::Item::JungleWood => Some(300u32),
+            crate::Item::Ladder => Some(300u32),
+            crate::Item::LavaBucket => Some(20000u32),
+            crate::Item::LightBlueBanner => Some(300u32),
+            crate::Item::LightBlueCarpet => Some(67u32),
+            crate::Item::LightBlueWool => Some(100u32),
+            crate::Item::LightGrayBanner => Some(300u32),
+            crate::Item::LightGrayCarpet => Some(67u32),
+            crate::Item::LightGrayWool => Some(100u32),
+            crate::Item::LimeBanner => Some(300u32),
+            crate::Item::LimeCarpet => Some(67u32),
+            crate::Item::LimeWool => Some(100u32),
+            crate::Item::MagentaBanner => Some(300u32),
+            crate::Item::MagentaCarpet => Some(67u32),
+            crate::Item::MagentaWool => Some(100u32),
+            crate::Item::NoteBlock => Some(300u32),
+            crate::Item::OakBoat => Some(400u32),
+            crate::Item::OakButton => Some(100u32),
+            crate::Item::OakDoor => Some(200u32),
+            crate::Item::OakFence => Some(300u32),
+            crate::Item::OakFenceGate => Some(300u32),
+            crate::Item::OakLog => Some(300u32),
+            crate::Item::OakPlanks => Some(300u32),
+            crate::Item::OakPressurePlate => Some(300u32),
+            crate::Item::OakSapling => Some(100u32),
+            crate::Item::OakSlab => Some(150u32),
+            crate::Item::OakStairs => Some(300u32),
+            crate::Item::OakTrapdoor => Some(300u32),
+            crate::Item::OakWood => Some(300u32),
+            crate::Item::OrangeBanner => Some(300u32),
+            crate::Item::OrangeCarpet => Some(67u32),
+            crate::Item::OrangeWool => Some(100u32),
+            crate::Item::PinkBanner => Some(300u32),
+            crate::Item::PinkCarpet => Some(67u32),
+            crate::Item::PinkWool => Some(100u32),
+            crate::Item::PurpleBanner => Some(300u32),
+            crate::Item::PurpleCarpet => Some(67u32),
+            crate::Item::PurpleWool => Some(100u32),
+            crate::Item::RedBanner => Some(300u32),
+            crate::Item::RedCarpet => Some(67u32),
+            crate::Item::RedWool => Some(100u32),
+            crate::Item::Sign => Some(200u32),
+            crate::Item::SpruceBoat => Some(400u32),
+            crate::Item::SpruceButton => Some(100u32),
+            crate::Item::SpruceDoor => Some(200u32),
+            crate::Item::SpruceFence => Some(300u32),
+            crate::Item::SpruceFenceGate => Some(300u32),
+            crate::Item::SpruceLog => Some(300u32),
+            crate::Item::SprucePlanks => Some(300u32),
+            crate::Item::SprucePressurePlate => Some(300u32),
+            crate::Item::SpruceSapling => Some(100u32),
+            crate::Item::SpruceSlab => Some(150u32),
+            crate::Item::SpruceStairs => Some(300u32),
+            crate::Item::SpruceTrapdoor => Some(300u32),
+            crate::Item::SpruceWood => Some(300u32),
+            crate::Item::Stick => Some(100u32),
+            crate::Item::StrippedAcaciaLog => Some(300u32),
+            crate::Item::StrippedAcaciaWood => Some(300u32),
+            crate::Item::StrippedBirchLog => Some(300u32),
+            crate::Item::StrippedBirchWood => Some(300u32),
+            crate::Item::StrippedDarkOakLog => Some(300u32),
+            crate::Item::StrippedDarkOakWood => Some(300u32),
+            crate::Item::StrippedJungleLog => Some(300u32),
+            crate::Item::StrippedJungleWood => Some(300u32),
+            crate::Item::StrippedOakLog => Some(300u32),
+            crate::Item::StrippedOakWood => Some(300u32),
+            crate::Item::StrippedSpruceLog => Some(300u32),
+            crate::Item::StrippedSpruceWood => Some(300u32),
+            crate::Item::TrappedChest => Some(300u32),
+            crate::Item::WhiteBanner => Some(300u32),
+            crate::Item::WhiteCarpet => Some(67u32),
+            crate::Item::WhiteWool => Some(100u32),
+            crate::Item::WoodenAxe => Some(200u32),
+            crate::Item::WoodenHoe => Some(200u32),
+            crate::Item::WoodenPickaxe => Some(200u32),
+            crate::Item::WoodenShovel => Some(200u32),
+            crate::Item::WoodenSword => Some(200u32),
+            crate::Item::YellowBanner => Some(300u32),
+            crate::Item::YellowCarpet => Some(67u32),
+            crate::Item::YellowWool => Some(100u32),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,611 @@
+// This file is @generated
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ToPrimitive, FromPrimitive)]
+pub enum DyeColor {
+    White,
+    Orange,
+    Magenta,
+    LightBlue,
+    Yellow,
+    Lime,
+    Pink,
+    Gray,
+    LightGray,
+    Cyan,
+    Purple,
+    Blue,
+    Brown,
+    Green,
+    Red,
+    Black,
+}
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ToPrimitive, FromPrimitive)]
+pub enum MapColor {
+    Grass,
+    Sand,
+    Wool,
+    Fire,
+    Ice,
+    Metal,
+    Plant,
+    Snow,
+    Clay,
+    Dirt,
+    Stone,
+    Water,
+    Wood,
+    Quartz,
+    Orange,
+    Magenta,
+    LightBlue,
+    Yellow,
+    Lime,
+    Pink,
+    Gray,
+    LightGray,
+    Cyan,
+    Purple,
+    Blue,
+    Brown,
+    Green,
+    Red,
+    Black,
+    Gold,
+    Diamond,
+    Lapis,
+    Emerald,
+    Podzol,
+    Nether,
+    WhiteTerracotta,
+    OrangeTerracotta,
+    MagentaTerracotta,
+    LightBlueTerracotta,
+    YellowTerracotta,
+    LimeTerracotta,
+    PinkTerracotta,
+    GrayTerracotta,
+    LightGrayTerracotta,
+    CyanTerracotta,
+    PurpleTerracotta,
+    BlueTerracotta,
+    BrownTerracotta,
+    GreenTerracotta,
+    RedTerracotta,
+    BlackTerracotta,
+}
+impl crate::BlockKind {
+    pub fn map_color(self) -> Option<crate::MapColor> {
+        match self {
+            crate::BlockKind::AcaciaDoor => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaFence => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaFenceGate => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaLeaves => Some(crate::MapColor::Plant),
+            crate::BlockKind::AcaciaLog => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaPlanks => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaPressurePlate => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaSapling => Some(crate::MapColor::Plant),
+            crate::BlockKind::AcaciaSlab => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaStairs => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaTrapdoor => Some(crate::MapColor::Orange),
+            crate::BlockKind::AcaciaWood => Some(crate::MapColor::Orange),
+            crate::BlockKind::Allium => Some(crate::MapColor::Plant),
+            crate::BlockKind::Andesite => Some(crate::MapColor::Stone),
+            crate::BlockKind::Anvil => Some(crate::MapColor::Metal),
+            crate::BlockKind::AttachedMelonStem => Some(crate::MapColor::Plant),
+            crate::BlockKind::AttachedPumpkinStem => Some(crate::MapColor::Plant),
+            crate::BlockKind::AzureBluet => Some(crate::MapColor::Plant),
+            crate::BlockKind::Beacon => Some(crate::MapColor::Diamond),
+            crate::BlockKind::Bedrock => Some(crate::MapColor::Stone),
+            crate::BlockKind::Beetroots => Some(crate::MapColor::Plant),
+            crate::BlockKind::BirchDoor => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchFence => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchFenceGate => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchLeaves => Some(crate::MapColor::Plant),
+            crate::BlockKind::BirchLog => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchPlanks => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchPressurePlate => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchSapling => Some(crate::MapColor::Plant),
+            crate::BlockKind::BirchSlab => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchStairs => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchTrapdoor => Some(crate::MapColor::Sand),
+            crate::BlockKind::BirchWood => Some(crate::MapColor::Sand),
+            crate::BlockKind::BlackBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::BlackBed => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackCarpet => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackConcrete => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackConcretePowder => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackGlazedTerracotta => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackShulkerBox => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackStainedGlass => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackStainedGlassPane => Some(crate::MapColor::Black),
+            crate::BlockKind::BlackTerracotta => Some(crate::MapColor::BlackTerracotta),
+            crate::BlockKind::BlackWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::BlackWool => Some(crate::MapColor::Black),
+            crate::BlockKind::BlueBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::BlueBed => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueCarpet => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueConcrete => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueConcretePowder => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueGlazedTerracotta => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueIce => Some(crate::MapColor::Ice),
+            crate::BlockKind::BlueOrchid => Some(crate::MapColor::Plant),
+            crate::BlockKind::BlueShulkerBox => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueStainedGlass => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueStainedGlassPane => Some(crate::MapColor::Blue),
+            crate::BlockKind::BlueTerracotta => Some(crate::MapColor::BlueTerracotta),
+            crate::BlockKind::BlueWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::BlueWool => Some(crate::MapColor::Blue),
+            crate::BlockKind::BoneBlock => Some(crate::MapColor::Sand),
+            crate::BlockKind::Bookshelf => Some(crate::MapColor::Wood),
+            crate::BlockKind::BrainCoral => Some(crate::MapColor::Pink),
+            crate::BlockKind::BrainCoralBlock => Some(crate::MapColor::Pink),
+            crate::BlockKind::BrainCoralFan => Some(crate::MapColor::Pink),
+            crate::BlockKind::BrainCoralWallFan => Some(crate::MapColor::Pink),
+            crate::BlockKind::BrewingStand => Some(crate::MapColor::Metal),
+            crate::BlockKind::BrickSlab => Some(crate::MapColor::Red),
+            crate::BlockKind::BrickStairs => Some(crate::MapColor::Red),
+            crate::BlockKind::Bricks => Some(crate::MapColor::Red),
+            crate::BlockKind::BrownBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::BrownBed => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownCarpet => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownConcrete => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownConcretePowder => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownGlazedTerracotta => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownMushroom => Some(crate::MapColor::Plant),
+            crate::BlockKind::BrownMushroomBlock => Some(crate::MapColor::Dirt),
+            crate::BlockKind::BrownShulkerBox => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownStainedGlass => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownStainedGlassPane => Some(crate::MapColor::Brown),
+            crate::BlockKind::BrownTerracotta => Some(crate::MapColor::BrownTerracotta),
+            crate::BlockKind::BrownWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::BrownWool => Some(crate::MapColor::Brown),
+            crate::BlockKind::BubbleColumn => Some(crate::MapColor::Water),
+            crate::BlockKind::BubbleCoral => Some(crate::MapColor::Purple),
+            crate::BlockKind::BubbleCoralBlock => Some(crate::MapColor::Purple),
+            crate::BlockKind::BubbleCoralFan => Some(crate::MapColor::Purple),
+            crate::BlockKind::BubbleCoralWallFan => Some(crate::MapColor::Purple),
+            crate::BlockKind::Cactus => Some(crate::MapColor::Plant),
+            crate::BlockKind::Carrots => Some(crate::MapColor::Plant),
+            crate::BlockKind::CarvedPumpkin => Some(crate::MapColor::Orange),
+            crate::BlockKind::Cauldron => Some(crate::MapColor::Stone),
+            crate::BlockKind::ChainCommandBlock => Some(crate::MapColor::Green),
+            crate::BlockKind::Chest => Some(crate::MapColor::Wood),
+            crate::BlockKind::ChippedAnvil => Some(crate::MapColor::Metal),
+            crate::BlockKind::ChiseledQuartzBlock => Some(crate::MapColor::Quartz),
+            crate::BlockKind::ChiseledRedSandstone => Some(crate::MapColor::Orange),
+            crate::BlockKind::ChiseledSandstone => Some(crate::MapColor::Sand),
+            crate::BlockKind::ChiseledStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::ChorusFlower => Some(crate::MapColor::Purple),
+            crate::BlockKind::ChorusPlant => Some(crate::MapColor::Purple),
+            crate::BlockKind::Clay => Some(crate::MapColor::Clay),
+            crate::BlockKind::CoalBlock => Some(crate::MapColor::Black),
+            crate::BlockKind::CoalOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::CoarseDirt => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Cobblestone => Some(crate::MapColor::Stone),
+            crate::BlockKind::CobblestoneSlab => Some(crate::MapColor::Stone),
+            crate::BlockKind::CobblestoneStairs => Some(crate::MapColor::Stone),
+            crate::BlockKind::CobblestoneWall => Some(crate::MapColor::Stone),
+            crate::BlockKind::Cobweb => Some(crate::MapColor::Wool),
+            crate::BlockKind::Cocoa => Some(crate::MapColor::Plant),
+            crate::BlockKind::CommandBlock => Some(crate::MapColor::Brown),
+            crate::BlockKind::Conduit => Some(crate::MapColor::Diamond),
+            crate::BlockKind::CrackedStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::CraftingTable => Some(crate::MapColor::Wood),
+            crate::BlockKind::CutRedSandstone => Some(crate::MapColor::Orange),
+            crate::BlockKind::CutSandstone => Some(crate::MapColor::Sand),
+            crate::BlockKind::CyanBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::CyanBed => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanCarpet => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanConcrete => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanConcretePowder => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanGlazedTerracotta => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanShulkerBox => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanStainedGlass => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanStainedGlassPane => Some(crate::MapColor::Cyan),
+            crate::BlockKind::CyanTerracotta => Some(crate::MapColor::CyanTerracotta),
+            crate::BlockKind::CyanWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::CyanWool => Some(crate::MapColor::Cyan),
+            crate::BlockKind::DamagedAnvil => Some(crate::MapColor::Metal),
+            crate::BlockKind::Dandelion => Some(crate::MapColor::Plant),
+            crate::BlockKind::DarkOakDoor => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakFence => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakFenceGate => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakLeaves => Some(crate::MapColor::Plant),
+            crate::BlockKind::DarkOakLog => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakPlanks => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakPressurePlate => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakSapling => Some(crate::MapColor::Plant),
+            crate::BlockKind::DarkOakSlab => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakStairs => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakTrapdoor => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkOakWood => Some(crate::MapColor::Brown),
+            crate::BlockKind::DarkPrismarine => Some(crate::MapColor::Diamond),
+            crate::BlockKind::DarkPrismarineSlab => Some(crate::MapColor::Diamond),
+            crate::BlockKind::DarkPrismarineStairs => Some(crate::MapColor::Diamond),
+            crate::BlockKind::DaylightDetector => Some(crate::MapColor::Wood),
+            crate::BlockKind::DeadBrainCoral => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBrainCoralBlock => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBrainCoralFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBrainCoralWallFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBubbleCoral => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBubbleCoralBlock => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBubbleCoralFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBubbleCoralWallFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadBush => Some(crate::MapColor::Wood),
+            crate::BlockKind::DeadFireCoral => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadFireCoralBlock => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadFireCoralFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadFireCoralWallFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadHornCoral => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadHornCoralBlock => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadHornCoralFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadHornCoralWallFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadTubeCoral => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadTubeCoralBlock => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadTubeCoralFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DeadTubeCoralWallFan => Some(crate::MapColor::Gray),
+            crate::BlockKind::DiamondBlock => Some(crate::MapColor::Diamond),
+            crate::BlockKind::DiamondOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::Diorite => Some(crate::MapColor::Quartz),
+            crate::BlockKind::Dirt => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Dispenser => Some(crate::MapColor::Stone),
+            crate::BlockKind::DragonEgg => Some(crate::MapColor::Black),
+            crate::BlockKind::DriedKelpBlock => Some(crate::MapColor::Green),
+            crate::BlockKind::Dropper => Some(crate::MapColor::Stone),
+            crate::BlockKind::EmeraldBlock => Some(crate::MapColor::Emerald),
+            crate::BlockKind::EmeraldOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::EnchantingTable => Some(crate::MapColor::Red),
+            crate::BlockKind::EndGateway => Some(crate::MapColor::Black),
+            crate::BlockKind::EndPortal => Some(crate::MapColor::Black),
+            crate::BlockKind::EndPortalFrame => Some(crate::MapColor::Green),
+            crate::BlockKind::EndStone => Some(crate::MapColor::Sand),
+            crate::BlockKind::EndStoneBricks => Some(crate::MapColor::Sand),
+            crate::BlockKind::EnderChest => Some(crate::MapColor::Stone),
+            crate::BlockKind::Farmland => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Fern => Some(crate::MapColor::Plant),
+            crate::BlockKind::Fire => Some(crate::MapColor::Fire),
+            crate::BlockKind::FireCoral => Some(crate::MapColor::Red),
+            crate::BlockKind::FireCoralBlock => Some(crate::MapColor::Red),
+            crate::BlockKind::FireCoralFan => Some(crate::MapColor::Red),
+            crate::BlockKind::FireCoralWallFan => Some(crate::MapColor::Red),
+            crate::BlockKind::FrostedIce => Some(crate::MapColor::Ice),
+            crate::BlockKind::Furnace => Some(crate::MapColor::Stone),
+            crate::BlockKind::Glowstone => Some(crate::MapColor::Sand),
+            crate::BlockKind::GoldBlock => Some(crate::MapColor::Gold),
+            crate::BlockKind::GoldOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::Granite => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Grass => Some(crate::MapColor::Plant),
+            crate::BlockKind::GrassBlock => Some(crate::MapColor::Grass),
+            crate::BlockKind::GrassPath => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Gravel => Some(crate::MapColor::Stone),
+            crate::BlockKind::GrayBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::GrayBed => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayCarpet => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayConcrete => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayConcretePowder => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayGlazedTerracotta => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayShulkerBox => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayStainedGlass => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayStainedGlassPane => Some(crate::MapColor::Gray),
+            crate::BlockKind::GrayTerracotta => Some(crate::MapColor::GrayTerracotta),
+            crate::BlockKind::GrayWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::GrayWool => Some(crate::MapColor::Gray),
+            crate::BlockKind::GreenBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::GreenBed => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenCarpet => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenConcrete => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenConcretePowder => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenGlazedTerracotta => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenShulkerBox => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenStainedGlass => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenStainedGlassPane => Some(crate::MapColor::Green),
+            crate::BlockKind::GreenTerracotta => Some(crate::MapColor::GreenTerracotta),
+            crate::BlockKind::GreenWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::GreenWool => Some(crate::MapColor::Green),
+            crate::BlockKind::HayBlock => Some(crate::MapColor::Yellow),
+            crate::BlockKind::HeavyWeightedPressurePlate => Some(crate::MapColor::Metal),
+            crate::BlockKind::Hopper => Some(crate::MapColor::Stone),
+            crate::BlockKind::HornCoral => Some(crate::MapColor::Yellow),
+            crate::BlockKind::HornCoralBlock => Some(crate::MapColor::Yellow),
+            crate::BlockKind::HornCoralFan => Some(crate::MapColor::Yellow),
+            crate::BlockKind::HornCoralWallFan => Some(crate::MapColor::Yellow),
+            crate::BlockKind::Ice => Some(crate::MapColor::Ice),
+            crate::BlockKind::InfestedChiseledStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::InfestedCobblestone => Some(crate::MapColor::Stone),
+            crate::BlockKind::InfestedCrackedStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::InfestedMossyStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::InfestedStone => Some(crate::MapColor::Stone),
+            crate::BlockKind::InfestedStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::IronBars => Some(crate::MapColor::Metal),
+            crate::BlockKind::IronBlock => Some(crate::MapColor::Metal),
+            crate::BlockKind::IronDoor => Some(crate::MapColor::Metal),
+            crate::BlockKind::IronOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::IronTrapdoor => Some(crate::MapColor::Metal),
+            crate::BlockKind::JackOLantern => Some(crate::MapColor::Orange),
+            crate::BlockKind::Jukebox => Some(crate::MapColor::Wood),
+            crate::BlockKind::JungleDoor => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleFence => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleFenceGate => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleLeaves => Some(crate::MapColor::Plant),
+            crate::BlockKind::JungleLog => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JunglePlanks => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JunglePressurePlate => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleSapling => Some(crate::MapColor::Plant),
+            crate::BlockKind::JungleSlab => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleStairs => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleTrapdoor => Some(crate::MapColor::Dirt),
+            crate::BlockKind::JungleWood => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Kelp => Some(crate::MapColor::Water),
+            crate::BlockKind::KelpPlant => Some(crate::MapColor::Water),
+            crate::BlockKind::LapisBlock => Some(crate::MapColor::Lapis),
+            crate::BlockKind::LapisOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::LargeFern => Some(crate::MapColor::Plant),
+            crate::BlockKind::Lava => Some(crate::MapColor::Fire),
+            crate::BlockKind::LightBlueBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::LightBlueBed => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueCarpet => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueConcrete => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueConcretePowder => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueGlazedTerracotta => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueShulkerBox => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueStainedGlass => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueStainedGlassPane => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightBlueTerracotta => Some(crate::MapColor::LightBlueTerracotta),
+            crate::BlockKind::LightBlueWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::LightBlueWool => Some(crate::MapColor::LightBlue),
+            crate::BlockKind::LightGrayBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::LightGrayBed => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayCarpet => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayConcrete => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayConcretePowder => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayGlazedTerracotta => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayShulkerBox => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayStainedGlass => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayStainedGlassPane => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightGrayTerracotta => Some(crate::MapColor::LightGrayTerracotta),
+            crate::BlockKind::LightGrayWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::LightGrayWool => Some(crate::MapColor::LightGray),
+            crate::BlockKind::LightWeightedPressurePlate => Some(crate::MapColor::Gold),
+            crate::BlockKind::Lilac => Some(crate::MapColor::Plant),
+            crate::BlockKind::LilyPad => Some(crate::MapColor::Plant),
+            crate::BlockKind::LimeBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::LimeBed => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeCarpet => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeConcrete => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeConcretePowder => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeGlazedTerracotta => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeShulkerBox => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeStainedGlass => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeStainedGlassPane => Some(crate::MapColor::Lime),
+            crate::BlockKind::LimeTerracotta => Some(crate::MapColor::LimeTerracotta),
+            crate::BlockKind::LimeWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::LimeWool => Some(crate::MapColor::Lime),
+            crate::BlockKind::MagentaBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::MagentaBed => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaCarpet => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaConcrete => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaConcretePowder => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaGlazedTerracotta => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaShulkerBox => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaStainedGlass => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaStainedGlassPane => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagentaTerracotta => Some(crate::MapColor::MagentaTerracotta),
+            crate::BlockKind::MagentaWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::MagentaWool => Some(crate::MapColor::Magenta),
+            crate::BlockKind::MagmaBlock => Some(crate::MapColor::Nether),
+            crate::BlockKind::Melon => Some(crate::MapColor::Lime),
+            crate::BlockKind::MelonStem => Some(crate::MapColor::Plant),
+            crate::BlockKind::MossyCobblestone => Some(crate::MapColor::Stone),
+            crate::BlockKind::MossyCobblestoneWall => Some(crate::MapColor::Stone),
+            crate::BlockKind::MossyStoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::MovingPiston => Some(crate::MapColor::Stone),
+            crate::BlockKind::MushroomStem => Some(crate::MapColor::Wool),
+            crate::BlockKind::Mycelium => Some(crate::MapColor::Purple),
+            crate::BlockKind::NetherBrickFence => Some(crate::MapColor::Nether),
+            crate::BlockKind::NetherBrickSlab => Some(crate::MapColor::Nether),
+            crate::BlockKind::NetherBrickStairs => Some(crate::MapColor::Nether),
+            crate::BlockKind::NetherBricks => Some(crate::MapColor::Nether),
+            crate::BlockKind::NetherQuartzOre => Some(crate::MapColor::Nether),
+            crate::BlockKind::NetherWart => Some(crate::MapColor::Red),
+            crate::BlockKind::NetherWartBlock => Some(crate::MapColor::Red),
+            crate::BlockKind::Netherrack => Some(crate::MapColor::Nether),
+            crate::BlockKind::NoteBlock => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakDoor => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakFence => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakFenceGate => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakLeaves => Some(crate::MapColor::Plant),
+            crate::BlockKind::OakLog => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakPlanks => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakPressurePlate => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakSapling => Some(crate::MapColor::Plant),
+            crate::BlockKind::OakSlab => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakStairs => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakTrapdoor => Some(crate::MapColor::Wood),
+            crate::BlockKind::OakWood => Some(crate::MapColor::Wood),
+            crate::BlockKind::Observer => Some(crate::MapColor::Stone),
+            crate::BlockKind::Obsidian => Some(crate::MapColor::Black),
+            crate::BlockKind::OrangeBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::OrangeBed => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeCarpet => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeConcrete => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeConcretePowder => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeGlazedTerracotta => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeShulkerBox => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeStainedGlass => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeStainedGlassPane => Some(crate::MapColor::Orange),
+            crate::BlockKind::OrangeTerracotta => Some(crate::MapColor::OrangeTerracotta),
+            crate::BlockKind::OrangeTulip => Some(crate::MapColor::Plant),
+            crate::BlockKind::OrangeWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::OrangeWool => Some(crate::MapColor::Orange),
+            crate::BlockKind::OxeyeDaisy => Some(crate::MapColor::Plant),
+            crate::BlockKind::PackedIce => Some(crate::MapColor::Ice),
+            crate::BlockKind::Peony => Some(crate::MapColor::Plant),
+            crate::BlockKind::PetrifiedOakSlab => Some(crate::MapColor::Wood),
+            crate::BlockKind::PinkBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::PinkBed => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkCarpet => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkConcrete => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkConcretePowder => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkGlazedTerracotta => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkShulkerBox => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkStainedGlass => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkStainedGlassPane => Some(crate::MapColor::Pink),
+            crate::BlockKind::PinkTerracotta => Some(crate::MapColor::PinkTerracotta),
+            crate::BlockKind::PinkTulip => Some(crate::MapColor::Plant),
+            crate::BlockKind::PinkWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::PinkWool => Some(crate::MapColor::Pink),
+            crate::BlockKind::Piston => Some(crate::MapColor::Stone),
+            crate::BlockKind::PistonHead => Some(crate::MapColor::Stone),
+            crate::BlockKind::Podzol => Some(crate::MapColor::Podzol),
+            crate::BlockKind::PolishedAndesite => Some(crate::MapColor::Stone),
+            crate::BlockKind::PolishedDiorite => Some(crate::MapColor::Quartz),
+            crate::BlockKind::PolishedGranite => Some(crate::MapColor::Dirt),
+            crate::BlockKind::Poppy => Some(crate::MapColor::Plant),
+            crate::BlockKind::Potatoes => Some(crate::MapColor::Plant),
+            crate::BlockKind::Prismarine => Some(crate::MapColor::Cyan),
+            crate::BlockKind::PrismarineBrickSlab => Some(crate::MapColor::Diamond),
+            crate::BlockKind::PrismarineBrickStairs => Some(crate::MapColor::Diamond),
+            crate::BlockKind::PrismarineBricks => Some(crate::MapColor::Diamond),
+            crate::BlockKind::PrismarineSlab => Some(crate::MapColor::Cyan),
+            crate::BlockKind::PrismarineStairs => Some(crate::MapColor::Cyan),
+            crate::BlockKind::Pumpkin => Some(crate::MapColor::Orange),
+            crate::BlockKind::PumpkinStem => Some(crate::MapColor::Plant),
+            crate::BlockKind::PurpleBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::PurpleBed => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleCarpet => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleConcrete => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleConcretePowder => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleGlazedTerracotta => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleShulkerBox => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleStainedGlass => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleStainedGlassPane => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpleTerracotta => Some(crate::MapColor::PurpleTerracotta),
+            crate::BlockKind::PurpleWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::PurpleWool => Some(crate::MapColor::Purple),
+            crate::BlockKind::PurpurBlock => Some(crate::MapColor::Magenta),
+            crate::BlockKind::PurpurPillar => Some(crate::MapColor::Magenta),
+            crate::BlockKind::PurpurSlab => Some(crate::MapColor::Magenta),
+            crate::BlockKind::PurpurStairs => Some(crate::MapColor::Magenta),
+            crate::BlockKind::QuartzBlock => Some(crate::MapColor::Quartz),
+            crate::BlockKind::QuartzPillar => Some(crate::MapColor::Quartz),
+            crate::BlockKind::QuartzSlab => Some(crate::MapColor::Quartz),
+            crate::BlockKind::QuartzStairs => Some(crate::MapColor::Quartz),
+            crate::BlockKind::RedBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::RedBed => Some(crate::MapColor::Red),
+            crate::BlockKind::RedCarpet => Some(crate::MapColor::Red),
+            crate::BlockKind::RedConcrete => Some(crate::MapColor::Red),
+            crate::BlockKind::RedConcretePowder => Some(crate::MapColor::Red),
+            crate::BlockKind::RedGlazedTerracotta => Some(crate::MapColor::Red),
+            crate::BlockKind::RedMushroom => Some(crate::MapColor::Plant),
+            crate::BlockKind::RedMushroomBlock => Some(crate::MapColor::Red),
+            crate::BlockKind::RedNetherBricks => Some(crate::MapColor::Nether),
+            crate::BlockKind::RedSand => Some(crate::MapColor::Orange),
+            crate::BlockKind::RedSandstone => Some(crate::MapColor::Orange),
+            crate::BlockKind::RedSandstoneSlab => Some(crate::MapColor::Orange),
+            crate::BlockKind::RedSandstoneStairs => Some(crate::MapColor::Orange),
+            crate::BlockKind::RedShulkerBox => Some(crate::MapColor::Red),
+            crate::BlockKind::RedStainedGlass => Some(crate::MapColor::Red),
+            crate::BlockKind::RedStainedGlassPane => Some(crate::MapColor::Red),
+            crate::BlockKind::RedTerracotta => Some(crate::MapColor::RedTerracotta),
+            crate::BlockKind::RedTulip => Some(crate::MapColor::Plant),
+            crate::BlockKind::RedWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::RedWool => Some(crate::MapColor::Red),
+            crate::BlockKind::RedstoneBlock => Some(crate::MapColor::Fire),
+            crate::BlockKind::RedstoneOre => Some(crate::MapColor::Stone),
+            crate::BlockKind::RepeatingCommandBlock => Some(crate::MapColor::Purple),
+            crate::BlockKind::RoseBush => Some(crate::MapColor::Plant),
+            crate::BlockKind::Sand => Some(crate::MapColor::Sand),
+            crate::BlockKind::Sandstone => Some(crate::MapColor::Sand),
+            crate::BlockKind::SandstoneSlab => Some(crate::MapColor::Sand),
+            crate::BlockKind::SandstoneStairs => Some(crate::MapColor::Sand),
+            crate::BlockKind::SeaLantern => Some(crate::MapColor::Quartz),
+            crate::BlockKind::SeaPickle => Some(crate::MapColor::Green),
+            crate::BlockKind::Seagrass => Some(crate::MapColor::Water),
+            crate::BlockKind::ShulkerBox => Some(crate::MapColor::Purple),
+            crate::BlockKind::Sign => Some(crate::MapColor::Wood),
+            crate::BlockKind::SlimeBlock => Some(crate::MapColor::Grass),
+            crate::BlockKind::SmoothQuartz => Some(crate::MapColor::Quartz),
+            crate::BlockKind::SmoothRedSandstone => Some(crate::MapColor::Orange),
+            crate::BlockKind::SmoothSandstone => Some(crate::MapColor::Sand),
+            crate::BlockKind::SmoothStone => Some(crate::MapColor::Stone),
+            crate::BlockKind::Snow => Some(crate::MapColor::Snow),
+            crate::BlockKind::SnowBlock => Some(crate::MapColor::Snow),
+            crate::BlockKind::SoulSand => Some(crate::MapColor::Brown),
+            crate::BlockKind::Spawner => Some(crate::MapColor::Stone),
+            crate::BlockKind::Sponge => Some(crate::MapColor::Yellow),
+            crate::BlockKind::SpruceDoor => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceFence => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceFenceGate => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceLeaves => Some(crate::MapColor::Plant),
+            crate::BlockKind::SpruceLog => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SprucePlanks => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SprucePressurePlate => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceSapling => Some(crate::MapColor::Plant),
+            crate::BlockKind::SpruceSlab => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceStairs => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceTrapdoor => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SpruceWood => Some(crate::MapColor::Podzol),
+            crate::BlockKind::StickyPiston => Some(crate::MapColor::Stone),
+            crate::BlockKind::Stone => Some(crate::MapColor::Stone),
+            crate::BlockKind::StoneBrickSlab => Some(crate::MapColor::Stone),
+            crate::BlockKind::StoneBrickStairs => Some(crate::MapColor::Stone),
+            crate::BlockKind::StoneBricks => Some(crate::MapColor::Stone),
+            crate::BlockKind::StonePressurePlate => Some(crate::MapColor::Stone),
+            crate::BlockKind::StoneSlab => Some(crate::MapColor::Stone),
+            crate::BlockKind::StrippedAcaciaLog => Some(crate::MapColor::Orange),
+            crate::BlockKind::StrippedAcaciaWood => Some(crate::MapColor::Orange),
+            crate::BlockKind::StrippedBirchLog => Some(crate::MapColor::Sand),
+            crate::BlockKind::StrippedBirchWood => Some(crate::MapColor::Sand),
+            crate::BlockKind::StrippedDarkOakLog => Some(crate::MapColor::Brown),
+            crate::BlockKind::StrippedDarkOakWood => Some(crate::MapColor::Brown),
+            crate::BlockKind::StrippedJungleLog => Some(crate::MapColor::Dirt),
+            crate::BlockKind::StrippedJungleWood => Some(crate::MapColor::Dirt),
+            crate::BlockKind::StrippedOakLog => Some(crate::MapColor::Wood),
+            crate::BlockKind::StrippedOakWood => Some(crate::MapColor::Wood),
+            crate::BlockKind::StrippedSpruceLog => Some(crate::MapColor::Podzol),
+            crate::BlockKind::StrippedSpruceWood => Some(crate::MapColor::Podzol),
+            crate::BlockKind::SugarCane => Some(crate::MapColor::Plant),
+            crate::BlockKind::Sunflower => Some(crate::MapColor::Plant),
+            crate::BlockKind::TallGrass => Some(crate::MapColor::Plant),
+            crate::BlockKind::TallSeagrass => Some(crate::MapColor::Water),
+            crate::BlockKind::Terracotta => Some(crate::MapColor::Orange),
+            crate::BlockKind::Tnt => Some(crate::MapColor::Fire),
+            crate::BlockKind::TrappedChest => Some(crate::MapColor::Wood),
+            crate::BlockKind::TubeCoral => Some(crate::MapColor::Blue),
+            crate::BlockKind::TubeCoralBlock => Some(crate::MapColor::Blue),
+            crate::BlockKind::TubeCoralFan => Some(crate::MapColor::Blue),
+            crate::BlockKind::TubeCoralWallFan => Some(crate::MapColor::Blue),
+            crate::BlockKind::TurtleEgg => Some(crate::MapColor::Sand),
+            crate::BlockKind::Vine => Some(crate::MapColor::Plant),
+            crate::BlockKind::WallSign => Some(crate::MapColor::Wood),
+            crate::BlockKind::Water => Some(crate::MapColor::Water),
+            crate::BlockKind::WetSponge => Some(crate::MapColor::Yellow),
+            crate::BlockKind::Wheat => Some(crate::MapColor::Plant),
+            crate::BlockKind::WhiteBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::WhiteBed => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteCarpet => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteConcrete => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteConcretePowder => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteGlazedTerracotta => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteShulkerBox => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteStainedGlass => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteStainedGlassPane => Some(crate::MapColor::Snow),
+            crate::BlockKind::WhiteTerracotta => Some(crate::MapColor::WhiteTerracotta),
+            crate::BlockKind::WhiteTulip => Some(crate::MapColor::Plant),
+            crate::BlockKind::WhiteWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::WhiteWool => Some(crate::MapColor::Snow),
+            crate::BlockKind::YellowBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::YellowBed => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowCarpet => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowConcrete => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowConcretePowder => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowGlazedTerracotta => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowShulkerBox => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowStainedGlass => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowStainedGlassPane => Some(crate::MapColor::Yellow),
+            crate::BlockKind::YellowTerracotta => Some(crate::MapColor::YellowTerracotta),
+            crate::BlockKind::YellowWallBanner => Some(crate::MapColor::Wood),
+            crate::BlockKind::YellowWool => Some(crate::MapColor::Yellow),
+            _ => None,
+        }
+    }
+}
@@ -1,7 +1,19 @@
 // This file is @generated
+mod armor;
+pub use armor::*;
 mod block;
 pub use block::*;
+mod flammability;
+pub use flammability::*;
+mod food;
+pub use food::*;
+mod fuel;
+pub use fuel::*;
 mod item;
 pub use item::*;
+mod map_color;
+pub use map_color::*;
 mod tool;
 pub use tool::*;
+mod weapon;
+pub use weapon::*;
@@ -92,6 +92,17 @@ impl crate::ToolMaterial {
         }
     }
 }
+impl crate::ToolMaterial {
+    pub fn harvest_level(self) -> u32 {
+        match self {
+            crate::ToolMaterial::Diamond => 3u32,
+            crate::ToolMaterial::Golden => 0u32,
+            crate::ToolMaterial::Iron => 2u32,
+            crate::ToolMaterial::Stone => 1u32,
+            crate::ToolMaterial::Wooden => 0u32,
+        }
+    }
+}
 impl crate::Item {
     pub fn durability(self) -> Option<u32> {
         match self {
@@ -143,6 +154,7 @@ impl crate::Item {
             crate::Item::StoneShovel => Some(131u32),
             crate::Item::StoneSword => Some(131u32),
             crate::Item::Trident => Some(250u32),
+            crate::Item::TurtleHelmet => Some(275u32),
             crate::Item::WoodenAxe => Some(59u32),
             crate::Item::WoodenHoe => Some(59u32),
             crate::Item::WoodenPickaxe => Some(59u32),
@@ -155,13 +167,310 @@ impl crate::Item {
 impl crate::BlockKind {
     pub fn best_tool(self) -> Option<crate::Tool> {
         match self {
+            crate::BlockKind::AcaciaButton => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaDoor => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaFence => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaFenceGate => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaLeaves => Some(crate::Tool::Shears),
+            crate::BlockKind::AcaciaLog => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaPlanks => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaPressurePlate => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaSlab => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaStairs => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaTrapdoor => Some(crate::Tool::Axe),
+            crate::BlockKind::AcaciaWood => Some(crate::Tool::Axe),
+            crate::BlockKind::ActivatorRail => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Andesite => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Anvil => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BirchButton => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchDoor => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchFence => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchFenceGate => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchLeaves => Some(crate::Tool::Shears),
+            crate::BlockKind::BirchLog => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchPlanks => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchPressurePlate => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchSlab => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchStairs => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchTrapdoor => Some(crate::Tool::Axe),
+            crate::BlockKind::BirchWood => Some(crate::Tool::Axe),
+            crate::BlockKind::BlackConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlackConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::BlackGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlackTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlackWool => Some(crate::Tool::Shears),
+            crate::BlockKind::BlueConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlueConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::BlueGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlueIce => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlueTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BlueWool => Some(crate::Tool::Shears),
+            crate::BlockKind::BoneBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Bookshelf => Some(crate::Tool::Axe),
+            crate::BlockKind::BrewingStand => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BrickSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BrickStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Bricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BrownConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BrownConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::BrownGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BrownTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::BrownWool => Some(crate::Tool::Shears),
+            crate::BlockKind::CarvedPumpkin => Some(crate::Tool::Axe),
+            crate::BlockKind::Cauldron => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Chest => Some(crate::Tool::Axe),
+            crate::BlockKind::ChippedAnvil => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::ChiseledQuartzBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::ChiseledRedSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::ChiseledSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::ChiseledStoneBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Clay => Some(crate::Tool::Shovel),
+            crate::BlockKind::CoalBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CoalOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CoarseDirt => Some(crate::Tool::Shovel),
             crate::BlockKind::Cobblestone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CobblestoneSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CobblestoneStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CobblestoneWall => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Cobweb => Some(crate::Tool::Shears),
+            crate::BlockKind::CrackedStoneBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CraftingTable => Some(crate::Tool::Axe),
+            crate::BlockKind::CutRedSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CutSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CyanConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CyanConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::CyanGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CyanTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::CyanWool => Some(crate::Tool::Shears),
+            crate::BlockKind::DamagedAnvil => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::DarkOakButton => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakDoor => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakFence => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakFenceGate => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakLeaves => Some(crate::Tool::Shears),
+            crate::BlockKind::DarkOakLog => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakPlanks => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakPressurePlate => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakSlab => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakStairs => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakTrapdoor => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkOakWood => Some(crate::Tool::Axe),
+            crate::BlockKind::DarkPrismarine => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::DarkPrismarineSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::DarkPrismarineStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::DetectorRail => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::DiamondBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::DiamondOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Diorite => Some(crate::Tool::Pickaxe),
             crate::BlockKind::Dirt => Some(crate::Tool::Shovel),
+            crate::BlockKind::Dispenser => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Dropper => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::EmeraldBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::EmeraldOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::EnchantingTable => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::EndStone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::EndStoneBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::EnderChest => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Farmland => Some(crate::Tool::Shovel),
+            crate::BlockKind::FrostedIce => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Furnace => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GoldBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GoldOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Granite => Some(crate::Tool::Pickaxe),
             crate::BlockKind::GrassBlock => Some(crate::Tool::Shovel),
+            crate::BlockKind::GrassPath => Some(crate::Tool::Shovel),
+            crate::BlockKind::Gravel => Some(crate::Tool::Shovel),
+            crate::BlockKind::GrayConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GrayConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::GrayGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GrayTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GrayWool => Some(crate::Tool::Shears),
+            crate::BlockKind::GreenConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GreenConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::GreenGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GreenTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::GreenWool => Some(crate::Tool::Shears),
+            crate::BlockKind::HeavyWeightedPressurePlate => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Hopper => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Ice => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::IronBars => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::IronBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::IronDoor => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::IronOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::IronTrapdoor => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::JackOLantern => Some(crate::Tool::Axe),
+            crate::BlockKind::Jukebox => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleButton => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleDoor => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleFence => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleFenceGate => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleLeaves => Some(crate::Tool::Shears),
+            crate::BlockKind::JungleLog => Some(crate::Tool::Axe),
+            crate::BlockKind::JunglePlanks => Some(crate::Tool::Axe),
+            crate::BlockKind::JunglePressurePlate => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleSlab => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleStairs => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleTrapdoor => Some(crate::Tool::Axe),
+            crate::BlockKind::JungleWood => Some(crate::Tool::Axe),
+            crate::BlockKind::Ladder => Some(crate::Tool::Axe),
+            crate::BlockKind::LapisBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LapisOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightBlueConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightBlueConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::LightBlueGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightBlueTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightBlueWool => Some(crate::Tool::Shears),
+            crate::BlockKind::LightGrayConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightGrayConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::LightGrayGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightGrayTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LightGrayWool => Some(crate::Tool::Shears),
+            crate::BlockKind::LightWeightedPressurePlate => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LimeConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LimeConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::LimeGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LimeTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::LimeWool => Some(crate::Tool::Shears),
+            crate::BlockKind::MagentaConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::MagentaConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::MagentaGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::MagentaTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::MagentaWool => Some(crate::Tool::Shears),
+            crate::BlockKind::MagmaBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Melon => Some(crate::Tool::Axe),
+            crate::BlockKind::MossyCobblestone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::MossyCobblestoneWall => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::MossyStoneBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Mycelium => Some(crate::Tool::Shovel),
+            crate::BlockKind::NetherBrickFence => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::NetherBrickSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::NetherBrickStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::NetherBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::NetherQuartzOre => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Netherrack => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::NoteBlock => Some(crate::Tool::Axe),
+            crate::BlockKind::OakButton => Some(crate::Tool::Axe),
+            crate::BlockKind::OakDoor => Some(crate::Tool::Axe),
+            crate::BlockKind::OakFence => Some(crate::Tool::Axe),
+            crate::BlockKind::OakFenceGate => Some(crate::Tool::Axe),
+            crate::BlockKind::OakLeaves => Some(crate::Tool::Shears),
+            crate::BlockKind::OakLog => Some(crate::Tool::Axe),
+            crate::BlockKind::OakPlanks => Some(crate::Tool::Axe),
+            crate::BlockKind::OakPressurePlate => Some(crate::Tool::Axe),
+            crate::BlockKind::OakSlab => Some(crate::Tool::Axe),
+            crate::BlockKind::OakStairs => Some(crate::Tool::Axe),
+            crate::BlockKind::OakTrapdoor => Some(crate::Tool::Axe),
+            crate::BlockKind::OakWood => Some(crate::Tool::Axe),
+            crate::BlockKind::Observer => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Obsidian => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::OrangeConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::OrangeConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::OrangeGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::OrangeTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::OrangeWool => Some(crate::Tool::Shears),
+            crate::BlockKind::PackedIce => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PinkConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PinkConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::PinkGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PinkTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PinkWool => Some(crate::Tool::Shears),
+            crate::BlockKind::Piston => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Podzol => Some(crate::Tool::Shovel),
+            crate::BlockKind::PolishedAndesite => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PolishedDiorite => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PolishedGranite => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PoweredRail => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Prismarine => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PrismarineBrickSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PrismarineBrickStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PrismarineBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PrismarineSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PrismarineStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Pumpkin => Some(crate::Tool::Axe),
+            crate::BlockKind::PurpleConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PurpleConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::PurpleGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PurpleTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PurpleWool => Some(crate::Tool::Shears),
+            crate::BlockKind::PurpurBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PurpurPillar => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PurpurSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::PurpurStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::QuartzBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::QuartzPillar => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::QuartzSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::QuartzStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Rail => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::RedGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedNetherBricks => Some(crate::Tool::Pickaxe),
             crate::BlockKind::RedSand => Some(crate::Tool::Shovel),
+            crate::BlockKind::RedSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedSandstoneSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedSandstoneStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedWool => Some(crate::Tool::Shears),
+            crate::BlockKind::RedstoneBlock => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::RedstoneOre => Some(crate::Tool::Pickaxe),
             crate::BlockKind::Sand => Some(crate::Tool::Shovel),
             crate::BlockKind::Sandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::SandstoneSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::SandstoneStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Sign => Some(crate::Tool::Axe),
+            crate::BlockKind::SmoothQuartz => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::SmoothRedSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::SmoothSandstone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::SmoothStone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::Snow => Some(crate::Tool::Shovel),
+            crate::BlockKind::SnowBlock => Some(crate::Tool::Shovel),
+            crate::BlockKind::SoulSand => Some(crate::Tool::Shovel),
+            crate::BlockKind::Spawner => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::SpruceButton => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceDoor => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceFence => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceFenceGate => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceLeaves => Some(crate::Tool::Shears),
+            crate::BlockKind::SpruceLog => Some(crate::Tool::Axe),
+            crate::BlockKind::SprucePlanks => Some(crate::Tool::Axe),
+            crate::BlockKind::SprucePressurePlate => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceSlab => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceStairs => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceTrapdoor => Some(crate::Tool::Axe),
+            crate::BlockKind::SpruceWood => Some(crate::Tool::Axe),
+            crate::BlockKind::StickyPiston => Some(crate::Tool::Pickaxe),
             crate::BlockKind::Stone => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StoneBrickSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StoneBrickStairs => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StoneBricks => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StoneButton => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StonePressurePlate => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StoneSlab => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::StrippedAcaciaLog => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedAcaciaWood => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedBirchLog => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedBirchWood => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedDarkOakLog => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedDarkOakWood => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedJungleLog => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedJungleWood => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedOakLog => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedOakWood => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedSpruceLog => Some(crate::Tool::Axe),
+            crate::BlockKind::StrippedSpruceWood => Some(crate::Tool::Axe),
+            crate::BlockKind::Terracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::TrappedChest => Some(crate::Tool::Axe),
+            crate::BlockKind::WallSign => Some(crate::Tool::Axe),
+            crate::BlockKind::WhiteConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::WhiteConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::WhiteGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::WhiteTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::WhiteWool => Some(crate::Tool::Shears),
+            crate::BlockKind::YellowConcrete => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::YellowConcretePowder => Some(crate::Tool::Shovel),
+            crate::BlockKind::YellowGlazedTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::YellowTerracotta => Some(crate::Tool::Pickaxe),
+            crate::BlockKind::YellowWool => Some(crate::Tool::Shears),
             _ => None,
         }
     }
@@ -169,10 +478,178 @@ impl crate::BlockKind {
 impl crate::BlockKind {
     pub fn best_tool_required(self) -> bool {
         match self {
+            crate::BlockKind::Andesite => true,
+            crate::BlockKind::Anvil => true,
+            crate::BlockKind::BlackConcrete => true,
+            crate::BlockKind::BlackGlazedTerracotta => true,
+            crate::BlockKind::BlackTerracotta => true,
+            crate::BlockKind::BlueConcrete => true,
+            crate::BlockKind::BlueGlazedTerracotta => true,
+            crate::BlockKind::BlueTerracotta => true,
+            crate::BlockKind::BoneBlock => true,
+            crate::BlockKind::BrewingStand => true,
+            crate::BlockKind::BrickSlab => true,
+            crate::BlockKind::BrickStairs => true,
+            crate::BlockKind::Bricks => true,
+            crate::BlockKind::BrownConcrete => true,
+            crate::BlockKind::BrownGlazedTerracotta => true,
+            crate::BlockKind::BrownTerracotta => true,
+            crate::BlockKind::Cauldron => true,
+            crate::BlockKind::ChippedAnvil => true,
+            crate::BlockKind::ChiseledQuartzBlock => true,
+            crate::BlockKind::ChiseledRedSandstone => true,
+            crate::BlockKind::ChiseledSandstone => true,
+            crate::BlockKind::ChiseledStoneBricks => true,
+            crate::BlockKind::CoalBlock => true,
+            crate::BlockKind::CoalOre => true,
             crate::BlockKind::Cobblestone => true,
+            crate::BlockKind::CobblestoneSlab => true,
+            crate::BlockKind::CobblestoneStairs => true,
+            crate::BlockKind::CobblestoneWall => true,
+            crate::BlockKind::Cobweb => true,
+            crate::BlockKind::CrackedStoneBricks => true,
+            crate::BlockKind::CutRedSandstone => true,
+            crate::BlockKind::CutSandstone => true,
+            crate::BlockKind::CyanConcrete => true,
+            crate::BlockKind::CyanGlazedTerracotta => true,
+            crate::BlockKind::CyanTerracotta => true,
+            crate::BlockKind::DamagedAnvil => true,
+            crate::BlockKind::DarkPrismarine => true,
+            crate::BlockKind::DarkPrismarineSlab => true,
+            crate::BlockKind::DarkPrismarineStairs => true,
+            crate::BlockKind::DiamondBlock => true,
+            crate::BlockKind::DiamondOre => true,
+            crate::BlockKind::Diorite => true,
+            crate::BlockKind::Dispenser => true,
+            crate::BlockKind::Dropper => true,
+            crate::BlockKind::EmeraldBlock => true,
+            crate::BlockKind::EmeraldOre => true,
+            crate::BlockKind::EnchantingTable => true,
+            crate::BlockKind::EndStone => true,
+            crate::BlockKind::EndStoneBricks => true,
+            crate::BlockKind::EnderChest => true,
+            crate::BlockKind::Furnace => true,
+            crate::BlockKind::GoldBlock => true,
+            crate::BlockKind::GoldOre => true,
+            crate::BlockKind::Granite => true,
+            crate::BlockKind::GrayConcrete => true,
+            crate::BlockKind::GrayGlazedTerracotta => true,
+            crate::BlockKind::GrayTerracotta => true,
+            crate::BlockKind::GreenConcrete => true,
+            crate::BlockKind::GreenGlazedTerracotta => true,
+            crate::BlockKind::GreenTerracotta => true,
+            crate::BlockKind::HeavyWeightedPressurePlate => true,
+            crate::BlockKind::Hopper => true,
+            crate::BlockKind::IronBars => true,
+            crate::BlockKind::IronBlock => true,
+            crate::BlockKind::IronDoor => true,
+            crate::BlockKind::IronOre => true,
+            crate::BlockKind::IronTrapdoor => true,
+            crate::BlockKind::LapisBlock => true,
+            crate::BlockKind::LapisOre => true,
+            crate::BlockKind::LightBlueConcrete => true,
+            crate::BlockKind::LightBlueGlazedTerracotta => true,
+            crate::BlockKind::LightBlueTerracotta => true,
+            crate::BlockKind::LightGrayConcrete => true,
+            crate::BlockKind::LightGrayGlazedTerracotta => true,
+            crate::BlockKind::LightGrayTerracotta => true,
+            crate::BlockKind::LightWeightedPressurePlate => true,
+            crate::BlockKind::LimeConcrete => true,
+            crate::BlockKind::LimeGlazedTerracotta => true,
+            crate::BlockKind::LimeTerracotta => true,
+            crate::BlockKind::MagentaConcrete => true,
+            crate::BlockKind::MagentaGlazedTerracotta => true,
+            crate::BlockKind::MagentaTerracotta => true,
+            crate::BlockKind::MagmaBlock => true,
+            crate::BlockKind::MossyCobblestone => true,
+            crate::BlockKind::MossyCobblestoneWall => true,
+            crate::BlockKind::MossyStoneBricks => true,
+            crate::BlockKind::NetherBrickFence => true,
+            crate::BlockKind::NetherBrickSlab => true,
+            crate::BlockKind::NetherBrickStairs => true,
+            crate::BlockKind::NetherBricks => true,
+            crate::BlockKind::NetherQuartzOre => true,
+            crate::BlockKind::Netherrack => true,
+            crate::BlockKind::Observer => true,
+            crate::BlockKind::Obsidian => true,
+            crate::BlockKind::OrangeConcrete => true,
+            crate::BlockKind::OrangeGlazedTerracotta => true,
+            crate::BlockKind::OrangeTerracotta => true,
+            crate::BlockKind::PinkConcrete => true,
+            crate::BlockKind::PinkGlazedTerracotta => true,
+            crate::BlockKind::PinkTerracotta => true,
+            crate::BlockKind::PolishedAndesite => true,
+            crate::BlockKind::PolishedDiorite => true,
+            crate::BlockKind::PolishedGranite => true,
+            crate::BlockKind::Prismarine => true,
+            crate::BlockKind::PrismarineBrickSlab => true,
+            crate::BlockKind::PrismarineBrickStairs => true,
+            crate::BlockKind::PrismarineBricks => true,
+            crate::BlockKind::PrismarineSlab => true,
+            crate::BlockKind::PrismarineStairs => true,
+            crate::BlockKind::PurpleConcrete => true,
+            crate::BlockKind::PurpleGlazedTerracotta => true,
+            crate::BlockKind::PurpleTerracotta => true,
+            crate::BlockKind::PurpurBlock => true,
+            crate::BlockKind::PurpurPillar => true,
+            crate::BlockKind::PurpurSlab => true,
+            crate::BlockKind::PurpurStairs => true,
+            crate::BlockKind::QuartzBlock => true,
+            crate::BlockKind::QuartzPillar => true,
+            crate::BlockKind::QuartzSlab => true,
+            crate::BlockKind::QuartzStairs => true,
+            crate::BlockKind::RedConcrete => true,
+            crate::BlockKind::RedGlazedTerracotta => true,
+            crate::BlockKind::RedNetherBricks => true,
+            crate::BlockKind::RedSandstone => true,
+            crate::BlockKind::RedSandstoneSlab => true,
+            crate::BlockKind::RedSandstoneStairs => true,
+            crate::BlockKind::RedTerracotta => true,
+            crate::BlockKind::RedstoneBlock => true,
+            crate::BlockKind::RedstoneOre => true,
             crate::BlockKind::Sandstone => true,
+            crate::BlockKind::SandstoneSlab => true,
+            crate::BlockKind::SandstoneStairs => true,
+            crate::BlockKind::SmoothQuartz => true,
+            crate::BlockKind::SmoothRedSandstone => true,
+            crate::BlockKind::SmoothSandstone => true,
+            crate::BlockKind::SmoothStone => true,
+            crate::BlockKind::Snow => true,
+            crate::BlockKind::SnowBlock => true,
+            crate::BlockKind::Spawner => true,
             crate::BlockKind::Stone => true,
+            crate::BlockKind::StoneBrickSlab => true,
+            crate::BlockKind::StoneBrickStairs => true,
+            crate::BlockKind::StoneBricks => true,
+            crate::BlockKind::StonePressurePlate => true,
+            crate::BlockKind::StoneSlab => true,
+            crate::BlockKind::Terracotta => true,
+            crate::BlockKind::WhiteConcrete => true,
+            crate::BlockKind::WhiteGlazedTerracotta => true,
+            crate::BlockKind::WhiteTerracotta => true,
+            crate::BlockKind::YellowConcrete => true,
+            crate::BlockKind::YellowGlazedTerracotta => true,
+            crate::BlockKind::YellowTerracotta => true,
             _ => false,
         }
     }
 }
+impl crate::BlockKind {
+    pub fn harvest_level(self) -> Option<u32> {
+        match self {
+            crate::BlockKind::DiamondBlock => Some(2u32),
+            crate::BlockKind::DiamondOre => Some(2u32),
+            crate::BlockKind::EmeraldBlock => Some(2u32),
+            crate::BlockKind::EmeraldOre => Some(2u32),
+            crate::BlockKind::GoldBlock => Some(2u32),
+            crate::BlockKind::GoldOre => Some(2u32),
+            crate::BlockKind::IronBlock => Some(1u32),
+            crate::BlockKind::IronOre => Some(1u32),
+            crate::BlockKind::LapisBlock => Some(1u32),
+            crate::BlockKind::LapisOre => Some(1u32),
+            crate::BlockKind::Obsidian => Some(3u32),
+            crate::BlockKind::RedstoneOre => Some(2u32),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// This file is @generated
+impl crate::Item {
+    pub fn attack_damage(self) -> Option<f64> {
+        match self {
+            crate::Item::DiamondAxe => Some(9f64),
+            crate::Item::DiamondHoe => Some(1f64),
+            crate::Item::DiamondPickaxe => Some(5f64),
+            crate::Item::DiamondShovel => Some(5.5f64),
+            crate::Item::DiamondSword => Some(7f64),
+            crate::Item::GoldenAxe => Some(7f64),
+            crate::Item::GoldenHoe => Some(1f64),
+            crate::Item::GoldenPickaxe => Some(2f64),
+            crate::Item::GoldenShovel => Some(2.5f64),
+            crate::Item::GoldenSword => Some(4f64),
+            crate::Item::IronAxe => Some(9f64),
+            crate::Item::IronHoe => Some(1f64),
+            crate::Item::IronPickaxe => Some(4f64),
+            crate::Item::IronShovel => Some(4.5f64),
+            crate::Item::IronSword => Some(6f64),
+            crate::Item::StoneAxe => Some(9f64),
+            crate::Item::StoneHoe => Some(1f64),
+            crate::Item::StonePickaxe => Some(3f64),
+            crate::Item::StoneShovel => Some(3.5f64),
+            crate::Item::StoneSword => Some(5f64),
+            crate::Item::Trident => Some(9f64),
+            crate::Item::WoodenAxe => Some(7f64),
+            crate::Item::WoodenHoe => Some(1f64),
+            crate::Item::WoodenPickaxe => Some(2f64),
+            crate::Item::WoodenShovel => Some(2.5f64),
+            crate::Item::WoodenSword => Some(4f64),
+            _ => None,
+        }
+    }
+}
+impl crate::Item {
+    pub fn attack_speed(self) -> Option<f64> {
+        match self {
+            crate::Item::DiamondAxe => Some(1f64),
+            crate::Item::DiamondHoe => Some(4f64),
+            crate::Item::DiamondPickaxe => Some(1.2f64),
+            crate::Item::DiamondShovel => Some(1f64),
+            crate::Item::DiamondSword => Some(1.6f64),
+            crate::Item::GoldenAxe => Some(1f64),
+            crate::Item::GoldenHoe => Some(1f64),
+            crate::Item::GoldenPickaxe => Some(1.2f64),
+            crate::Item::GoldenShovel => Some(1f64),
+            crate::Item::GoldenSword => Some(1.6f64),
+            crate::Item::IronAxe => Some(0.9f64),
+            crate::Item::IronHoe => Some(3f64),
+            crate::Item::IronPickaxe => Some(1.2f64),
+            crate::Item::IronShovel => Some(1f64),
+            crate::Item::IronSword => Some(1.6f64),
+            crate::Item::StoneAxe => Some(0.8f64),
+            crate::Item::StoneHoe => Some(2f64),
+            crate::Item::StonePickaxe => Some(1.2f64),
+            crate::Item::StoneShovel => Some(1f64),
+            crate::Item::StoneSword => Some(1.6f64),
+            crate::Item::Trident => Some(1.1f64),
+            crate::Item::WoodenAxe => Some(0.8f64),
+            crate::Item::WoodenHoe => Some(1f64),
+            crate::Item::WoodenPickaxe => Some(1.2f64),
+            crate::Item::WoodenShovel => Some(1f64),
+            crate::Item::WoodenSword => Some(1.6f64),
+            _ => None,
+        }
+    }
+}
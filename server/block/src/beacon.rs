@@ -0,0 +1,648 @@
+//! Beacons, which give status effects to nearby players
+//! while they stand on a pyramid of mineral blocks and
+//! their beam can reach the sky.
+//!
+//! See https://minecraft.gamepedia.com/Beacon.
+
+use crate::ShouldReplace;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::blocks::{BlockId, BlockKind};
+use feather_core::inventory::{Area, Window};
+use feather_core::items::Item;
+use feather_core::network::packets::{
+    OpenWindow, SetBeaconEffect, SetSlot, SoundCategory, WindowProperty,
+};
+use feather_core::text::TextRoot;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, Dead, Game, InteractionHandler, Inventory,
+    InventoryUpdateEvent, ItemDropEvent, Network, PacketBuffers, Player, Sound, StatusEffect,
+    StatusEffectKind, WindowCloseEvent, WindowOpenEvent, WorldId,
+};
+use fecs::{component, Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use std::sync::Arc;
+
+/// Ticks between each time beacons check their
+/// pyramid and give effects to players.
+const UPDATE_INTERVAL: u64 = 80;
+
+/// Number of levels a pyramid may have.
+const MAX_LEVELS: u8 = 4;
+
+/// The effects which may be selected as the primary
+/// effect of a beacon, by the pyramid level needed.
+const PRIMARY_EFFECTS: [&[StatusEffectKind]; 3] = [
+    &[StatusEffectKind::Speed, StatusEffectKind::Haste],
+    &[StatusEffectKind::Resistance, StatusEffectKind::JumpBoost],
+    &[StatusEffectKind::Strength],
+];
+
+// Window properties of beacons.
+const PROPERTY_LEVELS: i16 = 0;
+const PROPERTY_PRIMARY: i16 = 1;
+const PROPERTY_SECONDARY: i16 = 2;
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Beacon,
+});
+
+/// Component for beacons.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Beacon {
+    /// Levels of the pyramid below the beacon, or 0
+    /// if its beam can't reach the sky.
+    pub levels: u8,
+    pub primary: Option<StatusEffectKind>,
+    /// Only given by beacons on pyramids of all four levels.
+    pub secondary: Option<StatusEffectKind>,
+}
+
+/// Component for the inventory behind a beacon window, which
+/// is created for each player opening a beacon and holds the
+/// item they pay to select its effects.
+#[derive(Copy, Clone, Debug)]
+pub struct BeaconWindow {
+    /// The beacon being configured.
+    pub beacon: Entity,
+    /// The ID of the window opened for the player.
+    pub window_id: u8,
+}
+
+/// Creates a beacon.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with(pos, Beacon::default())
+}
+
+/// Creates a beacon with the given pyramid levels and effects.
+pub fn create_with(pos: BlockPosition, beacon: Beacon) -> EntityBuilder {
+    crate::base(pos)
+        .with(beacon)
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    let beacon = *accessor.get::<Beacon>();
+    let id = |effect: Option<StatusEffectKind>| effect.map_or(0, StatusEffectKind::id);
+
+    BlockEntityData {
+        base: crate::serialize_base(accessor),
+        kind: BlockEntityKind::Beacon {
+            levels: i32::from(beacon.levels),
+            primary: id(beacon.primary),
+            secondary: id(beacon.secondary),
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let beacon = match data.kind {
+        BlockEntityKind::Beacon {
+            levels,
+            primary,
+            secondary,
+        } => Beacon {
+            levels: levels.clamp(0, i32::from(MAX_LEVELS)) as u8,
+            primary: StatusEffectKind::from_id(primary),
+            secondary: StatusEffectKind::from_id(secondary),
+        },
+        _ => bail!("not a beacon"),
+    };
+
+    Ok(create_with(pos, beacon))
+}
+
+/// Returns whether a block can be part of a beacon pyramid.
+fn is_base_block(block: BlockId) -> bool {
+    matches!(
+        block.kind(),
+        BlockKind::IronBlock
+            | BlockKind::GoldBlock
+            | BlockKind::EmeraldBlock
+            | BlockKind::DiamondBlock
+    )
+}
+
+/// Returns whether an item can be paid to select the effects of a beacon.
+pub fn is_payment(item: Item) -> bool {
+    matches!(
+        item,
+        Item::IronIngot | Item::GoldIngot | Item::Emerald | Item::Diamond
+    )
+}
+
+/// Returns the number of complete levels of the pyramid
+/// below a beacon. Each level is a square of mineral blocks
+/// which is one block wider on each side than the level above.
+pub fn pyramid_levels(game: &Game, world_id: WorldId, pos: BlockPosition) -> u8 {
+    let min_y = game.worlds[world_id].vertical_bounds(pos).start;
+    let mut levels = 0;
+    for level in 1..=MAX_LEVELS {
+        let radius = i32::from(level);
+        let y = pos.y - radius;
+        if y < min_y {
+            break;
+        }
+
+        let complete = (-radius..=radius).all(|x| {
+            (-radius..=radius).all(|z| {
+                game.block_at(world_id, BlockPosition::new(pos.x + x, y, pos.z + z))
+                    .map_or(false, is_base_block)
+            })
+        });
+        if !complete {
+            break;
+        }
+        levels = level;
+    }
+    levels
+}
+
+/// Returns whether the beam of a beacon can reach the sky,
+/// i.e. there are no opaque blocks above it. Bedrock lets
+/// the beam through.
+pub fn has_sky_access(game: &Game, world_id: WorldId, pos: BlockPosition) -> bool {
+    let max_y = game.worlds[world_id].vertical_bounds(pos).end;
+    (pos.y + 1..max_y).all(
+        |y| match game.block_at(world_id, BlockPosition::new(pos.x, y, pos.z)) {
+            Some(block) => block.light_opacity() < 15 || block.kind() == BlockKind::Bedrock,
+            None => true,
+        },
+    )
+}
+
+/// Returns whether an effect may be selected as the
+/// primary effect of a beacon with the given levels.
+fn is_valid_primary(levels: u8, effect: StatusEffectKind) -> bool {
+    PRIMARY_EFFECTS
+        .iter()
+        .take(levels as usize)
+        .any(|effects| effects.contains(&effect))
+}
+
+/// Returns whether an effect may be selected as the secondary
+/// effect of a beacon with the given levels and primary effect.
+/// It is either Regeneration or a second level of the primary effect.
+fn is_valid_secondary(levels: u8, primary: StatusEffectKind, effect: StatusEffectKind) -> bool {
+    levels >= MAX_LEVELS && (effect == StatusEffectKind::Regeneration || effect == primary)
+}
+
+/// Returns the status effects given by a beacon.
+fn effects(beacon: Beacon) -> Vec<StatusEffect> {
+    let mut effects = Vec::new();
+    let primary = match beacon.primary {
+        Some(primary) if beacon.levels > 0 => primary,
+        _ => return effects,
+    };
+
+    let duration = (9 + 2 * u32::from(beacon.levels)) * 20;
+    let doubled = beacon.levels >= MAX_LEVELS && beacon.secondary == Some(primary);
+    effects.push(StatusEffect::new(primary, doubled as u8, duration).ambient());
+    match beacon.secondary {
+        Some(secondary) if beacon.levels >= MAX_LEVELS && secondary != primary => {
+            effects.push(StatusEffect::new(secondary, 0, duration).ambient());
+        }
+        _ => (),
+    }
+    effects
+}
+
+/// Selects the effects of the beacon whose window is open,
+/// using up the item paid for them. Returns whether the
+/// effects could be selected.
+pub fn select_effects(
+    game: &mut Game,
+    world: &mut World,
+    window: Entity,
+    primary: i32,
+    secondary: i32,
+) -> bool {
+    let beacon_entity = world.get::<BeaconWindow>(window).beacon;
+    if !world.is_alive(beacon_entity) {
+        return false;
+    }
+    let mut beacon = *world.get::<Beacon>(beacon_entity);
+
+    let primary = match StatusEffectKind::from_id(primary) {
+        Some(primary) if is_valid_primary(beacon.levels, primary) => primary,
+        _ => return false,
+    };
+    let secondary = match StatusEffectKind::from_id(secondary) {
+        Some(secondary) if is_valid_secondary(beacon.levels, primary, secondary) => Some(secondary),
+        Some(_) => return false,
+        None => None,
+    };
+
+    {
+        let inventory = world.get::<Inventory>(window);
+        match inventory.item_at(Area::BeaconPayment, 0).unwrap() {
+            Some(item) if is_payment(item.ty) => {
+                inventory
+                    .set_item_at(Area::BeaconPayment, 0, item.of_amount(item.amount - 1))
+                    .unwrap();
+            }
+            _ => return false,
+        }
+    }
+
+    beacon.primary = Some(primary);
+    beacon.secondary = secondary;
+    *world.get_mut::<Beacon>(beacon_entity) = beacon;
+
+    let world_id = game.world_of(world, beacon_entity);
+    let pos = *world.get::<Position>(beacon_entity);
+    let sound = Sound::new("block.beacon.power_select", SoundCategory::Blocks);
+    game.play_sound(world, world_id, pos, &sound);
+    true
+}
+
+/// System which handles Set Beacon Effect packets, selecting
+/// the effects of the beacon whose window a player has open.
+#[fecs::system]
+pub fn handle_set_beacon_effect(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    for (player, packet) in packet_buffers.received::<SetBeaconEffect>() {
+        if !world.is_alive(player) {
+            continue;
+        }
+        let window = world
+            .get::<Window>(player)
+            .wrapped_entities()
+            .iter()
+            .copied()
+            .find(|entity| world.has::<BeaconWindow>(*entity));
+        let window = match window {
+            Some(window) => window,
+            None => continue,
+        };
+
+        if select_effects(
+            game,
+            world,
+            window,
+            packet.primary_effect,
+            packet.secondary_effect,
+        ) {
+            if let Some(network) = world.try_get::<Network>(player) {
+                network.send(SetSlot {
+                    window_id: world.get::<BeaconWindow>(window).window_id as i8,
+                    slot: 0,
+                    slot_data: world
+                        .get::<Inventory>(window)
+                        .item_at(Area::BeaconPayment, 0)
+                        .unwrap(),
+                });
+            }
+        }
+    }
+}
+
+/// System which updates the pyramid levels of beacons every four
+/// seconds and gives their effects to the players in their range.
+#[fecs::system]
+pub fn update_beacons(game: &mut Game, world: &mut World) {
+    if game.tick_count % UPDATE_INTERVAL != 0 {
+        return;
+    }
+
+    let beacons: Vec<(Entity, WorldId, BlockPosition)> =
+        <(Read<Beacon>, Read<BlockPosition>)>::query()
+            .iter_entities(world.inner())
+            .map(|(entity, (_, pos))| (entity, game.world_of(world, entity), *pos))
+            .collect();
+
+    for (entity, world_id, pos) in beacons {
+        let levels = if has_sky_access(game, world_id, pos) {
+            pyramid_levels(game, world_id, pos)
+        } else {
+            0
+        };
+
+        let old = world.get::<Beacon>(entity).levels;
+        world.get_mut::<Beacon>(entity).levels = levels;
+        if (old > 0) != (levels > 0) {
+            let name = if levels > 0 {
+                "block.beacon.activate"
+            } else {
+                "block.beacon.deactivate"
+            };
+            let sound = Sound::new(name, SoundCategory::Blocks);
+            game.play_sound(world, world_id, Position::from(pos), &sound);
+        }
+
+        give_effects(game, world, world_id, pos, *world.get::<Beacon>(entity));
+    }
+}
+
+/// Gives the effects of a beacon to the players in its range,
+/// which extends from the sky down to `range` blocks below it.
+fn give_effects(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    beacon: Beacon,
+) {
+    let effects = effects(beacon);
+    if effects.is_empty() {
+        return;
+    }
+
+    let range = 10.0 + 10.0 * f64::from(beacon.levels);
+    let (x, z) = (f64::from(pos.x) + 0.5, f64::from(pos.z) + 0.5);
+    let players: Vec<Entity> = <Read<Position>>::query()
+        .filter(component::<Player>() & !component::<Dead>())
+        .iter_entities(world.inner())
+        .filter(|(player, player_pos)| {
+            game.world_of(world, *player) == world_id
+                && (player_pos.x - x).abs() <= range + 0.5
+                && (player_pos.z - z).abs() <= range + 0.5
+                && player_pos.y >= f64::from(pos.y) - range
+        })
+        .map(|(player, _)| player)
+        .collect();
+
+    for player in players {
+        for effect in &effects {
+            game.add_status_effect(world, player, *effect);
+        }
+    }
+}
+
+/// When a player closes a beacon window, gives them back
+/// the item they put in and removes the window's inventory.
+#[fecs::event_handler]
+pub fn on_window_close_return_beacon_payment(
+    event: &WindowCloseEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<BeaconWindow>(event.closed) {
+        return;
+    }
+
+    let item = world
+        .get::<Inventory>(event.closed)
+        .item_at(Area::BeaconPayment, 0)
+        .unwrap();
+    if let Some(item) = item {
+        let (slots, remaining) = match world.try_get::<Inventory>(event.player) {
+            Some(inventory) => inventory.collect_item(item),
+            None => (Default::default(), item.amount),
+        };
+        if !slots.is_empty() {
+            game.handle(
+                world,
+                InventoryUpdateEvent {
+                    slots,
+                    entity: event.player,
+                },
+            );
+        }
+        if remaining > 0 {
+            game.handle(
+                world,
+                ItemDropEvent {
+                    slot: None,
+                    stack: item.of_amount(remaining),
+                    player: event.player,
+                },
+            );
+        }
+    }
+
+    world.despawn(event.closed);
+}
+
+/// Handler for player right clicking on beacons.
+pub struct BeaconInteraction;
+inventory::submit!(Box::new(BeaconInteraction) as Box<dyn InteractionHandler>);
+
+impl InteractionHandler for BeaconInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let beacon_entity = match game.worlds[world_id].block_entities.get(&pos).copied() {
+            Some(beacon) if world.has::<Beacon>(beacon) => beacon,
+            _ => return,
+        };
+        let beacon = *world.get::<Beacon>(beacon_entity);
+
+        let window = EntityBuilder::new()
+            .with(Inventory::beacon())
+            .with(BeaconWindow {
+                beacon: beacon_entity,
+                window_id,
+            })
+            .build()
+            .spawn_in(world);
+
+        {
+            let network = world.get::<Network>(player);
+            network.send(OpenWindow {
+                window_id,
+                window_type: String::from("minecraft:beacon"),
+                window_title: TextRoot::from("Beacon").into(),
+                number_of_slots: 1,
+                entity_id: None,
+            });
+            let id = |effect: Option<StatusEffectKind>| effect.map_or(-1, StatusEffectKind::id);
+            for &(property, value) in &[
+                (PROPERTY_LEVELS, i32::from(beacon.levels)),
+                (PROPERTY_PRIMARY, id(beacon.primary)),
+                (PROPERTY_SECONDARY, id(beacon.secondary)),
+            ] {
+                network.send(WindowProperty {
+                    window_id,
+                    property,
+                    value: value as i16,
+                });
+            }
+        }
+        *world.get_mut::<Window>(player) = Window::beacon(player, window);
+        game.handle(
+            world,
+            WindowOpenEvent {
+                player,
+                opened: window,
+            },
+        );
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        BlockKind::Beacon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::items::ItemStack;
+    use feather_core::position;
+    use feather_core::util::ChunkPosition;
+    use feather_server_types::StatusEffects;
+    use feather_test_framework::Test;
+
+    const POS: BlockPosition = BlockPosition { x: 0, y: 10, z: 0 };
+
+    /// Builds the given number of pyramid levels below `POS`.
+    fn build_pyramid(test: &mut Test, levels: i32) {
+        for level in 1..=levels {
+            for x in -level..=level {
+                for z in -level..=level {
+                    let pos = BlockPosition::new(POS.x + x, POS.y - level, POS.z + z);
+                    test.set_block(pos, BlockId::iron_block());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pyramid_and_sky_access() {
+        let mut test = Test::new();
+        assert_eq!(pyramid_levels(&test.game, WorldId::MAIN, POS), 0);
+
+        build_pyramid(&mut test, 2);
+        assert_eq!(pyramid_levels(&test.game, WorldId::MAIN, POS), 2);
+        // An incomplete level doesn't count.
+        test.set_block(BlockPosition::new(1, 9, 1), BlockId::stone());
+        assert_eq!(pyramid_levels(&test.game, WorldId::MAIN, POS), 0);
+
+        assert!(has_sky_access(&test.game, WorldId::MAIN, POS));
+        test.set_block(BlockPosition::new(0, 50, 0), BlockId::glass());
+        assert!(has_sky_access(&test.game, WorldId::MAIN, POS));
+        test.set_block(BlockPosition::new(0, 60, 0), BlockId::stone());
+        assert!(!has_sky_access(&test.game, WorldId::MAIN, POS));
+    }
+
+    #[test]
+    fn pyramid_and_sky_access_use_world_height() {
+        let mut test = Test::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                test.game.worlds[WorldId::MAIN]
+                    .chunk_map
+                    .chunk_at_mut(ChunkPosition::new(x, z))
+                    .unwrap()
+                    .resize(-64, 384);
+            }
+        }
+
+        // Pyramids may be built below Y 0, down to the bottom of the world.
+        let bottom = BlockPosition::new(0, -63, 0);
+        for x in -1..=1 {
+            for z in -1..=1 {
+                test.set_block(BlockPosition::new(x, -64, z), BlockId::iron_block());
+            }
+        }
+        assert_eq!(pyramid_levels(&test.game, WorldId::MAIN, bottom), 1);
+
+        // Blocks above Y 255 still stop the beam.
+        assert!(has_sky_access(&test.game, WorldId::MAIN, bottom));
+        test.set_block(BlockPosition::new(0, 300, 0), BlockId::stone());
+        assert!(!has_sky_access(&test.game, WorldId::MAIN, bottom));
+    }
+
+    #[test]
+    fn give_effects_in_range() {
+        let mut test = Test::new();
+        build_pyramid(&mut test, 1);
+        let beacon = Beacon {
+            levels: 0,
+            primary: Some(StatusEffectKind::Speed),
+            secondary: None,
+        };
+        let beacon = test.entity(create_with(POS, beacon));
+        let near = test.player("near", position!(5.0, 64.0, 5.0));
+        let far = test.player("far", position!(25.0, 10.0, 0.0));
+
+        test.game.tick_count = UPDATE_INTERVAL;
+        test.run(update_beacons);
+        assert_eq!(test.world.get::<Beacon>(beacon).levels, 1);
+
+        let effects = test.world.get::<StatusEffects>(near);
+        let speed = effects.get(StatusEffectKind::Speed).unwrap();
+        assert_eq!((speed.amplifier, speed.duration), (0, 220));
+        assert!(speed.ambient);
+        drop(effects);
+        assert!(!test.world.has::<StatusEffects>(far));
+
+        // Blocking the beam deactivates the beacon.
+        test.set_block(BlockPosition::new(0, 11, 0), BlockId::stone());
+        test.game.tick_count = 2 * UPDATE_INTERVAL;
+        test.run(update_beacons);
+        assert_eq!(test.world.get::<Beacon>(beacon).levels, 0);
+    }
+
+    #[test]
+    fn select_effects_with_payment() {
+        let mut test = Test::new();
+        let beacon = Beacon {
+            levels: 4,
+            primary: None,
+            secondary: None,
+        };
+        let beacon = test.entity(create_with(POS, beacon));
+        let window = test.entity(EntityBuilder::new().with(Inventory::beacon()).with(
+            BeaconWindow {
+                beacon,
+                window_id: 1,
+            },
+        ));
+
+        // Nothing is paid yet.
+        assert!(!select_effects(
+            &mut test.game,
+            &mut test.world,
+            window,
+            3,
+            3
+        ));
+
+        test.world
+            .get::<Inventory>(window)
+            .set_item_at(Area::BeaconPayment, 0, ItemStack::new(Item::Diamond, 2))
+            .unwrap();
+        // Regeneration can only be the secondary effect.
+        assert!(!select_effects(
+            &mut test.game,
+            &mut test.world,
+            window,
+            10,
+            0
+        ));
+        assert!(select_effects(
+            &mut test.game,
+            &mut test.world,
+            window,
+            3,
+            3
+        ));
+
+        let selected = *test.world.get::<Beacon>(beacon);
+        assert_eq!(selected.primary, Some(StatusEffectKind::Haste));
+        assert_eq!(selected.secondary, Some(StatusEffectKind::Haste));
+        assert_eq!(
+            test.world
+                .get::<Inventory>(window)
+                .item_at(Area::BeaconPayment, 0)
+                .unwrap(),
+            Some(ItemStack::new(Item::Diamond, 1))
+        );
+
+        let haste = effects(selected)[0];
+        assert_eq!((haste.kind, haste.amplifier), (StatusEffectKind::Haste, 1));
+    }
+}
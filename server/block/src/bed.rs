@@ -0,0 +1,386 @@
+//! Beds, which players sleep in to set their spawn point
+//! and to skip the night.
+//!
+//! Players can sleep at night or during thunderstorms if no
+//! monsters are nearby. Once the share of players given by the
+//! `playersSleepingPercentage` game rule has slept for a while,
+//! the night is skipped and the rain stops. Beds explode when
+//! used outside the overworld.
+//!
+//! See https://minecraft.gamepedia.com/Bed.
+
+use feather_core::blocks::{BlockId, BlockKind, Part, SimplifiedBlockKind};
+use feather_core::network::packets::UseBed;
+use feather_core::util::{vec3, BlockPosition, Dimension, Gamemode, Position};
+use feather_server_entity::{explode, BlockDamage, Monster};
+use feather_server_types::{
+    other_bed_half, send_status_message, BlockUpdateCause, BlockUpdateEvent, EntityDeathEvent,
+    Game, InteractionHandler, NetworkId, Player, PlayerLeaveEvent, Sleeping, SpawnPoint,
+    Teleported, Weather, WorldId, DAY_LENGTH,
+};
+use feather_server_util::broadcast_time;
+use fecs::{Entity, IntoQuery, Read, World, Write};
+
+/// Power of the explosion of a bed used outside the overworld.
+const EXPLOSION_POWER: f32 = 5.0;
+/// Greatest horizontal distance from which a player can use a bed.
+const MAX_HORIZONTAL_DISTANCE: f64 = 3.0;
+/// Greatest vertical distance from which a player can use a bed.
+const MAX_VERTICAL_DISTANCE: f64 = 2.0;
+/// Players can't sleep while a monster is within this
+/// distance from the bed on each axis.
+const MONSTER_RADIUS: (f64, f64, f64) = (8.0, 5.0, 8.0);
+/// Height above the bottom of the bed at which players lie.
+const SLEEPING_HEIGHT: f64 = 0.6875;
+
+struct BedInteraction(BlockKind);
+
+inventory::submit!(Box::new(BedInteraction(BlockKind::WhiteBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::OrangeBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::MagentaBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::LightBlueBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::YellowBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::LimeBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::PinkBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::GrayBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::LightGrayBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::CyanBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::PurpleBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::BlueBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::BrownBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::GreenBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::RedBed)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(BedInteraction(BlockKind::BlackBed)) as Box<dyn InteractionHandler>);
+
+impl InteractionHandler for BedInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        _window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        if let Err(key) = try_sleep(game, world, world_id, pos, player) {
+            send_status_message(world, player, key);
+        }
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        self.0
+    }
+}
+
+/// Puts a player to sleep in the bed at the given position,
+/// returning the message to show them if they can't sleep.
+fn try_sleep(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    player: Entity,
+) -> Result<(), &'static str> {
+    let head = match game.block_at(world_id, pos) {
+        Some(bed) if bed.part() == Some(Part::Foot) => match other_bed_half(bed, pos) {
+            Some(head) => head,
+            None => return Ok(()),
+        },
+        Some(bed) if bed.simplified_kind() == SimplifiedBlockKind::Bed => pos,
+        _ => return Ok(()),
+    };
+    let bed = match game.block_at(world_id, head) {
+        Some(bed) if bed.part() == Some(Part::Head) => bed,
+        _ => return Ok(()),
+    };
+
+    let overworld = game
+        .worlds
+        .get(world_id)
+        .map_or(false, |state| state.dimension == Dimension::Overwold);
+    if !overworld {
+        blow_up(game, world, world_id, head, bed);
+        return Ok(());
+    }
+
+    if bed.occupied() == Some(true) {
+        return Err("block.minecraft.bed.occupied");
+    }
+    if !game.is_sleeping_time(world_id) {
+        return Err("block.minecraft.bed.no_sleep");
+    }
+
+    let center = head.position() + vec3(0.5, 0.0, 0.5);
+    let player_pos = *world.get::<Position>(player);
+    if (player_pos.x - center.x).abs() > MAX_HORIZONTAL_DISTANCE
+        || (player_pos.y - center.y).abs() > MAX_VERTICAL_DISTANCE
+        || (player_pos.z - center.z).abs() > MAX_HORIZONTAL_DISTANCE
+    {
+        return Err("block.minecraft.bed.too_far_away");
+    }
+
+    let (x, y, z) = MONSTER_RADIUS;
+    let monster_nearby = <(Read<Monster>, Read<Position>, Read<WorldId>)>::query()
+        .iter(world.inner())
+        .any(|(_, pos, id)| {
+            *id == world_id
+                && (pos.x - center.x).abs() <= x
+                && (pos.y - center.y).abs() <= y
+                && (pos.z - center.z).abs() <= z
+        });
+    if monster_nearby {
+        return Err("block.minecraft.bed.not_safe");
+    }
+
+    sleep(game, world, world_id, head, player);
+    Ok(())
+}
+
+/// Removes a bed and causes an explosion in its place.
+fn blow_up(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    head: BlockPosition,
+    bed: BlockId,
+) {
+    let halves = std::iter::once(head).chain(other_bed_half(bed, head));
+    for pos in halves {
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            BlockId::air(),
+            BlockUpdateCause::Explosion,
+        );
+    }
+
+    let center = head.position() + vec3(0.5, 0.5, 0.5);
+    explode(
+        game,
+        world,
+        world_id,
+        center,
+        EXPLOSION_POWER,
+        BlockDamage::BreakAndBurn,
+        None,
+    );
+}
+
+/// Puts a player to sleep in the bed whose head is at the
+/// given position, which becomes their spawn point.
+fn sleep(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    head: BlockPosition,
+    player: Entity,
+) {
+    world.add(player, Sleeping::new(head)).unwrap();
+    if world.has::<SpawnPoint>(player) {
+        *world.get_mut::<SpawnPoint>(player) = SpawnPoint(head);
+    } else {
+        world.add(player, SpawnPoint(head)).unwrap();
+    }
+    game.set_bed_occupied(world, world_id, head, true);
+
+    let old = *world.get::<Position>(player);
+    *world.get_mut::<Position>(player) = Position {
+        yaw: old.yaw,
+        pitch: old.pitch,
+        ..head.position() + vec3(0.5, SLEEPING_HEIGHT, 0.5)
+    };
+    world.add(player, Teleported).unwrap();
+
+    let packet = UseBed {
+        entity_id: world.get::<NetworkId>(player).0,
+        location: head,
+    };
+    game.broadcast_entity_update(world, packet, player, None);
+}
+
+/// System which skips the night once enough players in the
+/// overworld have slept. Players are woken once they may
+/// no longer sleep, e.g. when the night is over.
+#[fecs::system]
+pub fn skip_night(game: &mut Game, world: &mut World) {
+    let mut sleepers = Vec::new();
+    for (player, (mut sleeping, world_id)) in
+        <(Write<Sleeping>, Read<WorldId>)>::query().iter_entities_mut(world.inner_mut())
+    {
+        sleeping.ticks += 1;
+        sleepers.push((player, *world_id, sleeping.is_deeply_asleep()));
+    }
+    if sleepers.is_empty() {
+        return;
+    }
+
+    let mut awake = Vec::new();
+    for &(player, world_id, _) in &sleepers {
+        if !game.is_sleeping_time(world_id) {
+            awake.push(player);
+        }
+    }
+    for player in awake {
+        game.wake_up(world, player);
+    }
+
+    let percentage = game.game_rules.players_sleeping_percentage;
+    let enough_asleep = game
+        .worlds
+        .iter()
+        .filter(|state| state.dimension == Dimension::Overwold)
+        .any(|state| {
+            let players = <(Read<Player>, Read<WorldId>, Read<Gamemode>)>::query()
+                .iter(world.inner())
+                .filter(|(_, id, gamemode)| **id == state.id && **gamemode != Gamemode::Spectator)
+                .count() as u32;
+            let asleep = sleepers
+                .iter()
+                .filter(|(player, world_id, deeply)| {
+                    *world_id == state.id && *deeply && world.has::<Sleeping>(*player)
+                })
+                .count() as u32;
+            let needed = ((players * percentage + 99) / 100).max(1);
+            asleep >= needed
+        });
+    if !enough_asleep {
+        return;
+    }
+
+    if game.game_rules.do_daylight_cycle {
+        for state in game.worlds.iter_mut() {
+            state.time.day_time = (state.time.day() + 1) * DAY_LENGTH;
+        }
+        broadcast_time(game, world);
+    }
+    if game.game_rules.do_weather_cycle && game.weather() != Weather::Clear {
+        game.set_weather(world, Weather::Clear, 0);
+    }
+
+    let sleepers: Vec<Entity> = <Read<Sleeping>>::query()
+        .iter_entities(world.inner())
+        .map(|(player, _)| player)
+        .collect();
+    for player in sleepers {
+        game.wake_up(world, player);
+    }
+}
+
+/// Gets a player out of their bed when they die.
+#[fecs::event_handler]
+pub fn on_entity_death_wake_up(event: &EntityDeathEvent, game: &mut Game, world: &mut World) {
+    game.wake_up(world, event.entity);
+}
+
+/// Frees the bed of a player who leaves while asleep.
+#[fecs::event_handler]
+pub fn on_player_leave_wake_up(event: &PlayerLeaveEvent, game: &mut Game, world: &mut World) {
+    game.wake_up(world, event.player);
+}
+
+/// Wakes the players sleeping in a bed which was broken.
+#[fecs::event_handler]
+pub fn on_bed_break_wake_up(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
+    if event.old.simplified_kind() != SimplifiedBlockKind::Bed
+        || event.new.kind() == event.old.kind()
+    {
+        return;
+    }
+    let head = match event.old.part() {
+        Some(Part::Head) => event.pos,
+        _ => match other_bed_half(event.old, event.pos) {
+            Some(head) => head,
+            None => return,
+        },
+    };
+
+    let sleepers: Vec<Entity> = <(Read<Sleeping>, Read<WorldId>)>::query()
+        .iter_entities(world.inner())
+        .filter(|(_, (sleeping, world_id))| sleeping.bed == head && **world_id == event.world)
+        .map(|(player, _)| player)
+        .collect();
+    for player in sleepers {
+        game.wake_up(world, player);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::FacingCardinal;
+    use feather_core::position;
+    use feather_test_framework::Test;
+
+    fn place_bed(test: &mut Test, foot: BlockPosition) -> BlockPosition {
+        let head = foot + BlockPosition::new(0, 0, 1);
+        for (pos, part) in &[(foot, Part::Foot), (head, Part::Head)] {
+            let bed = BlockId::red_bed()
+                .with_facing_cardinal(FacingCardinal::South)
+                .with_part(*part);
+            test.game.set_block_at(
+                &mut test.world,
+                WorldId::MAIN,
+                *pos,
+                bed,
+                BlockUpdateCause::Unknown,
+            );
+        }
+        head
+    }
+
+    fn night(test: &mut Test) {
+        test.game.worlds[WorldId::MAIN].time.day_time = 18_000;
+    }
+
+    #[test]
+    fn sleeping_skips_the_night() {
+        let mut test = Test::new();
+        night(&mut test);
+        let foot = BlockPosition::new(0, 64, 0);
+        let head = place_bed(&mut test, foot);
+        let player = test.player("player", position!(0.5, 64.0, 2.5));
+
+        BedInteraction(BlockKind::RedBed).handle_interaction(
+            &mut test.game,
+            &mut test.world,
+            foot,
+            player,
+            0,
+        );
+        assert_eq!(*test.world.get::<SpawnPoint>(player), SpawnPoint(head));
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, foot).unwrap().occupied(),
+            Some(true)
+        );
+        assert!(test.sent::<UseBed>(player).is_some());
+
+        for _ in 0..=Sleeping::DEEP_SLEEP_TICKS {
+            test.run(skip_night);
+        }
+        assert_eq!(test.game.worlds[WorldId::MAIN].time.time_of_day(), 0);
+        assert!(!test.world.has::<Sleeping>(player));
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, head).unwrap().occupied(),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn cannot_sleep_during_the_day() {
+        let mut test = Test::new();
+        let foot = BlockPosition::new(0, 64, 0);
+        place_bed(&mut test, foot);
+        let player = test.player("player", position!(0.5, 64.0, 2.5));
+
+        BedInteraction(BlockKind::RedBed).handle_interaction(
+            &mut test.game,
+            &mut test.world,
+            foot,
+            player,
+            0,
+        );
+        assert!(!test.world.has::<Sleeping>(player));
+        assert!(!test.world.has::<SpawnPoint>(player));
+    }
+}
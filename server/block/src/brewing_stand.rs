@@ -0,0 +1,677 @@
+//! Brewing stands, which brew potions from water bottles
+//! and ingredients using blaze powder as fuel.
+
+use crate::{Layout, ShouldReplace, WindowViewers};
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::blocks::BlockKind;
+use feather_core::inventory::{Area, SlotIndex, Window};
+use feather_core::items::{Item, ItemStack, Potion};
+use feather_core::network::packets::OpenWindow;
+use feather_core::text::TextRoot;
+use feather_core::util::BlockPosition;
+use feather_server_types::{
+    crafting_remainder, BlockEntityLoaderRegistration, BlockSerializer, BlockUpdateCause, Game,
+    InteractionHandler, Inventory, InventoryUpdateEvent,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World, Write};
+
+/// The bottle slots, numbered 0 to 2,
+/// followed by the ingredient and fuel slots.
+const LAYOUT: Layout = &[
+    (Area::BrewingBottle, 3),
+    (Area::BrewingIngredient, 1),
+    (Area::BrewingFuel, 1),
+];
+
+/// Ticks needed to brew potions.
+const BREW_TIME: i16 = 400;
+/// Number of brews a blaze powder fuels.
+const BLAZE_POWDER_FUEL: i8 = 20;
+
+// Window properties of brewing stands.
+const PROPERTY_BREW_TIME: i16 = 0;
+const PROPERTY_FUEL: i16 = 1;
+
+/// Ingredients which turn the potion of a bottle into another.
+/// Modifiers apply to potions in any kind of bottle.
+const POTION_MIXES: &[(Potion, Item, Potion)] = &[
+    (Potion::Water, Item::NetherWart, Potion::Awkward),
+    (Potion::Water, Item::GlowstoneDust, Potion::Thick),
+    (Potion::Water, Item::Redstone, Potion::Mundane),
+    (Potion::Water, Item::GlisteringMelonSlice, Potion::Mundane),
+    (Potion::Water, Item::GhastTear, Potion::Mundane),
+    (Potion::Water, Item::RabbitFoot, Potion::Mundane),
+    (Potion::Water, Item::BlazePowder, Potion::Mundane),
+    (Potion::Water, Item::SpiderEye, Potion::Mundane),
+    (Potion::Water, Item::Sugar, Potion::Mundane),
+    (Potion::Water, Item::MagmaCream, Potion::Mundane),
+    (Potion::Water, Item::FermentedSpiderEye, Potion::Weakness),
+    (Potion::Awkward, Item::GoldenCarrot, Potion::NightVision),
+    (Potion::NightVision, Item::Redstone, Potion::LongNightVision),
+    (
+        Potion::NightVision,
+        Item::FermentedSpiderEye,
+        Potion::Invisibility,
+    ),
+    (
+        Potion::LongNightVision,
+        Item::FermentedSpiderEye,
+        Potion::LongInvisibility,
+    ),
+    (
+        Potion::Invisibility,
+        Item::Redstone,
+        Potion::LongInvisibility,
+    ),
+    (Potion::Awkward, Item::MagmaCream, Potion::FireResistance),
+    (
+        Potion::FireResistance,
+        Item::Redstone,
+        Potion::LongFireResistance,
+    ),
+    (Potion::Awkward, Item::RabbitFoot, Potion::Leaping),
+    (Potion::Leaping, Item::Redstone, Potion::LongLeaping),
+    (Potion::Leaping, Item::GlowstoneDust, Potion::StrongLeaping),
+    (Potion::Leaping, Item::FermentedSpiderEye, Potion::Slowness),
+    (
+        Potion::LongLeaping,
+        Item::FermentedSpiderEye,
+        Potion::LongSlowness,
+    ),
+    (Potion::Slowness, Item::Redstone, Potion::LongSlowness),
+    (
+        Potion::Swiftness,
+        Item::FermentedSpiderEye,
+        Potion::Slowness,
+    ),
+    (
+        Potion::LongSwiftness,
+        Item::FermentedSpiderEye,
+        Potion::LongSlowness,
+    ),
+    (Potion::Awkward, Item::TurtleHelmet, Potion::TurtleMaster),
+    (
+        Potion::TurtleMaster,
+        Item::Redstone,
+        Potion::LongTurtleMaster,
+    ),
+    (
+        Potion::TurtleMaster,
+        Item::GlowstoneDust,
+        Potion::StrongTurtleMaster,
+    ),
+    (Potion::Awkward, Item::Sugar, Potion::Swiftness),
+    (Potion::Swiftness, Item::Redstone, Potion::LongSwiftness),
+    (
+        Potion::Swiftness,
+        Item::GlowstoneDust,
+        Potion::StrongSwiftness,
+    ),
+    (Potion::Awkward, Item::Pufferfish, Potion::WaterBreathing),
+    (
+        Potion::WaterBreathing,
+        Item::Redstone,
+        Potion::LongWaterBreathing,
+    ),
+    (Potion::Awkward, Item::GlisteringMelonSlice, Potion::Healing),
+    (Potion::Healing, Item::GlowstoneDust, Potion::StrongHealing),
+    (Potion::Healing, Item::FermentedSpiderEye, Potion::Harming),
+    (
+        Potion::StrongHealing,
+        Item::FermentedSpiderEye,
+        Potion::StrongHarming,
+    ),
+    (Potion::Harming, Item::GlowstoneDust, Potion::StrongHarming),
+    (Potion::Poison, Item::FermentedSpiderEye, Potion::Harming),
+    (
+        Potion::LongPoison,
+        Item::FermentedSpiderEye,
+        Potion::Harming,
+    ),
+    (
+        Potion::StrongPoison,
+        Item::FermentedSpiderEye,
+        Potion::StrongHarming,
+    ),
+    (Potion::Awkward, Item::SpiderEye, Potion::Poison),
+    (Potion::Poison, Item::Redstone, Potion::LongPoison),
+    (Potion::Poison, Item::GlowstoneDust, Potion::StrongPoison),
+    (Potion::Awkward, Item::GhastTear, Potion::Regeneration),
+    (
+        Potion::Regeneration,
+        Item::Redstone,
+        Potion::LongRegeneration,
+    ),
+    (
+        Potion::Regeneration,
+        Item::GlowstoneDust,
+        Potion::StrongRegeneration,
+    ),
+    (Potion::Awkward, Item::BlazePowder, Potion::Strength),
+    (Potion::Strength, Item::Redstone, Potion::LongStrength),
+    (
+        Potion::Strength,
+        Item::GlowstoneDust,
+        Potion::StrongStrength,
+    ),
+    (Potion::Weakness, Item::Redstone, Potion::LongWeakness),
+    (Potion::Awkward, Item::PhantomMembrane, Potion::SlowFalling),
+    (Potion::SlowFalling, Item::Redstone, Potion::LongSlowFalling),
+];
+
+/// Ingredients which turn a kind of bottle into another,
+/// keeping its potion.
+const CONTAINER_MIXES: &[(Item, Item, Item)] = &[
+    (Item::Potion, Item::Gunpowder, Item::SplashPotion),
+    (
+        Item::SplashPotion,
+        Item::DragonBreath,
+        Item::LingeringPotion,
+    ),
+];
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::BrewingStand,
+});
+
+/// Marker component for brewing stands.
+pub struct BrewingStand;
+
+/// The brewing progress and fuel of a brewing stand.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BrewingProgress {
+    /// Ticks until the potions being brewed are done,
+    /// or 0 if none are.
+    pub brew_time: i16,
+    /// Number of brews left from the last blaze powder.
+    pub fuel: i8,
+}
+
+/// Creates a brewing stand.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with(pos, Inventory::brewing_stand(), BrewingProgress::default())
+}
+
+/// Creates a brewing stand with the given inventory and progress.
+pub fn create_with(
+    pos: BlockPosition,
+    inventory: Inventory,
+    progress: BrewingProgress,
+) -> EntityBuilder {
+    crate::base(pos)
+        .with(BrewingStand)
+        .with(inventory)
+        .with(progress)
+        .with(WindowViewers::default())
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    let base = crate::serialize_base(accessor);
+    let items = crate::serialize_inventory(&*accessor.get::<Inventory>(), LAYOUT);
+    let progress = *accessor.get::<BrewingProgress>();
+
+    BlockEntityData {
+        base,
+        kind: BlockEntityKind::BrewingStand {
+            items,
+            brew_time: progress.brew_time,
+            fuel: progress.fuel,
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let (slots, progress) = match data.kind {
+        BlockEntityKind::BrewingStand {
+            items,
+            brew_time,
+            fuel,
+        } => (items, BrewingProgress { brew_time, fuel }),
+        _ => bail!("not a brewing stand"),
+    };
+
+    let inventory = Inventory::brewing_stand();
+    crate::load_inventory(&inventory, LAYOUT, &slots);
+
+    Ok(create_with(pos, inventory, progress))
+}
+
+/// Returns the bottle brewing `ingredient` into `bottle` produces,
+/// or `None` if the ingredient can't be brewed into it.
+///
+/// Potions without a `Potion` tag are uncraftable
+/// and can't be brewed into anything.
+pub fn brew(bottle: ItemStack, ingredient: Item) -> Option<ItemStack> {
+    if !is_bottle(bottle.ty) {
+        return None;
+    }
+
+    let container = CONTAINER_MIXES
+        .iter()
+        .find(|(from, with, _)| *from == bottle.ty && *with == ingredient);
+    if let Some((_, _, to)) = container {
+        let mut result = bottle;
+        result.ty = *to;
+        return Some(result);
+    }
+
+    let potion = bottle.potion?;
+    let (_, _, to) = POTION_MIXES
+        .iter()
+        .find(|(from, with, _)| *from == potion && *with == ingredient)?;
+    let mut result = bottle;
+    result.potion = Some(*to);
+    Some(result)
+}
+
+/// Returns whether an item is a bottle which
+/// can be put in a brewing stand.
+fn is_bottle(item: Item) -> bool {
+    matches!(
+        item,
+        Item::Potion | Item::SplashPotion | Item::LingeringPotion
+    )
+}
+
+/// Returns whether the ingredient of a brewing stand
+/// can be brewed into any of its bottles.
+fn can_brew(inventory: &Inventory) -> bool {
+    let ingredient = match inventory.item_at(Area::BrewingIngredient, 0).unwrap() {
+        Some(ingredient) => ingredient,
+        None => return false,
+    };
+    (0..3).any(|slot| {
+        inventory
+            .item_at(Area::BrewingBottle, slot)
+            .unwrap()
+            .and_then(|bottle| brew(bottle, ingredient.ty))
+            .is_some()
+    })
+}
+
+/// Advances the brewing in a brewing stand by a tick, refueling
+/// it with blaze powder when it runs out of fuel and using up
+/// fuel to start brewing.
+///
+/// Returns the slots whose items changed.
+fn tick(progress: &mut BrewingProgress, inventory: &Inventory) -> Vec<SlotIndex> {
+    let mut slots = vec![];
+
+    if progress.fuel <= 0 {
+        let fuel = inventory.item_at(Area::BrewingFuel, 0).unwrap();
+        if let Some(fuel) = fuel.filter(|fuel| fuel.ty == Item::BlazePowder) {
+            progress.fuel = BLAZE_POWDER_FUEL;
+            let remaining = fuel.of_amount(fuel.amount - 1);
+            inventory
+                .set_item_at(Area::BrewingFuel, 0, remaining)
+                .unwrap();
+            slots.push(slot(Area::BrewingFuel, 0));
+        }
+    }
+
+    let brewable = can_brew(inventory);
+    if progress.brew_time > 0 {
+        progress.brew_time -= 1;
+        if !brewable {
+            // Brewing stops when the ingredient or bottles are taken out.
+            progress.brew_time = 0;
+        } else if progress.brew_time == 0 {
+            finish_brewing(inventory, &mut slots);
+        }
+    } else if brewable && progress.fuel > 0 {
+        progress.fuel -= 1;
+        progress.brew_time = BREW_TIME;
+    }
+
+    slots
+}
+
+/// Brews the ingredient of a brewing stand into its bottles,
+/// using up one ingredient item.
+fn finish_brewing(inventory: &Inventory, slots: &mut Vec<SlotIndex>) {
+    let ingredient = inventory
+        .item_at(Area::BrewingIngredient, 0)
+        .unwrap()
+        .unwrap();
+
+    for index in 0..3 {
+        let result = inventory
+            .item_at(Area::BrewingBottle, index)
+            .unwrap()
+            .and_then(|bottle| brew(bottle, ingredient.ty));
+        if let Some(result) = result {
+            inventory
+                .set_item_at(Area::BrewingBottle, index, result)
+                .unwrap();
+            slots.push(slot(Area::BrewingBottle, index));
+        }
+    }
+
+    let remaining = match crafting_remainder(ingredient.ty) {
+        Some(remainder) if ingredient.amount == 1 => ItemStack::new(remainder, 1),
+        _ => ingredient.of_amount(ingredient.amount - 1),
+    };
+    inventory
+        .set_item_at(Area::BrewingIngredient, 0, remaining)
+        .unwrap();
+    slots.push(slot(Area::BrewingIngredient, 0));
+}
+
+fn slot(area: Area, slot: usize) -> SlotIndex {
+    SlotIndex { area, slot }
+}
+
+/// System which brews potions in brewing stands, updating
+/// the bottles shown on them and sending their progress to
+/// the players who have them open.
+#[fecs::system]
+pub fn tick_brewing_stands(game: &mut Game, world: &mut World) {
+    let mut updates = vec![];
+    for (brewing_stand, (_, mut progress, inventory)) in
+        <(Read<BrewingStand>, Write<BrewingProgress>, Read<Inventory>)>::query()
+            .iter_entities_mut(world.inner_mut())
+    {
+        let old = *progress;
+        let slots = tick(&mut progress, &inventory);
+
+        if *progress != old || !slots.is_empty() {
+            updates.push((brewing_stand, old, *progress, slots));
+        }
+    }
+
+    for (brewing_stand, old, new, slots) in updates {
+        crate::send_window_updates(
+            world,
+            brewing_stand,
+            LAYOUT,
+            &slots,
+            &changed_properties(Some(old), new).collect::<Vec<_>>(),
+        );
+
+        if !slots.is_empty() {
+            game.handle(
+                world,
+                InventoryUpdateEvent {
+                    slots: slots.into_iter().collect(),
+                    entity: brewing_stand,
+                },
+            );
+        }
+    }
+}
+
+/// When the bottles in a brewing stand change, updates
+/// the bottles shown on its block.
+#[fecs::event_handler]
+pub fn on_inventory_update_update_brewing_stand_bottles(
+    event: &InventoryUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<BrewingStand>(event.entity)
+        || !event
+            .slots
+            .iter()
+            .any(|slot| slot.area == Area::BrewingBottle)
+    {
+        return;
+    }
+
+    let bottles = {
+        let inventory = world.get::<Inventory>(event.entity);
+        let bottle = |slot| {
+            inventory
+                .item_at(Area::BrewingBottle, slot)
+                .unwrap()
+                .is_some()
+        };
+        [bottle(0), bottle(1), bottle(2)]
+    };
+
+    let world_id = game.world_of(world, event.entity);
+    let pos = *world.get::<BlockPosition>(event.entity);
+    let block = match game.block_at(world_id, pos) {
+        Some(block) if block.kind() == BlockKind::BrewingStand => block,
+        _ => return,
+    };
+    let updated = block
+        .with_has_bottle_0(bottles[0])
+        .with_has_bottle_1(bottles[1])
+        .with_has_bottle_2(bottles[2]);
+    if updated != block {
+        game.set_block_at(world, world_id, pos, updated, BlockUpdateCause::Tick);
+    }
+}
+
+/// Returns the window properties which differ from
+/// `old`, or all properties if it is `None`.
+fn changed_properties(
+    old: Option<BrewingProgress>,
+    new: BrewingProgress,
+) -> impl Iterator<Item = (i16, i16)> {
+    let properties = |progress: BrewingProgress| {
+        [
+            (PROPERTY_BREW_TIME, progress.brew_time),
+            (PROPERTY_FUEL, i16::from(progress.fuel)),
+        ]
+    };
+    let old = old.map(properties);
+
+    (0..2).filter_map(move |index| {
+        let property = properties(new)[index];
+        match old {
+            Some(old) if old[index] == property => None,
+            _ => Some(property),
+        }
+    })
+}
+
+/// Handler for player right clicking on brewing stands.
+pub struct BrewingStandInteraction;
+inventory::submit!(Box::new(BrewingStandInteraction) as Box<dyn InteractionHandler>);
+
+impl InteractionHandler for BrewingStandInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let brewing_stand = match game.worlds[world_id].block_entities.get(&pos).copied() {
+            Some(brewing_stand) if world.has::<BrewingStand>(brewing_stand) => brewing_stand,
+            _ => return,
+        };
+
+        let progress = *world.get::<BrewingProgress>(brewing_stand);
+        crate::open_window(
+            game,
+            world,
+            player,
+            brewing_stand,
+            Window::brewing_stand(player, brewing_stand),
+            OpenWindow {
+                window_id,
+                window_type: String::from("minecraft:brewing_stand"),
+                window_title: TextRoot::from("Brewing Stand").into(),
+                number_of_slots: 5,
+                entity_id: None,
+            },
+            LAYOUT,
+        );
+        crate::send_window_updates(
+            world,
+            brewing_stand,
+            LAYOUT,
+            &[],
+            &changed_properties(None, progress).collect::<Vec<_>>(),
+        );
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        BlockKind::BrewingStand
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn potion(ty: Item, potion: Potion) -> ItemStack {
+        let mut stack = ItemStack::new(ty, 1);
+        stack.potion = Some(potion);
+        stack
+    }
+
+    #[test]
+    fn brew_mixes() {
+        let water = potion(Item::Potion, Potion::Water);
+        assert_eq!(
+            brew(water, Item::NetherWart),
+            Some(potion(Item::Potion, Potion::Awkward))
+        );
+        assert_eq!(
+            brew(water, Item::Sugar),
+            Some(potion(Item::Potion, Potion::Mundane))
+        );
+        assert_eq!(brew(water, Item::GoldenCarrot), None);
+
+        let awkward = potion(Item::SplashPotion, Potion::Awkward);
+        assert_eq!(
+            brew(awkward, Item::GoldenCarrot),
+            Some(potion(Item::SplashPotion, Potion::NightVision))
+        );
+        assert_eq!(
+            brew(
+                potion(Item::Potion, Potion::Swiftness),
+                Item::FermentedSpiderEye
+            ),
+            Some(potion(Item::Potion, Potion::Slowness))
+        );
+
+        assert_eq!(
+            brew(water, Item::Gunpowder),
+            Some(potion(Item::SplashPotion, Potion::Water))
+        );
+        assert_eq!(
+            brew(awkward, Item::DragonBreath),
+            Some(potion(Item::LingeringPotion, Potion::Awkward))
+        );
+
+        assert_eq!(
+            brew(ItemStack::new(Item::Potion, 1), Item::NetherWart),
+            None
+        );
+        assert_eq!(brew(ItemStack::new(Item::Stone, 1), Item::NetherWart), None);
+    }
+
+    #[test]
+    fn brew_with_fuel() {
+        let inventory = Inventory::brewing_stand();
+        let mut progress = BrewingProgress::default();
+        inventory
+            .set_item_at(Area::BrewingBottle, 0, potion(Item::Potion, Potion::Water))
+            .unwrap();
+        inventory
+            .set_item_at(Area::BrewingBottle, 2, ItemStack::new(Item::Stone, 1))
+            .unwrap();
+        inventory
+            .set_item_at(
+                Area::BrewingIngredient,
+                0,
+                ItemStack::new(Item::NetherWart, 2),
+            )
+            .unwrap();
+
+        // Nothing happens without fuel.
+        assert!(tick(&mut progress, &inventory).is_empty());
+        assert_eq!(progress, BrewingProgress::default());
+
+        inventory
+            .set_item_at(Area::BrewingFuel, 0, ItemStack::new(Item::BlazePowder, 1))
+            .unwrap();
+        assert_eq!(
+            tick(&mut progress, &inventory),
+            vec![slot(Area::BrewingFuel, 0)]
+        );
+        assert_eq!(
+            progress,
+            BrewingProgress {
+                brew_time: BREW_TIME,
+                fuel: BLAZE_POWDER_FUEL - 1,
+            }
+        );
+        assert_eq!(inventory.item_at(Area::BrewingFuel, 0).unwrap(), None);
+
+        for _ in 1..BREW_TIME {
+            assert!(tick(&mut progress, &inventory).is_empty());
+        }
+        assert_eq!(
+            tick(&mut progress, &inventory),
+            vec![
+                slot(Area::BrewingBottle, 0),
+                slot(Area::BrewingIngredient, 0)
+            ]
+        );
+        assert_eq!(progress.brew_time, 0);
+        assert_eq!(
+            inventory.item_at(Area::BrewingBottle, 0).unwrap(),
+            Some(potion(Item::Potion, Potion::Awkward))
+        );
+        assert_eq!(
+            inventory.item_at(Area::BrewingBottle, 2).unwrap(),
+            Some(ItemStack::new(Item::Stone, 1))
+        );
+        assert_eq!(
+            inventory.item_at(Area::BrewingIngredient, 0).unwrap(),
+            Some(ItemStack::new(Item::NetherWart, 1))
+        );
+
+        // Awkward potions can't be brewed with nether wart.
+        assert!(tick(&mut progress, &inventory).is_empty());
+        assert_eq!(progress.brew_time, 0);
+    }
+
+    #[test]
+    fn stop_brewing_without_ingredient() {
+        let inventory = Inventory::brewing_stand();
+        let mut progress = BrewingProgress {
+            brew_time: 0,
+            fuel: 3,
+        };
+        inventory
+            .set_item_at(
+                Area::BrewingBottle,
+                1,
+                potion(Item::Potion, Potion::Awkward),
+            )
+            .unwrap();
+        inventory
+            .set_item_at(Area::BrewingIngredient, 0, ItemStack::new(Item::Sugar, 1))
+            .unwrap();
+
+        tick(&mut progress, &inventory);
+        tick(&mut progress, &inventory);
+        assert_eq!(
+            progress,
+            BrewingProgress {
+                brew_time: BREW_TIME - 1,
+                fuel: 2,
+            }
+        );
+
+        inventory
+            .remove_item_at(Area::BrewingIngredient, 0)
+            .unwrap();
+        tick(&mut progress, &inventory);
+        assert_eq!(progress.brew_time, 0);
+        assert_eq!(progress.fuel, 2);
+    }
+}
@@ -1,32 +1,30 @@
-use crate::ShouldReplace;
+use crate::container::{viewers_packet, ContainerViewers, Lid};
+use crate::{Layout, ShouldReplace};
 use anyhow::bail;
 use arrayvec::ArrayVec;
-use feather_core::util::{BlockPosition, Position};
+use feather_core::util::BlockPosition;
 use feather_core::{
-    anvil::{
-        block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant},
-        player::InventorySlot,
-    },
+    anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant},
     blocks::{BlockId, BlockKind, ChestKind, FacingCardinal},
     inventory::{Area, Window},
-    items::{Item, ItemStack},
+    items::ItemStack,
     network::{
-        packets::{BlockAction, OpenWindow, WindowItems},
+        packets::{OpenWindow, WindowItems},
         Packet,
     },
     text::TextRoot,
 };
-use feather_server_entity::drops::drop_item;
 use feather_server_types::{
-    BlockEntityLoaderRegistration, BlockSerializer, BlockUpdateCause, BlockUpdateEvent, BumpVec,
-    EntityDespawnEvent, Game, InteractionHandler, Inventory, Network, SpawnPacketCreator,
-    WindowCloseEvent, WindowOpenEvent,
+    BlockEntityLoaderRegistration, BlockSerializer, BlockUpdateCause, BlockUpdateEvent, Game,
+    InteractionHandler, Inventory, Network, SpawnPacketCreator, WindowOpenEvent, WorldId,
+    WorldState,
 };
 use fecs::{Entity, EntityBuilder, EntityRef, World};
-use num_traits::ToPrimitive;
 
 pub const SLOTS: usize = 27;
 
+const LAYOUT: Layout = &[(Area::Chest, SLOTS)];
+
 inventory::submit!(BlockEntityLoaderRegistration {
     f: &load,
     kind: BlockEntityVariant::Chest,
@@ -35,11 +33,6 @@ inventory::submit!(BlockEntityLoaderRegistration {
 /// Marker component for chests.
 pub struct Chest;
 
-/// Stores number of players currently viewing a chest.
-/// ("Viewing" seems to mean "has chest open," though the documentation is somewhat vague.)
-/// This value is used on the client to render lid animations.
-pub struct ChestViewers(u32);
-
 /// Creates a chest.
 pub fn create(pos: BlockPosition) -> EntityBuilder {
     create_with_inventory(pos, Inventory::chest())
@@ -49,7 +42,8 @@ pub fn create(pos: BlockPosition) -> EntityBuilder {
 pub fn create_with_inventory(pos: BlockPosition, inventory: Inventory) -> EntityBuilder {
     crate::base(pos)
         .with(Chest)
-        .with(ChestViewers(0))
+        .with(ContainerViewers::default())
+        .with(Lid::Chest)
         .with(inventory)
         .with(SpawnPacketCreator(&create_spawn_packet))
         .with(BlockSerializer(&serialize))
@@ -60,31 +54,6 @@ fn should_replace(_old: BlockId, new: BlockId) -> bool {
     new.kind() != BlockKind::Chest
 }
 
-/// When a chest is despawned, drops its contents.
-#[fecs::event_handler]
-pub fn on_chest_break_drop_contents(
-    event: &EntityDespawnEvent,
-    game: &mut Game,
-    world: &mut World,
-) {
-    let entity = event.entity;
-    if !world.has::<Chest>(entity) {
-        return;
-    }
-
-    let items = BumpVec::from_iter_in(
-        world
-            .get::<Inventory>(entity)
-            .iter_mut()
-            .filter_map(|mut guard| guard.take()),
-        game.bump(),
-    );
-    let pos = *world.get::<Position>(entity);
-    for item in items {
-        drop_item(game, world, item, pos);
-    }
-}
-
 #[fecs::event_handler]
 pub fn on_chest_create_try_connect(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
     if event.new.kind() != BlockKind::Chest {
@@ -95,7 +64,7 @@ pub fn on_chest_create_try_connect(event: &BlockUpdateEvent, game: &mut Game, wo
         return;
     }
 
-    try_connect_chests(game, world, event.pos);
+    try_connect_chests(game, world, event.world, event.pos);
 }
 
 /// When a chest is broken and it is connected with another chest,
@@ -121,65 +90,33 @@ pub fn on_chest_break_try_disconnect(event: &BlockUpdateEvent, game: &mut Game,
         };
         debug_assert!(to_update != event.pos);
 
-        let old_block = game.block_at(to_update);
+        let old_block = game.block_at(event.world, to_update);
         if let Some(old_block) = old_block {
             let new_block = old_block.with_chest_kind(ChestKind::Single);
 
-            game.set_block_at(world, to_update, new_block, BlockUpdateCause::Unknown);
+            game.set_block_at(
+                world,
+                event.world,
+                to_update,
+                new_block,
+                BlockUpdateCause::Unknown,
+            );
         }
     }
 }
 
 fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
-    Box::new(viewers_packet(accessor))
-}
-
-#[fecs::event_handler]
-pub fn on_chest_open_increment_viewers(event: &WindowOpenEvent, game: &Game, world: &mut World) {
-    let should_resend = if let Some(mut viewers) = world.try_get_mut::<ChestViewers>(event.opened) {
-        viewers.0 += 1;
-        true
-    } else {
-        false
-    };
-
-    if should_resend {
-        resend_viewers(game, world, event.opened);
-    }
-}
-
-#[fecs::event_handler]
-pub fn on_chest_close_decrement_viewers(event: &WindowCloseEvent, game: &Game, world: &mut World) {
-    let should_resend = if let Some(mut viewers) = world.try_get_mut::<ChestViewers>(event.closed) {
-        viewers.0 = viewers.0.checked_sub(1).unwrap_or_default();
-        true
-    } else {
-        false
-    };
-
-    if should_resend {
-        resend_viewers(game, world, event.closed);
-    }
-}
-
-fn resend_viewers(game: &Game, world: &World, chest: Entity) {
-    let packet = viewers_packet(&world.entity(chest).unwrap());
-    game.broadcast_entity_update(world, packet, chest, None);
-}
-
-fn viewers_packet(chest: &EntityRef) -> impl Packet {
-    BlockAction {
-        location: *chest.get::<BlockPosition>(),
-        action_id: 1,
-        action_param: chest.get::<ChestViewers>().0 as u8,
-        block_type: BlockKind::Chest.to_i32().unwrap(),
-    }
+    Box::new(viewers_packet(
+        *accessor.get::<BlockPosition>(),
+        BlockKind::Chest,
+        accessor.get::<ContainerViewers>().0,
+    ))
 }
 
 fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
     let base = crate::serialize_base(accessor);
 
-    let items = serialize_items(&*accessor.get::<Inventory>());
+    let items = crate::serialize_inventory(&*accessor.get::<Inventory>(), LAYOUT);
 
     BlockEntityData {
         base,
@@ -191,18 +128,6 @@ fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
     }
 }
 
-fn serialize_items(inventory: &Inventory) -> Vec<InventorySlot> {
-    let mut slots = Vec::new();
-    for i in 0..27 {
-        let item = inventory.item_at(Area::Chest, i).unwrap();
-
-        if let Some(item) = item {
-            slots.push(InventorySlot::from_inventory_index(i as i8, item));
-        }
-    }
-    slots
-}
-
 fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
     let pos = crate::load_base(&data.base);
     let slots = match data.kind {
@@ -210,25 +135,12 @@ fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
         _ => bail!("not a chest"),
     };
 
-    let inventory = load_inventory(&slots);
+    let inventory = Inventory::chest();
+    crate::load_inventory(&inventory, LAYOUT, &slots);
 
     Ok(create_with_inventory(pos, inventory))
 }
 
-fn load_inventory(slots: &[InventorySlot]) -> Inventory {
-    let inv = Inventory::chest();
-
-    for slot in slots {
-        if Item::from_identifier(&slot.item).is_some() {
-            if let Err(e) = inv.set_item_at(Area::Chest, slot.slot as usize, slot.into()) {
-                log::warn!("Invalid chest slot: {}", e);
-            }
-        }
-    }
-
-    inv
-}
-
 /// If the block at the given position is a chest, and it is connected
 /// to another chest to form a large chest, returns a tuple (left, right)
 // where `left` is the left chest and `right` is the right chest position.
@@ -271,9 +183,10 @@ pub fn connected_chest(
 pub fn try_connect_chests(
     game: &mut Game,
     world: &mut World,
+    world_id: WorldId,
     pos: BlockPosition,
 ) -> Option<BlockPosition> {
-    let block = game.block_at(pos).unwrap_or_default();
+    let block = game.block_at(world_id, pos).unwrap_or_default();
     if block.kind() != BlockKind::Chest {
         return None;
     }
@@ -283,7 +196,7 @@ pub fn try_connect_chests(
     for (x_offset, z_offset) in offsets.iter().copied() {
         let pos2 = BlockPosition::new(pos.x + x_offset, pos.y, pos.z + z_offset);
 
-        let block2 = game.block_at(pos2).unwrap_or_default();
+        let block2 = game.block_at(world_id, pos2).unwrap_or_default();
         if block2.kind() != BlockKind::Chest {
             continue;
         }
@@ -313,8 +226,20 @@ pub fn try_connect_chests(
             left_block = left_block.with_chest_kind(ChestKind::Right);
             right_block = right_block.with_chest_kind(ChestKind::Left);
 
-            game.set_block_at(world, left_pos, left_block, BlockUpdateCause::Unknown);
-            game.set_block_at(world, right_pos, right_block, BlockUpdateCause::Unknown);
+            game.set_block_at(
+                world,
+                world_id,
+                left_pos,
+                left_block,
+                BlockUpdateCause::Unknown,
+            );
+            game.set_block_at(
+                world,
+                world_id,
+                right_pos,
+                right_block,
+                BlockUpdateCause::Unknown,
+            );
 
             return Some(pos2);
         }
@@ -351,7 +276,8 @@ impl InteractionHandler for ChestInteraction {
         // chest (ChestKind::Right, oddly enough) and the
         // bottom row is the right chest (ChestKind::Left).
 
-        let chests: ArrayVec<[Option<Entity>; 2]> = opened_chests(game, pos);
+        let world_id = game.world_of(world, player);
+        let chests: ArrayVec<[Option<Entity>; 2]> = opened_chests(&game.worlds[world_id], pos);
         let slots = slots(world, &chests);
 
         send_open_window(world, player, slots.len(), window_id);
@@ -365,14 +291,14 @@ impl InteractionHandler for ChestInteraction {
     }
 }
 
-fn opened_chests(game: &Game, pos: BlockPosition) -> ArrayVec<[Option<Entity>; 2]> {
-    if let Some((left, right)) = connected_chest(pos, game.block_at(pos).unwrap_or_default()) {
+fn opened_chests(state: &WorldState, pos: BlockPosition) -> ArrayVec<[Option<Entity>; 2]> {
+    if let Some((left, right)) = connected_chest(pos, state.block_at(pos).unwrap_or_default()) {
         ArrayVec::from([
-            game.block_entities.get(&left).copied(),
-            game.block_entities.get(&right).copied(),
+            state.block_entities.get(&left).copied(),
+            state.block_entities.get(&right).copied(),
         ])
     } else {
-        std::iter::once(game.block_entities.get(&pos).copied()).collect()
+        std::iter::once(state.block_entities.get(&pos).copied()).collect()
     }
 }
 
@@ -488,12 +414,14 @@ mod tests {
         for (pos_left, pos_right, block_left, block_right) in pairs {
             assert!(test.game.set_block_at(
                 &mut test.world,
+                WorldId::MAIN,
                 pos_left,
                 block_left,
                 BlockUpdateCause::Unknown,
             ));
             assert!(test.game.set_block_at(
                 &mut test.world,
+                WorldId::MAIN,
                 pos_right,
                 block_right,
                 BlockUpdateCause::Unknown,
@@ -511,6 +439,7 @@ mod tests {
         let mut test = Test::new();
         test.game.set_block_at(
             &mut test.world,
+            WorldId::MAIN,
             BlockPosition::new(0, 0, 0),
             BlockId::chest().with_chest_kind(ChestKind::Single),
             BlockUpdateCause::Unknown,
@@ -530,6 +459,7 @@ mod tests {
         let mut test = Test::new();
         test.game.set_block_at(
             &mut test.world,
+            WorldId::MAIN,
             BlockPosition::new(0, 0, 0),
             BlockId::chest()
                 .with_chest_kind(ChestKind::Single)
@@ -537,12 +467,18 @@ mod tests {
             BlockUpdateCause::Unknown,
         );
         assert_eq!(
-            try_connect_chests(&mut test.game, &mut test.world, BlockPosition::new(0, 0, 0)),
+            try_connect_chests(
+                &mut test.game,
+                &mut test.world,
+                WorldId::MAIN,
+                BlockPosition::new(0, 0, 0)
+            ),
             None
         );
 
         test.game.set_block_at(
             &mut test.world,
+            WorldId::MAIN,
             BlockPosition::new(0, 0, 1),
             BlockId::chest()
                 .with_chest_kind(ChestKind::Single)
@@ -550,12 +486,23 @@ mod tests {
             BlockUpdateCause::Unknown,
         );
         assert_eq!(
-            try_connect_chests(&mut test.game, &mut test.world, BlockPosition::new(0, 0, 0)),
+            try_connect_chests(
+                &mut test.game,
+                &mut test.world,
+                WorldId::MAIN,
+                BlockPosition::new(0, 0, 0)
+            ),
             Some(BlockPosition::new(0, 0, 1))
         );
 
-        let left = test.game.block_at(BlockPosition::new(0, 0, 0)).unwrap();
-        let right = test.game.block_at(BlockPosition::new(0, 0, 1)).unwrap();
+        let left = test
+            .game
+            .block_at(WorldId::MAIN, BlockPosition::new(0, 0, 0))
+            .unwrap();
+        let right = test
+            .game
+            .block_at(WorldId::MAIN, BlockPosition::new(0, 0, 1))
+            .unwrap();
 
         assert_eq!(left.chest_kind(), Some(ChestKind::Left));
         assert_eq!(right.chest_kind(), Some(ChestKind::Right));
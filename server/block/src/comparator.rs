@@ -0,0 +1,106 @@
+//! Redstone comparators. The strength of the signal a comparator
+//! outputs is kept in its block entity, since its block state
+//! only tells whether it is powered.
+
+use crate::ShouldReplace;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::util::BlockPosition;
+use feather_server_types::{BlockEntityLoaderRegistration, BlockSerializer, Game, Inventory};
+use fecs::{EntityBuilder, EntityRef};
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Comparator,
+});
+
+/// The strength of the signal output by a comparator, from 0 to 15.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ComparatorOutput(pub u8);
+
+/// Creates a comparator which doesn't output a signal.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with_output(pos, ComparatorOutput::default())
+}
+
+/// Creates a comparator outputting a signal of the given strength.
+pub fn create_with_output(pos: BlockPosition, output: ComparatorOutput) -> EntityBuilder {
+    crate::base(pos)
+        .with(output)
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+/// Returns the strength of the signal a comparator reads from
+/// a container: 0 if it is empty, 15 if it is full, and between
+/// 1 and 14 depending on how full its slots are otherwise.
+pub fn container_signal(inventory: &Inventory) -> u8 {
+    let mut slots = 0;
+    let mut fullness = 0.0;
+    let mut empty = true;
+    for (_, slot) in inventory.enumerate() {
+        slots += 1;
+        if let Some(stack) = slot {
+            fullness += f64::from(stack.amount) / f64::from(stack.ty.stack_size().min(64));
+            empty = false;
+        }
+    }
+
+    if empty {
+        return 0;
+    }
+    (fullness / f64::from(slots) * 14.0).floor() as u8 + 1
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    BlockEntityData {
+        base: crate::serialize_base(accessor),
+        kind: BlockEntityKind::Comparator {
+            output_signal: i32::from(accessor.get::<ComparatorOutput>().0),
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let output = match data.kind {
+        BlockEntityKind::Comparator { output_signal } => output_signal.max(0).min(15) as u8,
+        _ => bail!("not a comparator"),
+    };
+
+    Ok(create_with_output(pos, ComparatorOutput(output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::inventory::Area;
+    use feather_core::items::{Item, ItemStack};
+
+    #[test]
+    fn signal_from_chest() {
+        let chest = Inventory::chest();
+        assert_eq!(container_signal(&chest), 0);
+
+        chest
+            .set_item_at(Area::Chest, 0, ItemStack::new(Item::Stone, 1))
+            .unwrap();
+        assert_eq!(container_signal(&chest), 1);
+
+        for slot in 0..27 {
+            chest
+                .set_item_at(Area::Chest, slot, ItemStack::new(Item::Stone, 64))
+                .unwrap();
+        }
+        assert_eq!(container_signal(&chest), 15);
+
+        // Items which don't stack count as full slots.
+        let chest = Inventory::chest();
+        for slot in 0..14 {
+            chest
+                .set_item_at(Area::Chest, slot, ItemStack::new(Item::DiamondSword, 1))
+                .unwrap();
+        }
+        assert_eq!(container_signal(&chest), 8);
+    }
+}
@@ -0,0 +1,351 @@
+//! Conduits, which give Conduit Power to nearby players in
+//! water while they are surrounded by water and a frame of
+//! prismarine blocks. Conduits with a complete frame also
+//! attack monsters in the water around them.
+//!
+//! See https://minecraft.gamepedia.com/Conduit.
+
+use crate::ShouldReplace;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::blocks::{BlockId, BlockKind, SimplifiedBlockKind};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{vec3, BlockPosition, Position};
+use feather_server_entity::Monster;
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, DamageSource, Dead, Game, Player, Sound,
+    StatusEffect, StatusEffectKind, WorldId,
+};
+use fecs::{component, Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use rand::seq::SliceRandom;
+
+/// Ticks between each time conduits check their
+/// frame, give effects and attack monsters.
+const UPDATE_INTERVAL: u64 = 40;
+
+/// Number of frame blocks a conduit needs to be active.
+const MIN_FRAME: u32 = 16;
+/// Number of blocks in a complete frame.
+const FULL_FRAME: u32 = 42;
+
+/// Ticks Conduit Power given by conduits lasts for.
+const EFFECT_DURATION: u32 = 260;
+
+/// Distance from which conduits attack monsters.
+const ATTACK_RANGE: f64 = 8.0;
+/// Damage dealt by each attack of a conduit.
+const ATTACK_DAMAGE: u32 = 4;
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Conduit,
+});
+
+/// Component for conduits.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Conduit {
+    /// Number of blocks in the conduit's frame, or 0
+    /// if it isn't surrounded by water.
+    pub frame: u32,
+    /// The monster the conduit attacks.
+    pub target: Option<Entity>,
+}
+
+impl Conduit {
+    pub fn is_active(&self) -> bool {
+        self.frame >= MIN_FRAME
+    }
+
+    /// Returns the distance from which players get
+    /// Conduit Power, which grows with every seven
+    /// blocks in the frame.
+    pub fn range(&self) -> f64 {
+        f64::from(self.frame / 7 * 16)
+    }
+}
+
+/// Creates a conduit.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    crate::base(pos)
+        .with(Conduit::default())
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    BlockEntityData {
+        base: crate::serialize_base(accessor),
+        kind: BlockEntityKind::Conduit,
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    match data.kind {
+        BlockEntityKind::Conduit => Ok(create(crate::load_base(&data.base))),
+        _ => bail!("not a conduit"),
+    }
+}
+
+fn is_water(block: BlockId) -> bool {
+    block.simplified_kind() == SimplifiedBlockKind::Water
+        || block.waterlogged() == Some(true)
+        || block.kind() == BlockKind::BubbleColumn
+}
+
+fn is_frame_block(block: BlockId) -> bool {
+    matches!(
+        block.kind(),
+        BlockKind::Prismarine
+            | BlockKind::PrismarineBricks
+            | BlockKind::DarkPrismarine
+            | BlockKind::SeaLantern
+    )
+}
+
+/// Returns whether an offset from a conduit lies in its frame,
+/// which consists of three 5x5 rings around the conduit, one
+/// along each pair of axes.
+fn is_frame_offset(x: i32, y: i32, z: i32) -> bool {
+    let (dx, dy, dz) = (x.abs(), y.abs(), z.abs());
+    (dx > 1 || dy > 1 || dz > 1)
+        && ((x == 0 && (dy == 2 || dz == 2))
+            || (y == 0 && (dx == 2 || dz == 2))
+            || (z == 0 && (dx == 2 || dy == 2)))
+}
+
+/// Returns the number of blocks in the frame of a conduit,
+/// or 0 if the conduit isn't surrounded by water.
+pub fn frame_size(game: &Game, world_id: WorldId, pos: BlockPosition) -> u32 {
+    let offset = |x, y, z| BlockPosition::new(pos.x + x, pos.y + y, pos.z + z);
+    let surrounded = (-1..=1).all(|x| {
+        (-1..=1).all(|y| {
+            (-1..=1).all(|z| {
+                (x, y, z) == (0, 0, 0)
+                    || game
+                        .block_at(world_id, offset(x, y, z))
+                        .map_or(false, is_water)
+            })
+        })
+    });
+    if !surrounded {
+        return 0;
+    }
+
+    let mut frame = 0;
+    for x in -2..=2 {
+        for y in -2..=2 {
+            for z in -2..=2 {
+                if is_frame_offset(x, y, z)
+                    && game
+                        .block_at(world_id, offset(x, y, z))
+                        .map_or(false, is_frame_block)
+                {
+                    frame += 1;
+                }
+            }
+        }
+    }
+    frame
+}
+
+/// Returns whether an entity at the given position is wet,
+/// i.e. in water or out in the rain.
+fn is_wet(game: &Game, world_id: WorldId, pos: Position) -> bool {
+    let block = pos.block();
+    game.block_at(world_id, block).map_or(false, is_water) || game.is_raining_at(world_id, block)
+}
+
+fn center(pos: BlockPosition) -> Position {
+    Position::from(pos) + vec3(0.5, 0.5, 0.5)
+}
+
+/// System which updates the frames of conduits every two seconds,
+/// giving Conduit Power to the wet players in their range and
+/// attacking monsters if their frame is complete.
+#[fecs::system]
+pub fn update_conduits(game: &mut Game, world: &mut World) {
+    if game.tick_count % UPDATE_INTERVAL != 0 {
+        return;
+    }
+
+    let conduits: Vec<(Entity, BlockPosition)> = <(Read<Conduit>, Read<BlockPosition>)>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, (_, pos))| (entity, *pos))
+        .collect();
+
+    for (entity, pos) in conduits {
+        let world_id = game.world_of(world, entity);
+        let old = *world.get::<Conduit>(entity);
+        let mut conduit = old;
+        conduit.frame = frame_size(game, world_id, pos);
+
+        if conduit.is_active() != old.is_active() {
+            let name = if conduit.is_active() {
+                "block.conduit.activate"
+            } else {
+                "block.conduit.deactivate"
+            };
+            let sound = Sound::new(name, SoundCategory::Blocks);
+            game.play_sound(world, world_id, center(pos), &sound);
+        }
+
+        if conduit.is_active() {
+            give_conduit_power(game, world, world_id, pos, conduit.range());
+        }
+        conduit.target = if conduit.frame >= FULL_FRAME {
+            find_target(game, world, world_id, pos, conduit.target)
+        } else {
+            None
+        };
+        *world.get_mut::<Conduit>(entity) = conduit;
+
+        if let Some(target) = conduit.target {
+            let target_pos = *world.get::<Position>(target);
+            let sound = Sound::new("block.conduit.attack.target", SoundCategory::Blocks);
+            game.play_sound(world, world_id, target_pos, &sound);
+            game.damage(target, ATTACK_DAMAGE, DamageSource::Magic, world);
+        }
+    }
+}
+
+/// Gives Conduit Power to the wet players within `range` of a conduit.
+fn give_conduit_power(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    range: f64,
+) {
+    let center = center(pos);
+    let players: Vec<Entity> = <Read<Position>>::query()
+        .filter(component::<Player>() & !component::<Dead>())
+        .iter_entities(world.inner())
+        .filter(|(player, player_pos)| {
+            game.world_of(world, *player) == world_id
+                && player_pos.distance_to(center) < range
+                && is_wet(game, world_id, *player_pos)
+        })
+        .map(|(player, _)| player)
+        .collect();
+
+    let effect = StatusEffect::new(StatusEffectKind::ConduitPower, 0, EFFECT_DURATION).ambient();
+    for player in players {
+        game.add_status_effect(world, player, effect);
+    }
+}
+
+/// Returns the monster a conduit attacks: its current target if
+/// it's still alive, wet and in range, or else a random wet
+/// monster in range.
+fn find_target(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    target: Option<Entity>,
+) -> Option<Entity> {
+    let center = center(pos);
+    let is_valid = |monster: Entity| {
+        world.is_alive(monster)
+            && !world.has::<Dead>(monster)
+            && game.world_of(world, monster) == world_id
+            && world
+                .try_get::<Position>(monster)
+                .map_or(false, |monster_pos| {
+                    monster_pos.distance_to(center) < ATTACK_RANGE
+                        && is_wet(game, world_id, *monster_pos)
+                })
+    };
+
+    if let Some(target) = target.filter(|&target| is_valid(target)) {
+        return Some(target);
+    }
+    let monsters: Vec<Entity> = <Read<Monster>>::query()
+        .iter_entities(world.inner())
+        .map(|(monster, _)| monster)
+        .filter(|&monster| is_valid(monster))
+        .collect();
+    monsters.choose(&mut *game.rng()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::position;
+    use feather_server_entity::MobKind;
+    use feather_server_types::{Health, StatusEffects};
+    use feather_test_framework::Test;
+
+    const POS: BlockPosition = BlockPosition { x: 0, y: 10, z: 0 };
+
+    /// Fills the area around `POS` with water, and places `frame`
+    /// prismarine blocks in the frame of a conduit there.
+    fn build_conduit(test: &mut Test, frame: usize) -> Entity {
+        for x in -2..=2 {
+            for y in -2..=2 {
+                for z in -2..=2 {
+                    let pos = BlockPosition::new(POS.x + x, POS.y + y, POS.z + z);
+                    test.set_block(pos, BlockId::water());
+                }
+            }
+        }
+        let offsets: Vec<(i32, i32, i32)> = (-2..=2)
+            .flat_map(|x| (-2..=2).flat_map(move |y| (-2..=2).map(move |z| (x, y, z))))
+            .filter(|&(x, y, z)| is_frame_offset(x, y, z))
+            .collect();
+        assert_eq!(offsets.len(), FULL_FRAME as usize);
+        for &(x, y, z) in offsets.iter().take(frame) {
+            let pos = BlockPosition::new(POS.x + x, POS.y + y, POS.z + z);
+            test.set_block(pos, BlockId::prismarine());
+        }
+
+        test.set_block(POS, BlockId::conduit());
+        test.entity(create(POS))
+    }
+
+    #[test]
+    fn frame_detection() {
+        let mut test = Test::new();
+        build_conduit(&mut test, 16);
+        assert_eq!(frame_size(&test.game, WorldId::MAIN, POS), 16);
+
+        // Conduits must be surrounded by water.
+        test.set_block(BlockPosition::new(1, 11, 1), BlockId::air());
+        assert_eq!(frame_size(&test.game, WorldId::MAIN, POS), 0);
+    }
+
+    #[test]
+    fn conduit_power_for_wet_players() {
+        let mut test = Test::new();
+        let conduit = build_conduit(&mut test, 20);
+        let wet = test.player("wet", position!(1.5, 10.0, 0.5));
+        let dry = test.player("dry", position!(4.5, 20.0, 0.5));
+
+        test.game.tick_count = UPDATE_INTERVAL;
+        test.run(update_conduits);
+        assert!(test.world.get::<Conduit>(conduit).is_active());
+        let effects = test.world.get::<StatusEffects>(wet);
+        assert!(effects.has(StatusEffectKind::ConduitPower));
+        drop(effects);
+        assert!(!test.world.has::<StatusEffects>(dry));
+        // Only complete frames attack.
+        assert_eq!(test.world.get::<Conduit>(conduit).target, None);
+    }
+
+    #[test]
+    fn attack_monsters() {
+        let mut test = Test::new();
+        let conduit = build_conduit(&mut test, FULL_FRAME as usize);
+        let zombie = test.entity(
+            MobKind::Zombie
+                .create()
+                .with(position!(0.5, 11.0, 1.5))
+                .with(Health(20)),
+        );
+
+        test.game.tick_count = UPDATE_INTERVAL;
+        test.run(update_conduits);
+        assert_eq!(test.world.get::<Conduit>(conduit).target, Some(zombie));
+        assert_eq!(test.world.get::<Health>(zombie).0, 16);
+    }
+}
@@ -0,0 +1,173 @@
+//! Lids of containers, which open while any player has
+//! the container's window open and close once the last
+//! of them closes it, playing a sound either way.
+//!
+//! Barrels, which only open their lid from 1.14 on,
+//! aren't implemented.
+
+use crate::chest::connected_chest;
+use feather_core::blocks::{BlockKind, ChestKind};
+use feather_core::network::packets::{BlockAction, SoundCategory};
+use feather_core::util::{vec3, BlockPosition, Position};
+use feather_server_types::{Game, Sound, WindowCloseEvent, WindowOpenEvent};
+use fecs::{Entity, World};
+use num_traits::ToPrimitive;
+use rand::Rng;
+
+/// Block action which sets the number of viewers of a container.
+const ACTION_VIEWERS: u8 = 1;
+
+/// The kind of lid a container has.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Lid {
+    Chest,
+    ShulkerBox,
+}
+
+impl Lid {
+    fn sound(self, open: bool) -> &'static str {
+        match (self, open) {
+            (Lid::Chest, true) => "block.chest.open",
+            (Lid::Chest, false) => "block.chest.close",
+            (Lid::ShulkerBox, true) => "block.shulker_box.open",
+            (Lid::ShulkerBox, false) => "block.shulker_box.close",
+        }
+    }
+}
+
+/// Stores the number of players who have a container's window open.
+/// Clients render the lid open as long as it is above zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContainerViewers(pub u32);
+
+/// Returns the block action telling clients how many
+/// players are viewing the container at the given position.
+pub fn viewers_packet(pos: BlockPosition, kind: BlockKind, viewers: u32) -> BlockAction {
+    BlockAction {
+        location: pos,
+        action_id: ACTION_VIEWERS,
+        action_param: viewers.min(u8::max_value() as u32) as u8,
+        block_type: kind.to_i32().unwrap(),
+    }
+}
+
+#[fecs::event_handler]
+pub fn on_container_open_increment_viewers(
+    event: &WindowOpenEvent,
+    game: &Game,
+    world: &mut World,
+) {
+    update_viewers(game, world, event.opened, |viewers| viewers + 1);
+}
+
+#[fecs::event_handler]
+pub fn on_container_close_decrement_viewers(
+    event: &WindowCloseEvent,
+    game: &Game,
+    world: &mut World,
+) {
+    update_viewers(game, world, event.closed, |viewers| {
+        viewers.saturating_sub(1)
+    });
+}
+
+fn update_viewers(game: &Game, world: &mut World, container: Entity, change: fn(u32) -> u32) {
+    let (old, new) = match world.try_get_mut::<ContainerViewers>(container) {
+        Some(mut viewers) => {
+            let old = viewers.0;
+            viewers.0 = change(old);
+            (old, viewers.0)
+        }
+        None => return,
+    };
+    if old == new {
+        return;
+    }
+
+    let pos = *world.get::<BlockPosition>(container);
+    let world_id = game.world_of(world, container);
+    let block = match game.block_at(world_id, pos) {
+        Some(block) => block,
+        None => return,
+    };
+    let packet = viewers_packet(pos, block.kind(), new);
+    game.broadcast_entity_update(world, packet, container, None);
+
+    if (old == 0) == (new == 0) {
+        return;
+    }
+    // Both halves of a large chest are opened together, so
+    // only the left one plays the sound, from the middle.
+    let center = match (block.chest_kind(), connected_chest(pos, block)) {
+        (Some(ChestKind::Left), _) => return,
+        (_, Some((left, right))) => {
+            let x = f64::from(right.x - left.x) / 2.0 + 0.5;
+            let z = f64::from(right.z - left.z) / 2.0 + 0.5;
+            Position::from(left) + vec3(x, 0.5, z)
+        }
+        _ => Position::from(pos) + vec3(0.5, 0.5, 0.5),
+    };
+
+    let lid = *world.get::<Lid>(container);
+    let pitch = game.rng().gen_range(0.9, 1.0);
+    let sound = Sound::new(lid.sound(new > 0), SoundCategory::Blocks)
+        .volume(0.5)
+        .pitch(pitch);
+    game.play_sound(world, world_id, center, &sound);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chest;
+    use feather_core::blocks::BlockId;
+    use feather_core::position;
+    use feather_server_types::{BlockUpdateCause, WorldId};
+    use feather_test_framework::Test;
+
+    fn viewers(test: &Test, container: Entity) -> u32 {
+        test.world.get::<ContainerViewers>(container).0
+    }
+
+    #[test]
+    fn viewers_follow_windows() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            BlockId::chest(),
+            BlockUpdateCause::Unknown,
+        );
+        let chest = test.entity(chest::create(pos));
+        let player1 = test.player("", position!(2.0, 64.0, 0.0));
+        let player2 = test.player("", position!(-2.0, 64.0, 0.0));
+
+        for &player in &[player1, player2] {
+            let event = WindowOpenEvent {
+                player,
+                opened: chest,
+            };
+            test.handle(event, on_container_open_increment_viewers);
+        }
+        assert_eq!(viewers(&test, chest), 2);
+
+        for &player in &[player1, player2, player2] {
+            let event = WindowCloseEvent {
+                player,
+                closed: chest,
+            };
+            test.handle(event, on_container_close_decrement_viewers);
+        }
+        assert_eq!(viewers(&test, chest), 0);
+    }
+
+    #[test]
+    fn viewers_packet_clamps() {
+        let pos = BlockPosition::new(0, 64, 0);
+        let packet = viewers_packet(pos, BlockKind::Chest, 300);
+        assert_eq!(packet.action_id, ACTION_VIEWERS);
+        assert_eq!(packet.action_param, 255);
+    }
+}
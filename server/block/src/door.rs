@@ -0,0 +1,369 @@
+//! Doors, trapdoors and fence gates, which open and close when
+//! right clicked or powered by redstone. Iron doors and iron
+//! trapdoors only open when powered.
+//!
+//! Opening or closing a wooden door by hand also toggles the door
+//! it forms a double door with: the door next to it on the side
+//! away from its hinge, facing the same way with the other hinge.
+//!
+//! See https://minecraft.gamepedia.com/Door.
+
+use feather_core::blocks::{
+    BlockId, BlockKind, FacingCardinal, HalfUpperLower, Hinge, SimplifiedBlockKind,
+};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{BlockUpdateCause, Game, InteractionHandler, Sound, WorldId};
+use fecs::{Entity, World};
+use rand::Rng;
+
+/// Returns whether a block is a door, a trapdoor or a fence gate.
+pub fn is_openable(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::WoodenDoor
+            | SimplifiedBlockKind::IronDoor
+            | SimplifiedBlockKind::WoodenTrapdoor
+            | SimplifiedBlockKind::IronTrapdoor
+            | SimplifiedBlockKind::FenceGate
+    )
+}
+
+fn is_door(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::WoodenDoor | SimplifiedBlockKind::IronDoor
+    )
+}
+
+/// Returns the position of the other half of
+/// the door at the given position, if it is one.
+pub fn other_door_half(door: BlockId, pos: BlockPosition) -> Option<BlockPosition> {
+    if !is_door(door) {
+        return None;
+    }
+    match door.half_upper_lower()? {
+        HalfUpperLower::Lower => Some(pos.up()),
+        HalfUpperLower::Upper => Some(pos.down()),
+    }
+}
+
+/// Returns the position of the door forming a double door
+/// with the door at the given position, if there is one.
+fn paired_door(game: &Game, world_id: WorldId, pos: BlockPosition) -> Option<BlockPosition> {
+    let door = game.block_at(world_id, pos)?;
+    let facing = door.facing_cardinal()?;
+    let (side, paired_hinge) = match door.hinge()? {
+        Hinge::Left => (facing.right(), Hinge::Right),
+        Hinge::Right => (facing.left(), Hinge::Left),
+    };
+
+    let paired_pos = pos + side.offset();
+    let paired = game.block_at(world_id, paired_pos)?;
+    if paired.kind() == door.kind()
+        && paired.facing_cardinal() == Some(facing)
+        && paired.hinge() == Some(paired_hinge)
+        && paired.half_upper_lower() == door.half_upper_lower()
+        && paired.open() == door.open()
+    {
+        Some(paired_pos)
+    } else {
+        None
+    }
+}
+
+/// Returns the direction a player at the given position looks
+/// towards, ignoring whether they look up or down.
+fn looking_towards(pos: Position) -> FacingCardinal {
+    let direction = pos.direction();
+    if direction.x.abs() > direction.z.abs() {
+        if direction.x > 0.0 {
+            FacingCardinal::East
+        } else {
+            FacingCardinal::West
+        }
+    } else if direction.z > 0.0 {
+        FacingCardinal::South
+    } else {
+        FacingCardinal::North
+    }
+}
+
+fn play_sound(
+    game: &mut Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+    open: bool,
+) {
+    let name = match block.simplified_kind() {
+        SimplifiedBlockKind::WoodenDoor => "block.wooden_door",
+        SimplifiedBlockKind::IronDoor => "block.iron_door",
+        SimplifiedBlockKind::WoodenTrapdoor => "block.wooden_trapdoor",
+        SimplifiedBlockKind::IronTrapdoor => "block.iron_trapdoor",
+        SimplifiedBlockKind::FenceGate => "block.fence_gate",
+        _ => return,
+    };
+    let name = if open {
+        format!("{}.open", name)
+    } else {
+        format!("{}.close", name)
+    };
+
+    let pitch = game.rng().gen_range(0.9, 1.0);
+    let sound = Sound::new(name, SoundCategory::Blocks).pitch(pitch);
+    game.play_sound(world, world_id, Position::from(pos), &sound);
+}
+
+/// Changes the block at the given position with `change`, along
+/// with the other half of the door if it is one.
+fn change_openable(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    cause: BlockUpdateCause,
+    change: impl Fn(BlockId) -> BlockId,
+) {
+    let block = match game.block_at(world_id, pos) {
+        Some(block) if is_openable(block) => block,
+        _ => return,
+    };
+    let new = change(block);
+    if new == block {
+        return;
+    }
+
+    if let Some(other_pos) = other_door_half(block, pos) {
+        if let Some(other) = game
+            .block_at(world_id, other_pos)
+            .filter(|other| is_door(*other))
+        {
+            game.set_block_at(world, world_id, other_pos, change(other), cause);
+        }
+    }
+    game.set_block_at(world, world_id, pos, new, cause);
+
+    if new.open() != block.open() {
+        play_sound(game, world, world_id, pos, new, new.open() == Some(true));
+    }
+}
+
+/// Opens or closes the door, trapdoor or fence gate at the given
+/// position as a player right clicking it would.
+pub fn toggle_openable(game: &mut Game, world: &mut World, pos: BlockPosition, player: Entity) {
+    let world_id = game.world_of(world, player);
+    let block = match game.block_at(world_id, pos) {
+        Some(block) => block,
+        None => return,
+    };
+    let open = block.open() != Some(true);
+
+    // Fence gates swing away from the player opening them.
+    let facing = match block.facing_cardinal() {
+        Some(facing) if block.simplified_kind() == SimplifiedBlockKind::FenceGate && open => {
+            let towards = looking_towards(*world.get::<Position>(player));
+            if towards == facing.opposite() {
+                towards
+            } else {
+                facing
+            }
+        }
+        facing => facing,
+    };
+
+    let paired = paired_door(game, world_id, pos);
+    let cause = BlockUpdateCause::Entity(player);
+    change_openable(game, world, world_id, pos, cause, |block| {
+        let block = block.with_open(open);
+        match facing {
+            Some(facing) if !is_door(block) => block.with_facing_cardinal(facing),
+            _ => block,
+        }
+    });
+    if let Some(paired) = paired {
+        change_openable(game, world, world_id, paired, cause, |block| {
+            block.with_open(open)
+        });
+    }
+}
+
+/// Powers or unpowers the door, trapdoor or fence gate at the
+/// given position, opening it when it becomes powered and closing
+/// it when it stops being powered.
+pub fn set_openable_powered(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    powered: bool,
+) {
+    change_openable(
+        game,
+        world,
+        world_id,
+        pos,
+        BlockUpdateCause::Redstone,
+        |block| {
+            if block.powered() == Some(powered) {
+                block
+            } else {
+                block.with_powered(powered).with_open(powered)
+            }
+        },
+    );
+}
+
+/// Handler for players right clicking on wooden doors,
+/// wooden trapdoors and fence gates.
+struct DoorInteraction(BlockKind);
+
+inventory::submit!(Box::new(DoorInteraction(BlockKind::OakDoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(DoorInteraction(BlockKind::SpruceDoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(DoorInteraction(BlockKind::BirchDoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(DoorInteraction(BlockKind::JungleDoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(DoorInteraction(BlockKind::AcaciaDoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(DoorInteraction(BlockKind::DarkOakDoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(Box::new(DoorInteraction(BlockKind::OakTrapdoor)) as Box<dyn InteractionHandler>);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::SpruceTrapdoor)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::BirchTrapdoor)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::JungleTrapdoor)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::AcaciaTrapdoor)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::DarkOakTrapdoor)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::OakFenceGate)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::SpruceFenceGate)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::BirchFenceGate)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::JungleFenceGate)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::AcaciaFenceGate)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(DoorInteraction(BlockKind::DarkOakFenceGate)) as Box<dyn InteractionHandler>
+);
+
+impl InteractionHandler for DoorInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        _window_id: u8,
+    ) {
+        toggle_openable(game, world, pos, player);
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::position;
+    use feather_test_framework::Test;
+
+    fn place_door(test: &mut Test, pos: BlockPosition, door: BlockId, hinge: Hinge) {
+        let door = door
+            .with_facing_cardinal(FacingCardinal::North)
+            .with_hinge(hinge);
+        test.set_block(pos, door.with_half_upper_lower(HalfUpperLower::Lower));
+        test.set_block(pos.up(), door.with_half_upper_lower(HalfUpperLower::Upper));
+    }
+
+    #[test]
+    fn open_door_by_hand() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        let pos = BlockPosition::new(0, 64, 0);
+        place_door(&mut test, pos, BlockId::oak_door(), Hinge::Left);
+
+        toggle_openable(&mut test.game, &mut test.world, pos.up(), player);
+        assert_eq!(test.block(pos).open(), Some(true));
+        assert_eq!(test.block(pos.up()).open(), Some(true));
+
+        toggle_openable(&mut test.game, &mut test.world, pos, player);
+        assert_eq!(test.block(pos).open(), Some(false));
+        assert_eq!(test.block(pos.up()).open(), Some(false));
+    }
+
+    #[test]
+    fn double_doors_open_together() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        let left = BlockPosition::new(0, 64, 0);
+        let right = BlockPosition::new(1, 64, 0);
+        let lonely = BlockPosition::new(-1, 64, 0);
+        place_door(&mut test, left, BlockId::oak_door(), Hinge::Left);
+        place_door(&mut test, right, BlockId::oak_door(), Hinge::Right);
+        place_door(&mut test, lonely, BlockId::oak_door(), Hinge::Right);
+
+        toggle_openable(&mut test.game, &mut test.world, left, player);
+        assert_eq!(test.block(right).open(), Some(true));
+        assert_eq!(test.block(right.up()).open(), Some(true));
+        assert_eq!(test.block(lonely).open(), Some(false));
+
+        toggle_openable(&mut test.game, &mut test.world, right.up(), player);
+        assert_eq!(test.block(left).open(), Some(false));
+        assert_eq!(test.block(right).open(), Some(false));
+    }
+
+    #[test]
+    fn power_opens_iron_door() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        place_door(&mut test, pos, BlockId::iron_door(), Hinge::Left);
+
+        set_openable_powered(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            pos.up(),
+            true,
+        );
+        assert_eq!(test.block(pos).open(), Some(true));
+        assert_eq!(test.block(pos).powered(), Some(true));
+        assert_eq!(test.block(pos.up()).open(), Some(true));
+
+        set_openable_powered(&mut test.game, &mut test.world, WorldId::MAIN, pos, false);
+        assert_eq!(test.block(pos).open(), Some(false));
+        assert_eq!(test.block(pos.up()).powered(), Some(false));
+    }
+
+    #[test]
+    fn fence_gate_swings_away() {
+        let mut test = Test::new();
+        // Looking north, towards the gate's back.
+        let player = test.player("", position!(0.5, 64.0, 2.0, 0.0, 180.0));
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(
+            pos,
+            BlockId::oak_fence_gate().with_facing_cardinal(FacingCardinal::South),
+        );
+
+        toggle_openable(&mut test.game, &mut test.world, pos, player);
+        let gate = test.block(pos);
+        assert_eq!(gate.open(), Some(true));
+        assert_eq!(gate.facing_cardinal(), Some(FacingCardinal::North));
+    }
+}
@@ -0,0 +1,635 @@
+//! Farming: crops, stems, sugar cane and cactus growing
+//! over time, farmland drying out, and bone meal.
+
+use crate::tick::light_at;
+use feather_core::blocks::{BlockId, BlockKind, FacingCardinal, SimplifiedBlockKind};
+use feather_core::items::Item;
+use feather_core::network::packets::Effect;
+use feather_core::util::BlockPosition;
+use feather_server_entity::consume_held_item;
+use feather_server_types::{
+    BlockTickEvent, BlockTickKind, BlockUpdateCause, BlockUpdateEvent, Game, ItemUseOnBlockEvent,
+    WorldId, WorldState,
+};
+use fecs::World;
+use rand::Rng;
+
+/// Light level crops and stems need to grow.
+const MIN_GROWTH_LIGHT: u8 = 9;
+/// Greatest moisture of farmland, reached near water.
+const MAX_MOISTURE: i32 = 7;
+/// Horizontal distance from water within which
+/// farmland stays moist.
+const HYDRATION_DISTANCE: i32 = 4;
+/// Greatest height to which sugar cane and cactus grow.
+const MAX_PLANT_HEIGHT: i32 = 3;
+/// World event showing bone meal particles.
+const BONE_MEAL_EFFECT: i32 = 2005;
+
+const STEM_DIRECTIONS: [FacingCardinal; 4] = [
+    FacingCardinal::North,
+    FacingCardinal::East,
+    FacingCardinal::South,
+    FacingCardinal::West,
+];
+
+fn is_water(block: BlockId) -> bool {
+    block.simplified_kind() == SimplifiedBlockKind::Water || block.waterlogged() == Some(true)
+}
+
+fn is_crop(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::Wheat
+            | SimplifiedBlockKind::Carrots
+            | SimplifiedBlockKind::Potatoes
+            | SimplifiedBlockKind::Beetroots
+    )
+}
+
+fn is_stem(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::PumpkinStem | SimplifiedBlockKind::MelonStem
+    )
+}
+
+/// Returns the fruit grown by a stem and
+/// the stem attached to it.
+fn stem_fruit(stem: BlockId) -> Option<(BlockId, BlockId)> {
+    match stem.kind() {
+        BlockKind::PumpkinStem => Some((BlockId::pumpkin(), BlockId::attached_pumpkin_stem())),
+        BlockKind::MelonStem => Some((BlockId::melon(), BlockId::attached_melon_stem())),
+        _ => None,
+    }
+}
+
+/// Returns the chance that a crop or stem grows when
+/// randomly ticked, based on the farmland below and around it.
+fn crop_growth_chance(state: &WorldState, pos: BlockPosition) -> f64 {
+    let mut points = 1.0;
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            let farmland = match state.block_at(pos + BlockPosition::new(dx, -1, dz)) {
+                Some(block) if block.simplified_kind() == SimplifiedBlockKind::Farmland => block,
+                _ => continue,
+            };
+
+            let mut farmland_points = if farmland.moisture().unwrap_or(0) > 0 {
+                3.0
+            } else {
+                1.0
+            };
+            // Farmland around the crop counts a quarter as much.
+            if dx != 0 || dz != 0 {
+                farmland_points /= 4.0;
+            }
+            points += farmland_points;
+        }
+    }
+
+    1.0 / ((25.0 / points).floor() + 1.0)
+}
+
+/// Grows wheat, carrots, potatoes, beetroots and stems when
+/// randomly ticked, faster when planted on moist farmland.
+/// Fully grown stems grow a pumpkin or melon next to them.
+#[fecs::event_handler]
+pub fn on_block_tick_grow_crops(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Random || !(is_crop(event.block) || is_stem(event.block)) {
+        return;
+    }
+    let (stage, max_stage) = match event.block.growth_stage() {
+        Some(stage) => stage,
+        None => return,
+    };
+    if stage >= max_stage && !is_stem(event.block) {
+        return;
+    }
+
+    let state = &game.worlds[event.world];
+    if light_at(state, event.pos).map_or(true, |light| light < MIN_GROWTH_LIGHT) {
+        return;
+    }
+    let mut chance = crop_growth_chance(state, event.pos);
+    // Beetroots grow a third as often.
+    if event.block.simplified_kind() == SimplifiedBlockKind::Beetroots {
+        chance /= 3.0;
+    }
+    if !game.rng().gen_bool(chance) {
+        return;
+    }
+
+    if stage < max_stage {
+        let grown = event.block.with_growth_stage(stage + 1);
+        game.set_block_at(world, event.world, event.pos, grown, BlockUpdateCause::Tick);
+    } else {
+        grow_fruit(game, world, event.world, event.pos, event.block);
+    }
+}
+
+/// Grows the fruit of a fully grown stem on a random side
+/// of it, if there's room and soil for it.
+fn grow_fruit(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    stem: BlockId,
+) {
+    let (fruit, attached) = match stem_fruit(stem) {
+        Some(fruit) => fruit,
+        None => return,
+    };
+    let dir = STEM_DIRECTIONS[game.rng().gen_range(0, STEM_DIRECTIONS.len())];
+    let fruit_pos = pos + dir.offset();
+
+    let state = &game.worlds[world_id];
+    let has_room = state.block_at(fruit_pos).map_or(false, BlockId::is_air);
+    let has_soil = state.block_at(fruit_pos.down()).map_or(false, |block| {
+        matches!(
+            block.simplified_kind(),
+            SimplifiedBlockKind::Farmland
+                | SimplifiedBlockKind::Dirt
+                | SimplifiedBlockKind::CoarseDirt
+                | SimplifiedBlockKind::Podzol
+                | SimplifiedBlockKind::GrassBlock
+        )
+    });
+    if !has_room || !has_soil {
+        return;
+    }
+
+    game.set_block_at(world, world_id, fruit_pos, fruit, BlockUpdateCause::Tick);
+    game.set_block_at(
+        world,
+        world_id,
+        pos,
+        attached.with_facing_cardinal(dir),
+        BlockUpdateCause::Tick,
+    );
+}
+
+/// Grows sugar cane and cactus up to three blocks
+/// tall when randomly ticked.
+#[fecs::event_handler]
+pub fn on_block_tick_grow_cane_and_cactus(
+    event: &BlockTickEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.kind != BlockTickKind::Random
+        || !matches!(
+            event.block.simplified_kind(),
+            SimplifiedBlockKind::SugarCane | SimplifiedBlockKind::Cactus
+        )
+    {
+        return;
+    }
+    let (stage, max_stage) = match event.block.growth_stage() {
+        Some(stage) => stage,
+        None => return,
+    };
+
+    let state = &game.worlds[event.world];
+    if !state
+        .block_at(event.pos.up())
+        .map_or(false, BlockId::is_air)
+    {
+        return;
+    }
+    let height = (1..MAX_PLANT_HEIGHT)
+        .take_while(|&dy| {
+            state
+                .block_at(event.pos - BlockPosition::new(0, dy, 0))
+                .map_or(false, |block| block.kind() == event.block.kind())
+        })
+        .count() as i32
+        + 1;
+    if height >= MAX_PLANT_HEIGHT {
+        return;
+    }
+
+    if stage < max_stage {
+        let block = event.block.with_growth_stage(stage + 1);
+        game.set_block_at(world, event.world, event.pos, block, BlockUpdateCause::Tick);
+    } else {
+        let top = if event.block.kind() == BlockKind::Cactus {
+            BlockId::cactus()
+        } else {
+            BlockId::sugar_cane()
+        };
+        game.set_block_at(
+            world,
+            event.world,
+            event.pos.up(),
+            top,
+            BlockUpdateCause::Tick,
+        );
+        game.set_block_at(
+            world,
+            event.world,
+            event.pos,
+            event.block.with_growth_stage(0),
+            BlockUpdateCause::Tick,
+        );
+    }
+}
+
+/// Returns whether there's water within reach
+/// of farmland at the given position.
+fn is_near_water(state: &WorldState, pos: BlockPosition) -> bool {
+    for dx in -HYDRATION_DISTANCE..=HYDRATION_DISTANCE {
+        for dz in -HYDRATION_DISTANCE..=HYDRATION_DISTANCE {
+            for dy in 0..=1 {
+                let near = pos + BlockPosition::new(dx, dy, dz);
+                if state.block_at(near).map_or(false, is_water) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Moistens farmland near water or in the rain when randomly
+/// ticked, and otherwise dries it out. Dry farmland without
+/// a crop on it turns back into dirt.
+#[fecs::event_handler]
+pub fn on_block_tick_hydrate_farmland(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Random
+        || event.block.simplified_kind() != SimplifiedBlockKind::Farmland
+    {
+        return;
+    }
+    let moisture = event.block.moisture().unwrap_or(0);
+
+    let state = &game.worlds[event.world];
+    let hydrated =
+        is_near_water(state, event.pos) || game.is_raining_at(event.world, event.pos.up());
+    let block = if hydrated {
+        if moisture == MAX_MOISTURE {
+            return;
+        }
+        event.block.with_moisture(MAX_MOISTURE)
+    } else if moisture > 0 {
+        event.block.with_moisture(moisture - 1)
+    } else {
+        let planted = state.block_at(event.pos.up()).map_or(false, |block| {
+            is_crop(block)
+                || is_stem(block)
+                || block.kind() == BlockKind::AttachedPumpkinStem
+                || block.kind() == BlockKind::AttachedMelonStem
+        });
+        if planted {
+            return;
+        }
+        BlockId::dirt()
+    };
+    game.set_block_at(world, event.world, event.pos, block, BlockUpdateCause::Tick);
+}
+
+/// Turns farmland covered by a solid block into dirt,
+/// and detaches stems from pumpkins and melons which
+/// are removed.
+#[fecs::event_handler]
+pub fn on_block_update_update_farmland(
+    event: &BlockUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.old == event.new {
+        return;
+    }
+
+    let below = event.pos.down();
+    let covers_farmland = event.new.is_solid()
+        && event.new.simplified_kind() != SimplifiedBlockKind::FenceGate
+        && game.block_at(event.world, below).map_or(false, |block| {
+            block.simplified_kind() == SimplifiedBlockKind::Farmland
+        });
+    if covers_farmland {
+        game.set_block_at(
+            world,
+            event.world,
+            below,
+            BlockId::dirt(),
+            BlockUpdateCause::Unsupported,
+        );
+    }
+
+    let (stem, attached) = match event.old.kind() {
+        BlockKind::Pumpkin => (BlockId::pumpkin_stem(), BlockKind::AttachedPumpkinStem),
+        BlockKind::Melon => (BlockId::melon_stem(), BlockKind::AttachedMelonStem),
+        _ => return,
+    };
+    if event.new.kind() == event.old.kind() {
+        return;
+    }
+    for &dir in &STEM_DIRECTIONS {
+        let pos = event.pos + dir.offset();
+        let is_attached = game.block_at(event.world, pos).map_or(false, |block| {
+            block.kind() == attached && block.facing_cardinal() == Some(dir.opposite())
+        });
+        if is_attached {
+            game.set_block_at(
+                world,
+                event.world,
+                pos,
+                stem.with_growth_stage(7),
+                BlockUpdateCause::Unsupported,
+            );
+        }
+    }
+}
+
+/// Grows a plant with bone meal. Returns `false` if
+/// the block isn't a plant bone meal can grow.
+pub fn apply_bone_meal(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+) -> bool {
+    let block = match game.block_at(world_id, pos) {
+        Some(block) => block,
+        None => return false,
+    };
+
+    let grown = match block.simplified_kind() {
+        _ if is_crop(block) || is_stem(block) => {
+            let (stage, max_stage) = match block.growth_stage() {
+                Some(stage) if stage.0 < stage.1 => stage,
+                _ => return false,
+            };
+            let mut growth = game.rng().gen_range(2, 6);
+            // Beetroots grow a third as fast.
+            if block.simplified_kind() == SimplifiedBlockKind::Beetroots {
+                growth /= 3;
+            }
+            Some(block.with_growth_stage((stage + growth).min(max_stage)))
+        }
+        SimplifiedBlockKind::Cocoa => match block.growth_stage() {
+            Some((stage, max_stage)) if stage < max_stage => {
+                Some(block.with_growth_stage(stage + 1))
+            }
+            _ => return false,
+        },
+        SimplifiedBlockKind::Sapling => match block.growth_stage() {
+            // Growing saplings into trees isn't supported yet.
+            Some((0, _)) => {
+                if game.rng().gen_bool(0.45) {
+                    Some(block.with_growth_stage(1))
+                } else {
+                    None
+                }
+            }
+            _ => return false,
+        },
+        SimplifiedBlockKind::GrassBlock => {
+            if !game
+                .block_at(world_id, pos.up())
+                .map_or(false, BlockId::is_air)
+            {
+                return false;
+            }
+            grow_grass(game, world, world_id, pos);
+            None
+        }
+        _ => return false,
+    };
+
+    if let Some(grown) = grown {
+        game.set_block_at(world, world_id, pos, grown, BlockUpdateCause::Tick);
+    }
+    true
+}
+
+/// Grows grass and flowers around a grass block.
+fn grow_grass(game: &mut Game, world: &mut World, world_id: WorldId, pos: BlockPosition) {
+    let mut grown = Vec::new();
+    {
+        let state = &game.worlds[world_id];
+        let mut rng = game.rng();
+        'attempts: for attempt in 0..128 {
+            // Later attempts wander further from the grass block.
+            let mut target = pos.up();
+            for _ in 0..attempt / 16 {
+                target = target
+                    + BlockPosition::new(
+                        rng.gen_range(-1, 2),
+                        rng.gen_range(-1, 2) * rng.gen_range(0, 3) / 2,
+                        rng.gen_range(-1, 2),
+                    );
+                let on_grass = state.block_at(target.down()).map_or(false, |block| {
+                    block.simplified_kind() == SimplifiedBlockKind::GrassBlock
+                });
+                if !on_grass || state.block_at(target).map_or(true, BlockId::is_full_block) {
+                    continue 'attempts;
+                }
+            }
+
+            if state.block_at(target).map_or(false, BlockId::is_air)
+                && !grown.iter().any(|&(pos, _)| pos == target)
+            {
+                let plant = match rng.gen_range(0, 16) {
+                    0 => BlockId::dandelion(),
+                    1 => BlockId::poppy(),
+                    _ => BlockId::grass(),
+                };
+                grown.push((target, plant));
+            }
+        }
+    }
+
+    for (pos, plant) in grown {
+        game.set_block_at(world, world_id, pos, plant, BlockUpdateCause::Tick);
+    }
+}
+
+/// Grows plants which players use bone meal on,
+/// using up the bone meal unless in creative mode.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_apply_bone_meal(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.stack.ty != Item::BoneMeal || !apply_bone_meal(game, world, event.world, event.pos) {
+        return;
+    }
+
+    let packet = Effect {
+        effect_id: BONE_MEAL_EFFECT,
+        location: event.pos,
+        data: 0,
+        disable_relative_volume: false,
+    };
+    game.broadcast_chunk_update(world, packet, event.world, event.pos.chunk(), None);
+
+    consume_held_item(game, world, event.player, event.stack);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    fn random_tick(
+        test: &mut Test,
+        pos: BlockPosition,
+        handler: impl fecs::RawEventHandler<Event = BlockTickEvent>,
+    ) -> BlockId {
+        let block = test.block(pos);
+        test.handle(
+            BlockTickEvent {
+                world: WorldId::MAIN,
+                pos,
+                block,
+                kind: BlockTickKind::Random,
+            },
+            handler,
+        );
+        test.block(pos)
+    }
+
+    #[test]
+    fn crop_growth_chance_on_farmland() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 65, 0);
+        let state = &test.game.worlds[WorldId::MAIN];
+        assert!((crop_growth_chance(state, pos) - 1.0 / 26.0).abs() < 1e-9);
+
+        test.set_block(pos.down(), BlockId::farmland().with_moisture(7));
+        let state = &test.game.worlds[WorldId::MAIN];
+        assert!((crop_growth_chance(state, pos) - 1.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn farmland_hydration() {
+        let mut test = Test::new();
+        let wet = BlockPosition::new(0, 64, 0);
+        let dry = BlockPosition::new(10, 64, 10);
+        test.set_block(wet, BlockId::farmland());
+        test.set_block(wet + BlockPosition::new(4, 0, 4), BlockId::water());
+        test.set_block(dry, BlockId::farmland().with_moisture(1));
+
+        assert_eq!(
+            random_tick(&mut test, wet, on_block_tick_hydrate_farmland).moisture(),
+            Some(MAX_MOISTURE)
+        );
+        assert_eq!(
+            random_tick(&mut test, dry, on_block_tick_hydrate_farmland).moisture(),
+            Some(0)
+        );
+        assert_eq!(
+            random_tick(&mut test, dry, on_block_tick_hydrate_farmland),
+            BlockId::dirt()
+        );
+    }
+
+    #[test]
+    fn covered_farmland_turns_to_dirt() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(pos, BlockId::farmland());
+        let event = BlockUpdateEvent {
+            world: WorldId::MAIN,
+            pos: pos.up(),
+            old: BlockId::air(),
+            new: BlockId::stone(),
+            cause: BlockUpdateCause::Unknown,
+        };
+        test.handle(event, on_block_update_update_farmland);
+
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, pos),
+            Some(BlockId::dirt())
+        );
+    }
+
+    #[test]
+    fn sugar_cane_grows_three_tall() {
+        let mut test = Test::new();
+        let base = BlockPosition::new(0, 64, 0);
+        test.set_block(base, BlockId::sugar_cane().with_growth_stage(15));
+
+        random_tick(&mut test, base, on_block_tick_grow_cane_and_cactus);
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, base.up()),
+            Some(BlockId::sugar_cane())
+        );
+
+        let middle = base.up();
+        test.set_block(middle, BlockId::sugar_cane().with_growth_stage(15));
+        random_tick(&mut test, middle, on_block_tick_grow_cane_and_cactus);
+        let top = middle.up();
+        test.set_block(top, BlockId::sugar_cane().with_growth_stage(15));
+        random_tick(&mut test, top, on_block_tick_grow_cane_and_cactus);
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, top.up()),
+            Some(BlockId::air())
+        );
+    }
+
+    #[test]
+    fn bone_meal_grows_crops() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(pos, BlockId::wheat());
+
+        assert!(apply_bone_meal(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            pos
+        ));
+        let (stage, _) = test
+            .game
+            .block_at(WorldId::MAIN, pos)
+            .unwrap()
+            .growth_stage()
+            .unwrap();
+        assert!((2..=5).contains(&stage));
+
+        test.set_block(pos, BlockId::wheat().with_growth_stage(7));
+        assert!(!apply_bone_meal(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            pos
+        ));
+        test.set_block(pos, BlockId::stone());
+        assert!(!apply_bone_meal(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            pos
+        ));
+    }
+
+    #[test]
+    fn removing_fruit_detaches_stem() {
+        let mut test = Test::new();
+        let stem = BlockPosition::new(0, 64, 0);
+        let fruit = stem + FacingCardinal::East.offset();
+        test.set_block(
+            stem,
+            BlockId::attached_melon_stem().with_facing_cardinal(FacingCardinal::East),
+        );
+        test.set_block(fruit, BlockId::air());
+        let event = BlockUpdateEvent {
+            world: WorldId::MAIN,
+            pos: fruit,
+            old: BlockId::melon(),
+            new: BlockId::air(),
+            cause: BlockUpdateCause::Unknown,
+        };
+        test.handle(event, on_block_update_update_farmland);
+
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, stem),
+            Some(BlockId::melon_stem().with_growth_stage(7))
+        );
+    }
+}
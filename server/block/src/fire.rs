@@ -0,0 +1,545 @@
+//! Fire, which burns flammable blocks and spreads to those near it.
+//!
+//! Fire is ticked every 30 to 40 ticks. Each time, it ages, may burn
+//! the blocks next to it away depending on their flammability, and
+//! may spread to air near flammable blocks depending on their fire
+//! encouragement. Rain puts it out, and it goes out once there is
+//! nothing left to burn, except on netherrack and magma blocks, which
+//! burn forever. Lava sets fire to flammable blocks near it, and players
+//! light it with flint and steel. TNT reached by fire is primed. The
+//! `doFireTick` game rule stops fire from ticking.
+//!
+//! See https://minecraft.gamepedia.com/Fire.
+
+use crate::portal::light_nether_portal;
+use crate::schedule_block_tick;
+use crate::tnt::{ignite_tnt, is_tnt};
+use feather_core::biomes::Biome;
+use feather_core::blocks::{BlockId, BlockKind, SimplifiedBlockKind};
+use feather_core::inventory::{slot, Area};
+use feather_core::items::Item;
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{BlockPosition, Dimension, Gamemode, Position};
+use feather_server_types::{
+    BlockTickEvent, BlockTickKind, BlockUpdateCause, BlockUpdateEvent, Game, HeldItem,
+    ItemDamageEvent, ItemUseOnBlockEvent, Sound, TickPriority, WorldId, WorldState,
+};
+use feather_server_util::adjacent_blocks;
+use fecs::World;
+use rand::Rng;
+
+/// The greatest age of fire.
+const MAX_AGE: i32 = 15;
+
+/// Fire encouragement added by the difficulty.
+/// The server always plays on normal difficulty.
+const DIFFICULTY_ENCOURAGEMENT: i32 = 2 * 7;
+
+/// Downfall above which fire spreads slower in a biome.
+const HUMID_DOWNFALL: f32 = 0.85;
+
+/// The blocks next to fire which it may burn away, with
+/// the chance out of which their flammability is rolled.
+const BURN_CHANCES: [(BlockPosition, i32); 6] = [
+    (BlockPosition { x: 1, y: 0, z: 0 }, 300),
+    (BlockPosition { x: -1, y: 0, z: 0 }, 300),
+    (BlockPosition { x: 0, y: -1, z: 0 }, 250),
+    (BlockPosition { x: 0, y: 1, z: 0 }, 250),
+    (BlockPosition { x: 0, y: 0, z: -1 }, 300),
+    (BlockPosition { x: 0, y: 0, z: 1 }, 300),
+];
+
+/// Returns whether fire can burn a block.
+pub fn is_flammable(block: BlockId) -> bool {
+    encouragement(block) > 0
+}
+
+/// Returns how likely fire is to spread next to a block.
+fn encouragement(block: BlockId) -> i32 {
+    if block.waterlogged() == Some(true) {
+        return 0;
+    }
+    block.kind().fire_encouragement().unwrap_or(0) as i32
+}
+
+/// Returns how likely a block is to burn away.
+fn flammability(block: BlockId) -> i32 {
+    if block.waterlogged() == Some(true) {
+        return 0;
+    }
+    block.kind().flammability().unwrap_or(0) as i32
+}
+
+/// Returns whether fire can stand on a block.
+fn supports_fire(block: BlockId) -> bool {
+    block.is_full_block()
+}
+
+/// Returns whether fire on a block burns forever.
+fn is_infiniburn(state: &WorldState, block: BlockId) -> bool {
+    match block.kind() {
+        BlockKind::Netherrack | BlockKind::MagmaBlock => true,
+        BlockKind::Bedrock => state.dimension == Dimension::End,
+        _ => false,
+    }
+}
+
+fn has_flammable_neighbor(state: &WorldState, pos: BlockPosition) -> bool {
+    adjacent_blocks(pos)
+        .into_iter()
+        .any(|pos| state.block_at(pos).map_or(false, is_flammable))
+}
+
+/// Returns whether fire at `pos` stands on a block
+/// or is attached to a flammable block.
+fn can_survive(state: &WorldState, pos: BlockPosition) -> bool {
+    state.block_at(pos.down()).map_or(false, supports_fire) || has_flammable_neighbor(state, pos)
+}
+
+/// Returns the fire with the given age to place at `pos`. Fire
+/// which doesn't stand on a block is drawn on the sides of the
+/// flammable blocks around it.
+fn fire_at(state: &WorldState, pos: BlockPosition, age: i32) -> BlockId {
+    let fire = BlockId::fire().with_age_0_15(age);
+    let below = state.block_at(pos.down()).unwrap_or_else(BlockId::air);
+    if supports_fire(below) || is_flammable(below) {
+        return fire;
+    }
+
+    let flammable = |x, y, z| {
+        state
+            .block_at(pos + BlockPosition::new(x, y, z))
+            .map_or(false, is_flammable)
+    };
+    fire.with_north_connected(flammable(0, 0, -1))
+        .with_east_connected(flammable(1, 0, 0))
+        .with_south_connected(flammable(0, 0, 1))
+        .with_west_connected(flammable(-1, 0, 0))
+        .with_up(flammable(0, 1, 0))
+}
+
+/// Returns the chance of fire spreading into the block at
+/// `pos`, which is 0 unless it's air next to flammable blocks.
+fn spread_encouragement(state: &WorldState, pos: BlockPosition) -> i32 {
+    if state.block_at(pos) != Some(BlockId::air()) {
+        return 0;
+    }
+    adjacent_blocks(pos)
+        .into_iter()
+        .filter_map(|pos| state.block_at(pos))
+        .map(encouragement)
+        .max()
+        .unwrap_or(0)
+}
+
+fn is_humid(state: &WorldState, pos: BlockPosition) -> bool {
+    let biome = state.chunk_map.chunk_at(pos.chunk()).and_then(|chunk| {
+        let (x, y, z) = chunk.local_pos(pos)?;
+        Some(chunk.biome_at_3d(x, y, z))
+    });
+    biome.map_or(false, |biome: Biome| biome.downfall() > HUMID_DOWNFALL)
+}
+
+/// Returns whether rain falls on `pos` or a block beside it.
+fn is_near_rain(game: &Game, world_id: WorldId, pos: BlockPosition) -> bool {
+    game.is_raining_at(world_id, pos)
+        || adjacent_blocks(pos)
+            .into_iter()
+            .filter(|adjacent| adjacent.y == pos.y)
+            .any(|adjacent| game.is_raining_at(world_id, adjacent))
+}
+
+fn schedule_fire_tick(game: &mut Game, world_id: WorldId, pos: BlockPosition) {
+    let delay = 30 + game.rng().gen_range(0, 10);
+    schedule_block_tick(game, world_id, pos, delay, TickPriority::NORMAL);
+}
+
+fn set_fire_block(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+) {
+    game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Fire);
+}
+
+/// Ages the fire at `pos`, burns the blocks around it and spreads it.
+fn burn(game: &mut Game, world: &mut World, world_id: WorldId, pos: BlockPosition, fire: BlockId) {
+    if !can_survive(&game.worlds[world_id], pos) {
+        set_fire_block(game, world, world_id, pos, BlockId::air());
+        return;
+    }
+    schedule_fire_tick(game, world_id, pos);
+
+    let state = &game.worlds[world_id];
+    let below = state.block_at(pos.down()).unwrap_or_else(BlockId::air);
+    let infiniburn = is_infiniburn(state, below);
+    let age = fire.age_0_15().unwrap_or(0);
+
+    if !infiniburn
+        && is_near_rain(game, world_id, pos)
+        && game.rng().gen_range(0.0, 1.0) < 0.2 + f64::from(age) * 0.03
+    {
+        set_fire_block(game, world, world_id, pos, BlockId::air());
+        return;
+    }
+
+    let new_age = (age + game.rng().gen_range(0, 3) / 2).min(MAX_AGE);
+    if new_age != age {
+        set_fire_block(game, world, world_id, pos, fire.with_age_0_15(new_age));
+    }
+
+    if !infiniburn {
+        if !has_flammable_neighbor(&game.worlds[world_id], pos) {
+            if !supports_fire(below) || age > 3 {
+                set_fire_block(game, world, world_id, pos, BlockId::air());
+            }
+            return;
+        }
+        if age == MAX_AGE && game.rng().gen_range(0, 4) == 0 && !is_flammable(below) {
+            set_fire_block(game, world, world_id, pos, BlockId::air());
+            return;
+        }
+    }
+
+    let humid = is_humid(&game.worlds[world_id], pos);
+    for &(offset, chance) in &BURN_CHANCES {
+        let chance = if humid { chance - 50 } else { chance };
+        burn_block(game, world, world_id, pos + offset, chance, age);
+    }
+
+    for x in -1..=1 {
+        for z in -1..=1 {
+            for y in -1..=4 {
+                if x == 0 && y == 0 && z == 0 {
+                    continue;
+                }
+                let target = pos + BlockPosition::new(x, y, z);
+                spread(game, world, world_id, target, y, age, humid);
+            }
+        }
+    }
+}
+
+/// Burns the block at `pos` away, or sets it on fire,
+/// depending on its flammability.
+fn burn_block(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    chance: i32,
+    age: i32,
+) {
+    let block = match game.block_at(world_id, pos) {
+        Some(block) => block,
+        None => return,
+    };
+    if game.rng().gen_range(0, chance) >= flammability(block) {
+        return;
+    }
+    if is_tnt(block) {
+        ignite_tnt(game, world, world_id, pos, BlockUpdateCause::Fire);
+        return;
+    }
+
+    let catches_fire = game.rng().gen_range(0, age + 10) < 5;
+    let new = if catches_fire && !game.is_raining_at(world_id, pos) {
+        let age = (age + game.rng().gen_range(0, 5) / 4).min(MAX_AGE);
+        fire_at(&game.worlds[world_id], pos, age)
+    } else {
+        BlockId::air()
+    };
+    set_fire_block(game, world, world_id, pos, new);
+}
+
+/// Spreads fire of the given age into the block at `pos`,
+/// which is `height` blocks above the fire.
+fn spread(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    height: i32,
+    age: i32,
+    humid: bool,
+) {
+    let encouragement = spread_encouragement(&game.worlds[world_id], pos);
+    if encouragement == 0 {
+        return;
+    }
+
+    // Fire spreads up to four blocks upwards, but
+    // less likely the higher it has to spread.
+    let difficulty = 100 + (height - 1).max(0) * 100;
+    let mut chance = (encouragement + 40 + DIFFICULTY_ENCOURAGEMENT) / (age + 30);
+    if humid {
+        chance /= 2;
+    }
+
+    if chance > 0
+        && game.rng().gen_range(0, difficulty) <= chance
+        && !is_near_rain(game, world_id, pos)
+    {
+        let age = (age + game.rng().gen_range(0, 5) / 4).min(MAX_AGE);
+        let fire = fire_at(&game.worlds[world_id], pos, age);
+        set_fire_block(game, world, world_id, pos, fire);
+    }
+}
+
+/// When a block changes, schedules a tick for fire placed there.
+/// Fire next to the block is redrawn, and goes out if it's no
+/// longer attached to anything.
+#[fecs::event_handler]
+pub fn on_block_update_update_fire(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
+    if event.old == event.new {
+        return;
+    }
+
+    if event.new.kind() == BlockKind::Fire && event.old.kind() != BlockKind::Fire {
+        if light_nether_portal(game, world, event.world, event.pos) {
+            return;
+        }
+        if can_survive(&game.worlds[event.world], event.pos) {
+            schedule_fire_tick(game, event.world, event.pos);
+        } else {
+            set_fire_block(game, world, event.world, event.pos, BlockId::air());
+            return;
+        }
+    }
+
+    for pos in adjacent_blocks(event.pos) {
+        let fire = match game.block_at(event.world, pos) {
+            Some(block) if block.kind() == BlockKind::Fire => block,
+            _ => continue,
+        };
+        let state = &game.worlds[event.world];
+        let new = if can_survive(state, pos) {
+            fire_at(state, pos, fire.age_0_15().unwrap_or(0))
+        } else {
+            BlockId::air()
+        };
+        if new != fire {
+            set_fire_block(game, world, event.world, pos, new);
+        }
+    }
+}
+
+/// Lights fire with flint and steel, or primes TNT it's used on.
+/// The flint and steel takes damage unless the player is in
+/// creative mode.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_light_fire(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.stack.ty != Item::FlintAndSteel {
+        return;
+    }
+    let cause = BlockUpdateCause::Entity(event.player);
+    let pos = if game.block_at(event.world, event.pos).map_or(false, is_tnt) {
+        ignite_tnt(game, world, event.world, event.pos, cause);
+        event.pos
+    } else {
+        let pos = event.pos + event.face.placement_offset();
+        if game.block_at(event.world, pos) != Some(BlockId::air()) {
+            return;
+        }
+        let fire = fire_at(&game.worlds[event.world], pos, 0);
+        game.set_block_at(world, event.world, pos, fire, cause);
+        pos
+    };
+
+    let pitch = game.rng().gen_range(0.8, 1.2);
+    let sound = Sound::new("item.flintandsteel.use", SoundCategory::Blocks).pitch(pitch);
+    game.play_sound(world, event.world, Position::from(pos), &sound);
+
+    if *world.get::<Gamemode>(event.player) != Gamemode::Creative {
+        let held_item = world.get::<HeldItem>(event.player).0;
+        game.handle(
+            world,
+            ItemDamageEvent {
+                player: event.player,
+                slot: slot(Area::Hotbar, held_item),
+                damage_taken: 1,
+            },
+        );
+    }
+}
+
+/// Ages, spreads and puts out fire when it's ticked.
+#[fecs::event_handler]
+pub fn on_block_tick_burn_fire(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.block.kind() != BlockKind::Fire || !game.game_rules.do_fire_tick {
+        return;
+    }
+
+    burn(game, world, event.world, event.pos, event.block);
+}
+
+/// Sets fire to flammable blocks near lava when it's randomly
+/// ticked. Lava either looks for air next to flammable blocks
+/// up to three blocks above it, or for flammable blocks beside
+/// it with air above them.
+#[fecs::event_handler]
+pub fn on_block_tick_ignite_near_lava(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Random
+        || event.block.simplified_kind() != SimplifiedBlockKind::Lava
+        || !game.game_rules.do_fire_tick
+    {
+        return;
+    }
+
+    let attempts = game.rng().gen_range(0, 3);
+    if attempts > 0 {
+        let mut pos = event.pos;
+        for _ in 0..attempts {
+            let offset = {
+                let mut rng = game.rng();
+                BlockPosition::new(rng.gen_range(-1, 2), 1, rng.gen_range(-1, 2))
+            };
+            pos = pos + offset;
+
+            let state = &game.worlds[event.world];
+            let block = match state.block_at(pos) {
+                Some(block) => block,
+                None => return,
+            };
+            if block.is_air() {
+                if has_flammable_neighbor(state, pos) {
+                    let fire = fire_at(state, pos, 0);
+                    set_fire_block(game, world, event.world, pos, fire);
+                    return;
+                }
+            } else if block.is_solid() {
+                return;
+            }
+        }
+    } else {
+        for _ in 0..3 {
+            let offset = {
+                let mut rng = game.rng();
+                BlockPosition::new(rng.gen_range(-1, 2), 0, rng.gen_range(-1, 2))
+            };
+            let pos = event.pos + offset;
+
+            let state = &game.worlds[event.world];
+            let block = match state.block_at(pos) {
+                Some(block) => block,
+                None => return,
+            };
+            if state.block_at(pos.up()) == Some(BlockId::air()) && is_flammable(block) {
+                let fire = fire_at(state, pos.up(), 0);
+                set_fire_block(game, world, event.world, pos.up(), fire);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::items::ItemStack;
+    use feather_core::network::packets::Face;
+    use feather_server_entity::PrimedTnt;
+    use feather_test_framework::Test;
+    use fecs::{IntoQuery, Read};
+
+    fn tick_fire(test: &mut Test, pos: BlockPosition) -> BlockId {
+        let block = test.block(pos);
+        test.handle(
+            BlockTickEvent {
+                world: WorldId::MAIN,
+                pos,
+                block,
+                kind: BlockTickKind::Scheduled,
+            },
+            on_block_tick_burn_fire,
+        );
+        test.block(pos)
+    }
+
+    #[test]
+    fn flammability() {
+        assert!(is_flammable(BlockId::oak_planks()));
+        assert!(is_flammable(BlockId::white_wool()));
+        assert!(!is_flammable(BlockId::stone()));
+        assert!(!is_flammable(BlockId::oak_slab().with_waterlogged(true)));
+    }
+
+    #[test]
+    fn fire_goes_out_without_fuel() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 65, 0);
+        test.set_block(pos.down(), BlockId::stone());
+        test.set_block(pos, BlockId::fire().with_age_0_15(5));
+
+        assert_eq!(tick_fire(&mut test, pos), BlockId::air());
+    }
+
+    #[test]
+    fn fire_goes_out_without_support() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 65, 0);
+        test.set_block(pos, BlockId::fire());
+
+        assert_eq!(tick_fire(&mut test, pos), BlockId::air());
+    }
+
+    #[test]
+    fn fire_burns_forever_on_netherrack() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 65, 0);
+        test.set_block(pos.down(), BlockId::netherrack());
+        test.set_block(pos, BlockId::fire().with_age_0_15(MAX_AGE));
+
+        for _ in 0..10 {
+            assert_eq!(tick_fire(&mut test, pos).kind(), BlockKind::Fire);
+        }
+    }
+
+    #[test]
+    fn fire_drawn_on_flammable_blocks() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 65, 0);
+        test.set_block(pos + BlockPosition::new(0, 0, -1), BlockId::oak_planks());
+
+        let fire = fire_at(&test.game.worlds[WorldId::MAIN], pos, 0);
+        assert_eq!(fire.north_connected(), Some(true));
+        assert_eq!(fire.east_connected(), Some(false));
+
+        test.set_block(pos.down(), BlockId::stone());
+        let fire = fire_at(&test.game.worlds[WorldId::MAIN], pos, 0);
+        assert_eq!(fire, BlockId::fire());
+    }
+
+    #[test]
+    fn flint_and_steel_primes_tnt() {
+        let mut test = Test::new();
+        let player = test.player("", position!(3.0, 65.0, 0.0));
+        let pos = BlockPosition::new(0, 65, 0);
+        test.set_block(pos, BlockId::tnt());
+
+        let event = ItemUseOnBlockEvent {
+            player,
+            world: WorldId::MAIN,
+            pos,
+            face: Face::Top,
+            stack: ItemStack::new(Item::FlintAndSteel, 1),
+        };
+        test.handle(event, on_item_use_on_block_light_fire);
+
+        assert_eq!(test.game.block_at(WorldId::MAIN, pos), Some(BlockId::air()));
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, pos.up()),
+            Some(BlockId::air())
+        );
+        let primed = <Read<PrimedTnt>>::query()
+            .iter(test.world.inner())
+            .next()
+            .map(|tnt| tnt.igniter);
+        assert_eq!(primed, Some(Some(player)));
+    }
+}
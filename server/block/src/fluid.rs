@@ -0,0 +1,675 @@
+//! Water and lava, which flow out of their sources
+//! using scheduled block ticks.
+//!
+//! A fluid flows downwards when it can, and sideways otherwise,
+//! towards the nearest drop within a few blocks. It loses a level
+//! for each block it flows sideways, and flowing fluid which nothing
+//! feeds drains away. Water between two sources becomes a source
+//! itself. Lava touching water solidifies into obsidian, cobblestone
+//! or stone. Waterlogged blocks and water plants act as water sources.
+//!
+//! See https://minecraft.gamepedia.com/Fluid.
+
+use crate::schedule_block_tick;
+use feather_core::blocks::{BlockId, BlockKind, FacingCubic, SimplifiedBlockKind};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{BlockPosition, Dimension, Position};
+use feather_server_types::{
+    BlockTickEvent, BlockTickKind, BlockUpdateCause, BlockUpdateEvent, Game, Sound, TickPriority,
+    WorldId, WorldState,
+};
+use feather_server_util::adjacent_blocks;
+use fecs::World;
+use rand::Rng;
+
+/// The amount of fluid in a source or falling fluid.
+const FULL_AMOUNT: i32 = 8;
+
+/// The least amount of flowing lava which turns into
+/// cobblestone when it touches water.
+const MIN_COBBLESTONE_AMOUNT: i32 = 4;
+
+/// Distance to a drop when there is none nearby.
+const NO_DROP: u32 = 1000;
+
+const HORIZONTAL_DIRECTIONS: [FacingCubic; 4] = [
+    FacingCubic::North,
+    FacingCubic::East,
+    FacingCubic::South,
+    FacingCubic::West,
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Fluid {
+    Water,
+    Lava,
+}
+
+/// The fluid in a block.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct FluidState {
+    fluid: Fluid,
+    /// How much fluid the block holds, from 1 to 8.
+    amount: i32,
+    /// Whether the fluid is falling from the block above.
+    falling: bool,
+}
+
+impl FluidState {
+    fn source(fluid: Fluid) -> Self {
+        Self {
+            fluid,
+            amount: FULL_AMOUNT,
+            falling: false,
+        }
+    }
+
+    fn flowing(fluid: Fluid, amount: i32, falling: bool) -> Self {
+        Self {
+            fluid,
+            amount,
+            falling,
+        }
+    }
+
+    /// Returns the fluid in a block, if any.
+    fn of(block: BlockId) -> Option<Self> {
+        let fluid = match block.simplified_kind() {
+            SimplifiedBlockKind::Water => Fluid::Water,
+            SimplifiedBlockKind::Lava => Fluid::Lava,
+            SimplifiedBlockKind::Seagrass
+            | SimplifiedBlockKind::TallSeagrass
+            | SimplifiedBlockKind::Kelp
+            | SimplifiedBlockKind::KelpPlant
+            | SimplifiedBlockKind::BubbleColumn => return Some(Self::source(Fluid::Water)),
+            _ if block.waterlogged() == Some(true) => return Some(Self::source(Fluid::Water)),
+            _ => return None,
+        };
+
+        // Levels 1 to 7 are flowing fluid and levels
+        // from 8 are falling fluid.
+        Some(match block.water_level().unwrap_or(0) {
+            0 => Self::source(fluid),
+            level if level < FULL_AMOUNT => Self::flowing(fluid, FULL_AMOUNT - level, false),
+            _ => Self::flowing(fluid, FULL_AMOUNT, true),
+        })
+    }
+
+    fn is_source(self) -> bool {
+        self.amount == FULL_AMOUNT && !self.falling
+    }
+
+    /// Returns the fluid block holding this fluid.
+    fn block(self) -> BlockId {
+        let block = match self.fluid {
+            Fluid::Water => BlockId::water(),
+            Fluid::Lava => BlockId::lava(),
+        };
+        let level = if self.is_source() {
+            0
+        } else if self.falling {
+            FULL_AMOUNT
+        } else {
+            FULL_AMOUNT - self.amount
+        };
+        block.with_water_level(level)
+    }
+}
+
+/// Returns whether fluid can flow into a block, destroying
+/// it if it isn't air or fluid.
+fn can_hold_fluid(block: BlockId) -> bool {
+    // Flowing water doesn't waterlog blocks.
+    if block.has_waterlogged()
+        || matches!(
+            block.kind(),
+            BlockKind::EndGateway | BlockKind::StructureVoid
+        )
+    {
+        return false;
+    }
+
+    match block.simplified_kind() {
+        SimplifiedBlockKind::WoodenDoor
+        | SimplifiedBlockKind::IronDoor
+        | SimplifiedBlockKind::Sign
+        | SimplifiedBlockKind::WallSign
+        | SimplifiedBlockKind::Banner
+        | SimplifiedBlockKind::WallBanner
+        | SimplifiedBlockKind::Ladder
+        | SimplifiedBlockKind::SugarCane
+        | SimplifiedBlockKind::StonePressurePlate
+        | SimplifiedBlockKind::WoodenPressurePlate
+        | SimplifiedBlockKind::LightWeightedPressurePlate
+        | SimplifiedBlockKind::HeavyWeightedPressurePlate
+        | SimplifiedBlockKind::NetherPortal
+        | SimplifiedBlockKind::EndPortal
+        | SimplifiedBlockKind::BubbleColumn
+        | SimplifiedBlockKind::Seagrass
+        | SimplifiedBlockKind::TallSeagrass
+        | SimplifiedBlockKind::Kelp
+        | SimplifiedBlockKind::KelpPlant => false,
+        _ => !block.is_solid(),
+    }
+}
+
+/// Reads how one fluid flows in a world.
+struct Flow<'a> {
+    state: &'a WorldState,
+    fluid: Fluid,
+}
+
+impl<'a> Flow<'a> {
+    fn new(state: &'a WorldState, fluid: Fluid) -> Self {
+        Self { state, fluid }
+    }
+
+    fn is_lava_in_nether(&self) -> bool {
+        self.fluid == Fluid::Lava && self.state.dimension == Dimension::Nether
+    }
+
+    /// Ticks between each step of the fluid's flow.
+    fn tick_delay(&self) -> u64 {
+        match self.fluid {
+            Fluid::Water => 5,
+            Fluid::Lava if self.is_lava_in_nether() => 10,
+            Fluid::Lava => 30,
+        }
+    }
+
+    /// Amount of fluid lost for each block it flows sideways.
+    fn drop_off(&self) -> i32 {
+        match self.fluid {
+            Fluid::Lava if !self.is_lava_in_nether() => 2,
+            _ => 1,
+        }
+    }
+
+    /// How far the fluid looks for a drop to flow towards.
+    fn drop_search_distance(&self) -> u32 {
+        match self.fluid {
+            Fluid::Lava if !self.is_lava_in_nether() => 2,
+            _ => 4,
+        }
+    }
+
+    fn block(&self, pos: BlockPosition) -> BlockId {
+        // Fluids don't flow into unloaded chunks.
+        self.state.block_at(pos).unwrap_or_else(BlockId::barrier)
+    }
+
+    /// Returns the fluid at `pos` if it is this fluid.
+    fn fluid_at(&self, pos: BlockPosition) -> Option<FluidState> {
+        FluidState::of(self.block(pos)).filter(|state| state.fluid == self.fluid)
+    }
+
+    fn is_source(&self, pos: BlockPosition) -> bool {
+        self.fluid_at(pos).map_or(false, FluidState::is_source)
+    }
+
+    fn source_neighbors(&self, pos: BlockPosition) -> usize {
+        HORIZONTAL_DIRECTIONS
+            .iter()
+            .filter(|dir| self.is_source(pos + dir.offset()))
+            .count()
+    }
+
+    fn can_hold(&self, pos: BlockPosition) -> bool {
+        can_hold_fluid(self.block(pos))
+    }
+
+    /// Returns whether the fluid can fall into the block at `pos`.
+    fn is_drop(&self, pos: BlockPosition) -> bool {
+        self.fluid_at(pos).is_some() || self.can_hold(pos)
+    }
+
+    fn can_pass_through(&self, pos: BlockPosition) -> bool {
+        !self.is_source(pos) && self.can_hold(pos)
+    }
+
+    /// Returns the fluid the blocks around `pos` feed it with,
+    /// or `None` if they don't.
+    fn fed_state(&self, pos: BlockPosition) -> Option<FluidState> {
+        let mut amount = 0;
+        let mut sources = 0;
+        for dir in &HORIZONTAL_DIRECTIONS {
+            if let Some(neighbor) = self.fluid_at(pos + dir.offset()) {
+                if neighbor.is_source() {
+                    sources += 1;
+                }
+                amount = amount.max(neighbor.amount);
+            }
+        }
+
+        if self.fluid == Fluid::Water && sources >= 2 {
+            let below = pos.down();
+            if self.block(below).is_solid() || self.is_source(below) {
+                return Some(FluidState::source(self.fluid));
+            }
+        }
+
+        if self.fluid_at(pos.up()).is_some() {
+            return Some(FluidState::flowing(self.fluid, FULL_AMOUNT, true));
+        }
+
+        let amount = amount - self.drop_off();
+        if amount > 0 {
+            Some(FluidState::flowing(self.fluid, amount, false))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the fluid can flow into the block at
+    /// `pos` from the block next to it in direction `dir`.
+    fn can_flow_into(&self, pos: BlockPosition, dir: FacingCubic, new: FluidState) -> bool {
+        let replaceable = match FluidState::of(self.block(pos)) {
+            None => true,
+            Some(existing) => match (existing.fluid, new.fluid) {
+                (Fluid::Water, Fluid::Lava) => dir == FacingCubic::Down,
+                (Fluid::Lava, Fluid::Water) => existing.amount >= MIN_COBBLESTONE_AMOUNT,
+                _ => false,
+            },
+        };
+        replaceable && self.can_hold(pos)
+    }
+
+    /// Returns the distance from `pos` to the nearest drop,
+    /// not going back in direction `from`.
+    fn drop_distance(&self, pos: BlockPosition, depth: u32, from: FacingCubic) -> u32 {
+        let mut distance = NO_DROP;
+        for &dir in HORIZONTAL_DIRECTIONS.iter().filter(|&&dir| dir != from) {
+            let next = pos + dir.offset();
+            if !self.can_pass_through(next) {
+                continue;
+            }
+            if self.is_drop(next.down()) {
+                return depth;
+            }
+            if depth < self.drop_search_distance() {
+                distance = distance.min(self.drop_distance(next, depth + 1, dir.opposite()));
+            }
+        }
+        distance
+    }
+
+    /// Returns the directions in which the fluid at `pos` flows
+    /// sideways, which are those leading to the nearest drops,
+    /// with the fluid flowing into each.
+    fn sideways_flow(&self, pos: BlockPosition) -> Vec<(FacingCubic, FluidState)> {
+        let mut nearest = NO_DROP;
+        let mut flow = Vec::new();
+        for &dir in &HORIZONTAL_DIRECTIONS {
+            let next = pos + dir.offset();
+            if !self.can_pass_through(next) {
+                continue;
+            }
+            let new = match self.fed_state(next) {
+                Some(new) => new,
+                None => continue,
+            };
+
+            let distance = if self.is_drop(next.down()) {
+                0
+            } else {
+                self.drop_distance(next, 1, dir.opposite())
+            };
+            if distance < nearest {
+                flow.clear();
+                nearest = distance;
+            }
+            if distance == nearest {
+                flow.push((dir, new));
+            }
+        }
+        flow
+    }
+}
+
+/// Updates the fluid at `pos`, then lets it flow.
+fn flow(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    current: FluidState,
+) {
+    let mut state = current;
+    if !current.is_source() {
+        match Flow::new(&game.worlds[world_id], current.fluid).fed_state(pos) {
+            None => {
+                game.set_block_at(world, world_id, pos, BlockId::air(), BlockUpdateCause::Tick);
+                return;
+            }
+            Some(new) if new != current => {
+                game.set_block_at(world, world_id, pos, new.block(), BlockUpdateCause::Tick);
+                state = new;
+            }
+            Some(_) => (),
+        }
+    }
+
+    let below = pos.down();
+    let (down, sideways) = {
+        let flow = Flow::new(&game.worlds[world_id], state.fluid);
+        match flow.fed_state(below) {
+            Some(new) if flow.can_flow_into(below, FacingCubic::Down, new) => {
+                // Fluid surrounded by sources keeps spreading
+                // sideways while it falls.
+                (Some(new), flow.source_neighbors(pos) >= 3)
+            }
+            _ => (None, state.is_source() || !flow.is_drop(below)),
+        }
+    };
+
+    if let Some(new) = down {
+        flow_into(game, world, world_id, below, FacingCubic::Down, new);
+    }
+    if sideways {
+        flow_sideways(game, world, world_id, pos, state);
+    }
+}
+
+fn flow_sideways(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    state: FluidState,
+) {
+    let targets: Vec<(BlockPosition, FacingCubic, FluidState)> = {
+        let flow = Flow::new(&game.worlds[world_id], state.fluid);
+        let amount = if state.falling {
+            FULL_AMOUNT - 1
+        } else {
+            state.amount - flow.drop_off()
+        };
+        if amount <= 0 {
+            return;
+        }
+
+        flow.sideways_flow(pos)
+            .into_iter()
+            .map(|(dir, new)| (pos + dir.offset(), dir, new))
+            .filter(|&(target, dir, new)| flow.can_flow_into(target, dir, new))
+            .collect()
+    };
+
+    for (target, dir, new) in targets {
+        flow_into(game, world, world_id, target, dir, new);
+    }
+}
+
+/// Places fluid flowing in direction `dir` at `pos`.
+fn flow_into(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    dir: FacingCubic,
+    new: FluidState,
+) {
+    let target = game.block_at(world_id, pos).unwrap_or_else(BlockId::air);
+    match FluidState::of(target) {
+        Some(existing) => {
+            if new.fluid == Fluid::Lava
+                && existing.fluid == Fluid::Water
+                && dir == FacingCubic::Down
+            {
+                game.set_block_at(
+                    world,
+                    world_id,
+                    pos,
+                    BlockId::stone(),
+                    BlockUpdateCause::Tick,
+                );
+                play_fizz(game, world, world_id, pos);
+                return;
+            }
+        }
+        // Water washes away the blocks it flows into,
+        // while lava burns them.
+        None if !target.is_air() => match new.fluid {
+            Fluid::Water => {
+                game.set_block_at(
+                    world,
+                    world_id,
+                    pos,
+                    BlockId::air(),
+                    BlockUpdateCause::Unsupported,
+                );
+            }
+            Fluid::Lava => play_fizz(game, world, world_id, pos),
+        },
+        None => (),
+    }
+
+    game.set_block_at(world, world_id, pos, new.block(), BlockUpdateCause::Tick);
+}
+
+/// Turns lava touching water into obsidian if it is a source
+/// and into cobblestone otherwise. Returns whether it solidified.
+fn solidify_lava(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    lava: FluidState,
+) -> bool {
+    // Water below lava doesn't solidify it.
+    let touches_water = adjacent_blocks(pos)
+        .into_iter()
+        .filter(|&adjacent| adjacent != pos.down())
+        .filter_map(|adjacent| game.block_at(world_id, adjacent).and_then(FluidState::of))
+        .any(|state| state.fluid == Fluid::Water);
+    if !touches_water {
+        return false;
+    }
+
+    let block = if lava.is_source() {
+        BlockId::obsidian()
+    } else if lava.amount >= MIN_COBBLESTONE_AMOUNT {
+        BlockId::cobblestone()
+    } else {
+        return false;
+    };
+    game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Tick);
+    play_fizz(game, world, world_id, pos);
+    true
+}
+
+fn play_fizz(game: &Game, world: &World, world_id: WorldId, pos: BlockPosition) {
+    let pitch = {
+        let mut rng = game.rng();
+        2.6 + (rng.gen_range(0.0, 1.0) - rng.gen_range(0.0, 1.0)) * 0.8
+    };
+    let sound = Sound::new("block.lava.extinguish", SoundCategory::Blocks)
+        .volume(0.5)
+        .pitch(pitch);
+    game.play_sound(world, world_id, Position::from(pos), &sound);
+}
+
+/// When a block changes, schedules a tick for the fluid in and next
+/// to it, and solidifies lava which now touches water. Breaking a
+/// waterlogged block leaves its water behind.
+#[fecs::event_handler]
+pub fn on_block_update_update_fluids(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
+    if event.old == event.new {
+        return;
+    }
+
+    if event.old.waterlogged() == Some(true)
+        && event.new.is_air()
+        && event.cause != BlockUpdateCause::Piston
+    {
+        game.set_block_at(world, event.world, event.pos, BlockId::water(), event.cause);
+        return;
+    }
+
+    let mut positions = vec![event.pos];
+    positions.extend(adjacent_blocks(event.pos));
+    for pos in positions {
+        let state = match game.block_at(event.world, pos).and_then(FluidState::of) {
+            Some(state) => state,
+            None => continue,
+        };
+        if state.fluid == Fluid::Lava && solidify_lava(game, world, event.world, pos, state) {
+            continue;
+        }
+
+        let delay = Flow::new(&game.worlds[event.world], state.fluid).tick_delay();
+        schedule_block_tick(game, event.world, pos, delay, TickPriority::NORMAL);
+    }
+}
+
+/// Lets fluids flow when their scheduled ticks are due.
+#[fecs::event_handler]
+pub fn on_block_tick_flow_fluids(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Scheduled {
+        return;
+    }
+
+    if let Some(state) = FluidState::of(event.block) {
+        flow(game, world, event.world, event.pos, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    /// Creates a test with a stone floor at y = 64.
+    fn test_with_floor() -> Test {
+        let mut test = Test::new();
+        for x in -6..=8 {
+            for z in -6..=8 {
+                test.set_block(BlockPosition::new(x, 64, z), BlockId::stone());
+            }
+        }
+        test
+    }
+
+    fn tick(test: &mut Test, pos: BlockPosition) {
+        let block = test.block(pos);
+        test.handle(
+            BlockTickEvent {
+                world: WorldId::MAIN,
+                pos,
+                block,
+                kind: BlockTickKind::Scheduled,
+            },
+            on_block_tick_flow_fluids,
+        );
+    }
+
+    #[test]
+    fn water_flows_sideways() {
+        let mut test = test_with_floor();
+        let source = BlockPosition::new(1, 65, 1);
+        test.set_block(source, BlockId::water());
+        tick(&mut test, source);
+
+        for dir in &HORIZONTAL_DIRECTIONS {
+            let pos = source + dir.offset();
+            assert_eq!(test.block(pos), BlockId::water().with_water_level(1));
+        }
+        assert_eq!(test.block(source.down()), BlockId::stone());
+    }
+
+    #[test]
+    fn water_flows_down() {
+        let mut test = test_with_floor();
+        let source = BlockPosition::new(1, 66, 1);
+        test.set_block(source, BlockId::water());
+        test.set_block(source.down(), BlockId::torch());
+        tick(&mut test, source);
+
+        assert_eq!(
+            test.block(source.down()),
+            BlockId::water().with_water_level(8)
+        );
+        assert_eq!(
+            test.block(source + BlockPosition::new(1, 0, 0)),
+            BlockId::air()
+        );
+    }
+
+    #[test]
+    fn flowing_water_drains() {
+        let mut test = test_with_floor();
+        let pos = BlockPosition::new(1, 65, 1);
+        test.set_block(pos, BlockId::water().with_water_level(3));
+        tick(&mut test, pos);
+
+        assert_eq!(test.block(pos), BlockId::air());
+    }
+
+    #[test]
+    fn infinite_water_source() {
+        let mut test = test_with_floor();
+        let pos = BlockPosition::new(1, 65, 1);
+        test.set_block(pos + BlockPosition::new(-1, 0, 0), BlockId::water());
+        test.set_block(pos + BlockPosition::new(1, 0, 0), BlockId::water());
+        test.set_block(pos, BlockId::water().with_water_level(1));
+        tick(&mut test, pos);
+
+        assert_eq!(test.block(pos), BlockId::water());
+    }
+
+    #[test]
+    fn lava_solidifies_next_to_water() {
+        let mut test = test_with_floor();
+        let source = BlockPosition::new(0, 65, 0);
+        let flowing = BlockPosition::new(0, 65, 3);
+        test.set_block(source, BlockId::lava());
+        test.set_block(flowing, BlockId::lava().with_water_level(2));
+
+        for &lava in &[source, flowing] {
+            let water = lava + BlockPosition::new(1, 0, 0);
+            test.set_block(water, BlockId::water());
+            test.handle(
+                BlockUpdateEvent {
+                    world: WorldId::MAIN,
+                    pos: water,
+                    old: BlockId::air(),
+                    new: BlockId::water(),
+                    cause: BlockUpdateCause::Unknown,
+                },
+                on_block_update_update_fluids,
+            );
+        }
+
+        assert_eq!(test.block(source), BlockId::obsidian());
+        assert_eq!(test.block(flowing), BlockId::cobblestone());
+    }
+
+    #[test]
+    fn lava_flowing_into_water() {
+        let mut test = test_with_floor();
+        let water = BlockPosition::new(1, 65, 1);
+        test.set_block(water, BlockId::water());
+        test.set_block(water.up(), BlockId::lava());
+        tick(&mut test, water.up());
+
+        assert_eq!(test.block(water), BlockId::stone());
+    }
+
+    #[test]
+    fn broken_waterlogged_block_leaves_water() {
+        let mut test = test_with_floor();
+        let pos = BlockPosition::new(1, 65, 1);
+        test.handle(
+            BlockUpdateEvent {
+                world: WorldId::MAIN,
+                pos,
+                old: BlockId::oak_stairs().with_waterlogged(true),
+                new: BlockId::air(),
+                cause: BlockUpdateCause::Unknown,
+            },
+            on_block_update_update_fluids,
+        );
+
+        assert_eq!(test.block(pos), BlockId::water());
+    }
+}
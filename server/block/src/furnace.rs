@@ -0,0 +1,492 @@
+//! Furnaces, which smelt items using fuel and keep their
+//! contents and smelting progress while their chunk is unloaded.
+
+use crate::{Layout, ShouldReplace, WindowViewers};
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::blocks::BlockKind;
+use feather_core::inventory::{Area, SlotIndex, Window};
+use feather_core::items::ItemStack;
+use feather_core::network::packets::OpenWindow;
+use feather_core::text::TextRoot;
+use feather_core::util::BlockPosition;
+use feather_server_types::{
+    crafting_remainder, BlockEntityLoaderRegistration, BlockSerializer, BlockUpdateCause,
+    CookingKind, Game, InteractionHandler, Inventory, InventoryUpdateEvent, RecipeRegistry,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World, Write};
+
+/// The ingredient, fuel and output slots, numbered 0, 1 and 2.
+const LAYOUT: Layout = &[
+    (Area::FurnaceIngredient, 1),
+    (Area::FurnaceFuel, 1),
+    (Area::FurnaceOutput, 1),
+];
+
+// Window properties of furnaces.
+const PROPERTY_BURN_TIME: i16 = 0;
+const PROPERTY_FUEL_TIME: i16 = 1;
+const PROPERTY_COOK_TIME: i16 = 2;
+const PROPERTY_COOK_TIME_TOTAL: i16 = 3;
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Furnace,
+});
+
+/// Component for furnaces, storing the kind
+/// of recipes they cook.
+pub struct Furnace(pub CookingKind);
+
+/// The smelting progress of a furnace, in ticks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FurnaceProgress {
+    /// Ticks until the current fuel item is used up.
+    pub burn_time: i16,
+    /// Ticks the current fuel item burns for in total.
+    /// Not saved, but computed from the fuel slot on load.
+    pub fuel_time: i16,
+    /// Ticks the current item has been smelting for.
+    pub cook_time: i16,
+    /// Ticks needed to smelt the current item.
+    pub cook_time_total: i16,
+}
+
+/// Experience gained from the items a furnace smelted.
+///
+/// Not saved, as in vanilla it is computed from the recipes used,
+/// which aren't supported by the block entity format yet.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FurnaceExperience(pub f32);
+
+/// Creates a furnace.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with(pos, Inventory::furnace(), FurnaceProgress::default())
+}
+
+/// Creates a furnace with the given inventory and progress.
+pub fn create_with(
+    pos: BlockPosition,
+    inventory: Inventory,
+    progress: FurnaceProgress,
+) -> EntityBuilder {
+    crate::base(pos)
+        .with(Furnace(CookingKind::Smelting))
+        .with(inventory)
+        .with(progress)
+        .with(FurnaceExperience::default())
+        .with(WindowViewers::default())
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    let base = crate::serialize_base(accessor);
+    let items = crate::serialize_inventory(&*accessor.get::<Inventory>(), LAYOUT);
+    let progress = *accessor.get::<FurnaceProgress>();
+
+    BlockEntityData {
+        base,
+        kind: BlockEntityKind::Furnace {
+            items,
+            burn_time: progress.burn_time,
+            cook_time: progress.cook_time,
+            cook_time_total: progress.cook_time_total,
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let (slots, mut progress) = match data.kind {
+        BlockEntityKind::Furnace {
+            items,
+            burn_time,
+            cook_time,
+            cook_time_total,
+        } => (
+            items,
+            FurnaceProgress {
+                burn_time,
+                fuel_time: 0,
+                cook_time,
+                cook_time_total,
+            },
+        ),
+        _ => bail!("not a furnace"),
+    };
+
+    let inventory = Inventory::furnace();
+    crate::load_inventory(&inventory, LAYOUT, &slots);
+
+    progress.fuel_time = inventory
+        .item_at(Area::FurnaceFuel, 0)?
+        .and_then(|fuel| fuel.ty.fuel_time())
+        .map_or(0, |time| time as i16);
+
+    Ok(create_with(pos, inventory, progress))
+}
+
+/// The changes made by a tick of a furnace.
+#[derive(Debug, Default)]
+struct Changes {
+    /// Slots whose items changed.
+    slots: Vec<SlotIndex>,
+    /// Experience gained by smelting an item.
+    experience: f32,
+}
+
+/// Advances the smelting in a furnace by a tick, using up
+/// fuel when the current fuel item has burnt and an item
+/// can be smelted.
+fn tick(
+    kind: CookingKind,
+    progress: &mut FurnaceProgress,
+    inventory: &Inventory,
+    recipes: &RecipeRegistry,
+) -> Changes {
+    let mut changes = Changes::default();
+    if progress.burn_time > 0 {
+        progress.burn_time -= 1;
+    }
+
+    let ingredient = inventory.item_at(Area::FurnaceIngredient, 0).unwrap();
+    let output = inventory.item_at(Area::FurnaceOutput, 0).unwrap();
+    let recipe = ingredient
+        .and_then(|ingredient| recipes.match_cooking(kind, ingredient))
+        .filter(|recipe| fits(output, recipe.result));
+
+    let (ingredient, recipe) = match (ingredient, recipe) {
+        (Some(ingredient), Some(recipe)) => (ingredient, recipe),
+        _ => {
+            // Progress is lost while nothing can be smelted.
+            progress.cook_time = 0;
+            return changes;
+        }
+    };
+
+    if progress.burn_time == 0 {
+        let fuel = inventory.item_at(Area::FurnaceFuel, 0).unwrap();
+        if let Some((fuel, time)) = fuel.and_then(|fuel| Some((fuel, fuel.ty.fuel_time()?))) {
+            let time = (time / kind.fuel_rate()) as i16;
+            progress.burn_time = time;
+            progress.fuel_time = time;
+
+            let remaining = match crafting_remainder(fuel.ty) {
+                Some(remainder) if fuel.amount == 1 => ItemStack::new(remainder, 1),
+                _ => fuel.of_amount(fuel.amount - 1),
+            };
+            inventory
+                .set_item_at(Area::FurnaceFuel, 0, remaining)
+                .unwrap();
+            changes.slots.push(slot(Area::FurnaceFuel));
+        }
+    }
+
+    if progress.burn_time == 0 {
+        // Progress is slowly lost while the furnace is out of fuel.
+        progress.cook_time = (progress.cook_time - 2).max(0);
+        return changes;
+    }
+
+    progress.cook_time_total = recipe.cooking_time as i16;
+    progress.cook_time += 1;
+    if progress.cook_time >= progress.cook_time_total {
+        progress.cook_time = 0;
+
+        let output = match output {
+            Some(output) => output.of_amount(output.amount + recipe.result.amount),
+            None => recipe.result,
+        };
+        inventory
+            .set_item_at(
+                Area::FurnaceIngredient,
+                0,
+                ingredient.of_amount(ingredient.amount - 1),
+            )
+            .unwrap();
+        inventory
+            .set_item_at(Area::FurnaceOutput, 0, output)
+            .unwrap();
+        changes.slots.push(slot(Area::FurnaceIngredient));
+        changes.slots.push(slot(Area::FurnaceOutput));
+        changes.experience = recipe.experience;
+    }
+
+    changes
+}
+
+/// Returns whether a result can be added to the output slot.
+fn fits(output: Option<ItemStack>, result: ItemStack) -> bool {
+    match output {
+        Some(output) => {
+            output.eq_ignore_amount(result)
+                && output.amount + result.amount <= output.ty.stack_size() as u8
+        }
+        None => true,
+    }
+}
+
+fn slot(area: Area) -> SlotIndex {
+    SlotIndex { area, slot: 0 }
+}
+
+/// System which smelts items in furnaces, lighting them
+/// while they burn and sending their progress to the players
+/// who have them open.
+#[fecs::system]
+pub fn tick_furnaces(game: &mut Game, world: &mut World) {
+    let mut updates = vec![];
+    for (furnace, (kind, mut progress, inventory, mut experience)) in <(
+        Read<Furnace>,
+        Write<FurnaceProgress>,
+        Read<Inventory>,
+        Write<FurnaceExperience>,
+    )>::query()
+    .iter_entities_mut(world.inner_mut())
+    {
+        let old = *progress;
+        let changes = tick(kind.0, &mut progress, &inventory, &game.recipes);
+        experience.0 += changes.experience;
+
+        if *progress != old || !changes.slots.is_empty() {
+            updates.push((furnace, old, *progress, changes.slots));
+        }
+    }
+
+    for (furnace, old, new, slots) in updates {
+        crate::send_window_updates(
+            world,
+            furnace,
+            LAYOUT,
+            &slots,
+            &changed_properties(Some(old), new).collect::<Vec<_>>(),
+        );
+
+        let burning = new.burn_time > 0;
+        if burning != (old.burn_time > 0) {
+            let world_id = game.world_of(world, furnace);
+            let pos = *world.get::<BlockPosition>(furnace);
+            if let Some(block) = game.block_at(world_id, pos) {
+                game.set_block_at(
+                    world,
+                    world_id,
+                    pos,
+                    block.with_lit(burning),
+                    BlockUpdateCause::Tick,
+                );
+            }
+        }
+
+        if !slots.is_empty() {
+            game.handle(
+                world,
+                InventoryUpdateEvent {
+                    slots: slots.into_iter().collect(),
+                    entity: furnace,
+                },
+            );
+        }
+    }
+}
+
+/// Returns the window properties which differ from
+/// `old`, or all properties if it is `None`.
+fn changed_properties(
+    old: Option<FurnaceProgress>,
+    new: FurnaceProgress,
+) -> impl Iterator<Item = (i16, i16)> {
+    let properties = |progress: FurnaceProgress| {
+        [
+            (PROPERTY_BURN_TIME, progress.burn_time),
+            (PROPERTY_FUEL_TIME, progress.fuel_time),
+            (PROPERTY_COOK_TIME, progress.cook_time),
+            (PROPERTY_COOK_TIME_TOTAL, progress.cook_time_total),
+        ]
+    };
+    let old = old.map(properties);
+
+    (0..4).filter_map(move |index| {
+        let property = properties(new)[index];
+        match old {
+            Some(old) if old[index] == property => None,
+            _ => Some(property),
+        }
+    })
+}
+
+/// Handler for player right clicking on furnaces.
+pub struct FurnaceInteraction;
+inventory::submit!(Box::new(FurnaceInteraction) as Box<dyn InteractionHandler>);
+
+impl InteractionHandler for FurnaceInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let furnace = match game.worlds[world_id].block_entities.get(&pos).copied() {
+            Some(furnace) if world.has::<Furnace>(furnace) => furnace,
+            _ => return,
+        };
+
+        let progress = *world.get::<FurnaceProgress>(furnace);
+        crate::open_window(
+            game,
+            world,
+            player,
+            furnace,
+            Window::furnace(player, furnace),
+            OpenWindow {
+                window_id,
+                window_type: String::from("minecraft:furnace"),
+                window_title: TextRoot::from("Furnace").into(),
+                number_of_slots: 3,
+                entity_id: None,
+            },
+            LAYOUT,
+        );
+        crate::send_window_updates(
+            world,
+            furnace,
+            LAYOUT,
+            &[],
+            &changed_properties(None, progress).collect::<Vec<_>>(),
+        );
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        BlockKind::Furnace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::items::Item;
+    use feather_server_types::Recipe;
+
+    fn recipes() -> RecipeRegistry {
+        let mut recipes = RecipeRegistry::default();
+        let json = br#"{
+            "type": "smelting",
+            "ingredient": { "item": "minecraft:iron_ore" },
+            "result": "minecraft:iron_ingot",
+            "experience": 0.7,
+            "cookingtime": 4
+        }"#;
+        recipes.add(
+            Recipe::from_json("minecraft:iron_ingot", json, &Default::default())
+                .unwrap()
+                .unwrap(),
+        );
+        recipes
+    }
+
+    fn item(inventory: &Inventory, area: Area) -> Option<ItemStack> {
+        inventory.item_at(area, 0).unwrap()
+    }
+
+    #[test]
+    fn smelt_with_fuel() {
+        let recipes = recipes();
+        let inventory = Inventory::furnace();
+        let mut progress = FurnaceProgress::default();
+
+        // Nothing happens without fuel.
+        inventory
+            .set_item_at(Area::FurnaceIngredient, 0, ItemStack::new(Item::IronOre, 2))
+            .unwrap();
+        let changes = tick(CookingKind::Smelting, &mut progress, &inventory, &recipes);
+        assert!(changes.slots.is_empty());
+        assert_eq!(progress, FurnaceProgress::default());
+
+        inventory
+            .set_item_at(Area::FurnaceFuel, 0, ItemStack::new(Item::Stick, 2))
+            .unwrap();
+        let changes = tick(CookingKind::Smelting, &mut progress, &inventory, &recipes);
+        assert_eq!(changes.slots, vec![slot(Area::FurnaceFuel)]);
+        assert_eq!(
+            item(&inventory, Area::FurnaceFuel),
+            Some(ItemStack::new(Item::Stick, 1))
+        );
+        assert_eq!(progress.burn_time, 100);
+        assert_eq!(progress.fuel_time, 100);
+        assert_eq!((progress.cook_time, progress.cook_time_total), (1, 4));
+
+        let mut experience = 0.0;
+        for _ in 0..7 {
+            experience +=
+                tick(CookingKind::Smelting, &mut progress, &inventory, &recipes).experience;
+        }
+        assert_eq!(item(&inventory, Area::FurnaceIngredient), None);
+        assert_eq!(
+            item(&inventory, Area::FurnaceOutput),
+            Some(ItemStack::new(Item::IronIngot, 2))
+        );
+        assert!((experience - 1.4).abs() < 0.001);
+
+        // With nothing left to smelt, fuel burns out without
+        // another fuel item being used up.
+        assert_eq!(progress.burn_time, 93);
+        tick(CookingKind::Smelting, &mut progress, &inventory, &recipes);
+        assert_eq!(progress.burn_time, 92);
+        assert_eq!(progress.cook_time, 0);
+        assert_eq!(
+            item(&inventory, Area::FurnaceFuel),
+            Some(ItemStack::new(Item::Stick, 1))
+        );
+    }
+
+    #[test]
+    fn full_output_and_fuel_remainder() {
+        let recipes = recipes();
+        let inventory = Inventory::furnace();
+        let mut progress = FurnaceProgress::default();
+
+        inventory
+            .set_item_at(Area::FurnaceIngredient, 0, ItemStack::new(Item::IronOre, 1))
+            .unwrap();
+        inventory
+            .set_item_at(Area::FurnaceOutput, 0, ItemStack::new(Item::IronIngot, 64))
+            .unwrap();
+        inventory
+            .set_item_at(Area::FurnaceFuel, 0, ItemStack::new(Item::LavaBucket, 1))
+            .unwrap();
+        tick(CookingKind::Smelting, &mut progress, &inventory, &recipes);
+        assert_eq!(progress, FurnaceProgress::default());
+
+        // Lava buckets leave a bucket behind.
+        inventory.remove_item_at(Area::FurnaceOutput, 0).unwrap();
+        tick(CookingKind::Smelting, &mut progress, &inventory, &recipes);
+        assert_eq!(progress.burn_time, 20000);
+        assert_eq!(
+            item(&inventory, Area::FurnaceFuel),
+            Some(ItemStack::new(Item::Bucket, 1))
+        );
+    }
+
+    #[test]
+    fn window_properties() {
+        let old = FurnaceProgress {
+            burn_time: 10,
+            fuel_time: 300,
+            cook_time: 5,
+            cook_time_total: 200,
+        };
+        let new = FurnaceProgress {
+            burn_time: 9,
+            cook_time: 6,
+            ..old
+        };
+        assert_eq!(
+            changed_properties(Some(old), new).collect::<Vec<_>>(),
+            vec![(PROPERTY_BURN_TIME, 9), (PROPERTY_COOK_TIME, 6)]
+        );
+        assert_eq!(changed_properties(None, new).count(), 4);
+    }
+}
@@ -1,9 +1,15 @@
-use crate::{chest, ShouldReplace};
+use crate::{
+    beacon, brewing_stand, chest, comparator, conduit, furnace, jukebox, shulker_box, sign,
+    spawner, ShouldReplace,
+};
 use ahash::AHashMap;
 use feather_core::blocks::BlockKind;
-use feather_core::util::BlockPosition;
-use feather_server_types::{BlockEntity, BlockUpdateEvent, EntitySpawnEvent, Game};
-use fecs::{EntityBuilder, World};
+use feather_core::util::{BlockPosition, Position};
+use feather_server_entity::drops::drop_item;
+use feather_server_types::{
+    BlockEntity, BlockUpdateEvent, ChunkUnloadEvent, EntitySpawnEvent, Game, Inventory,
+};
+use fecs::{Entity, EntityBuilder, World};
 use once_cell::sync::Lazy;
 
 type BlockEntityCreator = fn(BlockPosition) -> EntityBuilder;
@@ -13,10 +19,56 @@ static BLOCK_ENTITY_MAP: Lazy<AHashMap<BlockKind, BlockEntityCreator>> = Lazy::n
     let mut map: AHashMap<_, fn(BlockPosition) -> EntityBuilder> = AHashMap::new();
 
     map.insert(BlockKind::Chest, chest::create);
+    map.insert(BlockKind::Furnace, furnace::create);
+    map.insert(BlockKind::BrewingStand, brewing_stand::create);
+    map.insert(BlockKind::Sign, sign::create);
+    map.insert(BlockKind::WallSign, sign::create);
+    map.insert(BlockKind::Spawner, spawner::create);
+    map.insert(BlockKind::Comparator, comparator::create);
+    map.insert(BlockKind::Jukebox, jukebox::create);
+    map.insert(BlockKind::Beacon, beacon::create);
+    map.insert(BlockKind::Conduit, conduit::create);
+    for &kind in shulker_box::KINDS.iter() {
+        map.insert(kind, shulker_box::create);
+    }
 
     map
 });
 
+/// When a block entity with an inventory is about to
+/// be replaced, e.g. because its block was broken,
+/// drops its contents.
+///
+/// Must run before `on_block_update_create_block_entity`.
+#[fecs::event_handler]
+pub fn on_block_break_drop_contents(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
+    let entity = match game.worlds[event.world]
+        .block_entities
+        .get(&event.pos)
+        .copied()
+    {
+        Some(entity) => entity,
+        None => return,
+    };
+    if let Some(should_replace) = world.try_get::<ShouldReplace>(entity).map(|x| x.0) {
+        if !should_replace(event.old, event.new) {
+            return;
+        }
+    }
+
+    let items: Vec<_> = match world.try_get::<Inventory>(entity) {
+        Some(inventory) => inventory
+            .iter_mut()
+            .filter_map(|mut guard| guard.take())
+            .collect(),
+        None => return,
+    };
+    let pos = Position::from(event.pos);
+    for item in items {
+        drop_item(game, world, event.world, item, pos);
+    }
+}
+
 /// When a block is created, and there is a block entity kind
 /// associated with it, creates the block entity. Additionally,
 /// removes any old block entity, if it existed.
@@ -26,7 +78,8 @@ pub fn on_block_update_create_block_entity(
     game: &mut Game,
     world: &mut World,
 ) {
-    if let Some(entity) = game.block_entities.get(&event.pos).copied() {
+    let state = &mut game.worlds[event.world];
+    if let Some(entity) = state.block_entities.get(&event.pos).copied() {
         // Determine whether we should replace the entity
         // or keep the existing block entity.
         if let Some(should_replace) = world.try_get::<ShouldReplace>(entity).map(|x| x.0) {
@@ -34,13 +87,13 @@ pub fn on_block_update_create_block_entity(
                 return; // should keep existing block entity; block entities remain unchanged
             }
         }
-        game.block_entities.remove(&event.pos);
+        state.block_entities.remove(&event.pos);
         game.despawn(entity, world);
     }
 
     if let Some(init) = BLOCK_ENTITY_MAP.get(&event.new.kind()) {
         // Spawn block entity
-        let entity = init(event.pos).build().spawn_in(world);
+        let entity = init(event.pos).with(event.world).build().spawn_in(world);
 
         game.handle(world, EntitySpawnEvent { entity });
     }
@@ -54,7 +107,33 @@ pub fn on_block_entity_create_insert_to_map(
 ) {
     if let Some(pos) = world.try_get::<BlockPosition>(event.entity) {
         if world.has::<BlockEntity>(event.entity) {
-            game.block_entities.insert(*pos, event.entity);
+            let world_id = game.world_of(world, event.entity);
+            game.worlds[world_id]
+                .block_entities
+                .insert(*pos, event.entity);
         }
     }
 }
+
+/// When a chunk is unloaded, despawns its block entities.
+/// They are saved along with the chunk beforehand.
+#[fecs::event_handler]
+pub fn on_chunk_unload_despawn_block_entities(
+    event: &ChunkUnloadEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let entities: Vec<Entity> = game.worlds[event.world]
+        .chunk_entities
+        .entities_in_chunk(event.chunk)
+        .iter()
+        .copied()
+        .filter(|&entity| world.has::<BlockEntity>(entity))
+        .collect();
+
+    for entity in entities {
+        let pos = *world.get::<BlockPosition>(entity);
+        game.worlds[event.world].block_entities.remove(&pos);
+        game.despawn(entity, world);
+    }
+}
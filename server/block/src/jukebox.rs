@@ -0,0 +1,311 @@
+//! Jukeboxes, which play the music disc players insert into them
+//! until it is ejected by right clicking the jukebox again or by
+//! breaking it. Comparators read which disc a jukebox holds.
+//!
+//! See https://minecraft.gamepedia.com/Jukebox.
+
+use crate::ShouldReplace;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::anvil::player::InventorySlot;
+use feather_core::blocks::BlockKind;
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::Effect;
+use feather_core::util::{vec3, BlockPosition, Position};
+use feather_server_entity::drops::drop_item;
+use feather_server_entity::{consume_held_item, InventoryExt};
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, BlockUpdateCause, BlockUpdateEvent, Game,
+    InteractionHandler, Inventory, WorldId,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, World};
+
+/// World event which starts playing the music disc with
+/// the item ID given as data, or stops playing if it is 0.
+const RECORD_EFFECT: i32 = 1010;
+
+/// Music discs, in the order of the signal
+/// comparators read from jukeboxes holding them.
+const MUSIC_DISCS: [Item; 12] = [
+    Item::MusicDisc13,
+    Item::MusicDiscCat,
+    Item::MusicDiscBlocks,
+    Item::MusicDiscChirp,
+    Item::MusicDiscFar,
+    Item::MusicDiscMall,
+    Item::MusicDiscMellohi,
+    Item::MusicDiscStal,
+    Item::MusicDiscStrad,
+    Item::MusicDiscWard,
+    Item::MusicDisc11,
+    Item::MusicDiscWait,
+];
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Jukebox,
+});
+
+/// The music disc in a jukebox, if any.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Record(pub Option<ItemStack>);
+
+/// Returns whether an item is a music disc.
+pub fn is_music_disc(item: Item) -> bool {
+    MUSIC_DISCS.contains(&item)
+}
+
+/// Returns the signal a comparator reads from a jukebox
+/// holding the given record: 0 if it is empty, and
+/// from 1 to 12 depending on the disc otherwise.
+pub fn record_signal(record: &Record) -> u8 {
+    record
+        .0
+        .and_then(|stack| MUSIC_DISCS.iter().position(|&disc| disc == stack.ty))
+        .map_or(0, |index| index as u8 + 1)
+}
+
+/// Creates an empty jukebox.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with_record(pos, Record::default())
+}
+
+/// Creates a jukebox holding the given record.
+pub fn create_with_record(pos: BlockPosition, record: Record) -> EntityBuilder {
+    crate::base(pos)
+        .with(record)
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    BlockEntityData {
+        base: crate::serialize_base(accessor),
+        kind: BlockEntityKind::Jukebox {
+            record_item: accessor
+                .get::<Record>()
+                .0
+                .map(|stack| InventorySlot::from_inventory_index(0, stack)),
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let record = match data.kind {
+        BlockEntityKind::Jukebox { record_item } => record_item
+            .filter(|slot| Item::from_identifier(&slot.item).is_some())
+            .map(ItemStack::from),
+        _ => bail!("not a jukebox"),
+    };
+
+    Ok(create_with_record(pos, Record(record)))
+}
+
+fn jukebox_at(game: &Game, world: &World, world_id: WorldId, pos: BlockPosition) -> Option<Entity> {
+    game.worlds[world_id]
+        .block_entities
+        .get(&pos)
+        .copied()
+        .filter(|&entity| world.has::<Record>(entity))
+}
+
+fn play_record(game: &Game, world: &World, world_id: WorldId, pos: BlockPosition, data: i32) {
+    let packet = Effect {
+        effect_id: RECORD_EFFECT,
+        location: pos,
+        data,
+        disable_relative_volume: false,
+    };
+    game.broadcast_chunk_update(world, packet, world_id, pos.chunk(), None);
+}
+
+/// Inserts a music disc into the empty jukebox at
+/// the given position and starts playing it.
+pub fn insert_record(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    disc: ItemStack,
+    cause: BlockUpdateCause,
+) {
+    let jukebox = match jukebox_at(game, world, world_id, pos) {
+        Some(jukebox) => jukebox,
+        None => return,
+    };
+    *world.get_mut::<Record>(jukebox) = Record(Some(disc.of_amount(1)));
+
+    if let Some(block) = game.block_at(world_id, pos) {
+        game.set_block_at(world, world_id, pos, block.with_has_record(true), cause);
+    }
+    play_record(game, world, world_id, pos, disc.ty.vanilla_id() as i32);
+    crate::redstone::update_comparators(game, world, world_id, pos);
+}
+
+/// Stops the jukebox at the given position playing
+/// and drops the music disc in it on top of it.
+pub fn eject_record(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    cause: BlockUpdateCause,
+) {
+    let disc = match jukebox_at(game, world, world_id, pos) {
+        Some(jukebox) => world.get_mut::<Record>(jukebox).0.take(),
+        None => return,
+    };
+    let disc = match disc {
+        Some(disc) => disc,
+        None => return,
+    };
+
+    play_record(game, world, world_id, pos, 0);
+    drop_item(
+        game,
+        world,
+        world_id,
+        disc,
+        Position::from(pos) + vec3(0.5, 1.0, 0.5),
+    );
+
+    if let Some(block) = game.block_at(world_id, pos) {
+        if block.kind() == BlockKind::Jukebox {
+            game.set_block_at(world, world_id, pos, block.with_has_record(false), cause);
+            crate::redstone::update_comparators(game, world, world_id, pos);
+        }
+    }
+}
+
+/// When a jukebox is broken, ejects its music disc.
+///
+/// Must run before `on_block_update_create_block_entity`.
+#[fecs::event_handler]
+pub fn on_jukebox_break_eject_record(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
+    if event.old.kind() == BlockKind::Jukebox && event.new.kind() != BlockKind::Jukebox {
+        eject_record(game, world, event.world, event.pos, event.cause);
+    }
+}
+
+/// Handler for players right clicking on jukeboxes, which inserts
+/// the music disc they hold or ejects the one already inside.
+struct JukeboxInteraction;
+
+inventory::submit!(Box::new(JukeboxInteraction) as Box<dyn InteractionHandler>);
+
+impl InteractionHandler for JukeboxInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        _window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let cause = BlockUpdateCause::Entity(player);
+        if game
+            .block_at(world_id, pos)
+            .and_then(|block| block.has_record())
+            == Some(true)
+        {
+            eject_record(game, world, world_id, pos, cause);
+            return;
+        }
+
+        let held = world
+            .get::<Inventory>(player)
+            .item_in_main_hand(player, world);
+        if let Some(disc) = held.filter(|stack| is_music_disc(stack.ty)) {
+            insert_record(game, world, world_id, pos, disc, cause);
+            consume_held_item(game, world, player, disc);
+        }
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        BlockKind::Jukebox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::inventory::Area;
+    use feather_core::position;
+    use feather_core::util::Gamemode;
+    use feather_test_framework::Test;
+
+    fn place_jukebox(test: &mut Test, pos: BlockPosition) -> Entity {
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            BlockId::jukebox(),
+            BlockUpdateCause::Unknown,
+        );
+        let jukebox = test.entity(create(pos));
+        test.game.worlds[WorldId::MAIN]
+            .block_entities
+            .insert(pos, jukebox);
+        jukebox
+    }
+
+    #[test]
+    fn signals() {
+        assert_eq!(record_signal(&Record(None)), 0);
+        let cat = ItemStack::new(Item::MusicDiscCat, 1);
+        assert_eq!(record_signal(&Record(Some(cat))), 2);
+        let wait = ItemStack::new(Item::MusicDiscWait, 1);
+        assert_eq!(record_signal(&Record(Some(wait))), 12);
+    }
+
+    #[test]
+    fn insert_and_eject() {
+        let mut test = Test::new();
+        let player = test.player("", position!(2.0, 64.0, 0.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let disc = ItemStack::new(Item::MusicDiscFar, 1);
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, disc)
+            .unwrap();
+
+        let pos = BlockPosition::new(0, 64, 0);
+        let jukebox = place_jukebox(&mut test, pos);
+
+        JukeboxInteraction.handle_interaction(&mut test.game, &mut test.world, pos, player, 0);
+        assert_eq!(test.world.get::<Record>(jukebox).0, Some(disc));
+        let block = test.game.block_at(WorldId::MAIN, pos).unwrap();
+        assert_eq!(block.has_record(), Some(true));
+        assert_eq!(
+            test.world
+                .get::<Inventory>(player)
+                .item_at(Area::Hotbar, 0)
+                .unwrap(),
+            None
+        );
+
+        JukeboxInteraction.handle_interaction(&mut test.game, &mut test.world, pos, player, 0);
+        assert_eq!(test.world.get::<Record>(jukebox).0, None);
+        let block = test.game.block_at(WorldId::MAIN, pos).unwrap();
+        assert_eq!(block.has_record(), Some(false));
+    }
+
+    #[test]
+    fn only_discs_are_inserted() {
+        let mut test = Test::new();
+        let player = test.player("", position!(2.0, 64.0, 0.0));
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, ItemStack::new(Item::Stone, 1))
+            .unwrap();
+
+        let pos = BlockPosition::new(0, 64, 0);
+        let jukebox = place_jukebox(&mut test, pos);
+
+        JukeboxInteraction.handle_interaction(&mut test.game, &mut test.world, pos, player, 0);
+        assert_eq!(test.world.get::<Record>(jukebox).0, None);
+    }
+}
@@ -1,20 +1,86 @@
 #![forbid(unsafe_code)]
 
+pub mod beacon;
+mod bed;
+pub mod brewing_stand;
 pub mod chest;
+pub mod comparator;
+pub mod conduit;
+pub mod container;
+mod door;
+mod farming;
+mod fire;
+mod fluid;
+pub mod furnace;
 mod init;
+pub mod jukebox;
+mod note_block;
+pub mod piston;
+mod poi;
+mod portal;
+mod redstone;
+pub mod shulker_box;
+pub mod sign;
+pub mod spawner;
+mod structure;
+mod tick;
+mod tnt;
 
-pub use chest::{
-    on_chest_break_drop_contents, on_chest_break_try_disconnect, on_chest_close_decrement_viewers,
-    on_chest_create_try_connect, on_chest_open_increment_viewers,
+pub use beacon::on_window_close_return_beacon_payment;
+pub use bed::{
+    on_bed_break_wake_up, on_entity_death_wake_up, on_player_leave_wake_up, skip_night,
 };
+pub use brewing_stand::on_inventory_update_update_brewing_stand_bottles;
+pub use chest::{on_chest_break_try_disconnect, on_chest_create_try_connect};
+pub use container::{on_container_close_decrement_viewers, on_container_open_increment_viewers};
 use feather_core::{
-    anvil::block_entity::BlockEntityBase,
+    anvil::{block_entity::BlockEntityBase, player::InventorySlot},
     blocks::BlockId,
+    inventory::{Area, SlotIndex, Window},
+    items::Item,
+    network::packets::{OpenWindow, SetSlot, WindowItems, WindowProperty},
     util::{BlockPosition, Position},
 };
-use feather_server_types::BlockEntity;
-use fecs::{EntityBuilder, EntityRef};
-pub use init::{on_block_entity_create_insert_to_map, on_block_update_create_block_entity};
+use feather_server_types::{
+    BlockEntity, Game, Inventory, Network, WindowCloseEvent, WindowOpenEvent,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, World};
+pub use farming::{
+    apply_bone_meal, on_block_tick_grow_cane_and_cactus, on_block_tick_grow_crops,
+    on_block_tick_hydrate_farmland, on_block_update_update_farmland,
+    on_item_use_on_block_apply_bone_meal,
+};
+pub use fire::{
+    is_flammable, on_block_tick_burn_fire, on_block_tick_ignite_near_lava,
+    on_block_update_update_fire, on_item_use_on_block_light_fire,
+};
+pub use door::{is_openable, toggle_openable};
+pub use fluid::{on_block_tick_flow_fluids, on_block_update_update_fluids};
+pub use init::{
+    on_block_break_drop_contents, on_block_entity_create_insert_to_map,
+    on_block_update_create_block_entity, on_chunk_unload_despawn_block_entities,
+};
+pub use jukebox::on_jukebox_break_eject_record;
+pub use note_block::on_block_update_update_note_blocks;
+pub use piston::on_block_tick_move_pistons;
+pub use poi::on_block_update_update_poi;
+pub use portal::{
+    light_nether_portal, on_block_update_break_portals, on_entity_despawn_cancel_travel,
+    on_item_use_on_block_insert_eye, travel_through_portals, PortalCooldown, PortalKind,
+    PortalTime, PortalTravel,
+};
+pub use redstone::{
+    is_power_source, on_block_tick_update_redstone, on_block_update_update_redstone,
+    on_inventory_update_update_comparators, on_item_frame_update_update_comparators,
+    press_detector_rails, press_pressure_plates,
+};
+pub use sign::on_sign_place_open_editor;
+pub use structure::{export_structure, place_structure};
+pub use tick::{
+    on_block_tick_decay_leaves, on_block_tick_melt_ice, on_block_update_schedule_leaves,
+    on_chunk_unload_remove_block_ticks, schedule_block_tick, tick_blocks,
+};
+pub use tnt::ignite_tnt;
 
 /// A function which determines whether a given change between
 /// block states should cause a block entity to be destroyed/recreated.
@@ -24,6 +90,102 @@ pub use init::{on_block_entity_create_insert_to_map, on_block_update_create_bloc
 /// if it should be replaced with a block entity for the new block.
 pub struct ShouldReplace(pub fn(BlockId, BlockId) -> bool);
 
+/// The players who have a block entity's window open,
+/// with the IDs of their windows.
+#[derive(Clone, Debug, Default)]
+pub struct WindowViewers(pub Vec<(Entity, u8)>);
+
+/// When a player closes the window of a block entity,
+/// stops sending them updates to it.
+#[fecs::event_handler]
+pub fn on_window_close_remove_viewer(event: &WindowCloseEvent, world: &mut World) {
+    if let Some(mut viewers) = world.try_get_mut::<WindowViewers>(event.closed) {
+        viewers.0.retain(|(player, _)| *player != event.player);
+    }
+}
+
+/// Opens the window of a block entity for a player,
+/// sending them the items in its slots, which are
+/// numbered as in `layout`.
+fn open_window(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    entity: Entity,
+    window: Window,
+    packet: OpenWindow,
+    layout: Layout,
+) {
+    let window_id = packet.window_id;
+    {
+        let network = world.get::<Network>(player);
+        network.send(packet);
+
+        let inventory = world.get::<Inventory>(entity);
+        let slots = layout_slots(layout)
+            .map(|(area, index)| inventory.item_at(area, index).unwrap())
+            .collect();
+        network.send(WindowItems { window_id, slots });
+    }
+
+    *world.get_mut::<Window>(player) = window;
+    if let Some(mut viewers) = world.try_get_mut::<WindowViewers>(entity) {
+        viewers.0.push((player, window_id));
+    }
+    game.handle(
+        world,
+        WindowOpenEvent {
+            player,
+            opened: entity,
+        },
+    );
+}
+
+/// Sends the players who have a block entity's window open
+/// the items in the given slots, numbered as in `layout`,
+/// and the given window properties.
+fn send_window_updates(
+    world: &World,
+    entity: Entity,
+    layout: Layout,
+    slots: &[SlotIndex],
+    properties: &[(i16, i16)],
+) {
+    let inventory = world.get::<Inventory>(entity);
+    for &(player, window_id) in &world.get::<WindowViewers>(entity).0 {
+        let network = match world.try_get::<Network>(player) {
+            Some(network) => network,
+            None => continue,
+        };
+
+        for slot in slots {
+            let window_slot = match layout_slots(layout)
+                .position(|(area, index)| area == slot.area && index == slot.slot)
+            {
+                Some(window_slot) => window_slot,
+                None => continue,
+            };
+            network.send(SetSlot {
+                window_id: window_id as i8,
+                slot: window_slot as i16,
+                slot_data: inventory.item_at(slot.area, slot.slot).unwrap(),
+            });
+        }
+
+        for &(property, value) in properties {
+            network.send(WindowProperty {
+                window_id,
+                property,
+                value,
+            });
+        }
+    }
+}
+
+/// The areas of a block entity's inventory, in the order
+/// its slots are numbered in the block entity's NBT.
+type Layout = &'static [(Area, usize)];
+
 /// Returns the base components all block entities have.
 fn base(pos: BlockPosition) -> EntityBuilder {
     EntityBuilder::new()
@@ -44,3 +206,75 @@ fn serialize_base(accessor: &EntityRef) -> BlockEntityBase {
 fn load_base(data: &BlockEntityBase) -> BlockPosition {
     BlockPosition::new(data.x, data.y, data.z)
 }
+
+/// `ShouldReplace` function for block entities which stay
+/// as long as the block kind is unchanged, e.g. a furnace
+/// which is lit or a sign which is rotated.
+fn kind_changed(old: BlockId, new: BlockId) -> bool {
+    old.kind() != new.kind()
+}
+
+/// Converts the items in an inventory to NBT slots.
+fn serialize_inventory(inventory: &Inventory, layout: Layout) -> Vec<InventorySlot> {
+    layout_slots(layout)
+        .enumerate()
+        .filter_map(|(slot, (area, index))| {
+            let item = inventory.item_at(area, index).ok()??;
+            Some(InventorySlot::from_inventory_index(slot as i8, item))
+        })
+        .collect()
+}
+
+/// Fills an inventory from NBT slots. Unknown items are skipped.
+fn load_inventory(inventory: &Inventory, layout: Layout, slots: &[InventorySlot]) {
+    for slot in slots {
+        if Item::from_identifier(&slot.item).is_none() {
+            continue;
+        }
+
+        match layout_slots(layout).nth(slot.slot as usize) {
+            Some((area, index)) if slot.slot >= 0 => {
+                if let Err(e) = inventory.set_item_at(area, index, slot.into()) {
+                    log::warn!("Invalid block entity slot: {}", e);
+                }
+            }
+            _ => log::warn!("Invalid block entity slot: {}", slot.slot),
+        }
+    }
+}
+
+/// Returns the area and index of each slot in a layout.
+fn layout_slots(layout: Layout) -> impl Iterator<Item = (Area, usize)> {
+    layout
+        .iter()
+        .flat_map(|&(area, size)| (0..size).map(move |index| (area, index)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::items::ItemStack;
+
+    #[test]
+    fn inventory_round_trip() {
+        const LAYOUT: Layout = &[(Area::FurnaceIngredient, 1), (Area::FurnaceFuel, 1)];
+
+        let inventory = Inventory::furnace();
+        inventory
+            .set_item_at(Area::FurnaceFuel, 0, ItemStack::new(Item::Coal, 3))
+            .unwrap();
+
+        let slots = serialize_inventory(&inventory, LAYOUT);
+        assert_eq!(slots.len(), 1);
+        assert_eq!(slots[0].slot, 1);
+        assert_eq!(slots[0].item, "minecraft:coal");
+
+        let loaded = Inventory::furnace();
+        load_inventory(&loaded, LAYOUT, &slots);
+        assert_eq!(
+            loaded.item_at(Area::FurnaceFuel, 0).unwrap(),
+            Some(ItemStack::new(Item::Coal, 3))
+        );
+        assert_eq!(loaded.item_at(Area::FurnaceIngredient, 0).unwrap(), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Note blocks, which play a note when right clicked or powered
+//! by redstone. Right clicking a note block also tunes it up by
+//! a semitone, wrapping around after two octaves. The instrument
+//! played depends on the block below the note block.
+//!
+//! See https://minecraft.gamepedia.com/Note_Block.
+
+use feather_core::blocks::{BlockId, BlockKind, Instrument, SimplifiedBlockKind};
+use feather_core::network::packets::{BlockAction, SoundCategory};
+use feather_core::util::{vec3, BlockPosition, Position};
+use feather_server_types::{
+    BlockUpdateCause, BlockUpdateEvent, Game, InteractionHandler, Sound, WorldId,
+};
+use fecs::{Entity, World};
+use num_traits::ToPrimitive;
+
+/// Number of notes a note block can be tuned to.
+const NOTES: i32 = 25;
+
+/// Returns the instrument a note block plays
+/// when placed on top of the given block.
+pub fn instrument_for(below: BlockId) -> Instrument {
+    use SimplifiedBlockKind::*;
+    match below.simplified_kind() {
+        Planks | Log | Bookshelf | Chest | TrappedChest | CraftingTable | NoteBlock | Jukebox
+        | Sign | WallSign | WoodenDoor | WoodenTrapdoor | Fence | FenceGate
+        | WoodenPressurePlate | DaylightDetector | Banner | WallBanner | BrownMushroomBlock
+        | RedMushroomBlock | MushroomStem => Instrument::Bass,
+        Sand | RedSand | Gravel | ConcretePowder => Instrument::Snare,
+        Glass | StainedGlass | GlassPane | StainedGlassPane | Glowstone | SeaLantern | Beacon => {
+            Instrument::Hat
+        }
+        Stone | Granite | PolishedGranite | Diorite | PolishedDiorite | Andesite
+        | PolishedAndesite | Cobblestone | MossyCobblestone | Bedrock | GoldOre | IronOre
+        | CoalOre | LapisOre | DiamondOre | RedstoneOre | EmeraldOre | NetherQuartzOre
+        | Sandstone | ChiseledSandstone | CutSandstone | SmoothSandstone | RedSandstone
+        | ChiseledRedSandstone | CutRedSandstone | SmoothRedSandstone | Bricks | StoneBricks
+        | MossyStoneBricks | CrackedStoneBricks | ChiseledStoneBricks | Obsidian | Netherrack
+        | NetherBricks | RedNetherBricks | EndStone | EndStoneBricks | Prismarine
+        | PrismarineBricks | DarkPrismarine | QuartzBlock | ChiseledQuartzBlock | QuartzPillar
+        | SmoothQuartz | SmoothStone | PurpurBlock | PurpurPillar | Terracotta
+        | GlazedTerracotta | Concrete | CoalBlock | MagmaBlock | Dispenser | Dropper | Furnace
+        | Spawner | EnchantingTable | EnderChest | Observer | CobblestoneWall
+        | MossyCobblestoneWall => Instrument::Basedrum,
+        GoldBlock => Instrument::Bell,
+        Clay => Instrument::Flute,
+        PackedIce => Instrument::Chime,
+        Wool => Instrument::Guitar,
+        BoneBlock => Instrument::Xylophone,
+        _ => Instrument::Harp,
+    }
+}
+
+/// Returns the pitch at which a note block tuned
+/// to the given note plays its instrument.
+pub fn note_pitch(note: i32) -> f32 {
+    2f32.powf((note - 12) as f32 / 12.0)
+}
+
+/// Plays the note of the note block at the given position,
+/// unless the block above it is in the way.
+pub fn play_note(game: &Game, world: &World, world_id: WorldId, pos: BlockPosition) {
+    let block = match game.block_at(world_id, pos) {
+        Some(block) if block.kind() == BlockKind::NoteBlock => block,
+        _ => return,
+    };
+    if !game
+        .block_at(world_id, pos.up())
+        .map_or(false, BlockId::is_air)
+    {
+        return;
+    }
+    let instrument = block.instrument().unwrap_or(Instrument::Harp);
+    let note = block.note().unwrap_or(0);
+
+    // Clients show the note particle when they receive the block action.
+    let packet = BlockAction {
+        location: pos,
+        action_id: instrument as u8,
+        action_param: note as u8,
+        block_type: BlockKind::NoteBlock.to_i32().unwrap(),
+    };
+    game.broadcast_chunk_update(world, packet, world_id, pos.chunk(), None);
+
+    let name = format!("block.note_block.{}", instrument.as_str());
+    let sound = Sound::new(name, SoundCategory::Records)
+        .volume(3.0)
+        .pitch(note_pitch(note));
+    let center = Position::from(pos) + vec3(0.5, 0.5, 0.5);
+    game.play_sound(world, world_id, center, &sound);
+}
+
+/// Powers or unpowers the note block at the given
+/// position, playing its note when it becomes powered.
+pub(crate) fn set_powered(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+    powered: bool,
+) {
+    if block.powered() == Some(powered) {
+        return;
+    }
+    game.set_block_at(
+        world,
+        world_id,
+        pos,
+        block.with_powered(powered),
+        BlockUpdateCause::Redstone,
+    );
+    if powered {
+        play_note(game, world, world_id, pos);
+    }
+}
+
+/// When a block changes, sets the instrument of the note block
+/// placed there or of the note block standing on top of it.
+#[fecs::event_handler]
+pub fn on_block_update_update_note_blocks(
+    event: &BlockUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.old == event.new {
+        return;
+    }
+
+    for &pos in &[event.pos, event.pos.up()] {
+        let block = match game.block_at(event.world, pos) {
+            Some(block) if block.kind() == BlockKind::NoteBlock => block,
+            _ => continue,
+        };
+        let instrument = match game.block_at(event.world, pos.down()) {
+            Some(below) => instrument_for(below),
+            None => continue,
+        };
+        if block.instrument() != Some(instrument) {
+            game.set_block_at(
+                world,
+                event.world,
+                pos,
+                block.with_instrument(instrument),
+                event.cause,
+            );
+        }
+    }
+}
+
+/// Handler for players right clicking on note blocks.
+struct NoteBlockInteraction;
+
+inventory::submit!(Box::new(NoteBlockInteraction) as Box<dyn InteractionHandler>);
+
+impl InteractionHandler for NoteBlockInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        _window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let block = match game.block_at(world_id, pos) {
+            Some(block) => block,
+            None => return,
+        };
+
+        let note = (block.note().unwrap_or(0) + 1) % NOTES;
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            block.with_note(note),
+            BlockUpdateCause::Entity(player),
+        );
+        play_note(game, world, world_id, pos);
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        BlockKind::NoteBlock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::position;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn instruments() {
+        assert_eq!(instrument_for(BlockId::oak_planks()), Instrument::Bass);
+        assert_eq!(instrument_for(BlockId::sand()), Instrument::Snare);
+        assert_eq!(instrument_for(BlockId::stone()), Instrument::Basedrum);
+        assert_eq!(instrument_for(BlockId::gold_block()), Instrument::Bell);
+        assert_eq!(instrument_for(BlockId::dirt()), Instrument::Harp);
+    }
+
+    #[test]
+    fn pitches() {
+        assert!((note_pitch(0) - 0.5).abs() < 1e-6);
+        assert!((note_pitch(12) - 1.0).abs() < 1e-6);
+        assert!((note_pitch(24) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn instrument_follows_block_below() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(pos.down(), BlockId::stone());
+        test.set_block(pos, BlockId::note_block());
+
+        let event = BlockUpdateEvent {
+            world: WorldId::MAIN,
+            pos,
+            old: BlockId::air(),
+            new: BlockId::note_block(),
+            cause: BlockUpdateCause::Unknown,
+        };
+        test.handle(event, on_block_update_update_note_blocks);
+        let block = test.block(pos);
+        assert_eq!(block.instrument(), Some(Instrument::Basedrum));
+
+        test.set_block(pos.down(), BlockId::white_wool());
+        let event = BlockUpdateEvent {
+            world: WorldId::MAIN,
+            pos: pos.down(),
+            old: BlockId::stone(),
+            new: BlockId::white_wool(),
+            cause: BlockUpdateCause::Unknown,
+        };
+        test.handle(event, on_block_update_update_note_blocks);
+        let block = test.block(pos);
+        assert_eq!(block.instrument(), Some(Instrument::Guitar));
+    }
+
+    #[test]
+    fn right_click_tunes() {
+        let mut test = Test::new();
+        let player = test.player("", position!(2.0, 64.0, 0.0));
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(pos, BlockId::note_block().with_note(24));
+
+        NoteBlockInteraction.handle_interaction(&mut test.game, &mut test.world, pos, player, 0);
+        let block = test.block(pos);
+        assert_eq!(block.note(), Some(0));
+
+        NoteBlockInteraction.handle_interaction(&mut test.game, &mut test.world, pos, player, 0);
+        let block = test.block(pos);
+        assert_eq!(block.note(), Some(1));
+    }
+}
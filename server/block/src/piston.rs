@@ -0,0 +1,901 @@
+//! Pistons, which push the blocks in front of them when powered.
+//! Sticky pistons also pull the block in front of them back when
+//! they retract.
+//!
+//! A piston moves at most `PUSH_LIMIT` blocks. Blocks such as
+//! obsidian and blocks with block entities stop it, while blocks
+//! such as torches and plants are destroyed when pushed. Slime
+//! blocks move the blocks stuck to their sides along with them.
+//!
+//! Moving blocks are replaced by moving piston blocks whose block
+//! entities store the blocks being moved. They are placed at their
+//! destination once they have finished moving, after two ticks.
+//!
+//! See https://minecraft.gamepedia.com/Piston.
+
+use crate::redstone::is_piston_powered;
+use crate::{schedule_block_tick, ShouldReplace};
+use ahash::AHashSet;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::anvil::region::LevelPaletteEntry;
+use feather_core::blocks::{BlockId, BlockKind, FacingCubic, PistonKind, SimplifiedBlockKind};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, BlockTickEvent, BlockTickKind,
+    BlockUpdateCause, BumpVec, EntitySpawnEvent, Game, Sound, TickPriority, WorldId, WorldState,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, World, Write};
+use rand::Rng;
+use std::ops::Range;
+
+/// The greatest number of blocks a piston can move.
+pub const PUSH_LIMIT: usize = 12;
+
+/// Progress of a moving block each tick.
+const PROGRESS_PER_TICK: f32 = 0.5;
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Piston,
+});
+
+/// Block entity of a moving piston block, which
+/// stores a block moved by a piston.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MovingBlock {
+    /// The block placed once it has finished moving.
+    pub block: BlockId,
+    /// The direction the piston faces.
+    pub facing: FacingCubic,
+    pub extending: bool,
+    /// Whether this is the piston or its head
+    /// rather than a block it moves.
+    pub source: bool,
+    /// How far the block has moved, from 0 to 1.
+    pub progress: f32,
+}
+
+/// Creates the block entity of a moving piston block.
+pub fn create(pos: BlockPosition, moving: MovingBlock) -> EntityBuilder {
+    crate::base(pos)
+        .with(moving)
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+/// How a block reacts to being pushed by a piston.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PushReaction {
+    Normal,
+    /// The block is destroyed, dropping its items.
+    Destroy,
+    /// The block stops the piston.
+    Block,
+    /// The block can be pushed but not pulled.
+    PushOnly,
+}
+
+fn push_reaction(block: BlockId) -> PushReaction {
+    match block.simplified_kind() {
+        SimplifiedBlockKind::Piston | SimplifiedBlockKind::StickyPiston => {
+            if block.extended() == Some(true) {
+                PushReaction::Block
+            } else {
+                PushReaction::Normal
+            }
+        }
+        // Blocks with block entities, and other heavy blocks
+        SimplifiedBlockKind::Obsidian
+        | SimplifiedBlockKind::PistonHead
+        | SimplifiedBlockKind::MovingPiston
+        | SimplifiedBlockKind::NetherPortal
+        | SimplifiedBlockKind::Anvil
+        | SimplifiedBlockKind::EnchantingTable
+        | SimplifiedBlockKind::EnderChest
+        | SimplifiedBlockKind::Beacon
+        | SimplifiedBlockKind::Conduit
+        | SimplifiedBlockKind::Chest
+        | SimplifiedBlockKind::TrappedChest
+        | SimplifiedBlockKind::Furnace
+        | SimplifiedBlockKind::BrewingStand
+        | SimplifiedBlockKind::Spawner
+        | SimplifiedBlockKind::Hopper
+        | SimplifiedBlockKind::Dispenser
+        | SimplifiedBlockKind::Dropper
+        | SimplifiedBlockKind::Jukebox
+        | SimplifiedBlockKind::DaylightDetector
+        | SimplifiedBlockKind::Sign
+        | SimplifiedBlockKind::WallSign => PushReaction::Block,
+        SimplifiedBlockKind::GlazedTerracotta => PushReaction::PushOnly,
+        SimplifiedBlockKind::WoodenDoor
+        | SimplifiedBlockKind::IronDoor
+        | SimplifiedBlockKind::Bed
+        | SimplifiedBlockKind::Ladder
+        | SimplifiedBlockKind::Cactus
+        | SimplifiedBlockKind::Cake
+        | SimplifiedBlockKind::Snow
+        | SimplifiedBlockKind::Pumpkin
+        | SimplifiedBlockKind::CarvedPumpkin
+        | SimplifiedBlockKind::JackOLantern
+        | SimplifiedBlockKind::Melon
+        | SimplifiedBlockKind::Cocoa
+        | SimplifiedBlockKind::DragonEgg
+        | SimplifiedBlockKind::FlowerPot
+        | SimplifiedBlockKind::PottedPlant
+        | SimplifiedBlockKind::TurtleEgg
+        | SimplifiedBlockKind::ShulkerBox
+        | SimplifiedBlockKind::SkeletonSkull
+        | SimplifiedBlockKind::SkeletonWallSkull
+        | SimplifiedBlockKind::WitherSkeletonSkull
+        | SimplifiedBlockKind::WitherSkeletonWallSkull
+        | SimplifiedBlockKind::ZombieHead
+        | SimplifiedBlockKind::ZombieWallHead
+        | SimplifiedBlockKind::PlayerHead
+        | SimplifiedBlockKind::PlayerWallHead
+        | SimplifiedBlockKind::CreeperHead
+        | SimplifiedBlockKind::CreeperWallHead
+        | SimplifiedBlockKind::DragonHead
+        | SimplifiedBlockKind::DragonWallHead => PushReaction::Destroy,
+        // Unbreakable blocks, such as bedrock and barriers
+        _ if block.kind().hardness() < 0.0 => PushReaction::Block,
+        _ if !block.is_solid() => PushReaction::Destroy,
+        _ => PushReaction::Normal,
+    }
+}
+
+fn is_piston(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::Piston | SimplifiedBlockKind::StickyPiston
+    )
+}
+
+/// Returns whether blocks stick to the sides of a
+/// block, moving along with it.
+fn is_sticky(block: BlockId) -> bool {
+    block.kind() == BlockKind::SlimeBlock
+}
+
+fn piston_kind(piston: BlockId) -> PistonKind {
+    if piston.kind() == BlockKind::StickyPiston {
+        PistonKind::Sticky
+    } else {
+        PistonKind::Normal
+    }
+}
+
+/// Returns whether a piston can move a block.
+///
+/// `push_direction` is the direction the block is moved in,
+/// and `direction` the direction in which it is reached from
+/// the piston. Destroyable blocks count as movable if
+/// `allow_destroy` is set. Blocks can't be moved out of
+/// the world, whose Y coordinates are given by `bounds`.
+fn is_movable(
+    block: BlockId,
+    pos: BlockPosition,
+    bounds: Range<i32>,
+    push_direction: FacingCubic,
+    allow_destroy: bool,
+    direction: FacingCubic,
+) -> bool {
+    if !bounds.contains(&pos.y)
+        || (push_direction == FacingCubic::Down && pos.y == bounds.start)
+        || (push_direction == FacingCubic::Up && pos.y == bounds.end - 1)
+    {
+        return false;
+    }
+
+    match push_reaction(block) {
+        PushReaction::Normal => true,
+        PushReaction::Destroy => allow_destroy,
+        PushReaction::Block => false,
+        PushReaction::PushOnly => push_direction == direction,
+    }
+}
+
+fn offset(pos: BlockPosition, dir: FacingCubic, distance: i32) -> BlockPosition {
+    let offset = dir.offset();
+    pos + BlockPosition::new(
+        offset.x * distance,
+        offset.y * distance,
+        offset.z * distance,
+    )
+}
+
+const DIRECTIONS: [FacingCubic; 6] = [
+    FacingCubic::Down,
+    FacingCubic::Up,
+    FacingCubic::North,
+    FacingCubic::South,
+    FacingCubic::West,
+    FacingCubic::East,
+];
+
+/// The blocks moved and destroyed when a piston extends or retracts.
+struct PushStructure<'a> {
+    state: &'a WorldState,
+    piston: BlockPosition,
+    facing: FacingCubic,
+    extending: bool,
+    /// The direction blocks are moved in.
+    push_direction: FacingCubic,
+    /// The first block moved.
+    start: BlockPosition,
+    /// The blocks to move. They are moved in reverse order,
+    /// so that each block is moved before the block moving
+    /// into its position.
+    to_push: Vec<BlockPosition>,
+    to_destroy: Vec<BlockPosition>,
+}
+
+impl<'a> PushStructure<'a> {
+    fn new(
+        state: &'a WorldState,
+        piston: BlockPosition,
+        facing: FacingCubic,
+        extending: bool,
+    ) -> Self {
+        let (push_direction, start) = if extending {
+            (facing, offset(piston, facing, 1))
+        } else {
+            (facing.opposite(), offset(piston, facing, 2))
+        };
+
+        Self {
+            state,
+            piston,
+            facing,
+            extending,
+            push_direction,
+            start,
+            to_push: Vec::new(),
+            to_destroy: Vec::new(),
+        }
+    }
+
+    fn block(&self, pos: BlockPosition) -> BlockId {
+        // Blocks in unloaded chunks can't be moved.
+        self.state.block_at(pos).unwrap_or_else(BlockId::barrier)
+    }
+
+    fn is_movable(&self, pos: BlockPosition, allow_destroy: bool, direction: FacingCubic) -> bool {
+        is_movable(
+            self.block(pos),
+            pos,
+            self.state.vertical_bounds(pos),
+            self.push_direction,
+            allow_destroy,
+            direction,
+        )
+    }
+
+    /// Finds the blocks to move and destroy. Returns `false`
+    /// if the piston can't move, because too many blocks would
+    /// be moved or an immovable block is in the way.
+    fn resolve(&mut self) -> bool {
+        self.to_push.clear();
+        self.to_destroy.clear();
+
+        let block = self.block(self.start);
+        if !self.is_movable(self.start, false, self.facing) {
+            if self.extending && push_reaction(block) == PushReaction::Destroy {
+                self.to_destroy.push(self.start);
+                return true;
+            }
+            return false;
+        }
+
+        if !self.add_block_line(self.start, self.push_direction) {
+            return false;
+        }
+
+        let mut i = 0;
+        while i < self.to_push.len() {
+            let pos = self.to_push[i];
+            if is_sticky(self.block(pos)) && !self.add_branching_blocks(pos) {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Adds a block to move, along with the blocks stuck behind it
+    /// and the blocks in front of it in the push direction.
+    fn add_block_line(&mut self, origin: BlockPosition, direction: FacingCubic) -> bool {
+        let mut block = self.block(origin);
+        if block.is_air()
+            || !self.is_movable(origin, false, direction)
+            || origin == self.piston
+            || self.to_push.contains(&origin)
+        {
+            return true;
+        }
+
+        let back = self.push_direction.opposite();
+        let mut count = 1;
+        if count + self.to_push.len() > PUSH_LIMIT {
+            return false;
+        }
+        while is_sticky(block) {
+            let pos = offset(origin, back, count as i32);
+            let behind = self.block(pos);
+            if behind.is_air() || !self.is_movable(pos, false, back) || pos == self.piston {
+                break;
+            }
+
+            count += 1;
+            if count + self.to_push.len() > PUSH_LIMIT {
+                return false;
+            }
+            block = behind;
+        }
+
+        let mut added = 0;
+        for distance in (0..count).rev() {
+            self.to_push.push(offset(origin, back, distance as i32));
+            added += 1;
+        }
+
+        let mut distance = 1;
+        loop {
+            let pos = offset(origin, self.push_direction, distance);
+            if let Some(index) = self.to_push.iter().position(|&other| other == pos) {
+                // The line runs into blocks already being moved,
+                // which have to move after the blocks in front of them.
+                self.reorder_at_collision(added, index);
+                let end = (index + added + 1).min(self.to_push.len());
+                for pos in self.to_push[..end].to_vec() {
+                    if is_sticky(self.block(pos)) && !self.add_branching_blocks(pos) {
+                        return false;
+                    }
+                }
+                return true;
+            }
+
+            let block = self.block(pos);
+            if block.is_air() {
+                return true;
+            }
+            if !self.is_movable(pos, true, self.push_direction) || pos == self.piston {
+                return false;
+            }
+            if push_reaction(block) == PushReaction::Destroy {
+                self.to_destroy.push(pos);
+                return true;
+            }
+            if self.to_push.len() >= PUSH_LIMIT {
+                return false;
+            }
+
+            self.to_push.push(pos);
+            added += 1;
+            distance += 1;
+        }
+    }
+
+    /// Moves the last `added` blocks before the block at `index`.
+    fn reorder_at_collision(&mut self, added: usize, index: usize) {
+        let len = self.to_push.len();
+        let mut reordered = Vec::with_capacity(len);
+        reordered.extend_from_slice(&self.to_push[..index]);
+        reordered.extend_from_slice(&self.to_push[len - added..]);
+        reordered.extend_from_slice(&self.to_push[index..len - added]);
+        self.to_push = reordered;
+    }
+
+    /// Adds the blocks stuck to the sides of a sticky block.
+    fn add_branching_blocks(&mut self, from: BlockPosition) -> bool {
+        for &dir in &DIRECTIONS {
+            if dir.axis() != self.push_direction.axis()
+                && !self.add_block_line(from + dir.offset(), dir)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Replaces a block with a moving piston block
+/// whose block entity stores the moved block.
+fn set_moving_block(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    moving: MovingBlock,
+    kind: PistonKind,
+) {
+    let block = BlockId::moving_piston()
+        .with_facing_cubic(moving.facing)
+        .with_piston_kind(kind);
+    game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Piston);
+
+    if let Some(old) = game.worlds[world_id].block_entities.remove(&pos) {
+        game.despawn(old, world);
+    }
+    let entity = create(pos, moving).with(world_id).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+}
+
+/// Places the block stored in a moving piston block.
+fn finish_moving_block(game: &mut Game, world: &mut World, entity: Entity) {
+    let world_id = game.world_of(world, entity);
+    let pos = *world.get::<BlockPosition>(entity);
+    let moving = *world.get::<MovingBlock>(entity);
+
+    let block_entities = &mut game.worlds[world_id].block_entities;
+    if block_entities.get(&pos) == Some(&entity) {
+        block_entities.remove(&pos);
+    }
+    game.despawn(entity, world);
+
+    if game.block_at(world_id, pos).map(BlockId::kind) == Some(BlockKind::MovingPiston) {
+        game.set_block_at(world, world_id, pos, moving.block, BlockUpdateCause::Piston);
+    }
+}
+
+/// Places the block moving into a position at once, if any.
+fn finish_moving_block_at(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+) -> Option<MovingBlock> {
+    let entity = *game.worlds[world_id].block_entities.get(&pos)?;
+    let moving = *world.try_get::<MovingBlock>(entity)?;
+    finish_moving_block(game, world, entity);
+    Some(moving)
+}
+
+/// Moves the blocks in front of a piston. Returns `false`
+/// if the blocks can't be moved.
+fn move_blocks(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    piston: BlockPosition,
+    facing: FacingCubic,
+    extending: bool,
+    kind: PistonKind,
+) -> bool {
+    let head = offset(piston, facing, 1);
+    if !extending && game.block_at(world_id, head).map(BlockId::kind) == Some(BlockKind::PistonHead)
+    {
+        game.set_block_at(
+            world,
+            world_id,
+            head,
+            BlockId::air(),
+            BlockUpdateCause::Piston,
+        );
+    }
+
+    let (to_push, to_destroy) = {
+        let mut structure = PushStructure::new(&game.worlds[world_id], piston, facing, extending);
+        if !structure.resolve() {
+            return false;
+        }
+        (structure.to_push, structure.to_destroy)
+    };
+
+    let direction = if extending { facing } else { facing.opposite() };
+    let moved: Vec<(BlockPosition, BlockId)> = to_push
+        .iter()
+        .map(|&pos| {
+            (
+                pos,
+                game.block_at(world_id, pos).unwrap_or_else(BlockId::air),
+            )
+        })
+        .collect();
+    let mut vacated: AHashSet<BlockPosition> = to_push.iter().copied().collect();
+
+    for &pos in to_destroy.iter().rev() {
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            BlockId::air(),
+            BlockUpdateCause::Unsupported,
+        );
+    }
+
+    for &(pos, block) in moved.iter().rev() {
+        let destination = offset(pos, direction, 1);
+        vacated.remove(&destination);
+        let moving = MovingBlock {
+            block,
+            facing,
+            extending,
+            source: false,
+            progress: 0.0,
+        };
+        set_moving_block(game, world, world_id, destination, moving, kind);
+    }
+
+    if extending {
+        vacated.remove(&head);
+        let moving = MovingBlock {
+            block: BlockId::piston_head()
+                .with_facing_cubic(facing)
+                .with_piston_kind(kind),
+            facing,
+            extending: true,
+            source: true,
+            progress: 0.0,
+        };
+        set_moving_block(game, world, world_id, head, moving, kind);
+    }
+
+    for pos in vacated {
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            BlockId::air(),
+            BlockUpdateCause::Piston,
+        );
+    }
+    true
+}
+
+/// Extends a piston, pushing the blocks in front of it.
+fn extend(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    piston: BlockId,
+) {
+    let facing = piston.facing_cubic().unwrap_or(FacingCubic::North);
+    if !move_blocks(
+        game,
+        world,
+        world_id,
+        pos,
+        facing,
+        true,
+        piston_kind(piston),
+    ) {
+        return;
+    }
+
+    game.set_block_at(
+        world,
+        world_id,
+        pos,
+        piston.with_extended(true),
+        BlockUpdateCause::Piston,
+    );
+    play_sound(game, world, world_id, pos, "block.piston.extend", 0.25);
+}
+
+/// Retracts a piston. Sticky pistons pull back the block
+/// in front of their head, unless it is still being pushed.
+fn retract(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    piston: BlockId,
+) {
+    let facing = piston.facing_cubic().unwrap_or(FacingCubic::North);
+    let kind = piston_kind(piston);
+    let head = offset(pos, facing, 1);
+
+    // A head which is still extending is placed at once.
+    if game.block_at(world_id, head).map(BlockId::kind) == Some(BlockKind::MovingPiston) {
+        finish_moving_block_at(game, world, world_id, head);
+    }
+
+    let moving = MovingBlock {
+        block: piston.with_extended(false),
+        facing,
+        extending: false,
+        source: true,
+        progress: 0.0,
+    };
+    set_moving_block(game, world, world_id, pos, moving, kind);
+
+    let mut pulled = false;
+    if kind == PistonKind::Sticky {
+        let front = offset(pos, facing, 2);
+        let block = game
+            .block_at(world_id, front)
+            .unwrap_or_else(BlockId::barrier);
+
+        // Blocks which were just pushed are dropped
+        // by pistons which retract at once.
+        let dropped = block.kind() == BlockKind::MovingPiston
+            && game.worlds[world_id]
+                .block_entities
+                .get(&front)
+                .and_then(|&entity| world.try_get::<MovingBlock>(entity).map(|moving| *moving))
+                .map_or(false, |moving| moving.facing == facing && moving.extending);
+        if dropped {
+            finish_moving_block_at(game, world, world_id, front);
+        } else if !block.is_air()
+            && is_movable(
+                block,
+                front,
+                game.worlds[world_id].vertical_bounds(front),
+                facing.opposite(),
+                false,
+                facing,
+            )
+            && (push_reaction(block) == PushReaction::Normal || is_piston(block))
+        {
+            pulled = move_blocks(game, world, world_id, pos, facing, false, kind);
+        }
+    }
+
+    if !pulled && game.block_at(world_id, head).map(BlockId::kind) == Some(BlockKind::PistonHead) {
+        game.set_block_at(
+            world,
+            world_id,
+            head,
+            BlockId::air(),
+            BlockUpdateCause::Piston,
+        );
+    }
+    play_sound(game, world, world_id, pos, "block.piston.contract", 0.15);
+}
+
+fn play_sound(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    name: &str,
+    pitch_range: f32,
+) {
+    let pitch = game.rng().gen_range(0.6, 0.6 + pitch_range);
+    let sound = Sound::new(name, SoundCategory::Blocks)
+        .volume(0.5)
+        .pitch(pitch);
+    game.play_sound(world, world_id, Position::from(pos), &sound);
+}
+
+/// Schedules a piston to extend or retract if it
+/// was powered or unpowered.
+pub(crate) fn update_piston(game: &mut Game, world: &World, world_id: WorldId, pos: BlockPosition) {
+    let piston = match game.block_at(world_id, pos) {
+        Some(block) if is_piston(block) => block,
+        _ => return,
+    };
+
+    let facing = piston.facing_cubic().unwrap_or(FacingCubic::North);
+    let powered = is_piston_powered(game, world, world_id, pos, facing);
+    if powered != (piston.extended() == Some(true)) {
+        schedule_block_tick(game, world_id, pos, 0, TickPriority::EXTREMELY_HIGH);
+    }
+}
+
+/// Extends or retracts pistons when their scheduled ticks are due.
+#[fecs::event_handler]
+pub fn on_block_tick_move_pistons(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Scheduled || !is_piston(event.block) {
+        return;
+    }
+
+    let piston = event.block;
+    let facing = piston.facing_cubic().unwrap_or(FacingCubic::North);
+    let powered = is_piston_powered(game, world, event.world, event.pos, facing);
+    match piston.extended() {
+        Some(false) if powered => extend(game, world, event.world, event.pos, piston),
+        Some(true) if !powered => retract(game, world, event.world, event.pos, piston),
+        _ => (),
+    }
+}
+
+/// System which advances the blocks moved by pistons,
+/// placing them once they have finished moving.
+#[fecs::system]
+pub fn move_piston_blocks(game: &mut Game, world: &mut World) {
+    let mut finished = BumpVec::new_in(game.bump());
+    for (entity, mut moving) in <Write<MovingBlock>>::query().iter_entities_mut(world.inner_mut()) {
+        moving.progress = (moving.progress + PROGRESS_PER_TICK).min(1.0);
+        if moving.progress >= 1.0 {
+            finished.push(entity);
+        }
+    }
+
+    for entity in finished {
+        if world.is_alive(entity) {
+            finish_moving_block(game, world, entity);
+        }
+    }
+}
+
+/// Returns the 3D data value of a direction, used to save it.
+fn direction_id(dir: FacingCubic) -> i32 {
+    match dir {
+        FacingCubic::Down => 0,
+        FacingCubic::Up => 1,
+        FacingCubic::North => 2,
+        FacingCubic::South => 3,
+        FacingCubic::West => 4,
+        FacingCubic::East => 5,
+    }
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    let moving = *accessor.get::<MovingBlock>();
+    BlockEntityData {
+        base: crate::serialize_base(accessor),
+        kind: BlockEntityKind::Piston {
+            block_state: LevelPaletteEntry::from_block(moving.block),
+            facing: direction_id(moving.facing),
+            progress: moving.progress,
+            extending: moving.extending,
+            source: moving.source,
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let moving = match data.kind {
+        BlockEntityKind::Piston {
+            block_state,
+            facing,
+            progress,
+            extending,
+            source,
+        } => MovingBlock {
+            block: block_state.to_block().unwrap_or_else(BlockId::air),
+            facing: DIRECTIONS
+                .iter()
+                .copied()
+                .find(|&dir| direction_id(dir) == facing)
+                .unwrap_or(FacingCubic::North),
+            extending,
+            source,
+            progress,
+        },
+        _ => bail!("not a moving piston"),
+    };
+
+    Ok(create(pos, moving))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::util::ChunkPosition;
+    use feather_test_framework::Test;
+
+    fn resolve(test: &Test, piston: BlockPosition) -> Option<PushStructure> {
+        let mut structure = PushStructure::new(
+            &test.game.worlds[WorldId::MAIN],
+            piston,
+            FacingCubic::East,
+            true,
+        );
+        if structure.resolve() {
+            Some(structure)
+        } else {
+            None
+        }
+    }
+
+    fn tick_piston(test: &mut Test, pos: BlockPosition) {
+        let event = BlockTickEvent {
+            world: WorldId::MAIN,
+            pos,
+            block: test.block(pos),
+            kind: BlockTickKind::Scheduled,
+        };
+        test.handle(event, on_block_tick_move_pistons);
+    }
+
+    #[test]
+    fn push_limit() {
+        let mut test = Test::new();
+        let piston = BlockPosition::new(0, 64, 0);
+        for x in 1..=12 {
+            test.set_block(BlockPosition::new(x, 64, 0), BlockId::stone());
+        }
+        assert_eq!(resolve(&test, piston).unwrap().to_push.len(), 12);
+
+        test.set_block(BlockPosition::new(13, 64, 0), BlockId::stone());
+        assert!(resolve(&test, piston).is_none());
+    }
+
+    #[test]
+    fn blocks_stay_within_world_height() {
+        let mut test = Test::new();
+        test.game.worlds[WorldId::MAIN]
+            .chunk_map
+            .chunk_at_mut(ChunkPosition::new(0, 0))
+            .unwrap()
+            .resize(-64, 384);
+
+        let push = |test: &Test, piston: BlockPosition, facing: FacingCubic| {
+            PushStructure::new(&test.game.worlds[WorldId::MAIN], piston, facing, true).resolve()
+        };
+
+        // Blocks below Y 0 and above Y 255 can be moved...
+        test.set_block(BlockPosition::new(0, -10, 0), BlockId::stone());
+        assert!(push(&test, BlockPosition::new(0, -9, 0), FacingCubic::Down));
+        test.set_block(BlockPosition::new(0, 300, 0), BlockId::stone());
+        assert!(push(&test, BlockPosition::new(0, 299, 0), FacingCubic::Up));
+
+        // ...but not out of the world.
+        test.set_block(BlockPosition::new(0, -64, 0), BlockId::stone());
+        assert!(!push(
+            &test,
+            BlockPosition::new(0, -63, 0),
+            FacingCubic::Down
+        ));
+        test.set_block(BlockPosition::new(0, 319, 0), BlockId::stone());
+        assert!(!push(&test, BlockPosition::new(0, 318, 0), FacingCubic::Up));
+    }
+
+    #[test]
+    fn immovable_and_destroyed_blocks() {
+        let mut test = Test::new();
+        let piston = BlockPosition::new(0, 64, 0);
+        test.set_block(BlockPosition::new(1, 64, 0), BlockId::stone());
+        test.set_block(BlockPosition::new(2, 64, 0), BlockId::torch());
+        let structure = resolve(&test, piston).unwrap();
+        assert_eq!(structure.to_push, vec![BlockPosition::new(1, 64, 0)]);
+        assert_eq!(structure.to_destroy, vec![BlockPosition::new(2, 64, 0)]);
+
+        test.set_block(BlockPosition::new(2, 64, 0), BlockId::obsidian());
+        assert!(resolve(&test, piston).is_none());
+    }
+
+    #[test]
+    fn slime_blocks_move_attached_blocks() {
+        let mut test = Test::new();
+        let piston = BlockPosition::new(0, 64, 0);
+        test.set_block(BlockPosition::new(1, 64, 0), BlockId::slime_block());
+        test.set_block(BlockPosition::new(1, 65, 0), BlockId::stone());
+        test.set_block(BlockPosition::new(1, 64, 1), BlockId::obsidian());
+
+        let structure = resolve(&test, piston).unwrap();
+        assert!(structure.to_push.contains(&BlockPosition::new(1, 65, 0)));
+        assert_eq!(structure.to_push.len(), 2);
+
+        test.set_block(BlockPosition::new(2, 65, 0), BlockId::obsidian());
+        assert!(resolve(&test, piston).is_none());
+    }
+
+    #[test]
+    fn sticky_piston_extends_and_retracts() {
+        let mut test = Test::new();
+        let piston = BlockPosition::new(1, 64, 1);
+        let power = BlockPosition::new(0, 64, 1);
+        let head = BlockPosition::new(2, 64, 1);
+        let pushed = BlockPosition::new(3, 64, 1);
+        test.set_block(
+            piston,
+            BlockId::sticky_piston().with_facing_cubic(FacingCubic::East),
+        );
+        test.set_block(head, BlockId::stone());
+        test.set_block(power, BlockId::redstone_block());
+
+        tick_piston(&mut test, piston);
+        assert_eq!(test.block(piston).extended(), Some(true));
+        assert_eq!(test.block(head).kind(), BlockKind::MovingPiston);
+        assert_eq!(test.block(pushed).kind(), BlockKind::MovingPiston);
+
+        test.run(move_piston_blocks).run(move_piston_blocks);
+        assert_eq!(test.block(head).kind(), BlockKind::PistonHead);
+        assert_eq!(test.block(pushed), BlockId::stone());
+
+        test.set_block(power, BlockId::air());
+        tick_piston(&mut test, piston);
+        test.run(move_piston_blocks).run(move_piston_blocks);
+        assert_eq!(test.block(piston).kind(), BlockKind::StickyPiston);
+        assert_eq!(test.block(piston).extended(), Some(false));
+        assert_eq!(test.block(head), BlockId::stone());
+        assert!(test.block(pushed).is_air());
+    }
+}
@@ -0,0 +1,27 @@
+//! Keeps the points of interest of worlds up to date.
+
+use feather_server_types::{BlockUpdateEvent, Game, Poi, PoiType};
+
+/// Adds or removes a point of interest when a block changes.
+#[fecs::event_handler]
+pub fn on_block_update_update_poi(event: &BlockUpdateEvent, game: &mut Game) {
+    let state = match game.worlds.get_mut(event.world) {
+        Some(state) => state,
+        None => return,
+    };
+
+    let old = PoiType::from_block(event.old);
+    let new = PoiType::from_block(event.new);
+    // Keep existing claims if only the block's state changed,
+    // e.g. when a bed becomes occupied.
+    if old == new {
+        return;
+    }
+
+    if old.is_some() {
+        state.poi.remove(event.pos);
+    }
+    if let Some(kind) = new {
+        state.poi.add(Poi::new(event.pos, kind));
+    }
+}
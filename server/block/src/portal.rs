@@ -0,0 +1,998 @@
+//! Nether and End portals.
+//!
+//! Lighting fire inside an obsidian frame in the overworld or the
+//! Nether fills it with portal blocks, which disappear once the frame
+//! is broken. Entities standing in a Nether portal are moved to the
+//! other dimension, with horizontal distances scaled by 8, and arrive
+//! at the nearest portal there, which is built if there is none.
+//!
+//! End portals are activated by placing an eye of ender into each
+//! of the twelve frame blocks around them. They lead to a platform
+//! in the End, and from the End back to the spawn point.
+//!
+//! Before an entity travels, a portal ticket loads the chunks around
+//! its destination, so that portals there can be found by their
+//! points of interest.
+//!
+//! See https://minecraft.gamepedia.com/Nether_portal and
+//! https://minecraft.gamepedia.com/End_portal.
+
+use feather_core::blocks::{AxisXz, BlockId, BlockKind, FacingCardinal};
+use feather_core::items::Item;
+use feather_core::network::packets::{Effect, SoundCategory};
+use feather_core::util::{vec3, BlockPosition, ChunkPosition, Dimension, Gamemode, Position};
+use feather_server_entity::consume_held_item;
+use feather_server_types::{
+    AABBExt, AddTicketRequest, BlockEntity, BlockUpdateCause, BlockUpdateEvent, Dead,
+    EntityDespawnEvent, Game, ItemUseOnBlockEvent, Network, Physics, Player, PoiType,
+    RemoveTicketRequest, Sound, Ticket, TicketKind, Velocity, WorldId, WorldState, PLAYER_HEIGHT,
+    PLAYER_WIDTH,
+};
+use feather_server_util::adjacent_blocks;
+use fecs::{Entity, IntoQuery, Read, World, Write};
+
+/// Smallest width of the inside of a Nether portal.
+const MIN_WIDTH: i32 = 2;
+/// Greatest width of the inside of a Nether portal.
+const MAX_WIDTH: i32 = 21;
+/// Smallest height of the inside of a Nether portal.
+const MIN_HEIGHT: i32 = 3;
+/// Greatest height of the inside of a Nether portal.
+const MAX_HEIGHT: i32 = 21;
+
+/// Ratio of distances in the overworld to those in the Nether.
+const NETHER_SCALE: f64 = 8.0;
+/// Radius in which portals are searched for in the overworld.
+const OVERWORLD_SEARCH_RADIUS: i32 = 128;
+/// Radius in which portals are searched for in the Nether.
+const NETHER_SEARCH_RADIUS: i32 = 16;
+/// Horizontal radius in which a place is searched for
+/// to build a portal if none was found.
+const BUILD_RADIUS: i32 = 16;
+/// Lowest height at which a portal is built if
+/// there is no suitable place for it.
+const MIN_FORCED_HEIGHT: i32 = 70;
+
+/// Ticks players in survival mode need to stand in a Nether portal.
+const SURVIVAL_PORTAL_TIME: u32 = 80;
+/// Ticks until players can use a portal again after travelling.
+const PLAYER_COOLDOWN: u32 = 10;
+/// Ticks until other entities can use a portal again.
+const ENTITY_COOLDOWN: u32 = 300;
+
+/// Centre of the obsidian platform entities arrive at in the End.
+const END_PLATFORM: BlockPosition = BlockPosition {
+    x: 100,
+    y: 48,
+    z: 0,
+};
+
+/// World event played to players who travelled through a portal.
+const TRAVEL_EFFECT: i32 = 1032;
+/// World event played to all players when an End portal is activated.
+const END_PORTAL_EFFECT: i32 = 1038;
+
+/// A kind of portal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PortalKind {
+    Nether,
+    End,
+}
+
+/// Component counting the ticks an entity has stood in a Nether portal.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PortalTime(pub u32);
+
+/// Component for entities which recently travelled through a
+/// portal, counting the ticks until they can use one again.
+#[derive(Copy, Clone, Debug)]
+pub struct PortalCooldown(pub u32);
+
+/// Component for entities waiting for the chunks
+/// around the destination of a portal to load.
+#[derive(Copy, Clone, Debug)]
+pub struct PortalTravel {
+    pub kind: PortalKind,
+    pub world: WorldId,
+    /// Where the entity would arrive without a portal there.
+    pub target: BlockPosition,
+}
+
+impl PortalTravel {
+    /// Returns the radius, in chunks, of the area which
+    /// must be loaded before the entity can arrive.
+    fn chunk_radius(self, game: &Game) -> u8 {
+        let blocks = match (self.kind, dimension(game, self.world)) {
+            (PortalKind::End, _) => 0,
+            (PortalKind::Nether, Some(Dimension::Overwold)) => OVERWORLD_SEARCH_RADIUS,
+            (PortalKind::Nether, _) => NETHER_SEARCH_RADIUS.max(BUILD_RADIUS),
+        };
+        (blocks / 16 + 1) as u8
+    }
+}
+
+fn dimension(game: &Game, world_id: WorldId) -> Option<Dimension> {
+    game.worlds.get(world_id).map(|state| state.dimension)
+}
+
+fn is_frame(state: &WorldState, pos: BlockPosition) -> bool {
+    state
+        .block_at(pos)
+        .map_or(false, |block| block.kind() == BlockKind::Obsidian)
+}
+
+/// Returns whether a block can be inside a portal frame.
+fn is_empty(state: &WorldState, pos: BlockPosition) -> bool {
+    state.block_at(pos).map_or(false, |block| {
+        matches!(
+            block.kind(),
+            BlockKind::Air | BlockKind::Fire | BlockKind::NetherPortal
+        )
+    })
+}
+
+fn axis_offset(axis: AxisXz, distance: i32) -> BlockPosition {
+    match axis {
+        AxisXz::X => BlockPosition::new(distance, 0, 0),
+        AxisXz::Z => BlockPosition::new(0, 0, distance),
+    }
+}
+
+/// The inside of an obsidian frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct PortalShape {
+    /// The lowest inside block on the negative side of the axis.
+    corner: BlockPosition,
+    axis: AxisXz,
+    width: i32,
+    height: i32,
+}
+
+impl PortalShape {
+    /// Finds the frame around a block along the given axis.
+    fn find(state: &WorldState, pos: BlockPosition, axis: AxisXz) -> Option<Self> {
+        let mut bottom = pos;
+        while bottom.y - pos.y > -MAX_HEIGHT && is_empty(state, bottom.down()) {
+            bottom = bottom.down();
+        }
+        if !is_frame(state, bottom.down()) {
+            return None;
+        }
+
+        let mut corner = bottom;
+        for _ in 0..MAX_WIDTH {
+            let next = corner + axis_offset(axis, -1);
+            if !is_empty(state, next) || !is_frame(state, next.down()) {
+                break;
+            }
+            corner = next;
+        }
+        if !is_frame(state, corner + axis_offset(axis, -1)) {
+            return None;
+        }
+
+        let width = (0..=MAX_WIDTH)
+            .take_while(|&i| {
+                let pos = corner + axis_offset(axis, i);
+                is_empty(state, pos) && is_frame(state, pos.down())
+            })
+            .count() as i32;
+        if width < MIN_WIDTH
+            || width > MAX_WIDTH
+            || !is_frame(state, corner + axis_offset(axis, width))
+        {
+            return None;
+        }
+
+        let row_is_inside = |y: i32| {
+            let row = corner + BlockPosition::new(0, y, 0);
+            is_frame(state, row + axis_offset(axis, -1))
+                && is_frame(state, row + axis_offset(axis, width))
+                && (0..width).all(|i| is_empty(state, row + axis_offset(axis, i)))
+        };
+        let height = (0..=MAX_HEIGHT).take_while(|&y| row_is_inside(y)).count() as i32;
+        let top = corner + BlockPosition::new(0, height, 0);
+        if height < MIN_HEIGHT
+            || height > MAX_HEIGHT
+            || !(0..width).all(|i| is_frame(state, top + axis_offset(axis, i)))
+        {
+            return None;
+        }
+
+        Some(Self {
+            corner,
+            axis,
+            width,
+            height,
+        })
+    }
+
+    fn blocks(self) -> impl Iterator<Item = BlockPosition> {
+        (0..self.height).flat_map(move |y| {
+            (0..self.width)
+                .map(move |i| self.corner + BlockPosition::new(0, y, 0) + axis_offset(self.axis, i))
+        })
+    }
+}
+
+/// Fills the obsidian frame around a fire which was lit with
+/// portal blocks. Returns whether a portal was created.
+pub fn light_nether_portal(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+) -> bool {
+    let state = &game.worlds[world_id];
+    if !matches!(state.dimension, Dimension::Overwold | Dimension::Nether) {
+        return false;
+    }
+    let shape = match PortalShape::find(state, pos, AxisXz::X)
+        .or_else(|| PortalShape::find(state, pos, AxisXz::Z))
+    {
+        Some(shape) => shape,
+        None => return false,
+    };
+
+    let portal = BlockId::nether_portal().with_axis_xz(shape.axis);
+    for pos in shape.blocks() {
+        game.set_block_at(world, world_id, pos, portal, BlockUpdateCause::Unknown);
+    }
+    true
+}
+
+/// Returns whether a Nether portal block is still
+/// surrounded by its frame or other portal blocks.
+fn is_portal_supported(state: &WorldState, pos: BlockPosition, portal: BlockId) -> bool {
+    let axis = portal.axis_xz().unwrap_or(AxisXz::X);
+    let neighbors = [
+        pos.up(),
+        pos.down(),
+        pos + axis_offset(axis, 1),
+        pos + axis_offset(axis, -1),
+    ];
+    neighbors.iter().all(|&pos| {
+        state.block_at(pos).map_or(true, |block| {
+            matches!(block.kind(), BlockKind::Obsidian | BlockKind::NetherPortal)
+        })
+    })
+}
+
+/// Breaks the Nether portal blocks which are
+/// left without a frame when a block is broken.
+#[fecs::event_handler]
+pub fn on_block_update_break_portals(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
+    let was_frame = matches!(
+        event.old.kind(),
+        BlockKind::Obsidian | BlockKind::NetherPortal
+    );
+    if !was_frame || event.new.kind() == event.old.kind() {
+        return;
+    }
+
+    for pos in adjacent_blocks(event.pos) {
+        let state = match game.worlds.get(event.world) {
+            Some(state) => state,
+            None => return,
+        };
+        match state.block_at(pos) {
+            Some(portal)
+                if portal.kind() == BlockKind::NetherPortal
+                    && !is_portal_supported(state, pos, portal) =>
+            {
+                game.set_block_at(
+                    world,
+                    event.world,
+                    pos,
+                    BlockId::air(),
+                    BlockUpdateCause::Unsupported,
+                );
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Places an eye of ender into an End portal frame, activating
+/// the portal once all frames around it have an eye.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_insert_eye(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.stack.ty != Item::EnderEye {
+        return;
+    }
+    let frame = match game.block_at(event.world, event.pos) {
+        Some(frame) if frame.kind() == BlockKind::EndPortalFrame && frame.eye() == Some(false) => {
+            frame.with_eye(true)
+        }
+        _ => return,
+    };
+
+    game.set_block_at(
+        world,
+        event.world,
+        event.pos,
+        frame,
+        BlockUpdateCause::Entity(event.player),
+    );
+    consume_held_item(game, world, event.player, event.stack);
+    let sound = Sound::new("block.end_portal_frame.fill", SoundCategory::Blocks);
+    game.play_sound(world, event.world, event.pos.position(), &sound);
+
+    let center = match find_end_portal(&game.worlds[event.world], event.pos, frame) {
+        Some(center) => center,
+        None => return,
+    };
+    for x in -1..=1 {
+        for z in -1..=1 {
+            game.set_block_at(
+                world,
+                event.world,
+                center + BlockPosition::new(x, 0, z),
+                BlockId::end_portal(),
+                BlockUpdateCause::Unknown,
+            );
+        }
+    }
+
+    let packet = Effect {
+        effect_id: END_PORTAL_EFFECT,
+        location: center,
+        data: 0,
+        disable_relative_volume: true,
+    };
+    game.broadcast_world(world, packet, event.world, None);
+}
+
+/// Returns the centre of the End portal which the given frame
+/// completes, if all twelve frames have an eye and face inwards.
+fn find_end_portal(
+    state: &WorldState,
+    pos: BlockPosition,
+    frame: BlockId,
+) -> Option<BlockPosition> {
+    let inwards = frame.facing_cardinal()?.offset();
+    let sideways = BlockPosition::new(inwards.z, 0, inwards.x);
+
+    (-1..=1)
+        .map(|i| {
+            pos + BlockPosition::new(
+                inwards.x * 2 + sideways.x * i,
+                0,
+                inwards.z * 2 + sideways.z * i,
+            )
+        })
+        .find(|&center| is_end_portal_complete(state, center))
+}
+
+fn is_end_portal_complete(state: &WorldState, center: BlockPosition) -> bool {
+    let facings = [
+        FacingCardinal::North,
+        FacingCardinal::East,
+        FacingCardinal::South,
+        FacingCardinal::West,
+    ];
+    facings.iter().all(|&facing| {
+        let inwards = facing.offset();
+        let sideways = BlockPosition::new(inwards.z, 0, inwards.x);
+        (-1..=1).all(|i| {
+            let pos = center
+                + BlockPosition::new(
+                    -inwards.x * 2 + sideways.x * i,
+                    0,
+                    -inwards.z * 2 + sideways.z * i,
+                );
+            state.block_at(pos).map_or(false, |block| {
+                block.kind() == BlockKind::EndPortalFrame
+                    && block.eye() == Some(true)
+                    && block.facing_cardinal() == Some(facing)
+            })
+        })
+    })
+}
+
+/// Returns the kind of portal an entity is standing in, if any.
+fn portal_at(game: &Game, world: &World, world_id: WorldId, entity: Entity) -> Option<PortalKind> {
+    let pos = *world.get::<Position>(entity);
+    let (width, height) =
+        world
+            .try_get::<Physics>(entity)
+            .map_or((PLAYER_WIDTH, PLAYER_HEIGHT), |physics| {
+                let size = physics.bbox.size();
+                (size.x, size.y)
+            });
+
+    let min = pos - vec3(width / 2.0, 0.0, width / 2.0);
+    let max = pos + vec3(width / 2.0, height, width / 2.0);
+    let (min, max) = (min.block(), max.block());
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let kind = game
+                    .block_at(world_id, BlockPosition::new(x, y, z))
+                    .map(BlockId::kind);
+                match kind {
+                    Some(BlockKind::NetherPortal) => return Some(PortalKind::Nether),
+                    Some(BlockKind::EndPortal) => return Some(PortalKind::End),
+                    _ => (),
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Returns the ticks an entity needs to stand in a Nether portal.
+fn portal_time(world: &World, entity: Entity) -> u32 {
+    match world.try_get::<Gamemode>(entity).map(|gamemode| *gamemode) {
+        Some(Gamemode::Survival) | Some(Gamemode::Adventure) => SURVIVAL_PORTAL_TIME,
+        _ => 1,
+    }
+}
+
+fn portal_cooldown(world: &World, entity: Entity) -> u32 {
+    if world.has::<Player>(entity) {
+        PLAYER_COOLDOWN
+    } else {
+        ENTITY_COOLDOWN
+    }
+}
+
+/// System which moves entities standing in portals to their
+/// destination once the chunks around it have loaded.
+#[fecs::system]
+pub fn travel_through_portals(game: &mut Game, world: &mut World) {
+    let mut in_portal = Vec::new();
+    let mut left_portal = Vec::new();
+    for (entity, (_, world_id)) in
+        <(Read<Velocity>, Read<WorldId>)>::query().iter_entities(world.inner())
+    {
+        if world.has::<BlockEntity>(entity)
+            || world.has::<Dead>(entity)
+            || world.has::<PortalTravel>(entity)
+        {
+            continue;
+        }
+        match portal_at(game, world, *world_id, entity) {
+            Some(kind) => in_portal.push((entity, *world_id, kind)),
+            None => left_portal.push(entity),
+        }
+    }
+
+    // Cooldowns only run out outside of portals.
+    let mut cooled_down = Vec::new();
+    for (entity, mut cooldown) in
+        <Write<PortalCooldown>>::query().iter_entities_mut(world.inner_mut())
+    {
+        cooldown.0 = cooldown.0.saturating_sub(1);
+        if cooldown.0 == 0 {
+            cooled_down.push(entity);
+        }
+    }
+    for entity in cooled_down {
+        let _ = world.remove::<PortalCooldown>(entity);
+    }
+    for entity in left_portal {
+        let _ = world.remove::<PortalTime>(entity);
+    }
+
+    for (entity, world_id, kind) in in_portal {
+        if world.has::<PortalCooldown>(entity) {
+            let cooldown = portal_cooldown(world, entity);
+            world.get_mut::<PortalCooldown>(entity).0 = cooldown;
+            continue;
+        }
+
+        if kind == PortalKind::Nether {
+            let ticks = world.try_get::<PortalTime>(entity).map_or(0, |time| time.0) + 1;
+            if ticks < portal_time(world, entity) {
+                world.add(entity, PortalTime(ticks)).unwrap();
+                continue;
+            }
+            let _ = world.remove::<PortalTime>(entity);
+        }
+
+        start_travel(game, world, entity, world_id, kind);
+    }
+
+    let travelling: Vec<(Entity, PortalTravel)> = <Read<PortalTravel>>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, travel)| (entity, *travel))
+        .collect();
+    for (entity, travel) in travelling {
+        if is_destination_loaded(game, travel) {
+            arrive(game, world, entity, travel);
+        }
+    }
+}
+
+/// Works out where an entity in a portal travels to,
+/// and starts loading the chunks there.
+fn start_travel(
+    game: &mut Game,
+    world: &mut World,
+    entity: Entity,
+    world_id: WorldId,
+    kind: PortalKind,
+) {
+    let from = match dimension(game, world_id) {
+        Some(dimension) => dimension,
+        None => return,
+    };
+    let pos = *world.get::<Position>(entity);
+
+    let (to, target) = match (kind, from) {
+        (PortalKind::End, Dimension::End) => (Dimension::Overwold, game.world_spawn()),
+        (PortalKind::End, _) => (Dimension::End, END_PLATFORM.position()),
+        (PortalKind::Nether, Dimension::Nether) => (
+            Dimension::Overwold,
+            Position {
+                x: pos.x * NETHER_SCALE,
+                z: pos.z * NETHER_SCALE,
+                ..pos
+            },
+        ),
+        (PortalKind::Nether, Dimension::Overwold) => (
+            Dimension::Nether,
+            Position {
+                x: pos.x / NETHER_SCALE,
+                z: pos.z / NETHER_SCALE,
+                ..pos
+            },
+        ),
+        (PortalKind::Nether, _) => (Dimension::Nether, pos),
+    };
+    let world_id = match game.worlds.by_dimension(to) {
+        Some(world_id) => world_id,
+        None => return,
+    };
+    let target = game.worlds[world_id].border.clamp(target).block();
+
+    let travel = PortalTravel {
+        kind,
+        world: world_id,
+        target,
+    };
+    let ticket = Ticket::with_radius(TicketKind::Portal(entity), travel.chunk_radius(game));
+    world.add(entity, travel).unwrap();
+    game.handle(
+        world,
+        AddTicketRequest {
+            world: world_id,
+            chunk: target.chunk(),
+            ticket,
+        },
+    );
+}
+
+fn is_destination_loaded(game: &Game, travel: PortalTravel) -> bool {
+    let state = match game.worlds.get(travel.world) {
+        Some(state) => state,
+        None => return false,
+    };
+    let radius = i32::from(travel.chunk_radius(game));
+    let center = travel.target.chunk();
+    (-radius..=radius).all(|x| {
+        (-radius..=radius).all(|z| {
+            let chunk = ChunkPosition::new(center.x + x, center.z + z);
+            state.chunk_map.chunk_at(chunk).is_some()
+        })
+    })
+}
+
+/// Moves an entity to the destination of its portal,
+/// whose chunks have been loaded.
+fn arrive(game: &mut Game, world: &mut World, entity: Entity, travel: PortalTravel) {
+    let old = *world.get::<Position>(entity);
+    let destination = match travel.kind {
+        PortalKind::Nether => Position {
+            yaw: old.yaw,
+            pitch: old.pitch,
+            ..nether_destination(game, world, travel)
+        },
+        PortalKind::End if dimension(game, travel.world) == Some(Dimension::End) => {
+            build_end_platform(game, world, travel.world);
+            Position {
+                yaw: 90.0,
+                ..END_PLATFORM.up().position() + vec3(0.5, 0.0, 0.5)
+            }
+        }
+        PortalKind::End => Position {
+            yaw: old.yaw,
+            pitch: old.pitch,
+            ..game
+                .bed_spawn(world, entity)
+                .unwrap_or_else(|| game.world_spawn())
+        },
+    };
+
+    let _ = world.remove::<PortalTravel>(entity);
+    game.handle(
+        world,
+        RemoveTicketRequest {
+            world: travel.world,
+            chunk: travel.target.chunk(),
+            kind: TicketKind::Portal(entity),
+        },
+    );
+
+    game.change_world(world, entity, travel.world, destination);
+    let cooldown = portal_cooldown(world, entity);
+    world.add(entity, PortalCooldown(cooldown)).unwrap();
+
+    if let Some(network) = world.try_get::<Network>(entity) {
+        network.send(Effect {
+            effect_id: TRAVEL_EFFECT,
+            location: BlockPosition::default(),
+            data: 0,
+            disable_relative_volume: false,
+        });
+    }
+}
+
+/// Returns where an entity arrives through a Nether portal:
+/// at the nearest portal, which is built if there is none.
+fn nether_destination(game: &mut Game, world: &mut World, travel: PortalTravel) -> Position {
+    let state = &game.worlds[travel.world];
+    let radius = if state.dimension == Dimension::Nether {
+        NETHER_SEARCH_RADIUS
+    } else {
+        OVERWORLD_SEARCH_RADIUS
+    };
+
+    let portal = state
+        .poi
+        .nearest(travel.target, radius, |poi| {
+            poi.kind == PoiType::NetherPortal
+        })
+        .map(|poi| poi.pos);
+    let portal = match portal {
+        Some(mut pos) => {
+            // Arrive at the bottom of the portal.
+            while state
+                .block_at(pos.down())
+                .map_or(false, |block| block.kind() == BlockKind::NetherPortal)
+            {
+                pos = pos.down();
+            }
+            pos
+        }
+        None => build_nether_portal(game, world, travel.world, travel.target),
+    };
+    portal.position() + vec3(0.5, 0.0, 0.5)
+}
+
+/// Returns the greatest height to which portals lead in a world.
+fn max_portal_height(game: &Game, state: &WorldState) -> i32 {
+    game.dimension_types
+        .get(state.dimension.type_id())
+        .map_or(256, |ty| ty.logical_height)
+        - 1
+}
+
+/// Returns whether a portal along the X axis whose lowest
+/// inside block on the negative side is at `pos` can be
+/// built without replacing any blocks.
+fn is_portal_site(state: &WorldState, pos: BlockPosition) -> bool {
+    let is_air = |x: i32, y: i32, z: i32| {
+        state
+            .block_at(pos + BlockPosition::new(x, y, z))
+            .map_or(false, BlockId::is_air)
+    };
+    let is_solid = |x: i32| {
+        state
+            .block_at(pos + BlockPosition::new(x, -1, 0))
+            .map_or(false, BlockId::is_solid)
+    };
+
+    (0..=1).all(is_solid)
+        && (-1..=2).all(|x| (0..=3).all(|y| is_air(x, y, 0)))
+        // Room to step out of the portal on both sides.
+        && (0..=1).all(|x| (0..=1).all(|y| is_air(x, y, -1) && is_air(x, y, 1)))
+}
+
+/// Builds a Nether portal near the given position, returning
+/// the position of its lowest inside block.
+fn build_nether_portal(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    target: BlockPosition,
+) -> BlockPosition {
+    let state = &game.worlds[world_id];
+    let max_y = max_portal_height(game, state) - 4;
+
+    let mut site: Option<(BlockPosition, i64)> = None;
+    for x in -BUILD_RADIUS..=BUILD_RADIUS {
+        for z in -BUILD_RADIUS..=BUILD_RADIUS {
+            for y in (1..=max_y).rev() {
+                let pos = BlockPosition::new(target.x + x, y, target.z + z);
+                if !is_portal_site(state, pos) {
+                    continue;
+                }
+                let distance = distance_squared(pos, target);
+                if site.map_or(true, |(_, best)| distance < best) {
+                    site = Some((pos, distance));
+                }
+            }
+        }
+    }
+
+    let corner = match site {
+        Some((pos, _)) => pos,
+        None => {
+            // Build a platform for the portal in the air.
+            let y = target.y.min(max_y - 6).max(MIN_FORCED_HEIGHT);
+            let corner = BlockPosition::new(target.x, y, target.z);
+            for x in -1..=2 {
+                for z in -1..=1 {
+                    for y in -1..=3 {
+                        let block = if y == -1 && (0..=1).contains(&x) {
+                            BlockId::obsidian()
+                        } else {
+                            BlockId::air()
+                        };
+                        let pos = corner + BlockPosition::new(x, y, z);
+                        game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Unknown);
+                    }
+                }
+            }
+            corner
+        }
+    };
+
+    for x in -1..=2 {
+        for y in -1..=3 {
+            let frame = x == -1 || x == 2 || y == -1 || y == 3;
+            let block = if frame {
+                BlockId::obsidian()
+            } else {
+                BlockId::nether_portal().with_axis_xz(AxisXz::X)
+            };
+            let pos = corner + BlockPosition::new(x, y, 0);
+            game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Unknown);
+        }
+    }
+    corner
+}
+
+fn distance_squared(a: BlockPosition, b: BlockPosition) -> i64 {
+    let (x, y, z) = (
+        i64::from(a.x - b.x),
+        i64::from(a.y - b.y),
+        i64::from(a.z - b.z),
+    );
+    x * x + y * y + z * z
+}
+
+/// Builds the obsidian platform entities arrive at in the
+/// End, clearing the space above it.
+fn build_end_platform(game: &mut Game, world: &mut World, world_id: WorldId) {
+    for x in -2..=2 {
+        for z in -2..=2 {
+            for y in 0..=3 {
+                let block = if y == 0 {
+                    BlockId::obsidian()
+                } else {
+                    BlockId::air()
+                };
+                let pos = END_PLATFORM + BlockPosition::new(x, y, z);
+                if game.block_at(world_id, pos) != Some(block) {
+                    game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Unknown);
+                }
+            }
+        }
+    }
+}
+
+/// Releases the chunks loaded for an entity which
+/// was despawned while travelling through a portal.
+#[fecs::event_handler]
+pub fn on_entity_despawn_cancel_travel(
+    event: &EntityDespawnEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let travel = match world.try_get::<PortalTravel>(event.entity) {
+        Some(travel) => *travel,
+        None => return,
+    };
+    game.handle(
+        world,
+        RemoveTicketRequest {
+            world: travel.world,
+            chunk: travel.target.chunk(),
+            kind: TicketKind::Portal(event.entity),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::chunk::Chunk;
+    use feather_core::items::ItemStack;
+    use feather_core::network::packets::Face;
+    use feather_core::position;
+    use feather_test_framework::Test;
+
+    /// Builds an obsidian frame with a 2x3 inside
+    /// whose lowest block is at the given position.
+    fn build_frame(test: &mut Test, corner: BlockPosition) {
+        for x in -1..=2 {
+            for y in -1..=3 {
+                if x == -1 || x == 2 || y == -1 || y == 3 {
+                    let pos = corner + BlockPosition::new(x, y, 0);
+                    test.set_block(pos, BlockId::obsidian());
+                }
+            }
+        }
+    }
+
+    fn is_portal(test: &Test, world_id: WorldId, pos: BlockPosition) -> bool {
+        test.game
+            .block_at(world_id, pos)
+            .map_or(false, |block| block.kind() == BlockKind::NetherPortal)
+    }
+
+    #[test]
+    fn fire_lights_portal_in_frame() {
+        let mut test = Test::new();
+        let corner = BlockPosition::new(0, 65, 0);
+        build_frame(&mut test, corner);
+
+        assert!(light_nether_portal(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            corner + BlockPosition::new(1, 2, 0),
+        ));
+        for x in 0..=1 {
+            for y in 0..=2 {
+                let pos = corner + BlockPosition::new(x, y, 0);
+                assert_eq!(
+                    test.game.block_at(WorldId::MAIN, pos),
+                    Some(BlockId::nether_portal().with_axis_xz(AxisXz::X))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn incomplete_frame_is_not_lit() {
+        let mut test = Test::new();
+        let corner = BlockPosition::new(0, 65, 0);
+        build_frame(&mut test, corner);
+        test.set_block(corner + BlockPosition::new(2, 1, 0), BlockId::air());
+
+        assert!(!light_nether_portal(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            corner,
+        ));
+        assert!(!is_portal(&test, WorldId::MAIN, corner));
+    }
+
+    #[test]
+    fn breaking_frame_breaks_portal() {
+        let mut test = Test::new();
+        let corner = BlockPosition::new(0, 65, 0);
+        build_frame(&mut test, corner);
+        light_nether_portal(&mut test.game, &mut test.world, WorldId::MAIN, corner);
+
+        let pos = corner + BlockPosition::new(2, 1, 0);
+        test.set_block(pos, BlockId::air());
+        test.handle(
+            BlockUpdateEvent {
+                world: WorldId::MAIN,
+                pos,
+                old: BlockId::obsidian(),
+                new: BlockId::air(),
+                cause: BlockUpdateCause::Unknown,
+            },
+            on_block_update_break_portals,
+        );
+
+        assert!(!is_portal(
+            &test,
+            WorldId::MAIN,
+            corner + BlockPosition::new(1, 1, 0)
+        ));
+        assert!(is_portal(&test, WorldId::MAIN, corner));
+    }
+
+    #[test]
+    fn eyes_activate_end_portal() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 65.0, 0.5));
+        let center = BlockPosition::new(0, 64, 0);
+        let facings = [
+            FacingCardinal::North,
+            FacingCardinal::East,
+            FacingCardinal::South,
+            FacingCardinal::West,
+        ];
+        for &facing in &facings {
+            let inwards = facing.offset();
+            for i in -1..=1 {
+                let pos = center
+                    + BlockPosition::new(
+                        -inwards.x * 2 + inwards.z * i,
+                        0,
+                        -inwards.z * 2 + inwards.x * i,
+                    );
+                let frame = BlockId::end_portal_frame()
+                    .with_facing_cardinal(facing)
+                    .with_eye(true);
+                test.set_block(pos, frame);
+            }
+        }
+        let last = center + BlockPosition::new(0, 0, -2);
+        let frame = BlockId::end_portal_frame().with_facing_cardinal(FacingCardinal::South);
+        test.set_block(last, frame);
+        assert_ne!(
+            test.game.block_at(WorldId::MAIN, center),
+            Some(BlockId::end_portal())
+        );
+
+        test.handle(
+            ItemUseOnBlockEvent {
+                player,
+                world: WorldId::MAIN,
+                pos: last,
+                face: Face::Top,
+                stack: ItemStack::new(Item::EnderEye, 1),
+            },
+            on_item_use_on_block_insert_eye,
+        );
+
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, last).unwrap().eye(),
+            Some(true)
+        );
+        for x in -1..=1 {
+            for z in -1..=1 {
+                assert_eq!(
+                    test.game
+                        .block_at(WorldId::MAIN, center + BlockPosition::new(x, 0, z)),
+                    Some(BlockId::end_portal())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn entities_travel_to_the_nether() {
+        let mut test = Test::new();
+        let generator = test.game.worlds[WorldId::MAIN].generator.clone();
+        let nether = test
+            .game
+            .worlds
+            .add("nether", Dimension::Nether, "nether", generator);
+        for x in -2..=2 {
+            for z in -2..=2 {
+                test.game.worlds[nether]
+                    .chunk_map
+                    .insert(Chunk::new(ChunkPosition::new(x, z)));
+            }
+        }
+
+        let corner = BlockPosition::new(8, 65, 8);
+        build_frame(&mut test, corner);
+        light_nether_portal(&mut test.game, &mut test.world, WorldId::MAIN, corner);
+        let player = test.player("player", position!(8.5, 65.0, 8.5));
+
+        test.run(travel_through_portals);
+
+        assert_eq!(*test.world.get::<WorldId>(player), nether);
+        assert!(test.world.has::<PortalCooldown>(player));
+        assert!(!test.world.has::<PortalTravel>(player));
+        // No portal existed, so one was built.
+        let pos = test.world.get::<Position>(player).block();
+        assert!(is_portal(&test, nether, pos));
+    }
+}
@@ -0,0 +1,1628 @@
+//! Redstone power and the components which emit and react to it.
+//!
+//! Power sources, such as levers and redstone torches, emit power
+//! from 0 to 15 into adjacent blocks. Solid opaque blocks conduct
+//! power: a block strongly powered by a source powers all redstone
+//! components next to it. Redstone wire only weakly powers the
+//! blocks it points into, which then power components other than
+//! wire. Wire loses one level of power per block it carries it.
+//!
+//! When a block changes, the components within two blocks of it
+//! are updated. Wire and redstone lamps turning on change at once,
+//! while torches, repeaters, comparators and observers change after
+//! a delay using scheduled block ticks. Buttons and pressure plates
+//! are released by scheduled ticks as well, as are detector rails
+//! once minecarts leave them. Powered and activator rails pass the
+//! power they receive on along a line of up to eight rails.
+//!
+//! See https://minecraft.gamepedia.com/Redstone_mechanics.
+
+use crate::comparator::{self, ComparatorOutput};
+use crate::door;
+use crate::jukebox::{self, Record};
+use crate::note_block;
+use crate::schedule_block_tick;
+use crate::tnt::{ignite_tnt, is_tnt};
+use ahash::{AHashMap, AHashSet};
+use feather_core::blocks::{
+    BlockId, BlockKind, ComparatorMode, EastWire, Face, FacingCardinal, FacingCubic, NorthWire,
+    PoweredRailShape, SimplifiedBlockKind, SouthWire, WestWire,
+};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{BlockPosition, Gamemode, Position};
+use feather_server_entity::{frame_signal, Minecart};
+use feather_server_types::{
+    BlockEntity, BlockTickEvent, BlockTickKind, BlockUpdateCause, BlockUpdateEvent, BumpVec, Dead,
+    Game, Health, InteractionHandler, Inventory, InventoryUpdateEvent, ItemFrameUpdateEvent, Sound,
+    TickPriority, WorldId, WorldState,
+};
+use fecs::{component, Entity, IntoQuery, Read, World};
+use std::cell::RefCell;
+
+/// The greatest level of power.
+const MAX_POWER: u8 = 15;
+
+/// Ticks before a redstone torch changes.
+const TORCH_DELAY: u64 = 2;
+/// Ticks before a comparator or observer changes.
+const COMPARATOR_DELAY: u64 = 2;
+/// Ticks before a redstone lamp turns off.
+const LAMP_DELAY: u64 = 4;
+/// Ticks before an entity-free pressure plate is released.
+const PRESSURE_PLATE_DELAY: u64 = 20;
+/// Ticks before an entity-free weighted pressure plate is released.
+const WEIGHTED_PRESSURE_PLATE_DELAY: u64 = 10;
+/// Ticks before a detector rail without minecarts turns off.
+const DETECTOR_RAIL_DELAY: u64 = 20;
+/// Greatest number of rails power is passed on along
+/// by powered and activator rails.
+const MAX_RAIL_DISTANCE: usize = 8;
+
+const DIRECTIONS: [FacingCubic; 6] = [
+    FacingCubic::Down,
+    FacingCubic::Up,
+    FacingCubic::North,
+    FacingCubic::South,
+    FacingCubic::West,
+    FacingCubic::East,
+];
+
+const HORIZONTAL_DIRECTIONS: [FacingCubic; 4] = [
+    FacingCubic::North,
+    FacingCubic::East,
+    FacingCubic::South,
+    FacingCubic::West,
+];
+
+/// Returns whether a block emits power, or can when activated.
+pub fn is_power_source(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::RedstoneBlock
+            | SimplifiedBlockKind::Lever
+            | SimplifiedBlockKind::StoneButton
+            | SimplifiedBlockKind::WoodenButton
+            | SimplifiedBlockKind::StonePressurePlate
+            | SimplifiedBlockKind::WoodenPressurePlate
+            | SimplifiedBlockKind::LightWeightedPressurePlate
+            | SimplifiedBlockKind::HeavyWeightedPressurePlate
+            | SimplifiedBlockKind::DetectorRail
+            | SimplifiedBlockKind::RedstoneTorch
+            | SimplifiedBlockKind::RedstoneWallTorch
+            | SimplifiedBlockKind::Repeater
+            | SimplifiedBlockKind::Comparator
+            | SimplifiedBlockKind::Observer
+            | SimplifiedBlockKind::RedstoneWire
+    )
+}
+
+/// Returns whether a block conducts the power of the sources next to it.
+fn is_conductor(block: BlockId) -> bool {
+    block.is_opaque() && !is_power_source(block)
+}
+
+fn is_wire(block: BlockId) -> bool {
+    block.simplified_kind() == SimplifiedBlockKind::RedstoneWire
+}
+
+fn is_diode(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::Repeater | SimplifiedBlockKind::Comparator
+    )
+}
+
+fn is_pressure_plate(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::StonePressurePlate
+            | SimplifiedBlockKind::WoodenPressurePlate
+            | SimplifiedBlockKind::LightWeightedPressurePlate
+            | SimplifiedBlockKind::HeavyWeightedPressurePlate
+    )
+}
+
+/// Returns the direction from a torch, lever
+/// or button to the block it is attached to.
+fn attached_direction(block: BlockId) -> FacingCubic {
+    let wall = || {
+        block
+            .facing_cardinal()
+            .unwrap_or(FacingCardinal::North)
+            .opposite()
+            .to_facing_cubic()
+    };
+    match block.simplified_kind() {
+        SimplifiedBlockKind::RedstoneWallTorch => wall(),
+        SimplifiedBlockKind::Lever
+        | SimplifiedBlockKind::StoneButton
+        | SimplifiedBlockKind::WoodenButton => match block.face() {
+            Some(Face::Wall) => wall(),
+            Some(Face::Ceiling) => FacingCubic::Up,
+            _ => FacingCubic::Down,
+        },
+        _ => FacingCubic::Down,
+    }
+}
+
+/// Returns the direction from a repeater, comparator
+/// or observer to the block it outputs power into.
+fn output_direction(block: BlockId) -> FacingCubic {
+    match block.facing_cubic() {
+        Some(facing) => facing.opposite(),
+        None => input_direction(block).opposite(),
+    }
+}
+
+/// Returns the direction from a repeater or comparator
+/// to the block it takes its input from.
+fn input_direction(block: BlockId) -> FacingCubic {
+    block
+        .facing_cardinal()
+        .unwrap_or(FacingCardinal::North)
+        .to_facing_cubic()
+}
+
+/// How redstone wire connects to the block next to it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum WireSide {
+    /// Connected to wire on top of the block.
+    Up,
+    Side,
+    None,
+}
+
+fn wire_side(wire: BlockId, dir: FacingCubic) -> WireSide {
+    let side = match dir {
+        FacingCubic::North => wire.north_wire().map(|side| match side {
+            NorthWire::Up => WireSide::Up,
+            NorthWire::Side => WireSide::Side,
+            NorthWire::None => WireSide::None,
+        }),
+        FacingCubic::East => wire.east_wire().map(|side| match side {
+            EastWire::Up => WireSide::Up,
+            EastWire::Side => WireSide::Side,
+            EastWire::None => WireSide::None,
+        }),
+        FacingCubic::South => wire.south_wire().map(|side| match side {
+            SouthWire::Up => WireSide::Up,
+            SouthWire::Side => WireSide::Side,
+            SouthWire::None => WireSide::None,
+        }),
+        FacingCubic::West => wire.west_wire().map(|side| match side {
+            WestWire::Up => WireSide::Up,
+            WestWire::Side => WireSide::Side,
+            WestWire::None => WireSide::None,
+        }),
+        FacingCubic::Up | FacingCubic::Down => None,
+    };
+    side.unwrap_or(WireSide::None)
+}
+
+fn with_wire_side(wire: BlockId, dir: FacingCubic, side: WireSide) -> BlockId {
+    match dir {
+        FacingCubic::North => wire.with_north_wire(match side {
+            WireSide::Up => NorthWire::Up,
+            WireSide::Side => NorthWire::Side,
+            WireSide::None => NorthWire::None,
+        }),
+        FacingCubic::East => wire.with_east_wire(match side {
+            WireSide::Up => EastWire::Up,
+            WireSide::Side => EastWire::Side,
+            WireSide::None => EastWire::None,
+        }),
+        FacingCubic::South => wire.with_south_wire(match side {
+            WireSide::Up => SouthWire::Up,
+            WireSide::Side => SouthWire::Side,
+            WireSide::None => SouthWire::None,
+        }),
+        FacingCubic::West => wire.with_west_wire(match side {
+            WireSide::Up => WestWire::Up,
+            WireSide::Side => WestWire::Side,
+            WireSide::None => WestWire::None,
+        }),
+        FacingCubic::Up | FacingCubic::Down => wire,
+    }
+}
+
+fn wire_power(wire: BlockId) -> u8 {
+    wire.power().unwrap_or(0) as u8
+}
+
+/// Returns the power redstone wire emits into the block next to it in
+/// direction `dir`. Wire powers the block below it and the blocks it
+/// points into, or all blocks around it if it isn't connected to anything.
+fn wire_power_towards(wire: BlockId, dir: FacingCubic) -> u8 {
+    let power = wire_power(wire);
+    let connected = |dir| wire_side(wire, dir) != WireSide::None;
+    let powers = match dir {
+        FacingCubic::Down => true,
+        FacingCubic::Up => false,
+        _ => {
+            let perpendicular = HORIZONTAL_DIRECTIONS
+                .iter()
+                .filter(|other| other.axis() != dir.axis());
+            !HORIZONTAL_DIRECTIONS.iter().copied().any(connected)
+                || (connected(dir.opposite()) && !perpendicular.copied().any(connected))
+        }
+    };
+
+    if powers {
+        power
+    } else {
+        0
+    }
+}
+
+/// Returns whether redstone wire connects to a block next to it
+/// in direction `dir`, without going up or down a block.
+fn wire_connects_to(block: BlockId, dir: FacingCubic) -> bool {
+    match block.simplified_kind() {
+        SimplifiedBlockKind::Repeater => input_direction(block).axis() == dir.axis(),
+        SimplifiedBlockKind::Observer => block.facing_cubic() == Some(dir),
+        _ => is_power_source(block),
+    }
+}
+
+/// View of the blocks in a world used to compute
+/// the power received and emitted by each block.
+struct Circuit<'a> {
+    state: &'a WorldState,
+    world: &'a World,
+    world_id: WorldId,
+}
+
+impl<'a> Circuit<'a> {
+    fn new(game: &'a Game, world: &'a World, world_id: WorldId) -> Self {
+        Self {
+            state: &game.worlds[world_id],
+            world,
+            world_id,
+        }
+    }
+
+    fn block(&self, pos: BlockPosition) -> BlockId {
+        self.state.block_at(pos).unwrap_or_else(BlockId::air)
+    }
+
+    fn is_conductor(&self, pos: BlockPosition) -> bool {
+        is_conductor(self.block(pos))
+    }
+
+    fn is_wire(&self, pos: BlockPosition) -> bool {
+        is_wire(self.block(pos))
+    }
+
+    /// Returns the power the block at `pos` emits into the block
+    /// next to it in direction `dir`. Power emitted by wire
+    /// only counts if `wires` is set.
+    fn weak_power(&self, pos: BlockPosition, dir: FacingCubic, wires: bool) -> u8 {
+        let block = self.block(pos);
+        let powered = block.powered() == Some(true);
+        let full_if = |condition: bool| if condition { MAX_POWER } else { 0 };
+
+        match block.simplified_kind() {
+            SimplifiedBlockKind::RedstoneBlock => MAX_POWER,
+            SimplifiedBlockKind::Lever
+            | SimplifiedBlockKind::StoneButton
+            | SimplifiedBlockKind::WoodenButton
+            | SimplifiedBlockKind::StonePressurePlate
+            | SimplifiedBlockKind::WoodenPressurePlate
+            | SimplifiedBlockKind::DetectorRail => full_if(powered),
+            SimplifiedBlockKind::LightWeightedPressurePlate
+            | SimplifiedBlockKind::HeavyWeightedPressurePlate => block.power().unwrap_or(0) as u8,
+            SimplifiedBlockKind::RedstoneTorch | SimplifiedBlockKind::RedstoneWallTorch => {
+                full_if(block.lit() == Some(true) && dir != attached_direction(block))
+            }
+            SimplifiedBlockKind::Repeater | SimplifiedBlockKind::Observer => {
+                full_if(powered && dir == output_direction(block))
+            }
+            SimplifiedBlockKind::Comparator if powered && dir == output_direction(block) => {
+                self.comparator_signal(pos)
+            }
+            SimplifiedBlockKind::RedstoneWire if wires => wire_power_towards(block, dir),
+            _ => 0,
+        }
+    }
+
+    /// Returns the power the block at `pos` emits into the block next
+    /// to it in direction `dir` which is conducted to other blocks.
+    fn strong_power(&self, pos: BlockPosition, dir: FacingCubic, wires: bool) -> u8 {
+        let power = self.weak_power(pos, dir, wires);
+        if power == 0 {
+            return 0;
+        }
+
+        let block = self.block(pos);
+        let strong = match block.simplified_kind() {
+            SimplifiedBlockKind::RedstoneBlock => false,
+            SimplifiedBlockKind::Lever
+            | SimplifiedBlockKind::StoneButton
+            | SimplifiedBlockKind::WoodenButton => dir == attached_direction(block),
+            SimplifiedBlockKind::RedstoneTorch | SimplifiedBlockKind::RedstoneWallTorch => {
+                dir == FacingCubic::Up
+            }
+            SimplifiedBlockKind::DetectorRail => dir == FacingCubic::Down,
+            _ if is_pressure_plate(block) => dir == FacingCubic::Down,
+            _ => true,
+        };
+
+        if strong {
+            power
+        } else {
+            0
+        }
+    }
+
+    /// Returns the greatest power a block is strongly powered with.
+    fn strong_power_into(&self, pos: BlockPosition, wires: bool) -> u8 {
+        DIRECTIONS
+            .iter()
+            .map(|&dir| self.strong_power(pos + dir.offset(), dir.opposite(), wires))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the power the block at `pos` gives the block next to
+    /// it in direction `dir`, either emitting it or conducting it.
+    fn power_from(&self, pos: BlockPosition, dir: FacingCubic, wires: bool) -> u8 {
+        if self.is_conductor(pos) {
+            self.strong_power_into(pos, wires)
+        } else {
+            self.weak_power(pos, dir, wires)
+        }
+    }
+
+    /// Returns the greatest power a block receives from the blocks next to it.
+    fn received_power(&self, pos: BlockPosition, wires: bool) -> u8 {
+        DIRECTIONS
+            .iter()
+            .map(|&dir| self.power_from(pos + dir.offset(), dir.opposite(), wires))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns whether a powered or activator rail receives power,
+    /// either itself or through the line of rails of the same kind
+    /// it joins, up to `MAX_RAIL_DISTANCE` rails away.
+    fn is_rail_powered(&self, pos: BlockPosition, rail: BlockId) -> bool {
+        let mut visited = vec![pos];
+        let mut line = vec![pos];
+        for _ in 0..=MAX_RAIL_DISTANCE {
+            if line.iter().any(|&pos| self.received_power(pos, true) > 0) {
+                return true;
+            }
+            line = line
+                .iter()
+                .flat_map(|&pos| self.joined_rails(pos, rail.kind()))
+                .filter(|pos| !visited.contains(pos))
+                .collect();
+            visited.extend(&line);
+        }
+        false
+    }
+
+    /// Returns the positions of the rails of the given kind which
+    /// the powered or activator rail at `pos` joins in a line.
+    fn joined_rails(&self, pos: BlockPosition, kind: BlockKind) -> Vec<BlockPosition> {
+        let runs_north_south = |block: BlockId| {
+            block.powered_rail_shape().map(|shape| {
+                matches!(
+                    shape,
+                    PoweredRailShape::NorthSouth
+                        | PoweredRailShape::AscendingNorth
+                        | PoweredRailShape::AscendingSouth
+                )
+            })
+        };
+        let north_south = match runs_north_south(self.block(pos)) {
+            Some(north_south) => north_south,
+            None => return vec![],
+        };
+        let sides = if north_south {
+            [FacingCubic::North, FacingCubic::South]
+        } else {
+            [FacingCubic::East, FacingCubic::West]
+        };
+
+        sides
+            .iter()
+            .filter_map(|side| {
+                let next = pos + side.offset();
+                // Rails on slopes join rails one block higher or lower.
+                [next, next.up(), next.down()]
+                    .iter()
+                    .copied()
+                    .find(|&other| {
+                        let block = self.block(other);
+                        block.kind() == kind && runs_north_south(block) == Some(north_south)
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns whether the block a redstone torch is attached to
+    /// is powered, which turns the torch off.
+    fn is_torch_powered(&self, pos: BlockPosition, torch: BlockId) -> bool {
+        let dir = attached_direction(torch);
+        self.power_from(pos + dir.offset(), dir.opposite(), true) > 0
+    }
+
+    /// Returns the power a repeater or comparator receives from behind.
+    fn diode_input(&self, pos: BlockPosition, diode: BlockId) -> u8 {
+        let dir = input_direction(diode);
+        let input = pos + dir.offset();
+        let power = self.power_from(input, dir.opposite(), true);
+
+        let input_block = self.block(input);
+        if is_wire(input_block) {
+            power.max(wire_power(input_block))
+        } else {
+            power
+        }
+    }
+
+    /// Returns the power a repeater or comparator receives from its
+    /// sides. Repeaters are locked by repeaters and comparators
+    /// only, so other sources are ignored unless `all_sources` is set.
+    fn diode_side_input(&self, pos: BlockPosition, diode: BlockId, all_sources: bool) -> u8 {
+        let facing = input_direction(diode)
+            .to_facing_cardinal()
+            .unwrap_or(FacingCardinal::North);
+
+        [facing.left(), facing.right()]
+            .iter()
+            .map(|side| {
+                let dir = side.to_facing_cubic();
+                let side_pos = pos + dir.offset();
+                let block = self.block(side_pos);
+                if is_diode(block) {
+                    self.strong_power(side_pos, dir.opposite(), true)
+                } else if !all_sources {
+                    0
+                } else if block.simplified_kind() == SimplifiedBlockKind::RedstoneBlock {
+                    MAX_POWER
+                } else if is_wire(block) {
+                    wire_power(block)
+                } else if is_power_source(block) {
+                    self.strong_power(side_pos, dir.opposite(), true)
+                } else {
+                    0
+                }
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn is_repeater_locked(&self, pos: BlockPosition, repeater: BlockId) -> bool {
+        self.diode_side_input(pos, repeater, false) > 0
+    }
+
+    /// Returns whether a repeater or comparator outputs into the side of
+    /// another one, whose ticks then run first to keep circuits in sync.
+    fn is_facing_diode(&self, pos: BlockPosition, diode: BlockId) -> bool {
+        let output = self.block(pos + output_direction(diode).offset());
+        is_diode(output) && output.facing_cardinal() != diode.facing_cardinal()
+    }
+
+    /// Returns the signal a comparator reads from
+    /// the contents of a container, if any.
+    fn container_signal(&self, pos: BlockPosition) -> Option<u8> {
+        let entity = *self.state.block_entities.get(&pos)?;
+        if let Some(record) = self.world.try_get::<Record>(entity) {
+            return Some(jukebox::record_signal(&record));
+        }
+        let inventory = self.world.try_get::<Inventory>(entity)?;
+        Some(comparator::container_signal(&inventory))
+    }
+
+    /// Returns the signal currently output by a comparator.
+    fn comparator_signal(&self, pos: BlockPosition) -> u8 {
+        self.state
+            .block_entities
+            .get(&pos)
+            .and_then(|&entity| self.world.try_get::<ComparatorOutput>(entity))
+            .map_or(0, |output| output.0)
+    }
+
+    /// Computes the signal a comparator should output from its inputs.
+    fn comparator_output(&self, pos: BlockPosition, comparator: BlockId) -> u8 {
+        let dir = input_direction(comparator);
+        let input = pos + dir.offset();
+
+        let mut rear = self.diode_input(pos, comparator);
+        if let Some(signal) = self.container_signal(input) {
+            rear = signal;
+        } else if rear < MAX_POWER && self.is_conductor(input) {
+            // Comparators read containers through a block, and
+            // item frames hanging on the other side of it.
+            let behind = input + dir.offset();
+            if let Some(signal) = self.container_signal(behind) {
+                rear = signal;
+            } else if self.block(behind).is_air() {
+                if let Some(signal) = frame_signal(self.world, self.world_id, behind, dir) {
+                    rear = signal;
+                }
+            }
+        }
+
+        let side = self.diode_side_input(pos, comparator, true);
+        match comparator.comparator_mode() {
+            Some(ComparatorMode::Subtract) => rear.saturating_sub(side),
+            _ if side > rear => 0,
+            _ => rear,
+        }
+    }
+
+    /// Returns how a redstone wire connects to the block next to it.
+    fn wire_side(&self, pos: BlockPosition, dir: FacingCubic) -> WireSide {
+        let side = pos + dir.offset();
+        if !self.is_conductor(pos.up()) && self.is_conductor(side) && self.is_wire(side.up()) {
+            return WireSide::Up;
+        }
+
+        if wire_connects_to(self.block(side), dir)
+            || (!self.is_conductor(side) && self.is_wire(side.down()))
+        {
+            WireSide::Side
+        } else {
+            WireSide::None
+        }
+    }
+
+    /// Returns the redstone wire which carries power
+    /// to or from the wire at `pos`.
+    fn connected_wires(&self, pos: BlockPosition) -> Vec<BlockPosition> {
+        let mut wires = Vec::new();
+        for dir in &HORIZONTAL_DIRECTIONS {
+            let side = pos + dir.offset();
+            if self.is_wire(side) {
+                wires.push(side);
+            }
+
+            if self.is_conductor(side) {
+                if !self.is_conductor(pos.up()) && self.is_wire(side.up()) {
+                    wires.push(side.up());
+                }
+            } else if self.is_wire(side.down()) {
+                wires.push(side.down());
+            }
+        }
+        wires
+    }
+
+    /// Computes the power and shape of each redstone wire
+    /// connected to the wire at `start`, directly or through
+    /// other wire. Power from other wire is one level weaker
+    /// than the wire it comes from.
+    fn wire_network(&self, start: BlockPosition) -> Vec<(BlockPosition, BlockId)> {
+        let mut positions = vec![start];
+        let mut indices = AHashMap::new();
+        indices.insert(start, 0);
+        let mut neighbors: Vec<Vec<usize>> = Vec::new();
+
+        let mut next = 0;
+        while next < positions.len() {
+            let mut wire_neighbors = Vec::new();
+            for pos in self.connected_wires(positions[next]) {
+                let index = *indices.entry(pos).or_insert_with(|| {
+                    positions.push(pos);
+                    positions.len() - 1
+                });
+                wire_neighbors.push(index);
+            }
+            neighbors.push(wire_neighbors);
+            next += 1;
+        }
+
+        let sources: Vec<u8> = positions
+            .iter()
+            .map(|&pos| self.received_power(pos, false))
+            .collect();
+        let mut power = sources.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in 0..positions.len() {
+                let from_wires = neighbors[index]
+                    .iter()
+                    .map(|&neighbor| power[neighbor].saturating_sub(1))
+                    .max()
+                    .unwrap_or(0);
+                let new = sources[index].max(from_wires);
+                if new > power[index] {
+                    power[index] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        positions
+            .iter()
+            .zip(power)
+            .map(|(&pos, power)| {
+                let mut wire = self.block(pos).with_power(i32::from(power));
+                for &dir in &HORIZONTAL_DIRECTIONS {
+                    wire = with_wire_side(wire, dir, self.wire_side(pos, dir));
+                }
+                (pos, wire)
+            })
+            .collect()
+    }
+}
+
+/// Returns whether a piston is powered from any side but its front.
+/// Pistons are also powered by the power sources around the block
+/// above them, even if it isn't powered itself.
+pub(crate) fn is_piston_powered(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    facing: FacingCubic,
+) -> bool {
+    let circuit = Circuit::new(game, world, world_id);
+    let powered_from = |pos: BlockPosition, except: FacingCubic| {
+        DIRECTIONS
+            .iter()
+            .filter(|&&dir| dir != except)
+            .any(|&dir| circuit.power_from(pos + dir.offset(), dir.opposite(), true) > 0)
+    };
+    powered_from(pos, facing) || powered_from(pos.up(), FacingCubic::Down)
+}
+
+/// Returns the positions within two blocks of `pos`, including itself.
+fn nearby_positions(pos: BlockPosition) -> impl Iterator<Item = BlockPosition> {
+    (-2..=2).flat_map(move |x: i32| {
+        (-2..=2).flat_map(move |y: i32| {
+            (-2..=2)
+                .filter(move |z: &i32| x.abs() + y.abs() + z.abs() <= 2)
+                .map(move |z| pos + BlockPosition::new(x, y, z))
+        })
+    })
+}
+
+/// Sets the power and shape of the redstone wire
+/// connected to the wire at the given positions.
+fn update_wires(game: &mut Game, world: &mut World, world_id: WorldId, wires: &[BlockPosition]) {
+    let mut changes = Vec::new();
+    {
+        let circuit = Circuit::new(game, world, world_id);
+        let mut updated = AHashSet::new();
+        for &start in wires {
+            if updated.contains(&start) {
+                continue;
+            }
+            for (pos, wire) in circuit.wire_network(start) {
+                updated.insert(pos);
+                if circuit.block(pos) != wire {
+                    changes.push((pos, wire));
+                }
+            }
+        }
+    }
+
+    for (pos, wire) in changes {
+        game.set_block_at(world, world_id, pos, wire, BlockUpdateCause::Redstone);
+    }
+}
+
+/// Updates the redstone components within two blocks of a position,
+/// which may be powered through a block by a change at the position.
+/// Wire is only updated if `wires` is set.
+fn update_nearby_components(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    wires: bool,
+) {
+    let mut wire_positions = Vec::new();
+    let mut components = Vec::new();
+    for pos in nearby_positions(pos) {
+        match game.block_at(world_id, pos) {
+            Some(block) if is_wire(block) => wire_positions.push(pos),
+            Some(_) => components.push(pos),
+            None => (),
+        }
+    }
+
+    if wires && !wire_positions.is_empty() {
+        update_wires(game, world, world_id, &wire_positions);
+    }
+    for pos in components {
+        update_component(game, world, world_id, pos);
+    }
+}
+
+/// Updates a redstone component after the power it receives may have
+/// changed, either changing it at once or scheduling it to change.
+fn update_component(game: &mut Game, world: &mut World, world_id: WorldId, pos: BlockPosition) {
+    let block = match game.block_at(world_id, pos) {
+        Some(block) => block,
+        None => return,
+    };
+
+    match block.simplified_kind() {
+        SimplifiedBlockKind::RedstoneTorch | SimplifiedBlockKind::RedstoneWallTorch => {
+            let lit = !Circuit::new(game, world, world_id).is_torch_powered(pos, block);
+            if block.lit() != Some(lit) {
+                schedule_block_tick(game, world_id, pos, TORCH_DELAY, TickPriority::NORMAL);
+            }
+        }
+        SimplifiedBlockKind::Piston | SimplifiedBlockKind::StickyPiston => {
+            crate::piston::update_piston(game, world, world_id, pos)
+        }
+        SimplifiedBlockKind::Repeater => update_repeater(game, world, world_id, pos, block),
+        SimplifiedBlockKind::Comparator => {
+            let (output, signal, facing_diode) = {
+                let circuit = Circuit::new(game, world, world_id);
+                (
+                    circuit.comparator_output(pos, block),
+                    circuit.comparator_signal(pos),
+                    circuit.is_facing_diode(pos, block),
+                )
+            };
+            if output != signal || block.powered() != Some(output > 0) {
+                let priority = if facing_diode {
+                    TickPriority::HIGH
+                } else {
+                    TickPriority::NORMAL
+                };
+                schedule_block_tick(game, world_id, pos, COMPARATOR_DELAY, priority);
+            }
+        }
+        SimplifiedBlockKind::NoteBlock => {
+            let powered = Circuit::new(game, world, world_id).received_power(pos, true) > 0;
+            note_block::set_powered(game, world, world_id, pos, block, powered);
+        }
+        SimplifiedBlockKind::PoweredRail | SimplifiedBlockKind::ActivatorRail => {
+            update_powered_rail(game, world, world_id, pos, block)
+        }
+        _ if door::is_openable(block) => {
+            let powered = {
+                let circuit = Circuit::new(game, world, world_id);
+                let other_half = door::other_door_half(block, pos);
+                circuit.received_power(pos, true) > 0
+                    || other_half.map_or(false, |other| circuit.received_power(other, true) > 0)
+            };
+            door::set_openable_powered(game, world, world_id, pos, powered);
+        }
+        _ if is_tnt(block) => {
+            if Circuit::new(game, world, world_id).received_power(pos, true) > 0 {
+                ignite_tnt(game, world, world_id, pos, BlockUpdateCause::Redstone);
+            }
+        }
+        SimplifiedBlockKind::RedstoneLamp => {
+            let powered = Circuit::new(game, world, world_id).received_power(pos, true) > 0;
+            match block.lit() {
+                Some(false) if powered => {
+                    game.set_block_at(
+                        world,
+                        world_id,
+                        pos,
+                        block.with_lit(true),
+                        BlockUpdateCause::Redstone,
+                    );
+                }
+                Some(true) if !powered => {
+                    schedule_block_tick(game, world_id, pos, LAMP_DELAY, TickPriority::NORMAL);
+                }
+                _ => (),
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Turns a powered or activator rail on or off, and updates the
+/// rails it joins, which it may pass power on to or have powered.
+fn update_powered_rail(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    rail: BlockId,
+) {
+    let (powered, joined) = {
+        let circuit = Circuit::new(game, world, world_id);
+        (
+            circuit.is_rail_powered(pos, rail),
+            circuit.joined_rails(pos, rail.kind()),
+        )
+    };
+    if rail.powered() == Some(powered) {
+        return;
+    }
+
+    game.set_block_at(
+        world,
+        world_id,
+        pos,
+        rail.with_powered(powered),
+        BlockUpdateCause::Redstone,
+    );
+    for pos in joined {
+        update_component(game, world, world_id, pos);
+    }
+}
+
+/// Locks or unlocks a repeater, and schedules it to
+/// turn on or off if its input has changed.
+fn update_repeater(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+) {
+    let (locked, input, facing_diode) = {
+        let circuit = Circuit::new(game, world, world_id);
+        (
+            circuit.is_repeater_locked(pos, block),
+            circuit.diode_input(pos, block) > 0,
+            circuit.is_facing_diode(pos, block),
+        )
+    };
+
+    if block.locked() != Some(locked) {
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            block.with_locked(locked),
+            BlockUpdateCause::Redstone,
+        );
+    }
+    if locked {
+        return;
+    }
+
+    let powered = block.powered() == Some(true);
+    if input != powered {
+        let priority = if facing_diode {
+            TickPriority::EXTREMELY_HIGH
+        } else if powered {
+            TickPriority::VERY_HIGH
+        } else {
+            TickPriority::HIGH
+        };
+        schedule_block_tick(game, world_id, pos, repeater_delay(block), priority);
+    }
+}
+
+/// Returns the number of ticks a repeater delays its signal by.
+fn repeater_delay(repeater: BlockId) -> u64 {
+    repeater.delay().unwrap_or(1) as u64 * 2
+}
+
+/// Schedules ticks for the observers watching a changed block.
+fn trigger_observers(game: &mut Game, world_id: WorldId, pos: BlockPosition) {
+    for &dir in &DIRECTIONS {
+        let observer_pos = pos + dir.offset();
+        let watching = game
+            .block_at(world_id, observer_pos)
+            .map_or(false, |block| {
+                block.simplified_kind() == SimplifiedBlockKind::Observer
+                    && block.facing_cubic() == Some(dir.opposite())
+                    && block.powered() == Some(false)
+            });
+        if watching {
+            schedule_block_tick(
+                game,
+                world_id,
+                observer_pos,
+                COMPARATOR_DELAY,
+                TickPriority::NORMAL,
+            );
+        }
+    }
+}
+
+/// When a block changes, triggers the observers watching it
+/// and updates the redstone components around it.
+#[fecs::event_handler]
+pub fn on_block_update_update_redstone(
+    event: &BlockUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.old == event.new {
+        return;
+    }
+
+    trigger_observers(game, event.world, event.pos);
+
+    // Components only depend on power sources and whether blocks
+    // conduct power, so other changes to a block's state don't matter.
+    if event.old.kind() == event.new.kind()
+        && !is_power_source(event.old)
+        && !is_power_source(event.new)
+    {
+        return;
+    }
+
+    // Wire which changed is updated along with the rest of its wire.
+    let wires = !(is_wire(event.old) && is_wire(event.new));
+    update_nearby_components(game, world, event.world, event.pos, wires);
+}
+
+/// Updates the comparators which may read the contents
+/// of the container at the given position.
+pub(crate) fn update_comparators(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+) {
+    update_nearby_components(game, world, world_id, pos, false);
+}
+
+/// When the contents of a container change, updates
+/// the comparators reading its contents.
+#[fecs::event_handler]
+pub fn on_inventory_update_update_comparators(
+    event: &InventoryUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<BlockEntity>(event.entity) {
+        return;
+    }
+    let pos = match world.try_get::<BlockPosition>(event.entity) {
+        Some(pos) => *pos,
+        None => return,
+    };
+
+    let world_id = game.world_of(world, event.entity);
+    update_comparators(game, world, world_id, pos);
+}
+
+/// When the contents of an item frame change, or it is placed
+/// or broken, updates the comparators reading it.
+#[fecs::event_handler]
+pub fn on_item_frame_update_update_comparators(
+    event: &ItemFrameUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    update_comparators(game, world, event.world, event.pos);
+}
+
+/// Changes redstone components when their scheduled ticks are due.
+#[fecs::event_handler]
+pub fn on_block_tick_update_redstone(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Scheduled {
+        return;
+    }
+
+    let (world_id, pos, block) = (event.world, event.pos, event.block);
+    let new = match block.simplified_kind() {
+        SimplifiedBlockKind::RedstoneTorch | SimplifiedBlockKind::RedstoneWallTorch => {
+            let lit = !Circuit::new(game, world, world_id).is_torch_powered(pos, block);
+            block.with_lit(lit)
+        }
+        SimplifiedBlockKind::Repeater => {
+            let (locked, input) = {
+                let circuit = Circuit::new(game, world, world_id);
+                (
+                    circuit.is_repeater_locked(pos, block),
+                    circuit.diode_input(pos, block) > 0,
+                )
+            };
+            match block.powered() {
+                _ if locked => return,
+                Some(true) if !input => block.with_powered(false),
+                Some(false) => {
+                    // Short pulses are extended to the repeater's delay.
+                    if !input {
+                        schedule_block_tick(
+                            game,
+                            world_id,
+                            pos,
+                            repeater_delay(block),
+                            TickPriority::VERY_HIGH,
+                        );
+                    }
+                    block.with_powered(true)
+                }
+                _ => return,
+            }
+        }
+        SimplifiedBlockKind::Comparator => {
+            tick_comparator(game, world, world_id, pos, block);
+            return;
+        }
+        SimplifiedBlockKind::RedstoneLamp => {
+            if Circuit::new(game, world, world_id).received_power(pos, true) > 0 {
+                return;
+            }
+            block.with_lit(false)
+        }
+        SimplifiedBlockKind::Observer => {
+            if block.powered() == Some(false) {
+                schedule_block_tick(game, world_id, pos, COMPARATOR_DELAY, TickPriority::NORMAL);
+            }
+            block.with_powered(block.powered() != Some(true))
+        }
+        SimplifiedBlockKind::StoneButton | SimplifiedBlockKind::WoodenButton => {
+            if block.powered() != Some(true) {
+                return;
+            }
+            play_click(game, world, world_id, pos, block, false);
+            block.with_powered(false)
+        }
+        _ if is_pressure_plate(block) => {
+            update_pressure_plate(game, world, world_id, pos, block);
+            return;
+        }
+        SimplifiedBlockKind::DetectorRail => {
+            update_detector_rail(game, world, world_id, pos, block);
+            return;
+        }
+        _ => return,
+    };
+
+    if new != block {
+        game.set_block_at(world, world_id, pos, new, BlockUpdateCause::Redstone);
+    }
+}
+
+/// Sets the signal a comparator outputs, turning it on or off.
+fn tick_comparator(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+) {
+    let (output, signal) = {
+        let circuit = Circuit::new(game, world, world_id);
+        (
+            circuit.comparator_output(pos, block),
+            circuit.comparator_signal(pos),
+        )
+    };
+
+    if let Some(&entity) = game.worlds[world_id].block_entities.get(&pos) {
+        if let Some(mut stored) = world.try_get_mut::<ComparatorOutput>(entity) {
+            stored.0 = output;
+        }
+    }
+
+    let powered = output > 0;
+    if block.powered() != Some(powered) {
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            block.with_powered(powered),
+            BlockUpdateCause::Redstone,
+        );
+    } else if output != signal {
+        // The block is unchanged, so notify the components it powers.
+        update_nearby_components(game, world, world_id, pos, true);
+    }
+}
+
+/// Returns the number of entities standing on a pressure plate
+/// which press it. Stone pressure plates are only pressed by
+/// players and mobs.
+fn entities_on_pressure_plate(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    plate: BlockId,
+) -> usize {
+    let living_only = plate.simplified_kind() == SimplifiedBlockKind::StonePressurePlate;
+    game.worlds[world_id]
+        .chunk_entities
+        .entities_in_chunk(pos.chunk())
+        .iter()
+        .copied()
+        .filter(|&entity| presses_pressure_plates(world, entity))
+        .filter(|&entity| !living_only || world.has::<Health>(entity))
+        .filter(|&entity| {
+            world
+                .try_get::<Position>(entity)
+                .map_or(false, |entity_pos| {
+                    entity_pos.block() == pos && entity_pos.y < f64::from(pos.y) + 0.25
+                })
+        })
+        .count()
+}
+
+fn is_pressed(plate: BlockId) -> bool {
+    plate.power().unwrap_or(0) > 0 || plate.powered() == Some(true)
+}
+
+fn presses_pressure_plates(world: &World, entity: Entity) -> bool {
+    !world.has::<BlockEntity>(entity)
+        && !world.has::<Dead>(entity)
+        && world
+            .try_get::<Gamemode>(entity)
+            .map_or(true, |gamemode| *gamemode != Gamemode::Spectator)
+}
+
+/// Sets the power of a pressure plate from the entities on it.
+/// While pressed, the plate is ticked again after a delay to
+/// release it once the entities have left.
+fn update_pressure_plate(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    plate: BlockId,
+) {
+    let count = entities_on_pressure_plate(game, world, world_id, pos, plate);
+    let (power, delay) = match plate.simplified_kind() {
+        SimplifiedBlockKind::LightWeightedPressurePlate => {
+            (count.min(15) as i32, WEIGHTED_PRESSURE_PLATE_DELAY)
+        }
+        SimplifiedBlockKind::HeavyWeightedPressurePlate => (
+            ((count + 9) / 10).min(15) as i32,
+            WEIGHTED_PRESSURE_PLATE_DELAY,
+        ),
+        _ => (
+            if count > 0 { i32::from(MAX_POWER) } else { 0 },
+            PRESSURE_PLATE_DELAY,
+        ),
+    };
+
+    let new = if plate.has_power() {
+        plate.with_power(power)
+    } else {
+        plate.with_powered(power > 0)
+    };
+    if new != plate {
+        if is_pressed(plate) != (power > 0) {
+            play_click(game, world, world_id, pos, plate, power > 0);
+        }
+        game.set_block_at(world, world_id, pos, new, BlockUpdateCause::Redstone);
+    }
+
+    if power > 0 {
+        schedule_block_tick(game, world_id, pos, delay, TickPriority::NORMAL);
+    }
+}
+
+/// System which presses the pressure plates entities stand on.
+#[fecs::system]
+pub fn press_pressure_plates(game: &mut Game, world: &mut World) {
+    let pressed = RefCell::new(BumpVec::<(WorldId, BlockPosition)>::new_in(game.bump()));
+
+    <Read<Position>>::query()
+        .filter(!component::<BlockEntity>())
+        .filter(!component::<Dead>())
+        .for_each_entities(world.inner(), |(entity, pos)| {
+            let world_id = game.world_of(world, entity);
+            let block_pos = pos.block();
+            // Pressed plates are released by scheduled ticks.
+            let unpressed = game.block_at(world_id, block_pos).map_or(false, |block| {
+                is_pressure_plate(block) && !is_pressed(block)
+            });
+            if unpressed {
+                pressed.borrow_mut().push((world_id, block_pos));
+            }
+        });
+
+    let mut pressed = pressed.into_inner();
+    pressed.sort_unstable_by_key(|&(world_id, pos)| (world_id.0, pos.x, pos.y, pos.z));
+    pressed.dedup();
+    for (world_id, pos) in pressed {
+        if let Some(plate) = game.block_at(world_id, pos) {
+            update_pressure_plate(game, world, world_id, pos, plate);
+        }
+    }
+}
+
+/// Returns whether a minecart is on the rail at the given position.
+fn has_minecart(game: &Game, world: &World, world_id: WorldId, pos: BlockPosition) -> bool {
+    game.worlds[world_id]
+        .chunk_entities
+        .entities_in_chunk(pos.chunk())
+        .iter()
+        .any(|&entity| {
+            world.has::<Minecart>(entity)
+                && world
+                    .try_get::<Position>(entity)
+                    .map_or(false, |cart_pos| cart_pos.block() == pos)
+        })
+}
+
+/// Powers a detector rail while a minecart is on it. While
+/// powered, the rail is ticked again after a delay to turn
+/// it off once the minecarts have left.
+fn update_detector_rail(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    rail: BlockId,
+) {
+    let occupied = has_minecart(game, world, world_id, pos);
+    if rail.powered() != Some(occupied) {
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            rail.with_powered(occupied),
+            BlockUpdateCause::Redstone,
+        );
+    }
+
+    if occupied {
+        schedule_block_tick(
+            game,
+            world_id,
+            pos,
+            DETECTOR_RAIL_DELAY,
+            TickPriority::NORMAL,
+        );
+    }
+}
+
+/// System which powers the detector rails minecarts are on.
+#[fecs::system]
+pub fn press_detector_rails(game: &mut Game, world: &mut World) {
+    let mut pressed = Vec::new();
+    for (cart, pos) in <Read<Position>>::query()
+        .filter(component::<Minecart>())
+        .iter_entities(world.inner())
+    {
+        let world_id = game.world_of(world, cart);
+        let rail_pos = pos.block();
+        // Powered rails are turned off by scheduled ticks.
+        let unpowered = game.block_at(world_id, rail_pos).map_or(false, |block| {
+            block.simplified_kind() == SimplifiedBlockKind::DetectorRail
+                && block.powered() == Some(false)
+        });
+        if unpowered {
+            pressed.push((world_id, rail_pos));
+        }
+    }
+
+    pressed.sort_unstable_by_key(|&(world_id, pos)| (world_id.0, pos.x, pos.y, pos.z));
+    pressed.dedup();
+    for (world_id, pos) in pressed {
+        if let Some(rail) = game.block_at(world_id, pos) {
+            update_detector_rail(game, world, world_id, pos, rail);
+        }
+    }
+}
+
+/// Plays the sound of a button or pressure plate
+/// being pressed, or of a lever being switched.
+fn play_click(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+    on: bool,
+) {
+    let (name, pitch_on, pitch_off) = match block.simplified_kind() {
+        SimplifiedBlockKind::Lever => ("block.lever.click", 0.6, 0.5),
+        SimplifiedBlockKind::StoneButton => ("block.stone_button.click", 0.6, 0.5),
+        SimplifiedBlockKind::WoodenButton => ("block.wooden_button.click", 0.6, 0.5),
+        SimplifiedBlockKind::StonePressurePlate => ("block.stone_pressure_plate.click", 0.6, 0.5),
+        SimplifiedBlockKind::WoodenPressurePlate => ("block.wooden_pressure_plate.click", 0.8, 0.7),
+        SimplifiedBlockKind::LightWeightedPressurePlate
+        | SimplifiedBlockKind::HeavyWeightedPressurePlate => {
+            ("block.metal_pressure_plate.click", 0.9, 0.75)
+        }
+        _ => return,
+    };
+
+    let name = match block.simplified_kind() {
+        SimplifiedBlockKind::Lever => name.to_owned(),
+        _ if on => format!("{}_on", name),
+        _ => format!("{}_off", name),
+    };
+    let pitch = if on { pitch_on } else { pitch_off };
+    let sound = Sound::new(name, SoundCategory::Blocks)
+        .volume(0.3)
+        .pitch(pitch);
+    game.play_sound(world, world_id, Position::from(pos), &sound);
+}
+
+/// Handler for players right clicking on levers, buttons,
+/// repeaters and comparators.
+struct RedstoneInteraction(BlockKind);
+
+inventory::submit!(Box::new(RedstoneInteraction(BlockKind::Lever)) as Box<dyn InteractionHandler>);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::StoneButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::OakButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::SpruceButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::BirchButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::JungleButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::AcaciaButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::DarkOakButton)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::Repeater)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(RedstoneInteraction(BlockKind::Comparator)) as Box<dyn InteractionHandler>
+);
+
+impl InteractionHandler for RedstoneInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        _window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let block = match game.block_at(world_id, pos) {
+            Some(block) => block,
+            None => return,
+        };
+
+        let new = match block.simplified_kind() {
+            SimplifiedBlockKind::Lever => {
+                let powered = block.powered() != Some(true);
+                play_click(game, world, world_id, pos, block, powered);
+                block.with_powered(powered)
+            }
+            SimplifiedBlockKind::StoneButton | SimplifiedBlockKind::WoodenButton => {
+                if block.powered() == Some(true) {
+                    return;
+                }
+                play_click(game, world, world_id, pos, block, true);
+                block.with_powered(true)
+            }
+            SimplifiedBlockKind::Repeater => block.with_delay(block.delay().unwrap_or(1) % 4 + 1),
+            SimplifiedBlockKind::Comparator => {
+                let mode = match block.comparator_mode() {
+                    Some(ComparatorMode::Compare) => ComparatorMode::Subtract,
+                    _ => ComparatorMode::Compare,
+                };
+                let pitch = if mode == ComparatorMode::Subtract {
+                    0.55
+                } else {
+                    0.5
+                };
+                let sound = Sound::new("block.comparator.click", SoundCategory::Blocks)
+                    .volume(0.3)
+                    .pitch(pitch);
+                game.play_sound(world, world_id, Position::from(pos), &sound);
+                block.with_comparator_mode(mode)
+            }
+            _ => return,
+        };
+
+        game.set_block_at(world, world_id, pos, new, BlockUpdateCause::Entity(player));
+
+        if new.simplified_kind() == SimplifiedBlockKind::StoneButton
+            || new.simplified_kind() == SimplifiedBlockKind::WoodenButton
+        {
+            let delay = if new.simplified_kind() == SimplifiedBlockKind::StoneButton {
+                20
+            } else {
+                30
+            };
+            schedule_block_tick(game, world_id, pos, delay, TickPriority::NORMAL);
+        }
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::HalfUpperLower;
+    use feather_core::position;
+    use feather_test_framework::Test;
+
+    fn tick(test: &mut Test, pos: BlockPosition) {
+        let event = BlockTickEvent {
+            world: WorldId::MAIN,
+            pos,
+            block: test.block(pos),
+            kind: BlockTickKind::Scheduled,
+        };
+        test.handle(event, on_block_tick_update_redstone);
+    }
+
+    #[test]
+    fn wire_power_decays() {
+        let mut test = Test::new();
+        test.set_block(BlockPosition::new(0, 64, 0), BlockId::redstone_block());
+        for x in 1..=4 {
+            test.set_block(BlockPosition::new(x, 64, 0), BlockId::redstone_wire());
+        }
+
+        update_wires(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            &[BlockPosition::new(4, 64, 0)],
+        );
+
+        for x in 1..=4 {
+            let wire = test.block(BlockPosition::new(x, 64, 0));
+            assert_eq!(wire.power(), Some(16 - x));
+            assert_eq!(wire_side(wire, FacingCubic::West), WireSide::Side);
+        }
+    }
+
+    #[test]
+    fn torch_powered_through_block() {
+        let mut test = Test::new();
+        let torch = BlockPosition::new(1, 65, 1);
+        let lever = BlockPosition::new(2, 64, 1);
+        test.set_block(BlockPosition::new(1, 64, 1), BlockId::stone());
+        test.set_block(torch, BlockId::redstone_torch());
+        test.set_block(
+            lever,
+            BlockId::lever()
+                .with_face(Face::Wall)
+                .with_facing_cardinal(FacingCardinal::East),
+        );
+
+        let circuit = Circuit::new(&test.game, &test.world, WorldId::MAIN);
+        assert!(!circuit.is_torch_powered(torch, test.block(torch)));
+
+        let powered = test.block(lever).with_powered(true);
+        test.set_block(lever, powered);
+        let circuit = Circuit::new(&test.game, &test.world, WorldId::MAIN);
+        assert!(circuit.is_torch_powered(torch, test.block(torch)));
+
+        tick(&mut test, torch);
+        assert_eq!(test.block(torch).lit(), Some(false));
+    }
+
+    #[test]
+    fn tnt_primed_by_power() {
+        let mut test = Test::new();
+        let tnt = BlockPosition::new(1, 64, 1);
+        test.set_block(tnt, BlockId::tnt());
+
+        let lever = BlockPosition::new(1, 65, 1);
+        test.set_block(lever, BlockId::lever().with_powered(true));
+        update_nearby_components(&mut test.game, &mut test.world, WorldId::MAIN, lever, true);
+
+        assert!(test.block(tnt).is_air());
+    }
+
+    #[test]
+    fn door_opened_by_power() {
+        let mut test = Test::new();
+        let door = BlockId::iron_door().with_facing_cardinal(FacingCardinal::North);
+        let pos = BlockPosition::new(1, 64, 1);
+        test.set_block(pos, door.with_half_upper_lower(HalfUpperLower::Lower));
+        test.set_block(pos.up(), door.with_half_upper_lower(HalfUpperLower::Upper));
+
+        let lever = BlockPosition::new(2, 65, 1);
+        test.set_block(lever, BlockId::lever().with_powered(true));
+        update_nearby_components(&mut test.game, &mut test.world, WorldId::MAIN, lever, true);
+        assert_eq!(test.block(pos).open(), Some(true));
+        assert_eq!(test.block(pos.up()).open(), Some(true));
+
+        test.set_block(lever, BlockId::lever());
+        update_nearby_components(&mut test.game, &mut test.world, WorldId::MAIN, lever, true);
+        assert_eq!(test.block(pos).open(), Some(false));
+        assert_eq!(test.block(pos.up()).powered(), Some(false));
+    }
+
+    #[test]
+    fn comparator_modes() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(1, 64, 1);
+        let comparator = BlockId::comparator().with_facing_cardinal(FacingCardinal::North);
+        test.set_block(pos, comparator);
+        test.set_block(BlockPosition::new(1, 64, 0), BlockId::redstone_block());
+        test.set_block(
+            BlockPosition::new(2, 64, 1),
+            BlockId::redstone_wire().with_power(5),
+        );
+
+        let circuit = Circuit::new(&test.game, &test.world, WorldId::MAIN);
+        assert_eq!(circuit.comparator_output(pos, comparator), 15);
+        let subtract = comparator.with_comparator_mode(ComparatorMode::Subtract);
+        assert_eq!(circuit.comparator_output(pos, subtract), 10);
+    }
+
+    #[test]
+    fn observer_pulse() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(1, 64, 1);
+        let watched = BlockPosition::new(1, 64, 0);
+        test.set_block(
+            pos,
+            BlockId::observer().with_facing_cubic(FacingCubic::North),
+        );
+
+        trigger_observers(&mut test.game, WorldId::MAIN, watched);
+        assert!(test.game.worlds[WorldId::MAIN]
+            .block_ticks
+            .is_scheduled(pos, BlockKind::Observer));
+
+        tick(&mut test, pos);
+        assert_eq!(test.block(pos).powered(), Some(true));
+        tick(&mut test, pos);
+        assert_eq!(test.block(pos).powered(), Some(false));
+    }
+
+    #[test]
+    fn pressure_plate_pressed() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(1, 64, 1);
+        test.set_block(pos, BlockId::oak_pressure_plate());
+        let player = test.player("player", position!(1.5, 64.0, 1.5));
+
+        test.run(press_pressure_plates);
+        assert_eq!(test.block(pos).powered(), Some(true));
+
+        test.position(player, position!(5.5, 64.0, 5.5));
+        tick(&mut test, pos);
+        assert_eq!(test.block(pos).powered(), Some(false));
+    }
+
+    #[test]
+    fn powered_rails_pass_power_on() {
+        let mut test = Test::new();
+        let rail = BlockId::powered_rail().with_powered_rail_shape(PoweredRailShape::EastWest);
+        for x in 0..=10 {
+            test.set_block(BlockPosition::new(x, 64, 0), rail);
+        }
+        test.set_block(BlockPosition::new(0, 64, 1), BlockId::redstone_block());
+
+        update_component(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            BlockPosition::new(0, 64, 0),
+        );
+        for x in 0..=8 {
+            assert_eq!(
+                test.block(BlockPosition::new(x, 64, 0)).powered(),
+                Some(true)
+            );
+        }
+        for x in 9..=10 {
+            assert_eq!(
+                test.block(BlockPosition::new(x, 64, 0)).powered(),
+                Some(false)
+            );
+        }
+    }
+
+    #[test]
+    fn detector_rail_detects_minecarts() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(1, 64, 1);
+        test.set_block(pos, BlockId::detector_rail());
+        let cart = test.entity(feather_server_entity::minecart::create(
+            position!(1.5, 64.0625, 1.5),
+            feather_server_entity::MinecartKind::Rideable,
+        ));
+
+        test.run(press_detector_rails);
+        assert_eq!(test.block(pos).powered(), Some(true));
+
+        test.position(cart, position!(5.5, 64.0625, 5.5));
+        tick(&mut test, pos);
+        assert_eq!(test.block(pos).powered(), Some(false));
+    }
+}
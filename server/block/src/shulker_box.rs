@@ -0,0 +1,277 @@
+//! Shulker boxes, which store items like a single chest.
+//! They can only be opened when the block in front of
+//! their lid doesn't stop it from rising.
+//!
+//! Broken shulker boxes drop their contents like other
+//! containers rather than keeping them in the dropped item.
+
+use crate::container::{ContainerViewers, Lid};
+use crate::{Layout, ShouldReplace};
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::blocks::BlockKind;
+use feather_core::inventory::{Area, Window};
+use feather_core::network::packets::OpenWindow;
+use feather_core::text::TextRoot;
+use feather_core::util::BlockPosition;
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, Game, InteractionHandler, Inventory, WorldId,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, World};
+
+pub const SLOTS: usize = 27;
+
+const LAYOUT: Layout = &[(Area::Chest, SLOTS)];
+
+/// The shulker box in each color, along with the
+/// uncolored one.
+pub const KINDS: [BlockKind; 17] = [
+    BlockKind::ShulkerBox,
+    BlockKind::WhiteShulkerBox,
+    BlockKind::OrangeShulkerBox,
+    BlockKind::MagentaShulkerBox,
+    BlockKind::LightBlueShulkerBox,
+    BlockKind::YellowShulkerBox,
+    BlockKind::LimeShulkerBox,
+    BlockKind::PinkShulkerBox,
+    BlockKind::GrayShulkerBox,
+    BlockKind::LightGrayShulkerBox,
+    BlockKind::CyanShulkerBox,
+    BlockKind::PurpleShulkerBox,
+    BlockKind::BlueShulkerBox,
+    BlockKind::BrownShulkerBox,
+    BlockKind::GreenShulkerBox,
+    BlockKind::RedShulkerBox,
+    BlockKind::BlackShulkerBox,
+];
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::ShulkerBox,
+});
+
+/// Marker component for shulker boxes.
+pub struct ShulkerBox;
+
+/// Creates a shulker box.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with_inventory(pos, Inventory::chest())
+}
+
+/// Creates a shulker box with the given inventory.
+pub fn create_with_inventory(pos: BlockPosition, inventory: Inventory) -> EntityBuilder {
+    crate::base(pos)
+        .with(ShulkerBox)
+        .with(ContainerViewers::default())
+        .with(Lid::ShulkerBox)
+        .with(inventory)
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    BlockEntityData {
+        base: crate::serialize_base(accessor),
+        kind: BlockEntityKind::ShulkerBox {
+            items: crate::serialize_inventory(&*accessor.get::<Inventory>(), LAYOUT),
+            loot_table: None,
+            loot_table_seed: None,
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let slots = match data.kind {
+        BlockEntityKind::ShulkerBox { items, .. } => items,
+        _ => bail!("not a shulker box"),
+    };
+
+    let inventory = Inventory::chest();
+    crate::load_inventory(&inventory, LAYOUT, &slots);
+
+    Ok(create_with_inventory(pos, inventory))
+}
+
+/// Returns whether the lid of the shulker box at the given
+/// position is free to open.
+fn can_open(game: &Game, world_id: WorldId, pos: BlockPosition) -> bool {
+    let facing = match game
+        .block_at(world_id, pos)
+        .and_then(|block| block.facing_cubic())
+    {
+        Some(facing) => facing,
+        None => return false,
+    };
+    game.block_at(world_id, pos + facing.offset())
+        .map_or(true, |block| !block.is_solid())
+}
+
+/// Handler for players right clicking on shulker boxes.
+struct ShulkerBoxInteraction(BlockKind);
+
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::ShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::WhiteShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::OrangeShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::MagentaShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::LightBlueShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::YellowShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::LimeShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::PinkShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::GrayShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::LightGrayShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::CyanShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::PurpleShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::BlueShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::BrownShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::GreenShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::RedShulkerBox)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(ShulkerBoxInteraction(BlockKind::BlackShulkerBox)) as Box<dyn InteractionHandler>
+);
+
+impl InteractionHandler for ShulkerBoxInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        window_id: u8,
+    ) {
+        let world_id = game.world_of(world, player);
+        let shulker_box = match game.worlds[world_id].block_entities.get(&pos).copied() {
+            Some(shulker_box) if world.has::<ShulkerBox>(shulker_box) => shulker_box,
+            _ => return,
+        };
+        if !can_open(game, world_id, pos) {
+            return;
+        }
+
+        crate::open_window(
+            game,
+            world,
+            player,
+            shulker_box,
+            Window::chest(player, shulker_box),
+            OpenWindow {
+                window_id,
+                window_type: String::from("minecraft:shulker_box"),
+                window_title: TextRoot::from("Shulker Box").into(),
+                number_of_slots: SLOTS as u8,
+                entity_id: None,
+            },
+            LAYOUT,
+        );
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::{BlockId, FacingCubic};
+    use feather_core::position;
+    use feather_server_types::BlockUpdateCause;
+    use feather_test_framework::Test;
+
+    fn place_shulker_box(test: &mut Test, pos: BlockPosition) -> Entity {
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            BlockId::red_shulker_box().with_facing_cubic(FacingCubic::Up),
+            BlockUpdateCause::Unknown,
+        );
+        let shulker_box = test.entity(create(pos));
+        test.game.worlds[WorldId::MAIN]
+            .block_entities
+            .insert(pos, shulker_box);
+        shulker_box
+    }
+
+    fn opened(test: &Test, player: Entity, shulker_box: Entity) -> bool {
+        test.world
+            .get::<Window>(player)
+            .wrapped_entities()
+            .contains(&shulker_box)
+    }
+
+    #[test]
+    fn opens_window() {
+        let mut test = Test::new();
+        let player = test.player("", position!(2.0, 64.0, 0.0));
+        let pos = BlockPosition::new(0, 64, 0);
+        let shulker_box = place_shulker_box(&mut test, pos);
+
+        ShulkerBoxInteraction(BlockKind::RedShulkerBox).handle_interaction(
+            &mut test.game,
+            &mut test.world,
+            pos,
+            player,
+            1,
+        );
+        assert!(opened(&test, player, shulker_box));
+        let packet = test.sent::<OpenWindow>(player).unwrap();
+        assert_eq!(packet.window_type, "minecraft:shulker_box");
+    }
+
+    #[test]
+    fn blocked_lid() {
+        let mut test = Test::new();
+        let player = test.player("", position!(2.0, 64.0, 0.0));
+        let pos = BlockPosition::new(0, 64, 0);
+        let shulker_box = place_shulker_box(&mut test, pos);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos.up(),
+            BlockId::stone(),
+            BlockUpdateCause::Unknown,
+        );
+
+        ShulkerBoxInteraction(BlockKind::RedShulkerBox).handle_interaction(
+            &mut test.game,
+            &mut test.world,
+            pos,
+            player,
+            1,
+        );
+        assert!(!opened(&test, player, shulker_box));
+    }
+}
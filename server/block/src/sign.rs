@@ -0,0 +1,266 @@
+//! Signs, which store four lines of text.
+
+use crate::ShouldReplace;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{BlockEntityData, BlockEntityKind, BlockEntityVariant};
+use feather_core::network::packets::{OpenSignEditor, UpdateBlockEntity, UpdateSign};
+use feather_core::network::Packet;
+use feather_core::text::Text;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, BlockUpdateCause, BlockUpdateEvent, Game,
+    Network, PacketBuffers, SpawnPacketCreator,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, World};
+use nbt::Blob;
+use std::sync::Arc;
+
+/// The Update Block Entity action which sets the text of a sign.
+const SET_SIGN_TEXT: u8 = 9;
+
+/// The maximum length of a line sent by the client.
+const MAX_LINE_LENGTH: usize = 384;
+
+/// The maximum distance from which a player may edit a sign.
+const MAX_EDIT_DISTANCE: f64 = 8.0;
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::Sign,
+});
+
+/// The lines of text on a sign, as JSON text components.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignText(pub [String; 4]);
+
+impl Default for SignText {
+    fn default() -> Self {
+        let empty = || String::from(r#"{"text":""}"#);
+        SignText([empty(), empty(), empty(), empty()])
+    }
+}
+
+/// The player who placed a sign and may set its text.
+/// Removed once the player has done so.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SignEditor(pub Entity);
+
+/// Creates a sign without any text.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with_text(pos, SignText::default())
+}
+
+/// Creates a sign with the given text.
+pub fn create_with_text(pos: BlockPosition, text: SignText) -> EntityBuilder {
+    crate::base(pos)
+        .with(text)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let pos = *accessor.get::<BlockPosition>();
+    let text = accessor.get::<SignText>();
+    Box::new(text_packet(pos, &text))
+}
+
+/// Returns the packet which shows the text of a sign.
+fn text_packet(pos: BlockPosition, text: &SignText) -> UpdateBlockEntity {
+    let mut data = Blob::new();
+    data.insert("id", "minecraft:sign").unwrap();
+    data.insert("x", pos.x).unwrap();
+    data.insert("y", pos.y).unwrap();
+    data.insert("z", pos.z).unwrap();
+    for (i, line) in text.0.iter().enumerate() {
+        data.insert(format!("Text{}", i + 1), line.as_str())
+            .unwrap();
+    }
+
+    UpdateBlockEntity {
+        location: pos,
+        action: SET_SIGN_TEXT,
+        data,
+    }
+}
+
+/// When a player places a sign, opens the sign
+/// editor so they can write its text.
+///
+/// Must run after `on_block_update_create_block_entity`.
+#[fecs::event_handler]
+pub fn on_sign_place_open_editor(event: &BlockUpdateEvent, game: &Game, world: &mut World) {
+    let player = match event.cause {
+        BlockUpdateCause::Entity(player) => player,
+        _ => return,
+    };
+    let sign = match game.worlds[event.world].block_entities.get(&event.pos) {
+        Some(&sign) if world.has::<SignText>(sign) => sign,
+        _ => return,
+    };
+    if event.old.kind() == event.new.kind() {
+        return;
+    }
+
+    match world.try_get::<Network>(player) {
+        Some(network) => network.send(OpenSignEditor {
+            location: event.pos,
+        }),
+        None => return,
+    }
+    world.add(sign, SignEditor(player)).unwrap();
+}
+
+/// System which handles Update Sign packets, sent by
+/// players once they finish editing a sign, by setting
+/// its text and broadcasting it.
+#[fecs::system]
+pub fn handle_sign_updates(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    for (player, packet) in packet_buffers.received::<UpdateSign>() {
+        if world.is_alive(player) {
+            update_sign(game, world, player, packet);
+        }
+    }
+}
+
+fn update_sign(game: &mut Game, world: &mut World, player: Entity, packet: UpdateSign) {
+    let world_id = game.world_of(world, player);
+    let sign = match game.worlds[world_id].block_entities.get(&packet.location) {
+        Some(&sign) => sign,
+        None => return,
+    };
+
+    // Only the player who placed the sign may edit it, once.
+    if world.try_get::<SignEditor>(sign).map(|editor| editor.0) != Some(player) {
+        log::debug!("Player {:?} tried to edit a sign they may not edit", player);
+        return;
+    }
+    let _ = world.remove::<SignEditor>(sign);
+
+    let distance = world
+        .get::<Position>(player)
+        .distance_to(Position::from(packet.location));
+    if distance > MAX_EDIT_DISTANCE {
+        return;
+    }
+
+    let line = |line: String| {
+        let line: String = line.chars().take(MAX_LINE_LENGTH).collect();
+        String::from(Text::of(line))
+    };
+    let text = SignText([
+        line(packet.line_1),
+        line(packet.line_2),
+        line(packet.line_3),
+        line(packet.line_4),
+    ]);
+
+    let packet = text_packet(packet.location, &text);
+    world.add(sign, text).unwrap();
+    game.worlds[world_id]
+        .dirty_chunks
+        .mark_unsaved(packet.location.chunk());
+    game.broadcast_entity_update(world, packet, sign, None);
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    let base = crate::serialize_base(accessor);
+    let [text1, text2, text3, text4] = accessor.get::<SignText>().0.clone();
+
+    BlockEntityData {
+        base,
+        kind: BlockEntityKind::Sign {
+            text1,
+            text2,
+            text3,
+            text4,
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let text = match data.kind {
+        BlockEntityKind::Sign {
+            text1,
+            text2,
+            text3,
+            text4,
+        } => SignText([text1, text2, text3, text4]),
+        _ => bail!("not a sign"),
+    };
+
+    Ok(create_with_text(pos, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::position;
+    use feather_server_types::WorldId;
+    use feather_test_framework::Test;
+
+    fn place_sign(test: &mut Test, player: Entity, pos: BlockPosition) -> Entity {
+        let sign = test.entity(create(pos));
+        test.game.worlds[WorldId::MAIN]
+            .block_entities
+            .insert(pos, sign);
+        test.handle(
+            BlockUpdateEvent {
+                world: WorldId::MAIN,
+                pos,
+                old: BlockId::air(),
+                new: BlockId::sign(),
+                cause: BlockUpdateCause::Entity(player),
+            },
+            on_sign_place_open_editor,
+        );
+        sign
+    }
+
+    fn edit(test: &mut Test, player: Entity, pos: BlockPosition, first_line: &str) {
+        let packet = UpdateSign {
+            location: pos,
+            line_1: first_line.to_owned(),
+            ..Default::default()
+        };
+        update_sign(&mut test.game, &mut test.world, player, packet);
+    }
+
+    #[test]
+    fn edit_placed_sign() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let other = test.player("", position!(2.0, 64.0, 0.0));
+        let pos = BlockPosition::new(1, 64, 0);
+
+        let sign = place_sign(&mut test, player, pos);
+        assert_eq!(test.sent::<OpenSignEditor>(player).unwrap().location, pos);
+        assert!(test.sent::<OpenSignEditor>(other).is_none());
+
+        // Other players may not edit the sign.
+        edit(&mut test, other, pos, "Nope");
+        assert_eq!(*test.world.get::<SignText>(sign), SignText::default());
+
+        edit(&mut test, player, pos, "Hello");
+        assert_eq!(
+            test.world.get::<SignText>(sign).0[0],
+            String::from(Text::of("Hello"))
+        );
+        let packet = test.sent::<UpdateBlockEntity>(other).unwrap();
+        assert_eq!(packet.location, pos);
+        assert_eq!(packet.action, SET_SIGN_TEXT);
+
+        // Signs may only be edited once.
+        edit(&mut test, player, pos, "Again");
+        assert_eq!(
+            test.world.get::<SignText>(sign).0[0],
+            String::from(Text::of("Hello"))
+        );
+    }
+}
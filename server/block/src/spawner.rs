@@ -0,0 +1,386 @@
+//! Mob spawners, which spawn mobs around them while a player
+//! is nearby. Their settings are kept so that spawners in
+//! loaded worlds are saved unchanged.
+//!
+//! See https://minecraft.gamepedia.com/Monster_Spawner.
+
+use crate::ShouldReplace;
+use anyhow::bail;
+use feather_core::anvil::block_entity::{
+    BlockEntityData, BlockEntityKind, BlockEntityVariant, SpawnData,
+};
+use feather_core::blocks::BlockKind;
+use feather_core::entitymeta::Metadata;
+use feather_core::network::{
+    packets::{BlockAction, Effect, UpdateBlockEntity},
+    Packet,
+};
+use feather_core::util::{vec3, BlockPosition, Gamemode, Position};
+use feather_server_entity::{is_valid_spawn, spawn_mob, MobKind};
+use feather_server_types::{
+    BlockEntityLoaderRegistration, BlockSerializer, Game, Player, SpawnPacketCreator, WorldId,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use nbt::{Blob, Value};
+use num_traits::ToPrimitive;
+use rand::Rng;
+use std::mem::discriminant;
+
+/// The Update Block Entity action which sets the data of a spawner.
+const SET_SPAWNER_DATA: u8 = 1;
+/// The Block Action which makes clients reset the delay of a spawner.
+const RESET_DELAY: u8 = 1;
+/// World event showing smoke and flames where a spawner spawned a mob.
+const SPAWN_EFFECT: i32 = 2004;
+/// Mob spawned by spawners which don't have an entity set.
+const DEFAULT_ENTITY: MobKind = MobKind::Pig;
+
+inventory::submit!(BlockEntityLoaderRegistration {
+    f: &load,
+    kind: BlockEntityVariant::MobSpawner,
+});
+
+/// The settings of a mob spawner. Delays are in ticks
+/// and ranges in blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spawner {
+    /// The namespaced ID of the entity to spawn next.
+    pub entity: Option<String>,
+    /// Ticks until the next spawn.
+    pub delay: i16,
+    pub min_spawn_delay: i16,
+    pub max_spawn_delay: i16,
+    /// Number of entities spawned at once.
+    pub spawn_count: i16,
+    /// No entities are spawned while this many are nearby.
+    pub max_nearby_entities: i16,
+    /// Entities are only spawned while a player is within this range.
+    pub required_player_range: i16,
+    pub spawn_range: i16,
+}
+
+impl Default for Spawner {
+    fn default() -> Self {
+        Self {
+            entity: None,
+            delay: 20,
+            min_spawn_delay: 200,
+            max_spawn_delay: 800,
+            spawn_count: 4,
+            max_nearby_entities: 6,
+            required_player_range: 16,
+            spawn_range: 4,
+        }
+    }
+}
+
+impl Spawner {
+    /// Returns the kind of mob this spawner spawns, or `None`
+    /// if it is set to an entity which isn't a mob.
+    pub fn mob(&self) -> Option<MobKind> {
+        match &self.entity {
+            Some(entity) => MobKind::from_identifier(entity),
+            None => Some(DEFAULT_ENTITY),
+        }
+    }
+
+    /// Picks the delay until the next spawn, between
+    /// the minimum and maximum delays.
+    fn reset_delay(&mut self, rng: &mut impl Rng) {
+        self.delay = if self.max_spawn_delay <= self.min_spawn_delay {
+            self.min_spawn_delay
+        } else {
+            rng.gen_range(self.min_spawn_delay, self.max_spawn_delay)
+        };
+    }
+}
+
+/// Creates a spawner with the default settings.
+pub fn create(pos: BlockPosition) -> EntityBuilder {
+    create_with(pos, Spawner::default())
+}
+
+/// Creates a spawner with the given settings.
+pub fn create_with(pos: BlockPosition, spawner: Spawner) -> EntityBuilder {
+    crate::base(pos)
+        .with(spawner)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(BlockSerializer(&serialize))
+        .with(ShouldReplace(crate::kind_changed))
+}
+
+/// Sends the entity to spawn, which the client shows inside the spawner.
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let pos = *accessor.get::<BlockPosition>();
+    let spawner = accessor.get::<Spawner>();
+
+    let mut data = Blob::new();
+    data.insert("x", pos.x).unwrap();
+    data.insert("y", pos.y).unwrap();
+    data.insert("z", pos.z).unwrap();
+    data.insert("Delay", spawner.delay).unwrap();
+    if let Some(entity) = &spawner.entity {
+        let spawn_data = std::iter::once((String::from("id"), Value::from(entity.as_str())));
+        data.insert("SpawnData", Value::Compound(spawn_data.collect()))
+            .unwrap();
+    }
+
+    Box::new(UpdateBlockEntity {
+        location: pos,
+        action: SET_SPAWNER_DATA,
+        data,
+    })
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> BlockEntityData {
+    let base = crate::serialize_base(accessor);
+    let spawner = accessor.get::<Spawner>().clone();
+
+    BlockEntityData {
+        base,
+        kind: BlockEntityKind::MobSpawner {
+            spawn_data: spawner.entity.map(|id| SpawnData { id }),
+            delay: spawner.delay,
+            min_spawn_delay: spawner.min_spawn_delay,
+            max_spawn_delay: spawner.max_spawn_delay,
+            spawn_count: spawner.spawn_count,
+            max_nearby_entities: spawner.max_nearby_entities,
+            required_player_range: spawner.required_player_range,
+            spawn_range: spawner.spawn_range,
+        },
+    }
+}
+
+fn load(data: BlockEntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = crate::load_base(&data.base);
+    let spawner = match data.kind {
+        BlockEntityKind::MobSpawner {
+            spawn_data,
+            delay,
+            min_spawn_delay,
+            max_spawn_delay,
+            spawn_count,
+            max_nearby_entities,
+            required_player_range,
+            spawn_range,
+        } => Spawner {
+            entity: spawn_data.map(|data| data.id),
+            delay,
+            min_spawn_delay,
+            max_spawn_delay,
+            spawn_count,
+            max_nearby_entities,
+            required_player_range,
+            spawn_range,
+        },
+        _ => bail!("not a spawner"),
+    };
+
+    Ok(create_with(pos, spawner))
+}
+
+/// System which counts down the delays of spawners
+/// with players in range, spawning mobs once they run out.
+#[fecs::system]
+pub fn tick_spawners(game: &mut Game, world: &mut World) {
+    let spawners: Vec<(Entity, BlockPosition)> = <(Read<Spawner>, Read<BlockPosition>)>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, (_, pos))| (entity, *pos))
+        .collect();
+
+    for (entity, pos) in spawners {
+        let world_id = game.world_of(world, entity);
+        let mut spawner = world.get::<Spawner>(entity).clone();
+        let kind = match spawner.mob() {
+            Some(kind) => kind,
+            None => continue,
+        };
+        if !is_player_in_range(world, world_id, pos, spawner.required_player_range) {
+            continue;
+        }
+
+        if spawner.delay > 0 {
+            spawner.delay -= 1;
+        } else if spawn(game, world, world_id, pos, &spawner, kind) {
+            spawner.reset_delay(&mut *game.rng());
+            let packet = BlockAction {
+                location: pos,
+                action_id: RESET_DELAY,
+                action_param: 0,
+                block_type: BlockKind::Spawner.to_i32().unwrap(),
+            };
+            game.broadcast_chunk_update(world, packet, world_id, pos.chunk(), None);
+        }
+        *world.get_mut::<Spawner>(entity) = spawner;
+    }
+}
+
+fn is_player_in_range(world: &World, world_id: WorldId, pos: BlockPosition, range: i16) -> bool {
+    let center = Position::from(pos) + vec3(0.5, 0.5, 0.5);
+    let range = f64::from(range);
+    <(Read<Player>, Read<Position>, Read<WorldId>, Read<Gamemode>)>::query()
+        .iter(world.inner())
+        .any(|(_, player, id, gamemode)| {
+            *id == world_id
+                && *gamemode != Gamemode::Spectator
+                && player.distance_squared_to(center) < range * range
+        })
+}
+
+/// Returns the number of mobs of a kind in the area a spawner spawns into.
+fn count_nearby(
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    spawner: &Spawner,
+    kind: MobKind,
+) -> usize {
+    let range = i32::from(spawner.spawn_range);
+    let (min, max) = (
+        Position::from(pos - BlockPosition::new(range, range, range)),
+        Position::from(pos + BlockPosition::new(range + 1, range + 1, range + 1)),
+    );
+    let metadata = discriminant(&kind.metadata());
+    <(Read<Metadata>, Read<Position>, Read<WorldId>)>::query()
+        .iter(world.inner())
+        .filter(|(meta, entity, id)| {
+            **id == world_id
+                && discriminant(&**meta) == metadata
+                && (min.x..max.x).contains(&entity.x)
+                && (min.y..max.y).contains(&entity.y)
+                && (min.z..max.z).contains(&entity.z)
+        })
+        .count()
+}
+
+/// Tries to spawn a spawner's mobs around it, returning
+/// whether any were spawned.
+fn spawn(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    spawner: &Spawner,
+    kind: MobKind,
+) -> bool {
+    let mut spawned = false;
+    let range = f64::from(spawner.spawn_range);
+    for _ in 0..spawner.spawn_count {
+        if count_nearby(world, world_id, pos, spawner, kind) >= spawner.max_nearby_entities as usize
+        {
+            // Too crowded: wait for the next delay.
+            return true;
+        }
+
+        let mob_pos = {
+            let mut rng = game.rng();
+            let offset = vec3(
+                (rng.gen::<f64>() - rng.gen::<f64>()) * range + 0.5,
+                f64::from(rng.gen_range(-1, 2)),
+                (rng.gen::<f64>() - rng.gen::<f64>()) * range + 0.5,
+            );
+            let mob_pos = Position::from(pos) + offset;
+            if !is_valid_spawn(
+                game,
+                &game.worlds[world_id],
+                kind,
+                mob_pos.block(),
+                &mut *rng,
+            ) {
+                continue;
+            }
+            Position {
+                yaw: rng.gen_range(0.0, 360.0),
+                ..mob_pos
+            }
+        };
+
+        spawn_mob(game, world, world_id, kind, mob_pos);
+        let effect = Effect {
+            effect_id: SPAWN_EFFECT,
+            location: mob_pos.block(),
+            data: 0,
+            disable_relative_volume: false,
+        };
+        game.broadcast_chunk_update(world, effect, world_id, mob_pos.chunk(), None);
+        spawned = true;
+    }
+    spawned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::position;
+    use feather_server_types::BlockUpdateCause;
+    use feather_test_framework::Test;
+
+    fn add_spawner(test: &mut Test, pos: BlockPosition, spawner: Spawner) -> Entity {
+        for x in -4..=4 {
+            for z in -4..=4 {
+                test.game.set_block_at(
+                    &mut test.world,
+                    WorldId::MAIN,
+                    pos + BlockPosition::new(x, -1, z),
+                    BlockId::stone(),
+                    BlockUpdateCause::Unknown,
+                );
+            }
+        }
+        test.entity(create_with(pos, spawner))
+    }
+
+    fn zombies(test: &Test) -> usize {
+        <Read<feather_server_entity::zombie::Zombie>>::query()
+            .iter(test.world.inner())
+            .count()
+    }
+
+    fn zombie_spawner() -> Spawner {
+        Spawner {
+            entity: Some(String::from("minecraft:zombie")),
+            delay: 0,
+            spawn_count: 100,
+            max_nearby_entities: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spawners_need_a_player_in_range() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        add_spawner(&mut test, pos, zombie_spawner());
+        test.player("player", position!(0.5, 64.0, 30.5));
+
+        test.run(tick_spawners);
+        assert_eq!(zombies(&test), 0);
+    }
+
+    #[test]
+    fn spawners_spawn_up_to_max_nearby_entities() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        let spawner = add_spawner(&mut test, pos, zombie_spawner());
+        test.player("player", position!(0.5, 64.0, 8.5));
+        // Midnight, so that zombies can spawn under the open sky.
+        test.game.worlds[WorldId::MAIN].time.day_time = 18_000;
+
+        test.run(tick_spawners);
+        assert_eq!(zombies(&test), 3);
+        let spawner = test.world.get::<Spawner>(spawner);
+        assert!(spawner.delay >= spawner.min_spawn_delay);
+        assert!(spawner.delay < spawner.max_spawn_delay);
+    }
+
+    #[test]
+    fn default_spawners_spawn_pigs() {
+        assert_eq!(Spawner::default().mob(), Some(MobKind::Pig));
+        let spawner = Spawner {
+            entity: Some(String::from("minecraft:item")),
+            ..Default::default()
+        };
+        assert_eq!(spawner.mob(), None);
+    }
+}
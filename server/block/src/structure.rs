@@ -0,0 +1,101 @@
+//! Placing structures in a world and copying regions of
+//! a world into structures.
+
+use feather_core::anvil::structure::{Structure, StructureError};
+use feather_core::blocks::BlockKind;
+use feather_core::util::BlockPosition;
+use feather_server_types::{BlockSerializer, BlockUpdateCause, EntitySpawnEvent, Game, WorldId};
+use feather_server_util::EntityLoader;
+use fecs::World;
+
+/// Places a structure with its origin at `origin`, replacing
+/// existing blocks and their block entities. Blocks in unloaded
+/// chunks are skipped.
+///
+/// To place a structure in another orientation, transform
+/// it first with `Structure::transformed`.
+///
+/// Returns the number of blocks placed.
+pub fn place_structure(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    structure: &Structure,
+    origin: BlockPosition,
+) -> usize {
+    let loader = EntityLoader::new();
+
+    let mut placed = 0;
+    for block in structure.blocks() {
+        let pos = origin + block.pos;
+        if !game.set_block_at(world, world_id, pos, block.block, BlockUpdateCause::Unknown) {
+            continue;
+        }
+        placed += 1;
+
+        let data = match block.block_entity(pos) {
+            Some(data) => data,
+            None => continue,
+        };
+        let builder = match loader.load_block(data) {
+            Some(Ok(builder)) => builder,
+            Some(Err(e)) => {
+                log::warn!(
+                    "Failed to load block entity of structure at {:?}: {}",
+                    pos,
+                    e
+                );
+                continue;
+            }
+            None => continue,
+        };
+
+        // Replace the empty block entity created for the new block.
+        if let Some(entity) = game.worlds[world_id].block_entities.remove(&pos) {
+            game.despawn(entity, world);
+        }
+        let entity = builder.with(world_id).build().spawn_in(world);
+        game.handle(world, EntitySpawnEvent { entity });
+    }
+
+    placed
+}
+
+/// Copies the blocks in the box between two corners, both
+/// inclusive, into a structure. Structure voids and blocks
+/// in unloaded chunks are left out.
+pub fn export_structure(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    a: BlockPosition,
+    b: BlockPosition,
+) -> Result<Structure, StructureError> {
+    let min = BlockPosition::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = BlockPosition::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+    let size = BlockPosition::new(max.x - min.x + 1, max.y - min.y + 1, max.z - min.z + 1);
+
+    let state = &game.worlds[world_id];
+    let mut structure = Structure::new(size);
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let pos = BlockPosition::new(x, y, z);
+                let block = match state.block_at(pos) {
+                    Some(block) if block.kind() != BlockKind::StructureVoid => block,
+                    _ => continue,
+                };
+
+                let block_entity = state.block_entities.get(&pos).and_then(|&entity| {
+                    let serializer = world.try_get::<BlockSerializer>(entity)?;
+                    let accessor = world.entity(entity).expect("entity does not exist");
+                    Some(serializer.serialize(game, &accessor))
+                });
+
+                structure.add_block(pos - min, block, block_entity.as_ref())?;
+            }
+        }
+    }
+
+    Ok(structure)
+}
@@ -0,0 +1,383 @@
+//! Scheduled and random block ticks, and the
+//! behavior of blocks when they are ticked.
+//!
+//! Each tick, `tick_blocks` triggers a `BlockTickEvent` for the
+//! due scheduled ticks in each block-ticking chunk. Then, for
+//! every section of those chunks, it picks `randomTickSpeed`
+//! random blocks and triggers a `BlockTickEvent` for those which
+//! are randomly ticked. Blocks implement their behavior with
+//! handlers for `BlockTickEvent`.
+
+use feather_core::blocks::{BlockId, BlockKind, SimplifiedBlockKind};
+use feather_core::util::{BlockPosition, ChunkPosition, Dimension};
+use feather_server_types::{
+    BlockTickEvent, BlockTickKind, BlockUpdateCause, BlockUpdateEvent, ChunkUnloadEvent, Game,
+    ScheduledTick, TickPriority, WorldId, WorldState,
+};
+use feather_server_util::adjacent_blocks;
+use fecs::World;
+use rand::Rng;
+
+/// The greatest distance of leaves from a log. Non-persistent
+/// leaves this far from any log decay.
+const MAX_LEAVES_DISTANCE: i32 = 7;
+
+/// Schedules a tick for the block at the given position,
+/// due after `delay` ticks. The tick is skipped if the
+/// block changes to another kind meanwhile.
+///
+/// Returns `false` if the block's chunk isn't loaded
+/// or a tick is already scheduled for the block.
+pub fn schedule_block_tick(
+    game: &mut Game,
+    world_id: WorldId,
+    pos: BlockPosition,
+    delay: u64,
+    priority: TickPriority,
+) -> bool {
+    let kind = match game.block_at(world_id, pos) {
+        Some(block) => block.kind(),
+        None => return false,
+    };
+
+    let time = game.tick_count + delay;
+    game.worlds[world_id].block_ticks.schedule(ScheduledTick {
+        pos,
+        kind,
+        time,
+        priority,
+    })
+}
+
+/// System which runs the due scheduled ticks
+/// and random ticks in every world.
+#[fecs::system]
+pub fn tick_blocks(game: &mut Game, world: &mut World) {
+    let now = game.tick_count;
+
+    let mut scheduled = Vec::new();
+    for state in game.worlds.iter_mut() {
+        let chunks: Vec<ChunkPosition> = state
+            .block_ticks
+            .chunks()
+            .filter(|&chunk| state.chunk_tickets.is_block_ticking(chunk))
+            .collect();
+        for chunk in chunks {
+            scheduled.extend(
+                state
+                    .block_ticks
+                    .take_due(chunk, now)
+                    .into_iter()
+                    .map(|tick| (state.id, tick)),
+            );
+        }
+    }
+
+    for (world_id, tick) in scheduled {
+        // An earlier tick may have changed the block.
+        let block = match game.block_at(world_id, tick.pos) {
+            Some(block) if block.kind() == tick.kind => block,
+            _ => continue,
+        };
+        game.handle(
+            world,
+            BlockTickEvent {
+                world: world_id,
+                pos: tick.pos,
+                block,
+                kind: BlockTickKind::Scheduled,
+            },
+        );
+    }
+
+    let random = random_ticks(game);
+    for (world_id, pos) in random {
+        let block = match game.block_at(world_id, pos) {
+            Some(block) if block.is_randomly_ticked() => block,
+            _ => continue,
+        };
+        game.handle(
+            world,
+            BlockTickEvent {
+                world: world_id,
+                pos,
+                block,
+                kind: BlockTickKind::Random,
+            },
+        );
+    }
+}
+
+/// Picks the blocks to randomly tick this tick.
+fn random_ticks(game: &Game) -> Vec<(WorldId, BlockPosition)> {
+    let speed = game.game_rules.random_tick_speed;
+    let mut rng = game.rng();
+
+    let mut ticks = Vec::new();
+    if speed == 0 {
+        return ticks;
+    }
+
+    for state in game.worlds.iter() {
+        for pos in state.chunk_tickets.loaded_chunks() {
+            if !state.chunk_tickets.is_block_ticking(pos) {
+                continue;
+            }
+            let chunk = match state.chunk_map.chunk_at(pos) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            for index in 0..chunk.num_sections() {
+                let section = match chunk.section(index) {
+                    Some(section) => section,
+                    None => continue,
+                };
+                // Skip sections without any randomly ticked blocks.
+                // Sections using the global palette are always checked.
+                if let Some(palette) = section.palette() {
+                    if !palette.iter().any(|block| block.is_randomly_ticked()) {
+                        continue;
+                    }
+                }
+
+                for _ in 0..speed {
+                    let (x, y, z) = (
+                        rng.gen_range(0, 16),
+                        rng.gen_range(0, 16),
+                        rng.gen_range(0, 16),
+                    );
+                    if section.block_at(x, y, z).is_randomly_ticked() {
+                        ticks.push((
+                            state.id,
+                            BlockPosition::new(
+                                pos.x * 16 + x as i32,
+                                chunk.min_y() + index as i32 * 16 + y as i32,
+                                pos.z * 16 + z as i32,
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    ticks
+}
+
+/// When a chunk is unloaded, discards its scheduled ticks.
+#[fecs::event_handler]
+pub fn on_chunk_unload_remove_block_ticks(event: &ChunkUnloadEvent, game: &mut Game) {
+    if let Some(state) = game.worlds.get_mut(event.world) {
+        state.block_ticks.remove_chunk(event.chunk);
+    }
+}
+
+/// Returns the light level at a block, the
+/// greater of its sky light and block light.
+pub(crate) fn light_at(state: &WorldState, pos: BlockPosition) -> Option<u8> {
+    let chunk = state.chunk_map.chunk_at(pos.chunk())?;
+    let (x, y, z) = chunk.local_pos(pos)?;
+    Some(
+        chunk
+            .sky_light_at(x, y, z)
+            .max(chunk.block_light_at(x, y, z)),
+    )
+}
+
+fn block_light_at(state: &WorldState, pos: BlockPosition) -> Option<u8> {
+    let chunk = state.chunk_map.chunk_at(pos.chunk())?;
+    let (x, y, z) = chunk.local_pos(pos)?;
+    Some(chunk.block_light_at(x, y, z))
+}
+
+/// Melts ice and snow layers near bright light sources.
+#[fecs::event_handler]
+pub fn on_block_tick_melt_ice(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.kind != BlockTickKind::Random {
+        return;
+    }
+
+    let state = &game.worlds[event.world];
+    let light = match block_light_at(state, event.pos) {
+        Some(light) => light,
+        None => return,
+    };
+
+    let melted = match event.block.kind() {
+        // Ice lets light through, so it melts in slightly dimmer light.
+        BlockKind::Ice if light > 10 => {
+            if state.dimension == Dimension::Nether {
+                BlockId::air()
+            } else {
+                BlockId::water()
+            }
+        }
+        BlockKind::Snow if light > 11 => BlockId::air(),
+        _ => return,
+    };
+    game.set_block_at(
+        world,
+        event.world,
+        event.pos,
+        melted,
+        BlockUpdateCause::Tick,
+    );
+}
+
+/// Returns the distance of leaves at the given
+/// position from the nearest log, judging by the
+/// distances of adjacent leaves.
+fn leaves_distance(state: &WorldState, pos: BlockPosition) -> i32 {
+    adjacent_blocks(pos)
+        .into_iter()
+        .filter_map(|pos| {
+            let block = state.block_at(pos)?;
+            match block.simplified_kind() {
+                SimplifiedBlockKind::Log => Some(1),
+                SimplifiedBlockKind::Leaves => block.distance().map(|distance| distance + 1),
+                _ => None,
+            }
+        })
+        .min()
+        .unwrap_or(MAX_LEAVES_DISTANCE)
+        .min(MAX_LEAVES_DISTANCE)
+}
+
+/// When a block changes, schedules a tick for adjacent
+/// leaves, which then update their distance from a log.
+#[fecs::event_handler]
+pub fn on_block_update_schedule_leaves(event: &BlockUpdateEvent, game: &mut Game) {
+    if event.old == event.new {
+        return;
+    }
+
+    for pos in adjacent_blocks(event.pos) {
+        let is_leaves = game.block_at(event.world, pos).map_or(false, |block| {
+            block.simplified_kind() == SimplifiedBlockKind::Leaves
+        });
+        if is_leaves {
+            schedule_block_tick(game, event.world, pos, 1, TickPriority::NORMAL);
+        }
+    }
+}
+
+/// Updates the distance of leaves from a log when they
+/// are ticked. Non-persistent leaves which are randomly
+/// ticked too far from any log decay.
+#[fecs::event_handler]
+pub fn on_block_tick_decay_leaves(event: &BlockTickEvent, game: &mut Game, world: &mut World) {
+    if event.block.simplified_kind() != SimplifiedBlockKind::Leaves {
+        return;
+    }
+
+    let distance = leaves_distance(&game.worlds[event.world], event.pos);
+    let block = if event.kind == BlockTickKind::Random
+        && distance == MAX_LEAVES_DISTANCE
+        && event.block.persistent() == Some(false)
+    {
+        BlockId::air()
+    } else if event.block.distance() != Some(distance) {
+        event.block.with_distance(distance)
+    } else {
+        return;
+    };
+    game.set_block_at(world, event.world, event.pos, block, BlockUpdateCause::Tick);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn scheduled_tick_skipped_after_change() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            BlockId::stone(),
+            BlockUpdateCause::Unknown,
+        );
+
+        assert!(schedule_block_tick(
+            &mut test.game,
+            WorldId::MAIN,
+            pos,
+            1,
+            TickPriority::NORMAL
+        ));
+        assert!(!schedule_block_tick(
+            &mut test.game,
+            WorldId::MAIN,
+            pos,
+            5,
+            TickPriority::NORMAL
+        ));
+        assert!(test.game.worlds[WorldId::MAIN]
+            .block_ticks
+            .is_scheduled(pos, BlockKind::Stone));
+    }
+
+    #[test]
+    fn leaves_distance_from_logs() {
+        let mut test = Test::new();
+        let log = BlockPosition::new(0, 64, 0);
+        let leaves = log + BlockPosition::new(1, 0, 0);
+        let state = &test.game.worlds[WorldId::MAIN];
+        assert_eq!(leaves_distance(state, leaves), MAX_LEAVES_DISTANCE);
+
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            log,
+            BlockId::oak_log(),
+            BlockUpdateCause::Unknown,
+        );
+        let state = &test.game.worlds[WorldId::MAIN];
+        assert_eq!(leaves_distance(state, leaves), 1);
+    }
+
+    #[test]
+    fn leaves_decay_without_log() {
+        let mut test = Test::new();
+        let decaying = BlockPosition::new(0, 64, 0);
+        let persistent = BlockPosition::new(5, 64, 5);
+        for &(pos, is_persistent) in &[(decaying, false), (persistent, true)] {
+            let block = BlockId::oak_leaves()
+                .with_distance(3)
+                .with_persistent(is_persistent);
+            test.game.set_block_at(
+                &mut test.world,
+                WorldId::MAIN,
+                pos,
+                block,
+                BlockUpdateCause::Unknown,
+            );
+            test.handle(
+                BlockTickEvent {
+                    world: WorldId::MAIN,
+                    pos,
+                    block,
+                    kind: BlockTickKind::Random,
+                },
+                on_block_tick_decay_leaves,
+            );
+        }
+
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, decaying),
+            Some(BlockId::air())
+        );
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, persistent),
+            Some(
+                BlockId::oak_leaves()
+                    .with_distance(MAX_LEAVES_DISTANCE)
+                    .with_persistent(true)
+            )
+        );
+    }
+}
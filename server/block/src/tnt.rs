@@ -0,0 +1,34 @@
+//! TNT, which is primed when lit with flint and steel, burnt
+//! by fire or powered by redstone. Primed TNT and the TNT
+//! caught in explosions are handled by the entity crate.
+//!
+//! See https://minecraft.gamepedia.com/TNT.
+
+use feather_core::blocks::{BlockId, BlockKind};
+use feather_core::util::BlockPosition;
+use feather_server_entity::tnt;
+use feather_server_types::{BlockUpdateCause, Game, WorldId};
+use fecs::World;
+
+/// Returns whether a block is TNT.
+pub fn is_tnt(block: BlockId) -> bool {
+    block.kind() == BlockKind::Tnt
+}
+
+/// Replaces the TNT at the given position with primed TNT. The
+/// entity responsible for `cause`, if any, is blamed for the damage
+/// of the explosion.
+pub fn ignite_tnt(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    cause: BlockUpdateCause,
+) {
+    let igniter = match cause {
+        BlockUpdateCause::Entity(entity) => Some(entity),
+        _ => None,
+    };
+    game.set_block_at(world, world_id, pos, BlockId::air(), cause);
+    tnt::prime(game, world, world_id, pos, tnt::FUSE, igniter);
+}
@@ -1,20 +1,26 @@
 //! Module for interacting with the chunk worker thread
 //! from the server threads.
 //!
-//! Also handles unloading chunks when unused.
+//! Also maintains the chunk tickets of each world, loading
+//! the chunks they cover and unloading chunks no ticket covers.
+//! Each player has a ticket covering its view.
 use crossbeam::channel::{Receiver, Sender};
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::chunk_worker;
+use crate::pregen::{self, Pregenerator};
+use crate::world_source::WorldStorage;
 use ahash::AHashSet;
 use chunk_worker::ChunkSave;
-use feather_core::anvil::{block_entity::BlockEntityData, entity::EntityData};
+use feather_core::anvil::{block_entity::BlockEntityData, entity::EntityData, poi::PoiChunk};
 use feather_core::chunk::Chunk;
-use feather_core::util::ChunkPosition;
+use feather_core::util::{ChunkPosition, Position};
 use feather_server_types::{
-    ChunkHolder, ChunkHolderReleaseEvent, ChunkLoadEvent, ChunkLoadFailEvent, ChunkUnloadEvent,
-    EntityDespawnEvent, EntitySpawnEvent, Game, HoldChunkRequest, LoadChunkRequest,
-    ReleaseChunkRequest, TPS,
+    AddTicketRequest, ChunkCrossEvent, ChunkHolder, ChunkHolderReleaseEvent, ChunkLoadEvent,
+    ChunkLoadFailEvent, ChunkUnloadEvent, EntityDespawnEvent, EntitySpawnEvent, Game,
+    HoldChunkRequest, LoadChunkRequest, Player, PlayerLeaveEvent, ReleaseChunkRequest,
+    RemoveTicketRequest, Ticket, TicketKind, View, ViewUpdateEvent, WorldChangeEvent,
+    WorldCreateEvent, WorldId, WorldState, TPS,
 };
 use feather_server_util::current_time_in_millis;
 use fecs::{Entity, World};
@@ -26,7 +32,7 @@ use std::sync::Arc;
 
 /// Set of chunks which are currently being loaded.
 #[derive(Debug, Clone, Default)]
-pub struct LoadingChunks(pub AHashSet<ChunkPosition>);
+pub struct LoadingChunks(pub AHashSet<(WorldId, ChunkPosition)>);
 
 /// A handle for interacting with the chunk
 /// worker thread.
@@ -43,34 +49,76 @@ pub fn handle_chunk_worker_replies(
     world: &mut World,
     chunk_worker_handle: &ChunkWorkerHandle,
     #[default] loading_chunks: &mut LoadingChunks,
+    #[default] chunk_unload_queue: &mut ChunkUnloadQueue,
+    #[default] pregenerator: &mut Pregenerator,
 ) {
     while let Ok(reply) = chunk_worker_handle.receiver.try_recv() {
         match reply {
-            chunk_worker::Reply::LoadedChunk(pos, result) => {
-                loading_chunks.0.remove(&pos);
+            chunk_worker::Reply::LoadedChunk(world_id, pos, result) => {
+                loading_chunks.0.remove(&(world_id, pos));
+                let state = match game.worlds.get_mut(world_id) {
+                    Some(state) => state,
+                    None => continue,
+                };
+
                 match result {
-                    Ok(loaded) => {
-                        game.chunk_map.insert(loaded.chunk);
+                    Ok(mut loaded) => {
+                        // Chunks span the vertical bounds of the world's dimension,
+                        // whatever their height when they were saved or generated.
+                        if let Some(ty) = game.dimension_types.get(state.dimension.type_id()) {
+                            loaded.chunk.resize(ty.min_y, ty.height as usize);
+                        }
+                        state.chunk_map.insert(loaded.chunk);
+                        state.poi.insert_chunk(pos, loaded.pois);
 
                         loaded.entities.into_iter().for_each(|builder| {
-                            let entity = builder.build().spawn_in(world);
+                            let entity = builder.with(world_id).build().spawn_in(world);
                             game.handle(world, EntitySpawnEvent { entity });
                         });
 
-                        game.handle(world, ChunkLoadEvent { chunk: pos });
+                        game.handle(
+                            world,
+                            ChunkLoadEvent {
+                                world: world_id,
+                                chunk: pos,
+                            },
+                        );
+
+                        // Tickets may have been removed while the chunk was loading.
+                        if !game.worlds[world_id].chunk_tickets.is_loaded(pos) {
+                            queue_unload(game, chunk_unload_queue, world_id, pos);
+                        }
 
-                        log::trace!("Loaded chunk at {:?}", pos);
+                        log::trace!("Loaded chunk at {:?} in {:?}", pos, world_id);
                     }
                     Err(error) => {
                         log::warn!("Failed to load chunk at {:?}: {}", pos, error);
-                        game.handle(world, ChunkLoadFailEvent { pos, error });
+                        game.handle(
+                            world,
+                            ChunkLoadFailEvent {
+                                world: world_id,
+                                pos,
+                                error,
+                            },
+                        );
                     }
                 }
             }
-            chunk_worker::Reply::SavedChunk(pos, result) => match result {
+            chunk_worker::Reply::SavedChunk(_, pos, result) => match result {
                 Ok(()) => log::trace!("Saved chunk at {:?}", pos),
                 Err(error) => log::warn!("Failed to save chunk at {:?}: {}", pos, error),
             },
+            chunk_worker::Reply::PregeneratedChunk(world_id, pos, result) => {
+                pregen::handle_pregenerated_chunk(
+                    game,
+                    pregenerator,
+                    loading_chunks,
+                    chunk_worker_handle,
+                    world_id,
+                    pos,
+                    result,
+                );
+            }
         }
     }
 }
@@ -78,10 +126,15 @@ pub fn handle_chunk_worker_replies(
 pub fn remove_chunk_holder(
     game: &mut Game,
     world: &mut World,
+    world_id: WorldId,
     chunk: ChunkPosition,
     holder: Entity,
 ) {
-    if let Some(vec) = game.chunk_holders.inner.get_mut(&chunk) {
+    let holders = match game.worlds.get_mut(world_id) {
+        Some(state) => &mut state.chunk_holders,
+        None => return,
+    };
+    if let Some(vec) = holders.inner.get_mut(&chunk) {
         let index = vec.iter().position(|e| *e == holder);
         if let Some(index) = index {
             vec.remove(index);
@@ -89,6 +142,7 @@ pub fn remove_chunk_holder(
             game.handle(
                 world,
                 ChunkHolderReleaseEvent {
+                    world: world_id,
                     chunk,
                     entity: holder,
                 },
@@ -108,6 +162,8 @@ pub struct ChunkUnloadQueue {
 /// A chunk to be unloaded.
 #[derive(Clone, Copy, Debug, Default)]
 struct ChunkUnload {
+    /// The world containing this chunk.
+    world: WorldId,
     /// The position of this chunk.
     chunk: ChunkPosition,
     /// The tick count at which to unload the chunk.
@@ -118,13 +174,13 @@ struct ChunkUnload {
 /// a chunk is queued for unloading and when it is unloaded.
 const CHUNK_UNLOAD_TIME: u64 = TPS * 5; // 5 seconds - TODO make this configurable
 
-/// System for unloading chunks when they have no holders.
+/// System for unloading chunks which no ticket keeps loaded.
 /// This system through chunks which are currently
 /// queued to be loaded and unloads them if the
 /// period of time has elapsed.
 ///
-/// Chunks are not unloaded immediately after having
-/// no holders because doing so could open up
+/// Chunks are not unloaded immediately after losing
+/// their last ticket because doing so could open up
 /// opportunities for exploits. For example, a player
 /// could quickly move between chunk boundaries, causing
 /// chunks at the edge of their view distance
@@ -144,24 +200,32 @@ pub fn chunk_unload(
     // to find which chunks to unload.
     while let Some(unload) = chunk_unload_queue.queue.front().copied() {
         if game.tick_count >= unload.time {
-            // Don't unload if new chunk holders have appeared.
-            if game.chunk_holders.chunk_has_holders(unload.chunk) {
-                chunk_unload_queue.queue.pop_front();
+            chunk_unload_queue.queue.pop_front();
+
+            let state = match game.worlds.get(unload.world) {
+                Some(state) => state,
+                None => continue,
+            };
+
+            // Don't unload if new tickets have appeared.
+            if state.chunk_tickets.is_loaded(unload.chunk) {
                 continue;
             }
 
-            // Unload chunk and pop from queue.
-            if game.chunk_map.chunk_at(unload.chunk).is_some() {
+            // Unload chunk.
+            if state.chunk_map.chunk_at(unload.chunk).is_some() {
                 game.handle(
                     world,
                     ChunkUnloadEvent {
+                        world: unload.world,
                         chunk: unload.chunk,
                     },
                 );
-                game.chunk_map.remove(unload.chunk);
-                log::trace!("Unloaded chunk at {}", unload.chunk);
+                let state = &mut game.worlds[unload.world];
+                state.chunk_map.remove(unload.chunk);
+                state.poi.remove_chunk(unload.chunk);
+                log::trace!("Unloaded chunk at {} in {:?}", unload.chunk, unload.world);
             }
-            chunk_unload_queue.queue.pop_front();
         } else {
             // We're done - all chunks farther up in
             // the queue were queued before this one,
@@ -171,25 +235,66 @@ pub fn chunk_unload(
     }
 }
 
-/// Event handler which handles holder release events. If
-/// a chunk has no more holders, then a chunk unload is queued.
-#[fecs::event_handler]
-pub fn on_chunk_holder_release_unload_chunk(
-    event: &ChunkHolderReleaseEvent,
-    game: &mut Game,
+/// Queues a chunk for unloading.
+fn queue_unload(
+    game: &Game,
     chunk_unload_queue: &mut ChunkUnloadQueue,
+    world: WorldId,
+    chunk: ChunkPosition,
 ) {
-    // Handle holder release events.
-    // If the chunk now has zero holders, queue it for unloading.
-    if !game.chunk_holders.chunk_has_holders(event.chunk) {
-        let unload = ChunkUnload {
-            chunk: event.chunk,
-            time: game.tick_count + CHUNK_UNLOAD_TIME,
-        };
-        chunk_unload_queue.queue.push_back(unload);
+    let unload = ChunkUnload {
+        world,
+        chunk,
+        time: game.tick_count + CHUNK_UNLOAD_TIME,
+    };
+    chunk_unload_queue.queue.push_back(unload);
+}
+
+/// Moves a player's ticket along with its view.
+#[fecs::event_handler]
+pub fn on_view_update_move_ticket(event: &ViewUpdateEvent, game: &mut Game, world: &mut World) {
+    let kind = TicketKind::Player(event.player);
+    let world_id = game.world_of(world, event.player);
+
+    // Add the new ticket first, so chunks in both views stay loaded.
+    game.handle(
+        world,
+        AddTicketRequest {
+            world: world_id,
+            chunk: event.new.center,
+            ticket: Ticket::with_radius(kind, event.new.distance),
+        },
+    );
+    if let Some(old) = event.old {
+        game.handle(
+            world,
+            RemoveTicketRequest {
+                world: world_id,
+                chunk: old.center,
+                kind,
+            },
+        );
     }
 }
 
+#[fecs::event_handler]
+pub fn on_player_leave_remove_ticket(event: &PlayerLeaveEvent, game: &mut Game, world: &mut World) {
+    let view = match world.try_get::<View>(event.player) {
+        Some(view) => *view,
+        None => return,
+    };
+
+    let world_id = game.world_of(world, event.player);
+    game.handle(
+        world,
+        RemoveTicketRequest {
+            world: world_id,
+            chunk: view.center,
+            kind: TicketKind::Player(event.player),
+        },
+    );
+}
+
 /// System for removing an entity's chunk holds
 /// once it is destroyed.
 #[fecs::event_handler]
@@ -206,8 +311,9 @@ pub fn on_entity_despawn_remove_chunk_holder(
         Vec::new()
     };
 
+    let world_id = game.world_of(world, event.entity);
     for hold in holds {
-        remove_chunk_holder(game, world, hold, event.entity);
+        remove_chunk_holder(game, world, world_id, hold, event.entity);
     }
 }
 
@@ -235,9 +341,11 @@ pub fn chunk_optimize(game: &mut Game) {
     let start_time = current_time_in_millis();
     let count = AtomicU32::new(0);
 
-    game.chunk_map.0.par_values().for_each(|chunk| {
-        count.fetch_add(chunk.write().optimize(), Ordering::Relaxed);
-    });
+    for state in game.worlds.iter() {
+        state.chunk_map.0.par_values().for_each(|chunk| {
+            count.fetch_add(chunk.write().optimize(), Ordering::Relaxed);
+        });
+    }
 
     let end_time = current_time_in_millis();
     let elapsed = end_time - start_time;
@@ -255,22 +363,32 @@ pub fn chunk_optimize(game: &mut Game) {
     );
 }
 
-/// Adds a hold for a chunk for the given entity.
-pub fn hold_chunk(game: &mut Game, holder: &mut ChunkHolder, chunk: ChunkPosition, entity: Entity) {
+/// Adds a hold for a chunk in the given world for the given entity.
+pub fn hold_chunk(
+    game: &mut Game,
+    holder: &mut ChunkHolder,
+    world_id: WorldId,
+    chunk: ChunkPosition,
+    entity: Entity,
+) {
     holder.holds.insert(chunk);
-    game.chunk_holders
-        .inner
-        .entry(chunk)
-        .or_default()
-        .push(entity);
+    game.worlds[world_id]
+        .chunk_holders
+        .insert_holder(chunk, entity);
     log::trace!("Obtained chunk hold on {} for player {:?}", chunk, entity);
 }
 
-/// Releases a hold for a chunk for the given entity.
-pub fn release_chunk(game: &mut Game, world: &mut World, chunk: ChunkPosition, entity: Entity) {
+/// Releases a hold for a chunk in the given world for the given entity.
+pub fn release_chunk(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    chunk: ChunkPosition,
+    entity: Entity,
+) {
     let mut holder = world.get_mut::<ChunkHolder>(entity);
     holder.holds.remove(&chunk);
-    if let Some(vec) = game.chunk_holders.inner.get_mut(&chunk) {
+    if let Some(vec) = game.worlds[world_id].chunk_holders.inner.get_mut(&chunk) {
         let mut index = None;
         for (i, e) in vec.iter().enumerate() {
             if *e == entity {
@@ -284,44 +402,134 @@ pub fn release_chunk(game: &mut Game, world: &mut World, chunk: ChunkPosition, e
     }
     log::trace!("Released chunk hold on {} for player {:?}", chunk, entity);
     drop(holder);
-    game.handle(world, ChunkHolderReleaseEvent { chunk, entity });
+    game.handle(
+        world,
+        ChunkHolderReleaseEvent {
+            world: world_id,
+            chunk,
+            entity,
+        },
+    );
 }
 
 /// Asynchronously loads the chunk at the given position.
 /// At some point in time after this function is called,
-/// the chunk will appear in the chunk map.
+/// the chunk will appear in the world's chunk map.
 ///
 /// In the event that the requested chunk does not exist
 /// in the world save, it will be generated asynchronously.
-pub fn load_chunk(handle: &ChunkWorkerHandle, pos: ChunkPosition) {
+pub fn load_chunk(handle: &ChunkWorkerHandle, world: WorldId, pos: ChunkPosition) {
     // Send request to chunk worker thread
     handle
         .sender
-        .send(chunk_worker::Request::LoadChunk(pos))
+        .send(chunk_worker::Request::LoadChunk(world, pos))
+        .unwrap();
+}
+
+/// Registers a world with the chunk worker, which loads
+/// its chunks from and saves them to the given storage.
+pub fn add_world(handle: &ChunkWorkerHandle, state: &WorldState, storage: WorldStorage) {
+    handle
+        .sender
+        .send(chunk_worker::Request::AddWorld(
+            state.id,
+            storage.source(&state.dir),
+            state.generator.clone(),
+        ))
+        .unwrap();
+}
+
+#[fecs::event_handler]
+pub fn on_world_create_add_to_chunk_worker(
+    event: &WorldCreateEvent,
+    game: &mut Game,
+    handle: &ChunkWorkerHandle,
+) {
+    let storage =
+        WorldStorage::from_config(&game.config.world).expect("config is validated on startup");
+    add_world(handle, &game.worlds[event.world], storage);
+}
+
+/// Prioritizes generation of chunks near the chunk
+/// a player has moved into.
+#[fecs::event_handler]
+pub fn on_chunk_cross_update_generation_focus(
+    event: &ChunkCrossEvent,
+    game: &mut Game,
+    handle: &ChunkWorkerHandle,
+    world: &mut World,
+) {
+    if world.try_get::<Player>(event.entity).is_none() {
+        return;
+    }
+
+    let world_id = game.world_of(world, event.entity);
+    handle
+        .sender
+        .send(chunk_worker::Request::SetFocus(
+            event.entity,
+            Some((world_id, event.new)),
+        ))
+        .unwrap();
+}
+
+/// Prioritizes generation of chunks near a player
+/// which has moved to another world.
+#[fecs::event_handler]
+pub fn on_world_change_update_generation_focus(
+    event: &WorldChangeEvent,
+    handle: &ChunkWorkerHandle,
+    world: &mut World,
+) {
+    if world.try_get::<Player>(event.entity).is_none() {
+        return;
+    }
+
+    let chunk = world.get::<Position>(event.entity).chunk();
+    handle
+        .sender
+        .send(chunk_worker::Request::SetFocus(
+            event.entity,
+            Some((event.new, chunk)),
+        ))
+        .unwrap();
+}
+
+#[fecs::event_handler]
+pub fn on_player_leave_remove_generation_focus(
+    event: &PlayerLeaveEvent,
+    handle: &ChunkWorkerHandle,
+) {
+    handle
+        .sender
+        .send(chunk_worker::Request::SetFocus(event.player, None))
         .unwrap();
 }
 
 /// Asynchronously saves the chunk at the given position.
 pub fn save_chunk(
     handle: &ChunkWorkerHandle,
+    world: WorldId,
     chunk: Arc<RwLock<Chunk>>,
     entities: SmallVec<[EntityData; 4]>,
     block_entities: SmallVec<[BlockEntityData; 4]>,
+    poi: PoiChunk,
 ) {
     let save = ChunkSave {
         chunk,
         entities,
         block_entities,
+        poi,
     };
     handle
         .sender
-        .send(chunk_worker::Request::SaveChunk(save))
+        .send(chunk_worker::Request::SaveChunk(world, save))
         .unwrap();
 }
 
 #[fecs::event_handler]
 pub fn release_chunk_request(event: &ReleaseChunkRequest, game: &mut Game, world: &mut World) {
-    release_chunk(game, world, event.chunk, event.player);
+    release_chunk(game, world, event.world, event.chunk, event.player);
 }
 
 #[fecs::event_handler]
@@ -329,11 +537,44 @@ pub fn hold_chunk_request(event: &HoldChunkRequest, game: &mut Game, world: &mut
     hold_chunk(
         game,
         &mut *world.get_mut::<ChunkHolder>(event.player),
+        event.world,
         event.chunk,
         event.player,
     );
 }
 
+#[fecs::event_handler]
+pub fn add_ticket_request(event: &AddTicketRequest, game: &mut Game, world: &mut World) {
+    let state = match game.worlds.get_mut(event.world) {
+        Some(state) => state,
+        None => return,
+    };
+    for chunk in state.chunk_tickets.add(event.chunk, event.ticket) {
+        game.handle(
+            world,
+            LoadChunkRequest {
+                world: event.world,
+                chunk,
+            },
+        );
+    }
+}
+
+#[fecs::event_handler]
+pub fn remove_ticket_request(
+    event: &RemoveTicketRequest,
+    game: &mut Game,
+    chunk_unload_queue: &mut ChunkUnloadQueue,
+) {
+    let state = match game.worlds.get_mut(event.world) {
+        Some(state) => state,
+        None => return,
+    };
+    for chunk in state.chunk_tickets.remove(event.chunk, event.kind) {
+        queue_unload(game, chunk_unload_queue, event.world, chunk);
+    }
+}
+
 #[fecs::event_handler]
 pub fn load_chunk_request(
     event: &LoadChunkRequest,
@@ -341,10 +582,15 @@ pub fn load_chunk_request(
     loading_chunks: &mut LoadingChunks,
     game: &mut Game,
 ) {
+    let loaded = match game.worlds.get(event.world) {
+        Some(state) => state.chunk_map.0.contains_key(&event.chunk),
+        None => return,
+    };
+
     // Don't load chunk if it's already loading or already loaded.
-    if !loading_chunks.0.insert(event.chunk) || game.chunk_map.0.contains_key(&event.chunk) {
+    if loaded || !loading_chunks.0.insert((event.world, event.chunk)) {
         return;
     }
 
-    load_chunk(handle, event.chunk);
+    load_chunk(handle, event.world, event.chunk);
 }
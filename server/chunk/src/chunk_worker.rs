@@ -2,26 +2,36 @@
 //! of chunks. It receives load and save requests from the server
 //! (over a channel) and executes them.
 //!
-//! If a chunk cannot be loaded, it is generated on the Rayon thread pool
-//! instead.
+//! Each world's chunks are loaded from and saved to its own
+//! `WorldSource`, on the threads of an `IoPool`, and the results
+//! are sent back to the server, which handles them between ticks.
+//! If a chunk doesn't exist in the source, it is queued for
+//! generation on the `GenerationPool` instead.
+//!
+//! Chunks can also be pregenerated: they are generated if they
+//! don't exist, then sent back to the server to be saved, rather
+//! than loaded into the world.
+use crate::generation::{GenerationPool, Purpose};
+use crate::io::{Completion, IoJob, IoPool};
+use crate::world_source::{StoredChunk, WorldSource};
 use ahash::AHashMap;
 use crossbeam::channel::{Receiver, Sender};
+use feather_core::anvil::block_entity::BlockEntityData;
 use feather_core::anvil::entity::EntityData;
-use feather_core::anvil::region;
-use feather_core::anvil::{
-    block_entity::BlockEntityData,
-    region::{RegionHandle, RegionPosition},
-};
+use feather_core::anvil::poi::PoiChunk;
 use feather_core::chunk::Chunk;
 use feather_core::util::ChunkPosition;
+use feather_server_types::{load_chunk_pois, Poi, WorldId};
 use feather_server_util::EntityLoader;
 use feather_server_worldgen::WorldGenerator;
-use fecs::EntityBuilder;
+use fecs::{Entity, EntityBuilder};
 use parking_lot::RwLock;
 use smallvec::SmallVec;
-use std::path::{Path, PathBuf};
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of threads reading and writing chunks.
+const IO_THREADS: usize = 4;
 
 /// Stores a chunk and associated data (entities, block entities, tile ticks, ...)
 /// for saving to the world save.
@@ -32,6 +42,8 @@ pub struct ChunkSave {
     pub entities: SmallVec<[EntityData; 4]>,
     /// Block entities within this chunk.
     pub block_entities: SmallVec<[BlockEntityData; 4]>,
+    /// Points of interest within this chunk.
+    pub poi: PoiChunk,
 }
 
 /// Stores a chunk and associated data loaded
@@ -41,40 +53,42 @@ pub struct ChunkLoad {
     /// Entities within this chunk, pre-built with their components.
     /// Includes block entities as well.
     pub entities: SmallVec<[EntityBuilder; 4]>,
+    /// Points of interest within this chunk.
+    pub pois: Vec<Poi>,
 }
 
 #[allow(clippy::large_enum_variant)]
 pub enum Reply {
-    LoadedChunk(ChunkPosition, anyhow::Result<ChunkLoad>),
-    SavedChunk(ChunkPosition, anyhow::Result<()>),
+    LoadedChunk(WorldId, ChunkPosition, anyhow::Result<ChunkLoad>),
+    SavedChunk(WorldId, ChunkPosition, anyhow::Result<()>),
+    /// The result of `Request::Pregenerate`: the generated
+    /// chunk, or `None` if it already existed.
+    PregeneratedChunk(WorldId, ChunkPosition, anyhow::Result<Option<ChunkLoad>>),
 }
 
-#[derive(Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum Request {
-    LoadChunk(ChunkPosition),
-    SaveChunk(ChunkSave),
+    /// Adds a world, whose chunks are stored in the given
+    /// source and generated by the given generator.
+    AddWorld(WorldId, Box<dyn WorldSource>, Arc<dyn WorldGenerator>),
+    LoadChunk(WorldId, ChunkPosition),
+    SaveChunk(WorldId, ChunkSave),
+    /// Generates a chunk if it doesn't exist in the world's source.
+    /// Chunks being loaded are generated first.
+    Pregenerate(WorldId, ChunkPosition),
+    /// Sets the world and chunk a player is in, prioritizing
+    /// generation of chunks near it. `None` removes the player.
+    SetFocus(Entity, Option<(WorldId, ChunkPosition)>),
+    /// Flushes saved chunks to disk.
+    Flush,
+    /// Flushes saved chunks to disk and stops the worker.
     ShutDown,
 }
 
-/// An open region file
-struct RegionFile {
-    /// The handle for the file
-    handle: RegionHandle,
-    /// The timestamp of the last time
-    /// this region file was used. This
-    /// value is used to close
-    /// the file after it isn't used for
-    /// some period of time.
-    ///
-    /// TODO actually implement this
-    _last_used: u64,
-}
-
 struct ChunkWorker {
-    /// The directory in which the world
-    /// resides
-    dir: PathBuf,
+    /// The storage of each world, from which chunks
+    /// are loaded and to which they are saved
+    sources: AHashMap<WorldId, Arc<dyn WorldSource>>,
 
     /// Channel used to send chunks and errors
     /// back to the server thread
@@ -83,11 +97,18 @@ struct ChunkWorker {
     /// from the server thread
     receiver: Receiver<Request>,
 
-    /// A map of currently open region files
-    open_regions: AHashMap<RegionPosition, RegionFile>,
+    /// Thread pool loading and saving chunks.
+    io: IoPool,
+    /// Channel used to receive the results of I/O jobs.
+    completions: Receiver<Completion>,
+    /// Chunks with a running I/O job, mapped to the jobs
+    /// waiting for it to complete. Jobs for the same chunk
+    /// run one at a time, so a chunk which is loaded right
+    /// after being saved is read with its saved state.
+    running: AHashMap<(WorldId, ChunkPosition), VecDeque<IoJob>>,
 
-    /// World generator for new chunks.
-    world_generator: Arc<dyn WorldGenerator>,
+    /// Thread pool generating new chunks.
+    generation: GenerationPool,
 
     /// State for loading entities.
     entity_loader: EntityLoader,
@@ -96,19 +117,21 @@ struct ChunkWorker {
 /// Starts a chunk worker on a new thread.
 /// The returned channels can be used
 /// to communicate with the worker.
-pub fn start(
-    world_dir: &Path,
-    world_gen: Arc<dyn WorldGenerator>,
-) -> (Sender<Request>, Receiver<Reply>) {
+///
+/// Worlds are added with `Request::AddWorld`.
+pub fn start() -> (Sender<Request>, Receiver<Reply>) {
     let (request_tx, request_rx) = crossbeam::channel::unbounded();
     let (reply_tx, reply_rx) = crossbeam::channel::unbounded();
+    let (completion_tx, completion_rx) = crossbeam::channel::unbounded();
 
     let worker = ChunkWorker {
-        dir: world_dir.to_path_buf(),
-        sender: reply_tx,
+        sources: AHashMap::new(),
+        sender: reply_tx.clone(),
         receiver: request_rx,
-        open_regions: AHashMap::new(),
-        world_generator: world_gen,
+        io: IoPool::start(completion_tx, IO_THREADS),
+        completions: completion_rx,
+        running: AHashMap::new(),
+        generation: GenerationPool::start(reply_tx, rayon::current_num_threads()),
         entity_loader: EntityLoader::new(),
     };
 
@@ -129,49 +152,164 @@ pub fn start(
 /// Runs the chunk worker on the current thread,
 /// blocking indefinitely.
 fn run(mut worker: ChunkWorker) {
-    while let Ok(request) = worker.receiver.recv() {
-        match request {
-            Request::ShutDown => break,
-            Request::SaveChunk(save) => {
-                save_chunk(&mut worker, save);
+    loop {
+        crossbeam::select! {
+            recv(worker.receiver) -> request => match request {
+                Ok(Request::ShutDown) | Err(_) => break,
+                Ok(request) => handle_request(&mut worker, request),
+            },
+            recv(worker.completions) -> completion => {
+                let completion = completion.expect("the I/O pool stopped before the worker");
+                handle_completion(&mut worker, completion);
             }
-            Request::LoadChunk(pos) => {
-                if let Some(reply) = load_chunk(&mut worker, pos) {
-                    worker.sender.send(reply).unwrap();
+        }
+    }
+
+    // Finish pending saves before the final flush.
+    while !worker.running.is_empty() {
+        match worker.completions.recv() {
+            Ok(completion) => handle_completion(&mut worker, completion),
+            Err(_) => break,
+        }
+    }
+    flush(&worker);
+
+    worker.io.shut_down();
+    worker.generation.shut_down();
+    log::info!("Chunk worker terminating");
+}
+
+fn handle_request(worker: &mut ChunkWorker, request: Request) {
+    match request {
+        Request::ShutDown => unreachable!("handled by the worker loop"),
+        Request::Flush => flush(worker),
+        Request::AddWorld(world, source, generator) => {
+            worker.sources.insert(world, Arc::from(source));
+            worker.generation.add_world(world, generator);
+        }
+        Request::SetFocus(player, chunk) => worker.generation.set_focus(player, chunk),
+        Request::SaveChunk(world, save) => {
+            let pos = save.chunk.read().position();
+            match worker.sources.get(&world) {
+                Some(source) => {
+                    let job = IoJob::Save(world, pos, Arc::clone(source), save);
+                    submit(worker, world, pos, job);
                 }
+                None => worker
+                    .sender
+                    .send(Reply::SavedChunk(world, pos, Err(missing_world(world))))
+                    .unwrap(),
             }
         }
+        Request::LoadChunk(world, pos) => match worker.sources.get(&world) {
+            Some(source) => {
+                let job = IoJob::Load(world, pos, Arc::clone(source));
+                submit(worker, world, pos, job);
+            }
+            None => worker
+                .sender
+                .send(Reply::LoadedChunk(world, pos, Err(missing_world(world))))
+                .unwrap(),
+        },
+        Request::Pregenerate(world, pos) => match worker.sources.get(&world) {
+            Some(source) => {
+                let job = IoJob::Check(world, pos, Arc::clone(source));
+                submit(worker, world, pos, job);
+            }
+            None => worker
+                .sender
+                .send(Reply::PregeneratedChunk(
+                    world,
+                    pos,
+                    Err(missing_world(world)),
+                ))
+                .unwrap(),
+        },
+    }
+}
+
+/// Flushes the source of every world. Chunks
+/// still being saved may not be flushed.
+fn flush(worker: &ChunkWorker) {
+    for (&world, source) in &worker.sources {
+        worker.io.submit(IoJob::Flush(world, Arc::clone(source)));
     }
+}
 
-    log::info!("Chunk worker terminating");
+fn missing_world(world: WorldId) -> anyhow::Error {
+    anyhow::anyhow!("world {:?} was not added to the chunk worker", world)
 }
 
-/// Attempts to load the chunk at the specified position.
-fn load_chunk(worker: &mut ChunkWorker, pos: ChunkPosition) -> Option<Reply> {
-    let rpos = RegionPosition::from_chunk(pos);
-
-    let file = worker_region(&mut worker.open_regions, &worker.dir, rpos);
-    // Load from region file
-    load_chunk_from_handle(
-        pos,
-        &mut file.handle,
-        &Arc::from(worker.sender.clone()),
-        &worker.world_generator,
-        &worker.entity_loader,
-    )
+/// Submits an I/O job for a chunk to the pool, or queues it
+/// if another job for the same chunk is still running.
+fn submit(worker: &mut ChunkWorker, world: WorldId, pos: ChunkPosition, job: IoJob) {
+    match worker.running.get_mut(&(world, pos)) {
+        Some(waiting) => waiting.push_back(job),
+        None => {
+            worker.running.insert((world, pos), VecDeque::new());
+            worker.io.submit(job);
+        }
+    }
+}
+
+fn handle_completion(worker: &mut ChunkWorker, completion: Completion) {
+    let (world, pos) = match completion {
+        Completion::Loaded(world, pos, result) => {
+            if let Some(reply) = loaded_chunk(worker, world, pos, result) {
+                worker.sender.send(reply).unwrap();
+            }
+            (world, pos)
+        }
+        Completion::Saved(world, pos, result) => {
+            worker
+                .sender
+                .send(Reply::SavedChunk(world, pos, result))
+                .unwrap();
+            (world, pos)
+        }
+        Completion::Checked(world, pos, result) => {
+            match result {
+                Ok(false) => worker.generation.submit(world, pos, Purpose::Pregenerate),
+                result => worker
+                    .sender
+                    .send(Reply::PregeneratedChunk(world, pos, result.map(|_| None)))
+                    .unwrap(),
+            }
+            (world, pos)
+        }
+    };
+
+    // Start the next job for the chunk, if any.
+    let next = worker
+        .running
+        .get_mut(&(world, pos))
+        .and_then(VecDeque::pop_front);
+    match next {
+        Some(job) => worker.io.submit(job),
+        None => {
+            worker.running.remove(&(world, pos));
+        }
+    }
 }
 
-fn load_chunk_from_handle(
+/// Builds the reply for a chunk loaded from a world's source,
+/// or queues the chunk for generation if it doesn't exist.
+fn loaded_chunk(
+    worker: &ChunkWorker,
+    world: WorldId,
     pos: ChunkPosition,
-    handle: &mut RegionHandle,
-    sender: &Arc<Sender<Reply>>,
-    generator: &Arc<dyn WorldGenerator>,
-    entity_loader: &EntityLoader,
+    result: anyhow::Result<Option<StoredChunk>>,
 ) -> Option<Reply> {
-    let result = handle.load_chunk(pos);
-
     match result {
-        Ok((chunk, entities, block_entities)) => {
+        Ok(Some(StoredChunk {
+            chunk,
+            entities,
+            block_entities,
+            poi,
+        })) => {
+            let entity_loader = &worker.entity_loader;
+            // An entity which fails to load is dropped
+            // rather than failing the whole chunk.
             let entities = entities
                 .into_iter()
                 .filter_map(|entity| entity_loader.load(entity))
@@ -180,107 +318,144 @@ fn load_chunk_from_handle(
                         .into_iter()
                         .filter_map(|block_entity| entity_loader.load_block(block_entity)),
                 )
-                .collect::<Result<SmallVec<_>, anyhow::Error>>();
+                .filter_map(|result| match result {
+                    Ok(builder) => Some(builder),
+                    Err(e) => {
+                        log::warn!("Failed to load entity in chunk at {}: {}", pos, e);
+                        None
+                    }
+                })
+                .collect::<SmallVec<_>>();
+            let pois = load_chunk_pois(&chunk, poi.as_ref());
 
             Some(Reply::LoadedChunk(
+                world,
                 pos,
-                match entities {
-                    Ok(entities) => Ok(ChunkLoad { chunk, entities }),
-                    Err(e) => Err(e),
-                },
+                Ok(ChunkLoad {
+                    chunk,
+                    entities,
+                    pois,
+                }),
             ))
         }
-        Err(e) => match e {
-            region::Error::ChunkNotExist => {
-                schedule_generate_new_chunk(sender, pos, generator);
-                None
-            }
-            err => Some(Reply::LoadedChunk(pos, Err(err.into()))),
-        },
+        Ok(None) => {
+            worker.generation.submit(world, pos, Purpose::Load);
+            None
+        }
+        Err(e) => Some(Reply::LoadedChunk(world, pos, Err(e))),
     }
 }
 
-/// Generates a new chunk asynchronously,
-/// sending the result to the provided Sender.
-fn schedule_generate_new_chunk(
-    sender: &Arc<Sender<Reply>>,
-    pos: ChunkPosition,
-    generator: &Arc<dyn WorldGenerator>,
-) {
-    let sender = sender.clone();
-    let generator = Arc::clone(generator);
-    rayon::spawn(move || {
-        sender.send(generate_new_chunk(pos, &generator)).unwrap();
-    });
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnvilWorldSource, MemoryWorldSource};
+    use feather_core::blocks::BlockId;
+    use feather_server_worldgen::EmptyWorldGenerator;
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn load_after_save() {
+        let dir = env::temp_dir().join(format!("feather-chunk-worker-{}", std::process::id()));
+        let (sender, receiver) = start();
+        sender
+            .send(Request::AddWorld(
+                WorldId::MAIN,
+                Box::new(AnvilWorldSource::new(&dir)),
+                Arc::new(EmptyWorldGenerator {}),
+            ))
+            .unwrap();
 
-/// Generates a new chunk synchronously,
-/// returning a Reply to send to a Sender.
-fn generate_new_chunk(pos: ChunkPosition, generator: &Arc<dyn WorldGenerator>) -> Reply {
-    Reply::LoadedChunk(
-        pos,
-        Ok(ChunkLoad {
-            chunk: generator.generate_chunk(pos),
+        let pos = ChunkPosition::new(3, -7);
+        let mut chunk = Chunk::new(pos);
+        chunk.set_block_at(4, 20, 5, BlockId::stone());
+        let save = ChunkSave {
+            chunk: Arc::new(RwLock::new(chunk)),
             entities: SmallVec::new(),
-        }),
-    )
-}
-
-/// Saves the chunk at the specified position.
-fn save_chunk(worker: &mut ChunkWorker, save: ChunkSave) {
-    let chunk = save.chunk.read();
-    let rpos = RegionPosition::from_chunk(chunk.position());
-
-    let file = worker_region(&mut worker.open_regions, &worker.dir, rpos);
-
-    let result = file
-        .handle
-        .save_chunk(&*chunk, &save.entities, &save.block_entities);
-
-    worker
-        .sender
-        .send(Reply::SavedChunk(
-            chunk.position(),
-            result.map_err(|err| err.into()),
-        ))
-        .unwrap();
-}
-
-/// Returns whether the given chunk's region
-/// is already loaded.
-fn is_region_loaded(
-    open_regions: &AHashMap<RegionPosition, RegionFile>,
-    rpos: RegionPosition,
-) -> bool {
-    open_regions.contains_key(&rpos)
-}
+            block_entities: SmallVec::new(),
+            poi: PoiChunk::new(),
+        };
 
-fn worker_region<'a>(
-    open_regions: &'a mut AHashMap<RegionPosition, RegionFile>,
-    dir: &PathBuf,
-    rpos: RegionPosition,
-) -> &'a mut RegionFile {
-    if !is_region_loaded(open_regions, rpos) {
-        // Need to load region into memory
-        let mut handle = region::load_region(&dir, rpos);
-        if handle.is_err() {
-            // Create a new region file
-            handle = region::create_region(&dir, rpos);
+        // The load waits for the save, so it doesn't
+        // generate the chunk instead.
+        sender
+            .send(Request::SaveChunk(WorldId::MAIN, save))
+            .unwrap();
+        sender.send(Request::LoadChunk(WorldId::MAIN, pos)).unwrap();
+
+        match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Reply::SavedChunk(_, saved, result) => {
+                assert_eq!(saved, pos);
+                result.unwrap();
+            }
+            _ => panic!("expected a saved chunk"),
+        }
+        match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Reply::LoadedChunk(_, loaded, result) => {
+                assert_eq!(loaded, pos);
+                assert_eq!(result.unwrap().chunk.block_at(4, 20, 5), BlockId::stone());
+            }
+            _ => panic!("expected a loaded chunk"),
         }
 
-        let handle = handle.unwrap();
+        sender.send(Request::ShutDown).unwrap();
+        while receiver.recv().is_ok() {}
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-        let last_used = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    #[test]
+    fn pregenerate() {
+        let (sender, receiver) = start();
+        sender
+            .send(Request::AddWorld(
+                WorldId::MAIN,
+                Box::new(MemoryWorldSource::new()),
+                Arc::new(EmptyWorldGenerator {}),
+            ))
+            .unwrap();
 
-        let file = RegionFile {
-            handle,
-            _last_used: last_used,
+        let saved = ChunkPosition::new(0, 0);
+        let save = ChunkSave {
+            chunk: Arc::new(RwLock::new(Chunk::new(saved))),
+            entities: SmallVec::new(),
+            block_entities: SmallVec::new(),
+            poi: PoiChunk::new(),
         };
+        sender
+            .send(Request::SaveChunk(WorldId::MAIN, save))
+            .unwrap();
+        assert!(matches!(
+            receiver.recv_timeout(Duration::from_secs(10)).unwrap(),
+            Reply::SavedChunk(_, _, Ok(()))
+        ));
+
+        // Existing chunks aren't generated again.
+        sender
+            .send(Request::Pregenerate(WorldId::MAIN, saved))
+            .unwrap();
+        match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Reply::PregeneratedChunk(_, pos, result) => {
+                assert_eq!(pos, saved);
+                assert!(result.unwrap().is_none());
+            }
+            _ => panic!("expected a pregenerated chunk"),
+        }
+
+        let missing = ChunkPosition::new(1, 0);
+        sender
+            .send(Request::Pregenerate(WorldId::MAIN, missing))
+            .unwrap();
+        match receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Reply::PregeneratedChunk(_, pos, result) => {
+                assert_eq!(pos, missing);
+                assert_eq!(result.unwrap().unwrap().chunk.position(), missing);
+            }
+            _ => panic!("expected a pregenerated chunk"),
+        }
 
-        open_regions.insert(rpos, file);
+        sender.send(Request::ShutDown).unwrap();
+        while receiver.recv().is_ok() {}
     }
-    open_regions.get_mut(&rpos).unwrap()
 }
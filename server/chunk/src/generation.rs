@@ -0,0 +1,373 @@
+//! Generation of new chunks on a dedicated thread pool.
+//!
+//! Chunks which don't exist in the world save are queued here
+//! by the chunk worker. Queued chunks are generated in order of
+//! their distance to the nearest player in the same world, so the
+//! chunks a player sees first arrive first, regardless of request
+//! order. Each world has its own generator. Chunks which are
+//! pregenerated come after all chunks being loaded.
+//!
+//! Generators only place blocks; light is calculated
+//! here once a chunk has been generated.
+
+use crate::chunk_worker::{ChunkLoad, Reply};
+use ahash::AHashMap;
+use crossbeam::channel::Sender;
+use feather_core::chunk::light;
+use feather_core::util::ChunkPosition;
+use feather_server_types::{load_chunk_pois, WorldId};
+use feather_server_worldgen::WorldGenerator;
+use fecs::Entity;
+use parking_lot::{Condvar, Mutex, RwLock};
+use smallvec::SmallVec;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Why a chunk is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Purpose {
+    /// The chunk is being loaded into its world.
+    Load,
+    /// The chunk is pregenerated, so it is only saved.
+    Pregenerate,
+}
+
+/// A chunk waiting to be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QueuedChunk {
+    world: WorldId,
+    pos: ChunkPosition,
+    purpose: Purpose,
+    /// Distance, in chunks, to the nearest player.
+    distance: i32,
+    /// Order in which the chunk was queued, used
+    /// to break ties between equally distant chunks.
+    sequence: u64,
+}
+
+impl Ord for QueuedChunk {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap, so the
+        // comparison is reversed.
+        (other.purpose, other.distance, other.sequence).cmp(&(
+            self.purpose,
+            self.distance,
+            self.sequence,
+        ))
+    }
+}
+
+impl PartialOrd for QueuedChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Priority queue of chunks to generate, keyed
+/// by distance to the nearest player.
+#[derive(Debug, Default)]
+pub struct GenerationQueue {
+    chunks: BinaryHeap<QueuedChunk>,
+    /// The world and chunk each player is in.
+    focus: AHashMap<Entity, (WorldId, ChunkPosition)>,
+    next_sequence: u64,
+}
+
+impl GenerationQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a chunk for generation.
+    pub fn push(&mut self, world: WorldId, pos: ChunkPosition, purpose: Purpose) {
+        let chunk = QueuedChunk {
+            world,
+            pos,
+            purpose,
+            distance: self.distance(world, pos),
+            sequence: self.next_sequence,
+        };
+        self.next_sequence += 1;
+        self.chunks.push(chunk);
+    }
+
+    /// Removes and returns the chunk with the highest priority.
+    pub fn pop(&mut self) -> Option<(WorldId, ChunkPosition, Purpose)> {
+        self.chunks
+            .pop()
+            .map(|chunk| (chunk.world, chunk.pos, chunk.purpose))
+    }
+
+    /// Sets the world and chunk a player is in, or removes the
+    /// player if `chunk` is `None`. Queued chunks are reprioritized.
+    pub fn set_focus(&mut self, player: Entity, chunk: Option<(WorldId, ChunkPosition)>) {
+        match chunk {
+            Some(chunk) => self.focus.insert(player, chunk),
+            None => self.focus.remove(&player),
+        };
+
+        let chunks = mem::take(&mut self.chunks).into_vec();
+        self.chunks = chunks
+            .into_iter()
+            .map(|chunk| QueuedChunk {
+                distance: self.distance(chunk.world, chunk.pos),
+                ..chunk
+            })
+            .collect();
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the distance from the given chunk to the nearest
+    /// player in its world. Chunks in worlds without players
+    /// come last, in request order.
+    fn distance(&self, world: WorldId, pos: ChunkPosition) -> i32 {
+        self.focus
+            .values()
+            .filter(|(focus_world, _)| *focus_world == world)
+            .map(|(_, focus)| (focus.x - pos.x).abs().max((focus.z - pos.z).abs()))
+            .min()
+            .unwrap_or(i32::MAX)
+    }
+}
+
+struct Shared {
+    queue: Mutex<GenerationQueue>,
+    generators: RwLock<AHashMap<WorldId, Arc<dyn WorldGenerator>>>,
+    /// Notified when chunks are queued or the pool shuts down.
+    available: Condvar,
+    shut_down: AtomicBool,
+}
+
+/// A pool of threads which generate chunks from
+/// a `GenerationQueue`, sending the results as
+/// `Reply::LoadedChunk` or `Reply::PregeneratedChunk`.
+pub struct GenerationPool {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl GenerationPool {
+    /// Starts a pool with the given number of threads.
+    /// Worlds are added with `add_world`.
+    pub fn start(sender: Sender<Reply>, num_threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(GenerationQueue::new()),
+            generators: RwLock::new(AHashMap::new()),
+            available: Condvar::new(),
+            shut_down: AtomicBool::new(false),
+        });
+
+        let threads = (0..num_threads.max(1))
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                let sender = sender.clone();
+                // Generation recurses deeply enough to overflow
+                // the default stack, as in the chunk worker.
+                std::thread::Builder::new()
+                    .stack_size(1024 * 1024 * 5)
+                    .name(format!("Worldgen Thread #{}", i))
+                    .spawn(move || run(&shared, &sender))
+                    .expect("Unable to start worldgen thread")
+            })
+            .collect();
+
+        Self { shared, threads }
+    }
+
+    /// Sets the generator for a world's chunks.
+    pub fn add_world(&self, world: WorldId, generator: Arc<dyn WorldGenerator>) {
+        self.shared.generators.write().insert(world, generator);
+    }
+
+    /// Removes a world's generator. Its queued
+    /// chunks are discarded once popped.
+    pub fn remove_world(&self, world: WorldId) {
+        self.shared.generators.write().remove(&world);
+    }
+
+    /// Queues a chunk for generation.
+    pub fn submit(&self, world: WorldId, pos: ChunkPosition, purpose: Purpose) {
+        self.shared.queue.lock().push(world, pos, purpose);
+        self.shared.available.notify_one();
+    }
+
+    /// Updates the world and chunk a player is in,
+    /// or removes the player if `chunk` is `None`.
+    pub fn set_focus(&self, player: Entity, chunk: Option<(WorldId, ChunkPosition)>) {
+        self.shared.queue.lock().set_focus(player, chunk);
+    }
+
+    /// Stops the pool, discarding queued chunks and
+    /// waiting for chunks being generated to finish.
+    pub fn shut_down(self) {
+        self.shared.shut_down.store(true, AtomicOrdering::SeqCst);
+        {
+            // Holding the lock ensures no thread is between
+            // checking the flag and waiting.
+            let _queue = self.shared.queue.lock();
+            self.shared.available.notify_all();
+        }
+
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(shared: &Shared, sender: &Sender<Reply>) {
+    loop {
+        let (world, pos, purpose) = {
+            let mut queue = shared.queue.lock();
+            loop {
+                if shared.shut_down.load(AtomicOrdering::SeqCst) {
+                    return;
+                }
+                match queue.pop() {
+                    Some(pos) => break pos,
+                    None => shared.available.wait(&mut queue),
+                }
+            }
+        };
+
+        let generator = match shared.generators.read().get(&world) {
+            Some(generator) => Arc::clone(generator),
+            None => continue,
+        };
+        let mut chunk = generator.generate_chunk(pos);
+        light::compute_light(&mut chunk);
+        let pois = load_chunk_pois(&chunk, None);
+
+        let load = ChunkLoad {
+            chunk,
+            entities: SmallVec::new(),
+            pois,
+        };
+        let reply = match purpose {
+            Purpose::Load => Reply::LoadedChunk(world, pos, Ok(load)),
+            Purpose::Pregenerate => Reply::PregeneratedChunk(world, pos, Ok(Some(load))),
+        };
+        if sender.send(reply).is_err() {
+            // The server has stopped listening.
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_worldgen::EmptyWorldGenerator;
+    use std::time::Duration;
+
+    #[test]
+    fn nearest_chunks_first() {
+        let mut world = fecs::World::new();
+        let player = fecs::EntityBuilder::new().build().spawn_in(&mut world);
+        let other_world = WorldId(1);
+
+        let mut queue = GenerationQueue::new();
+        for x in 0..5 {
+            queue.push(WorldId::MAIN, ChunkPosition::new(x, 0), Purpose::Load);
+        }
+        let pop = |queue: &mut GenerationQueue| queue.pop().map(|(world, pos, _)| (world, pos));
+        // Without players, chunks are generated in request order.
+        assert_eq!(
+            pop(&mut queue),
+            Some((WorldId::MAIN, ChunkPosition::new(0, 0)))
+        );
+
+        queue.set_focus(player, Some((WorldId::MAIN, ChunkPosition::new(3, 0))));
+        assert_eq!(
+            pop(&mut queue),
+            Some((WorldId::MAIN, ChunkPosition::new(3, 0)))
+        );
+        // Ties are broken by request order.
+        assert_eq!(
+            pop(&mut queue),
+            Some((WorldId::MAIN, ChunkPosition::new(2, 0)))
+        );
+        assert_eq!(
+            pop(&mut queue),
+            Some((WorldId::MAIN, ChunkPosition::new(4, 0)))
+        );
+
+        // Players only prioritize chunks in their own world.
+        queue.push(other_world, ChunkPosition::new(3, 0), Purpose::Load);
+        assert_eq!(
+            pop(&mut queue),
+            Some((WorldId::MAIN, ChunkPosition::new(1, 0)))
+        );
+
+        queue.set_focus(player, None);
+        queue.push(WorldId::MAIN, ChunkPosition::new(100, 100), Purpose::Load);
+        assert_eq!(
+            pop(&mut queue),
+            Some((other_world, ChunkPosition::new(3, 0)))
+        );
+        assert_eq!(
+            pop(&mut queue),
+            Some((WorldId::MAIN, ChunkPosition::new(100, 100)))
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pregenerated_chunks_last() {
+        let mut world = fecs::World::new();
+        let player = fecs::EntityBuilder::new().build().spawn_in(&mut world);
+
+        let mut queue = GenerationQueue::new();
+        queue.set_focus(player, Some((WorldId::MAIN, ChunkPosition::new(0, 0))));
+        queue.push(
+            WorldId::MAIN,
+            ChunkPosition::new(0, 0),
+            Purpose::Pregenerate,
+        );
+        queue.push(WorldId::MAIN, ChunkPosition::new(20, 0), Purpose::Load);
+
+        assert_eq!(
+            queue.pop(),
+            Some((WorldId::MAIN, ChunkPosition::new(20, 0), Purpose::Load))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some((
+                WorldId::MAIN,
+                ChunkPosition::new(0, 0),
+                Purpose::Pregenerate
+            ))
+        );
+    }
+
+    #[test]
+    fn pool_generates_chunks() {
+        let (tx, rx) = crossbeam::channel::unbounded();
+        let pool = GenerationPool::start(tx, 2);
+        pool.add_world(WorldId::MAIN, Arc::new(EmptyWorldGenerator {}));
+
+        pool.submit(WorldId::MAIN, ChunkPosition::new(1, 2), Purpose::Load);
+        match rx.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Reply::LoadedChunk(world, pos, Ok(load)) => {
+                assert_eq!(world, WorldId::MAIN);
+                assert_eq!(pos, ChunkPosition::new(1, 2));
+                assert_eq!(load.chunk.position(), pos);
+            }
+            _ => panic!("expected a loaded chunk"),
+        }
+
+        pool.shut_down();
+        // All senders are dropped once the threads exit.
+        assert!(rx.recv().is_err());
+    }
+}
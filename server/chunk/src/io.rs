@@ -0,0 +1,111 @@
+//! Reading and writing of chunks on a dedicated thread pool.
+//!
+//! The chunk worker submits an `IoJob` for each chunk to load
+//! or save, and receives a `Completion` once the job has run.
+//! Jobs run in the order they were submitted, but several run
+//! at once, so the worker must not submit a job for a chunk
+//! while another job for the same chunk is running.
+
+use crate::chunk_worker::ChunkSave;
+use crate::world_source::{StoredChunk, WorldSource};
+use crossbeam::channel::{Receiver, Sender};
+use feather_core::util::ChunkPosition;
+use feather_server_types::WorldId;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// An operation on a world's `WorldSource`.
+#[allow(clippy::large_enum_variant)]
+pub enum IoJob {
+    Load(WorldId, ChunkPosition, Arc<dyn WorldSource>),
+    Save(WorldId, ChunkPosition, Arc<dyn WorldSource>, ChunkSave),
+    /// Checks whether a chunk exists, without loading it.
+    Check(WorldId, ChunkPosition, Arc<dyn WorldSource>),
+    /// Flushes the chunks saved so far. Errors are logged.
+    Flush(WorldId, Arc<dyn WorldSource>),
+}
+
+/// The result of a load, save or check job.
+#[allow(clippy::large_enum_variant)]
+pub enum Completion {
+    /// `None` if the chunk doesn't exist in the source.
+    Loaded(WorldId, ChunkPosition, anyhow::Result<Option<StoredChunk>>),
+    Saved(WorldId, ChunkPosition, anyhow::Result<()>),
+    Checked(WorldId, ChunkPosition, anyhow::Result<bool>),
+}
+
+/// A pool of threads which run `IoJob`s, sending
+/// the results of loads and saves as `Completion`s.
+pub struct IoPool {
+    jobs: Sender<IoJob>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl IoPool {
+    /// Starts a pool with the given number of threads.
+    pub fn start(completions: Sender<Completion>, num_threads: usize) -> Self {
+        let (jobs, receiver) = crossbeam::channel::unbounded();
+
+        let threads = (0..num_threads.max(1))
+            .map(|i| {
+                let receiver = receiver.clone();
+                let completions = completions.clone();
+                // Loading chunks recurses deeply enough to
+                // overflow the default stack.
+                std::thread::Builder::new()
+                    .stack_size(1024 * 1024 * 5)
+                    .name(format!("Chunk I/O Thread #{}", i))
+                    .spawn(move || run(&receiver, &completions))
+                    .expect("Unable to start chunk I/O thread")
+            })
+            .collect();
+
+        Self { jobs, threads }
+    }
+
+    /// Queues a job to run on the pool.
+    pub fn submit(&self, job: IoJob) {
+        self.jobs
+            .send(job)
+            .expect("chunk I/O threads stopped before the pool");
+    }
+
+    /// Runs the remaining queued jobs, then stops the threads.
+    pub fn shut_down(self) {
+        drop(self.jobs);
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(jobs: &Receiver<IoJob>, completions: &Sender<Completion>) {
+    while let Ok(job) = jobs.recv() {
+        let completion = match job {
+            IoJob::Load(world, pos, source) => {
+                Completion::Loaded(world, pos, source.load_chunk(pos))
+            }
+            IoJob::Save(world, pos, source, save) => {
+                // Write a copy of the chunk, so the server
+                // isn't blocked from modifying it meanwhile.
+                let chunk = save.chunk.read().clone();
+                let result =
+                    source.save_chunk(&chunk, &save.entities, &save.block_entities, &save.poi);
+                Completion::Saved(world, pos, result)
+            }
+            IoJob::Check(world, pos, source) => {
+                Completion::Checked(world, pos, source.contains_chunk(pos))
+            }
+            IoJob::Flush(world, source) => {
+                if let Err(e) = source.flush() {
+                    log::error!("Failed to flush saved chunks of {:?}: {:?}", world, e);
+                }
+                continue;
+            }
+        };
+
+        // The worker may have stopped listening
+        // if it's shutting down.
+        let _ = completions.send(completion);
+    }
+}
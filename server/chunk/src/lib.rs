@@ -2,7 +2,13 @@
 
 mod chunk_manager;
 pub mod chunk_worker;
+pub mod generation;
+mod io;
+mod pregen;
 mod save;
+pub mod world_source;
 
 pub use chunk_manager::*;
+pub use pregen::*;
 pub use save::*;
+pub use world_source::{AnvilWorldSource, MemoryWorldSource, StoredChunk, WorldSource, WorldStorage};
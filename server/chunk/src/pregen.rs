@@ -0,0 +1,450 @@
+//! Pregeneration of the chunks around a position.
+//!
+//! Pregenerated chunks are generated and saved without being
+//! loaded into their world, so players exploring the area later
+//! don't have to wait for them to generate. Chunks which already
+//! exist are skipped.
+//!
+//! To keep ticks fast, the chunk worker generates chunks being
+//! loaded for players first, only a limited number of chunks are
+//! requested at once, and no chunks are requested while the server
+//! is falling behind.
+
+use crate::chunk_manager::{self, LoadingChunks};
+use crate::chunk_worker::{ChunkLoad, Request};
+use crate::ChunkWorkerHandle;
+use ahash::AHashMap;
+use feather_core::text::{Text, TextComponentBuilder};
+use feather_core::util::ChunkPosition;
+use feather_server_types::{Game, MessageReceiver, PoiStorage, WorldId, TICK_LENGTH, TPS};
+use fecs::{Entity, World};
+use parking_lot::RwLock;
+use smallvec::SmallVec;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// The maximum number of chunks of a pregeneration
+/// the chunk worker is working on at once.
+const MAX_PENDING: usize = 32;
+
+/// The interval, in ticks, at which progress is reported.
+const REPORT_INTERVAL: u64 = TPS * 10;
+
+/// The largest radius, in chunks, which can be pregenerated.
+pub const MAX_PREGEN_RADIUS: u32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum PregenError {
+    #[error("Chunks are already being pregenerated in this world")]
+    AlreadyRunning,
+    #[error("The radius must be at most {0} chunks")]
+    TooLarge(u32),
+}
+
+/// The progress of a pregeneration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PregenProgress {
+    /// The number of chunks to pregenerate.
+    pub total: usize,
+    /// The number of chunks which have been generated.
+    pub generated: usize,
+    /// The number of chunks which already existed.
+    pub existing: usize,
+    /// The number of chunks which failed to generate.
+    pub failed: usize,
+}
+
+impl PregenProgress {
+    /// Returns the number of chunks which have been handled.
+    pub fn done(self) -> usize {
+        self.generated + self.existing + self.failed
+    }
+
+    /// Returns the percentage of chunks which have been handled.
+    pub fn percent(self) -> f64 {
+        self.done() as f64 / self.total as f64 * 100.0
+    }
+}
+
+impl Display for PregenProgress {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} chunks ({:.1}%, {} generated, {} already existed",
+            self.done(),
+            self.total,
+            self.percent(),
+            self.generated,
+            self.existing
+        )?;
+        if self.failed > 0 {
+            write!(f, ", {} failed", self.failed)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// The pregeneration of the chunks in a world within
+/// a radius around a center chunk.
+#[derive(Debug)]
+struct PregenTask {
+    /// The chunks which haven't been requested yet.
+    remaining: Rings,
+    progress: PregenProgress,
+    /// The number of chunks requested from the chunk
+    /// worker which haven't been handled yet.
+    pending: usize,
+    /// The entity which started the pregeneration,
+    /// to which progress is reported.
+    requester: Option<Entity>,
+    started: Instant,
+    /// The tick count at which progress was last reported.
+    last_report: u64,
+}
+
+impl PregenTask {
+    fn is_finished(&self) -> bool {
+        self.remaining.is_empty() && self.pending == 0
+    }
+}
+
+/// The pregenerations which are running, at most one per world.
+#[derive(Debug, Default)]
+pub struct Pregenerator {
+    tasks: AHashMap<WorldId, PregenTask>,
+    /// The time at which `pregenerate` last ran.
+    last_run: Option<Instant>,
+}
+
+impl Pregenerator {
+    /// Starts pregenerating the chunks in a world within the
+    /// given radius around a chunk. Progress is reported to
+    /// the requester, if any, and logged.
+    ///
+    /// Returns the number of chunks to pregenerate.
+    pub fn start(
+        &mut self,
+        game: &Game,
+        world: WorldId,
+        center: ChunkPosition,
+        radius: u32,
+        requester: Option<Entity>,
+    ) -> Result<usize, PregenError> {
+        if radius > MAX_PREGEN_RADIUS {
+            return Err(PregenError::TooLarge(MAX_PREGEN_RADIUS));
+        }
+        if self.tasks.contains_key(&world) {
+            return Err(PregenError::AlreadyRunning);
+        }
+
+        let remaining = Rings::new(center, radius as i32);
+        let total = remaining.len();
+        self.tasks.insert(
+            world,
+            PregenTask {
+                remaining,
+                progress: PregenProgress {
+                    total,
+                    generated: 0,
+                    existing: 0,
+                    failed: 0,
+                },
+                pending: 0,
+                requester,
+                started: Instant::now(),
+                last_report: game.tick_count,
+            },
+        );
+
+        log::info!(
+            "Pregenerating {} chunks around {} in {:?}",
+            total,
+            center,
+            world
+        );
+        Ok(total)
+    }
+
+    /// Stops pregenerating chunks in a world, returning
+    /// the progress made, or `None` if none were being
+    /// pregenerated. Chunks already requested are still
+    /// saved once they have been generated.
+    pub fn cancel(&mut self, world: WorldId) -> Option<PregenProgress> {
+        let task = self.tasks.remove(&world)?;
+        log::info!(
+            "Cancelled pregeneration in {:?} after {}",
+            world,
+            task.progress
+        );
+        Some(task.progress)
+    }
+
+    /// Returns the progress of the pregeneration in a world.
+    pub fn progress(&self, world: WorldId) -> Option<PregenProgress> {
+        self.tasks.get(&world).map(|task| task.progress)
+    }
+
+    /// Records that a chunk requested from the chunk worker has
+    /// been handled, and whether it was generated.
+    fn chunk_done(&mut self, world: WorldId, result: &anyhow::Result<bool>) {
+        let task = match self.tasks.get_mut(&world) {
+            Some(task) => task,
+            None => return,
+        };
+        task.pending = task.pending.saturating_sub(1);
+        match result {
+            Ok(true) => task.progress.generated += 1,
+            Ok(false) => task.progress.existing += 1,
+            Err(_) => task.progress.failed += 1,
+        }
+    }
+}
+
+/// System which requests the chunks of running
+/// pregenerations from the chunk worker and reports
+/// their progress.
+#[fecs::system]
+pub fn pregenerate(
+    game: &Game,
+    world: &mut World,
+    #[default] pregenerator: &mut Pregenerator,
+    chunk_worker_handle: &ChunkWorkerHandle,
+) {
+    let now = Instant::now();
+    // Skip a tick if the previous one overran.
+    let behind = pregenerator.last_run.map_or(false, |last_run| {
+        now - last_run > Duration::from_millis(TICK_LENGTH * 2)
+    });
+    pregenerator.last_run = Some(now);
+
+    let mut finished = Vec::new();
+    for (&world_id, task) in &mut pregenerator.tasks {
+        let state = match game.worlds.get(world_id) {
+            Some(state) => state,
+            None => {
+                finished.push(world_id);
+                continue;
+            }
+        };
+
+        while !behind && task.pending < MAX_PENDING {
+            let pos = match task.remaining.next() {
+                Some(pos) => pos,
+                None => break,
+            };
+            // Loaded chunks are saved by autosave.
+            if state.chunk_map.chunk_at(pos).is_some() {
+                task.progress.existing += 1;
+                continue;
+            }
+
+            task.pending += 1;
+            chunk_worker_handle
+                .sender
+                .send(Request::Pregenerate(world_id, pos))
+                .unwrap();
+        }
+
+        if task.is_finished() {
+            finished.push(world_id);
+        } else if game.tick_count >= task.last_report + REPORT_INTERVAL {
+            task.last_report = game.tick_count;
+            let message = format!("Pregenerating: {}", task.progress);
+            log::info!("{} in {:?}", message, world_id);
+            report(world, task.requester, Text::from(message));
+        }
+    }
+
+    for world_id in finished {
+        if let Some(task) = pregenerator.tasks.remove(&world_id) {
+            let message = format!(
+                "Pregenerated {} in {:.1}s",
+                task.progress,
+                task.started.elapsed().as_secs_f64()
+            );
+            log::info!("{} in {:?}", message, world_id);
+            report(world, task.requester, Text::from(message).green());
+        }
+    }
+}
+
+/// Handles a chunk pregenerated by the chunk worker,
+/// saving it if it was generated.
+pub fn handle_pregenerated_chunk(
+    game: &mut Game,
+    pregenerator: &mut Pregenerator,
+    loading_chunks: &LoadingChunks,
+    chunk_worker_handle: &ChunkWorkerHandle,
+    world_id: WorldId,
+    pos: ChunkPosition,
+    result: anyhow::Result<Option<ChunkLoad>>,
+) {
+    let result = match result {
+        Ok(Some(load)) => {
+            // A chunk which was loaded into its world meanwhile
+            // is saved with the changes made to it instead.
+            let loaded = game
+                .worlds
+                .get(world_id)
+                .map_or(true, |state| state.chunk_map.chunk_at(pos).is_some())
+                || loading_chunks.0.contains(&(world_id, pos));
+            if !loaded {
+                save_generated_chunk(chunk_worker_handle, world_id, pos, load);
+            }
+            Ok(true)
+        }
+        Ok(None) => Ok(false),
+        Err(e) => {
+            log::warn!("Failed to pregenerate chunk at {}: {}", pos, e);
+            Err(e)
+        }
+    };
+
+    pregenerator.chunk_done(world_id, &result);
+}
+
+fn save_generated_chunk(
+    chunk_worker_handle: &ChunkWorkerHandle,
+    world_id: WorldId,
+    pos: ChunkPosition,
+    load: ChunkLoad,
+) {
+    let mut pois = PoiStorage::new();
+    pois.insert_chunk(pos, load.pois);
+
+    chunk_manager::save_chunk(
+        chunk_worker_handle,
+        world_id,
+        Arc::new(RwLock::new(load.chunk)),
+        SmallVec::new(),
+        SmallVec::new(),
+        pois.to_stored(pos),
+    );
+}
+
+/// Sends a message about a pregeneration to the entity
+/// which started it, if it still exists.
+fn report(world: &mut World, requester: Option<Entity>, message: Text) {
+    if let Some(requester) = requester {
+        if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(requester) {
+            receiver.send(message);
+        }
+    }
+}
+
+/// Iterator over the chunks in a square around a center chunk,
+/// in rings of increasing distance from the center, so nearby
+/// chunks come first.
+#[derive(Debug, Clone)]
+struct Rings {
+    center: ChunkPosition,
+    radius: i32,
+    /// The distance of the current ring from the center.
+    ring: i32,
+    /// The index of the next chunk in the current ring.
+    index: i32,
+}
+
+impl Rings {
+    fn new(center: ChunkPosition, radius: i32) -> Self {
+        Self {
+            center,
+            radius,
+            ring: 0,
+            index: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ring > self.radius
+    }
+
+    /// The number of chunks in rings `0..ring`.
+    fn area(ring: i32) -> usize {
+        let side = (2 * ring - 1).max(0) as usize;
+        side * side
+    }
+}
+
+impl Iterator for Rings {
+    type Item = ChunkPosition;
+
+    fn next(&mut self) -> Option<ChunkPosition> {
+        let ring = self.ring;
+        if ring > self.radius {
+            return None;
+        }
+
+        // Each side of a ring has `2 * ring` chunks, excluding
+        // the corner at which the next side starts.
+        let side = 2 * ring;
+        let i = self.index;
+        let (dx, dz) = match i.checked_div(side).unwrap_or(0) {
+            0 => (-ring + i, -ring),
+            1 => (ring, -ring + i - side),
+            2 => (ring - (i - 2 * side), ring),
+            _ => (-ring, ring - (i - 3 * side)),
+        };
+
+        self.index += 1;
+        if self.index >= (4 * side).max(1) {
+            self.ring += 1;
+            self.index = 0;
+        }
+
+        Some(ChunkPosition::new(self.center.x + dx, self.center.z + dz))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = Self::area(self.radius + 1) - Self::area(self.ring) - self.index as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Rings {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ahash::AHashSet;
+
+    #[test]
+    fn rings() {
+        let center = ChunkPosition::new(10, -4);
+        let mut rings = Rings::new(center, 3);
+        assert_eq!(rings.len(), 49);
+        assert_eq!(rings.next(), Some(center));
+        assert_eq!(rings.len(), 48);
+
+        let mut seen = AHashSet::new();
+        let mut last_distance = 0;
+        for pos in rings.by_ref() {
+            let distance = (pos.x - center.x).abs().max((pos.z - center.z).abs());
+            assert!(distance >= last_distance && distance <= 3);
+            assert!(seen.insert(pos));
+            last_distance = distance;
+        }
+        assert_eq!(seen.len(), 48);
+        assert!(rings.is_empty());
+        assert_eq!(rings.len(), 0);
+
+        assert_eq!(Rings::new(center, 0).collect::<Vec<_>>(), vec![center]);
+    }
+
+    #[test]
+    fn progress() {
+        let progress = PregenProgress {
+            total: 8,
+            generated: 3,
+            existing: 1,
+            failed: 0,
+        };
+        assert_eq!(progress.done(), 4);
+        assert_eq!(
+            progress.to_string(),
+            "4/8 chunks (50.0%, 3 generated, 1 already existed)"
+        );
+    }
+}
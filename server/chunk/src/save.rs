@@ -1,16 +1,18 @@
 //! Handles saving of chunks and entities
 
+use crate::chunk_worker::Request;
 use crate::{chunk_manager, ChunkWorkerHandle};
 use feather_core::anvil::entity::{AnimalData, BaseEntityData, EntityData};
 use feather_core::anvil::{
     block_entity::BlockEntityData,
-    player::{InventorySlot, PlayerData},
+    player::{ExperienceData, FoodData, InventorySlot, PlayerData, SpawnData},
 };
 use feather_core::inventory::{Inventory, Window};
 use feather_core::util::{ChunkPosition, Gamemode, Position, Vec3d};
 use feather_server_types::{
-    tasks, BlockSerializer, ChunkLoadEvent, ChunkUnloadEvent, ComponentSerializer, Game, Health,
-    HeldItem, PlayerLeaveEvent, Uuid, TICK_LENGTH, TPS,
+    tasks, BlockEntity, BlockSerializer, ChunkLoadEvent, ChunkUnloadEvent, ComponentSerializer,
+    Experience, Game, Health, HeldItem, Hunger, Player, PlayerLeaveEvent, SpawnPoint, Uuid,
+    WorldId, TICK_LENGTH, TPS,
 };
 use fecs::{Entity, World};
 use std::collections::VecDeque;
@@ -20,6 +22,8 @@ use std::sync::Arc;
 /// A chunk to save + the tick count at which to do so.
 #[derive(Clone, Copy, Debug)]
 struct SaveTask {
+    /// World containing the chunk.
+    world: WorldId,
     /// Chunk position to save.
     chunk: ChunkPosition,
     /// Tick count at which to save this chunk.
@@ -37,7 +41,7 @@ pub fn on_chunk_load_queue_for_saving(
     game: &mut Game,
     #[default] save_queue: &mut SaveQueue,
 ) {
-    queue_for_saving(game, save_queue, event.chunk);
+    queue_for_saving(game, save_queue, event.world, event.chunk);
 }
 
 /// On a chunk unload, saves the chunk first.
@@ -48,14 +52,55 @@ pub fn on_chunk_unload_save_chunk(
     world: &mut World,
     chunk_worker_handle: &ChunkWorkerHandle,
 ) {
-    save_chunk_at(game, world, event.chunk, chunk_worker_handle);
+    save_chunk_at(game, world, event.world, event.chunk, chunk_worker_handle);
 }
 
-fn queue_for_saving(game: &mut Game, save_queue: &mut SaveQueue, chunk: ChunkPosition) {
-    let tick_to_save_at =
-        game.tick_count + (game.config.world.save_interval.as_millis() as u64) / TICK_LENGTH;
+/// On a chunk unload, despawns the entities in the chunk
+/// after they have been saved by `on_chunk_unload_save_chunk`.
+/// Players and block entities are left alone.
+#[fecs::event_handler]
+pub fn on_chunk_unload_despawn_entities(
+    event: &ChunkUnloadEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let entities: Vec<Entity> = game.worlds[event.world]
+        .chunk_entities
+        .entities_in_chunk(event.chunk)
+        .iter()
+        .copied()
+        .filter(|&entity| !world.has::<Player>(entity) && !world.has::<BlockEntity>(entity))
+        .collect();
+
+    for entity in entities {
+        game.despawn(entity, world);
+    }
+}
+
+/// On a chunk unload, stops tracking changes to the chunk
+/// once it has been saved and its entities despawned.
+#[fecs::event_handler]
+pub fn on_chunk_unload_forget_dirty(event: &ChunkUnloadEvent, game: &mut Game) {
+    if let Some(state) = game.worlds.get_mut(event.world) {
+        state.dirty_chunks.remove(event.chunk);
+    }
+}
+
+/// Returns the autosave interval in ticks.
+fn save_interval_ticks(game: &Game) -> u64 {
+    ((game.config.world.save_interval.as_millis() as u64) / TICK_LENGTH).max(1)
+}
+
+fn queue_for_saving(
+    game: &mut Game,
+    save_queue: &mut SaveQueue,
+    world: WorldId,
+    chunk: ChunkPosition,
+) {
+    let tick_to_save_at = game.tick_count + save_interval_ticks(game);
 
     let task = SaveTask {
+        world,
         chunk,
         at: tick_to_save_at,
     };
@@ -65,6 +110,9 @@ fn queue_for_saving(game: &mut Game, save_queue: &mut SaveQueue, chunk: ChunkPos
 
 /// System which checks for chunks which have been queued for saving
 /// and, if it is time, saves them.
+///
+/// Once per autosave interval, the chunk worker is also
+/// told to flush the chunks saved so far to disk.
 #[fecs::system]
 pub fn chunk_save(
     game: &mut Game,
@@ -72,6 +120,11 @@ pub fn chunk_save(
     save_queue: &mut SaveQueue,
     chunk_worker_handle: &ChunkWorkerHandle,
 ) {
+    if game.tick_count > 0 && game.tick_count % save_interval_ticks(game) == 0 {
+        log::debug!("Flushing saved chunks");
+        let _ = chunk_worker_handle.sender.send(Request::Flush);
+    }
+
     // no need to run this system every tick
     if game.tick_count % TPS != 0 {
         return;
@@ -83,7 +136,10 @@ pub fn chunk_save(
             None => return, // no save tasks to run
         };
 
-        if game.chunk_map.chunk_at(task.chunk).is_none() {
+        let loaded = game.worlds.get(task.world).map_or(false, |state| {
+            state.chunk_map.chunk_at(task.chunk).is_some()
+        });
+        if !loaded {
             save_queue
                 .0
                 .pop_front()
@@ -93,7 +149,7 @@ pub fn chunk_save(
 
         if task.at <= game.tick_count {
             // Save the chunk, then pop the task from the queue.
-            save_chunk_at(game, world, task.chunk, chunk_worker_handle);
+            save_chunk_at(game, world, task.world, task.chunk, chunk_worker_handle);
 
             save_queue
                 .0
@@ -101,49 +157,60 @@ pub fn chunk_save(
                 .expect("we just verified the front task exists");
 
             // Requeue the chunk for saving again.
-            queue_for_saving(game, save_queue, task.chunk);
+            queue_for_saving(game, save_queue, task.world, task.chunk);
         } else {
             return;
         }
     }
 }
 
+/// Saves a chunk if it changed since it was last saved.
 pub fn save_chunk_at(
-    game: &Game,
+    game: &mut Game,
     world: &World,
+    world_id: WorldId,
     pos: ChunkPosition,
     chunk_worker_handle: &ChunkWorkerHandle,
 ) {
-    let chunk = game
+    let state = &mut game.worlds[world_id];
+    let chunk = state
         .chunk_map
         .chunk_handle_at(pos)
         .expect("chunk does not exist");
 
-    if !chunk.write().check_modified() && game.chunk_entities.entities_in_chunk(pos).is_empty() {
+    // Both flags need to be reset, so don't short-circuit.
+    let modified = chunk.write().check_modified();
+    let unsaved = state.dirty_chunks.take_unsaved(pos);
+    if !modified && !unsaved {
         return;
     }
 
     // Serialize the entities in the chunk.
-    let (entities, block_entities) = serialize_entities(game, world, pos);
+    let (entities, block_entities) = serialize_entities(game, world, world_id, pos);
+    let poi = game.worlds[world_id].poi.to_stored(pos);
 
-    log::trace!("Queuing chunk at {} for saving", pos);
+    log::trace!("Queuing chunk at {} in {:?} for saving", pos, world_id);
     chunk_manager::save_chunk(
         chunk_worker_handle,
-        game.chunk_map.chunk_handle_at(pos).unwrap(),
+        world_id,
+        chunk,
         entities.collect(),
         block_entities.collect(),
+        poi,
     );
 }
 
 fn serialize_entities<'a>(
     game: &'a Game,
     world: &'a World,
+    world_id: WorldId,
     pos: ChunkPosition,
 ) -> (
     impl Iterator<Item = EntityData> + 'a,
     impl Iterator<Item = BlockEntityData> + 'a,
 ) {
-    let entities = game
+    let state = &game.worlds[world_id];
+    let entities = state
         .chunk_entities
         .entities_in_chunk(pos)
         .iter()
@@ -157,7 +224,7 @@ fn serialize_entities<'a>(
             }
         });
 
-    let block_entities = game
+    let block_entities = state
         .chunk_entities
         .entities_in_chunk(pos)
         .iter()
@@ -196,14 +263,42 @@ pub fn save_player_data(game: &Game, world: &World, player: Entity) {
         .try_get::<Health>(player)
         .map(|health| health.0 as f32)
         .unwrap_or(1.0);
+    let hunger = world
+        .try_get::<Hunger>(player)
+        .map(|hunger| *hunger)
+        .unwrap_or_default();
+    let experience = world
+        .try_get::<Experience>(player)
+        .map(|experience| *experience)
+        .unwrap_or_default();
     let data = PlayerData {
         animal: AnimalData::new(
             BaseEntityData::new(*world.get::<Position>(player), Vec3d::broadcast(0.0)),
             health,
         ),
         gamemode: world.get::<Gamemode>(player).id() as i32,
+        dimension: game
+            .worlds
+            .get(game.world_of(world, player))
+            .map_or(0, |state| state.dimension.id()),
         inventory,
         held_item: world.get::<HeldItem>(player).0 as i32,
+        food: FoodData {
+            food_level: hunger.food as i32,
+            saturation: hunger.saturation,
+            exhaustion: hunger.exhaustion,
+            tick_timer: hunger.tick_timer as i32,
+        },
+        experience: ExperienceData {
+            level: experience.level as i32,
+            progress: experience.progress,
+            total: experience.total as i32,
+            seed: experience.seed,
+        },
+        spawn: world
+            .try_get::<SpawnPoint>(player)
+            .map(|spawn| SpawnData::from(*spawn))
+            .unwrap_or_default(),
     };
 
     let uuid = *world.get::<Uuid>(player);
@@ -0,0 +1,371 @@
+//! Sources from which the chunk worker loads chunks,
+//! and to which it saves them.
+//!
+//! The default source is `AnvilWorldSource`, which reads
+//! and writes the region files of a vanilla world save.
+//! `MemoryWorldSource` keeps chunks in memory instead, for
+//! tests and servers whose worlds are never persisted.
+
+use ahash::AHashMap;
+use feather_core::anvil::block_entity::BlockEntityData;
+use feather_core::anvil::compression::RegionCompression;
+use feather_core::anvil::entity::EntityData;
+use feather_core::anvil::poi::{PoiChunk, POI_DIR};
+use feather_core::anvil::region::{self, RegionHandle, RegionPosition, REGION_DIR};
+use feather_core::chunk::Chunk;
+use feather_core::util::ChunkPosition;
+use feather_server_config::World as WorldConfig;
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A chunk and the entities within it, as stored in a `WorldSource`.
+#[derive(Clone)]
+pub struct StoredChunk {
+    pub chunk: Chunk,
+    pub entities: Vec<EntityData>,
+    pub block_entities: Vec<BlockEntityData>,
+    /// The points of interest in the chunk, or `None` if
+    /// they haven't been saved.
+    pub poi: Option<PoiChunk>,
+}
+
+/// Storage for the chunks of a world.
+///
+/// Sources are used from several chunk I/O threads at once,
+/// though never for the same chunk at the same time.
+pub trait WorldSource: Send + Sync + 'static {
+    /// Loads the chunk at the given position. Returns `None`
+    /// if the chunk doesn't exist and should be generated.
+    fn load_chunk(&self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>>;
+
+    /// Returns whether the chunk at the given position exists.
+    fn contains_chunk(&self, pos: ChunkPosition) -> anyhow::Result<bool> {
+        Ok(self.load_chunk(pos)?.is_some())
+    }
+
+    /// Saves a chunk and the entities and points of interest
+    /// within it, replacing any existing data for the chunk.
+    fn save_chunk(
+        &self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+        poi: &PoiChunk,
+    ) -> anyhow::Result<()>;
+
+    /// Ensures all saved chunks have been written to
+    /// persistent storage. Called on autosave and shutdown.
+    fn flush(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Where the chunks of worlds are stored, as set
+/// by the `storage` option of the world config.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorldStorage {
+    /// The region files in each world's directory,
+    /// written with the given compression.
+    Anvil(RegionCompression),
+    /// Memory only, for servers whose worlds are never persisted.
+    Memory,
+}
+
+#[derive(Debug, Error)]
+#[error("invalid world storage {0} (expected anvil or memory)")]
+pub struct WorldStorageParseError(String);
+
+impl WorldStorage {
+    /// Returns the storage set by the world config.
+    pub fn from_config(config: &WorldConfig) -> anyhow::Result<Self> {
+        match config.storage.as_str() {
+            "anvil" => Ok(WorldStorage::Anvil(config.region_compression.parse()?)),
+            "memory" => Ok(WorldStorage::Memory),
+            storage => Err(WorldStorageParseError(storage.to_owned()).into()),
+        }
+    }
+
+    /// Creates the source for the chunks of the world in the given directory.
+    pub fn source(self, dir: &Path) -> Box<dyn WorldSource> {
+        match self {
+            WorldStorage::Anvil(compression) => {
+                Box::new(AnvilWorldSource::new(dir).with_compression(compression))
+            }
+            WorldStorage::Memory => Box::new(MemoryWorldSource::new()),
+        }
+    }
+}
+
+/// A `WorldSource` reading and writing the `.mca` region files
+/// in the `region` directory of a world, and the points of
+/// interest in the `poi` directory.
+pub struct AnvilWorldSource {
+    /// The root directory of the world.
+    dir: PathBuf,
+    /// The compression used for saved chunks.
+    compression: RegionCompression,
+    /// Region files which have been opened. Each region is
+    /// locked separately, so different regions are read and
+    /// written concurrently.
+    regions: Regions,
+    /// Region files of points of interest which have been opened.
+    poi_regions: Regions,
+}
+
+type Regions = Mutex<AHashMap<RegionPosition, Arc<Mutex<RegionHandle>>>>;
+
+impl AnvilWorldSource {
+    /// Creates a source for the world in the given directory,
+    /// e.g. `${SERVER_DIR}/world`. Region files are opened
+    /// when first used.
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            compression: RegionCompression::default(),
+            regions: Mutex::new(AHashMap::new()),
+            poi_regions: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Sets the compression used for saved chunks.
+    pub fn with_compression(mut self, compression: RegionCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the handle for the given region in one of the
+    /// world's region directories, opening its file if necessary.
+    /// If the file doesn't exist, it is created when `create`
+    /// is set; otherwise, `None` is returned.
+    fn region(
+        &self,
+        regions: &Regions,
+        subdir: &str,
+        rpos: RegionPosition,
+        create: bool,
+    ) -> anyhow::Result<Option<Arc<Mutex<RegionHandle>>>> {
+        let mut regions = regions.lock();
+        if !regions.contains_key(&rpos) {
+            let mut handle = if region::region_exists_in(&self.dir, subdir, rpos) {
+                region::load_region_in(&self.dir, subdir, rpos)?
+            } else if create {
+                region::create_region_in(&self.dir, subdir, rpos)?
+            } else {
+                return Ok(None);
+            };
+            handle.set_compression(self.compression);
+            regions.insert(rpos, Arc::new(Mutex::new(handle)));
+        }
+
+        Ok(regions.get(&rpos).cloned())
+    }
+
+    /// Loads the points of interest in the chunk at the given position.
+    fn load_poi(&self, pos: ChunkPosition) -> anyhow::Result<Option<PoiChunk>> {
+        let rpos = RegionPosition::from_chunk(pos);
+        match self.region(&self.poi_regions, POI_DIR, rpos, false)? {
+            Some(handle) => Ok(handle.lock().load_poi(pos)?),
+            None => Ok(None),
+        }
+    }
+}
+
+impl WorldSource for AnvilWorldSource {
+    fn load_chunk(&self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+        let rpos = RegionPosition::from_chunk(pos);
+        let handle = match self.region(&self.regions, REGION_DIR, rpos, false)? {
+            Some(handle) => handle,
+            None => return Ok(None),
+        };
+
+        let result = handle.lock().load_chunk(pos);
+        let (chunk, entities, block_entities) = match result {
+            Ok(loaded) => loaded,
+            Err(region::Error::ChunkNotExist) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Invalid points of interest are found
+        // again by scanning the chunk.
+        let poi = match self.load_poi(pos) {
+            Ok(poi) => poi,
+            Err(e) => {
+                log::warn!(
+                    "Failed to load points of interest in chunk at {}: {}",
+                    pos,
+                    e
+                );
+                None
+            }
+        };
+
+        Ok(Some(StoredChunk {
+            chunk,
+            entities,
+            block_entities,
+            poi,
+        }))
+    }
+
+    fn contains_chunk(&self, pos: ChunkPosition) -> anyhow::Result<bool> {
+        let rpos = RegionPosition::from_chunk(pos);
+        match self.region(&self.regions, REGION_DIR, rpos, false)? {
+            Some(handle) => Ok(handle.lock().contains_chunk(pos)),
+            None => Ok(false),
+        }
+    }
+
+    fn save_chunk(
+        &self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+        poi: &PoiChunk,
+    ) -> anyhow::Result<()> {
+        let pos = chunk.position();
+        let rpos = RegionPosition::from_chunk(pos);
+        let handle = self
+            .region(&self.regions, REGION_DIR, rpos, true)?
+            .expect("region is created if missing");
+        handle.lock().save_chunk(chunk, entities, block_entities)?;
+
+        let handle = self
+            .region(&self.poi_regions, POI_DIR, rpos, true)?
+            .expect("region is created if missing");
+        handle.lock().save_poi(pos, poi)?;
+        Ok(())
+    }
+
+    fn flush(&self) -> anyhow::Result<()> {
+        // Don't hold the maps' locks while flushing,
+        // so other regions can be opened meanwhile.
+        let handles: Vec<_> = self
+            .regions
+            .lock()
+            .values()
+            .chain(self.poi_regions.lock().values())
+            .cloned()
+            .collect();
+        for handle in handles {
+            handle.lock().flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// A `WorldSource` keeping saved chunks in memory. Nothing is
+/// written to disk, so chunks are lost once the source is dropped.
+#[derive(Default)]
+pub struct MemoryWorldSource {
+    chunks: Mutex<AHashMap<ChunkPosition, StoredChunk>>,
+}
+
+impl MemoryWorldSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of chunks which have been saved.
+    pub fn len(&self) -> usize {
+        self.chunks.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.lock().is_empty()
+    }
+}
+
+impl WorldSource for MemoryWorldSource {
+    fn load_chunk(&self, pos: ChunkPosition) -> anyhow::Result<Option<StoredChunk>> {
+        Ok(self.chunks.lock().get(&pos).cloned())
+    }
+
+    fn contains_chunk(&self, pos: ChunkPosition) -> anyhow::Result<bool> {
+        Ok(self.chunks.lock().contains_key(&pos))
+    }
+
+    fn save_chunk(
+        &self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+        poi: &PoiChunk,
+    ) -> anyhow::Result<()> {
+        let stored = StoredChunk {
+            chunk: chunk.clone(),
+            entities: entities.to_vec(),
+            block_entities: block_entities.to_vec(),
+            poi: Some(poi.clone()),
+        };
+        self.chunks.lock().insert(chunk.position(), stored);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn anvil_round_trip() {
+        let dir = env::temp_dir().join(format!("feather-world-source-{}", std::process::id()));
+        let source = AnvilWorldSource::new(&dir);
+
+        // Loading doesn't create region files.
+        let pos = ChunkPosition::new(40, -3);
+        assert!(source.load_chunk(pos).unwrap().is_none());
+        assert!(!source.contains_chunk(pos).unwrap());
+        assert!(!dir.join("region").exists());
+
+        let mut chunk = Chunk::new(pos);
+        chunk.set_block_at(1, 64, 2, BlockId::stone());
+        let mut poi = PoiChunk::new();
+        poi.set_section(4, vec![]);
+        source.save_chunk(&chunk, &[], &[], &poi).unwrap();
+        source.flush().unwrap();
+
+        // A fresh source reads the chunk back from disk.
+        let source = AnvilWorldSource::new(&dir);
+        let stored = source.load_chunk(pos).unwrap().unwrap();
+        let other = source.load_chunk(ChunkPosition::new(41, -3)).unwrap();
+        let contains = source.contains_chunk(pos).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(stored.chunk.position(), pos);
+        assert_eq!(stored.chunk.block_at(1, 64, 2), BlockId::stone());
+        assert_eq!(stored.chunk.block_at(1, 65, 2), BlockId::air());
+        assert_eq!(stored.poi, Some(poi));
+        assert!(other.is_none());
+        assert!(contains);
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        let source = MemoryWorldSource::new();
+        let pos = ChunkPosition::new(-2, 9);
+        assert!(source.load_chunk(pos).unwrap().is_none());
+        assert!(source.is_empty());
+
+        let mut chunk = Chunk::new(pos);
+        chunk.set_block_at(3, 10, 4, BlockId::stone());
+        source
+            .save_chunk(&chunk, &[], &[], &PoiChunk::new())
+            .unwrap();
+
+        // Later changes don't affect the saved chunk.
+        chunk.set_block_at(3, 11, 4, BlockId::stone());
+
+        let stored = source.load_chunk(pos).unwrap().unwrap();
+        assert_eq!(stored.chunk.block_at(3, 10, 4), BlockId::stone());
+        assert_eq!(stored.chunk.block_at(3, 11, 4), BlockId::air());
+        assert_eq!(stored.poi, Some(PoiChunk::new()));
+        assert!(source.contains_chunk(pos).unwrap());
+        assert!(!source.contains_chunk(ChunkPosition::new(0, 0)).unwrap());
+        assert_eq!(source.len(), 1);
+    }
+}
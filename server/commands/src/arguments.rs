@@ -1,12 +1,20 @@
+use crate::suggestions::SuggestionProvider;
+use crate::tree::DeclaredArgument;
 use crate::CommandCtx;
+use feather_core::blocks::{BlockId, Mirror, MirrorParseError, Rotation, RotationParseError};
+use feather_core::items::ItemStack;
+use feather_core::network::packets::ArgumentParser;
 use feather_core::position;
 use feather_core::util::{Gamemode, Position};
 use feather_definitions::Item;
 use feather_server_types::{Game, Name, NetworkId, Player};
+use feather_server_worldgen::StructureKind;
 use fecs::{component, Entity, IntoQuery, Read, World};
 use lieutenant::{ArgumentKind, Input};
 use rand::Rng;
 use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::num::ParseFloatError;
 use std::str::FromStr;
@@ -314,6 +322,32 @@ impl ArgumentKind<CommandCtx> for ParsedGamemode {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum StructureParseError {
+    #[error("unknown structure {0}")]
+    UnknownStructure(String),
+}
+
+/// A structure name, as used by `/locate` ("Village", "Shipwreck", ...)
+#[derive(Copy, Clone, Debug)]
+pub struct StructureArgument(pub StructureKind);
+
+impl ArgumentKind<CommandCtx> for StructureArgument {
+    type ParseError = StructureParseError;
+
+    fn satisfies<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        !input.advance_until(" ").is_empty()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        let s = input.advance_until(" ");
+
+        StructureKind::from_name(s)
+            .map(StructureArgument)
+            .ok_or_else(|| StructureParseError::UnknownStructure(s.to_owned()))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum TextParseError {}
 
@@ -359,7 +393,7 @@ impl ArgumentKind<CommandCtx> for ItemArgument {
 
     fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
         let text = input.advance_until(" ");
-        let item = Item::from_identifier(text);
+        let item = Item::from_identifier(&namespaced(text));
         match item {
             Some(s) => Ok(ItemArgument(s)),
             None => Err(ItemParseError::ItemDoesNotExist(text.to_owned())),
@@ -367,6 +401,118 @@ impl ArgumentKind<CommandCtx> for ItemArgument {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum ItemStackParseError {
+    #[error("Unknown item {0}")]
+    UnknownItem(String),
+    #[error("Item NBT is not supported, found {0}")]
+    UnsupportedNbt(String),
+}
+
+/// An item stack, as given by `/give`: an item identifier,
+/// e.g. `diamond_sword`. The parsed stack holds a single item.
+#[derive(Copy, Clone, Debug)]
+pub struct ItemStackArgument(pub ItemStack);
+
+impl ArgumentKind<CommandCtx> for ItemStackArgument {
+    type ParseError = ItemStackParseError;
+
+    fn satisfies<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        Self::parse(ctx, input).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        let text = input.advance_until(" ");
+        if let Some(start) = text.find('{') {
+            return Err(ItemStackParseError::UnsupportedNbt(
+                text[start..].to_owned(),
+            ));
+        }
+
+        Item::from_identifier(&namespaced(text))
+            .map(|item| ItemStackArgument(ItemStack::new(item, 1)))
+            .ok_or_else(|| ItemStackParseError::UnknownItem(text.to_owned()))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum BlockStateParseError {
+    #[error("Unknown block {0}")]
+    UnknownBlock(String),
+    #[error("Block {0} has no property {1}")]
+    UnknownProperty(String, String),
+    #[error("Invalid block properties {0}")]
+    InvalidProperties(String),
+}
+
+/// A block state: a block identifier, optionally followed by
+/// some of its properties, e.g. `oak_log[axis=x]`. Properties
+/// which aren't given keep their default value.
+#[derive(Copy, Clone, Debug)]
+pub struct BlockStateArgument(pub BlockId);
+
+impl ArgumentKind<CommandCtx> for BlockStateArgument {
+    type ParseError = BlockStateParseError;
+
+    fn satisfies<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        Self::parse(ctx, input).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        parse_block_state(input.advance_until(" ")).map(BlockStateArgument)
+    }
+}
+
+fn parse_block_state(s: &str) -> Result<BlockId, BlockStateParseError> {
+    let (name, properties) = match s.find('[') {
+        Some(start) if s.ends_with(']') => (&s[..start], &s[start + 1..s.len() - 1]),
+        Some(_) => return Err(BlockStateParseError::InvalidProperties(s.to_owned())),
+        None => (s, ""),
+    };
+
+    let identifier = namespaced(name);
+    let block = BlockId::from_identifier(&identifier)
+        .ok_or_else(|| BlockStateParseError::UnknownBlock(name.to_owned()))?;
+    if properties.is_empty() {
+        return Ok(block);
+    }
+
+    let mut values: BTreeMap<String, String> = block
+        .to_properties_map()
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+    for property in properties.split(',') {
+        let mut parts = property.splitn(2, '=');
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return Err(BlockStateParseError::InvalidProperties(s.to_owned())),
+        };
+        match values.get_mut(key) {
+            Some(old) => *old = value.to_owned(),
+            None => {
+                return Err(BlockStateParseError::UnknownProperty(
+                    name.to_owned(),
+                    key.to_owned(),
+                ))
+            }
+        }
+    }
+
+    BlockId::from_identifier_and_properties(&identifier, &values)
+        .ok_or_else(|| BlockStateParseError::InvalidProperties(s.to_owned()))
+}
+
+/// Prefixes an identifier with the `minecraft` namespace
+/// if it has none, e.g. `stone` becomes `minecraft:stone`.
+fn namespaced(identifier: &str) -> Cow<str> {
+    if identifier.contains(':') {
+        Cow::Borrowed(identifier)
+    } else {
+        Cow::Owned(format!("minecraft:{}", identifier))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum I32ParseError {
     #[error("Invalid integer {0}")]
@@ -426,3 +572,205 @@ impl ArgumentKind<CommandCtx> for PositiveI32Argument {
         }
     }
 }
+
+#[derive(Debug, Error)]
+pub enum F64ParseError {
+    #[error("Invalid number {0}")]
+    Invalid(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct F64Argument(pub f64);
+
+impl ArgumentKind<CommandCtx> for F64Argument {
+    type ParseError = F64ParseError;
+
+    fn satisfies<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        !input.advance_until(" ").is_empty()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        let text = input.advance_until(" ");
+        match text.parse::<f64>() {
+            Ok(number) if number.is_finite() => Ok(F64Argument(number)),
+            _ => Err(F64ParseError::Invalid(text.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TimeParseError {
+    #[error("Invalid time {0}")]
+    Invalid(String),
+    #[error("Time must not be less than 0, found {0}")]
+    Negative(String),
+}
+
+/// A duration in ticks, optionally with a unit: `d` for
+/// days, `s` for seconds or `t` for ticks, e.g. `1.5d`.
+#[derive(Copy, Clone, Debug)]
+pub struct TimeArgument(pub i32);
+
+impl ArgumentKind<CommandCtx> for TimeArgument {
+    type ParseError = TimeParseError;
+
+    fn satisfies<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        Self::parse(ctx, input).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        let text = input.advance_until(" ");
+        let (number, ticks_per_unit) = match text.char_indices().last() {
+            Some((i, 'd')) => (&text[..i], 24_000.0),
+            Some((i, 's')) => (&text[..i], 20.0),
+            Some((i, 't')) => (&text[..i], 1.0),
+            _ => (text, 1.0),
+        };
+
+        let ticks = match number.parse::<f32>() {
+            Ok(number) if number.is_finite() => (number * ticks_per_unit).round(),
+            _ => return Err(TimeParseError::Invalid(text.to_owned())),
+        };
+        if ticks < 0.0 {
+            return Err(TimeParseError::Negative(text.to_owned()));
+        }
+        Ok(TimeArgument(ticks.min(i32::MAX as f32) as i32))
+    }
+}
+
+/// A structure rotation, e.g. `clockwise_90`.
+#[derive(Copy, Clone, Debug)]
+pub struct RotationArgument(pub Rotation);
+
+impl ArgumentKind<CommandCtx> for RotationArgument {
+    type ParseError = RotationParseError;
+
+    fn satisfies<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        Self::parse(ctx, input).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        Rotation::from_str(input.advance_until(" ")).map(RotationArgument)
+    }
+}
+
+/// A structure mirror, e.g. `left_right`.
+#[derive(Copy, Clone, Debug)]
+pub struct MirrorArgument(pub Mirror);
+
+impl ArgumentKind<CommandCtx> for MirrorArgument {
+    type ParseError = MirrorParseError;
+
+    fn satisfies<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        Self::parse(ctx, input).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        Mirror::from_str(input.advance_until(" ")).map(MirrorArgument)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StructureFileParseError {
+    #[error("invalid structure file name {0}")]
+    InvalidName(String),
+}
+
+/// The name of a structure file, as used by `/structure`, e.g.
+/// `house` or `house.schem`. Names consist of lowercase letters,
+/// digits, `-` and `_`, optionally followed by an extension.
+#[derive(Clone, Debug)]
+pub struct StructureFileArgument(pub String);
+
+impl ArgumentKind<CommandCtx> for StructureFileArgument {
+    type ParseError = StructureFileParseError;
+
+    fn satisfies<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        Self::parse(ctx, input).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        let s = input.advance_until(" ");
+
+        let mut parts = s.splitn(2, '.');
+        let valid = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        };
+        let stem_valid = parts.next().map_or(false, valid);
+        let extension_valid = parts.next().map_or(true, valid);
+
+        if stem_valid && extension_valid {
+            Ok(StructureFileArgument(s.to_owned()))
+        } else {
+            Err(StructureFileParseError::InvalidName(s.to_owned()))
+        }
+    }
+}
+
+impl DeclaredArgument for EntitySelector {
+    const PARSER: ArgumentParser = ArgumentParser::Entity(0);
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Entities);
+}
+
+impl DeclaredArgument for Coordinates {
+    const PARSER: ArgumentParser = ArgumentParser::Vec3;
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Coordinates);
+}
+
+impl DeclaredArgument for ParsedGamemode {
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Gamemodes);
+}
+
+impl DeclaredArgument for StructureArgument {
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Structures);
+}
+
+impl DeclaredArgument for TextArgument {
+    const PARSER: ArgumentParser = ArgumentParser::GreedyPhrase;
+}
+
+impl DeclaredArgument for ItemArgument {
+    const PARSER: ArgumentParser = ArgumentParser::ItemStack;
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Items);
+}
+
+impl DeclaredArgument for ItemStackArgument {
+    const PARSER: ArgumentParser = ArgumentParser::ItemStack;
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Items);
+}
+
+impl DeclaredArgument for BlockStateArgument {
+    const PARSER: ArgumentParser = ArgumentParser::BlockState;
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Blocks);
+}
+
+impl DeclaredArgument for I32Argument {
+    const PARSER: ArgumentParser = ArgumentParser::Integer(None);
+}
+
+impl DeclaredArgument for PositiveI32Argument {
+    const PARSER: ArgumentParser = ArgumentParser::Integer(Some(0));
+}
+
+impl DeclaredArgument for F64Argument {
+    const PARSER: ArgumentParser = ArgumentParser::Double;
+}
+
+/// Clients of 1.13.2 have no parser for times
+/// with units, so they are declared as words.
+impl DeclaredArgument for TimeArgument {}
+
+impl DeclaredArgument for RotationArgument {
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Rotations);
+}
+
+impl DeclaredArgument for MirrorArgument {
+    const SUGGESTIONS: Option<SuggestionProvider> = Some(SuggestionProvider::Mirrors);
+}
+
+impl DeclaredArgument for StructureFileArgument {}
+
+impl DeclaredArgument for String {}
@@ -2,22 +2,37 @@
 
 use crate::arguments::Coordinates;
 use crate::{
-    arguments::{EntitySelector, ItemArgument, ParsedGamemode, PositiveI32Argument, TextArgument},
-    CommandCtx,
+    arguments::{
+        BlockStateArgument, EntitySelector, F64Argument, I32Argument, ItemArgument,
+        ItemStackArgument, MirrorArgument, ParsedGamemode, PositiveI32Argument, RotationArgument,
+        StructureArgument, StructureFileArgument, TextArgument, TimeArgument,
+    },
+    CommandCtx, CommandResult,
 };
+use feather_core::anvil::structure::Structure;
+use feather_core::blocks::{Mirror, Rotation};
 use feather_core::inventory::{Inventory, SlotIndex};
+use feather_core::items::ItemStack;
+use feather_core::network::packets::{WorldBorder as WorldBorderPacket, WorldBorderAction};
+use feather_core::position;
 use feather_core::text::{Text, TextComponentBuilder, TextValue};
 use feather_core::util::{Gamemode, Position};
 use feather_definitions::Item;
+use feather_server_block::{export_structure, place_structure};
+use feather_server_chunk::Pregenerator;
 use feather_server_types::{
-    Ban, ChatEvent, ChatPosition, GamemodeUpdateEvent, InventoryUpdateEvent, MessageReceiver, Name,
-    Player, ShutdownChannels, Teleported, WrappedBanInfo,
+    Ban, BlockUpdateCause, ChatEvent, ChatPosition, Experience, Game, GamemodeUpdateEvent,
+    InventoryUpdateEvent, ItemDropEvent, MessageReceiver, Name, Player, ShutdownChannels,
+    Teleported, Time, Weather, WorldBorder, WorldId, WrappedBanInfo, MAX_BORDER_SIZE,
 };
-use feather_server_util::{name_to_uuid_offline, name_to_uuid_online};
+use feather_server_util::{broadcast_time, name_to_uuid_offline, name_to_uuid_online};
 use fecs::{Entity, IntoQuery, Read, ResourcesProvider, World};
-use lieutenant::command;
+use rand::Rng;
 use smallvec::SmallVec;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
 use thiserror::Error;
 use tokio::runtime::Runtime;
@@ -31,11 +46,11 @@ pub enum TpError {
     TooManyEntities,
 }
 
-#[command(usage = "tp|teleport <destination>")]
-pub fn tp_1(ctx: &mut CommandCtx, destination: EntitySelector) -> anyhow::Result<()> {
+pub fn tp_1(ctx: &mut CommandCtx, destination: EntitySelector) -> CommandResult {
     if let Some(first) = destination.entities.first() {
         if let Some(pos) = ctx.world.try_get::<Position>(*first).map(|r| *r) {
-            teleport_entity_to_pos(&mut ctx.world, ctx.sender, pos);
+            let world_id = ctx.game.world_of(&*ctx.world, *first);
+            teleport_entity_to_world(&mut ctx.game, &mut ctx.world, ctx.sender, world_id, pos);
         }
 
         Ok(Some(format!(
@@ -48,9 +63,8 @@ pub fn tp_1(ctx: &mut CommandCtx, destination: EntitySelector) -> anyhow::Result
     }
 }
 
-#[command(usage = "tp|teleport <location>")]
-pub fn tp_2(ctx: &mut CommandCtx, location: Coordinates) -> anyhow::Result<()> {
-    teleport_entity(&mut ctx.world, ctx.sender, location);
+pub fn tp_2(ctx: &mut CommandCtx, location: Coordinates) -> CommandResult {
+    teleport_entity(&mut ctx.game, &mut ctx.world, ctx.sender, location);
 
     let position = ctx.world.get::<Position>(ctx.sender);
     Ok(Some(format!(
@@ -62,17 +76,12 @@ pub fn tp_2(ctx: &mut CommandCtx, location: Coordinates) -> anyhow::Result<()> {
     )))
 }
 
-#[command(usage = "tp|teleport <targets> <location>")]
-pub fn tp_3(
-    ctx: &mut CommandCtx,
-    targets: EntitySelector,
-    location: Coordinates,
-) -> anyhow::Result<()> {
+pub fn tp_3(ctx: &mut CommandCtx, targets: EntitySelector, location: Coordinates) -> CommandResult {
     if targets.entities.is_empty() {
         Err(TpError::NoMatchingEntities.into())
     } else {
         for entity in &targets.entities {
-            teleport_entity(&mut ctx.world, *entity, location);
+            teleport_entity(&mut ctx.game, &mut ctx.world, *entity, location);
         }
 
         let position = ctx
@@ -88,12 +97,11 @@ pub fn tp_3(
     }
 }
 
-#[command(usage = "tp|teleport <targets> <destination>")]
 pub fn tp_4(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     destination: EntitySelector,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     if destination.entities.len() > 1 {
         Err(TpError::TooManyEntities.into())
     } else if let Some(location) = destination
@@ -105,8 +113,15 @@ pub fn tp_4(
         if targets.entities.is_empty() {
             Err(TpError::NoMatchingEntities.into())
         } else {
+            let world_id = ctx.game.world_of(&*ctx.world, destination.entities[0]);
             for entity in &targets.entities {
-                teleport_entity_to_pos(&mut ctx.world, *entity, location);
+                teleport_entity_to_world(
+                    &mut ctx.game,
+                    &mut ctx.world,
+                    *entity,
+                    world_id,
+                    location,
+                );
             }
             Ok(Some(format!(
                 "Teleported {0} to {1}",
@@ -119,14 +134,15 @@ pub fn tp_4(
     }
 }
 
-fn teleport_entity(world: &mut World, entity: Entity, location: Coordinates) {
+fn teleport_entity(game: &mut Game, world: &mut World, entity: Entity, location: Coordinates) {
     let new_pos = world
         .try_get::<Position>(entity)
         .map(|r| *r)
         .map(|relative_to| location.into_position(relative_to));
 
     if let Some(new_pos) = new_pos {
-        teleport_entity_to_pos(world, entity, new_pos);
+        let world_id = game.world_of(world, entity);
+        teleport_entity_to_world(game, world, entity, world_id, new_pos);
     }
 }
 
@@ -137,8 +153,28 @@ fn teleport_entity_to_pos(world: &mut World, entity: Entity, pos: Position) {
     let _ = world.add(entity, Teleported);
 }
 
-#[command(usage = "gamemode <gamemode>")]
-pub fn gamemode_1(ctx: &mut CommandCtx, gamemode: ParsedGamemode) -> anyhow::Result<()> {
+/// Teleports an entity to a position which may be in another world.
+/// Positions outside the world's border are moved inside it.
+fn teleport_entity_to_world(
+    game: &mut Game,
+    world: &mut World,
+    entity: Entity,
+    world_id: WorldId,
+    pos: Position,
+) {
+    let pos = game
+        .worlds
+        .get(world_id)
+        .map_or(pos, |state| state.border.clamp(pos));
+
+    if game.world_of(world, entity) == world_id {
+        teleport_entity_to_pos(world, entity, pos);
+    } else {
+        game.change_world(world, entity, world_id, pos);
+    }
+}
+
+pub fn gamemode_1(ctx: &mut CommandCtx, gamemode: ParsedGamemode) -> CommandResult {
     update_gamemode(ctx, gamemode.0, ctx.sender);
     Ok(Some(format!(
         "Set own gamemode to {} Mode",
@@ -146,12 +182,11 @@ pub fn gamemode_1(ctx: &mut CommandCtx, gamemode: ParsedGamemode) -> anyhow::Res
     )))
 }
 
-#[command(usage = "gamemode <gamemode> <target>")]
 pub fn gamemode_2(
     ctx: &mut CommandCtx,
     gamemode: ParsedGamemode,
     target: EntitySelector,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     for entity in &target.entities {
         update_gamemode(ctx, gamemode.0, *entity)
     }
@@ -189,12 +224,11 @@ fn update_gamemode(ctx: &mut CommandCtx, gamemode: Gamemode, entity: Entity) {
     }
 }
 
-#[command(usage = "tell|msg|w <target> <message>")]
 pub fn whisper(
     ctx: &mut CommandCtx,
     target: EntitySelector,
     message: TextArgument,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     let sender_name = if let Some(sender_name) = ctx.world.try_get::<Name>(ctx.sender) {
         sender_name.0.clone()
     } else {
@@ -248,8 +282,7 @@ pub fn whisper(
     Ok(None)
 }
 
-#[command(usage = "say <message>")]
-pub fn say(ctx: &mut CommandCtx, message: TextArgument) -> anyhow::Result<()> {
+pub fn say(ctx: &mut CommandCtx, message: TextArgument) -> CommandResult {
     let name = ctx.world.try_get::<Name>(ctx.sender);
 
     let sender_name = if let Some(name) = &name {
@@ -273,8 +306,7 @@ pub fn say(ctx: &mut CommandCtx, message: TextArgument) -> anyhow::Result<()> {
     Ok(None)
 }
 
-#[command(usage = "me <action>")]
-pub fn me(ctx: &mut CommandCtx, action: TextArgument) -> anyhow::Result<()> {
+pub fn me(ctx: &mut CommandCtx, action: TextArgument) -> CommandResult {
     let command_output = {
         let name = ctx.world.try_get::<Name>(ctx.sender);
         let sender_name = name.as_deref().map_or("@", |Name(n)| n);
@@ -300,8 +332,7 @@ pub enum KickError {
     NoEntities,
 }
 
-#[command(usage = "kick <targets>")]
-pub fn kick_1(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<()> {
+pub fn kick_1(ctx: &mut CommandCtx, targets: EntitySelector) -> CommandResult {
     kick_players(
         ctx,
         &targets,
@@ -309,12 +340,11 @@ pub fn kick_1(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<(
     )
 }
 
-#[command(usage = "kick <targets> <reason>")]
 pub fn kick_2(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     reason: TextArgument,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     kick_players(ctx, &targets, reason.0.into())
 }
 
@@ -349,8 +379,7 @@ fn kick_players(
     Ok(None)
 }
 
-#[command(usage = "stop")]
-pub fn stop(ctx: &mut CommandCtx) -> anyhow::Result<()> {
+pub fn stop(ctx: &mut CommandCtx) -> CommandResult {
     // Confirmation message
     // TODO Server ops should also see the message
     if let Some(mut sender_message_receiver) = ctx.world.try_get_mut::<MessageReceiver>(ctx.sender)
@@ -382,8 +411,7 @@ pub enum ClearError {
     NoEntities,
 }
 
-#[command(usage = "clear")]
-pub fn clear_1(ctx: &mut CommandCtx) -> anyhow::Result<()> {
+pub fn clear_1(ctx: &mut CommandCtx) -> CommandResult {
     if ctx.world.try_get::<Player>(ctx.sender).is_some() {
         // Go through the player's inventory and set all the slots to no items.
         // Also, keep track of how many items we delete.
@@ -407,8 +435,7 @@ pub fn clear_1(ctx: &mut CommandCtx) -> anyhow::Result<()> {
     }
 }
 
-#[command(usage = "clear <targets>")]
-pub fn clear_2(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<()> {
+pub fn clear_2(ctx: &mut CommandCtx, targets: EntitySelector) -> CommandResult {
     let mut players = true;
     for entity in &targets.entities {
         players &= ctx.world.try_get::<Player>(*entity).is_some();
@@ -437,12 +464,7 @@ pub fn clear_2(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<
     }
 }
 
-#[command(usage = "clear <targets> <item>")]
-pub fn clear_3(
-    ctx: &mut CommandCtx,
-    targets: EntitySelector,
-    item: ItemArgument,
-) -> anyhow::Result<()> {
+pub fn clear_3(ctx: &mut CommandCtx, targets: EntitySelector, item: ItemArgument) -> CommandResult {
     let mut players = true;
     for entity in &targets.entities {
         players &= ctx.world.try_get::<Player>(*entity).is_some();
@@ -471,13 +493,12 @@ pub fn clear_3(
     }
 }
 
-#[command(usage = "clear <targets> <item> <maxcount>")]
 pub fn clear_4(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     item: ItemArgument,
     maxcount: PositiveI32Argument,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     let mut players = true;
     for entity in &targets.entities {
         players &= ctx.world.try_get::<Player>(*entity).is_some();
@@ -565,8 +586,112 @@ fn clear_items(
     }
 }
 
-#[command(usage = "seed")]
-pub fn seed(ctx: &mut CommandCtx) -> anyhow::Result<()> {
+/// The most stacks of an item `/give` gives at once.
+const MAX_GIVE_STACKS: i32 = 100;
+
+#[derive(Debug, Error)]
+pub enum GiveError {
+    #[error("No player was found")]
+    NoPlayers,
+    #[error(
+        "Only players may be affected by this command, but the provided selector includes entities"
+    )]
+    NoEntities,
+    #[error("Can't give less than 1 item")]
+    NoItems,
+    #[error("Can't give more than {0} of {1}")]
+    TooManyItems(i32, String),
+}
+
+pub fn give_1(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    item: ItemStackArgument,
+) -> CommandResult {
+    give_items(ctx, &targets, item.0, 1)
+}
+
+pub fn give_2(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    item: ItemStackArgument,
+    count: PositiveI32Argument,
+) -> CommandResult {
+    give_items(ctx, &targets, item.0, count.0)
+}
+
+fn give_items(
+    ctx: &mut CommandCtx,
+    targets: &EntitySelector,
+    stack: ItemStack,
+    count: i32,
+) -> anyhow::Result<Option<String>> {
+    let stack_size = stack.ty.stack_size() as i32;
+    if count == 0 {
+        return Err(GiveError::NoItems.into());
+    }
+    if count > stack_size * MAX_GIVE_STACKS {
+        return Err(GiveError::TooManyItems(
+            stack_size * MAX_GIVE_STACKS,
+            stack.ty.display_name().to_owned(),
+        )
+        .into());
+    }
+    if targets.entities.is_empty() {
+        return Err(GiveError::NoPlayers.into());
+    }
+    if !targets
+        .entities
+        .iter()
+        .all(|entity| ctx.world.has::<Player>(*entity))
+    {
+        return Err(GiveError::NoEntities.into());
+    }
+
+    for player in &targets.entities {
+        let mut remaining = count;
+        while remaining > 0 {
+            let amount = remaining.min(stack_size);
+            give_stack(ctx, *player, stack.of_amount(amount as u8));
+            remaining -= amount;
+        }
+    }
+
+    Ok(Some(format!(
+        "Gave {} [{}] to {}",
+        count,
+        stack.ty.display_name(),
+        targets.entities_to_string(ctx, false)
+    )))
+}
+
+/// Adds a stack to a player's inventory. The items
+/// which don't fit are dropped by the player.
+fn give_stack(ctx: &mut CommandCtx, player: Entity, stack: ItemStack) {
+    let (slots, remaining) = ctx.world.get::<Inventory>(player).collect_item(stack);
+
+    if !slots.is_empty() {
+        ctx.game.handle(
+            &mut *ctx.world,
+            InventoryUpdateEvent {
+                entity: player,
+                slots,
+            },
+        );
+    }
+    if remaining > 0 {
+        ctx.game.handle(
+            &mut *ctx.world,
+            ItemDropEvent {
+                slot: None,
+                stack: stack.of_amount(remaining),
+                player,
+            },
+        );
+    }
+}
+
+pub fn seed(ctx: &mut CommandCtx) -> CommandResult {
     if let Some(mut message_receiver) = ctx.world.try_get_mut::<MessageReceiver>(ctx.sender) {
         message_receiver.send(
             Text::from("Seed: [")
@@ -579,6 +704,36 @@ pub fn seed(ctx: &mut CommandCtx) -> anyhow::Result<()> {
     Ok(None)
 }
 
+#[derive(Debug, Error)]
+pub enum LocateError {
+    #[error("Could not find that structure nearby")]
+    NotFound,
+}
+
+pub fn locate(ctx: &mut CommandCtx, structure: StructureArgument) -> CommandResult {
+    let pos = ctx
+        .world
+        .try_get::<Position>(ctx.sender)
+        .map(|r| *r)
+        .unwrap_or(position!(0.0, 0.0, 0.0));
+
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let found = ctx.game.worlds[world_id]
+        .generator
+        .locate_structure(structure.0, pos.block())
+        .ok_or(LocateError::NotFound)?;
+
+    let dx = f64::from(found.x) - pos.x;
+    let dz = f64::from(found.z) - pos.z;
+    Ok(Some(format!(
+        "The nearest {} is at [{}, ~, {}] ({} blocks away)",
+        structure.0.name(),
+        found.x,
+        found.z,
+        (dx * dx + dz * dz).sqrt().floor()
+    )))
+}
+
 #[derive(Debug, Error)]
 pub enum BanError {
     #[error(
@@ -589,31 +744,27 @@ pub enum BanError {
     NoTargets,
 }
 
-#[command(usage = "ban <targets> <reason>")]
 pub fn ban_withreason(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     reason: TextArgument,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     ban_players(ctx, targets, reason.0, false)
 }
 
-#[command(usage = "ban <targets>")]
-pub fn ban_noreason(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<()> {
+pub fn ban_noreason(ctx: &mut CommandCtx, targets: EntitySelector) -> CommandResult {
     ban_players(ctx, targets, "Banned by an operator.".to_owned(), false)
 }
 
-#[command(usage = "ban-ip <targets> <reason>")]
 pub fn banip_withreason(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     reason: TextArgument,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     ban_players(ctx, targets, reason.0, true)
 }
 
-#[command(usage = "ban-ip <targets>")]
-pub fn banip_noreason(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<()> {
+pub fn banip_noreason(ctx: &mut CommandCtx, targets: EntitySelector) -> CommandResult {
     ban_players(ctx, targets, "Banned by an operator.".to_owned(), true)
 }
 
@@ -623,17 +774,15 @@ pub enum BanIpError {
     InvalidIp,
 }
 
-#[command(usage = "ban-ip <ip> <reason>")]
 pub fn banip_withreason_ip(
     ctx: &mut CommandCtx,
     ip: String,
     reason: TextArgument,
-) -> anyhow::Result<()> {
+) -> CommandResult {
     ban_ip(ctx, ip, reason.0)
 }
 
-#[command(usage = "ban-ip <ip>")]
-pub fn banip_noreason_ip(ctx: &mut CommandCtx, ip: String) -> anyhow::Result<()> {
+pub fn banip_noreason_ip(ctx: &mut CommandCtx, ip: String) -> CommandResult {
     ban_ip(ctx, ip, "IP Banned by an operator.".to_string())
 }
 
@@ -751,8 +900,7 @@ pub enum PardonError {
     NotPlayer,
 }
 
-#[command(usage = "pardon <name>")]
-pub fn pardon(ctx: &mut CommandCtx, name: TextArgument) -> anyhow::Result<()> {
+pub fn pardon(ctx: &mut CommandCtx, name: TextArgument) -> CommandResult {
     // Get UUID from name
     let online_mode = ctx.game.shared.config.server.online_mode;
     let uuid = if online_mode {
@@ -794,8 +942,7 @@ pub enum PardonIpError {
     NotIp,
 }
 
-#[command(usage = "pardon-ip <ip>")]
-pub fn pardonip(ctx: &mut CommandCtx, ip: String) -> anyhow::Result<()> {
+pub fn pardonip(ctx: &mut CommandCtx, ip: String) -> CommandResult {
     // Try to parse ip
     let addr = IpAddr::from_str(&ip).map_err(|_| PardonIpError::NotIp)?;
 
@@ -816,3 +963,703 @@ pub fn pardonip(ctx: &mut CommandCtx, ip: String) -> anyhow::Result<()> {
 
     Ok(None)
 }
+
+#[derive(Debug, Error)]
+pub enum WorldBorderError {
+    #[error("The world border cannot be smaller than 1 block wide")]
+    TooSmall,
+    #[error("The world border cannot be bigger than {0} blocks wide")]
+    TooBig(f64),
+    #[error("The value must not be negative, found {0}")]
+    Negative(f64),
+}
+
+/// Modifies the border of the sender's world, sending
+/// the action returned by `update` to the world's players.
+fn update_border(
+    ctx: &mut CommandCtx,
+    update: impl FnOnce(&mut WorldBorder) -> WorldBorderAction,
+) -> WorldBorder {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let border = &mut ctx.game.worlds[world_id].border;
+    let action = update(border);
+    let border = border.clone();
+
+    ctx.game
+        .broadcast_world(&*ctx.world, WorldBorderPacket { action }, world_id, None);
+    border
+}
+
+fn resize_border(ctx: &mut CommandCtx, size: f64, seconds: i32) -> anyhow::Result<Option<String>> {
+    if size < 1.0 {
+        return Err(WorldBorderError::TooSmall.into());
+    }
+    if size > MAX_BORDER_SIZE {
+        return Err(WorldBorderError::TooBig(MAX_BORDER_SIZE).into());
+    }
+
+    let border = update_border(ctx, |border| {
+        border.lerp_size(size, seconds as u64 * 1000);
+        border.size_action()
+    });
+
+    if seconds > 0 {
+        Ok(Some(format!(
+            "Moving the world border to {:.1} blocks wide over {} seconds",
+            border.target_size(),
+            seconds
+        )))
+    } else {
+        Ok(Some(format!(
+            "Set the world border to {:.1} blocks wide",
+            border.size()
+        )))
+    }
+}
+
+pub fn worldborder_get(ctx: &mut CommandCtx) -> CommandResult {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    Ok(Some(format!(
+        "The world border is currently {:.0} blocks wide",
+        ctx.game.worlds[world_id].border.size()
+    )))
+}
+
+pub fn worldborder_set_1(ctx: &mut CommandCtx, distance: F64Argument) -> CommandResult {
+    resize_border(ctx, distance.0, 0)
+}
+
+pub fn worldborder_set_2(
+    ctx: &mut CommandCtx,
+    distance: F64Argument,
+    time: PositiveI32Argument,
+) -> CommandResult {
+    resize_border(ctx, distance.0, time.0)
+}
+
+pub fn worldborder_add_1(ctx: &mut CommandCtx, distance: F64Argument) -> CommandResult {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let size = ctx.game.worlds[world_id].border.target_size() + distance.0;
+    resize_border(ctx, size, 0)
+}
+
+pub fn worldborder_add_2(
+    ctx: &mut CommandCtx,
+    distance: F64Argument,
+    time: PositiveI32Argument,
+) -> CommandResult {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let size = ctx.game.worlds[world_id].border.target_size() + distance.0;
+    resize_border(ctx, size, time.0)
+}
+
+pub fn worldborder_center(ctx: &mut CommandCtx, x: F64Argument, z: F64Argument) -> CommandResult {
+    update_border(ctx, |border| {
+        border.center_x = x.0;
+        border.center_z = z.0;
+        WorldBorderAction::SetCenter(x.0, z.0)
+    });
+
+    Ok(Some(format!(
+        "Set the center of the world border to {:.2}, {:.2}",
+        x.0, z.0
+    )))
+}
+
+pub fn worldborder_damage_amount(ctx: &mut CommandCtx, amount: F64Argument) -> CommandResult {
+    if amount.0 < 0.0 {
+        return Err(WorldBorderError::Negative(amount.0).into());
+    }
+
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    ctx.game.worlds[world_id].border.damage_per_block = amount.0;
+
+    Ok(Some(format!(
+        "Set the world border damage to {:.2} per block",
+        amount.0
+    )))
+}
+
+pub fn worldborder_damage_buffer(ctx: &mut CommandCtx, distance: F64Argument) -> CommandResult {
+    if distance.0 < 0.0 {
+        return Err(WorldBorderError::Negative(distance.0).into());
+    }
+
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    ctx.game.worlds[world_id].border.safe_zone = distance.0;
+
+    Ok(Some(format!(
+        "Set the world border damage buffer to {:.2} blocks",
+        distance.0
+    )))
+}
+
+pub fn worldborder_warning_distance(
+    ctx: &mut CommandCtx,
+    distance: PositiveI32Argument,
+) -> CommandResult {
+    update_border(ctx, |border| {
+        border.warning_blocks = distance.0;
+        WorldBorderAction::SetWarningBlocks(distance.0)
+    });
+
+    Ok(Some(format!(
+        "Set the world border warning distance to {} blocks",
+        distance.0
+    )))
+}
+
+pub fn worldborder_warning_time(ctx: &mut CommandCtx, time: PositiveI32Argument) -> CommandResult {
+    update_border(ctx, |border| {
+        border.warning_time = time.0;
+        WorldBorderAction::SetWarningTime(time.0)
+    });
+
+    Ok(Some(format!(
+        "Set the world border warning time to {} seconds",
+        time.0
+    )))
+}
+
+/// The largest number of blocks `/structure save` copies at once.
+const MAX_STRUCTURE_VOLUME: i64 = 1 << 20;
+
+#[derive(Debug, Error)]
+pub enum StructureCommandError {
+    #[error("Structures cannot contain more than {0} blocks, found {1}")]
+    TooLarge(i64, i64),
+    #[error("Could not find structure {0}")]
+    NotFound(String),
+}
+
+/// Returns the path of a structure file in the sender's world.
+/// Names without an extension refer to structure templates.
+fn structure_path(ctx: &CommandCtx, name: &str) -> PathBuf {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let file = if name.contains('.') {
+        name.to_owned()
+    } else {
+        format!("{}.nbt", name)
+    };
+
+    ctx.game.worlds[world_id]
+        .dir
+        .join("generated")
+        .join("structures")
+        .join(file)
+}
+
+fn is_schematic(name: &str) -> bool {
+    name.ends_with(".schem")
+}
+
+fn sender_position(ctx: &CommandCtx) -> Position {
+    ctx.world
+        .try_get::<Position>(ctx.sender)
+        .map(|r| *r)
+        .unwrap_or(position!(0.0, 0.0, 0.0))
+}
+
+pub fn structure_save(
+    ctx: &mut CommandCtx,
+    name: StructureFileArgument,
+    from: Coordinates,
+    to: Coordinates,
+) -> CommandResult {
+    let relative_to = sender_position(ctx);
+    let a = from.into_position(relative_to).block();
+    let b = to.into_position(relative_to).block();
+
+    let volume = (i64::from((a.x - b.x).abs()) + 1)
+        * (i64::from((a.y - b.y).abs()) + 1)
+        * (i64::from((a.z - b.z).abs()) + 1);
+    if volume > MAX_STRUCTURE_VOLUME {
+        return Err(StructureCommandError::TooLarge(MAX_STRUCTURE_VOLUME, volume).into());
+    }
+
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let structure = export_structure(&ctx.game, &ctx.world, world_id, a, b)?;
+
+    let path = structure_path(ctx, &name.0);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(File::create(&path)?);
+    if is_schematic(&name.0) {
+        structure.write_schematic(&mut writer)?;
+    } else {
+        structure.write_template(&mut writer)?;
+    }
+    writer.flush()?;
+
+    Ok(Some(format!(
+        "Saved {} blocks to structure {}",
+        structure.blocks().len(),
+        name.0
+    )))
+}
+
+fn load_structure(
+    ctx: &mut CommandCtx,
+    name: &str,
+    location: Coordinates,
+    rotation: Rotation,
+    mirror: Mirror,
+) -> anyhow::Result<Option<String>> {
+    let path = structure_path(ctx, name);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(StructureCommandError::NotFound(name.to_owned()).into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let mut reader = BufReader::new(file);
+    let structure = if is_schematic(name) {
+        Structure::read_schematic(&mut reader)?
+    } else {
+        Structure::read_template(&mut reader)?
+    };
+    let structure = structure.transformed(mirror, rotation);
+
+    let origin = location.into_position(sender_position(ctx)).block();
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let placed = place_structure(&mut ctx.game, &mut ctx.world, world_id, &structure, origin);
+
+    Ok(Some(format!(
+        "Placed {} blocks of structure {} at {}, {}, {}",
+        placed, name, origin.x, origin.y, origin.z
+    )))
+}
+
+pub fn structure_load_1(
+    ctx: &mut CommandCtx,
+    name: StructureFileArgument,
+    location: Coordinates,
+) -> CommandResult {
+    load_structure(ctx, &name.0, location, Rotation::None, Mirror::None)
+}
+
+pub fn structure_load_2(
+    ctx: &mut CommandCtx,
+    name: StructureFileArgument,
+    location: Coordinates,
+    rotation: RotationArgument,
+    mirror: MirrorArgument,
+) -> CommandResult {
+    load_structure(ctx, &name.0, location, rotation.0, mirror.0)
+}
+
+#[derive(Debug, Error)]
+pub enum SetblockError {
+    #[error("That position is not loaded")]
+    NotLoaded,
+    #[error("Could not set the block")]
+    Unchanged,
+}
+
+pub fn setblock(
+    ctx: &mut CommandCtx,
+    location: Coordinates,
+    block: BlockStateArgument,
+) -> CommandResult {
+    let pos = location.into_position(sender_position(ctx)).block();
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    match ctx.game.block_at(world_id, pos) {
+        None => return Err(SetblockError::NotLoaded.into()),
+        Some(old) if old == block.0 => return Err(SetblockError::Unchanged.into()),
+        Some(_) => (),
+    }
+
+    ctx.game.set_block_at(
+        &mut ctx.world,
+        world_id,
+        pos,
+        block.0,
+        BlockUpdateCause::Unknown,
+    );
+
+    Ok(Some(format!(
+        "Changed the block at {}, {}, {}",
+        pos.x, pos.y, pos.z
+    )))
+}
+
+#[derive(Debug, Error)]
+pub enum PregenCommandError {
+    #[error("No chunks are being pregenerated in this world")]
+    NotRunning,
+}
+
+/// Starts pregenerating the chunks within a radius
+/// around a position in the sender's world.
+fn start_pregen(
+    ctx: &mut CommandCtx,
+    radius: i32,
+    center: Position,
+) -> anyhow::Result<Option<String>> {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let center = center.chunk();
+    let total = ctx.game.resources.get_mut::<Pregenerator>().start(
+        &ctx.game,
+        world_id,
+        center,
+        radius as u32,
+        Some(ctx.sender),
+    )?;
+
+    Ok(Some(format!(
+        "Pregenerating {} chunks around chunk {}, {}",
+        total, center.x, center.z
+    )))
+}
+
+pub fn pregen_start_1(ctx: &mut CommandCtx, radius: PositiveI32Argument) -> CommandResult {
+    let center = sender_position(ctx);
+    start_pregen(ctx, radius.0, center)
+}
+
+pub fn pregen_start_2(
+    ctx: &mut CommandCtx,
+    radius: PositiveI32Argument,
+    x: F64Argument,
+    z: F64Argument,
+) -> CommandResult {
+    start_pregen(ctx, radius.0, position!(x.0, 0.0, z.0))
+}
+
+pub fn pregen_status(ctx: &mut CommandCtx) -> CommandResult {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let progress = ctx
+        .game
+        .resources
+        .get::<Pregenerator>()
+        .progress(world_id)
+        .ok_or(PregenCommandError::NotRunning)?;
+
+    Ok(Some(format!("Pregenerating: {}", progress)))
+}
+
+pub fn pregen_cancel(ctx: &mut CommandCtx) -> CommandResult {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    let progress = ctx
+        .game
+        .resources
+        .get_mut::<Pregenerator>()
+        .cancel(world_id)
+        .ok_or(PregenCommandError::NotRunning)?;
+
+    Ok(Some(format!("Cancelled pregeneration after {}", progress)))
+}
+
+#[derive(Debug, Error)]
+pub enum XpError {
+    #[error("No player was found")]
+    NoPlayers,
+    #[error(
+        "Only players may be affected by this command, but the provided selector includes entities"
+    )]
+    NoEntities,
+    #[error("Only one player is allowed, but the provided selector allows for more than one")]
+    TooManyPlayers,
+    #[error(
+        "Cannot set experience points above the maximum points for the player's current level"
+    )]
+    TooManyPoints,
+}
+
+/// Whether an /xp command changes points or levels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum XpUnit {
+    Points,
+    Levels,
+}
+
+impl XpUnit {
+    fn name(self) -> &'static str {
+        match self {
+            XpUnit::Points => "points",
+            XpUnit::Levels => "levels",
+        }
+    }
+}
+
+pub fn xp_add_1(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: I32Argument,
+) -> CommandResult {
+    add_experience(ctx, targets, amount.0, XpUnit::Points)
+}
+
+pub fn xp_add_points(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: I32Argument,
+) -> CommandResult {
+    add_experience(ctx, targets, amount.0, XpUnit::Points)
+}
+
+pub fn xp_add_levels(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: I32Argument,
+) -> CommandResult {
+    add_experience(ctx, targets, amount.0, XpUnit::Levels)
+}
+
+pub fn xp_set_1(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: PositiveI32Argument,
+) -> CommandResult {
+    set_experience(ctx, targets, amount.0, XpUnit::Points)
+}
+
+pub fn xp_set_points(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: PositiveI32Argument,
+) -> CommandResult {
+    set_experience(ctx, targets, amount.0, XpUnit::Points)
+}
+
+pub fn xp_set_levels(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: PositiveI32Argument,
+) -> CommandResult {
+    set_experience(ctx, targets, amount.0, XpUnit::Levels)
+}
+
+pub fn xp_query_points(ctx: &mut CommandCtx, target: EntitySelector) -> CommandResult {
+    query_experience(ctx, target, XpUnit::Points)
+}
+
+pub fn xp_query_levels(ctx: &mut CommandCtx, target: EntitySelector) -> CommandResult {
+    query_experience(ctx, target, XpUnit::Levels)
+}
+
+/// Checks that all entities selected by an /xp command are players.
+fn experience_targets(ctx: &CommandCtx, targets: &EntitySelector) -> Result<(), XpError> {
+    if targets.entities.is_empty() {
+        return Err(XpError::NoPlayers);
+    }
+    if targets
+        .entities
+        .iter()
+        .any(|entity| !ctx.world.has::<Experience>(*entity))
+    {
+        return Err(XpError::NoEntities);
+    }
+    Ok(())
+}
+
+fn add_experience(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: i32,
+    unit: XpUnit,
+) -> anyhow::Result<Option<String>> {
+    experience_targets(ctx, &targets)?;
+
+    for player in &targets.entities {
+        match unit {
+            XpUnit::Points => ctx.game.give_experience(&mut ctx.world, *player, amount),
+            XpUnit::Levels => ctx
+                .game
+                .add_experience_levels(&mut ctx.world, *player, amount),
+        }
+    }
+
+    Ok(Some(format!(
+        "Gave {} experience {} to {}",
+        amount,
+        unit.name(),
+        targets.entities_to_string(ctx, true)
+    )))
+}
+
+fn set_experience(
+    ctx: &mut CommandCtx,
+    targets: EntitySelector,
+    amount: i32,
+    unit: XpUnit,
+) -> anyhow::Result<Option<String>> {
+    experience_targets(ctx, &targets)?;
+
+    let amount = amount as u32;
+    if unit == XpUnit::Points
+        && targets.entities.iter().any(|player| {
+            amount >= Experience::points_to_next_level(ctx.world.get::<Experience>(*player).level)
+        })
+    {
+        return Err(XpError::TooManyPoints.into());
+    }
+
+    for player in &targets.entities {
+        ctx.game
+            .update_experience(&mut ctx.world, *player, |experience| match unit {
+                XpUnit::Points => {
+                    experience.set_points_in_level(amount);
+                }
+                XpUnit::Levels => experience.level = amount,
+            });
+    }
+
+    Ok(Some(format!(
+        "Set {} experience {} on {}",
+        amount,
+        unit.name(),
+        targets.entities_to_string(ctx, true)
+    )))
+}
+
+fn query_experience(
+    ctx: &mut CommandCtx,
+    target: EntitySelector,
+    unit: XpUnit,
+) -> anyhow::Result<Option<String>> {
+    experience_targets(ctx, &target)?;
+    if target.entities.len() > 1 {
+        return Err(XpError::TooManyPlayers.into());
+    }
+
+    let player = target.entities[0];
+    let experience = *ctx.world.get::<Experience>(player);
+    let amount = match unit {
+        XpUnit::Points => experience.points_in_level(),
+        XpUnit::Levels => experience.level,
+    };
+
+    Ok(Some(format!(
+        "{} has {} experience {}",
+        ctx.world.get::<Name>(player).0,
+        amount,
+        unit.name()
+    )))
+}
+
+/// The longest weather `/weather` can set, in seconds.
+const MAX_WEATHER_SECONDS: i32 = 1_000_000;
+
+#[derive(Debug, Error)]
+pub enum WeatherError {
+    #[error("Duration must not be more than {}, found {}", MAX_WEATHER_SECONDS, .0)]
+    TooLong(i32),
+}
+
+pub fn weather_clear_1(ctx: &mut CommandCtx) -> CommandResult {
+    change_weather(ctx, Weather::Clear, None)
+}
+
+pub fn weather_clear_2(ctx: &mut CommandCtx, duration: PositiveI32Argument) -> CommandResult {
+    change_weather(ctx, Weather::Clear, Some(duration.0))
+}
+
+pub fn weather_rain_1(ctx: &mut CommandCtx) -> CommandResult {
+    change_weather(ctx, Weather::Rain, None)
+}
+
+pub fn weather_rain_2(ctx: &mut CommandCtx, duration: PositiveI32Argument) -> CommandResult {
+    change_weather(ctx, Weather::Rain, Some(duration.0))
+}
+
+pub fn weather_thunder_1(ctx: &mut CommandCtx) -> CommandResult {
+    change_weather(ctx, Weather::Thunder, None)
+}
+
+pub fn weather_thunder_2(ctx: &mut CommandCtx, duration: PositiveI32Argument) -> CommandResult {
+    change_weather(ctx, Weather::Thunder, Some(duration.0))
+}
+
+/// Sets the weather for the given number of seconds,
+/// or for 5 to 15 minutes if none is given.
+fn change_weather(
+    ctx: &mut CommandCtx,
+    weather: Weather,
+    seconds: Option<i32>,
+) -> anyhow::Result<Option<String>> {
+    let seconds = match seconds {
+        Some(seconds) if seconds > MAX_WEATHER_SECONDS => {
+            return Err(WeatherError::TooLong(seconds).into())
+        }
+        Some(seconds) => seconds,
+        None => 300 + ctx.game.rng().gen_range(0, 600),
+    };
+    ctx.game.set_weather(&mut ctx.world, weather, seconds * 20);
+
+    let name = match weather {
+        Weather::Clear => "clear",
+        Weather::Rain => "rain",
+        Weather::Thunder => "rain & thunder",
+    };
+    Ok(Some(format!("Changing to {}", name)))
+}
+
+pub fn time_set_day(ctx: &mut CommandCtx) -> CommandResult {
+    set_time(ctx, 1000)
+}
+
+pub fn time_set_noon(ctx: &mut CommandCtx) -> CommandResult {
+    set_time(ctx, 6000)
+}
+
+pub fn time_set_night(ctx: &mut CommandCtx) -> CommandResult {
+    set_time(ctx, 13_000)
+}
+
+pub fn time_set_midnight(ctx: &mut CommandCtx) -> CommandResult {
+    set_time(ctx, 18_000)
+}
+
+pub fn time_set(ctx: &mut CommandCtx, time: TimeArgument) -> CommandResult {
+    set_time(ctx, time.0 as u64)
+}
+
+pub fn time_add(ctx: &mut CommandCtx, time: TimeArgument) -> CommandResult {
+    for state in ctx.game.worlds.iter_mut() {
+        state.time.day_time += time.0 as u64;
+    }
+    broadcast_time(&*ctx.game, &*ctx.world);
+
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    Ok(Some(format!(
+        "Set the time to {}",
+        ctx.game.worlds[world_id].time.time_of_day()
+    )))
+}
+
+pub fn time_query_daytime(ctx: &mut CommandCtx) -> CommandResult {
+    query_time(ctx, |time| time.time_of_day())
+}
+
+pub fn time_query_gametime(ctx: &mut CommandCtx) -> CommandResult {
+    query_time(ctx, |time| time.world_age())
+}
+
+pub fn time_query_day(ctx: &mut CommandCtx) -> CommandResult {
+    query_time(ctx, |time| time.day())
+}
+
+/// Sets the time of day in all worlds, keeping
+/// the number of days which have passed.
+fn set_time(ctx: &mut CommandCtx, time_of_day: u64) -> anyhow::Result<Option<String>> {
+    for state in ctx.game.worlds.iter_mut() {
+        state.time.set_time_of_day(time_of_day);
+    }
+    broadcast_time(&*ctx.game, &*ctx.world);
+
+    Ok(Some(format!("Set the time to {}", time_of_day)))
+}
+
+fn query_time(
+    ctx: &mut CommandCtx,
+    value: impl FnOnce(Time) -> u64,
+) -> anyhow::Result<Option<String>> {
+    let world_id = ctx.game.world_of(&*ctx.world, ctx.sender);
+    Ok(Some(format!(
+        "The time is {}",
+        value(ctx.game.worlds[world_id].time)
+    )))
+}
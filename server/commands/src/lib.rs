@@ -1,24 +1,30 @@
 //! Implements the Feather command dispatching framework,
-//! based on our `lieutenant` library (a Rust fork
-//! of Mojang's [brigadier](https://github.com/Mojang/brigadier).
+//! a tree of commands in the style of Mojang's
+//! [brigadier](https://github.com/Mojang/brigadier), whose
+//! arguments are parsed by our `lieutenant` library.
 //!
 //! Also implements vanilla commands not defined by plugins.
 
 mod arguments;
 mod impls;
+mod suggestions;
+mod tree;
 
+use arguments::*;
+use feather_core::network::packets::DeclareCommands;
 use feather_core::text::{Text, TextComponentBuilder};
 use feather_server_types::{Game, MessageReceiver};
 use fecs::{Entity, World};
 use impls::*;
-use lieutenant::CommandDispatcher;
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+
+pub use suggestions::{SuggestionProvider, Suggestions};
+pub use tree::{ArgumentDeclaration, CommandFn, CommandResult, CommandTree, DeclaredArgument};
 
 /// Dumb workaround for a certain lifetime issue.
 ///
-/// `CommandCtx` stores references to `Game`, and it
-/// is used as the `C` parameter for `CommandDispatcher`,
+/// `CommandCtx` stores references to `Game`, and it is
+/// the parameter of the commands stored in `CommandTree`.
 /// This combination of lifetimes and storage in structs
 /// prevents a lifetime-based `CommandCtx` from being stored
 /// in `CommandState` without adding a lifetime parameter to `CommandState`.
@@ -71,17 +77,9 @@ impl lieutenant::Context for CommandCtx {
     type Ok = Option<String>;
 }
 
-macro_rules! commands {
-    ($dispatcher:ident : $($command:expr,)*) => {
-        $(
-            $dispatcher.register($command).unwrap();
-        )*
-    }
-}
-
 /// State storing all registered commands.
 pub struct CommandState {
-    dispatcher: Arc<CommandDispatcher<CommandCtx>>,
+    tree: CommandTree,
 }
 
 impl Default for CommandState {
@@ -93,48 +91,118 @@ impl Default for CommandState {
 impl CommandState {
     /// Initializes the command state.
     pub fn new() -> Self {
-        let mut dispatcher = CommandDispatcher::<CommandCtx>::new();
+        let mut tree = CommandTree::default();
+        tree.register("tp|teleport <destination>", tp_1);
+        tree.register("tp|teleport <location>", tp_2);
+        tree.register("tp|teleport <targets> <location>", tp_3);
+        tree.register("tp|teleport <targets> <destination>", tp_4);
 
-        commands! {
-            dispatcher:
-                tp_1,
-                tp_2,
-                tp_3,
-                tp_4,
+        tree.register("gamemode <gamemode>", gamemode_1);
+        tree.register("gamemode <gamemode> <target>", gamemode_2);
 
-                gamemode_1,
-                gamemode_2,
+        tree.register("tell|msg|w <target> <message>", whisper);
+        tree.register("say <message>", say);
+        tree.register("me <action>", me);
 
-                whisper,
-                say,
-                me,
+        tree.register("kick <targets>", kick_1);
+        tree.register("kick <targets> <reason>", kick_2);
 
-                kick_1,
-                kick_2,
+        tree.register("stop", stop);
 
-                stop,
+        tree.register("clear", clear_1);
+        tree.register("clear <targets>", clear_2);
+        tree.register("clear <targets> <item>", clear_3);
+        tree.register("clear <targets> <item> <maxcount>", clear_4);
 
-                clear_1,
-                clear_2,
-                clear_3,
-                clear_4,
+        tree.register("give <targets> <item>", give_1);
+        tree.register("give <targets> <item> <count>", give_2);
 
-                seed,
+        tree.register("seed", seed);
 
-                ban_withreason,
-                ban_noreason,
-                banip_withreason,
-                banip_noreason,
-                banip_withreason_ip,
-                banip_noreason_ip,
+        tree.register("locate <structure>", locate);
 
-                pardon,
-                pardonip,
-        }
+        tree.register("ban <targets> <reason>", ban_withreason);
+        tree.register("ban <targets>", ban_noreason);
+        tree.register("ban-ip <targets> <reason>", banip_withreason);
+        tree.register("ban-ip <targets>", banip_noreason);
+        tree.register("ban-ip <ip> <reason>", banip_withreason_ip);
+        tree.register("ban-ip <ip>", banip_noreason_ip);
 
-        Self {
-            dispatcher: Arc::new(dispatcher),
-        }
+        tree.register("pardon <name>", pardon);
+        tree.register("pardon-ip <ip>", pardonip);
+
+        tree.register("worldborder get", worldborder_get);
+        tree.register("worldborder set <distance>", worldborder_set_1);
+        tree.register("worldborder set <distance> <time>", worldborder_set_2);
+        tree.register("worldborder add <distance>", worldborder_add_1);
+        tree.register("worldborder add <distance> <time>", worldborder_add_2);
+        tree.register("worldborder center <x> <z>", worldborder_center);
+        tree.register(
+            "worldborder damage amount <amount>",
+            worldborder_damage_amount,
+        );
+        tree.register(
+            "worldborder damage buffer <distance>",
+            worldborder_damage_buffer,
+        );
+        tree.register(
+            "worldborder warning distance <distance>",
+            worldborder_warning_distance,
+        );
+        tree.register("worldborder warning time <time>", worldborder_warning_time);
+
+        tree.register("structure save <name> <from> <to>", structure_save);
+        tree.register("structure load <name> <location>", structure_load_1);
+        tree.register(
+            "structure load <name> <location> <rotation> <mirror>",
+            structure_load_2,
+        );
+
+        tree.register("setblock <location> <block>", setblock);
+
+        tree.register("pregen start <radius>", pregen_start_1);
+        tree.register("pregen start <radius> <x> <z>", pregen_start_2);
+        tree.register("pregen status", pregen_status);
+        tree.register("pregen cancel", pregen_cancel);
+
+        tree.register("xp|experience add <targets> <amount>", xp_add_1);
+        tree.register("xp|experience add <targets> <amount> points", xp_add_points);
+        tree.register("xp|experience add <targets> <amount> levels", xp_add_levels);
+        tree.register("xp|experience set <targets> <amount>", xp_set_1);
+        tree.register("xp|experience set <targets> <amount> points", xp_set_points);
+        tree.register("xp|experience set <targets> <amount> levels", xp_set_levels);
+        tree.register("xp|experience query <target> points", xp_query_points);
+        tree.register("xp|experience query <target> levels", xp_query_levels);
+
+        tree.register("weather clear", weather_clear_1);
+        tree.register("weather clear <duration>", weather_clear_2);
+        tree.register("weather rain", weather_rain_1);
+        tree.register("weather rain <duration>", weather_rain_2);
+        tree.register("weather thunder", weather_thunder_1);
+        tree.register("weather thunder <duration>", weather_thunder_2);
+
+        tree.register("time set day", time_set_day);
+        tree.register("time set noon", time_set_noon);
+        tree.register("time set night", time_set_night);
+        tree.register("time set midnight", time_set_midnight);
+        tree.register("time set <time>", time_set);
+        tree.register("time add <time>", time_add);
+        tree.register("time query daytime", time_query_daytime);
+        tree.register("time query gametime", time_query_gametime);
+        tree.register("time query day", time_query_day);
+
+        Self { tree }
+    }
+
+    /// Returns the packet declaring the registered commands to clients.
+    pub fn declare_commands(&self) -> DeclareCommands {
+        self.tree.declare_commands()
+    }
+
+    /// Returns suggestions completing the last word
+    /// of a partially typed command (without the slash).
+    pub fn suggest(&self, world: &World, command: &str) -> Suggestions {
+        self.tree.suggest(world, command)
     }
 
     /// Dispatches a command.
@@ -145,26 +213,53 @@ impl CommandState {
             sender,
         };
 
-        match self.dispatcher.dispatch(&mut ctx, command) {
-            Ok(ok) => {
-                if let Some(msg) = ok {
-                    if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(sender) {
-                        receiver.send(Text::from(msg));
-                    }
-                }
-            }
-
-            Err(errs) => {
-                let msg = if let Some(last) = errs.last() {
-                    Text::from(last.to_string()).red()
-                } else {
-                    Text::from("Unknown command.")
-                };
-
-                if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(sender) {
-                    receiver.send(msg);
-                }
-            }
+        let msg = match self.tree.dispatch(&mut ctx, command) {
+            Ok(Some(msg)) => Text::from(msg),
+            Ok(None) => return,
+            Err(e) => Text::from(e.to_string()).red(),
+        };
+
+        if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(sender) {
+            receiver.send(msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::network::packets::{ArgumentParser, CommandNodeKind};
+
+    #[test]
+    fn arguments_declared_with_parsers_of_their_types() {
+        let declared = CommandState::new().declare_commands();
+        let parsers: Vec<(&str, ArgumentParser)> = declared
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.kind {
+                CommandNodeKind::Argument { name, parser, .. } => Some((name.as_str(), *parser)),
+                _ => None,
+            })
+            .collect();
+
+        for expected in &[
+            ("targets", ArgumentParser::Entity(0)),
+            ("location", ArgumentParser::Vec3),
+            ("from", ArgumentParser::Vec3),
+            ("count", ArgumentParser::Integer(Some(0))),
+            ("amount", ArgumentParser::Integer(None)),
+            ("amount", ArgumentParser::Double),
+            ("distance", ArgumentParser::Double),
+            ("x", ArgumentParser::Double),
+            ("duration", ArgumentParser::Integer(Some(0))),
+            ("time", ArgumentParser::Word),
+            ("name", ArgumentParser::GreedyPhrase),
+            ("name", ArgumentParser::Word),
+            ("message", ArgumentParser::GreedyPhrase),
+            ("item", ArgumentParser::ItemStack),
+            ("block", ArgumentParser::BlockState),
+        ] {
+            assert!(parsers.contains(expected), "{:?} not declared", expected);
         }
     }
 }
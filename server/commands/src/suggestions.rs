@@ -0,0 +1,98 @@
+//! Suggestions completing the commands players type,
+//! sent in response to their tab completion requests.
+//!
+//! Literal nodes of the command tree suggest themselves,
+//! while argument nodes suggest the values given by the
+//! `SuggestionProvider` of their argument type.
+
+use feather_core::blocks::BlockId;
+use feather_definitions::Item;
+use feather_server_types::{Name, Player};
+use feather_server_worldgen::StructureKind;
+use fecs::{component, IntoQuery, Read, World};
+use std::collections::BTreeSet;
+
+/// Entity selectors suggested along with the names of players.
+const SELECTORS: [&str; 5] = ["@a", "@e", "@p", "@r", "@s"];
+
+/// Suggestions for the word a player is typing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Suggestions {
+    /// Index into the command of the first character of the word.
+    pub start: usize,
+    /// Length of the word typed so far.
+    pub length: usize,
+    /// The values which may replace the word, in alphabetical order.
+    pub matches: Vec<String>,
+}
+
+/// A source of suggestions for the values of an argument.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SuggestionProvider {
+    /// Entity selectors and the names of online players.
+    Entities,
+    Gamemodes,
+    Items,
+    Blocks,
+    Structures,
+    Rotations,
+    Mirrors,
+    /// Coordinates relative to the sender, `~ ~ ~`.
+    Coordinates,
+}
+
+impl SuggestionProvider {
+    /// Returns the values suggested for the word at
+    /// the given index into the argument.
+    pub fn values(self, world: &World, index: usize) -> Vec<String> {
+        match self {
+            SuggestionProvider::Entities => to_strings(&SELECTORS)
+                .into_iter()
+                .chain(
+                    <Read<Name>>::query()
+                        .filter(component::<Player>())
+                        .iter(world.inner())
+                        .map(|name| name.0.clone()),
+                )
+                .collect(),
+            SuggestionProvider::Gamemodes => {
+                to_strings(&["adventure", "creative", "spectator", "survival"])
+            }
+            SuggestionProvider::Items => (0..)
+                .map(Item::from_vanilla_id)
+                .take_while(Option::is_some)
+                .flatten()
+                .map(|item| item.identifier().to_owned())
+                .collect(),
+            SuggestionProvider::Blocks => BlockId::all()
+                .map(BlockId::identifier)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect(),
+            SuggestionProvider::Structures => StructureKind::values()
+                .iter()
+                .map(|kind| kind.name().to_owned())
+                .collect(),
+            SuggestionProvider::Rotations => {
+                to_strings(&["none", "clockwise_90", "180", "counterclockwise_90"])
+            }
+            SuggestionProvider::Mirrors => to_strings(&["none", "left_right", "front_back"]),
+            SuggestionProvider::Coordinates => vec![vec!["~"; 3 - index].join(" ")],
+        }
+    }
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+/// Returns whether a suggested value completes the given word.
+/// Identifiers in the `minecraft` namespace also complete
+/// words without a namespace.
+pub(crate) fn completes(value: &str, word: &str) -> bool {
+    value.starts_with(word)
+        || value
+            .strip_prefix("minecraft:")
+            .map_or(false, |path| path.starts_with(word))
+}
@@ -0,0 +1,510 @@
+//! The tree of registered commands, which dispatches the
+//! commands players type, suggests completions for them
+//! and is declared to clients.
+//!
+//! Each command is registered with its usage, e.g.
+//! `tp|teleport <targets> <location>`, and the function
+//! implementing it. The words of the usage form a path
+//! of nodes: literals, which match themselves, and
+//! arguments, whose parser and suggestions are given
+//! by the type of the matching function parameter.
+
+use crate::suggestions::{completes, SuggestionProvider, Suggestions};
+use crate::CommandCtx;
+use feather_core::network::packets::{
+    ArgumentParser, CommandNode, CommandNodeKind, DeclareCommands,
+};
+use fecs::World;
+use lieutenant::{ArgumentKind, Input};
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// Suggestions type of arguments whose
+/// suggestions clients request from the server.
+const ASK_SERVER: &str = "minecraft:ask_server";
+
+/// The result of running a command: a message
+/// for the sender, if any, or an error.
+pub type CommandResult = anyhow::Result<Option<String>>;
+
+/// An argument type of commands, as declared to clients.
+pub trait DeclaredArgument {
+    /// The parser clients use for the argument.
+    const PARSER: ArgumentParser = ArgumentParser::Word;
+    /// The source of suggestions for values of the argument.
+    const SUGGESTIONS: Option<SuggestionProvider> = None;
+}
+
+/// The parser and suggestions of an argument in the usage of a command.
+pub type ArgumentDeclaration = (ArgumentParser, Option<SuggestionProvider>);
+
+/// A function implementing a command, taking the command
+/// context followed by arguments of the types in `A`.
+pub trait CommandFn<A>: Send + Sync + 'static {
+    /// Returns the declarations of the arguments, in order.
+    fn arguments() -> Vec<ArgumentDeclaration>;
+
+    /// Parses the given values of the arguments and runs the command.
+    /// Returns `Err` without running it if a value does not parse.
+    fn run(&self, ctx: &mut CommandCtx, values: &[String]) -> anyhow::Result<CommandResult>;
+}
+
+macro_rules! impl_command_fn {
+    ($($argument:ident),*) => {
+        impl<F, $($argument),*> CommandFn<($($argument,)*)> for F
+        where
+            F: Fn(&mut CommandCtx, $($argument),*) -> CommandResult + Send + Sync + 'static,
+            $(
+                $argument: ArgumentKind<CommandCtx> + DeclaredArgument,
+                <$argument as ArgumentKind<CommandCtx>>::ParseError:
+                    std::error::Error + Send + Sync + 'static,
+            )*
+        {
+            fn arguments() -> Vec<ArgumentDeclaration> {
+                vec![$(($argument::PARSER, $argument::SUGGESTIONS)),*]
+            }
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn run(&self, ctx: &mut CommandCtx, values: &[String]) -> anyhow::Result<CommandResult> {
+                let mut values = values.iter();
+                $(
+                    let value = values.next().expect("missing argument value");
+                    let $argument = $argument::parse(ctx, &mut Input::new(value))?;
+                )*
+                Ok(self(ctx, $($argument),*))
+            }
+        }
+    };
+}
+
+impl_command_fn!();
+impl_command_fn!(A);
+impl_command_fn!(A, B);
+impl_command_fn!(A, B, C);
+impl_command_fn!(A, B, C, D);
+
+/// A registered command, taking the values of its arguments.
+type Executor =
+    Box<dyn Fn(&mut CommandCtx, &[String]) -> anyhow::Result<CommandResult> + Send + Sync>;
+
+#[derive(Debug, Error)]
+#[error("Unknown or incomplete command {0}")]
+pub struct UnknownCommand(String);
+
+#[derive(Debug, PartialEq, Eq)]
+enum NodeKind {
+    /// A literal word and its aliases, e.g. `tp|teleport`.
+    Literal(Vec<String>),
+    /// An argument, e.g. `<targets>`.
+    Argument(String, ArgumentParser, Option<SuggestionProvider>),
+}
+
+impl NodeKind {
+    /// Parses a word of the usage of a command, taking the
+    /// declaration of arguments from the given iterator.
+    fn parse(word: &str, arguments: &mut impl Iterator<Item = ArgumentDeclaration>) -> Self {
+        if word.starts_with('<') && word.ends_with('>') {
+            let name = &word[1..word.len() - 1];
+            let (parser, provider) = arguments
+                .next()
+                .unwrap_or_else(|| panic!("no parameter for argument <{}>", name));
+            NodeKind::Argument(name.to_owned(), parser, provider)
+        } else {
+            NodeKind::Literal(word.split('|').map(ToOwned::to_owned).collect())
+        }
+    }
+
+    /// Returns the number of words taken by the node,
+    /// or `None` if it takes the rest of the command.
+    fn words(&self) -> Option<usize> {
+        match self {
+            NodeKind::Argument(_, ArgumentParser::GreedyPhrase, _) => None,
+            NodeKind::Argument(_, ArgumentParser::Vec3, _) => Some(3),
+            _ => Some(1),
+        }
+    }
+
+    /// Returns whether the node accepts the given words.
+    /// Arguments accept any values, which are parsed
+    /// only once the whole command has matched.
+    fn accepts(&self, words: &[&str]) -> bool {
+        match self {
+            NodeKind::Literal(aliases) => aliases.iter().any(|alias| alias == words[0]),
+            NodeKind::Argument(..) => true,
+        }
+    }
+}
+
+struct Node {
+    kind: NodeKind,
+    /// The command ending at this node, if any.
+    executor: Option<Executor>,
+    children: Vec<Node>,
+}
+
+/// Tree of the registered commands.
+#[derive(Default)]
+pub struct CommandTree {
+    nodes: Vec<Node>,
+}
+
+impl CommandTree {
+    /// Registers a command with the given usage, e.g.
+    /// `tp|teleport <targets> <location>`. The arguments of the
+    /// usage are declared with the types of the parameters of
+    /// `command`, in order.
+    pub fn register<A, F>(&mut self, usage: &str, command: F) -> &mut Self
+    where
+        A: 'static,
+        F: CommandFn<A>,
+    {
+        let arguments = F::arguments();
+        let mut arguments = arguments.iter().copied();
+        let mut kinds = usage
+            .split(' ')
+            .map(|word| NodeKind::parse(word, &mut arguments))
+            .collect::<Vec<_>>()
+            .into_iter();
+        assert!(
+            arguments.next().is_none(),
+            "more parameters than arguments in `{}`",
+            usage
+        );
+
+        let mut node = child(&mut self.nodes, kinds.next().expect("empty usage"));
+        for kind in kinds {
+            node = child(&mut node.children, kind);
+        }
+        assert!(
+            node.executor.is_none(),
+            "command `{}` registered twice",
+            usage
+        );
+        node.executor = Some(Box::new(move |ctx: &mut CommandCtx, values: &[String]| {
+            command.run(ctx, values)
+        }));
+        self
+    }
+
+    /// Runs a command (without the slash).
+    ///
+    /// Several registered commands may match the words of the
+    /// command, e.g. `ban-ip <targets>` and `ban-ip <ip>`. They
+    /// are tried in the order they were registered, and the
+    /// first whose arguments parse is run. Otherwise, the error
+    /// of the last one is returned.
+    pub fn dispatch(&self, ctx: &mut CommandCtx, command: &str) -> CommandResult {
+        let mut error = None;
+        for (executor, values) in self.resolve(command) {
+            match executor(ctx, &values) {
+                Ok(result) => return result,
+                Err(e) => error = Some(e),
+            }
+        }
+        Err(error.unwrap_or_else(|| UnknownCommand(command.to_owned()).into()))
+    }
+
+    /// Returns the commands matching the words of a command,
+    /// along with the values of their arguments.
+    fn resolve(&self, command: &str) -> Vec<(&Executor, Vec<String>)> {
+        let words: Vec<&str> = command.split(' ').collect();
+        let mut resolved = vec![];
+        resolve(&self.nodes, &words, &mut vec![], &mut resolved);
+        resolved
+    }
+
+    /// Returns the packet declaring the commands in the tree to clients.
+    pub fn declare_commands(&self) -> DeclareCommands {
+        let mut nodes = vec![];
+        let children = declare(&self.nodes, &mut nodes);
+        nodes.push(CommandNode {
+            kind: CommandNodeKind::Root,
+            executable: false,
+            children,
+            redirect: None,
+        });
+
+        DeclareCommands {
+            root_index: nodes.len() as i32 - 1,
+            nodes,
+        }
+    }
+
+    /// Returns suggestions completing the last word
+    /// of a partially typed command (without the slash).
+    pub fn suggest(&self, world: &World, command: &str) -> Suggestions {
+        let start = command.rfind(' ').map_or(0, |index| index + 1);
+        let words: Vec<&str> = command.split(' ').collect();
+
+        let mut matches = BTreeSet::new();
+        suggest(&self.nodes, world, &words, &mut matches);
+
+        Suggestions {
+            start,
+            length: command.len() - start,
+            matches: matches.into_iter().collect(),
+        }
+    }
+}
+
+/// Returns the node of the given kind among `nodes`, adding it if needed.
+fn child(nodes: &mut Vec<Node>, kind: NodeKind) -> &mut Node {
+    let index = match nodes.iter().position(|node| node.kind == kind) {
+        Some(index) => index,
+        None => {
+            nodes.push(Node {
+                kind,
+                executor: None,
+                children: vec![],
+            });
+            nodes.len() - 1
+        }
+    };
+    &mut nodes[index]
+}
+
+fn resolve<'a>(
+    nodes: &'a [Node],
+    words: &[&str],
+    values: &mut Vec<String>,
+    resolved: &mut Vec<(&'a Executor, Vec<String>)>,
+) {
+    for node in nodes {
+        let width = node.kind.words().unwrap_or_else(|| words.len().max(1));
+        if words.len() < width || !node.kind.accepts(&words[..width]) {
+            continue;
+        }
+
+        let is_argument = matches!(node.kind, NodeKind::Argument(..));
+        if is_argument {
+            values.push(words[..width].join(" "));
+        }
+        if words.len() == width {
+            if let Some(executor) = &node.executor {
+                resolved.push((executor, values.clone()));
+            }
+        } else {
+            resolve(&node.children, &words[width..], values, resolved);
+        }
+        if is_argument {
+            values.pop();
+        }
+    }
+}
+
+fn suggest(nodes: &[Node], world: &World, words: &[&str], matches: &mut BTreeSet<String>) {
+    let word = words[words.len() - 1];
+    for node in nodes {
+        // The rest of the command belongs to greedy arguments.
+        let width = node.kind.words().unwrap_or(words.len());
+        if words.len() > width {
+            if node.kind.accepts(&words[..width]) {
+                suggest(&node.children, world, &words[width..], matches);
+            }
+            continue;
+        }
+
+        // The word being typed belongs to this node.
+        match &node.kind {
+            NodeKind::Literal(aliases) => matches.extend(
+                aliases
+                    .iter()
+                    .filter(|alias| alias.starts_with(word))
+                    .cloned(),
+            ),
+            NodeKind::Argument(_, _, Some(provider)) => matches.extend(
+                provider
+                    .values(world, words.len() - 1)
+                    .into_iter()
+                    .filter(|value| completes(value, word)),
+            ),
+            NodeKind::Argument(_, _, None) => (),
+        }
+    }
+}
+
+/// Adds the given nodes and their children to the declared nodes.
+/// Returns the indices of the declared nodes, with a node for
+/// each alias of a literal.
+fn declare(nodes: &[Node], declared: &mut Vec<CommandNode>) -> Vec<i32> {
+    let mut indices = vec![];
+    for node in nodes {
+        let children = declare(&node.children, declared);
+        let kinds: Vec<CommandNodeKind> = match &node.kind {
+            NodeKind::Literal(aliases) => aliases
+                .iter()
+                .cloned()
+                .map(CommandNodeKind::Literal)
+                .collect(),
+            NodeKind::Argument(name, parser, provider) => {
+                vec![CommandNodeKind::Argument {
+                    name: name.clone(),
+                    parser: *parser,
+                    suggestions: provider.map(|_| ASK_SERVER.to_owned()),
+                }]
+            }
+        };
+
+        for kind in kinds {
+            indices.push(declared.len() as i32);
+            declared.push(CommandNode {
+                kind,
+                executable: node.executor.is_some(),
+                children: children.clone(),
+                redirect: None,
+            });
+        }
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arguments::{Coordinates, EntitySelector, PositiveI32Argument, TextArgument};
+
+    fn without_arguments(_ctx: &mut CommandCtx) -> CommandResult {
+        Ok(None)
+    }
+
+    fn with_target(_ctx: &mut CommandCtx, _target: EntitySelector) -> CommandResult {
+        Ok(None)
+    }
+
+    fn with_location(_ctx: &mut CommandCtx, _location: Coordinates) -> CommandResult {
+        Ok(None)
+    }
+
+    fn with_count(
+        _ctx: &mut CommandCtx,
+        _target: EntitySelector,
+        _count: PositiveI32Argument,
+    ) -> CommandResult {
+        Ok(None)
+    }
+
+    fn with_message(_ctx: &mut CommandCtx, _message: TextArgument) -> CommandResult {
+        Ok(None)
+    }
+
+    fn tree() -> CommandTree {
+        let mut tree = CommandTree::default();
+        tree.register("tp|teleport <destination>", with_target)
+            .register("tp|teleport <location>", with_location)
+            .register("give <targets> <count>", with_count)
+            .register("say <message>", with_message)
+            .register("seed", without_arguments);
+        tree
+    }
+
+    /// Returns the child of a declared node with the given name.
+    fn child<'a>(declared: &'a DeclareCommands, node: &CommandNode, name: &str) -> &'a CommandNode {
+        node.children
+            .iter()
+            .map(|&index| &declared.nodes[index as usize])
+            .find(|child| match &child.kind {
+                CommandNodeKind::Literal(literal) => literal == name,
+                CommandNodeKind::Argument { name: argument, .. } => argument == name,
+                CommandNodeKind::Root => false,
+            })
+            .unwrap_or_else(|| panic!("no node {}", name))
+    }
+
+    #[test]
+    fn declared_tree() {
+        let declared = tree().declare_commands();
+        let root = &declared.nodes[declared.root_index as usize];
+        assert_eq!(root.kind, CommandNodeKind::Root);
+        assert_eq!(root.children.len(), 5);
+
+        let tp = child(&declared, root, "tp");
+        assert!(!tp.executable);
+        assert_eq!(tp.children, child(&declared, root, "teleport").children);
+        let destination = child(&declared, tp, "destination");
+        assert!(destination.executable);
+        assert_eq!(
+            destination.kind,
+            CommandNodeKind::Argument {
+                name: "destination".to_owned(),
+                parser: ArgumentParser::Entity(0),
+                suggestions: Some(ASK_SERVER.to_owned()),
+            }
+        );
+        match &child(&declared, tp, "location").kind {
+            CommandNodeKind::Argument { parser, .. } => assert_eq!(*parser, ArgumentParser::Vec3),
+            kind => panic!("expected an argument, got {:?}", kind),
+        }
+
+        let targets = child(&declared, child(&declared, root, "give"), "targets");
+        assert!(!targets.executable);
+        let count = child(&declared, targets, "count");
+        assert!(count.executable);
+        assert_eq!(
+            count.kind,
+            CommandNodeKind::Argument {
+                name: "count".to_owned(),
+                parser: ArgumentParser::Integer(Some(0)),
+                suggestions: None,
+            }
+        );
+
+        assert!(child(&declared, root, "seed").executable);
+    }
+
+    #[test]
+    fn resolved_arguments() {
+        let tree = tree();
+        let resolve = |command: &str| -> Vec<Vec<String>> {
+            tree.resolve(command)
+                .into_iter()
+                .map(|(_, values)| values)
+                .collect()
+        };
+
+        assert_eq!(resolve("tp Steve"), vec![vec!["Steve"]]);
+        assert_eq!(resolve("teleport Steve"), vec![vec!["Steve"]]);
+        assert_eq!(resolve("tp ~ 64 ~-1"), vec![vec!["~ 64 ~-1"]]);
+        assert_eq!(resolve("give Steve 5"), vec![vec!["Steve", "5"]]);
+        assert_eq!(resolve("say hello there"), vec![vec!["hello there"]]);
+        assert_eq!(resolve("seed"), vec![Vec::<String>::new()]);
+
+        assert!(resolve("tp").is_empty());
+        assert!(resolve("tp a b").is_empty());
+        assert!(resolve("give Steve").is_empty());
+        assert!(resolve("seed 1").is_empty());
+        assert!(resolve("unknown").is_empty());
+    }
+
+    #[test]
+    fn suggestions() {
+        let tree = tree();
+        let world = World::new();
+
+        let suggestions = tree.suggest(&world, "t");
+        assert_eq!((suggestions.start, suggestions.length), (0, 1));
+        assert_eq!(suggestions.matches, vec!["teleport", "tp"]);
+
+        let suggestions = tree.suggest(&world, "tp @");
+        assert_eq!((suggestions.start, suggestions.length), (3, 1));
+        assert_eq!(suggestions.matches, vec!["@a", "@e", "@p", "@r", "@s"]);
+
+        // Each coordinate completes the rest.
+        assert_eq!(tree.suggest(&world, "tp ").matches.last().unwrap(), "~ ~ ~");
+        assert_eq!(tree.suggest(&world, "tp 1 ").matches, vec!["~ ~"]);
+
+        assert!(tree.suggest(&world, "give Steve ").matches.is_empty());
+        assert!(tree.suggest(&world, "say hello t").matches.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no parameter for argument <count>")]
+    fn arguments_without_parameters() {
+        CommandTree::default().register("give <targets> <count>", with_target);
+    }
+
+    #[test]
+    #[should_panic(expected = "more parameters than arguments")]
+    fn parameters_without_arguments() {
+        CommandTree::default().register("give <targets>", with_count);
+    }
+}
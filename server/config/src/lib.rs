@@ -21,6 +21,9 @@ pub struct Config {
     pub log: Log,
     pub resource_pack: ResourcePack,
     pub world: World,
+    pub rcon: Rcon,
+    pub query: Query,
+    pub rate_limit: RateLimit,
 }
 
 impl Config {
@@ -41,6 +44,27 @@ impl Config {
         toml::to_string_pretty(self).expect("failed to serialize config")
     }
 
+    /// Returns the addresses the server listens on: `server.address`
+    /// and `server.port`, followed by `server.listeners`.
+    pub fn listeners(&self) -> Vec<Listener> {
+        let mut listeners = vec![Listener::new(self.server.address.clone(), self.server.port)];
+        listeners.extend(self.server.listeners.iter().cloned());
+        listeners
+    }
+
+    /// Returns the configuration of connections accepted
+    /// by the given listener, with its overrides applied.
+    pub fn for_listener(&self, listener: &Listener) -> Config {
+        let mut config = self.clone();
+        if let Some(online_mode) = listener.online_mode {
+            config.server.online_mode = online_mode;
+        }
+        if let Some(proxy_protocol) = listener.proxy_protocol {
+            config.proxy.proxy_protocol = proxy_protocol;
+        }
+        config
+    }
+
     /// Saves the configuration to the given file.
     pub async fn save_to_file(&self, f: &mut File) -> anyhow::Result<()> {
         let string = self.save();
@@ -60,11 +84,20 @@ impl Default for Config {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct IO {
     pub compression_threshold: i32,
+    pub compression_level: u32,
+    #[serde(with = "humantime_serde")]
+    pub keepalive_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    #[serde(with = "humantime_serde")]
+    pub join_timeout: Duration,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Proxy {
     pub proxy_mode: ProxyMode,
+    pub velocity_secret: String,
+    pub proxy_protocol: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -72,10 +105,39 @@ pub struct Server {
     pub online_mode: bool,
     pub motd: String,
     pub max_players: i32,
+    pub player_sample_size: usize,
     pub view_distance: u8,
     pub address: String,
     pub port: u16,
     pub default_gamemode: Gamemode,
+    /// Addresses to listen on in addition to `address` and `port`.
+    #[serde(default)]
+    pub listeners: Vec<Listener>,
+}
+
+/// An additional address the server listens on, which may
+/// override settings for the connections accepted on it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub address: String,
+    pub port: u16,
+    /// Overrides `server.online_mode`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub online_mode: Option<bool>,
+    /// Overrides `proxy.proxy_protocol`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_protocol: Option<bool>,
+}
+
+impl Listener {
+    pub fn new(address: impl Into<String>, port: u16) -> Self {
+        Self {
+            address: address.into(),
+            port,
+            online_mode: None,
+            proxy_protocol: None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -84,6 +146,28 @@ pub struct Gameplay {
     pub animal_spawning: bool,
     pub pvp: bool,
     pub nerf_spawner_mobs: bool,
+    pub pvp_style: PvpStyle,
+}
+
+/// Which version's melee combat mechanics are used.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PvpStyle {
+    /// 1.8 combat: attacks always deal full damage,
+    /// without a cooldown or sweeping attacks.
+    #[serde(alias = "classic")]
+    Classic,
+    /// 1.9 combat, where attacking again before the
+    /// attack cooldown is over deals less damage.
+    #[serde(alias = "new")]
+    New,
+}
+
+impl PvpStyle {
+    /// Returns whether attacks have a cooldown, which
+    /// also enables sweeping attacks.
+    pub fn has_attack_cooldown(self) -> bool {
+        self == PvpStyle::New
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -95,6 +179,8 @@ pub struct Log {
 pub struct ResourcePack {
     pub url: String,
     pub hash: String,
+    pub forced: bool,
+    pub prompt: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -104,6 +190,38 @@ pub struct World {
     pub seed: String,
     #[serde(with = "humantime_serde")]
     pub save_interval: Duration,
+    pub storage: String,
+    pub region_compression: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rcon {
+    pub enabled: bool,
+    pub port: u16,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Query {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RateLimit {
+    pub packets_per_second: u32,
+    pub packet_burst: u32,
+    pub bytes_per_second: u32,
+    pub byte_burst: u32,
+    pub action: RateLimitAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitAction {
+    #[serde(alias = "disconnect")]
+    Disconnect,
+    #[serde(alias = "throttle")]
+    Throttle,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -172,21 +290,28 @@ mod tests {
         let config = Config::load(input).expect("invalid default configuration");
         let io = &config.io;
         assert_eq!(io.compression_threshold, 256);
+        assert_eq!(io.compression_level, 6);
+        assert_eq!(io.keepalive_interval.as_secs(), 15);
+        assert_eq!(io.timeout.as_secs(), 30);
+        assert_eq!(io.join_timeout.as_secs(), 60);
 
         let server = &config.server;
         assert_eq!(server.online_mode, true);
         assert_eq!(server.motd, "A Feather server");
         assert_eq!(server.max_players, 16);
+        assert_eq!(server.player_sample_size, 12);
         assert_eq!(server.default_gamemode, Gamemode::Creative);
         assert_eq!(server.view_distance, 6);
         assert_eq!(server.address, "0.0.0.0");
         assert_eq!(server.port, 25565);
+        assert!(server.listeners.is_empty());
 
         let gameplay = &config.gameplay;
         assert_eq!(gameplay.animal_spawning, true);
         assert_eq!(gameplay.monster_spawning, true);
         assert_eq!(gameplay.pvp, true);
         assert_eq!(gameplay.nerf_spawner_mobs, false);
+        assert_eq!(gameplay.pvp_style, PvpStyle::New);
 
         let log = &config.log;
         assert_eq!(log.level, "debug");
@@ -194,14 +319,58 @@ mod tests {
         let resource_pack = &config.resource_pack;
         assert_eq!(resource_pack.url, "");
         assert_eq!(resource_pack.hash, "");
+        assert_eq!(resource_pack.forced, false);
+        assert_eq!(resource_pack.prompt, "");
 
         let world = &config.world;
         assert_eq!(world.name, "world");
         assert_eq!(world.generator, "default");
         assert_eq!(world.seed, "");
         assert_eq!(world.save_interval.as_millis(), 1000 * 60);
+        assert_eq!(world.storage, "anvil");
+        assert_eq!(world.region_compression, "zlib");
 
         let proxy = &config.proxy;
         assert_eq!(proxy.proxy_mode, ProxyMode::None);
+        assert_eq!(proxy.velocity_secret, "");
+        assert_eq!(proxy.proxy_protocol, false);
+
+        let rcon = &config.rcon;
+        assert_eq!(rcon.enabled, false);
+        assert_eq!(rcon.port, 25575);
+        assert_eq!(rcon.password, "");
+
+        let query = &config.query;
+        assert_eq!(query.enabled, false);
+        assert_eq!(query.port, 25565);
+
+        let rate_limit = &config.rate_limit;
+        assert_eq!(rate_limit.packets_per_second, 500);
+        assert_eq!(rate_limit.packet_burst, 1000);
+        assert_eq!(rate_limit.bytes_per_second, 1_048_576);
+        assert_eq!(rate_limit.byte_burst, 4_194_304);
+        assert_eq!(rate_limit.action, RateLimitAction::Disconnect);
+    }
+
+    #[test]
+    fn listener_overrides() {
+        let mut config = Config::load(include_str!("../feather.toml")).unwrap();
+        config.server.listeners = vec![Listener {
+            proxy_protocol: Some(true),
+            ..Listener::new("::", 25566)
+        }];
+
+        let listeners = config.listeners();
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(listeners[0], Listener::new("0.0.0.0", 25565));
+
+        let proxied = config.for_listener(&listeners[1]);
+        assert!(proxied.proxy.proxy_protocol);
+        assert!(proxied.server.online_mode);
+        assert!(!config.for_listener(&listeners[0]).proxy.proxy_protocol);
+
+        // Listeners survive a round trip through the file.
+        let saved = Config::load(&config.save()).unwrap();
+        assert_eq!(saved.server.listeners, config.server.listeners);
     }
 }
@@ -1,5 +1,6 @@
 mod entity_creation;
 mod entity_deletion;
+mod hurt;
 mod inventory;
 mod item_collect;
 mod metadata;
@@ -8,6 +9,7 @@ mod movement;
 pub use self::inventory::*;
 pub use entity_creation::*;
 pub use entity_deletion::*;
+pub use hurt::*;
 pub use item_collect::*;
 pub use metadata::*;
 pub use movement::*;
@@ -2,8 +2,8 @@ use feather_core::entitymeta::EntityMetadata;
 use feather_core::network::packets::PacketEntityMetadata;
 use feather_core::util::Position;
 use feather_server_types::{
-    CreationPacketCreator, EntitySendEvent, EntitySpawnEvent, Game, Network, NetworkId,
-    PlayerJoinEvent, SpawnPacketCreator,
+    hidden_player_info, CreationPacketCreator, EntitySendEvent, EntitySpawnEvent, Game, Network,
+    NetworkId, PlayerJoinEvent, SpawnPacketCreator,
 };
 use fecs::{IntoQuery, Read, World};
 
@@ -35,16 +35,21 @@ pub fn on_entity_spawn_send_to_clients(
             game.broadcast_entity_update(world, packet, event.entity, Some(event.entity));
         }
 
+        if let Some(info) = hidden_player_info(world, event.entity) {
+            game.broadcast_entity_update(world, info, event.entity, Some(event.entity));
+        }
+
         // Now send spawn packet: Spawn Object / Spawn Player / Spawn Mob / whatever.
         let packet = creator.get(&accessor);
         game.broadcast_entity_update_boxed(world, packet, event.entity, Some(event.entity));
 
         let chunk = world.get::<Position>(event.entity).chunk();
+        let world_id = game.world_of(world, event.entity);
 
         drop(creator);
 
         // trigger on_entity_send
-        for player in game.chunk_holders.holders_for(chunk) {
+        for player in game.worlds[world_id].chunk_holders.holders_for(chunk) {
             if world.try_get::<Network>(*player).is_some() {
                 to_trigger.push(*player);
             }
@@ -0,0 +1,49 @@
+use feather_core::network::packets::EntityStatus;
+use feather_server_types::{Game, HealthUpdateEvent, NetworkId};
+use fecs::World;
+
+/// Entity status which plays the hurt animation and sound.
+const HURT_STATUS: i8 = 2;
+
+/// Broadcasts the hurt animation when an entity takes damage.
+#[fecs::event_handler]
+pub fn on_health_update_broadcast_hurt(event: &HealthUpdateEvent, game: &Game, world: &mut World) {
+    if event.new >= event.old {
+        return;
+    }
+
+    let entity_id = match world.try_get::<NetworkId>(event.entity) {
+        Some(id) => id.0,
+        None => return,
+    };
+    let packet = EntityStatus {
+        entity_id,
+        entity_status: HURT_STATUS,
+    };
+
+    game.broadcast_entity_update(world, packet, event.entity, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn broadcast_hurt() {
+        let mut test = Test::new();
+
+        let (packet, player) = test.broadcast_routine::<EntityStatus, _, _, _>(
+            |_test, player1, _player2| HealthUpdateEvent {
+                old: 20,
+                new: 15,
+                entity: player1,
+            },
+            on_health_update_broadcast_hurt,
+            true,
+        );
+
+        assert_eq!(packet.entity_id, test.id(player));
+        assert_eq!(packet.entity_status, HURT_STATUS);
+    }
+}
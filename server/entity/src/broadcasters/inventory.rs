@@ -2,11 +2,11 @@
 
 use crate::inventory::Equipment;
 use feather_core::inventory::{slot, Area, Inventory, SlotIndex, Window};
-use feather_core::network::packets::{EntityEquipment, NamedSoundEffect, SetSlot, SoundCategory};
+use feather_core::network::packets::{EntityEquipment, SetSlot, SoundCategory};
 use feather_core::util::Position;
 use feather_server_types::{
     EntitySendEvent, Game, HeldItem, InventoryUpdateEvent, ItemDamageEvent, Network, NetworkId,
-    Player,
+    Player, Sound,
 };
 use fecs::{Entity, World};
 use num_traits::ToPrimitive;
@@ -170,28 +170,12 @@ pub fn on_damage_item(event: &ItemDamageEvent, game: &mut Game, world: &mut Worl
 }
 
 fn send_item_broken_sound_effect(player: Entity, game: &mut Game, world: &mut World) {
-    let (effect_pos_x, effect_pos_y, effect_pos_z) = {
-        let pos = world.get::<Position>(player);
-        (
-            // https://wiki.vg/Data_types#Fixed-point_numbers
-            (pos.x * 8.0) as i32,
-            (pos.y * 8.0) as i32,
-            (pos.z * 8.0) as i32,
-        )
-    };
-    let mut rng = game.rng();
-    let sound_packet = NamedSoundEffect {
-        sound_name: "entity.item.break".into(),
-        sound_category: SoundCategory::Players as i32,
-        effect_pos_x,
-        effect_pos_y,
-        effect_pos_z,
-        volume: 1.0,
-        pitch: rng.gen_range(0.8, 1.2),
-    };
+    let pos = *world.get::<Position>(player);
+    let sound = Sound::new("entity.item.break", SoundCategory::Players)
+        .pitch(game.rng().gen_range(0.8, 1.2));
 
     let network = world.get::<Network>(player);
-    network.send(sound_packet);
+    network.send(sound.packet(pos));
 }
 
 #[cfg(test)]
@@ -293,6 +277,7 @@ mod tests {
             ty: Item::StoneShovel,
             amount: 1,
             damage: Some(10),
+            ..Default::default()
         };
         let slot = SlotIndex {
             area: Area::Main,
@@ -1,23 +1,110 @@
 //! Sending of entity metadata.
 
-use feather_core::entitymeta::EntityMetadata;
+use feather_core::entitymeta::{
+    EntityBitMask, EntityMetadata, Metadata, META_INDEX_ENTITY_BITMASK,
+    META_INDEX_LIVING_HAND_STATE, PLAYER_LAYOUT,
+};
 use feather_core::network::packets::PacketEntityMetadata;
-use feather_server_types::{EntitySendEvent, Network, NetworkId};
-use fecs::World;
+use feather_server_types::{
+    hand_state, Blocking, BumpVec, Burning, EntitySendEvent, Game, Gliding, Network, NetworkId,
+    Player,
+};
+use fecs::{Entity, IntoQuery, Read, World, Write};
 
 /// System which sends entity metadata when an entity
 /// is sent to a player.
 #[fecs::event_handler]
 pub fn on_entity_send_send_metadata(event: &EntitySendEvent, world: &mut World) {
-    if let Some(metadata) = world.try_get::<EntityMetadata>(event.entity) {
-        if let Some(network) = world.try_get::<Network>(event.client) {
-            let entity_id = world.get::<NetworkId>(event.entity).0;
-            let packet = PacketEntityMetadata {
-                entity_id,
-                metadata: (&*metadata).clone(),
-            };
-            network.send(packet);
+    let metadata = match world.try_get::<EntityMetadata>(event.entity) {
+        Some(metadata) => (&*metadata).clone(),
+        None if world.has::<Player>(event.entity) => {
+            let flags = player_flags(world, event.entity);
+            let hand = world.try_get::<Blocking>(event.entity).map(|b| b.hand);
+            if flags.is_empty() && hand.is_none() {
+                return;
+            }
+            let metadata = EntityMetadata::new()
+                .with_layout(PLAYER_LAYOUT)
+                .with(META_INDEX_ENTITY_BITMASK, flags.bits());
+            match hand {
+                Some(hand) => {
+                    metadata.with(META_INDEX_LIVING_HAND_STATE, hand_state(Some(hand)).bits())
+                }
+                None => metadata,
+            }
         }
+        None => return,
+    };
+
+    if let Some(network) = world.try_get::<Network>(event.client) {
+        let entity_id = world.get::<NetworkId>(event.entity).0;
+        let packet = PacketEntityMetadata {
+            entity_id,
+            metadata,
+        };
+        network.send(packet);
+    }
+}
+
+/// Sets or clears one of the flags of an entity, such as
+/// whether it's on fire, and broadcasts the change.
+pub fn set_entity_flag(
+    game: &Game,
+    world: &mut World,
+    entity: Entity,
+    flag: EntityBitMask,
+    value: bool,
+) {
+    if let Some(mut metadata) = world.try_get_mut::<Metadata>(entity) {
+        let mut bitmask = EntityBitMask::from_bits_truncate(metadata.entity_bitmask());
+        bitmask.set(flag, value);
+        metadata.set_entity_bitmask(bitmask.bits());
+        return;
+    }
+
+    // Players have no metadata model, so their whole
+    // bitmask is sent on its own, built from their components.
+    let mut bitmask = player_flags(world, entity);
+    bitmask.set(flag, value);
+    let packet = PacketEntityMetadata {
+        entity_id: world.get::<NetworkId>(entity).0,
+        metadata: EntityMetadata::new().with(META_INDEX_ENTITY_BITMASK, bitmask.bits()),
+    };
+    game.broadcast_entity_update(world, packet, entity, None);
+}
+
+/// Returns the flags of an entity without a metadata
+/// model, such as a player, given its components.
+fn player_flags(world: &World, entity: Entity) -> EntityBitMask {
+    let mut bitmask = EntityBitMask::empty();
+    bitmask.set(EntityBitMask::ON_FIRE, world.has::<Burning>(entity));
+    bitmask.set(
+        EntityBitMask::FLYING_WITH_ELYTRA,
+        world.has::<Gliding>(entity),
+    );
+    bitmask
+}
+
+/// System which broadcasts the fields of entities'
+/// `Metadata` which changed since the last tick.
+#[fecs::system]
+pub fn broadcast_metadata_updates(game: &mut Game, world: &mut World) {
+    let mut updates = BumpVec::new_in(game.bump());
+    for (entity, (mut metadata, entity_id)) in
+        <(Write<Metadata>, Read<NetworkId>)>::query().iter_entities_mut(world.inner_mut())
+    {
+        let changes = metadata.to_raw_metadata();
+        if !changes.is_empty() {
+            updates.push((entity, entity_id.0, changes));
+        }
+    }
+
+    for (entity, entity_id, metadata) in updates {
+        let packet = PacketEntityMetadata {
+            entity_id,
+            metadata,
+        };
+        game.broadcast_entity_update(world, packet, entity, None);
     }
 }
 
@@ -26,8 +113,9 @@ mod tests {
     use super::*;
     use crate::item;
     use feather_core::entitymeta::{MetaEntry, META_INDEX_ITEM_SLOT};
+    use feather_core::inventory::{Area, Inventory};
     use feather_core::items::{Item, ItemStack};
-    use feather_core::util::Position;
+    use feather_core::util::{Hand, Position};
     use feather_test_framework::Test;
 
     #[test]
@@ -57,4 +145,109 @@ mod tests {
 
         assert!(test.sent::<PacketEntityMetadata>(player2).is_none());
     }
+
+    #[test]
+    fn broadcast_changes() {
+        let mut test = Test::new();
+
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let creeper =
+            test.entity(crate::mob::base(crate::MobKind::Creeper).with(position!(1.0, 64.0, 0.0)));
+
+        test.run(broadcast_metadata_updates);
+        assert!(test.sent::<PacketEntityMetadata>(player).is_none());
+
+        if let Metadata::Creeper(meta) = &mut *test.world.get_mut::<Metadata>(creeper) {
+            meta.set_ignited(true);
+        }
+        test.run(broadcast_metadata_updates);
+
+        let packet = test.sent::<PacketEntityMetadata>(player).unwrap();
+        assert_eq!(packet.entity_id, test.id(creeper));
+        assert_eq!(packet.metadata.iter().count(), 1);
+        assert_eq!(packet.metadata.get(14), Some(MetaEntry::Boolean(true)));
+    }
+
+    #[test]
+    fn player_flags_are_combined() {
+        let mut test = Test::new();
+
+        let player1 = test.player("", position!(0.0, 64.0, 0.0));
+        let player2 = test.player("", position!(1.0, 64.0, 0.0));
+        test.world.add(player1, Burning(100)).unwrap();
+
+        set_entity_flag(
+            &test.game,
+            &mut test.world,
+            player1,
+            EntityBitMask::FLYING_WITH_ELYTRA,
+            true,
+        );
+
+        let flags = EntityBitMask::ON_FIRE | EntityBitMask::FLYING_WITH_ELYTRA;
+        let packet = test.sent::<PacketEntityMetadata>(player2).unwrap();
+        assert_eq!(packet.entity_id, test.id(player1));
+        assert_eq!(
+            packet.metadata.get(META_INDEX_ENTITY_BITMASK),
+            Some(MetaEntry::Byte(flags.bits() as i8))
+        );
+    }
+
+    #[test]
+    fn send_player_flags() {
+        let mut test = Test::new();
+
+        let player1 = test.player("", position!(0.0, 64.0, 0.0));
+        let player2 = test.player("", position!(1.0, 64.0, 0.0));
+        let event = EntitySendEvent {
+            client: player2,
+            entity: player1,
+        };
+
+        test.handle(event, on_entity_send_send_metadata);
+        assert!(test.sent::<PacketEntityMetadata>(player2).is_none());
+
+        test.world.add(player1, Gliding::default()).unwrap();
+        test.handle(event, on_entity_send_send_metadata);
+        assert!(test.sent::<PacketEntityMetadata>(player2).is_some());
+    }
+
+    #[test]
+    fn send_hand_state_of_blocking_players() {
+        let mut test = Test::new();
+
+        let player1 = test.player("", position!(0.0, 64.0, 0.0));
+        let player2 = test.player("", position!(1.0, 64.0, 0.0));
+        test.world
+            .get::<Inventory>(player1)
+            .set_item_at(Area::Offhand, 0, ItemStack::new(Item::Shield, 1))
+            .unwrap();
+
+        assert!(!test.game.raise_shield(&mut test.world, player1, Hand::Main));
+        assert!(test.game.raise_shield(&mut test.world, player1, Hand::Off));
+        let packet = test.sent::<PacketEntityMetadata>(player2).unwrap();
+        assert_eq!(
+            packet.metadata.get(META_INDEX_LIVING_HAND_STATE),
+            Some(MetaEntry::Byte(0x03))
+        );
+        assert!(test.sent::<PacketEntityMetadata>(player1).is_none());
+
+        let event = EntitySendEvent {
+            client: player2,
+            entity: player1,
+        };
+        test.handle(event, on_entity_send_send_metadata);
+        let packet = test.sent::<PacketEntityMetadata>(player2).unwrap();
+        assert_eq!(
+            packet.metadata.get(META_INDEX_LIVING_HAND_STATE),
+            Some(MetaEntry::Byte(0x03))
+        );
+
+        test.game.lower_shield(&mut test.world, player1);
+        let packet = test.sent::<PacketEntityMetadata>(player2).unwrap();
+        assert_eq!(
+            packet.metadata.get(META_INDEX_LIVING_HAND_STATE),
+            Some(MetaEntry::Byte(0))
+        );
+    }
 }
@@ -8,7 +8,7 @@ use feather_core::network::Packet;
 use feather_core::util::Position;
 use feather_server_types::{
     EntityClientRemoveEvent, EntitySendEvent, Game, LastKnownPositions, Network, NetworkId,
-    PreviousPosition, PreviousVelocity, Velocity,
+    PreviousPosition, PreviousVelocity, Velocity, WorldId,
 };
 use feather_server_util::{calculate_relative_move, degrees_to_stops, protocol_velocity};
 use fecs::{IntoQuery, Read, World};
@@ -18,51 +18,57 @@ use std::ops::Deref;
 /// System to broadcast when an entity moves.
 #[fecs::system]
 pub fn broadcast_movement(game: &mut Game, world: &mut World) {
-    <(Read<Position>, Read<PreviousPosition>, Read<NetworkId>)>::query().par_entities_for_each(
-        world.inner(),
-        |(entity, (pos, prev_pos, id))| {
-            let pos: Position = *pos;
-
-            let prev_pos = match prev_pos.0 {
-                Some(prev_pos) => prev_pos,
-                None => return,
-            };
-
-            if pos == prev_pos {
-                return;
-            }
+    <(
+        Read<Position>,
+        Read<PreviousPosition>,
+        Read<NetworkId>,
+        Read<WorldId>,
+    )>::query()
+    .par_entities_for_each(world.inner(), |(entity, (pos, prev_pos, id, world_id))| {
+        let pos: Position = *pos;
+
+        let prev_pos = match prev_pos.0 {
+            Some(prev_pos) => prev_pos,
+            None => return,
+        };
+
+        if pos == prev_pos {
+            return;
+        }
 
-            let entity_id = id.0;
-
-            let chunk = pos.chunk();
-            let players = game.chunk_holders.holders_for(chunk);
-
-            for player in players.iter().filter(|player| **player != entity) {
-                if let Some(network) = world.try_get::<Network>(*player) {
-                    let last_known_positions = world.get::<LastKnownPositions>(*player);
-                    let last_known_positions = last_known_positions.deref();
-
-                    if let Some(mut last_known_pos) = last_known_positions.0.get_mut(&entity) {
-                        for packet in
-                            packets_for_movement_update(entity_id, *last_known_pos.value(), pos)
-                        {
-                            network.send_boxed(packet);
-                        }
-
-                        log::trace!("Updated position of {:?} on client {:?}", entity, player);
-
-                        *last_known_pos.value_mut() = pos;
-                    } else {
-                        log::trace!(
-                            "Missing last position entry for {:?} on client {:?}",
-                            entity,
-                            player
-                        );
-                    };
-                }
+        let entity_id = id.0;
+
+        let chunk = pos.chunk();
+        let players = match game.worlds.get(*world_id) {
+            Some(state) => state.chunk_holders.holders_for(chunk),
+            None => return,
+        };
+
+        for player in players.iter().filter(|player| **player != entity) {
+            if let Some(network) = world.try_get::<Network>(*player) {
+                let last_known_positions = world.get::<LastKnownPositions>(*player);
+                let last_known_positions = last_known_positions.deref();
+
+                if let Some(mut last_known_pos) = last_known_positions.0.get_mut(&entity) {
+                    for packet in
+                        packets_for_movement_update(entity_id, *last_known_pos.value(), pos)
+                    {
+                        network.send_boxed(packet);
+                    }
+
+                    log::trace!("Updated position of {:?} on client {:?}", entity, player);
+
+                    *last_known_pos.value_mut() = pos;
+                } else {
+                    log::trace!(
+                        "Missing last position entry for {:?} on client {:?}",
+                        entity,
+                        player
+                    );
+                };
             }
-        },
-    );
+        }
+    });
 }
 
 #[fecs::event_handler]
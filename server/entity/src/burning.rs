@@ -0,0 +1,274 @@
+//! Entities which are set on fire by touching fire or lava.
+//! Burning entities take damage every second until the fire
+//! runs out or they get wet. Dropped items in fire or lava
+//! burn up at once.
+
+use crate::set_entity_flag;
+use feather_core::blocks::{BlockId, BlockKind, SimplifiedBlockKind};
+use feather_core::entitymeta::EntityBitMask;
+use feather_core::items::{Enchantment, ItemStack};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::Position;
+use feather_server_types::{
+    worn_armor, Burning, DamageSource, Dead, FireResistance, Game, Health, Sound,
+};
+use fecs::{component, Entity, IntoQuery, Read, World};
+use rand::Rng;
+
+/// Ticks between each damage taken by burning entities.
+const BURN_DAMAGE_INTERVAL: u32 = 20;
+/// Seconds an entity burns for after touching fire.
+const FIRE_SECONDS: u32 = 8;
+/// Seconds an entity burns for after touching lava.
+const LAVA_SECONDS: u32 = 15;
+/// Damage dealt each tick to entities in fire.
+const FIRE_DAMAGE: u32 = 1;
+/// Damage dealt each tick to entities in lava.
+const LAVA_DAMAGE: u32 = 4;
+/// Ticks by which the fire of entities resistant
+/// to it runs out each tick.
+const FIRE_RESISTANT_BURN_RATE: u32 = 4;
+
+/// What an entity is touching, as far as fire is concerned.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Touching {
+    Nothing,
+    Water,
+    Fire,
+    Lava,
+}
+
+fn touching(block: BlockId) -> Touching {
+    if block.kind() == BlockKind::Fire {
+        Touching::Fire
+    } else if block.simplified_kind() == SimplifiedBlockKind::Lava {
+        Touching::Lava
+    } else if block.simplified_kind() == SimplifiedBlockKind::Water
+        || block.waterlogged() == Some(true)
+        || block.kind() == BlockKind::BubbleColumn
+    {
+        Touching::Water
+    } else {
+        Touching::Nothing
+    }
+}
+
+/// Sets an entity on fire for the given number of seconds, unless
+/// it's already burning for longer. Fire Protection on its armor
+/// shortens the time.
+pub fn set_on_fire(game: &mut Game, world: &mut World, entity: Entity, seconds: u32) {
+    let protection = worn_armor(world, entity)
+        .iter()
+        .filter_map(|stack| stack.enchantments.level(Enchantment::FireProtection))
+        .max()
+        .unwrap_or(0)
+        .max(0) as u32;
+    let ticks = seconds * 20;
+    let ticks = ticks.saturating_sub((f64::from(ticks) * f64::from(protection) * 0.15) as u32);
+
+    if let Some(mut burning) = world.try_get_mut::<Burning>(entity) {
+        burning.0 = burning.0.max(ticks);
+        return;
+    }
+    if ticks > 0 {
+        world.add(entity, Burning(ticks)).unwrap();
+        set_entity_flag(game, world, entity, EntityBitMask::ON_FIRE, true);
+    }
+}
+
+/// Puts out the fire of a burning entity.
+pub fn extinguish(game: &mut Game, world: &mut World, entity: Entity) {
+    if world.remove::<Burning>(entity).is_ok() {
+        set_entity_flag(game, world, entity, EntityBitMask::ON_FIRE, false);
+    }
+}
+
+/// System which sets entities touching fire or lava on fire,
+/// damages burning entities and puts out those which get wet.
+#[fecs::system]
+pub fn burn_entities(game: &mut Game, world: &mut World) {
+    // (entity, what it's touching, whether rain falls on it, whether it's an item)
+    let entities: Vec<(Entity, Touching, bool, bool)> = <Read<Position>>::query()
+        .filter(!component::<Dead>())
+        .iter_entities(world.inner())
+        .filter_map(|(entity, pos)| {
+            let is_item = world.has::<ItemStack>(entity);
+            if !is_item && !world.has::<Health>(entity) {
+                return None;
+            }
+
+            let world_id = game.world_of(world, entity);
+            let mut blocks = vec![pos.block()];
+            if !is_item {
+                blocks.push((*pos + glm::vec3(0.0, 1.0, 0.0)).block());
+            }
+            let touching = blocks
+                .into_iter()
+                .filter_map(|pos| game.block_at(world_id, pos))
+                .map(touching)
+                .max_by_key(|touching| match touching {
+                    Touching::Nothing => 0,
+                    Touching::Fire => 1,
+                    Touching::Water => 2,
+                    Touching::Lava => 3,
+                })
+                .unwrap_or(Touching::Nothing);
+            let in_rain = game.is_raining_at(world_id, pos.block());
+            Some((entity, touching, in_rain, is_item))
+        })
+        .collect();
+
+    for (entity, touching, in_rain, is_item) in entities {
+        if is_item {
+            if touching == Touching::Fire || touching == Touching::Lava {
+                burn_up(game, world, entity);
+            }
+            continue;
+        }
+
+        match touching {
+            Touching::Lava => {
+                if game.game_rules.fire_damage {
+                    game.damage(entity, LAVA_DAMAGE, DamageSource::Lava, world);
+                }
+                set_on_fire(game, world, entity, LAVA_SECONDS);
+            }
+            Touching::Fire => {
+                if game.game_rules.fire_damage {
+                    game.damage(entity, FIRE_DAMAGE, DamageSource::Fire, world);
+                }
+                match world.try_get_mut::<Burning>(entity) {
+                    Some(mut burning) => burning.0 += 1,
+                    None => set_on_fire(game, world, entity, FIRE_SECONDS),
+                }
+            }
+            Touching::Water | Touching::Nothing => (),
+        }
+
+        if touching == Touching::Water || in_rain {
+            extinguish(game, world, entity);
+        } else if world.is_alive(entity) && !world.has::<Dead>(entity) {
+            burn(game, world, entity);
+        }
+    }
+}
+
+/// Counts down the fire of an entity, damaging it every second.
+fn burn(game: &mut Game, world: &mut World, entity: Entity) {
+    let ticks = match world.try_get::<Burning>(entity) {
+        Some(burning) => burning.0,
+        None => return,
+    };
+
+    let ticks = if world.has::<FireResistance>(entity) {
+        ticks.saturating_sub(FIRE_RESISTANT_BURN_RATE)
+    } else {
+        if ticks % BURN_DAMAGE_INTERVAL == 0 && game.game_rules.fire_damage {
+            game.damage(entity, FIRE_DAMAGE, DamageSource::Fire, world);
+        }
+        ticks.saturating_sub(1)
+    };
+
+    if ticks == 0 {
+        extinguish(game, world, entity);
+    } else if let Some(mut burning) = world.try_get_mut::<Burning>(entity) {
+        burning.0 = ticks;
+    }
+}
+
+/// Destroys a dropped item which fell into fire or lava.
+fn burn_up(game: &mut Game, world: &mut World, item: Entity) {
+    let pitch = {
+        let mut rng = game.rng();
+        2.0 + rng.gen_range(0.0, 0.4)
+    };
+    let sound = Sound::new("entity.generic.burn", SoundCategory::Neutral)
+        .volume(0.4)
+        .pitch(pitch);
+    let pos = *world.get::<Position>(item);
+    let world_id = game.world_of(world, item);
+    game.play_sound(world, world_id, pos, &sound);
+
+    game.despawn(item, world);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::items::Item;
+    use feather_core::network::packets::PacketEntityMetadata;
+    use feather_server_types::{BlockUpdateCause, CanTakeDamage, WorldId};
+    use feather_test_framework::Test;
+
+    #[test]
+    fn burning_in_fire() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        test.world.add(player, CanTakeDamage).unwrap();
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            position!(0.5, 64.0, 0.5).block(),
+            BlockId::fire(),
+            BlockUpdateCause::Unknown,
+        );
+
+        test.run(burn_entities);
+        assert_eq!(
+            *test.world.get::<Burning>(player),
+            Burning(FIRE_SECONDS * 20 - 1)
+        );
+        assert!(test.world.get::<Health>(player).0 < 20);
+        assert!(test.sent::<PacketEntityMetadata>(player).is_some());
+    }
+
+    #[test]
+    fn water_extinguishes() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        set_on_fire(&mut test.game, &mut test.world, player, FIRE_SECONDS);
+        assert!(test.world.has::<Burning>(player));
+
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            position!(0.5, 64.0, 0.5).block(),
+            BlockId::water(),
+            BlockUpdateCause::Unknown,
+        );
+        test.run(burn_entities);
+        assert!(!test.world.has::<Burning>(player));
+    }
+
+    #[test]
+    fn fire_resistance() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        test.world.add(player, CanTakeDamage).unwrap();
+        test.world.add(player, FireResistance).unwrap();
+        set_on_fire(&mut test.game, &mut test.world, player, 1);
+
+        for _ in 0..5 {
+            test.run(burn_entities);
+        }
+        assert!(!test.world.has::<Burning>(player));
+        assert_eq!(test.world.get::<Health>(player).0, 20);
+    }
+
+    #[test]
+    fn items_burn_up_in_lava() {
+        let mut test = Test::new();
+        let item = test.entity(
+            crate::item::create(ItemStack::new(Item::Stone, 1), 0).with(position!(0.5, 64.0, 0.5)),
+        );
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            position!(0.5, 64.0, 0.5).block(),
+            BlockId::lava(),
+            BlockUpdateCause::Unknown,
+        );
+
+        test.run(burn_entities).assert_dead(item);
+    }
+}
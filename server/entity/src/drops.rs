@@ -1,9 +1,11 @@
 use crate::{item, InventoryExt};
+use feather_core::blocks::BlockId;
 use feather_core::items::ItemStack;
 use feather_core::loot::{loot_table, Conditions};
-use feather_core::util::Position;
+use feather_core::util::{BlockPosition, Position};
 use feather_server_types::{
-    BlockUpdateEvent, CanInstaBreak, EntitySpawnEvent, Game, Inventory, Velocity, TPS,
+    can_harvest, BlockUpdateEvent, CanInstaBreak, EntitySpawnEvent, Game, Inventory, Velocity,
+    WorldId, TPS,
 };
 use fecs::{Entity, World};
 use rand::Rng;
@@ -28,44 +30,58 @@ pub fn on_block_break_drop_loot(event: &BlockUpdateEvent, game: &mut Game, world
                 .map(|inv| inv.item_in_main_hand(entity, world))
                 .flatten();
 
-            // If the block was not broken with the correct tool, don't drop loot.
-            if event.old.kind().best_tool_required() {
-                let tool_used = item.map(|item| item.ty.tool()).flatten();
-
-                let best_tool = event.old.kind().best_tool();
-
-                if tool_used != best_tool {
-                    return;
-                }
+            // If the block was not broken with a suitable tool, don't drop loot.
+            if !can_harvest(event.old.kind(), item.map(|item| item.ty)) {
+                return;
             }
 
             item
         }
-        feather_server_types::BlockUpdateCause::Unsupported => None,
+        feather_server_types::BlockUpdateCause::Unsupported
+        | feather_server_types::BlockUpdateCause::Tick => None,
         _ => return,
     };
 
-    if let Some(loot_table) = loot_table(&format!("blocks/{}", &event.old.identifier()[10..])) {
-        let conditions = Conditions { item };
+    drop_block_loot(game, world, event.world, event.old, event.pos, item);
+}
+
+/// Drops the items from the loot table of a block broken
+/// with the given tool, or without one.
+pub fn drop_block_loot(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    block: BlockId,
+    pos: BlockPosition,
+    tool: Option<ItemStack>,
+) {
+    if let Some(loot_table) = loot_table(&format!("blocks/{}", &block.identifier()[10..])) {
+        let conditions = Conditions { item: tool };
         let items = loot_table
             .sample(&mut *game.rng(), &conditions)
             .unwrap_or_else(|e| {
                 log::error!(
                     "Error sampling from loot table `{}`: {:?}",
-                    event.old.identifier(),
+                    block.identifier(),
                     e
                 );
                 Default::default()
             });
 
         for item in items {
-            drop_item(game, world, item, event.pos.position());
+            drop_item(game, world, world_id, item, pos.position());
         }
     }
 }
 
 /// "Naturally" drops an item caused by e.g. a broken block or a dead entity.
-pub fn drop_item(game: &mut Game, world: &mut World, item: ItemStack, pos: Position) -> Entity {
+pub fn drop_item(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    item: ItemStack,
+    pos: Position,
+) -> Entity {
     // Compute velocity. Based on Glowstone's implementation of `World#dropItemNaturally()`.
     let mut rng = game.rng();
 
@@ -80,6 +96,7 @@ pub fn drop_item(game: &mut Game, world: &mut World, item: ItemStack, pos: Posit
 
     let entity = item::create(item, game.tick_count + TPS)
         .with(pos)
+        .with(world_id)
         .with(Velocity(glm::vec3(offset_x, offset_y, offset_z)))
         .build()
         .spawn_in(world);
@@ -0,0 +1,513 @@
+//! Explosions, which blow up blocks and damage and knock
+//! back entities near them.
+//!
+//! Blocks are destroyed along rays cast from the centre of the
+//! explosion until their strength runs out, which happens sooner
+//! through blocks with a high blast resistance. Entities take less
+//! damage the further they are from the centre and the more of them
+//! is hidden behind blocks. TNT caught in an explosion is primed
+//! with a short fuse, and end crystals explode in turn.
+//!
+//! See https://minecraft.gamepedia.com/Explosion.
+
+use crate::drops::drop_block_loot;
+use crate::object::end_crystal::{self, EndCrystal};
+use crate::object::tnt::{self, PrimedTnt};
+use feather_core::blocks::{BlockId, BlockKind};
+use feather_core::items::{Enchantment, ItemStack, Tool};
+use feather_core::network::packets::{Explosion, SoundCategory};
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    worn_armor, AABBExt, BlockUpdateCause, DamageSource, Dead, Game, Health, Network, Physics,
+    Sound, Velocity, WorldId, PLAYER_HEIGHT, PLAYER_WIDTH,
+};
+use feather_server_util::nearby_entities;
+use fecs::{Entity, World};
+use glm::DVec3;
+use rand::Rng;
+use std::collections::BTreeSet;
+
+/// Number of rays cast along each edge of the cube around an explosion.
+const RAYS_PER_EDGE: i32 = 16;
+/// Distance between the points each ray checks.
+const RAY_STEP: f64 = 0.3;
+/// Strength a ray loses each step, besides the blast
+/// resistance of the blocks it passes through.
+const RAY_DECAY: f64 = 0.225;
+/// Blast resistance of water and lava.
+const FLUID_RESISTANCE: f64 = 100.0;
+
+/// What an explosion does to the blocks around it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlockDamage {
+    /// Blocks are left intact, such as by creepers when
+    /// the `mobGriefing` game rule is disabled.
+    None,
+    /// Blocks are destroyed.
+    Break,
+    /// Blocks are destroyed, and fire is lit on some of them.
+    BreakAndBurn,
+}
+
+/// Returns how well a block resists explosions. Blocks which
+/// can't be broken can't be blown up either.
+fn blast_resistance(block: BlockId) -> f64 {
+    let kind = block.kind();
+    let hardness = kind.hardness();
+    if hardness < 0.0 {
+        return f64::INFINITY;
+    }
+    if block.is_fluid() || block.waterlogged() == Some(true) {
+        return FLUID_RESISTANCE;
+    }
+
+    match kind {
+        BlockKind::Obsidian
+        | BlockKind::Anvil
+        | BlockKind::ChippedAnvil
+        | BlockKind::DamagedAnvil
+        | BlockKind::EnchantingTable => 1200.0,
+        BlockKind::EnderChest => 600.0,
+        BlockKind::EndStone => 9.0,
+        // Stone and other soft blocks needing a pickaxe
+        // resist explosions more than their hardness suggests.
+        _ if kind.best_tool_required()
+            && kind.best_tool() == Some(Tool::Pickaxe)
+            && hardness <= 2.0 =>
+        {
+            6.0
+        }
+        _ => hardness,
+    }
+}
+
+/// Causes an explosion of the given power, e.g. 4 for TNT
+/// or 5 for a bed in the Nether.
+pub fn explode(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    center: Position,
+    power: f32,
+    block_damage: BlockDamage,
+    source: Option<Entity>,
+) {
+    let pitch = {
+        let mut rng = game.rng();
+        (1.0 + (rng.gen::<f32>() - rng.gen::<f32>()) * 0.2) * 0.7
+    };
+    let sound = Sound::new("entity.generic.explode", SoundCategory::Blocks)
+        .volume(4.0)
+        .pitch(pitch);
+    game.play_sound(world, world_id, center, &sound);
+
+    let blocks = if block_damage == BlockDamage::None {
+        BTreeSet::new()
+    } else {
+        blocks_to_destroy(game, world_id, center, f64::from(power))
+    };
+    let (knockback, crystals) =
+        hurt_entities(game, world, world_id, center, f64::from(power), source);
+
+    for &pos in &blocks {
+        let block = match game.block_at(world_id, pos) {
+            Some(block) if !block.is_air() => block,
+            _ => continue,
+        };
+        let drops = game.rng().gen_range(0.0, f64::from(power)) < 1.0;
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            BlockId::air(),
+            BlockUpdateCause::Explosion,
+        );
+        if block.kind() == BlockKind::Tnt {
+            let fuse = tnt::chain_fuse(&mut *game.rng());
+            tnt::prime(game, world, world_id, pos, fuse, source);
+        } else if drops && !block.is_fluid() {
+            drop_block_loot(game, world, world_id, block, pos, None);
+        }
+    }
+
+    if block_damage == BlockDamage::BreakAndBurn {
+        light_fires(game, world, world_id, &blocks);
+    }
+
+    send_explosion(game, world, world_id, center, power, &blocks, &knockback);
+
+    for crystal in crystals {
+        if world.is_alive(crystal) {
+            end_crystal::detonate(game, world, crystal, source);
+        }
+    }
+}
+
+/// Casts rays from the centre of an explosion and returns
+/// the blocks they destroy.
+fn blocks_to_destroy(
+    game: &Game,
+    world_id: WorldId,
+    center: Position,
+    power: f64,
+) -> BTreeSet<BlockPosition> {
+    let mut rng = game.rng();
+    let mut blocks = BTreeSet::new();
+
+    let max = RAYS_PER_EDGE - 1;
+    for x in 0..RAYS_PER_EDGE {
+        for y in 0..RAYS_PER_EDGE {
+            for z in 0..RAYS_PER_EDGE {
+                let on_edge = [x, y, z].iter().any(|&i| i == 0 || i == max);
+                if !on_edge {
+                    continue;
+                }
+
+                let direction = glm::normalize(&glm::vec3(
+                    f64::from(x) / f64::from(max) * 2.0 - 1.0,
+                    f64::from(y) / f64::from(max) * 2.0 - 1.0,
+                    f64::from(z) / f64::from(max) * 2.0 - 1.0,
+                )) * RAY_STEP;
+
+                let mut strength = power * rng.gen_range(0.7, 1.3);
+                let mut point = center;
+                while strength > 0.0 {
+                    let pos = point.block();
+                    let block = match game.block_at(world_id, pos) {
+                        Some(block) => block,
+                        None => break,
+                    };
+                    if !block.is_air() {
+                        strength -= (blast_resistance(block) + 0.3) * RAY_STEP;
+                        if strength > 0.0 {
+                            blocks.insert(pos);
+                        }
+                    }
+
+                    point = point + direction;
+                    strength -= RAY_DECAY;
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Damages and knocks back the entities near an explosion. Dropped
+/// items are destroyed, and primed TNT is only knocked back. Returns
+/// the knockback of each player, who are knocked back by their clients,
+/// and the end crystals caught in the explosion.
+fn hurt_entities(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    center: Position,
+    power: f64,
+    source: Option<Entity>,
+) -> (Vec<(Entity, DVec3)>, Vec<Entity>) {
+    let radius = power * 2.0;
+    let entities = nearby_entities(
+        world,
+        game,
+        world_id,
+        center,
+        glm::vec3(radius, radius, radius),
+    );
+
+    let mut knockback = Vec::new();
+    let mut crystals = Vec::new();
+    for entity in entities {
+        if world.has::<Dead>(entity) {
+            continue;
+        }
+        let pos = *world.get::<Position>(entity);
+        let offset: DVec3 = (pos - center).into();
+        let distance = glm::length(&offset) / radius;
+        if distance > 1.0 {
+            continue;
+        }
+
+        let impact = (1.0 - distance) * exposure(game, world, world_id, center, entity);
+        let direction = if glm::length(&offset) > 0.0 {
+            glm::normalize(&offset)
+        } else {
+            glm::vec3(0.0, 0.0, 0.0)
+        };
+        if world.has::<ItemStack>(entity) {
+            if impact > 0.0 {
+                game.despawn(entity, world);
+            }
+            continue;
+        }
+        if world.has::<EndCrystal>(entity) {
+            if impact > 0.0 {
+                crystals.push(entity);
+            }
+            continue;
+        }
+        if world.has::<PrimedTnt>(entity) {
+            world.get_mut::<Velocity>(entity).0 += direction * impact;
+            continue;
+        }
+        if !world.has::<Health>(entity) {
+            continue;
+        }
+
+        let damage = ((impact * impact + impact) / 2.0 * 7.0 * radius + 1.0) as u32;
+        game.damage(entity, damage, DamageSource::Explosion(source), world);
+
+        let push = direction * impact * (1.0 - blast_protection(world, entity));
+        if let Some(mut velocity) = world.try_get_mut::<Velocity>(entity) {
+            velocity.0 += push;
+        }
+        knockback.push((entity, push));
+    }
+
+    (knockback, crystals)
+}
+
+/// Returns how much Blast Protection on an entity's
+/// armor reduces the knockback it takes.
+fn blast_protection(world: &World, entity: Entity) -> f64 {
+    let level = worn_armor(world, entity)
+        .iter()
+        .filter_map(|stack| stack.enchantments.level(Enchantment::BlastProtection))
+        .max()
+        .unwrap_or(0)
+        .max(0);
+    (f64::from(level) * 0.15).min(1.0)
+}
+
+/// Returns the fraction of an entity which an explosion
+/// can reach without passing through solid blocks.
+fn exposure(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    center: Position,
+    entity: Entity,
+) -> f64 {
+    let pos = *world.get::<Position>(entity);
+    // Mobs without physics are assumed to be the size of a player.
+    let size = world.try_get::<Physics>(entity).map_or(
+        glm::vec3(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH),
+        |physics| physics.bbox.size(),
+    );
+    let corner = glm::vec3(pos.x - size.x / 2.0, pos.y, pos.z - size.z / 2.0);
+
+    let fractions = [0.0, 0.5, 1.0];
+    let mut samples = 0;
+    let mut exposed = 0;
+    for &fx in &fractions {
+        for &fy in &fractions {
+            for &fz in &fractions {
+                let point = corner + glm::vec3(size.x * fx, size.y * fy, size.z * fz);
+                samples += 1;
+                if !is_blocked(game, world_id, center, point) {
+                    exposed += 1;
+                }
+            }
+        }
+    }
+
+    f64::from(exposed) / f64::from(samples)
+}
+
+/// Returns whether a solid block lies between two points.
+fn is_blocked(game: &Game, world_id: WorldId, from: Position, to: DVec3) -> bool {
+    let from: DVec3 = from.into();
+    let offset = to - from;
+    let steps = (glm::length(&offset) / RAY_STEP).ceil() as usize;
+    (1..steps).any(|step| {
+        let point = from + offset * (step as f64 / steps as f64);
+        let pos = BlockPosition::new(
+            point.x.floor() as i32,
+            point.y.floor() as i32,
+            point.z.floor() as i32,
+        );
+        game.block_at(world_id, pos)
+            .map_or(false, |block| block.is_solid() && block.is_full_block())
+    })
+}
+
+/// Lights fire on a third of the destroyed blocks
+/// which are above a full block.
+fn light_fires(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    blocks: &BTreeSet<BlockPosition>,
+) {
+    for &pos in blocks {
+        let can_burn = game.block_at(world_id, pos).map_or(false, BlockId::is_air)
+            && game
+                .block_at(world_id, pos.down())
+                .map_or(false, BlockId::is_full_block);
+        if can_burn && game.rng().gen_range(0, 3) == 0 {
+            game.set_block_at(
+                world,
+                world_id,
+                pos,
+                BlockId::fire(),
+                BlockUpdateCause::Fire,
+            );
+        }
+    }
+}
+
+/// Sends the explosion to players nearby, so that their clients
+/// show it and knock them back.
+fn send_explosion(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    center: Position,
+    power: f32,
+    blocks: &BTreeSet<BlockPosition>,
+    knockback: &[(Entity, DVec3)],
+) {
+    let origin = center.block();
+    let records: Vec<(i8, i8, i8)> = blocks
+        .iter()
+        .map(|pos| {
+            (
+                (pos.x - origin.x) as i8,
+                (pos.y - origin.y) as i8,
+                (pos.z - origin.z) as i8,
+            )
+        })
+        .collect();
+
+    let state = match game.worlds.get(world_id) {
+        Some(state) => state,
+        None => return,
+    };
+    for &player in state.chunk_holders.holders_for(center.chunk()) {
+        let network = match world.try_get::<Network>(player) {
+            Some(network) => network,
+            None => continue,
+        };
+        let motion = knockback
+            .iter()
+            .find(|(entity, _)| *entity == player)
+            .map_or(glm::vec3(0.0, 0.0, 0.0), |(_, push)| *push);
+
+        network.send(Explosion {
+            x: origin.x as f32,
+            y: origin.y as f32,
+            z: origin.z as f32,
+            radius: power,
+            records: records.clone(),
+            player_motion_x: motion.x as f32,
+            player_motion_y: motion.y as f32,
+            player_motion_z: motion.z as f32,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_types::CanTakeDamage;
+    use feather_test_framework::Test;
+    use fecs::{IntoQuery, Read};
+
+    #[test]
+    fn destroys_blocks_but_not_bedrock() {
+        let mut test = Test::new();
+        let center = BlockPosition::new(0, 64, 0);
+        for pos in &[center.down(), center.up()] {
+            test.game.set_block_at(
+                &mut test.world,
+                WorldId::MAIN,
+                *pos,
+                BlockId::dirt(),
+                BlockUpdateCause::Unknown,
+            );
+        }
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            center + BlockPosition::new(1, 0, 0),
+            BlockId::bedrock(),
+            BlockUpdateCause::Unknown,
+        );
+
+        explode(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            center.position() + glm::vec3(0.5, 0.5, 0.5),
+            4.0,
+            BlockDamage::Break,
+            None,
+        );
+        assert!(test
+            .game
+            .block_at(WorldId::MAIN, center.up())
+            .unwrap()
+            .is_air());
+        assert!(test
+            .game
+            .block_at(WorldId::MAIN, center.down())
+            .unwrap()
+            .is_air());
+        assert_eq!(
+            test.game
+                .block_at(WorldId::MAIN, center + BlockPosition::new(1, 0, 0))
+                .unwrap(),
+            BlockId::bedrock()
+        );
+    }
+
+    #[test]
+    fn primes_tnt() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(2, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            BlockId::tnt(),
+            BlockUpdateCause::Unknown,
+        );
+
+        explode(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            position!(0.5, 64.5, 0.5),
+            4.0,
+            BlockDamage::Break,
+            None,
+        );
+        assert!(test.game.block_at(WorldId::MAIN, pos).unwrap().is_air());
+
+        let primed: Vec<PrimedTnt> = <Read<PrimedTnt>>::query()
+            .iter_entities(test.world.inner())
+            .map(|(_, tnt)| *tnt)
+            .collect();
+        assert_eq!(primed.len(), 1);
+        assert!(primed[0].fuse < tnt::FUSE);
+    }
+
+    #[test]
+    fn hurts_entities() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(6.5, 64.0, 0.5));
+        test.world.add(player, CanTakeDamage).unwrap();
+
+        explode(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            position!(0.5, 64.0, 0.5),
+            4.0,
+            BlockDamage::Break,
+            None,
+        );
+        assert!(test.world.get::<Health>(player).0 < 20);
+        assert!(test.sent::<Explosion>(player).is_some());
+    }
+}
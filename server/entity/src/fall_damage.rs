@@ -1,56 +1,232 @@
 //! Handles fall damage for entities
 
-use feather_core::util::Position;
+use feather_core::blocks::{BlockId, BlockKind, SimplifiedBlockKind};
+use feather_core::util::{BlockPosition, Position};
 use feather_server_types::{
-    BlocksFallen, BumpVec, CanTakeDamage, Dead, Game, Health, PreviousPosition,
+    BlockUpdateCause, BlocksFallen, BumpVec, CanTakeDamage, DamageSource, Dead, Game, Gliding,
+    Health, PreviousPosition,
 };
-use fecs::{component, Entity, IntoQuery, Read, World, Write};
+use fecs::{component, Entity, IntoQuery, Read, World};
+use rand::Rng;
 use std::cell::RefCell;
 
-/// System which updates `BlocksFallen` for all entities.
+/// Distance an entity can fall without taking damage.
+const SAFE_FALL_DISTANCE: f32 = 3.0;
+
+/// Distance an entity can fall onto farmland
+/// without trampling it.
+const SAFE_TRAMPLE_DISTANCE: f64 = 0.5;
+
+/// Speed, in blocks per tick, below which entities gliding
+/// with an elytra fall without building up fall distance.
+const SAFE_GLIDE_SPEED: f64 = 0.5;
+
+/// Returns the fall damage taken by an entity which fell
+/// the given number of blocks before landing on a block
+/// with the given damage multiplier.
+///
+/// https://minecraft.gamepedia.com/Damage#Fall_damage
+pub fn fall_damage(blocks_fallen: f64, multiplier: f32) -> u32 {
+    ((blocks_fallen as f32 - SAFE_FALL_DISTANCE) * multiplier)
+        .ceil()
+        .max(0.0) as u32
+}
+
+/// Returns the factor by which fall damage is multiplied
+/// for entities landing on a block.
+pub fn fall_damage_multiplier(block: BlockId) -> f32 {
+    if block.simplified_kind() == SimplifiedBlockKind::Bed {
+        return 0.5;
+    }
+
+    match block.kind() {
+        BlockKind::HayBlock => 0.2,
+        // Entities bounce off slime blocks.
+        BlockKind::SlimeBlock => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Returns whether an entity inside a block stops falling,
+/// such as when it falls into water or grabs onto a ladder.
+pub fn breaks_fall(block: BlockId) -> bool {
+    block.simplified_kind() == SimplifiedBlockKind::Water
+        || block.waterlogged() == Some(true)
+        || matches!(
+            block.kind(),
+            BlockKind::BubbleColumn | BlockKind::Ladder | BlockKind::Vine | BlockKind::Cobweb
+        )
+}
+
+/// Returns the position of the block an entity
+/// standing at the given position has landed on.
+fn landed_on(pos: Position) -> Position {
+    Position {
+        y: pos.y - 0.2,
+        ..pos
+    }
+}
+
+/// Returns whether an entity which fell the given number of
+/// blocks onto a block tramples it, turning farmland into dirt.
+fn tramples(block: BlockId, blocks_fallen: f64, rng: &mut impl Rng) -> bool {
+    block.simplified_kind() == SimplifiedBlockKind::Farmland
+        && rng.gen::<f64>() < blocks_fallen - SAFE_TRAMPLE_DISTANCE
+}
+
+/// System which updates `BlocksFallen` for all entities
+/// and damages those which landed after falling too far.
+/// Entities landing on farmland may trample it.
 #[fecs::system]
 pub fn update_blocks_fallen(game: &mut Game, world: &mut World) {
-    // Entities who went from !on_ground => on_ground
-
-    // (Legion for_each closures are not FnMuts; no idea why.)
-    let landed = RefCell::new(BumpVec::<Entity>::new_in(game.bump()));
+    // (entity, new blocks fallen, damage taken by landing, trampled farmland)
+    let updates =
+        RefCell::new(BumpVec::<(Entity, f64, u32, Option<BlockPosition>)>::new_in(game.bump()));
 
     // TODO: use parallel iterator (blocked on allocator API)
     // (BumpVec isn't Send.)
-    <(Read<Position>, Read<PreviousPosition>, Write<BlocksFallen>)>::query()
+    <(Read<Position>, Read<PreviousPosition>, Read<BlocksFallen>)>::query()
         .filter(component::<Health>())
         .filter(component::<CanTakeDamage>())
         .filter(!component::<Dead>())
-        .for_each_entities_mut(
-            world.inner_mut(),
-            |(entity, (pos, prev_pos, mut blocks_fallen))| {
-                match (prev_pos.0.map(|pos| pos.on_ground), pos.on_ground) {
-                    (Some(false), false) => {
-                        // In air: update blocks_fallen
-                        blocks_fallen.0 += (prev_pos.0.unwrap().y - pos.y).max(0.0);
-                    }
-                    (Some(true), false) => {
-                        // reset blocks_fallen
-                        blocks_fallen.0 = 0.0;
+        .for_each_entities(world.inner(), |(entity, (pos, prev_pos, blocks_fallen))| {
+            let prev_pos = match prev_pos.0 {
+                Some(prev_pos) => prev_pos,
+                None => return,
+            };
+            let world_id = game.world_of(world, entity);
+
+            let mut fallen = blocks_fallen.0;
+            let mut damage = 0;
+            let mut trampled = None;
+            if game
+                .block_at(world_id, pos.block())
+                .map_or(false, breaks_fall)
+            {
+                fallen = 0.0;
+            } else {
+                match (prev_pos.on_ground, pos.on_ground) {
+                    // In air: update blocks_fallen. Gliding entities
+                    // only build it up while diving.
+                    (false, false) => {
+                        let dropped = (prev_pos.y - pos.y).max(0.0);
+                        if world.has::<Gliding>(entity) && dropped < SAFE_GLIDE_SPEED {
+                            fallen = 1.0;
+                        } else {
+                            fallen += dropped;
+                        }
                     }
-                    (Some(false), true) => {
-                        // landed
-                        landed.borrow_mut().push(entity);
+                    // Left the ground: reset blocks_fallen
+                    (true, false) => fallen = 0.0,
+                    // Landed
+                    (false, true) => {
+                        let ground = landed_on(*pos).block();
+                        let block = game.block_at(world_id, ground);
+                        let multiplier = block.map_or(1.0, fall_damage_multiplier);
+                        damage = fall_damage(fallen, multiplier);
+                        if block.map_or(false, |block| tramples(block, fallen, &mut *game.rng())) {
+                            trampled = Some(ground);
+                        }
+                        fallen = 0.0;
                     }
-                    _ => (),
+                    (true, true) => (),
                 }
-            },
-        );
+            }
+
+            #[allow(clippy::float_cmp)]
+            let changed = fallen != blocks_fallen.0;
+            if changed || damage != 0 {
+                updates
+                    .borrow_mut()
+                    .push((entity, fallen, damage, trampled));
+            }
+        });
 
     // Damage landed entities
-    for entity in landed.into_inner() {
-        let blocks_fallen = world.get::<BlocksFallen>(entity).0;
+    for (entity, fallen, damage, trampled) in updates.into_inner() {
+        world.get_mut::<BlocksFallen>(entity).0 = fallen;
 
-        // https://minecraft.gamepedia.com/Damage#Fall_damage
-        let damage = (blocks_fallen - 3.0).max(0.0).round() as u32;
+        if let Some(pos) = trampled {
+            let world_id = game.world_of(world, entity);
+            game.set_block_at(
+                world,
+                world_id,
+                pos,
+                BlockId::dirt(),
+                BlockUpdateCause::Entity(entity),
+            );
+        }
 
         if damage != 0 {
-            game.damage(entity, damage, world);
+            game.damage(entity, damage, DamageSource::Fall, world);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn fall_damage_after_three_blocks() {
+        assert_eq!(fall_damage(3.0, 1.0), 0);
+        assert_eq!(fall_damage(3.5, 1.0), 1);
+        assert_eq!(fall_damage(23.0, 1.0), 20);
+    }
+
+    #[test]
+    fn safe_landing_blocks() {
+        let hay = BlockId::hay_block();
+        assert!((fall_damage_multiplier(hay) - 0.2).abs() < 1e-6);
+        assert_eq!(fall_damage(23.0, fall_damage_multiplier(hay)), 4);
+        assert_eq!(
+            fall_damage(100.0, fall_damage_multiplier(BlockId::slime_block())),
+            0
+        );
+        assert!((fall_damage_multiplier(BlockId::stone()) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn water_breaks_fall() {
+        assert!(breaks_fall(BlockId::water()));
+        assert!(breaks_fall(BlockId::ladder()));
+        assert!(!breaks_fall(BlockId::air()));
+        assert!(!breaks_fall(BlockId::stone()));
+    }
+
+    #[test]
+    fn gliding_breaks_fall() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 100.0, 0.0, false));
+        test.world.add(player, CanTakeDamage).unwrap();
+        test.world.add(player, BlocksFallen(20.0)).unwrap();
+        test.world.add(player, Gliding::default()).unwrap();
+        test.world
+            .add(
+                player,
+                PreviousPosition(Some(position!(0.0, 100.3, 0.0, false))),
+            )
+            .unwrap();
+
+        test.run(update_blocks_fallen);
+        assert!((test.world.get::<BlocksFallen>(player).0 - 1.0).abs() < 1e-6);
+
+        test.world
+            .add(
+                player,
+                PreviousPosition(Some(position!(0.0, 101.0, 0.0, false))),
+            )
+            .unwrap();
+        test.run(update_blocks_fallen);
+        assert!((test.world.get::<BlocksFallen>(player).0 - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn trampling_farmland() {
+        let mut rng = rand::thread_rng();
+        assert!(!tramples(BlockId::farmland(), 0.5, &mut rng));
+        assert!(tramples(BlockId::farmland(), 2.0, &mut rng));
+        assert!(!tramples(BlockId::dirt(), 2.0, &mut rng));
+    }
+}
@@ -1,6 +1,7 @@
 use feather_core::inventory::{slot, Area, SlotIndex};
-use feather_core::items::ItemStack;
-use feather_server_types::{HeldItem, Inventory};
+use feather_core::items::{Item, ItemStack};
+use feather_core::util::Gamemode;
+use feather_server_types::{Game, HeldItem, Inventory, InventoryUpdateEvent};
 use fecs::{Entity, World};
 use num_derive::{FromPrimitive, ToPrimitive};
 
@@ -16,6 +17,26 @@ impl InventoryExt for Inventory {
     }
 }
 
+/// Uses up one of the given stack, which a player holds in
+/// their main hand, unless they are in creative mode.
+pub fn consume_held_item(game: &mut Game, world: &mut World, player: Entity, stack: ItemStack) {
+    if *world.get::<Gamemode>(player) == Gamemode::Creative {
+        return;
+    }
+    let held_item = world.get::<HeldItem>(player).0;
+    world
+        .get::<Inventory>(player)
+        .set_item_at(Area::Hotbar, held_item, stack.of_amount(stack.amount - 1))
+        .unwrap();
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: std::iter::once(slot(Area::Hotbar, held_item)).collect(),
+            entity: player,
+        },
+    );
+}
+
 /// An equipment slot, with variants
 /// listed in the order of the Entity Equipment
 /// IDs to allow for easy conversion using `ToPrimitive`/`FromPrimitive`.
@@ -30,6 +51,28 @@ pub enum Equipment {
 }
 
 impl Equipment {
+    /// Returns the slot an item is equipped in, such as
+    /// the head for helmets and the main hand for tools.
+    pub fn for_item(item: Item) -> Self {
+        let identifier = item.identifier();
+        match item {
+            Item::CarvedPumpkin
+            | Item::SkeletonSkull
+            | Item::WitherSkeletonSkull
+            | Item::PlayerHead
+            | Item::ZombieHead
+            | Item::CreeperHead
+            | Item::DragonHead => Equipment::Helmet,
+            Item::Elytra => Equipment::Chestplate,
+            Item::Shield => Equipment::OffHand,
+            _ if identifier.ends_with("_helmet") => Equipment::Helmet,
+            _ if identifier.ends_with("_chestplate") => Equipment::Chestplate,
+            _ if identifier.ends_with("_leggings") => Equipment::Leggings,
+            _ if identifier.ends_with("_boots") => Equipment::Boots,
+            _ => Equipment::MainHand,
+        }
+    }
+
     pub fn from_slot_index(index: SlotIndex, held_item: usize) -> Option<Self> {
         use feather_core::inventory::Area::*;
         match index.area {
@@ -0,0 +1,433 @@
+//! Leads, which leash mobs to players and fences.
+//!
+//! Right clicking a mob with a lead leashes it to the player, who
+//! can then tie it to a fence by right clicking the fence, creating
+//! a leash knot there. A leash breaks and drops its lead once the mob
+//! is more than 10 blocks away from its holder, or when the holder is
+//! gone. Leashes aren't saved with the world yet.
+//!
+//! See https://minecraft.gamepedia.com/Lead.
+
+use crate::drops::drop_item;
+use crate::leash_knot::{self, knot_at, LeashKnot};
+use crate::{consume_held_item, MobCategory};
+use feather_core::blocks::SimplifiedBlockKind;
+use feather_core::entitymeta::Metadata;
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{AttachEntity, SoundCategory};
+use feather_core::util::{BlockPosition, Gamemode, Position};
+use feather_server_types::{
+    BlockUpdateEvent, Dead, EntityDeathEvent, EntityInteractEvent, EntitySendEvent, Game, Network,
+    NetworkId, Sound, WorldId,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+
+/// Distance from its holder beyond which a leash breaks.
+const MAX_LEASH_DISTANCE: f64 = 10.0;
+/// Distance on each axis from a fence within which
+/// the mobs a player holds are tied to it.
+const TIE_RADIUS: f64 = 7.0;
+
+/// Component for leashed mobs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Leashed {
+    /// The player or leash knot holding the leash.
+    pub holder: Entity,
+}
+
+/// Returns whether a mob can be put on a leash. Monsters, water
+/// mobs, bats, villagers and turtles can't be leashed, nor can
+/// mobs which already are.
+pub fn can_be_leashed(world: &World, mob: Entity) -> bool {
+    let leashable = match world.try_get::<Metadata>(mob) {
+        Some(meta) => !matches!(*meta, Metadata::Villager(_) | Metadata::Turtle(_)),
+        None => false,
+    };
+    // Golems have no category and can be leashed.
+    let category = world.try_get::<MobCategory>(mob).map(|category| *category);
+    leashable
+        && matches!(category, None | Some(MobCategory::Creature))
+        && !world.has::<Leashed>(mob)
+}
+
+/// Returns the mobs leashed to an entity.
+pub fn leashed_to(world: &World, holder: Entity) -> Vec<Entity> {
+    <Read<Leashed>>::query()
+        .iter_entities(world.inner())
+        .filter(|(_, leashed)| leashed.holder == holder)
+        .map(|(mob, _)| mob)
+        .collect()
+}
+
+/// Leashes a mob to a player or a leash knot.
+pub fn leash(game: &Game, world: &mut World, mob: Entity, holder: Entity) {
+    world.add(mob, Leashed { holder }).unwrap();
+    let packet = AttachEntity {
+        attached_entity_id: world.get::<NetworkId>(mob).0,
+        holding_entity_id: world.get::<NetworkId>(holder).0,
+    };
+    game.broadcast_entity_update(world, packet, mob, None);
+}
+
+/// Removes the leash of a mob, which drops
+/// its lead if `drop_lead` is set.
+pub fn unleash(game: &mut Game, world: &mut World, mob: Entity, drop_lead: bool) {
+    if world.remove::<Leashed>(mob).is_err() {
+        return;
+    }
+    let packet = AttachEntity {
+        attached_entity_id: world.get::<NetworkId>(mob).0,
+        holding_entity_id: -1,
+    };
+    game.broadcast_entity_update(world, packet, mob, None);
+
+    if drop_lead {
+        let world_id = game.world_of(world, mob);
+        let pos = *world.get::<Position>(mob);
+        drop_item(game, world, world_id, ItemStack::new(Item::Lead, 1), pos);
+    }
+}
+
+/// Ties the mobs a player holds near a fence to it, using
+/// the knot on the fence or creating one. Returns whether
+/// any mob was tied.
+pub fn tie_to_fence(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    world_id: WorldId,
+    fence: BlockPosition,
+) -> bool {
+    let center = fence.position() + glm::vec3(0.5, 0.5, 0.5);
+    let mobs: Vec<Entity> = leashed_to(world, player)
+        .into_iter()
+        .filter(|mob| {
+            let pos = *world.get::<Position>(*mob);
+            game.world_of(world, *mob) == world_id
+                && (pos.x - center.x).abs() <= TIE_RADIUS
+                && (pos.y - center.y).abs() <= TIE_RADIUS
+                && (pos.z - center.z).abs() <= TIE_RADIUS
+        })
+        .collect();
+    if mobs.is_empty() {
+        return false;
+    }
+
+    let knot = knot_at(world, world_id, fence)
+        .unwrap_or_else(|| leash_knot::spawn(game, world, world_id, fence));
+    for mob in mobs {
+        leash(game, world, mob, knot);
+    }
+    true
+}
+
+/// Breaks a leash knot, releasing the mobs tied to it. They drop
+/// their leads unless the knot is broken by a player in creative mode.
+pub fn break_leash_knot(game: &mut Game, world: &mut World, knot: Entity, player: Entity) {
+    let drop_leads = *world.get::<Gamemode>(player) != Gamemode::Creative;
+    remove_knot(game, world, knot, drop_leads);
+}
+
+fn remove_knot(game: &mut Game, world: &mut World, knot: Entity, drop_leads: bool) {
+    let world_id = game.world_of(world, knot);
+    let pos = *world.get::<Position>(knot);
+    let sound = Sound::new("entity.leash_knot.break", SoundCategory::Neutral);
+    game.play_sound(world, world_id, pos, &sound);
+
+    for mob in leashed_to(world, knot) {
+        unleash(game, world, mob, drop_leads);
+    }
+    game.despawn(knot, world);
+}
+
+/// When a player right clicks a mob holding a lead, leashes the
+/// mob to them. Right clicking a mob they hold removes its leash.
+///
+/// Right clicking a leash knot ties the mobs the player holds to
+/// it, or breaks it if they hold none.
+#[fecs::event_handler]
+pub fn on_entity_interact_leash(event: &EntityInteractEvent, game: &mut Game, world: &mut World) {
+    let (player, target) = (event.player, event.target);
+
+    if let Some(fence) = world.try_get::<LeashKnot>(target).map(|knot| knot.0) {
+        let world_id = game.world_of(world, target);
+        if !tie_to_fence(game, world, player, world_id, fence) {
+            break_leash_knot(game, world, target, player);
+        }
+        return;
+    }
+
+    let holder = world
+        .try_get::<Leashed>(target)
+        .map(|leashed| leashed.holder);
+    if holder == Some(player) {
+        let drop_lead = *world.get::<Gamemode>(player) != Gamemode::Creative;
+        unleash(game, world, target, drop_lead);
+        return;
+    }
+
+    match event.stack {
+        Some(stack) if stack.ty == Item::Lead && can_be_leashed(world, target) => {
+            leash(game, world, target, player);
+            consume_held_item(game, world, player, stack);
+        }
+        _ => (),
+    }
+}
+
+/// System which breaks the leashes of mobs too far from
+/// their holder, or whose holder is dead, gone or in
+/// another world. Leash knots which no longer hold any
+/// mob are removed.
+#[fecs::system]
+pub fn break_leashes(game: &mut Game, world: &mut World) {
+    let max_distance_squared = MAX_LEASH_DISTANCE * MAX_LEASH_DISTANCE;
+    let mut broken = Vec::new();
+    for (mob, (leashed, pos)) in
+        <(Read<Leashed>, Read<Position>)>::query().iter_entities(world.inner())
+    {
+        let holder = leashed.holder;
+        let held = world.is_alive(holder)
+            && !world.has::<Dead>(holder)
+            && game.world_of(world, holder) == game.world_of(world, mob)
+            && world
+                .try_get::<Position>(holder)
+                .map_or(false, |holder_pos| {
+                    holder_pos.distance_squared_to(*pos) <= max_distance_squared
+                });
+        if !held {
+            broken.push(mob);
+        }
+    }
+    for mob in broken {
+        unleash(game, world, mob, true);
+    }
+
+    let empty_knots: Vec<Entity> = <Read<LeashKnot>>::query()
+        .iter_entities(world.inner())
+        .map(|(knot, _)| knot)
+        .filter(|knot| leashed_to(world, *knot).is_empty())
+        .collect();
+    for knot in empty_knots {
+        game.despawn(knot, world);
+    }
+}
+
+/// When a leashed mob dies, drops its lead.
+#[fecs::event_handler]
+pub fn on_entity_death_drop_lead(event: &EntityDeathEvent, game: &mut Game, world: &mut World) {
+    unleash(game, world, event.entity, true);
+}
+
+/// When a fence with a leash knot is broken,
+/// removes the knot, releasing its mobs.
+#[fecs::event_handler]
+pub fn on_block_update_remove_leash_knots(
+    event: &BlockUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.old.simplified_kind() != SimplifiedBlockKind::Fence
+        || event.new.simplified_kind() == SimplifiedBlockKind::Fence
+    {
+        return;
+    }
+    if let Some(knot) = knot_at(world, event.world, event.pos) {
+        remove_knot(game, world, knot, true);
+    }
+}
+
+/// When an entity is sent to a client, sends the leash
+/// of the entity and those of the mobs it holds.
+#[fecs::event_handler]
+pub fn on_entity_send_send_leashes(event: &EntitySendEvent, world: &mut World) {
+    let network = match world.try_get::<Network>(event.client) {
+        Some(network) => network,
+        None => return,
+    };
+
+    let mut leashes = leashed_to(world, event.entity)
+        .into_iter()
+        .map(|mob| (mob, event.entity))
+        .collect::<Vec<_>>();
+    if let Some(leashed) = world.try_get::<Leashed>(event.entity) {
+        leashes.push((event.entity, leashed.holder));
+    }
+
+    for (mob, holder) in leashes {
+        let (mob_id, holder_id) = match (
+            world.try_get::<NetworkId>(mob),
+            world.try_get::<NetworkId>(holder),
+        ) {
+            (Some(mob_id), Some(holder_id)) => (mob_id.0, holder_id.0),
+            _ => continue,
+        };
+        network.send(AttachEntity {
+            attached_entity_id: mob_id,
+            holding_entity_id: holder_id,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MobKind;
+    use feather_core::blocks::BlockId;
+    use feather_server_types::BlockUpdateCause;
+    use feather_test_framework::Test;
+
+    fn mob(test: &mut Test, kind: MobKind, pos: Position) -> Entity {
+        test.entity(kind.create().with(pos))
+    }
+
+    fn interact(test: &mut Test, player: Entity, target: Entity, stack: Option<ItemStack>) {
+        let event = EntityInteractEvent {
+            player,
+            target,
+            stack,
+        };
+        test.handle(event, on_entity_interact_leash);
+    }
+
+    fn lead_items(test: &Test) -> usize {
+        <Read<ItemStack>>::query()
+            .iter(test.world.inner())
+            .filter(|stack| stack.ty == Item::Lead)
+            .count()
+    }
+
+    #[test]
+    fn leashable_mobs() {
+        let mut test = Test::new();
+        let cow = mob(&mut test, MobKind::Cow, position!(0.0, 64.0, 0.0));
+        let golem = mob(&mut test, MobKind::IronGolem, position!(0.0, 64.0, 0.0));
+        let zombie = mob(&mut test, MobKind::Zombie, position!(0.0, 64.0, 0.0));
+        let villager = mob(&mut test, MobKind::Villager, position!(0.0, 64.0, 0.0));
+        let bat = mob(&mut test, MobKind::Bat, position!(0.0, 64.0, 0.0));
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+
+        assert!(can_be_leashed(&test.world, cow));
+        assert!(can_be_leashed(&test.world, golem));
+        assert!(!can_be_leashed(&test.world, zombie));
+        assert!(!can_be_leashed(&test.world, villager));
+        assert!(!can_be_leashed(&test.world, bat));
+        assert!(!can_be_leashed(&test.world, player));
+    }
+
+    #[test]
+    fn leash_and_unleash_mob() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let cow = mob(&mut test, MobKind::Cow, position!(2.0, 64.0, 0.0));
+
+        interact(&mut test, player, cow, Some(ItemStack::new(Item::Lead, 1)));
+        assert_eq!(*test.world.get::<Leashed>(cow), Leashed { holder: player });
+        let packet = test.sent::<AttachEntity>(player).unwrap();
+        assert_eq!(packet.attached_entity_id, test.id(cow));
+        assert_eq!(packet.holding_entity_id, test.id(player));
+
+        // Players in creative mode don't get their lead back.
+        interact(&mut test, player, cow, None);
+        assert!(!test.world.has::<Leashed>(cow));
+        let packet = test.sent::<AttachEntity>(player).unwrap();
+        assert_eq!(packet.holding_entity_id, -1);
+        assert_eq!(lead_items(&test), 0);
+    }
+
+    #[test]
+    fn leash_breaks_when_too_far() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let cow = mob(&mut test, MobKind::Cow, position!(5.0, 64.0, 0.0));
+        leash(&test.game, &mut test.world, cow, player);
+
+        test.run(break_leashes);
+        assert!(test.world.has::<Leashed>(cow));
+
+        test.position(player, position!(-6.0, 64.0, 0.0));
+        test.run(break_leashes);
+        assert!(!test.world.has::<Leashed>(cow));
+        assert_eq!(lead_items(&test), 1);
+    }
+
+    #[test]
+    fn tie_mobs_to_fence() {
+        let mut test = Test::new();
+        let fence = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            fence,
+            BlockId::oak_fence(),
+            BlockUpdateCause::Unknown,
+        );
+        let player = test.player("", position!(1.0, 64.0, 1.0));
+        let cow = mob(&mut test, MobKind::Cow, position!(3.0, 64.0, 0.0));
+        let far_cow = mob(&mut test, MobKind::Cow, position!(9.0, 64.0, 0.0));
+        leash(&test.game, &mut test.world, cow, player);
+        leash(&test.game, &mut test.world, far_cow, player);
+
+        assert!(tie_to_fence(
+            &mut test.game,
+            &mut test.world,
+            player,
+            WorldId::MAIN,
+            fence
+        ));
+        let knot = knot_at(&test.world, WorldId::MAIN, fence).unwrap();
+        assert_eq!(test.world.get::<Leashed>(cow).holder, knot);
+        assert_eq!(test.world.get::<Leashed>(far_cow).holder, player);
+
+        // Breaking the fence removes the knot.
+        let event = BlockUpdateEvent {
+            pos: fence,
+            world: WorldId::MAIN,
+            old: BlockId::oak_fence(),
+            new: BlockId::air(),
+            cause: BlockUpdateCause::Unknown,
+        };
+        test.handle(event, on_block_update_remove_leash_knots);
+        test.assert_dead(knot);
+        assert!(!test.world.has::<Leashed>(cow));
+        assert_eq!(lead_items(&test), 1);
+    }
+
+    #[test]
+    fn empty_knots_are_removed() {
+        let mut test = Test::new();
+        let cow = mob(&mut test, MobKind::Cow, position!(2.0, 64.0, 0.0));
+        let knot = leash_knot::spawn(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            BlockPosition::new(0, 64, 0),
+        );
+        leash(&test.game, &mut test.world, cow, knot);
+
+        test.run(break_leashes);
+        test.assert_alive(knot);
+
+        unleash(&mut test.game, &mut test.world, cow, false);
+        test.run(break_leashes);
+        test.assert_dead(knot);
+    }
+
+    #[test]
+    fn right_click_knot_breaks_it() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let cow = mob(&mut test, MobKind::Cow, position!(2.0, 64.0, 0.0));
+        let knot = leash_knot::spawn(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            BlockPosition::new(0, 64, 0),
+        );
+        leash(&test.game, &mut test.world, cow, knot);
+
+        interact(&mut test, player, knot, None);
+        test.assert_dead(knot);
+        assert!(!test.world.has::<Leashed>(cow));
+    }
+}
@@ -7,27 +7,82 @@
 extern crate feather_core;
 
 mod broadcasters;
+mod burning;
 pub mod drops;
+mod explosion;
 mod fall_damage;
 mod inventory;
+mod leash;
+mod lightning;
+pub mod map;
 mod mob;
+mod name_tag;
 mod object;
 pub mod particle;
+mod riding;
+mod spawning;
+mod status_effects;
+mod vehicle;
+mod void_damage;
 
-pub use self::inventory::InventoryExt;
+pub use self::inventory::{consume_held_item, InventoryExt};
 pub use broadcasters::*;
+pub use burning::{burn_entities, extinguish, set_on_fire};
 pub use drops::on_block_break_drop_loot;
+pub use explosion::{explode, BlockDamage};
 pub use fall_damage::update_blocks_fallen;
+pub use leash::{
+    break_leash_knot, break_leashes, can_be_leashed, leash, leashed_to,
+    on_block_update_remove_leash_knots, on_entity_death_drop_lead, on_entity_interact_leash,
+    on_entity_send_send_leashes, tie_to_fence, unleash, Leashed,
+};
+pub use lightning::{strike_lightning, strike_lightning_in_storms};
 pub use mob::*;
+pub use name_tag::on_entity_interact_apply_name_tag;
+pub use object::armor_stand::{
+    hit_armor_stand, on_item_use_on_block_place_armor_stand, use_armor_stand, ArmorStand,
+};
+pub use object::arrow::{update_arrows, Arrow};
+pub use object::boat::{hit_boat, move_boats, place_boat, set_paddles, Boat, BoatType};
+pub use object::end_crystal::{on_item_use_on_block_place_end_crystal, EndCrystal};
+pub use object::experience_orb::{experience_orb_collect, spawn_experience_orbs};
 pub use object::falling_block::{on_entity_land_remove_falling_block, spawn_falling_blocks};
+pub use object::firework_rocket::{update_firework_rockets, FireworkRocket};
+pub use object::hanging::{
+    break_hanging, hit_hanging, on_block_update_break_hanging_entities,
+    on_item_use_on_block_place_hanging, Hanging,
+};
 pub use object::item::{item_collect, on_item_drop_spawn_item_entity};
+pub use object::item_frame::{
+    frame_signal, on_entity_interact_use_item_frame, on_entity_send_send_framed_map, ItemFrame,
+};
+pub use object::leash_knot::LeashKnot;
+pub use object::minecart::{
+    collect_items_into_hopper_minecarts, hit_minecart, move_minecarts,
+    on_entity_interact_open_minecart, on_item_use_on_block_place_minecart, Minecart, MinecartKind,
+};
+pub use object::painting::{Motive, Painting};
+pub use object::tnt::{explode_primed_tnt, PrimedTnt};
 pub use object::*;
+pub use riding::{
+    boost_steered_mob, can_steer, on_entity_death_drop_saddle, on_entity_interact_ride_mob,
+    steer_ridden_mobs, tame, Ridable, Saddled, Taming,
+};
+pub use spawning::{despawn_mobs, is_valid_spawn, spawn_mob, spawn_mobs};
+pub use status_effects::{on_entity_death_clear_status_effects, tick_status_effects};
+pub use vehicle::{
+    carry_passengers, dismount, driven_vehicle, mount, on_entity_death_dismount,
+    on_entity_despawn_dismount, on_entity_interact_mount_vehicle, on_entity_send_send_passengers,
+    Riding, Vehicle,
+};
+pub use void_damage::damage_in_void;
 
 extern crate nalgebra_glm as glm;
 
 use feather_core::util::Position;
 use feather_server_types::{
-    ChunkCrossEvent, Game, NetworkId, PreviousPosition, PreviousVelocity, Velocity,
+    ChunkCrossEvent, EntityDespawnEvent, Game, NetworkId, Player, PreviousPosition,
+    PreviousVelocity, Velocity,
 };
 use fecs::{EntityBuilder, IntoQuery, Read, World, Write};
 use std::sync::atomic::{AtomicI32, Ordering};
@@ -51,11 +106,50 @@ pub fn previous_position_velocity_reset(world: &mut World) {
     );
 }
 
+/// Marks the chunk an entity left as unsaved, so
+/// the entity is no longer saved in it.
 #[fecs::event_handler]
-pub fn on_chunk_cross_mark_modified(event: &ChunkCrossEvent, game: &mut Game) {
+pub fn on_chunk_cross_mark_unsaved(event: &ChunkCrossEvent, game: &mut Game, world: &mut World) {
     if let Some(pos) = event.old {
-        if let Some(mut old_chunk) = game.chunk_map.chunk_at_mut(pos) {
-            old_chunk.set_modified()
+        let world_id = game.world_of(world, event.entity);
+        game.worlds[world_id].dirty_chunks.mark_unsaved(pos);
+    }
+}
+
+/// Marks the chunk of a despawned entity as unsaved.
+#[fecs::event_handler]
+pub fn on_entity_despawn_mark_unsaved(
+    event: &EntityDespawnEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if let Some(pos) = world.try_get::<Position>(event.entity) {
+        let world_id = game.world_of(world, event.entity);
+        game.worlds[world_id].dirty_chunks.mark_unsaved(pos.chunk());
+    }
+}
+
+/// Marks the chunks containing entities which moved
+/// during the tick as unsaved. Players are saved
+/// separately, so they are skipped.
+#[fecs::system]
+pub fn mark_moved_entities_unsaved(game: &mut Game, world: &mut World) {
+    let mut moved = Vec::new();
+    for (entity, (pos, previous_pos)) in
+        <(Read<Position>, Read<PreviousPosition>)>::query().iter_entities(world.inner())
+    {
+        if previous_pos.0 != Some(*pos) {
+            moved.push((entity, pos.chunk()));
+        }
+    }
+
+    for (entity, chunk) in moved {
+        if world.has::<Player>(entity) {
+            continue;
+        }
+        let world_id = game.world_of(world, entity);
+        if let Some(state) = game.worlds.get_mut(world_id) {
+            state.dirty_chunks.mark_unsaved(chunk);
         }
     }
 }
@@ -0,0 +1,239 @@
+//! Lightning, which strikes during thunderstorms. It sets
+//! the ground on fire and burns and damages entities near
+//! it. Some mobs turn into others when struck.
+
+use crate::mob::{creeper::Creeper, pig::Pig, villager::Villager};
+use crate::{mob, new_id, set_on_fire};
+use feather_core::blocks::BlockId;
+use feather_core::entitymeta::Metadata;
+use feather_core::network::packets::{SoundCategory, SpawnGlobalEntity};
+use feather_core::util::{BlockPosition, Dimension, Position};
+use feather_server_types::{
+    BlockUpdateCause, DamageSource, Dead, EntitySpawnEvent, Game, Health, Sound, Uuid, WorldId,
+};
+use feather_server_util::nearby_entities;
+use fecs::{Entity, EntityBuilder, World};
+use rand::Rng;
+
+/// One in this many block-ticking chunks is struck
+/// by lightning each tick during a thunderstorm.
+const LIGHTNING_CHANCE: u32 = 100_000;
+/// Type of lightning bolts in the Spawn Global Entity packet.
+const LIGHTNING_BOLT_TYPE: u8 = 1;
+/// Damage dealt to entities struck by lightning.
+const LIGHTNING_DAMAGE: u32 = 5;
+/// Seconds entities struck by lightning burn for.
+const LIGHTNING_FIRE_SECONDS: u32 = 8;
+/// Number of fires lit around the bolt, besides
+/// the one where it strikes.
+const EXTRA_FIRES: usize = 4;
+/// Health of a zombie pigman which hasn't taken any damage.
+const ZOMBIE_PIGMAN_HEALTH: u32 = 20;
+/// Health of a witch which hasn't taken any damage.
+const WITCH_HEALTH: u32 = 26;
+
+/// System which strikes random places in the rain
+/// with lightning during a thunderstorm.
+#[fecs::system]
+pub fn strike_lightning_in_storms(game: &mut Game, world: &mut World) {
+    if !game.is_thundering() {
+        return;
+    }
+
+    let mut strikes = Vec::new();
+    for state in game.worlds.iter() {
+        if state.dimension != Dimension::Overwold {
+            continue;
+        }
+        for pos in state.chunk_tickets.loaded_chunks() {
+            if !state.chunk_tickets.is_block_ticking(pos) {
+                continue;
+            }
+            let mut rng = game.rng();
+            if rng.gen_range(0, LIGHTNING_CHANCE) != 0 {
+                continue;
+            }
+            let chunk = match state.chunk_map.chunk_at(pos) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            // Lightning strikes the highest block in a random column.
+            let (x, z) = (rng.gen_range(0, 16), rng.gen_range(0, 16));
+            let y = chunk.min_y() + i32::from(chunk.heightmap(x, z).motion_blocking());
+            let target = BlockPosition::new(pos.x * 16 + x as i32, y, pos.z * 16 + z as i32);
+            if game.is_raining_at(state.id, target) {
+                strikes.push((state.id, target));
+            }
+        }
+    }
+
+    for (world_id, pos) in strikes {
+        let pos = pos.position() + glm::vec3(0.5, 0.0, 0.5);
+        strike_lightning(game, world, world_id, pos);
+    }
+}
+
+/// Strikes lightning at a position, lighting fires and
+/// striking the entities nearby.
+pub fn strike_lightning(game: &mut Game, world: &mut World, world_id: WorldId, pos: Position) {
+    let packet = SpawnGlobalEntity {
+        entity_id: new_id(),
+        ty: LIGHTNING_BOLT_TYPE,
+        x: pos.x,
+        y: pos.y,
+        z: pos.z,
+    };
+    game.broadcast_chunk_update(world, packet, world_id, pos.chunk(), None);
+
+    let (thunder_pitch, impact_pitch) = {
+        let mut rng = game.rng();
+        (0.8 + rng.gen_range(0.0, 0.2), 0.5 + rng.gen_range(0.0, 0.2))
+    };
+    let thunder = Sound::new("entity.lightning_bolt.thunder", SoundCategory::Weather)
+        .volume(10_000.0)
+        .pitch(thunder_pitch);
+    let impact = Sound::new("entity.lightning_bolt.impact", SoundCategory::Weather)
+        .volume(2.0)
+        .pitch(impact_pitch);
+    game.play_sound(world, world_id, pos, &thunder);
+    game.play_sound(world, world_id, pos, &impact);
+
+    // Fire is only lit on normal and hard difficulty.
+    if game.game_rules.do_fire_tick && game.level.difficulty >= 2 {
+        light_fires(game, world, world_id, pos.block());
+    }
+
+    let struck = nearby_entities(
+        world,
+        game,
+        world_id,
+        pos + glm::vec3(0.0, 3.0, 0.0),
+        glm::vec3(3.0, 6.0, 3.0),
+    );
+    for entity in struck {
+        if world.is_alive(entity) && world.has::<Health>(entity) && !world.has::<Dead>(entity) {
+            strike_entity(game, world, entity);
+        }
+    }
+}
+
+/// Lights a fire where lightning strikes and a few around it.
+fn light_fires(game: &mut Game, world: &mut World, world_id: WorldId, pos: BlockPosition) {
+    let mut targets = vec![pos];
+    {
+        let mut rng = game.rng();
+        for _ in 0..EXTRA_FIRES {
+            targets.push(
+                pos + BlockPosition::new(
+                    rng.gen_range(-1, 2),
+                    rng.gen_range(-1, 2),
+                    rng.gen_range(-1, 2),
+                ),
+            );
+        }
+    }
+
+    for target in targets {
+        let can_burn = game
+            .block_at(world_id, target)
+            .map_or(false, BlockId::is_air)
+            && game
+                .block_at(world_id, target.down())
+                .map_or(false, BlockId::is_full_block);
+        if can_burn {
+            game.set_block_at(
+                world,
+                world_id,
+                target,
+                BlockId::fire(),
+                BlockUpdateCause::Fire,
+            );
+        }
+    }
+}
+
+/// Strikes an entity with lightning. Pigs turn into zombie
+/// pigmen, villagers into witches and creepers become charged.
+/// Other entities are damaged and set on fire.
+fn strike_entity(game: &mut Game, world: &mut World, entity: Entity) {
+    if world.has::<Pig>(entity) {
+        convert(
+            game,
+            world,
+            entity,
+            mob::zombie_pigman::create(),
+            ZOMBIE_PIGMAN_HEALTH,
+        );
+        return;
+    }
+    if world.has::<Villager>(entity) {
+        convert(game, world, entity, mob::witch::create(), WITCH_HEALTH);
+        return;
+    }
+    if world.has::<Creeper>(entity) {
+        if let Some(mut metadata) = world.try_get_mut::<Metadata>(entity) {
+            if let Metadata::Creeper(meta) = &mut *metadata {
+                meta.set_charged(true);
+            }
+        }
+    }
+
+    game.damage(entity, LIGHTNING_DAMAGE, DamageSource::Lightning, world);
+    if world.is_alive(entity) && !world.has::<Dead>(entity) {
+        set_on_fire(game, world, entity, LIGHTNING_FIRE_SECONDS);
+    }
+}
+
+/// Replaces a mob with a new one in the same place.
+fn convert(game: &mut Game, world: &mut World, old: Entity, builder: EntityBuilder, health: u32) {
+    let pos = *world.get::<Position>(old);
+    let world_id = game.world_of(world, old);
+    game.despawn(old, world);
+
+    let entity = builder
+        .with(pos)
+        .with(world_id)
+        .with(Health(health))
+        .with(Uuid::new_v4())
+        .build()
+        .spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_types::Burning;
+    use feather_test_framework::Test;
+    use fecs::{IntoQuery, Read};
+
+    #[test]
+    fn pigs_turn_into_zombie_pigmen() {
+        let mut test = Test::new();
+        let pos = position!(0.5, 64.0, 0.5);
+        let pig = test.entity(mob::pig::create().with(pos).with(Health(10)));
+
+        strike_lightning(&mut test.game, &mut test.world, WorldId::MAIN, pos);
+        test.assert_dead(pig);
+        let pigmen = <Read<mob::zombie_pigman::ZombiePigman>>::query()
+            .iter(test.world.inner())
+            .count();
+        assert_eq!(pigmen, 1);
+    }
+
+    #[test]
+    fn creepers_become_charged() {
+        let mut test = Test::new();
+        let pos = position!(0.5, 64.0, 0.5);
+        let creeper = test.entity(mob::creeper::create().with(pos).with(Health(20)));
+
+        strike_lightning(&mut test.game, &mut test.world, WorldId::MAIN, pos);
+        match &*test.world.get::<Metadata>(creeper) {
+            Metadata::Creeper(meta) => assert!(meta.charged()),
+            _ => panic!("creeper lost its metadata"),
+        }
+        assert_eq!(test.world.get::<Health>(creeper).0, 15);
+        assert!(test.world.has::<Burning>(creeper));
+    }
+}
@@ -0,0 +1,453 @@
+//! Filled map items, which show a drawing of the terrain
+//! around the players holding them.
+//!
+//! Maps are drawn a few columns of pixels each tick, like
+//! in vanilla. The pixels changed are sent to the players
+//! holding the map, along with markers for those players.
+
+use crate::InventoryExt;
+use feather_core::blocks::MapColor;
+use feather_core::chunk::Chunk;
+use feather_core::inventory::{Area, SlotIndex};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::MapIcon;
+use feather_core::util::{ChunkPosition, Position};
+use feather_server_types::{
+    map_color_id, Game, HeldItem, Inventory, InventoryUpdateEvent, MapRegion, MapShade, MapState,
+    Network, WorldState, MAP_SIZE,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+use smallvec::smallvec;
+
+/// The number of ticks over which all columns of a map are drawn.
+const RENDER_INTERVAL: usize = 16;
+/// The number of ticks between updates of the icons on a map.
+const ICON_INTERVAL: u64 = 5;
+
+/// Icon of a player on the map.
+const ICON_PLAYER: i32 = 0;
+/// Icon of a player beyond the edges of the map.
+const ICON_PLAYER_OFF_MAP: i32 = 6;
+
+/// Component storing the ID of the map whose pixels were
+/// all sent to a player, after which only changes are sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SyncedMap(pub i32);
+
+/// A player holding a map, with the map's ID.
+#[derive(Copy, Clone, Debug)]
+struct Holder {
+    player: Entity,
+    map: i32,
+    pos: Position,
+}
+
+/// Returns the players holding a filled map of their world.
+fn holders(game: &Game, world: &World) -> Vec<Holder> {
+    let mut holders = vec![];
+    for (player, (pos, inventory, _)) in
+        <(Read<Position>, Read<Inventory>, Read<HeldItem>)>::query().iter_entities(world.inner())
+    {
+        let map = match inventory.item_in_main_hand(player, world) {
+            Some(ItemStack {
+                ty: Item::FilledMap,
+                map: Some(map),
+                ..
+            }) => map,
+            _ => continue,
+        };
+
+        match game.maps.get(map) {
+            Some(state) if state.world == game.world_of(world, player) => holders.push(Holder {
+                player,
+                map,
+                pos: *pos,
+            }),
+            _ => (),
+        }
+    }
+    holders
+}
+
+/// System which draws the terrain around players
+/// holding maps onto the maps.
+#[fecs::system]
+pub fn render_maps(game: &mut Game, world: &mut World) {
+    let column = (game.tick_count % RENDER_INTERVAL as u64) as usize;
+    for holder in holders(game, world) {
+        render(game, &holder, column);
+    }
+}
+
+/// Draws the given columns of pixels of a map within
+/// sight of its holder.
+fn render(game: &mut Game, holder: &Holder, first_column: usize) {
+    let map = match game.maps.get_mut(holder.map) {
+        Some(map) if !map.locked => map,
+        _ => return,
+    };
+    let world = &game.worlds[map.world];
+
+    let blocks_per_pixel = map.blocks_per_pixel();
+    let (holder_x, holder_z) = map.pixel_at(holder.pos.x, holder.pos.z);
+    let radius = f64::from(MAP_SIZE as i32 / blocks_per_pixel);
+
+    for x in (first_column..MAP_SIZE).step_by(RENDER_INTERVAL) {
+        // Pixels are shaded by the height of the
+        // pixel north of them, so start a row above.
+        let mut previous_height = None;
+        for z in -1..MAP_SIZE as i32 {
+            let (block_x, block_z) = map.block_at_pixel(x as i32, z);
+            let sample = match sample(world, block_x, block_z) {
+                Some(sample) => sample,
+                None => {
+                    previous_height = None;
+                    continue;
+                }
+            };
+
+            let dx = x as f64 - holder_x;
+            let dz = f64::from(z) - holder_z;
+            if let Some(previous_height) = previous_height {
+                if z >= 0 && dx * dx + dz * dz <= radius * radius {
+                    let shade = sample.shade(previous_height, blocks_per_pixel, x as i32 + z);
+                    map.set_color(x, z as usize, map_color_id(sample.color, shade));
+                }
+            }
+            previous_height = Some(sample.height);
+        }
+    }
+}
+
+/// The block drawn on a map for a column of blocks.
+struct Sample {
+    color: Option<MapColor>,
+    /// Y coordinate of the block plus one.
+    height: i32,
+    /// Depth of the water, if the block is water.
+    water_depth: i32,
+}
+
+impl Sample {
+    /// Returns the shade of this block given the height of the
+    /// block north of it. Water is shaded by its depth instead.
+    ///
+    /// Like in vanilla, pixels are dithered in a checkerboard
+    /// pattern so that gentle slopes and depths are visible.
+    fn shade(&self, previous_height: i32, blocks_per_pixel: i32, parity: i32) -> MapShade {
+        let checker = f64::from(parity & 1);
+        if self.color == Some(MapColor::Water) {
+            let depth = f64::from(self.water_depth) * 0.1 + checker * 0.2;
+            if depth < 0.5 {
+                MapShade::Light
+            } else if depth > 0.9 {
+                MapShade::Dark
+            } else {
+                MapShade::Normal
+            }
+        } else {
+            let slope = f64::from(self.height - previous_height) * 4.0
+                / f64::from(blocks_per_pixel + 4)
+                + (checker - 0.5) * 0.4;
+            if slope > 0.6 {
+                MapShade::Light
+            } else if slope < -0.6 {
+                MapShade::Dark
+            } else {
+                MapShade::Normal
+            }
+        }
+    }
+}
+
+/// Finds the highest block with a map color at the given
+/// coordinates, or `None` if the chunk isn't loaded.
+fn sample(world: &WorldState, x: i32, z: i32) -> Option<Sample> {
+    let chunk = world
+        .chunk_map
+        .chunk_at(ChunkPosition::new(x >> 4, z >> 4))?;
+    let (x, z) = ((x & 15) as usize, (z & 15) as usize);
+
+    let mut y = chunk.heightmap(x, z).world_surface() as usize;
+    while y > 0 {
+        y -= 1;
+        let color = chunk.block_at(x, y, z).kind().map_color();
+        if color.is_some() {
+            let water_depth = if color == Some(MapColor::Water) {
+                water_depth(&chunk, x, y, z)
+            } else {
+                0
+            };
+            return Some(Sample {
+                color,
+                height: y as i32 + 1,
+                water_depth,
+            });
+        }
+    }
+
+    Some(Sample {
+        color: None,
+        height: 0,
+        water_depth: 0,
+    })
+}
+
+fn water_depth(chunk: &Chunk, x: usize, mut y: usize, z: usize) -> i32 {
+    let mut depth = 0;
+    while chunk.block_at(x, y, z).kind().map_color() == Some(MapColor::Water) {
+        depth += 1;
+        if y == 0 {
+            break;
+        }
+        y -= 1;
+    }
+    depth
+}
+
+/// System which sends the pixels of maps changed since the last
+/// tick to the players holding them, along with the icons of
+/// those players. Players who just started holding a map are
+/// sent all of its pixels.
+#[fecs::system]
+pub fn send_map_data(game: &mut Game, world: &mut World) {
+    let holders = holders(game, world);
+
+    // Players no longer holding a map may miss changes
+    // to it, so they are sent all pixels again later.
+    let mut unsynced = vec![];
+    for (player, synced) in <Read<SyncedMap>>::query().iter_entities(world.inner()) {
+        if !holders
+            .iter()
+            .any(|holder| holder.player == player && holder.map == synced.0)
+        {
+            unsynced.push(player);
+        }
+    }
+    for player in unsynced {
+        let _ = world.remove::<SyncedMap>(player);
+    }
+
+    let mut regions: Vec<(i32, Option<MapRegion>)> = vec![];
+    for holder in &holders {
+        let region = match regions.iter().find(|(map, _)| *map == holder.map) {
+            Some((_, region)) => *region,
+            None => {
+                let region = game.maps.get_mut(holder.map).unwrap().take_dirty_region();
+                regions.push((holder.map, region));
+                region
+            }
+        };
+
+        let synced = world.has::<SyncedMap>(holder.player);
+        let region = if synced {
+            region
+        } else {
+            Some(MapRegion::FULL)
+        };
+        if region.is_none() && game.tick_count % ICON_INTERVAL != 0 {
+            continue;
+        }
+
+        let map = game.maps.get(holder.map).unwrap();
+        let icons = holders
+            .iter()
+            .filter(|other| other.map == holder.map)
+            .map(|other| icon(map, other.pos))
+            .collect();
+        world
+            .get::<Network>(holder.player)
+            .send(map.packet(holder.map, region, icons));
+
+        if !synced {
+            world.add(holder.player, SyncedMap(holder.map)).unwrap();
+        }
+    }
+}
+
+/// Returns the icon of a player at the given position.
+fn icon(map: &MapState, pos: Position) -> MapIcon {
+    let (x, z) = map.pixel_at(pos.x, pos.z);
+    // Icon coordinates are in half pixels from the center.
+    let x = (x - (MAP_SIZE / 2) as f64) * 2.0;
+    let z = (z - (MAP_SIZE / 2) as f64) * 2.0;
+
+    let on_map = (-128.0..128.0).contains(&x) && (-128.0..128.0).contains(&z);
+    let (kind, direction) = if on_map {
+        let yaw = f64::from(pos.yaw);
+        let yaw = yaw + if yaw < 0.0 { -8.0 } else { 8.0 };
+        (ICON_PLAYER, (yaw * 16.0 / 360.0) as i32 & 15)
+    } else {
+        (ICON_PLAYER_OFF_MAP, 0)
+    };
+
+    MapIcon {
+        kind,
+        x: x.max(-128.0).min(127.0) as i8,
+        z: z.max(-128.0).min(127.0) as i8,
+        direction: direction as i8,
+        display_name: None,
+    }
+}
+
+/// Turns the empty map held by a player into a filled
+/// map of a new map around them.
+pub fn fill_held_map(game: &mut Game, world: &mut World, player: Entity) {
+    let held_item = world.get::<HeldItem>(player).0;
+    let held_slot = SlotIndex {
+        area: Area::Hotbar,
+        slot: held_item,
+    };
+    let pos = *world.get::<Position>(player);
+    let map = MapState::new(game.world_of(world, player), pos.x, pos.z, 0);
+
+    let mut filled = ItemStack::new(Item::FilledMap, 1);
+    filled.map = Some(game.maps.len() as i32);
+
+    let slots = {
+        let inventory = world.get::<Inventory>(player);
+        let amount = match inventory.item_at(Area::Hotbar, held_item) {
+            Ok(Some(stack)) if stack.ty == Item::Map => stack.amount,
+            _ => return,
+        };
+
+        if amount == 1 {
+            inventory
+                .set_item_at(Area::Hotbar, held_item, filled)
+                .unwrap();
+            smallvec![held_slot]
+        } else {
+            let (mut slots, remaining) = inventory.collect_item(filled);
+            if remaining > 0 {
+                // No room for the filled map
+                return;
+            }
+            inventory
+                .set_item_at(
+                    Area::Hotbar,
+                    held_item,
+                    ItemStack::new(Item::Map, amount - 1),
+                )
+                .unwrap();
+            slots.push(held_slot);
+            slots
+        }
+    };
+
+    game.maps.add(map);
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots,
+            entity: player,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::network::packets::MapData;
+    use feather_core::util::BlockPosition;
+    use feather_server_types::WorldId;
+    use feather_test_framework::Test;
+
+    fn hold_map(test: &mut Test, player: Entity) -> i32 {
+        let pos = *test.world.get::<Position>(player);
+        let id = test
+            .game
+            .maps
+            .add(MapState::new(WorldId::MAIN, pos.x, pos.z, 0));
+
+        let mut stack = ItemStack::new(Item::FilledMap, 1);
+        stack.map = Some(id);
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, stack)
+            .unwrap();
+        id
+    }
+
+    #[test]
+    fn render_and_send() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 65.0, 0.5));
+        let id = hold_map(&mut test, player);
+
+        test.game.worlds[WorldId::MAIN]
+            .chunk_map
+            .set_block_at(BlockPosition::new(0, 64, 0), BlockId::grass_block());
+
+        // Pixel 64 is drawn in the first tick.
+        test.run(render_maps);
+        let grass = map_color_id(Some(MapColor::Grass), MapShade::Light);
+        let map = test.game.maps.get(id).unwrap();
+        assert_eq!(map.color(64, 64), grass);
+        assert_eq!(
+            map.dirty_region(),
+            Some(MapRegion {
+                min_x: 64,
+                min_z: 64,
+                max_x: 64,
+                max_z: 64,
+            })
+        );
+
+        // All pixels are sent to a player who starts holding the map.
+        test.run(send_map_data);
+        let packet = test.sent::<MapData>(player).unwrap();
+        assert_eq!(packet.map_id, id);
+        assert_eq!((packet.columns, packet.rows), (128, 128));
+        assert_eq!(packet.data[64 + 64 * MAP_SIZE], grass);
+        assert_eq!(
+            packet.icons,
+            vec![MapIcon {
+                kind: ICON_PLAYER,
+                x: 1,
+                z: 1,
+                direction: 0,
+                display_name: None,
+            }]
+        );
+        assert_eq!(test.game.maps.get(id).unwrap().dirty_region(), None);
+
+        // Afterwards, only changes are sent.
+        test.game.tick_count = 1;
+        test.run(send_map_data);
+        assert!(test.sent::<MapData>(player).is_none());
+
+        test.game.maps.get_mut(id).unwrap().set_color(3, 4, grass);
+        test.run(send_map_data);
+        let packet = test.sent::<MapData>(player).unwrap();
+        assert_eq!(
+            (packet.columns, packet.rows, packet.x, packet.z),
+            (1, 1, 3, 4)
+        );
+        assert_eq!(packet.data, vec![grass]);
+    }
+
+    #[test]
+    fn fill_empty_map() {
+        let mut test = Test::new();
+        let player = test.player("", position!(100.0, 65.0, -10.0));
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, ItemStack::new(Item::Map, 2))
+            .unwrap();
+
+        fill_held_map(&mut test.game, &mut test.world, player);
+
+        let map = test.game.maps.get(0).unwrap();
+        assert_eq!((map.center_x, map.center_z), (128, 0));
+
+        let inventory = test.world.get::<Inventory>(player);
+        assert_eq!(
+            inventory.item_at(Area::Hotbar, 0).unwrap(),
+            Some(ItemStack::new(Item::Map, 1))
+        );
+        let mut filled = ItemStack::new(Item::FilledMap, 1);
+        filled.map = Some(0);
+        assert_eq!(inventory.item_at(Area::Hotbar, 1).unwrap(), Some(filled));
+    }
+}
@@ -8,11 +8,12 @@ mod passive;
 
 pub use boss::*;
 pub use defensive::*;
-use feather_core::entitymeta::EntityMetadata;
+use feather_core::anvil::entity::{AnimalData, BaseEntityData};
+use feather_core::entitymeta::{model, EntityMetadata, Metadata};
 use feather_core::network::packets::SpawnMob;
 use feather_core::network::Packet;
-use feather_core::util::Position;
-use feather_server_types::{NetworkId, SpawnPacketCreator, Uuid, Velocity};
+use feather_core::util::{Position, Vec3d};
+use feather_server_types::{Health, NetworkId, SpawnPacketCreator, Uuid, Velocity};
 use feather_server_util::{degrees_to_stops, protocol_velocity};
 use fecs::{EntityBuilder, EntityRef};
 pub use hostile::*;
@@ -88,10 +89,436 @@ pub enum MobKind {
     Phantom = 90,
 }
 
+/// Every kind of mob.
+const ALL_MOB_KINDS: [MobKind; 57] = [
+    MobKind::Bat,
+    MobKind::Blaze,
+    MobKind::CaveSpider,
+    MobKind::Chicken,
+    MobKind::Cod,
+    MobKind::Cow,
+    MobKind::Creeper,
+    MobKind::Donkey,
+    MobKind::Dolphin,
+    MobKind::Drowned,
+    MobKind::ElderGuardian,
+    MobKind::EnderDragon,
+    MobKind::Enderman,
+    MobKind::Endermite,
+    MobKind::EvocationIllager,
+    MobKind::Ghast,
+    MobKind::Giant,
+    MobKind::Guardian,
+    MobKind::Horse,
+    MobKind::Husk,
+    MobKind::IllusionIllager,
+    MobKind::Llama,
+    MobKind::MagmaCube,
+    MobKind::Mule,
+    MobKind::MushroomCow,
+    MobKind::Ocelot,
+    MobKind::Parrot,
+    MobKind::Pig,
+    MobKind::Pufferfish,
+    MobKind::PigZombie,
+    MobKind::PolarBear,
+    MobKind::Rabbit,
+    MobKind::Salmon,
+    MobKind::Sheep,
+    MobKind::Shulker,
+    MobKind::Silverfish,
+    MobKind::Skeleton,
+    MobKind::SkeletonHorse,
+    MobKind::Slime,
+    MobKind::SnowGolem,
+    MobKind::Spider,
+    MobKind::Squid,
+    MobKind::Stray,
+    MobKind::TropicalFish,
+    MobKind::Turtle,
+    MobKind::Vex,
+    MobKind::Villager,
+    MobKind::IronGolem,
+    MobKind::VindicationIllager,
+    MobKind::Witch,
+    MobKind::Wither,
+    MobKind::WitherSkeleton,
+    MobKind::Wolf,
+    MobKind::Zombie,
+    MobKind::ZombieHorse,
+    MobKind::ZombieVillager,
+    MobKind::Phantom,
+];
+
+/// Category of mobs which spawn naturally. The number of
+/// mobs in each category near players is limited separately.
+///
+/// This is a component of all mobs which have a category.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MobCategory {
+    Monster,
+    Creature,
+    Ambient,
+    WaterCreature,
+}
+
+impl MobCategory {
+    /// Every mob category.
+    pub const ALL: [MobCategory; 4] = [
+        MobCategory::Monster,
+        MobCategory::Creature,
+        MobCategory::Ambient,
+        MobCategory::WaterCreature,
+    ];
+
+    /// Returns the greatest number of mobs in this category
+    /// around a single player. The limit for a world grows
+    /// with the number of chunks around its players.
+    pub fn max_count(self) -> usize {
+        match self {
+            MobCategory::Monster => 70,
+            MobCategory::Creature => 10,
+            MobCategory::Ambient => 15,
+            MobCategory::WaterCreature => 5,
+        }
+    }
+}
+
+impl MobKind {
+    /// Returns whether mobs of this kind are monsters, which keep
+    /// players nearby from sleeping. Slimes, ghasts and phantoms
+    /// are hostile but not monsters.
+    pub fn is_monster(self) -> bool {
+        matches!(
+            self,
+            MobKind::Blaze
+                | MobKind::CaveSpider
+                | MobKind::Creeper
+                | MobKind::Drowned
+                | MobKind::ElderGuardian
+                | MobKind::Enderman
+                | MobKind::Endermite
+                | MobKind::EvocationIllager
+                | MobKind::Giant
+                | MobKind::Guardian
+                | MobKind::Husk
+                | MobKind::IllusionIllager
+                | MobKind::PigZombie
+                | MobKind::Silverfish
+                | MobKind::Skeleton
+                | MobKind::Spider
+                | MobKind::Stray
+                | MobKind::Vex
+                | MobKind::VindicationIllager
+                | MobKind::Witch
+                | MobKind::Wither
+                | MobKind::WitherSkeleton
+                | MobKind::Zombie
+                | MobKind::ZombieVillager
+        )
+    }
+
+    /// Returns the category this kind of mob counts towards
+    /// for natural spawning, or `None` for mobs which never
+    /// spawn naturally, like golems and villagers.
+    pub fn category(self) -> Option<MobCategory> {
+        if self.is_monster() {
+            return Some(MobCategory::Monster);
+        }
+        match self {
+            MobKind::EnderDragon
+            | MobKind::Ghast
+            | MobKind::MagmaCube
+            | MobKind::Phantom
+            | MobKind::Shulker
+            | MobKind::Slime => Some(MobCategory::Monster),
+            MobKind::Chicken
+            | MobKind::Cow
+            | MobKind::Donkey
+            | MobKind::Horse
+            | MobKind::Llama
+            | MobKind::MushroomCow
+            | MobKind::Mule
+            | MobKind::Ocelot
+            | MobKind::Parrot
+            | MobKind::Pig
+            | MobKind::PolarBear
+            | MobKind::Rabbit
+            | MobKind::Sheep
+            | MobKind::SkeletonHorse
+            | MobKind::Turtle
+            | MobKind::Wolf
+            | MobKind::ZombieHorse => Some(MobCategory::Creature),
+            MobKind::Bat => Some(MobCategory::Ambient),
+            MobKind::Cod
+            | MobKind::Dolphin
+            | MobKind::Pufferfish
+            | MobKind::Salmon
+            | MobKind::Squid
+            | MobKind::TropicalFish => Some(MobCategory::WaterCreature),
+            _ => None,
+        }
+    }
+
+    /// Returns the namespaced ID of this kind of mob,
+    /// as used by spawners and commands.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            MobKind::Bat => "minecraft:bat",
+            MobKind::Blaze => "minecraft:blaze",
+            MobKind::CaveSpider => "minecraft:cave_spider",
+            MobKind::Chicken => "minecraft:chicken",
+            MobKind::Cod => "minecraft:cod",
+            MobKind::Cow => "minecraft:cow",
+            MobKind::Creeper => "minecraft:creeper",
+            MobKind::Donkey => "minecraft:donkey",
+            MobKind::Dolphin => "minecraft:dolphin",
+            MobKind::Drowned => "minecraft:drowned",
+            MobKind::ElderGuardian => "minecraft:elder_guardian",
+            MobKind::EnderDragon => "minecraft:ender_dragon",
+            MobKind::Enderman => "minecraft:enderman",
+            MobKind::Endermite => "minecraft:endermite",
+            MobKind::EvocationIllager => "minecraft:evoker",
+            MobKind::Ghast => "minecraft:ghast",
+            MobKind::Giant => "minecraft:giant",
+            MobKind::Guardian => "minecraft:guardian",
+            MobKind::Horse => "minecraft:horse",
+            MobKind::Husk => "minecraft:husk",
+            MobKind::IllusionIllager => "minecraft:illusioner",
+            MobKind::Llama => "minecraft:llama",
+            MobKind::MagmaCube => "minecraft:magma_cube",
+            MobKind::Mule => "minecraft:mule",
+            MobKind::MushroomCow => "minecraft:mooshroom",
+            MobKind::Ocelot => "minecraft:ocelot",
+            MobKind::Parrot => "minecraft:parrot",
+            MobKind::Pig => "minecraft:pig",
+            MobKind::Pufferfish => "minecraft:pufferfish",
+            MobKind::PigZombie => "minecraft:zombie_pigman",
+            MobKind::PolarBear => "minecraft:polar_bear",
+            MobKind::Rabbit => "minecraft:rabbit",
+            MobKind::Salmon => "minecraft:salmon",
+            MobKind::Sheep => "minecraft:sheep",
+            MobKind::Shulker => "minecraft:shulker",
+            MobKind::Silverfish => "minecraft:silverfish",
+            MobKind::Skeleton => "minecraft:skeleton",
+            MobKind::SkeletonHorse => "minecraft:skeleton_horse",
+            MobKind::Slime => "minecraft:slime",
+            MobKind::SnowGolem => "minecraft:snow_golem",
+            MobKind::Spider => "minecraft:spider",
+            MobKind::Squid => "minecraft:squid",
+            MobKind::Stray => "minecraft:stray",
+            MobKind::TropicalFish => "minecraft:tropical_fish",
+            MobKind::Turtle => "minecraft:turtle",
+            MobKind::Vex => "minecraft:vex",
+            MobKind::Villager => "minecraft:villager",
+            MobKind::IronGolem => "minecraft:iron_golem",
+            MobKind::VindicationIllager => "minecraft:vindicator",
+            MobKind::Witch => "minecraft:witch",
+            MobKind::Wither => "minecraft:wither",
+            MobKind::WitherSkeleton => "minecraft:wither_skeleton",
+            MobKind::Wolf => "minecraft:wolf",
+            MobKind::Zombie => "minecraft:zombie",
+            MobKind::ZombieHorse => "minecraft:zombie_horse",
+            MobKind::ZombieVillager => "minecraft:zombie_villager",
+            MobKind::Phantom => "minecraft:phantom",
+        }
+    }
+
+    /// Returns the kind of mob with the given namespaced ID.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        ALL_MOB_KINDS
+            .iter()
+            .copied()
+            .find(|kind| kind.identifier() == identifier)
+    }
+
+    /// Returns the components of a new mob of this kind.
+    /// Its position and world still have to be added.
+    pub fn create(self) -> EntityBuilder {
+        match self {
+            MobKind::Bat => passive::bat::create(),
+            MobKind::Blaze => hostile::blaze::create(),
+            MobKind::CaveSpider => neutral::cave_spider::create(),
+            MobKind::Chicken => passive::chicken::create(),
+            MobKind::Cod => passive::cod::create(),
+            MobKind::Cow => passive::cow::create(),
+            MobKind::Creeper => hostile::creeper::create(),
+            MobKind::Donkey => passive::donkey::create(),
+            MobKind::Dolphin => neutral::dolphin::create(),
+            MobKind::Drowned => hostile::drowned::create(),
+            MobKind::ElderGuardian => hostile::elder_guardian::create(),
+            MobKind::EnderDragon => boss::ender_dragon::create(),
+            MobKind::Enderman => neutral::enderman::create(),
+            MobKind::Endermite => hostile::endermite::create(),
+            MobKind::EvocationIllager => hostile::evoker::create(),
+            MobKind::Ghast => hostile::ghast::create(),
+            MobKind::Guardian => hostile::guardian::create(),
+            MobKind::Horse => passive::horse::create(),
+            MobKind::Husk => hostile::husk::create(),
+            MobKind::Llama => neutral::llama::create(),
+            MobKind::MagmaCube => hostile::magma_cube::create(),
+            MobKind::Mule => passive::mule::create(),
+            MobKind::MushroomCow => passive::mooshroom::create(),
+            MobKind::Ocelot => passive::ocelot::create(),
+            MobKind::Parrot => passive::parrot::create(),
+            MobKind::Pig => passive::pig::create(),
+            MobKind::Pufferfish => defensive::pufferfish::create(),
+            MobKind::PigZombie => neutral::zombie_pigman::create(),
+            MobKind::PolarBear => neutral::polar_bear::create(),
+            MobKind::Rabbit => passive::rabbit::create(),
+            MobKind::Salmon => passive::salmon::create(),
+            MobKind::Sheep => passive::sheep::create(),
+            MobKind::Shulker => hostile::shulker::create(),
+            MobKind::Silverfish => hostile::silverfish::create(),
+            MobKind::Skeleton => hostile::skeleton::create(),
+            MobKind::SkeletonHorse => passive::skeleton_horse::create(),
+            MobKind::Slime => hostile::slime::create(),
+            MobKind::SnowGolem => passive::snow_golem::create(),
+            MobKind::Spider => neutral::spider::create(),
+            MobKind::Squid => passive::squid::create(),
+            MobKind::Stray => hostile::stray::create(),
+            MobKind::TropicalFish => passive::tropical_fish::create(),
+            MobKind::Turtle => passive::turtle::create(),
+            MobKind::Vex => hostile::vex::create(),
+            MobKind::Villager => passive::villager::create(),
+            MobKind::IronGolem => neutral::iron_golem::create(),
+            MobKind::VindicationIllager => hostile::vindicator::create(),
+            MobKind::Witch => hostile::witch::create(),
+            MobKind::Wither => boss::wither::create(),
+            MobKind::WitherSkeleton => hostile::wither_skeleton::create(),
+            MobKind::Wolf => neutral::wolf::create(),
+            MobKind::Zombie => hostile::zombie::create(),
+            MobKind::ZombieVillager => hostile::zombie_villager::create(),
+            MobKind::Phantom => hostile::phantom::create(),
+            MobKind::Giant | MobKind::IllusionIllager | MobKind::ZombieHorse => base(self),
+        }
+    }
+
+    /// Returns the default metadata of a mob of this kind.
+    pub fn metadata(self) -> Metadata {
+        match self {
+            MobKind::Bat => Metadata::Bat(model::Bat::default()),
+            MobKind::Blaze => Metadata::Blaze(model::Blaze::default()),
+            MobKind::CaveSpider => Metadata::CaveSpider(model::CaveSpider::default()),
+            MobKind::Chicken => Metadata::Chicken(model::Chicken::default()),
+            MobKind::Cod => Metadata::Cod(model::Cod::default()),
+            MobKind::Cow => Metadata::Cow(model::Cow::default()),
+            MobKind::Creeper => Metadata::Creeper(model::Creeper::default()),
+            MobKind::Donkey => Metadata::Donkey(model::Donkey::default()),
+            MobKind::Dolphin => Metadata::Dolphin(model::Dolphin::default()),
+            MobKind::Drowned => Metadata::Drowned(model::Drowned::default()),
+            MobKind::ElderGuardian => Metadata::ElderGuardian(model::ElderGuardian::default()),
+            MobKind::EnderDragon => Metadata::EnderDragon(model::EnderDragon::default()),
+            MobKind::Enderman => Metadata::Enderman(model::Enderman::default()),
+            MobKind::Endermite => Metadata::Endermite(model::Endermite::default()),
+            MobKind::EvocationIllager => {
+                Metadata::EvocationIllager(model::EvocationIllager::default())
+            }
+            MobKind::Ghast => Metadata::Ghast(model::Ghast::default()),
+            MobKind::Giant => Metadata::Giant(model::Giant::default()),
+            MobKind::Guardian => Metadata::Guardian(model::Guardian::default()),
+            MobKind::Horse => Metadata::Horse(model::Horse::default()),
+            MobKind::Husk => Metadata::Husk(model::Husk::default()),
+            MobKind::IllusionIllager => {
+                Metadata::IllusionIllager(model::IllusionIllager::default())
+            }
+            MobKind::Llama => Metadata::Llama(model::Llama::default()),
+            MobKind::MagmaCube => Metadata::MagmaCube(model::MagmaCube::default()),
+            MobKind::Mule => Metadata::Mule(model::Mule::default()),
+            MobKind::MushroomCow => Metadata::MushroomCow(model::MushroomCow::default()),
+            MobKind::Ocelot => Metadata::Ocelot(model::Ocelot::default()),
+            MobKind::Parrot => Metadata::Parrot(model::Parrot::default()),
+            MobKind::Pig => Metadata::Pig(model::Pig::default()),
+            MobKind::Pufferfish => Metadata::Pufferfish(model::Pufferfish::default()),
+            MobKind::PigZombie => Metadata::PigZombie(model::PigZombie::default()),
+            MobKind::PolarBear => Metadata::PolarBear(model::PolarBear::default()),
+            MobKind::Rabbit => Metadata::Rabbit(model::Rabbit::default()),
+            MobKind::Salmon => Metadata::Salmon(model::Salmon::default()),
+            MobKind::Sheep => Metadata::Sheep(model::Sheep::default()),
+            MobKind::Shulker => Metadata::Shulker(model::Shulker::default()),
+            MobKind::Silverfish => Metadata::Silverfish(model::Silverfish::default()),
+            MobKind::Skeleton => Metadata::Skeleton(model::Skeleton::default()),
+            MobKind::SkeletonHorse => Metadata::SkeletonHorse(model::SkeletonHorse::default()),
+            MobKind::Slime => Metadata::Slime(model::Slime::default()),
+            MobKind::SnowGolem => Metadata::SnowGolem(model::SnowGolem::default()),
+            MobKind::Spider => Metadata::Spider(model::Spider::default()),
+            MobKind::Squid => Metadata::Squid(model::Squid::default()),
+            MobKind::Stray => Metadata::Stray(model::Stray::default()),
+            MobKind::TropicalFish => Metadata::TropicalFish(model::TropicalFish::default()),
+            MobKind::Turtle => Metadata::Turtle(model::Turtle::default()),
+            MobKind::Vex => Metadata::Vex(model::Vex::default()),
+            MobKind::Villager => Metadata::Villager(model::Villager::default()),
+            MobKind::IronGolem => Metadata::IronGolem(model::IronGolem::default()),
+            MobKind::VindicationIllager => {
+                Metadata::VindicationIllager(model::VindicationIllager::default())
+            }
+            MobKind::Witch => Metadata::Witch(model::Witch::default()),
+            MobKind::Wither => Metadata::Wither(model::Wither::default()),
+            MobKind::WitherSkeleton => Metadata::WitherSkeleton(model::WitherSkeleton::default()),
+            MobKind::Wolf => Metadata::Wolf(model::Wolf::default()),
+            MobKind::Zombie => Metadata::Zombie(model::Zombie::default()),
+            MobKind::ZombieHorse => Metadata::ZombieHorse(model::ZombieHorse::default()),
+            MobKind::ZombieVillager => Metadata::ZombieVillager(model::ZombieVillager::default()),
+            MobKind::Phantom => Metadata::Phantom(model::Phantom::default()),
+        }
+    }
+}
+
+/// Marker component for monsters.
+#[derive(Copy, Clone, Debug)]
+pub struct Monster;
+
 /// Returns the base components for a mob with the given
-/// kind.
+/// kind, including its default `Metadata` and `MobCategory`.
 pub fn base(kind: MobKind) -> EntityBuilder {
-    super::base().with(spawn_packet_creator(kind))
+    base_with_metadata(kind, kind.metadata())
+}
+
+/// Returns the base components for a mob with the given
+/// kind, like `base`, but with the given `Metadata`.
+pub fn base_with_metadata(kind: MobKind, metadata: Metadata) -> EntityBuilder {
+    let mut builder = super::base()
+        .with(spawn_packet_creator(kind))
+        .with(metadata);
+    if kind.is_monster() {
+        builder = builder.with(Monster);
+    }
+    match kind.category() {
+        Some(category) => builder.with(category),
+        None => builder,
+    }
+}
+
+/// Returns the `AnimalData` of a mob for saving. Mobs
+/// without a `Health` component are saved with `max_health`.
+pub fn serialize_animal(accessor: &EntityRef, max_health: f32) -> AnimalData {
+    let vel = accessor.get::<Velocity>().0;
+    let health = accessor
+        .try_get::<Health>()
+        .map(|health| health.0 as f32)
+        .unwrap_or(max_health);
+
+    let data = AnimalData::new(
+        BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z)),
+        health,
+    );
+    match accessor.try_get::<Uuid>() {
+        Some(uuid) => data.with_uuid(*uuid),
+        None => data,
+    }
+}
+
+/// Adds the position, velocity, health and UUID
+/// stored in an `AnimalData` to a mob being loaded.
+pub fn load_animal(builder: EntityBuilder, data: &AnimalData) -> anyhow::Result<EntityBuilder> {
+    let pos = data.base.read_position()?;
+    let vel = data.base.read_velocity()?;
+
+    Ok(builder
+        .with(pos)
+        .with(Velocity(glm::vec3(vel.x, vel.y, vel.z)))
+        .with(Health(data.health.ceil() as u32))
+        .with(data.uuid().unwrap_or_else(Uuid::new_v4)))
 }
 
 /// Returns a `SpawnPacketCreator` for a mob with the given kind.
@@ -105,10 +532,13 @@ pub fn spawn_packet_creator(kind: MobKind) -> SpawnPacketCreator {
 
         let position = *accessor.get::<Position>();
         let velocity = *accessor.get::<Velocity>();
-        let meta = accessor
-            .try_get::<EntityMetadata>()
-            .map(|meta| (&*meta).clone())
-            .unwrap_or_else(EntityMetadata::entity_base);
+        let meta = match accessor.try_get::<Metadata>() {
+            Some(meta) => meta.to_full_raw_metadata(),
+            None => accessor
+                .try_get::<EntityMetadata>()
+                .map(|meta| (&*meta).clone())
+                .unwrap_or_else(EntityMetadata::entity_base),
+        };
 
         let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity.0);
 
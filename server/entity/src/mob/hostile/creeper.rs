@@ -1,8 +1,182 @@
-use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+//! Creepers, which explode once ignited. Creepers have no AI
+//! yet, so they are only ignited by players using flint and
+//! steel on them.
+
+use crate::explosion::BlockDamage;
+use crate::{explode, mob, MobKind};
+use feather_core::entitymeta::Metadata;
+use feather_core::inventory::{slot, Area};
+use feather_core::items::Item;
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{Gamemode, Position};
+use feather_server_types::{EntityInteractEvent, Game, HeldItem, ItemDamageEvent, Sound};
+use fecs::{EntityBuilder, IntoQuery, World, Write};
+
+/// Ticks between a creeper being ignited and exploding.
+const FUSE: u32 = 30;
+/// Power of the explosion of a creeper, doubled for charged creepers.
+const EXPLOSION_POWER: f32 = 3.0;
 
 pub struct Creeper;
 
+/// Component for ignited creepers, storing the
+/// number of ticks left before they explode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CreeperFuse(pub u32);
+
 pub fn create() -> EntityBuilder {
     mob::base(MobKind::Creeper).with(Creeper)
 }
+
+/// Ignites a creeper when a player uses flint and steel on it,
+/// which takes damage unless the player is in creative mode.
+#[fecs::event_handler]
+pub fn on_entity_interact_ignite_creeper(
+    event: &EntityInteractEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    match event.stack {
+        Some(stack) if stack.ty == Item::FlintAndSteel => (),
+        _ => return,
+    }
+    if !world.has::<Creeper>(event.target) || world.has::<CreeperFuse>(event.target) {
+        return;
+    }
+
+    world.add(event.target, CreeperFuse(FUSE)).unwrap();
+    if let Some(mut metadata) = world.try_get_mut::<Metadata>(event.target) {
+        if let Metadata::Creeper(meta) = &mut *metadata {
+            meta.set_ignited(true);
+        }
+    }
+
+    let pos = *world.get::<Position>(event.target);
+    let world_id = game.world_of(world, event.target);
+    let sound = Sound::new("item.flintandsteel.use", SoundCategory::Players);
+    game.play_sound(world, world_id, pos, &sound);
+    let sound = Sound::new("entity.creeper.primed", SoundCategory::Hostile).pitch(0.5);
+    game.play_sound(world, world_id, pos, &sound);
+
+    if *world.get::<Gamemode>(event.player) != Gamemode::Creative {
+        let held_item = world.get::<HeldItem>(event.player).0;
+        game.handle(
+            world,
+            ItemDamageEvent {
+                player: event.player,
+                slot: slot(Area::Hotbar, held_item),
+                damage_taken: 1,
+            },
+        );
+    }
+}
+
+/// System which explodes ignited creepers once their fuse runs out.
+/// Creepers only destroy blocks if the `mobGriefing` game rule is
+/// enabled, and charged creepers explode twice as powerfully.
+#[fecs::system]
+pub fn explode_creepers(game: &mut Game, world: &mut World) {
+    let mut exploding = Vec::new();
+    for (creeper, mut fuse) in <Write<CreeperFuse>>::query().iter_entities_mut(world.inner_mut()) {
+        fuse.0 = fuse.0.saturating_sub(1);
+        if fuse.0 == 0 {
+            exploding.push(creeper);
+        }
+    }
+
+    let block_damage = if game.game_rules.mob_griefing {
+        BlockDamage::Break
+    } else {
+        BlockDamage::None
+    };
+    for creeper in exploding {
+        let charged = match &*world.get::<Metadata>(creeper) {
+            Metadata::Creeper(meta) => meta.charged(),
+            _ => false,
+        };
+        let power = if charged {
+            EXPLOSION_POWER * 2.0
+        } else {
+            EXPLOSION_POWER
+        };
+
+        let pos = *world.get::<Position>(creeper);
+        let world_id = game.world_of(world, creeper);
+        game.despawn(creeper, world);
+        explode(game, world, world_id, pos, power, block_damage, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::items::ItemStack;
+    use feather_core::util::BlockPosition;
+    use feather_server_types::{BlockUpdateCause, WorldId};
+    use feather_test_framework::Test;
+
+    fn ignite(test: &mut Test) -> fecs::Entity {
+        let player = test.player("", position!(5.0, 64.0, 0.0));
+        let creeper = test.entity(create().with(position!(0.5, 64.0, 0.5)));
+        let event = EntityInteractEvent {
+            player,
+            target: creeper,
+            stack: Some(ItemStack::new(Item::FlintAndSteel, 1)),
+        };
+        test.handle(event, on_entity_interact_ignite_creeper);
+        creeper
+    }
+
+    #[test]
+    fn ignited_creeper_explodes() {
+        let mut test = Test::new();
+        let below = BlockPosition::new(0, 63, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            below,
+            BlockId::dirt(),
+            BlockUpdateCause::Unknown,
+        );
+
+        let creeper = ignite(&mut test);
+        match &*test.world.get::<Metadata>(creeper) {
+            Metadata::Creeper(meta) => assert!(meta.ignited()),
+            _ => panic!("not a creeper"),
+        }
+
+        for _ in 0..FUSE - 1 {
+            test.run(explode_creepers);
+        }
+        test.assert_alive(creeper);
+
+        test.run(explode_creepers);
+        test.assert_dead(creeper);
+        assert!(test.game.block_at(WorldId::MAIN, below).unwrap().is_air());
+    }
+
+    #[test]
+    fn no_mob_griefing() {
+        let mut test = Test::new();
+        test.game.game_rules.mob_griefing = false;
+        let below = BlockPosition::new(0, 63, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            below,
+            BlockId::dirt(),
+            BlockUpdateCause::Unknown,
+        );
+
+        let creeper = ignite(&mut test);
+        for _ in 0..FUSE {
+            test.run(explode_creepers);
+        }
+        test.assert_dead(creeper);
+        assert_eq!(
+            test.game.block_at(WorldId::MAIN, below),
+            Some(BlockId::dirt())
+        );
+    }
+}
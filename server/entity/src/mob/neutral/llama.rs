@@ -1,8 +1,30 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a llama which hasn't taken any damage.
+const MAX_HEALTH: f32 = 15.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Llama, &load)
+}
 
 pub struct Llama;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Llama).with(Llama)
+    mob::base(MobKind::Llama)
+        .with(Llama)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Llama(mob::serialize_animal(accessor, MAX_HEALTH))
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Llama(data) => mob::load_animal(create(), &data),
+        _ => panic!("attempted to use llama::load to load a non-llama"),
+    }
 }
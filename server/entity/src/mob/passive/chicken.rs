@@ -1,8 +1,30 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a chicken which hasn't taken any damage.
+const MAX_HEALTH: f32 = 4.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Chicken, &load)
+}
 
 pub struct Chicken;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Chicken).with(Chicken)
+    mob::base(MobKind::Chicken)
+        .with(Chicken)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Chicken(mob::serialize_animal(accessor, MAX_HEALTH))
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Chicken(data) => mob::load_animal(create(), &data),
+        _ => panic!("attempted to use chicken::load to load a non-chicken"),
+    }
 }
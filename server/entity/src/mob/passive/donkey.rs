@@ -1,8 +1,30 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a donkey which hasn't taken any damage.
+const MAX_HEALTH: f32 = 15.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Donkey, &load)
+}
 
 pub struct Donkey;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Donkey).with(Donkey)
+    mob::base(MobKind::Donkey)
+        .with(Donkey)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Donkey(mob::serialize_animal(accessor, MAX_HEALTH))
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Donkey(data) => mob::load_animal(create(), &data),
+        _ => panic!("attempted to use donkey::load to load a non-donkey"),
+    }
 }
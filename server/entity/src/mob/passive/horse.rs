@@ -1,8 +1,73 @@
-use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use crate::riding::set_riding_metadata;
+use crate::{mob, MobKind, Ridable, Saddled, Taming, Vehicle};
+use feather_core::anvil::entity::{EntityData, EntityDataKind, HorseData, ItemData};
+use feather_core::items::{Item, ItemStack};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a horse which hasn't taken any damage.
+const MAX_HEALTH: f32 = 15.0;
+/// Height of a horse, on top of which its rider dismounts.
+const HEIGHT: f64 = 1.6;
+/// Blocks a horse walks per tick when steered.
+const SPEED: f64 = 0.45;
+/// Initial upward velocity of the jumps of a horse.
+const JUMP_STRENGTH: f64 = 0.7;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Horse, &load)
+}
 
 pub struct Horse;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Horse).with(Horse)
+    create_with(Taming::default(), Saddled::default())
+}
+
+/// Creates a horse which may be tamed and wear a saddle.
+pub fn create_with(taming: Taming, saddled: Saddled) -> EntityBuilder {
+    let mut metadata = MobKind::Horse.metadata();
+    set_riding_metadata(&mut metadata, saddled, taming);
+
+    mob::base_with_metadata(MobKind::Horse, metadata)
+        .with(Horse)
+        .with(taming)
+        .with(saddled)
+        .with(Vehicle::new(1, HEIGHT, false))
+        .with(Ridable::new(SPEED, "entity.horse.saddle").jump_strength(JUMP_STRENGTH))
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let taming = *accessor.get::<Taming>();
+    let saddle_item = if accessor.get::<Saddled>().0 {
+        Some(ItemData::from(ItemStack::new(Item::Saddle, 1)))
+    } else {
+        None
+    };
+    EntityData::Horse(HorseData {
+        animal: mob::serialize_animal(accessor, MAX_HEALTH),
+        tame: taming.tame as i8,
+        temper: taming.temper as i32,
+        owner_uuid: taming.owner.map(|owner| owner.to_string()),
+        saddle_item,
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Horse(data) => {
+            let taming = Taming {
+                tame: data.tame != 0,
+                temper: data.temper.max(0) as u32,
+                owner: data.owner(),
+            };
+            let saddled = data
+                .saddle_item
+                .as_ref()
+                .map_or(false, |item| ItemStack::from(item).ty == Item::Saddle);
+            mob::load_animal(create_with(taming, Saddled(saddled)), &data.animal)
+        }
+        _ => panic!("attempted to use horse::load to load a non-horse"),
+    }
 }
@@ -1,8 +1,30 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a mooshroom which hasn't taken any damage.
+const MAX_HEALTH: f32 = 10.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Mooshroom, &load)
+}
 
 pub struct Mooshroom;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::MushroomCow).with(Mooshroom)
+    mob::base(MobKind::MushroomCow)
+        .with(Mooshroom)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Mooshroom(mob::serialize_animal(accessor, MAX_HEALTH))
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Mooshroom(data) => mob::load_animal(create(), &data),
+        _ => panic!("attempted to use mooshroom::load to load a non-mooshroom"),
+    }
 }
@@ -1,8 +1,53 @@
-use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use crate::riding::set_riding_metadata;
+use crate::{mob, MobKind, Ridable, Saddled, Vehicle};
+use feather_core::anvil::entity::{EntityData, EntityDataKind, PigData};
+use feather_core::items::Item;
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a pig which hasn't taken any damage.
+const MAX_HEALTH: f32 = 10.0;
+/// Height of a pig, on top of which its rider dismounts.
+const HEIGHT: f64 = 0.9;
+/// Blocks a pig walks per tick when steered.
+const SPEED: f64 = 0.12;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Pig, &load)
+}
 
 pub struct Pig;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Pig).with(Pig)
+    create_with_saddle(Saddled::default())
+}
+
+/// Creates a pig which may wear a saddle.
+pub fn create_with_saddle(saddled: Saddled) -> EntityBuilder {
+    let mut metadata = MobKind::Pig.metadata();
+    set_riding_metadata(&mut metadata, saddled, Default::default());
+
+    mob::base_with_metadata(MobKind::Pig, metadata)
+        .with(Pig)
+        .with(saddled)
+        .with(Vehicle::new(1, HEIGHT, false))
+        .with(Ridable::new(SPEED, "entity.pig.saddle").steered_with(Item::CarrotOnAStick))
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Pig(PigData {
+        animal: mob::serialize_animal(accessor, MAX_HEALTH),
+        saddle: accessor.get::<Saddled>().0 as i8,
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Pig(data) => {
+            let saddled = Saddled(data.saddle != 0);
+            mob::load_animal(create_with_saddle(saddled), &data.animal)
+        }
+        _ => panic!("attempted to use pig::load to load a non-pig"),
+    }
 }
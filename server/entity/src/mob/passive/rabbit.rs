@@ -1,8 +1,30 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a rabbit which hasn't taken any damage.
+const MAX_HEALTH: f32 = 3.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Rabbit, &load)
+}
 
 pub struct Rabbit;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Rabbit).with(Rabbit)
+    mob::base(MobKind::Rabbit)
+        .with(Rabbit)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Rabbit(mob::serialize_animal(accessor, MAX_HEALTH))
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Rabbit(data) => mob::load_animal(create(), &data),
+        _ => panic!("attempted to use rabbit::load to load a non-rabbit"),
+    }
 }
@@ -1,8 +1,55 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind, SheepData};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a sheep which hasn't taken any damage.
+const MAX_HEALTH: f32 = 8.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Sheep, &load)
+}
 
 pub struct Sheep;
 
+/// The wool of a sheep.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Wool {
+    /// The dye color, from 0 (white) to 15 (black).
+    pub color: u8,
+    pub sheared: bool,
+}
+
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Sheep).with(Sheep)
+    create_with_wool(Wool::default())
+}
+
+/// Creates a sheep with the given wool.
+pub fn create_with_wool(wool: Wool) -> EntityBuilder {
+    mob::base(MobKind::Sheep)
+        .with(Sheep)
+        .with(wool)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let wool = *accessor.get::<Wool>();
+    EntityData::Sheep(SheepData {
+        animal: mob::serialize_animal(accessor, MAX_HEALTH),
+        color: wool.color as i8,
+        sheared: wool.sheared as i8,
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Sheep(data) => {
+            let wool = Wool {
+                color: (data.color as u8).min(15),
+                sheared: data.sheared != 0,
+            };
+            mob::load_animal(create_with_wool(wool), &data.animal)
+        }
+        _ => panic!("attempted to use sheep::load to load a non-sheep"),
+    }
 }
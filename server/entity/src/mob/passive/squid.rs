@@ -1,8 +1,30 @@
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{EntityData, EntityDataKind};
+use feather_server_types::{ComponentSerializer, EntityLoaderRegistration, Game};
+use fecs::{EntityBuilder, EntityRef};
+
+/// Health of a squid which hasn't taken any damage.
+const MAX_HEALTH: f32 = 10.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Squid, &load)
+}
 
 pub struct Squid;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Squid).with(Squid)
+    mob::base(MobKind::Squid)
+        .with(Squid)
+        .with(ComponentSerializer(&serialize))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    EntityData::Squid(mob::serialize_animal(accessor, MAX_HEALTH))
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Squid(data) => mob::load_animal(create(), &data),
+        _ => panic!("attempted to use squid::load to load a non-squid"),
+    }
 }
@@ -1,8 +1,1045 @@
+//! Villagers, their professions and the trades they offer.
+//!
+//! Villagers without a profession take the one of the nearest free
+//! job site. Each level of a profession unlocks trades, generated
+//! from the tables in `trades`. Trades sell out after a number of
+//! uses until the villager restocks while working at its job site.
+//! Their prices rise with the demand for them, and are lowered or
+//! raised by the villager's gossip about each player.
+
+mod trades;
+
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use feather_core::anvil::entity::{
+    BrainData, EntityData, EntityDataKind, GlobalPositionData, GossipData, ItemData, MemoryData,
+    TradeOfferData, TradeOffersData, VillagerData, VillagerKindData,
+};
+use feather_core::entitymeta::Metadata;
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::Trade;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    ComponentSerializer, EntityDeathEvent, EntityLoaderRegistration, Game, PoiType, Uuid, WorldId,
+    DAY_LENGTH,
+};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use rand::Rng;
+
+pub use trades::TradeTemplate;
+
+/// Health of a villager which hasn't taken any damage.
+const MAX_HEALTH: f32 = 20.0;
+
+/// The highest level of a profession, master.
+pub const MAX_LEVEL: u8 = 5;
+
+/// Experience needed to reach each level from level 2 on.
+const LEVEL_XP: [i32; 4] = [10, 70, 150, 250];
+
+/// Number of trades unlocked at each level.
+const TRADES_PER_LEVEL: usize = 2;
+
+/// Distance from which villagers take job sites.
+const JOB_SITE_RANGE: i32 = 48;
+
+/// Ticks between villagers looking for job sites.
+const JOB_SITE_INTERVAL: u64 = 100;
+
+/// Ticks between restocks on the same day.
+const RESTOCK_COOLDOWN: u64 = 2400;
+
+/// Times a villager can restock each day.
+const MAX_RESTOCKS: u8 = 2;
+
+/// Time of day during which villagers work at their job sites.
+const WORK_HOURS: std::ops::Range<u64> = 2000..9000;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Villager, &load)
+}
 
 pub struct Villager;
 
+/// The profession of a villager, which determines its trades.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Profession {
+    /// An unemployed villager, which takes the
+    /// profession of the first job site it finds.
+    None,
+    Armorer,
+    Butcher,
+    Cartographer,
+    Cleric,
+    Farmer,
+    Fisherman,
+    Fletcher,
+    Leatherworker,
+    Librarian,
+    Mason,
+    /// A villager which can't have a job.
+    Nitwit,
+    Shepherd,
+    Toolsmith,
+    Weaponsmith,
+}
+
+const ALL_PROFESSIONS: [Profession; 15] = [
+    Profession::None,
+    Profession::Armorer,
+    Profession::Butcher,
+    Profession::Cartographer,
+    Profession::Cleric,
+    Profession::Farmer,
+    Profession::Fisherman,
+    Profession::Fletcher,
+    Profession::Leatherworker,
+    Profession::Librarian,
+    Profession::Mason,
+    Profession::Nitwit,
+    Profession::Shepherd,
+    Profession::Toolsmith,
+    Profession::Weaponsmith,
+];
+
+impl Profession {
+    /// Returns the namespaced identifier of this profession,
+    /// e.g. `minecraft:farmer`.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            Profession::None => "minecraft:none",
+            Profession::Armorer => "minecraft:armorer",
+            Profession::Butcher => "minecraft:butcher",
+            Profession::Cartographer => "minecraft:cartographer",
+            Profession::Cleric => "minecraft:cleric",
+            Profession::Farmer => "minecraft:farmer",
+            Profession::Fisherman => "minecraft:fisherman",
+            Profession::Fletcher => "minecraft:fletcher",
+            Profession::Leatherworker => "minecraft:leatherworker",
+            Profession::Librarian => "minecraft:librarian",
+            Profession::Mason => "minecraft:mason",
+            Profession::Nitwit => "minecraft:nitwit",
+            Profession::Shepherd => "minecraft:shepherd",
+            Profession::Toolsmith => "minecraft:toolsmith",
+            Profession::Weaponsmith => "minecraft:weaponsmith",
+        }
+    }
+
+    /// Returns the profession with the given namespaced identifier.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        ALL_PROFESSIONS
+            .iter()
+            .copied()
+            .find(|profession| profession.identifier() == identifier)
+    }
+
+    /// Returns the name of this profession shown to players.
+    pub fn name(self) -> &'static str {
+        match self {
+            Profession::None | Profession::Nitwit => "Villager",
+            Profession::Armorer => "Armorer",
+            Profession::Butcher => "Butcher",
+            Profession::Cartographer => "Cartographer",
+            Profession::Cleric => "Cleric",
+            Profession::Farmer => "Farmer",
+            Profession::Fisherman => "Fisherman",
+            Profession::Fletcher => "Fletcher",
+            Profession::Leatherworker => "Leatherworker",
+            Profession::Librarian => "Librarian",
+            Profession::Mason => "Mason",
+            Profession::Shepherd => "Shepherd",
+            Profession::Toolsmith => "Toolsmith",
+            Profession::Weaponsmith => "Weaponsmith",
+        }
+    }
+
+    /// Returns the type of job site of this profession.
+    pub fn job_site(self) -> Option<PoiType> {
+        Some(match self {
+            Profession::None | Profession::Nitwit => return None,
+            Profession::Armorer => PoiType::Armorer,
+            Profession::Butcher => PoiType::Butcher,
+            Profession::Cartographer => PoiType::Cartographer,
+            Profession::Cleric => PoiType::Cleric,
+            Profession::Farmer => PoiType::Farmer,
+            Profession::Fisherman => PoiType::Fisherman,
+            Profession::Fletcher => PoiType::Fletcher,
+            Profession::Leatherworker => PoiType::Leatherworker,
+            Profession::Librarian => PoiType::Librarian,
+            Profession::Mason => PoiType::Mason,
+            Profession::Shepherd => PoiType::Shepherd,
+            Profession::Toolsmith => PoiType::Toolsmith,
+            Profession::Weaponsmith => PoiType::Weaponsmith,
+        })
+    }
+
+    /// Returns the profession whose job site is
+    /// the given type of point of interest.
+    pub fn from_job_site(kind: PoiType) -> Option<Self> {
+        ALL_PROFESSIONS
+            .iter()
+            .copied()
+            .find(|profession| profession.job_site() == Some(kind))
+    }
+
+    /// Returns the ID of the 1.13.2 profession shown
+    /// to clients, which had fewer professions.
+    pub fn legacy_id(self) -> i32 {
+        match self {
+            Profession::None
+            | Profession::Farmer
+            | Profession::Fisherman
+            | Profession::Fletcher
+            | Profession::Shepherd => 0,
+            Profession::Librarian | Profession::Cartographer => 1,
+            Profession::Cleric => 2,
+            Profession::Armorer | Profession::Toolsmith | Profession::Weaponsmith => 3,
+            Profession::Butcher | Profession::Leatherworker | Profession::Mason => 4,
+            Profession::Nitwit => 5,
+        }
+    }
+}
+
+/// Component for a villager's profession, the level it
+/// reached in it and its experience from trading.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Career {
+    pub profession: Profession,
+    /// The level, from 1 (novice) to 5 (master).
+    pub level: u8,
+    pub xp: i32,
+}
+
+impl Default for Career {
+    fn default() -> Self {
+        Self {
+            profession: Profession::None,
+            level: 1,
+            xp: 0,
+        }
+    }
+}
+
+impl Career {
+    /// Returns whether the villager has gained
+    /// enough experience to reach the next level.
+    pub fn can_level_up(&self) -> bool {
+        self.level >= 1 && self.level < MAX_LEVEL && self.xp >= LEVEL_XP[self.level as usize - 1]
+    }
+
+    /// Returns whether the villager has ever traded, after
+    /// which it keeps its profession even without a job site.
+    pub fn has_traded(&self) -> bool {
+        self.xp > 0 || self.level > 1
+    }
+}
+
+/// Component for the job site claimed by a villager.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct JobSite(pub Option<BlockPosition>);
+
+/// A trade offered by a merchant.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Offer {
+    pub input_1: ItemStack,
+    pub input_2: Option<ItemStack>,
+    pub output: ItemStack,
+    pub uses: i32,
+    pub max_uses: i32,
+    /// Whether players are given experience for the trade.
+    pub reward_xp: bool,
+    /// Experience the merchant gains from the trade.
+    pub xp: i32,
+    /// How strongly demand and gossip change the price.
+    pub price_multiplier: f32,
+    /// How much more often the trade was used than not
+    /// since it was unlocked. Raises the price if positive.
+    pub demand: i32,
+}
+
+impl Offer {
+    /// Creates an offer which can be used `max_uses` times before restocking.
+    pub fn new(
+        input_1: ItemStack,
+        input_2: Option<ItemStack>,
+        output: ItemStack,
+        max_uses: i32,
+        xp: i32,
+        price_multiplier: f32,
+    ) -> Self {
+        Self {
+            input_1,
+            input_2,
+            output,
+            uses: 0,
+            max_uses,
+            reward_xp: true,
+            xp,
+            price_multiplier,
+            demand: 0,
+        }
+    }
+
+    pub fn is_out_of_stock(&self) -> bool {
+        self.uses >= self.max_uses
+    }
+
+    /// Returns this offer as sent to a player, whose
+    /// price is changed by `special_price`.
+    pub fn to_trade(&self, special_price: i32) -> Trade {
+        Trade {
+            input_1: self.input_1,
+            input_2: self.input_2,
+            output: self.output,
+            disabled: self.is_out_of_stock(),
+            uses: self.uses,
+            max_uses: self.max_uses,
+            experience: self.xp,
+            special_price,
+            price_multiplier: self.price_multiplier,
+            demand: self.demand,
+        }
+    }
+
+    /// Returns the first input with its price adjusted by
+    /// demand and by `special_price`.
+    pub fn price(&self, special_price: i32) -> ItemStack {
+        self.to_trade(special_price).adjusted_input_1()
+    }
+
+    /// Returns the change to the price of this offer for a
+    /// player the merchant has the given reputation of.
+    pub fn special_price(&self, reputation: i32) -> i32 {
+        -(reputation as f32 * self.price_multiplier).floor() as i32
+    }
+
+    /// Returns whether the given items pay for this offer.
+    pub fn is_paid_by(
+        &self,
+        first: Option<ItemStack>,
+        second: Option<ItemStack>,
+        special_price: i32,
+    ) -> bool {
+        !self.is_out_of_stock()
+            && covers(first, Some(self.price(special_price)))
+            && covers(second, self.input_2)
+    }
+
+    /// Takes the price of this offer from the given items, which
+    /// should pay for it. Items which are used up are set to `None`.
+    pub fn pay(
+        &self,
+        first: &mut Option<ItemStack>,
+        second: &mut Option<ItemStack>,
+        special_price: i32,
+    ) {
+        take(first, Some(self.price(special_price)));
+        take(second, self.input_2);
+    }
+
+    /// Updates the demand of this offer from its uses since the last
+    /// restock, and restocks it.
+    pub fn restock(&mut self) {
+        self.demand += self.uses - (self.max_uses - self.uses);
+        self.uses = 0;
+    }
+}
+
+/// Returns whether `item` is at least the `cost`.
+fn covers(item: Option<ItemStack>, cost: Option<ItemStack>) -> bool {
+    match (item, cost) {
+        (_, None) => item.is_none(),
+        (Some(item), Some(cost)) => item.ty == cost.ty && item.amount >= cost.amount,
+        (None, Some(_)) => false,
+    }
+}
+
+fn take(item: &mut Option<ItemStack>, cost: Option<ItemStack>) {
+    if let (Some(stack), Some(cost)) = (*item, cost) {
+        *item = if stack.amount > cost.amount {
+            Some(stack.of_amount(stack.amount - cost.amount))
+        } else {
+            None
+        };
+    }
+}
+
+/// Component for the trades offered by a merchant.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Offers(pub Vec<Offer>);
+
+impl Offers {
+    /// Returns the index of the offer paid for by the given
+    /// items. The offer at `selected`, which the player selected,
+    /// is preferred. `special_prices` holds the change to the
+    /// price of each offer for the player.
+    pub fn find(
+        &self,
+        selected: Option<usize>,
+        first: Option<ItemStack>,
+        second: Option<ItemStack>,
+        special_prices: &[i32],
+    ) -> Option<usize> {
+        // A single item pays for offers with one input
+        // whichever slot it is in.
+        let (first, second) = match first {
+            Some(_) => (first, second),
+            None => (second, None),
+        };
+        let special_price = |index: usize| special_prices.get(index).copied().unwrap_or(0);
+
+        if let Some(selected) = selected {
+            if let Some(offer) = self.0.get(selected) {
+                if offer.is_paid_by(first, second, special_price(selected)) {
+                    return Some(selected);
+                }
+            }
+        }
+        self.0
+            .iter()
+            .enumerate()
+            .position(|(index, offer)| offer.is_paid_by(first, second, special_price(index)))
+    }
+
+    /// Adds the trades unlocked at the given level of a profession.
+    pub fn unlock(&mut self, profession: Profession, level: u8, rng: &mut impl Rng) {
+        self.0
+            .extend(trades::generate(profession, level, TRADES_PER_LEVEL, rng));
+    }
+}
+
+/// Component for when a villager last restocked its trades.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Restocks {
+    /// The world time of the last restock.
+    pub last: u64,
+    /// The number of restocks on the current day.
+    pub today: u8,
+    /// The day the villager was last updated on.
+    pub day: u64,
+}
+
+impl Restocks {
+    /// Returns whether the villager may restock at the given world time.
+    pub fn can_restock(&self, time: u64) -> bool {
+        self.today == 0 || (self.today < MAX_RESTOCKS && time > self.last + RESTOCK_COOLDOWN)
+    }
+}
+
+/// Component for the player a merchant is trading with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Customer(pub Entity);
+
+/// A kind of gossip villagers have about players.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GossipKind {
+    /// The player killed a villager.
+    MajorNegative,
+    /// The player hurt a villager.
+    MinorNegative,
+    /// The player cured a zombie villager.
+    MinorPositive,
+    /// The player cured this villager.
+    MajorPositive,
+    /// The player traded with the villager.
+    Trading,
+}
+
+impl GossipKind {
+    pub fn identifier(self) -> &'static str {
+        match self {
+            GossipKind::MajorNegative => "major_negative",
+            GossipKind::MinorNegative => "minor_negative",
+            GossipKind::MinorPositive => "minor_positive",
+            GossipKind::MajorPositive => "major_positive",
+            GossipKind::Trading => "trading",
+        }
+    }
+
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        Some(match identifier {
+            "major_negative" => GossipKind::MajorNegative,
+            "minor_negative" => GossipKind::MinorNegative,
+            "minor_positive" => GossipKind::MinorPositive,
+            "major_positive" => GossipKind::MajorPositive,
+            "trading" => GossipKind::Trading,
+            _ => return None,
+        })
+    }
+
+    /// Returns how much gossip of this kind counts
+    /// towards the reputation of a player.
+    pub fn weight(self) -> i32 {
+        match self {
+            GossipKind::MajorNegative => -5,
+            GossipKind::MinorNegative => -1,
+            GossipKind::MinorPositive => 1,
+            GossipKind::MajorPositive => 5,
+            GossipKind::Trading => 1,
+        }
+    }
+
+    /// Returns the most gossip of this kind about a player.
+    pub fn max(self) -> i32 {
+        match self {
+            GossipKind::MajorNegative | GossipKind::MajorPositive => 100,
+            GossipKind::MinorNegative | GossipKind::MinorPositive => 200,
+            GossipKind::Trading => 25,
+        }
+    }
+
+    /// Returns how much gossip of this kind is forgotten each day.
+    pub fn decay(self) -> i32 {
+        match self {
+            GossipKind::MajorNegative => 10,
+            GossipKind::MinorNegative => 20,
+            GossipKind::MinorPositive => 1,
+            GossipKind::MajorPositive => 0,
+            GossipKind::Trading => 2,
+        }
+    }
+}
+
+/// Gossip about a player.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Gossip {
+    pub target: Uuid,
+    pub kind: GossipKind,
+    pub value: i32,
+}
+
+/// Component for the gossip a villager has about players.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gossips(pub Vec<Gossip>);
+
+impl Gossips {
+    /// Adds gossip of the given kind about a player,
+    /// up to the most gossip of that kind.
+    pub fn add(&mut self, target: Uuid, kind: GossipKind, value: i32) {
+        match self
+            .0
+            .iter_mut()
+            .find(|gossip| gossip.target == target && gossip.kind == kind)
+        {
+            Some(gossip) => gossip.value = (gossip.value + value).min(kind.max()),
+            None => self.0.push(Gossip {
+                target,
+                kind,
+                value: value.min(kind.max()),
+            }),
+        }
+        self.0.retain(|gossip| gossip.value > 0);
+    }
+
+    /// Returns the reputation of a player, which lowers
+    /// prices if positive and raises them if negative.
+    pub fn reputation(&self, target: Uuid) -> i32 {
+        self.0
+            .iter()
+            .filter(|gossip| gossip.target == target)
+            .map(|gossip| gossip.value * gossip.kind.weight())
+            .sum()
+    }
+
+    /// Forgets a day's worth of gossip.
+    pub fn decay(&mut self) {
+        for gossip in &mut self.0 {
+            gossip.value -= gossip.kind.decay();
+        }
+        self.0.retain(|gossip| gossip.value > 0);
+    }
+}
+
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Villager).with(Villager)
+    create_with_career(Career::default())
+}
+
+/// Creates a villager with the given career and no trades.
+pub fn create_with_career(career: Career) -> EntityBuilder {
+    build(
+        career,
+        JobSite::default(),
+        Offers::default(),
+        Restocks::default(),
+        Gossips::default(),
+    )
+}
+
+fn build(
+    career: Career,
+    job_site: JobSite,
+    offers: Offers,
+    restocks: Restocks,
+    gossips: Gossips,
+) -> EntityBuilder {
+    let mut metadata = MobKind::Villager.metadata();
+    if let Metadata::Villager(meta) = &mut metadata {
+        meta.set_profession(career.profession.legacy_id());
+    }
+
+    // Built like `mob::base`, but with the profession in the metadata.
+    crate::base()
+        .with(mob::spawn_packet_creator(MobKind::Villager))
+        .with(metadata)
+        .with(Villager)
+        .with(career)
+        .with(job_site)
+        .with(offers)
+        .with(restocks)
+        .with(gossips)
+        .with(ComponentSerializer(&serialize))
+}
+
+/// Sets the profession of a villager, resetting its trades.
+pub fn set_profession(game: &Game, world: &mut World, villager: Entity, profession: Profession) {
+    {
+        let mut career = world.get_mut::<Career>(villager);
+        *career = Career {
+            profession,
+            ..Career::default()
+        };
+    }
+
+    let mut offers = Offers::default();
+    offers.unlock(profession, 1, &mut *game.rng());
+    *world.get_mut::<Offers>(villager) = offers;
+
+    if let Some(mut metadata) = world.try_get_mut::<Metadata>(villager) {
+        if let Metadata::Villager(meta) = &mut *metadata {
+            meta.set_profession(profession.legacy_id());
+        }
+    }
+}
+
+/// Raises the level of a villager while it has enough
+/// experience, unlocking the trades of each level.
+///
+/// Returns whether the villager levelled up.
+pub fn level_up(game: &Game, world: &mut World, villager: Entity) -> bool {
+    let mut career = world.get_mut::<Career>(villager);
+    let mut offers = world.get_mut::<Offers>(villager);
+    let mut levelled_up = false;
+    while career.can_level_up() {
+        career.level += 1;
+        offers.unlock(career.profession, career.level, &mut *game.rng());
+        levelled_up = true;
+    }
+    levelled_up
+}
+
+/// Returns the change to the price of each trade of
+/// a merchant for the player with the given UUID.
+pub fn special_prices(world: &World, merchant: Entity, player: Uuid) -> Vec<i32> {
+    let reputation = world
+        .try_get::<Gossips>(merchant)
+        .map_or(0, |gossips| gossips.reputation(player));
+    world
+        .get::<Offers>(merchant)
+        .0
+        .iter()
+        .map(|offer| offer.special_price(reputation))
+        .collect()
+}
+
+/// Lets villagers without a job site claim the nearest free
+/// one. Unemployed villagers take its profession. Villagers
+/// whose job site was removed lose their profession unless
+/// they have traded.
+#[fecs::system]
+pub fn find_job_sites(game: &mut Game, world: &mut World) {
+    if game.tick_count % JOB_SITE_INTERVAL != 0 {
+        return;
+    }
+
+    let villagers: Vec<(Entity, Career, JobSite, Position)> =
+        <(Read<Career>, Read<JobSite>, Read<Position>)>::query()
+            .iter_entities(world.inner())
+            .map(|(entity, (career, job_site, pos))| (entity, *career, *job_site, *pos))
+            .collect();
+
+    for (villager, career, job_site, pos) in villagers {
+        let world_id = game.world_of(world, villager);
+
+        if let Some(site) = job_site.0 {
+            let kept = game.worlds[world_id]
+                .poi
+                .get(site)
+                .map_or(false, |poi| Some(poi.kind) == career.profession.job_site());
+            if kept {
+                continue;
+            }
+
+            world.get_mut::<JobSite>(villager).0 = None;
+            if !career.has_traded() {
+                set_profession(game, world, villager, Profession::None);
+            }
+            continue;
+        }
+
+        if career.profession == Profession::Nitwit {
+            continue;
+        }
+
+        let profession = career.profession;
+        let site = game.worlds[world_id]
+            .poi
+            .nearest(pos.block(), JOB_SITE_RANGE, move |poi| {
+                poi.free_claims > 0
+                    && poi.kind.is_job_site()
+                    && (profession == Profession::None || profession.job_site() == Some(poi.kind))
+            })
+            .map(|poi| (poi.pos, poi.kind));
+        let (site, kind) = match site {
+            Some(site) => site,
+            None => continue,
+        };
+
+        if !game.worlds[world_id].claim_poi(site) {
+            continue;
+        }
+        world.get_mut::<JobSite>(villager).0 = Some(site);
+
+        if profession == Profession::None {
+            if let Some(profession) = Profession::from_job_site(kind) {
+                set_profession(game, world, villager, profession);
+            }
+        }
+    }
+}
+
+/// Restocks the trades of villagers working at their job
+/// sites, and makes villagers forget gossip each day.
+#[fecs::system]
+pub fn restock_villagers(game: &mut Game, world: &mut World) {
+    let villagers: Vec<Entity> = <(Read<Restocks>, Read<Offers>)>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for villager in villagers {
+        let world_id = game.world_of(world, villager);
+        let time = game.worlds[world_id].time;
+
+        let mut restocks = world.get_mut::<Restocks>(villager);
+        let day = time.world_age() / DAY_LENGTH;
+        if restocks.day != day {
+            restocks.day = day;
+            restocks.today = 0;
+            if let Some(mut gossips) = world.try_get_mut::<Gossips>(villager) {
+                gossips.decay();
+            }
+        }
+
+        let working = WORK_HOURS.contains(&time.time_of_day())
+            && world
+                .try_get::<JobSite>(villager)
+                .map_or(false, |site| site.0.is_some());
+        // Villagers don't restock while trading, as the
+        // trades shown to the player would be outdated.
+        if !working || world.has::<Customer>(villager) || !restocks.can_restock(time.world_age()) {
+            continue;
+        }
+
+        let mut offers = world.get_mut::<Offers>(villager);
+        if offers.0.iter().all(|offer| offer.uses == 0) {
+            continue;
+        }
+        offers.0.iter_mut().for_each(Offer::restock);
+        restocks.last = time.world_age();
+        restocks.today += 1;
+    }
+}
+
+/// Releases the job site of a villager which died.
+#[fecs::event_handler]
+pub fn on_entity_death_release_job_site(
+    event: &EntityDeathEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let site = match world.try_get::<JobSite>(event.entity) {
+        Some(site) => site.0,
+        None => return,
+    };
+
+    if let Some(site) = site {
+        let world_id = game.world_of(world, event.entity);
+        game.worlds[world_id].release_poi(site);
+    }
+}
+
+fn serialize(game: &Game, accessor: &EntityRef) -> EntityData {
+    let career = *accessor.get::<Career>();
+    let restocks = *accessor.get::<Restocks>();
+    let world_id = accessor
+        .try_get::<WorldId>()
+        .map_or(WorldId::MAIN, |id| *id);
+
+    let offers = accessor
+        .get::<Offers>()
+        .0
+        .iter()
+        .map(|offer| TradeOfferData {
+            buy: ItemData::from(offer.input_1),
+            buy_b: offer.input_2.map(ItemData::from),
+            sell: ItemData::from(offer.output),
+            uses: offer.uses,
+            max_uses: offer.max_uses,
+            reward_exp: offer.reward_xp as i8,
+            xp: offer.xp,
+            price_multiplier: offer.price_multiplier,
+            special_price: 0,
+            demand: offer.demand,
+        })
+        .collect();
+    let gossips = accessor
+        .get::<Gossips>()
+        .0
+        .iter()
+        .map(|gossip| GossipData::new(gossip.kind.identifier(), gossip.value, gossip.target))
+        .collect();
+
+    let mut brain = BrainData::default();
+    brain.memories.job_site = accessor.get::<JobSite>().0.map(|pos| MemoryData {
+        value: GlobalPositionData {
+            pos,
+            dimension: game.worlds[world_id].dimension.type_id().to_owned(),
+        },
+    });
+
+    EntityData::Villager(VillagerData {
+        animal: mob::serialize_animal(accessor, MAX_HEALTH),
+        kind: VillagerKindData {
+            profession: career.profession.identifier().to_owned(),
+            level: i32::from(career.level),
+            ..VillagerKindData::default()
+        },
+        xp: career.xp,
+        offers: Some(TradeOffersData { recipes: offers }),
+        gossips,
+        last_restock: restocks.last as i64,
+        restocks_today: i32::from(restocks.today),
+        brain,
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Villager(data) => {
+            let career = Career {
+                profession: Profession::from_identifier(&data.kind.profession)
+                    .unwrap_or(Profession::None),
+                level: data.kind.level.max(1).min(i32::from(MAX_LEVEL)) as u8,
+                xp: data.xp,
+            };
+            let offers = data
+                .offers
+                .map(|offers| offers.recipes)
+                .unwrap_or_default()
+                .iter()
+                .map(|offer| Offer {
+                    input_1: ItemStack::from(&offer.buy),
+                    input_2: offer
+                        .buy_b
+                        .as_ref()
+                        .map(ItemStack::from)
+                        .filter(|item| item.ty != Item::Air),
+                    output: ItemStack::from(&offer.sell),
+                    uses: offer.uses,
+                    max_uses: offer.max_uses,
+                    reward_xp: offer.reward_exp != 0,
+                    xp: offer.xp,
+                    price_multiplier: offer.price_multiplier,
+                    demand: offer.demand,
+                })
+                .collect();
+            let gossips = data
+                .gossips
+                .iter()
+                .filter_map(|gossip| {
+                    Some(Gossip {
+                        target: gossip.target(),
+                        kind: GossipKind::from_identifier(&gossip.kind)?,
+                        value: gossip.value,
+                    })
+                })
+                .collect();
+            let last_restock = data.last_restock.max(0) as u64;
+
+            let restocks = Restocks {
+                last: last_restock,
+                today: data.restocks_today.max(0).min(i32::from(MAX_RESTOCKS)) as u8,
+                day: last_restock / DAY_LENGTH,
+            };
+            let job_site = JobSite(data.brain.memories.job_site.map(|memory| memory.value.pos));
+
+            let builder = build(career, job_site, Offers(offers), restocks, Gossips(gossips));
+            mob::load_animal(builder, &data.animal)
+        }
+        _ => panic!("attempted to use villager::load to load a non-villager"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    fn offer() -> Offer {
+        Offer::new(
+            ItemStack::new(Item::Emerald, 10),
+            None,
+            ItemStack::new(Item::Bookshelf, 1),
+            12,
+            1,
+            0.05,
+        )
+    }
+
+    #[test]
+    fn prices() {
+        let mut offer = offer();
+        assert_eq!(offer.price(0).amount, 10);
+        assert_eq!(offer.price(-3).amount, 7);
+        assert_eq!(offer.price(-20).amount, 1);
+
+        // Demand of 4 raises the price by 10 * 4 * 0.05 = 2.
+        offer.demand = 4;
+        assert_eq!(offer.price(0).amount, 12);
+        offer.demand = -10;
+        assert_eq!(offer.price(0).amount, 10);
+
+        let mut book = offer;
+        book.input_1 = ItemStack::new(Item::Emerald, 64);
+        book.demand = 10;
+        assert_eq!(book.price(0).amount, 64);
+    }
+
+    #[test]
+    fn restock_updates_demand() {
+        let mut offer = offer();
+        offer.uses = 12;
+        offer.restock();
+        assert_eq!(offer.uses, 0);
+        assert_eq!(offer.demand, 12);
+
+        offer.uses = 2;
+        offer.restock();
+        assert_eq!(offer.demand, 4);
+        offer.restock();
+        assert_eq!(offer.demand, -8);
+    }
+
+    #[test]
+    fn pay_for_offers() {
+        let mut offer = offer();
+        offer.input_2 = Some(ItemStack::new(Item::Book, 1));
+
+        let emeralds = Some(ItemStack::new(Item::Emerald, 15));
+        let book = Some(ItemStack::new(Item::Book, 1));
+        assert!(offer.is_paid_by(emeralds, book, 0));
+        assert!(!offer.is_paid_by(emeralds, None, 0));
+        assert!(!offer.is_paid_by(book, emeralds, 0));
+        assert!(!offer.is_paid_by(emeralds, book, 6));
+
+        let (mut first, mut second) = (emeralds, book);
+        offer.pay(&mut first, &mut second, -2);
+        assert_eq!(first, Some(ItemStack::new(Item::Emerald, 7)));
+        assert_eq!(second, None);
+
+        offer.uses = offer.max_uses;
+        assert!(!offer.is_paid_by(emeralds, book, 0));
+    }
+
+    #[test]
+    fn find_offers() {
+        let mut cheap = offer();
+        cheap.input_1.amount = 5;
+        let offers = Offers(vec![offer(), cheap]);
+        let emeralds = |amount| Some(ItemStack::new(Item::Emerald, amount));
+
+        assert_eq!(offers.find(None, emeralds(10), None, &[]), Some(0));
+        assert_eq!(offers.find(Some(1), emeralds(10), None, &[]), Some(1));
+        assert_eq!(offers.find(Some(0), emeralds(7), None, &[]), Some(1));
+        assert_eq!(offers.find(None, None, emeralds(7), &[]), Some(1));
+        assert_eq!(offers.find(None, emeralds(7), None, &[-3, 0]), Some(0));
+        assert_eq!(offers.find(None, emeralds(4), None, &[]), None);
+    }
+
+    #[test]
+    fn gossip() {
+        let player = Uuid::from_u128(1);
+        let mut gossips = Gossips::default();
+        for _ in 0..20 {
+            gossips.add(player, GossipKind::Trading, 2);
+        }
+        assert_eq!(gossips.reputation(player), 25);
+        assert_eq!(gossips.reputation(Uuid::from_u128(2)), 0);
+        assert_eq!(offer().special_price(25), -1);
+
+        gossips.add(player, GossipKind::MinorNegative, 25);
+        assert_eq!(gossips.reputation(player), 0);
+        gossips.add(player, GossipKind::MajorNegative, 25);
+        assert_eq!(gossips.reputation(player), -125);
+        assert_eq!(offer().special_price(-125), 7);
+
+        gossips.decay();
+        assert_eq!(gossips.reputation(player), 23 - 5 - 75);
+        for _ in 0..2 {
+            gossips.decay();
+        }
+        assert_eq!(gossips.reputation(player), 19);
+    }
+
+    #[test]
+    fn levels() {
+        let mut career = Career {
+            profession: Profession::Librarian,
+            ..Career::default()
+        };
+        assert!(!career.can_level_up());
+        assert!(!career.has_traded());
+        career.xp = 10;
+        assert!(career.can_level_up());
+        career.level = 2;
+        assert!(!career.can_level_up());
+        career.level = MAX_LEVEL;
+        career.xp = 1000;
+        assert!(!career.can_level_up());
+
+        let mut offers = Offers::default();
+        let mut rng = StepRng::new(0, 1);
+        for level in 1..=MAX_LEVEL {
+            offers.unlock(Profession::Librarian, level, &mut rng);
+        }
+        assert_eq!(offers.0.len(), 9);
+        offers.unlock(Profession::Nitwit, 1, &mut rng);
+        assert_eq!(offers.0.len(), 9);
+    }
+
+    #[test]
+    fn professions() {
+        for &profession in &ALL_PROFESSIONS {
+            assert_eq!(
+                Profession::from_identifier(profession.identifier()),
+                Some(profession)
+            );
+            if let Some(site) = profession.job_site() {
+                assert!(site.is_job_site());
+                assert_eq!(Profession::from_job_site(site), Some(profession));
+            }
+        }
+        assert_eq!(Profession::from_job_site(PoiType::Home), None);
+    }
+
+    #[test]
+    fn restock_cooldown() {
+        let mut restocks = Restocks::default();
+        assert!(restocks.can_restock(0));
+        restocks.today = 1;
+        restocks.last = 3000;
+        assert!(!restocks.can_restock(5000));
+        assert!(restocks.can_restock(5401));
+        restocks.today = MAX_RESTOCKS;
+        assert!(!restocks.can_restock(10_000));
+    }
 }
@@ -0,0 +1,582 @@
+//! The trades unlocked at each level of a profession.
+//!
+//! These are the trades of vanilla 1.14 whose items exist
+//! in 1.13. Trades which would need items added since, like
+//! bells or crossbows, are left out.
+
+use super::{Offer, Profession};
+use feather_core::items::{Enchantment, Item, ItemStack};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Price multiplier of trades for common items.
+const LOW_MULTIPLIER: f32 = 0.05;
+
+/// Price multiplier of trades for gear and enchanted books.
+const HIGH_MULTIPLIER: f32 = 0.2;
+
+/// Uses of trades for enchanted gear before restocking.
+const ENCHANTED_MAX_USES: i32 = 3;
+
+/// A template from which an offer is generated.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TradeTemplate {
+    /// The villager buys `count` of an item for an emerald.
+    Buy {
+        item: Item,
+        count: u8,
+        max_uses: i32,
+        xp: i32,
+    },
+    /// The villager sells `count` of an item for `price` emeralds.
+    Sell {
+        item: Item,
+        price: u8,
+        count: u8,
+        max_uses: i32,
+        xp: i32,
+        multiplier: f32,
+    },
+    /// The villager turns `count` of an item and an
+    /// emerald into `output_count` of another item.
+    Convert {
+        item: Item,
+        count: u8,
+        output: Item,
+        output_count: u8,
+        max_uses: i32,
+        xp: i32,
+    },
+    /// The villager sells a book with a random enchantment
+    /// for emeralds and a book.
+    EnchantedBook { xp: i32 },
+    /// The villager sells a randomly enchanted item for `price`
+    /// emeralds and more for each level of the enchantment.
+    EnchantedItem { item: Item, price: u8, xp: i32 },
+}
+
+const fn buy(item: Item, count: u8, max_uses: i32, xp: i32) -> TradeTemplate {
+    TradeTemplate::Buy {
+        item,
+        count,
+        max_uses,
+        xp,
+    }
+}
+
+const fn sell(item: Item, price: u8, count: u8, max_uses: i32, xp: i32) -> TradeTemplate {
+    TradeTemplate::Sell {
+        item,
+        price,
+        count,
+        max_uses,
+        xp,
+        multiplier: LOW_MULTIPLIER,
+    }
+}
+
+const fn sell_gear(item: Item, price: u8, xp: i32) -> TradeTemplate {
+    TradeTemplate::Sell {
+        item,
+        price,
+        count: 1,
+        max_uses: 12,
+        xp,
+        multiplier: HIGH_MULTIPLIER,
+    }
+}
+
+const fn convert(
+    item: Item,
+    count: u8,
+    output: Item,
+    output_count: u8,
+    max_uses: i32,
+    xp: i32,
+) -> TradeTemplate {
+    TradeTemplate::Convert {
+        item,
+        count,
+        output,
+        output_count,
+        max_uses,
+        xp,
+    }
+}
+
+const fn enchanted(item: Item, price: u8, xp: i32) -> TradeTemplate {
+    TradeTemplate::EnchantedItem { item, price, xp }
+}
+
+const fn book(xp: i32) -> TradeTemplate {
+    TradeTemplate::EnchantedBook { xp }
+}
+
+type Levels = [&'static [TradeTemplate]; 5];
+
+const ARMORER: Levels = [
+    &[
+        buy(Item::Coal, 15, 16, 2),
+        sell_gear(Item::IronLeggings, 7, 1),
+        sell_gear(Item::IronBoots, 4, 1),
+        sell_gear(Item::IronHelmet, 5, 1),
+        sell_gear(Item::IronChestplate, 9, 1),
+    ],
+    &[
+        buy(Item::IronIngot, 4, 12, 10),
+        sell_gear(Item::ChainmailBoots, 1, 5),
+        sell_gear(Item::ChainmailLeggings, 3, 5),
+    ],
+    &[
+        buy(Item::LavaBucket, 1, 12, 20),
+        buy(Item::Diamond, 1, 12, 20),
+        sell_gear(Item::ChainmailHelmet, 1, 10),
+        sell_gear(Item::ChainmailChestplate, 4, 10),
+        sell_gear(Item::Shield, 5, 10),
+    ],
+    &[
+        enchanted(Item::DiamondLeggings, 14, 15),
+        enchanted(Item::DiamondBoots, 8, 15),
+    ],
+    &[
+        enchanted(Item::DiamondHelmet, 8, 30),
+        enchanted(Item::DiamondChestplate, 16, 30),
+    ],
+];
+
+const BUTCHER: Levels = [
+    &[
+        buy(Item::Chicken, 14, 16, 2),
+        buy(Item::Porkchop, 7, 16, 2),
+        buy(Item::Rabbit, 4, 16, 2),
+        sell(Item::RabbitStew, 1, 1, 12, 1),
+    ],
+    &[
+        buy(Item::Coal, 15, 16, 2),
+        sell(Item::CookedPorkchop, 1, 5, 16, 5),
+        sell(Item::CookedChicken, 1, 8, 16, 5),
+    ],
+    &[buy(Item::Mutton, 7, 16, 20), buy(Item::Beef, 10, 16, 20)],
+    &[buy(Item::DriedKelpBlock, 10, 12, 30)],
+    &[],
+];
+
+const CARTOGRAPHER: Levels = [
+    &[buy(Item::Paper, 24, 16, 2), sell(Item::Map, 7, 1, 12, 1)],
+    &[buy(Item::GlassPane, 11, 16, 10)],
+    &[buy(Item::Compass, 1, 12, 20)],
+    &[
+        sell(Item::ItemFrame, 7, 1, 12, 15),
+        sell(Item::WhiteBanner, 3, 1, 12, 15),
+    ],
+    &[],
+];
+
+const CLERIC: Levels = [
+    &[
+        buy(Item::RottenFlesh, 32, 16, 2),
+        sell(Item::Redstone, 1, 2, 12, 1),
+    ],
+    &[
+        buy(Item::GoldIngot, 3, 12, 10),
+        sell(Item::LapisLazuli, 1, 1, 12, 5),
+    ],
+    &[
+        buy(Item::RabbitFoot, 2, 12, 20),
+        sell(Item::Glowstone, 4, 1, 12, 10),
+    ],
+    &[
+        buy(Item::Scute, 4, 12, 30),
+        buy(Item::GlassBottle, 9, 12, 30),
+        sell(Item::EnderPearl, 5, 1, 12, 15),
+    ],
+    &[
+        buy(Item::NetherWart, 22, 12, 30),
+        sell(Item::ExperienceBottle, 3, 1, 12, 30),
+    ],
+];
+
+const FARMER: Levels = [
+    &[
+        buy(Item::Wheat, 20, 16, 2),
+        buy(Item::Potato, 26, 16, 2),
+        buy(Item::Carrot, 22, 16, 2),
+        buy(Item::Beetroot, 15, 16, 2),
+        sell(Item::Bread, 1, 6, 16, 1),
+    ],
+    &[
+        buy(Item::Pumpkin, 6, 12, 10),
+        sell(Item::PumpkinPie, 1, 4, 12, 5),
+        sell(Item::Apple, 1, 4, 16, 5),
+    ],
+    &[
+        sell(Item::Cookie, 3, 18, 12, 10),
+        buy(Item::Melon, 4, 12, 20),
+    ],
+    &[sell(Item::Cake, 1, 1, 12, 15)],
+    &[
+        sell(Item::GoldenCarrot, 3, 3, 12, 30),
+        sell(Item::GlisteringMelonSlice, 4, 3, 12, 30),
+    ],
+];
+
+const FISHERMAN: Levels = [
+    &[
+        buy(Item::String, 20, 16, 2),
+        buy(Item::Coal, 10, 16, 2),
+        convert(Item::Cod, 6, Item::CookedCod, 6, 16, 1),
+        sell(Item::CodBucket, 3, 1, 16, 1),
+    ],
+    &[
+        buy(Item::Cod, 15, 16, 10),
+        convert(Item::Salmon, 6, Item::CookedSalmon, 6, 16, 5),
+    ],
+    &[
+        buy(Item::Salmon, 13, 16, 20),
+        enchanted(Item::FishingRod, 3, 10),
+    ],
+    &[buy(Item::TropicalFish, 6, 12, 30)],
+    &[buy(Item::Pufferfish, 4, 12, 30)],
+];
+
+const FLETCHER: Levels = [
+    &[
+        buy(Item::Stick, 32, 16, 2),
+        sell(Item::Arrow, 1, 16, 12, 1),
+        convert(Item::Gravel, 10, Item::Flint, 10, 12, 1),
+    ],
+    &[buy(Item::Flint, 26, 12, 10), sell_gear(Item::Bow, 2, 5)],
+    &[buy(Item::String, 14, 16, 20)],
+    &[buy(Item::Feather, 24, 16, 30), enchanted(Item::Bow, 2, 15)],
+    &[buy(Item::TripwireHook, 8, 12, 30)],
+];
+
+const LEATHERWORKER: Levels = [
+    &[
+        buy(Item::Leather, 6, 16, 2),
+        sell_gear(Item::LeatherLeggings, 3, 1),
+        sell_gear(Item::LeatherChestplate, 7, 1),
+    ],
+    &[
+        buy(Item::Flint, 26, 12, 10),
+        sell_gear(Item::LeatherHelmet, 5, 5),
+        sell_gear(Item::LeatherBoots, 4, 5),
+    ],
+    &[
+        buy(Item::RabbitHide, 9, 12, 20),
+        sell_gear(Item::LeatherChestplate, 7, 10),
+    ],
+    &[
+        buy(Item::Scute, 4, 12, 30),
+        sell_gear(Item::LeatherBoots, 4, 15),
+    ],
+    &[
+        sell_gear(Item::Saddle, 6, 30),
+        sell_gear(Item::LeatherHelmet, 5, 30),
+    ],
+];
+
+const LIBRARIAN: Levels = [
+    &[
+        buy(Item::Paper, 24, 16, 2),
+        book(1),
+        sell(Item::Bookshelf, 9, 1, 12, 1),
+    ],
+    &[buy(Item::Book, 4, 12, 10), book(5)],
+    &[
+        buy(Item::InkSac, 5, 12, 20),
+        book(10),
+        sell(Item::Glass, 1, 4, 12, 10),
+    ],
+    &[
+        buy(Item::WritableBook, 2, 12, 30),
+        book(15),
+        sell(Item::Clock, 5, 1, 12, 15),
+        sell(Item::Compass, 4, 1, 12, 15),
+    ],
+    &[sell(Item::NameTag, 20, 1, 12, 30)],
+];
+
+const MASON: Levels = [
+    &[
+        buy(Item::ClayBall, 10, 16, 2),
+        sell(Item::Brick, 1, 10, 16, 1),
+    ],
+    &[
+        buy(Item::Stone, 20, 16, 10),
+        sell(Item::ChiseledStoneBricks, 1, 4, 16, 5),
+    ],
+    &[
+        buy(Item::Granite, 16, 16, 20),
+        buy(Item::Andesite, 16, 16, 20),
+        buy(Item::Diorite, 16, 16, 20),
+        sell(Item::PolishedAndesite, 1, 4, 16, 10),
+        sell(Item::PolishedDiorite, 1, 4, 16, 10),
+        sell(Item::PolishedGranite, 1, 4, 16, 10),
+    ],
+    &[
+        buy(Item::Quartz, 12, 12, 30),
+        sell(Item::WhiteTerracotta, 1, 1, 12, 15),
+        sell(Item::OrangeTerracotta, 1, 1, 12, 15),
+        sell(Item::WhiteGlazedTerracotta, 1, 1, 12, 15),
+        sell(Item::OrangeGlazedTerracotta, 1, 1, 12, 15),
+    ],
+    &[
+        sell(Item::QuartzPillar, 1, 1, 12, 30),
+        sell(Item::QuartzBlock, 1, 1, 12, 30),
+    ],
+];
+
+const SHEPHERD: Levels = [
+    &[
+        buy(Item::WhiteWool, 18, 16, 2),
+        buy(Item::BrownWool, 18, 16, 2),
+        buy(Item::BlackWool, 18, 16, 2),
+        buy(Item::GrayWool, 18, 16, 2),
+        sell(Item::Shears, 2, 1, 12, 1),
+    ],
+    &[
+        buy(Item::InkSac, 12, 16, 10),
+        buy(Item::GrayDye, 12, 16, 10),
+        buy(Item::LimeDye, 12, 16, 10),
+        buy(Item::LightBlueDye, 12, 16, 10),
+        sell(Item::WhiteWool, 1, 1, 16, 5),
+        sell(Item::WhiteCarpet, 1, 4, 16, 5),
+    ],
+    &[
+        buy(Item::DandelionYellow, 12, 16, 20),
+        buy(Item::LightGrayDye, 12, 16, 20),
+        sell(Item::WhiteBed, 3, 1, 12, 10),
+    ],
+    &[
+        buy(Item::CocoaBeans, 12, 16, 30),
+        buy(Item::PurpleDye, 12, 16, 30),
+        buy(Item::MagentaDye, 12, 16, 30),
+        sell(Item::WhiteBanner, 3, 1, 12, 15),
+    ],
+    &[sell(Item::Painting, 2, 3, 12, 30)],
+];
+
+const TOOLSMITH: Levels = [
+    &[
+        buy(Item::Coal, 15, 16, 2),
+        sell_gear(Item::StoneAxe, 1, 1),
+        sell_gear(Item::StoneShovel, 1, 1),
+        sell_gear(Item::StonePickaxe, 1, 1),
+        sell_gear(Item::StoneHoe, 1, 1),
+    ],
+    &[buy(Item::IronIngot, 4, 12, 10)],
+    &[
+        buy(Item::Flint, 30, 12, 20),
+        enchanted(Item::IronAxe, 1, 10),
+        enchanted(Item::IronShovel, 2, 10),
+        enchanted(Item::IronPickaxe, 3, 10),
+        sell_gear(Item::DiamondHoe, 4, 10),
+    ],
+    &[
+        buy(Item::Diamond, 1, 12, 30),
+        enchanted(Item::DiamondAxe, 12, 15),
+        enchanted(Item::DiamondShovel, 5, 15),
+    ],
+    &[enchanted(Item::DiamondPickaxe, 13, 30)],
+];
+
+const WEAPONSMITH: Levels = [
+    &[
+        buy(Item::Coal, 15, 16, 2),
+        sell_gear(Item::IronAxe, 3, 1),
+        enchanted(Item::IronSword, 2, 1),
+    ],
+    &[buy(Item::IronIngot, 4, 12, 10)],
+    &[buy(Item::Flint, 24, 12, 20)],
+    &[
+        buy(Item::Diamond, 1, 12, 30),
+        enchanted(Item::DiamondAxe, 12, 15),
+    ],
+    &[enchanted(Item::DiamondSword, 8, 30)],
+];
+
+/// Returns the trades of each level of a profession.
+pub fn levels(profession: Profession) -> Option<&'static Levels> {
+    Some(match profession {
+        Profession::None | Profession::Nitwit => return None,
+        Profession::Armorer => &ARMORER,
+        Profession::Butcher => &BUTCHER,
+        Profession::Cartographer => &CARTOGRAPHER,
+        Profession::Cleric => &CLERIC,
+        Profession::Farmer => &FARMER,
+        Profession::Fisherman => &FISHERMAN,
+        Profession::Fletcher => &FLETCHER,
+        Profession::Leatherworker => &LEATHERWORKER,
+        Profession::Librarian => &LIBRARIAN,
+        Profession::Mason => &MASON,
+        Profession::Shepherd => &SHEPHERD,
+        Profession::Toolsmith => &TOOLSMITH,
+        Profession::Weaponsmith => &WEAPONSMITH,
+    })
+}
+
+/// Generates up to `count` different offers unlocked
+/// at the given level of a profession.
+pub fn generate(profession: Profession, level: u8, count: usize, rng: &mut impl Rng) -> Vec<Offer> {
+    let templates = match levels(profession) {
+        Some(levels) if level >= 1 && level as usize <= levels.len() => levels[level as usize - 1],
+        _ => return Vec::new(),
+    };
+
+    templates
+        .choose_multiple(rng, count)
+        .copied()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|template| template.generate(rng))
+        .collect()
+}
+
+impl TradeTemplate {
+    /// Generates an offer from this template.
+    pub fn generate(self, rng: &mut impl Rng) -> Offer {
+        let emeralds = |amount: u8| ItemStack::new(Item::Emerald, amount);
+        match self {
+            TradeTemplate::Buy {
+                item,
+                count,
+                max_uses,
+                xp,
+            } => Offer::new(
+                ItemStack::new(item, count),
+                None,
+                emeralds(1),
+                max_uses,
+                xp,
+                LOW_MULTIPLIER,
+            ),
+            TradeTemplate::Sell {
+                item,
+                price,
+                count,
+                max_uses,
+                xp,
+                multiplier,
+            } => Offer::new(
+                emeralds(price),
+                None,
+                ItemStack::new(item, count),
+                max_uses,
+                xp,
+                multiplier,
+            ),
+            TradeTemplate::Convert {
+                item,
+                count,
+                output,
+                output_count,
+                max_uses,
+                xp,
+            } => Offer::new(
+                emeralds(1),
+                Some(ItemStack::new(item, count)),
+                ItemStack::new(output, output_count),
+                max_uses,
+                xp,
+                LOW_MULTIPLIER,
+            ),
+            TradeTemplate::EnchantedBook { xp } => {
+                let enchantment = *Enchantment::values().choose(rng).unwrap();
+                let level = rng.gen_range(1, enchantment.max_level() + 1);
+
+                let mut price =
+                    2 + rng.gen_range(0, 5 + i32::from(level) * 10) + 3 * i32::from(level);
+                if is_treasure(enchantment) {
+                    price *= 2;
+                }
+
+                let mut output = ItemStack::new(Item::EnchantedBook, 1);
+                output.enchantments.set(enchantment, level);
+                Offer::new(
+                    emeralds(price.min(64) as u8),
+                    Some(ItemStack::new(Item::Book, 1)),
+                    output,
+                    12,
+                    xp,
+                    HIGH_MULTIPLIER,
+                )
+            }
+            TradeTemplate::EnchantedItem { item, price, xp } => {
+                // Vanilla enchants with 5 to 19 levels, which
+                // are also added to the price.
+                let levels = rng.gen_range(5, 20);
+
+                let mut output = ItemStack::new(item, 1);
+                let applicable: Vec<Enchantment> = Enchantment::values()
+                    .iter()
+                    .copied()
+                    .filter(|enchantment| {
+                        enchantment.can_apply_to(item) && !is_treasure(*enchantment)
+                    })
+                    .collect();
+                if let Some(&enchantment) = applicable.choose(rng) {
+                    let max_level = i32::from(enchantment.max_level());
+                    let level = (1 + (max_level - 1) * (levels - 5) / 14) as i16;
+                    output.enchantments.set(enchantment, level);
+                }
+
+                Offer::new(
+                    emeralds((i32::from(price) + levels).min(64) as u8),
+                    None,
+                    output,
+                    ENCHANTED_MAX_USES,
+                    xp,
+                    HIGH_MULTIPLIER,
+                )
+            }
+        }
+    }
+}
+
+/// Returns whether an enchantment is a treasure, which
+/// can't be got from enchanting tables and costs more.
+fn is_treasure(enchantment: Enchantment) -> bool {
+    enchantment.is_curse() || matches!(enchantment, Enchantment::Mending | Enchantment::FrostWalker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn generate_offers() {
+        let mut rng = StepRng::new(0, 1);
+        let offers = generate(Profession::Farmer, 1, 2, &mut rng);
+        assert_eq!(offers.len(), 2);
+        assert_ne!(offers[0], offers[1]);
+
+        assert_eq!(generate(Profession::Farmer, 4, 2, &mut rng).len(), 1);
+        assert!(generate(Profession::Nitwit, 1, 2, &mut rng).is_empty());
+        assert!(generate(Profession::Farmer, 6, 2, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn enchanted_book() {
+        let mut rng = StepRng::new(0, 1);
+        for _ in 0..10 {
+            let offer = book(1).generate(&mut rng);
+            assert_eq!(offer.input_1.ty, Item::Emerald);
+            assert!(offer.input_1.amount >= 5 && offer.input_1.amount <= 64);
+            assert_eq!(offer.input_2, Some(ItemStack::new(Item::Book, 1)));
+            assert_eq!(offer.output.enchantments.iter().count(), 1);
+        }
+    }
+
+    #[test]
+    fn enchanted_item() {
+        let mut rng = StepRng::new(0, 1);
+        let offer = enchanted(Item::DiamondSword, 8, 30).generate(&mut rng);
+        assert!(offer.input_1.amount >= 13 && offer.input_1.amount <= 27);
+        let (enchantment, level) = offer.output.enchantments.iter().next().unwrap();
+        assert!(enchantment.can_apply_to(Item::DiamondSword));
+        assert!(level >= 1 && level <= enchantment.max_level());
+        assert_eq!(offer.max_uses, ENCHANTED_MAX_USES);
+    }
+}
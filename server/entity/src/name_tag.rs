@@ -0,0 +1,105 @@
+//! Name tags, which give mobs the custom name of the tag.
+//! Named mobs never despawn.
+//!
+//! See https://minecraft.gamepedia.com/Name_Tag.
+
+use crate::{consume_held_item, Leashed};
+use feather_core::entitymeta::Metadata;
+use feather_core::items::Item;
+use feather_core::text::Text;
+use feather_server_types::{CanDespawn, EntityInteractEvent, Game};
+use fecs::World;
+
+/// When a player right clicks a mob with a renamed name tag,
+/// names the mob after it. Mobs on the player's leash are
+/// unleashed instead.
+#[fecs::event_handler]
+pub fn on_entity_interact_apply_name_tag(
+    event: &EntityInteractEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let (stack, name) = match event.stack {
+        Some(stack) if stack.ty == Item::NameTag => match stack.name {
+            Some(name) => (stack, name),
+            None => return,
+        },
+        _ => return,
+    };
+    let held_by_player = world
+        .try_get::<Leashed>(event.target)
+        .map_or(false, |leashed| leashed.holder == event.player);
+    if held_by_player {
+        return;
+    }
+
+    match world.try_get_mut::<Metadata>(event.target) {
+        Some(mut meta) => {
+            meta.set_custom_name(Some(Text::from(name.as_str().to_owned()).to_string()))
+        }
+        None => return,
+    }
+    let _ = world.remove::<CanDespawn>(event.target);
+    consume_held_item(game, world, event.player, stack);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MobKind;
+    use feather_core::items::{ItemName, ItemStack};
+    use feather_test_framework::Test;
+
+    fn name_tag(name: Option<&str>) -> ItemStack {
+        let mut stack = ItemStack::new(Item::NameTag, 1);
+        stack.name = name.map(ItemName::new);
+        stack
+    }
+
+    #[test]
+    fn name_mob() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let zombie = test.entity(
+            MobKind::Zombie
+                .create()
+                .with(position!(1.0, 64.0, 0.0))
+                .with(CanDespawn),
+        );
+
+        let event = EntityInteractEvent {
+            player,
+            target: zombie,
+            stack: Some(name_tag(Some("Bob"))),
+        };
+        test.handle(event, on_entity_interact_apply_name_tag);
+
+        assert_eq!(
+            test.world.get::<Metadata>(zombie).custom_name(),
+            Some(String::from("\"Bob\""))
+        );
+        assert!(!test.world.has::<CanDespawn>(zombie));
+    }
+
+    #[test]
+    fn unnamed_tag_does_nothing() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let zombie = test.entity(
+            MobKind::Zombie
+                .create()
+                .with(position!(1.0, 64.0, 0.0))
+                .with(CanDespawn),
+        );
+
+        let event = EntityInteractEvent {
+            player,
+            target: zombie,
+            stack: Some(name_tag(None)),
+        };
+        test.handle(event, on_entity_interact_apply_name_tag);
+
+        assert_eq!(test.world.get::<Metadata>(zombie).custom_name(), None);
+        assert!(test.world.has::<CanDespawn>(zombie));
+    }
+}
@@ -1,4 +1,15 @@
+pub mod armor_stand;
 pub mod arrow;
+pub mod boat;
+pub mod end_crystal;
+pub mod experience_orb;
 pub mod falling_block;
+pub mod firework_rocket;
+pub mod hanging;
 pub mod item;
+pub mod item_frame;
+pub mod leash_knot;
+pub mod minecart;
+pub mod painting;
 pub mod supported_blocks;
+pub mod tnt;
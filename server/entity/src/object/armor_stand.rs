@@ -0,0 +1,578 @@
+//! Armor stands, which players place to display armor and
+//! held items. Right clicking a stand with an item equips it,
+//! and with an empty hand takes back the item at the height
+//! clicked. Armor stands only hold items in their hands when
+//! they show their arms.
+//!
+//! Hitting a stand twice in quick succession breaks it,
+//! dropping it along with its equipment, while players in
+//! creative mode break it at once without drops.
+
+use crate::consume_held_item;
+use crate::drops::drop_item;
+use crate::inventory::Equipment;
+use feather_core::anvil::entity::{
+    ArmorStandEntityData, ArmorStandPoseData, BaseEntityData, EntityData, EntityDataKind, ItemData,
+};
+use feather_core::entitymeta::{
+    ArmorStandBitMask, EntityBitMask, EntityMetadata, Rotation, ARMOR_STAND_LAYOUT,
+    META_INDEX_ARMOR_STAND_BITMASK, META_INDEX_ARMOR_STAND_BODY_ROTATION,
+    META_INDEX_ARMOR_STAND_HEAD_ROTATION, META_INDEX_ARMOR_STAND_LEFT_ARM_ROTATION,
+    META_INDEX_ARMOR_STAND_LEFT_LEG_ROTATION, META_INDEX_ARMOR_STAND_RIGHT_ARM_ROTATION,
+    META_INDEX_ARMOR_STAND_RIGHT_LEG_ROTATION, META_INDEX_ENTITY_BITMASK,
+};
+use feather_core::inventory::{slot, Area, SlotIndex};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{EntityStatus, SoundCategory, SpawnObject};
+use feather_core::network::Packet;
+use feather_core::util::{Gamemode, Position, Vec3d};
+use feather_server_types::{
+    ComponentSerializer, EntityLoaderRegistration, EntitySpawnEvent, Game, HeldItem, Inventory,
+    InventoryUpdateEvent, ItemUseOnBlockEvent, NetworkId, Sound, SpawnPacketCreator, Uuid,
+};
+use feather_server_util::{degrees_to_stops, nearby_entities};
+use fecs::{Entity, EntityBuilder, EntityRef, World};
+use smallvec::smallvec;
+
+/// Entity status which wobbles an armor stand after it is hit.
+const HIT_STATUS: i8 = 32;
+
+/// Ticks within which a second hit breaks an armor stand.
+const BREAK_TICKS: u64 = 5;
+
+/// Equipment saved in `ArmorItems`, in order.
+const ARMOR_ITEMS: [Equipment; 4] = [
+    Equipment::Boots,
+    Equipment::Leggings,
+    Equipment::Chestplate,
+    Equipment::Helmet,
+];
+
+/// Equipment saved in `HandItems`, in order.
+const HAND_ITEMS: [Equipment; 2] = [Equipment::MainHand, Equipment::OffHand];
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::ArmorStand, &load)
+}
+
+/// Component for armor stands.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ArmorStand {
+    pub flags: ArmorStandBitMask,
+    pub invisible: bool,
+    /// The tick at which the stand was last hit, if any.
+    pub last_hit: Option<u64>,
+}
+
+impl Default for ArmorStand {
+    fn default() -> Self {
+        Self {
+            flags: ArmorStandBitMask::empty(),
+            invisible: false,
+            last_hit: None,
+        }
+    }
+}
+
+/// The rotation of each part of an armor stand.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ArmorStandPose {
+    pub head: Rotation,
+    pub body: Rotation,
+    pub left_arm: Rotation,
+    pub right_arm: Rotation,
+    pub left_leg: Rotation,
+    pub right_leg: Rotation,
+}
+
+impl Default for ArmorStandPose {
+    fn default() -> Self {
+        Self {
+            head: Rotation::new(0.0, 0.0, 0.0),
+            body: Rotation::new(0.0, 0.0, 0.0),
+            left_arm: Rotation::new(-10.0, 0.0, -10.0),
+            right_arm: Rotation::new(-15.0, 0.0, 10.0),
+            left_leg: Rotation::new(-1.0, 0.0, -1.0),
+            right_leg: Rotation::new(1.0, 0.0, 1.0),
+        }
+    }
+}
+
+/// Returns an entity builder to create an armor stand without
+/// equipment at the given position. Its world still has to be added.
+pub fn create(pos: Position) -> EntityBuilder {
+    create_with(
+        pos,
+        ArmorStand::default(),
+        ArmorStandPose::default(),
+        Inventory::equipment(),
+    )
+}
+
+/// Returns an entity builder to create an armor stand with the
+/// given flags, pose and equipment. Its world still has to be added.
+pub fn create_with(
+    pos: Position,
+    stand: ArmorStand,
+    pose: ArmorStandPose,
+    equipment: Inventory,
+) -> EntityBuilder {
+    let mut bitmask = EntityBitMask::empty();
+    bitmask.set(EntityBitMask::INVISIBLE, stand.invisible);
+    let meta = EntityMetadata::entity_base()
+        .with_layout(ARMOR_STAND_LAYOUT)
+        .with(META_INDEX_ENTITY_BITMASK, bitmask.bits())
+        .with(META_INDEX_ARMOR_STAND_BITMASK, stand.flags.bits())
+        .with(META_INDEX_ARMOR_STAND_HEAD_ROTATION, pose.head)
+        .with(META_INDEX_ARMOR_STAND_BODY_ROTATION, pose.body)
+        .with(META_INDEX_ARMOR_STAND_LEFT_ARM_ROTATION, pose.left_arm)
+        .with(META_INDEX_ARMOR_STAND_RIGHT_ARM_ROTATION, pose.right_arm)
+        .with(META_INDEX_ARMOR_STAND_LEFT_LEG_ROTATION, pose.left_leg)
+        .with(META_INDEX_ARMOR_STAND_RIGHT_LEG_ROTATION, pose.right_leg);
+
+    crate::base()
+        .with(stand)
+        .with(pose)
+        .with(pos)
+        .with(equipment)
+        .with(HeldItem(0))
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(ComponentSerializer(&serialize))
+}
+
+/// Places an armor stand when a player uses one on a block
+/// with two free blocks above, turned towards the player.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_place_armor_stand(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.stack.ty != Item::ArmorStand {
+        return;
+    }
+    let base = event.pos + event.face.placement_offset();
+    let free = [base, base.up()].iter().all(|&pos| {
+        game.block_at(event.world, pos)
+            .map_or(false, |block| block.is_air())
+    });
+    let center = base.position() + glm::vec3(0.5, 1.0, 0.5);
+    if !free
+        || !nearby_entities(world, game, event.world, center, glm::vec3(0.5, 1.0, 0.5)).is_empty()
+    {
+        return;
+    }
+
+    // Stands face the player, snapped to 45 degrees.
+    let player_yaw = world.get::<Position>(event.player).yaw;
+    let mut pos = base.position() + glm::vec3(0.5, 0.0, 0.5);
+    pos.yaw = ((player_yaw - 180.0 + 22.5) / 45.0).floor() * 45.0;
+
+    let entity = create(pos).with(event.world).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+    consume_held_item(game, world, event.player, event.stack);
+
+    let sound = Sound::new("entity.armor_stand.place", SoundCategory::Blocks)
+        .volume(0.75)
+        .pitch(0.8);
+    game.play_sound(world, event.world, pos, &sound);
+}
+
+/// Returns the equipment a player with an empty hand takes
+/// from an armor stand when clicking it at height `y` above
+/// its feet, if it holds any there.
+fn equipment_at(world: &World, stand: Entity, y: f32) -> Option<Equipment> {
+    let small = world
+        .get::<ArmorStand>(stand)
+        .flags
+        .contains(ArmorStandBitMask::SMALL);
+    let y = if small { y * 2.0 } else { y };
+    let (chest_min, chest_max, legs_max) = if small {
+        (1.2, 1.9, 1.4)
+    } else {
+        (0.9, 1.6, 0.9)
+    };
+
+    let equipment = world.get::<Inventory>(stand);
+    let holds = |equipment_slot: Equipment| {
+        let slot = equipment_slot.slot_index(0);
+        equipment.item_at(slot.area, slot.slot).unwrap().is_some()
+    };
+    // The ranges overlap, so parts are tried in the order
+    // vanilla uses. Empty slots are skipped.
+    let parts = [
+        ((0.1..0.55).contains(&y), Equipment::Boots),
+        ((chest_min..chest_max).contains(&y), Equipment::Chestplate),
+        ((0.4..legs_max).contains(&y), Equipment::Leggings),
+        (y >= 1.6, Equipment::Helmet),
+        (true, Equipment::MainHand),
+    ];
+    parts
+        .iter()
+        .find(|&&(clicked, equipment)| clicked && holds(equipment))
+        .map(|&(_, equipment)| equipment)
+}
+
+/// Handles a player right clicking an armor stand at height
+/// `y` above its feet. An item held by the player is equipped
+/// on the stand, swapping it with the item already equipped in
+/// its slot, while an empty hand takes the item clicked.
+pub fn use_armor_stand(game: &mut Game, world: &mut World, stand: Entity, player: Entity, y: f32) {
+    let flags = world.get::<ArmorStand>(stand).flags;
+    if flags.contains(ArmorStandBitMask::MARKER) {
+        return;
+    }
+
+    let held_slot = slot(Area::Hotbar, world.get::<HeldItem>(player).0);
+    let held = world
+        .get::<Inventory>(player)
+        .item_at(held_slot.area, held_slot.slot)
+        .unwrap();
+    let equipment = match held {
+        Some(stack) => Equipment::for_item(stack.ty),
+        None => match equipment_at(world, stand, y) {
+            Some(equipment) => equipment,
+            None => return,
+        },
+    };
+    if equipment == Equipment::MainHand && !flags.contains(ArmorStandBitMask::HAS_ARMS) {
+        return;
+    }
+
+    let stand_slot = equipment.slot_index(0);
+    let equipped = world
+        .get::<Inventory>(stand)
+        .item_at(stand_slot.area, stand_slot.slot)
+        .unwrap();
+    let creative = *world.get::<Gamemode>(player) == Gamemode::Creative;
+
+    match held {
+        // Players in creative mode keep the item they equip.
+        Some(stack) if creative && equipped.is_none() => {
+            set_item(game, world, stand, stand_slot, Some(stack.of_amount(1)));
+        }
+        // Only one item of a stack is equipped, in an empty slot.
+        Some(stack) if stack.amount > 1 => {
+            if equipped.is_some() {
+                return;
+            }
+            set_item(game, world, stand, stand_slot, Some(stack.of_amount(1)));
+            consume_held_item(game, world, player, stack);
+        }
+        _ => {
+            set_item(game, world, stand, stand_slot, held);
+            set_item(game, world, player, held_slot, equipped);
+        }
+    }
+}
+
+/// Sets the item in a slot of an inventory and
+/// notifies the handlers of inventory updates.
+fn set_item(
+    game: &mut Game,
+    world: &mut World,
+    entity: Entity,
+    index: SlotIndex,
+    item: Option<ItemStack>,
+) {
+    {
+        let inventory = world.get::<Inventory>(entity);
+        match item {
+            Some(stack) => inventory.set_item_at(index.area, index.slot, stack),
+            None => inventory.remove_item_at(index.area, index.slot),
+        }
+        .unwrap();
+    }
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: smallvec![index],
+            entity,
+        },
+    );
+}
+
+/// Handles a player hitting an armor stand. The stand
+/// breaks when hit again shortly after, or at once by
+/// players in creative mode.
+pub fn hit_armor_stand(game: &mut Game, world: &mut World, stand: Entity, player: Entity) {
+    if *world.get::<Gamemode>(player) == Gamemode::Creative {
+        break_armor_stand(game, world, stand, false);
+        return;
+    }
+
+    let tick = game.tick_count;
+    let last_hit = world.get_mut::<ArmorStand>(stand).last_hit.replace(tick);
+    if last_hit.map_or(false, |last_hit| tick - last_hit <= BREAK_TICKS) {
+        break_armor_stand(game, world, stand, true);
+        return;
+    }
+
+    let packet = EntityStatus {
+        entity_id: world.get::<NetworkId>(stand).0,
+        entity_status: HIT_STATUS,
+    };
+    game.broadcast_entity_update(world, packet, stand, None);
+    let world_id = game.world_of(world, stand);
+    let pos = *world.get::<Position>(stand);
+    let sound = Sound::new("entity.armor_stand.hit", SoundCategory::Neutral);
+    game.play_sound(world, world_id, pos, &sound);
+}
+
+/// Breaks an armor stand, dropping it along with
+/// its equipment if `drops` is set.
+pub fn break_armor_stand(game: &mut Game, world: &mut World, stand: Entity, drops: bool) {
+    let world_id = game.world_of(world, stand);
+    let pos = *world.get::<Position>(stand);
+    let items: Vec<ItemStack> = if drops {
+        let equipment = world.get::<Inventory>(stand);
+        std::iter::once(ItemStack::new(Item::ArmorStand, 1))
+            .chain(equipment.enumerate().filter_map(|(_, item)| item))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    game.despawn(stand, world);
+    let sound = Sound::new("entity.armor_stand.break", SoundCategory::Neutral);
+    game.play_sound(world, world_id, pos, &sound);
+    for stack in items {
+        drop_item(game, world, world_id, stack, pos);
+    }
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 78, // Type 78 for armor stands
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
+
+fn rotation_data(rotation: Rotation) -> Option<Vec<f32>> {
+    Some(vec![rotation.x, rotation.y, rotation.z])
+}
+
+fn rotation_from_data(data: &Option<Vec<f32>>, default: Rotation) -> Rotation {
+    match data.as_deref() {
+        Some(&[x, y, z]) => Rotation::new(x, y, z),
+        _ => default,
+    }
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let stand = *accessor.get::<ArmorStand>();
+    let pose = *accessor.get::<ArmorStandPose>();
+    let inventory = accessor.get::<Inventory>();
+    let items = |equipment: &[Equipment]| {
+        equipment
+            .iter()
+            .map(|equipment| {
+                let slot = equipment.slot_index(0);
+                inventory
+                    .item_at(slot.area, slot.slot)
+                    .unwrap()
+                    .map(ItemData::from)
+                    .unwrap_or_default()
+            })
+            .collect()
+    };
+
+    EntityData::ArmorStand(ArmorStandEntityData {
+        entity: BaseEntityData::new(*accessor.get::<Position>(), Vec3d::broadcast(0.0)),
+        armor_items: items(&ARMOR_ITEMS),
+        hand_items: items(&HAND_ITEMS),
+        pose: ArmorStandPoseData {
+            head: rotation_data(pose.head),
+            body: rotation_data(pose.body),
+            left_arm: rotation_data(pose.left_arm),
+            right_arm: rotation_data(pose.right_arm),
+            left_leg: rotation_data(pose.left_leg),
+            right_leg: rotation_data(pose.right_leg),
+        },
+        invisible: stand.invisible as i8,
+        marker: stand.flags.contains(ArmorStandBitMask::MARKER) as i8,
+        no_base_plate: stand.flags.contains(ArmorStandBitMask::NO_BASE_PLATE) as i8,
+        show_arms: stand.flags.contains(ArmorStandBitMask::HAS_ARMS) as i8,
+        small: stand.flags.contains(ArmorStandBitMask::SMALL) as i8,
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    let data = match data {
+        EntityData::ArmorStand(data) => data,
+        _ => panic!("attempted to use armor_stand::load to load a non-armor stand"),
+    };
+    let pos = data.entity.read_position()?;
+
+    let mut flags = ArmorStandBitMask::empty();
+    flags.set(ArmorStandBitMask::MARKER, data.marker != 0);
+    flags.set(ArmorStandBitMask::NO_BASE_PLATE, data.no_base_plate != 0);
+    flags.set(ArmorStandBitMask::HAS_ARMS, data.show_arms != 0);
+    flags.set(ArmorStandBitMask::SMALL, data.small != 0);
+    let stand = ArmorStand {
+        flags,
+        invisible: data.invisible != 0,
+        last_hit: None,
+    };
+
+    let default = ArmorStandPose::default();
+    let pose = ArmorStandPose {
+        head: rotation_from_data(&data.pose.head, default.head),
+        body: rotation_from_data(&data.pose.body, default.body),
+        left_arm: rotation_from_data(&data.pose.left_arm, default.left_arm),
+        right_arm: rotation_from_data(&data.pose.right_arm, default.right_arm),
+        left_leg: rotation_from_data(&data.pose.left_leg, default.left_leg),
+        right_leg: rotation_from_data(&data.pose.right_leg, default.right_leg),
+    };
+
+    let inventory = Inventory::equipment();
+    let items = ARMOR_ITEMS
+        .iter()
+        .zip(&data.armor_items)
+        .chain(HAND_ITEMS.iter().zip(&data.hand_items));
+    for (equipment, item) in items {
+        let stack = ItemStack::from(item);
+        if stack.ty != Item::Air {
+            let slot = equipment.slot_index(0);
+            inventory.set_item_at(slot.area, slot.slot, stack)?;
+        }
+    }
+
+    Ok(create_with(pos, stand, pose, inventory))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::network::packets::Face;
+    use feather_core::util::BlockPosition;
+    use feather_server_types::{BlockUpdateCause, WorldId};
+    use feather_test_framework::Test;
+    use fecs::{IntoQuery, Read};
+
+    fn stands(test: &Test) -> Vec<Entity> {
+        <Read<ArmorStand>>::query()
+            .iter_entities(test.world.inner())
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    fn equipped(test: &Test, stand: Entity, equipment: Equipment) -> Option<ItemStack> {
+        let slot = equipment.slot_index(0);
+        test.world
+            .get::<Inventory>(stand)
+            .item_at(slot.area, slot.slot)
+            .unwrap()
+    }
+
+    fn hold(test: &mut Test, player: Entity, item: Option<ItemStack>) {
+        let inventory = test.world.get::<Inventory>(player);
+        match item {
+            Some(stack) => inventory.set_item_at(Area::Hotbar, 0, stack),
+            None => inventory.remove_item_at(Area::Hotbar, 0),
+        }
+        .unwrap();
+    }
+
+    #[test]
+    fn place_facing_player() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 65.0, 3.0, 180.0, 0.0));
+        let base = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            base,
+            BlockId::stone(),
+            BlockUpdateCause::Unknown,
+        );
+
+        let event = ItemUseOnBlockEvent {
+            player,
+            world: WorldId::MAIN,
+            pos: base,
+            face: Face::Top,
+            stack: ItemStack::new(Item::ArmorStand, 1),
+        };
+        test.handle(event.clone(), on_item_use_on_block_place_armor_stand);
+        let stand = stands(&test)[0];
+        let pos = *test.world.get::<Position>(stand);
+        assert_eq!(pos.block(), base.up());
+        assert!(pos.yaw.abs() < std::f32::EPSILON);
+
+        // The space is now taken.
+        test.handle(event, on_item_use_on_block_place_armor_stand);
+        assert_eq!(stands(&test).len(), 1);
+    }
+
+    #[test]
+    fn equip_and_take() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let stand = test.entity(create(position!(0.5, 64.0, 0.5)));
+
+        let helmet = ItemStack::new(Item::IronHelmet, 1);
+        hold(&mut test, player, Some(helmet));
+        use_armor_stand(&mut test.game, &mut test.world, stand, player, 1.0);
+        assert_eq!(equipped(&test, stand, Equipment::Helmet), Some(helmet));
+        assert_eq!(
+            test.world
+                .get::<Inventory>(player)
+                .item_at(Area::Hotbar, 0)
+                .unwrap(),
+            None
+        );
+
+        // Stands without arms don't hold items.
+        hold(&mut test, player, Some(ItemStack::new(Item::Stick, 1)));
+        use_armor_stand(&mut test.game, &mut test.world, stand, player, 1.0);
+        assert_eq!(equipped(&test, stand, Equipment::MainHand), None);
+
+        hold(&mut test, player, None);
+        use_armor_stand(&mut test.game, &mut test.world, stand, player, 1.0);
+        assert_eq!(equipped(&test, stand, Equipment::Helmet), Some(helmet));
+        use_armor_stand(&mut test.game, &mut test.world, stand, player, 1.8);
+        assert_eq!(equipped(&test, stand, Equipment::Helmet), None);
+        assert_eq!(
+            test.world
+                .get::<Inventory>(player)
+                .item_at(Area::Hotbar, 0)
+                .unwrap(),
+            Some(helmet)
+        );
+    }
+
+    #[test]
+    fn break_after_two_hits() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let stand = test.entity(create(position!(0.5, 64.0, 0.5)));
+
+        hit_armor_stand(&mut test.game, &mut test.world, stand, player);
+        test.assert_alive(stand);
+        test.game.tick_count += BREAK_TICKS + 1;
+        hit_armor_stand(&mut test.game, &mut test.world, stand, player);
+        test.assert_alive(stand);
+        test.game.tick_count += 1;
+        hit_armor_stand(&mut test.game, &mut test.world, stand, player);
+        test.assert_dead(stand);
+    }
+}
@@ -1,15 +1,77 @@
-use feather_core::anvil::entity::{ArrowEntityData, BaseEntityData, EntityData};
+//! Arrows shot from bows, which damage the entities
+//! they hit depending on how fast they fly.
+//!
+//! See https://minecraft.gamepedia.com/Arrow.
+
+use feather_core::anvil::entity::{ArrowEntityData, BaseEntityData, EntityData, EntityDataKind};
+use feather_core::entitymeta::{EntityMetadata, ARROW_LAYOUT, META_INDEX_ARROW_FLAGS};
 use feather_core::network::packets::SpawnObject;
 use feather_core::network::Packet;
-use feather_core::util::{Position, Vec3d};
+use feather_core::util::{Gamemode, Position, Vec3d};
 use feather_server_types::{
-    ComponentSerializer, Game, NetworkId, PhysicsBuilder, SpawnPacketCreator, Uuid, Velocity,
+    ComponentSerializer, DamageSource, Dead, EntityLoaderRegistration, Game, Health, NetworkId,
+    PhysicsBuilder, SpawnPacketCreator, Uuid, Velocity,
 };
-use feather_server_util::{degrees_to_stops, protocol_velocity};
-use fecs::{EntityBuilder, EntityRef};
+use feather_server_util::{degrees_to_stops, nearby_entities, protocol_velocity};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use glm::DVec3;
+use rand::Rng;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Arrow, &load)
+}
+
+/// Damage dealt by an arrow flying one block per tick.
+pub const BASE_DAMAGE: f64 = 2.0;
+/// Speed below which an arrow lies still and can't hurt anything.
+const MIN_SPEED: f64 = 0.1;
+/// Ticks an arrow stuck in the ground stays for before despawning.
+const DESPAWN_TICKS: u32 = 1200;
+/// Distance between the points along its path
+/// at which an arrow checks for entities it hit.
+const HIT_STEP: f64 = 0.5;
+/// Horizontal distance from an entity within which an arrow hits it.
+const HIT_RADIUS: f64 = 0.6;
+/// Height of the entities hit by arrows.
+const HIT_HEIGHT: f64 = 1.8;
+
+/// Flag of the arrow metadata for critical arrows, which leave a trail.
+const CRITICAL_FLAG: u8 = 0x01;
+
+/// Component for arrows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Arrow {
+    /// The entity which shot the arrow.
+    pub shooter: Option<Entity>,
+    /// Damage dealt by the arrow flying one block per tick.
+    pub damage: f64,
+    /// Whether the arrow was shot by a fully drawn bow, which
+    /// gives it a chance to deal extra damage.
+    pub critical: bool,
+    /// Ticks the arrow has been stuck in the ground for.
+    pub ticks_in_ground: u32,
+}
+
+impl Default for Arrow {
+    fn default() -> Self {
+        Self {
+            shooter: None,
+            damage: BASE_DAMAGE,
+            critical: false,
+            ticks_in_ground: 0,
+        }
+    }
+}
+
+pub fn create(arrow: Arrow) -> EntityBuilder {
+    let flags = if arrow.critical { CRITICAL_FLAG } else { 0 };
+    let meta = EntityMetadata::entity_base()
+        .with_layout(ARROW_LAYOUT)
+        .with(META_INDEX_ARROW_FLAGS, flags);
 
-pub fn create() -> EntityBuilder {
     crate::base()
+        .with(arrow)
+        .with(meta)
         .with(SpawnPacketCreator(&create_spawn_packet))
         .with(ComponentSerializer(&serialize))
         .with(
@@ -22,6 +84,92 @@ pub fn create() -> EntityBuilder {
         )
 }
 
+/// Returns the damage dealt by an arrow hitting
+/// an entity while flying at the given speed.
+pub fn impact_damage(arrow: &Arrow, speed: f64, rng: &mut impl Rng) -> u32 {
+    let damage = (speed * arrow.damage).ceil().max(0.0) as u32;
+    if arrow.critical {
+        damage + rng.gen_range(0, damage / 2 + 2)
+    } else {
+        damage
+    }
+}
+
+/// Returns the extra damage dealt by arrows shot from
+/// a bow with Power of the given level.
+pub fn power_bonus(level: i16) -> f64 {
+    if level > 0 {
+        0.5 * f64::from(level) + 0.5
+    } else {
+        0.0
+    }
+}
+
+/// System which damages the entities hit by flying arrows
+/// and despawns arrows lying in the ground for too long.
+#[fecs::system]
+pub fn update_arrows(game: &mut Game, world: &mut World) {
+    let arrows: Vec<(Entity, Arrow, Position, DVec3)> =
+        <(Read<Arrow>, Read<Position>, Read<Velocity>)>::query()
+            .iter_entities(world.inner())
+            .map(|(entity, (arrow, pos, velocity))| (entity, *arrow, *pos, velocity.0))
+            .collect();
+
+    for (entity, arrow, pos, velocity) in arrows {
+        let speed = velocity.norm();
+        if pos.on_ground || speed < MIN_SPEED {
+            world.get_mut::<Arrow>(entity).ticks_in_ground += 1;
+            if arrow.ticks_in_ground + 1 >= DESPAWN_TICKS {
+                game.despawn(entity, world);
+            }
+            continue;
+        }
+
+        if let Some(target) = find_target(game, world, entity, &arrow, pos, velocity) {
+            let damage = impact_damage(&arrow, speed, &mut *game.rng());
+            game.damage(target, damage, DamageSource::Arrow(arrow.shooter), world);
+            game.despawn(entity, world);
+        }
+    }
+}
+
+/// Finds the entity hit by an arrow on its path
+/// during the last tick, if any.
+fn find_target(
+    game: &Game,
+    world: &World,
+    entity: Entity,
+    arrow: &Arrow,
+    pos: Position,
+    velocity: DVec3,
+) -> Option<Entity> {
+    let world_id = game.world_of(world, entity);
+    let steps = (velocity.norm() / HIT_STEP).ceil().max(1.0) as usize;
+    (0..=steps).find_map(|step| {
+        let back = velocity * (step as f64 / steps as f64);
+        let center = Position {
+            x: pos.x - back.x,
+            y: pos.y - back.y - HIT_HEIGHT / 2.0,
+            z: pos.z - back.z,
+            ..pos
+        };
+        let radius = glm::vec3(HIT_RADIUS, HIT_HEIGHT / 2.0, HIT_RADIUS);
+        nearby_entities(world, game, world_id, center, radius)
+            .into_iter()
+            .find(|&target| can_hit(world, arrow, target))
+    })
+}
+
+fn can_hit(world: &World, arrow: &Arrow, target: Entity) -> bool {
+    // Arrows never hit the entity which shot them.
+    Some(target) != arrow.shooter
+        && world.has::<Health>(target)
+        && !world.has::<Dead>(target)
+        && world
+            .try_get::<Gamemode>(target)
+            .map_or(true, |gamemode| *gamemode != Gamemode::Spectator)
+}
+
 fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
     let position = *accessor.get::<Position>();
     let velocity = *accessor.get::<Velocity>();
@@ -52,6 +200,87 @@ fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
 
     EntityData::Arrow(ArrowEntityData {
         entity: BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z)),
-        critical: 0, // TODO
+        critical: accessor.get::<Arrow>().critical as i8,
     })
 }
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Arrow(data) => {
+            let pos = data.entity.read_position()?;
+            let vel = data.entity.read_velocity()?;
+            let arrow = Arrow {
+                critical: data.critical != 0,
+                ..Default::default()
+            };
+
+            Ok(create(arrow)
+                .with(pos)
+                .with(Velocity(glm::vec3(vel.x, vel.y, vel.z))))
+        }
+        _ => panic!("attempted to use arrow::load to load a non-arrow"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_types::CanTakeDamage;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn damage_depends_on_speed() {
+        let mut rng = rand::thread_rng();
+        let arrow = Arrow::default();
+        // A fully drawn bow shoots arrows at 3 blocks per tick.
+        assert_eq!(impact_damage(&arrow, 3.0, &mut rng), 6);
+        assert_eq!(impact_damage(&arrow, 1.2, &mut rng), 3);
+
+        let arrow = Arrow {
+            damage: BASE_DAMAGE + power_bonus(5),
+            critical: true,
+            ..Default::default()
+        };
+        let damage = impact_damage(&arrow, 3.0, &mut rng);
+        assert!((15..24).contains(&damage));
+    }
+
+    #[test]
+    fn arrow_hits_entities_in_its_path() {
+        let mut test = Test::new();
+        let shooter = test.player("shooter", position!(0.0, 64.0, 0.0));
+        let target = test.player("target", position!(4.0, 64.0, 0.0));
+        test.world.add(target, CanTakeDamage).unwrap();
+
+        let arrow = Arrow {
+            shooter: Some(shooter),
+            ..Default::default()
+        };
+        // The arrow flew past the target during the last tick.
+        let entity = test.entity(
+            create(arrow)
+                .with(position!(5.5, 65.0, 0.0, false))
+                .with(Velocity(glm::vec3(3.0, 0.0, 0.0))),
+        );
+
+        test.run(update_arrows);
+        test.assert_dead(entity);
+        assert_eq!(test.world.get::<Health>(target).0, 14);
+        assert_eq!(test.world.get::<Health>(shooter).0, 20);
+    }
+
+    #[test]
+    fn arrows_in_the_ground_despawn() {
+        let mut test = Test::new();
+        let arrow = Arrow {
+            ticks_in_ground: DESPAWN_TICKS - 2,
+            ..Default::default()
+        };
+        let entity = test.entity(create(arrow).with(position!(0.0, 64.0, 0.0)));
+
+        test.run(update_arrows);
+        test.assert_alive(entity);
+        test.run(update_arrows);
+        test.assert_dead(entity);
+    }
+}
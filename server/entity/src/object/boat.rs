@@ -0,0 +1,506 @@
+//! Boats, which players place on water and paddle around in.
+//! The client of the player driving a boat moves it, so the
+//! server only floats boats which nobody drives to the surface,
+//! lets them fall and slows them down.
+//!
+//! Hitting a boat damages it, and it breaks into its item once
+//! damaged enough. Players in creative mode break it at once
+//! without drops.
+
+use crate::consume_held_item;
+use crate::drops::drop_item;
+use crate::vehicle::Vehicle;
+use feather_core::anvil::entity::{BaseEntityData, BoatEntityData, EntityData, EntityDataKind};
+use feather_core::blocks::{BlockId, SimplifiedBlockKind};
+use feather_core::entitymeta::{
+    EntityMetadata, BOAT_LAYOUT, META_INDEX_BOAT_DAMAGE_TAKEN, META_INDEX_BOAT_FORWARD_DIRECTION,
+    META_INDEX_BOAT_LEFT_PADDLE_TURNING, META_INDEX_BOAT_RIGHT_PADDLE_TURNING,
+    META_INDEX_BOAT_TIME_SINCE_HIT, META_INDEX_BOAT_TYPE,
+};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{PacketEntityMetadata, SoundCategory, SpawnObject};
+use feather_core::network::Packet;
+use feather_core::util::{BlockPosition, Gamemode, Position, Vec3d};
+use feather_server_types::{
+    ComponentSerializer, EntityLoaderRegistration, EntitySpawnEvent, Game, Network, NetworkId,
+    Sound, SpawnPacketCreator, Uuid, Velocity, WorldId, PLAYER_EYE_HEIGHT,
+};
+use feather_server_util::degrees_to_stops;
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+
+/// Upwards acceleration of boats in water.
+const BUOYANCY: f64 = 0.04;
+/// Greatest speed at which boats rise in water.
+const MAX_RISE_SPEED: f64 = 0.1;
+/// Downwards acceleration of boats out of water.
+const GRAVITY: f64 = 0.04;
+/// Factors by which boats slow down each tick on
+/// water, on land and in the air.
+const WATER_DRAG: f64 = 0.9;
+const GROUND_DRAG: f64 = 0.5;
+const AIR_DRAG: f64 = 0.98;
+/// Horizontal speed below which boats stop.
+const MIN_SPEED: f64 = 0.001;
+
+/// Damage a hit deals to a boat.
+const HIT_DAMAGE: f32 = 10.0;
+/// Damage above which a boat breaks.
+const MAX_DAMAGE: f32 = 40.0;
+/// Ticks a boat wobbles for after it is hit.
+const HIT_TICKS: i32 = 10;
+
+/// Distance from the eyes of a player within
+/// which they can place a boat.
+const REACH: f64 = 5.0;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Boat, &load)
+}
+
+/// The wood a boat is made of.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BoatType {
+    Oak,
+    Spruce,
+    Birch,
+    Jungle,
+    Acacia,
+    DarkOak,
+}
+
+impl BoatType {
+    const ALL: [BoatType; 6] = [
+        BoatType::Oak,
+        BoatType::Spruce,
+        BoatType::Birch,
+        BoatType::Jungle,
+        BoatType::Acacia,
+        BoatType::DarkOak,
+    ];
+
+    /// Returns the ID of the type in entity metadata.
+    pub fn id(self) -> i32 {
+        Self::ALL.iter().position(|&ty| ty == self).unwrap() as i32
+    }
+
+    /// Returns the name of the type in the `Type` tag of boats.
+    pub fn name(self) -> &'static str {
+        match self {
+            BoatType::Oak => "oak",
+            BoatType::Spruce => "spruce",
+            BoatType::Birch => "birch",
+            BoatType::Jungle => "jungle",
+            BoatType::Acacia => "acacia",
+            BoatType::DarkOak => "dark_oak",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|ty| ty.name() == name)
+    }
+
+    /// Returns the item the boat drops.
+    pub fn item(self) -> Item {
+        match self {
+            BoatType::Oak => Item::OakBoat,
+            BoatType::Spruce => Item::SpruceBoat,
+            BoatType::Birch => Item::BirchBoat,
+            BoatType::Jungle => Item::JungleBoat,
+            BoatType::Acacia => Item::AcaciaBoat,
+            BoatType::DarkOak => Item::DarkOakBoat,
+        }
+    }
+
+    pub fn from_item(item: Item) -> Option<Self> {
+        Self::ALL.iter().copied().find(|ty| ty.item() == item)
+    }
+}
+
+/// Component for boats.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Boat {
+    pub ty: BoatType,
+    /// Damage taken from recent hits, which wears off over time.
+    pub damage: f32,
+    /// Ticks left for the boat to wobble after it was hit.
+    pub time_since_hit: i32,
+    /// The direction the boat wobbles in, which flips on every hit.
+    pub forward_direction: i32,
+}
+
+impl Boat {
+    pub fn new(ty: BoatType) -> Self {
+        Self {
+            ty,
+            damage: 0.0,
+            time_since_hit: 0,
+            forward_direction: 1,
+        }
+    }
+}
+
+/// Returns an entity builder to create a boat of the given
+/// type at the given position. Its world still has to be added.
+pub fn create(pos: Position, ty: BoatType) -> EntityBuilder {
+    let meta = EntityMetadata::entity_base()
+        .with_layout(BOAT_LAYOUT)
+        .with(META_INDEX_BOAT_TIME_SINCE_HIT, 0i32)
+        .with(META_INDEX_BOAT_FORWARD_DIRECTION, 1i32)
+        .with(META_INDEX_BOAT_DAMAGE_TAKEN, 0.0f32)
+        .with(META_INDEX_BOAT_TYPE, ty.id())
+        .with(META_INDEX_BOAT_RIGHT_PADDLE_TURNING, false)
+        .with(META_INDEX_BOAT_LEFT_PADDLE_TURNING, false);
+
+    crate::base()
+        .with(Boat::new(ty))
+        .with(Vehicle::new(2, 0.5625, true))
+        .with(pos)
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(ComponentSerializer(&serialize))
+}
+
+fn is_water(block: BlockId) -> bool {
+    block.simplified_kind() == SimplifiedBlockKind::Water || block.waterlogged() == Some(true)
+}
+
+/// Places a boat on the water or the block a player looks
+/// at, turned the way they look. Returns `false` if there
+/// is no such block within reach.
+pub fn place_boat(game: &mut Game, world: &mut World, player: Entity, stack: ItemStack) -> bool {
+    let ty = match BoatType::from_item(stack.ty) {
+        Some(ty) => ty,
+        None => return false,
+    };
+    let world_id = game.world_of(world, player);
+    let player_pos = *world.get::<Position>(player);
+    let eyes = player_pos + glm::vec3(0.0, PLAYER_EYE_HEIGHT, 0.0);
+    let direction = player_pos.direction();
+
+    let steps = (REACH / 0.05) as usize;
+    let target = (0..=steps)
+        .map(|step| eyes + direction * (step as f64 * 0.05))
+        .find(|point| {
+            game.block_at(world_id, point.block())
+                .map_or(false, |block| is_water(block) || block.is_solid())
+        });
+    let target = match target {
+        Some(target) => target,
+        None => return false,
+    };
+    let free = game
+        .block_at(world_id, target.block().up())
+        .map_or(false, |block| !block.is_solid());
+    if !free {
+        return false;
+    }
+
+    let mut pos = target;
+    pos.y = f64::from(target.block().y + 1);
+    pos.yaw = player_pos.yaw;
+    pos.pitch = 0.0;
+    let entity = create(pos, ty).with(world_id).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+    consume_held_item(game, world, player, stack);
+    true
+}
+
+/// System which floats boats nobody drives to the surface
+/// of water, lets them fall and slows them down. Boats
+/// driven by players are moved by their clients instead.
+#[fecs::system]
+pub fn move_boats(game: &mut Game, world: &mut World) {
+    let boats: Vec<Entity> = <Read<Boat>>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for boat in boats {
+        {
+            let mut state = world.get_mut::<Boat>(boat);
+            state.damage = (state.damage - 1.0).max(0.0);
+            state.time_since_hit = (state.time_since_hit - 1).max(0);
+        }
+        let driven_by_client = world
+            .get::<Vehicle>(boat)
+            .driver()
+            .map_or(false, |driver| world.has::<Network>(driver));
+        if driven_by_client {
+            continue;
+        }
+
+        let world_id = game.world_of(world, boat);
+        let pos = *world.get::<Position>(boat);
+        let velocity = world.get::<Velocity>(boat).0;
+        let (pos, velocity) = move_boat(game, world_id, pos, velocity);
+        *world.get_mut::<Position>(boat) = pos;
+        world.get_mut::<Velocity>(boat).0 = velocity;
+    }
+}
+
+/// Returns the position and velocity of
+/// a boat after it moved for a tick.
+fn move_boat(
+    game: &Game,
+    world_id: WorldId,
+    mut pos: Position,
+    mut velocity: glm::DVec3,
+) -> (Position, glm::DVec3) {
+    let water = |pos: BlockPosition| game.block_at(world_id, pos).map_or(false, is_water);
+    let solid = |pos: BlockPosition| {
+        game.block_at(world_id, pos)
+            .map_or(true, |block| block.is_solid())
+    };
+
+    let block = pos.block();
+    let drag = if water(block) {
+        velocity.y = (velocity.y + BUOYANCY).min(MAX_RISE_SPEED);
+        WATER_DRAG
+    } else {
+        velocity.y -= GRAVITY;
+        if water(block.down()) {
+            WATER_DRAG
+        } else if pos.on_ground {
+            GROUND_DRAG
+        } else {
+            AIR_DRAG
+        }
+    };
+    velocity.x *= drag;
+    velocity.z *= drag;
+    if velocity.x.abs() < MIN_SPEED && velocity.z.abs() < MIN_SPEED {
+        velocity.x = 0.0;
+        velocity.z = 0.0;
+    }
+
+    // Boats stop at walls.
+    if solid((pos + glm::vec3(velocity.x, 0.0, velocity.z)).block()) {
+        velocity.x = 0.0;
+        velocity.z = 0.0;
+    }
+
+    let mut next = pos + velocity;
+    let below = next.block();
+    next.on_ground = false;
+    // Falling boats land on top of water and solid blocks.
+    if velocity.y < 0.0 && !water(block) && (water(below) || solid(below)) {
+        next.y = f64::from(below.y + 1);
+        next.on_ground = true;
+        velocity.y = 0.0;
+    }
+
+    pos.x = next.x;
+    pos.y = next.y;
+    pos.z = next.z;
+    pos.on_ground = next.on_ground;
+    (pos, velocity)
+}
+
+/// Sets whether the paddles of a boat turn, showing
+/// the paddles to the players who can see the boat.
+pub fn set_paddles(game: &Game, world: &mut World, boat: Entity, left: bool, right: bool) {
+    {
+        let mut meta = match world.try_get_mut::<EntityMetadata>(boat) {
+            Some(meta) => meta,
+            None => return,
+        };
+        meta.set(META_INDEX_BOAT_LEFT_PADDLE_TURNING, left);
+        meta.set(META_INDEX_BOAT_RIGHT_PADDLE_TURNING, right);
+    }
+
+    let packet = PacketEntityMetadata {
+        entity_id: world.get::<NetworkId>(boat).0,
+        metadata: EntityMetadata::new()
+            .with_layout(BOAT_LAYOUT)
+            .with(META_INDEX_BOAT_LEFT_PADDLE_TURNING, left)
+            .with(META_INDEX_BOAT_RIGHT_PADDLE_TURNING, right),
+    };
+    game.broadcast_entity_update(world, packet, boat, None);
+}
+
+/// Handles a player hitting a boat, which breaks once it
+/// took enough damage, or at once by players in creative mode.
+pub fn hit_boat(game: &mut Game, world: &mut World, boat: Entity, player: Entity) {
+    if *world.get::<Gamemode>(player) == Gamemode::Creative {
+        break_boat(game, world, boat, false);
+        return;
+    }
+
+    let state = {
+        let mut state = world.get_mut::<Boat>(boat);
+        state.forward_direction = -state.forward_direction;
+        state.time_since_hit = HIT_TICKS;
+        state.damage += HIT_DAMAGE;
+        *state
+    };
+    if state.damage > MAX_DAMAGE {
+        break_boat(game, world, boat, true);
+        return;
+    }
+
+    let packet = PacketEntityMetadata {
+        entity_id: world.get::<NetworkId>(boat).0,
+        metadata: EntityMetadata::new()
+            .with_layout(BOAT_LAYOUT)
+            .with(META_INDEX_BOAT_TIME_SINCE_HIT, state.time_since_hit)
+            .with(META_INDEX_BOAT_FORWARD_DIRECTION, state.forward_direction)
+            .with(META_INDEX_BOAT_DAMAGE_TAKEN, state.damage),
+    };
+    game.broadcast_entity_update(world, packet, boat, None);
+}
+
+/// Breaks a boat, dropping it if `drops` is set.
+pub fn break_boat(game: &mut Game, world: &mut World, boat: Entity, drops: bool) {
+    let world_id = game.world_of(world, boat);
+    let pos = *world.get::<Position>(boat);
+    let ty = world.get::<Boat>(boat).ty;
+
+    game.despawn(boat, world);
+    if drops {
+        drop_item(game, world, world_id, ItemStack::new(ty.item(), 1), pos);
+    }
+    let sound = Sound::new("entity.item.break", SoundCategory::Neutral).volume(0.5);
+    game.play_sound(world, world_id, pos, &sound);
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 1, // Type 1 for boats
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let vel = accessor.get::<Velocity>().0;
+
+    EntityData::Boat(BoatEntityData {
+        entity: BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z)),
+        ty: accessor.get::<Boat>().ty.name().to_owned(),
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    let data = match data {
+        EntityData::Boat(data) => data,
+        _ => panic!("attempted to use boat::load to load a non-boat"),
+    };
+    let pos = data.entity.read_position()?;
+    let vel = data.entity.read_velocity()?;
+    let ty = BoatType::from_name(&data.ty).unwrap_or(BoatType::Oak);
+
+    Ok(create(pos, ty).with(Velocity(glm::vec3(vel.x, vel.y, vel.z))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_types::BlockUpdateCause;
+    use feather_test_framework::Test;
+
+    fn fill(test: &mut Test, from: BlockPosition, to: BlockPosition, block: BlockId) {
+        for x in from.x..=to.x {
+            for y in from.y..=to.y {
+                for z in from.z..=to.z {
+                    test.game.set_block_at(
+                        &mut test.world,
+                        WorldId::MAIN,
+                        BlockPosition::new(x, y, z),
+                        block,
+                        BlockUpdateCause::Unknown,
+                    );
+                }
+            }
+        }
+    }
+
+    fn boats(test: &Test) -> Vec<Entity> {
+        <Read<Boat>>::query()
+            .iter_entities(test.world.inner())
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    #[test]
+    fn boat_type_round_trip() {
+        for &ty in &BoatType::ALL {
+            assert_eq!(BoatType::from_name(ty.name()), Some(ty));
+            assert_eq!(BoatType::from_item(ty.item()), Some(ty));
+        }
+        assert_eq!(BoatType::DarkOak.id(), 5);
+    }
+
+    #[test]
+    fn place_on_water() {
+        let mut test = Test::new();
+        let water = BlockPosition::new(0, 62, 3);
+        fill(&mut test, water, water, BlockId::water());
+        fill(&mut test, water.up(), water.up().up(), BlockId::air());
+        let player = test.player("", position!(0.5, 63.0, 0.5, 0.0, 30.0));
+
+        let placed = place_boat(
+            &mut test.game,
+            &mut test.world,
+            player,
+            ItemStack::new(Item::SpruceBoat, 1),
+        );
+        assert!(placed);
+        let boat = boats(&test)[0];
+        assert_eq!(test.world.get::<Boat>(boat).ty, BoatType::Spruce);
+        assert_eq!(test.world.get::<Position>(boat).block(), water.up());
+    }
+
+    #[test]
+    fn float_to_surface() {
+        let mut test = Test::new();
+        fill(
+            &mut test,
+            BlockPosition::new(-2, 60, -2),
+            BlockPosition::new(2, 62, 2),
+            BlockId::water(),
+        );
+        fill(
+            &mut test,
+            BlockPosition::new(-2, 63, -2),
+            BlockPosition::new(2, 64, 2),
+            BlockId::air(),
+        );
+        let boat = test.entity(create(position!(0.5, 60.5, 0.5), BoatType::Oak));
+
+        for _ in 0..60 {
+            test.run(move_boats);
+        }
+        let pos = *test.world.get::<Position>(boat);
+        assert!((pos.y - 63.0).abs() < 0.2, "{}", pos.y);
+    }
+
+    #[test]
+    fn break_after_hits() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let boat = test.entity(create(position!(2.0, 64.0, 0.0), BoatType::Birch));
+
+        for _ in 0..4 {
+            hit_boat(&mut test.game, &mut test.world, boat, player);
+        }
+        test.assert_alive(boat);
+        assert!(test.sent::<PacketEntityMetadata>(player).is_some());
+
+        hit_boat(&mut test.game, &mut test.world, boat, player);
+        test.assert_dead(boat);
+    }
+}
@@ -0,0 +1,190 @@
+//! End crystals, which players place on obsidian or bedrock
+//! and which explode when attacked or caught in an explosion.
+//! Crystals generated on the pillars of the End, and the beams
+//! healing the ender dragon, aren't implemented.
+
+use crate::consume_held_item;
+use crate::explode;
+use crate::explosion::BlockDamage;
+use feather_core::blocks::BlockKind;
+use feather_core::entitymeta::{
+    EntityMetadata, END_CRYSTAL_LAYOUT, META_INDEX_END_CRYSTAL_SHOW_BOTTOM,
+};
+use feather_core::items::Item;
+use feather_core::network::packets::SpawnObject;
+use feather_core::network::Packet;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    EntitySpawnEvent, Game, ItemUseOnBlockEvent, NetworkId, SpawnPacketCreator, Uuid, WorldId,
+};
+use feather_server_util::{degrees_to_stops, nearby_entities};
+use fecs::{Entity, EntityBuilder, EntityRef, World};
+
+/// Power of the explosion of an end crystal.
+const EXPLOSION_POWER: f32 = 6.0;
+
+/// Marker component for end crystals.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EndCrystal;
+
+/// Returns an entity builder to create an end crystal standing
+/// on the block at the given position. Its world still has to
+/// be added.
+pub fn create(base: BlockPosition) -> EntityBuilder {
+    let meta = EntityMetadata::entity_base()
+        .with_layout(END_CRYSTAL_LAYOUT)
+        .with(META_INDEX_END_CRYSTAL_SHOW_BOTTOM, false);
+
+    crate::base()
+        .with(EndCrystal)
+        .with(base.up().position() + glm::vec3(0.5, 0.0, 0.5))
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+}
+
+/// Returns whether an end crystal can be placed
+/// on the block at the given position.
+fn can_place(game: &Game, world: &World, world_id: WorldId, base: BlockPosition) -> bool {
+    let on_pillar = game.block_at(world_id, base).map_or(false, |block| {
+        matches!(block.kind(), BlockKind::Obsidian | BlockKind::Bedrock)
+    });
+    let free = [base.up(), base.up().up()].iter().all(|&pos| {
+        game.block_at(world_id, pos)
+            .map_or(false, |block| block.is_air())
+    });
+    if !on_pillar || !free {
+        return false;
+    }
+
+    let center = base.up().position() + glm::vec3(0.5, 1.0, 0.5);
+    nearby_entities(world, game, world_id, center, glm::vec3(0.5, 1.0, 0.5)).is_empty()
+}
+
+/// Places an end crystal when a player uses one on
+/// top of obsidian or bedrock with room above.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_place_end_crystal(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.stack.ty != Item::EndCrystal || !can_place(game, world, event.world, event.pos) {
+        return;
+    }
+
+    let entity = create(event.pos).with(event.world).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+    consume_held_item(game, world, event.player, event.stack);
+}
+
+/// Blows up an end crystal. `source` is the entity
+/// responsible, such as the player who attacked it.
+pub fn detonate(game: &mut Game, world: &mut World, crystal: Entity, source: Option<Entity>) {
+    let center = *world.get::<Position>(crystal);
+    let world_id = game.world_of(world, crystal);
+    game.despawn(crystal, world);
+    explode(
+        game,
+        world,
+        world_id,
+        center,
+        EXPLOSION_POWER,
+        BlockDamage::Break,
+        source,
+    );
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 51, // Type 51 for end crystals
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::inventory::{Area, Inventory};
+    use feather_core::items::ItemStack;
+    use feather_core::network::packets::Face;
+    use feather_core::util::Gamemode;
+    use feather_server_types::{BlockUpdateCause, CanTakeDamage, Health};
+    use feather_test_framework::Test;
+    use fecs::{IntoQuery, Read};
+
+    fn crystals(test: &Test) -> Vec<Entity> {
+        <Read<EndCrystal>>::query()
+            .iter_entities(test.world.inner())
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    #[test]
+    fn place_on_obsidian() {
+        let mut test = Test::new();
+        let player = test.player("", position!(3.0, 64.0, 0.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let stack = ItemStack::new(Item::EndCrystal, 2);
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, stack)
+            .unwrap();
+
+        let base = BlockPosition::new(0, 64, 0);
+        let event = ItemUseOnBlockEvent {
+            player,
+            world: WorldId::MAIN,
+            pos: base,
+            face: Face::Top,
+            stack,
+        };
+        test.handle(event.clone(), on_item_use_on_block_place_end_crystal);
+        assert!(crystals(&test).is_empty());
+
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            base,
+            BlockId::obsidian(),
+            BlockUpdateCause::Unknown,
+        );
+        test.handle(event, on_item_use_on_block_place_end_crystal);
+
+        let crystal = crystals(&test)[0];
+        assert_eq!(test.world.get::<Position>(crystal).block(), base.up());
+        assert_eq!(
+            test.world
+                .get::<Inventory>(player)
+                .item_at(Area::Hotbar, 0)
+                .unwrap(),
+            Some(ItemStack::new(Item::EndCrystal, 1))
+        );
+    }
+
+    #[test]
+    fn explodes_when_attacked() {
+        let mut test = Test::new();
+        let player = test.player("", position!(3.0, 64.0, 0.0));
+        test.world.add(player, CanTakeDamage).unwrap();
+        let crystal = test.entity(create(BlockPosition::new(0, 63, 0)));
+
+        detonate(&mut test.game, &mut test.world, crystal, Some(player));
+        test.assert_dead(crystal);
+        assert!(test.world.get::<Health>(player).0 < 20);
+    }
+}
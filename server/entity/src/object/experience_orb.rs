@@ -0,0 +1,162 @@
+//! Experience orbs, dropped by players and mobs when they die and
+//! by villagers when traded with. Players collect them by walking
+//! into them, gaining the experience points they carry.
+
+use feather_core::network::packets::{SoundCategory, SpawnExperienceOrb};
+use feather_core::network::Packet;
+use feather_core::util::Position;
+use feather_server_types::{
+    split_experience, BumpVec, Dead, EntitySpawnEvent, Experience, Game, ItemCollectEvent,
+    NetworkId, PhysicsBuilder, Sound, SpawnPacketCreator, Velocity, WorldId,
+};
+use feather_server_util::nearby_entities;
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use rand::Rng;
+use std::cell::RefCell;
+
+/// Component storing the number of experience points carried by an orb.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ExperienceOrb(pub u32);
+
+/// Returns an entity builder to create an experience
+/// orb carrying the given number of points.
+pub fn create(points: u32) -> EntityBuilder {
+    crate::base()
+        .with(ExperienceOrb(points))
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(
+            PhysicsBuilder::new()
+                .bbox(0.5, 0.5, 0.5)
+                .drag(0.98)
+                .gravity(-0.03)
+                .build(),
+        )
+}
+
+/// Spawns experience orbs carrying the given number of points
+/// in total at a position, scattering them in random directions.
+pub fn spawn_experience_orbs(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: Position,
+    points: u32,
+) {
+    for size in split_experience(points) {
+        let velocity = {
+            let mut rng = game.rng();
+            glm::vec3(
+                rng.gen_range(-0.1, 0.1),
+                rng.gen_range(0.0, 0.2),
+                rng.gen_range(-0.1, 0.1),
+            )
+        };
+
+        let entity = create(size)
+            .with(pos)
+            .with(world_id)
+            .with(Velocity(velocity))
+            .build()
+            .spawn_in(world);
+        game.handle(world, EntitySpawnEvent { entity });
+    }
+}
+
+/// System which gives players the experience of orbs they walk into.
+#[fecs::system]
+pub fn experience_orb_collect(game: &mut Game, world: &mut World) {
+    // (orb, player, points)
+    let collected = RefCell::new(BumpVec::<(Entity, Entity, u32)>::new_in(game.bump()));
+
+    <(Read<ExperienceOrb>, Read<Position>, Read<WorldId>)>::query().for_each_entities(
+        world.inner(),
+        |(orb, (points, pos, world_id))| {
+            let collector = nearby_entities(world, game, *world_id, *pos, glm::vec3(1.0, 1.0, 1.0))
+                .into_iter()
+                .find(|entity| world.has::<Experience>(*entity) && !world.has::<Dead>(*entity));
+            if let Some(collector) = collector {
+                collected.borrow_mut().push((orb, collector, points.0));
+            }
+        },
+    );
+
+    for (orb, player, points) in collected.into_inner() {
+        game.give_experience(world, player, points as i32);
+        game.handle(
+            world,
+            ItemCollectEvent {
+                item: orb,
+                collector: player,
+                amount: 1,
+            },
+        );
+
+        let pitch = {
+            let mut rng = game.rng();
+            (rng.gen_range(0.0, 1.0) - rng.gen_range(0.0, 1.0)) * 0.35 + 0.9
+        };
+        let sound = Sound::new("entity.experience_orb.pickup", SoundCategory::Players)
+            .volume(0.1)
+            .pitch(pitch);
+        let pos = *world.get::<Position>(player);
+        game.play_sound(world, game.world_of(world, player), pos, &sound);
+
+        game.despawn(orb, world);
+    }
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnExperienceOrb {
+        entity_id: accessor.get::<NetworkId>().0,
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        count: accessor
+            .get::<ExperienceOrb>()
+            .0
+            .min(i16::max_value() as u32) as i16,
+    };
+
+    Box::new(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn spawn_orbs() {
+        let mut test = Test::new();
+
+        spawn_experience_orbs(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            position!(0.0, 64.0, 0.0),
+            10,
+        );
+
+        let mut orbs = <Read<ExperienceOrb>>::query()
+            .iter(test.world.inner())
+            .map(|orb| orb.0)
+            .collect::<Vec<_>>();
+        orbs.sort();
+        assert_eq!(orbs, vec![3, 7]);
+    }
+
+    #[test]
+    fn collect_orb() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        let orb = test.entity(create(10).with(position!(0.5, 64.0, 0.5)));
+
+        test.run(experience_orb_collect).assert_dead(orb);
+
+        let experience = *test.world.get::<Experience>(player);
+        assert_eq!(experience.level, 1);
+        assert_eq!(experience.total, 10);
+    }
+}
@@ -4,13 +4,15 @@ use crate::drops::drop_item;
 use feather_core::item_block::BlockToItem;
 use feather_core::items::ItemStack;
 use feather_core::blocks::{BlockId, SimplifiedBlockKind, BlockKind};
-use feather_core::entitymeta::{EntityMetadata, META_INDEX_FALLING_BLOCK_SPAWN_POSITION};
+use feather_core::entitymeta::{
+    EntityMetadata, FALLING_BLOCK_LAYOUT, META_INDEX_FALLING_BLOCK_SPAWN_POSITION,
+};
 use feather_core::network::packets::{Effect, SpawnObject};
 use feather_core::network::Packet;
 use feather_core::util::{BlockPosition, Position};
 use feather_server_types::{
     BlockUpdateCause, BumpVec, EntityLandEvent, EntitySpawnEvent, Game, NetworkId, PhysicsBuilder,
-    SpawnPacketCreator, Uuid, Velocity,
+    SpawnPacketCreator, Uuid, Velocity, WorldId,
 };
 use feather_server_util::{
     degrees_to_stops, protocol_velocity, BlockNotifyBlock, BlockNotifyFallingBlock,
@@ -33,26 +35,31 @@ pub fn spawn_falling_blocks(game: &mut Game, world: &mut World) {
     let mut actions = BumpVec::new_in(game.bump());
 
     actions.extend(
-        <(Read<BlockNotifyBlock>, Read<BlockNotifyPosition>)>::query()
-            .filter(component::<BlockNotifyFallingBlock>())
-            .iter_entities(world.inner())
-            .map(|(entity, (block, position))| {
-                let builder = if game.block_at(position.0 - BlockPosition::new(0, 1, 0))
-                    == Some(BlockId::air())
-                {
-                    Some(
-                        create(block.0, position.0)
-                            .with(position.0.position() + position!(0.0, -0.5, 0.0)),
-                    )
-                } else {
-                    None
-                };
-
-                (entity, builder, position.0)
-            }),
+        <(
+            Read<BlockNotifyBlock>,
+            Read<BlockNotifyPosition>,
+            Read<WorldId>,
+        )>::query()
+        .filter(component::<BlockNotifyFallingBlock>())
+        .iter_entities(world.inner())
+        .map(|(entity, (block, position, world_id))| {
+            let builder = if game.block_at(*world_id, position.0 - BlockPosition::new(0, 1, 0))
+                == Some(BlockId::air())
+            {
+                Some(
+                    create(block.0, position.0)
+                        .with(position.0.position() + position!(0.0, -0.5, 0.0))
+                        .with(*world_id),
+                )
+            } else {
+                None
+            };
+
+            (entity, builder, *world_id, position.0)
+        }),
     );
 
-    for (entity_to_delete, entity_builder, block_to_clear) in actions {
+    for (entity_to_delete, entity_builder, world_id, block_to_clear) in actions {
         world.despawn(entity_to_delete);
 
         if let Some(entity_builder) = entity_builder {
@@ -66,6 +73,7 @@ pub fn spawn_falling_blocks(game: &mut Game, world: &mut World) {
 
             game.set_block_at(
                 world,
+                world_id,
                 block_to_clear,
                 BlockId::air(),
                 BlockUpdateCause::Unknown,
@@ -89,8 +97,9 @@ pub fn on_entity_land_remove_falling_block(
         .map(|block| block.0)
     {
         let pos = event.pos.block();
-        if !drop_falling_block(pos, &block, game, world) {
-            game.set_block_at(world, pos, block, BlockUpdateCause::Unknown);
+        let world_id = game.world_of(world, event.entity);
+        if !drop_falling_block(world_id, pos, &block, game, world) {
+            game.set_block_at(world, world_id, pos, block, BlockUpdateCause::Unknown);
 
             if block.simplified_kind() == SimplifiedBlockKind::Anvil {
                 game.broadcast_chunk_update(
@@ -101,6 +110,7 @@ pub fn on_entity_land_remove_falling_block(
                         data: 0,
                         disable_relative_volume: false,
                     },
+                    world_id,
                     event.pos.chunk(),
                     None,
                 );
@@ -113,19 +123,20 @@ pub fn on_entity_land_remove_falling_block(
 
 /// Drops falling block as item when the block on the ground
 /// is not a solid block.
-fn drop_falling_block(pos: BlockPosition,
+fn drop_falling_block(world_id: WorldId,
+                      pos: BlockPosition,
                       falling_block: &BlockId,
                       game: &mut Game,
                       world: &mut World) -> bool {
     let item = falling_block.to_item();
 
-    let not_solid_block_kind = game.block_at(pos)
+    let not_solid_block_kind = game.block_at(world_id, pos)
         .map(|block| block.kind())
         .filter(|kind| !kind.solid() && kind != &BlockKind::Air);
 
     if let Some(item) = item {
         if let Some(_) = not_solid_block_kind {
-            drop_item(game, world, ItemStack::new(item, 1), pos.position());
+            drop_item(game, world, world_id, ItemStack::new(item, 1), pos.position());
             return true;
         }
     }
@@ -135,8 +146,9 @@ fn drop_falling_block(pos: BlockPosition,
 
 /// Returns an `EntityBuilder` for a falling block of the given type.
 pub fn create(ty: BlockId, spawn_pos: BlockPosition) -> EntityBuilder {
-    let meta =
-        EntityMetadata::entity_base().with(META_INDEX_FALLING_BLOCK_SPAWN_POSITION, spawn_pos);
+    let meta = EntityMetadata::entity_base()
+        .with_layout(FALLING_BLOCK_LAYOUT)
+        .with(META_INDEX_FALLING_BLOCK_SPAWN_POSITION, spawn_pos);
 
     crate::base()
         .with(FallingBlock)
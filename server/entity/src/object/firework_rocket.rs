@@ -0,0 +1,189 @@
+//! Firework rockets used by players gliding with an elytra,
+//! which boost them until the rocket explodes. Rockets fired
+//! on their own, and their firework stars, aren't implemented.
+
+use feather_core::entitymeta::{
+    EntityMetadata, FIREWORK_ROCKET_LAYOUT, META_INDEX_FIREWORK_ROCKET_BOOSTED_ENTITY,
+    META_INDEX_FIREWORK_ROCKET_ITEM,
+};
+use feather_core::items::ItemStack;
+use feather_core::network::packets::{EntityStatus, SoundCategory, SpawnObject};
+use feather_core::network::Packet;
+use feather_core::util::Position;
+use feather_server_types::{EntitySpawnEvent, Game, NetworkId, Sound, SpawnPacketCreator, Uuid};
+use feather_server_util::degrees_to_stops;
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use rand::Rng;
+
+/// Ticks a rocket flies for, before random variation.
+const BASE_LIFETIME: u32 = 20;
+
+/// Entity status which plays the explosion of a rocket.
+const EXPLODE_STATUS: i8 = 17;
+
+/// Component for firework rockets boosting an entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FireworkRocket {
+    /// The entity boosted by the rocket.
+    pub boosted: Entity,
+    /// Ticks left before the rocket explodes.
+    pub ticks_left: u32,
+}
+
+/// Returns an entity builder to create a rocket boosting the given
+/// entity, which has the given network ID. Its position and world
+/// still have to be added.
+pub fn create(stack: ItemStack, boosted: Entity, boosted_id: i32, lifetime: u32) -> EntityBuilder {
+    let meta = EntityMetadata::entity_base()
+        .with_layout(FIREWORK_ROCKET_LAYOUT)
+        .with(META_INDEX_FIREWORK_ROCKET_ITEM, Some(stack))
+        .with(META_INDEX_FIREWORK_ROCKET_BOOSTED_ENTITY, boosted_id);
+
+    crate::base()
+        .with(FireworkRocket {
+            boosted,
+            ticks_left: lifetime,
+        })
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+}
+
+/// Launches a rocket made from the given stack which
+/// boosts an entity gliding with an elytra.
+pub fn boost(game: &mut Game, world: &mut World, entity: Entity, stack: ItemStack) -> Entity {
+    let lifetime = {
+        let mut rng = game.rng();
+        BASE_LIFETIME + rng.gen_range(0, 6) + rng.gen_range(0, 7)
+    };
+    let pos = *world.get::<Position>(entity);
+    let world_id = game.world_of(world, entity);
+    let boosted_id = world.get::<NetworkId>(entity).0;
+
+    let stack = ItemStack { amount: 1, ..stack };
+    let rocket = create(stack, entity, boosted_id, lifetime)
+        .with(pos)
+        .with(world_id)
+        .build()
+        .spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity: rocket });
+
+    let sound = Sound::new("entity.firework_rocket.launch", SoundCategory::Ambient);
+    game.play_sound(world, world_id, pos, &sound);
+    rocket
+}
+
+/// System which moves rockets along with the entities they
+/// boost and explodes them once their flight is over.
+#[fecs::system]
+pub fn update_firework_rockets(game: &mut Game, world: &mut World) {
+    let rockets: Vec<(Entity, FireworkRocket)> = <Read<FireworkRocket>>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, rocket)| (entity, *rocket))
+        .collect();
+
+    for (entity, rocket) in rockets {
+        let boosted_pos = if world.is_alive(rocket.boosted) {
+            world.try_get::<Position>(rocket.boosted).map(|pos| *pos)
+        } else {
+            None
+        };
+
+        match boosted_pos {
+            Some(pos) if rocket.ticks_left > 0 => {
+                *world.get_mut::<Position>(entity) = pos;
+                world.get_mut::<FireworkRocket>(entity).ticks_left -= 1;
+            }
+            _ => explode(game, world, entity),
+        }
+    }
+}
+
+fn explode(game: &mut Game, world: &mut World, rocket: Entity) {
+    let packet = EntityStatus {
+        entity_id: world.get::<NetworkId>(rocket).0,
+        entity_status: EXPLODE_STATUS,
+    };
+    game.broadcast_entity_update(world, packet, rocket, None);
+    game.despawn(rocket, world);
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 76, // Type 76 for firework rockets
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::entitymeta::MetaEntry;
+    use feather_core::items::Item;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn boost_player() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 80.0, 0.0, false));
+
+        let stack = ItemStack::new(Item::FireworkRocket, 16);
+        let rocket = boost(&mut test.game, &mut test.world, player, stack);
+
+        let state = *test.world.get::<FireworkRocket>(rocket);
+        assert_eq!(state.boosted, player);
+        assert!(state.ticks_left >= BASE_LIFETIME && state.ticks_left <= BASE_LIFETIME + 11);
+
+        let meta = test.world.get::<EntityMetadata>(rocket);
+        assert_eq!(
+            meta.get(META_INDEX_FIREWORK_ROCKET_ITEM),
+            Some(MetaEntry::Slot(Some(ItemStack::new(
+                Item::FireworkRocket,
+                1
+            ))))
+        );
+        assert_eq!(
+            meta.get(META_INDEX_FIREWORK_ROCKET_BOOSTED_ENTITY),
+            Some(MetaEntry::VarInt(test.id(player)))
+        );
+    }
+
+    #[test]
+    fn rocket_follows_and_explodes() {
+        let mut test = Test::new();
+        let player1 = test.player("", position!(0.0, 80.0, 0.0, false));
+        let player2 = test.player("", position!(5.0, 64.0, 0.0));
+
+        let stack = ItemStack::new(Item::FireworkRocket, 1);
+        let id = test.id(player1);
+        let rocket = test.entity(create(stack, player1, id, 1).with(position!(0.0, 80.0, 0.0)));
+
+        test.position(player1, position!(2.0, 81.0, 0.0, false));
+        test.run(update_firework_rockets);
+        test.assert_alive(rocket);
+        assert_eq!(
+            *test.world.get::<Position>(rocket),
+            position!(2.0, 81.0, 0.0, false)
+        );
+
+        let rocket_id = test.id(rocket);
+        test.run(update_firework_rockets);
+        test.assert_dead(rocket);
+        let packet = test.sent::<EntityStatus>(player2).unwrap();
+        assert_eq!(packet.entity_id, rocket_id);
+        assert_eq!(packet.entity_status, EXPLODE_STATUS);
+    }
+}
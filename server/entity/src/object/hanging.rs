@@ -0,0 +1,380 @@
+//! Entities hanging on the side of blocks, namely item frames and
+//! paintings. Players place them by using their item on a block,
+//! and they break when hit or when the blocks they hang on are
+//! removed, dropping their item unless broken in creative mode.
+
+use crate::consume_held_item;
+use crate::drops::drop_item;
+use crate::object::item_frame::{self, ItemFrame};
+use crate::object::painting::{self, Painting};
+use feather_core::anvil::entity::{BaseEntityData, HangingEntityData};
+use feather_core::blocks::FacingCubic;
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::SoundCategory;
+use feather_core::util::{vec3, BlockPosition, Gamemode, Position};
+use feather_server_types::{
+    BlockUpdateEvent, EntitySpawnEvent, Game, ItemFrameUpdateEvent, ItemUseOnBlockEvent, Sound,
+    WorldId,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+
+/// Component for entities hanging on blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Hanging {
+    /// The block the entity occupies, or the one it is centered
+    /// on for paintings larger than a block.
+    pub pos: BlockPosition,
+    /// The direction the entity faces, away from the blocks it hangs on.
+    pub facing: FacingCubic,
+    /// Width of the entity in blocks.
+    pub width: i32,
+    /// Height of the entity in blocks.
+    pub height: i32,
+}
+
+impl Hanging {
+    /// Returns a hanging entity one block large.
+    pub fn new(pos: BlockPosition, facing: FacingCubic) -> Self {
+        Self {
+            pos,
+            facing,
+            width: 1,
+            height: 1,
+        }
+    }
+
+    /// Returns this hanging entity with the given size in blocks.
+    pub fn with_size(self, width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    /// Returns the blocks the entity occupies. Entities larger
+    /// than a block extend to the left of the direction they face
+    /// and upwards first, like in vanilla.
+    pub fn occupied(&self) -> Vec<BlockPosition> {
+        let left = match self.facing.to_facing_cardinal() {
+            Some(facing) => facing.left().offset(),
+            None => return vec![self.pos],
+        };
+
+        let mut occupied = Vec::with_capacity((self.width * self.height) as usize);
+        for x in -(self.width - 1) / 2..=self.width / 2 {
+            for y in -(self.height - 1) / 2..=self.height / 2 {
+                occupied.push(self.pos + BlockPosition::new(left.x * x, y, left.z * x));
+            }
+        }
+        occupied
+    }
+
+    /// Returns the blocks the entity hangs on.
+    pub fn wall(&self) -> Vec<BlockPosition> {
+        let offset = self.facing.offset();
+        self.occupied()
+            .into_iter()
+            .map(|pos| pos + BlockPosition::new(-offset.x, -offset.y, -offset.z))
+            .collect()
+    }
+
+    /// Returns the position of the center of the entity,
+    /// rotated to face the same way.
+    pub fn position(&self) -> Position {
+        let occupied = self.occupied();
+        let (first, last) = (occupied[0], occupied[occupied.len() - 1]);
+        let center = vec3(
+            f64::from(first.x + last.x) / 2.0 + 0.5,
+            f64::from(first.y + last.y) / 2.0 + 0.5,
+            f64::from(first.z + last.z) / 2.0 + 0.5,
+        );
+
+        let (yaw, pitch) = match self.facing {
+            FacingCubic::South => (0.0, 0.0),
+            FacingCubic::West => (90.0, 0.0),
+            FacingCubic::North => (180.0, 0.0),
+            FacingCubic::East => (270.0, 0.0),
+            FacingCubic::Up => (0.0, -90.0),
+            FacingCubic::Down => (0.0, 90.0),
+        };
+        let mut pos = Position::default() + center;
+        pos.yaw = yaw;
+        pos.pitch = pitch;
+        pos
+    }
+
+    /// Returns the data saved for a hanging entity at this position,
+    /// with its facing saved as `facing`.
+    pub fn to_data(&self, facing: i8) -> HangingEntityData {
+        HangingEntityData {
+            entity: BaseEntityData::new(self.position(), vec3(0.0, 0.0, 0.0)),
+            tile_x: self.pos.x,
+            tile_y: self.pos.y,
+            tile_z: self.pos.z,
+            facing,
+        }
+    }
+}
+
+/// Returns the 3D data value of a direction, as
+/// used to save and spawn item frames.
+pub fn facing_data_3d(facing: FacingCubic) -> i8 {
+    match facing {
+        FacingCubic::Down => 0,
+        FacingCubic::Up => 1,
+        FacingCubic::North => 2,
+        FacingCubic::South => 3,
+        FacingCubic::West => 4,
+        FacingCubic::East => 5,
+    }
+}
+
+/// Returns the direction with the given 3D data value.
+pub fn facing_from_data_3d(data: i8) -> Option<FacingCubic> {
+    Some(match data {
+        0 => FacingCubic::Down,
+        1 => FacingCubic::Up,
+        2 => FacingCubic::North,
+        3 => FacingCubic::South,
+        4 => FacingCubic::West,
+        5 => FacingCubic::East,
+        _ => return None,
+    })
+}
+
+/// Returns the 2D data value of a horizontal direction,
+/// as used to save and spawn paintings.
+pub fn facing_data_2d(facing: FacingCubic) -> i8 {
+    match facing {
+        FacingCubic::South => 0,
+        FacingCubic::West => 1,
+        FacingCubic::North => 2,
+        _ => 3,
+    }
+}
+
+/// Returns the horizontal direction with the given 2D data value.
+pub fn facing_from_data_2d(data: i8) -> Option<FacingCubic> {
+    Some(match data {
+        0 => FacingCubic::South,
+        1 => FacingCubic::West,
+        2 => FacingCubic::North,
+        3 => FacingCubic::East,
+        _ => return None,
+    })
+}
+
+/// Returns whether a hanging entity is still supported:
+/// the blocks it hangs on must be solid, while those it
+/// occupies must not be.
+fn is_supported(game: &Game, world_id: WorldId, hanging: &Hanging) -> bool {
+    let solid = |pos: BlockPosition| game.block_at(world_id, pos).map(|block| block.is_solid());
+    hanging
+        .wall()
+        .into_iter()
+        .all(|pos| solid(pos) == Some(true))
+        && hanging
+            .occupied()
+            .into_iter()
+            .all(|pos| solid(pos) == Some(false))
+}
+
+/// Returns whether an entity can be hung at the given
+/// position, which no other hanging entity overlaps.
+pub fn can_hang(game: &Game, world: &World, world_id: WorldId, hanging: &Hanging) -> bool {
+    if !is_supported(game, world_id, hanging) {
+        return false;
+    }
+
+    let occupied = hanging.occupied();
+    <(Read<Hanging>, Read<WorldId>)>::query()
+        .iter(world.inner())
+        .filter(|(other, other_world)| **other_world == world_id && other.facing == hanging.facing)
+        .all(|(other, _)| other.occupied().iter().all(|pos| !occupied.contains(pos)))
+}
+
+fn sound_prefix(world: &World, entity: Entity) -> &'static str {
+    if world.has::<Painting>(entity) {
+        "entity.painting"
+    } else {
+        "entity.item_frame"
+    }
+}
+
+/// Breaks a hanging entity, dropping it as an item along with
+/// the item it holds. `player` is the player who broke it, if
+/// any; nothing is dropped if they are in creative mode.
+pub fn break_hanging(game: &mut Game, world: &mut World, entity: Entity, player: Option<Entity>) {
+    let hanging = *world.get::<Hanging>(entity);
+    let world_id = game.world_of(world, entity);
+    let pos = *world.get::<Position>(entity);
+
+    let sound = Sound::new(
+        format!("{}.break", sound_prefix(world, entity)),
+        SoundCategory::Neutral,
+    );
+    game.play_sound(world, world_id, pos, &sound);
+
+    let creative = player.map_or(false, |player| {
+        *world.get::<Gamemode>(player) == Gamemode::Creative
+    });
+    let mut drops = vec![];
+    let frame = world.try_get::<ItemFrame>(entity).map(|frame| *frame);
+    if let Some(frame) = frame {
+        drops.push(ItemStack::new(Item::ItemFrame, 1));
+        drops.extend(frame.item);
+    } else if world.has::<Painting>(entity) {
+        drops.push(ItemStack::new(Item::Painting, 1));
+    }
+
+    game.despawn(entity, world);
+    if !creative {
+        for stack in drops {
+            drop_item(game, world, world_id, stack, pos);
+        }
+    }
+    if frame.is_some() {
+        game.handle(
+            world,
+            ItemFrameUpdateEvent {
+                world: world_id,
+                pos: hanging.pos,
+            },
+        );
+    }
+}
+
+/// Handles a player hitting a hanging entity. The item
+/// in an item frame is knocked out first, after which
+/// hitting the frame again breaks it.
+pub fn hit_hanging(game: &mut Game, world: &mut World, entity: Entity, player: Entity) {
+    if item_frame::take_item(game, world, entity, Some(player)).is_none() {
+        break_hanging(game, world, entity, Some(player));
+    }
+}
+
+/// Hangs an item frame or a painting when a player uses
+/// one on the side of a block. Paintings only hang on
+/// the sides of blocks, not on their top or bottom.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_place_hanging(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let facing = event.face.facing_cubic();
+    let hanging = Hanging::new(event.pos + event.face.placement_offset(), facing);
+
+    let builder = match event.stack.ty {
+        Item::ItemFrame if can_hang(game, world, event.world, &hanging) => {
+            item_frame::create(hanging)
+        }
+        Item::Painting if facing.is_horizontal() => {
+            match painting::choose_motive(game, world, event.world, hanging) {
+                Some(motive) => painting::create(hanging, motive),
+                None => return,
+            }
+        }
+        _ => return,
+    };
+
+    let entity = builder.with(event.world).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+    consume_held_item(game, world, event.player, event.stack);
+
+    let sound = Sound::new(
+        format!("{}.place", sound_prefix(world, entity)),
+        SoundCategory::Neutral,
+    );
+    let pos = *world.get::<Position>(entity);
+    game.play_sound(world, event.world, pos, &sound);
+    if world.has::<ItemFrame>(entity) {
+        game.handle(
+            world,
+            ItemFrameUpdateEvent {
+                world: event.world,
+                pos: hanging.pos,
+            },
+        );
+    }
+}
+
+/// When a block changes, breaks the hanging entities
+/// it no longer supports or which it is in the way of.
+#[fecs::event_handler]
+pub fn on_block_update_break_hanging_entities(
+    event: &BlockUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.old.is_solid() == event.new.is_solid() {
+        return;
+    }
+
+    let unsupported: Vec<Entity> = <(Read<Hanging>, Read<WorldId>)>::query()
+        .iter_entities(world.inner())
+        .filter(|(_, (hanging, world_id))| {
+            **world_id == event.world
+                && (hanging.wall().contains(&event.pos) || hanging.occupied().contains(&event.pos))
+                && !is_supported(game, event.world, hanging)
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in unsupported {
+        break_hanging(game, world, entity, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::FacingCardinal;
+
+    #[test]
+    fn occupied_blocks() {
+        let pos = BlockPosition::new(0, 64, 0);
+        let frame = Hanging::new(pos, FacingCubic::Up);
+        assert_eq!(frame.occupied(), vec![pos]);
+        assert_eq!(frame.wall(), vec![pos.down()]);
+
+        // Facing north, the left of the painting is to the west.
+        assert_eq!(FacingCardinal::North.left(), FacingCardinal::West);
+        let painting = Hanging::new(pos, FacingCubic::North).with_size(2, 1);
+        assert_eq!(
+            painting.occupied(),
+            vec![pos, pos + BlockPosition::new(-1, 0, 0)]
+        );
+        assert_eq!(
+            painting.wall(),
+            vec![
+                pos + BlockPosition::new(0, 0, 1),
+                pos + BlockPosition::new(-1, 0, 1)
+            ]
+        );
+
+        let painting = Hanging::new(pos, FacingCubic::East).with_size(4, 3);
+        let occupied = painting.occupied();
+        assert_eq!(occupied.len(), 12);
+        assert!(occupied.contains(&(pos + BlockPosition::new(0, -1, 1))));
+        assert!(occupied.contains(&(pos + BlockPosition::new(0, 1, -2))));
+    }
+
+    #[test]
+    fn facing_data() {
+        for &facing in &[
+            FacingCubic::Down,
+            FacingCubic::Up,
+            FacingCubic::North,
+            FacingCubic::South,
+            FacingCubic::West,
+            FacingCubic::East,
+        ] {
+            assert_eq!(facing_from_data_3d(facing_data_3d(facing)), Some(facing));
+            if facing.is_horizontal() {
+                assert_eq!(facing_from_data_2d(facing_data_2d(facing)), Some(facing));
+            }
+        }
+    }
+}
@@ -3,7 +3,7 @@
 use feather_core::anvil::entity::{
     BaseEntityData, EntityData, EntityDataKind, ItemData, ItemEntityData, ItemNbt,
 };
-use feather_core::entitymeta::{EntityMetadata, META_INDEX_ITEM_SLOT};
+use feather_core::entitymeta::{model, EntityMetadata, META_INDEX_ITEM_SLOT};
 use feather_core::inventory::Inventory;
 use feather_core::items::{Item, ItemStack};
 use feather_core::network::packets::SpawnObject;
@@ -12,7 +12,7 @@ use feather_core::util::{Position, Vec3d};
 use feather_server_types::{
     ComponentSerializer, Dead, EntityLoaderRegistration, EntitySpawnEvent, Game,
     InventoryUpdateEvent, ItemCollectEvent, ItemDropEvent, NetworkId, PhysicsBuilder, Player,
-    SpawnPacketCreator, Uuid, Velocity, PLAYER_EYE_HEIGHT, TPS,
+    SpawnPacketCreator, Uuid, Velocity, WorldId, PLAYER_EYE_HEIGHT, TPS,
 };
 use feather_server_util::{degrees_to_stops, nearby_entities, protocol_velocity};
 use fecs::{component, EntityBuilder, EntityRef, IntoQuery, Read, World, Write};
@@ -23,7 +23,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 /// Component which stores the world time at which an item
 /// will be collectable.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct CollectableAt(u64);
+pub struct CollectableAt(pub(crate) u64);
 
 /// Component used to store whether an item has been collected/
 /// removed on a given tick. Used by `item_collect` and `item_merge`
@@ -70,8 +70,10 @@ pub fn on_item_drop_spawn_item_entity(event: &ItemDropEvent, game: &mut Game, wo
 
     drop(rng);
 
+    let world_id = game.world_of(world, event.player);
     let entity = create(event.stack, game.tick_count + TPS)
         .with(pos)
+        .with(world_id)
         .with(Velocity(velocity))
         .build()
         .spawn_in(world);
@@ -95,66 +97,74 @@ pub fn item_collect(game: &mut Game, world: &mut World) {
     // and we only access item entities inside the loop. As such,
     // we will not have multiple mutable references to the same component.
     unsafe {
-        <(Read<Position>, Write<Inventory>)>::query()
+        <(Read<Position>, Read<WorldId>, Write<Inventory>)>::query()
             .filter(component::<Player>())
             .filter(!component::<Dead>())
-            .par_entities_for_each_unchecked(world.inner(), |(player, (pos, mut inventory))| {
-                let inventory: &mut Inventory = &mut *inventory;
-
-                let nearby_entities = nearby_entities(world, game, *pos, glm::vec3(1.0, 1.0, 1.0));
-                let nearby_items = nearby_entities.iter().filter_map(|entity| {
-                    world
-                        .try_get::<CollectableAt>(*entity)
-                        .map(|collectable_at| {
-                            if collectable_at.0 <= game.time.world_age() {
-                                Some(*entity)
+            .par_entities_for_each_unchecked(
+                world.inner(),
+                |(player, (pos, world_id, mut inventory))| {
+                    let inventory: &mut Inventory = &mut *inventory;
+
+                    let world_age = match game.worlds.get(*world_id) {
+                        Some(state) => state.time.world_age(),
+                        None => return,
+                    };
+                    let nearby_entities =
+                        nearby_entities(world, game, *world_id, *pos, glm::vec3(1.0, 1.0, 1.0));
+                    let nearby_items = nearby_entities.iter().filter_map(|entity| {
+                        world
+                            .try_get::<CollectableAt>(*entity)
+                            .map(|collectable_at| {
+                                if collectable_at.0 <= world_age {
+                                    Some(*entity)
+                                } else {
+                                    None
+                                }
+                            })
+                            .flatten()
+                    });
+
+                    for item in nearby_items {
+                        debug_assert!(!world.has::<Player>(item));
+                        // try to mark this item is collected
+                        // (this ensures another thread has not collected it
+                        // as well, which makes the mutable access below
+                        // safe)
+                        let is_removed = world.get::<IsRemoved>(item);
+
+                        if !is_removed.0.compare_and_swap(false, true, Ordering::AcqRel) {
+                            // we now have unique access to this item and its components.
+                            let mut stack = world.get_mut_unchecked::<ItemStack>(item);
+
+                            let (slots, stack_remaining) = inventory.collect_item(*stack);
+
+                            let initial_remaining = stack.amount;
+
+                            let event = InventoryUpdateEvent {
+                                slots,
+                                entity: player,
+                            };
+                            inventory_update_events.lock().push(event);
+
+                            // update stack
+                            if stack_remaining == 0 {
+                                items_to_remove.lock().push(item);
                             } else {
-                                None
+                                stack.amount = stack_remaining;
+                                world
+                                    .get_mut_unchecked::<EntityMetadata>(item)
+                                    .set(META_INDEX_ITEM_SLOT, Some(*stack));
                             }
-                        })
-                        .flatten()
-                });
-
-                for item in nearby_items {
-                    debug_assert!(!world.has::<Player>(item));
-                    // try to mark this item is collected
-                    // (this ensures another thread has not collected it
-                    // as well, which makes the mutable access below
-                    // safe)
-                    let is_removed = world.get::<IsRemoved>(item);
-
-                    if !is_removed.0.compare_and_swap(false, true, Ordering::AcqRel) {
-                        // we now have unique access to this item and its components.
-                        let mut stack = world.get_mut_unchecked::<ItemStack>(item);
-
-                        let (slots, stack_remaining) = inventory.collect_item(*stack);
-
-                        let initial_remaining = stack.amount;
-
-                        let event = InventoryUpdateEvent {
-                            slots,
-                            entity: player,
-                        };
-                        inventory_update_events.lock().push(event);
-
-                        // update stack
-                        if stack_remaining == 0 {
-                            items_to_remove.lock().push(item);
-                        } else {
-                            stack.amount = stack_remaining;
-                            world
-                                .get_mut_unchecked::<EntityMetadata>(item)
-                                .set(META_INDEX_ITEM_SLOT, Some(*stack));
-                        }
 
-                        item_collect_events.lock().push(ItemCollectEvent {
-                            item,
-                            collector: player,
-                            amount: initial_remaining - stack_remaining,
-                        });
+                            item_collect_events.lock().push(ItemCollectEvent {
+                                item,
+                                collector: player,
+                                amount: initial_remaining - stack_remaining,
+                            });
+                        }
                     }
-                }
-            });
+                },
+            );
     }
 
     // Trigger events + deferred entity deletes.
@@ -177,7 +187,9 @@ pub fn item_collect(game: &mut Game, world: &mut World) {
 /// Returns an entity builder to create an item entity
 /// with the given stack and collectable tick.
 pub fn create(stack: ItemStack, collectable_at: u64) -> EntityBuilder {
-    let meta = EntityMetadata::entity_base().with(META_INDEX_ITEM_SLOT, Some(stack));
+    let meta = EntityMetadata::entity_base()
+        .with_layout(model::Item::LAYOUT)
+        .with(META_INDEX_ITEM_SLOT, Some(stack));
     let collectable_at = CollectableAt(collectable_at);
 
     crate::base()
@@ -0,0 +1,422 @@
+//! Item frames, which show an item on the side of a block.
+//! Right clicking an empty frame puts the held item into it,
+//! and right clicking it again rotates the item by 45 degrees.
+//!
+//! Comparators reading through the block a frame hangs on
+//! output the rotation of its item. Filled maps put into
+//! frames show their drawing as of when the frame was sent.
+
+use crate::consume_held_item;
+use crate::drops::drop_item;
+use crate::object::hanging::{facing_data_3d, facing_from_data_3d, Hanging};
+use feather_core::anvil::entity::{EntityData, EntityDataKind, ItemFrameEntityData};
+use feather_core::blocks::FacingCubic;
+use feather_core::entitymeta::{
+    EntityMetadata, ITEM_FRAME_LAYOUT, META_INDEX_ITEM_FRAME_ITEM, META_INDEX_ITEM_FRAME_ROTATION,
+};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{MapData, PacketEntityMetadata, SoundCategory, SpawnObject};
+use feather_core::network::Packet;
+use feather_core::util::{BlockPosition, Gamemode, Position};
+use feather_server_types::{
+    ComponentSerializer, EntityInteractEvent, EntityLoaderRegistration, EntitySendEvent, Game,
+    ItemFrameUpdateEvent, MapRegion, Network, NetworkId, Sound, SpawnPacketCreator, Uuid, WorldId,
+};
+use feather_server_util::degrees_to_stops;
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+
+/// Number of rotations of the item in a frame.
+const ROTATIONS: u8 = 8;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::ItemFrame, &load)
+}
+
+/// Component for item frames, storing the
+/// item they hold and its rotation.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ItemFrame {
+    pub item: Option<ItemStack>,
+    /// Number of times the item has been rotated by 45 degrees.
+    pub rotation: u8,
+}
+
+impl ItemFrame {
+    /// Returns the signal a comparator reads from this frame:
+    /// 0 if it is empty, and from 1 to 8 depending on the
+    /// rotation of its item otherwise.
+    pub fn signal(&self) -> u8 {
+        match self.item {
+            Some(_) => self.rotation % ROTATIONS + 1,
+            None => 0,
+        }
+    }
+}
+
+/// Returns an entity builder to create an empty item
+/// frame. Its world still has to be added.
+pub fn create(hanging: Hanging) -> EntityBuilder {
+    create_with(hanging, ItemFrame::default())
+}
+
+/// Returns an entity builder to create an item frame
+/// holding the given item. Its world still has to be added.
+pub fn create_with(hanging: Hanging, frame: ItemFrame) -> EntityBuilder {
+    let meta = EntityMetadata::entity_base()
+        .with_layout(ITEM_FRAME_LAYOUT)
+        .with(META_INDEX_ITEM_FRAME_ITEM, frame.item)
+        .with(META_INDEX_ITEM_FRAME_ROTATION, i32::from(frame.rotation));
+
+    crate::base()
+        .with(hanging)
+        .with(frame)
+        .with(hanging.position())
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(ComponentSerializer(&serialize))
+}
+
+/// Returns the signal a comparator facing in direction `facing`
+/// reads from the item frame occupying the given block, if any.
+pub fn frame_signal(
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    facing: FacingCubic,
+) -> Option<u8> {
+    <(Read<ItemFrame>, Read<Hanging>, Read<WorldId>)>::query()
+        .iter(world.inner())
+        .find(|(_, hanging, frame_world)| {
+            hanging.pos == pos && hanging.facing == facing && **frame_world == world_id
+        })
+        .map(|(frame, _, _)| frame.signal())
+}
+
+/// Changes the item in a frame or its rotation,
+/// sending the change to the players who see it.
+fn set_contents(game: &mut Game, world: &mut World, entity: Entity, frame: ItemFrame) {
+    *world.get_mut::<ItemFrame>(entity) = frame;
+    {
+        let mut metadata = world.get_mut::<EntityMetadata>(entity);
+        metadata.set(META_INDEX_ITEM_FRAME_ITEM, frame.item);
+        metadata.set(META_INDEX_ITEM_FRAME_ROTATION, i32::from(frame.rotation));
+    }
+
+    let packet = PacketEntityMetadata {
+        entity_id: world.get::<NetworkId>(entity).0,
+        metadata: EntityMetadata::new()
+            .with_layout(ITEM_FRAME_LAYOUT)
+            .with(META_INDEX_ITEM_FRAME_ITEM, frame.item)
+            .with(META_INDEX_ITEM_FRAME_ROTATION, i32::from(frame.rotation)),
+    };
+    game.broadcast_entity_update(world, packet, entity, None);
+    if let Some(packet) = map_packet(game, world, entity) {
+        game.broadcast_entity_update(world, packet, entity, None);
+    }
+
+    let world_id = game.world_of(world, entity);
+    let pos = world.get::<Hanging>(entity).pos;
+    game.worlds[world_id].dirty_chunks.mark_unsaved(pos.chunk());
+    game.handle(
+        world,
+        ItemFrameUpdateEvent {
+            world: world_id,
+            pos,
+        },
+    );
+}
+
+/// Takes the item out of a frame, dropping it unless `player`,
+/// who knocked it out, is in creative mode.
+pub fn take_item(
+    game: &mut Game,
+    world: &mut World,
+    entity: Entity,
+    player: Option<Entity>,
+) -> Option<ItemStack> {
+    let item = world.try_get::<ItemFrame>(entity)?.item?;
+    set_contents(game, world, entity, ItemFrame::default());
+
+    let world_id = game.world_of(world, entity);
+    let pos = *world.get::<Position>(entity);
+    let sound = Sound::new("entity.item_frame.remove_item", SoundCategory::Neutral);
+    game.play_sound(world, world_id, pos, &sound);
+
+    let creative = player.map_or(false, |player| {
+        *world.get::<Gamemode>(player) == Gamemode::Creative
+    });
+    if !creative {
+        drop_item(game, world, world_id, item, pos);
+    }
+    Some(item)
+}
+
+/// When a player right clicks an item frame, puts the item
+/// they hold into it, or rotates the item already inside.
+#[fecs::event_handler]
+pub fn on_entity_interact_use_item_frame(
+    event: &EntityInteractEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let frame = match world.try_get::<ItemFrame>(event.target) {
+        Some(frame) => *frame,
+        None => return,
+    };
+
+    let (frame, sound) = match (frame.item, event.stack) {
+        (Some(_), _) => (
+            ItemFrame {
+                rotation: (frame.rotation + 1) % ROTATIONS,
+                ..frame
+            },
+            "entity.item_frame.rotate_item",
+        ),
+        (None, Some(stack)) => {
+            consume_held_item(game, world, event.player, stack);
+            (
+                ItemFrame {
+                    item: Some(stack.of_amount(1)),
+                    rotation: 0,
+                },
+                "entity.item_frame.add_item",
+            )
+        }
+        (None, None) => return,
+    };
+    set_contents(game, world, event.target, frame);
+
+    let world_id = game.world_of(world, event.target);
+    let pos = *world.get::<Position>(event.target);
+    game.play_sound(
+        world,
+        world_id,
+        pos,
+        &Sound::new(sound, SoundCategory::Neutral),
+    );
+}
+
+/// Returns the drawing of the filled map in an item frame, if any.
+fn map_packet(game: &Game, world: &World, entity: Entity) -> Option<MapData> {
+    let id = match world.try_get::<ItemFrame>(entity)?.item? {
+        ItemStack {
+            ty: Item::FilledMap,
+            map: Some(id),
+            ..
+        } => id,
+        _ => return None,
+    };
+    let map = game.maps.get(id)?;
+    Some(map.packet(id, Some(MapRegion::FULL), vec![]))
+}
+
+/// When an item frame holding a filled map is sent
+/// to a client, sends them the drawing of the map.
+#[fecs::event_handler]
+pub fn on_entity_send_send_framed_map(event: &EntitySendEvent, game: &Game, world: &mut World) {
+    if let Some(packet) = map_packet(game, world, event.entity) {
+        if let Some(network) = world.try_get::<Network>(event.client) {
+            network.send(packet);
+        }
+    }
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let hanging = *accessor.get::<Hanging>();
+    let position = *accessor.get::<Position>();
+
+    // Clients place frames in the block at the
+    // coordinates given, facing the direction in `data`.
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 71, // Type 71 for item frames
+        x: f64::from(hanging.pos.x),
+        y: f64::from(hanging.pos.y),
+        z: f64::from(hanging.pos.z),
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: i32::from(facing_data_3d(hanging.facing)),
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let hanging = *accessor.get::<Hanging>();
+    let frame = *accessor.get::<ItemFrame>();
+
+    EntityData::ItemFrame(ItemFrameEntityData {
+        hanging: hanging.to_data(facing_data_3d(hanging.facing)),
+        item: frame.item.map(Into::into),
+        item_rotation: frame.rotation as i8,
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::ItemFrame(data) => {
+            let facing = facing_from_data_3d(data.hanging.facing)
+                .ok_or_else(|| anyhow::anyhow!("invalid item frame facing"))?;
+            let frame = ItemFrame {
+                item: data
+                    .item
+                    .map(ItemStack::from)
+                    .filter(|stack| stack.ty != Item::Air),
+                rotation: (data.item_rotation as u8) % ROTATIONS,
+            };
+
+            Ok(create_with(
+                Hanging::new(data.hanging.tile(), facing),
+                frame,
+            ))
+        }
+        _ => panic!("attempted to use item_frame::load to load a non-item frame"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::hanging::{hit_hanging, on_item_use_on_block_place_hanging};
+    use feather_core::blocks::BlockId;
+    use feather_core::inventory::{Area, Inventory};
+    use feather_core::network::packets::Face;
+    use feather_server_types::{BlockUpdateCause, ItemUseOnBlockEvent};
+    use feather_test_framework::Test;
+
+    fn frames(test: &Test) -> Vec<Entity> {
+        <Read<ItemFrame>>::query()
+            .iter_entities(test.world.inner())
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    fn place_frame(test: &mut Test, player: Entity) -> Entity {
+        let wall = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            wall,
+            BlockId::stone(),
+            BlockUpdateCause::Unknown,
+        );
+        let event = ItemUseOnBlockEvent {
+            player,
+            world: WorldId::MAIN,
+            pos: wall,
+            face: Face::South,
+            stack: ItemStack::new(Item::ItemFrame, 1),
+        };
+        test.handle(event, on_item_use_on_block_place_hanging);
+        frames(test)[0]
+    }
+
+    #[test]
+    fn place_insert_rotate() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        let frame = place_frame(&mut test, player);
+        assert_eq!(
+            *test.world.get::<Hanging>(frame),
+            Hanging::new(BlockPosition::new(0, 64, 1), FacingCubic::South)
+        );
+
+        let stack = ItemStack::new(Item::Diamond, 5);
+        let event = EntityInteractEvent {
+            player,
+            target: frame,
+            stack: Some(stack),
+        };
+        test.handle(event.clone(), on_entity_interact_use_item_frame);
+        let contents = *test.world.get::<ItemFrame>(frame);
+        assert_eq!(contents.item, Some(ItemStack::new(Item::Diamond, 1)));
+        assert_eq!(contents.signal(), 1);
+
+        for _ in 0..ROTATIONS + 2 {
+            test.handle(event.clone(), on_entity_interact_use_item_frame);
+        }
+        assert_eq!(test.world.get::<ItemFrame>(frame).rotation, 2);
+        assert_eq!(test.world.get::<ItemFrame>(frame).signal(), 3);
+        assert_eq!(
+            frame_signal(
+                &test.world,
+                WorldId::MAIN,
+                BlockPosition::new(0, 64, 1),
+                FacingCubic::South
+            ),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn hitting_knocks_item_out_then_breaks() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let frame = place_frame(&mut test, player);
+        let event = EntityInteractEvent {
+            player,
+            target: frame,
+            stack: Some(ItemStack::new(Item::Diamond, 1)),
+        };
+        test.handle(event, on_entity_interact_use_item_frame);
+
+        hit_hanging(&mut test.game, &mut test.world, frame, player);
+        test.assert_alive(frame);
+        assert_eq!(test.world.get::<ItemFrame>(frame).item, None);
+
+        hit_hanging(&mut test.game, &mut test.world, frame, player);
+        test.assert_dead(frame);
+    }
+
+    #[test]
+    fn survival_placement_uses_item() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, ItemStack::new(Item::ItemFrame, 1))
+            .unwrap();
+        place_frame(&mut test, player);
+        assert_eq!(
+            test.world
+                .get::<Inventory>(player)
+                .item_at(Area::Hotbar, 0)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn breaks_without_wall() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+        let frame = place_frame(&mut test, player);
+
+        let wall = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            wall,
+            BlockId::air(),
+            BlockUpdateCause::Unknown,
+        );
+        let event = feather_server_types::BlockUpdateEvent {
+            world: WorldId::MAIN,
+            pos: wall,
+            old: BlockId::stone(),
+            new: BlockId::air(),
+            cause: BlockUpdateCause::Unknown,
+        };
+        test.handle(
+            event,
+            crate::object::hanging::on_block_update_break_hanging_entities,
+        );
+        test.assert_dead(frame);
+    }
+}
@@ -0,0 +1,69 @@
+//! Leash knots, created on fences which players tie leashed mobs to.
+//! Knots aren't saved: they are removed once no mob is tied to them.
+
+use feather_core::network::packets::{SoundCategory, SpawnObject};
+use feather_core::network::Packet;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    EntitySpawnEvent, Game, NetworkId, Sound, SpawnPacketCreator, Uuid, WorldId,
+};
+use feather_server_util::degrees_to_stops;
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+
+/// Component for leash knots, storing the
+/// position of the fence they are tied to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LeashKnot(pub BlockPosition);
+
+/// Returns an entity builder to create a knot on the fence
+/// at the given position. Its world still has to be added.
+pub fn create(fence: BlockPosition) -> EntityBuilder {
+    crate::base()
+        .with(LeashKnot(fence))
+        .with(fence.position() + glm::vec3(0.5, 0.5, 0.5))
+        .with(SpawnPacketCreator(&create_spawn_packet))
+}
+
+/// Spawns a knot on the fence at the given position.
+pub fn spawn(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    fence: BlockPosition,
+) -> Entity {
+    let entity = create(fence).with(world_id).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+
+    let sound = Sound::new("entity.leash_knot.place", SoundCategory::Neutral);
+    game.play_sound(world, world_id, *world.get::<Position>(entity), &sound);
+    entity
+}
+
+/// Returns the knot on the fence at the given position, if any.
+pub fn knot_at(world: &World, world_id: WorldId, fence: BlockPosition) -> Option<Entity> {
+    <(Read<LeashKnot>, Read<WorldId>)>::query()
+        .iter_entities(world.inner())
+        .find(|(_, (knot, knot_world))| knot.0 == fence && **knot_world == world_id)
+        .map(|(entity, _)| entity)
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 77, // Type 77 for leash knots
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
@@ -0,0 +1,900 @@
+//! Minecarts, which players place on rails and ride along them.
+//!
+//! Minecarts follow the shape of the rail they are on, speeding up
+//! down slopes and slowing down over time. Powered rails boost
+//! minecarts while powered and brake them otherwise, and detector
+//! rails power redstone while a minecart is on them. The driver of
+//! a rideable minecart pushes it forward when it stands still.
+//!
+//! Chest and hopper minecarts carry an inventory, which players open
+//! by right clicking them. Hopper minecarts pick up items near them,
+//! unless they pass over a powered activator rail.
+//!
+//! Hitting a minecart damages it, and it breaks once damaged enough,
+//! dropping its items. Players in creative mode break it at once
+//! without drops.
+
+use crate::consume_held_item;
+use crate::drops::drop_item;
+use crate::object::item::CollectableAt;
+use crate::vehicle::Vehicle;
+use feather_core::anvil::entity::{
+    BaseEntityData, ChestMinecartEntityData, EntityData, EntityDataKind, HopperMinecartEntityData,
+    MinecartEntityData,
+};
+use feather_core::anvil::player::InventorySlot;
+use feather_core::blocks::{BlockId, PoweredRailShape, RailShape, SimplifiedBlockKind};
+use feather_core::entitymeta::{
+    EntityMetadata, META_INDEX_ITEM_SLOT, META_INDEX_MINECART_SHAKING_DIRECTION,
+    META_INDEX_MINECART_SHAKING_MULTIPLIER, META_INDEX_MINECART_SHAKING_POWER, MINECART_LAYOUT,
+};
+use feather_core::inventory::{Area, Window};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{
+    OpenWindow, PacketEntityMetadata, SoundCategory, SpawnObject, WindowItems,
+};
+use feather_core::network::Packet;
+use feather_core::text::TextRoot;
+use feather_core::util::{BlockPosition, Gamemode, Position, Vec3d};
+use feather_server_types::{
+    ComponentSerializer, EntityInteractEvent, EntityLoaderRegistration, EntitySpawnEvent, Game,
+    Inventory, InventoryUpdateEvent, ItemUseOnBlockEvent, Network, NetworkId, OpenWindowCount,
+    Sound, SpawnPacketCreator, Uuid, Velocity, WindowOpenEvent, WorldId,
+};
+use feather_server_util::{degrees_to_stops, nearby_entities};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, Read, World};
+
+/// Height of minecarts above the rails they are on.
+const RAIL_HEIGHT: f64 = 0.0625;
+/// Acceleration of minecarts down sloped rails.
+const SLOPE_ACCELERATION: f64 = 0.0078125;
+/// Acceleration of minecarts on powered rails.
+const POWERED_RAIL_BOOST: f64 = 0.06;
+/// Speed at which powered rails push standing
+/// minecarts away from a block at their end.
+const POWERED_RAIL_START_SPEED: f64 = 0.02;
+/// Speed below which unpowered powered rails stop minecarts.
+const POWERED_RAIL_STOP_SPEED: f64 = 0.03;
+/// Speed below which the driver of a minecart pushes it.
+const DRIVER_PUSH_SPEED: f64 = 0.01;
+/// Acceleration of minecarts pushed by their driver.
+const DRIVER_PUSH: f64 = 0.1;
+/// Greatest speed of minecarts on rails.
+const MAX_SPEED: f64 = 0.4;
+/// Factors by which minecarts slow down each tick on
+/// rails, with and without passengers, and off rails
+/// on the ground and in the air.
+const RIDDEN_DRAG: f64 = 0.997;
+const EMPTY_DRAG: f64 = 0.96;
+const GROUND_DRAG: f64 = 0.5;
+const AIR_DRAG: f64 = 0.95;
+/// Downwards acceleration of minecarts off rails.
+const GRAVITY: f64 = 0.04;
+/// Speed below which minecarts stop.
+const MIN_SPEED: f64 = 0.001;
+
+/// Damage a hit deals to a minecart.
+const HIT_DAMAGE: f32 = 10.0;
+/// Damage above which a minecart breaks.
+const MAX_DAMAGE: f32 = 40.0;
+/// Ticks a minecart shakes for after it is hit.
+const HIT_TICKS: i32 = 10;
+
+/// Ticks between hopper minecarts picking up items.
+const HOPPER_INTERVAL: u64 = 4;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Minecart, &load)
+}
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::ChestMinecart, &load)
+}
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::HopperMinecart, &load)
+}
+
+/// The kind of a minecart.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MinecartKind {
+    Rideable,
+    Chest,
+    Hopper,
+}
+
+impl MinecartKind {
+    /// Returns the data of Spawn Object packets for the kind.
+    fn data(self) -> i32 {
+        match self {
+            MinecartKind::Rideable => 0,
+            MinecartKind::Chest => 1,
+            MinecartKind::Hopper => 5,
+        }
+    }
+
+    /// Returns the item which places a minecart of the kind.
+    pub fn item(self) -> Item {
+        match self {
+            MinecartKind::Rideable => Item::Minecart,
+            MinecartKind::Chest => Item::ChestMinecart,
+            MinecartKind::Hopper => Item::HopperMinecart,
+        }
+    }
+
+    pub fn from_item(item: Item) -> Option<Self> {
+        match item {
+            Item::Minecart => Some(MinecartKind::Rideable),
+            Item::ChestMinecart => Some(MinecartKind::Chest),
+            Item::HopperMinecart => Some(MinecartKind::Hopper),
+            _ => None,
+        }
+    }
+
+    /// Returns the items a minecart of the kind breaks into.
+    fn drops(self) -> &'static [Item] {
+        match self {
+            MinecartKind::Rideable => &[Item::Minecart],
+            MinecartKind::Chest => &[Item::Minecart, Item::Chest],
+            MinecartKind::Hopper => &[Item::Minecart, Item::Hopper],
+        }
+    }
+
+    /// Returns the area holding the items of a minecart of the kind.
+    fn storage(self) -> Option<Area> {
+        match self {
+            MinecartKind::Rideable => None,
+            MinecartKind::Chest => Some(Area::Chest),
+            MinecartKind::Hopper => Some(Area::Hopper),
+        }
+    }
+
+    /// Returns an empty inventory for a minecart of the kind.
+    fn inventory(self) -> Option<Inventory> {
+        match self {
+            MinecartKind::Rideable => None,
+            MinecartKind::Chest => Some(Inventory::chest()),
+            MinecartKind::Hopper => Some(Inventory::hopper()),
+        }
+    }
+}
+
+/// Component for minecarts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Minecart {
+    pub kind: MinecartKind,
+    /// Damage taken from recent hits, which wears off over time.
+    pub damage: f32,
+    /// Ticks left for the minecart to shake after it was hit.
+    pub shaking_ticks: i32,
+    /// The direction the minecart shakes in, which flips on every hit.
+    pub shaking_direction: i32,
+    /// Whether a hopper minecart picks up items.
+    pub enabled: bool,
+}
+
+impl Minecart {
+    pub fn new(kind: MinecartKind) -> Self {
+        Self {
+            kind,
+            damage: 0.0,
+            shaking_ticks: 0,
+            shaking_direction: 1,
+            enabled: true,
+        }
+    }
+}
+
+/// Returns an entity builder to create an empty minecart of the
+/// given kind at the given position. Its world still has to be added.
+pub fn create(pos: Position, kind: MinecartKind) -> EntityBuilder {
+    create_with(pos, Minecart::new(kind), kind.inventory())
+}
+
+/// Returns an entity builder to create a minecart with the given
+/// items, which chest and hopper minecarts need. Its world still
+/// has to be added.
+pub fn create_with(
+    pos: Position,
+    minecart: Minecart,
+    inventory: Option<Inventory>,
+) -> EntityBuilder {
+    let meta = EntityMetadata::entity_base()
+        .with_layout(MINECART_LAYOUT)
+        .with(META_INDEX_MINECART_SHAKING_POWER, 0i32)
+        .with(META_INDEX_MINECART_SHAKING_DIRECTION, 1i32)
+        .with(META_INDEX_MINECART_SHAKING_MULTIPLIER, 0.0f32);
+
+    let mut builder = crate::base()
+        .with(minecart)
+        .with(pos)
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(ComponentSerializer(&serialize));
+    if minecart.kind == MinecartKind::Rideable {
+        builder = builder.with(Vehicle::new(1, 0.7, false));
+    }
+    if let Some(inventory) = inventory {
+        builder = builder.with(inventory);
+    }
+    builder
+}
+
+/// Returns the shape of a rail, or `None` if the block isn't a rail.
+fn rail_shape(block: BlockId) -> Option<RailShape> {
+    if let Some(shape) = block.rail_shape() {
+        return Some(shape);
+    }
+    Some(match block.powered_rail_shape()? {
+        PoweredRailShape::NorthSouth => RailShape::NorthSouth,
+        PoweredRailShape::EastWest => RailShape::EastWest,
+        PoweredRailShape::AscendingEast => RailShape::AscendingEast,
+        PoweredRailShape::AscendingWest => RailShape::AscendingWest,
+        PoweredRailShape::AscendingNorth => RailShape::AscendingNorth,
+        PoweredRailShape::AscendingSouth => RailShape::AscendingSouth,
+    })
+}
+
+/// Returns the horizontal offsets to the
+/// two blocks a rail of the given shape joins.
+fn exits(shape: RailShape) -> [(i32, i32); 2] {
+    match shape {
+        RailShape::NorthSouth | RailShape::AscendingNorth | RailShape::AscendingSouth => {
+            [(0, -1), (0, 1)]
+        }
+        RailShape::EastWest | RailShape::AscendingEast | RailShape::AscendingWest => {
+            [(-1, 0), (1, 0)]
+        }
+        RailShape::SouthEast => [(0, 1), (1, 0)],
+        RailShape::SouthWest => [(0, 1), (-1, 0)],
+        RailShape::NorthWest => [(0, -1), (-1, 0)],
+        RailShape::NorthEast => [(0, -1), (1, 0)],
+    }
+}
+
+/// Returns the horizontal direction in which
+/// a rail of the given shape rises, if it does.
+fn uphill(shape: RailShape) -> Option<(i32, i32)> {
+    match shape {
+        RailShape::AscendingEast => Some((1, 0)),
+        RailShape::AscendingWest => Some((-1, 0)),
+        RailShape::AscendingNorth => Some((0, -1)),
+        RailShape::AscendingSouth => Some((0, 1)),
+        _ => None,
+    }
+}
+
+/// Returns the height of a minecart at the given
+/// position on a rail of the given shape.
+fn rail_y(rail: BlockPosition, shape: RailShape, pos: Position) -> f64 {
+    let (x, z) = (pos.x - f64::from(rail.x), pos.z - f64::from(rail.z));
+    let rise = match uphill(shape) {
+        Some((1, _)) => x,
+        Some((-1, _)) => 1.0 - x,
+        Some((_, 1)) => z,
+        Some(_) => 1.0 - z,
+        None => 0.0,
+    };
+    f64::from(rail.y) + RAIL_HEIGHT + rise.clamp(0.0, 1.0)
+}
+
+/// Returns the rail a minecart at the given position is on, if any.
+fn rail_at(game: &Game, world_id: WorldId, pos: Position) -> Option<(BlockPosition, BlockId)> {
+    let block = pos.block();
+    [block, block.down()].iter().find_map(|&rail_pos| {
+        let rail = game.block_at(world_id, rail_pos)?;
+        rail_shape(rail).map(|_| (rail_pos, rail))
+    })
+}
+
+fn is_solid(game: &Game, world_id: WorldId, pos: BlockPosition) -> bool {
+    game.block_at(world_id, pos)
+        .map_or(true, |block| block.is_solid())
+}
+
+/// Places a minecart when a player uses one on a rail.
+#[fecs::event_handler]
+pub fn on_item_use_on_block_place_minecart(
+    event: &ItemUseOnBlockEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let kind = match MinecartKind::from_item(event.stack.ty) {
+        Some(kind) => kind,
+        None => return,
+    };
+    let shape = match game.block_at(event.world, event.pos).and_then(rail_shape) {
+        Some(shape) => shape,
+        None => return,
+    };
+
+    let mut pos = event.pos.position() + glm::vec3(0.5, 0.0, 0.5);
+    pos.y = rail_y(event.pos, shape, pos);
+    let entity = create(pos, kind).with(event.world).build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+    consume_held_item(game, world, event.player, event.stack);
+}
+
+/// System which moves minecarts along the rails they are
+/// on, and lets those off rails fall and slow down.
+#[fecs::system]
+pub fn move_minecarts(game: &mut Game, world: &mut World) {
+    let carts: Vec<Entity> = <Read<Minecart>>::query()
+        .iter_entities(world.inner())
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for cart in carts {
+        {
+            let mut state = world.get_mut::<Minecart>(cart);
+            state.damage = (state.damage - 1.0).max(0.0);
+            state.shaking_ticks = (state.shaking_ticks - 1).max(0);
+        }
+
+        let world_id = game.world_of(world, cart);
+        let pos = *world.get::<Position>(cart);
+        let mut velocity = world.get::<Velocity>(cart).0;
+        let (pos, velocity) = match rail_at(game, world_id, pos) {
+            Some((rail_pos, rail)) => {
+                if rail.simplified_kind() == SimplifiedBlockKind::ActivatorRail {
+                    world.get_mut::<Minecart>(cart).enabled = rail.powered() != Some(true);
+                }
+
+                let (driver, driving) = match world.try_get::<Vehicle>(cart) {
+                    Some(vehicle) => (vehicle.driver(), vehicle.forward > 0.0),
+                    None => (None, false),
+                };
+                let standing = velocity.x.hypot(velocity.z) < DRIVER_PUSH_SPEED;
+                if let Some(driver) = driver.filter(|_| driving && standing) {
+                    let look = world.get::<Position>(driver).direction();
+                    velocity.x += look.x * DRIVER_PUSH;
+                    velocity.z += look.z * DRIVER_PUSH;
+                }
+
+                let drag = if driver.is_some() {
+                    RIDDEN_DRAG
+                } else {
+                    EMPTY_DRAG
+                };
+                move_on_rail(game, world_id, pos, velocity, (rail_pos, rail), drag)
+            }
+            None => move_off_rail(game, world_id, pos, velocity),
+        };
+        *world.get_mut::<Position>(cart) = pos;
+        world.get_mut::<Velocity>(cart).0 = velocity;
+    }
+}
+
+/// Returns the position and velocity of a minecart after it
+/// moved for a tick along the given rail, slowed down by `drag`.
+fn move_on_rail(
+    game: &Game,
+    world_id: WorldId,
+    mut pos: Position,
+    mut velocity: glm::DVec3,
+    (rail_pos, rail): (BlockPosition, BlockId),
+    drag: f64,
+) -> (Position, glm::DVec3) {
+    let shape = rail_shape(rail).unwrap();
+    let [start, end] = exits(shape);
+    let (dx, dz) = (f64::from(end.0 - start.0), f64::from(end.1 - start.1));
+    let length = dx.hypot(dz);
+    let (dx, dz) = (dx / length, dz / length);
+
+    if let Some((x, z)) = uphill(shape) {
+        velocity.x -= f64::from(x) * SLOPE_ACCELERATION;
+        velocity.z -= f64::from(z) * SLOPE_ACCELERATION;
+    }
+
+    // Minecarts keep their speed along the rail,
+    // turning with it through curves.
+    let along = velocity.x * dx + velocity.z * dz;
+    let mut speed = if along.abs() < std::f64::EPSILON {
+        0.0
+    } else {
+        velocity.x.hypot(velocity.z) * along.signum()
+    };
+
+    if rail.simplified_kind() == SimplifiedBlockKind::PoweredRail {
+        if rail.powered() == Some(true) {
+            if speed.abs() > DRIVER_PUSH_SPEED {
+                speed += POWERED_RAIL_BOOST * speed.signum();
+            } else {
+                // Standing minecarts are pushed away
+                // from a block at one end of the rail.
+                for &(x, z) in &[start, end] {
+                    if is_solid(game, world_id, rail_pos + BlockPosition::new(x, 0, z)) {
+                        let towards = f64::from(x) * dx + f64::from(z) * dz;
+                        speed = -towards.signum() * POWERED_RAIL_START_SPEED;
+                    }
+                }
+            }
+        } else if speed.abs() < POWERED_RAIL_STOP_SPEED {
+            speed = 0.0;
+        } else {
+            speed *= 0.5;
+        }
+    }
+
+    speed = (speed * drag).clamp(-MAX_SPEED, MAX_SPEED);
+    if speed.abs() < MIN_SPEED {
+        speed = 0.0;
+    }
+    velocity = glm::vec3(dx * speed, 0.0, dz * speed);
+
+    // Minecarts are kept on the middle of the rail.
+    let (center_x, center_z) = (f64::from(rail_pos.x) + 0.5, f64::from(rail_pos.z) + 0.5);
+    let offset = (pos.x - center_x) * dx + (pos.z - center_z) * dz;
+    let mut next = pos;
+    next.x = center_x + dx * offset + velocity.x;
+    next.z = center_z + dz * offset + velocity.z;
+
+    let base = BlockPosition::new(next.x.floor() as i32, rail_pos.y, next.z.floor() as i32);
+    let next_rail = [base, base.up(), base.down()]
+        .iter()
+        .find_map(|&pos| Some((pos, rail_shape(game.block_at(world_id, pos)?)?)));
+    match next_rail {
+        Some((next_rail, next_shape)) => next.y = rail_y(next_rail, next_shape, next),
+        // Minecarts stop at the end of a rail against a block.
+        None if is_solid(game, world_id, base) => {
+            next = pos;
+            velocity = glm::vec3(0.0, 0.0, 0.0);
+        }
+        None => next.y = f64::from(rail_pos.y) + RAIL_HEIGHT,
+    }
+
+    pos.x = next.x;
+    pos.y = next.y;
+    pos.z = next.z;
+    pos.on_ground = true;
+    (pos, velocity)
+}
+
+/// Returns the position and velocity of a minecart
+/// after it moved for a tick while off rails.
+fn move_off_rail(
+    game: &Game,
+    world_id: WorldId,
+    mut pos: Position,
+    mut velocity: glm::DVec3,
+) -> (Position, glm::DVec3) {
+    velocity.y -= GRAVITY;
+    let drag = if pos.on_ground { GROUND_DRAG } else { AIR_DRAG };
+    velocity.x *= drag;
+    velocity.z *= drag;
+    if velocity.x.abs() < MIN_SPEED && velocity.z.abs() < MIN_SPEED {
+        velocity.x = 0.0;
+        velocity.z = 0.0;
+    }
+    if is_solid(
+        game,
+        world_id,
+        (pos + glm::vec3(velocity.x, 0.0, velocity.z)).block(),
+    ) {
+        velocity.x = 0.0;
+        velocity.z = 0.0;
+    }
+
+    let next = pos + velocity;
+    let below = next.block();
+    pos.on_ground = velocity.y < 0.0 && is_solid(game, world_id, below);
+    if pos.on_ground {
+        pos.y = f64::from(below.y + 1);
+        velocity.y = 0.0;
+    } else {
+        pos.y = next.y;
+    }
+    pos.x = next.x;
+    pos.z = next.z;
+    (pos, velocity)
+}
+
+/// System which makes enabled hopper minecarts
+/// pick up the items lying around them.
+#[fecs::system]
+pub fn collect_items_into_hopper_minecarts(game: &mut Game, world: &mut World) {
+    if game.tick_count % HOPPER_INTERVAL != 0 {
+        return;
+    }
+
+    let carts: Vec<(Entity, Position)> = <(Read<Minecart>, Read<Position>)>::query()
+        .iter_entities(world.inner())
+        .filter(|(_, (cart, _))| cart.kind == MinecartKind::Hopper && cart.enabled)
+        .map(|(entity, (_, pos))| (entity, *pos))
+        .collect();
+
+    for (cart, pos) in carts {
+        let world_id = game.world_of(world, cart);
+        let world_age = match game.worlds.get(world_id) {
+            Some(state) => state.time.world_age(),
+            None => continue,
+        };
+        let center = pos + glm::vec3(0.0, 0.5, 0.0);
+        let items: Vec<Entity> =
+            nearby_entities(world, game, world_id, center, glm::vec3(1.0, 1.0, 1.0))
+                .into_iter()
+                .filter(|&item| {
+                    world
+                        .try_get::<CollectableAt>(item)
+                        .map_or(false, |collectable_at| collectable_at.0 <= world_age)
+                })
+                .collect();
+        for item in items {
+            collect_item(game, world, cart, item);
+        }
+    }
+}
+
+/// Moves as much of an item entity's stack as fits into a hopper minecart.
+fn collect_item(game: &mut Game, world: &mut World, cart: Entity, item: Entity) {
+    let stack = *world.get::<ItemStack>(item);
+    let (slots, remaining) = world
+        .get::<Inventory>(cart)
+        .insert_item(Area::Hopper, stack)
+        .unwrap();
+    if slots.is_empty() {
+        return;
+    }
+
+    if remaining == 0 {
+        game.despawn(item, world);
+    } else {
+        world.get_mut::<ItemStack>(item).amount = remaining;
+        world
+            .get_mut::<EntityMetadata>(item)
+            .set(META_INDEX_ITEM_SLOT, Some(stack.of_amount(remaining)));
+    }
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots,
+            entity: cart,
+        },
+    );
+}
+
+/// When a player right clicks a chest or hopper
+/// minecart, opens a window showing its items.
+#[fecs::event_handler]
+pub fn on_entity_interact_open_minecart(
+    event: &EntityInteractEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let kind = match world.try_get::<Minecart>(event.target) {
+        Some(cart) => cart.kind,
+        None => return,
+    };
+    let (window_type, window_title, window) = match kind {
+        MinecartKind::Rideable => return,
+        MinecartKind::Chest => (
+            "minecraft:generic_9x3",
+            "Minecart with Chest",
+            Window::chest(event.player, event.target),
+        ),
+        MinecartKind::Hopper => (
+            "minecraft:hopper",
+            "Minecart with Hopper",
+            Window::hopper(event.player, event.target),
+        ),
+    };
+    let window_id = match world.try_get_mut::<OpenWindowCount>(event.player) {
+        Some(mut count) => count.get_increment(),
+        None => return,
+    };
+
+    {
+        let slots: Vec<_> = world
+            .get::<Inventory>(event.target)
+            .enumerate()
+            .map(|(_, item)| item)
+            .collect();
+        let network = world.get::<Network>(event.player);
+        network.send(OpenWindow {
+            window_id,
+            window_type: String::from(window_type),
+            window_title: TextRoot::from(window_title).into(),
+            number_of_slots: slots.len() as u8,
+            entity_id: None,
+        });
+        network.send(WindowItems { window_id, slots });
+    }
+
+    *world.get_mut::<Window>(event.player) = window;
+    game.handle(
+        world,
+        WindowOpenEvent {
+            player: event.player,
+            opened: event.target,
+        },
+    );
+}
+
+/// Handles a player hitting a minecart, which breaks once it
+/// took enough damage, or at once by players in creative mode.
+pub fn hit_minecart(game: &mut Game, world: &mut World, cart: Entity, player: Entity) {
+    if *world.get::<Gamemode>(player) == Gamemode::Creative {
+        break_minecart(game, world, cart, false);
+        return;
+    }
+
+    let state = {
+        let mut state = world.get_mut::<Minecart>(cart);
+        state.shaking_direction = -state.shaking_direction;
+        state.shaking_ticks = HIT_TICKS;
+        state.damage += HIT_DAMAGE;
+        *state
+    };
+    if state.damage > MAX_DAMAGE {
+        break_minecart(game, world, cart, true);
+        return;
+    }
+
+    let packet = PacketEntityMetadata {
+        entity_id: world.get::<NetworkId>(cart).0,
+        metadata: EntityMetadata::new()
+            .with_layout(MINECART_LAYOUT)
+            .with(META_INDEX_MINECART_SHAKING_POWER, state.shaking_ticks)
+            .with(
+                META_INDEX_MINECART_SHAKING_DIRECTION,
+                state.shaking_direction,
+            )
+            .with(META_INDEX_MINECART_SHAKING_MULTIPLIER, state.damage),
+    };
+    game.broadcast_entity_update(world, packet, cart, None);
+}
+
+/// Breaks a minecart, dropping it along
+/// with its items if `drops` is set.
+pub fn break_minecart(game: &mut Game, world: &mut World, cart: Entity, drops: bool) {
+    let world_id = game.world_of(world, cart);
+    let pos = *world.get::<Position>(cart);
+    let kind = world.get::<Minecart>(cart).kind;
+    let items: Vec<ItemStack> = if drops {
+        let contents: Vec<ItemStack> = match world.try_get::<Inventory>(cart) {
+            Some(inventory) => inventory.enumerate().filter_map(|(_, item)| item).collect(),
+            None => vec![],
+        };
+        kind.drops()
+            .iter()
+            .map(|&item| ItemStack::new(item, 1))
+            .chain(contents)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    game.despawn(cart, world);
+    let sound = Sound::new("entity.item.break", SoundCategory::Neutral).volume(0.5);
+    game.play_sound(world, world_id, pos, &sound);
+    for stack in items {
+        drop_item(game, world, world_id, stack, pos);
+    }
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 10, // Type 10 for minecarts
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: accessor.get::<Minecart>().kind.data(),
+        velocity_x: 0,
+        velocity_y: 0,
+        velocity_z: 0,
+    };
+
+    Box::new(packet)
+}
+
+fn serialize_items(accessor: &EntityRef) -> Vec<InventorySlot> {
+    accessor
+        .get::<Inventory>()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            Some(InventorySlot::from_inventory_index(index.slot as i8, item?))
+        })
+        .collect()
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let vel = accessor.get::<Velocity>().0;
+    let entity = BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z));
+    let cart = accessor.get::<Minecart>();
+
+    match cart.kind {
+        MinecartKind::Rideable => EntityData::Minecart(MinecartEntityData { entity }),
+        MinecartKind::Chest => EntityData::ChestMinecart(ChestMinecartEntityData {
+            entity,
+            items: serialize_items(accessor),
+        }),
+        MinecartKind::Hopper => EntityData::HopperMinecart(HopperMinecartEntityData {
+            entity,
+            items: serialize_items(accessor),
+            enabled: Some(cart.enabled as i8),
+        }),
+    }
+}
+
+/// Fills the storage area of a minecart's inventory
+/// from NBT slots. Unknown items are skipped.
+fn load_items(kind: MinecartKind, inventory: &Inventory, slots: &[InventorySlot]) {
+    let area = match kind.storage() {
+        Some(area) => area,
+        None => return,
+    };
+    for slot in slots {
+        if Item::from_identifier(&slot.item).is_none() || slot.slot < 0 {
+            continue;
+        }
+        if let Err(e) = inventory.set_item_at(area, slot.slot as usize, slot.into()) {
+            log::warn!("Invalid minecart slot: {}", e);
+        }
+    }
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    let (entity, kind, items, enabled) = match &data {
+        EntityData::Minecart(data) => (&data.entity, MinecartKind::Rideable, &[][..], None),
+        EntityData::ChestMinecart(data) => {
+            (&data.entity, MinecartKind::Chest, &data.items[..], None)
+        }
+        EntityData::HopperMinecart(data) => (
+            &data.entity,
+            MinecartKind::Hopper,
+            &data.items[..],
+            data.enabled,
+        ),
+        _ => panic!("attempted to use minecart::load to load a non-minecart"),
+    };
+    let pos = entity.read_position()?;
+    let vel = entity.read_velocity()?;
+
+    let mut cart = Minecart::new(kind);
+    cart.enabled = enabled.map_or(true, |enabled| enabled != 0);
+    let inventory = kind.inventory();
+    if let Some(inventory) = &inventory {
+        load_items(kind, inventory, items);
+    }
+
+    Ok(create_with(pos, cart, inventory).with(Velocity(glm::vec3(vel.x, vel.y, vel.z))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    /// Lays a straight line of rails from west to east.
+    fn lay_rails(test: &mut Test, from_x: i32, to_x: i32, rail: BlockId) {
+        for x in from_x..=to_x {
+            test.set_block(BlockPosition::new(x, 63, 0), BlockId::stone());
+            test.set_block(BlockPosition::new(x, 64, 0), rail);
+        }
+    }
+
+    fn east_west(rail: BlockId) -> BlockId {
+        match rail.rail_shape() {
+            Some(_) => rail.with_rail_shape(RailShape::EastWest),
+            None => rail.with_powered_rail_shape(PoweredRailShape::EastWest),
+        }
+    }
+
+    #[test]
+    fn follow_rail() {
+        let mut test = Test::new();
+        lay_rails(&mut test, -8, 8, east_west(BlockId::rail()));
+        let cart = test.entity(create(position!(0.5, 64.0625, 0.7), MinecartKind::Rideable));
+        test.world.get_mut::<Velocity>(cart).0 = glm::vec3(0.3, 0.0, 0.1);
+
+        test.run(move_minecarts);
+        let pos = *test.world.get::<Position>(cart);
+        let velocity = test.world.get::<Velocity>(cart).0;
+        assert!(pos.x > 0.5);
+        assert!((pos.z - 0.5).abs() < 1e-9);
+        assert!((pos.y - 64.0625).abs() < 1e-9);
+        assert_eq!(velocity.z, 0.0);
+    }
+
+    #[test]
+    fn powered_rail_boosts_and_brakes() {
+        let mut test = Test::new();
+        let powered = east_west(BlockId::powered_rail()).with_powered(true);
+        lay_rails(&mut test, -2, 2, powered);
+        let cart = test.entity(create(position!(0.5, 64.0625, 0.5), MinecartKind::Rideable));
+        test.world.get_mut::<Velocity>(cart).0 = glm::vec3(0.1, 0.0, 0.0);
+        test.run(move_minecarts);
+        assert!(test.world.get::<Velocity>(cart).0.x > 0.1);
+
+        lay_rails(&mut test, -2, 2, powered.with_powered(false));
+        test.position(cart, position!(0.5, 64.0625, 0.5));
+        test.world.get_mut::<Velocity>(cart).0 = glm::vec3(0.02, 0.0, 0.0);
+        test.run(move_minecarts);
+        assert_eq!(test.world.get::<Velocity>(cart).0.x, 0.0);
+    }
+
+    #[test]
+    fn stop_at_wall() {
+        let mut test = Test::new();
+        lay_rails(&mut test, 0, 0, east_west(BlockId::rail()));
+        test.set_block(BlockPosition::new(1, 64, 0), BlockId::stone());
+        let cart = test.entity(create(position!(0.9, 64.0625, 0.5), MinecartKind::Rideable));
+        test.world.get_mut::<Velocity>(cart).0 = glm::vec3(0.3, 0.0, 0.0);
+
+        test.run(move_minecarts);
+        assert!(test.world.get::<Position>(cart).x < 1.0);
+        assert_eq!(test.world.get::<Velocity>(cart).0.x, 0.0);
+    }
+
+    #[test]
+    fn hopper_minecart_collects_items() {
+        let mut test = Test::new();
+        let cart = test.entity(create(position!(0.5, 64.0, 0.5), MinecartKind::Hopper));
+        let item = test.entity(
+            crate::object::item::create(ItemStack::new(Item::Diamond, 3), 0)
+                .with(position!(0.5, 64.5, 0.5)),
+        );
+
+        test.game.tick_count = HOPPER_INTERVAL;
+        test.run(collect_items_into_hopper_minecarts);
+        test.assert_dead(item);
+        assert_eq!(
+            test.world
+                .get::<Inventory>(cart)
+                .item_at(Area::Hopper, 0)
+                .unwrap(),
+            Some(ItemStack::new(Item::Diamond, 3))
+        );
+    }
+
+    #[test]
+    fn break_drops_contents() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        let cart = test.entity(create(position!(2.0, 64.0, 0.0), MinecartKind::Chest));
+        test.world
+            .get::<Inventory>(cart)
+            .set_item_at(Area::Chest, 4, ItemStack::new(Item::Stone, 5))
+            .unwrap();
+
+        for _ in 0..4 {
+            hit_minecart(&mut test.game, &mut test.world, cart, player);
+        }
+        test.assert_alive(cart);
+        hit_minecart(&mut test.game, &mut test.world, cart, player);
+        test.assert_dead(cart);
+
+        let drops: Vec<ItemStack> = <Read<ItemStack>>::query()
+            .iter(test.world.inner())
+            .map(|stack| *stack)
+            .collect();
+        assert_eq!(drops.len(), 3);
+        assert!(drops.contains(&ItemStack::new(Item::Minecart, 1)));
+        assert!(drops.contains(&ItemStack::new(Item::Chest, 1)));
+        assert!(drops.contains(&ItemStack::new(Item::Stone, 5)));
+    }
+
+    #[test]
+    fn open_chest_minecart() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let cart = test.entity(create(position!(2.0, 64.0, 0.0), MinecartKind::Chest));
+
+        let event = EntityInteractEvent {
+            player,
+            target: cart,
+            stack: None,
+        };
+        test.handle(event, on_entity_interact_open_minecart);
+        let packet = test.sent::<OpenWindow>(player).unwrap();
+        assert_eq!(packet.number_of_slots, 27);
+        assert_eq!(test.world.get::<Window>(player).wrapped_entities(), &[cart]);
+    }
+}
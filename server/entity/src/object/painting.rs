@@ -0,0 +1,294 @@
+//! Paintings, which show one of a set of motives of various
+//! sizes. A placed painting gets a random motive among the
+//! largest ones which fit on the wall it is placed on.
+
+use crate::object::hanging::{can_hang, facing_data_2d, facing_from_data_2d, Hanging};
+use feather_core::anvil::entity::{EntityData, EntityDataKind, PaintingEntityData};
+use feather_core::network::packets::SpawnPainting;
+use feather_core::network::Packet;
+use feather_server_types::{
+    ComponentSerializer, EntityLoaderRegistration, Game, NetworkId, SpawnPacketCreator, Uuid,
+    WorldId,
+};
+use fecs::{EntityBuilder, EntityRef, World};
+use rand::Rng;
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Painting, &load)
+}
+
+/// A motive of a painting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Motive {
+    Kebab,
+    Aztec,
+    Alban,
+    Aztec2,
+    Bomb,
+    Plant,
+    Wasteland,
+    Pool,
+    Courbet,
+    Sea,
+    Sunset,
+    Creebet,
+    Wanderer,
+    Graham,
+    Match,
+    Bust,
+    Stage,
+    Void,
+    SkullAndRoses,
+    Wither,
+    Fighters,
+    Pointer,
+    Pigscene,
+    BurningSkull,
+    Skeleton,
+    DonkeyKong,
+}
+
+/// All motives, in the order of their protocol IDs.
+const MOTIVES: [Motive; 26] = [
+    Motive::Kebab,
+    Motive::Aztec,
+    Motive::Alban,
+    Motive::Aztec2,
+    Motive::Bomb,
+    Motive::Plant,
+    Motive::Wasteland,
+    Motive::Pool,
+    Motive::Courbet,
+    Motive::Sea,
+    Motive::Sunset,
+    Motive::Creebet,
+    Motive::Wanderer,
+    Motive::Graham,
+    Motive::Match,
+    Motive::Bust,
+    Motive::Stage,
+    Motive::Void,
+    Motive::SkullAndRoses,
+    Motive::Wither,
+    Motive::Fighters,
+    Motive::Pointer,
+    Motive::Pigscene,
+    Motive::BurningSkull,
+    Motive::Skeleton,
+    Motive::DonkeyKong,
+];
+
+impl Motive {
+    /// Returns the protocol ID of this motive.
+    pub fn id(self) -> i32 {
+        MOTIVES.iter().position(|&motive| motive == self).unwrap() as i32
+    }
+
+    /// Returns the namespaced identifier of this motive.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            Motive::Kebab => "minecraft:kebab",
+            Motive::Aztec => "minecraft:aztec",
+            Motive::Alban => "minecraft:alban",
+            Motive::Aztec2 => "minecraft:aztec2",
+            Motive::Bomb => "minecraft:bomb",
+            Motive::Plant => "minecraft:plant",
+            Motive::Wasteland => "minecraft:wasteland",
+            Motive::Pool => "minecraft:pool",
+            Motive::Courbet => "minecraft:courbet",
+            Motive::Sea => "minecraft:sea",
+            Motive::Sunset => "minecraft:sunset",
+            Motive::Creebet => "minecraft:creebet",
+            Motive::Wanderer => "minecraft:wanderer",
+            Motive::Graham => "minecraft:graham",
+            Motive::Match => "minecraft:match",
+            Motive::Bust => "minecraft:bust",
+            Motive::Stage => "minecraft:stage",
+            Motive::Void => "minecraft:void",
+            Motive::SkullAndRoses => "minecraft:skull_and_roses",
+            Motive::Wither => "minecraft:wither",
+            Motive::Fighters => "minecraft:fighters",
+            Motive::Pointer => "minecraft:pointer",
+            Motive::Pigscene => "minecraft:pigscene",
+            Motive::BurningSkull => "minecraft:burning_skull",
+            Motive::Skeleton => "minecraft:skeleton",
+            Motive::DonkeyKong => "minecraft:donkey_kong",
+        }
+    }
+
+    /// Returns the motive with the given namespaced identifier.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        MOTIVES
+            .iter()
+            .copied()
+            .find(|motive| motive.identifier() == identifier)
+    }
+
+    /// Returns the width and height of this motive in blocks.
+    pub fn size(self) -> (i32, i32) {
+        match self {
+            Motive::Kebab
+            | Motive::Aztec
+            | Motive::Alban
+            | Motive::Aztec2
+            | Motive::Bomb
+            | Motive::Plant
+            | Motive::Wasteland => (1, 1),
+            Motive::Pool | Motive::Courbet | Motive::Sea | Motive::Sunset | Motive::Creebet => {
+                (2, 1)
+            }
+            Motive::Wanderer | Motive::Graham => (1, 2),
+            Motive::Match
+            | Motive::Bust
+            | Motive::Stage
+            | Motive::Void
+            | Motive::SkullAndRoses
+            | Motive::Wither => (2, 2),
+            Motive::Fighters => (4, 2),
+            Motive::Pointer | Motive::Pigscene | Motive::BurningSkull => (4, 4),
+            Motive::Skeleton | Motive::DonkeyKong => (4, 3),
+        }
+    }
+}
+
+/// Component for paintings, storing their motive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Painting(pub Motive);
+
+/// Returns an entity builder to create a painting with the
+/// given motive. Its world still has to be added.
+pub fn create(hanging: Hanging, motive: Motive) -> EntityBuilder {
+    let (width, height) = motive.size();
+    let hanging = hanging.with_size(width, height);
+
+    crate::base()
+        .with(hanging)
+        .with(Painting(motive))
+        .with(hanging.position())
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(ComponentSerializer(&serialize))
+}
+
+/// Chooses a random motive among the largest ones which
+/// can hang at the given position, if any.
+pub fn choose_motive(
+    game: &Game,
+    world: &World,
+    world_id: WorldId,
+    hanging: Hanging,
+) -> Option<Motive> {
+    let fitting: Vec<Motive> = MOTIVES
+        .iter()
+        .copied()
+        .filter(|motive| {
+            let (width, height) = motive.size();
+            can_hang(game, world, world_id, &hanging.with_size(width, height))
+        })
+        .collect();
+
+    let area = |motive: &Motive| {
+        let (width, height) = motive.size();
+        width * height
+    };
+    let largest = fitting.iter().map(area).max()?;
+    let largest: Vec<Motive> = fitting
+        .into_iter()
+        .filter(|motive| area(motive) == largest)
+        .collect();
+    let index = game.rng().gen_range(0, largest.len());
+    Some(largest[index])
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let hanging = *accessor.get::<Hanging>();
+
+    let packet = SpawnPainting {
+        entity_id: accessor.get::<NetworkId>().0,
+        entity_uuid: Uuid::new_v4(),
+        motive: accessor.get::<Painting>().0.id(),
+        location: hanging.pos,
+        direction: facing_data_2d(hanging.facing) as u8,
+    };
+
+    Box::new(packet)
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let hanging = *accessor.get::<Hanging>();
+
+    EntityData::Painting(PaintingEntityData {
+        hanging: hanging.to_data(facing_data_2d(hanging.facing)),
+        motive: accessor.get::<Painting>().0.identifier().to_owned(),
+    })
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Painting(data) => {
+            let facing = facing_from_data_2d(data.hanging.facing)
+                .ok_or_else(|| anyhow::anyhow!("invalid painting facing"))?;
+            let motive = Motive::from_identifier(&data.motive)
+                .ok_or_else(|| anyhow::anyhow!("invalid painting motive {}", data.motive))?;
+
+            Ok(create(Hanging::new(data.hanging.tile(), facing), motive))
+        }
+        _ => panic!("attempted to use painting::load to load a non-painting"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::hanging::on_item_use_on_block_place_hanging;
+    use feather_core::blocks::{BlockId, FacingCubic};
+    use feather_core::items::{Item, ItemStack};
+    use feather_core::network::packets::Face;
+    use feather_core::util::BlockPosition;
+    use feather_server_types::{BlockUpdateCause, ItemUseOnBlockEvent};
+    use feather_test_framework::Test;
+    use fecs::{IntoQuery, Read};
+
+    #[test]
+    fn motives() {
+        assert_eq!(Motive::Kebab.id(), 0);
+        assert_eq!(Motive::DonkeyKong.id(), 25);
+        for &motive in &MOTIVES {
+            assert_eq!(Motive::from_identifier(motive.identifier()), Some(motive));
+        }
+    }
+
+    #[test]
+    fn largest_fitting_motive() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.5, 64.0, 3.0));
+
+        // A wall two blocks wide and one block high.
+        for &x in &[0, 1] {
+            test.game.set_block_at(
+                &mut test.world,
+                WorldId::MAIN,
+                BlockPosition::new(x, 64, 0),
+                BlockId::stone(),
+                BlockUpdateCause::Unknown,
+            );
+        }
+        let event = ItemUseOnBlockEvent {
+            player,
+            world: WorldId::MAIN,
+            pos: BlockPosition::new(0, 64, 0),
+            face: Face::South,
+            stack: ItemStack::new(Item::Painting, 1),
+        };
+        test.handle(event, on_item_use_on_block_place_hanging);
+
+        let paintings: Vec<(Hanging, Painting)> = <(Read<Hanging>, Read<Painting>)>::query()
+            .iter(test.world.inner())
+            .map(|(hanging, painting)| (*hanging, *painting))
+            .collect();
+        assert_eq!(paintings.len(), 1);
+        let (hanging, painting) = paintings[0];
+        assert_eq!(painting.0.size(), (2, 1));
+        assert_eq!(hanging.facing, FacingCubic::South);
+        assert_eq!(hanging.pos, BlockPosition::new(0, 64, 1));
+    }
+}
@@ -1,7 +1,7 @@
 //! Implements blocks that break when not supported by a full block: torches, snow, grass, etc.
 
 use feather_core::blocks::BlockId;
-use feather_server_types::{BlockUpdateCause, BumpVec, Game};
+use feather_server_types::{BlockUpdateCause, BumpVec, Game, WorldId};
 use feather_server_util::{
     is_block_supported_at, BlockNotifyBlock, BlockNotifyPosition, BlockNotifySupportedBlock,
 };
@@ -14,26 +14,39 @@ pub fn break_unsupported_blocks(game: &mut Game, world: &mut World) {
     let mut actions = BumpVec::new_in(game.bump());
 
     actions.extend(
-        <(Read<BlockNotifyBlock>, Read<BlockNotifyPosition>)>::query()
-            .filter(component::<BlockNotifySupportedBlock>())
-            .iter_entities(world.inner())
-            .map(|(entity, (block, position))| {
-                let pos = if !is_block_supported_at(block.0, game, position.0) {
-                    Some(position.0) // Mark block for destruction
-                } else {
-                    None
-                };
+        <(
+            Read<BlockNotifyBlock>,
+            Read<BlockNotifyPosition>,
+            Read<WorldId>,
+        )>::query()
+        .filter(component::<BlockNotifySupportedBlock>())
+        .iter_entities(world.inner())
+        .map(|(entity, (block, position, world_id))| {
+            let supported = game.worlds.get(*world_id).map_or(true, |state| {
+                is_block_supported_at(block.0, state, position.0)
+            });
+            let pos = if !supported {
+                Some(position.0) // Mark block for destruction
+            } else {
+                None
+            };
 
-                (entity, pos)
-            }),
+            (entity, *world_id, pos)
+        }),
     );
 
-    for (entity, pos) in actions {
+    for (entity, world_id, pos) in actions {
         world.despawn(entity); // Despawn BlockNotify entity
 
         if let Some(pos) = pos {
             // Destroy block
-            game.set_block_at(world, pos, BlockId::air(), BlockUpdateCause::Unsupported);
+            game.set_block_at(
+                world,
+                world_id,
+                pos,
+                BlockId::air(),
+                BlockUpdateCause::Unsupported,
+            );
         }
     }
 }
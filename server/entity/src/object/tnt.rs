@@ -0,0 +1,196 @@
+//! Primed TNT, which falls like a block until its
+//! fuse runs out and it explodes.
+
+use crate::explode;
+use crate::explosion::BlockDamage;
+use feather_core::entitymeta::{EntityMetadata, META_INDEX_PRIMED_TNT_FUSE, PRIMED_TNT_LAYOUT};
+use feather_core::network::packets::{SoundCategory, SpawnObject};
+use feather_core::network::Packet;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{
+    EntitySpawnEvent, Game, NetworkId, PhysicsBuilder, Sound, SpawnPacketCreator, Uuid, Velocity,
+    WorldId,
+};
+use feather_server_util::{degrees_to_stops, protocol_velocity};
+use fecs::{Entity, EntityBuilder, EntityRef, IntoQuery, World, Write};
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Ticks before TNT lit by a player or redstone explodes.
+pub const FUSE: u32 = 80;
+
+/// Power of the explosion of TNT.
+const EXPLOSION_POWER: f32 = 4.0;
+
+/// Component for primed TNT.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PrimedTnt {
+    /// Ticks left before the TNT explodes.
+    pub fuse: u32,
+    /// The entity which lit the TNT, if any, who
+    /// is blamed for the damage the explosion deals.
+    pub igniter: Option<Entity>,
+}
+
+/// Returns the fuse of TNT lit by an explosion, which
+/// explodes sooner than TNT lit otherwise.
+pub fn chain_fuse(rng: &mut impl Rng) -> u32 {
+    rng.gen_range(0, FUSE / 4) + FUSE / 8
+}
+
+/// Returns an entity builder to create primed TNT with the
+/// given fuse. Its position and world still have to be added.
+pub fn create(fuse: u32, igniter: Option<Entity>) -> EntityBuilder {
+    let meta = EntityMetadata::entity_base()
+        .with_layout(PRIMED_TNT_LAYOUT)
+        .with(META_INDEX_PRIMED_TNT_FUSE, fuse as i32);
+
+    crate::base()
+        .with(PrimedTnt { fuse, igniter })
+        .with(meta)
+        .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(
+            PhysicsBuilder::new()
+                .bbox(0.98, 0.98, 0.98)
+                .drag(0.98)
+                .gravity(-0.04)
+                .build(),
+        )
+}
+
+/// Primes the TNT block at the given position, which
+/// has to be removed by the caller. The TNT jumps up
+/// and drifts a little in a random direction.
+pub fn prime(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    fuse: u32,
+    igniter: Option<Entity>,
+) -> Entity {
+    let angle = game.rng().gen_range(0.0, PI * 2.0);
+    let velocity = glm::vec3(-angle.sin() * 0.02, 0.2, -angle.cos() * 0.02);
+
+    let position = pos.position() + glm::vec3(0.5, 0.0, 0.5);
+    let entity = create(fuse, igniter)
+        .with(position)
+        .with(world_id)
+        .with(Velocity(velocity))
+        .build()
+        .spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+
+    let sound = Sound::new("entity.tnt.primed", SoundCategory::Blocks);
+    game.play_sound(world, world_id, position, &sound);
+    entity
+}
+
+/// System which burns the fuses of primed TNT
+/// and explodes those which run out.
+#[fecs::system]
+pub fn explode_primed_tnt(game: &mut Game, world: &mut World) {
+    let mut exploding = Vec::new();
+    for (entity, mut tnt) in <Write<PrimedTnt>>::query().iter_entities_mut(world.inner_mut()) {
+        tnt.fuse = tnt.fuse.saturating_sub(1);
+        if tnt.fuse == 0 {
+            exploding.push((entity, tnt.igniter));
+        }
+    }
+
+    for (entity, igniter) in exploding {
+        let center = *world.get::<Position>(entity) + glm::vec3(0.0, 0.0625, 0.0);
+        let world_id = game.world_of(world, entity);
+        game.despawn(entity, world);
+        explode(
+            game,
+            world,
+            world_id,
+            center,
+            EXPLOSION_POWER,
+            BlockDamage::Break,
+            igniter,
+        );
+    }
+}
+
+fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
+    let position = *accessor.get::<Position>();
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(accessor.get::<Velocity>().0);
+
+    let packet = SpawnObject {
+        entity_id: accessor.get::<NetworkId>().0,
+        object_uuid: Uuid::new_v4(),
+        ty: 50, // Type 50 for primed TNT
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+
+    Box::new(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_server_types::BlockUpdateCause;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn chain_fuse_is_shorter() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let fuse = chain_fuse(&mut rng);
+            assert!((10..30).contains(&fuse));
+        }
+    }
+
+    #[test]
+    fn primed_tnt_explodes() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos.down(),
+            BlockId::dirt(),
+            BlockUpdateCause::Unknown,
+        );
+
+        let tnt = prime(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            FUSE,
+            None,
+        );
+        assert_eq!(test.world.get::<PrimedTnt>(tnt).fuse, FUSE);
+        assert!(test.world.get::<Velocity>(tnt).0.y > 0.0);
+
+        for _ in 0..FUSE - 1 {
+            test.run(explode_primed_tnt);
+        }
+        test.assert_alive(tnt);
+        assert!(!test
+            .game
+            .block_at(WorldId::MAIN, pos.down())
+            .unwrap()
+            .is_air());
+
+        test.run(explode_primed_tnt);
+        test.assert_dead(tnt);
+        assert!(test
+            .game
+            .block_at(WorldId::MAIN, pos.down())
+            .unwrap()
+            .is_air());
+    }
+}
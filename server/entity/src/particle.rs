@@ -21,9 +21,9 @@ fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
 
     Box::new(packets::Particle {
         long_distance: false,
-        x: pos.x as f32,
-        y: pos.y as f32,
-        z: pos.z as f32,
+        x: pos.x,
+        y: pos.y,
+        z: pos.z,
         offset_x: 0.0, // TODO: offsets
         offset_y: 0.0,
         offset_z: 0.0,
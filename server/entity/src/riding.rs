@@ -0,0 +1,641 @@
+//! Mobs which players ride and steer: horses and pigs.
+//!
+//! Right clicking a mob with a saddle saddles it, and right clicking
+//! a saddled mob rides it. Horses have to be tamed before they accept
+//! a saddle: a player riding a wild horse is thrown off every so often,
+//! which makes the horse more likely to let them stay the next time.
+//! Pigs walk where their rider looks while the rider holds a carrot on
+//! a stick, which also gives them a short speed boost when used.
+//!
+//! Ridden mobs are moved by the server, from the input of the Steer
+//! Vehicle packets of their rider.
+//!
+//! See https://minecraft.gamepedia.com/Horse#Taming
+//! and https://minecraft.gamepedia.com/Pig#Riding.
+
+use crate::drops::drop_item;
+use crate::{consume_held_item, dismount, mount, InventoryExt, Leashed, Riding, Vehicle};
+use feather_core::entitymeta::Metadata;
+use feather_core::inventory::{slot, Area};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{EntityStatus, SoundCategory};
+use feather_core::util::{BlockPosition, Gamemode, Position};
+use feather_server_types::{
+    EntityDeathEvent, EntityInteractEvent, Game, HeldItem, Inventory, ItemDamageEvent, NetworkId,
+    Player, Sound, Uuid, Velocity, WorldId,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Horse metadata flag set once the horse is tamed.
+const HORSE_TAME: u8 = 0x02;
+/// Horse metadata flag set while the horse wears a saddle.
+const HORSE_SADDLED: u8 = 0x04;
+/// Temper at which a horse always accepts its rider.
+const MAX_TEMPER: u32 = 100;
+/// Temper gained by a horse each time it throws off its rider.
+const TEMPER_PER_ATTEMPT: u32 = 5;
+/// A wild horse tries to throw off its rider once
+/// every this many ticks on average.
+const TAMING_CHANCE: u32 = 50;
+/// Entity statuses showing hearts and smoke around a horse
+/// which was tamed or threw off its rider.
+const TAMED_STATUS: i8 = 7;
+const THROWN_OFF_STATUS: i8 = 6;
+/// Shortest and longest boost given by a carrot on a stick, in ticks.
+const MIN_BOOST: u32 = 140;
+const MAX_BOOST: u32 = 980;
+/// Speed added halfway through a boost, relative to the usual speed.
+const BOOST_SPEED: f64 = 1.15;
+/// Durability used up by each boost.
+const BOOST_DAMAGE: u32 = 7;
+/// Downward acceleration and drag of falling mobs.
+const GRAVITY: f64 = 0.08;
+const AIR_DRAG: f64 = 0.98;
+/// Speed at which mobs stop falling faster,
+/// so that they can't fall through blocks.
+const MAX_FALL_SPEED: f64 = 0.9;
+/// Horses walk backwards and sideways slower than forwards.
+const BACKWARD_FACTOR: f32 = 0.25;
+const SIDEWAYS_FACTOR: f32 = 0.5;
+
+/// Whether a mob wears a saddle.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Saddled(pub bool);
+
+/// Taming progress of a horse.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Taming {
+    pub tame: bool,
+    /// Chance out of 100 that the horse lets its rider
+    /// stay the next time it tries to throw them off.
+    pub temper: u32,
+    /// The player who tamed the horse.
+    pub owner: Option<Uuid>,
+}
+
+/// Component for mobs which players can saddle, ride and steer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ridable {
+    /// Blocks walked per tick at full forward input.
+    pub speed: f64,
+    /// Initial upward velocity of jumps, or 0 for
+    /// mobs which don't jump when steered.
+    pub jump_strength: f64,
+    /// Item the rider has to hold in their main hand to steer
+    /// the mob. Such mobs walk where their rider looks,
+    /// regardless of the rider's input.
+    pub steered_with: Option<Item>,
+    /// Sound played when the mob is saddled.
+    pub saddle_sound: &'static str,
+    /// Ticks into the current boost, and its length.
+    pub boost_time: u32,
+    pub boost_length: u32,
+}
+
+impl Ridable {
+    pub fn new(speed: f64, saddle_sound: &'static str) -> Self {
+        Self {
+            speed,
+            jump_strength: 0.0,
+            steered_with: None,
+            saddle_sound,
+            boost_time: 0,
+            boost_length: 0,
+        }
+    }
+
+    pub fn jump_strength(mut self, jump_strength: f64) -> Self {
+        self.jump_strength = jump_strength;
+        self
+    }
+
+    pub fn steered_with(mut self, item: Item) -> Self {
+        self.steered_with = Some(item);
+        self
+    }
+
+    /// Returns whether the mob is boosted.
+    pub fn is_boosted(&self) -> bool {
+        self.boost_time < self.boost_length
+    }
+
+    /// Returns the factor by which the speed of the mob
+    /// is boosted, which rises and falls over the boost.
+    fn boost_factor(&self) -> f64 {
+        if self.is_boosted() {
+            let progress = f64::from(self.boost_time) / f64::from(self.boost_length);
+            1.0 + BOOST_SPEED * (progress * PI).sin()
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Shows whether a mob is saddled, and whether
+/// it is tamed for horses, in its metadata.
+pub(crate) fn set_riding_metadata(meta: &mut Metadata, saddled: Saddled, taming: Taming) {
+    match meta {
+        Metadata::Pig(meta) => meta.set_has_saddle(saddled.0),
+        Metadata::Horse(meta) => {
+            let mut flags = meta.horse_bitmask() & !(HORSE_TAME | HORSE_SADDLED);
+            if taming.tame {
+                flags |= HORSE_TAME;
+            }
+            if saddled.0 {
+                flags |= HORSE_SADDLED;
+            }
+            meta.set_horse_bitmask(flags);
+            meta.set_owner(taming.owner);
+        }
+        _ => (),
+    }
+}
+
+fn update_metadata(world: &mut World, mob: Entity) {
+    let saddled = *world.get::<Saddled>(mob);
+    let taming = world
+        .try_get::<Taming>(mob)
+        .map(|taming| *taming)
+        .unwrap_or_default();
+    if let Some(mut meta) = world.try_get_mut::<Metadata>(mob) {
+        set_riding_metadata(&mut meta, saddled, taming);
+    }
+}
+
+fn broadcast_status(game: &Game, world: &World, mob: Entity, status: i8) {
+    let packet = EntityStatus {
+        entity_id: world.get::<NetworkId>(mob).0,
+        entity_status: status,
+    };
+    game.broadcast_entity_update(world, packet, mob, None);
+}
+
+fn is_wild(world: &World, mob: Entity) -> bool {
+    world
+        .try_get::<Taming>(mob)
+        .map_or(false, |taming| !taming.tame)
+}
+
+/// Returns whether a player riding a mob can steer it: the mob
+/// has to be saddled and tame, and the player has to hold the
+/// item the mob is steered with, if any.
+pub fn can_steer(world: &World, mob: Entity, rider: Entity) -> bool {
+    let steered_with = match world.try_get::<Ridable>(mob) {
+        Some(ridable) => ridable.steered_with,
+        None => return false,
+    };
+    if !world.has::<Player>(rider) || !world.get::<Saddled>(mob).0 || is_wild(world, mob) {
+        return false;
+    }
+    match steered_with {
+        Some(item) => {
+            let held = world
+                .get::<Inventory>(rider)
+                .item_in_main_hand(rider, world);
+            held.map_or(false, |held| held.ty == item)
+        }
+        None => true,
+    }
+}
+
+/// Saddles a mob with the stack a player holds. Wild
+/// horses and mobs which are already saddled refuse it.
+fn saddle(game: &mut Game, world: &mut World, player: Entity, mob: Entity, stack: ItemStack) {
+    if world.get::<Saddled>(mob).0 || is_wild(world, mob) {
+        return;
+    }
+    *world.get_mut::<Saddled>(mob) = Saddled(true);
+    update_metadata(world, mob);
+
+    let world_id = game.world_of(world, mob);
+    let pos = *world.get::<Position>(mob);
+    let name = world.get::<Ridable>(mob).saddle_sound;
+    let sound = Sound::new(name, SoundCategory::Neutral);
+    game.play_sound(world, world_id, pos, &sound);
+    consume_held_item(game, world, player, stack);
+}
+
+/// Tames a horse, making the given player its owner.
+pub fn tame(game: &Game, world: &mut World, horse: Entity, owner: Entity) {
+    let owner = *world.get::<Uuid>(owner);
+    {
+        let mut taming = world.get_mut::<Taming>(horse);
+        taming.tame = true;
+        taming.owner = Some(owner);
+    }
+    update_metadata(world, horse);
+    broadcast_status(game, world, horse, TAMED_STATUS);
+}
+
+/// Makes a wild horse try to throw off the player riding it. The
+/// higher its temper, the more likely the horse is to be tamed by
+/// the player instead. Otherwise, its temper rises.
+fn try_tame(game: &Game, world: &mut World, horse: Entity, rider: Entity) {
+    let temper = world.get::<Taming>(horse).temper;
+    if game.rng().gen_range(0, MAX_TEMPER) < temper {
+        tame(game, world, horse, rider);
+        return;
+    }
+
+    {
+        let mut taming = world.get_mut::<Taming>(horse);
+        taming.temper = (taming.temper + TEMPER_PER_ATTEMPT).min(MAX_TEMPER);
+    }
+    dismount(game, world, rider);
+    broadcast_status(game, world, horse, THROWN_OFF_STATUS);
+    let world_id = game.world_of(world, horse);
+    let pos = *world.get::<Position>(horse);
+    let sound = Sound::new("entity.horse.angry", SoundCategory::Neutral);
+    game.play_sound(world, world_id, pos, &sound);
+}
+
+/// Boosts the mob a player rides and steers with the item they hold,
+/// such as a pig steered with a carrot on a stick, using up some of
+/// the item's durability. Returns whether the mob was boosted, which
+/// it isn't if it already is.
+pub fn boost_steered_mob(game: &mut Game, world: &mut World, player: Entity) -> bool {
+    let mob = match world.try_get::<Riding>(player) {
+        Some(riding) => riding.0,
+        None => return false,
+    };
+    let steered_with_item = world
+        .try_get::<Ridable>(mob)
+        .map_or(false, |ridable| ridable.steered_with.is_some());
+    if !steered_with_item
+        || world.get::<Vehicle>(mob).driver() != Some(player)
+        || !can_steer(world, mob, player)
+        || world.get::<Ridable>(mob).is_boosted()
+    {
+        return false;
+    }
+
+    let length = game.rng().gen_range(MIN_BOOST, MAX_BOOST + 1);
+    {
+        let mut ridable = world.get_mut::<Ridable>(mob);
+        ridable.boost_time = 0;
+        ridable.boost_length = length;
+    }
+    if let Some(mut meta) = world.try_get_mut::<Metadata>(mob) {
+        if let Metadata::Pig(meta) = &mut *meta {
+            meta.set_boost_time(length as i32);
+        }
+    }
+
+    if *world.get::<Gamemode>(player) != Gamemode::Creative {
+        let held_item = world.get::<HeldItem>(player).0;
+        game.handle(
+            world,
+            ItemDamageEvent {
+                player,
+                slot: slot(Area::Hotbar, held_item),
+                damage_taken: BOOST_DAMAGE,
+            },
+        );
+    }
+    true
+}
+
+/// When a player right clicks a ridable mob with a saddle, saddles
+/// it. Otherwise, makes the player ride the mob if it is saddled,
+/// or if it is a wild horse, which the player then tries to tame.
+/// Players in spectator mode can't ride mobs.
+#[fecs::event_handler]
+pub fn on_entity_interact_ride_mob(
+    event: &EntityInteractEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let (player, mob) = (event.player, event.target);
+    if !world.has::<Ridable>(mob) || *world.get::<Gamemode>(player) == Gamemode::Spectator {
+        return;
+    }
+    // Leads and name tags are used on the mob instead.
+    let held_by_player = world
+        .try_get::<Leashed>(mob)
+        .map_or(false, |leashed| leashed.holder == player);
+    match event.stack {
+        Some(stack) if stack.ty == Item::Saddle => {
+            saddle(game, world, player, mob, stack);
+            return;
+        }
+        Some(stack) if stack.ty == Item::Lead || stack.ty == Item::NameTag => return,
+        _ if held_by_player => return,
+        _ => (),
+    }
+
+    if world.get::<Saddled>(mob).0 || is_wild(world, mob) {
+        mount(game, world, player, mob);
+    }
+}
+
+/// When a saddled mob dies, drops its saddle.
+#[fecs::event_handler]
+pub fn on_entity_death_drop_saddle(event: &EntityDeathEvent, game: &mut Game, world: &mut World) {
+    let saddled = world
+        .try_get::<Saddled>(event.entity)
+        .map_or(false, |saddled| saddled.0);
+    if !saddled {
+        return;
+    }
+    *world.get_mut::<Saddled>(event.entity) = Saddled(false);
+    let world_id = game.world_of(world, event.entity);
+    let pos = *world.get::<Position>(event.entity);
+    drop_item(game, world, world_id, ItemStack::new(Item::Saddle, 1), pos);
+}
+
+/// System which moves ridden mobs as their rider steers them,
+/// and makes wild horses try to throw off their rider.
+/// Ridable mobs in the air fall down.
+#[fecs::system]
+pub fn steer_ridden_mobs(game: &mut Game, world: &mut World) {
+    let mobs: Vec<Entity> = <Read<Ridable>>::query()
+        .iter_entities(world.inner())
+        .map(|(mob, _)| mob)
+        .collect();
+
+    for mob in mobs {
+        let ridable = {
+            let mut ridable = world.get_mut::<Ridable>(mob);
+            if ridable.is_boosted() {
+                ridable.boost_time += 1;
+            }
+            *ridable
+        };
+        let vehicle = world.get::<Vehicle>(mob).clone();
+        let rider = vehicle.driver();
+
+        if let Some(rider) = rider {
+            if is_wild(world, mob)
+                && world.has::<Player>(rider)
+                && game.rng().gen_range(0, TAMING_CHANCE) == 0
+            {
+                try_tame(game, world, mob, rider);
+                continue;
+            }
+        }
+
+        let mut pos = *world.get::<Position>(mob);
+        let mut velocity = world.get::<Velocity>(mob).0;
+        let rider = rider.filter(|&rider| can_steer(world, mob, rider));
+        let motion = match rider {
+            Some(rider) => {
+                pos.yaw = world.get::<Position>(rider).yaw;
+                let (forward, sideways) = if ridable.steered_with.is_some() {
+                    (1.0, 0.0)
+                } else if vehicle.forward < 0.0 {
+                    (
+                        vehicle.forward * BACKWARD_FACTOR,
+                        vehicle.sideways * SIDEWAYS_FACTOR,
+                    )
+                } else {
+                    (vehicle.forward, vehicle.sideways * SIDEWAYS_FACTOR)
+                };
+                if vehicle.jumping && pos.on_ground && ridable.jump_strength > 0.0 {
+                    velocity.y = ridable.jump_strength;
+                    pos.on_ground = false;
+                }
+                let speed = ridable.speed * ridable.boost_factor();
+                steering_motion(pos.yaw, forward, sideways) * speed
+            }
+            None if pos.on_ground => continue,
+            None => glm::vec3(0.0, 0.0, 0.0),
+        };
+
+        let world_id = game.world_of(world, mob);
+        let (pos, velocity) = walk(game, world_id, pos, velocity, motion, vehicle.height);
+        *world.get_mut::<Position>(mob) = pos;
+        world.get_mut::<Velocity>(mob).0 = velocity;
+    }
+}
+
+/// Returns the horizontal direction a mob facing `yaw` walks
+/// in with the given input. Positive `sideways` input
+/// steers to the left.
+fn steering_motion(yaw: f32, forward: f32, sideways: f32) -> glm::DVec3 {
+    let (sin, cos) = f64::from(yaw).to_radians().sin_cos();
+    let (forward, sideways) = (f64::from(forward), f64::from(sideways));
+    let motion = glm::vec3(
+        sideways * cos - forward * sin,
+        0.0,
+        forward * cos + sideways * sin,
+    );
+    if glm::length(&motion) > 1.0 {
+        glm::normalize(&motion)
+    } else {
+        motion
+    }
+}
+
+/// Returns the position and velocity of a mob `height` blocks
+/// tall after it walked along `motion` for a tick. Mobs on the
+/// ground step up blocks in their way.
+fn walk(
+    game: &Game,
+    world_id: WorldId,
+    pos: Position,
+    mut velocity: glm::DVec3,
+    motion: glm::DVec3,
+    height: f64,
+) -> (Position, glm::DVec3) {
+    let solid = |pos: BlockPosition| {
+        game.block_at(world_id, pos)
+            .map_or(true, |block| block.is_solid())
+    };
+    let fits = |pos: Position| {
+        let feet = pos.block();
+        let top = (pos.y + height).ceil() as i32;
+        (feet.y..top).all(|y| !solid(BlockPosition::new(feet.x, y, feet.z)))
+    };
+
+    let mut next = pos + motion;
+    if !fits(next) {
+        let mut step = next;
+        step.y = f64::from(next.block().y + 1);
+        if pos.on_ground && fits(step) {
+            next = step;
+        } else {
+            next.x = pos.x;
+            next.z = pos.z;
+        }
+    }
+    velocity.x = next.x - pos.x;
+    velocity.z = next.z - pos.z;
+
+    if !pos.on_ground {
+        velocity.y = ((velocity.y - GRAVITY) * AIR_DRAG).max(-MAX_FALL_SPEED);
+    }
+    let mut moved = next + glm::vec3(0.0, velocity.y, 0.0);
+    if velocity.y > 0.0 && !fits(moved) {
+        moved = next;
+        velocity.y = 0.0;
+    }
+
+    let below = (moved + glm::vec3(0.0, -0.01, 0.0)).block();
+    moved.on_ground = velocity.y <= 0.0 && solid(below);
+    if moved.on_ground {
+        moved.y = f64::from(below.y + 1);
+        velocity.y = 0.0;
+    }
+    (moved, velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{horse, pig};
+    use feather_core::blocks::BlockId;
+    use feather_server_types::BlockUpdateCause;
+    use feather_test_framework::Test;
+
+    fn fill(test: &mut Test, from: BlockPosition, to: BlockPosition, block: BlockId) {
+        for x in from.x..=to.x {
+            for y in from.y..=to.y {
+                for z in from.z..=to.z {
+                    test.game.set_block_at(
+                        &mut test.world,
+                        WorldId::MAIN,
+                        BlockPosition::new(x, y, z),
+                        block,
+                        BlockUpdateCause::Unknown,
+                    );
+                }
+            }
+        }
+    }
+
+    fn floor(test: &mut Test) {
+        fill(
+            test,
+            BlockPosition::new(-5, 63, -5),
+            BlockPosition::new(5, 63, 10),
+            BlockId::stone(),
+        );
+    }
+
+    fn interact(test: &mut Test, player: Entity, mob: Entity, stack: Option<ItemStack>) {
+        let event = EntityInteractEvent {
+            player,
+            target: mob,
+            stack,
+        };
+        test.handle(event, on_entity_interact_ride_mob);
+    }
+
+    fn hold(test: &mut Test, player: Entity, item: Item) {
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, ItemStack::new(item, 1))
+            .unwrap();
+    }
+
+    #[test]
+    fn saddle_and_steer_pig() {
+        let mut test = Test::new();
+        floor(&mut test);
+        let player = test.player("", position!(0.5, 64.0, 0.5));
+        let pig = test.entity(pig::create().with(position!(0.5, 64.0, 0.5)));
+        let saddle = Some(ItemStack::new(Item::Saddle, 1));
+
+        interact(&mut test, player, pig, None);
+        assert!(!test.world.has::<Riding>(player));
+
+        interact(&mut test, player, pig, saddle);
+        assert!(test.world.get::<Saddled>(pig).0);
+        match &*test.world.get::<Metadata>(pig) {
+            Metadata::Pig(meta) => assert!(meta.has_saddle()),
+            _ => panic!("pig without pig metadata"),
+        }
+
+        interact(&mut test, player, pig, None);
+        assert_eq!(test.world.get::<Riding>(player).0, pig);
+
+        // Pigs only follow a carrot on a stick.
+        test.run(steer_ridden_mobs);
+        assert_eq!(test.world.get::<Position>(pig).z, 0.5);
+
+        hold(&mut test, player, Item::CarrotOnAStick);
+        test.run(steer_ridden_mobs);
+        let pos = *test.world.get::<Position>(pig);
+        assert!(pos.z > 0.5);
+        assert!((pos.x - 0.5).abs() < 1e-9);
+        assert_eq!(pos.y, 64.0);
+    }
+
+    #[test]
+    fn boost_pig_once_at_a_time() {
+        let mut test = Test::new();
+        floor(&mut test);
+        let player = test.player("", position!(0.5, 64.0, 0.5));
+        let pig =
+            test.entity(pig::create_with_saddle(Saddled(true)).with(position!(0.5, 64.0, 0.5)));
+        interact(&mut test, player, pig, None);
+
+        assert!(!boost_steered_mob(&mut test.game, &mut test.world, player));
+        hold(&mut test, player, Item::CarrotOnAStick);
+        assert!(boost_steered_mob(&mut test.game, &mut test.world, player));
+        assert!(test.world.get::<Ridable>(pig).is_boosted());
+        assert!(!boost_steered_mob(&mut test.game, &mut test.world, player));
+    }
+
+    #[test]
+    fn tame_horse() {
+        let mut test = Test::new();
+        floor(&mut test);
+        let player = test.player("", position!(0.5, 64.0, 0.5));
+        let horse = test.entity(horse::create().with(position!(0.5, 64.0, 0.5)));
+        let saddle = Some(ItemStack::new(Item::Saddle, 1));
+
+        // Wild horses refuse saddles, but can be ridden.
+        interact(&mut test, player, horse, saddle);
+        assert!(!test.world.get::<Saddled>(horse).0);
+        interact(&mut test, player, horse, None);
+        assert!(test.world.has::<Riding>(player));
+
+        try_tame(&test.game, &mut test.world, horse, player);
+        assert!(!test.world.has::<Riding>(player));
+        assert_eq!(test.world.get::<Taming>(horse).temper, TEMPER_PER_ATTEMPT);
+
+        test.world.get_mut::<Taming>(horse).temper = MAX_TEMPER;
+        interact(&mut test, player, horse, None);
+        try_tame(&test.game, &mut test.world, horse, player);
+        assert!(test.world.has::<Riding>(player));
+        let taming = *test.world.get::<Taming>(horse);
+        assert!(taming.tame);
+        assert_eq!(taming.owner, Some(test.uuid(player)));
+
+        interact(&mut test, player, horse, saddle);
+        assert!(test.world.get::<Saddled>(horse).0);
+    }
+
+    #[test]
+    fn steer_horse_up_steps() {
+        let mut test = Test::new();
+        floor(&mut test);
+        fill(
+            &mut test,
+            BlockPosition::new(0, 64, 3),
+            BlockPosition::new(0, 64, 10),
+            BlockId::stone(),
+        );
+        let player = test.player("", position!(0.5, 64.0, 0.5));
+        let taming = Taming {
+            tame: true,
+            temper: 0,
+            owner: Some(test.uuid(player)),
+        };
+        let horse =
+            test.entity(horse::create_with(taming, Saddled(true)).with(position!(0.5, 64.0, 0.5)));
+        interact(&mut test, player, horse, None);
+
+        test.world.get_mut::<Vehicle>(horse).forward = 1.0;
+        for _ in 0..10 {
+            test.run(steer_ridden_mobs);
+        }
+        let pos = *test.world.get::<Position>(horse);
+        assert!(pos.z > 3.0);
+        assert_eq!(pos.y, 65.0);
+        assert!(pos.on_ground);
+    }
+}
@@ -0,0 +1,666 @@
+//! Natural mob spawning and despawning.
+//!
+//! Each tick, mobs try to spawn in packs in the chunks around
+//! players, as long as there are fewer mobs of their category
+//! in the world than its cap, which grows with the number of
+//! chunks around players. Which mobs spawn depends on the biome,
+//! and each kind of mob has its own rules on where it can spawn,
+//! e.g. monsters need darkness and animals need grass.
+//!
+//! Monsters, bats and water creatures despawn once they are far
+//! from any player. Animals never despawn.
+//!
+//! See https://minecraft.gamepedia.com/Spawn.
+
+use crate::{MobCategory, MobKind};
+use feather_core::biomes::Biome;
+use feather_core::blocks::{BlockId, BlockKind};
+use feather_core::util::{vec3, BlockPosition, ChunkPosition, Dimension, Gamemode, Position};
+use feather_server_types::{CanDespawn, EntitySpawnEvent, Game, Player, WorldId, WorldState};
+use fecs::{Entity, IntoQuery, Read, World};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Radius, in chunks, of the area around
+/// each player in which mobs can spawn.
+const SPAWN_CHUNK_RADIUS: i32 = 8;
+/// Number of chunks around a single player. Mob caps
+/// are given for this many chunks.
+const CHUNKS_PER_PLAYER: usize = 289;
+/// Animals only try to spawn this often.
+const CREATURE_SPAWN_INTERVAL: u64 = 400;
+/// Number of packs which try to spawn in each chunk.
+const PACKS_PER_CHUNK: usize = 3;
+/// Number of positions tried for the mobs of each pack.
+const PACK_ATTEMPTS: usize = 4;
+/// Greatest distance mobs in a pack spread from each other.
+const PACK_SPREAD: i32 = 6;
+/// Mobs don't spawn this close to players or the world spawn.
+const MIN_PLAYER_DISTANCE: f64 = 24.0;
+/// Mobs further from players than this despawn right away.
+const DESPAWN_DISTANCE: f64 = 128.0;
+/// Mobs further from players than this randomly despawn.
+const RANDOM_DESPAWN_DISTANCE: f64 = 32.0;
+/// Chance each tick for a mob far from players to despawn.
+const RANDOM_DESPAWN_CHANCE: u32 = 800;
+/// The Y coordinate of the sea in the overworld.
+const SEA_LEVEL: i32 = 63;
+
+/// An entry in the list of mobs which spawn in a biome.
+#[derive(Copy, Clone, Debug)]
+struct SpawnEntry {
+    kind: MobKind,
+    weight: u32,
+    min_pack: u32,
+    max_pack: u32,
+}
+
+const fn entry(kind: MobKind, weight: u32, min_pack: u32, max_pack: u32) -> SpawnEntry {
+    SpawnEntry {
+        kind,
+        weight,
+        min_pack,
+        max_pack,
+    }
+}
+
+const MONSTERS: &[SpawnEntry] = &[
+    entry(MobKind::Spider, 100, 4, 4),
+    entry(MobKind::Zombie, 95, 4, 4),
+    entry(MobKind::ZombieVillager, 5, 1, 1),
+    entry(MobKind::Skeleton, 100, 4, 4),
+    entry(MobKind::Creeper, 100, 4, 4),
+    entry(MobKind::Slime, 100, 4, 4),
+    entry(MobKind::Enderman, 10, 1, 4),
+    entry(MobKind::Witch, 5, 1, 1),
+];
+const DESERT_MONSTERS: &[SpawnEntry] = &[
+    entry(MobKind::Spider, 100, 4, 4),
+    entry(MobKind::Zombie, 19, 4, 4),
+    entry(MobKind::ZombieVillager, 1, 1, 1),
+    entry(MobKind::Husk, 80, 4, 4),
+    entry(MobKind::Skeleton, 100, 4, 4),
+    entry(MobKind::Creeper, 100, 4, 4),
+    entry(MobKind::Slime, 100, 4, 4),
+    entry(MobKind::Enderman, 10, 1, 4),
+    entry(MobKind::Witch, 5, 1, 1),
+];
+const SNOWY_MONSTERS: &[SpawnEntry] = &[
+    entry(MobKind::Spider, 100, 4, 4),
+    entry(MobKind::Zombie, 95, 4, 4),
+    entry(MobKind::ZombieVillager, 5, 1, 1),
+    entry(MobKind::Skeleton, 20, 4, 4),
+    entry(MobKind::Stray, 80, 4, 4),
+    entry(MobKind::Creeper, 100, 4, 4),
+    entry(MobKind::Slime, 100, 4, 4),
+    entry(MobKind::Enderman, 10, 1, 4),
+    entry(MobKind::Witch, 5, 1, 1),
+];
+const NETHER_MONSTERS: &[SpawnEntry] = &[
+    entry(MobKind::Ghast, 50, 4, 4),
+    entry(MobKind::PigZombie, 100, 4, 4),
+    entry(MobKind::MagmaCube, 2, 4, 4),
+];
+const END_MONSTERS: &[SpawnEntry] = &[entry(MobKind::Enderman, 10, 4, 4)];
+
+const CREATURES: &[SpawnEntry] = &[
+    entry(MobKind::Sheep, 12, 4, 4),
+    entry(MobKind::Pig, 10, 4, 4),
+    entry(MobKind::Chicken, 10, 4, 4),
+    entry(MobKind::Cow, 8, 4, 4),
+];
+const PLAINS_CREATURES: &[SpawnEntry] = &[
+    entry(MobKind::Sheep, 12, 4, 4),
+    entry(MobKind::Pig, 10, 4, 4),
+    entry(MobKind::Chicken, 10, 4, 4),
+    entry(MobKind::Cow, 8, 4, 4),
+    entry(MobKind::Horse, 5, 2, 6),
+    entry(MobKind::Donkey, 1, 1, 3),
+];
+const MUSHROOM_CREATURES: &[SpawnEntry] = &[entry(MobKind::MushroomCow, 8, 4, 8)];
+
+const AMBIENT: &[SpawnEntry] = &[entry(MobKind::Bat, 10, 8, 8)];
+
+const OCEAN_WATER_CREATURES: &[SpawnEntry] = &[
+    entry(MobKind::Squid, 1, 1, 4),
+    entry(MobKind::Cod, 10, 3, 6),
+];
+const RIVER_WATER_CREATURES: &[SpawnEntry] = &[
+    entry(MobKind::Squid, 2, 1, 4),
+    entry(MobKind::Salmon, 5, 1, 5),
+];
+
+/// Returns the mobs of a category which spawn in a biome.
+fn spawn_entries(biome: Biome, category: MobCategory) -> &'static [SpawnEntry] {
+    match (biome, category) {
+        (Biome::Nether, MobCategory::Monster) => NETHER_MONSTERS,
+        (Biome::Nether, _) => &[],
+        (Biome::TheEnd, MobCategory::Monster)
+        | (Biome::SmallEndIslands, MobCategory::Monster)
+        | (Biome::EndMidlands, MobCategory::Monster)
+        | (Biome::EndHighlands, MobCategory::Monster)
+        | (Biome::EndBarrens, MobCategory::Monster) => END_MONSTERS,
+        (Biome::TheEnd, _)
+        | (Biome::SmallEndIslands, _)
+        | (Biome::EndMidlands, _)
+        | (Biome::EndHighlands, _)
+        | (Biome::EndBarrens, _)
+        | (Biome::TheVoid, _) => &[],
+        (Biome::MushroomFields, MobCategory::Creature)
+        | (Biome::MushroomFieldShore, MobCategory::Creature) => MUSHROOM_CREATURES,
+        (Biome::MushroomFields, MobCategory::Monster)
+        | (Biome::MushroomFieldShore, MobCategory::Monster) => &[],
+        (Biome::Desert, MobCategory::Monster)
+        | (Biome::DesertHills, MobCategory::Monster)
+        | (Biome::DesertLakes, MobCategory::Monster) => DESERT_MONSTERS,
+        (Biome::SnowyTundra, MobCategory::Monster)
+        | (Biome::IceSpikes, MobCategory::Monster)
+        | (Biome::SnowyMountains, MobCategory::Monster) => SNOWY_MONSTERS,
+        (_, MobCategory::Monster) => MONSTERS,
+        (Biome::Desert, MobCategory::Creature)
+        | (Biome::DesertHills, MobCategory::Creature)
+        | (Biome::DesertLakes, MobCategory::Creature) => &[],
+        (Biome::Plains, MobCategory::Creature)
+        | (Biome::SunflowerPlains, MobCategory::Creature) => PLAINS_CREATURES,
+        (biome, MobCategory::Creature) if biome.is_ocean() => &[],
+        (_, MobCategory::Creature) => CREATURES,
+        (_, MobCategory::Ambient) => AMBIENT,
+        (biome, MobCategory::WaterCreature) if biome.is_ocean() => OCEAN_WATER_CREATURES,
+        (Biome::River, MobCategory::WaterCreature)
+        | (Biome::FrozenRiver, MobCategory::WaterCreature) => RIVER_WATER_CREATURES,
+        (_, MobCategory::WaterCreature) => &[],
+    }
+}
+
+/// Picks a random entry, weighted by their weights.
+fn pick_entry(entries: &[SpawnEntry], rng: &mut impl Rng) -> Option<SpawnEntry> {
+    let total: u32 = entries.iter().map(|entry| entry.weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut n = rng.gen_range(0, total);
+    for entry in entries {
+        if n < entry.weight {
+            return Some(*entry);
+        }
+        n -= entry.weight;
+    }
+    None
+}
+
+/// Returns whether the difficulty is peaceful, so that no monsters spawn.
+fn is_peaceful(game: &Game) -> bool {
+    game.level.difficulty == 0
+}
+
+/// Returns the light level at a block, with the
+/// sky light reduced by the time of day.
+fn light_at(game: &Game, state: &WorldState, pos: BlockPosition) -> u8 {
+    let chunk = match state.chunk_map.chunk_at(pos.chunk()) {
+        Some(chunk) => chunk,
+        None => return 0,
+    };
+    match chunk.local_pos(pos) {
+        Some((x, y, z)) => {
+            let sky = chunk
+                .sky_light_at(x, y, z)
+                .saturating_sub(game.sky_darkness(state.id));
+            sky.max(chunk.block_light_at(x, y, z))
+        }
+        // Above the world, there is only sky.
+        None if pos.y >= chunk.max_y() => 15u8.saturating_sub(game.sky_darkness(state.id)),
+        None => 0,
+    }
+}
+
+fn sky_light_at(state: &WorldState, pos: BlockPosition) -> u8 {
+    state
+        .chunk_map
+        .chunk_at(pos.chunk())
+        .and_then(|chunk| {
+            let (x, y, z) = chunk.local_pos(pos)?;
+            Some(chunk.sky_light_at(x, y, z))
+        })
+        .unwrap_or(0)
+}
+
+/// Returns whether it's dark enough at a block for monsters to spawn.
+fn is_dark_enough(game: &Game, state: &WorldState, pos: BlockPosition, rng: &mut impl Rng) -> bool {
+    if sky_light_at(state, pos) > rng.gen_range(0, 32) {
+        return false;
+    }
+    light_at(game, state, pos) <= rng.gen_range(0, 8)
+}
+
+/// Returns whether slimes spawn underground in a chunk.
+/// This matches the chunks chosen by the vanilla server,
+/// which are derived from the world seed.
+fn is_slime_chunk(seed: i64, chunk: ChunkPosition) -> bool {
+    let (x, z) = (chunk.x, chunk.z);
+    let seed = seed
+        .wrapping_add(i64::from(x.wrapping_mul(x).wrapping_mul(0x4c_1906)))
+        .wrapping_add(i64::from(x.wrapping_mul(0x5a_c0db)))
+        .wrapping_add(i64::from(z.wrapping_mul(z)).wrapping_mul(0x43_07a7))
+        .wrapping_add(i64::from(z.wrapping_mul(0x5f_24f)))
+        ^ 0x3ad8_025f;
+    java_random_int(seed, 10) == 0
+}
+
+/// Returns the first `nextInt(bound)` of a
+/// `java.util.Random` with the given seed.
+fn java_random_int(seed: i64, bound: i32) -> i32 {
+    const MULTIPLIER: i64 = 0x5_deec_e66d;
+    const MASK: i64 = (1 << 48) - 1;
+    let mut seed = (seed ^ MULTIPLIER) & MASK;
+    loop {
+        seed = (seed.wrapping_mul(MULTIPLIER).wrapping_add(0xb)) & MASK;
+        let bits = (seed >> 17) as i32;
+        let value = bits % bound;
+        if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+            return value;
+        }
+    }
+}
+
+/// Returns whether a block is free for a mob to stand in.
+fn is_free(block: BlockId) -> bool {
+    !block.is_solid() && !block.is_fluid() && block.kind() != BlockKind::PoweredRail
+}
+
+/// Returns whether a mob of the given kind can spawn with its
+/// feet in the given block. This checks the blocks around it,
+/// the light level and the rules of the kind of mob.
+pub fn is_valid_spawn(
+    game: &Game,
+    state: &WorldState,
+    kind: MobKind,
+    pos: BlockPosition,
+    rng: &mut impl Rng,
+) -> bool {
+    let (below, feet, head) = match (
+        state.block_at(pos.down()),
+        state.block_at(pos),
+        state.block_at(pos.up()),
+    ) {
+        (Some(below), Some(feet), Some(head)) => (below, feet, head),
+        _ => return false,
+    };
+
+    let category = kind.category();
+    if category == Some(MobCategory::WaterCreature) {
+        let in_water = feet.kind() == BlockKind::Water && below.kind() == BlockKind::Water;
+        if !in_water || head.is_solid() {
+            return false;
+        }
+    } else {
+        let can_stand = below.is_solid()
+            && below.kind() != BlockKind::Bedrock
+            && below.kind() != BlockKind::Barrier;
+        if !can_stand || !is_free(feet) || !is_free(head) {
+            return false;
+        }
+    }
+
+    if category == Some(MobCategory::Monster) && is_peaceful(game) {
+        return false;
+    }
+
+    match kind {
+        MobKind::Slime => {
+            let biome = biome_at(state, pos);
+            if matches!(biome, Some(Biome::Swamp) | Some(Biome::SwampHills))
+                && pos.y > 50
+                && pos.y < 70
+                && rng.gen_bool(0.5)
+                && light_at(game, state, pos) <= rng.gen_range(0, 8)
+            {
+                return true;
+            }
+            is_slime_chunk(game.level.seed, pos.chunk()) && rng.gen_range(0, 10) == 0 && pos.y < 40
+        }
+        MobKind::Ghast => rng.gen_range(0, 20) == 0,
+        MobKind::PigZombie | MobKind::MagmaCube => below.kind() != BlockKind::NetherWartBlock,
+        MobKind::MushroomCow => {
+            below.kind() == BlockKind::Mycelium && light_at(game, state, pos) > 8
+        }
+        MobKind::Bat => pos.y < SEA_LEVEL && light_at(game, state, pos) <= rng.gen_range(0, 4),
+        MobKind::Squid => pos.y > 45 && pos.y < SEA_LEVEL,
+        _ => match category {
+            Some(MobCategory::Monster) => is_dark_enough(game, state, pos, rng),
+            Some(MobCategory::Creature) => {
+                below.kind() == BlockKind::GrassBlock && light_at(game, state, pos) > 8
+            }
+            _ => true,
+        },
+    }
+}
+
+fn biome_at(state: &WorldState, pos: BlockPosition) -> Option<Biome> {
+    let chunk = state.chunk_map.chunk_at(pos.chunk())?;
+    Some(chunk.biome_at(pos.x as usize & 0xf, pos.z as usize & 0xf))
+}
+
+/// Spawns a mob into a world. Mobs other than
+/// animals despawn when no player is nearby.
+pub fn spawn_mob(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    kind: MobKind,
+    pos: Position,
+) -> Entity {
+    let mut builder = kind.create().with(pos).with(world_id);
+    if kind
+        .category()
+        .map_or(false, |category| category != MobCategory::Creature)
+    {
+        builder = builder.with(CanDespawn);
+    }
+    let entity = builder.build().spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+    entity
+}
+
+/// Returns the positions of the players in a world who
+/// count for spawning, i.e. those who aren't spectating.
+fn spawning_players(world: &World, world_id: WorldId) -> Vec<Position> {
+    let mut players = Vec::new();
+    for (_, pos, id, gamemode) in
+        <(Read<Player>, Read<Position>, Read<WorldId>, Read<Gamemode>)>::query().iter(world.inner())
+    {
+        if *id == world_id && *gamemode != Gamemode::Spectator {
+            players.push(*pos);
+        }
+    }
+    players
+}
+
+fn nearest_distance_squared(players: &[Position], pos: Position) -> Option<f64> {
+    players
+        .iter()
+        .map(|player| player.distance_squared_to(pos))
+        .min_by(|a, b| a.partial_cmp(b).unwrap())
+}
+
+/// System which spawns mobs in the chunks around players.
+#[fecs::system]
+pub fn spawn_mobs(game: &mut Game, world: &mut World) {
+    if !game.game_rules.do_mob_spawning {
+        return;
+    }
+
+    let mut counts: HashMap<(WorldId, MobCategory), usize> = HashMap::new();
+    for (category, world_id) in <(Read<MobCategory>, Read<WorldId>)>::query().iter(world.inner()) {
+        *counts.entry((*world_id, *category)).or_default() += 1;
+    }
+
+    let world_ids: Vec<WorldId> = game.worlds.iter().map(|state| state.id).collect();
+    for world_id in world_ids {
+        let players = spawning_players(world, world_id);
+        if players.is_empty() {
+            continue;
+        }
+
+        let mut chunks = HashSet::new();
+        for player in &players {
+            let center = player.chunk();
+            for x in -SPAWN_CHUNK_RADIUS..=SPAWN_CHUNK_RADIUS {
+                for z in -SPAWN_CHUNK_RADIUS..=SPAWN_CHUNK_RADIUS {
+                    chunks.insert(ChunkPosition::new(center.x + x, center.z + z));
+                }
+            }
+        }
+        let chunks: Vec<ChunkPosition> = chunks.into_iter().collect();
+
+        for &category in &MobCategory::ALL {
+            if category == MobCategory::Creature && game.tick_count % CREATURE_SPAWN_INTERVAL != 0 {
+                continue;
+            }
+            if category == MobCategory::Monster && is_peaceful(game) {
+                continue;
+            }
+
+            let cap = category.max_count() * chunks.len() / CHUNKS_PER_PLAYER;
+            let mut count = counts.get(&(world_id, category)).copied().unwrap_or(0);
+            for &chunk in &chunks {
+                if count >= cap {
+                    break;
+                }
+                count += spawn_packs(game, world, world_id, chunk, category, &players);
+            }
+        }
+    }
+}
+
+/// Tries to spawn packs of mobs in a chunk, returning
+/// the number of mobs spawned.
+fn spawn_packs(
+    game: &mut Game,
+    world: &mut World,
+    world_id: WorldId,
+    chunk: ChunkPosition,
+    category: MobCategory,
+    players: &[Position],
+) -> usize {
+    let state = &game.worlds[world_id];
+    let start = {
+        let chunk_data = match state.chunk_map.chunk_at(chunk) {
+            Some(chunk) => chunk,
+            None => return 0,
+        };
+        let mut rng = game.rng();
+        let (x, z) = (rng.gen_range(0, 16), rng.gen_range(0, 16));
+        let top = chunk_data.min_y() + i32::from(chunk_data.heightmap(x, z).motion_blocking());
+        let y = rng.gen_range(chunk_data.min_y(), top + 1);
+        BlockPosition::new(chunk.x * 16 + x as i32, y, chunk.z * 16 + z as i32)
+    };
+    if state.block_at(start).map_or(true, BlockId::is_full_block) {
+        return 0;
+    }
+
+    let world_spawn = game.world_spawn();
+    let mut spawned = Vec::new();
+    for _ in 0..PACKS_PER_CHUNK {
+        let mut pos = start;
+        let mut pack: Option<(SpawnEntry, u32)> = None;
+        let mut pack_spawned = 0;
+        for _ in 0..PACK_ATTEMPTS {
+            let mut rng = game.rng();
+            pos.x += rng.gen_range(0, PACK_SPREAD) - rng.gen_range(0, PACK_SPREAD);
+            pos.z += rng.gen_range(0, PACK_SPREAD) - rng.gen_range(0, PACK_SPREAD);
+            let center = pos.position() + vec3(0.5, 0.0, 0.5);
+
+            let too_close = |other: Position| {
+                center.distance_squared_to(other) < MIN_PLAYER_DISTANCE * MIN_PLAYER_DISTANCE
+            };
+            if players.iter().copied().any(too_close)
+                || (state.dimension == Dimension::Overwold && too_close(world_spawn))
+            {
+                continue;
+            }
+
+            let (entry, size) = match pack {
+                Some(pack) => pack,
+                None => {
+                    let biome = match biome_at(state, pos) {
+                        Some(biome) => biome,
+                        None => break,
+                    };
+                    let entry = match pick_entry(spawn_entries(biome, category), &mut *rng) {
+                        Some(entry) => entry,
+                        None => break,
+                    };
+                    let size = rng.gen_range(entry.min_pack, entry.max_pack + 1);
+                    pack = Some((entry, size));
+                    (entry, size)
+                }
+            };
+
+            if !is_valid_spawn(game, state, entry.kind, pos, &mut *rng) {
+                continue;
+            }
+            let yaw = rng.gen_range(0.0, 360.0);
+            spawned.push((entry.kind, Position { yaw, ..center }));
+            pack_spawned += 1;
+            if pack_spawned >= size {
+                break;
+            }
+        }
+    }
+
+    let count = spawned.len();
+    for (kind, pos) in spawned {
+        spawn_mob(game, world, world_id, kind, pos);
+    }
+    count
+}
+
+/// System which despawns mobs far from players.
+#[fecs::system]
+pub fn despawn_mobs(game: &mut Game, world: &mut World) {
+    let mut players: HashMap<WorldId, Vec<Position>> = HashMap::new();
+    let mut despawned = Vec::new();
+    for (entity, (_, pos, world_id)) in
+        <(Read<CanDespawn>, Read<Position>, Read<WorldId>)>::query().iter_entities(world.inner())
+    {
+        let players = players
+            .entry(*world_id)
+            .or_insert_with(|| spawning_players(world, *world_id));
+
+        let monster = world
+            .try_get::<MobCategory>(entity)
+            .map(|category| *category)
+            == Some(MobCategory::Monster);
+        let far_away = match nearest_distance_squared(players, *pos) {
+            Some(distance) if distance <= DESPAWN_DISTANCE * DESPAWN_DISTANCE => {
+                distance > RANDOM_DESPAWN_DISTANCE * RANDOM_DESPAWN_DISTANCE
+                    && game.rng().gen_range(0, RANDOM_DESPAWN_CHANCE) == 0
+            }
+            _ => true,
+        };
+        if far_away || (monster && is_peaceful(game)) {
+            despawned.push(entity);
+        }
+    }
+
+    for entity in despawned {
+        game.despawn(entity, world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+    use rand::rngs::mock::StepRng;
+
+    fn set_light(test: &mut Test, pos: BlockPosition, sky: u8, block: u8) {
+        let mut chunk = test.game.worlds[WorldId::MAIN]
+            .chunk_map
+            .chunk_at_mut(pos.chunk())
+            .unwrap();
+        let (x, y, z) = chunk.local_pos(pos).unwrap();
+        chunk.set_sky_light_at(x, y, z, sky);
+        chunk.set_block_light_at(x, y, z, block);
+    }
+
+    fn is_valid(test: &Test, kind: MobKind, pos: BlockPosition) -> bool {
+        // Always rolls the lowest value, so that monsters
+        // only spawn in complete darkness.
+        let mut rng = StepRng::new(0, 0);
+        is_valid_spawn(
+            &test.game,
+            &test.game.worlds[WorldId::MAIN],
+            kind,
+            pos,
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn monsters_spawn_in_darkness() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(pos.down(), BlockId::stone());
+        set_light(&mut test, pos, 0, 0);
+        assert!(is_valid(&test, MobKind::Zombie, pos));
+
+        set_light(&mut test, pos, 0, 14);
+        assert!(!is_valid(&test, MobKind::Zombie, pos));
+
+        test.game.level.difficulty = 0;
+        set_light(&mut test, pos, 0, 0);
+        assert!(!is_valid(&test, MobKind::Zombie, pos));
+    }
+
+    #[test]
+    fn mobs_need_room_and_ground() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        assert!(!is_valid(&test, MobKind::Zombie, pos));
+
+        test.set_block(pos.down(), BlockId::bedrock());
+        assert!(!is_valid(&test, MobKind::Zombie, pos));
+
+        test.set_block(pos.down(), BlockId::stone());
+        test.set_block(pos.up(), BlockId::stone());
+        assert!(!is_valid(&test, MobKind::Zombie, pos));
+    }
+
+    #[test]
+    fn animals_spawn_on_grass_in_light() {
+        let mut test = Test::new();
+        test.game.worlds[WorldId::MAIN].time.day_time = 6000;
+        let pos = BlockPosition::new(0, 64, 0);
+        test.set_block(pos.down(), BlockId::stone());
+        set_light(&mut test, pos, 15, 0);
+        assert!(!is_valid(&test, MobKind::Cow, pos));
+
+        test.set_block(pos.down(), BlockId::grass_block());
+        assert!(is_valid(&test, MobKind::Cow, pos));
+
+        set_light(&mut test, pos, 0, 0);
+        assert!(!is_valid(&test, MobKind::Cow, pos));
+    }
+
+    #[test]
+    fn one_in_ten_chunks_are_slime_chunks() {
+        let slime_chunks = (-50..50)
+            .flat_map(|x| (-50..50).map(move |z| ChunkPosition::new(x, z)))
+            .filter(|chunk| is_slime_chunk(0, *chunk))
+            .count();
+        assert!(slime_chunks > 900 && slime_chunks < 1100);
+    }
+
+    #[test]
+    fn monsters_far_from_players_despawn() {
+        let mut test = Test::new();
+        test.player("player", position!(0.5, 64.0, 0.5));
+        let near = spawn_mob(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            MobKind::Zombie,
+            position!(10.5, 64.0, 0.5),
+        );
+        let far = spawn_mob(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            MobKind::Zombie,
+            position!(200.5, 64.0, 0.5),
+        );
+        let cow = spawn_mob(
+            &mut test.game,
+            &mut test.world,
+            WorldId::MAIN,
+            MobKind::Cow,
+            position!(200.5, 64.0, 0.5),
+        );
+
+        test.run(despawn_mobs);
+        assert!(test.world.is_alive(near));
+        assert!(!test.world.is_alive(far));
+        assert!(test.world.is_alive(cow));
+    }
+}
@@ -0,0 +1,147 @@
+//! Counting down the status effects of entities,
+//! which wear off once their duration runs out.
+
+use feather_server_types::{Dead, EntityDeathEvent, Game, StatusEffectKind, StatusEffects};
+use fecs::{component, IntoQuery, World, Write};
+
+/// Returns the number of ticks between each
+/// health point healed by Regeneration.
+fn regeneration_interval(amplifier: u8) -> u32 {
+    (50 >> amplifier.min(5)).max(1)
+}
+
+/// System which counts down the status effects of entities,
+/// healing those with Regeneration, and removes the effects
+/// which wore off.
+#[fecs::system]
+pub fn tick_status_effects(game: &mut Game, world: &mut World) {
+    let mut healed = vec![];
+    let mut expired = vec![];
+    for (entity, mut effects) in <Write<StatusEffects>>::query()
+        .filter(!component::<Dead>())
+        .iter_entities_mut(world.inner_mut())
+    {
+        for effect in &mut effects.0 {
+            if effect.kind == StatusEffectKind::Regeneration
+                && effect.duration % regeneration_interval(effect.amplifier) == 0
+            {
+                healed.push(entity);
+            }
+
+            effect.duration = effect.duration.saturating_sub(1);
+            if effect.duration == 0 {
+                expired.push((entity, effect.kind));
+            }
+        }
+    }
+
+    for entity in healed {
+        game.heal(entity, 1, world);
+    }
+    for (entity, kind) in expired {
+        game.remove_status_effect(world, entity, kind);
+    }
+}
+
+/// When an entity dies, removes its status effects.
+#[fecs::event_handler]
+pub fn on_entity_death_clear_status_effects(
+    event: &EntityDeathEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    game.clear_status_effects(world, event.entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::inventory::{Area, Inventory};
+    use feather_core::items::{Item, ItemStack};
+    use feather_core::network::packets::{EntityEffect, EntityStatus, RemoveEntityEffect};
+    use feather_server_types::{
+        CanTakeDamage, DamageSource, FireResistance, Health, HurtTime, StatusEffect,
+    };
+    use feather_test_framework::Test;
+
+    #[test]
+    fn regeneration_heals_until_it_wears_off() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        test.world.get_mut::<Health>(player).0 = 10;
+
+        let effect = StatusEffect::new(StatusEffectKind::Regeneration, 1, 60);
+        test.game.add_status_effect(&mut test.world, player, effect);
+        let packet = test.sent::<EntityEffect>(player).unwrap();
+        assert_eq!(packet.entity_id, test.id(player));
+        assert_eq!(packet.effect_id, 10);
+        assert_eq!((packet.amplifier, packet.duration), (1, 60));
+
+        for _ in 0..59 {
+            test.run(tick_status_effects);
+        }
+        assert_eq!(test.world.get::<Health>(player).0, 12);
+        assert!(test.sent::<RemoveEntityEffect>(player).is_none());
+
+        test.run(tick_status_effects);
+        let packet = test.sent::<RemoveEntityEffect>(player).unwrap();
+        assert_eq!(packet.effect_id, 10);
+        assert!(test.world.get::<StatusEffects>(player).0.is_empty());
+    }
+
+    #[test]
+    fn fire_resistance_marker() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+
+        let effect = StatusEffect::new(StatusEffectKind::FireResistance, 0, 2);
+        test.game.add_status_effect(&mut test.world, player, effect);
+        assert!(test.world.has::<FireResistance>(player));
+
+        test.run(tick_status_effects).run(tick_status_effects);
+        assert!(!test.world.has::<FireResistance>(player));
+    }
+
+    #[test]
+    fn totem_of_undying_prevents_death() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        test.world.add(player, CanTakeDamage).unwrap();
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Offhand, 0, ItemStack::new(Item::TotemOfUndying, 1))
+            .unwrap();
+        let effect = StatusEffect::new(StatusEffectKind::Glowing, 0, 1000);
+        test.game.add_status_effect(&mut test.world, player, effect);
+
+        test.game
+            .damage(player, 30, DamageSource::Generic, &mut test.world);
+        assert!(!test.world.has::<Dead>(player));
+        assert_eq!(test.world.get::<Health>(player).0, 1);
+        assert_eq!(
+            test.world
+                .get::<Inventory>(player)
+                .item_at(Area::Offhand, 0)
+                .unwrap(),
+            None
+        );
+        let effects = test.world.get::<StatusEffects>(player).clone();
+        assert!(!effects.has(StatusEffectKind::Glowing));
+        assert_eq!(
+            effects
+                .get(StatusEffectKind::Regeneration)
+                .map(|e| e.amplifier),
+            Some(1)
+        );
+        assert!(test.world.has::<FireResistance>(player));
+
+        let status = test.sent::<EntityStatus>(player).unwrap();
+        assert_eq!(status.entity_status, 35);
+
+        // Without another totem, the player dies.
+        test.game.tick_count += HurtTime::INVULNERABLE_TICKS;
+        test.game
+            .damage(player, 30, DamageSource::Generic, &mut test.world);
+        assert_eq!(test.world.get::<Health>(player).0, 0);
+    }
+}
@@ -0,0 +1,315 @@
+//! Vehicles, such as boats and minecarts, and the entities riding them.
+//!
+//! Right clicking a vehicle with a free seat makes a player ride it,
+//! and sneaking dismounts them. The first passenger of a vehicle drives
+//! it. Vehicles which the client of their driver moves, such as boats,
+//! take their position from the driver's Vehicle Move packets, while
+//! the others are moved by the server and only steered by their driver.
+//! Passengers aren't saved with the world yet.
+
+use crate::Ridable;
+use feather_core::network::packets::SetPassengers;
+use feather_core::util::{Gamemode, Position};
+use feather_server_types::{
+    Dead, EntityDeathEvent, EntityDespawnEvent, EntityInteractEvent, EntitySendEvent, Game,
+    Network, NetworkId, Player, Teleported,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+use smallvec::SmallVec;
+
+/// Component for entities which carry passengers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vehicle {
+    /// The number of passengers the vehicle can carry.
+    pub seats: usize,
+    /// Height of the vehicle, on top of which
+    /// passengers are put when they dismount.
+    pub height: f64,
+    /// Whether the vehicle is moved by the
+    /// client of the player driving it.
+    pub client_moved: bool,
+    /// The passengers, the first of which drives the vehicle.
+    pub passengers: SmallVec<[Entity; 2]>,
+    /// The forward input of the driver, between -1 and 1.
+    pub forward: f32,
+    /// The sideways input of the driver, between -1 and 1.
+    /// Positive values steer to the left.
+    pub sideways: f32,
+    /// Whether the driver holds the jump key.
+    pub jumping: bool,
+}
+
+impl Vehicle {
+    pub fn new(seats: usize, height: f64, client_moved: bool) -> Self {
+        Self {
+            seats,
+            height,
+            client_moved,
+            passengers: SmallVec::new(),
+            forward: 0.0,
+            sideways: 0.0,
+            jumping: false,
+        }
+    }
+
+    /// Returns the passenger driving the vehicle, if any.
+    pub fn driver(&self) -> Option<Entity> {
+        self.passengers.first().copied()
+    }
+}
+
+/// Component for entities riding a vehicle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Riding(pub Entity);
+
+/// Returns the vehicle driven by an entity, if any.
+pub fn driven_vehicle(world: &World, driver: Entity) -> Option<Entity> {
+    let vehicle = world.try_get::<Riding>(driver)?.0;
+    let driven = world
+        .try_get::<Vehicle>(vehicle)
+        .map_or(false, |vehicle| vehicle.driver() == Some(driver));
+    if driven {
+        Some(vehicle)
+    } else {
+        None
+    }
+}
+
+fn passengers_packet(world: &World, vehicle: Entity) -> Option<SetPassengers> {
+    let passengers = world
+        .try_get::<Vehicle>(vehicle)?
+        .passengers
+        .iter()
+        .filter_map(|&passenger| world.try_get::<NetworkId>(passenger).map(|id| id.0))
+        .collect();
+    Some(SetPassengers {
+        entity_id: world.try_get::<NetworkId>(vehicle)?.0,
+        passengers,
+    })
+}
+
+fn broadcast_passengers(game: &Game, world: &World, vehicle: Entity) {
+    if let Some(packet) = passengers_packet(world, vehicle) {
+        game.broadcast_entity_update(world, packet, vehicle, None);
+    }
+}
+
+/// Makes an entity ride a vehicle. Returns `false` if the
+/// vehicle is full or the entity already rides a vehicle.
+pub fn mount(game: &Game, world: &mut World, passenger: Entity, vehicle: Entity) -> bool {
+    if passenger == vehicle || world.has::<Riding>(passenger) {
+        return false;
+    }
+    {
+        let mut state = match world.try_get_mut::<Vehicle>(vehicle) {
+            Some(state) => state,
+            None => return false,
+        };
+        if state.passengers.len() >= state.seats {
+            return false;
+        }
+        state.passengers.push(passenger);
+    }
+
+    world.add(passenger, Riding(vehicle)).unwrap();
+    broadcast_passengers(game, world, vehicle);
+    true
+}
+
+/// Makes an entity get off the vehicle it rides,
+/// putting it on top of the vehicle.
+pub fn dismount(game: &Game, world: &mut World, passenger: Entity) {
+    let vehicle = match world.try_get::<Riding>(passenger) {
+        Some(riding) => riding.0,
+        None => return,
+    };
+    world.remove::<Riding>(passenger).unwrap();
+
+    let height = match world.try_get_mut::<Vehicle>(vehicle) {
+        Some(mut state) => {
+            if state.driver() == Some(passenger) {
+                state.forward = 0.0;
+                state.sideways = 0.0;
+                state.jumping = false;
+            }
+            state.passengers.retain(|other| *other != passenger);
+            state.height
+        }
+        None => return,
+    };
+
+    let vehicle_pos = *world.get::<Position>(vehicle);
+    if let Some(mut pos) = world.try_get_mut::<Position>(passenger) {
+        pos.x = vehicle_pos.x;
+        pos.y = vehicle_pos.y + height;
+        pos.z = vehicle_pos.z;
+    }
+    if world.has::<Player>(passenger) {
+        let _ = world.add(passenger, Teleported);
+    }
+    broadcast_passengers(game, world, vehicle);
+}
+
+/// Dismounts an entity from its vehicle, and
+/// the passengers of the entity if it has any.
+fn eject(game: &Game, world: &mut World, entity: Entity) {
+    dismount(game, world, entity);
+
+    let passengers = match world.try_get::<Vehicle>(entity) {
+        Some(state) => state.passengers.clone(),
+        None => return,
+    };
+    for passenger in passengers {
+        dismount(game, world, passenger);
+    }
+}
+
+/// When a player right clicks a vehicle with a free seat, makes
+/// them ride it. Players in spectator mode can't ride vehicles.
+/// Ridable mobs are mounted by `on_entity_interact_ride_mob`
+/// instead, as they may need a saddle first.
+#[fecs::event_handler]
+pub fn on_entity_interact_mount_vehicle(
+    event: &EntityInteractEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<Vehicle>(event.target)
+        || world.has::<Ridable>(event.target)
+        || *world.get::<Gamemode>(event.player) == Gamemode::Spectator
+    {
+        return;
+    }
+    mount(game, world, event.player, event.target);
+}
+
+/// When an entity dies, dismounts it from its
+/// vehicle, along with its passengers.
+#[fecs::event_handler]
+pub fn on_entity_death_dismount(event: &EntityDeathEvent, game: &mut Game, world: &mut World) {
+    eject(game, world, event.entity);
+}
+
+/// When an entity is removed, dismounts it from
+/// its vehicle, along with its passengers.
+#[fecs::event_handler]
+pub fn on_entity_despawn_dismount(event: &EntityDespawnEvent, game: &mut Game, world: &mut World) {
+    eject(game, world, event.entity);
+}
+
+/// When an entity is sent to a client, sends the passengers of the
+/// entity and those of the vehicle it rides.
+#[fecs::event_handler]
+pub fn on_entity_send_send_passengers(event: &EntitySendEvent, world: &mut World) {
+    let network = match world.try_get::<Network>(event.client) {
+        Some(network) => network,
+        None => return,
+    };
+
+    let riding = world.try_get::<Riding>(event.entity).map(|riding| riding.0);
+    for vehicle in std::iter::once(event.entity).chain(riding) {
+        match passengers_packet(world, vehicle) {
+            Some(packet) if !packet.passengers.is_empty() => network.send(packet),
+            _ => (),
+        }
+    }
+}
+
+/// System which moves passengers along with their vehicles,
+/// and dismounts those whose vehicle is dead or gone.
+#[fecs::system]
+pub fn carry_passengers(game: &mut Game, world: &mut World) {
+    let passengers: Vec<(Entity, Entity)> = <Read<Riding>>::query()
+        .iter_entities(world.inner())
+        .map(|(passenger, riding)| (passenger, riding.0))
+        .collect();
+
+    for (passenger, vehicle) in passengers {
+        let vehicle_pos = if world.is_alive(vehicle) && !world.has::<Dead>(vehicle) {
+            world.try_get::<Position>(vehicle).map(|pos| *pos)
+        } else {
+            None
+        };
+        match vehicle_pos {
+            Some(vehicle_pos) => {
+                let mut pos = world.get_mut::<Position>(passenger);
+                pos.x = vehicle_pos.x;
+                pos.y = vehicle_pos.y;
+                pos.z = vehicle_pos.z;
+                pos.on_ground = vehicle_pos.on_ground;
+            }
+            None => dismount(game, world, passenger),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    fn vehicle(test: &mut Test, seats: usize, pos: Position) -> Entity {
+        test.entity(
+            crate::base()
+                .with(Vehicle::new(seats, 0.5, false))
+                .with(pos),
+        )
+    }
+
+    fn interact(test: &mut Test, player: Entity, target: Entity) {
+        let event = EntityInteractEvent {
+            player,
+            target,
+            stack: None,
+        };
+        test.handle(event, on_entity_interact_mount_vehicle);
+    }
+
+    #[test]
+    fn mount_until_full() {
+        let mut test = Test::new();
+        let player1 = test.player("", position!(0.0, 64.0, 0.0));
+        let player2 = test.player("", position!(1.0, 64.0, 0.0));
+        let vehicle = vehicle(&mut test, 1, position!(0.0, 64.0, 2.0));
+
+        interact(&mut test, player1, vehicle);
+        interact(&mut test, player2, vehicle);
+
+        assert_eq!(test.world.get::<Riding>(player1).0, vehicle);
+        assert!(!test.world.has::<Riding>(player2));
+        assert_eq!(driven_vehicle(&test.world, player1), Some(vehicle));
+
+        let packet = test.sent::<SetPassengers>(player2).unwrap();
+        assert_eq!(packet.entity_id, test.id(vehicle));
+        assert_eq!(packet.passengers, vec![test.id(player1)]);
+    }
+
+    #[test]
+    fn carry_and_dismount() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let vehicle = vehicle(&mut test, 2, position!(0.0, 64.0, 2.0));
+        interact(&mut test, player, vehicle);
+
+        test.position(vehicle, position!(5.0, 64.0, 2.0));
+        test.run(carry_passengers);
+        assert_eq!(test.world.get::<Position>(player).x, 5.0);
+
+        dismount(&test.game, &mut test.world, player);
+        assert!(!test.world.has::<Riding>(player));
+        assert!(test.world.get::<Vehicle>(vehicle).passengers.is_empty());
+        assert_eq!(test.world.get::<Position>(player).y, 64.5);
+    }
+
+    #[test]
+    fn dismount_when_vehicle_despawns() {
+        let mut test = Test::new();
+        let player = test.player("", position!(0.0, 64.0, 0.0));
+        let vehicle = vehicle(&mut test, 1, position!(0.0, 64.0, 2.0));
+        interact(&mut test, player, vehicle);
+
+        test.game.despawn(vehicle, &mut test.world);
+        test.run(carry_passengers);
+        assert!(!test.world.has::<Riding>(player));
+    }
+}
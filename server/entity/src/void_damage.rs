@@ -0,0 +1,32 @@
+//! Handles entities which fall out of the world.
+
+use feather_core::util::Position;
+use feather_server_types::{DamageSource, Dead, Game, Health};
+use fecs::{component, Entity, IntoQuery, Read, World};
+
+/// Height below which entities are in the void.
+const VOID_Y: f64 = -64.0;
+
+/// Damage dealt each tick to entities in the void.
+const VOID_DAMAGE: u32 = 4;
+
+/// System which damages entities in the void
+/// and removes those which can't be damaged,
+/// such as dropped items.
+#[fecs::system]
+pub fn damage_in_void(game: &mut Game, world: &mut World) {
+    let in_void: Vec<Entity> = <Read<Position>>::query()
+        .filter(!component::<Dead>())
+        .iter_entities(world.inner())
+        .filter(|(_, pos)| pos.y < VOID_Y)
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in in_void {
+        if world.has::<Health>(entity) {
+            game.damage(entity, VOID_DAMAGE, DamageSource::Void, world);
+        } else {
+            game.despawn(entity, world);
+        }
+    }
+}
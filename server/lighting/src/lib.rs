@@ -1,62 +1,48 @@
-//! An implementation of lighting, primarily based on 3D flood fill
-//! algorithms.
+//! Incremental updates of sky and block light.
 //!
 //! # Structure
 //! Lighting is done on a separate _lighting worker thread_ which
-//! stores its own copy of the chunk map. The server notifies
+//! stores its own copy of each world's chunk map. The server notifies
 //! it when chunks are loaded and unloaded, and it can
-//! request that it handle a lighting update, either for
-//! an entire chunk or for a single block update. Since the lighting
-//! worker has clones of the `Arc`s in which chunks are held, any
-//! updates it makes to light data are visible to the server thread.
+//! request that it handle a lighting update for a single
+//! block update. Since the lighting worker has clones of the
+//! `Arc`s in which chunks are held, any updates it makes to
+//! light data are visible to the server thread.
 //!
-//! # Algorithms: block light
-//! For block light calculation, we define four types of block
-//! updates for which to perform lighting:
+//! Chunks arrive with their own light already calculated, either
+//! from the world save or by `feather_core::chunk::light::compute_light`
+//! after generation. When a chunk is loaded, light is spread across
+//! its borders with the neighbouring loaded chunks.
 //!
-//! * Creation of a light-emitting block. We simply propagate
-//! the light update using flood fill.
+//! # Algorithm
+//! Both kinds of light are updated with the same two passes,
+//! using the propagation rules of `feather_core::chunk::light`.
 //!
-//! * Removal of a light-emitting block. We first perform flood fill
-//! and set any blocks which were previously affected by this block's
-//! light to 0. Then, we recalculate lighting for light sources within
-//! a range of 30 blocks based on algorithm #1.
+//! * Decrease: light is removed from the changed block and every
+//! block which received its light from it, i.e. each neighbour
+//! with a lower level (or full sky light directly below full sky light).
+//! Brighter neighbours reached along the way are remembered, as
+//! their light may need to spread back into the darkened area.
 //!
-//! * Creation of an opaque, non-emitting block. We first set the created
-//! block to air temporarily. We then query for nearby lights
-//! within a range of 15 (the maximum distance travelled by light) and perform
-//! algorithm #2 on them. Finally, we set the created block back to the correct
-//! value and perform algorithm #1 on all lights.
+//! * Increase: light spreads from the changed block (if it emits
+//! light) and from the remembered neighbours to every block it
+//! makes brighter, like a flood fill.
 //!
-//! * Removal of an opaque, non-emitting block. In this case,
-//! we set the new air block's light to the highest value of an
-//! adjacent block minus 1. We then perform algorithm #1 on this new block.
-//!
-//! Each algorithm is implemented in a separate function, and `LightingSystem`
-//! determines which to use based on the values of the block update event.
-//!
-//! If we are recalculating light for an entire chunk, e.g. when a chunk is generated,
-//! we first zero out light, then find all light sources in the chunk and perform
-//! algorithm #1 on them as if they had just been placed.
-
-extern crate nalgebra_glm as glm;
+//! A block update is handled for each kind of light whose
+//! opacity or emission changed.
 
-#[cfg_attr(test, macro_use)]
-extern crate smallvec;
-
-use ahash::{AHashMap, AHashSet};
 use arrayvec::ArrayVec;
 use feather_core::util::{BlockPosition, ChunkPosition};
 
 use feather_core::blocks::BlockId;
+use feather_core::chunk::light::{emitted_light, propagated_light, LightKind, MAX_LIGHT};
 use feather_core::chunk::Chunk;
-use feather_core::chunk_map::{chunk_relative_pos, ChunkMap};
-use feather_server_types::{BlockUpdateEvent, ChunkLoadEvent, ChunkUnloadEvent, Game};
-use feather_server_util::chunks_within_distance;
+use feather_core::chunk_map::ChunkMap;
+use feather_server_types::{BlockUpdateEvent, ChunkLoadEvent, ChunkUnloadEvent, Game, WorldId};
 use parking_lot::{RwLock, RwLockWriteGuard};
-use smallvec::SmallVec;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::sync::Arc;
 
 #[fecs::event_handler]
@@ -64,10 +50,15 @@ pub fn on_block_update_notify_lighting_worker(
     event: &BlockUpdateEvent,
     #[default] handle: &LightingWorkerHandle,
 ) {
-    let (pos, old, new) = (event.pos, event.old, event.new);
+    let (world, pos, old, new) = (event.world, event.pos, event.old, event.new);
     handle
         .tx
-        .send(Request::HandleBlockUpdate { pos, old, new })
+        .send(Request::HandleBlockUpdate {
+            world,
+            pos,
+            old,
+            new,
+        })
         .expect("failed to notify lighting worker of block update");
 }
 
@@ -77,7 +68,7 @@ pub fn on_chunk_load_notify_lighting_worker(
     game: &mut Game,
     handle: &LightingWorkerHandle,
 ) {
-    let chunk_handle = game
+    let chunk_handle = game.worlds[event.world]
         .chunk_map
         .chunk_handle_at(event.chunk)
         .expect("chunk load event triggered, but chunk not in chunk map");
@@ -85,6 +76,7 @@ pub fn on_chunk_load_notify_lighting_worker(
     handle
         .tx
         .send(Request::LoadChunk {
+            world: event.world,
             pos: event.chunk,
             handle: chunk_handle,
         })
@@ -98,7 +90,10 @@ pub fn on_chunk_unload_notify_lighting_worker(
 ) {
     handle
         .tx
-        .send(Request::UnloadChunk { pos: event.chunk })
+        .send(Request::UnloadChunk {
+            world: event.world,
+            pos: event.chunk,
+        })
         .expect("failed to notify lighting worker of chunk unload");
 }
 
@@ -106,15 +101,18 @@ pub fn on_chunk_unload_notify_lighting_worker(
 pub enum Request {
     /// Notifies the worker of a new loaded chunk.
     LoadChunk {
+        world: WorldId,
         pos: ChunkPosition,
         handle: Arc<RwLock<Chunk>>,
     },
     /// Notifies the worker that a chunk was unloaded.
-    UnloadChunk { pos: ChunkPosition },
+    UnloadChunk { world: WorldId, pos: ChunkPosition },
     /// Requests that the lighting worker shuts down.
     ShutDown,
     /// Requests that the lighting worker handles a block update.
     HandleBlockUpdate {
+        /// The world containing the block.
+        world: WorldId,
         /// The position of the block which was updated.
         pos: BlockPosition,
         /// The old value of the block.
@@ -147,43 +145,13 @@ fn start_worker() -> LightingWorkerHandle {
     LightingWorkerHandle { tx, shutdown_rx }
 }
 
-/// Cache storing the light sources in each chunk.
-#[derive(Debug, Default)]
-struct ChunkLights(AHashMap<ChunkPosition, SmallVec<[BlockPosition; 8]>>);
-
-impl ChunkLights {
-    /// Returns an iterator over light sources within the given radius
-    /// of a block position.
-    pub fn lights_within_radius<'a>(
-        &'a self,
-        pos: BlockPosition,
-        radius: u8,
-    ) -> impl Iterator<Item = BlockPosition> + 'a + Clone {
-        let radius = f64::from(radius);
-        let chunks = chunks_within_distance(pos.position(), glm::vec3(radius, radius, radius));
-
-        chunks
-            .into_iter()
-            .flat_map(move |chunk| {
-                self.0
-                    .get(&chunk)
-                    .map(|vec| vec.as_slice())
-                    .unwrap_or(&[])
-                    .iter()
-            })
-            .copied()
-    }
-}
-
 /// Internal worker state.
 struct Worker {
     /// Receiver for new requests.
     rx: crossbeam::Receiver<Request>,
-    /// The worker's own copy of the chunk map, with `Arc`s
-    /// being cloned from the server thread's "official" chunk map.
-    chunk_map: ChunkMap,
-    /// Caches the light sources in each chunk.
-    lights: ChunkLights,
+    /// The worker's own copy of each world's chunk map, with `Arc`s
+    /// being cloned from the server thread's "official" chunk maps.
+    chunk_maps: HashMap<WorldId, ChunkMap>,
     /// Whether the worker should shut down.
     should_shut_down: bool,
 }
@@ -191,8 +159,7 @@ struct Worker {
 fn run_worker(rx: crossbeam::Receiver<Request>, shutdown_tx: crossbeam::Sender<()>) {
     let mut worker = Worker {
         rx,
-        chunk_map: Default::default(),
-        lights: Default::default(),
+        chunk_maps: HashMap::new(),
         should_shut_down: false,
     };
 
@@ -212,38 +179,30 @@ fn run_worker(rx: crossbeam::Receiver<Request>, shutdown_tx: crossbeam::Sender<(
 fn handle_request(worker: &mut Worker, request: Request) {
     match request {
         Request::ShutDown => worker.should_shut_down = true,
-        Request::LoadChunk { pos, handle } => load_chunk(worker, pos, handle),
-        Request::UnloadChunk { pos } => unload_chunk(worker, pos),
-        Request::HandleBlockUpdate { pos, old, new } => handle_block_update(worker, pos, old, new),
+        Request::LoadChunk { world, pos, handle } => load_chunk(worker, world, pos, handle),
+        Request::UnloadChunk { world, pos } => unload_chunk(worker, world, pos),
+        Request::HandleBlockUpdate {
+            world,
+            pos,
+            old,
+            new,
+        } => handle_block_update(worker, world, pos, old, new),
     }
 }
 
-fn load_chunk(worker: &mut Worker, pos: ChunkPosition, handle: Arc<RwLock<Chunk>>) {
-    worker
-        .lights
-        .0
-        .insert(pos, lights_in_chunk(&*handle.read()).collect());
-    worker.chunk_map.0.insert(pos, handle);
-}
-
-fn lights_in_chunk<'a>(chunk: &'a Chunk) -> impl Iterator<Item = BlockPosition> + 'a {
-    (0..16)
-        .flat_map(|x| (0..256).map(move |y| (x, y)))
-        .flat_map(|(x, y)| (0..16).map(move |z| (x, y, z)))
-        .filter_map(move |(x, y, z)| {
-            let block = chunk.block_at(x, y, z);
+fn load_chunk(worker: &mut Worker, world: WorldId, pos: ChunkPosition, handle: Arc<RwLock<Chunk>>) {
+    let chunk_map = worker.chunk_maps.entry(world).or_default();
+    chunk_map.0.insert(pos, handle);
 
-            if block.light_emission() > 0 {
-                Some(BlockPosition::new(x as i32, y as i32, z as i32))
-            } else {
-                None
-            }
-        })
+    if let Some(mut ctx) = Context::new(chunk_map, pos) {
+        spread_across_borders(&mut ctx, pos);
+    }
 }
 
-fn unload_chunk(worker: &mut Worker, pos: ChunkPosition) {
-    worker.lights.0.remove(&pos);
-    worker.chunk_map.0.remove(&pos);
+fn unload_chunk(worker: &mut Worker, world: WorldId, pos: ChunkPosition) {
+    if let Some(chunk_map) = worker.chunk_maps.get_mut(&world) {
+        chunk_map.0.remove(&pos);
+    }
 }
 
 /// Lighter context, used to cache things during
@@ -282,223 +241,258 @@ impl<'a> Context<'a> {
         }
     }
 
-    fn block_light_at(&mut self, pos: BlockPosition) -> u8 {
-        match self.chunk_at_mut(pos.chunk()) {
-            Some(chunk) => {
-                let (x, y, z) = chunk_relative_pos(pos);
-                chunk.block_light_at(x, y, z)
-            }
-            None => 0, // TODO: graceful handling of missing chunk information?
+    /// Returns the loaded chunk containing a block, along with
+    /// the block's position in it, if the block is inside its bounds.
+    fn locate(&mut self, pos: BlockPosition) -> Option<(&mut Chunk, (usize, usize, usize))> {
+        let chunk = self.chunk_at_mut(pos.chunk())?;
+        let local = chunk.local_pos(pos)?;
+        Some((chunk, local))
+    }
+
+    fn is_loaded(&mut self, pos: BlockPosition) -> bool {
+        self.locate(pos).is_some()
+    }
+
+    /// Returns whether a block is at the top of its chunk.
+    fn is_top(&mut self, pos: BlockPosition) -> bool {
+        self.chunk_at_mut(pos.chunk())
+            .map_or(false, |chunk| pos.y == chunk.max_y() - 1)
+    }
+
+    /// Returns the Y coordinates spanned by a chunk.
+    fn y_range(&mut self, chunk: ChunkPosition) -> Range<i32> {
+        self.chunk_at_mut(chunk)
+            .map_or(0..0, |chunk| chunk.min_y()..chunk.max_y())
+    }
+
+    fn light_at(&mut self, kind: LightKind, pos: BlockPosition) -> u8 {
+        match self.locate(pos) {
+            Some((chunk, (x, y, z))) => match kind {
+                LightKind::Sky => chunk.sky_light_at(x, y, z),
+                LightKind::Block => chunk.block_light_at(x, y, z),
+            },
+            None => 0,
         }
     }
 
-    fn set_block_light_at(&mut self, pos: BlockPosition, value: u8) {
-        if let Some(chunk) = self.chunk_at_mut(pos.chunk()) {
-            let (x, y, z) = chunk_relative_pos(pos);
-            chunk.set_block_light_at(x, y, z, value);
+    fn set_light_at(&mut self, kind: LightKind, pos: BlockPosition, value: u8) {
+        if let Some((chunk, (x, y, z))) = self.locate(pos) {
+            // Avoid creating missing sections whose
+            // implicit light already has this value.
+            match kind {
+                LightKind::Sky if chunk.sky_light_at(x, y, z) != value => {
+                    chunk.set_sky_light_at(x, y, z, value)
+                }
+                LightKind::Block if chunk.block_light_at(x, y, z) != value => {
+                    chunk.set_block_light_at(x, y, z, value)
+                }
+                _ => (),
+            }
         }
     }
 
     fn block_at(&mut self, pos: BlockPosition) -> BlockId {
-        match self.chunk_at_mut(pos.chunk()) {
-            Some(chunk) => {
-                let (x, y, z) = chunk_relative_pos(pos);
-                chunk.block_at(x, y, z)
-            }
+        match self.locate(pos) {
+            Some((chunk, (x, y, z))) => chunk.block_at(x, y, z),
             None => BlockId::air(),
         }
     }
 
+    #[cfg(test)]
     fn set_block_at(&mut self, pos: BlockPosition, block: BlockId) {
-        if let Some(chunk) = self.chunk_at_mut(pos.chunk()) {
-            let (x, y, z) = chunk_relative_pos(pos);
+        if let Some((chunk, (x, y, z))) = self.locate(pos) {
             chunk.set_block_at(x, y, z, block);
         }
     }
 }
 
-const MAX_TRAVEL_DISTANCE: u8 = 15;
+const LIGHT_KINDS: [LightKind; 2] = [LightKind::Sky, LightKind::Block];
 
-fn handle_block_update(worker: &mut Worker, pos: BlockPosition, old: BlockId, new: BlockId) {
-    let mut ctx = match Context::new(&worker.chunk_map, pos.chunk()) {
+fn handle_block_update(
+    worker: &mut Worker,
+    world: WorldId,
+    pos: BlockPosition,
+    old: BlockId,
+    new: BlockId,
+) {
+    let chunk_map = match worker.chunk_maps.get(&world) {
+        Some(chunk_map) => chunk_map,
+        None => return, // No loaded chunks
+    };
+    let mut ctx = match Context::new(chunk_map, pos.chunk()) {
         Some(ctx) => ctx,
         None => return, // Unloaded chunk
     };
 
-    // Determine which algorithm to use.
-    if old.light_emission() < new.light_emission() {
-        ctx.set_block_light_at(pos, new.light_emission());
-        emitting_creation(&mut ctx, pos);
-    } else if new.light_emission() == 0 && old.light_emission() > 0 {
-        ctx.set_block_light_at(pos, 0);
-        emitting_removal(&mut ctx, &worker.lights, pos, old);
-    } else if old.is_opaque() && !new.is_opaque() {
-        opaque_non_emitting_removal(&mut ctx, pos);
-    } else {
-        opaque_non_emitting_creation(&mut ctx, &worker.lights, pos, new);
-    }
+    update_light(&mut ctx, pos, old, new);
+}
 
-    // Update `ChunkLights`.
-    if old.light_emission() != new.light_emission() {
-        if new.light_emission() == 0 {
-            worker
-                .lights
-                .0
-                .entry(pos.chunk())
-                .or_default()
-                .retain(|p| *p != pos);
-        } else if old.light_emission() == 0 {
-            worker.lights.0.entry(pos.chunk()).or_default().push(pos);
+/// Updates light after the block at `pos` changed from `old` to `new`.
+fn update_light(ctx: &mut Context, pos: BlockPosition, old: BlockId, new: BlockId) {
+    for &kind in &LIGHT_KINDS {
+        if old.light_opacity() != new.light_opacity()
+            || emitted_light(kind, old) != emitted_light(kind, new)
+        {
+            relight(ctx, kind, pos, new);
         }
     }
 }
 
-/// Algorithm #1, as described in the module-level docs.
-fn emitting_creation(context: &mut Context, position: BlockPosition) {
-    let emission = context.block_light_at(position);
-    // Perform flood fill starting from `position`.
-    // For each block, set the light value to the maximum light
-    // value of any adjacent block minus 1.
-    flood_fill(context, position, emission, |ctx, pos| {
-        let light = light_value_for_block(ctx, pos);
-        ctx.set_block_light_at(pos, light);
-    });
-}
+/// Recalculates one kind of light around a changed block.
+fn relight(ctx: &mut Context, kind: LightKind, pos: BlockPosition, block: BlockId) {
+    let mut increase = VecDeque::new();
+    let mut decrease = VecDeque::new();
 
-/// Algorithm #2, as described in the module-level docs.
-fn emitting_removal(
-    context: &mut Context,
-    chunk_lights: &ChunkLights,
-    position: BlockPosition,
-    old_block: BlockId,
-) {
-    // Perform flood fill and set all blocks affected by the old light to 0 light.
-    flood_fill(context, position, old_block.light_emission(), |ctx, pos| {
-        ctx.set_block_light_at(pos, 0);
-    });
-
-    // For all lights which could have affected the blocks we just set to 0,
-    // recalculate lighting using algorithm #1.
-    let nearby_lights = chunk_lights.lights_within_radius(position, MAX_TRAVEL_DISTANCE * 2);
-
-    for light in nearby_lights {
-        if light != position {
-            emitting_creation(context, light);
+    let old_level = ctx.light_at(kind, pos);
+    ctx.set_light_at(kind, pos, 0);
+    decrease.push_back((pos, old_level));
+    remove_light(ctx, kind, &mut decrease, &mut increase);
+
+    let emission = emitted_light(kind, block);
+    if emission > 0 {
+        ctx.set_light_at(kind, pos, emission);
+        increase.push_back(pos);
+    }
+
+    // Nothing is above the top of the world to
+    // block sky light from entering it.
+    if kind == LightKind::Sky && ctx.is_top(pos) {
+        let level = propagated_light(kind, MAX_LIGHT, block, true);
+        if level > ctx.light_at(kind, pos) {
+            ctx.set_light_at(kind, pos, level);
+            increase.push_back(pos);
         }
     }
+
+    spread_light(ctx, kind, increase);
 }
 
-/// Algorithm #3, as described in the module-level docs.
-fn opaque_non_emitting_creation(
-    context: &mut Context,
-    chunk_lights: &ChunkLights,
-    position: BlockPosition,
-    new_block: BlockId,
+/// The decrease pass: removes the light of the queued blocks,
+/// given with their previous levels, from all blocks which
+/// received light from them. Brighter blocks at the edges of
+/// the darkened area are queued in `increase`.
+fn remove_light(
+    ctx: &mut Context,
+    kind: LightKind,
+    decrease: &mut VecDeque<(BlockPosition, u8)>,
+    increase: &mut VecDeque<BlockPosition>,
 ) {
-    // Re-calculate all lights that could have affected this block.
-    // We ensure that all areas are correctly set to dark by first
-    // faking that the block was never created.
-    context.set_block_at(position, BlockId::air());
-
-    let nearby_lights = chunk_lights.lights_within_radius(position, MAX_TRAVEL_DISTANCE);
-
-    for light in nearby_lights {
-        let block = context.block_at(light);
-        emitting_removal(context, chunk_lights, light, block);
-    }
-
-    // Set block back to correct value.
-    context.set_block_at(position, new_block);
+    while let Some((pos, level)) = decrease.pop_front() {
+        for neighbour in adjacent_blocks(pos) {
+            if !ctx.is_loaded(neighbour) {
+                continue;
+            }
 
-    let nearby_lights = chunk_lights.lights_within_radius(position, MAX_TRAVEL_DISTANCE);
+            let neighbour_level = ctx.light_at(kind, neighbour);
+            if neighbour_level == 0 {
+                continue;
+            }
 
-    // Recalculate nearby lights.
-    for light in nearby_lights {
-        emitting_creation(context, light);
+            let downward = neighbour.y < pos.y;
+            let lit_by_pos = neighbour_level < level
+                || (kind == LightKind::Sky
+                    && downward
+                    && level == MAX_LIGHT
+                    && neighbour_level == MAX_LIGHT);
+
+            if lit_by_pos {
+                ctx.set_light_at(kind, neighbour, 0);
+                decrease.push_back((neighbour, neighbour_level));
+
+                // Light sources keep their own light.
+                let emission = emitted_light(kind, ctx.block_at(neighbour));
+                if emission > 0 {
+                    ctx.set_light_at(kind, neighbour, emission);
+                    increase.push_back(neighbour);
+                }
+            } else {
+                increase.push_back(neighbour);
+            }
+        }
     }
 }
 
-/// Algorithm #4, as described in the module-level docs.
-fn opaque_non_emitting_removal(context: &mut Context, position: BlockPosition) {
-    let value = light_value_for_block(context, position);
+/// The increase pass: spreads light from the queued blocks
+/// to all blocks which it makes brighter.
+fn spread_light(ctx: &mut Context, kind: LightKind, mut increase: VecDeque<BlockPosition>) {
+    while let Some(pos) = increase.pop_front() {
+        let level = ctx.light_at(kind, pos);
+        if level <= 1 {
+            continue;
+        }
 
-    context.set_block_light_at(position, value);
+        for neighbour in adjacent_blocks(pos) {
+            if !ctx.is_loaded(neighbour) {
+                continue;
+            }
 
-    // Propagate new light value for this block, as if it were a new light source.
-    if value > 0 {
-        emitting_creation(context, position);
+            let downward = neighbour.y < pos.y;
+            let new_level = propagated_light(kind, level, ctx.block_at(neighbour), downward);
+            if new_level > ctx.light_at(kind, neighbour) {
+                ctx.set_light_at(kind, neighbour, new_level);
+                increase.push_back(neighbour);
+            }
+        }
     }
 }
 
-/// Returns the light value for the block at `position`,
-/// equivalent to the maximum light value of an adjacent block
-/// minus 1.
-fn light_value_for_block(context: &mut Context, position: BlockPosition) -> u8 {
-    // Find highest light value of 6 adjacent blocks.
-    let adjacent = adjacent_blocks(position);
-
-    let mut value = adjacent
-        .into_iter()
-        .map(|pos| context.block_light_at(pos))
-        .max()
-        .unwrap();
-
-    if value > 0 {
-        value -= 1;
-    }
+/// Spreads light across the borders between a newly
+/// loaded chunk and its loaded neighbours, in both directions.
+fn spread_across_borders(ctx: &mut Context, chunk: ChunkPosition) {
+    let neighbours = [
+        ChunkPosition::new(chunk.x - 1, chunk.z),
+        ChunkPosition::new(chunk.x + 1, chunk.z),
+        ChunkPosition::new(chunk.x, chunk.z - 1),
+        ChunkPosition::new(chunk.x, chunk.z + 1),
+    ];
 
-    value
-}
+    for &kind in &LIGHT_KINDS {
+        let mut increase = VecDeque::new();
 
-/// Performs flood fill starting at `start` and travelling up
-/// to `max_dist` blocks.
-///
-/// For each block iterated over, the provided closure will be invoked.
-/// No block will be iterated more than once.
-fn flood_fill<F>(context: &mut Context, start: BlockPosition, max_dist: u8, mut f: F)
-where
-    F: FnMut(&mut Context, BlockPosition),
-{
-    // TODO: bump allocate these data structures.
-    // Don't iterate over same block more than once
-    let mut touched = AHashSet::with_capacity_and_hasher(64, ahash::RandomState::new());
-    touched.insert(start);
-
-    // We use a queue-based algorithm rather than a recursive
-    // one.
-    let mut queue = VecDeque::with_capacity(64);
-
-    queue.push_back(start);
-
-    while let Some(pos) = queue.pop_front() {
-        let blocks = adjacent_blocks(pos);
-
-        for pos in blocks {
-            if pos.manhattan_distance(start) > max_dist as i32 {
-                // Finished
-                return;
-            }
-
-            // Skip if we already went over this block
-            if !touched.insert(pos) {
+        for &neighbour in &neighbours {
+            if ctx.chunk_at_mut(neighbour).is_none() {
                 continue;
             }
 
-            let block = context.block_at(pos);
-            if block.is_opaque() {
-                continue; // Stop iterating
+            let inner = border_blocks(chunk, neighbour, ctx.y_range(chunk));
+            let outer = border_blocks(neighbour, chunk, ctx.y_range(neighbour));
+            for pos in inner.chain(outer) {
+                if ctx.light_at(kind, pos) > 1 {
+                    increase.push_back(pos);
+                }
             }
-
-            // Call closure
-            f(context, pos);
-
-            // Add block to queue
-            queue.push_back(pos);
         }
+
+        spread_light(ctx, kind, increase);
     }
 }
 
-/// Returns the up to six adjacent blocks to a given block position.
+/// Returns the blocks of chunk `from`, which spans the Y
+/// coordinates `ys`, along its border with the adjacent chunk `to`.
+fn border_blocks(
+    from: ChunkPosition,
+    to: ChunkPosition,
+    ys: Range<i32>,
+) -> impl Iterator<Item = BlockPosition> {
+    let (dx, dz) = (to.x - from.x, to.z - from.z);
+    let (x, z) = (
+        from.x * 16 + if dx > 0 { 15 } else { 0 },
+        from.z * 16 + if dz > 0 { 15 } else { 0 },
+    );
+
+    ys.flat_map(move |y| {
+        (0..16).map(move |offset| {
+            if dx != 0 {
+                BlockPosition::new(x, y, from.z * 16 + offset)
+            } else {
+                BlockPosition::new(from.x * 16 + offset, y, z)
+            }
+        })
+    })
+}
+
+/// Returns the six adjacent blocks to a given block position.
+/// Those outside the world are skipped by `Context::is_loaded`.
 fn adjacent_blocks(to: BlockPosition) -> ArrayVec<[BlockPosition; 6]> {
     let offsets = [
         (-1, 0, 0),
@@ -511,7 +505,6 @@ fn adjacent_blocks(to: BlockPosition) -> ArrayVec<[BlockPosition; 6]> {
     offsets
         .iter()
         .map(|(x, y, z)| BlockPosition::new(to.x + *x, to.y + *y, to.z + *z))
-        .filter(|pos| pos.y >= 0 && pos.y < 256)
         .collect()
 }
 
@@ -536,76 +529,114 @@ mod tests {
     }
 
     #[test]
-    fn test_emitting_creation() {
+    fn block_light_creation_and_removal() {
         let chunk_map = chunk_map();
         let mut ctx = Context::new(&chunk_map, ChunkPosition::new(0, 0)).unwrap();
 
         let pos = BlockPosition::new(0, 100, 0);
-        ctx.set_block_at(pos, BlockId::glowstone());
-        ctx.set_block_light_at(pos, BlockId::glowstone().light_emission());
-
-        emitting_creation(&mut ctx, pos);
-
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 99, 0)), 14);
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 99, 1)), 13);
+        set_block(&mut ctx, pos, BlockId::glowstone());
+        assert_eq!(ctx.light_at(LightKind::Block, pos), 15);
+        assert_eq!(block_light(&mut ctx, 0, 99, 0), 14);
+        assert_eq!(block_light(&mut ctx, 0, 99, 1), 13);
+        // Light spreads into neighbouring chunks.
+        assert_eq!(block_light(&mut ctx, -3, 100, 0), 12);
+
+        // A wall blocks light, which has to go around it.
+        set_block(&mut ctx, BlockPosition::new(2, 100, 0), BlockId::stone());
+        assert_eq!(block_light(&mut ctx, 2, 100, 0), 0);
+        assert_eq!(block_light(&mut ctx, 3, 100, 0), 10);
+
+        set_block(&mut ctx, pos, BlockId::air());
+        for &(x, y, z) in &[(0, 100, 0), (0, 99, 1), (-3, 100, 0), (3, 100, 0)] {
+            assert_eq!(block_light(&mut ctx, x, y, z), 0);
+        }
     }
 
     #[test]
-    fn test_opaque_non_emitting_removal() {
+    fn overlapping_lights() {
         let chunk_map = chunk_map();
         let mut ctx = Context::new(&chunk_map, ChunkPosition::new(0, 0)).unwrap();
 
-        ctx.set_block_light_at(BlockPosition::new(0, 0, 0), 10);
-        ctx.set_block_light_at(BlockPosition::new(0, 2, 0), 9);
-        ctx.set_block_light_at(BlockPosition::new(1, 1, 0), 8);
-        ctx.set_block_light_at(BlockPosition::new(-1, 1, 0), 11);
-        ctx.set_block_light_at(BlockPosition::new(0, 1, 1), 0);
-        ctx.set_block_light_at(BlockPosition::new(0, 1, -1), 12);
-        ctx.set_block_light_at(BlockPosition::new(0, 1, 0), 15);
-
-        opaque_non_emitting_removal(&mut ctx, BlockPosition::new(0, 1, 0));
-
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 1, 0)), 11);
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 1, 1)), 10);
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 1, 2)), 9);
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 1, 3)), 8);
-        assert_eq!(ctx.block_light_at(BlockPosition::new(0, 1, 4)), 7);
-        // ...
+        set_block(&mut ctx, BlockPosition::new(0, 50, 0), BlockId::glowstone());
+        set_block(&mut ctx, BlockPosition::new(4, 50, 0), BlockId::glowstone());
+        assert_eq!(block_light(&mut ctx, 2, 50, 0), 13);
+
+        // Removing one light leaves the other's light intact.
+        set_block(&mut ctx, BlockPosition::new(0, 50, 0), BlockId::air());
+        assert_eq!(block_light(&mut ctx, 2, 50, 0), 13);
+        assert_eq!(block_light(&mut ctx, 0, 50, 0), 11);
+        assert_eq!(block_light(&mut ctx, -2, 50, 0), 9);
     }
 
     #[test]
-    fn test_flood_fill() {
+    fn sky_light_shadows() {
         let chunk_map = chunk_map();
         let mut ctx = Context::new(&chunk_map, ChunkPosition::new(0, 0)).unwrap();
 
-        let mut count = 0;
+        assert_eq!(sky_light(&mut ctx, 1, 50, 1), 15);
 
-        flood_fill(&mut ctx, BlockPosition::new(100, 100, 100), 1, |_, _| {
-            count += 1
-        });
+        // A 3x3 roof casts a shadow all the way down,
+        // which is lit from its sides.
+        for x in 0..3 {
+            for z in 0..3 {
+                set_block(&mut ctx, BlockPosition::new(x, 100, z), BlockId::stone());
+            }
+        }
+        assert_eq!(sky_light(&mut ctx, 1, 100, 1), 0);
+        assert_eq!(sky_light(&mut ctx, 0, 99, 1), 14);
+        assert_eq!(sky_light(&mut ctx, 1, 99, 1), 13);
+        assert_eq!(sky_light(&mut ctx, 1, 20, 1), 13);
+        assert_eq!(sky_light(&mut ctx, 1, 101, 1), 15);
+
+        // Opening the roof lets sky light back in.
+        set_block(&mut ctx, BlockPosition::new(1, 100, 1), BlockId::air());
+        assert_eq!(sky_light(&mut ctx, 1, 100, 1), 15);
+        assert_eq!(sky_light(&mut ctx, 1, 20, 1), 15);
+        assert_eq!(sky_light(&mut ctx, 0, 99, 0), 14);
+    }
 
-        assert_eq!(count, 6);
+    #[test]
+    fn light_spreads_into_loaded_chunk() {
+        let mut chunk_map = ChunkMap::new();
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(15, 10, 0, BlockId::glowstone());
+        chunk.set_block_light_at(15, 10, 0, 15);
+        chunk_map.insert(chunk);
+        chunk_map.insert(Chunk::new(ChunkPosition::new(1, 0)));
+
+        let mut ctx = Context::new(&chunk_map, ChunkPosition::new(1, 0)).unwrap();
+        spread_across_borders(&mut ctx, ChunkPosition::new(1, 0));
+
+        assert_eq!(block_light(&mut ctx, 16, 10, 0), 14);
+        assert_eq!(block_light(&mut ctx, 18, 11, 0), 11);
+        assert_eq!(block_light(&mut ctx, 14, 10, 0), 14);
     }
 
     #[test]
-    fn test_chunk_lights() {
-        let mut chunk_lights = ChunkLights::default();
-        chunk_lights.0.insert(
-            ChunkPosition::new(0, 0),
-            smallvec![BlockPosition::new(0, 0, 0)],
-        );
-        chunk_lights.0.insert(
-            ChunkPosition::new(1, 0),
-            smallvec![BlockPosition::new(16, 0, 0)],
-        );
-
-        assert_eq!(
-            chunk_lights
-                .lights_within_radius(BlockPosition::new(0, 0, 0), 16)
-                .collect::<Vec<_>>()
-                .as_slice(),
-            &[BlockPosition::new(0, 0, 0), BlockPosition::new(16, 0, 0)]
-        );
+    fn light_below_zero() {
+        let mut chunk_map = ChunkMap::new();
+        chunk_map.insert(Chunk::with_bounds(ChunkPosition::new(0, 0), -64, 384));
+        let mut ctx = Context::new(&chunk_map, ChunkPosition::new(0, 0)).unwrap();
+
+        let lamp = BlockPosition::new(4, -60, 4);
+        set_block(&mut ctx, lamp, BlockId::glowstone());
+        assert_eq!(block_light(&mut ctx, 4, -60, 4), 15);
+        assert_eq!(block_light(&mut ctx, 4, -64, 4), 11);
+        assert_eq!(block_light(&mut ctx, 4, -65, 4), 0);
+    }
+
+    fn set_block(ctx: &mut Context, pos: BlockPosition, block: BlockId) {
+        let old = ctx.block_at(pos);
+        ctx.set_block_at(pos, block);
+        update_light(ctx, pos, old, block);
+    }
+
+    fn block_light(ctx: &mut Context, x: i32, y: i32, z: i32) -> u8 {
+        ctx.light_at(LightKind::Block, BlockPosition::new(x, y, z))
+    }
+
+    fn sky_light(ctx: &mut Context, x: i32, y: i32, z: i32) -> u8 {
+        ctx.light_at(LightKind::Sky, BlockPosition::new(x, y, z))
     }
 
     fn chunk_map() -> ChunkMap {
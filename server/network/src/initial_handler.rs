@@ -14,8 +14,7 @@
 //! speeding up the login process and making the latency calculation in
 //! the server list ping as low as possible.
 
-use std::net::SocketAddr;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 
@@ -25,15 +24,19 @@ use rsa_der as der;
 
 use thiserror::Error;
 
-use feather_core::network::{cast_packet, Packet, PacketStage, PacketType};
+use feather_core::network::{cast_packet, Packet, PacketStage, PacketType, ProtocolVersion};
 use feather_core::text::{Text, TextRoot};
 
-use crate::{PROTOCOL_VERSION, SERVER_VERSION};
+use crate::velocity;
+use crate::SERVER_NAME;
 use feather_core::network::packets::{
-    DisconnectLogin, EncryptionRequest, EncryptionResponse, Handshake, HandshakeState, LoginStart,
-    LoginSuccess, Ping, Pong, Request, Response, SetCompression,
+    DisconnectLogin, EncryptionRequest, EncryptionResponse, Handshake, HandshakeState,
+    LoginPluginRequest, LoginPluginResponse, LoginStart, LoginSuccess, Ping, Pong, Request,
+    Response, SetCompression,
+};
+use feather_server_types::{
+    run_status_hooks, BanInfo, Config, OnlinePlayers, ProxyMode, StatusPing, StatusResponse,
 };
-use feather_server_types::{BanInfo, Config, ProxyMode};
 use feather_server_util::name_to_uuid_offline;
 use mojang_api::ProfileProperty;
 use once_cell::sync::Lazy;
@@ -49,12 +52,25 @@ type VerifyToken = [u8; 4];
 const RSA_KEY_BITS: usize = 1024;
 /// The number of bytes in the shared secret
 const SHARED_SECRET_LEN: usize = 128 / 8;
+/// The message ID of the Login Plugin Request
+/// asking Velocity for the player's information.
+const VELOCITY_MESSAGE_ID: i32 = 1;
 
 pub static RSA_KEY: Lazy<RSAPrivateKey> = Lazy::new(|| {
     let mut rng = OsRng;
     RSAPrivateKey::new(&mut rng, RSA_KEY_BITS).unwrap()
 });
 
+/// The public part of `RSA_KEY`, DER-encoded as sent in
+/// Encryption Request and hashed to authenticate players.
+pub static PUBLIC_KEY_DER: Lazy<Vec<u8>> = Lazy::new(|| {
+    use num_bigint_dig::{BigInt, Sign::Plus};
+    der::public_key_to_der(
+        &BigInt::from_biguint(Plus, RSA_KEY.n().clone()).to_signed_bytes_be(),
+        &BigInt::from_biguint(Plus, RSA_KEY.e().clone()).to_signed_bytes_be(),
+    )
+});
+
 /// An action for the worker thread to execute
 /// after `InitialHandler::handle_packet` is called.
 pub enum Action {
@@ -63,6 +79,7 @@ pub enum Action {
     SendPacket(Box<dyn Packet>),
     Disconnect,
     SetStage(PacketStage),
+    SetProtocolVersion(ProtocolVersion),
     JoinGame(JoinResult),
 }
 
@@ -72,6 +89,8 @@ pub struct JoinResult {
     pub username: Option<String>,
     pub uuid: Uuid,
     pub props: Vec<mojang_api::ProfileProperty>,
+    /// The player's IP address, if forwarded by a proxy.
+    pub ip: Option<IpAddr>,
 }
 
 impl JoinResult {
@@ -95,6 +114,7 @@ impl Default for JoinResult {
             username: None,
             uuid: Uuid::new_v4(),
             props: vec![],
+            ip: None,
         }
     }
 }
@@ -132,12 +152,15 @@ pub struct InitialHandler {
     /// Server bans
     ban_info: Arc<RwLock<BanInfo>>,
     /// The server's player count.
-    player_count: Arc<AtomicU32>,
+    online_players: Arc<OnlinePlayers>,
     /// The server's icon, if any was loaded.
     server_icon: Arc<Option<String>>,
 
     /// The client's IP address.
     client_ip: SocketAddr,
+    /// The protocol version sent by the client
+    /// in its handshake.
+    protocol_version: u32,
 
     /// The player info, set to `Some` once
     /// the initial handler is finished and
@@ -152,7 +175,7 @@ impl InitialHandler {
     pub fn new(
         config: Arc<Config>,
         ban_info: Arc<RwLock<BanInfo>>,
-        player_count: Arc<AtomicU32>,
+        online_players: Arc<OnlinePlayers>,
         server_icon: Arc<Option<String>>,
         client_ip: SocketAddr,
     ) -> Self {
@@ -166,10 +189,11 @@ impl InitialHandler {
 
             config,
             ban_info,
-            player_count,
+            online_players,
             server_icon,
 
             client_ip,
+            protocol_version: 0,
 
             info: None,
 
@@ -220,6 +244,9 @@ async fn _handle_packet(ih: &mut InitialHandler, packet: Box<dyn Packet>) -> Res
         PacketType::EncryptionResponse => {
             handle_encryption_response(ih, &cast_packet::<EncryptionResponse>(packet)).await?
         }
+        PacketType::LoginPluginResponse => {
+            handle_login_plugin_response(ih, &cast_packet::<LoginPluginResponse>(packet))?
+        }
         ty => return Err(Error::InvalidPacket(ty, ih.stage)),
     }
 
@@ -229,6 +256,8 @@ async fn _handle_packet(ih: &mut InitialHandler, packet: Box<dyn Packet>) -> Res
 fn handle_handshake(ih: &mut InitialHandler, packet: &Handshake) -> Result<(), Error> {
     check_stage(ih, Stage::AwaitHandshake, packet.ty())?;
 
+    ih.protocol_version = packet.protocol_version;
+
     ih.stage = match packet.next_state {
         HandshakeState::Status => {
             ih.action_queue.push(Action::SetStage(PacketStage::Status));
@@ -237,20 +266,26 @@ fn handle_handshake(ih: &mut InitialHandler, packet: &Handshake) -> Result<(), E
         HandshakeState::Login => {
             // While status requests can use differing
             // protocol versions, a client
-            // needs to have a matching protocol version
+            // needs to have a supported protocol version
             // to log in.
-            if packet.protocol_version != PROTOCOL_VERSION {
-                return Err(Error::InvalidProtocol(packet.protocol_version));
-            }
+            let version = ProtocolVersion::from_id(packet.protocol_version)
+                .ok_or(Error::InvalidProtocol(packet.protocol_version))?;
+            ih.action_queue.push(Action::SetProtocolVersion(version));
 
             // If the server has BungeeCord proxy mode enabled, extract the data that is submitted
             // by BungeeCord if IP forwarding is enabled.
             if ih.config.proxy.proxy_mode == ProxyMode::BungeeCord {
                 let bungeecord_data = extract_bungeecord_data(packet)?;
+                let ip = bungeecord_data.client.parse().ok();
+                if let Some(ip) = ip {
+                    ih.client_ip = SocketAddr::new(ip, ih.client_ip.port());
+                }
+
                 ih.info = Some(JoinResult {
                     username: None,
                     uuid: bungeecord_data.uuid,
                     props: bungeecord_data.properties,
+                    ip,
                 });
             }
 
@@ -278,7 +313,13 @@ fn handle_handshake(ih: &mut InitialHandler, packet: &Handshake) -> Result<(), E
 /// | UUID            | The UUID that is associated to the clients account  |
 /// | Mojang response | A JSON formatted version of the `properties` field
 /// in [Mojangs response](https://wiki.vg/Protocol_Encryption#Server)       |
+///
+/// Clients connecting directly send only the host, so they are rejected.
 fn extract_bungeecord_data(packet: &Handshake) -> Result<BungeeCordData, Error> {
+    if !packet.server_address.contains('\0') {
+        return Err(Error::NotThroughBungeeCord);
+    }
+
     let bungee_information: Vec<&str> = packet.server_address.split('\0').collect();
     Ok(BungeeCordData::from_vec(&bungee_information)?)
 }
@@ -315,31 +356,28 @@ impl BungeeCordData {
 
 fn handle_request(ih: &mut InitialHandler, packet: &Request) -> Result<(), Error> {
     check_stage(ih, Stage::AwaitRequest, packet.ty())?;
-    let server_icon = (*ih.server_icon).clone().unwrap_or_default();
-
-    // Send response packet
-    let mut json = serde_json::json!({
-        "version": {
-            "name": SERVER_VERSION,
-            "protocol": PROTOCOL_VERSION,
-        },
-        "players": {
-            "max": ih.config.server.max_players,
-            "online": ih.player_count.load(Ordering::SeqCst),
-        },
-        "description": {
-            "text": ih.config.server.motd,
-        },
-        "favicon": server_icon,
-    });
 
-    // Remove the favicon field if there is no favicon
-    if server_icon.is_empty() {
-        json.as_object_mut().unwrap().remove("favicon");
-    }
+    // Clients using a supported version are shown
+    // their own, and others the newest one.
+    let version =
+        ProtocolVersion::from_id(ih.protocol_version).unwrap_or_else(ProtocolVersion::latest);
+
+    let config = &ih.config.server;
+    let mut status = StatusResponse::new(format!("{} {}", SERVER_NAME, version), version.id())
+        .with_motd(&config.motd)
+        .with_players(ih.online_players.count(), config.max_players)
+        .with_sample(ih.online_players.sample(config.player_sample_size))
+        .with_favicon((*ih.server_icon).clone());
+
+    let ping = StatusPing {
+        address: ih.client_ip,
+        protocol_version: ih.protocol_version,
+    };
+    run_status_hooks(&ping, &mut status);
 
+    // Send response packet
     let response = Response {
-        json_response: json.to_string(),
+        json_response: status.to_json().to_string(),
     };
     send_packet(ih, response);
 
@@ -366,58 +404,45 @@ fn handle_ping(ih: &mut InitialHandler, packet: &Ping) -> Result<(), Error> {
 fn handle_login_start(ih: &mut InitialHandler, packet: &LoginStart) -> Result<(), Error> {
     check_stage(ih, Stage::AwaitLoginStart, packet.ty())?;
 
-    if ih.player_count.load(Ordering::Acquire) >= ih.config.server.max_players as u32 {
+    if ih.online_players.count() >= ih.config.server.max_players as u32 {
         disconnect_login(ih, Text::from("Server is full!"));
         return Ok(());
     }
 
-    let (reason, remove_ban) = {
-        let ban_info = ih.ban_info.read().unwrap();
-        let ip_ban = ban_info.ip_bans.get(&ih.client_ip.ip());
+    if let Some(reason) = ip_ban_reason(ih) {
+        disconnect_login(ih, reason);
+        return Ok(());
+    }
 
-        if let Some(ban) = ip_ban {
-            // Expire the ban if it's over.
-            if let Some(expires) = ban.expires_after {
-                if expires < SystemTime::now() {
-                    (None, true)
-                } else {
-                    (Some(Text::from(ban.reason.clone())), false)
-                }
-            } else {
-                (Some(Text::from(ban.reason.clone())), false)
-            }
-        } else {
-            (None, false)
+    // Behind Velocity, the player's information is
+    // requested from the proxy instead.
+    if ih.config.proxy.proxy_mode == ProxyMode::Velocity {
+        if ih.config.proxy.velocity_secret.is_empty() {
+            return Err(Error::VelocitySecretNotSet);
         }
-    };
 
-    if let Some(reason) = reason {
-        disconnect_login(ih, reason);
+        let request = LoginPluginRequest {
+            message_id: VELOCITY_MESSAGE_ID,
+            channel: velocity::CHANNEL.to_string(),
+            data: vec![],
+        };
+        send_packet(ih, request);
+
+        ih.stage = Stage::AwaitLoginPluginResponse;
         return Ok(());
-    } else if remove_ban {
-        ih.ban_info
-            .write()
-            .unwrap()
-            .ip_bans
-            .remove(&ih.client_ip.ip());
     }
 
     // If in online mode, encryption needs to be enabled,
     // and authentication needs to be performed.
     // If not in online mode, the login sequence is
     // already finished, so we can call `finish` after
-    // setting the player's info.
-    if ih.config.server.online_mode {
-        use num_bigint_dig::{BigInt, Sign::Plus};
+    // setting the player's info. Behind BungeeCord,
+    // the proxy has authenticated the player already.
+    if ih.config.server.online_mode && ih.config.proxy.proxy_mode != ProxyMode::BungeeCord {
         // Start enabling encryption
-        let der = der::public_key_to_der(
-            &BigInt::from_biguint(Plus, RSA_KEY.n().clone()).to_signed_bytes_be(),
-            &BigInt::from_biguint(Plus, RSA_KEY.e().clone()).to_signed_bytes_be(),
-        );
-
         let encryption_request = EncryptionRequest {
             server_id: "".to_string(), // Server ID - always empty
-            public_key: der,
+            public_key: PUBLIC_KEY_DER.clone(),
             verify_token: ih.verify_token.to_vec(),
         };
         send_packet(ih, encryption_request);
@@ -451,6 +476,47 @@ async fn handle_encryption_response(
 ) -> Result<(), Error> {
     check_stage(ih, Stage::AwaitEncryptionResponse, packet.ty())?;
 
+    let key = enable_encryption(ih, packet)?;
+
+    // This unwrapping can be shorter with the use of .flatten() which will stabilize in Rust 1.40.
+    let username = ih
+        .info
+        .as_ref()
+        .map(|x| x.username.as_ref())
+        .and_then(|x| x)
+        .ok_or(Error::OptionIsNone)?;
+
+    // Perform authentication. The session server only knows
+    // the player if their client joined using the same shared
+    // secret and public key, which proves it is the account's owner.
+    let auth_result = mojang_api::server_auth(
+        &mojang_api::server_hash("", key, PUBLIC_KEY_DER.as_slice()),
+        username,
+    )
+    .await;
+
+    match auth_result {
+        Ok(auth) => {
+            let info = JoinResult {
+                username: Some(auth.name),
+                uuid: auth.id,
+                props: auth.properties,
+                ip: None,
+            };
+            ih.info = Some(info);
+        }
+        Err(e) => return Err(Error::AuthenticationFailed(e)),
+    }
+
+    finish(ih);
+
+    Ok(())
+}
+
+/// Decrypts the shared secret sent in an Encryption Response
+/// and enables encryption with it, after checking that the
+/// client encrypted the verify token correctly.
+fn enable_encryption(ih: &mut InitialHandler, packet: &EncryptionResponse) -> Result<Key, Error> {
     // Decrypt verify token + shared secret
     let shared_secret = decrypt_using_rsa(&packet.secret, &RSA_KEY)?;
     if shared_secret.len() != SHARED_SECRET_LEN {
@@ -474,47 +540,81 @@ async fn handle_encryption_response(
     }
 
     ih.key = Some(key);
-    ih.action_queue
-        .push(Action::EnableEncryption(ih.key.unwrap()));
+    ih.action_queue.push(Action::EnableEncryption(key));
 
-    use num_bigint_dig::{BigInt, Sign::Plus};
-    let der = der::public_key_to_der(
-        &BigInt::from_biguint(Plus, RSA_KEY.n().clone()).to_signed_bytes_be(),
-        &BigInt::from_biguint(Plus, RSA_KEY.e().clone()).to_signed_bytes_be(),
-    );
+    Ok(key)
+}
 
-    // This unwrapping can be shorter with the use of .flatten() which will stabilize in Rust 1.40.
-    let username = ih
-        .info
-        .as_ref()
-        .map(|x| x.username.as_ref())
-        .and_then(|x| x)
-        .ok_or(Error::OptionIsNone)?;
+fn handle_login_plugin_response(
+    ih: &mut InitialHandler,
+    packet: &LoginPluginResponse,
+) -> Result<(), Error> {
+    check_stage(ih, Stage::AwaitLoginPluginResponse, packet.ty())?;
+    if packet.message_id != VELOCITY_MESSAGE_ID {
+        return Err(Error::InvalidPacket(packet.ty(), ih.stage));
+    }
 
-    // Perform authentication
-    let auth_result = mojang_api::server_auth(
-        &mojang_api::server_hash("", ih.key.unwrap(), der.as_slice()),
-        username,
-    )
-    .await;
+    // Clients connecting directly don't understand the request.
+    if !packet.successful {
+        return Err(Error::NotThroughVelocity);
+    }
 
-    match auth_result {
-        Ok(auth) => {
-            let info = JoinResult {
-                username: Some(auth.name),
-                uuid: auth.id,
-                props: auth.properties,
-            };
-            ih.info = Some(info);
-        }
-        Err(e) => return Err(Error::AuthenticationFailed(e)),
+    let secret = ih.config.proxy.velocity_secret.as_bytes();
+    let info = velocity::read_player_info(&packet.data, secret)?;
+
+    // The ban check in Login Start saw the proxy's address.
+    ih.client_ip = SocketAddr::new(info.address, ih.client_ip.port());
+    if let Some(reason) = ip_ban_reason(ih) {
+        disconnect_login(ih, reason);
+        return Ok(());
     }
 
+    ih.info = Some(JoinResult {
+        username: Some(info.username),
+        uuid: info.uuid,
+        props: info.properties,
+        ip: Some(info.address),
+    });
+
     finish(ih);
 
     Ok(())
 }
 
+/// Returns the reason the client's IP address is banned
+/// for, if it is. Expired bans are removed.
+fn ip_ban_reason(ih: &mut InitialHandler) -> Option<Text> {
+    let (reason, remove_ban) = {
+        let ban_info = ih.ban_info.read().unwrap();
+        let ip_ban = ban_info.ip_bans.get(&ih.client_ip.ip());
+
+        if let Some(ban) = ip_ban {
+            // Expire the ban if it's over.
+            if let Some(expires) = ban.expires_after {
+                if expires < SystemTime::now() {
+                    (None, true)
+                } else {
+                    (Some(Text::from(ban.reason.clone())), false)
+                }
+            } else {
+                (Some(Text::from(ban.reason.clone())), false)
+            }
+        } else {
+            (None, false)
+        }
+    };
+
+    if remove_ban {
+        ih.ban_info
+            .write()
+            .unwrap()
+            .ip_bans
+            .remove(&ih.client_ip.ip());
+    }
+
+    reason
+}
+
 fn decrypt_using_rsa(data: &[u8], key: &RSAPrivateKey) -> Result<Vec<u8>, Error> {
     let buf = key
         .decrypt(PaddingScheme::PKCS1v15, data)
@@ -534,9 +634,10 @@ fn finish(ih: &mut InitialHandler) {
     assert!(ih.info.is_some());
     assert!(ih.info.as_ref().unwrap().username.is_some());
 
-    // Enable compression if necessary
+    // Enable compression if necessary. As in vanilla,
+    // a negative threshold disables compression.
     let compression_threshold = ih.config.io.compression_threshold;
-    if compression_threshold > 0 {
+    if compression_threshold >= 0 {
         enable_compression(ih, compression_threshold);
     }
 
@@ -638,6 +739,14 @@ enum Error {
     AuthenticationFailed(mojang_api::Error),
     #[error("received BungeeCord data does not match the specification: {0}")]
     BungeeSpecMismatch(String),
+    #[error("this server must be joined through BungeeCord, with IP forwarding enabled")]
+    NotThroughBungeeCord,
+    #[error("the Velocity forwarding secret is not set")]
+    VelocitySecretNotSet,
+    #[error("this server must be joined through Velocity")]
+    NotThroughVelocity,
+    #[error("Velocity forwarding failed: {0}")]
+    VelocityForwarding(#[from] velocity::Error),
     #[error("option that should not be None was None")]
     /// An Error type than can be used as the error type of using the Try operator on Option
     /// types. In rust-core, this is an unstable feature (issue #42327)
@@ -652,6 +761,7 @@ enum Stage {
     AwaitPing,
     AwaitLoginStart,
     AwaitEncryptionResponse,
+    AwaitLoginPluginResponse,
     Finished,
 }
 
@@ -663,15 +773,13 @@ mod tests {
     };
     use feather_core::network::PacketType;
 
-    use crate::PROTOCOL_VERSION;
-
     use super::*;
     use mojang_api::ProfileProperty;
 
     #[test]
     fn extract_bungeecord_data_normal() {
         let handshake = Handshake {
-           protocol_version: PROTOCOL_VERSION,
+           protocol_version: ProtocolVersion::V1_13_2.id(),
            server_address: "192.168.1.87\0192.168.1.67\0905c7e4fb96b45139645d123225575e2\0[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
            server_port: 25565,
            next_state: HandshakeState::Login,
@@ -695,7 +803,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_too_short() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: "192.168.1.87\0192.168.1.67\0905c7e4fb96b45139645d123225575e2"
                 .to_string(),
             server_port: 25565,
@@ -711,7 +819,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_too_long() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: "192.168.1.87\0192.168.1.67\0905c7e4fb96b45139645d123225575e2\0a\0b"
                 .to_string(),
             server_port: 25565,
@@ -727,7 +835,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_localhost_host_ip() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: "localhost\0192.168.1.67\0905c7e4fb96b45139645d123225575e2\0[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -751,7 +859,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_localhost_client_ip() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: "192.168.1.87\0localhost\0905c7e4fb96b45139645d123225575e2\0[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -775,7 +883,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_invalid_uuid() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: "192.168.1.87\0192.168.1.67\005c7e4fb9675e2\0[{\"name\":\"textures\",\"value\":\"textures_value\",\"signature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -792,7 +900,7 @@ mod tests {
     #[test]
     fn extract_bungeecord_data_invalid_properties() {
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: "192.168.1.87\0192.168.1.67\0905c7e4fb96b45139645d123225575e2\0[{\"name\":\"textures\",\"value\":\"textures_value\",\"sinature\":\"textures_signature\"}]".to_string(),
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -806,6 +914,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn extract_bungeecord_data_not_proxied() {
+        let handshake = Handshake {
+            protocol_version: ProtocolVersion::V1_13_2.id(),
+            server_address: "192.168.1.87".to_string(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+
+        assert_eq!(
+            extract_bungeecord_data(&handshake).err().unwrap(),
+            Error::NotThroughBungeeCord
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bungeecord_login() {
+        let mut config = Config::default();
+        config.proxy.proxy_mode = ProxyMode::BungeeCord;
+        assert!(config.server.online_mode);
+        let mut ih = ih_with_config(config);
+
+        let handshake = Handshake {
+            protocol_version: ProtocolVersion::V1_13_2.id(),
+            server_address: "192.168.1.87\0192.168.1.67\0905c7e4fb96b45139645d123225575e2\0[]"
+                .to_string(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        assert_eq!(ih.client_ip.ip(), "192.168.1.67".parse::<IpAddr>().unwrap());
+
+        let login_start = LoginStart {
+            username: String::from("test"),
+        };
+        ih.handle_packet(Box::new(login_start)).await;
+
+        // The proxy authenticates players, so the
+        // login finishes without encryption.
+        let info = ih.info.clone().unwrap();
+        assert_eq!(info.username.as_deref(), Some("test"));
+        assert_eq!(
+            info.uuid,
+            Uuid::parse_str("905c7e4fb96b45139645d123225575e2").unwrap()
+        );
+        assert_eq!(info.ip, Some("192.168.1.67".parse().unwrap()));
+        match ih.actions_to_execute().pop().unwrap() {
+            Action::JoinGame(_) => (),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn test_initial_handler_new() {
         let mut ih = ih();
@@ -819,7 +979,7 @@ mod tests {
         let mut ih = ih_with_player_count(player_count);
 
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: String::default(), // Unused - server address
             server_port: 25565,
             next_state: HandshakeState::Status,
@@ -848,7 +1008,15 @@ mod tests {
                 assert_eq!(response.ty(), PacketType::Response);
 
                 let response = cast_packet::<Response>(response);
-                let _: serde_json::Value = serde_json::from_str(&response.json_response).unwrap();
+                let json: serde_json::Value =
+                    serde_json::from_str(&response.json_response).unwrap();
+                assert_eq!(json["version"]["protocol"], 404);
+                assert_eq!(json["version"]["name"], "Feather 1.13.2");
+                assert_eq!(json["players"]["online"], player_count);
+                assert_eq!(json["players"]["max"], 16);
+                assert_eq!(json["players"]["sample"].as_array().unwrap().len(), 12);
+                assert_eq!(json["description"]["text"], "A Feather server");
+                assert_eq!(json["favicon"], "test");
             }
             _ => panic!(),
         }
@@ -878,6 +1046,37 @@ mod tests {
         }
     }
 
+    async fn status_response(protocol_version: u32) -> serde_json::Value {
+        let mut ih = ih();
+        let handshake = Handshake {
+            protocol_version,
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Status,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        ih.handle_packet(Box::new(Request {})).await;
+
+        match ih.actions_to_execute().pop().unwrap() {
+            Action::SendPacket(response) => {
+                let response = cast_packet::<Response>(response);
+                serde_json::from_str(&response.json_response).unwrap()
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_version() {
+        let json = status_response(ProtocolVersion::V1_16_5.id()).await;
+        assert_eq!(json["version"]["protocol"], 754);
+        assert_eq!(json["version"]["name"], "Feather 1.16.5");
+
+        // Unsupported versions are shown the newest one.
+        let json = status_response(578).await;
+        assert_eq!(json["version"]["protocol"], ProtocolVersion::latest().id());
+    }
+
     #[tokio::test]
     async fn test_login_sequence() {
         let mut config = Config::default();
@@ -885,7 +1084,7 @@ mod tests {
         let mut ih = ih_with_config(config.clone());
 
         let handshake = Handshake {
-            protocol_version: PROTOCOL_VERSION,
+            protocol_version: ProtocolVersion::V1_13_2.id(),
             server_address: String::default(), // Unused - server address
             server_port: 25565,
             next_state: HandshakeState::Login,
@@ -893,8 +1092,14 @@ mod tests {
         ih.handle_packet(Box::new(handshake)).await;
 
         let actions = ih.actions_to_execute();
-        assert_eq!(actions.len(), 1);
-        match actions.first().unwrap() {
+        assert_eq!(actions.len(), 2);
+        match &actions[0] {
+            Action::SetProtocolVersion(version) => {
+                assert_eq!(*version, ProtocolVersion::V1_13_2)
+            }
+            _ => panic!(),
+        }
+        match &actions[1] {
             Action::SetStage(stage) => assert_eq!(*stage, PacketStage::Login),
             _ => panic!(),
         }
@@ -952,21 +1157,164 @@ mod tests {
         }
     }
 
+    /// Starts logging in to a server in online mode,
+    /// returning the Encryption Request sent in response.
+    async fn start_online_login(ih: &mut InitialHandler) -> EncryptionRequest {
+        let handshake = Handshake {
+            protocol_version: ProtocolVersion::V1_13_2.id(),
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        let login_start = LoginStart {
+            username: String::from("test"),
+        };
+        ih.handle_packet(Box::new(login_start)).await;
+
+        let mut actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 3);
+        match actions.pop().unwrap() {
+            Action::SendPacket(packet) => cast_packet::<EncryptionRequest>(packet),
+            _ => panic!(),
+        }
+    }
+
+    fn encryption_response(secret: &[u8], verify_token: &[u8]) -> EncryptionResponse {
+        let mut rng = OsRng;
+        let secret = RSA_KEY
+            .encrypt(&mut rng, PaddingScheme::PKCS1v15, secret)
+            .unwrap();
+        let verify_token = RSA_KEY
+            .encrypt(&mut rng, PaddingScheme::PKCS1v15, verify_token)
+            .unwrap();
+        EncryptionResponse {
+            secret_length: secret.len() as i32,
+            secret,
+            verify_token_length: verify_token.len() as i32,
+            verify_token,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encryption_request() {
+        let mut ih = ih();
+        assert!(ih.config.server.online_mode);
+
+        let request = start_online_login(&mut ih).await;
+        assert_eq!(request.server_id, "");
+        assert_eq!(request.public_key, *PUBLIC_KEY_DER);
+        assert_eq!(request.verify_token, ih.verify_token);
+        assert_eq!(ih.stage, Stage::AwaitEncryptionResponse);
+    }
+
+    #[tokio::test]
+    async fn test_encryption_response() {
+        let mut ih = ih();
+        let request = start_online_login(&mut ih).await;
+
+        let secret = [7; SHARED_SECRET_LEN];
+        let response = encryption_response(&secret, &request.verify_token);
+        assert_eq!(enable_encryption(&mut ih, &response), Ok(secret));
+
+        let actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 1);
+        match &actions[0] {
+            Action::EnableEncryption(key) => assert_eq!(*key, secret),
+            _ => panic!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encryption_response_bad_verify_token() {
+        let mut ih = ih();
+        let request = start_online_login(&mut ih).await;
+
+        let verify_token: Vec<u8> = request.verify_token.iter().map(|b| b ^ 1).collect();
+        let response = encryption_response(&[7; SHARED_SECRET_LEN], &verify_token);
+        ih.handle_packet(Box::new(response)).await;
+
+        // The player is disconnected before encryption is enabled.
+        let actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 2);
+        match &actions[0] {
+            Action::SendPacket(packet) => assert_eq!(packet.ty(), PacketType::DisconnectLogin),
+            _ => panic!(),
+        }
+        match &actions[1] {
+            Action::Disconnect => (),
+            _ => panic!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_velocity_login() {
+        let mut config = Config::default();
+        config.proxy.proxy_mode = ProxyMode::Velocity;
+        config.proxy.velocity_secret = String::from("secret");
+        let mut ih = ih_with_config(config);
+
+        let handshake = Handshake {
+            protocol_version: ProtocolVersion::V1_13_2.id(),
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        let login_start = LoginStart {
+            username: String::from("test"),
+        };
+        ih.handle_packet(Box::new(login_start)).await;
+
+        // The player's information is requested
+        // from the proxy, without encryption.
+        let mut actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 3);
+        match actions.pop().unwrap() {
+            Action::SendPacket(packet) => {
+                let request = cast_packet::<LoginPluginRequest>(packet);
+                assert_eq!(request.channel, "velocity:player_info");
+                assert_eq!(request.message_id, VELOCITY_MESSAGE_ID);
+            }
+            _ => panic!(),
+        }
+
+        // Clients connecting directly don't know the channel.
+        let response = LoginPluginResponse {
+            message_id: VELOCITY_MESSAGE_ID,
+            successful: false,
+            data: vec![],
+        };
+        ih.handle_packet(Box::new(response)).await;
+
+        let actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 2);
+        match &actions[1] {
+            Action::Disconnect => (),
+            _ => panic!(),
+        }
+    }
+
     fn ih() -> InitialHandler {
         InitialHandler::new(
             Arc::new(Config::default()),
             Arc::new(RwLock::new(BanInfo::default())),
-            Arc::new(AtomicU32::new(0)),
+            Arc::new(OnlinePlayers::new()),
             Arc::new(Some(String::from("test"))),
             "127.0.0.1:8080".parse().unwrap(),
         )
     }
 
     fn ih_with_player_count(count: u32) -> InitialHandler {
+        let online_players = OnlinePlayers::new();
+        for i in 0..count {
+            online_players.add(Uuid::from_u128(i.into()), format!("player{}", i));
+        }
+
         InitialHandler::new(
             Arc::new(Config::default()),
             Arc::new(RwLock::new(BanInfo::default())),
-            Arc::new(AtomicU32::new(count)),
+            Arc::new(online_players),
             Arc::new(Some(String::from("test"))),
             "127.0.0.1:8080".parse().unwrap(),
         )
@@ -976,7 +1324,7 @@ mod tests {
         InitialHandler::new(
             Arc::new(config),
             Arc::new(RwLock::new(BanInfo::default())),
-            Arc::new(AtomicU32::new(0)),
+            Arc::new(OnlinePlayers::new()),
             Arc::new(Some(String::from("test"))),
             "127.0.0.1:8080".parse().unwrap(),
         )
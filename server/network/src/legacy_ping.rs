@@ -0,0 +1,168 @@
+//! Server list pings sent by clients older than 1.7, which
+//! predate the current protocol. Some server list crawlers
+//! still send them.
+//!
+//! A legacy ping starts with the byte 0xFE, which can't start
+//! a valid handshake in the current protocol except in rare
+//! cases, distinguished by the bytes which follow. The server
+//! responds with a kick packet holding its status, then closes
+//! the connection.
+
+use crate::SERVER_NAME;
+use feather_core::network::ProtocolVersion;
+use feather_server_types::{run_status_hooks, Config, OnlinePlayers, StatusPing, StatusResponse};
+use std::net::SocketAddr;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+/// The protocol version sent in responses,
+/// which no legacy client matches.
+const PROTOCOL_VERSION: u32 = 127;
+
+/// The formats of legacy pings and their responses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Sent by clients from Beta 1.8 to 1.3. The response only
+    /// holds the MOTD and the player counts.
+    Beta,
+    /// Sent by clients from 1.4 to 1.6. The response
+    /// also holds the server's version.
+    V1_4,
+}
+
+impl LegacyPing {
+    /// Detects a legacy ping from the first bytes sent by a client,
+    /// returning `None` if the client speaks the current protocol.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xFE] => Some(LegacyPing::Beta),
+            // 1.6 clients follow with a plugin message
+            // on the MC|PingHost channel, with ID 0xFA.
+            [0xFE, 0x01] | [0xFE, 0x01, 0xFA, ..] => Some(LegacyPing::V1_4),
+            _ => None,
+        }
+    }
+
+    /// Encodes the kick packet sent in response to this ping.
+    pub fn response(self, status: &StatusResponse) -> Vec<u8> {
+        let message = match self {
+            // `§` separates the fields, so formatting codes are removed.
+            LegacyPing::Beta => format!(
+                "{}§{}§{}",
+                strip_formatting(&status.motd),
+                status.online_players,
+                status.max_players
+            ),
+            LegacyPing::V1_4 => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                status.protocol,
+                status.version_name,
+                status.motd,
+                status.online_players,
+                status.max_players
+            ),
+        };
+
+        // The message is a string of UTF-16 code units,
+        // preceded by their number.
+        let units: Vec<u16> = message.encode_utf16().collect();
+        let mut packet = vec![0xFF];
+        packet.extend_from_slice(&(units.len() as u16).to_be_bytes());
+        for unit in units {
+            packet.extend_from_slice(&unit.to_be_bytes());
+        }
+        packet
+    }
+}
+
+/// Removes `§` formatting codes from a string.
+fn strip_formatting(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Checks whether a client sent a legacy ping,
+/// without consuming any data from the stream.
+pub async fn detect(stream: &mut TcpStream) -> tokio::io::Result<Option<LegacyPing>> {
+    let mut buf = [0; 3];
+    let read = stream.peek(&mut buf).await?;
+    Ok(LegacyPing::detect(&buf[..read]))
+}
+
+/// Responds to a legacy ping, then closes the connection.
+pub async fn respond(
+    mut stream: TcpStream,
+    ping: LegacyPing,
+    ip: SocketAddr,
+    config: &Config,
+    online_players: &OnlinePlayers,
+) -> tokio::io::Result<()> {
+    let version_name = format!("{} {}", SERVER_NAME, ProtocolVersion::latest());
+    let mut status = StatusResponse::new(version_name, PROTOCOL_VERSION)
+        .with_motd(&config.server.motd)
+        .with_players(online_players.count(), config.server.max_players);
+
+    let status_ping = StatusPing {
+        address: ip,
+        protocol_version: PROTOCOL_VERSION,
+    };
+    run_status_hooks(&status_ping, &mut status);
+
+    stream.write_all(&ping.response(&status)).await?;
+    stream.shutdown(std::net::Shutdown::Both)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_legacy_pings() {
+        assert_eq!(LegacyPing::detect(&[0xFE]), Some(LegacyPing::Beta));
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01]), Some(LegacyPing::V1_4));
+        assert_eq!(
+            LegacyPing::detect(&[0xFE, 0x01, 0xFA]),
+            Some(LegacyPing::V1_4)
+        );
+
+        // A handshake of length 254, which starts with 0xFE 0x01.
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01, 0x00]), None);
+        assert_eq!(LegacyPing::detect(&[0x10, 0x00, 0xF2]), None);
+        assert_eq!(LegacyPing::detect(&[]), None);
+    }
+
+    fn decode(packet: &[u8]) -> String {
+        assert_eq!(packet[0], 0xFF);
+        let len = u16::from_be_bytes([packet[1], packet[2]]) as usize;
+        let units: Vec<u16> = packet[3..]
+            .chunks(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        assert_eq!(units.len(), len);
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    fn responses() {
+        let status = StatusResponse::new("Feather 1.18.1", PROTOCOL_VERSION)
+            .with_motd("&aA Feather server")
+            .with_players(3, 16);
+
+        assert_eq!(
+            decode(&LegacyPing::Beta.response(&status)),
+            "A Feather server§3§16"
+        );
+        assert_eq!(
+            decode(&LegacyPing::V1_4.response(&status)),
+            "§1\u{0}127\u{0}Feather 1.18.1\u{0}§aA Feather server\u{0}3\u{0}16"
+        );
+    }
+}
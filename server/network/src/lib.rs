@@ -4,8 +4,7 @@
 //! and Tokio. Contains a listener task which accepts new connections
 //! and a worker task for each client which reads and writes packets.
 
-pub const PROTOCOL_VERSION: u32 = 404;
-pub const SERVER_VERSION: &str = "Feather 1.13.2";
+pub const SERVER_NAME: &str = "Feather";
 
 #[macro_use]
 extern crate feather_core;
@@ -14,20 +13,29 @@ use derivative::Derivative;
 use feather_core::anvil::player::PlayerData;
 use feather_core::util::Position;
 use feather_server_types::{
-    Config, PacketBuffers, ServerToWorkerMessage, Uuid, WorkerToServerMessage, WrappedBanInfo,
+    Config, OnlinePlayers, PacketBuffers, ServerToWorkerMessage, Uuid, WorkerToServerMessage,
+    WrappedBanInfo,
 };
 use fecs::Entity;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use std::net::SocketAddr;
-use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 
 mod initial_handler;
+mod legacy_ping;
 mod listener;
+mod proxy_protocol;
+mod query;
+mod rate_limit;
+mod rcon;
+mod velocity;
 mod worker;
 
+pub use query::run_query;
+pub use rcon::{RconCommand, RconHandle};
+
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum ListenerToServerMessage {
@@ -80,33 +88,38 @@ pub struct NetworkIoManager {
 }
 
 impl NetworkIoManager {
-    /// Starts a new IO listener.
+    /// Starts a listener task for each of the given sockets. Connections
+    /// accepted on a socket are handled with the config it is paired with.
     pub fn start(
-        listener: TcpListener,
-        config: Arc<Config>,
+        listeners: Vec<(TcpListener, Arc<Config>)>,
         ban_info: WrappedBanInfo,
-        player_count: Arc<AtomicU32>,
+        online_players: Arc<OnlinePlayers>,
         server_icon: Arc<Option<String>>,
         packet_buffers: Arc<PacketBuffers>,
     ) -> Self {
         let (listener_tx, rx) = flume::bounded(16);
         let (tx, listener_rx) = flume::bounded(16);
-
-        let future = run_listener(
-            listener,
-            listener_tx.clone(),
-            listener_rx,
-            (config, ban_info),
-            player_count,
-            server_icon,
-            packet_buffers,
-        );
-
-        if cfg!(test) {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.spawn(future);
-        } else {
-            tokio::spawn(future);
+        // Shared by the listeners, whose workers lock it
+        // while waiting for the server's response.
+        let listener_rx = Arc::new(tokio::sync::Mutex::new(listener_rx));
+
+        for (listener, config) in listeners {
+            let future = run_listener(
+                listener,
+                listener_tx.clone(),
+                Arc::clone(&listener_rx),
+                (config, Arc::clone(&ban_info)),
+                Arc::clone(&online_players),
+                Arc::clone(&server_icon),
+                Arc::clone(&packet_buffers),
+            );
+
+            if cfg!(test) {
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                rt.spawn(future);
+            } else {
+                tokio::spawn(future);
+            }
         }
 
         Self {
@@ -119,15 +132,15 @@ impl NetworkIoManager {
 
 /// Initializes certain static variables.
 pub fn init() {
-    Lazy::force(&initial_handler::RSA_KEY);
+    Lazy::force(&initial_handler::PUBLIC_KEY_DER);
 }
 
 async fn run_listener(
     listener: TcpListener,
     tx: flume::Sender<ListenerToServerMessage>,
-    rx: flume::Receiver<ServerToListenerMessage>,
+    rx: Arc<tokio::sync::Mutex<flume::Receiver<ServerToListenerMessage>>>,
     config_bans: (Arc<Config>, WrappedBanInfo),
-    player_count: Arc<AtomicU32>,
+    online_players: Arc<OnlinePlayers>,
     server_icon: Arc<Option<String>>,
     packet_buffers: Arc<PacketBuffers>,
 ) {
@@ -136,7 +149,7 @@ async fn run_listener(
         tx,
         rx,
         config_bans,
-        player_count,
+        online_players,
         server_icon,
         packet_buffers,
     )
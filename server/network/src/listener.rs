@@ -1,13 +1,13 @@
 //! Listener Tokio task.
 //!
 //! This task listens on a `TcpListener` and accepts
-//! connections, spawning worker tasks to handle them,4.
+//! connections, spawning worker tasks to handle them.
+//! One task is run for each address the server listens on.
 
 use crate::worker::run_worker;
 use crate::{ListenerToServerMessage, ServerToListenerMessage};
-use feather_server_types::{Config, PacketBuffers, WrappedBanInfo};
+use feather_server_types::{Config, OnlinePlayers, PacketBuffers, WrappedBanInfo};
 
-use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
 use tokio::io;
 use tokio::net::TcpListener;
@@ -16,14 +16,12 @@ use tokio::sync::Mutex;
 pub async fn run_listener(
     mut listener: TcpListener,
     tx: flume::Sender<ListenerToServerMessage>,
-    rx: flume::Receiver<ServerToListenerMessage>,
+    rx: Arc<Mutex<flume::Receiver<ServerToListenerMessage>>>,
     config_bans: (Arc<Config>, WrappedBanInfo),
-    player_count: Arc<AtomicU32>,
+    online_players: Arc<OnlinePlayers>,
     server_icon: Arc<Option<String>>,
     packet_buffers: Arc<PacketBuffers>,
 ) -> Result<(), io::Error> {
-    let rx = Arc::new(Mutex::new(rx));
-
     loop {
         let (stream, ip) = match listener.accept().await {
             Ok(res) => res,
@@ -42,7 +40,7 @@ pub async fn run_listener(
             Arc::clone(&rx),
             Arc::clone(&config_bans.0),
             Arc::clone(&config_bans.1),
-            Arc::clone(&player_count),
+            Arc::clone(&online_players),
             Arc::clone(&server_icon),
             Arc::clone(&packet_buffers),
         ));
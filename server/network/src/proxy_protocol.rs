@@ -0,0 +1,182 @@
+//! The PROXY protocol, with which TCP load balancers such as
+//! HAProxy pass the address of the client to the server.
+//!
+//! The load balancer sends a header before any data from the
+//! client: a line of text in version 1, or a binary header in
+//! version 2. Both are accepted when the protocol is enabled.
+//! Connections without a header are refused, since their
+//! client address can't be known.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use thiserror::Error;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+/// The signature starting a version 2 header.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// The maximum length of a version 1 header, including the CRLF.
+const V1_MAX_LEN: usize = 107;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("connection does not start with a PROXY protocol header")]
+    MissingHeader,
+    #[error("malformed PROXY protocol header")]
+    Malformed,
+    #[error("unsupported PROXY protocol version {0}")]
+    UnsupportedVersion(u8),
+}
+
+/// Reads the PROXY protocol header from a new connection,
+/// returning the address of the client, or `None` if the
+/// load balancer didn't provide one (e.g. for health checks).
+pub async fn read_header(stream: &mut TcpStream) -> anyhow::Result<Option<SocketAddr>> {
+    let mut signature = [0; 12];
+    stream.read_exact(&mut signature[..5]).await?;
+
+    if signature[..5] == *b"PROXY" {
+        // Read the rest of the line.
+        let mut line = signature[..5].to_vec();
+        while !line.ends_with(b"\r\n") {
+            if line.len() >= V1_MAX_LEN {
+                return Err(Error::Malformed.into());
+            }
+            line.push(stream.read_u8().await?);
+        }
+
+        let line = std::str::from_utf8(&line).map_err(|_| Error::Malformed)?;
+        return Ok(parse_v1(line)?);
+    }
+
+    stream.read_exact(&mut signature[5..]).await?;
+    if signature != V2_SIGNATURE {
+        return Err(Error::MissingHeader.into());
+    }
+
+    let version_command = stream.read_u8().await?;
+    let family = stream.read_u8().await?;
+    let len = stream.read_u16().await?;
+    let mut addresses = vec![0; len as usize];
+    stream.read_exact(&mut addresses).await?;
+
+    Ok(parse_v2(version_command, family, &addresses)?)
+}
+
+/// Parses a version 1 header, e.g.
+/// `PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n`.
+fn parse_v1(line: &str) -> Result<Option<SocketAddr>, Error> {
+    let mut fields = line.trim_end_matches("\r\n").split(' ');
+    if fields.next() != Some("PROXY") {
+        return Err(Error::Malformed);
+    }
+
+    match fields.next() {
+        Some("TCP4") | Some("TCP6") => (),
+        // The rest of the line is ignored.
+        Some("UNKNOWN") => return Ok(None),
+        _ => return Err(Error::Malformed),
+    }
+
+    let fields: Vec<&str> = fields.collect();
+    match fields.as_slice() {
+        [source, _destination, source_port, _destination_port] => {
+            let ip = IpAddr::from_str(source).map_err(|_| Error::Malformed)?;
+            let port = u16::from_str(source_port).map_err(|_| Error::Malformed)?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(Error::Malformed),
+    }
+}
+
+/// Parses the fields of a version 2 header following the signature.
+fn parse_v2(
+    version_command: u8,
+    family: u8,
+    addresses: &[u8],
+) -> Result<Option<SocketAddr>, Error> {
+    let version = version_command >> 4;
+    if version != 2 {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    match version_command & 0x0F {
+        // LOCAL: the connection was made by the load balancer itself.
+        0x0 => return Ok(None),
+        // PROXY
+        0x1 => (),
+        _ => return Err(Error::Malformed),
+    }
+
+    // The high nibble of the family is the address family and
+    // the low one the transport protocol, of which 1 is TCP.
+    match family {
+        // TCP over IPv4
+        0x11 => {
+            if addresses.len() < 12 {
+                return Err(Error::Malformed);
+            }
+            let mut ip = [0; 4];
+            ip.copy_from_slice(&addresses[..4]);
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            Ok(Some(SocketAddr::new(Ipv4Addr::from(ip).into(), port)))
+        }
+        // TCP over IPv6
+        0x21 => {
+            if addresses.len() < 36 {
+                return Err(Error::Malformed);
+            }
+            let mut ip = [0; 16];
+            ip.copy_from_slice(&addresses[..16]);
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            Ok(Some(SocketAddr::new(Ipv6Addr::from(ip).into(), port)))
+        }
+        // Unspecified or not TCP; the address is unknown.
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1() {
+        assert_eq!(
+            parse_v1("PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n"),
+            Ok(Some("192.0.2.1:56324".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_v1("PROXY TCP6 2001:db8::1 2001:db8::2 4000 25565\r\n"),
+            Ok(Some("[2001:db8::1]:4000".parse().unwrap()))
+        );
+        assert_eq!(parse_v1("PROXY UNKNOWN\r\n"), Ok(None));
+        assert_eq!(
+            parse_v1("PROXY TCP4 192.0.2.1 56324\r\n"),
+            Err(Error::Malformed)
+        );
+        assert_eq!(
+            parse_v1("PROXY UDP4 192.0.2.1 198.51.100.1 1 2\r\n"),
+            Err(Error::Malformed)
+        );
+    }
+
+    #[test]
+    fn v2() {
+        let addresses = [
+            192, 0, 2, 1, 198, 51, 100, 1, 0xDC, 0x04, 0x63, 0xDD, // TLVs may follow
+            0x04, 0x00, 0x01, 0x00,
+        ];
+        assert_eq!(
+            parse_v2(0x21, 0x11, &addresses),
+            Ok(Some("192.0.2.1:56324".parse().unwrap()))
+        );
+        assert_eq!(parse_v2(0x20, 0x11, &addresses), Ok(None));
+        assert_eq!(parse_v2(0x21, 0x00, &[]), Ok(None));
+        assert_eq!(parse_v2(0x21, 0x11, &addresses[..8]), Err(Error::Malformed));
+        assert_eq!(
+            parse_v2(0x11, 0x11, &addresses),
+            Err(Error::UnsupportedVersion(1))
+        );
+    }
+}
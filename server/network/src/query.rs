@@ -0,0 +1,288 @@
+//! The GameSpy 4 query protocol, through which hosting panels
+//! and server lists fetch the server's status over UDP.
+//!
+//! Clients first send a handshake to receive a challenge token,
+//! which proves that they aren't spoofing their address. They
+//! then request either the basic status, with the MOTD and player
+//! counts, or the full status, which adds the player list.
+
+use crate::SERVER_NAME;
+use feather_core::network::ProtocolVersion;
+use feather_server_types::{translate_color_codes, Config, OnlinePlayers};
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+const HANDSHAKE: u8 = 9;
+const STAT: u8 = 0;
+
+/// How long a challenge token may be used after a handshake.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+/// The size of the buffer requests are received into, which
+/// is larger than any valid request so that longer ones
+/// are rejected rather than truncated.
+const BUFFER_LEN: usize = 64;
+
+/// Padding sent before the key-value pairs of the full stat response.
+const KEY_VALUES_PADDING: &[u8] = b"splitnum\0\x80\0";
+/// Padding sent before the player list of the full stat response.
+const PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Request {
+    Handshake {
+        session_id: i32,
+    },
+    BasicStat {
+        session_id: i32,
+        token: i32,
+    },
+    /// Sent with four bytes of padding after the token.
+    FullStat {
+        session_id: i32,
+        token: i32,
+    },
+}
+
+impl Request {
+    fn parse(data: &[u8]) -> Option<Self> {
+        // Requests start with the magic bytes 0xFE 0xFD.
+        let (kind, session_id, payload) = match data {
+            [0xFE, 0xFD, kind, a, b, c, d, payload @ ..] => {
+                (*kind, i32::from_be_bytes([*a, *b, *c, *d]), payload)
+            }
+            _ => return None,
+        };
+
+        match (kind, payload) {
+            (HANDSHAKE, []) => Some(Request::Handshake { session_id }),
+            (STAT, [a, b, c, d]) => Some(Request::BasicStat {
+                session_id,
+                token: i32::from_be_bytes([*a, *b, *c, *d]),
+            }),
+            (STAT, [a, b, c, d, _, _, _, _]) => Some(Request::FullStat {
+                session_id,
+                token: i32::from_be_bytes([*a, *b, *c, *d]),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The server's status, as sent in stat responses.
+#[derive(Clone, Debug)]
+struct QueryInfo {
+    motd: String,
+    version: String,
+    /// The server software and its plugins, in
+    /// the format `Server: plugin; plugin`.
+    plugins: String,
+    map: String,
+    online_players: u32,
+    max_players: i32,
+    host_ip: String,
+    host_port: u16,
+    players: Vec<String>,
+}
+
+impl QueryInfo {
+    fn new(config: &Config, online_players: &OnlinePlayers) -> Self {
+        let version = ProtocolVersion::latest().to_string();
+        Self {
+            motd: translate_color_codes(&config.server.motd),
+            plugins: format!("{} {}", SERVER_NAME, version),
+            version,
+            map: config.world.name.clone(),
+            online_players: online_players.count(),
+            max_players: config.server.max_players,
+            host_ip: config.server.address.clone(),
+            host_port: config.server.port,
+            players: online_players.names(),
+        }
+    }
+}
+
+/// Writes a null-terminated string.
+fn push_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+}
+
+fn response_header(kind: u8, session_id: i32) -> Vec<u8> {
+    let mut buf = vec![kind];
+    buf.extend_from_slice(&session_id.to_be_bytes());
+    buf
+}
+
+fn handshake_response(session_id: i32, token: i32) -> Vec<u8> {
+    let mut buf = response_header(HANDSHAKE, session_id);
+    push_string(&mut buf, &token.to_string());
+    buf
+}
+
+fn basic_stat_response(session_id: i32, info: &QueryInfo) -> Vec<u8> {
+    let mut buf = response_header(STAT, session_id);
+    push_string(&mut buf, &info.motd);
+    push_string(&mut buf, "SMP");
+    push_string(&mut buf, &info.map);
+    push_string(&mut buf, &info.online_players.to_string());
+    push_string(&mut buf, &info.max_players.to_string());
+    // Unlike the rest of the protocol, the port is little-endian.
+    buf.extend_from_slice(&info.host_port.to_le_bytes());
+    push_string(&mut buf, &info.host_ip);
+    buf
+}
+
+fn full_stat_response(session_id: i32, info: &QueryInfo) -> Vec<u8> {
+    let mut buf = response_header(STAT, session_id);
+    buf.extend_from_slice(KEY_VALUES_PADDING);
+
+    let key_values = [
+        ("hostname", info.motd.clone()),
+        ("gametype", String::from("SMP")),
+        ("game_id", String::from("MINECRAFT")),
+        ("version", info.version.clone()),
+        ("plugins", info.plugins.clone()),
+        ("map", info.map.clone()),
+        ("numplayers", info.online_players.to_string()),
+        ("maxplayers", info.max_players.to_string()),
+        ("hostport", info.host_port.to_string()),
+        ("hostip", info.host_ip.clone()),
+    ];
+    for (key, value) in &key_values {
+        push_string(&mut buf, key);
+        push_string(&mut buf, value);
+    }
+    // An empty key ends the key-value pairs.
+    buf.push(0);
+
+    buf.extend_from_slice(PLAYERS_PADDING);
+    for player in &info.players {
+        push_string(&mut buf, player);
+    }
+    // An empty name ends the player list.
+    buf.push(0);
+    buf
+}
+
+/// Checks that a client sent the token issued to it by its last handshake.
+fn token_valid(tokens: &HashMap<SocketAddr, (i32, Instant)>, addr: SocketAddr, token: i32) -> bool {
+    match tokens.get(&addr) {
+        Some((expected, issued)) => *expected == token && issued.elapsed() < TOKEN_LIFETIME,
+        None => false,
+    }
+}
+
+/// Answers query requests received on `socket`.
+pub async fn run_query(
+    mut socket: UdpSocket,
+    config: Arc<Config>,
+    online_players: Arc<OnlinePlayers>,
+) {
+    // Challenge tokens and when they were issued, by client address.
+    let mut tokens: HashMap<SocketAddr, (i32, Instant)> = HashMap::new();
+    let mut buf = [0; BUFFER_LEN];
+
+    loop {
+        let (len, addr) = match socket.recv_from(&mut buf).await {
+            Ok(res) => res,
+            Err(e) => {
+                log::debug!("Failed to receive query request: {}", e);
+                continue;
+            }
+        };
+
+        let response = match Request::parse(&buf[..len]) {
+            Some(Request::Handshake { session_id }) => {
+                tokens.retain(|_, (_, issued)| issued.elapsed() < TOKEN_LIFETIME);
+                let token = rand::thread_rng().gen();
+                tokens.insert(addr, (token, Instant::now()));
+                handshake_response(session_id, token)
+            }
+            Some(Request::BasicStat { session_id, token }) if token_valid(&tokens, addr, token) => {
+                basic_stat_response(session_id, &QueryInfo::new(&config, &online_players))
+            }
+            Some(Request::FullStat { session_id, token }) if token_valid(&tokens, addr, token) => {
+                full_stat_response(session_id, &QueryInfo::new(&config, &online_players))
+            }
+            _ => continue,
+        };
+
+        if let Err(e) = socket.send_to(&response, &addr).await {
+            log::debug!("Failed to send query response to {}: {}", addr, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> QueryInfo {
+        QueryInfo {
+            motd: String::from("A Feather server"),
+            version: String::from("1.18.1"),
+            plugins: String::from("Feather 1.18.1"),
+            map: String::from("world"),
+            online_players: 2,
+            max_players: 16,
+            host_ip: String::from("0.0.0.0"),
+            host_port: 25565,
+            players: vec![String::from("a"), String::from("b")],
+        }
+    }
+
+    #[test]
+    fn parse_requests() {
+        assert_eq!(
+            Request::parse(&[0xFE, 0xFD, 9, 0, 0, 0, 1]),
+            Some(Request::Handshake { session_id: 1 })
+        );
+        assert_eq!(
+            Request::parse(&[0xFE, 0xFD, 0, 0, 0, 0, 1, 0, 0x91, 0x29, 0x5B]),
+            Some(Request::BasicStat {
+                session_id: 1,
+                token: 9_513_307
+            })
+        );
+        assert_eq!(
+            Request::parse(&[0xFE, 0xFD, 0, 0, 0, 0, 1, 0, 0x91, 0x29, 0x5B, 0, 0, 0, 0]),
+            Some(Request::FullStat {
+                session_id: 1,
+                token: 9_513_307
+            })
+        );
+        assert_eq!(Request::parse(&[0xFE, 0xFD, 9, 0, 0]), None);
+        assert_eq!(Request::parse(&[0xFE, 0xFD, 1, 0, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn handshake() {
+        assert_eq!(handshake_response(1, 9_513_307), b"\x09\0\0\0\x019513307\0");
+    }
+
+    #[test]
+    fn basic_stat() {
+        assert_eq!(
+            basic_stat_response(1, &info()),
+            &b"\0\0\0\0\x01A Feather server\0SMP\0world\02\016\0\xDD\x630.0.0.0\0"[..]
+        );
+    }
+
+    #[test]
+    fn full_stat() {
+        let response = full_stat_response(1, &info());
+        let mut expected = b"\0\0\0\0\x01splitnum\0\x80\0".to_vec();
+        expected.extend_from_slice(
+            b"hostname\0A Feather server\0gametype\0SMP\0game_id\0MINECRAFT\0\
+              version\01.18.1\0plugins\0Feather 1.18.1\0map\0world\0\
+              numplayers\02\0maxplayers\016\0hostport\025565\0hostip\00.0.0.0\0\0",
+        );
+        expected.extend_from_slice(b"\x01player_\0\0a\0b\0\0");
+        assert_eq!(response, expected);
+    }
+}
@@ -0,0 +1,172 @@
+//! Limits on the rate at which clients may send packets,
+//! so that a client spamming packets can't flood the
+//! packet buffers polled by the server thread.
+
+use feather_server_types::{RateLimit, RateLimitAction};
+use std::time::{Duration, Instant};
+
+/// A token bucket, which refills at a fixed rate
+/// and holds at most `capacity` tokens.
+#[derive(Debug)]
+struct TokenBucket {
+    /// Tokens added per second.
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket, or returns `None` if
+    /// `rate` is zero, i.e. there is no limit.
+    fn new(rate: u32, burst: u32, now: Instant) -> Option<Self> {
+        if rate == 0 {
+            return None;
+        }
+
+        let capacity = f64::from(burst.max(rate));
+        Some(Self {
+            rate: f64::from(rate),
+            capacity,
+            tokens: capacity,
+            last_refill: now,
+        })
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Takes tokens from the bucket. The bucket may go into
+    /// debt, which must be repaid before tokens can be taken again.
+    ///
+    /// Returns `false` if there were not enough tokens.
+    fn take(&mut self, amount: f64, now: Instant) -> bool {
+        self.refill(now);
+        let enough = self.tokens >= amount;
+        self.tokens -= amount;
+        enough
+    }
+
+    /// Returns the time until the bucket is out of debt.
+    fn time_until_repaid(&self) -> Duration {
+        if self.tokens >= 0.0 {
+            Duration::default()
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// What to do with a packet received from a client.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The client is within the limits.
+    Allow,
+    /// The client exceeded the limits and should
+    /// not be read from for the given time.
+    Throttle(Duration),
+    /// The client exceeded the limits and should be disconnected.
+    Disconnect,
+}
+
+/// The rate limits of a connection.
+#[derive(Debug)]
+pub struct RateLimiter {
+    packets: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+    action: RateLimitAction,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimit) -> Self {
+        Self::with_start(config, Instant::now())
+    }
+
+    fn with_start(config: &RateLimit, now: Instant) -> Self {
+        Self {
+            packets: TokenBucket::new(config.packets_per_second, config.packet_burst, now),
+            bytes: TokenBucket::new(config.bytes_per_second, config.byte_burst, now),
+            action: config.action,
+        }
+    }
+
+    /// Records a received packet with the given size.
+    pub fn on_packet(&mut self, bytes: u64) -> Verdict {
+        self.on_packet_at(bytes, Instant::now())
+    }
+
+    fn on_packet_at(&mut self, bytes: u64, now: Instant) -> Verdict {
+        let mut within_limits = true;
+        if let Some(bucket) = &mut self.packets {
+            within_limits &= bucket.take(1.0, now);
+        }
+        if let Some(bucket) = &mut self.bytes {
+            within_limits &= bucket.take(bytes as f64, now);
+        }
+
+        if within_limits {
+            return Verdict::Allow;
+        }
+
+        match self.action {
+            RateLimitAction::Disconnect => Verdict::Disconnect,
+            RateLimitAction::Throttle => {
+                let wait = self
+                    .packets
+                    .iter()
+                    .chain(&self.bytes)
+                    .map(TokenBucket::time_until_repaid)
+                    .max()
+                    .unwrap_or_default();
+                Verdict::Throttle(wait)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(action: RateLimitAction) -> RateLimit {
+        RateLimit {
+            packets_per_second: 10,
+            packet_burst: 20,
+            bytes_per_second: 0,
+            byte_burst: 0,
+            action,
+        }
+    }
+
+    #[test]
+    fn burst_then_rate() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::with_start(&config(RateLimitAction::Disconnect), start);
+
+        for _ in 0..20 {
+            assert_eq!(limiter.on_packet_at(100_000, start), Verdict::Allow);
+        }
+        assert_eq!(limiter.on_packet_at(1, start), Verdict::Disconnect);
+
+        // Enough time for the debt and one more packet.
+        let later = start + Duration::from_millis(200);
+        assert_eq!(limiter.on_packet_at(1, later), Verdict::Allow);
+    }
+
+    #[test]
+    fn throttle() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::with_start(&config(RateLimitAction::Throttle), start);
+
+        for _ in 0..20 {
+            limiter.on_packet_at(1, start);
+        }
+        match limiter.on_packet_at(1, start) {
+            Verdict::Throttle(wait) => assert!((wait.as_secs_f64() - 0.1).abs() < 1e-6),
+            verdict => panic!("expected throttle, got {:?}", verdict),
+        }
+    }
+}
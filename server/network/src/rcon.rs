@@ -0,0 +1,239 @@
+//! The remote console (RCON), which lets server panels and
+//! other tools run commands using the Source RCON protocol.
+//!
+//! Each connection must log in with the configured password
+//! before running commands. Commands are sent to the server
+//! thread as `RconCommand`s, and their output is sent back
+//! to the client.
+
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Packet type sent by clients to log in.
+const LOGIN: i32 = 3;
+/// Packet type sent by clients to run a command, and by
+/// the server in response to a login.
+const COMMAND: i32 = 2;
+/// Packet type of the server's responses to commands.
+const RESPONSE: i32 = 0;
+
+/// The request ID sent in response to a failed login.
+const AUTH_FAILURE_ID: i32 = -1;
+
+/// The longest packet accepted from clients, as in vanilla.
+const MAX_PACKET_LEN: usize = 1460;
+/// The longest body of a packet sent to clients. Longer
+/// responses are split across multiple packets.
+const MAX_RESPONSE_LEN: usize = 4096;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid packet length {0}")]
+    BadLength(i32),
+    #[error("packet body is not null-terminated")]
+    Unterminated,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A packet of the RCON protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RconPacket {
+    /// ID chosen by the client, which is sent back in responses.
+    pub request_id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    fn new(request_id: i32, kind: i32, body: impl Into<String>) -> Self {
+        Self {
+            request_id,
+            kind,
+            body: body.into(),
+        }
+    }
+
+    /// Decodes a packet, not including its length.
+    fn decode(data: &[u8]) -> Result<Self, Error> {
+        let request_id = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let kind = i32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        // The body is followed by two null bytes.
+        let body = match &data[8..] {
+            [body @ .., 0, 0] => String::from_utf8_lossy(body).into_owned(),
+            _ => return Err(Error::Unterminated),
+        };
+        Ok(Self::new(request_id, kind, body))
+    }
+
+    /// Encodes the packet, including its length.
+    fn encode(&self) -> Vec<u8> {
+        let len = 10 + self.body.len() as i32;
+        let mut buf = Vec::with_capacity(len as usize + 4);
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(&self.request_id.to_le_bytes());
+        buf.extend_from_slice(&self.kind.to_le_bytes());
+        buf.extend_from_slice(self.body.as_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf
+    }
+}
+
+/// A command received over RCON, to be run on the server thread.
+#[derive(Debug)]
+pub struct RconCommand {
+    pub command: String,
+    /// Channel to send the command's output to the client.
+    pub output: oneshot::Sender<String>,
+}
+
+/// Handle to the RCON listener, through which
+/// the server thread receives commands.
+pub struct RconHandle {
+    pub rx: flume::Receiver<RconCommand>,
+}
+
+impl RconHandle {
+    /// Starts accepting RCON connections, which log in with `password`.
+    pub fn start(listener: TcpListener, password: String) -> Self {
+        let (tx, rx) = flume::unbounded();
+        tokio::spawn(run_listener(listener, password, tx));
+        Self { rx }
+    }
+
+    /// Returns a handle which receives no commands,
+    /// for when RCON is disabled.
+    pub fn disabled() -> Self {
+        let (_, rx) = flume::unbounded();
+        Self { rx }
+    }
+}
+
+async fn run_listener(mut listener: TcpListener, password: String, tx: flume::Sender<RconCommand>) {
+    loop {
+        let (stream, ip) = match listener.accept().await {
+            Ok(res) => res,
+            Err(e) => {
+                log::info!("Failed to accept RCON connection: {}", e);
+                continue;
+            }
+        };
+
+        log::info!("RCON connection received from {}", ip);
+
+        let password = password.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_connection(stream, &password, &tx).await {
+                log::debug!("RCON connection from {} closed: {}", ip, e);
+            }
+        });
+    }
+}
+
+async fn run_connection(
+    mut stream: TcpStream,
+    password: &str,
+    tx: &flume::Sender<RconCommand>,
+) -> Result<(), Error> {
+    let mut logged_in = false;
+    loop {
+        let packet = read_packet(&mut stream).await?;
+        let responses = match packet.kind {
+            LOGIN if packet.body == password => {
+                logged_in = true;
+                vec![RconPacket::new(packet.request_id, COMMAND, "")]
+            }
+            COMMAND if logged_in => {
+                let (output_tx, output_rx) = oneshot::channel();
+                let command = RconCommand {
+                    command: packet.body,
+                    output: output_tx,
+                };
+                if tx.send(command).is_err() {
+                    // The server is shutting down.
+                    return Ok(());
+                }
+                let output = output_rx.await.unwrap_or_default();
+                split_response(packet.request_id, &output)
+            }
+            LOGIN | COMMAND => {
+                logged_in = false;
+                vec![RconPacket::new(AUTH_FAILURE_ID, COMMAND, "")]
+            }
+            kind => vec![RconPacket::new(
+                packet.request_id,
+                RESPONSE,
+                format!("Unknown request {:x}", kind),
+            )],
+        };
+
+        for response in responses {
+            stream.write_all(&response.encode()).await?;
+        }
+    }
+}
+
+async fn read_packet(stream: &mut TcpStream) -> Result<RconPacket, Error> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len).await?;
+    let len = i32::from_le_bytes(len);
+    if len < 10 || len as usize > MAX_PACKET_LEN {
+        return Err(Error::BadLength(len));
+    }
+
+    let mut data = vec![0; len as usize];
+    stream.read_exact(&mut data).await?;
+    RconPacket::decode(&data)
+}
+
+/// Splits a command's output into response packets,
+/// without splitting any character across packets.
+fn split_response(request_id: i32, mut output: &str) -> Vec<RconPacket> {
+    let mut packets = vec![];
+    loop {
+        let mut end = output.len().min(MAX_RESPONSE_LEN);
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (body, rest) = output.split_at(end);
+        packets.push(RconPacket::new(request_id, RESPONSE, body));
+
+        if rest.is_empty() {
+            return packets;
+        }
+        output = rest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets() {
+        let packet = RconPacket::new(7, COMMAND, "seed");
+        let encoded = packet.encode();
+        assert_eq!(&encoded[..4], &14i32.to_le_bytes());
+        assert_eq!(RconPacket::decode(&encoded[4..]).unwrap(), packet);
+
+        assert!(matches!(
+            RconPacket::decode(&encoded[4..encoded.len() - 1]),
+            Err(Error::Unterminated)
+        ));
+    }
+
+    #[test]
+    fn split_responses() {
+        let packets = split_response(1, "");
+        assert_eq!(packets, vec![RconPacket::new(1, RESPONSE, "")]);
+
+        let output = "é".repeat(MAX_RESPONSE_LEN);
+        let packets = split_response(1, &output);
+        assert_eq!(packets.len(), 2);
+        assert!(packets.iter().all(|p| p.body.len() <= MAX_RESPONSE_LEN));
+        assert_eq!(packets[0].body.len() + packets[1].body.len(), output.len());
+    }
+}
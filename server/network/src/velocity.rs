@@ -0,0 +1,187 @@
+//! Velocity's modern forwarding, through which a Velocity
+//! proxy passes the address and profile of players to the
+//! server during login.
+//!
+//! After Login Start, the server sends a Login Plugin Request on
+//! the `velocity:player_info` channel. The proxy responds with the
+//! player's information, signed using a secret shared with the server,
+//! so that players connecting directly can't forge it.
+
+use feather_core::network::{BytesExt, McTypeRead, TryGetError};
+use hmac::{Hmac, Mac};
+use mojang_api::ProfileProperty;
+use sha2::Sha256;
+use std::io::Cursor;
+use std::net::IpAddr;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// The channel of the Login Plugin Request sent to the proxy.
+pub const CHANNEL: &str = "velocity:player_info";
+
+/// The version of the forwarding format this server understands.
+const FORWARDING_VERSION: i32 = 1;
+
+/// The length of the HMAC-SHA256 signature preceding the forwarded data.
+const SIGNATURE_LEN: usize = 32;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("signature does not match the forwarding secret")]
+    BadSignature,
+    #[error("unsupported forwarding version {0}")]
+    UnsupportedVersion(i32),
+    #[error("malformed player information: {0}")]
+    Malformed(String),
+}
+
+impl From<TryGetError> for Error {
+    fn from(e: TryGetError) -> Self {
+        Error::Malformed(e.to_string())
+    }
+}
+
+/// The information of a player forwarded by Velocity.
+#[derive(Debug, PartialEq)]
+pub struct PlayerInfo {
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    pub username: String,
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Verifies and reads the data of the proxy's Login Plugin Response.
+pub fn read_player_info(data: &[u8], secret: &[u8]) -> Result<PlayerInfo, Error> {
+    if data.len() < SIGNATURE_LEN {
+        return Err(Error::BadSignature);
+    }
+    let (signature, data) = data.split_at(SIGNATURE_LEN);
+
+    let mut mac = Hmac::<Sha256>::new_varkey(secret).expect("HMAC accepts keys of any length");
+    mac.input(data);
+    mac.verify(signature).map_err(|_| Error::BadSignature)?;
+
+    let mut buf = Cursor::new(data);
+    let version = buf.try_get_var_int()?;
+    if version != FORWARDING_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    let address = buf.try_get_string()?;
+    let address = address
+        .parse()
+        .map_err(|_| Error::Malformed(format!("invalid address {}", address)))?;
+    let uuid = buf
+        .try_get_uuid()
+        .map_err(|e| Error::Malformed(e.to_string()))?;
+    let username = buf.try_get_string()?;
+
+    let num_properties = buf.try_get_var_int()?;
+    let mut properties = Vec::new();
+    for _ in 0..num_properties {
+        let name = buf.try_get_string()?;
+        let value = buf.try_get_string()?;
+        let signature = if buf.try_get_bool()? {
+            buf.try_get_string()?
+        } else {
+            String::new()
+        };
+        properties.push(ProfileProperty {
+            name,
+            value,
+            signature,
+        });
+    }
+
+    if buf.try_get_u8().is_ok() {
+        return Err(Error::Malformed(String::from("trailing bytes")));
+    }
+
+    Ok(PlayerInfo {
+        address,
+        uuid,
+        username,
+        properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"secret";
+
+    /// Writes a string in the protocol format,
+    /// for strings shorter than 128 bytes.
+    fn push_string(buf: &mut Vec<u8>, s: &str) {
+        buf.push(s.len() as u8);
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    /// Returns the player information sent by Velocity, signed with `secret`.
+    fn forwarded_data(secret: &[u8], version: u8) -> Vec<u8> {
+        let mut data = vec![version];
+        push_string(&mut data, "192.168.1.67");
+        data.extend_from_slice(Uuid::from_u128(0x905c_7e4f).as_bytes());
+        push_string(&mut data, "test");
+        data.push(2);
+        push_string(&mut data, "textures");
+        push_string(&mut data, "textures_value");
+        data.push(1);
+        push_string(&mut data, "textures_signature");
+        push_string(&mut data, "unsigned");
+        push_string(&mut data, "value");
+        data.push(0);
+
+        let mut mac = Hmac::<Sha256>::new_varkey(secret).unwrap();
+        mac.input(&data);
+        let mut signed = mac.result().code().to_vec();
+        signed.extend_from_slice(&data);
+        signed
+    }
+
+    #[test]
+    fn player_info() {
+        let info = read_player_info(&forwarded_data(SECRET, 1), SECRET).unwrap();
+        assert_eq!(
+            info,
+            PlayerInfo {
+                address: "192.168.1.67".parse().unwrap(),
+                uuid: Uuid::from_u128(0x905c_7e4f),
+                username: String::from("test"),
+                properties: vec![
+                    ProfileProperty {
+                        name: String::from("textures"),
+                        value: String::from("textures_value"),
+                        signature: String::from("textures_signature"),
+                    },
+                    ProfileProperty {
+                        name: String::from("unsigned"),
+                        value: String::from("value"),
+                        signature: String::new(),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn bad_signature() {
+        let data = forwarded_data(b"another secret", 1);
+        assert_eq!(read_player_info(&data, SECRET), Err(Error::BadSignature));
+        assert_eq!(read_player_info(&[], SECRET), Err(Error::BadSignature));
+
+        let mut data = forwarded_data(SECRET, 1);
+        *data.last_mut().unwrap() = 1;
+        assert_eq!(read_player_info(&data, SECRET), Err(Error::BadSignature));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let data = forwarded_data(SECRET, 2);
+        assert_eq!(
+            read_player_info(&data, SECRET),
+            Err(Error::UnsupportedVersion(2))
+        );
+    }
+}
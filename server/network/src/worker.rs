@@ -7,13 +7,17 @@
 //! to the worker for any given client.
 
 use crate::initial_handler::{Action, InitialHandler};
+use crate::legacy_ping;
+use crate::proxy_protocol;
+use crate::rate_limit::{RateLimiter, Verdict};
 use crate::{ListenerToServerMessage, NewClientInfo, ServerToListenerMessage};
 use feather_core::anvil::entity::{AnimalData, BaseEntityData};
-use feather_core::anvil::player::PlayerData;
+use feather_core::anvil::player::{ExperienceData, FoodData, PlayerData, SpawnData};
 use feather_core::network::{MinecraftCodec, Packet, PacketDirection};
-use feather_core::util::{Position, Vec3d};
+use feather_core::util::{Dimension, Position, Vec3d};
 use feather_server_types::{
-    BanInfo, Config, PacketBuffers, ServerToWorkerMessage, Uuid, WorkerToServerMessage,
+    BanInfo, Config, OnlinePlayers, PacketBuffers, ServerToWorkerMessage, Uuid,
+    WorkerToServerMessage,
 };
 use fecs::Entity;
 use futures::future::Either;
@@ -21,13 +25,16 @@ use futures::SinkExt;
 use futures::StreamExt;
 use std::net::SocketAddr;
 use std::path::Path;
-use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
 use std::sync::RwLock;
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 use tokio_util::codec::Framed;
 
+/// The number of bytes of packets buffered after which
+/// they are sent without waiting for the end of the tick.
+const FLUSH_THRESHOLD: u64 = 32 * 1024;
+
 struct Worker {
     framed: Framed<TcpStream, MinecraftCodec>,
     config: Arc<Config>,
@@ -52,28 +59,57 @@ struct Worker {
     initial_handler: Option<InitialHandler>,
     /// The entity for the player on the server thread.
     entity: Entity,
+    /// Limits the rate at which the client may send packets.
+    rate_limiter: RateLimiter,
+    /// The number of bytes received as of the last packet.
+    bytes_received: u64,
+    /// The number of bytes sent as of the last flush.
+    bytes_flushed: u64,
 }
 
 /// Runs a worker task for the given client.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_worker(
-    stream: TcpStream,
-    ip: SocketAddr,
+    mut stream: TcpStream,
+    mut ip: SocketAddr,
     listener_tx: flume::Sender<ListenerToServerMessage>,
     listener_rx: Arc<Mutex<flume::Receiver<ServerToListenerMessage>>>,
     config: Arc<Config>,
     ban_info: Arc<RwLock<BanInfo>>,
-    player_count: Arc<AtomicU32>,
+    online_players: Arc<OnlinePlayers>,
     server_icon: Arc<Option<String>>,
     packet_buffers: Arc<PacketBuffers>,
 ) {
+    if config.proxy.proxy_protocol {
+        match proxy_protocol::read_header(&mut stream).await {
+            Ok(Some(client)) => {
+                log::debug!("Connection from {} is proxied for {}", ip, client);
+                ip = client;
+            }
+            Ok(None) => (),
+            Err(e) => {
+                log::debug!("Failed to read PROXY protocol header from {}: {}", ip, e);
+                return;
+            }
+        }
+    }
+
+    // Clients older than 1.7 don't speak the protocol the codec
+    // understands, so their pings are answered separately.
+    if let Ok(Some(ping)) = legacy_ping::detect(&mut stream).await {
+        if let Err(e) = legacy_ping::respond(stream, ping, ip, &config, &online_players).await {
+            log::debug!("Failed to respond to legacy ping from {}: {}", ip, e);
+        }
+        return;
+    }
+
     let (server_tx, rx) = flume::unbounded();
     let (tx, server_rx) = flume::unbounded();
 
     let initial_handler = Some(InitialHandler::new(
         Arc::clone(&config),
         Arc::clone(&ban_info),
-        Arc::clone(&player_count),
+        Arc::clone(&online_players),
         Arc::clone(&server_icon),
         ip,
     ));
@@ -94,6 +130,9 @@ pub async fn run_worker(
         tx,
         initial_handler,
         entity,
+        rate_limiter: RateLimiter::new(&config.rate_limit),
+        bytes_received: 0,
+        bytes_flushed: 0,
         config,
     };
 
@@ -149,6 +188,7 @@ async fn run_worker_impl(worker: &mut Worker) -> anyhow::Result<()> {
 
                 let packet = packet_res?;
 
+                check_rate_limit(worker).await?;
                 handle_packet(worker, packet).await?;
             }
         }
@@ -157,18 +197,56 @@ async fn run_worker_impl(worker: &mut Worker) -> anyhow::Result<()> {
     }
 }
 
+/// Applies the rate limit to the last packet received.
+async fn check_rate_limit(worker: &mut Worker) -> anyhow::Result<()> {
+    // Includes packets the codec skipped since the last one.
+    let bytes_received = worker.framed.codec().bytes_received();
+    let bytes = bytes_received - worker.bytes_received;
+    worker.bytes_received = bytes_received;
+
+    match worker.rate_limiter.on_packet(bytes) {
+        Verdict::Allow => (),
+        Verdict::Throttle(wait) => tokio::time::delay_for(wait).await,
+        Verdict::Disconnect => anyhow::bail!("sent packets too quickly"),
+    }
+
+    Ok(())
+}
+
 async fn handle_server_to_worker_message(
     worker: &mut Worker,
     msg: ServerToWorkerMessage,
 ) -> anyhow::Result<()> {
     match msg {
-        ServerToWorkerMessage::SendPacket(packet) => worker.framed.send(packet).await?,
-        ServerToWorkerMessage::Disconnect => anyhow::bail!("server requested disconnect"),
+        ServerToWorkerMessage::SendPacket(packet) => {
+            worker.framed.feed(packet).await?;
+            // Don't let the buffer grow too large
+            // when many packets are sent in a tick.
+            if worker.framed.codec().bytes_sent() - worker.bytes_flushed >= FLUSH_THRESHOLD {
+                flush(worker).await?;
+            }
+        }
+        ServerToWorkerMessage::SendPacketNow(packet) => {
+            worker.framed.feed(packet).await?;
+            flush(worker).await?;
+        }
+        ServerToWorkerMessage::Flush => flush(worker).await?,
+        ServerToWorkerMessage::Disconnect => {
+            // Send the disconnect packet.
+            flush(worker).await?;
+            anyhow::bail!("server requested disconnect")
+        }
     }
 
     Ok(())
 }
 
+async fn flush(worker: &mut Worker) -> anyhow::Result<()> {
+    worker.framed.flush().await?;
+    worker.bytes_flushed = worker.framed.codec().bytes_sent();
+    Ok(())
+}
+
 async fn handle_packet(worker: &mut Worker, packet: Box<dyn Packet>) -> anyhow::Result<()> {
     if let Some(ref mut ih) = worker.initial_handler {
         ih.handle_packet(packet).await;
@@ -190,18 +268,27 @@ async fn handle_ih_actions(worker: &mut Worker) -> anyhow::Result<()> {
     {
         match action {
             Action::SendPacket(packet) => worker.framed.send(packet).await?,
-            Action::EnableCompression(threshold) => worker
-                .framed
-                .codec_mut()
-                .enable_compression(threshold as usize),
+            Action::EnableCompression(threshold) => {
+                let level = worker.config.io.compression_level;
+                worker
+                    .framed
+                    .codec_mut()
+                    .enable_compression(threshold as usize, level)
+            }
             Action::EnableEncryption(key) => worker.framed.codec_mut().enable_encryption(key),
             Action::Disconnect => anyhow::bail!("initial handler requested disconnect"),
             Action::SetStage(stage) => worker.framed.codec_mut().set_stage(stage),
+            Action::SetProtocolVersion(version) => {
+                worker.framed.codec_mut().set_protocol_version(version)
+            }
             Action::JoinGame(info) => {
                 let data = load_player_data(&worker.config, info.uuid).await?;
                 let position = data.animal.base.read_position()?;
+                let ip = info
+                    .ip
+                    .map_or(worker.ip, |ip| SocketAddr::new(ip, worker.ip.port()));
                 let info = NewClientInfo {
-                    ip: worker.ip,
+                    ip,
                     username: info.username.unwrap_or_else(|| String::from("undefined")),
                     profile: info.props,
                     uuid: info.uuid,
@@ -244,8 +331,12 @@ async fn load_player_data(config: &Config, uuid: Uuid) -> Result<PlayerData, any
                     20.0,
                 ),
                 gamemode: config.server.default_gamemode.id() as i32,
+                dimension: Dimension::Overwold.id(),
                 inventory: vec![],
                 held_item: 0,
+                food: FoodData::default(),
+                experience: ExperienceData::default(),
+                spawn: SpawnData::default(),
             };
 
             feather_core::anvil::player::save_player_data(
@@ -5,7 +5,7 @@ use crate::{block_impacted_by_ray, blocks_intersecting_bbox, Side};
 use feather_core::blocks::BlockKind;
 use feather_core::position;
 use feather_core::util::Position;
-use feather_server_types::{AABBExt, EntityLandEvent, Game, Physics, Velocity};
+use feather_server_types::{AABBExt, EntityLandEvent, Game, Physics, Velocity, WorldId};
 use fecs::{IntoQuery, Read, World, Write};
 use parking_lot::Mutex;
 
@@ -17,10 +17,25 @@ pub fn entity_physics(game: &mut Game, world: &mut World) {
     // to their velocities.
     let land_events = Mutex::new(vec![]);
 
-    let query = <(Write<Position>, Write<Velocity>, Read<Physics>)>::query();
+    let query = <(
+        Write<Position>,
+        Write<Velocity>,
+        Read<Physics>,
+        Read<WorldId>,
+    )>::query();
     query.par_entities_for_each_mut(
         world.inner_mut(),
-        |(entity, (mut position, mut velocity, physics))| {
+        |(entity, (mut position, mut velocity, physics, world_id))| {
+            let state = match game.worlds.get(*world_id) {
+                Some(state) => state,
+                None => return,
+            };
+
+            // Entities in chunks which aren't entity ticking are frozen.
+            if !state.chunk_tickets.is_entity_ticking(position.chunk()) {
+                return;
+            }
+
             let mut pending_position = *position + velocity.0;
 
             // Check for blocks along path between old position and pending position.
@@ -30,7 +45,8 @@ pub fn entity_physics(game: &mut Game, world: &mut World) {
             let direction = (pending_position - *position).into();
             let distance_squared = pending_position.distance_squared_to(*position);
 
-            if let Some(impacted) = block_impacted_by_ray(game, origin, direction, distance_squared)
+            if let Some(impacted) =
+                block_impacted_by_ray(state, origin, direction, distance_squared)
             {
                 // Set velocities along correct axis to 0 and then set position
                 // to just before the bbox would have impacted the block.
@@ -57,7 +73,7 @@ pub fn entity_physics(game: &mut Game, world: &mut World) {
             // Check for blocks around the bbox and apply offset
             // to position to stop the bbox from intersecting blocks.
             let intersect =
-                blocks_intersecting_bbox(game, *position, pending_position, &physics.bbox);
+                blocks_intersecting_bbox(state, *position, pending_position, &physics.bbox);
             intersect.apply_to(&mut pending_position);
 
             if intersect.x_affected() {
@@ -73,7 +89,7 @@ pub fn entity_physics(game: &mut Game, world: &mut World) {
             }
 
             // Delete entity if it has gone into unloaded chunks.
-            let block_at_pos = match game.block_at(pending_position.block()) {
+            let block_at_pos = match state.block_at(pending_position.block()) {
                 Some(block) => block,
                 None => {
                     // TODO: delete entity
@@ -82,7 +98,7 @@ pub fn entity_physics(game: &mut Game, world: &mut World) {
             };
 
             // Set on ground status.
-            pending_position.on_ground = match game.block_at(
+            pending_position.on_ground = match state.block_at(
                 position!(
                     pending_position.x,
                     pending_position.y - physics.bbox.size().y / 2.0 - 0.01,
@@ -5,7 +5,7 @@ use crate::block_bboxes::bbox_for_block;
 use bitflags::bitflags;
 use feather_core::blocks::BlockId;
 use feather_core::util::{BlockPosition, Position};
-use feather_server_types::{AABBExt, Game};
+use feather_server_types::{AABBExt, WorldState};
 
 use glm::{vec3, DVec3, Vec3};
 use heapless::consts::*;
@@ -87,7 +87,7 @@ pub struct RayImpact {
 /// Traces up to `max_distance` before returning `None`
 /// if no block was found.
 pub fn block_impacted_by_ray(
-    game: &Game,
+    state: &WorldState,
     origin: DVec3,
     ray: DVec3,
     max_distance_squared: f64,
@@ -165,7 +165,7 @@ pub fn block_impacted_by_ray(
     let mut current_pos = Position::from(origin).block();
 
     while dist_traveled.magnitude_squared() < max_distance_squared {
-        if let Some(block) = game.block_at(current_pos) {
+        if let Some(block) = state.block_at(current_pos) {
             if block.is_solid() {
                 // Calculate world-space position of
                 // impact using `ncollide`.
@@ -271,7 +271,7 @@ impl BlockIntersect {
 /// than 1 are not supported. If the bounding box's size
 /// is more than 1, this function will panic.
 pub fn blocks_intersecting_bbox(
-    game: &Game,
+    state: &WorldState,
     mut from: Position,
     mut dest: Position,
     bbox: &AABB<f64>,
@@ -304,7 +304,7 @@ pub fn blocks_intersecting_bbox(
     let mut checked = heapless::FnvIndexSet::new();
 
     for (axis, sign) in &axis {
-        let compound = adjacent_to_bbox(*axis, *sign, bbox, dest, &game, &mut checked);
+        let compound = adjacent_to_bbox(*axis, *sign, bbox, dest, state, &mut checked);
         blocks.push(compound);
     }
 
@@ -379,7 +379,7 @@ pub fn adjacent_to_bbox(
     sign: i32,
     bbox: &AABB<f64>,
     pos: Position,
-    game: &Game,
+    state: &WorldState,
     checked: &mut heapless::FnvIndexSet<BlockPosition, U32>,
 ) -> Compound<f64> {
     assert!(axis <= 2);
@@ -439,7 +439,7 @@ pub fn adjacent_to_bbox(
             continue;
         }
 
-        match game.block_at(block_pos) {
+        match state.block_at(block_pos) {
             Some(block) => {
                 if block.is_solid() {
                     checked.insert(block_pos).unwrap();
@@ -0,0 +1,94 @@
+//! Sending advancements to players and
+//! tracking their progress on them.
+
+use crate::IteratorExt;
+use feather_core::network::packets::{AdvancementTab, SelectAdvancementTab};
+use feather_server_types::{
+    AdvancementProgress, Game, Network, PacketBuffers, PlayerJoinEvent, RecipeUnlockEvent,
+    TRIGGER_RECIPE_UNLOCKED, TRIGGER_TICK,
+};
+use feather_server_util::current_time_in_millis;
+use fecs::{IntoQuery, Read, World, Write};
+use std::sync::Arc;
+
+/// When a player joins, sends them the advancements
+/// and achieves the criteria triggered by joining.
+#[fecs::event_handler]
+pub fn on_player_join_send_advancements(event: &PlayerJoinEvent, game: &Game, world: &mut World) {
+    let mut progress = AdvancementProgress::default();
+    progress.trigger(
+        &game.advancements,
+        TRIGGER_TICK,
+        current_time_in_millis() as i64,
+        |_| true,
+    );
+    // Sent in full below; toasts aren't shown for
+    // advancements made before joining.
+    progress.take_changed();
+
+    world
+        .get::<Network>(event.player)
+        .send(game.advancements.packet(&progress));
+    world.add(event.player, progress).unwrap();
+}
+
+/// Achieves the criteria triggered by unlocking recipes.
+#[fecs::event_handler]
+pub fn on_recipe_unlock_grant_advancements(
+    event: &RecipeUnlockEvent,
+    game: &Game,
+    world: &mut World,
+) {
+    let mut progress = match world.try_get_mut::<AdvancementProgress>(event.player) {
+        Some(progress) => progress,
+        None => return,
+    };
+
+    progress.trigger(
+        &game.advancements,
+        TRIGGER_RECIPE_UNLOCKED,
+        current_time_in_millis() as i64,
+        |criterion| match criterion.recipe() {
+            Some(recipe) => event.recipes.iter().any(|unlocked| unlocked == recipe),
+            None => false,
+        },
+    );
+}
+
+/// System which sends players the progress on advancements
+/// which changed this tick, showing toasts for those made.
+#[fecs::system]
+pub fn send_advancement_progress(game: &Game, world: &mut World) {
+    <(Write<AdvancementProgress>, Read<Network>)>::query().par_for_each_mut(
+        world.inner_mut(),
+        |(mut progress, network)| {
+            let changed = progress.take_changed();
+            if changed.is_empty() {
+                return;
+            }
+
+            let packet = game
+                .advancements
+                .progress_packet(&progress, changed.iter().map(String::as_str));
+            network.send(packet);
+        },
+    );
+}
+
+/// System which confirms the advancement tab
+/// a player switched to in the advancements screen.
+#[fecs::system]
+pub fn handle_advancement_tabs(world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<AdvancementTab>()
+        .for_each_valid(world, |world, (player, packet)| {
+            // Action 0 opens a tab; 1 closes the screen.
+            if packet.action != 0 {
+                return;
+            }
+
+            world.get::<Network>(player).send(SelectAdvancementTab {
+                identifier: Some(packet.tab_id),
+            });
+        });
+}
@@ -0,0 +1,739 @@
+//! Repairing, combining and renaming items in anvils.
+
+use feather_core::blocks::{BlockId, BlockKind};
+use feather_core::inventory::{Area, Inventory, SlotIndex, Window};
+use feather_core::items::{EnchantmentRarity, Item, ItemName, ItemStack};
+use feather_core::network::packets::{Effect, OpenWindow, SetSlot, WindowProperty};
+use feather_core::text::TextRoot;
+use feather_core::util::{BlockPosition, Gamemode};
+use feather_definitions::{Tool, ToolMaterial};
+use feather_server_types::{
+    BlockUpdateCause, Experience, Game, InteractionHandler, InventoryUpdateEvent, ItemDropEvent,
+    Network, WindowCloseEvent, WindowOpenEvent,
+};
+use fecs::{Entity, EntityBuilder, World};
+use rand::Rng;
+use smallvec::{smallvec, SmallVec};
+
+/// Costs from this many levels on are "Too Expensive!"
+/// for players not in creative mode.
+pub const TOO_EXPENSIVE: i32 = 40;
+
+/// The longest name which can be given to items in an anvil.
+pub const MAX_NAME_LENGTH: usize = 35;
+
+/// Chance of an anvil being damaged when it is used.
+const DAMAGE_CHANCE: f64 = 0.12;
+
+// Window properties of anvils.
+const PROPERTY_COST: i16 = 0;
+
+// Effects played when an anvil is used.
+const EFFECT_ANVIL_DESTROYED: i32 = 1029;
+const EFFECT_ANVIL_USED: i32 = 1030;
+
+/// Component for the inventory behind an anvil window,
+/// which is created for each player opening an anvil.
+#[derive(Clone, Debug)]
+pub struct AnvilWindow {
+    /// The player using the anvil.
+    pub player: Entity,
+    /// The ID of the window opened for the player.
+    pub window_id: u8,
+    /// The position of the anvil.
+    pub pos: BlockPosition,
+    /// The name the player typed in, if any.
+    pub name: Option<String>,
+    /// The work done on the input items.
+    pub work: AnvilWork,
+}
+
+/// The result of working on the items in an anvil.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnvilWork {
+    /// The resulting item, if the items can be worked on.
+    pub output: Option<ItemStack>,
+    /// The experience levels it costs, including the cost of
+    /// prior work. Shown to players even if it is too expensive.
+    pub cost: i32,
+    /// The number of repair material items used up.
+    /// If 0, the whole second item is used up.
+    pub material_used: u8,
+}
+
+/// Works on an item in an anvil: repairs it with its repair
+/// material or with a second item of its kind, adds the
+/// enchantments of a second item or enchanted book to it,
+/// and renames it.
+///
+/// `name` is the name typed in by the player, or `None` if they
+/// haven't typed one. An empty name removes the custom name.
+pub fn work(
+    left: Option<ItemStack>,
+    right: Option<ItemStack>,
+    name: Option<&str>,
+    creative: bool,
+) -> AnvilWork {
+    let left = match left {
+        Some(left) => left,
+        None => return AnvilWork::default(),
+    };
+
+    let mut output = left;
+    let mut cost = 0;
+    let prior_work_cost =
+        left.repair_cost.unwrap_or(0) + right.and_then(|right| right.repair_cost).unwrap_or(0);
+    let mut material_used = 0;
+
+    if let Some(right) = right {
+        let book = right.ty == Item::EnchantedBook && !right.enchantments.is_empty();
+        let durability = left.ty.durability().map(|durability| durability as i32);
+
+        match durability {
+            Some(durability) if is_repair_material(left.ty, right.ty) => {
+                // Each material item repairs a quarter of the durability.
+                let mut repaired = damage(output).min(durability / 4);
+                if repaired <= 0 {
+                    return AnvilWork::default();
+                }
+                while repaired > 0 && material_used < right.amount {
+                    output.damage = Some(damage(output) - repaired);
+                    cost += 1;
+                    material_used += 1;
+                    repaired = damage(output).min(durability / 4);
+                }
+            }
+            _ => {
+                if !book && (left.ty != right.ty || durability.is_none()) {
+                    return AnvilWork::default();
+                }
+
+                if let (Some(durability), false) = (durability, book) {
+                    // The durability of both items is added,
+                    // with a bonus of 12% of the maximum.
+                    let remaining = (durability - damage(left))
+                        + (durability - damage(right))
+                        + durability * 12 / 100;
+                    let combined_damage = (durability - remaining).max(0);
+                    if combined_damage < damage(output) {
+                        output.damage = Some(combined_damage);
+                        cost += 2;
+                    }
+                }
+
+                let mut applied = false;
+                let mut rejected = false;
+                for (enchantment, level) in right.enchantments.iter() {
+                    let current = output.enchantments.level(enchantment).unwrap_or(0);
+                    let level = if current == level {
+                        level + 1
+                    } else {
+                        level.max(current)
+                    };
+
+                    let mut applicable = enchantment.can_apply_to(left.ty)
+                        || creative
+                        || left.ty == Item::EnchantedBook;
+                    for (other, _) in output.enchantments.iter() {
+                        if other != enchantment && !enchantment.is_compatible_with(other) {
+                            applicable = false;
+                            cost += 1;
+                        }
+                    }
+
+                    if !applicable {
+                        rejected = true;
+                        continue;
+                    }
+                    applied = true;
+
+                    let level = level.min(enchantment.max_level());
+                    output.enchantments.set(enchantment, level);
+
+                    let mut multiplier = match enchantment.rarity() {
+                        EnchantmentRarity::Common => 1,
+                        EnchantmentRarity::Uncommon => 2,
+                        EnchantmentRarity::Rare => 4,
+                        EnchantmentRarity::VeryRare => 8,
+                    };
+                    if book {
+                        multiplier = (multiplier / 2).max(1);
+                    }
+                    cost += multiplier * i32::from(level);
+                    if left.amount > 1 {
+                        cost = TOO_EXPENSIVE;
+                    }
+                }
+
+                if rejected && !applied {
+                    return AnvilWork::default();
+                }
+            }
+        }
+    }
+
+    let mut rename_cost = 0;
+    match name {
+        Some(name) if name.trim().is_empty() => {
+            if left.name.is_some() {
+                rename_cost = 1;
+                output.name = None;
+            }
+        }
+        Some(name) if name != display_name(&left) => {
+            rename_cost = 1;
+            output.name = Some(ItemName::new(name));
+        }
+        _ => (),
+    }
+    cost += rename_cost;
+
+    let mut total_cost = prior_work_cost + cost;
+    let mut output = Some(output).filter(|_| cost > 0);
+    // Renaming alone is never too expensive.
+    if rename_cost == cost && rename_cost > 0 && total_cost >= TOO_EXPENSIVE {
+        total_cost = TOO_EXPENSIVE - 1;
+    }
+    if total_cost >= TOO_EXPENSIVE && !creative {
+        output = None;
+    }
+
+    if let Some(output) = output.as_mut() {
+        // Each time an item is worked on, working
+        // on it again costs about twice as much.
+        let mut repair_cost = output.repair_cost.unwrap_or(0);
+        if let Some(right) = right {
+            repair_cost = repair_cost.max(right.repair_cost.unwrap_or(0));
+        }
+        if rename_cost != cost || rename_cost == 0 {
+            repair_cost = repair_cost * 2 + 1;
+        }
+        output.repair_cost = Some(repair_cost);
+    }
+
+    AnvilWork {
+        output,
+        cost: total_cost,
+        material_used,
+    }
+}
+
+fn damage(stack: ItemStack) -> i32 {
+    stack.damage.unwrap_or(0)
+}
+
+/// Returns the name of an item as shown to players.
+fn display_name(stack: &ItemStack) -> &str {
+    match &stack.name {
+        Some(name) => name.as_str(),
+        None => stack.ty.display_name(),
+    }
+}
+
+/// Returns whether an item can be repaired with a material,
+/// e.g. iron tools with iron ingots.
+fn is_repair_material(item: Item, material: Item) -> bool {
+    let planks = material.identifier().ends_with("_planks");
+    match item {
+        Item::Elytra => return material == Item::PhantomMembrane,
+        Item::Shield => return planks,
+        Item::TurtleHelmet => return material == Item::Scute,
+        _ => (),
+    }
+
+    if item.tool().is_some() && item.tool() != Some(Tool::Shears) {
+        return match item.tool_material() {
+            Some(ToolMaterial::Wooden) => planks,
+            Some(ToolMaterial::Stone) => material == Item::Cobblestone,
+            Some(ToolMaterial::Iron) => material == Item::IronIngot,
+            Some(ToolMaterial::Golden) => material == Item::GoldIngot,
+            Some(ToolMaterial::Diamond) => material == Item::Diamond,
+            None => false,
+        };
+    }
+
+    // Armor
+    let identifier = item.identifier().trim_start_matches("minecraft:");
+    let armor = ["_helmet", "_chestplate", "_leggings", "_boots"]
+        .iter()
+        .any(|piece| identifier.ends_with(piece));
+    if !armor {
+        return false;
+    }
+    if identifier.starts_with("leather_") {
+        material == Item::Leather
+    } else if identifier.starts_with("chainmail_") || identifier.starts_with("iron_") {
+        material == Item::IronIngot
+    } else if identifier.starts_with("golden_") {
+        material == Item::GoldIngot
+    } else if identifier.starts_with("diamond_") {
+        material == Item::Diamond
+    } else {
+        false
+    }
+}
+
+/// Sets the name a player typed into an anvil window and
+/// updates its output.
+///
+/// Names with more than `MAX_NAME_LENGTH` characters are ignored.
+/// Formatting codes and control characters are removed.
+pub fn set_anvil_name(game: &mut Game, world: &mut World, anvil: Entity, name: &str) {
+    let name: String = name
+        .chars()
+        .filter(|c| *c != '§' && !c.is_control())
+        .collect();
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return;
+    }
+
+    match world.try_get_mut::<AnvilWindow>(anvil) {
+        Some(mut window) => window.name = Some(name),
+        None => return,
+    }
+    update_anvil_output(game, world, anvil);
+}
+
+/// Works on the input items of an anvil window, setting its
+/// output slot and sending the cost to its player.
+fn update_anvil_output(game: &mut Game, world: &mut World, anvil: Entity) {
+    let (player, window_id, work) = {
+        let inventory = world.get::<Inventory>(anvil);
+        let mut window = world.get_mut::<AnvilWindow>(anvil);
+        let creative = world
+            .try_get::<Gamemode>(window.player)
+            .map_or(false, |gamemode| *gamemode == Gamemode::Creative);
+        window.work = work(
+            inventory.item_at(Area::AnvilInput, 0).unwrap(),
+            inventory.item_at(Area::AnvilInput, 1).unwrap(),
+            window.name.as_deref(),
+            creative,
+        );
+        match window.work.output {
+            Some(output) => inventory.set_item_at(Area::AnvilOutput, 0, output),
+            None => inventory.remove_item_at(Area::AnvilOutput, 0),
+        }
+        .unwrap();
+        (window.player, window.window_id, window.work)
+    };
+
+    if let Some(network) = world.try_get::<Network>(player) {
+        network.send(SetSlot {
+            window_id: window_id as i8,
+            slot: 2,
+            slot_data: work.output,
+        });
+        network.send(WindowProperty {
+            window_id,
+            property: PROPERTY_COST,
+            value: work.cost as i16,
+        });
+    }
+
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: smallvec![SlotIndex {
+                area: Area::AnvilOutput,
+                slot: 0,
+            }],
+            entity: anvil,
+        },
+    );
+}
+
+/// When the input items of an anvil window change,
+/// works on them to update its output.
+#[fecs::event_handler]
+pub fn on_inventory_update_update_anvil_output(
+    event: &InventoryUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<AnvilWindow>(event.entity)
+        || !event.slots.iter().any(|slot| slot.area == Area::AnvilInput)
+    {
+        return;
+    }
+    update_anvil_output(game, world, event.entity);
+}
+
+/// Uses up the input items of an anvil window after its output
+/// was taken, which may damage the anvil.
+///
+/// Returns `false` if the output can't be taken. The taken item
+/// isn't added anywhere, and should be taken from the output
+/// slot beforehand.
+pub fn consume_anvil_inputs(game: &mut Game, world: &mut World, anvil: Entity) -> bool {
+    let (player, pos, cost, creative) = {
+        let window = match world.try_get::<AnvilWindow>(anvil) {
+            Some(window) => window,
+            None => return false,
+        };
+        if window.work.output.is_none() || window.work.cost <= 0 {
+            return false;
+        }
+        let cost = window.work.cost;
+        let creative = world
+            .try_get::<Gamemode>(window.player)
+            .map_or(false, |gamemode| *gamemode == Gamemode::Creative);
+        let has_levels = world
+            .try_get::<Experience>(window.player)
+            .map_or(false, |experience| experience.has_levels(cost as u32));
+        if !creative && !has_levels {
+            return false;
+        }
+
+        let inventory = world.get::<Inventory>(anvil);
+        inventory.remove_item_at(Area::AnvilInput, 0).unwrap();
+        let right = inventory.item_at(Area::AnvilInput, 1).unwrap();
+        let material_used = window.work.material_used;
+        match right {
+            Some(right) if material_used > 0 && right.amount > material_used => inventory
+                .set_item_at(
+                    Area::AnvilInput,
+                    1,
+                    right.of_amount(right.amount - material_used),
+                ),
+            _ => inventory.remove_item_at(Area::AnvilInput, 1),
+        }
+        .unwrap();
+
+        (window.player, window.pos, cost, creative)
+    };
+    if let Some(mut window) = world.try_get_mut::<AnvilWindow>(anvil) {
+        window.name = None;
+    }
+
+    if !creative {
+        game.add_experience_levels(world, player, -cost);
+    }
+    damage_anvil(game, world, player, pos, creative);
+
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: smallvec![
+                SlotIndex {
+                    area: Area::AnvilInput,
+                    slot: 0,
+                },
+                SlotIndex {
+                    area: Area::AnvilInput,
+                    slot: 1,
+                },
+            ],
+            entity: anvil,
+        },
+    );
+    true
+}
+
+/// Damages an anvil after it was used with a chance of 12%,
+/// unless the player is in creative mode. Damaged anvils
+/// are destroyed.
+fn damage_anvil(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    pos: BlockPosition,
+    creative: bool,
+) {
+    let world_id = game.world_of(world, player);
+    let block = match game.block_at(world_id, pos) {
+        Some(block) => block,
+        None => return,
+    };
+
+    let mut effect = EFFECT_ANVIL_USED;
+    if !creative && game.rng().gen_bool(DAMAGE_CHANCE) {
+        let damaged = match block.kind() {
+            BlockKind::Anvil => Some(BlockId::chipped_anvil()),
+            BlockKind::ChippedAnvil => Some(BlockId::damaged_anvil()),
+            _ => None,
+        };
+        let damaged = match (damaged, block.facing_cardinal()) {
+            (Some(damaged), Some(facing)) => damaged.with_facing_cardinal(facing),
+            _ => {
+                effect = EFFECT_ANVIL_DESTROYED;
+                BlockId::air()
+            }
+        };
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            damaged,
+            BlockUpdateCause::Entity(player),
+        );
+    }
+
+    game.broadcast_chunk_update(
+        world,
+        Effect {
+            effect_id: effect,
+            location: pos,
+            data: 0,
+            disable_relative_volume: false,
+        },
+        world_id,
+        pos.chunk(),
+        None,
+    );
+}
+
+/// When a player closes an anvil window, gives them back
+/// its input items and removes the window's inventory.
+#[fecs::event_handler]
+pub fn on_window_close_return_anvil_items(
+    event: &WindowCloseEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<AnvilWindow>(event.closed) {
+        return;
+    }
+
+    let items: SmallVec<[ItemStack; 2]> = {
+        let inventory = world.get::<Inventory>(event.closed);
+        (0..2)
+            .filter_map(|slot| inventory.item_at(Area::AnvilInput, slot).unwrap())
+            .collect()
+    };
+
+    for item in items {
+        let (slots, remaining) = match world.try_get::<Inventory>(event.player) {
+            Some(inventory) => inventory.collect_item(item),
+            None => (SmallVec::new(), item.amount),
+        };
+        if !slots.is_empty() {
+            game.handle(
+                world,
+                InventoryUpdateEvent {
+                    slots,
+                    entity: event.player,
+                },
+            );
+        }
+        if remaining > 0 {
+            game.handle(
+                world,
+                ItemDropEvent {
+                    slot: None,
+                    stack: item.of_amount(remaining),
+                    player: event.player,
+                },
+            );
+        }
+    }
+
+    world.despawn(event.closed);
+}
+
+/// Handler for player right clicking on anvils.
+pub struct AnvilInteraction(BlockKind);
+inventory::submit!(Box::new(AnvilInteraction(BlockKind::Anvil)) as Box<dyn InteractionHandler>);
+inventory::submit!(
+    Box::new(AnvilInteraction(BlockKind::ChippedAnvil)) as Box<dyn InteractionHandler>
+);
+inventory::submit!(
+    Box::new(AnvilInteraction(BlockKind::DamagedAnvil)) as Box<dyn InteractionHandler>
+);
+
+impl InteractionHandler for AnvilInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        pos: BlockPosition,
+        player: Entity,
+        window_id: u8,
+    ) {
+        let anvil = EntityBuilder::new()
+            .with(Inventory::anvil())
+            .with(AnvilWindow {
+                player,
+                window_id,
+                pos,
+                name: None,
+                work: AnvilWork::default(),
+            })
+            .build()
+            .spawn_in(world);
+
+        world.get::<Network>(player).send(OpenWindow {
+            window_id,
+            window_type: String::from("minecraft:anvil"),
+            window_title: TextRoot::from("Repair & Name").into(),
+            number_of_slots: 0,
+            entity_id: None,
+        });
+        *world.get_mut::<Window>(player) = Window::anvil(player, anvil);
+        game.handle(
+            world,
+            WindowOpenEvent {
+                player,
+                opened: anvil,
+            },
+        );
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::items::Enchantment;
+
+    fn sword(damage: i32) -> ItemStack {
+        let mut sword = ItemStack::new(Item::DiamondSword, 1);
+        sword.damage = Some(damage);
+        sword
+    }
+
+    fn book(enchantment: Enchantment, level: i16) -> ItemStack {
+        let mut book = ItemStack::new(Item::EnchantedBook, 1);
+        book.enchantments.set(enchantment, level);
+        book
+    }
+
+    #[test]
+    fn repair_with_material() {
+        let work = work(
+            Some(sword(1000)),
+            Some(ItemStack::new(Item::Diamond, 5)),
+            None,
+            false,
+        );
+        // 390 durability is repaired per diamond.
+        let output = work.output.unwrap();
+        assert_eq!(output.damage, Some(0));
+        assert_eq!(work.material_used, 3);
+        assert_eq!(work.cost, 3);
+        assert_eq!(output.repair_cost, Some(1));
+
+        assert_eq!(
+            super::work(
+                Some(sword(0)),
+                Some(ItemStack::new(Item::Diamond, 1)),
+                None,
+                false
+            ),
+            AnvilWork::default()
+        );
+        assert_eq!(
+            super::work(
+                Some(sword(10)),
+                Some(ItemStack::new(Item::IronIngot, 1)),
+                None,
+                false
+            ),
+            AnvilWork::default()
+        );
+    }
+
+    #[test]
+    fn combine_items() {
+        let mut left = sword(1000);
+        left.enchantments.set(Enchantment::Sharpness, 3);
+        let mut right = sword(1000);
+        right.enchantments.set(Enchantment::Sharpness, 3);
+        right.enchantments.set(Enchantment::Smite, 2);
+        right.enchantments.set(Enchantment::Unbreaking, 1);
+
+        let work = work(Some(left), Some(right), None, false);
+        let output = work.output.unwrap();
+        // 561 + 561 + 187 durability remain.
+        assert_eq!(output.damage, Some(252));
+        assert_eq!(
+            output.enchantments.iter().collect::<Vec<_>>(),
+            vec![(Enchantment::Sharpness, 4), (Enchantment::Unbreaking, 1)]
+        );
+        // Repair, Sharpness IV, conflicting Smite and Unbreaking I.
+        assert_eq!(work.cost, 2 + 4 + 1 + 2);
+    }
+
+    #[test]
+    fn apply_books() {
+        let work = work(
+            Some(sword(0)),
+            Some(book(Enchantment::Looting, 3)),
+            None,
+            false,
+        );
+        assert_eq!(
+            work.output
+                .unwrap()
+                .enchantments
+                .level(Enchantment::Looting),
+            Some(3)
+        );
+        assert_eq!(work.cost, 2 * 3);
+
+        // Books can't add enchantments which don't apply to the item.
+        assert_eq!(
+            super::work(
+                Some(sword(0)),
+                Some(book(Enchantment::Efficiency, 1)),
+                None,
+                false
+            ),
+            AnvilWork::default()
+        );
+        assert!(super::work(
+            Some(sword(0)),
+            Some(book(Enchantment::Efficiency, 1)),
+            None,
+            true
+        )
+        .output
+        .is_some());
+    }
+
+    #[test]
+    fn rename() {
+        let work = work(Some(sword(5)), None, Some("Excalibur"), false);
+        let output = work.output.unwrap();
+        assert_eq!(output.name, Some(ItemName::new("Excalibur")));
+        assert_eq!(output.damage, Some(5));
+        assert_eq!(work.cost, 1);
+        // Renaming alone doesn't double the prior work cost.
+        assert_eq!(output.repair_cost, Some(0));
+
+        assert_eq!(
+            super::work(Some(sword(5)), None, Some("Diamond Sword"), false),
+            AnvilWork {
+                output: None,
+                cost: 0,
+                material_used: 0,
+            }
+        );
+
+        let mut named = sword(5);
+        named.name = Some(ItemName::new("Excalibur"));
+        let work = super::work(Some(named), None, Some(""), false);
+        assert_eq!(work.output.unwrap().name, None);
+    }
+
+    #[test]
+    fn too_expensive() {
+        let mut left = sword(0);
+        left.repair_cost = Some(39);
+        let work = work(Some(left), Some(book(Enchantment::Looting, 3)), None, false);
+        assert_eq!(work.output, None);
+        assert_eq!(work.cost, 39 + 6);
+        assert!(
+            super::work(Some(left), Some(book(Enchantment::Looting, 3)), None, true)
+                .output
+                .is_some()
+        );
+
+        // Renaming is capped below the limit.
+        let work = super::work(Some(left), None, Some("Excalibur"), false);
+        assert!(work.output.is_some());
+        assert_eq!(work.cost, TOO_EXPENSIVE - 1);
+    }
+}
@@ -1,15 +1,15 @@
 mod animation;
 mod block;
 mod chat;
+mod experience;
 mod gamemode;
 mod health;
-mod keepalive;
 mod teleport;
 
 pub use animation::on_player_animation_broadcast_animation;
 pub use block::*;
 pub use chat::{flush_player_message_receiver, on_chat_broadcast};
+pub use experience::{on_experience_update_send, send_experience};
 pub use gamemode::*;
-pub use health::on_health_update_send;
-pub use keepalive::broadcast_keepalive;
+pub use health::{on_health_update_send, send_health};
 pub use teleport::send_teleported;
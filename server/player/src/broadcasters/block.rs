@@ -2,20 +2,120 @@
 
 use crate::packet_handlers::Digging;
 use crate::{FinishDiggingEvent, StartDiggingEvent};
-use feather_core::network::packets::{BlockBreakAnimation, BlockChange, Effect};
-use feather_server_types::{BlockUpdateCause, BlockUpdateEvent, BumpVec, Game, NetworkId};
+use feather_core::network::packets::{
+    BlockBreakAnimation, BlockChange, BlockChangeRecord, ChunkData, Effect, MultiBlockChange,
+};
+use feather_core::network::Packet;
+use feather_core::util::ChunkPosition;
+use feather_server_types::{
+    BlockChanges, BlockUpdateCause, BlockUpdateEvent, BumpVec, Game, NetworkId, WorldState,
+};
 use fecs::{IntoQuery, Read, World, Write};
+use std::collections::BTreeMap;
 
-/// System for broadcasting block update
-/// events to all clients.
-#[fecs::event_handler]
-pub fn on_block_update_broadcast(event: &BlockUpdateEvent, game: &mut Game, world: &mut World) {
-    // Broadcast Block Change packet.
-    let packet = BlockChange {
-        location: event.pos,
-        block_id: event.new.vanilla_id() as i32,
-    };
-    game.broadcast_chunk_update(world, packet, event.pos.into(), None);
+/// Sends the blocks changed during the tick to
+/// the players able to see them.
+///
+/// A single change is sent as a `BlockChange`, and several
+/// changes in a chunk as a `MultiBlockChange` per chunk section.
+/// When too many blocks in a chunk changed, the sections
+/// containing them are sent again instead.
+#[fecs::system]
+pub fn broadcast_block_changes(game: &mut Game, world: &mut World) {
+    let mut broadcasts = Vec::new();
+    for state in game.worlds.iter_mut() {
+        for (chunk, changes) in state.dirty_chunks.take_block_changes() {
+            for packet in block_changes_packets(state, chunk, changes) {
+                broadcasts.push((state.id, chunk, packet));
+            }
+        }
+    }
+
+    for (world_id, chunk, packet) in broadcasts {
+        game.broadcast_chunk_update_boxed(world, packet, world_id, chunk, None);
+    }
+}
+
+fn block_changes_packets(
+    state: &WorldState,
+    chunk: ChunkPosition,
+    changes: BlockChanges,
+) -> Vec<Box<dyn Packet>> {
+    match changes {
+        BlockChanges::Blocks(blocks) if blocks.len() == 1 => {
+            let location = blocks[0];
+            let block = match state.block_at(location) {
+                Some(block) => block,
+                None => return Vec::new(),
+            };
+            let packet: Box<dyn Packet> = Box::new(BlockChange {
+                location,
+                block_id: block.vanilla_id() as i32,
+            });
+            vec![packet]
+        }
+        BlockChanges::Blocks(blocks) => {
+            // Since 1.16.2, a `MultiBlockChange` can only
+            // hold the changes in a single chunk section.
+            let mut sections: BTreeMap<i32, Vec<BlockChangeRecord>> = BTreeMap::new();
+            // Records only hold Y coordinates from 0 to 255, so blocks
+            // outside that range are sent as separate `BlockChange`s.
+            let mut packets: Vec<Box<dyn Packet>> = Vec::new();
+            for pos in blocks {
+                let block = match state.block_at(pos) {
+                    Some(block) => block,
+                    None => continue,
+                };
+                if !(0..256).contains(&pos.y) {
+                    packets.push(Box::new(BlockChange {
+                        location: pos,
+                        block_id: block.vanilla_id() as i32,
+                    }));
+                    continue;
+                }
+                sections
+                    .entry(pos.y >> 4)
+                    .or_default()
+                    .push(BlockChangeRecord {
+                        x: (pos.x - chunk.x * 16) as u8,
+                        y: pos.y as u8,
+                        z: (pos.z - chunk.z * 16) as u8,
+                        block_id: block.vanilla_id() as i32,
+                    });
+            }
+
+            for records in sections.into_values() {
+                packets.push(Box::new(MultiBlockChange {
+                    chunk_x: chunk.x,
+                    chunk_z: chunk.z,
+                    records,
+                }));
+            }
+            packets
+        }
+        BlockChanges::Sections(sections) => {
+            let handle = match state.chunk_map.chunk_handle_at(chunk) {
+                Some(handle) => handle,
+                None => return Vec::new(),
+            };
+            // Sections which have become empty can't be
+            // sent on their own, so send the whole chunk.
+            let all_present = {
+                let chunk = handle.read();
+                (0..16).all(|i| {
+                    sections & (1 << i) == 0
+                        || chunk
+                            .local_y(i * 16)
+                            .map_or(false, |y| chunk.section(y / 16).is_some())
+                })
+            };
+            let packet: Box<dyn Packet> = Box::new(ChunkData {
+                chunk: handle,
+                section_mask: if all_present { Some(sections) } else { None },
+            });
+            vec![packet]
+        }
+    }
 }
 
 /// Sends an `Effect` packet with status `BlockBreak`
@@ -33,7 +133,7 @@ pub fn on_block_break_broadcast_effect(
             data: event.old.vanilla_id() as i32,
             disable_relative_volume: false,
         };
-        game.broadcast_chunk_update(world, packet, event.pos.chunk(), Some(source));
+        game.broadcast_chunk_update(world, packet, event.world, event.pos.chunk(), Some(source));
     }
 }
 
@@ -50,9 +150,9 @@ pub fn broadcast_block_break_animation(game: &mut Game, world: &mut World) {
         <(Read<Digging>, Read<NetworkId>, Write<LastDestroyStage>)>::query()
             .iter_entities_mut(world.inner_mut())
     {
-        let destroy_stage = ((digging.progress / digging.time) * 9.0).floor().min(9.0) as i8;
+        let destroy_stage = (digging.progress * 10.0).floor().min(9.0) as i8;
         if destroy_stage == last_destroy_stage.0 {
-            return; // no new data to send
+            continue; // no new data to send
         }
         last_destroy_stage.0 = destroy_stage;
 
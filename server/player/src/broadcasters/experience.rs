@@ -0,0 +1,32 @@
+use feather_core::network::packets::{SetExperience, SoundCategory};
+use feather_core::util::Position;
+use feather_server_types::{Experience, ExperienceUpdateEvent, Game, Network, Sound};
+use fecs::{Entity, World};
+
+/// When a player's experience is updated, updates it on the client
+/// and plays a sound each time they reach a multiple of five levels.
+#[fecs::event_handler]
+pub fn on_experience_update_send(event: &ExperienceUpdateEvent, game: &Game, world: &mut World) {
+    send_experience(world, event.player);
+
+    if event.new_level > event.old_level && event.new_level % 5 == 0 {
+        let volume = (event.new_level as f32 / 30.0).min(1.0) * 0.75;
+        let sound = Sound::new("entity.player.levelup", SoundCategory::Players).volume(volume);
+        let pos = *world.get::<Position>(event.player);
+        game.play_sound(world, game.world_of(world, event.player), pos, &sound);
+    }
+}
+
+/// Sends a player's experience bar, level and total experience to their client.
+pub fn send_experience(world: &World, player: Entity) {
+    if let (Some(network), Some(experience)) = (
+        world.try_get::<Network>(player),
+        world.try_get::<Experience>(player),
+    ) {
+        network.send(SetExperience {
+            experience_bar: experience.progress,
+            level: experience.level as i32,
+            total_experience: experience.total as i32,
+        });
+    }
+}
@@ -1,15 +1,24 @@
 use feather_core::network::packets::UpdateHealth;
-use feather_server_types::{HealthUpdateEvent, Network};
-use fecs::World;
+use feather_server_types::{HealthUpdateEvent, Hunger, Network};
+use fecs::{Entity, World};
 
 /// When a player's health is updated, updates it on the client.
 #[fecs::event_handler]
 pub fn on_health_update_send(event: &HealthUpdateEvent, world: &mut World) {
-    if let Some(network) = world.try_get::<Network>(event.entity) {
+    send_health(world, event.entity, event.new);
+}
+
+/// Sends a player's health, food level and saturation to their client.
+pub fn send_health(world: &World, player: Entity, health: u32) {
+    if let Some(network) = world.try_get::<Network>(player) {
+        let hunger = world
+            .try_get::<Hunger>(player)
+            .map(|hunger| *hunger)
+            .unwrap_or_default();
         let packet = UpdateHealth {
-            health: event.new as f32,
-            food: 20,        // todo
-            saturation: 5.0, // todo
+            health: health as f32,
+            food: hunger.food as i32,
+            saturation: hunger.saturation,
         };
         network.send(packet);
     }
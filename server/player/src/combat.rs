@@ -0,0 +1,470 @@
+//! Melee combat: attack strength and cooldown, critical
+//! hits, sweeping attacks, knockback and shields.
+//!
+//! See https://minecraft.gamepedia.com/Damage#Dealing_damage.
+
+use entity::InventoryExt;
+use feather_core::inventory::{Area, Inventory, SlotIndex};
+use feather_core::items::{Enchantment, Item, ItemStack};
+use feather_core::misc::ParticleData;
+use feather_core::network::packets::{AnimationClientbound, EntityVelocity, SoundCategory};
+use feather_core::util::{ClientboundAnimation, Gamemode, Position};
+use feather_definitions::Tool;
+use feather_server_types::{
+    BlocksFallen, DamageSource, Dead, Game, Health, HeldItem, ItemDamageEvent, NetworkId, Physics,
+    Player, Sound, Sprinting, Velocity,
+};
+use feather_server_util::{nearby_entities, protocol_velocity};
+use fecs::{Entity, World};
+use glm::DVec3;
+
+/// Attack damage of an empty hand or an item which isn't a weapon.
+const DEFAULT_ATTACK_DAMAGE: f64 = 1.0;
+/// Attack speed of an empty hand or an item which isn't a weapon.
+const DEFAULT_ATTACK_SPEED: f64 = 4.0;
+/// Square of the maximum distance from which a player can attack an entity.
+const MAX_REACH_SQUARED: f64 = 36.0;
+/// Attack strength above which an attack counts as fully charged.
+const FULL_STRENGTH: f64 = 0.9;
+/// Damage multiplier of critical hits.
+const CRITICAL_MULTIPLIER: f64 = 1.5;
+/// Knockback dealt to every entity hit by an attack.
+const BASE_KNOCKBACK: f64 = 0.4;
+/// Maximum upwards velocity given to an entity by knockback.
+const MAX_KNOCKBACK_Y: f64 = 0.4;
+/// Knockback dealt to an attacker whose attack was blocked by a shield.
+const SHIELD_KNOCKBACK: f64 = 0.5;
+/// Square of the maximum distance between the attacker
+/// and an entity hit by a sweeping attack.
+const SWEEP_REACH_SQUARED: f64 = 9.0;
+/// Height above a player's feet, halfway up their
+/// body, at which the sweep particle is shown.
+const SWEEP_PARTICLE_HEIGHT: f64 = 0.9;
+
+/// The tick on which a player's attack cooldown last started.
+///
+/// The cooldown restarts each time the player
+/// attacks or switches the item they are holding.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttackCooldown(pub u64);
+
+/// Returns the strength of an attack, between 0 and 1, made the given
+/// number of ticks after the attack cooldown started with an item
+/// making `attack_speed` full-strength attacks per second.
+pub fn attack_strength(ticks_since_reset: u64, attack_speed: f64) -> f64 {
+    let cooldown = 20.0 / attack_speed;
+    ((ticks_since_reset as f64 + 0.5) / cooldown)
+        .min(1.0)
+        .max(0.0)
+}
+
+/// Returns the damage dealt by an attack with the given strength. Damage
+/// from enchantments is reduced less by a weak attack than base damage.
+pub fn attack_damage(base: f64, enchantment_bonus: f64, strength: f64) -> f64 {
+    base * (0.2 + strength * strength * 0.8) + enchantment_bonus * strength
+}
+
+/// Returns the extra damage dealt by a weapon with Sharpness of the given level.
+pub fn sharpness_bonus(level: i16) -> f64 {
+    if level > 0 {
+        0.5 * f64::from(level) + 0.5
+    } else {
+        0.0
+    }
+}
+
+/// Returns the fraction of an attack's damage dealt to entities
+/// hit by a sweeping attack, given the level of Sweeping Edge.
+pub fn sweeping_ratio(level: i16) -> f64 {
+    let level = f64::from(level);
+    level / (level + 1.0)
+}
+
+/// Returns the velocity of an entity after it is knocked
+/// back in the given horizontal direction.
+pub fn knockback(velocity: DVec3, direction: DVec3, strength: f64, on_ground: bool) -> DVec3 {
+    let length = (direction.x * direction.x + direction.z * direction.z).sqrt();
+    let (x, z) = if length > 0.0 {
+        (direction.x / length, direction.z / length)
+    } else {
+        (0.0, 0.0)
+    };
+
+    let y = if on_ground {
+        (velocity.y / 2.0 + strength).min(MAX_KNOCKBACK_Y)
+    } else {
+        velocity.y
+    };
+    glm::vec3(
+        velocity.x / 2.0 + x * strength,
+        y,
+        velocity.z / 2.0 + z * strength,
+    )
+}
+
+/// Restarts the attack cooldown of a player.
+pub fn reset_attack_cooldown(game: &Game, world: &mut World, player: Entity) {
+    if let Some(mut cooldown) = world.try_get_mut::<AttackCooldown>(player) {
+        cooldown.0 = game.tick_count;
+    }
+}
+
+/// Makes a player attack an entity with the item they are holding.
+pub fn attack(game: &mut Game, world: &mut World, attacker: Entity, target: Entity) {
+    if attacker == target
+        || !world.is_alive(target)
+        || !world.has::<Health>(target)
+        || world.has::<Dead>(target)
+        || world.has::<Dead>(attacker)
+        || *world.get::<Gamemode>(attacker) == Gamemode::Spectator
+    {
+        return;
+    }
+    if world.has::<Player>(target) && !game.config.gameplay.pvp {
+        return;
+    }
+
+    let pos = *world.get::<Position>(attacker);
+    let target_pos = *world.get::<Position>(target);
+    if pos.distance_squared_to(target_pos) > MAX_REACH_SQUARED {
+        return;
+    }
+
+    let item = world
+        .get::<Inventory>(attacker)
+        .item_in_main_hand(attacker, world);
+    let base_damage = item
+        .as_ref()
+        .and_then(|stack| stack.ty.attack_damage())
+        .unwrap_or(DEFAULT_ATTACK_DAMAGE);
+    let attack_speed = item
+        .as_ref()
+        .and_then(|stack| stack.ty.attack_speed())
+        .unwrap_or(DEFAULT_ATTACK_SPEED);
+    let level = |enchantment| {
+        item.as_ref()
+            .and_then(|stack| stack.enchantments.level(enchantment))
+            .unwrap_or_default()
+    };
+
+    let has_cooldown = game.config.gameplay.pvp_style.has_attack_cooldown();
+    let strength = if has_cooldown {
+        let last_reset = world
+            .try_get::<AttackCooldown>(attacker)
+            .map_or(0, |cooldown| cooldown.0);
+        attack_strength(game.tick_count.saturating_sub(last_reset), attack_speed)
+    } else {
+        1.0
+    };
+    reset_attack_cooldown(game, world, attacker);
+
+    let enchantment_bonus = sharpness_bonus(level(Enchantment::Sharpness));
+    let mut damage = attack_damage(base_damage, enchantment_bonus, strength);
+    let strong = strength > FULL_STRENGTH;
+    let sprinting = world.has::<Sprinting>(attacker);
+    let sprint_knockback = strong && sprinting;
+
+    let falling = !pos.on_ground
+        && world
+            .try_get::<BlocksFallen>(attacker)
+            .map_or(false, |fallen| fallen.0 > 0.0);
+    let critical = strong && falling && !sprinting;
+    if critical {
+        damage *= CRITICAL_MULTIPLIER;
+    }
+
+    let holding_sword = item
+        .as_ref()
+        .map_or(false, |stack| stack.ty.tool() == Some(Tool::Sword));
+    let sweep =
+        has_cooldown && strong && !critical && !sprint_knockback && pos.on_ground && holding_sword;
+
+    let mut extra_knockback = f64::from(level(Enchantment::Knockback));
+    if sprint_knockback {
+        extra_knockback += 1.0;
+    }
+
+    let blocked = game.blocks_with_shield(world, target, DamageSource::Player(attacker));
+    let old_health = world.get::<Health>(target).0;
+    game.damage(
+        target,
+        damage.round() as u32,
+        DamageSource::Player(attacker),
+        world,
+    );
+    if blocked {
+        shield_knockback(game, world, attacker, target);
+        return;
+    }
+    // Mobs are despawned as soon as they are killed.
+    let despawned = !world.is_alive(target);
+    if !despawned && world.get::<Health>(target).0 >= old_health {
+        play_attack_sound(game, world, attacker, "entity.player.attack.nodamage");
+        return;
+    }
+
+    // Knock the target away from the attacker, and further
+    // in the direction the attacker is looking.
+    let look = pos.direction();
+    if !despawned {
+        let mut velocity = knockback(
+            entity_velocity(world, target),
+            target_pos.vec() - pos.vec(),
+            BASE_KNOCKBACK,
+            target_pos.on_ground,
+        );
+        if extra_knockback > 0.0 {
+            velocity = knockback(velocity, look, extra_knockback * 0.5, target_pos.on_ground);
+        }
+        set_velocity(game, world, target, velocity);
+    }
+
+    if sprint_knockback {
+        let _ = world.remove::<Sprinting>(attacker);
+        play_attack_sound(game, world, attacker, "entity.player.attack.knockback");
+    }
+
+    if sweep {
+        let sweep_damage = 1.0 + sweeping_ratio(level(Enchantment::Sweeping)) * damage;
+        sweep_attack(game, world, attacker, target, target_pos, sweep_damage);
+        play_attack_sound(game, world, attacker, "entity.player.attack.sweep");
+
+        let particle_pos = Position {
+            x: pos.x + look.x,
+            y: pos.y + SWEEP_PARTICLE_HEIGHT,
+            z: pos.z + look.z,
+            ..pos
+        };
+        game.spawn_particle(
+            world,
+            game.world_of(world, attacker),
+            particle_pos,
+            ParticleData::SweepAttack,
+            1,
+            glm::vec3(0.0, 0.0, 0.0),
+        );
+    }
+
+    if critical {
+        play_attack_sound(game, world, attacker, "entity.player.attack.crit");
+        if !despawned {
+            broadcast_animation(game, world, target, ClientboundAnimation::CriticalEffect);
+        }
+    } else if !sweep {
+        let sound = if strong {
+            "entity.player.attack.strong"
+        } else {
+            "entity.player.attack.weak"
+        };
+        play_attack_sound(game, world, attacker, sound);
+    }
+    if enchantment_bonus > 0.0 && !despawned {
+        broadcast_animation(
+            game,
+            world,
+            target,
+            ClientboundAnimation::MagicCriticalEffect,
+        );
+    }
+
+    if let Some(stack) = item {
+        damage_weapon(game, world, attacker, stack);
+    }
+}
+
+/// Deals damage from a sweeping attack to the
+/// entities standing next to the target.
+fn sweep_attack(
+    game: &mut Game,
+    world: &mut World,
+    attacker: Entity,
+    target: Entity,
+    target_pos: Position,
+    damage: f64,
+) {
+    let pos = *world.get::<Position>(attacker);
+    let world_id = game.world_of(world, attacker);
+
+    let pvp = game.config.gameplay.pvp;
+    let hit: Vec<Entity> =
+        nearby_entities(world, game, world_id, target_pos, glm::vec3(1.0, 0.25, 1.0))
+            .into_iter()
+            .filter(|&entity| {
+                entity != attacker
+                    && entity != target
+                    && world.has::<Health>(entity)
+                    && !world.has::<Dead>(entity)
+                    && (pvp || !world.has::<Player>(entity))
+                    && pos.distance_squared_to(*world.get::<Position>(entity)) < SWEEP_REACH_SQUARED
+            })
+            .collect();
+
+    let look = pos.direction();
+    for entity in hit {
+        game.damage(
+            entity,
+            damage.round() as u32,
+            DamageSource::Player(attacker),
+            world,
+        );
+        if !world.is_alive(entity) {
+            continue;
+        }
+        let on_ground = world.get::<Position>(entity).on_ground;
+        let velocity = knockback(
+            entity_velocity(world, entity),
+            look,
+            BASE_KNOCKBACK,
+            on_ground,
+        );
+        set_velocity(game, world, entity, velocity);
+    }
+}
+
+/// Knocks back the attacker of an entity which blocked the attack
+/// with a shield. The entity only takes the base knockback.
+fn shield_knockback(game: &Game, world: &mut World, attacker: Entity, target: Entity) {
+    let pos = *world.get::<Position>(attacker);
+    let target_pos = *world.get::<Position>(target);
+
+    let velocity = knockback(
+        entity_velocity(world, target),
+        target_pos.vec() - pos.vec(),
+        BASE_KNOCKBACK,
+        target_pos.on_ground,
+    );
+    set_velocity(game, world, target, velocity);
+
+    let velocity = knockback(
+        entity_velocity(world, attacker),
+        pos.vec() - target_pos.vec(),
+        SHIELD_KNOCKBACK,
+        pos.on_ground,
+    );
+    set_velocity(game, world, attacker, velocity);
+}
+
+fn entity_velocity(world: &World, entity: Entity) -> DVec3 {
+    world
+        .try_get::<Velocity>(entity)
+        .map_or(glm::vec3(0.0, 0.0, 0.0), |velocity| velocity.0)
+}
+
+/// Changes the velocity of an entity after it is knocked back.
+///
+/// Entities without physics, such as players, only
+/// have their new velocity sent to clients.
+fn set_velocity(game: &Game, world: &mut World, entity: Entity, velocity: DVec3) {
+    if world.has::<Physics>(entity) {
+        if let Some(mut vel) = world.try_get_mut::<Velocity>(entity) {
+            vel.0 = velocity;
+        }
+        return;
+    }
+
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity);
+    let packet = EntityVelocity {
+        entity_id: world.get::<NetworkId>(entity).0,
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+    game.broadcast_entity_update(world, packet, entity, None);
+}
+
+fn broadcast_animation(
+    game: &Game,
+    world: &World,
+    entity: Entity,
+    animation: ClientboundAnimation,
+) {
+    let packet = AnimationClientbound {
+        entity_id: world.get::<NetworkId>(entity).0,
+        animation,
+    };
+    game.broadcast_entity_update(world, packet, entity, None);
+}
+
+fn play_attack_sound(game: &Game, world: &World, attacker: Entity, name: &str) {
+    let pos = *world.get::<Position>(attacker);
+    let sound = Sound::new(name, SoundCategory::Players);
+    game.play_sound(world, game.world_of(world, attacker), pos, &sound);
+}
+
+/// Damages the weapon a player attacked with.
+fn damage_weapon(game: &mut Game, world: &mut World, player: Entity, stack: ItemStack) {
+    let damage_taken = match stack.ty.tool() {
+        _ if stack.ty == Item::Trident => 1,
+        Some(Tool::Sword) | Some(Tool::Hoe) => 1,
+        Some(_) => 2,
+        None => return,
+    };
+
+    let held_item = world.get::<HeldItem>(player).0;
+    game.handle(
+        world,
+        ItemDamageEvent {
+            player,
+            slot: SlotIndex {
+                area: Area::Hotbar,
+                slot: held_item,
+            },
+            damage_taken,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attack_strength_recharges() {
+        // A sword (1.6 attacks per second) recharges in 12.5 ticks.
+        assert!((attack_strength(0, 1.6) - 0.04).abs() < 1e-9);
+        assert!((attack_strength(6, 1.6) - 0.52).abs() < 1e-9);
+        assert_eq!(attack_strength(12, 1.6), 1.0);
+        assert_eq!(attack_strength(100, 1.6), 1.0);
+        // A fist recharges in 5 ticks.
+        assert_eq!(attack_strength(5, DEFAULT_ATTACK_SPEED), 1.0);
+    }
+
+    #[test]
+    fn weak_attacks_deal_less_damage() {
+        assert!((attack_damage(7.0, 0.0, 1.0) - 7.0).abs() < 1e-9);
+        assert!((attack_damage(7.0, 0.0, 0.0) - 1.4).abs() < 1e-9);
+        // Sharpness V adds 3 damage at full strength.
+        assert!((attack_damage(7.0, sharpness_bonus(5), 1.0) - 10.0).abs() < 1e-9);
+        assert!((attack_damage(7.0, sharpness_bonus(5), 0.5) - 4.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sweeping_edge() {
+        assert_eq!(sweeping_ratio(0), 0.0);
+        assert!((sweeping_ratio(3) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn knockback_pushes_away_and_up() {
+        let velocity = knockback(
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(2.0, 5.0, 0.0),
+            0.4,
+            true,
+        );
+        assert!((velocity.x - 0.4).abs() < 1e-9);
+        assert!((velocity.y - MAX_KNOCKBACK_Y).abs() < 1e-9);
+        assert_eq!(velocity.z, 0.0);
+
+        // Entities in the air keep their vertical velocity.
+        let velocity = knockback(
+            glm::vec3(0.2, -0.5, 0.0),
+            glm::vec3(0.0, 0.0, -1.0),
+            0.4,
+            false,
+        );
+        assert!((velocity.x - 0.1).abs() < 1e-9);
+        assert_eq!(velocity.y, -0.5);
+        assert!((velocity.z + 0.4).abs() < 1e-9);
+    }
+}
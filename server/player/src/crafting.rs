@@ -0,0 +1,117 @@
+//! Crafting in the crafting grids of inventories.
+
+use feather_core::inventory::{Area, Inventory, SlotIndex};
+use feather_core::items::ItemStack;
+use feather_server_types::{crafting_remainder, Game, InventoryUpdateEvent, Recipe};
+use fecs::{Entity, World};
+use smallvec::{smallvec, SmallVec};
+
+/// When the crafting grid of an inventory changes,
+/// sets its crafting output to the result of the
+/// recipe the grid matches.
+#[fecs::event_handler]
+pub fn on_inventory_update_update_crafting_output(
+    event: &InventoryUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !event
+        .slots
+        .iter()
+        .any(|slot| slot.area == Area::CraftingInput)
+    {
+        return;
+    }
+
+    {
+        let inventory = match world.try_get::<Inventory>(event.entity) {
+            Some(inventory) => inventory,
+            None => return,
+        };
+        let grid = crafting_grid(&inventory);
+        let result = game
+            .recipes
+            .match_crafting(&grid, grid_width(&grid))
+            .and_then(Recipe::result);
+
+        let updated = match result {
+            Some(result) => inventory.set_item_at(Area::CraftingOutput, 0, result),
+            None => inventory.remove_item_at(Area::CraftingOutput, 0),
+        };
+        if updated.is_err() {
+            return;
+        }
+    }
+
+    // Sent even if unchanged, as clients clear the
+    // output slot when they take the crafted item.
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: smallvec![SlotIndex {
+                area: Area::CraftingOutput,
+                slot: 0,
+            }],
+            entity: event.entity,
+        },
+    );
+}
+
+/// Returns the items in the crafting grid of an inventory, row by row.
+pub fn crafting_grid(inventory: &Inventory) -> Vec<Option<ItemStack>> {
+    inventory
+        .enumerate()
+        .filter(|(index, _)| index.area == Area::CraftingInput)
+        .map(|(_, slot)| slot)
+        .collect()
+}
+
+/// Returns the width of a square crafting grid:
+/// 2 for a player's inventory and 3 for a crafting table.
+fn grid_width(grid: &[Option<ItemStack>]) -> usize {
+    (grid.len() as f64).sqrt() as usize
+}
+
+/// Crafts the recipe matched by the crafting grid of an inventory,
+/// using up one item in each slot of the grid. Items such as water
+/// buckets leave a remainder in their slot.
+///
+/// Returns the ID of the recipe, or `None` if the grid matches none.
+/// The crafted item isn't added anywhere, and should be taken from
+/// the crafting output slot beforehand.
+pub fn consume_crafting_ingredients(
+    game: &mut Game,
+    world: &mut World,
+    entity: Entity,
+) -> Option<String> {
+    let (id, slots) = {
+        let inventory = world.try_get::<Inventory>(entity)?;
+        let grid = crafting_grid(&inventory);
+        let recipe = game.recipes.match_crafting(&grid, grid_width(&grid))?;
+        recipe.result()?;
+
+        let mut slots: SmallVec<[SlotIndex; 2]> = SmallVec::new();
+        for (slot, stack) in grid.iter().enumerate() {
+            let stack = match stack {
+                Some(stack) => *stack,
+                None => continue,
+            };
+            let remaining = match crafting_remainder(stack.ty) {
+                Some(remainder) if stack.amount == 1 => ItemStack::new(remainder, 1),
+                _ => stack.of_amount(stack.amount - 1),
+            };
+            inventory
+                .set_item_at(Area::CraftingInput, slot, remaining)
+                .ok()?;
+            slots.push(SlotIndex {
+                area: Area::CraftingInput,
+                slot,
+            });
+        }
+
+        (recipe.id.clone(), slots)
+    };
+
+    game.handle(world, InventoryUpdateEvent { slots, entity });
+    Some(id)
+}
@@ -6,18 +6,28 @@
 //! in that sense.
 
 use entity::drops::drop_item;
+use entity::spawn_experience_orbs;
+use feather_core::items::Enchantment;
+use feather_core::network::packets::{CombatEvent, CombatEventType, DeathCombatEvent};
+use feather_core::text::{TextRoot, Translate};
 use feather_core::util::Position;
-use feather_server_types::{Dead, EntityDeathEvent, Game, Inventory, InventoryUpdateEvent, Player};
+use feather_server_types::{
+    ChatEvent, ChatPosition, Dead, EntityDeathEvent, Experience, Game, Inventory,
+    InventoryUpdateEvent, Name, Network, NetworkId, Player,
+};
 use fecs::World;
 
-/// Scatters a player's items when they die.
+/// Scatters a player's items when they die, unless
+/// the `keepInventory` game rule is enabled.
+///
+/// Items with Curse of Vanishing are destroyed instead.
 #[fecs::event_handler]
 pub fn on_player_death_scatter_inventory(
     event: &EntityDeathEvent,
     game: &mut Game,
     world: &mut World,
 ) {
-    if !world.has::<Player>(event.entity) {
+    if !world.has::<Player>(event.entity) || game.game_rules.keep_inventory {
         return;
     }
 
@@ -40,13 +50,40 @@ pub fn on_player_death_scatter_inventory(
 
     drop(inventory);
 
-    for item in items_to_spawn {
-        drop_item(game, world, item, pos);
+    let world_id = game.world_of(world, event.entity);
+    for item in items_to_spawn.into_iter().filter(|item| {
+        item.enchantments
+            .level(Enchantment::VanishingCurse)
+            .is_none()
+    }) {
+        drop_item(game, world, world_id, item, pos);
     }
 
     game.handle(world, event);
 }
 
+/// Drops experience orbs when a player dies, unless
+/// the `keepInventory` game rule is enabled. The rest of
+/// the player's experience is lost when they respawn.
+#[fecs::event_handler]
+pub fn on_player_death_drop_experience(
+    event: &EntityDeathEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if game.game_rules.keep_inventory {
+        return;
+    }
+    let points = match world.try_get::<Experience>(event.entity) {
+        Some(experience) => experience.dropped_on_death(),
+        None => return,
+    };
+
+    let pos = *world.get::<Position>(event.entity);
+    let world_id = game.world_of(world, event.entity);
+    spawn_experience_orbs(game, world, world_id, pos, points);
+}
+
 /// Adds the `Dead` component to a player when they die to
 /// avoid causing them to be physically interacted with.
 ///
@@ -58,3 +95,53 @@ pub fn on_player_death_mark_dead(event: &EntityDeathEvent, world: &mut World) {
         world.add(event.entity, Dead).unwrap();
     }
 }
+
+/// Shows the death screen to a player when they die and, if
+/// the `showDeathMessages` game rule is enabled, announces
+/// their death in chat.
+#[fecs::event_handler]
+pub fn on_player_death_send_message(event: &EntityDeathEvent, game: &mut Game, world: &mut World) {
+    if !world.has::<Player>(event.entity) {
+        return;
+    }
+
+    let attacker = event
+        .source
+        .attacker()
+        .filter(|attacker| world.is_alive(*attacker));
+    let attacker_name = attacker
+        .and_then(|attacker| world.try_get::<Name>(attacker))
+        .map(|name| name.0.clone());
+
+    let mut args = vec![world.get::<Name>(event.entity).0.clone()];
+    args.extend(attacker_name.clone());
+    let message: String = TextRoot::from(
+        Translate::from(event.source.death_message_key(attacker_name.is_some())) * args,
+    )
+    .into();
+
+    let attacker_id = attacker
+        .and_then(|attacker| world.try_get::<NetworkId>(attacker))
+        .map_or(-1, |id| id.0);
+    let player_id = world.get::<NetworkId>(event.entity).0;
+    let network = world.get::<Network>(event.entity);
+    network.send(CombatEvent {
+        event: CombatEventType::EntityDead(player_id, attacker_id, message.clone()),
+    });
+    network.send(DeathCombatEvent {
+        player_id,
+        entity_id: attacker_id,
+        message: message.clone(),
+    });
+    drop(network);
+
+    if game.game_rules.show_death_messages {
+        game.handle(
+            world,
+            ChatEvent {
+                message,
+                position: ChatPosition::SystemMessage,
+            },
+        );
+    }
+}
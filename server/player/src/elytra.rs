@@ -0,0 +1,161 @@
+//! Gliding with an elytra. Players start gliding when they jump
+//! while falling with an elytra on and stop once they land.
+//! Elytra lose durability while gliding, and flying into a wall
+//! hurts. Firework rockets boost gliding players.
+
+use entity::{consume_held_item, set_entity_flag};
+use feather_core::blocks::SimplifiedBlockKind;
+use feather_core::entitymeta::EntityBitMask;
+use feather_core::inventory::{Area, Inventory, SlotIndex};
+use feather_core::items::{Item, ItemStack};
+use feather_core::util::{Gamemode, Position};
+use feather_server_types::{DamageSource, Dead, Game, Gliding, ItemDamageEvent, PreviousPosition};
+use fecs::{Entity, IntoQuery, Read, World};
+
+/// Ticks between each point of durability lost by an elytra.
+const DURABILITY_INTERVAL: u64 = 20;
+
+/// Horizontal speed, in blocks per tick, which a gliding player
+/// can lose in one tick without getting hurt by a wall.
+const SAFE_SPEED_LOSS: f64 = 0.3;
+
+/// Returns whether the given stack is an elytra which can be
+/// used to glide. Elytra are never broken by gliding: they
+/// stop working with one point of durability left.
+pub fn is_usable_elytra(stack: ItemStack) -> bool {
+    stack.ty == Item::Elytra
+        && stack.damage.unwrap_or(0) < Item::Elytra.durability().unwrap_or(0) as i32 - 1
+}
+
+/// Returns the damage taken by a gliding player whose
+/// horizontal speed went from `old_speed` to `new_speed`,
+/// in blocks per tick, by flying into a wall.
+pub fn wall_damage(old_speed: f64, new_speed: f64) -> u32 {
+    ((old_speed - new_speed - SAFE_SPEED_LOSS) * 10.0).max(0.0) as u32
+}
+
+fn wears_usable_elytra(world: &World, player: Entity) -> bool {
+    world
+        .get::<Inventory>(player)
+        .item_at(Area::Torso, 0)
+        .ok()
+        .flatten()
+        .map_or(false, is_usable_elytra)
+}
+
+/// Makes a player start gliding, if they are falling with
+/// a usable elytra on. Returns whether they started.
+pub fn start_gliding(game: &mut Game, world: &mut World, player: Entity) -> bool {
+    let pos = *world.get::<Position>(player);
+    let in_water = game
+        .block_at(game.world_of(world, player), pos.block())
+        .map_or(false, |block| {
+            block.simplified_kind() == SimplifiedBlockKind::Water
+                || block.waterlogged() == Some(true)
+        });
+    if pos.on_ground
+        || in_water
+        || world.has::<Gliding>(player)
+        || world.has::<Dead>(player)
+        || *world.get::<Gamemode>(player) == Gamemode::Spectator
+        || !wears_usable_elytra(world, player)
+    {
+        return false;
+    }
+
+    world.add(player, Gliding::default()).unwrap();
+    set_entity_flag(game, world, player, EntityBitMask::FLYING_WITH_ELYTRA, true);
+    true
+}
+
+/// Makes a gliding player stop gliding.
+pub fn stop_gliding(game: &mut Game, world: &mut World, player: Entity) {
+    if world.remove::<Gliding>(player).is_ok() {
+        set_entity_flag(
+            game,
+            world,
+            player,
+            EntityBitMask::FLYING_WITH_ELYTRA,
+            false,
+        );
+    }
+}
+
+/// Boosts a gliding player with the firework rocket they
+/// hold in their main hand, using it up.
+pub fn boost_with_firework(game: &mut Game, world: &mut World, player: Entity, stack: ItemStack) {
+    entity::firework_rocket::boost(game, world, player, stack);
+    consume_held_item(game, world, player, stack);
+}
+
+/// System which stops players from gliding once they land
+/// or their elytra can't be used anymore, damages their
+/// elytra and hurts those who fly into a wall.
+#[fecs::system]
+pub fn update_gliding(game: &mut Game, world: &mut World) {
+    let players: Vec<(Entity, f64, Position, Option<Position>)> =
+        <(Read<Gliding>, Read<Position>, Read<PreviousPosition>)>::query()
+            .iter_entities(world.inner())
+            .map(|(player, (gliding, pos, prev_pos))| (player, gliding.speed, *pos, prev_pos.0))
+            .collect();
+
+    for (player, old_speed, pos, prev_pos) in players {
+        if pos.on_ground || world.has::<Dead>(player) || !wears_usable_elytra(world, player) {
+            stop_gliding(game, world, player);
+            continue;
+        }
+
+        let speed = prev_pos.map_or(old_speed, |prev_pos| {
+            (pos.x - prev_pos.x).hypot(pos.z - prev_pos.z)
+        });
+        world.get_mut::<Gliding>(player).speed = speed;
+
+        let damage = wall_damage(old_speed, speed);
+        if damage > 0 {
+            game.damage(player, damage, DamageSource::FlyIntoWall, world);
+        }
+
+        if game.tick_count % DURABILITY_INTERVAL == 0
+            && *world.get::<Gamemode>(player) != Gamemode::Creative
+        {
+            game.handle(
+                world,
+                ItemDamageEvent {
+                    player,
+                    slot: SlotIndex {
+                        area: Area::Torso,
+                        slot: 0,
+                    },
+                    damage_taken: 1,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elytra_stops_working_before_breaking() {
+        let mut elytra = ItemStack::new(Item::Elytra, 1);
+        assert!(is_usable_elytra(elytra));
+        elytra.damage = Some(430);
+        assert!(is_usable_elytra(elytra));
+        elytra.damage = Some(431);
+        assert!(!is_usable_elytra(elytra));
+        assert!(!is_usable_elytra(ItemStack::new(
+            Item::DiamondChestplate,
+            1
+        )));
+    }
+
+    #[test]
+    fn flying_into_walls() {
+        assert_eq!(wall_damage(1.0, 0.95), 0);
+        assert_eq!(wall_damage(0.3, 0.0), 0);
+        assert_eq!(wall_damage(1.3, 0.0), 10);
+        assert_eq!(wall_damage(0.5, 1.5), 0);
+    }
+}
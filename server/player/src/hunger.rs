@@ -0,0 +1,240 @@
+//! Hunger of players: exhaustion from moving, natural
+//! regeneration, starvation and eating food.
+
+use crate::{send_health, ItemTimedUse};
+use feather_core::inventory::{Area, Inventory, SlotIndex};
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{EntityStatus, SoundCategory};
+use feather_core::util::{Gamemode, Position};
+use feather_server_types::{
+    BumpVec, CanTakeDamage, DamageSource, Dead, Game, Health, HealthUpdateEvent, HeldItem, Hunger,
+    HungerEffect, InventoryUpdateEvent, MaxHealth, Network, NetworkId, Player, PreviousPosition,
+    Sound, Sprinting, DAMAGE_EXHAUSTION, JUMP_EXHAUSTION, SPRINT_EXHAUSTION,
+    SPRINT_JUMP_EXHAUSTION,
+};
+use fecs::{component, Entity, IntoQuery, Read, World, Write};
+use rand::Rng;
+use smallvec::smallvec;
+use std::cell::RefCell;
+
+/// Number of ticks it takes to eat most food.
+const EAT_TICKS: u64 = 32;
+/// Number of ticks it takes to eat dried kelp.
+const EAT_FAST_TICKS: u64 = 16;
+
+/// Entity status sent to a player when they finish using an item.
+const FINISH_USING_ITEM_STATUS: i8 = 9;
+
+/// Adds exhaustion to a player from an action, unless
+/// they are in a gamemode where they can't take damage.
+pub fn exhaust(world: &mut World, player: Entity, exhaustion: f32) {
+    if !world.has::<CanTakeDamage>(player) {
+        return;
+    }
+
+    if let Some(mut hunger) = world.try_get_mut::<Hunger>(player) {
+        hunger.exhaust(exhaustion);
+    }
+}
+
+/// System which ticks the hunger of players, regenerating
+/// their health or damaging them if they are starving.
+#[fecs::system]
+pub fn tick_hunger(game: &mut Game, world: &mut World) {
+    let natural_regeneration = game.game_rules.natural_regeneration;
+
+    // (entity, effect, whether food or saturation changed)
+    let updates = RefCell::new(BumpVec::<(Entity, HungerEffect, bool)>::new_in(game.bump()));
+
+    <(Write<Hunger>, Read<Health>, Read<MaxHealth>)>::query()
+        .filter(!component::<Dead>())
+        .for_each_entities_mut(
+            world.inner_mut(),
+            |(entity, (mut hunger, health, max_health))| {
+                let old = *hunger;
+                let effect = hunger.tick(health.0, max_health.0, natural_regeneration);
+                let changed = old.food != hunger.food || old.saturation != hunger.saturation;
+                if changed || effect != HungerEffect::None {
+                    updates.borrow_mut().push((entity, effect, changed));
+                }
+            },
+        );
+
+    for (entity, effect, changed) in updates.into_inner() {
+        // Healing and damage send the new food level along with the health.
+        match effect {
+            HungerEffect::Heal(amount) => game.heal(entity, amount, world),
+            HungerEffect::Starve(amount) if world.has::<CanTakeDamage>(entity) => {
+                game.damage(entity, amount, DamageSource::Starvation, world)
+            }
+            _ if changed => {
+                let health = world.get::<Health>(entity).0;
+                send_health(world, entity, health);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// System which adds exhaustion to players who sprinted or jumped this tick.
+#[fecs::system]
+pub fn exhaust_moving_players(game: &mut Game, world: &mut World) {
+    let exhausted = RefCell::new(BumpVec::<(Entity, f32)>::new_in(game.bump()));
+
+    <(Read<Position>, Read<PreviousPosition>)>::query()
+        .filter(component::<Player>())
+        .filter(component::<Hunger>())
+        .filter(!component::<Dead>())
+        .for_each_entities(world.inner(), |(entity, (pos, prev_pos))| {
+            let prev_pos = match prev_pos.0 {
+                Some(prev_pos) => prev_pos,
+                None => return,
+            };
+            let sprinting = world.has::<Sprinting>(entity);
+
+            let mut exhaustion = 0.0;
+            if sprinting {
+                let distance = ((pos.x - prev_pos.x).powi(2) + (pos.z - prev_pos.z).powi(2)).sqrt();
+                exhaustion += distance as f32 * SPRINT_EXHAUSTION;
+            }
+            if prev_pos.on_ground && !pos.on_ground && pos.y > prev_pos.y {
+                exhaustion += if sprinting {
+                    SPRINT_JUMP_EXHAUSTION
+                } else {
+                    JUMP_EXHAUSTION
+                };
+            }
+
+            if exhaustion > 0.0 {
+                exhausted.borrow_mut().push((entity, exhaustion));
+            }
+        });
+
+    for (entity, exhaustion) in exhausted.into_inner() {
+        exhaust(world, entity, exhaustion);
+    }
+}
+
+/// When a player takes damage, adds exhaustion.
+#[fecs::event_handler]
+pub fn on_health_update_exhaust(event: &HealthUpdateEvent, world: &mut World) {
+    if event.new < event.old {
+        exhaust(world, event.entity, DAMAGE_EXHAUSTION);
+    }
+}
+
+/// Returns whether a player may start eating the given item.
+pub fn can_eat(world: &World, player: Entity, item: Item) -> bool {
+    if item.food_points().is_none() {
+        return false;
+    }
+
+    let always_edible = matches!(
+        item,
+        Item::GoldenApple | Item::EnchantedGoldenApple | Item::ChorusFruit
+    );
+    always_edible
+        || *world.get::<Gamemode>(player) == Gamemode::Creative
+        || world
+            .try_get::<Hunger>(player)
+            .map_or(false, |hunger| hunger.can_eat())
+}
+
+/// Returns the number of ticks it takes to eat an item.
+fn eat_ticks(item: Item) -> u64 {
+    match item {
+        Item::DriedKelp => EAT_FAST_TICKS,
+        _ => EAT_TICKS,
+    }
+}
+
+/// Returns the item left over after eating a food item, if any.
+fn leftover(item: Item) -> Option<Item> {
+    match item {
+        Item::MushroomStew | Item::RabbitStew | Item::BeetrootSoup => Some(Item::Bowl),
+        _ => None,
+    }
+}
+
+/// System which finishes eating food for players who
+/// have been using a food item for long enough.
+#[fecs::system]
+pub fn finish_eating(game: &mut Game, world: &mut World) {
+    let finished = RefCell::new(BumpVec::<(Entity, ItemStack)>::new_in(game.bump()));
+
+    <(Read<ItemTimedUse>, Read<Inventory>, Read<HeldItem>)>::query()
+        .filter(component::<Hunger>())
+        .for_each_entities(
+            world.inner(),
+            |(entity, (timed_use, inventory, held_item))| {
+                let stack = match inventory.item_at(Area::Hotbar, held_item.0) {
+                    Ok(Some(stack)) if stack.ty.food_points().is_some() => stack,
+                    _ => return,
+                };
+                if game.tick_count - timed_use.tick_start >= eat_ticks(stack.ty) {
+                    finished.borrow_mut().push((entity, stack));
+                }
+            },
+        );
+
+    for (player, stack) in finished.into_inner() {
+        eat(game, world, player, stack);
+    }
+}
+
+/// Eats the food item held by a player.
+fn eat(game: &mut Game, world: &mut World, player: Entity, stack: ItemStack) {
+    let _ = world.remove::<ItemTimedUse>(player);
+
+    let food = stack.ty.food_points().unwrap_or_default();
+    let saturation_modifier = stack.ty.saturation_modifier().unwrap_or_default();
+    world
+        .get_mut::<Hunger>(player)
+        .eat(food, saturation_modifier as f32);
+
+    if *world.get::<Gamemode>(player) != Gamemode::Creative {
+        let held_item = world.get::<HeldItem>(player).0;
+        {
+            let inventory = world.get::<Inventory>(player);
+            let result = match leftover(stack.ty) {
+                Some(leftover) => {
+                    inventory.set_item_at(Area::Hotbar, held_item, ItemStack::new(leftover, 1))
+                }
+                None if stack.amount > 1 => inventory.set_item_at(
+                    Area::Hotbar,
+                    held_item,
+                    stack.of_amount(stack.amount - 1),
+                ),
+                None => inventory.remove_item_at(Area::Hotbar, held_item),
+            };
+            result.unwrap();
+        }
+        game.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: smallvec![SlotIndex {
+                    area: Area::Hotbar,
+                    slot: held_item,
+                }],
+                entity: player,
+            },
+        );
+    }
+
+    let network = world.get::<Network>(player);
+    network.send(EntityStatus {
+        entity_id: world.get::<NetworkId>(player).0,
+        entity_status: FINISH_USING_ITEM_STATUS,
+    });
+    drop(network);
+
+    let pitch = 0.9 + game.rng().gen_range(0.0, 0.1);
+    let sound = Sound::new("entity.player.burp", SoundCategory::Players)
+        .volume(0.5)
+        .pitch(pitch);
+    let pos = *world.get::<Position>(player);
+    game.play_sound(world, game.world_of(world, player), pos, &sound);
+
+    let health = world.get::<Health>(player).0;
+    send_health(world, player, health);
+}
@@ -1,10 +1,13 @@
 //! Join logic for players.
 
+use feather_core::anvil::level::LevelGeneratorType;
 use feather_core::blocks::BlockId;
+use feather_core::dimension::dimension_codec;
 use feather_core::network::packets::{
     HeldItemChangeClientbound, JoinGame, PlayerPositionAndLookClientbound, SpawnPosition, Tags,
 };
-use feather_core::util::{BlockPosition, Difficulty, Dimension, Gamemode, Position};
+use feather_core::util::{BlockPosition, Difficulty, Gamemode, Position};
+use feather_server_commands::CommandState;
 use feather_server_network::{ListenerToServerMessage, NetworkIoManager, ServerToListenerMessage};
 use feather_server_types::{
     BumpVec, ChunkSendEvent, Game, HeldItem, Network, NetworkId, PlayerJoinEvent,
@@ -108,21 +111,43 @@ pub fn on_chunk_send_join_player(event: &ChunkSendEvent, game: &Game, world: &mu
 }
 
 #[fecs::event_handler]
-pub fn on_player_join_send_join_packets(event: &PlayerJoinEvent, game: &Game, world: &mut World) {
+pub fn on_player_join_send_join_packets(
+    event: &PlayerJoinEvent,
+    game: &Game,
+    world: &mut World,
+    #[default] commands: &CommandState,
+) {
     let network = world.get::<Network>(event.player);
     let id = world.get::<NetworkId>(event.player);
     let gamemode = *world.get::<Gamemode>(event.player);
     let held_item_slot = world.get::<HeldItem>(event.player);
 
+    let dimension = game.worlds[game.world_of(world, event.player)].dimension;
+
+    let mut world_names = Vec::new();
+    for state in game.worlds.iter() {
+        let name = state.dimension.type_id().to_owned();
+        if !world_names.contains(&name) {
+            world_names.push(name);
+        }
+    }
+
     // TODO
     let join_packet = JoinGame {
         entity_id: id.0,
         gamemode: gamemode.id(),
-        dimension: Dimension::Overwold.id(),
+        dimension: dimension.id(),
         difficulty: Difficulty::Medium.id(),
         max_players: game.config.server.max_players as u8,
         level_type: game.level.generator_name.clone(),
         reduced_debug_info: false,
+        view_distance: i32::from(game.config.server.view_distance),
+        dimension_codec: dimension_codec(&game.dimension_types)
+            .expect("failed to encode dimension codec"),
+        dimension_type: game.dimension_type_nbt(dimension),
+        world_name: dimension.type_id().to_owned(),
+        world_names,
+        is_flat: game.level.generator_type() == LevelGeneratorType::Flat,
     };
     network.send(join_packet);
 
@@ -161,7 +186,7 @@ pub fn on_player_join_send_join_packets(event: &PlayerJoinEvent, game: &Game, wo
     };
     network.send(tags_packet);
 
-    // TODO declare commands
+    network.send(commands.declare_commands());
 
     // TODO unlock recipes
 }
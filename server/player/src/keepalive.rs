@@ -0,0 +1,158 @@
+//! Keepalives, which measure players' latency and
+//! detect players who stopped responding.
+
+use crate::{IteratorExt, Joined};
+use feather_core::network::packets::{KeepAliveClientbound, KeepAliveServerbound};
+use feather_server_types::{BumpVec, Config, Game, Name, Network, PacketBuffers, TabListEntry};
+use feather_server_util::current_time_in_millis;
+use fecs::{IntoQuery, Read, World, Write};
+use std::sync::Arc;
+
+/// A player's keepalive state.
+#[derive(Copy, Clone, Debug)]
+pub struct KeepAlive {
+    /// Time the last keepalive was sent, in milliseconds.
+    pub last_sent: u64,
+    /// ID of the keepalive the player has yet to respond to.
+    /// This is the time it was sent.
+    pub pending: Option<u64>,
+}
+
+impl KeepAlive {
+    pub fn new(now: u64) -> Self {
+        Self {
+            last_sent: now,
+            pending: None,
+        }
+    }
+
+    /// Returns whether the player failed to respond to the
+    /// pending keepalive within the configured timeout. Players
+    /// who haven't joined yet are given the join timeout.
+    fn is_timed_out(self, now: u64, joined: bool, config: &Config) -> bool {
+        let timeout = if joined {
+            config.io.timeout
+        } else {
+            config.io.join_timeout
+        };
+        self.pending
+            .map_or(false, |sent| now >= sent + timeout.as_millis() as u64)
+    }
+}
+
+/// System which sends keepalives to players at the configured
+/// interval, once they have responded to the previous one.
+#[fecs::system]
+pub fn send_keepalives(game: &Game, world: &mut World) {
+    let now = current_time_in_millis();
+    let interval = game.config.io.keepalive_interval.as_millis() as u64;
+
+    <(Read<Network>, Write<KeepAlive>)>::query().par_for_each_mut(
+        world.inner_mut(),
+        |(network, mut keepalive)| {
+            if keepalive.pending.is_some() || now < keepalive.last_sent + interval {
+                return;
+            }
+
+            // Sent immediately so that the latency measured
+            // doesn't include the time until the flush.
+            network.send_now(KeepAliveClientbound { keep_alive_id: now });
+            keepalive.last_sent = now;
+            keepalive.pending = Some(now);
+        },
+    );
+}
+
+/// System which handles keepalive responses and
+/// measures players' latency from them.
+#[fecs::system]
+pub fn handle_keepalives(world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    let now = current_time_in_millis();
+    packet_buffers
+        .received::<KeepAliveServerbound>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let sent = {
+                let mut keepalive = world.get_mut::<KeepAlive>(player);
+                if keepalive.pending != Some(packet.id as u64) {
+                    log::debug!(
+                        "Player `{}` responded to unknown keepalive {}",
+                        world.get::<Name>(player).0,
+                        packet.id
+                    );
+                    return;
+                }
+                keepalive.pending.take().unwrap()
+            };
+
+            let mut entry = world.get_mut::<TabListEntry>(player);
+            entry.latency = smooth_latency(entry.latency, now.saturating_sub(sent));
+        });
+}
+
+/// Returns a player's latency after a keepalive
+/// response, smoothing it as vanilla does.
+fn smooth_latency(old: i32, measured: u64) -> i32 {
+    let measured = measured.min(i32::MAX as u64) as i64;
+    ((i64::from(old) * 3 + measured) / 4) as i32
+}
+
+/// System which disconnects players who haven't responded
+/// to a keepalive within the configured timeout.
+///
+/// Players who are still loading the chunks around them
+/// when joining are given the longer join timeout.
+#[fecs::system]
+pub fn time_out_players(game: &mut Game, world: &mut World) {
+    let now = current_time_in_millis();
+
+    let mut timed_out = BumpVec::new_in(game.bump());
+    for (player, keepalive) in <Read<KeepAlive>>::query().iter_entities(world.inner()) {
+        if keepalive.is_timed_out(now, world.has::<Joined>(player), &game.config) {
+            timed_out.push(player);
+        }
+    }
+
+    for player in timed_out {
+        game.disconnect(player, world, "Timed out");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timed_out_only_with_pending_keepalive() {
+        // The default timeout is 30 seconds.
+        let config = Config::default();
+        let mut keepalive = KeepAlive::new(1000);
+        assert!(!keepalive.is_timed_out(100_000, true, &config));
+
+        keepalive.pending = Some(1000);
+        assert!(!keepalive.is_timed_out(30_999, true, &config));
+        assert!(keepalive.is_timed_out(31_000, true, &config));
+    }
+
+    #[test]
+    fn joining_players_get_join_timeout() {
+        // The default join timeout is a minute.
+        let config = Config::default();
+        let keepalive = KeepAlive {
+            last_sent: 0,
+            pending: Some(0),
+        };
+
+        assert!(keepalive.is_timed_out(45_000, true, &config));
+        assert!(!keepalive.is_timed_out(45_000, false, &config));
+        assert!(keepalive.is_timed_out(60_000, false, &config));
+    }
+
+    #[test]
+    fn latency_is_smoothed() {
+        assert_eq!(smooth_latency(0, 100), 25);
+        assert_eq!(smooth_latency(100, 100), 100);
+        assert_eq!(smooth_latency(100, 20), 80);
+        // Latencies too large for the tab list are capped.
+        assert_eq!(smooth_latency(0, u64::MAX), i32::MAX / 4);
+    }
+}
@@ -2,35 +2,60 @@
 
 extern crate nalgebra_glm as glm;
 
+mod advancements;
+mod anvil;
 mod broadcasters;
 mod chat;
+mod combat;
+mod crafting;
 mod death;
+mod elytra;
+mod hunger;
 mod join;
+mod keepalive;
 mod packet_handlers;
+mod plugin_channels;
+mod rcon;
+mod resource_pack;
+mod skin;
+mod tab_list;
+mod trading;
 mod view;
 
 use feather_core::inventory::{Area, Inventory, SlotIndex, Window};
 use feather_core::network::packets::{PlayerInfo, PlayerInfoAction, SpawnPlayer};
 use feather_core::network::Packet;
-use feather_core::text::Text;
-use feather_core::util::{Gamemode, Position};
+use feather_core::util::{Dimension, Gamemode, Position};
 use feather_server_network::NewClientInfo;
 use feather_server_types::{
-    BlocksFallen, CanBreak, CanInstaBreak, CanRespawn, CanTakeDamage, ChunkHolder,
-    CreationPacketCreator, EntitySpawnEvent, Game, GamemodeUpdateEvent, Health, HealthUpdateEvent,
-    HeldItem, InventoryUpdateEvent, LastKnownPositions, MaxHealth, MessageReceiver, Name, Network,
-    NetworkId, OpenWindowCount, Player, PlayerJoinEvent, PlayerPreJoinEvent, PreviousPosition,
-    PreviousVelocity, ProfileProperties, SpawnPacketCreator, Uuid, Velocity,
+    add_player_info, BlocksFallen, CanBreak, CanInstaBreak, CanRespawn, CanTakeDamage, ChunkHolder,
+    ClientChannels, CreationPacketCreator, EntitySpawnEvent, Experience, Game, GamemodeUpdateEvent,
+    Health, HealthUpdateEvent, HeldItem, Hunger, InventoryUpdateEvent, LastKnownPositions,
+    MaxHealth, MessageReceiver, Name, Network, NetworkId, OpenWindowCount, Player, PlayerJoinEvent,
+    PlayerPreJoinEvent, PreviousPosition, PreviousVelocity, ProfileProperties, SpawnPacketCreator,
+    SpawnPoint, TabListEntry, TabListHeaderFooter, Uuid, Velocity, View, WorldId,
 };
-use feather_server_util::degrees_to_stops;
+use feather_server_util::{current_time_in_millis, degrees_to_stops};
 use fecs::{Entity, EntityRef, World};
 
+pub use advancements::*;
+pub use anvil::*;
 pub use broadcasters::*;
 pub use chat::*;
+pub use combat::*;
+pub use crafting::*;
 pub use death::*;
+pub use elytra::*;
+pub use hunger::*;
 pub use join::*;
+pub use keepalive::*;
 pub use packet_handlers::*;
-use std::sync::atomic::Ordering;
+pub use plugin_channels::*;
+pub use rcon::*;
+pub use resource_pack::*;
+pub use skin::*;
+pub use tab_list::*;
+pub use trading::*;
 pub use view::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -46,6 +71,11 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
     let entity = info.entity;
     world.add(entity, NetworkId(entity::new_id())).unwrap();
     world.add(entity, info.position).unwrap();
+    let world_id = game
+        .worlds
+        .by_dimension(Dimension::from_id(info.data.dimension))
+        .unwrap_or(WorldId::MAIN);
+    world.add(entity, world_id).unwrap();
     world.add(entity, PreviousPosition::default()).unwrap();
     world.add(entity, Velocity::default()).unwrap();
     world.add(entity, PreviousVelocity::default()).unwrap();
@@ -64,6 +94,15 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
     world.add(entity, Name(info.username)).unwrap();
     world.add(entity, OpenWindowCount::default()).unwrap();
     world.add(entity, ChunkHolder::default()).unwrap();
+    world
+        .add(
+            entity,
+            View {
+                center: info.position.chunk(),
+                distance: game.config.server.view_distance,
+            },
+        )
+        .unwrap();
     world.add(entity, LastKnownPositions::default()).unwrap();
     world
         .add(entity, SpawnPacketCreator(&create_spawn_packet))
@@ -111,6 +150,13 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
         .unwrap();
 
     world.add(entity, MessageReceiver::default()).unwrap();
+    world.add(entity, TabListEntry::default()).unwrap();
+    world.add(entity, TabListHeaderFooter::default()).unwrap();
+    world.add(entity, PendingInfoRemovals::default()).unwrap();
+    world.add(entity, ClientChannels::default()).unwrap();
+    world
+        .add(entity, KeepAlive::new(current_time_in_millis()))
+        .unwrap();
 
     world.add(entity, Player).unwrap();
 
@@ -120,8 +166,37 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
         .add(entity, Health(info.data.animal.health as u32))
         .unwrap();
     world.add(entity, BlocksFallen::default()).unwrap();
+    let food = info.data.food;
+    world
+        .add(
+            entity,
+            Hunger {
+                food: food.food_level.max(0) as u32,
+                saturation: food.saturation,
+                exhaustion: food.exhaustion,
+                tick_timer: food.tick_timer.max(0) as u32,
+            },
+        )
+        .unwrap();
+    let experience = info.data.experience;
+    world
+        .add(
+            entity,
+            Experience {
+                level: experience.level.max(0) as u32,
+                progress: experience.progress.max(0.0).min(1.0),
+                total: experience.total.max(0) as u32,
+                seed: experience.seed,
+            },
+        )
+        .unwrap();
+    world.add(entity, AttackCooldown(game.tick_count)).unwrap();
+    if let Some(spawn) = SpawnPoint::from_data(info.data.spawn) {
+        world.add(entity, spawn).unwrap();
+    }
 
-    game.player_count.fetch_add(1, Ordering::SeqCst);
+    let name = world.get::<Name>(entity).0.clone();
+    game.online_players.add(info.uuid, name);
     game.handle(world, EntitySpawnEvent { entity });
     game.handle(world, PlayerPreJoinEvent { player: entity });
     game.handle(world, PlayerJoinEvent { player: entity });
@@ -140,6 +215,7 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
             entity,
         },
     );
+    send_experience(world, entity);
 
     entity
 }
@@ -193,27 +269,13 @@ fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
 
 /// Function to create a `PlayerInfo` packet to broadcast when the player joins.
 fn create_initialization_packet(accessor: &EntityRef) -> Box<dyn Packet> {
-    let name = accessor.get::<Name>();
-    let props = accessor.get::<ProfileProperties>();
-    let uuid = *accessor.get::<Uuid>();
-
-    let props = props
-        .0
-        .iter()
-        .map(|prop| {
-            (
-                prop.name.clone(),
-                prop.value.clone(),
-                prop.signature.clone(),
-            )
-        })
-        .collect::<Vec<_>>();
-
-    let display_name = Text::of(name.0.clone()).into();
-
-    let action =
-        PlayerInfoAction::AddPlayer(name.0.clone(), props, Gamemode::Creative, 50, display_name);
+    // Hidden players are only added just before being spawned.
+    if accessor.get::<TabListEntry>().hidden {
+        return Box::new(PlayerInfo {
+            action: PlayerInfoAction::RemovePlayer,
+            uuid: *accessor.get::<Uuid>(),
+        });
+    }
 
-    let packet = PlayerInfo { action, uuid };
-    Box::new(packet)
+    Box::new(add_player_info(accessor))
 }
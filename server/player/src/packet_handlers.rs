@@ -2,24 +2,32 @@
 
 mod animation;
 mod chat;
+mod client_settings;
 mod client_status;
 mod digging;
+mod entity_action;
 mod inventory;
 mod movement;
 mod placement;
+mod use_entity;
 mod use_item;
+mod vehicle;
 mod window;
 
 pub use self::inventory::*;
 pub use animation::handle_animation;
-pub use chat::handle_chat;
+pub use chat::{handle_chat, handle_tab_complete};
+pub use client_settings::handle_client_settings;
 pub use client_status::handle_client_status;
 pub use digging::*;
+pub use entity_action::handle_entity_action;
 use fecs::{Entity, World};
 pub use movement::handle_movement_packets;
 pub use placement::handle_player_block_placement;
+pub use use_entity::handle_use_entity;
 pub use use_item::handle_player_use_item;
-pub use window::handle_close_window;
+pub use vehicle::{handle_steer_boat, handle_steer_vehicle, handle_vehicle_move};
+pub use window::{handle_close_window, handle_name_item, handle_select_trade};
 
 /// Iterator filter to ensure players have not been removed from the world.
 pub trait IteratorExt: Iterator {
@@ -1,8 +1,10 @@
 use crate::IteratorExt;
-use feather_core::network::packets::ChatMessageServerbound;
+use feather_core::network::packets::{
+    ChatMessageServerbound, TabCompleteClientbound, TabCompleteMatch, TabCompleteServerbound,
+};
 use feather_core::text::{TextRoot, Translate};
 use feather_server_commands::CommandState;
-use feather_server_types::{ChatEvent, ChatPosition, Game, Name, PacketBuffers};
+use feather_server_types::{ChatEvent, ChatPosition, Game, Name, Network, PacketBuffers};
 use fecs::World;
 use std::sync::Arc;
 
@@ -45,3 +47,38 @@ pub fn handle_chat(
             }
         });
 }
+
+/// Handles tab completion requests, suggesting
+/// completions for the command a player is typing.
+#[fecs::system]
+pub fn handle_tab_complete(
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+    #[default] commands: &CommandState,
+) {
+    packet_buffers
+        .received::<TabCompleteServerbound>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let command = match packet.text.strip_prefix('/') {
+                Some(command) => command,
+                None => return,
+            };
+            let suggestions = commands.suggest(world, command);
+
+            let packet = TabCompleteClientbound {
+                transaction_id: packet.transaction_id,
+                // The suggested range includes the slash.
+                start: suggestions.start as i32 + 1,
+                length: suggestions.length as i32,
+                matches: suggestions
+                    .matches
+                    .into_iter()
+                    .map(|text| TabCompleteMatch {
+                        text,
+                        tooltip: None,
+                    })
+                    .collect(),
+            };
+            world.get::<Network>(player).send(packet);
+        });
+}
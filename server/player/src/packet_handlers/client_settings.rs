@@ -0,0 +1,40 @@
+use crate::{IteratorExt, MIN_VIEW_DISTANCE};
+use feather_core::network::packets::ClientSettings;
+use feather_server_types::{Game, PacketBuffers, View, ViewUpdateEvent};
+use fecs::World;
+use std::sync::Arc;
+
+/// Handles the Client Settings packet, updating the player's
+/// view distance to the one chosen by the client, up to
+/// the server's view distance.
+#[fecs::system]
+pub fn handle_client_settings(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<ClientSettings>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let distance = packet
+                .view_distance
+                .max(MIN_VIEW_DISTANCE)
+                .min(game.config.server.view_distance);
+
+            let old = *world.get::<View>(player);
+            if old.distance == distance {
+                return;
+            }
+
+            let new = View { distance, ..old };
+            *world.get_mut::<View>(player) = new;
+            game.handle(
+                world,
+                ViewUpdateEvent {
+                    player,
+                    old: Some(old),
+                    new,
+                },
+            );
+        });
+}
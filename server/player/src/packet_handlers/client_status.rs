@@ -1,42 +1,103 @@
 use crate::packet_handlers::IteratorExt;
+use crate::send_experience;
 use feather_core::network::packets::ClientStatus;
 use feather_core::network::packets::Respawn;
 use feather_core::util::{Gamemode, Position};
-use feather_server_types::{Dead, Health, Network, PacketBuffers, Teleported};
-use fecs::World;
+use feather_server_types::{
+    send_status_message, BlocksFallen, Dead, Experience, Game, Health, HealthUpdateEvent, Hunger,
+    MaxHealth, Network, PacketBuffers, PlayerRespawnEvent, SpawnPoint, Sprinting, Teleported,
+    WorldId,
+};
+use fecs::{Entity, World};
 use std::sync::Arc;
 
 /// Handles the Client Status packet, which is sent
 /// when the user clicks the respawn button.
 #[fecs::system]
-pub fn handle_client_status(world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+pub fn handle_client_status(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
     packet_buffers
         .received::<ClientStatus>()
-        .for_each_valid(world, |world, (player, packet)| {
-            match packet.action_id {
-                0 => {
-                    // Perform respawn
-                    let _ = world.remove::<Dead>(player);
-
-                    // TODO: support spawn positons
-                    *world.get_mut::<Position>(player) = Position::default();
-
-                    world.get_mut::<Health>(player).0 = 20;
-
-                    world.add(player, Teleported).unwrap();
-
-                    let gamemode = *world.get::<Gamemode>(player);
-
-                    // Send Respawn packet
-                    let packet = Respawn {
-                        dimension: 0,
-                        difficulty: 1,
-                        gamemode: gamemode.id() as u8,
-                        level_type: String::from("default"),
-                    };
-                    world.get::<Network>(player).send(packet);
-                }
-                x => log::debug!("Unimplemented Client Status action ID {}", x),
+        .for_each_valid(world, |world, (player, packet)| match packet.action_id {
+            0 => respawn(game, world, player),
+            x => log::debug!("Unimplemented Client Status action ID {}", x),
+        });
+}
+
+/// Respawns a dead player at the spawn point with full
+/// health and hunger. Their experience is lost unless
+/// the `keepInventory` game rule is enabled.
+fn respawn(game: &mut Game, world: &mut World, player: Entity) {
+    if world.remove::<Dead>(player).is_err() {
+        return;
+    }
+
+    let max_health = world.get::<MaxHealth>(player).0;
+    world.get_mut::<Health>(player).0 = max_health;
+    *world.get_mut::<Hunger>(player) = Hunger::default();
+    let _ = world.remove::<Sprinting>(player);
+    world.get_mut::<BlocksFallen>(player).0 = 0.0;
+
+    // Players always respawn in the main world.
+    let spawn = spawn_position(game, world, player);
+    if game.world_of(world, player) != WorldId::MAIN {
+        game.change_world(world, player, WorldId::MAIN, spawn);
+    } else {
+        *world.get_mut::<Position>(player) = spawn;
+
+        world.add(player, Teleported).unwrap();
+
+        let gamemode = *world.get::<Gamemode>(player);
+
+        // Send Respawn packet
+        let dimension = game.worlds[WorldId::MAIN].dimension;
+        let packet = Respawn {
+            dimension: dimension.id(),
+            difficulty: 1,
+            gamemode: gamemode.id() as u8,
+            level_type: String::from("default"),
+            dimension_type: game.dimension_type_nbt(dimension),
+            world_name: dimension.type_id().to_owned(),
+            is_flat: false,
+        };
+        world.get::<Network>(player).send(packet);
+    }
+
+    game.handle(
+        world,
+        HealthUpdateEvent {
+            old: 0,
+            new: max_health,
+            entity: player,
+        },
+    );
+    // The client forgets the experience of dead players.
+    if game.game_rules.keep_inventory {
+        send_experience(world, player);
+    } else {
+        game.update_experience(world, player, |experience| {
+            *experience = Experience {
+                seed: experience.seed,
+                ..Default::default()
             }
         });
+    }
+    game.handle(world, PlayerRespawnEvent { player });
+}
+
+/// Returns where a player respawns: next to the bed they last
+/// slept in, or at the world spawn if they have no bed. Players
+/// whose bed is missing lose their spawn point.
+fn spawn_position(game: &Game, world: &mut World, player: Entity) -> Position {
+    if let Some(pos) = game.bed_spawn(world, player) {
+        return pos;
+    }
+
+    if world.remove::<SpawnPoint>(player).is_ok() {
+        send_status_message(world, player, "block.minecraft.bed.not_valid");
+    }
+    game.world_spawn()
 }
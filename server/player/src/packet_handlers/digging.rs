@@ -4,22 +4,27 @@
 //! for actions mostly unrelated to digging including eating, shooting bows,
 //! swapping items out to the offhand, and dropping items.
 
-use crate::{ItemTimedUse, IteratorExt};
+use crate::{exhaust, ItemTimedUse, IteratorExt};
 use entity::InventoryExt;
 use feather_core::blocks::{BlockId, HalfUpperLower, Part, SimplifiedBlockKind};
 use feather_core::inventory::{slot, Area, Inventory, Slot, SlotIndex};
-use feather_core::items::{Item, ItemStack};
-use feather_core::network::packets::{PlayerDigging, PlayerDiggingStatus};
+use feather_core::items::{Enchantment, Item, ItemStack};
+use feather_core::network::packets::{
+    BlockChange, PlayerDigging, PlayerDiggingStatus, SoundCategory,
+};
 use feather_core::util::{BlockPosition, Gamemode, Position};
 use feather_definitions::Tool;
 use feather_server_types::{
-    BlockUpdateCause, CanBreak, CanInstaBreak, EntitySpawnEvent, Game, HeldItem,
-    InventoryUpdateEvent, ItemDamageEvent, ItemDropEvent, PacketBuffers, Velocity,
-    PLAYER_EYE_HEIGHT, TPS,
+    dig_progress_per_tick, BlockUpdateCause, Blocking, BumpVec, CanBreak, CanInstaBreak,
+    DigConditions, EntitySpawnEvent, Game, Haste, HeldItem, InventoryUpdateEvent, ItemDamageEvent,
+    ItemDropEvent, MiningFatigue, Network, PacketBuffers, Sound, Velocity, BREAK_BLOCK_EXHAUSTION,
+    PLAYER_EYE_HEIGHT,
 };
 use feather_server_util::{charge_from_ticks_held, compute_projectile_velocity};
-use fecs::{Entity, IntoQuery, Read, World, Write};
+use fecs::{Entity, IntoQuery, Read, World};
+use rand::Rng;
 use smallvec::smallvec;
+use std::cell::RefCell;
 use std::sync::Arc;
 
 /// Stores the "digging status" of a player.
@@ -31,13 +36,22 @@ use std::sync::Arc;
 pub struct Digging {
     /// The position of the block being dug
     pub pos: BlockPosition,
-    /// The total time (in seconds) of digging needed
-    pub time: f64,
-    /// Progress made, in seconds (better tools increase this
-    /// value faster)
+    /// Fraction of the block dug so far. Better tools
+    /// increase this value faster, and the block can
+    /// be broken once it reaches 1.
     pub progress: f64,
 }
 
+/// Ticks a bow must be drawn for before it shoots.
+const MIN_DRAW_TICKS: u64 = 4;
+/// Ticks after which a bow is fully drawn.
+const MAX_DRAW_TICKS: u64 = 20;
+
+/// Fraction of a block a player must have dug, as tracked by the
+/// server, before they may finish digging it. Less than 1 to account
+/// for the latency between the client and the server.
+const MIN_FINISHED_PROGRESS: f64 = 0.7;
+
 /// System responsible for polling for PlayerDigging
 /// packets and writing the corresponding events.
 #[fecs::system]
@@ -120,27 +134,19 @@ fn handle_started_digging(
         return;
     }
 
-    // If the player can insta-break, or the block has hardness 0, then they can already break the block.
-    if world.has::<CanInstaBreak>(player)
-        || game
-            .block_at(packet.location)
-            .unwrap_or_default()
-            .kind()
-            .hardness()
-            < 0.01
-    {
+    // If the player can insta-break, or digs fast enough to break
+    // the block within a tick, then they can already break the block.
+    let world_id = game.world_of(world, player);
+    let block = game.block_at(world_id, packet.location).unwrap_or_default();
+    if world.has::<CanInstaBreak>(player) || dig_progress(game, world, player, block) >= 1.0 {
         dig(game, world, player, packet.location);
     } else {
         // Insert new `Digging`.
-        let block = game.block_at(packet.location).unwrap_or_default();
-        let hardness = block.kind().hardness();
-
         world
             .add(
                 player,
                 Digging {
                     pos: packet.location,
-                    time: hardness,
                     progress: 0.0,
                 },
             )
@@ -149,54 +155,71 @@ fn handle_started_digging(
     }
 }
 
+/// Returns the fraction of a block a player digs each tick.
+fn dig_progress(game: &Game, world: &World, player: Entity, block: BlockId) -> f64 {
+    let tool = {
+        let inventory = world.get::<Inventory>(player);
+        let held_item = world.get::<HeldItem>(player).0;
+        inventory.item_at(Area::Hotbar, held_item).ok().flatten()
+    };
+    dig_progress_per_tick(
+        block.kind(),
+        tool.as_ref(),
+        dig_conditions(game, world, player),
+    )
+}
+
+/// Returns the conditions under which a player digs.
+fn dig_conditions(game: &Game, world: &World, player: Entity) -> DigConditions {
+    let pos = *world.get::<Position>(player);
+    let eyes = pos + glm::vec3(0.0, PLAYER_EYE_HEIGHT, 0.0);
+    let head_in_water = game
+        .block_at(game.world_of(world, player), eyes.block())
+        .map_or(false, |block| {
+            block.simplified_kind() == SimplifiedBlockKind::Water
+                || block.waterlogged() == Some(true)
+        });
+    let aqua_affinity = world
+        .get::<Inventory>(player)
+        .item_at(Area::Head, 0)
+        .ok()
+        .flatten()
+        .map_or(false, |helmet| {
+            helmet
+                .enchantments
+                .level(Enchantment::AquaAffinity)
+                .is_some()
+        });
+
+    DigConditions {
+        haste: world.try_get::<Haste>(player).map_or(0, |haste| haste.0),
+        mining_fatigue: world
+            .try_get::<MiningFatigue>(player)
+            .map_or(0, |fatigue| fatigue.0),
+        underwater: head_in_water && !aqua_affinity,
+        on_ground: pos.on_ground,
+    }
+}
+
 /// System to advance the digging progress.
+///
+/// The progress depends on the block's hardness, the held tool and
+/// its enchantments, status effects, and whether the player is
+/// underwater or in the air, so it is computed again each tick.
 #[fecs::system]
 pub fn advance_dig_progress(game: &mut Game, world: &mut World) {
-    <(Write<Digging>, Read<Inventory>, Read<HeldItem>)>::query().par_for_each_mut(
-        world.inner_mut(),
-        |(mut digging, inventory, held_item)| {
-            // Advance progress depends on tool and the
-            // block kind: https://minecraft.gamepedia.com/Breaking#Speed
-            // * If the block requires some tool to harvest (i.e. it requires a tool to get the item after it breaks),
-            // then if that tool is not held, progress is hindered by a factor of 5. Otherwise, the hindrance
-            // is only a factor of 1.5.
-            // * If the player's tool helps dig the block (e.g. shovel => dirt, pickaxe => cobblestone),
-            // then a constant mutliplier is applied to the dig speed depending on the tool's material.
-            // This is retrieved through the `dig_multiplier` property on `ToolMaterial`.
-            let block = game.block_at(digging.pos).unwrap_or_default();
-            let best_tool = block.kind().best_tool();
-            let best_tool_required = block.kind().best_tool_required();
-
-            let item_in_main_hand: Slot = inventory
-                .item_at(Area::Hotbar, held_item.0)
-                .expect("held item out of bounds");
-            let held_tool = item_in_main_hand.map(|item| item.ty.tool()).flatten();
-
-            let multiplier = if best_tool == held_tool && best_tool.is_some() {
-                let dig_multiplier = item_in_main_hand
-                    .unwrap()
-                    .ty
-                    .tool_material()
-                    .map(|mat| mat.dig_multiplier())
-                    .unwrap_or_else(|| {
-                        // Missing data in feather-definitions;
-                        // panic. (TODO: maybe this should just be a log message)
-                        panic!(
-                            "no tool material for item {:?}, even though it has a tool",
-                            item_in_main_hand
-                        )
-                    });
-
-                (1.0 / 1.5) * dig_multiplier
-            } else if best_tool_required {
-                1.0 / 5.0
-            } else {
-                1.0 / 1.5
-            };
+    let progress = RefCell::new(BumpVec::<(Entity, f64)>::new_in(game.bump()));
 
-            digging.progress += (1.0 / TPS as f64) * multiplier;
-        },
-    );
+    <Read<Digging>>::query().for_each_entities(world.inner(), |(player, digging)| {
+        let world_id = game.world_of(world, player);
+        let block = game.block_at(world_id, digging.pos).unwrap_or_default();
+        let dug = digging.progress + dig_progress(game, world, player, block);
+        progress.borrow_mut().push((player, dug));
+    });
+
+    for (player, dug) in progress.into_inner() {
+        world.get_mut::<Digging>(player).progress = dug;
+    }
 }
 
 fn handle_cancelled_digging(game: &mut Game, world: &mut World, player: Entity) {
@@ -214,20 +237,21 @@ fn handle_finished_digging(
     player: Entity,
     packet: PlayerDigging,
 ) {
+    let insta_break = world.has::<CanInstaBreak>(player);
     let digging = match world.try_get::<Digging>(player) {
         Some(digging) => *digging,
         None => {
-            if world.has::<CanInstaBreak>(player) {
+            if insta_break {
                 // Can insta-break - no `StartedDigging` needed
                 Digging {
                     pos: packet.location,
-                    time: 0.0,
-                    progress: 0.0,
+                    progress: 1.0,
                 }
             } else {
                 // Player can't insta-break and has
                 // not sent StartedDigging.
                 // They cannot finish.
+                resend_block(game, world, player, packet.location);
                 return;
             }
         }
@@ -235,19 +259,40 @@ fn handle_finished_digging(
 
     let _ = world.remove::<Digging>(player);
 
-    if digging.pos != packet.location {
-        return;
+    if digging.pos != packet.location || (!insta_break && digging.progress < MIN_FINISHED_PROGRESS)
+    {
+        // The client broke the block sooner than it could have,
+        // so it is shown to them again.
+        log::trace!(
+            "player {:?} finished digging at {:?} too early",
+            player,
+            packet.location
+        );
+        resend_block(game, world, player, packet.location);
+    } else {
+        // Attempt to break the block
+        dig(game, world, player, digging.pos);
     }
 
-    // Attempt to break the block
-    dig(game, world, player, digging.pos);
-
     // Finished
     game.handle(world, FinishDiggingEvent { player, digging });
 }
 
+/// Sends a block to a player whose client
+/// removed it without the server agreeing.
+pub(crate) fn resend_block(game: &Game, world: &World, player: Entity, pos: BlockPosition) {
+    let world_id = game.world_of(world, player);
+    if let Some(block) = game.block_at(world_id, pos) {
+        world.get::<Network>(player).send(BlockChange {
+            location: pos,
+            block_id: block.vanilla_id() as i32,
+        });
+    }
+}
+
 fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
-    let block = match game.block_at(pos) {
+    let world_id = game.world_of(world, player);
+    let block = match game.block_at(world_id, pos) {
         Some(block) => block,
         None => {
             game.disconnect(
@@ -264,6 +309,7 @@ fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
     };
 
     damage_tool(player, block, game, world);
+    exhaust(world, player, BREAK_BLOCK_EXHAUSTION);
 
     // Handle multi-block destruction (i.e. doors and beds)
     if let Some(other_pos) = match block.simplified_kind() {
@@ -282,9 +328,10 @@ fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
         }
         _ => None,
     } {
-        if game.block_at(other_pos).unwrap().kind() == block.kind() {
+        if game.block_at(world_id, other_pos).unwrap().kind() == block.kind() {
             game.set_block_at(
                 world,
+                world_id,
                 other_pos,
                 BlockId::air(),
                 BlockUpdateCause::Entity(player),
@@ -292,7 +339,13 @@ fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
         };
     }
 
-    game.set_block_at(world, pos, BlockId::air(), BlockUpdateCause::Entity(player));
+    game.set_block_at(
+        world,
+        world_id,
+        pos,
+        BlockId::air(),
+        BlockUpdateCause::Entity(player),
+    );
 }
 
 fn damage_tool(player: Entity, block: BlockId, game: &mut Game, world: &mut World) {
@@ -397,10 +450,15 @@ fn handle_drop_item_stack(
     }
 }
 
-/// Handles food consumption and shooting arrows.
+/// Handles food consumption, shooting arrows and lowering shields.
 fn handle_consume_item(game: &mut Game, world: &mut World, player: Entity, packet: PlayerDigging) {
     assert_eq!(packet.status, PlayerDiggingStatus::ConsumeItem);
 
+    if world.has::<Blocking>(player) {
+        game.lower_shield(world, player);
+        return;
+    }
+
     // TODO: Fallback to off-hand if main-hand is not a consumable
     let inventory = world.get::<Inventory>(player);
     let used_item = inventory.item_in_main_hand(player, world);
@@ -409,33 +467,64 @@ fn handle_consume_item(game: &mut Game, world: &mut World, player: Entity, packe
         if item.ty == Item::Bow {
             drop(inventory);
             handle_shoot_bow(game, world, player);
+        } else if item.ty.food_points().is_some() {
+            // Stopped eating before finishing
+            drop(inventory);
+            let _ = world.remove::<ItemTimedUse>(player);
         }
-        // TODO: Food, potions
+        // TODO: potions
     }
 }
 
 fn handle_shoot_bow(game: &mut Game, world: &mut World, player: Entity) {
-    let gamemode = *world.get::<Gamemode>(player);
-
-    {
-        let inventory = world.get::<Inventory>(player);
-        let arrow_to_consume: Option<(SlotIndex, ItemStack)> = find_arrow(&inventory);
+    // Spam clicking can lead to a scenario where this system is called before the UseItem system adds the component
+    // In that case just return.
+    let tick_start = match world.try_get::<ItemTimedUse>(player) {
+        Some(timed_use) => timed_use.tick_start,
+        None => return,
+    };
+    world.remove::<ItemTimedUse>(player).unwrap();
 
-        if gamemode == Gamemode::Survival || gamemode == Gamemode::Adventure {
-            // If no arrow was found, don't shoot
-            if arrow_to_consume.is_none() {
-                return;
-            }
+    // if bow not held for at least 4 ticks, don't shoot at all
+    // to avoid extreme bowspamming
+    let time_held = (game.tick_count - tick_start).min(MAX_DRAW_TICKS);
+    if time_held < MIN_DRAW_TICKS {
+        return;
+    }
 
-            // Consume arrow
-            let (arrow_slot, arrow_stack) = arrow_to_consume.unwrap();
-            let mut arrow_stack: ItemStack = arrow_stack;
-            arrow_stack.amount -= 1;
+    let held_item = world.get::<HeldItem>(player).0;
+    let bow = match world
+        .get::<Inventory>(player)
+        .item_at(Area::Hotbar, held_item)
+        .unwrap()
+    {
+        Some(bow) => bow,
+        None => return,
+    };
+    let creative = *world.get::<Gamemode>(player) == Gamemode::Creative;
+
+    let arrow_to_consume = find_arrow(&world.get::<Inventory>(player));
+    // Default to generic arrow in creative mode with none in inventory
+    let arrow_type = match arrow_to_consume {
+        Some((_, arrow_stack)) => arrow_stack.ty,
+        None if creative => Item::Arrow,
+        // If no arrow was found, don't shoot
+        None => return,
+    };
 
-            inventory
-                .set_item_at(arrow_slot.area, arrow_slot.slot, arrow_stack)
+    // Infinity only spares normal arrows.
+    let infinity = bow.enchantments.level(Enchantment::Infinity).is_some();
+    let consumes_arrow = !creative && !(infinity && arrow_type == Item::Arrow);
+    if consumes_arrow {
+        if let Some((arrow_slot, arrow_stack)) = arrow_to_consume {
+            world
+                .get::<Inventory>(player)
+                .set_item_at(
+                    arrow_slot.area,
+                    arrow_slot.slot,
+                    arrow_stack.of_amount(arrow_stack.amount - 1),
+                )
                 .unwrap();
-            drop(inventory);
             game.handle(
                 world,
                 InventoryUpdateEvent {
@@ -444,33 +533,16 @@ fn handle_shoot_bow(game: &mut Game, world: &mut World, player: Entity) {
                 },
             );
         }
-
-        let _arrow_type: Item = match arrow_to_consume {
-            None => Item::Arrow, // Default to generic arrow in creative mode with none in inventory
-            Some((_, arrow_stack)) => arrow_stack.ty,
-        };
     }
-
-    let timed_use = world.try_get::<ItemTimedUse>(player);
-
-    // Spam clicking can lead to a scenario where this system is called before the UseItem system adds the component
-    // In that case just return.
-    if timed_use.is_none() {
-        return;
-    }
-
-    let timed_use = timed_use.unwrap();
-
-    let mut time_held = game.tick_count - timed_use.tick_start;
-
-    // if bow not held for at least 4 ticks, don't shoot at all
-    // to avoid extreme bowspamming
-    if time_held < 4 {
-        return;
-    }
-
-    if time_held > 20 {
-        time_held = 20;
+    if !creative {
+        game.handle(
+            world,
+            ItemDamageEvent {
+                player,
+                slot: slot(Area::Hotbar, held_item),
+                damage_taken: 1,
+            },
+        );
     }
 
     let charge_force = charge_from_ticks_held(time_held as u32);
@@ -483,7 +555,7 @@ fn handle_shoot_bow(game: &mut Game, world: &mut World, player: Entity) {
     let arrow_velocity = compute_projectile_velocity(
         glm::vec3(direction.x, direction.y, direction.z),
         charge_force as f64,
-        0.0,
+        1.0,
         &mut *game.rng(),
     );
     log::trace!(
@@ -492,23 +564,37 @@ fn handle_shoot_bow(game: &mut Game, world: &mut World, player: Entity) {
         arrow_velocity.norm()
     );
 
-    drop(timed_use);
-
-    world.remove::<ItemTimedUse>(player).unwrap();
+    // A fully drawn bow shoots critical arrows.
+    let power = bow
+        .enchantments
+        .level(Enchantment::Power)
+        .unwrap_or_default();
+    let arrow = entity::Arrow {
+        shooter: Some(player),
+        damage: entity::arrow::BASE_DAMAGE + entity::arrow::power_bonus(power),
+        critical: time_held >= MAX_DRAW_TICKS,
+        ..Default::default()
+    };
 
     log::trace!("Spawning arrow entity.");
-    let entity = entity::arrow::create()
+    let world_id = game.world_of(world, player);
+    let entity = entity::arrow::create(arrow)
         .with(init_position)
+        .with(world_id)
         .with(Velocity(arrow_velocity))
         .build()
         .spawn_in(world);
     game.handle(world, EntitySpawnEvent { entity });
+
+    let pitch = 1.0 / game.rng().gen_range(1.2, 1.6) + charge_force / 6.0;
+    let sound = Sound::new("entity.arrow.shoot", SoundCategory::Players).pitch(pitch);
+    game.play_sound(world, world_id, init_position, &sound);
 }
 
 fn find_arrow(inventory: &Inventory) -> Option<(SlotIndex, ItemStack)> {
     // Order of priority is: off-hand, hotbar (0 to 8), rest of inventory
 
-    if let Some(offhand) = inventory.item_at(Area::Hotbar, 0).unwrap() {
+    if let Some(offhand) = inventory.item_at(Area::Offhand, 0).unwrap() {
         if is_arrow_item(offhand.ty) {
             return Some((slot(Area::Offhand, 0), offhand));
         }
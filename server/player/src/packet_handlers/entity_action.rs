@@ -0,0 +1,32 @@
+use crate::{start_gliding, IteratorExt};
+use feather_core::network::packets::{EntityAction, EntityActionType};
+use feather_server_types::{Game, PacketBuffers, Sprinting};
+use fecs::World;
+use std::sync::Arc;
+
+/// Handles the Entity Action packet, which is sent when a player
+/// starts or stops sprinting, gets out of bed or starts gliding.
+#[fecs::system]
+pub fn handle_entity_action(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<EntityAction>()
+        .for_each_valid(world, |world, (player, packet)| match packet.action_id {
+            EntityActionType::StartSprinting => {
+                if !world.has::<Sprinting>(player) {
+                    world.add(player, Sprinting).unwrap();
+                }
+            }
+            EntityActionType::StopSprinting => {
+                let _ = world.remove::<Sprinting>(player);
+            }
+            EntityActionType::LeaveBed => game.wake_up(world, player),
+            EntityActionType::StartFlyingWithElytra => {
+                start_gliding(game, world, player);
+            }
+            _ => (),
+        });
+}
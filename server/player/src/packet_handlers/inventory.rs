@@ -2,15 +2,19 @@
 //! This currently includes Creative Inventory Action, Held Item
 //! Change, and the venerable Click Window.
 
-use crate::IteratorExt;
+use crate::{
+    complete_trade, consume_anvil_inputs, consume_crafting_ingredients, reset_attack_cooldown,
+    IteratorExt,
+};
 use feather_core::inventory::{Area, Inventory, SlotIndex, Window};
 use feather_core::items::ItemStack;
 use feather_core::network::packets::{
     ClickWindow, ConfirmTransactionClientbound, CreativeInventoryAction, HeldItemChangeServerbound,
 };
-use feather_core::util::Gamemode;
+use feather_core::util::{Gamemode, Hand};
 use feather_server_types::{
-    Game, HeldItem, InventoryUpdateEvent, ItemDropEvent, Network, PacketBuffers,
+    Blocking, Game, HeldItem, InventoryUpdateEvent, ItemDropEvent, Network, PacketBuffers,
+    RecipeUnlockEvent,
 };
 use fecs::{Entity, World};
 use smallvec::smallvec;
@@ -118,6 +122,14 @@ pub fn handle_held_item_change(
                 entity: player,
             };
             drop(held_item);
+            reset_attack_cooldown(game, world, player);
+            // Switching items lowers a shield held in the main hand.
+            let main_hand_shield = world
+                .try_get::<Blocking>(player)
+                .map_or(false, |blocking| blocking.hand == Hand::Main);
+            if main_hand_shield {
+                game.lower_shield(world, player);
+            }
             game.handle(world, event);
         });
 }
@@ -338,6 +350,17 @@ fn handle_single_click(
     packet: ClickWindow,
     button: MouseButton,
 ) -> anyhow::Result<()> {
+    let clicked_area = world
+        .get::<Window>(player)
+        .convert_network(packet.slot as usize)
+        .map(|index| index.area);
+    match clicked_area {
+        Some(Area::CraftingOutput) => return take_crafting_output(game, world, player, packet),
+        Some(Area::AnvilOutput) => return take_anvil_output(game, world, player, packet),
+        Some(Area::MerchantOutput) => return take_merchant_output(game, world, player, packet),
+        _ => (),
+    }
+
     if let Some(picked) = world.try_get::<PickedItem>(player).map(|i| *i) {
         // Put down the item on the clicked slot. Based on the mouse button:
         // * left => whole stack
@@ -423,6 +446,130 @@ fn handle_single_click(
     Ok(())
 }
 
+/// Takes the item in a crafting output slot, crafting it. This
+/// is only possible if the whole stack can be picked up, and
+/// items can't be put into the slot.
+fn take_crafting_output(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    packet: ClickWindow,
+) -> anyhow::Result<()> {
+    let window = world.get::<Window>(player);
+    let entity = window
+        .corresponding_entity(packet.slot as usize)
+        .ok_or_else(|| anyhow::anyhow!("invalid slot index"))?;
+    let output = match window.accessor(world)?.item_at(packet.slot as usize)? {
+        Some(output) => output,
+        None => return Ok(()),
+    };
+    drop(window);
+
+    let picked = world.try_get::<PickedItem>(player).map(|i| i.0);
+    let picked = match picked {
+        None => output,
+        Some(picked)
+            if picked.eq_ignore_amount(output)
+                && picked.amount + output.amount <= picked.ty.stack_size() as u8 =>
+        {
+            picked.of_amount(picked.amount + output.amount)
+        }
+        Some(_) => return Ok(()),
+    };
+
+    let recipe = match consume_crafting_ingredients(game, world, entity) {
+        Some(recipe) => recipe,
+        None => return Ok(()),
+    };
+
+    if world.has::<PickedItem>(player) {
+        world.get_mut::<PickedItem>(player).0 = picked;
+    } else {
+        world.add(player, PickedItem(picked)).unwrap();
+    }
+
+    game.handle(
+        world,
+        RecipeUnlockEvent {
+            player,
+            recipes: vec![recipe],
+        },
+    );
+
+    Ok(())
+}
+
+/// Takes the item in the output slot of an anvil, using up its
+/// input items. Like crafting outputs, this is only possible if
+/// the whole stack can be picked up.
+fn take_anvil_output(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    packet: ClickWindow,
+) -> anyhow::Result<()> {
+    let window = world.get::<Window>(player);
+    let anvil = window
+        .corresponding_entity(packet.slot as usize)
+        .ok_or_else(|| anyhow::anyhow!("invalid slot index"))?;
+    let output = match window.accessor(world)?.item_at(packet.slot as usize)? {
+        Some(output) => output,
+        None => return Ok(()),
+    };
+    drop(window);
+
+    if world.has::<PickedItem>(player) || !consume_anvil_inputs(game, world, anvil) {
+        return Ok(());
+    }
+    world.add(player, PickedItem(output)).unwrap();
+
+    Ok(())
+}
+
+/// Takes the item in the output slot of a merchant window,
+/// completing the trade. Like crafting outputs, the item can
+/// be stacked onto the picked item.
+fn take_merchant_output(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    packet: ClickWindow,
+) -> anyhow::Result<()> {
+    let window = world.get::<Window>(player);
+    let merchant_window = window
+        .corresponding_entity(packet.slot as usize)
+        .ok_or_else(|| anyhow::anyhow!("invalid slot index"))?;
+    let output = match window.accessor(world)?.item_at(packet.slot as usize)? {
+        Some(output) => output,
+        None => return Ok(()),
+    };
+    drop(window);
+
+    let picked = world.try_get::<PickedItem>(player).map(|i| i.0);
+    let picked = match picked {
+        None => output,
+        Some(picked)
+            if picked.eq_ignore_amount(output)
+                && picked.amount + output.amount <= picked.ty.stack_size() as u8 =>
+        {
+            picked.of_amount(picked.amount + output.amount)
+        }
+        Some(_) => return Ok(()),
+    };
+
+    if !complete_trade(game, world, merchant_window) {
+        return Ok(());
+    }
+
+    if world.has::<PickedItem>(player) {
+        world.get_mut::<PickedItem>(player).0 = picked;
+    } else {
+        world.add(player, PickedItem(picked)).unwrap();
+    }
+
+    Ok(())
+}
+
 fn handle_double_click(
     game: &mut Game,
     world: &mut World,
@@ -451,7 +598,12 @@ fn handle_double_click(
         let inventory = world.get::<Inventory>(player);
 
         // Iterate through all inventory slots, picking up items of the same type
-        for (index, slot) in inventory.enumerate() {
+        // Items can't be taken from the crafting output
+        // slot without crafting them.
+        for (index, slot) in inventory
+            .enumerate()
+            .filter(|(index, _)| index.area != Area::CraftingOutput)
+        {
             if let Some(slot) = slot {
                 // Remove items from the inventory until the player's PickedItem has reached its max stack size
                 if picked.eq_ignore_amount(slot) && slot.amount != stack_size {
@@ -1,7 +1,8 @@
 //! Handling of player block placement packets.
 
+use super::digging::resend_block;
 use crate::IteratorExt;
-use entity::InventoryExt;
+use entity::{tie_to_fence, InventoryExt};
 use feather_core::blocks::categories::PlacementType;
 use feather_core::blocks::{
     BlockId, BlockKind, Face, FacingCardinal, FacingCardinalAndDown, FacingCubic, HalfTopBottom,
@@ -12,10 +13,11 @@ use feather_core::item_block::ItemToBlock;
 use feather_core::network::packets::PlayerBlockPlacement;
 use feather_core::util::{BlockPosition, Gamemode, Position, Vec3d};
 use feather_server_types::{
-    BlockUpdateCause, Game, HeldItem, InteractionHandler, InventoryUpdateEvent, OpenWindowCount,
-    PacketBuffers,
+    AABBExt, BlockUpdateCause, Dead, Game, Health, HeldItem, InteractionHandler,
+    InventoryUpdateEvent, OpenWindowCount, PacketBuffers, Physics, WorldId, WorldState,
+    PLAYER_HEIGHT, PLAYER_WIDTH,
 };
-use feather_server_util::is_block_supported_at;
+use feather_server_util::{is_block_supported_at, nearby_entities};
 use fecs::{Entity, World};
 use once_cell::sync::Lazy;
 use smallvec::smallvec;
@@ -52,7 +54,8 @@ pub fn handle_player_block_placement(
     packet_buffers
         .received::<PlayerBlockPlacement>()
         .for_each_valid(world, |world, (player, packet)| {
-            let target_block = match game.block_at(packet.location) {
+            let world_id = game.world_of(world, player);
+            let target_block = match game.block_at(world_id, packet.location) {
                 Some(block) => block,
                 None => {
                     game.disconnect(
@@ -64,6 +67,13 @@ pub fn handle_player_block_placement(
                 }
             };
 
+            // Players holding leashed mobs tie them to the fences they click.
+            if target_block.simplified_kind() == SimplifiedBlockKind::Fence
+                && tie_to_fence(game, world, player, world_id, packet.location)
+            {
+                return;
+            }
+
             // Decide whether the player should place a block or interact with the block they are targeting
             // TODO: Maybe player shifting may need to be taken into account (shift click on interactable block)
             if let Some(interaction_handler) = INTERACTION_HANDLERS.get(&target_block.kind()) {
@@ -85,7 +95,7 @@ pub fn handle_player_block_placement(
                 );
             } else {
                 // Try to place a block
-                handle_block_placement(game, world, player, target_block, packet);
+                handle_block_placement(game, world, player, world_id, target_block, packet);
             }
         });
 }
@@ -94,6 +104,7 @@ pub fn handle_block_placement(
     game: &mut Game,
     world: &mut World,
     player: Entity,
+    world_id: WorldId,
     target_block: BlockId,
     packet: PlayerBlockPlacement,
 ) {
@@ -110,18 +121,39 @@ pub fn handle_block_placement(
 
     let block = match item.ty.to_block() {
         Some(block) => block,
-        None => return, // Item is not a block
+        None => {
+            // Item is not a block, but it may still do
+            // something to the block, like bone meal.
+            if gamemode != Gamemode::Spectator {
+                game.handle(
+                    world,
+                    ItemUseOnBlockEvent {
+                        player,
+                        world: world_id,
+                        pos: packet.location,
+                        face: packet.face,
+                        stack: item,
+                    },
+                );
+            }
+            return;
+        }
     };
 
-    if !handle_slab_placement(game, world, block, packet.location, packet.face) {
-        // TODO: waterlogged blocks, more
+    // Players in adventure mode can only place blocks on the blocks
+    // listed in the item's CanPlaceOn tag, which isn't supported yet.
+    if matches!(gamemode, Gamemode::Adventure | Gamemode::Spectator) {
+        return;
+    }
+
+    if !handle_slab_placement(game, world, world_id, block, packet.location, packet.face) {
         let pos = if target_block.is_replaceable() {
             packet.location
         } else {
             packet.location + packet.face.placement_offset()
         };
 
-        let current_block = match game.block_at(pos) {
+        let current_block = match game.block_at(world_id, pos) {
             Some(block) => block,
             None => return,
         };
@@ -139,21 +171,27 @@ pub fn handle_block_placement(
             return;
         }
 
-        let block = update_block_state_for_placement(
-            game,
+        let state = &game.worlds[world_id];
+        let mut block = update_block_state_for_placement(
+            state,
             block,
             pos,
             *world.get::<Position>(player),
             &packet,
         );
 
+        // Blocks placed in a water source are waterlogged
+        if current_block.kind() == BlockKind::Water && current_block.water_level() == Some(0) {
+            block.set_waterlogged(true);
+        }
+
         // Abort if block that needs support wouldn't have the needed support blocks
-        if !is_block_supported_at(block, game, pos) {
+        if !is_block_supported_at(block, state, pos) {
             return;
         }
 
         // handle multi-block placements (i.e. doors and beds)
-        if let Some((other_pos, other_block)) = match block.simplified_kind() {
+        let other = match block.simplified_kind() {
             SimplifiedBlockKind::Bed => {
                 let mut head = block;
                 head.set_part(Part::Head);
@@ -165,16 +203,43 @@ pub fn handle_block_placement(
                 Some((pos.up(), upper))
             }
             _ => None,
-        } {
+        };
+
+        // Deny placing blocks inside players and mobs, and
+        // multi-block placements without room for the other part
+        let denied = is_obstructed(game, world, world_id, pos, block)
+            || other.map_or(false, |(other_pos, other_block)| {
+                !state
+                    .block_at(other_pos)
+                    .map_or(false, BlockId::is_replaceable)
+                    || is_obstructed(game, world, world_id, other_pos, other_block)
+            });
+        if denied {
+            // The client already shows the placed blocks
+            resend_block(game, world, player, pos);
+            if let Some((other_pos, _)) = other {
+                resend_block(game, world, player, other_pos);
+            }
+            return;
+        }
+
+        if let Some((other_pos, other_block)) = other {
             game.set_block_at(
                 world,
+                world_id,
                 other_pos,
                 other_block,
                 BlockUpdateCause::Entity(player),
             );
         }
 
-        game.set_block_at(world, pos, block, BlockUpdateCause::Entity(player));
+        game.set_block_at(
+            world,
+            world_id,
+            pos,
+            block,
+            BlockUpdateCause::Entity(player),
+        );
     }
 
     // Update player's inventory if in survival
@@ -216,6 +281,7 @@ pub fn handle_block_placement(
 fn handle_slab_placement(
     game: &mut Game,
     world: &mut World,
+    world_id: WorldId,
     block_to_place: BlockId,
     mut target_block_pos: BlockPosition,
     placement_face: PacketFace,
@@ -224,7 +290,7 @@ fn handle_slab_placement(
         return false;
     }
 
-    let mut target_block = game.block_at(target_block_pos).unwrap();
+    let mut target_block = game.block_at(world_id, target_block_pos).unwrap();
     if target_block.simplified_kind() == SimplifiedBlockKind::Slab
         && target_block.slab_kind().unwrap() != SlabKind::Double
         && matches!(placement_face, PacketFace::Bottom | PacketFace::Top)
@@ -237,7 +303,7 @@ fn handle_slab_placement(
         }
     } else {
         target_block_pos = target_block_pos + placement_face.placement_offset();
-        if let Some(block) = game.block_at(target_block_pos) {
+        if let Some(block) = game.block_at(world_id, target_block_pos) {
             target_block = block;
         } else {
             return false;
@@ -256,6 +322,7 @@ fn handle_slab_placement(
 
     game.set_block_at(
         world,
+        world_id,
         target_block_pos,
         target_block,
         BlockUpdateCause::Unknown,
@@ -264,8 +331,62 @@ fn handle_slab_placement(
     true
 }
 
-fn update_block_state_for_placement(
+/// Returns whether a block placed at the given position would
+/// collide with a living entity, such as a player or a mob.
+fn is_obstructed(
     game: &Game,
+    world: &World,
+    world_id: WorldId,
+    pos: BlockPosition,
+    block: BlockId,
+) -> bool {
+    if !block.is_solid() {
+        return false;
+    }
+
+    let (bottom, top) = vertical_bounds(block);
+    let min = glm::vec3(pos.x as f64, pos.y as f64 + bottom, pos.z as f64);
+    let max = glm::vec3(min.x + 1.0, pos.y as f64 + top, min.z + 1.0);
+
+    let radius = glm::vec3(2.0, 3.0, 2.0);
+    nearby_entities(world, game, world_id, pos.position(), radius)
+        .into_iter()
+        .filter(|entity| world.has::<Health>(*entity) && !world.has::<Dead>(*entity))
+        .filter(|entity| {
+            world
+                .try_get::<Gamemode>(*entity)
+                .map_or(true, |gamemode| *gamemode != Gamemode::Spectator)
+        })
+        .any(|entity| {
+            let entity_pos = *world.get::<Position>(entity);
+            // Mobs without physics are assumed to be the size of a player.
+            let size = world.try_get::<Physics>(entity).map_or(
+                glm::vec3(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH),
+                |physics| physics.bbox.size(),
+            );
+
+            entity_pos.x - size.x / 2.0 < max.x
+                && entity_pos.x + size.x / 2.0 > min.x
+                && entity_pos.y < max.y
+                && entity_pos.y + size.y > min.y
+                && entity_pos.z - size.z / 2.0 < max.z
+                && entity_pos.z + size.z / 2.0 > min.z
+        })
+}
+
+/// Returns the bottom and top of a block's collision
+/// box, relative to the bottom of the block.
+fn vertical_bounds(block: BlockId) -> (f64, f64) {
+    match block.slab_kind() {
+        Some(SlabKind::Bottom) => (0.0, 0.5),
+        Some(SlabKind::Top) => (0.5, 1.0),
+        _ if block.simplified_kind() == SimplifiedBlockKind::Bed => (0.0, 0.5625),
+        _ => (0.0, 1.0),
+    }
+}
+
+fn update_block_state_for_placement(
+    state: &WorldState,
     mut block: BlockId,
     block_pos: BlockPosition,
     player_pos: Position,
@@ -340,7 +461,7 @@ fn update_block_state_for_placement(
 
     if block.has_stairs_shape() {
         block.set_stairs_shape(get_stairs_shape(
-            game,
+            state,
             block_pos,
             block.facing_cardinal().unwrap(),
             block.half_top_bottom().unwrap(),
@@ -353,7 +474,7 @@ fn update_block_state_for_placement(
 
     if block.has_hinge() {
         block.set_hinge(get_hinge_side(
-            game,
+            state,
             block.kind(),
             block_pos,
             block.facing_cardinal().unwrap(),
@@ -370,7 +491,7 @@ fn is_placed_top(face: Face, cursor_position_y: f32) -> bool {
 }
 
 fn get_hinge_side(
-    game: &Game,
+    state: &WorldState,
     block_kind: BlockKind,
     block_pos: BlockPosition,
     block_facing_cardinal: FacingCardinal,
@@ -382,17 +503,17 @@ fn get_hinge_side(
 
     let score = (
         // check right side
-        game.block_at(right_pos).unwrap().is_opaque() as i8
-            + game.block_at(right_pos.up()).unwrap().is_opaque() as i8
+        state.block_at(right_pos).unwrap().is_opaque() as i8
+            + state.block_at(right_pos.up()).unwrap().is_opaque() as i8
     ) - (
         // check left side
-        game.block_at(left_pos).unwrap().is_opaque() as i8
-            + game.block_at(left_pos.up()).unwrap().is_opaque() as i8
+        state.block_at(left_pos).unwrap().is_opaque() as i8
+            + state.block_at(left_pos.up()).unwrap().is_opaque() as i8
     );
 
     let (door_on_right, door_on_left) = {
         let is_door = |pos: BlockPosition| {
-            game.block_at(pos).and_then(|block| {
+            state.block_at(pos).and_then(|block| {
                 if block.kind() == block_kind {
                     block.half_upper_lower()
                 } else {
@@ -424,12 +545,12 @@ fn get_hinge_side(
 }
 
 fn get_stairs_shape(
-    game: &Game,
+    state: &WorldState,
     block_pos: BlockPosition,
     block_facing_cardinal: FacingCardinal,
     block_half_top_bottom: HalfTopBottom,
 ) -> StairsShape {
-    if let Some(adjacent_block) = game.block_at(block_pos + block_facing_cardinal.offset()) {
+    if let Some(adjacent_block) = state.block_at(block_pos + block_facing_cardinal.offset()) {
         if adjacent_block.simplified_kind() == SimplifiedBlockKind::Stairs
             && adjacent_block.half_top_bottom().unwrap() == block_half_top_bottom
         {
@@ -438,7 +559,7 @@ fn get_stairs_shape(
                 && is_different_stairs(
                     block_facing_cardinal,
                     block_half_top_bottom,
-                    game.block_at(block_pos + adjacent_block_facing_cardinal.opposite().offset()),
+                    state.block_at(block_pos + adjacent_block_facing_cardinal.opposite().offset()),
                 )
             {
                 if adjacent_block_facing_cardinal == block_facing_cardinal.left() {
@@ -450,7 +571,7 @@ fn get_stairs_shape(
         }
     }
 
-    if let Some(adjacent_block) = game.block_at(block_pos + block_facing_cardinal.offset()) {
+    if let Some(adjacent_block) = state.block_at(block_pos + block_facing_cardinal.offset()) {
         if adjacent_block.simplified_kind() == SimplifiedBlockKind::Stairs
             && adjacent_block.half_top_bottom().unwrap() == block_half_top_bottom
         {
@@ -459,7 +580,7 @@ fn get_stairs_shape(
                 && is_different_stairs(
                     block_facing_cardinal,
                     block_half_top_bottom,
-                    game.block_at(block_pos + adjacent_block_facing_cardinal.offset()),
+                    state.block_at(block_pos + adjacent_block_facing_cardinal.offset()),
                 )
             {
                 if adjacent_block_facing_cardinal == block_facing_cardinal.left() {
@@ -0,0 +1,111 @@
+use crate::{attack, exhaust, start_trading, IteratorExt};
+use entity::villager::Villager;
+use entity::{
+    break_leash_knot, end_crystal, hit_armor_stand, hit_boat, hit_hanging, hit_minecart,
+    use_armor_stand, ArmorStand, Boat, EndCrystal, Hanging, InventoryExt, LeashKnot, Minecart,
+};
+use feather_core::network::packets::{UseEntity, UseEntityType};
+use feather_core::util::Gamemode;
+use feather_server_types::{
+    EntityInteractEvent, Game, Inventory, NetworkId, PacketBuffers, ATTACK_EXHAUSTION,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+use std::sync::Arc;
+
+/// When a client sends Use Entity to interact with
+/// a villager, opens a window to trade with it.
+/// Interacting with other entities triggers an
+/// `EntityInteractEvent`.
+///
+/// Attacking an entity damages it and adds
+/// exhaustion to the player, while attacking
+/// a leash knot or a painting breaks it. Item
+/// frames break once their item is knocked out.
+///
+/// Armor stands are used where they are clicked,
+/// and break when hit twice in quick succession.
+/// Boats and minecarts break once hit enough.
+#[fecs::system]
+pub fn handle_use_entity(game: &mut Game, world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<UseEntity>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let target = match entity_with_network_id(world, packet.target) {
+                Some(target) => target,
+                None => return,
+            };
+
+            let spectator = *world.get::<Gamemode>(player) == Gamemode::Spectator;
+            match packet.ty {
+                UseEntityType::Interact => {
+                    if world.has::<Villager>(target) {
+                        start_trading(game, world, player, target);
+                    } else if !spectator {
+                        let stack = world
+                            .get::<Inventory>(player)
+                            .item_in_main_hand(player, world);
+                        game.handle(
+                            world,
+                            EntityInteractEvent {
+                                player,
+                                target,
+                                stack,
+                            },
+                        );
+                    }
+                }
+                UseEntityType::Attack => {
+                    if world.has::<LeashKnot>(target) {
+                        if !spectator {
+                            break_leash_knot(game, world, target, player);
+                        }
+                        return;
+                    }
+                    if world.has::<ArmorStand>(target) {
+                        if !spectator {
+                            hit_armor_stand(game, world, target, player);
+                        }
+                        return;
+                    }
+                    if world.has::<Hanging>(target) {
+                        if !spectator {
+                            hit_hanging(game, world, target, player);
+                        }
+                        return;
+                    }
+                    if world.has::<Boat>(target) {
+                        if !spectator {
+                            hit_boat(game, world, target, player);
+                        }
+                        return;
+                    }
+                    if world.has::<Minecart>(target) {
+                        if !spectator {
+                            hit_minecart(game, world, target, player);
+                        }
+                        return;
+                    }
+                    if world.has::<EndCrystal>(target) {
+                        if !spectator {
+                            end_crystal::detonate(game, world, target, Some(player));
+                        }
+                        return;
+                    }
+                    exhaust(world, player, ATTACK_EXHAUSTION);
+                    attack(game, world, player, target);
+                }
+                UseEntityType::InteractAt(_, y, _, hand) => {
+                    if hand == 0 && !spectator && world.has::<ArmorStand>(target) {
+                        use_armor_stand(game, world, target, player, y);
+                    }
+                }
+            }
+        });
+}
+
+fn entity_with_network_id(world: &World, id: i32) -> Option<Entity> {
+    <Read<NetworkId>>::query()
+        .iter_entities(world.inner())
+        .find(|(_, network_id)| network_id.0 == id)
+        .map(|(entity, _)| entity)
+}
@@ -1,10 +1,10 @@
-use crate::{ItemTimedUse, IteratorExt};
+use crate::{boost_with_firework, can_eat, ItemTimedUse, IteratorExt};
 use entity::InventoryExt;
 use feather_core::inventory::Inventory;
 use feather_core::items::Item;
 use feather_core::network::packets::UseItem;
 use feather_core::util::Hand;
-use feather_server_types::{Game, Name, PacketBuffers};
+use feather_server_types::{Game, Gliding, Name, PacketBuffers};
 use fecs::{Entity, World};
 use std::sync::Arc;
 
@@ -27,6 +27,9 @@ fn handle_use_item(game: &mut Game, world: &mut World, player: Entity, packet: U
         _ => Hand::Off,
     };
 
+    if game.raise_shield(world, player, hand) {
+        return;
+    }
     if hand != Hand::Main {
         return;
     }
@@ -36,7 +39,25 @@ fn handle_use_item(game: &mut Game, world: &mut World, player: Entity, packet: U
         .item_in_main_hand(player, world);
 
     if let Some(item_in_main_hand) = item_in_main_hand {
-        if item_in_main_hand.ty != Item::Bow {
+        if item_in_main_hand.ty == Item::Map {
+            entity::map::fill_held_map(game, world, player);
+            return;
+        }
+        if entity::BoatType::from_item(item_in_main_hand.ty).is_some() {
+            entity::place_boat(game, world, player, item_in_main_hand);
+            return;
+        }
+        if item_in_main_hand.ty == Item::CarrotOnAStick {
+            entity::boost_steered_mob(game, world, player);
+            return;
+        }
+        if item_in_main_hand.ty == Item::FireworkRocket {
+            if world.has::<Gliding>(player) {
+                boost_with_firework(game, world, player, item_in_main_hand);
+            }
+            return;
+        }
+        if item_in_main_hand.ty != Item::Bow && !can_eat(world, player, item_in_main_hand.ty) {
             //TODO: Handle other used items
             return;
         }
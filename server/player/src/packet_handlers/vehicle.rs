@@ -0,0 +1,80 @@
+use crate::IteratorExt;
+use entity::{dismount, driven_vehicle, set_paddles, Boat, Vehicle};
+use feather_core::network::packets::{SteerBoat, SteerVehicle, VehicleMoveServerbound};
+use feather_core::util::Position;
+use feather_server_types::{Game, PacketBuffers};
+use fecs::World;
+use std::sync::Arc;
+
+/// Flags of Steer Vehicle packets set while the player holds
+/// the jump key, and when they sneak to dismount.
+const JUMP_FLAG: u8 = 0x01;
+const DISMOUNT_FLAG: u8 = 0x02;
+
+/// System which handles Steer Vehicle packets, setting the input
+/// of the vehicle or mob a player drives, or dismounting the player from
+/// their vehicle when they sneak.
+#[fecs::system]
+pub fn handle_steer_vehicle(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<SteerVehicle>()
+        .for_each_valid(world, |world, (player, packet)| {
+            if packet.flags & DISMOUNT_FLAG != 0 {
+                dismount(game, world, player);
+                return;
+            }
+
+            if let Some(vehicle) = driven_vehicle(world, player) {
+                let mut vehicle = world.get_mut::<Vehicle>(vehicle);
+                vehicle.forward = packet.forward.clamp(-1.0, 1.0);
+                vehicle.sideways = packet.sideways.clamp(-1.0, 1.0);
+                vehicle.jumping = packet.flags & JUMP_FLAG != 0;
+            }
+        });
+}
+
+/// System which handles Vehicle Move packets, moving the
+/// vehicles which the clients of their drivers move.
+#[fecs::system]
+pub fn handle_vehicle_move(world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<VehicleMoveServerbound>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let vehicle = match driven_vehicle(world, player) {
+                Some(vehicle) if world.get::<Vehicle>(vehicle).client_moved => vehicle,
+                _ => return,
+            };
+
+            let mut pos = world.get_mut::<Position>(vehicle);
+            pos.x = packet.x;
+            pos.y = packet.y;
+            pos.z = packet.z;
+            pos.yaw = packet.yaw;
+            pos.pitch = packet.pitch;
+        });
+}
+
+/// System which handles Steer Boat packets, turning the
+/// paddles of the boat a player drives.
+#[fecs::system]
+pub fn handle_steer_boat(game: &mut Game, world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<SteerBoat>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let boat = match driven_vehicle(world, player) {
+                Some(boat) if world.has::<Boat>(boat) => boat,
+                _ => return,
+            };
+            set_paddles(
+                game,
+                world,
+                boat,
+                packet.left_paddle_turning,
+                packet.right_paddle_turning,
+            );
+        });
+}
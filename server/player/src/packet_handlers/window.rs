@@ -1,5 +1,8 @@
-use crate::IteratorExt;
-use feather_core::{inventory::Window, network::packets::CloseWindowServerbound};
+use crate::{select_trade, set_anvil_name, AnvilWindow, IteratorExt, MerchantWindow};
+use feather_core::{
+    inventory::Window,
+    network::packets::{CloseWindowServerbound, NameItem, SelectTrade},
+};
 use feather_server_types::{Game, PacketBuffers, WindowCloseEvent};
 use fecs::{Entity, World};
 use smallvec::SmallVec;
@@ -30,3 +33,45 @@ pub fn handle_close_window(
             }
         });
 }
+
+/// When a client sends Name Item, sets the name
+/// typed into the anvil window they have open.
+#[fecs::system]
+pub fn handle_name_item(game: &mut Game, world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<NameItem>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let anvil = world
+                .get::<Window>(player)
+                .wrapped_entities()
+                .iter()
+                .copied()
+                .find(|entity| world.has::<AnvilWindow>(*entity));
+            if let Some(anvil) = anvil {
+                set_anvil_name(game, world, anvil, &packet.item_name);
+            }
+        });
+}
+
+/// When a client sends Select Trade, selects the trade
+/// in the merchant window they have open.
+#[fecs::system]
+pub fn handle_select_trade(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<SelectTrade>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let merchant_window = world
+                .get::<Window>(player)
+                .wrapped_entities()
+                .iter()
+                .copied()
+                .find(|entity| world.has::<MerchantWindow>(*entity));
+            if let (Some(merchant_window), true) = (merchant_window, packet.selected_slot >= 0) {
+                select_trade(game, world, merchant_window, packet.selected_slot as usize);
+            }
+        });
+}
@@ -0,0 +1,74 @@
+//! Plugin messages: the server's brand, channel
+//! registration and messages on plugin channels.
+
+use crate::IteratorExt;
+use feather_core::network::packets::{PluginMessageClientbound, PluginMessageServerbound};
+use feather_core::network::McTypeRead;
+use feather_server_types::{
+    decode_channels, ClientBrand, ClientChannels, Game, Name, Network, PacketBuffers,
+    PlayerJoinEvent, PluginMessageEvent, BRAND_CHANNEL, REGISTER_CHANNEL, SERVER_BRAND,
+    UNREGISTER_CHANNEL,
+};
+use fecs::World;
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// System which handles plugin messages sent by clients.
+#[fecs::system]
+pub fn handle_plugin_messages(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<PluginMessageServerbound>()
+        .for_each_valid(world, |world, (player, packet)| {
+            match packet.channel.as_str() {
+                REGISTER_CHANNEL => {
+                    let mut channels = world.get_mut::<ClientChannels>(player);
+                    channels.0.extend(decode_channels(&packet.data));
+                }
+                UNREGISTER_CHANNEL => {
+                    let mut channels = world.get_mut::<ClientChannels>(player);
+                    for channel in decode_channels(&packet.data) {
+                        channels.0.remove(&channel);
+                    }
+                }
+                BRAND_CHANNEL => match Cursor::new(packet.data.as_slice()).try_get_string() {
+                    Ok(brand) => world.add(player, ClientBrand(brand)).unwrap(),
+                    Err(e) => log::debug!(
+                        "Player `{}` sent an invalid brand: {:?}",
+                        world.get::<Name>(player).0,
+                        e
+                    ),
+                },
+                channel if game.plugin_channels.is_registered(channel) => {
+                    game.handle(
+                        world,
+                        PluginMessageEvent {
+                            player,
+                            channel: packet.channel,
+                            data: packet.data,
+                        },
+                    );
+                }
+                _ => (),
+            }
+        });
+}
+
+/// When a player joins, sends them the server's brand
+/// and registers the server's plugin channels.
+#[fecs::event_handler]
+pub fn on_player_join_send_plugin_channels(
+    event: &PlayerJoinEvent,
+    game: &Game,
+    world: &mut World,
+) {
+    let network = world.get::<Network>(event.player);
+    network.send(PluginMessageClientbound::brand(SERVER_BRAND));
+
+    if let Some(packet) = game.plugin_channels.register_packet() {
+        network.send(packet);
+    }
+}
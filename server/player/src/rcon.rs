@@ -0,0 +1,43 @@
+//! Runs commands received over RCON.
+
+use feather_server_commands::CommandState;
+use feather_server_network::RconHandle;
+use feather_server_types::{Game, MessageReceiver, Name};
+use fecs::{EntityBuilder, World};
+
+/// The name of the entity which sends RCON commands, as in vanilla.
+const SENDER_NAME: &str = "Rcon";
+
+/// System which runs commands received over RCON and
+/// sends the messages they output back to the client.
+///
+/// Each command is sent by a new entity with a `Name`
+/// and a `MessageReceiver`, which is despawned afterwards.
+#[fecs::system]
+pub fn handle_rcon_commands(
+    game: &mut Game,
+    world: &mut World,
+    rcon: &RconHandle,
+    #[default] commands: &CommandState,
+) {
+    while let Ok(command) = rcon.rx.try_recv() {
+        log::info!("RCON executed command `{}`", command.command);
+
+        let sender = EntityBuilder::new()
+            .with(Name(SENDER_NAME.into()))
+            .with(MessageReceiver::default())
+            .build()
+            .spawn_in(world);
+        commands.dispatch(game, world, sender, &command.command);
+
+        let output: Vec<String> = world
+            .get_mut::<MessageReceiver>(sender)
+            .flush()
+            .map(|message| message.to_plain())
+            .collect();
+        world.despawn(sender);
+
+        // The client may have disconnected.
+        let _ = command.output.send(output.join("\n"));
+    }
+}
@@ -0,0 +1,77 @@
+//! Prompting players to download resource packs
+//! and enforcing forced resource packs.
+
+use crate::IteratorExt;
+use feather_core::network::packets::ResourcePackStatus as ResourcePackStatusPacket;
+use feather_server_types::{
+    Game, Name, Network, PacketBuffers, PlayerJoinEvent, ResourcePack, ResourcePackStatus,
+    ResourcePackStatusEvent,
+};
+use fecs::{Entity, World};
+use std::sync::Arc;
+
+/// Prompts a player to download a resource pack.
+///
+/// The player's response triggers a `ResourcePackStatusEvent`,
+/// and they are kicked if they reject a forced pack.
+pub fn send_resource_pack(world: &mut World, player: Entity, pack: ResourcePack) {
+    world.get::<Network>(player).send(pack.packet());
+    world.add(player, pack).unwrap();
+}
+
+/// System which handles the resource pack statuses sent by clients.
+#[fecs::system]
+pub fn handle_resource_pack_status(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<ResourcePackStatusPacket>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let status = match ResourcePackStatus::from_id(packet.result) {
+                Some(status) => status,
+                None => {
+                    log::debug!(
+                        "Player `{}` sent invalid resource pack status {}",
+                        world.get::<Name>(player).0,
+                        packet.result
+                    );
+                    return;
+                }
+            };
+
+            game.handle(world, ResourcePackStatusEvent { player, status });
+        });
+}
+
+/// When a player joins, sends them the resource pack
+/// configured in `feather.toml`, if any.
+#[fecs::event_handler]
+pub fn on_player_join_send_resource_pack(event: &PlayerJoinEvent, game: &Game, world: &mut World) {
+    if let Some(pack) = ResourcePack::from_config(&game.config.resource_pack) {
+        send_resource_pack(world, event.player, pack);
+    }
+}
+
+/// Kicks players who reject a forced resource pack.
+#[fecs::event_handler]
+pub fn on_resource_pack_status_enforce(
+    event: &ResourcePackStatusEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !event.status.is_rejected() {
+        return;
+    }
+    match world.try_get::<ResourcePack>(event.player) {
+        Some(pack) if pack.forced => (),
+        _ => return,
+    }
+
+    let reason = match event.status {
+        ResourcePackStatus::Declined => "You must accept the server's resource pack.",
+        _ => "Failed to download the server's resource pack.",
+    };
+    game.disconnect(event.player, world, reason);
+}
@@ -0,0 +1,91 @@
+//! Overriding players' skins and other profile properties.
+
+use feather_core::network::packets::{DestroyEntities, PlayerInfo, PlayerInfoAction};
+use feather_server_types::{
+    add_player_info, hidden_player_info, EntitySendEvent, Game, LastKnownPositions, Network,
+    NetworkId, ProfileProperties, SpawnPacketCreator, TabListEntry, Uuid,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+use mojang_api::ProfileProperty;
+
+/// Sets a player's skin and cape to the given value of the `textures`
+/// property, which is signed by Mojang. The signature may be empty,
+/// though clients may then ignore the textures.
+///
+/// See `set_profile_properties` for when the change is seen.
+pub fn set_skin(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    value: String,
+    signature: String,
+) {
+    world
+        .get_mut::<ProfileProperties>(player)
+        .set(ProfileProperty {
+            name: ProfileProperties::TEXTURES.to_owned(),
+            value,
+            signature,
+        });
+    resend_profile(game, world, player);
+}
+
+/// Sets the profile properties of a player, replacing those
+/// obtained when they were authenticated.
+///
+/// Clients only read properties when a player is added to their
+/// tab list, so the player is added again and respawned on the
+/// clients which see them. The player's own client only shows
+/// the change in the tab list until they rejoin.
+pub fn set_profile_properties(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    properties: Vec<ProfileProperty>,
+) {
+    world.get_mut::<ProfileProperties>(player).0 = properties;
+    resend_profile(game, world, player);
+}
+
+fn resend_profile(game: &mut Game, world: &mut World, player: Entity) {
+    let packet = PlayerInfo {
+        action: PlayerInfoAction::RemovePlayer,
+        uuid: *world.get::<Uuid>(player),
+    };
+    game.broadcast_global(world, packet, None);
+
+    let packet = add_player_info(&world.entity(player).expect("player does not exist"));
+    // Hidden players are only in their own tab list.
+    if world.get::<TabListEntry>(player).hidden {
+        world.get::<Network>(player).send(packet);
+    } else {
+        game.broadcast_global(world, packet, None);
+    }
+
+    let clients: Vec<Entity> = <Read<LastKnownPositions>>::query()
+        .iter_entities(world.inner())
+        .filter(|(client, positions)| *client != player && positions.0.contains_key(&player))
+        .map(|(client, _)| client)
+        .collect();
+    for client in clients {
+        if let Some(network) = world.try_get::<Network>(client) {
+            network.send(DestroyEntities {
+                entity_ids: vec![world.get::<NetworkId>(player).0],
+            });
+            if let Some(info) = hidden_player_info(world, player) {
+                network.send(info);
+            }
+            let accessor = world.entity(player).expect("player does not exist");
+            let creator = world.get::<SpawnPacketCreator>(player);
+            network.send_boxed(creator.get(&accessor));
+        }
+
+        game.handle(
+            world,
+            EntitySendEvent {
+                entity: player,
+                client,
+            },
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! The tab list: players' display names, latency, order and
+//! visibility, plus the header and footer shown to each player.
+//!
+//! Players are sorted using one scoreboard team per player
+//! with an order, since clients sort the tab list by team name.
+
+use feather_core::network::packets::{
+    PlayerInfo, PlayerInfoAction, PlayerListHeaderAndFooter, Teams, TeamsAction,
+};
+use feather_core::text::Text;
+use feather_server_types::{
+    add_player_info, create_team, EntityDespawnEvent, EntitySendEvent, Game, GamemodeUpdateEvent,
+    Network, NetworkId, Player, PlayerJoinEvent, TabListEntry, TabListHeaderFooter, Uuid,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+
+/// How often players' latency is sent to clients, in ticks.
+const LATENCY_UPDATE_INTERVAL: u64 = 600;
+/// How long hidden players stay in the tab list of a client
+/// after being sent to it, in ticks. Clients load a player's
+/// skin from their tab list entry when first rendering them,
+/// so removing it right away shows the default skin instead.
+const HIDDEN_PLAYER_INFO_DELAY: u64 = 20;
+
+/// Component of a client storing the hidden players to
+/// remove from its tab list and the tick to do so at.
+#[derive(Clone, Debug, Default)]
+pub struct PendingInfoRemovals(pub Vec<(Entity, u64)>);
+
+/// Sets the name shown for a player in the tab list,
+/// or shows their name if `display_name` is `None`.
+pub fn set_display_name(
+    game: &Game,
+    world: &mut World,
+    player: Entity,
+    display_name: Option<Text>,
+) {
+    world.get_mut::<TabListEntry>(player).display_name = display_name.clone();
+
+    let packet = PlayerInfo {
+        action: PlayerInfoAction::UpdateDisplayName(display_name.map(String::from)),
+        uuid: *world.get::<Uuid>(player),
+    };
+    game.broadcast_global(world, packet, None);
+}
+
+/// Sets the order of a player in the tab list. Players are
+/// sorted by ascending order, with those without one first.
+pub fn set_order(game: &Game, world: &mut World, player: Entity, order: Option<i32>) {
+    let network_id = *world.get::<NetworkId>(player);
+    let old_team = world.get::<TabListEntry>(player).team_name(network_id);
+    world.get_mut::<TabListEntry>(player).order = order;
+
+    if let Some(name) = old_team {
+        let packet = Teams {
+            name,
+            action: TeamsAction::Remove,
+        };
+        game.broadcast_global(world, packet, None);
+    }
+
+    let accessor = world.entity(player).expect("player does not exist");
+    if let Some(packet) = create_team(&accessor, &game.scoreboard) {
+        game.broadcast_global(world, packet, None);
+    }
+}
+
+/// Sets whether a player is hidden from the tab list of other
+/// players. Hidden players are still visible in the world.
+pub fn set_hidden(game: &Game, world: &mut World, player: Entity, hidden: bool) {
+    if world.get::<TabListEntry>(player).hidden == hidden {
+        return;
+    }
+    world.get_mut::<TabListEntry>(player).hidden = hidden;
+
+    let uuid = *world.get::<Uuid>(player);
+    if hidden {
+        let packet = PlayerInfo {
+            action: PlayerInfoAction::RemovePlayer,
+            uuid,
+        };
+        game.broadcast_global(world, packet, Some(player));
+    } else {
+        let accessor = world.entity(player).expect("player does not exist");
+        game.broadcast_global(world, add_player_info(&accessor), Some(player));
+    }
+}
+
+/// Sets the header and footer of the tab list shown to a player.
+pub fn set_header_footer(world: &mut World, player: Entity, header: Text, footer: Text) {
+    let header_footer = TabListHeaderFooter { header, footer };
+    send_header_footer(world, player, &header_footer);
+    *world.get_mut::<TabListHeaderFooter>(player) = header_footer;
+}
+
+fn send_header_footer(world: &World, player: Entity, header_footer: &TabListHeaderFooter) {
+    let packet = PlayerListHeaderAndFooter {
+        header: header_footer.header.clone().into(),
+        footer: header_footer.footer.clone().into(),
+    };
+    world.get::<Network>(player).send(packet);
+}
+
+/// System which periodically sends players' latency to all clients.
+#[fecs::system]
+pub fn broadcast_latency(game: &Game, world: &mut World) {
+    if game.tick_count % LATENCY_UPDATE_INTERVAL != 0 {
+        return;
+    }
+
+    for (uuid, entry) in <(Read<Uuid>, Read<TabListEntry>)>::query().iter(world.inner()) {
+        let packet = PlayerInfo {
+            action: PlayerInfoAction::UpdateLatency(entry.latency),
+            uuid: *uuid,
+        };
+        game.broadcast_global(world, packet, None);
+    }
+}
+
+/// When a player joins, sends them their tab list header and
+/// footer and the teams which sort other players.
+#[fecs::event_handler]
+pub fn on_player_join_send_tab_list(event: &PlayerJoinEvent, game: &Game, world: &mut World) {
+    send_header_footer(
+        world,
+        event.player,
+        &*world.get::<TabListHeaderFooter>(event.player),
+    );
+
+    let network = world.get::<Network>(event.player);
+    for (entity, _) in <Read<Player>>::query().iter_entities(world.inner()) {
+        // The player's own team was sent when their order was set.
+        if entity == event.player {
+            continue;
+        }
+
+        let accessor = world
+            .entity(entity)
+            .expect("query yielded entity which does not exist");
+        if let Some(packet) = create_team(&accessor, &game.scoreboard) {
+            network.send(packet);
+        }
+    }
+}
+
+/// When a player with an order is despawned, removes their team.
+#[fecs::event_handler]
+pub fn on_entity_despawn_remove_team(event: &EntityDespawnEvent, game: &Game, world: &mut World) {
+    let entry = match world.try_get::<TabListEntry>(event.entity) {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    if let Some(name) = entry.team_name(*world.get::<NetworkId>(event.entity)) {
+        let packet = Teams {
+            name,
+            action: TeamsAction::Remove,
+        };
+        game.broadcast_global(world, packet, Some(event.entity));
+    }
+}
+
+/// When a hidden player is sent to a client, schedules their
+/// removal from its tab list. See `hidden_player_info`.
+#[fecs::event_handler]
+pub fn on_entity_send_remove_hidden_player(
+    event: &EntitySendEvent,
+    game: &Game,
+    world: &mut World,
+) {
+    match world.try_get::<TabListEntry>(event.entity) {
+        Some(entry) if entry.hidden => (),
+        _ => return,
+    }
+
+    if let Some(mut pending) = world.try_get_mut::<PendingInfoRemovals>(event.client) {
+        pending
+            .0
+            .push((event.entity, game.tick_count + HIDDEN_PLAYER_INFO_DELAY));
+    }
+}
+
+/// System which removes hidden players from the tab
+/// lists of clients they were sent to, once due.
+#[fecs::system]
+pub fn remove_hidden_player_info(game: &Game, world: &mut World) {
+    for (client, network) in <Read<Network>>::query().iter_entities(world.inner()) {
+        let mut pending = match world.try_get_mut::<PendingInfoRemovals>(client) {
+            Some(pending) if !pending.0.is_empty() => pending,
+            _ => continue,
+        };
+
+        pending.0.retain(|&(player, tick)| {
+            if tick > game.tick_count {
+                return true;
+            }
+
+            // Players who were shown again or left
+            // in the meantime are left alone.
+            match world.try_get::<TabListEntry>(player) {
+                Some(entry) if entry.hidden => network.send(PlayerInfo {
+                    action: PlayerInfoAction::RemovePlayer,
+                    uuid: *world.get::<Uuid>(player),
+                }),
+                _ => (),
+            }
+            false
+        });
+    }
+}
+
+/// Updates a player's gamemode in the tab list.
+#[fecs::event_handler]
+pub fn on_gamemode_update_broadcast_tab_list(
+    event: &GamemodeUpdateEvent,
+    game: &Game,
+    world: &mut World,
+) {
+    if !world.is_alive(event.player) {
+        return;
+    }
+
+    let packet = PlayerInfo {
+        action: PlayerInfoAction::UpdateGamemode(event.new),
+        uuid: *world.get::<Uuid>(event.player),
+    };
+    game.broadcast_global(world, packet, None);
+}
@@ -0,0 +1,456 @@
+//! Trading with villagers through merchant windows.
+
+use entity::spawn_experience_orbs;
+use entity::villager::{self, Career, Customer, GossipKind, Gossips, Offers};
+use feather_core::inventory::{Area, Inventory, SlotIndex, Window};
+use feather_core::items::ItemStack;
+use feather_core::network::packets::{OpenWindow, SetSlot, TradeList, WindowItems};
+use feather_core::text::TextRoot;
+use feather_core::util::Position;
+use feather_server_types::{
+    Game, InventoryUpdateEvent, ItemDropEvent, Network, OpenWindowCount, Uuid, WindowCloseEvent,
+    WindowOpenEvent,
+};
+use fecs::{Entity, EntityBuilder, World};
+use rand::Rng;
+use smallvec::{smallvec, SmallVec};
+
+/// Number of slots in a merchant window, including
+/// the player's main inventory and hotbar.
+const MERCHANT_WINDOW_SLOTS: usize = 39;
+
+/// Gossip a villager gains about a player for each trade.
+const TRADING_GOSSIP: i32 = 2;
+
+/// Component for the inventory behind a merchant window,
+/// which is created each time a player starts trading.
+#[derive(Clone, Debug)]
+pub struct MerchantWindow {
+    /// The player trading.
+    pub player: Entity,
+    /// The merchant the player trades with.
+    pub merchant: Entity,
+    /// The ID of the window opened for the player.
+    pub window_id: u8,
+    /// The index of the trade the player selected, if any.
+    pub selected: Option<usize>,
+    /// The index of the trade paid for by the input items, if any.
+    pub offer: Option<usize>,
+}
+
+/// Opens a merchant window for a player to trade with a
+/// merchant. Returns `false` if the merchant has no trades
+/// or is already trading with another player.
+pub fn start_trading(game: &mut Game, world: &mut World, player: Entity, merchant: Entity) -> bool {
+    let has_offers = world
+        .try_get::<Offers>(merchant)
+        .map_or(false, |offers| !offers.0.is_empty());
+    if !has_offers || world.has::<Customer>(merchant) {
+        return false;
+    }
+
+    let window_id = match world.try_get_mut::<OpenWindowCount>(player) {
+        Some(mut count) => count.get_increment(),
+        None => return false,
+    };
+    let window = EntityBuilder::new()
+        .with(Inventory::merchant())
+        .with(MerchantWindow {
+            player,
+            merchant,
+            window_id,
+            selected: None,
+            offer: None,
+        })
+        .build()
+        .spawn_in(world);
+
+    let profession = world
+        .try_get::<Career>(merchant)
+        .map_or(villager::Profession::None, |career| career.profession);
+    world.get::<Network>(player).send(OpenWindow {
+        window_id,
+        window_type: String::from("minecraft:villager"),
+        window_title: TextRoot::from(profession.name()).into(),
+        number_of_slots: 3,
+        entity_id: None,
+    });
+    send_trades(world, window);
+
+    *world.get_mut::<Window>(player) = Window::merchant(player, window);
+    world.add(merchant, Customer(player)).unwrap();
+    game.handle(
+        world,
+        WindowOpenEvent {
+            player,
+            opened: window,
+        },
+    );
+    true
+}
+
+/// Returns the UUID of the player using a merchant window.
+fn player_uuid(world: &World, player: Entity) -> Uuid {
+    world
+        .try_get::<Uuid>(player)
+        .map(|uuid| *uuid)
+        .unwrap_or_default()
+}
+
+/// Sends the trades of a merchant, with prices
+/// for its player, to a merchant window.
+fn send_trades(world: &World, window: Entity) {
+    let (player, merchant, window_id) = {
+        let window = world.get::<MerchantWindow>(window);
+        (window.player, window.merchant, window.window_id)
+    };
+    let offers = match world.try_get::<Offers>(merchant) {
+        Some(offers) => offers,
+        None => return,
+    };
+
+    let special_prices = villager::special_prices(world, merchant, player_uuid(world, player));
+    let trades = offers
+        .0
+        .iter()
+        .zip(special_prices)
+        .map(|(offer, special_price)| offer.to_trade(special_price))
+        .collect();
+    let career = world
+        .try_get::<Career>(merchant)
+        .map(|career| *career)
+        .unwrap_or_default();
+
+    if let Some(network) = world.try_get::<Network>(player) {
+        network.send(TradeList {
+            window_id,
+            trades,
+            villager_level: i32::from(career.level),
+            experience: career.xp,
+            is_regular_villager: true,
+            can_restock: true,
+        });
+    }
+}
+
+/// Sends a player all items in their merchant window.
+fn send_window_items(world: &World, window: Entity) {
+    let (player, window_id) = {
+        let window = world.get::<MerchantWindow>(window);
+        (window.player, window.window_id)
+    };
+    let slots = {
+        let player_window = world.get::<Window>(player);
+        let accessor = match player_window.accessor(world) {
+            Ok(accessor) => accessor,
+            Err(_) => return,
+        };
+        (0..MERCHANT_WINDOW_SLOTS)
+            .map(|slot| accessor.item_at(slot).unwrap_or(None))
+            .collect()
+    };
+
+    if let Some(network) = world.try_get::<Network>(player) {
+        network.send(WindowItems { window_id, slots });
+    }
+}
+
+/// Selects a trade in a merchant window. The input items are
+/// given back to the player, and replaced with the items paying
+/// for the trade from their inventory.
+pub fn select_trade(game: &mut Game, world: &mut World, window: Entity, index: usize) {
+    let (player, merchant) = match world.try_get_mut::<MerchantWindow>(window) {
+        Some(mut merchant_window) => {
+            merchant_window.selected = Some(index);
+            (merchant_window.player, merchant_window.merchant)
+        }
+        None => return,
+    };
+    let offer = match world
+        .try_get::<Offers>(merchant)
+        .and_then(|offers| offers.0.get(index).copied())
+    {
+        Some(offer) => offer,
+        None => return,
+    };
+    let special_price = villager::special_prices(world, merchant, player_uuid(world, player))
+        .get(index)
+        .copied()
+        .unwrap_or(0);
+
+    {
+        let inventory = world.get::<Inventory>(window);
+        let player_inventory = match world.try_get::<Inventory>(player) {
+            Some(inventory) => inventory,
+            None => return,
+        };
+
+        let costs = [Some(offer.price(special_price)), offer.input_2];
+        for (slot, cost) in costs.iter().enumerate() {
+            // Give back the items in the slot, keeping those which don't fit.
+            if let Some(item) = inventory.item_at(Area::MerchantInput, slot).unwrap() {
+                let (_, remaining) = player_inventory.collect_item(item);
+                match remaining {
+                    0 => inventory.remove_item_at(Area::MerchantInput, slot),
+                    remaining => {
+                        inventory.set_item_at(Area::MerchantInput, slot, item.of_amount(remaining))
+                    }
+                }
+                .unwrap();
+            }
+
+            let empty = inventory
+                .item_at(Area::MerchantInput, slot)
+                .unwrap()
+                .is_none();
+            if let (Some(cost), true) = (cost, empty) {
+                if let Some(item) = take_items(&player_inventory, *cost) {
+                    inventory
+                        .set_item_at(Area::MerchantInput, slot, item)
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    update_merchant_output(game, world, window);
+    send_window_items(world, window);
+}
+
+/// Takes up to a stack of items of the same kind as `cost` from
+/// a player's main inventory and hotbar.
+fn take_items(inventory: &Inventory, cost: ItemStack) -> Option<ItemStack> {
+    let stack_size = cost.ty.stack_size() as u8;
+    let matching: Vec<(SlotIndex, ItemStack)> = inventory
+        .enumerate()
+        .filter(|(index, _)| matches!(index.area, Area::Main | Area::Hotbar))
+        .filter_map(|(index, item)| Some((index, item?)))
+        .filter(|(_, item)| item.eq_ignore_amount(cost))
+        .collect();
+
+    let mut taken: Option<ItemStack> = None;
+    for (index, item) in matching {
+        let amount = taken.map_or(0, |taken| taken.amount);
+        let moved = item.amount.min(stack_size - amount);
+        if moved == 0 {
+            break;
+        }
+
+        if moved == item.amount {
+            inventory.remove_item_at(index.area, index.slot)
+        } else {
+            inventory.set_item_at(index.area, index.slot, item.of_amount(item.amount - moved))
+        }
+        .unwrap();
+        taken = Some(item.of_amount(amount + moved));
+    }
+    taken
+}
+
+/// Finds the trade paid for by the input items of a merchant
+/// window, setting its output slot to the trade's output.
+fn update_merchant_output(game: &mut Game, world: &mut World, window: Entity) {
+    let (player, window_id, output) = {
+        let inventory = world.get::<Inventory>(window);
+        let mut merchant_window = world.get_mut::<MerchantWindow>(window);
+        let first = inventory.item_at(Area::MerchantInput, 0).unwrap();
+        let second = inventory.item_at(Area::MerchantInput, 1).unwrap();
+
+        let special_prices = villager::special_prices(
+            world,
+            merchant_window.merchant,
+            player_uuid(world, merchant_window.player),
+        );
+        let offers = world.get::<Offers>(merchant_window.merchant);
+        merchant_window.offer =
+            offers.find(merchant_window.selected, first, second, &special_prices);
+        let output = merchant_window.offer.map(|index| offers.0[index].output);
+
+        match output {
+            Some(output) => inventory.set_item_at(Area::MerchantOutput, 0, output),
+            None => inventory.remove_item_at(Area::MerchantOutput, 0),
+        }
+        .unwrap();
+        (merchant_window.player, merchant_window.window_id, output)
+    };
+
+    if let Some(network) = world.try_get::<Network>(player) {
+        network.send(SetSlot {
+            window_id: window_id as i8,
+            slot: 2,
+            slot_data: output,
+        });
+    }
+
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: smallvec![SlotIndex {
+                area: Area::MerchantOutput,
+                slot: 0,
+            }],
+            entity: window,
+        },
+    );
+}
+
+/// When the input items of a merchant window change,
+/// finds the trade they pay for to update its output.
+#[fecs::event_handler]
+pub fn on_inventory_update_update_merchant_output(
+    event: &InventoryUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if !world.has::<MerchantWindow>(event.entity)
+        || !event
+            .slots
+            .iter()
+            .any(|slot| slot.area == Area::MerchantInput)
+    {
+        return;
+    }
+    update_merchant_output(game, world, event.entity);
+}
+
+/// Completes the trade whose output was taken from a merchant
+/// window, taking its price from the input items. The merchant
+/// gains experience and gossip about the player, and most trades
+/// drop experience orbs for the player.
+///
+/// Returns `false` if the trade can't be made. The taken item
+/// isn't added anywhere, and should be taken from the output
+/// slot beforehand.
+pub fn complete_trade(game: &mut Game, world: &mut World, window: Entity) -> bool {
+    let (player, merchant, index) = match world.try_get::<MerchantWindow>(window) {
+        Some(merchant_window) => match merchant_window.offer {
+            Some(index) => (merchant_window.player, merchant_window.merchant, index),
+            None => return false,
+        },
+        None => return false,
+    };
+    let uuid = player_uuid(world, player);
+    let special_price = villager::special_prices(world, merchant, uuid)
+        .get(index)
+        .copied()
+        .unwrap_or(0);
+
+    let reward_xp = {
+        let mut offers = match world.try_get_mut::<Offers>(merchant) {
+            Some(offers) => offers,
+            None => return false,
+        };
+        let offer = &mut offers.0[index];
+
+        let inventory = world.get::<Inventory>(window);
+        let mut first = inventory.item_at(Area::MerchantInput, 0).unwrap();
+        let mut second = inventory.item_at(Area::MerchantInput, 1).unwrap();
+        // A single item pays for trades with one input
+        // whichever slot it is in.
+        if first.is_some() {
+            if !offer.is_paid_by(first, second, special_price) {
+                return false;
+            }
+            offer.pay(&mut first, &mut second, special_price);
+        } else {
+            if !offer.is_paid_by(second, None, special_price) {
+                return false;
+            }
+            offer.pay(&mut second, &mut first, special_price);
+        }
+        for (slot, item) in [first, second].iter().enumerate() {
+            match item {
+                Some(item) => inventory.set_item_at(Area::MerchantInput, slot, *item),
+                None => inventory.remove_item_at(Area::MerchantInput, slot),
+            }
+            .unwrap();
+        }
+
+        offer.uses += 1;
+        if let Some(mut career) = world.try_get_mut::<Career>(merchant) {
+            career.xp += offer.xp;
+        }
+        offer.reward_xp
+    };
+    if reward_xp {
+        let points = 3 + game.rng().gen_range(0, 4);
+        let pos = *world.get::<Position>(merchant) + glm::vec3(0.0, 0.5, 0.0);
+        let world_id = game.world_of(world, merchant);
+        spawn_experience_orbs(game, world, world_id, pos, points);
+    }
+    if let Some(mut gossips) = world.try_get_mut::<Gossips>(merchant) {
+        gossips.add(uuid, GossipKind::Trading, TRADING_GOSSIP);
+    }
+
+    // Uses and prices changed, so the trades are sent again.
+    send_trades(world, window);
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots: smallvec![
+                SlotIndex {
+                    area: Area::MerchantInput,
+                    slot: 0,
+                },
+                SlotIndex {
+                    area: Area::MerchantInput,
+                    slot: 1,
+                },
+            ],
+            entity: window,
+        },
+    );
+    true
+}
+
+/// When a player closes a merchant window, gives them back its
+/// input items and removes the window's inventory. The merchant
+/// levels up if it gained enough experience from the trades.
+#[fecs::event_handler]
+pub fn on_window_close_stop_trading(event: &WindowCloseEvent, game: &mut Game, world: &mut World) {
+    let merchant = match world.try_get::<MerchantWindow>(event.closed) {
+        Some(window) => window.merchant,
+        None => return,
+    };
+
+    let items: SmallVec<[ItemStack; 2]> = {
+        let inventory = world.get::<Inventory>(event.closed);
+        (0..2)
+            .filter_map(|slot| inventory.item_at(Area::MerchantInput, slot).unwrap())
+            .collect()
+    };
+
+    for item in items {
+        let (slots, remaining) = match world.try_get::<Inventory>(event.player) {
+            Some(inventory) => inventory.collect_item(item),
+            None => (SmallVec::new(), item.amount),
+        };
+        if !slots.is_empty() {
+            game.handle(
+                world,
+                InventoryUpdateEvent {
+                    slots,
+                    entity: event.player,
+                },
+            );
+        }
+        if remaining > 0 {
+            game.handle(
+                world,
+                ItemDropEvent {
+                    slot: None,
+                    stack: item.of_amount(remaining),
+                    player: event.player,
+                },
+            );
+        }
+    }
+
+    if world.is_alive(merchant) {
+        let _ = world.remove::<Customer>(merchant);
+        if world.has::<Career>(merchant) {
+            villager::level_up(game, world, merchant);
+        }
+    }
+    world.despawn(event.closed);
+}
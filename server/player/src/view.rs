@@ -4,37 +4,45 @@
 //! which handle sending new data as
 //! a player moves through the world.
 //!
-//! When a player crosses a chunk boundary, its
-//! view has changed: some chunks are no longer visible,
-//! while others now are. To account for this, we
-//! must send the new chunks, unload the old
+//! When a player crosses a chunk boundary or changes
+//! its view distance, its view has changed: some chunks
+//! are no longer visible, while others now are. To account
+//! for this, we must send the new chunks, unload the old
 //! chunks on the client, send new entities, and
 //! delete old ones.
 //!
 //! This is handled as follows:
 //! * A system queries all position components which have changed
-//! and adds a `CrossedChunk` component to these entities.
-//! * Other systems query for added `CrossedChunk` components
-//! and perform updates on these players' views.
+//! and triggers a `ChunkCrossEvent` for these entities.
+//! * For players, this moves their `View`, triggering a `ViewUpdateEvent`.
+//! The view distance is updated by the Client Settings packet handler.
+//! * Event handlers for `ViewUpdateEvent` perform updates
+//! on these players' views.
+//!
+//! When a player changes worlds, its whole view is released in
+//! the old world and sent anew in the new one, as if it had just joined.
 
-use crate::Player;
 use ahash::AHashMap;
+use feather_core::anvil::level::LevelGeneratorType;
 use feather_core::chunk::Chunk;
-use feather_core::network::packets::{ChunkData, DestroyEntities, UnloadChunk};
-use feather_core::util::{ChunkPosition, Position};
+use feather_core::network::packets::{
+    ChunkData, DestroyEntities, Respawn, UnloadChunk, UpdateLight, UpdateViewPosition,
+};
+use feather_core::util::{ChunkPosition, Dimension, Gamemode, Position};
 use feather_server_types::{
-    BumpVec, ChunkCrossEvent, ChunkLoadEvent, ChunkSendEvent, EntityClientRemoveEvent,
-    EntitySendEvent, Game, HoldChunkRequest, LoadChunkRequest, Network, NetworkId, PlayerJoinEvent,
-    PreviousPosition, ReleaseChunkRequest, SpawnPacketCreator,
+    hidden_player_info, BumpVec, ChunkCrossEvent, ChunkHolder, ChunkLoadEvent, ChunkSendEvent,
+    EntityClientRemoveEvent, EntitySendEvent, Game, HoldChunkRequest, LoadChunkRequest, Network,
+    NetworkId, PlayerJoinEvent, PreviousPosition, ReleaseChunkRequest, RemoveTicketRequest,
+    SpawnPacketCreator, Teleported, TicketKind, View, ViewUpdateEvent, WorldChangeEvent, WorldId,
 };
 use fecs::{Entity, IntoQuery, Read, World};
-use itertools::Either;
 use parking_lot::RwLock;
 use smallvec::SmallVec;
-use std::iter;
-use std::ops::Add;
 use std::sync::Arc;
 
+/// The lowest view distance a client may request.
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+
 /// System which polls for updated positions and
 /// calls `Game::on_chunk_cross()` accordingly.
 #[fecs::system]
@@ -80,52 +88,80 @@ pub fn on_player_join_trigger_chunk_cross(
     );
 }
 
+/// Moves a player's view when it crosses into a new chunk.
+#[fecs::event_handler]
+pub fn on_chunk_cross_update_view(event: &ChunkCrossEvent, game: &mut Game, world: &mut World) {
+    let old = match world.try_get::<View>(event.entity) {
+        Some(view) => *view,
+        None => return, // not a player
+    };
+
+    let new = View {
+        center: event.new,
+        ..old
+    };
+    *world.get_mut::<View>(event.entity) = new;
+
+    game.handle(
+        world,
+        ViewUpdateEvent {
+            player: event.entity,
+            // A player which just joined can't see any chunks yet.
+            old: event.old.map(|_| old),
+            new,
+        },
+    );
+}
+
 /// System which sends new chunks and unloads old chunks on the client
 /// when the view is updated.
 #[fecs::event_handler]
-pub fn on_chunk_cross_update_chunks(
-    event: &ChunkCrossEvent,
+pub fn on_view_update_update_chunks(
+    event: &ViewUpdateEvent,
     game: &mut Game,
     #[default] chunks_to_send: &mut ChunksToSend,
     world: &mut World,
 ) {
-    if world.try_get::<Player>(event.entity).is_none() {
-        return;
-    }
-
     // The client likes it if we send closer chunks first,
     // so we'll sort by the Manhattan distance to the player.
     let mut pending_send = BumpVec::new_in(game.bump());
-    pending_send.extend(find_new_chunks(
-        event.old,
-        event.new,
-        game.config.server.view_distance,
-    ));
-    pending_send.sort_unstable_by_key(|chunk| chunk.manhattan_distance_to(event.new));
+    pending_send.extend(find_new_chunks(event.old, event.new));
+    pending_send.sort_unstable_by_key(|chunk| chunk.manhattan_distance_to(event.new.center));
+
+    // Clients since 1.14 only load the chunks
+    // around the center of their view.
+    world.get::<Network>(event.player).send(UpdateViewPosition {
+        chunk_x: event.new.center.x,
+        chunk_z: event.new.center.z,
+    });
 
+    let world_id = game.world_of(world, event.player);
     for chunk in pending_send {
-        send_chunk_to_player(game, world, chunks_to_send, event.entity, chunk);
+        send_chunk_to_player(game, world, chunks_to_send, event.player, world_id, chunk);
     }
 
-    for chunk in find_old_chunks(event.old, event.new, game.config.server.view_distance) {
-        unload_chunk_for_player(game, world, chunk, event.entity);
+    for chunk in find_old_chunks(event.old, event.new) {
+        unload_chunk_for_player(game, world, world_id, chunk, event.player);
     }
 }
 
 /// System which sends new entities and removes old entities
-/// when a player crosses into a new view.
+/// when a player's view is updated.
 #[fecs::event_handler]
-pub fn on_chunk_cross_update_entities(event: &ChunkCrossEvent, game: &mut Game, world: &mut World) {
-    let network = match world.try_get::<Network>(event.entity) {
+pub fn on_view_update_update_entities(event: &ViewUpdateEvent, game: &mut Game, world: &mut World) {
+    let player = event.player;
+    let network = match world.try_get::<Network>(player) {
         Some(net) => net,
-        None => return, // not a player
+        None => return,
     };
 
+    let chunk_entities = &game.worlds[game.world_of(world, player)].chunk_entities;
+
     // Send newly visible entities.
     let mut sends_to_trigger = vec![];
-    for other in find_new_chunks(event.old, event.new, game.config.server.view_distance)
-        .flat_map(|chunk| game.chunk_entities.entities_in_chunk(chunk))
-        .filter(|other| **other != event.entity)
+    for other in find_new_chunks(event.old, event.new)
+        .flat_map(|chunk| chunk_entities.entities_in_chunk(chunk))
+        .filter(|other| **other != player)
     // don't send player to themselves!
     {
         if let Some(creator) = world.try_get::<SpawnPacketCreator>(*other) {
@@ -134,18 +170,24 @@ pub fn on_chunk_cross_update_entities(event: &ChunkCrossEvent, game: &mut Game,
                 .expect("entity in chunk entities does not exist");
             let packet = creator.get(&accessor);
 
+            if let Some(info) = hidden_player_info(world, *other) {
+                network.send(info);
+            }
             network.send_boxed(packet);
-            sends_to_trigger.push((*other, event.entity));
+            sends_to_trigger.push((*other, player));
         }
 
         // if this `other` is a player, also send `entity` to other
         if let Some(network) = world.try_get::<Network>(*other) {
-            if let Some(creator) = world.try_get::<SpawnPacketCreator>(event.entity) {
-                let accessor = world.entity(event.entity).expect("entity does not exist");
+            if let Some(creator) = world.try_get::<SpawnPacketCreator>(player) {
+                let accessor = world.entity(player).expect("entity does not exist");
                 let packet = creator.get(&accessor);
 
+                if let Some(info) = hidden_player_info(world, player) {
+                    network.send(info);
+                }
                 network.send_boxed(packet);
-                sends_to_trigger.push((event.entity, *other));
+                sends_to_trigger.push((player, *other));
             }
         }
     }
@@ -153,21 +195,21 @@ pub fn on_chunk_cross_update_entities(event: &ChunkCrossEvent, game: &mut Game,
     // Tell the client to despawn entities which are no longer visible.
     let mut to_client_remove_trigger = vec![];
     to_client_remove_trigger.extend(
-        find_old_chunks(event.old, event.new, game.config.server.view_distance)
-            .flat_map(|chunk| game.chunk_entities.entities_in_chunk(chunk))
-            .map(|other| (*other, event.entity)),
+        find_old_chunks(event.old, event.new)
+            .flat_map(|chunk| chunk_entities.entities_in_chunk(chunk))
+            .map(|other| (*other, player)),
     );
 
     // Despawn this entity on other visible clients.
-    find_old_chunks(event.old, event.new, game.config.server.view_distance)
-        .flat_map(|chunk| game.chunk_entities.entities_in_chunk(chunk))
+    find_old_chunks(event.old, event.new)
+        .flat_map(|chunk| chunk_entities.entities_in_chunk(chunk))
         .filter_map(|entity| world.try_get::<Network>(*entity).map(|net| (*entity, net)))
         .for_each(|(other, network)| {
             let packet = DestroyEntities {
-                entity_ids: vec![world.get::<NetworkId>(event.entity).0],
+                entity_ids: vec![world.get::<NetworkId>(player).0],
             };
             network.send(packet);
-            to_client_remove_trigger.push((event.entity, other));
+            to_client_remove_trigger.push((player, other));
         });
 
     let to_destroy = to_client_remove_trigger
@@ -201,57 +243,23 @@ pub fn on_chunk_cross_update_entities(event: &ChunkCrossEvent, game: &mut Game,
     }
 }
 
-/// Returns new chunks visible from a new chunk position.
-fn find_new_chunks(
-    old: Option<ChunkPosition>,
-    new: ChunkPosition,
-    view_distance: u8,
-) -> impl Iterator<Item = ChunkPosition> {
-    let within_view_distance = chunks_within_view_distance(new, view_distance);
-    if let Some(old) = old {
-        Either::Left(within_view_distance.filter(move |chunk| {
-            (chunk.x - old.x).abs() >= view_distance as i32
-                || (chunk.z - old.z).abs() >= view_distance as i32
-        }))
-    } else {
-        Either::Right(within_view_distance)
-    }
-}
-
-/// Returns chunks which are no longer visible from a new chunk position.
-fn find_old_chunks(
-    old: Option<ChunkPosition>,
-    new: ChunkPosition,
-    view_distance: u8,
-) -> impl Iterator<Item = ChunkPosition> {
-    if let Some(old) = old {
-        Either::Left(
-            chunks_within_view_distance(old, view_distance).filter(move |chunk| {
-                (chunk.x - new.x).abs() >= view_distance as i32
-                    || (chunk.z - new.z).abs() >= view_distance as i32
-            }),
-        )
-    } else {
-        Either::Right(iter::empty())
-    }
+/// Returns chunks visible in the new view, but not in the old one.
+fn find_new_chunks(old: Option<View>, new: View) -> impl Iterator<Item = ChunkPosition> {
+    new.chunks()
+        .filter(move |&chunk| old.map_or(true, |old| !old.contains(chunk)))
 }
 
-/// Finds all chunks within the view distance of a given chunk.
-fn chunks_within_view_distance(
-    chunk: ChunkPosition,
-    view_distance: u8,
-) -> impl Iterator<Item = ChunkPosition> {
-    let view_distance = i32::from(view_distance);
-
-    (-view_distance..=view_distance).flat_map(move |x| {
-        (-view_distance..=view_distance).map(move |z| chunk.add(ChunkPosition::new(x, z)))
-    })
+/// Returns chunks visible in the old view, but not in the new one.
+fn find_old_chunks(old: Option<View>, new: View) -> impl Iterator<Item = ChunkPosition> {
+    old.into_iter()
+        .flat_map(View::chunks)
+        .filter(move |&chunk| !new.contains(chunk))
 }
 
 /// Resource containing a mapping from chunks -> sets of players indicating
 /// which chunks are pending to send to a given player.
 #[derive(Default)]
-pub struct ChunksToSend(AHashMap<ChunkPosition, SmallVec<[Entity; 2]>>);
+pub struct ChunksToSend(AHashMap<(WorldId, ChunkPosition), SmallVec<[Entity; 2]>>);
 
 /// Asynchronously sends a chunk to a player.
 fn send_chunk_to_player(
@@ -259,6 +267,7 @@ fn send_chunk_to_player(
     world: &mut World,
     chunks_to_send: &mut ChunksToSend,
     player: Entity,
+    world_id: WorldId,
     chunk_pos: ChunkPosition,
 ) {
     if !world.is_alive(player) {
@@ -270,36 +279,45 @@ fn send_chunk_to_player(
         world,
         HoldChunkRequest {
             player,
+            world: world_id,
             chunk: chunk_pos,
         },
     );
 
     // If the chunk is already loaded, send it. Otherwise, we need to
     // queue it for loading.
-    if let Some(chunk) = game.chunk_map.chunk_handle_at(chunk_pos) {
-        world.get::<Network>(player).send(create_chunk_data(chunk));
+    if let Some(chunk) = game.worlds[world_id].chunk_map.chunk_handle_at(chunk_pos) {
+        send_chunk(&world.get::<Network>(player), chunk);
         game.handle(
             world,
             ChunkSendEvent {
                 player,
+                world: world_id,
                 chunk: chunk_pos,
             },
         );
     } else {
-        let contains = chunks_to_send.0.contains_key(&chunk_pos);
+        let key = (world_id, chunk_pos);
+        let contains = chunks_to_send.0.contains_key(&key);
 
-        let vec = match chunks_to_send.0.get_mut(&chunk_pos) {
+        let vec = match chunks_to_send.0.get_mut(&key) {
             Some(vec) => vec,
             None => {
-                chunks_to_send.0.insert(chunk_pos, SmallVec::new());
-                chunks_to_send.0.get_mut(&chunk_pos).unwrap()
+                chunks_to_send.0.insert(key, SmallVec::new());
+                chunks_to_send.0.get_mut(&key).unwrap()
             }
         };
         vec.push(player);
 
         if !contains {
             // Queue chunk for loading if it isn't already.
-            game.handle(world, LoadChunkRequest { chunk: chunk_pos });
+            game.handle(
+                world,
+                LoadChunkRequest {
+                    world: world_id,
+                    chunk: chunk_pos,
+                },
+            );
         }
     }
 }
@@ -308,11 +326,19 @@ fn send_chunk_to_player(
 fn unload_chunk_for_player(
     game: &mut Game,
     world: &mut World,
+    world_id: WorldId,
     chunk: ChunkPosition,
     player: Entity,
 ) {
     // Release hold on chunk so it can be unloaded on the server
-    game.handle(world, ReleaseChunkRequest { player, chunk });
+    game.handle(
+        world,
+        ReleaseChunkRequest {
+            player,
+            world: world_id,
+            chunk,
+        },
+    );
 
     // Send Unload Chunk packet.
     world.get::<Network>(player).send(UnloadChunk {
@@ -329,22 +355,23 @@ pub fn on_chunk_load_send_to_clients(
     world: &mut World,
     chunks_to_send: &mut ChunksToSend,
 ) {
-    if let Some(players) = chunks_to_send.0.get(&event.chunk) {
-        let chunk = game
+    let key = (event.world, event.chunk);
+    if let Some(players) = chunks_to_send.0.get(&key) {
+        let chunk = game.worlds[event.world]
             .chunk_map
             .chunk_handle_at(event.chunk)
             .expect("chunk not loaded, but load event was triggered");
         for player in players {
-            if !world.is_alive(*player) {
+            // The player may have changed worlds while the chunk was loading.
+            if !world.is_alive(*player) || game.world_of(world, *player) != event.world {
                 continue;
             }
 
-            world
-                .get::<Network>(*player)
-                .send(create_chunk_data(Arc::clone(&chunk)));
+            send_chunk(&world.get::<Network>(*player), Arc::clone(&chunk));
             game.handle(
                 world,
                 ChunkSendEvent {
+                    world: event.world,
                     chunk: event.chunk,
                     player: *player,
                 },
@@ -352,10 +379,203 @@ pub fn on_chunk_load_send_to_clients(
         }
     }
 
-    chunks_to_send.0.remove(&event.chunk);
+    chunks_to_send.0.remove(&key);
 }
 
-/// Creates a chunk data packet for the given chunk.
-fn create_chunk_data(chunk: Arc<RwLock<Chunk>>) -> ChunkData {
-    ChunkData { chunk }
+/// When an entity changes worlds, removes it from the view of
+/// players in its old world and shows it to players in the new one.
+///
+/// Players which change worlds additionally release the chunks
+/// of their old world and respawn on the client in the new one.
+#[fecs::event_handler]
+pub fn on_world_change_update_view(event: &WorldChangeEvent, game: &mut Game, world: &mut World) {
+    let entity = event.entity;
+
+    // Despawn the entity for players in the old world.
+    let mut to_client_remove_trigger = vec![];
+    let network_id = world.get::<NetworkId>(entity).0;
+    for other in game.worlds[event.old]
+        .chunk_holders
+        .holders_for(event.old_position.chunk())
+        .iter()
+        .copied()
+        .filter(|other| *other != entity)
+    {
+        if let Some(network) = world.try_get::<Network>(other) {
+            network.send(DestroyEntities {
+                entity_ids: vec![network_id],
+            });
+            to_client_remove_trigger.push((entity, other));
+        }
+    }
+
+    let old_view = world.try_get::<View>(entity).map(|view| *view);
+    if let Some(old_view) = old_view {
+        // The client forgets all chunks and entities when
+        // it respawns, so only the server's state is updated here.
+        to_client_remove_trigger.extend(
+            old_view
+                .chunks()
+                .flat_map(|chunk| {
+                    game.worlds[event.old]
+                        .chunk_entities
+                        .entities_in_chunk(chunk)
+                })
+                .filter(|other| **other != entity)
+                .map(|other| (*other, entity)),
+        );
+
+        let holds = world
+            .get::<ChunkHolder>(entity)
+            .holds
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        for chunk in holds {
+            game.handle(
+                world,
+                ReleaseChunkRequest {
+                    player: entity,
+                    world: event.old,
+                    chunk,
+                },
+            );
+        }
+        game.handle(
+            world,
+            RemoveTicketRequest {
+                world: event.old,
+                chunk: old_view.center,
+                kind: TicketKind::Player(entity),
+            },
+        );
+    }
+
+    for (other, client) in to_client_remove_trigger {
+        game.handle(
+            world,
+            EntityClientRemoveEvent {
+                entity: other,
+                client,
+            },
+        );
+    }
+
+    match old_view {
+        Some(old_view) => {
+            send_respawn(game, world, event.old, event.new, entity);
+
+            let new = View {
+                center: world.get::<Position>(entity).chunk(),
+                ..old_view
+            };
+            *world.get_mut::<View>(entity) = new;
+            game.handle(
+                world,
+                ViewUpdateEvent {
+                    player: entity,
+                    old: None,
+                    new,
+                },
+            );
+
+            let _ = world.add(entity, Teleported);
+        }
+        None => spawn_for_players(game, world, entity),
+    }
+}
+
+/// Sends a Respawn packet to move a client into the dimension of its new world.
+fn send_respawn(game: &Game, world: &World, old: WorldId, new: WorldId, player: Entity) {
+    let network = world.get::<Network>(player);
+    let gamemode = world.get::<Gamemode>(player).id() as u8;
+    let respawn = |dimension: Dimension| Respawn {
+        dimension: dimension.id(),
+        difficulty: 1,
+        gamemode,
+        level_type: game.level.generator_name.clone(),
+        dimension_type: game.dimension_type_nbt(dimension),
+        world_name: dimension.type_id().to_owned(),
+        is_flat: game.level.generator_type() == LevelGeneratorType::Flat,
+    };
+
+    let dimension = game.worlds[new].dimension;
+    if game.worlds[old].dimension == dimension {
+        // The client ignores a Respawn into its current dimension,
+        // so it has to be moved into another one first.
+        let other = match dimension {
+            Dimension::Overwold => Dimension::Nether,
+            _ => Dimension::Overwold,
+        };
+        network.send(respawn(other));
+    }
+    network.send(respawn(dimension));
+}
+
+/// Sends the spawn packet of an entity to the players
+/// which can see it.
+fn spawn_for_players(game: &mut Game, world: &mut World, entity: Entity) {
+    let packet = match world.try_get::<SpawnPacketCreator>(entity) {
+        Some(creator) => creator.get(&world.entity(entity).expect("entity does not exist")),
+        None => return,
+    };
+    if let Some(info) = hidden_player_info(world, entity) {
+        game.broadcast_entity_update(world, info, entity, Some(entity));
+    }
+    game.broadcast_entity_update_boxed(world, packet, entity, Some(entity));
+
+    let chunk = world.get::<Position>(entity).chunk();
+    let world_id = game.world_of(world, entity);
+    let clients = game.worlds[world_id]
+        .chunk_holders
+        .holders_for(chunk)
+        .iter()
+        .copied()
+        .filter(|client| world.has::<Network>(*client))
+        .collect::<Vec<_>>();
+    for client in clients {
+        game.handle(world, EntitySendEvent { entity, client });
+    }
+}
+
+/// Sends a chunk to a client. Clients since 1.14
+/// expect its light in a separate packet first.
+fn send_chunk(network: &Network, chunk: Arc<RwLock<Chunk>>) {
+    network.send(UpdateLight {
+        chunk: Arc::clone(&chunk),
+    });
+    network.send(ChunkData {
+        chunk,
+        section_mask: None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_changes() {
+        let old = View {
+            center: ChunkPosition::new(0, 0),
+            distance: 2,
+        };
+        assert_eq!(find_new_chunks(None, old).count(), 25);
+        assert_eq!(find_old_chunks(None, old).count(), 0);
+
+        let moved = View {
+            center: ChunkPosition::new(1, 0),
+            ..old
+        };
+        let new_chunks = find_new_chunks(Some(old), moved).collect::<Vec<_>>();
+        assert_eq!(new_chunks.len(), 5);
+        assert!(new_chunks.iter().all(|chunk| chunk.x == 3));
+        let old_chunks = find_old_chunks(Some(old), moved).collect::<Vec<_>>();
+        assert_eq!(old_chunks.len(), 5);
+        assert!(old_chunks.iter().all(|chunk| chunk.x == -2));
+
+        let shrunk = View { distance: 1, ..old };
+        assert_eq!(find_new_chunks(Some(old), shrunk).count(), 0);
+        assert_eq!(find_old_chunks(Some(old), shrunk).count(), 16);
+    }
 }
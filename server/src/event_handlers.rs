@@ -22,17 +22,44 @@ pub fn build_event_handlers() -> EventHandlers {
     event_handlers! {
         on_block_update_notify_adjacent,
         on_block_break_broadcast_effect,
-        on_block_update_broadcast,
         on_block_update_notify_lighting_worker,
         on_block_break_drop_loot,
-        on_chest_break_drop_contents,
+        on_block_break_drop_contents,
+        on_jukebox_break_eject_record,
         on_block_update_create_block_entity,
+        on_sign_place_open_editor,
+        on_block_update_update_poi,
         on_chest_create_try_connect,
         on_chest_break_try_disconnect,
-
+        on_block_update_schedule_leaves,
+        on_block_update_update_redstone,
+        on_block_update_update_note_blocks,
+        on_block_update_update_fluids,
+        on_block_update_update_fire,
+        on_block_update_update_farmland,
+        on_block_update_break_portals,
+        on_block_update_remove_leash_knots,
+        on_block_update_break_hanging_entities,
+        on_bed_break_wake_up,
+
+        on_block_tick_grow_crops,
+        on_block_tick_grow_cane_and_cactus,
+        on_block_tick_hydrate_farmland,
+        on_block_tick_melt_ice,
+        on_block_tick_decay_leaves,
+        on_block_tick_update_redstone,
+        on_block_tick_move_pistons,
+        on_block_tick_flow_fluids,
+        on_block_tick_burn_fire,
+        on_block_tick_ignite_near_lava,
+
+        on_entity_despawn_mark_unsaved,
         on_entity_despawn_remove_chunk_holder,
         on_entity_despawn_update_chunk_entities,
         on_entity_despawn_broadcast_despawn,
+        on_entity_despawn_remove_team,
+        on_entity_despawn_cancel_travel,
+        on_entity_despawn_dismount,
 
         on_block_entity_create_insert_to_map,
         on_entity_spawn_update_chunk_entities,
@@ -41,35 +68,71 @@ pub fn build_event_handlers() -> EventHandlers {
         on_entity_send_update_last_known_positions,
         on_entity_send_send_equipment,
         on_entity_send_send_metadata,
+        on_entity_send_remove_hidden_player,
+        on_entity_send_send_leashes,
+        on_entity_send_send_framed_map,
+        on_entity_send_send_passengers,
 
         on_entity_client_remove_update_last_known_positions,
 
         on_player_join_send_join_packets,
+        on_player_join_send_advancements,
+        on_player_join_send_plugin_channels,
         on_player_join_send_existing_entities,
+        on_player_join_send_tab_list,
+        on_player_join_send_scoreboard,
+        on_player_join_send_resource_pack,
         on_player_join_send_time,
+        on_player_join_send_border,
         on_player_join_trigger_chunk_cross,
         on_player_join_send_weather,
         on_player_join_broadcast_join_message,
 
+        on_player_leave_wake_up,
         on_player_leave_save_data,
+        on_player_leave_remove_generation_focus,
+        on_player_leave_remove_ticket,
 
         on_chunk_load_notify_lighting_worker,
         on_chunk_load_send_to_clients,
         on_chunk_load_queue_for_saving,
 
-        on_chunk_holder_release_unload_chunk,
+        on_chunk_unload_save_chunk,
+        on_chunk_unload_despawn_block_entities,
+        on_chunk_unload_despawn_entities,
+        on_chunk_unload_forget_dirty,
+        on_chunk_unload_remove_block_ticks,
 
-        on_chunk_cross_mark_modified,
-        on_chunk_cross_update_chunks,
+        on_chunk_cross_mark_unsaved,
+        on_chunk_cross_update_generation_focus,
+        on_chunk_cross_update_view,
         on_chunk_cross_update_chunk_entities,
-        on_chunk_cross_update_entities,
+
+        on_world_create_add_to_chunk_worker,
+
+        on_world_change_update_chunk_entities,
+        on_world_change_update_generation_focus,
+        on_world_change_update_view,
+        on_world_change_send_time,
+        on_world_change_send_weather,
+        on_world_change_send_border,
+
+        on_view_update_move_ticket,
+        on_view_update_update_chunks,
+        on_view_update_update_entities,
 
         on_chunk_send_join_player,
 
         on_damage_item,
 
+        on_inventory_update_update_crafting_output,
+        on_inventory_update_update_anvil_output,
+        on_inventory_update_update_merchant_output,
         on_inventory_update_send_set_slot,
         on_inventory_update_broadcast_equipment_update,
+        on_inventory_update_update_brewing_stand_bottles,
+        on_inventory_update_update_comparators,
+        on_item_frame_update_update_comparators,
 
         on_player_animation_broadcast_animation,
 
@@ -77,14 +140,39 @@ pub fn build_event_handlers() -> EventHandlers {
 
         on_item_collect_broadcast,
 
-        on_weather_change_broadcast_weather,
+        on_item_use_on_block_apply_bone_meal,
+        on_item_use_on_block_light_fire,
+        on_item_use_on_block_insert_eye,
+        on_item_use_on_block_place_end_crystal,
+        on_item_use_on_block_place_hanging,
+        on_item_use_on_block_place_armor_stand,
+        on_item_use_on_block_place_minecart,
+
+        on_entity_interact_apply_name_tag,
+        on_entity_interact_ride_mob,
+        on_entity_interact_leash,
+        creeper::on_entity_interact_ignite_creeper,
+        on_entity_interact_use_item_frame,
+        on_entity_interact_mount_vehicle,
+        on_entity_interact_open_minecart,
 
         on_chat_broadcast,
 
         on_entity_land_remove_falling_block,
 
+        villager::on_entity_death_release_job_site,
+        on_entity_death_wake_up,
+        on_entity_death_drop_lead,
+        on_entity_death_dismount,
+        on_entity_death_drop_saddle,
+        on_entity_death_clear_status_effects,
+
         load_chunk_request,
 
+        add_ticket_request,
+
+        remove_ticket_request,
+
         release_chunk_request,
 
         hold_chunk_request,
@@ -95,14 +183,30 @@ pub fn build_event_handlers() -> EventHandlers {
 
         on_gamemode_update_update_capabilities,
         on_gamemode_update_send,
+        on_gamemode_update_broadcast_tab_list,
+
+        on_resource_pack_status_enforce,
+
+        on_recipe_unlock_grant_advancements,
 
         on_health_update_send,
+        on_health_update_exhaust,
+        on_health_update_broadcast_hurt,
+
+        on_experience_update_send,
 
         on_player_death_scatter_inventory,
+        on_player_death_drop_experience,
         on_player_death_mark_dead,
+        on_player_death_send_message,
+
+        on_container_open_increment_viewers,
 
-        on_chest_open_increment_viewers,
+        on_container_close_decrement_viewers,
 
-        on_chest_close_decrement_viewers,
+        on_window_close_remove_viewer,
+        on_window_close_return_anvil_items,
+        on_window_close_return_beacon_payment,
+        on_window_close_stop_trading,
     }
 }
@@ -3,16 +3,23 @@
 use crate::{event_handlers, systems};
 use anyhow::Context;
 use feather_core::anvil::level::{LevelData, LevelGeneratorType};
-use feather_core::util::ChunkPosition;
-use feather_server_chunk::{chunk_worker, ChunkWorkerHandle};
+use feather_core::biomes::BiomeRegistry;
+use feather_core::dimension::{self, DimensionTypes};
+use feather_core::game_rules::GameRules;
+use feather_core::util::{ChunkPosition, Dimension};
+use feather_server_chunk::{chunk_worker, ChunkWorkerHandle, WorldStorage};
 use feather_server_config::DEFAULT_CONFIG_STR;
-use feather_server_network::NetworkIoManager;
+use feather_server_network::{NetworkIoManager, RconHandle};
 use feather_server_packet_buffer::PacketBuffers;
-use feather_server_types::{task, BanInfo, Config, Game, Shared, ShutdownChannels};
+use feather_server_types::{
+    task, AdvancementRegistry, BanInfo, Config, Game, RecipeRegistry, Shared, ShutdownChannels,
+    Ticket, TicketKind, Time, WeatherStrength, WorldBorder, WorldId, Worlds,
+};
 use feather_server_worldgen::{
-    ComposableGenerator, EmptyWorldGenerator, SuperflatWorldGenerator, WorldGenerator,
+    default_generator_for, ComposableGenerator, EmptyWorldGenerator, SuperflatWorldGenerator,
+    WorldGenSettings, WorldGenerator,
 };
-use fecs::{EntityBuilder, Executor, OwnedResources, ResourcesProvider, World};
+use fecs::{Executor, OwnedResources, ResourcesProvider, World};
 use fxhash::FxHasher;
 use rand::Rng;
 use std::hash::{Hash, Hasher};
@@ -20,7 +27,7 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, UdpSocket};
 use tokio::{io, runtime};
 
 /// Intializes the server.
@@ -51,31 +58,44 @@ pub async fn init(
         .await
         .context("Failed to load level file (is your world directory corrupted?)")?;
 
-    let cworker_handle = create_cworker_handle(&config, &level);
+    let dimension_types = load_dimension_types(&config)?;
+    load_biomes(&config)?;
+    let advancements = load_advancements()?;
+    let recipes = load_recipes()?;
+
+    let storage = WorldStorage::from_config(&config.world)
+        .context("Invalid world storage (please check your config file)")?;
+
+    let worlds = create_worlds(&config, &level);
+    let game_rules = load_game_rules(&level);
+    let cworker_handle = create_cworker_handle(&worlds, storage);
 
     let mut game = Game {
         shared: Arc::new(Shared {
             config: Arc::clone(&config),
             rng: Default::default(),
-            player_count: Arc::new(Default::default()),
+            online_players: Arc::new(Default::default()),
         }),
-        chunk_map: Default::default(),
+        worlds,
         tick_count: 0,
-        chunk_holders: Default::default(),
-        block_entities: Default::default(),
+        weather_strength: WeatherStrength::of(&level),
         level,
-        chunk_entities: Default::default(),
-        time: Default::default(),
         event_handlers: Arc::new(event_handlers),
         resources: Arc::new(Default::default()), // we override this momentarily
         bump: Default::default(),
-        game_rules: Default::default(),
+        game_rules,
+        dimension_types,
+        scoreboard: Default::default(),
+        plugin_channels: Default::default(),
+        maps: Default::default(),
+        advancements,
+        recipes,
     };
     task::init(runtime);
     let packet_buffers = Arc::new(PacketBuffers::new());
 
     log::info!("Queueing spawn chunks for loading");
-    load_spawn_chunks(&mut game, &mut world, &cworker_handle);
+    load_spawn_chunks(&mut game, &cworker_handle);
 
     log::info!("Creating RSA keypair");
     feather_server_network::init();
@@ -92,12 +112,19 @@ pub async fn init(
     )
     .await
     .context("Failed to start the networking task")?;
+    let rcon_handle = create_rcon_handle(&config)
+        .await
+        .context("Failed to start RCON")?;
+    start_query(&config, &game)
+        .await
+        .context("Failed to start the query listener")?;
 
     let resources = create_resources(
         resources,
         game,
         cworker_handle,
         networking_handle,
+        rcon_handle,
         packet_buffers,
         ban_info,
     );
@@ -185,36 +212,23 @@ fn generate_level(config: &Config) -> LevelData {
 
     // TODO: Generate spawn position properly
     LevelData {
-        allow_commands: false,
-        border_center_x: 0.0,
-        border_center_z: 0.0,
-        border_damage_per_block: 0.0,
-        border_safe_zone: 0.0,
-        border_size: 0.0,
-        clear_weather_time: 0,
-        data_version: 0,
-        day_time: 0,
-        difficulty: 0,
-        difficulty_locked: 0,
-        game_type: 0,
-        hardcore: false,
-        initialized: false,
-        last_played: 0,
-        raining: false,
-        rain_time: 0,
-        seed,
-        spawn_x: 0,
         spawn_y: 100,
-        spawn_z: 0,
-        thundering: false,
-        thunder_time: 0,
-        time: 0,
-        version: Default::default(),
-        generator_name: config.world.generator.to_string(),
-        generator_options: None,
+        ..LevelData::new(world_name, seed, &config.world.generator)
     }
 }
 
+/// Reads the game rules stored in the level,
+/// ignoring any unknown or invalid ones.
+fn load_game_rules(level: &LevelData) -> GameRules {
+    let mut game_rules = GameRules::default();
+    for (name, value) in &level.game_rules {
+        if !game_rules.set(name, value) {
+            log::warn!("Ignoring unknown or invalid game rule {}={}", name, value);
+        }
+    }
+    game_rules
+}
+
 fn seed_for_config(config: &Config) -> i64 {
     let seed_raw = &config.world.seed;
     // Empty seed: random
@@ -238,22 +252,111 @@ fn hash_seed(seed_raw: &str) -> i64 {
     hasher.finish() as i64
 }
 
-fn create_cworker_handle(config: &Config, level: &LevelData) -> ChunkWorkerHandle {
-    let generator: Arc<dyn WorldGenerator> = match level.generator_type() {
+fn load_dimension_types(config: &Config) -> anyhow::Result<DimensionTypes> {
+    let mut dimension_types = DimensionTypes::vanilla();
+    dimension_types
+        .load_datapacks(&Path::new(&config.world.name).join("datapacks"))
+        .context("Failed to load dimension types from data packs")?;
+    log::debug!("Loaded {} dimension types", dimension_types.len());
+    Ok(dimension_types)
+}
+
+fn load_advancements() -> anyhow::Result<AdvancementRegistry> {
+    let advancements = AdvancementRegistry::vanilla().context("Failed to load advancements")?;
+    log::debug!("Loaded {} advancements", advancements.len());
+    Ok(advancements)
+}
+
+fn load_recipes() -> anyhow::Result<RecipeRegistry> {
+    let recipes = RecipeRegistry::vanilla().context("Failed to load recipes")?;
+    log::debug!("Loaded {} recipes", recipes.len());
+    Ok(recipes)
+}
+
+/// Registers custom biomes from data packs. This must
+/// happen before any chunks are loaded or generated.
+fn load_biomes(config: &Config) -> anyhow::Result<()> {
+    let mut registry = BiomeRegistry::new();
+    dimension::load_biome_datapacks(
+        &mut registry,
+        &Path::new(&config.world.name).join("datapacks"),
+    )
+    .context("Failed to load biomes from data packs")?;
+    log::debug!("Loaded {} custom biomes", registry.custom_biomes().count());
+
+    if registry.install().is_err() {
+        anyhow::bail!("Biome registry was installed twice");
+    }
+    Ok(())
+}
+
+fn create_world_generator(level: &LevelData) -> Arc<dyn WorldGenerator> {
+    match level.generator_type() {
         LevelGeneratorType::Flat => Arc::new(SuperflatWorldGenerator {
             options: level.clone().generator_options.unwrap_or_default(),
         }),
         LevelGeneratorType::Default => {
-            Arc::new(ComposableGenerator::default_with_seed(level.seed as u64))
+            default_generator_for(Dimension::Overwold, level.seed as u64)
         }
+        LevelGeneratorType::Amplified => Arc::new(ComposableGenerator::default_with_settings(
+            level.seed as u64,
+            WorldGenSettings::amplified(),
+        )),
+        LevelGeneratorType::LargeBiomes => Arc::new(ComposableGenerator::default_with_settings(
+            level.seed as u64,
+            WorldGenSettings::large_biomes(),
+        )),
         _ => Arc::new(EmptyWorldGenerator {}),
+    }
+}
+
+/// Creates the main world from the level file, along
+/// with the Nether and the End, which are stored in the
+/// `DIM-1` and `DIM1` directories of the main world.
+fn create_worlds(config: &Config, level: &LevelData) -> Worlds {
+    let name = &config.world.name;
+    let dir = Path::new(name);
+    let seed = level.seed as u64;
+
+    let mut worlds = Worlds::new();
+    let main = worlds.add(
+        name.as_str(),
+        Dimension::Overwold,
+        dir,
+        create_world_generator(level),
+    );
+    worlds[main].time = Time {
+        age: level.time.max(0) as u64,
+        day_time: level.day_time.max(0) as u64,
     };
+    worlds[main].border = WorldBorder::from_level(level);
+
+    worlds.add(
+        format!("{}_nether", name),
+        Dimension::Nether,
+        dir.join("DIM-1"),
+        default_generator_for(Dimension::Nether, seed),
+    );
+    worlds.add(
+        format!("{}_the_end", name),
+        Dimension::End,
+        dir.join("DIM1"),
+        default_generator_for(Dimension::End, seed),
+    );
 
-    let (tx, rx) = chunk_worker::start(Path::new(&config.world.name), generator);
-    ChunkWorkerHandle {
+    worlds
+}
+
+fn create_cworker_handle(worlds: &Worlds, storage: WorldStorage) -> ChunkWorkerHandle {
+    let (tx, rx) = chunk_worker::start();
+    let handle = ChunkWorkerHandle {
         sender: tx,
         receiver: rx,
+    };
+    for state in worlds.iter() {
+        feather_server_chunk::add_world(&handle, state, storage);
     }
+    handle
 }
 
 async fn create_networking_handle(
@@ -266,28 +369,79 @@ async fn create_networking_handle(
         .await
         .context("failed to load server icon `server-icon.png` (is it corrupted?)")?;
 
-    let addr = format!("{}:{}", config.server.address, config.server.port);
-    let socket = TcpListener::bind(&addr)
-        .await
-        .context("failed to bind to port (is another server instance already running?)")?;
-
-    log::info!("Listening on {}", addr);
+    let mut listeners = vec![];
+    for listener in config.listeners() {
+        let socket = TcpListener::bind((listener.address.as_str(), listener.port))
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to bind to {} port {} (is another server instance already running?)",
+                    listener.address, listener.port
+                )
+            })?;
+
+        log::info!("Listening on {}", socket.local_addr()?);
+        listeners.push((socket, Arc::new(config.for_listener(&listener))));
+    }
 
     Ok(NetworkIoManager::start(
-        socket,
-        config,
+        listeners,
         ban_info,
-        Arc::clone(&game.player_count),
+        Arc::clone(&game.online_players),
         Arc::new(server_icon),
         packet_buffers,
     ))
 }
 
+/// Starts listening for RCON connections if RCON is enabled.
+async fn create_rcon_handle(config: &Config) -> anyhow::Result<RconHandle> {
+    let rcon = &config.rcon;
+    if !rcon.enabled {
+        return Ok(RconHandle::disabled());
+    }
+    if rcon.password.is_empty() {
+        log::warn!("Not starting RCON because no password is set (please check your config file)");
+        return Ok(RconHandle::disabled());
+    }
+
+    let addr = format!("{}:{}", config.server.address, rcon.port);
+    let socket = TcpListener::bind(&addr)
+        .await
+        .context("failed to bind to the RCON port")?;
+
+    log::info!("RCON listening on {}", addr);
+
+    Ok(RconHandle::start(socket, rcon.password.clone()))
+}
+
+/// Starts answering queries if the query protocol is enabled.
+async fn start_query(config: &Arc<Config>, game: &Game) -> anyhow::Result<()> {
+    if !config.query.enabled {
+        return Ok(());
+    }
+
+    let addr = format!("{}:{}", config.server.address, config.query.port);
+    let socket = UdpSocket::bind(&addr)
+        .await
+        .context("failed to bind to the query port")?;
+
+    log::info!("Answering queries on {}", addr);
+
+    tokio::spawn(feather_server_network::run_query(
+        socket,
+        Arc::clone(config),
+        Arc::clone(&game.online_players),
+    ));
+    Ok(())
+}
+
+/// Loads the server icon shown in the server list, if any.
 async fn load_server_icon() -> anyhow::Result<Option<String>> {
     match File::open("server-icon.png").await {
         Ok(mut file) => {
             let mut buf = vec![];
             file.read_to_end(&mut buf).await?;
+            check_server_icon(&buf)?;
 
             let encoded = base64::encode(&buf);
             Ok(Some(format!("data:image/png;base64,{}", encoded)))
@@ -297,28 +451,34 @@ async fn load_server_icon() -> anyhow::Result<Option<String>> {
     }
 }
 
-/// Loads the chunks around the spawn area and creates
-/// a chunk hold on those chunks to prevent them from
-/// being unloaded.
+/// Checks that a server icon is a 64x64 PNG image,
+/// as clients don't display it otherwise.
+fn check_server_icon(png: &[u8]) -> anyhow::Result<()> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    // The image header chunk, which holds the size, comes first.
+    if png.len() < 24 || !png.starts_with(SIGNATURE) || &png[12..16] != b"IHDR" {
+        anyhow::bail!("the server icon is not a PNG image");
+    }
+
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+    if (width, height) != (64, 64) {
+        anyhow::bail!("the server icon is {}x{}, but must be 64x64", width, height);
+    }
+    Ok(())
+}
+
+/// Loads the chunks around the spawn area and adds
+/// a spawn ticket to prevent them from being unloaded.
 ///
 /// Note that these chunks are loaded asynchronously,
 /// and this function will return before loading is complete.
-fn load_spawn_chunks(game: &mut Game, world: &mut World, cworker_handle: &ChunkWorkerHandle) {
-    let view_distance = i32::from(game.config.server.view_distance);
+fn load_spawn_chunks(game: &mut Game, cworker_handle: &ChunkWorkerHandle) {
+    let spawn = ChunkPosition::new(game.level.spawn_x >> 4, game.level.spawn_z >> 4);
+    let ticket = Ticket::with_radius(TicketKind::Spawn, game.config.server.view_distance);
 
-    // Create an entity for the server and
-    // add chunk holders using it.
-    let server_entity = EntityBuilder::new().build().spawn_in(world);
-
-    let offset_x = game.level.spawn_x / 16;
-    let offset_z = game.level.spawn_z / 16;
-    for x in -view_distance..=view_distance {
-        for z in -view_distance..=view_distance {
-            let chunk = ChunkPosition::new(x + offset_x, z + offset_z);
-
-            feather_server_chunk::load_chunk(cworker_handle, chunk);
-            game.chunk_holders.insert_holder(chunk, server_entity);
-        }
+    for chunk in game.worlds[WorldId::MAIN].chunk_tickets.add(spawn, ticket) {
+        feather_server_chunk::load_chunk(cworker_handle, WorldId::MAIN, chunk);
     }
 }
 
@@ -327,6 +487,7 @@ fn create_resources(
     game: Game,
     cworker_handle: ChunkWorkerHandle,
     networking_handle: NetworkIoManager,
+    rcon_handle: RconHandle,
     packet_buffers: Arc<PacketBuffers>,
     ban_info: Arc<RwLock<BanInfo>>,
 ) -> Arc<OwnedResources> {
@@ -335,6 +496,7 @@ fn create_resources(
             .with(game)
             .with(cworker_handle)
             .with(networking_handle)
+            .with(rcon_handle)
             .with(packet_buffers)
             .with(ban_info)
             .with(ShutdownChannels::new());
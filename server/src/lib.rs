@@ -127,7 +127,7 @@ async fn shut_down(resources: &OwnedResources, world: &mut World) -> anyhow::Res
     )?;
     log::info!("Saving chunks");
     shutdown::save_chunks(
-        &*resources.get::<Game>(),
+        &mut *resources.get_mut::<Game>(),
         &*resources.get::<ChunkWorkerHandle>(),
         &world,
     )?;
@@ -5,7 +5,7 @@ use feather_core::text::{TextRoot, TextValue};
 use feather_server_chunk::chunk_worker::Request;
 use feather_server_chunk::{save_chunk_at, ChunkWorkerHandle};
 use feather_server_lighting::LightingWorkerHandle;
-use feather_server_types::{tasks, BanInfo, Game, Network, Player};
+use feather_server_types::{tasks, BanInfo, Game, Network, Player, WorldId};
 use fecs::{IntoQuery, Read, World};
 use std::sync::{Arc, RwLock};
 use tokio::fs::File;
@@ -31,13 +31,22 @@ pub fn disconnect_players(world: &World) -> anyhow::Result<()> {
 }
 
 pub fn save_chunks(
-    game: &Game,
+    game: &mut Game,
     cworker_handle: &ChunkWorkerHandle,
     world: &World,
 ) -> anyhow::Result<()> {
-    for chunk in game.chunk_map.iter_chunks() {
-        let pos = chunk.read().position();
-        save_chunk_at(game, world, pos, cworker_handle);
+    let chunks: Vec<_> = game
+        .worlds
+        .iter()
+        .flat_map(|state| {
+            state
+                .chunk_map
+                .iter_chunks()
+                .map(move |chunk| (state.id, chunk.read().position()))
+        })
+        .collect();
+    for (world_id, pos) in chunks {
+        save_chunk_at(game, world, world_id, pos, cworker_handle);
     }
 
     // Wait for chunk worker to shut down
@@ -49,9 +58,17 @@ pub fn save_chunks(
 }
 
 pub async fn save_level(game: &mut Game) -> anyhow::Result<()> {
-    // Sync world time + level time
-    let time = game.time.world_age() as i64;
-    game.level.time = time;
+    // Sync the main world's time, its border and
+    // the game rules into the level data.
+    let time = game.worlds[WorldId::MAIN].time;
+    game.level.day_time = time.day_time as i64;
+    game.level.time = time.world_age() as i64;
+    game.worlds[WorldId::MAIN]
+        .border
+        .write_to_level(&mut game.level);
+    for (name, value) in game.game_rules.to_strings() {
+        game.level.game_rules.insert(name.to_owned(), value);
+    }
 
     let level_path = format!("{}/{}", game.config.world.name, "level.dat");
 
@@ -2,6 +2,7 @@
 
 use fecs::Executor;
 
+use feather_server_block as block;
 use feather_server_chunk as chunk_logic;
 use feather_server_entity as entity;
 use feather_server_physics as physics;
@@ -16,36 +17,99 @@ pub fn build_executor() -> Executor {
         .with(player::poll_new_clients)
         .with(physics::entity_physics)
         .with(player::handle_movement_packets)
+        .with(player::handle_steer_vehicle)
+        .with(player::handle_vehicle_move)
+        .with(player::handle_steer_boat)
         .with(player::handle_close_window)
+        .with(player::handle_name_item)
+        .with(player::handle_select_trade)
         .with(player::handle_creative_inventory_action)
         .with(player::handle_click_windows)
         .with(player::handle_held_item_change)
         .with(player::handle_animation)
+        .with(player::handle_entity_action)
         .with(player::handle_player_block_placement)
+        .with(block::sign::handle_sign_updates)
+        .with(block::beacon::handle_set_beacon_effect)
         .with(player::handle_player_use_item)
+        .with(player::handle_use_entity)
         .with(player::handle_player_digging)
         .with(player::advance_dig_progress)
+        .with(player::finish_eating)
         .with(player::broadcast_block_break_animation)
+        .with(player::handle_client_settings)
         .with(player::handle_client_status)
+        .with(player::handle_keepalives)
         .with(player::handle_chat)
+        .with(player::handle_tab_complete)
+        .with(player::handle_plugin_messages)
+        .with(player::handle_resource_pack_status)
+        .with(player::handle_advancement_tabs)
+        .with(player::handle_rcon_commands)
         .with(player::flush_player_message_receiver)
         .with(game::task::run_sync_tasks)
         .with(player::send_teleported)
         .with(weather::update_weather)
         .with(entity::item::item_collect)
+        .with(entity::experience_orb::experience_orb_collect)
         .with(chunk_logic::handle_chunk_worker_replies)
         .with(chunk_logic::chunk_unload)
         .with(chunk_logic::chunk_optimize)
+        .with(chunk_logic::pregenerate)
         .with(player::check_crossed_chunks)
-        .with(player::broadcast_keepalive)
+        .with(player::send_keepalives)
+        .with(player::time_out_players)
+        .with(player::broadcast_latency)
+        .with(player::remove_hidden_player_info)
+        .with(player::send_advancement_progress)
         .with(entity::broadcast_movement)
         .with(entity::update_blocks_fallen)
+        .with(player::update_gliding)
+        .with(entity::damage_in_void)
+        .with(entity::burn_entities)
+        .with(entity::tick_status_effects)
+        .with(entity::strike_lightning_in_storms)
+        .with(entity::spawn_mobs)
+        .with(entity::despawn_mobs)
+        .with(entity::break_leashes)
+        .with(entity::update_firework_rockets)
+        .with(entity::update_arrows)
+        .with(entity::move_boats)
+        .with(entity::move_minecarts)
+        .with(entity::steer_ridden_mobs)
+        .with(entity::carry_passengers)
+        .with(entity::collect_items_into_hopper_minecarts)
+        .with(entity::explode_primed_tnt)
+        .with(entity::creeper::explode_creepers)
+        .with(player::exhaust_moving_players)
+        .with(player::tick_hunger)
         .with(entity::broadcast_velocity)
+        .with(entity::broadcast_metadata_updates)
+        .with(entity::map::render_maps)
+        .with(entity::map::send_map_data)
         .with(entity::falling_block::spawn_falling_blocks)
+        .with(entity::villager::find_job_sites)
+        .with(entity::villager::restock_villagers)
         .with(entity::supported_blocks::break_unsupported_blocks)
+        .with(block::tick_blocks)
+        .with(block::press_pressure_plates)
+        .with(block::press_detector_rails)
+        .with(block::piston::move_piston_blocks)
+        .with(block::furnace::tick_furnaces)
+        .with(block::spawner::tick_spawners)
+        .with(block::brewing_stand::tick_brewing_stands)
+        .with(block::beacon::update_beacons)
+        .with(block::conduit::update_conduits)
+        .with(block::skip_night)
+        .with(block::travel_through_portals)
+        .with(player::broadcast_block_changes)
+        .with(entity::mark_moved_entities_unsaved)
         .with(chunk_logic::chunk_save)
         .with(game::reset_bump_allocators)
         .with(game::increment_tick_count)
         .with(util::increment_time)
+        .with(util::tick_world_borders)
+        .with(util::damage_outside_border)
+        .with(game::flush_packets)
         .with(entity::previous_position_velocity_reset) // should be at end
 }
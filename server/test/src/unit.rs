@@ -1,23 +1,29 @@
 //! Unit testing framework.
 
 use feather_core::anvil::entity::{AnimalData, BaseEntityData};
-use feather_core::anvil::player::PlayerData;
+use feather_core::anvil::player::{ExperienceData, FoodData, PlayerData, SpawnData};
+use feather_core::dimension::DimensionTypes;
 use feather_core::network::{cast_packet, Packet};
 use feather_core::{
+    blocks::BlockId,
     chunk::Chunk,
-    chunk_map::ChunkMap,
-    util::{vec3, ChunkPosition, Position},
+    util::{vec3, BlockPosition, ChunkPosition, Dimension, Position},
 };
 use feather_server_chunk::{
     chunk_worker, hold_chunk_request, release_chunk_request, ChunkWorkerHandle,
 };
 use feather_server_network::NewClientInfo;
-use feather_server_player::on_chunk_cross_update_chunks;
+use feather_server_player::{
+    on_chunk_cross_update_view, on_view_update_update_chunks, on_world_change_update_view,
+};
 use feather_server_types::{
-    ChunkCrossEvent, ChunkHolder, Game, Name, NetworkId, ServerToWorkerMessage, Shared, Uuid,
-    WorkerToServerMessage,
+    BlockUpdateCause, ChunkCrossEvent, Game, Name, NetworkId, PacketBuffers, ServerToWorkerMessage,
+    Shared, Uuid, WorkerToServerMessage, WorldId, WorldState, Worlds,
+};
+use feather_server_util::{
+    on_chunk_cross_update_chunk_entities, on_world_change_update_chunk_entities,
 };
-use feather_server_util::on_chunk_cross_update_chunk_entities;
+use feather_server_worldgen::EmptyWorldGenerator;
 use fecs::{
     Entity, EntityBuilder, Event, EventHandlers, Executor, OwnedResources, RawEventHandler,
     RawSystem, RefResources, ResourcesEnum, ResourcesProvider, World,
@@ -71,41 +77,63 @@ impl Test {
 
         let mut event_handlers = EventHandlers::new()
             .with(hold_chunk_request)
-            .with(release_chunk_request);
+            .with(release_chunk_request)
+            .with(on_view_update_update_chunks)
+            .with(on_world_change_update_chunk_entities)
+            .with(on_world_change_update_view);
         event_handlers.set_up(&mut resources, world);
 
-        let mut chunk_map = ChunkMap::new();
-        for x in -1..=1 {
-            for z in -1..=1 {
-                chunk_map.insert(Chunk::new(ChunkPosition::new(x, z)));
-            }
-        }
+        let mut worlds = Worlds::new();
+        let main = worlds.add(
+            "world",
+            Dimension::Overwold,
+            "world",
+            Arc::new(EmptyWorldGenerator {}),
+        );
+        insert_chunks(&mut worlds[main]);
 
         let mut game = Game {
-            chunk_map,
+            worlds,
             tick_count: 0,
-            chunk_holders: Default::default(),
             level: Default::default(),
-            chunk_entities: Default::default(),
-            block_entities: Default::default(),
-            time: Default::default(),
+            weather_strength: Default::default(),
             event_handlers: Arc::new(event_handlers),
             resources: Arc::new(Default::default()),
             bump: Default::default(),
             shared: Arc::new(Shared {
                 config: Arc::new(Default::default()),
                 rng: Default::default(),
-                player_count: Arc::new(Default::default()),
+                online_players: Arc::new(Default::default()),
             }),
             game_rules: Default::default(),
+            dimension_types: DimensionTypes::vanilla(),
+            scoreboard: Default::default(),
+            plugin_channels: Default::default(),
+            maps: Default::default(),
+            advancements: Default::default(),
+            recipes: Default::default(),
         };
         resources.insert(cworker_handle);
+        resources.insert(Arc::new(PacketBuffers::new()));
 
         let resources = Arc::new(resources);
         game.resources = resources;
         game
     }
 
+    /// Creates another world with the same chunks as the main world.
+    pub fn create_world(&mut self, name: &str, dimension: Dimension) -> WorldId {
+        let id = self.game.create_world(
+            &mut self.world,
+            name,
+            dimension,
+            name,
+            Arc::new(EmptyWorldGenerator {}),
+        );
+        insert_chunks(&mut self.game.worlds[id]);
+        id
+    }
+
     /// Adds a resource into the resource set.
     pub fn with_resource(mut self, resource: impl Any + Send + Sync) -> Self {
         let resources = Arc::get_mut(&mut self.game.resources).expect("resources already borrowed");
@@ -203,8 +231,12 @@ impl Test {
             data: PlayerData {
                 animal: AnimalData::new(BaseEntityData::new(position, vec3(0.0, 0.0, 0.0)), 20.0),
                 gamemode: 1,
+                dimension: Dimension::Overwold.id(),
                 inventory: vec![],
                 held_item: 0,
+                food: FoodData::default(),
+                experience: ExperienceData::default(),
+                spawn: SpawnData::default(),
             },
             position,
             sender: server_tx,
@@ -227,8 +259,13 @@ impl Test {
     }
 
     /// Adds an entity with the given name and components.
+    /// The entity is placed in the main world unless
+    /// the builder specifies another.
     pub fn entity(&mut self, builder: EntityBuilder) -> Entity {
         let entity = builder.build().spawn_in(&mut self.world);
+        if !self.world.has::<WorldId>(entity) {
+            self.world.add(entity, WorldId::MAIN).unwrap();
+        }
 
         if let Some(pos) = self.world.try_get::<Position>(entity).map(|r| *r) {
             self.update_structures(entity, None, pos);
@@ -247,6 +284,46 @@ impl Test {
         self
     }
 
+    /// Sets the block at the given position in the main world.
+    pub fn set_block(&mut self, pos: BlockPosition, block: BlockId) -> &mut Self {
+        self.set_block_in(WorldId::MAIN, pos, block)
+    }
+
+    /// Sets the block at the given position in a world.
+    /// Panics if the position is not in a loaded chunk.
+    pub fn set_block_in(
+        &mut self,
+        world_id: WorldId,
+        pos: BlockPosition,
+        block: BlockId,
+    ) -> &mut Self {
+        assert!(
+            self.game.set_block_at(
+                &mut self.world,
+                world_id,
+                pos,
+                block,
+                BlockUpdateCause::Unknown
+            ),
+            "block at {:?} is not loaded",
+            pos
+        );
+        self
+    }
+
+    /// Returns the block at the given position in the main world.
+    pub fn block(&self, pos: BlockPosition) -> BlockId {
+        self.block_in(WorldId::MAIN, pos)
+    }
+
+    /// Returns the block at the given position in a world.
+    /// Panics if the position is not in a loaded chunk.
+    pub fn block_in(&self, world_id: WorldId, pos: BlockPosition) -> BlockId {
+        self.game
+            .block_at(world_id, pos)
+            .unwrap_or_else(|| panic!("block at {:?} is not loaded", pos))
+    }
+
     /// Returns the network ID of an entity.
     pub fn id(&self, entity: Entity) -> i32 {
         self.world.get::<NetworkId>(entity).0
@@ -257,6 +334,16 @@ impl Test {
         *self.world.get::<Uuid>(entity)
     }
 
+    /// Queues a packet as received from `player`. It is handled
+    /// by the next system run which polls packets of its type.
+    pub fn receive(&mut self, player: Entity, packet: impl Packet) -> &mut Self {
+        self.game
+            .resources
+            .get::<Arc<PacketBuffers>>()
+            .push(player, Box::new(packet));
+        self
+    }
+
     /// Returns the packet of type `P` sent to `player`.
     pub fn sent<P>(&mut self, player: Entity) -> Option<P>
     where
@@ -304,9 +391,11 @@ impl Test {
     fn update_player(player: &mut TrackedPlayer) {
         for msg in player.worker_rx.lock().unwrap().try_iter() {
             match msg {
-                ServerToWorkerMessage::SendPacket(packet) => {
+                ServerToWorkerMessage::SendPacket(packet)
+                | ServerToWorkerMessage::SendPacketNow(packet) => {
                     player.buffered_sent_packets.push(packet)
                 }
+                ServerToWorkerMessage::Flush => (),
                 ServerToWorkerMessage::Disconnect => player.disconnected = true,
             }
         }
@@ -320,10 +409,7 @@ impl Test {
             entity,
         };
         self.handle(cross, on_chunk_cross_update_chunk_entities);
-
-        if self.world.has::<ChunkHolder>(entity) {
-            self.handle(cross, on_chunk_cross_update_chunks);
-        }
+        self.handle(cross, on_chunk_cross_update_view);
     }
 
     /// Verifies that an entity is alive.
@@ -347,6 +433,15 @@ impl Test {
     }
 }
 
+/// Inserts the empty chunks around the origin which tests start with.
+fn insert_chunks(state: &mut WorldState) {
+    for x in -1..=1 {
+        for z in -1..=1 {
+            state.chunk_map.insert(Chunk::new(ChunkPosition::new(x, z)));
+        }
+    }
+}
+
 pub struct ChunkWorkerTester {
     pub cworker_tx: crossbeam::Sender<chunk_worker::Reply>,
     pub cworker_rx: crossbeam::Receiver<chunk_worker::Request>,
@@ -369,3 +464,354 @@ impl ChunkWorkerTester {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_core::inventory::{Area, Inventory};
+    use feather_core::items::{Item, ItemStack};
+    use feather_core::network::packets::{
+        BlockChange, ChatMessageServerbound, ChunkData, DestroyEntities, Face,
+        PlayerBlockPlacement, PlayerInfo, PlayerInfoAction, Respawn, SpawnPlayer,
+    };
+    use feather_core::position;
+    use feather_core::util::{BlockPosition, Gamemode};
+    use feather_server_player::{
+        broadcast_block_changes, handle_chat, handle_player_block_placement,
+        on_chunk_load_send_to_clients, on_entity_send_remove_hidden_player,
+        remove_hidden_player_info, set_hidden, set_skin,
+    };
+    use feather_server_types::{
+        BlockUpdateCause, ChunkLoadEvent, EntitySendEvent, Health, LastKnownPositions,
+        MessageReceiver,
+    };
+
+    #[test]
+    fn chunk_loading_is_scoped_to_worlds() {
+        let mut test = Test::new();
+        let other = test.create_world("other", Dimension::Overwold);
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        while test.sent::<ChunkData>(player).is_some() {}
+
+        let loaded = ChunkPosition::new(0, 0);
+        assert!(test.game.worlds[WorldId::MAIN]
+            .chunk_holders
+            .holders_for(loaded)
+            .contains(&player));
+        assert!(test.game.worlds[other]
+            .chunk_holders
+            .holders_for(loaded)
+            .is_empty());
+
+        // The player waits for a chunk of its own world.
+        let chunk = ChunkPosition::new(2, 0);
+        test.game.worlds[other].chunk_map.insert(Chunk::new(chunk));
+        test.handle(
+            ChunkLoadEvent {
+                world: other,
+                chunk,
+            },
+            on_chunk_load_send_to_clients,
+        );
+        assert!(test.sent::<ChunkData>(player).is_none());
+
+        test.game.worlds[WorldId::MAIN]
+            .chunk_map
+            .insert(Chunk::new(chunk));
+        test.handle(
+            ChunkLoadEvent {
+                world: WorldId::MAIN,
+                chunk,
+            },
+            on_chunk_load_send_to_clients,
+        );
+        assert!(test.sent::<ChunkData>(player).is_some());
+    }
+
+    #[test]
+    fn block_changes_are_scoped_to_worlds() {
+        let mut test = Test::new();
+        let other = test.create_world("other", Dimension::Overwold);
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        let pos = BlockPosition::new(1, 64, 1);
+
+        test.game.set_block_at(
+            &mut test.world,
+            other,
+            pos,
+            BlockId::stone(),
+            BlockUpdateCause::Unknown,
+        );
+        test.run(broadcast_block_changes);
+        assert_eq!(test.game.block_at(other, pos), Some(BlockId::stone()));
+        assert_eq!(test.game.block_at(WorldId::MAIN, pos), Some(BlockId::air()));
+        assert!(test.sent::<BlockChange>(player).is_none());
+
+        test.game.set_block_at(
+            &mut test.world,
+            WorldId::MAIN,
+            pos,
+            BlockId::stone(),
+            BlockUpdateCause::Unknown,
+        );
+        test.run(broadcast_block_changes);
+        assert!(test.sent::<BlockChange>(player).is_some());
+    }
+
+    #[test]
+    fn entities_move_between_worlds() {
+        let mut test = Test::new();
+        let other = test.create_world("other", Dimension::Overwold);
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        let entity = test.entity(
+            EntityBuilder::new()
+                .with(position!(1.0, 64.0, 1.0))
+                .with(NetworkId(1000)),
+        );
+        let chunk = ChunkPosition::new(0, 0);
+
+        test.game
+            .change_world(&mut test.world, entity, other, position!(1.0, 64.0, 1.0));
+        assert_eq!(*test.world.get::<WorldId>(entity), other);
+        assert!(!test.game.worlds[WorldId::MAIN]
+            .chunk_entities
+            .entities_in_chunk(chunk)
+            .contains(&entity));
+        assert!(test.game.worlds[other]
+            .chunk_entities
+            .entities_in_chunk(chunk)
+            .contains(&entity));
+        // Players in the old world no longer see the entity.
+        let destroy = test.sent::<DestroyEntities>(player).unwrap();
+        assert_eq!(destroy.entity_ids, vec![1000]);
+
+        test.game
+            .change_world(&mut test.world, player, other, position!(0.0, 64.0, 0.0));
+        assert_eq!(*test.world.get::<WorldId>(player), other);
+        assert!(test.sent::<Respawn>(player).is_some());
+        assert!(test.game.worlds[WorldId::MAIN]
+            .chunk_holders
+            .holders_for(chunk)
+            .is_empty());
+        assert!(test.game.worlds[other]
+            .chunk_holders
+            .holders_for(chunk)
+            .contains(&player));
+        assert!(test.game.worlds[other]
+            .chunk_entities
+            .entities_in_chunk(chunk)
+            .contains(&player));
+    }
+
+    /// Places the given item on top of the block at `location`.
+    fn place(test: &mut Test, player: Entity, item: Item, location: BlockPosition) {
+        test.world
+            .get::<Inventory>(player)
+            .set_item_at(Area::Hotbar, 0, ItemStack::new(item, 1))
+            .unwrap();
+        test.receive(
+            player,
+            PlayerBlockPlacement {
+                location,
+                face: Face::Top,
+                ..Default::default()
+            },
+        );
+        test.run(handle_player_block_placement);
+    }
+
+    #[test]
+    fn placement_is_denied_inside_players_and_mobs() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        let mob = test.entity(
+            EntityBuilder::new()
+                .with(position!(4.5, 64.0, 4.5))
+                .with(Health(20)),
+        );
+        test.set_block(BlockPosition::new(0, 63, 0), BlockId::stone())
+            .set_block(BlockPosition::new(4, 63, 4), BlockId::stone());
+
+        for &(x, z) in &[(0, 0), (4, 4)] {
+            place(&mut test, player, Item::Stone, BlockPosition::new(x, 63, z));
+            let pos = BlockPosition::new(x, 64, z);
+            assert!(test.block(pos).is_air());
+            // The client is told the block is still air.
+            assert_eq!(test.sent::<BlockChange>(player).unwrap().location, pos);
+        }
+
+        test.world.remove::<Health>(mob).unwrap();
+        place(&mut test, player, Item::Stone, BlockPosition::new(4, 63, 4));
+        assert_eq!(test.block(BlockPosition::new(4, 64, 4)), BlockId::stone());
+    }
+
+    #[test]
+    fn placement_in_water_waterlogs() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        let pos = BlockPosition::new(4, 64, 4);
+        test.set_block(pos.down(), BlockId::stone())
+            .set_block(pos, BlockId::water());
+
+        place(&mut test, player, Item::OakSlab, pos.down());
+        let block = test.block(pos);
+        assert_eq!(block.kind(), BlockId::oak_slab().kind());
+        assert_eq!(block.waterlogged(), Some(true));
+    }
+
+    #[test]
+    fn doors_need_room_for_both_halves() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        let floor = BlockPosition::new(4, 63, 4);
+        let (lower, upper) = (floor.up(), floor.up().up());
+        test.set_block(floor, BlockId::stone())
+            .set_block(upper, BlockId::stone());
+
+        place(&mut test, player, Item::OakDoor, floor);
+        assert!(test.block(lower).is_air());
+        // Both halves shown by the client are reverted.
+        assert_eq!(test.sent::<BlockChange>(player).unwrap().location, lower);
+        assert_eq!(test.sent::<BlockChange>(player).unwrap().location, upper);
+
+        test.set_block(upper, BlockId::air());
+        test.entity(
+            EntityBuilder::new()
+                .with(position!(4.5, 65.0, 4.5))
+                .with(Health(20)),
+        );
+        place(&mut test, player, Item::OakDoor, floor);
+        assert!(test.block(lower).is_air());
+        assert!(test.block(upper).is_air());
+        assert_eq!(test.sent::<BlockChange>(player).unwrap().location, lower);
+        assert_eq!(test.sent::<BlockChange>(player).unwrap().location, upper);
+    }
+
+    #[test]
+    fn adventure_and_spectator_cannot_place() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.5, 64.0, 0.5));
+        let floor = BlockPosition::new(4, 63, 4);
+        test.set_block(floor, BlockId::stone());
+
+        for &gamemode in &[Gamemode::Adventure, Gamemode::Spectator] {
+            *test.world.get_mut::<Gamemode>(player) = gamemode;
+            place(&mut test, player, Item::Stone, floor);
+            assert!(test.block(floor.up()).is_air());
+        }
+
+        *test.world.get_mut::<Gamemode>(player) = Gamemode::Survival;
+        place(&mut test, player, Item::Stone, floor);
+        assert_eq!(test.block(floor.up()), BlockId::stone());
+    }
+
+    #[test]
+    fn skin_changes_respawn_player_for_other_clients() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        let client = test.player("client", position!(5.0, 64.0, 5.0));
+        let stranger = test.player("stranger", position!(5.0, 64.0, 5.0));
+        test.world
+            .get::<LastKnownPositions>(client)
+            .0
+            .insert(player, position!(0.0, 64.0, 0.0));
+        while test.sent::<PlayerInfo>(client).is_some() {}
+        while test.sent::<PlayerInfo>(stranger).is_some() {}
+        while test.sent::<SpawnPlayer>(client).is_some() {}
+        while test.sent::<SpawnPlayer>(stranger).is_some() {}
+
+        set_skin(
+            &mut test.game,
+            &mut test.world,
+            player,
+            "value".to_owned(),
+            String::new(),
+        );
+
+        let uuid = test.uuid(player);
+        for &other in &[client, stranger] {
+            let remove = test.sent::<PlayerInfo>(other).unwrap();
+            assert_eq!(remove.uuid, uuid);
+            assert_eq!(remove.action, PlayerInfoAction::RemovePlayer);
+            match test.sent::<PlayerInfo>(other).unwrap().action {
+                PlayerInfoAction::AddPlayer(_, properties, _, _, _) => assert_eq!(
+                    properties,
+                    vec![("textures".to_owned(), "value".to_owned(), String::new())]
+                ),
+                action => panic!("expected the player to be added, got {:?}", action),
+            }
+        }
+
+        // Only clients which see the player respawn them.
+        let id = test.id(player);
+        assert_eq!(
+            test.sent::<DestroyEntities>(client).unwrap().entity_ids,
+            vec![id]
+        );
+        assert_eq!(test.sent::<SpawnPlayer>(client).unwrap().entity_id, id);
+        assert!(test.sent::<DestroyEntities>(stranger).is_none());
+        assert!(test.sent::<SpawnPlayer>(stranger).is_none());
+    }
+
+    #[test]
+    fn hidden_players_are_removed_from_tab_list_after_delay() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        let client = test.player("client", position!(5.0, 64.0, 5.0));
+        set_hidden(&test.game, &mut test.world, player, true);
+        while test.sent::<PlayerInfo>(client).is_some() {}
+
+        test.handle(
+            EntitySendEvent {
+                entity: player,
+                client,
+            },
+            on_entity_send_remove_hidden_player,
+        );
+        test.run(remove_hidden_player_info);
+        assert!(test.sent::<PlayerInfo>(client).is_none());
+
+        test.game.tick_count += 20;
+        test.run(remove_hidden_player_info);
+        let remove = test.sent::<PlayerInfo>(client).unwrap();
+        assert_eq!(remove.uuid, test.uuid(player));
+        assert_eq!(remove.action, PlayerInfoAction::RemovePlayer);
+
+        test.game.tick_count += 20;
+        test.run(remove_hidden_player_info);
+        assert!(test.sent::<PlayerInfo>(client).is_none());
+    }
+
+    #[test]
+    fn chat_commands_are_dispatched() {
+        let mut test = Test::new();
+        let player = test.player("player", position!(0.0, 64.0, 0.0));
+        let chat = |test: &mut Test, message: &str| {
+            test.receive(
+                player,
+                ChatMessageServerbound {
+                    message: message.to_owned(),
+                },
+            );
+            test.run(handle_chat);
+        };
+
+        chat(&mut test, "/gamemode adventure");
+        assert_eq!(*test.world.get::<Gamemode>(player), Gamemode::Adventure);
+
+        chat(&mut test, "/gamemode sideways");
+        chat(&mut test, "/gamemode");
+        assert_eq!(*test.world.get::<Gamemode>(player), Gamemode::Adventure);
+
+        let messages: Vec<String> = test
+            .world
+            .get_mut::<MessageReceiver>(player)
+            .flush()
+            .map(|message| message.to_plain())
+            .collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[1].contains("sideways"));
+        assert!(messages[2].starts_with("Unknown or incomplete command"));
+    }
+}
@@ -0,0 +1,580 @@
+//! Advancements, which players make by achieving their
+//! criteria, and which are shown in the advancements screen.
+//!
+//! Definitions are loaded from the vanilla data directory.
+//! Only simple criteria are tracked so far: those triggered
+//! by `minecraft:tick`, which are achieved when a player joins,
+//! and by `minecraft:recipe_unlocked`.
+
+use ahash::AHashMap;
+use anyhow::Context;
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{AdvancementData, AdvancementDisplay, Advancements};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Trigger of criteria achieved when a player joins. (In vanilla,
+/// they are achieved on the first tick after joining.)
+pub const TRIGGER_TICK: &str = "minecraft:tick";
+/// Trigger of criteria achieved when a recipe is unlocked.
+pub const TRIGGER_RECIPE_UNLOCKED: &str = "minecraft:recipe_unlocked";
+
+/// A criterion of an advancement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Criterion {
+    pub name: String,
+    /// ID of the trigger achieving this criterion.
+    pub trigger: String,
+    /// Conditions on the trigger, specific to it.
+    pub conditions: Value,
+}
+
+impl Criterion {
+    /// Returns the recipe unlocking this criterion,
+    /// for `minecraft:recipe_unlocked` criteria.
+    pub fn recipe(&self) -> Option<&str> {
+        self.conditions.get("recipe")?.as_str()
+    }
+}
+
+/// The definition of an advancement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Advancement {
+    pub id: String,
+    pub parent: Option<String>,
+    /// `None` for advancements which aren't shown,
+    /// such as those unlocking recipes.
+    pub display: Option<AdvancementDisplay>,
+    pub criteria: Vec<Criterion>,
+    /// The advancement is made once, for each inner list,
+    /// one of its criteria has been achieved.
+    pub requirements: Vec<Vec<String>>,
+}
+
+impl Advancement {
+    /// Parses an advancement in the data pack JSON format.
+    pub fn from_json(id: impl Into<String>, json: &[u8]) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_slice(json)?;
+
+        let parent = value
+            .get("parent")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let display = value.get("display").map(parse_display).transpose()?;
+
+        let mut criteria = vec![];
+        if let Some(object) = value.get("criteria").and_then(Value::as_object) {
+            for (name, criterion) in object {
+                let trigger = criterion
+                    .get("trigger")
+                    .and_then(Value::as_str)
+                    .with_context(|| format!("criterion {} has no trigger", name))?;
+                criteria.push(Criterion {
+                    name: name.clone(),
+                    trigger: trigger.to_owned(),
+                    conditions: criterion.get("conditions").cloned().unwrap_or(Value::Null),
+                });
+            }
+        }
+
+        // By default, each criterion must be achieved.
+        let requirements = match value.get("requirements") {
+            Some(requirements) => serde_json::from_value(requirements.clone())?,
+            None => criteria
+                .iter()
+                .map(|criterion| vec![criterion.name.clone()])
+                .collect(),
+        };
+
+        Ok(Self {
+            id: id.into(),
+            parent,
+            display,
+            criteria,
+            requirements,
+        })
+    }
+
+    pub fn criterion(&self, name: &str) -> Option<&Criterion> {
+        self.criteria
+            .iter()
+            .find(|criterion| criterion.name == name)
+    }
+
+    /// Returns the advancement as sent in the `Advancements` packet.
+    pub fn data(&self) -> AdvancementData {
+        AdvancementData {
+            parent: self.parent.clone(),
+            display: self.display.clone(),
+            criteria: self
+                .criteria
+                .iter()
+                .map(|criterion| criterion.name.clone())
+                .collect(),
+            requirements: self.requirements.clone(),
+        }
+    }
+}
+
+fn parse_display(value: &Value) -> anyhow::Result<AdvancementDisplay> {
+    let icon = value
+        .get("icon")
+        .and_then(|icon| icon.get("item"))
+        .and_then(Value::as_str)
+        .context("display has no icon")?;
+    let icon = Item::from_identifier(icon).with_context(|| format!("unknown icon {}", icon))?;
+
+    let frame = match value.get("frame").and_then(Value::as_str) {
+        None | Some("task") => 0,
+        Some("challenge") => 1,
+        Some("goal") => 2,
+        Some(frame) => anyhow::bail!("unknown frame {}", frame),
+    };
+    let flag =
+        |name: &str, default: bool| value.get(name).and_then(Value::as_bool).unwrap_or(default);
+
+    Ok(AdvancementDisplay {
+        title: text_json(value.get("title")),
+        description: text_json(value.get("description")),
+        icon: Some(ItemStack::new(icon, 1)),
+        frame,
+        background: value
+            .get("background")
+            .and_then(Value::as_str)
+            .map(str::to_owned),
+        show_toast: flag("show_toast", true),
+        hidden: flag("hidden", false),
+        x: 0.0,
+        y: 0.0,
+    })
+}
+
+/// Returns the JSON text of a title or description.
+fn text_json(value: Option<&Value>) -> String {
+    match value {
+        // Plain strings are valid JSON text too.
+        Some(value) => value.to_string(),
+        None => "\"\"".to_owned(),
+    }
+}
+
+/// The advancements of the server.
+#[derive(Default, Debug)]
+pub struct AdvancementRegistry {
+    advancements: Vec<Advancement>,
+    by_id: AHashMap<String, usize>,
+}
+
+impl AdvancementRegistry {
+    /// Loads the vanilla advancements extracted
+    /// from the server JAR at build time.
+    pub fn vanilla() -> anyhow::Result<Self> {
+        let mut registry = Self::default();
+        let path = format!(
+            "{}/minecraft/data/minecraft/advancements",
+            feather_data::minecraft::PATH
+        );
+        registry.load_dir(Path::new(&path), "minecraft:")?;
+        registry.layout();
+        Ok(registry)
+    }
+
+    /// Loads the advancements in the given directory and its
+    /// subdirectories. Their IDs are their paths relative to
+    /// the directory, without the extension, after `prefix`.
+    pub fn load_dir(&mut self, dir: &Path, prefix: &str) -> anyhow::Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .collect::<Result<Vec<_>, _>>()?;
+        // Sorted so that siblings are laid out in a consistent order.
+        entries.sort_by_key(|entry| entry.path());
+
+        for entry in entries {
+            let path = entry.path();
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if path.is_dir() {
+                self.load_dir(&path, &format!("{}{}/", prefix, name))?;
+            } else if path
+                .extension()
+                .map_or(false, |extension| extension == "json")
+            {
+                let id = format!("{}{}", prefix, name);
+                let advancement = Advancement::from_json(id.clone(), &fs::read(&path)?)
+                    .with_context(|| format!("failed to load advancement {}", id))?;
+                self.add(advancement);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds an advancement, replacing any with the same ID.
+    pub fn add(&mut self, advancement: Advancement) {
+        match self.by_id.get(&advancement.id) {
+            Some(&index) => self.advancements[index] = advancement,
+            None => {
+                self.by_id
+                    .insert(advancement.id.clone(), self.advancements.len());
+                self.advancements.push(advancement);
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Advancement> {
+        self.by_id.get(id).map(|&index| &self.advancements[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Advancement> {
+        self.advancements.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.advancements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.advancements.is_empty()
+    }
+
+    /// Positions the displayed advancements in the advancements
+    /// screen as trees growing to the right of their roots, with
+    /// each leaf on its own row.
+    pub fn layout(&mut self) {
+        let mut children: AHashMap<&str, Vec<usize>> = AHashMap::new();
+        let mut roots = vec![];
+        for (index, advancement) in self.advancements.iter().enumerate() {
+            match &advancement.parent {
+                Some(parent) if self.by_id.contains_key(parent) => {
+                    children.entry(parent.as_str()).or_default().push(index)
+                }
+                _ => roots.push(index),
+            }
+        }
+
+        let mut positions = vec![(0.0, 0.0); self.advancements.len()];
+        for root in roots {
+            let mut row = 0.0;
+            place(
+                &self.advancements,
+                &children,
+                root,
+                0.0,
+                &mut row,
+                &mut positions,
+            );
+        }
+
+        for (advancement, (x, y)) in self.advancements.iter_mut().zip(positions) {
+            if let Some(display) = &mut advancement.display {
+                display.x = x;
+                display.y = y;
+            }
+        }
+    }
+
+    /// Creates the `Advancements` packet sending all advancements
+    /// and the given progress, replacing any the player had.
+    pub fn packet(&self, progress: &AdvancementProgress) -> Advancements {
+        Advancements {
+            reset: true,
+            advancements: self
+                .advancements
+                .iter()
+                .map(|advancement| (advancement.id.clone(), advancement.data()))
+                .collect(),
+            removed: vec![],
+            progress: self
+                .advancements
+                .iter()
+                .map(|advancement| (advancement.id.clone(), progress.criteria(advancement)))
+                .collect(),
+        }
+    }
+
+    /// Creates the `Advancements` packet updating the progress
+    /// of the given advancements. The client shows a toast for
+    /// those which were made.
+    pub fn progress_packet<'a>(
+        &self,
+        progress: &AdvancementProgress,
+        ids: impl IntoIterator<Item = &'a str>,
+    ) -> Advancements {
+        Advancements {
+            reset: false,
+            advancements: vec![],
+            removed: vec![],
+            progress: ids
+                .into_iter()
+                .filter_map(|id| self.get(id))
+                .map(|advancement| (advancement.id.clone(), progress.criteria(advancement)))
+                .collect(),
+        }
+    }
+}
+
+/// Places an advancement and its descendants, returning its row.
+/// Parents are placed on the row of their first child.
+fn place(
+    advancements: &[Advancement],
+    children: &AHashMap<&str, Vec<usize>>,
+    index: usize,
+    depth: f32,
+    row: &mut f32,
+    positions: &mut [(f32, f32)],
+) -> f32 {
+    let own_row = match children.get(advancements[index].id.as_str()) {
+        Some(indices) => {
+            let mut first = None;
+            for &child in indices {
+                let child_row = place(advancements, children, child, depth + 1.0, row, positions);
+                first.get_or_insert(child_row);
+            }
+            first.unwrap_or(*row)
+        }
+        None => {
+            *row += 1.0;
+            *row - 1.0
+        }
+    };
+    positions[index] = (depth, own_row);
+    own_row
+}
+
+/// Component storing a player's progress on advancements.
+#[derive(Clone, Debug, Default)]
+pub struct AdvancementProgress {
+    /// Time each achieved criterion was achieved, in milliseconds
+    /// since the epoch, by advancement and criterion.
+    achieved: AHashMap<String, AHashMap<String, i64>>,
+    /// Advancements whose progress changed since it was last sent.
+    changed: Vec<String>,
+}
+
+impl AdvancementProgress {
+    /// Returns the time the given criterion was achieved, if it was.
+    pub fn achieved(&self, advancement: &str, criterion: &str) -> Option<i64> {
+        self.achieved.get(advancement)?.get(criterion).copied()
+    }
+
+    /// Achieves a criterion. Returns whether it wasn't already achieved.
+    pub fn grant(&mut self, advancement: &str, criterion: &str, time: i64) -> bool {
+        let criteria = self.achieved.entry(advancement.to_owned()).or_default();
+        if criteria.contains_key(criterion) {
+            return false;
+        }
+        criteria.insert(criterion.to_owned(), time);
+
+        if !self.changed.iter().any(|id| id == advancement) {
+            self.changed.push(advancement.to_owned());
+        }
+        true
+    }
+
+    /// Returns whether the given advancement has been made.
+    pub fn is_done(&self, advancement: &Advancement) -> bool {
+        !advancement.requirements.is_empty()
+            && advancement.requirements.iter().all(|requirement| {
+                requirement
+                    .iter()
+                    .any(|criterion| self.achieved(&advancement.id, criterion).is_some())
+            })
+    }
+
+    /// Achieves the criteria with the given trigger for which `matches`
+    /// returns `true`, on advancements not yet made. Returns the number
+    /// of criteria achieved.
+    pub fn trigger(
+        &mut self,
+        registry: &AdvancementRegistry,
+        trigger: &str,
+        time: i64,
+        mut matches: impl FnMut(&Criterion) -> bool,
+    ) -> usize {
+        let mut count = 0;
+        for advancement in registry.iter() {
+            if self.is_done(advancement) {
+                continue;
+            }
+
+            for criterion in &advancement.criteria {
+                if criterion.trigger == trigger
+                    && matches(criterion)
+                    && self.grant(&advancement.id, &criterion.name, time)
+                {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Returns the progress on each criterion of the given
+    /// advancement, as sent in the `Advancements` packet.
+    pub fn criteria(&self, advancement: &Advancement) -> Vec<(String, Option<i64>)> {
+        advancement
+            .criteria
+            .iter()
+            .map(|criterion| {
+                (
+                    criterion.name.clone(),
+                    self.achieved(&advancement.id, &criterion.name),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the advancements whose progress changed since
+    /// this was last called, and marks them unchanged.
+    pub fn take_changed(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> AdvancementRegistry {
+        let mut registry = AdvancementRegistry::default();
+        registry.add(
+            Advancement::from_json(
+                "minecraft:story/root",
+                br#"{
+                    "display": {
+                        "icon": { "item": "minecraft:grass_block" },
+                        "title": { "translate": "advancements.story.root.title" },
+                        "description": { "translate": "advancements.story.root.description" },
+                        "background": "minecraft:textures/gui/advancements/backgrounds/stone.png",
+                        "show_toast": false,
+                        "announce_to_chat": false
+                    },
+                    "criteria": {
+                        "crafting_table": {
+                            "trigger": "minecraft:inventory_changed",
+                            "conditions": { "items": [{ "item": "minecraft:crafting_table" }] }
+                        }
+                    }
+                }"#,
+            )
+            .unwrap(),
+        );
+        registry.add(
+            Advancement::from_json(
+                "minecraft:story/mine_stone",
+                br#"{
+                    "parent": "minecraft:story/root",
+                    "display": {
+                        "icon": { "item": "minecraft:wooden_pickaxe" },
+                        "title": { "translate": "advancements.story.mine_stone.title" },
+                        "description": { "translate": "advancements.story.mine_stone.description" },
+                        "frame": "goal"
+                    },
+                    "criteria": {
+                        "joined": { "trigger": "minecraft:tick" },
+                        "has_recipe": {
+                            "trigger": "minecraft:recipe_unlocked",
+                            "conditions": { "recipe": "minecraft:furnace" }
+                        },
+                        "has_other_recipe": {
+                            "trigger": "minecraft:recipe_unlocked",
+                            "conditions": { "recipe": "minecraft:chest" }
+                        }
+                    },
+                    "requirements": [["joined"], ["has_recipe", "has_other_recipe"]]
+                }"#,
+            )
+            .unwrap(),
+        );
+        registry.add(
+            Advancement::from_json(
+                "minecraft:story/upgrade_tools",
+                br#"{
+                    "parent": "minecraft:story/root",
+                    "display": {
+                        "icon": { "item": "minecraft:stone_pickaxe" },
+                        "title": "Getting an Upgrade",
+                        "description": ""
+                    },
+                    "criteria": { "joined": { "trigger": "minecraft:tick" } }
+                }"#,
+            )
+            .unwrap(),
+        );
+        registry.layout();
+        registry
+    }
+
+    #[test]
+    fn parse_and_layout() {
+        let registry = registry();
+        assert_eq!(registry.len(), 3);
+
+        let root = registry.get("minecraft:story/root").unwrap();
+        assert_eq!(root.parent, None);
+        assert_eq!(root.requirements, vec![vec!["crafting_table".to_owned()]]);
+        let display = root.display.as_ref().unwrap();
+        assert_eq!(
+            display.title,
+            r#"{"translate":"advancements.story.root.title"}"#
+        );
+        assert_eq!(display.icon, Some(ItemStack::new(Item::GrassBlock, 1)));
+        assert!(display.background.is_some());
+        assert!(!display.show_toast);
+        assert_eq!((display.x, display.y), (0.0, 0.0));
+
+        let mine_stone = registry.get("minecraft:story/mine_stone").unwrap();
+        let display = mine_stone.display.as_ref().unwrap();
+        assert_eq!(display.frame, 2);
+        assert_eq!((display.x, display.y), (1.0, 0.0));
+        assert_eq!(
+            mine_stone.criterion("has_recipe").unwrap().recipe(),
+            Some("minecraft:furnace")
+        );
+
+        let upgrade_tools = registry.get("minecraft:story/upgrade_tools").unwrap();
+        let display = upgrade_tools.display.as_ref().unwrap();
+        assert_eq!(display.title, r#""Getting an Upgrade""#);
+        assert_eq!((display.x, display.y), (1.0, 1.0));
+    }
+
+    #[test]
+    fn trigger_criteria() {
+        let registry = registry();
+        let mine_stone = registry.get("minecraft:story/mine_stone").unwrap();
+        let mut progress = AdvancementProgress::default();
+
+        assert_eq!(progress.trigger(&registry, TRIGGER_TICK, 10, |_| true), 2);
+        assert_eq!(progress.trigger(&registry, TRIGGER_TICK, 20, |_| true), 0);
+        assert_eq!(
+            progress.take_changed(),
+            vec![
+                "minecraft:story/mine_stone".to_owned(),
+                "minecraft:story/upgrade_tools".to_owned()
+            ]
+        );
+        assert!(!progress.is_done(mine_stone));
+
+        let unlocked = progress.trigger(&registry, TRIGGER_RECIPE_UNLOCKED, 30, |criterion| {
+            criterion.recipe() == Some("minecraft:chest")
+        });
+        assert_eq!(unlocked, 1);
+        assert!(progress.is_done(mine_stone));
+        assert_eq!(
+            progress.criteria(mine_stone),
+            vec![
+                ("has_other_recipe".to_owned(), Some(30)),
+                ("has_recipe".to_owned(), None),
+                ("joined".to_owned(), Some(10)),
+            ]
+        );
+
+        // Made advancements no longer track their criteria.
+        let unlocked = progress.trigger(&registry, TRIGGER_RECIPE_UNLOCKED, 40, |_| true);
+        assert_eq!(unlocked, 0);
+    }
+}
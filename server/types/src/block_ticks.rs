@@ -0,0 +1,206 @@
+//! Block ticks scheduled to run at a later tick.
+//!
+//! Blocks are ticked in two ways. Scheduled ticks run a given
+//! number of ticks after being scheduled, e.g. for fluids spreading
+//! or repeaters toggling. Random ticks hit random blocks in each
+//! block-ticking chunk every tick, e.g. for crops growing or ice
+//! melting, and aren't tracked here.
+//!
+//! Scheduled ticks are stored per chunk. They only run while their
+//! chunk is block-ticking, and are discarded when it is unloaded.
+
+use ahash::{AHashMap, AHashSet};
+use feather_core::blocks::BlockKind;
+use feather_core::util::{BlockPosition, ChunkPosition};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// How a block is ticked.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BlockTickKind {
+    /// A tick scheduled with `BlockTicks::schedule`.
+    Scheduled,
+    /// A random tick.
+    Random,
+}
+
+/// The priority of a scheduled tick. Ticks due on the same
+/// tick run in order of priority, lowest first.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TickPriority(pub i32);
+
+impl TickPriority {
+    pub const EXTREMELY_HIGH: TickPriority = TickPriority(-3);
+    pub const VERY_HIGH: TickPriority = TickPriority(-2);
+    pub const HIGH: TickPriority = TickPriority(-1);
+    pub const NORMAL: TickPriority = TickPriority(0);
+    pub const LOW: TickPriority = TickPriority(1);
+    pub const VERY_LOW: TickPriority = TickPriority(2);
+    pub const EXTREMELY_LOW: TickPriority = TickPriority(3);
+}
+
+/// A block tick which is due at a given tick.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledTick {
+    pub pos: BlockPosition,
+    /// The kind of block to tick. The tick is skipped if the
+    /// block has changed to another kind once it is due.
+    pub kind: BlockKind,
+    /// The tick count at which the tick is due.
+    pub time: u64,
+    pub priority: TickPriority,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct QueuedTick {
+    time: u64,
+    priority: TickPriority,
+    /// Order in which the tick was scheduled, used to
+    /// break ties between ticks due at the same time.
+    sequence: u64,
+    pos: BlockPosition,
+    kind: BlockKind,
+}
+
+#[derive(Debug, Default)]
+struct ChunkTicks {
+    queue: BinaryHeap<Reverse<QueuedTick>>,
+    /// The blocks in `queue`, so each block is
+    /// scheduled at most once at a time.
+    scheduled: AHashSet<(BlockPosition, BlockKind)>,
+}
+
+/// The scheduled block ticks in a world.
+#[derive(Debug, Default)]
+pub struct BlockTicks {
+    chunks: AHashMap<ChunkPosition, ChunkTicks>,
+    next_sequence: u64,
+}
+
+impl BlockTicks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules a tick for a block of the given kind.
+    ///
+    /// Returns `false` without scheduling anything if the
+    /// block already has a tick scheduled for that kind.
+    pub fn schedule(&mut self, tick: ScheduledTick) -> bool {
+        let chunk = self.chunks.entry(tick.pos.chunk()).or_default();
+        if !chunk.scheduled.insert((tick.pos, tick.kind)) {
+            return false;
+        }
+
+        chunk.queue.push(Reverse(QueuedTick {
+            time: tick.time,
+            priority: tick.priority,
+            sequence: self.next_sequence,
+            pos: tick.pos,
+            kind: tick.kind,
+        }));
+        self.next_sequence += 1;
+        true
+    }
+
+    /// Returns whether a block has a tick
+    /// scheduled for the given kind.
+    pub fn is_scheduled(&self, pos: BlockPosition, kind: BlockKind) -> bool {
+        self.chunks
+            .get(&pos.chunk())
+            .map_or(false, |chunk| chunk.scheduled.contains(&(pos, kind)))
+    }
+
+    /// Returns the chunks with scheduled ticks.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPosition> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Removes and returns the ticks in a chunk which are
+    /// due at the given tick count, in the order they run.
+    pub fn take_due(&mut self, chunk: ChunkPosition, now: u64) -> Vec<ScheduledTick> {
+        let ticks = match self.chunks.get_mut(&chunk) {
+            Some(ticks) => ticks,
+            None => return Vec::new(),
+        };
+
+        let mut due = Vec::new();
+        while ticks
+            .queue
+            .peek()
+            .map_or(false, |Reverse(tick)| tick.time <= now)
+        {
+            let Reverse(tick) = ticks.queue.pop().expect("we just peeked a tick");
+            ticks.scheduled.remove(&(tick.pos, tick.kind));
+            due.push(ScheduledTick {
+                pos: tick.pos,
+                kind: tick.kind,
+                time: tick.time,
+                priority: tick.priority,
+            });
+        }
+
+        if ticks.queue.is_empty() {
+            self.chunks.remove(&chunk);
+        }
+        due
+    }
+
+    /// Discards the ticks scheduled in a chunk,
+    /// e.g. because it was unloaded.
+    pub fn remove_chunk(&mut self, chunk: ChunkPosition) {
+        self.chunks.remove(&chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(x: i32, kind: BlockKind, time: u64, priority: TickPriority) -> ScheduledTick {
+        ScheduledTick {
+            pos: BlockPosition::new(x, 64, 0),
+            kind,
+            time,
+            priority,
+        }
+    }
+
+    #[test]
+    fn due_ticks_in_order() {
+        let mut ticks = BlockTicks::new();
+        let late = tick(0, BlockKind::Water, 10, TickPriority::NORMAL);
+        let low = tick(1, BlockKind::Repeater, 5, TickPriority::LOW);
+        let high = tick(2, BlockKind::Repeater, 5, TickPriority::HIGH);
+        let first = tick(3, BlockKind::Water, 5, TickPriority::HIGH);
+        for &scheduled in &[late, low, high, first] {
+            assert!(ticks.schedule(scheduled));
+        }
+
+        let chunk = ChunkPosition::new(0, 0);
+        assert!(ticks.take_due(chunk, 4).is_empty());
+        assert_eq!(ticks.take_due(chunk, 5), vec![high, first, low]);
+        assert!(!ticks.is_scheduled(high.pos, high.kind));
+        assert!(ticks.is_scheduled(late.pos, late.kind));
+
+        assert_eq!(ticks.take_due(chunk, 20), vec![late]);
+        assert_eq!(ticks.chunks().count(), 0);
+    }
+
+    #[test]
+    fn scheduled_once() {
+        let mut ticks = BlockTicks::new();
+        let water = tick(0, BlockKind::Water, 10, TickPriority::NORMAL);
+        assert!(ticks.schedule(water));
+        assert!(!ticks.schedule(ScheduledTick { time: 2, ..water }));
+        // Another kind of block may be scheduled at the same position.
+        assert!(ticks.schedule(ScheduledTick {
+            kind: BlockKind::Lava,
+            ..water
+        }));
+
+        ticks.remove_chunk(water.pos.chunk());
+        assert!(!ticks.is_scheduled(water.pos, water.kind));
+        assert!(ticks.schedule(water));
+    }
+}
@@ -0,0 +1,261 @@
+//! The world border, which confines players to a square area
+//! around a center point.
+//!
+//! The border can shrink or grow over time, in which case its
+//! diameter is interpolated linearly each tick. Players outside
+//! the border, past its safe zone, take damage.
+
+use crate::TICK_LENGTH;
+use feather_core::anvil::level::LevelData;
+use feather_core::network::packets::WorldBorderAction;
+use feather_core::util::Position;
+
+/// The largest diameter of a world border.
+pub const MAX_BORDER_SIZE: f64 = 60_000_000.0;
+
+/// Distance from the origin at which clients stop
+/// teleporting through portals.
+const PORTAL_TELEPORT_BOUNDARY: i32 = 29_999_984;
+
+/// The world border of a single world.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    /// Diameter at the start of the current movement.
+    old_size: f64,
+    /// Diameter the border is moving towards.
+    target_size: f64,
+    /// Duration of the current movement, in milliseconds.
+    lerp_time: u64,
+    /// Time since the current movement started, in milliseconds.
+    lerp_elapsed: u64,
+    /// Damage dealt by each hit for every block
+    /// a player is outside of the safe zone.
+    pub damage_per_block: f64,
+    /// Distance outside the border in which
+    /// players don't take damage.
+    pub safe_zone: f64,
+    /// Distance from the border at which
+    /// clients show the warning effect.
+    pub warning_blocks: i32,
+    /// Clients show the warning effect if a shrinking border
+    /// reaches them in fewer than this many seconds.
+    pub warning_time: i32,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            old_size: MAX_BORDER_SIZE,
+            target_size: MAX_BORDER_SIZE,
+            lerp_time: 0,
+            lerp_elapsed: 0,
+            damage_per_block: 0.2,
+            safe_zone: 5.0,
+            warning_blocks: 5,
+            warning_time: 15,
+        }
+    }
+}
+
+impl WorldBorder {
+    /// Loads the border stored in a world's level data.
+    pub fn from_level(level: &LevelData) -> Self {
+        let mut border = Self {
+            center_x: level.border_center_x,
+            center_z: level.border_center_z,
+            damage_per_block: level.border_damage_per_block,
+            safe_zone: level.border_safe_zone,
+            warning_blocks: level.border_warning_blocks as i32,
+            warning_time: level.border_warning_time as i32,
+            ..Self::default()
+        };
+        border.set_size(level.border_size);
+        border.lerp_size(
+            level.border_size_lerp_target,
+            level.border_size_lerp_time.max(0) as u64,
+        );
+        border
+    }
+
+    /// Stores the border in a world's level data.
+    pub fn write_to_level(&self, level: &mut LevelData) {
+        level.border_center_x = self.center_x;
+        level.border_center_z = self.center_z;
+        level.border_damage_per_block = self.damage_per_block;
+        level.border_safe_zone = self.safe_zone;
+        level.border_size = self.size();
+        level.border_size_lerp_target = self.target_size;
+        level.border_size_lerp_time = self.remaining_time() as i64;
+        level.border_warning_blocks = f64::from(self.warning_blocks);
+        level.border_warning_time = f64::from(self.warning_time);
+    }
+
+    /// Returns the current diameter of the border.
+    pub fn size(&self) -> f64 {
+        if self.lerp_elapsed >= self.lerp_time {
+            self.target_size
+        } else {
+            let progress = self.lerp_elapsed as f64 / self.lerp_time as f64;
+            self.old_size + (self.target_size - self.old_size) * progress
+        }
+    }
+
+    /// Returns the diameter the border is moving towards,
+    /// or its current diameter if it isn't moving.
+    pub fn target_size(&self) -> f64 {
+        self.target_size
+    }
+
+    /// Returns the number of milliseconds until
+    /// the border reaches its target diameter.
+    pub fn remaining_time(&self) -> u64 {
+        self.lerp_time.saturating_sub(self.lerp_elapsed)
+    }
+
+    /// Sets the diameter of the border, stopping
+    /// any movement in progress.
+    pub fn set_size(&mut self, size: f64) {
+        let size = size.max(1.0).min(MAX_BORDER_SIZE);
+        self.old_size = size;
+        self.target_size = size;
+        self.lerp_time = 0;
+        self.lerp_elapsed = 0;
+    }
+
+    /// Moves the border from its current diameter
+    /// to `size` over `millis` milliseconds.
+    pub fn lerp_size(&mut self, size: f64, millis: u64) {
+        if millis == 0 {
+            self.set_size(size);
+            return;
+        }
+
+        self.old_size = self.size();
+        self.target_size = size.max(1.0).min(MAX_BORDER_SIZE);
+        self.lerp_time = millis;
+        self.lerp_elapsed = 0;
+    }
+
+    /// Advances the border's movement by one tick.
+    pub fn tick(&mut self) {
+        if self.lerp_elapsed < self.lerp_time {
+            self.lerp_elapsed = (self.lerp_elapsed + TICK_LENGTH).min(self.lerp_time);
+        }
+    }
+
+    /// Returns the distance from the given position to
+    /// the nearest edge of the border. The distance is
+    /// negative if the position is outside the border.
+    pub fn distance_inside(&self, pos: Position) -> f64 {
+        let radius = self.size() / 2.0;
+        let dx = radius - (pos.x - self.center_x).abs();
+        let dz = radius - (pos.z - self.center_z).abs();
+        dx.min(dz)
+    }
+
+    /// Returns whether the given position is inside the border.
+    pub fn contains(&self, pos: Position) -> bool {
+        self.distance_inside(pos) >= 0.0
+    }
+
+    /// Moves a position outside the border to
+    /// the nearest position inside it.
+    pub fn clamp(&self, mut pos: Position) -> Position {
+        let radius = self.size() / 2.0;
+        pos.x = pos
+            .x
+            .max(self.center_x - radius)
+            .min(self.center_x + radius);
+        pos.z = pos
+            .z
+            .max(self.center_z - radius)
+            .min(self.center_z + radius);
+        pos
+    }
+
+    /// Returns the damage dealt to an entity at
+    /// the given position by a single hit.
+    pub fn damage_at(&self, pos: Position) -> u32 {
+        let outside = -self.distance_inside(pos) - self.safe_zone;
+        if outside <= 0.0 || self.damage_per_block <= 0.0 {
+            return 0;
+        }
+
+        ((outside * self.damage_per_block).floor() as u32).max(1)
+    }
+
+    /// Returns the action sending the complete
+    /// border to a client.
+    pub fn initialize_action(&self) -> WorldBorderAction {
+        WorldBorderAction::Initialize {
+            x: self.center_x,
+            z: self.center_z,
+            old_diameter: self.size(),
+            new_diameter: self.target_size,
+            speed: self.remaining_time() as i64,
+            portal_teleport_boundary: PORTAL_TELEPORT_BOUNDARY,
+            warning_time: self.warning_time,
+            warning_blocks: self.warning_blocks,
+        }
+    }
+
+    /// Returns the action informing clients of
+    /// the border's current size and movement.
+    pub fn size_action(&self) -> WorldBorderAction {
+        if self.remaining_time() > 0 {
+            WorldBorderAction::LerpSize(self.size(), self.target_size, self.remaining_time() as i64)
+        } else {
+            WorldBorderAction::SetSize(self.target_size)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::position;
+
+    #[test]
+    fn interpolation() {
+        let mut border = WorldBorder::default();
+        border.set_size(100.0);
+        border.lerp_size(50.0, 1000);
+
+        for _ in 0..10 {
+            border.tick();
+        }
+        assert_eq!(border.size(), 75.0);
+        assert_eq!(border.remaining_time(), 500);
+
+        for _ in 0..20 {
+            border.tick();
+        }
+        assert_eq!(border.size(), 50.0);
+        assert_eq!(border.remaining_time(), 0);
+        assert_eq!(border.size_action(), WorldBorderAction::SetSize(50.0));
+    }
+
+    #[test]
+    fn bounds_and_damage() {
+        let mut border = WorldBorder::default();
+        border.set_size(20.0);
+        border.center_x = 100.0;
+
+        assert!(border.contains(position!(105.0, 64.0, -10.0)));
+        assert!(!border.contains(position!(115.0, 64.0, 0.0)));
+        assert_eq!(border.distance_inside(position!(100.0, 64.0, 2.0)), 8.0);
+
+        let clamped = border.clamp(position!(150.0, 64.0, -30.0));
+        assert_eq!((clamped.x, clamped.y, clamped.z), (110.0, 64.0, -10.0));
+
+        // Within the safe zone.
+        assert_eq!(border.damage_at(position!(114.0, 64.0, 0.0)), 0);
+        // Past the safe zone, at least one point is dealt.
+        assert_eq!(border.damage_at(position!(116.0, 64.0, 0.0)), 1);
+        assert_eq!(border.damage_at(position!(140.0, 64.0, 0.0)), 5);
+    }
+}
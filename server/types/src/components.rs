@@ -2,9 +2,11 @@ mod marker;
 mod network;
 mod physics;
 mod serialize;
+mod tab_list;
 
 pub use marker::*;
 pub use serialize::*;
+pub use tab_list::*;
 
 pub use feather_core::inventory::Inventory;
 pub use network::{Network, ServerToWorkerMessage, WorkerToServerMessage};
@@ -65,16 +67,71 @@ pub struct ChunkHolder {
     pub holds: AHashSet<ChunkPosition>,
 }
 
+/// Component storing the chunks a player can see: those
+/// within `distance` chunks of `center` along both axes.
+///
+/// The distance is the view distance requested by the
+/// client, limited to the server's view distance.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct View {
+    pub center: ChunkPosition,
+    pub distance: u8,
+}
+
+impl View {
+    /// Returns whether the given chunk is visible.
+    pub fn contains(self, chunk: ChunkPosition) -> bool {
+        let distance = i32::from(self.distance);
+        (chunk.x - self.center.x).abs() <= distance && (chunk.z - self.center.z).abs() <= distance
+    }
+
+    /// Returns all visible chunks.
+    pub fn chunks(self) -> impl Iterator<Item = ChunkPosition> {
+        let distance = i32::from(self.distance);
+        (-distance..=distance).flat_map(move |x| {
+            (-distance..=distance)
+                .map(move |z| ChunkPosition::new(self.center.x + x, self.center.z + z))
+        })
+    }
+}
+
 /// Component containing the last sent positions of all entities for a given client.
 /// This component is used to determine
 /// the relative movement for an entity.
 #[derive(Default, Debug)]
 pub struct LastKnownPositions(pub DashMap<Entity, Position>);
 
-/// Profile properties of a player.
+/// Profile properties of a player, obtained when they were
+/// authenticated or forwarded by a proxy, such as their skin.
 #[derive(Debug, Clone)]
 pub struct ProfileProperties(pub Vec<mojang_api::ProfileProperty>);
 
+impl ProfileProperties {
+    /// Name of the property holding a player's skin and cape.
+    pub const TEXTURES: &'static str = "textures";
+
+    pub fn get(&self, name: &str) -> Option<&mojang_api::ProfileProperty> {
+        self.0.iter().find(|prop| prop.name == name)
+    }
+
+    /// Sets a property, replacing any with the same name.
+    pub fn set(&mut self, prop: mojang_api::ProfileProperty) {
+        self.remove(&prop.name);
+        self.0.push(prop);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<mojang_api::ProfileProperty> {
+        let index = self.0.iter().position(|prop| prop.name == name)?;
+        Some(self.0.remove(index))
+    }
+
+    /// Returns the `textures` property, which holds the base64-encoded
+    /// skin and cape of the player, signed by Mojang.
+    pub fn textures(&self) -> Option<&mojang_api::ProfileProperty> {
+        self.get(Self::TEXTURES)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(transparent)]
 pub struct ParticleCount(pub u32);
@@ -126,7 +183,31 @@ pub struct Health(pub u32);
 #[derive(Copy, Clone, Debug)]
 pub struct MaxHealth(pub u32);
 
+/// The last time an entity was hurt. For a short time after
+/// being hurt, an entity only takes damage exceeding that
+/// of the last hit.
+#[derive(Copy, Clone, Debug)]
+pub struct HurtTime {
+    /// The tick on which the entity was hurt.
+    pub tick: u64,
+    /// The damage dealt by the last hit.
+    pub damage: u32,
+}
+
+impl HurtTime {
+    /// Number of ticks after being hurt during which
+    /// an entity only takes damage exceeding that of the last hit.
+    pub const INVULNERABLE_TICKS: u64 = 10;
+}
+
 /// Stores the number of blocks fallen by an entity
 /// since the last time they were on_ground.
 #[derive(Default, Copy, Clone, Debug)]
 pub struct BlocksFallen(pub f64);
+
+/// Component for players gliding with an elytra. Stores their
+/// horizontal speed as of the last tick, in blocks per tick.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Gliding {
+    pub speed: f64,
+}
@@ -17,6 +17,9 @@ pub struct CanInstaBreak;
 /// A player is allowed to break blocks.
 pub struct CanBreak;
 
+/// A player is sprinting.
+pub struct Sprinting;
+
 /// Marks that a player has teleported and
 /// we should force-update the client's
 /// position.
@@ -35,3 +38,6 @@ pub struct CanRespawn;
 /// Players with this component _should not be affected by gameplay actions_.
 /// They should not collect items, take damage, etc.
 pub struct Dead;
+
+/// A mob which despawns once no player is nearby.
+pub struct CanDespawn;
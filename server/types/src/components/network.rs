@@ -1,9 +1,11 @@
 use feather_core::network::Packet;
+use fecs::{IntoQuery, Read, World};
 use parking_lot::Mutex;
 
 /// Network component containing channels to send and receive packets.
 ///
 /// Systems should call `Network::send` to send a packet to this entity (player).
+/// Packets are buffered and sent together at the end of the tick.
 pub struct Network {
     pub tx: flume::Sender<ServerToWorkerMessage>,
     pub rx: Mutex<flume::Receiver<WorkerToServerMessage>>,
@@ -23,13 +25,42 @@ impl Network {
         // by the server)
         let _ = self.tx.try_send(ServerToWorkerMessage::SendPacket(packet));
     }
+
+    /// Sends a packet to this player immediately, along with
+    /// any packets buffered before it. Should only be used
+    /// for time-sensitive packets, such as keepalives.
+    pub fn send_now(&self, packet: impl Packet) {
+        let _ = self
+            .tx
+            .try_send(ServerToWorkerMessage::SendPacketNow(Box::new(packet)));
+    }
+
+    /// Sends the packets buffered for this player.
+    pub fn flush(&self) {
+        let _ = self.tx.try_send(ServerToWorkerMessage::Flush);
+    }
+}
+
+/// System which sends the packets buffered for
+/// each player during the tick.
+#[fecs::system]
+pub fn flush_packets(world: &mut World) {
+    for network in <Read<Network>>::query().iter(world.inner()) {
+        network.flush();
+    }
 }
 
 /// Message sent from the server threads to a player's
 /// IO task.
 pub enum ServerToWorkerMessage {
-    /// Requests that a packet be sent to the client.
+    /// Requests that a packet be buffered to be sent
+    /// to the client on the next flush.
     SendPacket(Box<dyn Packet>),
+    /// Requests that a packet be sent to the client immediately,
+    /// along with the packets buffered before it.
+    SendPacketNow(Box<dyn Packet>),
+    /// Requests that the buffered packets be sent to the client.
+    Flush,
     /// Requests that the client be disconnected.
     Disconnect,
 }
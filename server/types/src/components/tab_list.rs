@@ -0,0 +1,156 @@
+//! Components controlling how players are shown in the tab list.
+
+use crate::{Name, NetworkId, Player, ProfileProperties, Scoreboard, Uuid};
+use feather_core::network::packets::{PlayerInfo, PlayerInfoAction, TeamInfo, Teams, TeamsAction};
+use feather_core::text::Text;
+use feather_core::util::Gamemode;
+use fecs::{Entity, EntityRef, World};
+
+/// A player's entry in the tab list.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TabListEntry {
+    /// The name shown instead of the player's name.
+    pub display_name: Option<Text>,
+    /// The player's latency in milliseconds, which
+    /// determines the bars shown next to them.
+    pub latency: i32,
+    /// Players are sorted by ascending order, with those
+    /// without an order first. Players with the same
+    /// order are sorted by name.
+    ///
+    /// Ignored for players on a scoreboard team,
+    /// since a player can only be on one team.
+    pub order: Option<i32>,
+    /// Whether the player is left out of the tab list.
+    /// Hidden players are still visible in the world.
+    pub hidden: bool,
+}
+
+impl TabListEntry {
+    /// Returns the name of the team used to sort the player,
+    /// or `None` if the player has no order.
+    ///
+    /// Clients sort the tab list by team name, so the name
+    /// starts with the order in hex, offset so that negative
+    /// orders come first. The network ID makes it unique.
+    pub fn team_name(&self, network_id: NetworkId) -> Option<String> {
+        self.order
+            .map(|order| format!("{:08x}{:08x}", (order as u32) ^ 0x8000_0000, network_id.0))
+    }
+}
+
+/// The header and footer of the tab list shown to a player.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TabListHeaderFooter {
+    pub header: Text,
+    pub footer: Text,
+}
+
+impl Default for TabListHeaderFooter {
+    fn default() -> Self {
+        Self {
+            header: Text::of(""),
+            footer: Text::of(""),
+        }
+    }
+}
+
+/// Returns the `PlayerInfo` packet which adds a player to the tab list.
+///
+/// # Panics
+/// Panics if the entity is not a player.
+pub fn add_player_info(accessor: &EntityRef) -> PlayerInfo {
+    let name = accessor.get::<Name>();
+    let props = accessor
+        .get::<ProfileProperties>()
+        .0
+        .iter()
+        .map(|prop| {
+            (
+                prop.name.clone(),
+                prop.value.clone(),
+                prop.signature.clone(),
+            )
+        })
+        .collect();
+    let entry = accessor.get::<TabListEntry>();
+
+    PlayerInfo {
+        action: PlayerInfoAction::AddPlayer(
+            name.0.clone(),
+            props,
+            *accessor.get::<Gamemode>(),
+            entry.latency,
+            entry.display_name.clone().map(String::from),
+        ),
+        uuid: *accessor.get::<Uuid>(),
+    }
+}
+
+/// Returns the `PlayerInfo` packet to send before spawning
+/// `entity` if it is a player hidden from the tab list.
+///
+/// Clients only spawn players they have info for, so hidden
+/// players are added to the tab list just before they are
+/// spawned and removed again afterwards.
+pub fn hidden_player_info(world: &World, entity: Entity) -> Option<PlayerInfo> {
+    if !world.has::<Player>(entity) {
+        return None;
+    }
+    match world.try_get::<TabListEntry>(entity) {
+        Some(entry) if entry.hidden => (),
+        _ => return None,
+    }
+
+    let accessor = world.entity(entity).expect("entity does not exist");
+    Some(add_player_info(&accessor))
+}
+
+/// Returns the `Teams` packet which creates the team sorting
+/// a player in the tab list, if they have an order and
+/// aren't on a scoreboard team.
+pub fn create_team(accessor: &EntityRef, scoreboard: &Scoreboard) -> Option<Teams> {
+    let name = accessor.get::<Name>();
+    if scoreboard.team_of(&name.0).is_some() {
+        return None;
+    }
+    let team_name = accessor
+        .get::<TabListEntry>()
+        .team_name(*accessor.get::<NetworkId>())?;
+
+    Some(Teams {
+        name: team_name,
+        action: TeamsAction::Create(TeamInfo::default(), vec![name.0.clone()]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn team_names_sort_by_order() {
+        let name = |order| {
+            TabListEntry {
+                order,
+                ..Default::default()
+            }
+            .team_name(NetworkId(5))
+        };
+
+        assert_eq!(name(None), None);
+        assert_eq!(name(Some(0)).unwrap(), "8000000000000005");
+
+        let mut names: Vec<String> = [3, i32::MIN, -1, i32::MAX, 0]
+            .iter()
+            .map(|order| name(Some(*order)).unwrap())
+            .collect();
+        names.sort();
+        let expected: Vec<String> = [i32::MIN, -1, 0, 3, i32::MAX]
+            .iter()
+            .map(|order| name(Some(*order)).unwrap())
+            .collect();
+        assert_eq!(names, expected);
+        assert!(names.iter().all(|name| name.len() <= 16));
+    }
+}
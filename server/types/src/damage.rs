@@ -0,0 +1,267 @@
+//! Sources of damage and the reduction of damage
+//! by armor and protection enchantments.
+//!
+//! See https://minecraft.gamepedia.com/Damage.
+
+use feather_core::inventory::{Area, Inventory};
+use feather_core::items::{Enchantment, ItemStack};
+use fecs::{Entity, World};
+
+/// The armor slots of an entity's inventory.
+const ARMOR_AREAS: [Area; 4] = [Area::Head, Area::Torso, Area::Legs, Area::Feet];
+
+/// Maximum armor points which count towards reducing damage.
+const MAX_ARMOR: f64 = 20.0;
+/// Maximum protection factor from enchantments.
+const MAX_PROTECTION: i32 = 20;
+
+/// Number of ticks an entity keeps burning for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Burning(pub u32);
+
+/// Fire Resistance status effect of an entity,
+/// which makes it immune to fire and lava.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FireResistance;
+
+/// What caused an entity to take damage.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    /// Damage without a more specific cause.
+    Generic,
+    /// Hitting the ground after falling.
+    Fall,
+    /// Burning.
+    Fire,
+    /// Swimming in lava.
+    Lava,
+    /// Falling out of the world.
+    Void,
+    /// Having no food left.
+    Starvation,
+    /// Being stuck inside a block or outside the world border.
+    Suffocation,
+    /// Attacked by a mob.
+    Mob(Entity),
+    /// Attacked by a player.
+    Player(Entity),
+    /// Caught in an explosion, optionally caused by an entity
+    /// such as a player who lit TNT.
+    Explosion(Option<Entity>),
+    /// Struck by lightning.
+    Lightning,
+    /// Flying into a wall while gliding with an elytra.
+    FlyIntoWall,
+    /// Magic, such as the attacks of conduits.
+    Magic,
+    /// Shot by an arrow, optionally fired by an entity.
+    Arrow(Option<Entity>),
+}
+
+impl DamageSource {
+    /// Returns the entity responsible for the damage, if any.
+    pub fn attacker(self) -> Option<Entity> {
+        match self {
+            DamageSource::Mob(entity) | DamageSource::Player(entity) => Some(entity),
+            DamageSource::Explosion(entity) | DamageSource::Arrow(entity) => entity,
+            _ => None,
+        }
+    }
+
+    /// Returns whether armor has no effect on this damage.
+    pub fn bypasses_armor(self) -> bool {
+        matches!(
+            self,
+            DamageSource::Generic
+                | DamageSource::Fall
+                | DamageSource::Fire
+                | DamageSource::Void
+                | DamageSource::Starvation
+                | DamageSource::Suffocation
+                | DamageSource::FlyIntoWall
+                | DamageSource::Magic
+        )
+    }
+
+    /// Returns whether protection enchantments have no effect on this damage.
+    pub fn bypasses_enchantments(self) -> bool {
+        matches!(self, DamageSource::Void | DamageSource::Starvation)
+    }
+
+    /// Returns whether this damage is dealt even to players
+    /// in a gamemode where they can't take damage.
+    pub fn bypasses_invulnerability(self) -> bool {
+        self == DamageSource::Void
+    }
+
+    pub fn is_fire(self) -> bool {
+        matches!(self, DamageSource::Fire | DamageSource::Lava)
+    }
+
+    pub fn is_explosion(self) -> bool {
+        matches!(self, DamageSource::Explosion(_))
+    }
+
+    pub fn is_projectile(self) -> bool {
+        matches!(self, DamageSource::Arrow(_))
+    }
+
+    /// Returns the protection factor given by an enchantment
+    /// on a piece of armor against this damage.
+    pub fn protection_factor(self, enchantment: Enchantment, level: i16) -> i32 {
+        let level = i32::from(level);
+        match enchantment {
+            Enchantment::Protection => level,
+            Enchantment::FireProtection if self.is_fire() => level * 2,
+            Enchantment::FeatherFalling if self == DamageSource::Fall => level * 3,
+            Enchantment::BlastProtection if self.is_explosion() => level * 2,
+            Enchantment::ProjectileProtection if self.is_projectile() => level * 2,
+            _ => 0,
+        }
+    }
+
+    /// Returns the translation key of the death message shown
+    /// when an entity is killed by this damage. The message
+    /// takes the name of the killed entity, followed by the name
+    /// of the attacker if `attacker_named` is set.
+    pub fn death_message_key(self, attacker_named: bool) -> &'static str {
+        match self {
+            DamageSource::Generic => "death.attack.generic",
+            DamageSource::Fall => "death.attack.fall",
+            DamageSource::Fire => "death.attack.onFire",
+            DamageSource::Lava => "death.attack.lava",
+            DamageSource::Void => "death.attack.outOfWorld",
+            DamageSource::Starvation => "death.attack.starve",
+            DamageSource::Suffocation => "death.attack.inWall",
+            DamageSource::Lightning => "death.attack.lightningBolt",
+            DamageSource::FlyIntoWall => "death.attack.flyIntoWall",
+            DamageSource::Magic => "death.attack.magic",
+            DamageSource::Mob(_) if attacker_named => "death.attack.mob",
+            DamageSource::Player(_) if attacker_named => "death.attack.player",
+            DamageSource::Explosion(_) if attacker_named => "death.attack.explosion.player",
+            DamageSource::Explosion(_) => "death.attack.explosion",
+            DamageSource::Arrow(_) if attacker_named => "death.attack.arrow",
+            DamageSource::Mob(_) | DamageSource::Player(_) | DamageSource::Arrow(_) => {
+                "death.attack.generic"
+            }
+        }
+    }
+}
+
+/// Returns the damage left after armor with the given
+/// armor points and toughness absorbs some of it.
+pub fn damage_after_armor(damage: f64, armor: f64, toughness: f64) -> f64 {
+    let absorbed = (armor - damage / (2.0 + toughness / 4.0))
+        .max(armor * 0.2)
+        .min(MAX_ARMOR);
+    damage * (1.0 - absorbed / 25.0)
+}
+
+/// Returns the damage left after protection enchantments
+/// with the given total protection factor.
+pub fn damage_after_protection(damage: f64, protection: i32) -> f64 {
+    let protection = protection.max(0).min(MAX_PROTECTION);
+    damage * (1.0 - f64::from(protection) / 25.0)
+}
+
+/// Returns the armor worn by an entity.
+pub fn worn_armor(world: &World, entity: Entity) -> Vec<ItemStack> {
+    match world.try_get::<Inventory>(entity) {
+        Some(inventory) => ARMOR_AREAS
+            .iter()
+            .filter_map(|area| inventory.item_at(*area, 0).ok().flatten())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the damage an entity takes from the given source
+/// after it is reduced by the entity's armor and its enchantments.
+pub fn reduce_damage(world: &World, entity: Entity, damage: u32, source: DamageSource) -> u32 {
+    if source.is_fire() && world.has::<FireResistance>(entity) {
+        return 0;
+    }
+
+    let armor = worn_armor(world, entity);
+    let mut damage = f64::from(damage);
+
+    if !source.bypasses_armor() {
+        let points: u32 = armor
+            .iter()
+            .filter_map(|stack| stack.ty.armor_points())
+            .sum();
+        let toughness: f64 = armor
+            .iter()
+            .filter_map(|stack| stack.ty.armor_toughness())
+            .sum();
+        damage = damage_after_armor(damage, f64::from(points), toughness);
+    }
+
+    if !source.bypasses_enchantments() {
+        let protection = armor
+            .iter()
+            .flat_map(|stack| stack.enchantments.iter())
+            .map(|(enchantment, level)| source.protection_factor(enchantment, level))
+            .sum();
+        damage = damage_after_protection(damage, protection);
+    }
+
+    damage.round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armor_reduction() {
+        // No armor
+        assert_eq!(damage_after_armor(10.0, 0.0, 0.0), 10.0);
+        // Full iron armor absorbs most of small hits...
+        assert!((damage_after_armor(2.0, 15.0, 0.0) - 0.88).abs() < 1e-9);
+        // ...but little of large ones.
+        assert!((damage_after_armor(20.0, 15.0, 0.0) - 16.0).abs() < 1e-9);
+        // Toughness makes armor effective against large hits.
+        assert!(damage_after_armor(20.0, 20.0, 8.0) < damage_after_armor(20.0, 20.0, 0.0));
+    }
+
+    #[test]
+    fn protection_reduction() {
+        assert_eq!(damage_after_protection(10.0, 0), 10.0);
+        assert!((damage_after_protection(10.0, 5) - 8.0).abs() < 1e-9);
+        // Capped at 80%
+        assert!((damage_after_protection(10.0, 50) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn protection_factor() {
+        let fall = DamageSource::Fall;
+        assert_eq!(fall.protection_factor(Enchantment::FeatherFalling, 4), 12);
+        assert_eq!(fall.protection_factor(Enchantment::Protection, 4), 4);
+        assert_eq!(fall.protection_factor(Enchantment::FireProtection, 4), 0);
+        assert_eq!(
+            DamageSource::Lava.protection_factor(Enchantment::FireProtection, 2),
+            4
+        );
+        assert_eq!(
+            DamageSource::Explosion(None).protection_factor(Enchantment::BlastProtection, 3),
+            6
+        );
+        assert_eq!(
+            DamageSource::Arrow(None).protection_factor(Enchantment::ProjectileProtection, 4),
+            8
+        );
+    }
+
+    #[test]
+    fn death_message_keys() {
+        assert_eq!(
+            DamageSource::Void.death_message_key(false),
+            "death.attack.outOfWorld"
+        );
+        assert_eq!(
+            DamageSource::Explosion(None).death_message_key(false),
+            "death.attack.explosion"
+        );
+    }
+}
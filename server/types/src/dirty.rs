@@ -0,0 +1,202 @@
+//! Tracking of changes to chunks.
+//!
+//! Each world records which chunks have changed since they
+//! were last saved, and which blocks and sections have changed
+//! since they were last sent to clients. Autosave uses the former
+//! to skip unchanged chunks; the latter lets block changes made
+//! during a tick be sent together at the end of the tick.
+
+use ahash::AHashMap;
+use feather_core::util::{BlockPosition, ChunkPosition};
+
+/// The maximum number of changed blocks tracked in a chunk.
+/// When more blocks change, the sections containing them are
+/// sent again instead, as that is cheaper for both sides.
+pub const MAX_TRACKED_BLOCKS: usize = 64;
+
+/// The changes to a chunk which haven't yet been sent to clients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockChanges {
+    /// The positions of the changed blocks, in the order
+    /// they were first changed.
+    Blocks(Vec<BlockPosition>),
+    /// A bitmask of the sections containing the changed blocks,
+    /// used once more than `MAX_TRACKED_BLOCKS` blocks have changed.
+    Sections(u16),
+}
+
+#[derive(Debug, Default)]
+struct DirtyChunk {
+    /// Bitmask of sections with changes not yet sent to clients.
+    sections: u16,
+    /// Changed blocks not yet sent to clients. Cleared
+    /// once more than `MAX_TRACKED_BLOCKS` have changed.
+    blocks: Vec<BlockPosition>,
+    overflowed: bool,
+    /// Whether the chunk has changed since it was last saved.
+    unsaved: bool,
+}
+
+impl DirtyChunk {
+    fn is_clean(&self) -> bool {
+        self.sections == 0 && !self.unsaved
+    }
+}
+
+/// The chunks in a world with changes not yet saved
+/// or not yet sent to clients.
+#[derive(Debug, Default)]
+pub struct DirtyChunks {
+    chunks: AHashMap<ChunkPosition, DirtyChunk>,
+}
+
+impl DirtyChunks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks a block as changed. The position must be
+    /// within the vertical bounds of the world.
+    ///
+    /// The section mask only covers Y 0 to 255, so blocks
+    /// outside that range mark every section as changed.
+    pub fn mark_block(&mut self, pos: BlockPosition) {
+        let dirty = self.chunks.entry(pos.chunk()).or_default();
+        dirty.unsaved = true;
+        dirty.sections |= if (0..256).contains(&pos.y) {
+            1 << (pos.y / 16)
+        } else {
+            u16::MAX
+        };
+
+        if dirty.overflowed || dirty.blocks.contains(&pos) {
+            return;
+        }
+        if dirty.blocks.len() == MAX_TRACKED_BLOCKS {
+            dirty.blocks = Vec::new();
+            dirty.overflowed = true;
+        } else {
+            dirty.blocks.push(pos);
+        }
+    }
+
+    /// Marks a chunk as needing to be saved, e.g.
+    /// because the entities inside it changed.
+    pub fn mark_unsaved(&mut self, chunk: ChunkPosition) {
+        self.chunks.entry(chunk).or_default().unsaved = true;
+    }
+
+    /// Returns whether a chunk has changed since the last
+    /// call to this function, then marks it as saved.
+    pub fn take_unsaved(&mut self, chunk: ChunkPosition) -> bool {
+        let dirty = match self.chunks.get_mut(&chunk) {
+            Some(dirty) => dirty,
+            None => return false,
+        };
+        let unsaved = dirty.unsaved;
+        dirty.unsaved = false;
+
+        if dirty.is_clean() {
+            self.chunks.remove(&chunk);
+        }
+        unsaved
+    }
+
+    /// Returns the block changes in each chunk since the last
+    /// call to this function. Whether the chunks have been
+    /// saved is unaffected.
+    pub fn take_block_changes(&mut self) -> Vec<(ChunkPosition, BlockChanges)> {
+        let mut changes = Vec::new();
+        for (&chunk, dirty) in &mut self.chunks {
+            if dirty.sections == 0 {
+                continue;
+            }
+
+            let change = if dirty.overflowed {
+                BlockChanges::Sections(dirty.sections)
+            } else {
+                BlockChanges::Blocks(std::mem::take(&mut dirty.blocks))
+            };
+            changes.push((chunk, change));
+
+            dirty.sections = 0;
+            dirty.blocks.clear();
+            dirty.overflowed = false;
+        }
+
+        self.chunks.retain(|_, dirty| !dirty.is_clean());
+        changes
+    }
+
+    /// Stops tracking a chunk, e.g. because it was unloaded.
+    pub fn remove(&mut self, chunk: ChunkPosition) {
+        self.chunks.remove(&chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_changes() {
+        let mut dirty = DirtyChunks::new();
+        dirty.mark_block(BlockPosition::new(1, 2, 3));
+        dirty.mark_block(BlockPosition::new(1, 40, 3));
+        dirty.mark_block(BlockPosition::new(1, 2, 3));
+        dirty.mark_block(BlockPosition::new(16, 2, 3));
+
+        let mut changes = dirty.take_block_changes();
+        changes.sort_by_key(|(chunk, _)| chunk.x);
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    ChunkPosition::new(0, 0),
+                    BlockChanges::Blocks(vec![
+                        BlockPosition::new(1, 2, 3),
+                        BlockPosition::new(1, 40, 3)
+                    ])
+                ),
+                (
+                    ChunkPosition::new(1, 0),
+                    BlockChanges::Blocks(vec![BlockPosition::new(16, 2, 3)])
+                ),
+            ]
+        );
+        assert!(dirty.take_block_changes().is_empty());
+
+        // Sending changes doesn't count as saving.
+        assert!(dirty.take_unsaved(ChunkPosition::new(0, 0)));
+        assert!(!dirty.take_unsaved(ChunkPosition::new(0, 0)));
+    }
+
+    #[test]
+    fn too_many_blocks() {
+        let mut dirty = DirtyChunks::new();
+        for y in 0..=MAX_TRACKED_BLOCKS as i32 {
+            dirty.mark_block(BlockPosition::new(0, y, 0));
+        }
+
+        assert_eq!(
+            dirty.take_block_changes(),
+            vec![(ChunkPosition::new(0, 0), BlockChanges::Sections(0b1_1111))]
+        );
+    }
+
+    #[test]
+    fn unsaved() {
+        let mut dirty = DirtyChunks::new();
+        let chunk = ChunkPosition::new(-3, 5);
+        assert!(!dirty.take_unsaved(chunk));
+
+        dirty.mark_unsaved(chunk);
+        assert!(dirty.take_block_changes().is_empty());
+        assert!(dirty.take_unsaved(chunk));
+        assert!(!dirty.take_unsaved(chunk));
+
+        dirty.mark_block(BlockPosition::new(-48, 0, 80));
+        dirty.remove(chunk);
+        assert!(!dirty.take_unsaved(chunk));
+    }
+}
@@ -0,0 +1,116 @@
+//! Sounds and particles played at a position in a world.
+
+use crate::{Game, WorldId};
+use feather_core::misc::ParticleData;
+use feather_core::network::packets::{NamedSoundEffect, Particle, SoundCategory};
+use feather_core::util::Position;
+use fecs::World;
+
+/// A sound effect, identified by its name,
+/// e.g. `entity.item.break`.
+#[derive(Clone, Debug)]
+pub struct Sound {
+    pub name: String,
+    pub category: SoundCategory,
+    /// Volume, where 1.0 is 100%. Louder sounds
+    /// can be heard from further away.
+    pub volume: f32,
+    /// Pitch, between 0.5 and 2.0.
+    pub pitch: f32,
+}
+
+impl Sound {
+    pub fn new(name: impl Into<String>, category: SoundCategory) -> Self {
+        Self {
+            name: name.into(),
+            category,
+            volume: 1.0,
+            pitch: 1.0,
+        }
+    }
+
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Returns the packet playing this sound at a position.
+    pub fn packet(&self, pos: Position) -> NamedSoundEffect {
+        NamedSoundEffect {
+            sound_name: self.name.clone(),
+            sound_category: self.category as i32,
+            // https://wiki.vg/Data_types#Fixed-point_numbers
+            effect_pos_x: (pos.x * 8.0) as i32,
+            effect_pos_y: (pos.y * 8.0) as i32,
+            effect_pos_z: (pos.z * 8.0) as i32,
+            volume: self.volume,
+            pitch: self.pitch,
+        }
+    }
+}
+
+impl Game {
+    /// Plays a sound at a position, heard by all players
+    /// able to see the chunk containing it.
+    pub fn play_sound(&self, world: &World, world_id: WorldId, pos: Position, sound: &Sound) {
+        self.broadcast_chunk_update(world, sound.packet(pos), world_id, pos.chunk(), None);
+    }
+
+    /// Spawns particles at a position, shown to all players
+    /// able to see the chunk containing it.
+    ///
+    /// The particles are spread randomly around the
+    /// position by up to `offset` along each axis.
+    pub fn spawn_particle(
+        &self,
+        world: &World,
+        world_id: WorldId,
+        pos: Position,
+        particle: ParticleData,
+        count: u32,
+        offset: glm::Vec3,
+    ) {
+        let packet = Particle {
+            long_distance: false,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            offset_x: offset.x,
+            offset_y: offset.y,
+            offset_z: offset.z,
+            particle_data: 0.0,
+            particle_count: count as i32,
+            data: particle,
+        };
+        self.broadcast_chunk_update(world, packet, world_id, pos.chunk(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::position;
+
+    #[test]
+    fn sound_packet() {
+        let packet = Sound::new("block.note_block.harp", SoundCategory::Records)
+            .pitch(0.5)
+            .packet(position!(1.5, 64.0, -2.25));
+        assert_eq!(packet.sound_name, "block.note_block.harp");
+        assert_eq!(packet.sound_category, SoundCategory::Records as i32);
+        assert_eq!(
+            (
+                packet.effect_pos_x,
+                packet.effect_pos_y,
+                packet.effect_pos_z
+            ),
+            (12, 512, -18)
+        );
+        assert!((packet.pitch - 0.5).abs() < f32::EPSILON);
+    }
+}
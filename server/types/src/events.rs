@@ -1,13 +1,18 @@
-use crate::Weather;
+use crate::{
+    BlockTickKind, DamageSource, ResourcePackStatus, Ticket, TicketKind, View, Weather, WorldId,
+};
 use feather_core::blocks::BlockId;
 use feather_core::inventory::SlotIndex;
 use feather_core::items::ItemStack;
+use feather_core::network::packets::Face;
 use feather_core::util::{BlockPosition, ChunkPosition, ClientboundAnimation, Gamemode, Position};
 use fecs::Entity;
 use smallvec::SmallVec;
 
 #[derive(Copy, Clone, Debug)]
 pub struct BlockUpdateEvent {
+    /// The world containing the block.
+    pub world: WorldId,
     /// Position of the updated block
     pub pos: BlockPosition,
     /// Old block
@@ -26,10 +31,33 @@ pub enum BlockUpdateCause {
     /// So far only when a block that needs to be
     /// supported loses it's support.
     Unsupported,
+    /// The block changed on its own when it was
+    /// ticked, e.g. crops growing or leaves decaying.
+    Tick,
+    /// The block was powered or unpowered by redstone.
+    Redstone,
+    /// The block was moved by a piston.
+    Piston,
+    /// The block burnt away or caught fire.
+    Fire,
+    /// The block was blown up by an explosion.
+    Explosion,
     /// Unknown cause.
     Unknown,
 }
 
+/// Triggered when a block is ticked, either because
+/// a tick scheduled for it is due or by a random tick.
+#[derive(Copy, Clone, Debug)]
+pub struct BlockTickEvent {
+    /// The world containing the block.
+    pub world: WorldId,
+    pub pos: BlockPosition,
+    /// The block being ticked.
+    pub block: BlockId,
+    pub kind: BlockTickKind,
+}
+
 /// Triggered directly _before_ an entity is removed from the world.
 ///
 /// As such, components can still be accessed.
@@ -45,11 +73,14 @@ pub struct EntityDespawnEvent {
 #[derive(Copy, Clone, Debug)]
 pub struct EntityDeathEvent {
     pub entity: Entity,
+    /// What killed the entity.
+    pub source: DamageSource,
 }
 
 /// Triggered when a chunk is sent to a player.
 #[derive(Copy, Clone, Debug)]
 pub struct ChunkSendEvent {
+    pub world: WorldId,
     pub chunk: ChunkPosition,
     pub player: Entity,
 }
@@ -66,6 +97,13 @@ pub struct PlayerJoinEvent {
     pub player: Entity,
 }
 
+/// Triggered when a dead player clicks the respawn
+/// button, after they have been moved to their spawn point.
+#[derive(Copy, Clone, Debug)]
+pub struct PlayerRespawnEvent {
+    pub player: Entity,
+}
+
 /// Triggered when a player leaves.
 #[derive(Copy, Clone, Debug)]
 pub struct PlayerLeaveEvent {
@@ -109,6 +147,43 @@ pub struct ItemCollectEvent {
     pub amount: u8,
 }
 
+/// Event triggered when a player uses an item which
+/// doesn't place a block on a block, such as bone
+/// meal on a crop.
+#[derive(Debug, Clone)]
+pub struct ItemUseOnBlockEvent {
+    /// The player using the item.
+    pub player: Entity,
+    pub world: WorldId,
+    /// Position of the block the item was used on.
+    pub pos: BlockPosition,
+    /// The face of the block the item was used on.
+    pub face: Face,
+    /// The used item stack, held in the player's main hand.
+    pub stack: ItemStack,
+}
+
+/// Event triggered when a player right clicks an entity
+/// other than a villager, such as a mob or a leash knot.
+#[derive(Debug, Clone)]
+pub struct EntityInteractEvent {
+    /// The player interacting with the entity.
+    pub player: Entity,
+    /// The entity which was right clicked.
+    pub target: Entity,
+    /// The item stack held in the player's main hand, if any.
+    pub stack: Option<ItemStack>,
+}
+
+/// Event triggered when an item frame is placed or removed,
+/// or when the item it holds or its rotation changes.
+#[derive(Copy, Clone, Debug)]
+pub struct ItemFrameUpdateEvent {
+    pub world: WorldId,
+    /// Position of the block the item frame occupies.
+    pub pos: BlockPosition,
+}
+
 /// Event which is triggered when an entity's inventory
 /// is updated.
 ///
@@ -180,6 +255,17 @@ pub struct HealthUpdateEvent {
     pub entity: Entity,
 }
 
+/// Event triggered when a player's experience is updated.
+#[derive(Copy, Clone, Debug)]
+pub struct ExperienceUpdateEvent {
+    /// The player whose experience was updated.
+    pub player: Entity,
+    /// The player's level before the update.
+    pub old_level: u32,
+    /// The player's level after the update.
+    pub new_level: u32,
+}
+
 /// Event triggered when a player performs an animation (hits with their hand).
 #[derive(Copy, Clone, Debug)]
 pub struct PlayerAnimationEvent {
@@ -215,6 +301,18 @@ pub struct ChunkCrossEvent {
     pub new: ChunkPosition,
 }
 
+/// Event triggered when the chunks a player can see change,
+/// either because it crossed into another chunk or
+/// because its view distance changed.
+///
+/// `old` is `None` when the player has just joined.
+#[derive(Copy, Clone, Debug)]
+pub struct ViewUpdateEvent {
+    pub player: Entity,
+    pub old: Option<View>,
+    pub new: View,
+}
+
 /// Event triggered when an entity is sent to a client.
 ///
 /// This can be used to send additional packets along with the Spawn *
@@ -244,12 +342,14 @@ pub struct EntityClientRemoveEvent {
 /// Event triggered when a chunk is loaded.
 #[derive(Copy, Clone, Debug)]
 pub struct ChunkLoadEvent {
+    pub world: WorldId,
     pub chunk: ChunkPosition,
 }
 
 /// Event which is triggered when a chunk fails to load.
 #[derive(Debug)]
 pub struct ChunkLoadFailEvent {
+    pub world: WorldId,
     pub pos: ChunkPosition,
     pub error: anyhow::Error,
 }
@@ -257,12 +357,15 @@ pub struct ChunkLoadFailEvent {
 /// Event triggeered when a chunk is unloaded.
 #[derive(Copy, Clone, Debug)]
 pub struct ChunkUnloadEvent {
+    pub world: WorldId,
     pub chunk: ChunkPosition,
 }
 
 /// Event triggered when a chunk holder releases their hold on a chunk.
 #[derive(Copy, Clone, Debug)]
 pub struct ChunkHolderReleaseEvent {
+    /// The world containing the chunk.
+    pub world: WorldId,
     /// Entity which released their hold.
     pub entity: Entity,
     /// The chunk which was released.
@@ -285,6 +388,31 @@ pub struct GamemodeUpdateEvent {
     pub new: Gamemode,
 }
 
+/// Triggered when a player sends a plugin message on
+/// a channel registered in `Game::plugin_channels`.
+#[derive(Clone, Debug)]
+pub struct PluginMessageEvent {
+    pub player: Entity,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+/// Triggered when a player reports the status
+/// of a resource pack sent to them.
+#[derive(Copy, Clone, Debug)]
+pub struct ResourcePackStatusEvent {
+    pub player: Entity,
+    pub status: ResourcePackStatus,
+}
+
+/// Triggered when recipes are unlocked for a player.
+#[derive(Clone, Debug)]
+pub struct RecipeUnlockEvent {
+    pub player: Entity,
+    /// IDs of the recipes, e.g. `minecraft:furnace`.
+    pub recipes: Vec<String>,
+}
+
 /// Requests that a chunk be held for the given client.
 ///
 /// This is a "request"-type event: it has one handler defined
@@ -292,6 +420,7 @@ pub struct GamemodeUpdateEvent {
 #[derive(Copy, Clone, Debug)]
 pub struct HoldChunkRequest {
     pub player: Entity,
+    pub world: WorldId,
     pub chunk: ChunkPosition,
 }
 
@@ -299,11 +428,49 @@ pub struct HoldChunkRequest {
 #[derive(Copy, Clone, Debug)]
 pub struct ReleaseChunkRequest {
     pub player: Entity,
+    pub world: WorldId,
     pub chunk: ChunkPosition,
 }
 
 /// Requests that a chunk be queued for loading.
 #[derive(Copy, Clone, Debug)]
 pub struct LoadChunkRequest {
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+}
+
+/// Requests that a ticket be added to a chunk,
+/// loading the chunks around it.
+#[derive(Copy, Clone, Debug)]
+pub struct AddTicketRequest {
+    pub world: WorldId,
+    pub chunk: ChunkPosition,
+    pub ticket: Ticket,
+}
+
+/// Requests that a ticket be removed from a chunk,
+/// unloading the chunks no other ticket keeps loaded.
+#[derive(Copy, Clone, Debug)]
+pub struct RemoveTicketRequest {
+    pub world: WorldId,
     pub chunk: ChunkPosition,
+    pub kind: TicketKind,
+}
+
+/// Triggered when a world is created with `Game::create_world`.
+#[derive(Copy, Clone, Debug)]
+pub struct WorldCreateEvent {
+    pub world: WorldId,
+}
+
+/// Triggered after an entity has moved to another world
+/// with `Game::change_world`. Its `WorldId` and `Position`
+/// components already refer to the new world.
+#[derive(Copy, Clone, Debug)]
+pub struct WorldChangeEvent {
+    pub entity: Entity,
+    pub old: WorldId,
+    /// The entity's position in the old world.
+    pub old_position: Position,
+    pub new: WorldId,
 }
@@ -0,0 +1,288 @@
+//! Experience points and levels of players.
+//!
+//! Collecting experience orbs fills a player's experience bar,
+//! and each time it is full the player gains a level. Levels
+//! are spent on enchanting and working on items in anvils.
+//!
+//! See https://minecraft.gamepedia.com/Experience.
+
+use crate::{ExperienceUpdateEvent, Game};
+use fecs::{Entity, World};
+use rand::Rng;
+
+/// Sizes of the experience orbs which experience
+/// is split into, from largest to smallest.
+const ORB_SIZES: [u32; 11] = [2477, 1237, 617, 307, 149, 73, 37, 17, 7, 3, 1];
+
+/// Maximum number of bookshelves around an enchanting
+/// table which increase the cost of enchantments.
+pub const MAX_BOOKSHELVES: u32 = 15;
+
+/// Experience of a player.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Experience {
+    pub level: u32,
+    /// Progress towards the next level, between 0 and 1.
+    pub progress: f32,
+    /// Total experience points collected since the player
+    /// last died, shown as their score on the death screen.
+    pub total: u32,
+    /// Seed of the enchantments offered by enchanting tables,
+    /// which changes each time the player enchants an item.
+    pub seed: i32,
+}
+
+impl Experience {
+    /// Returns the number of experience points needed
+    /// to get from the given level to the next one.
+    pub fn points_to_next_level(level: u32) -> u32 {
+        if level >= 30 {
+            112 + (level - 30) * 9
+        } else if level >= 15 {
+            37 + (level - 15) * 5
+        } else {
+            7 + level * 2
+        }
+    }
+
+    /// Returns the number of experience points needed
+    /// to get from level 0 to the given level.
+    pub fn points_for_level(level: u32) -> u32 {
+        (0..level).map(Self::points_to_next_level).sum()
+    }
+
+    /// Returns the number of experience points
+    /// collected towards the next level.
+    pub fn points_in_level(&self) -> u32 {
+        (self.progress * Self::points_to_next_level(self.level) as f32).round() as u32
+    }
+
+    /// Adds experience points, gaining a level each time the
+    /// experience bar is full. Negative points are taken away,
+    /// losing a level each time the experience bar is empty.
+    pub fn add_points(&mut self, points: i32) {
+        self.total = (i64::from(self.total) + i64::from(points)).max(0) as u32;
+        self.progress += points as f32 / Self::points_to_next_level(self.level) as f32;
+
+        while self.progress >= 1.0 {
+            let points_left = (self.progress - 1.0) * Self::points_to_next_level(self.level) as f32;
+            self.level += 1;
+            self.progress = points_left / Self::points_to_next_level(self.level) as f32;
+        }
+        while self.progress < 0.0 {
+            if self.level == 0 {
+                self.progress = 0.0;
+                break;
+            }
+            let points_missing = self.progress * Self::points_to_next_level(self.level) as f32;
+            self.level -= 1;
+            self.progress = 1.0 + points_missing / Self::points_to_next_level(self.level) as f32;
+        }
+    }
+
+    /// Adds levels, or removes them if `levels` is negative. Removing
+    /// more levels than the player has empties their experience bar.
+    pub fn add_levels(&mut self, levels: i32) {
+        let level = i64::from(self.level) + i64::from(levels);
+        if level < 0 {
+            self.level = 0;
+            self.progress = 0.0;
+            self.total = 0;
+        } else {
+            self.level = level as u32;
+        }
+    }
+
+    /// Sets the points collected towards the next level.
+    ///
+    /// Returns `false` if there are too many points to
+    /// fit in the experience bar of the current level.
+    pub fn set_points_in_level(&mut self, points: u32) -> bool {
+        let needed = Self::points_to_next_level(self.level);
+        if points >= needed {
+            return false;
+        }
+        self.progress = points as f32 / needed as f32;
+        true
+    }
+
+    /// Returns whether the player has at least the given number of levels.
+    pub fn has_levels(&self, levels: u32) -> bool {
+        self.level >= levels
+    }
+
+    /// Returns the number of experience points
+    /// dropped when the player dies.
+    pub fn dropped_on_death(&self) -> u32 {
+        (self.level * 7).min(100)
+    }
+}
+
+/// Splits experience points into the sizes of the orbs which drop them.
+pub fn split_experience(mut points: u32) -> Vec<u32> {
+    let mut orbs = Vec::new();
+    while points > 0 {
+        let size = ORB_SIZES
+            .iter()
+            .copied()
+            .find(|size| *size <= points)
+            .unwrap_or(1);
+        orbs.push(size);
+        points -= size;
+    }
+    orbs
+}
+
+/// Returns the number of levels required to pick the enchantment in the
+/// given slot (0 to 2) offered by an enchanting table surrounded
+/// by the given number of bookshelves.
+pub fn enchantment_cost(rng: &mut impl Rng, slot: usize, bookshelves: u32) -> u32 {
+    let bookshelves = bookshelves.min(MAX_BOOKSHELVES);
+    let base = rng.gen_range(1, 9) + bookshelves / 2 + rng.gen_range(0, bookshelves + 1);
+    match slot {
+        0 => (base / 3).max(1),
+        1 => base * 2 / 3 + 1,
+        _ => base.max(bookshelves * 2),
+    }
+}
+
+impl Game {
+    /// Gives experience points to a player, or takes
+    /// them away if `points` is negative.
+    pub fn give_experience(&mut self, world: &mut World, player: Entity, points: i32) {
+        self.update_experience(world, player, |experience| experience.add_points(points));
+    }
+
+    /// Gives levels to a player, or takes them away if `levels` is negative.
+    pub fn add_experience_levels(&mut self, world: &mut World, player: Entity, levels: i32) {
+        self.update_experience(world, player, |experience| experience.add_levels(levels));
+    }
+
+    /// Changes the experience of a player using the given function,
+    /// triggering an `ExperienceUpdateEvent`.
+    pub fn update_experience(
+        &mut self,
+        world: &mut World,
+        player: Entity,
+        f: impl FnOnce(&mut Experience),
+    ) {
+        let (old_level, new_level) = match world.try_get_mut::<Experience>(player) {
+            Some(mut experience) => {
+                let old_level = experience.level;
+                f(&mut *experience);
+                (old_level, experience.level)
+            }
+            None => return,
+        };
+
+        self.handle(
+            world,
+            ExperienceUpdateEvent {
+                player,
+                old_level,
+                new_level,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn level_thresholds() {
+        assert_eq!(Experience::points_to_next_level(0), 7);
+        assert_eq!(Experience::points_to_next_level(15), 37);
+        assert_eq!(Experience::points_to_next_level(30), 112);
+        assert_eq!(Experience::points_for_level(16), 352);
+        assert_eq!(Experience::points_for_level(31), 1507);
+    }
+
+    #[test]
+    fn add_points_levels_up() {
+        let mut experience = Experience::default();
+        experience.add_points(10);
+        assert_eq!(experience.level, 1);
+        assert_eq!(experience.points_in_level(), 3);
+        assert_eq!(experience.total, 10);
+
+        experience.add_points(Experience::points_for_level(30) as i32);
+        assert_eq!(experience.level, 30);
+    }
+
+    #[test]
+    fn remove_points_levels_down() {
+        let mut experience = Experience::default();
+        experience.add_points(10);
+        experience.add_points(-5);
+        assert_eq!(experience.level, 0);
+        assert_eq!(experience.points_in_level(), 5);
+        assert_eq!(experience.total, 5);
+
+        experience.add_points(-100);
+        assert_eq!(experience.level, 0);
+        assert_eq!(experience.progress, 0.0);
+        assert_eq!(experience.total, 0);
+    }
+
+    #[test]
+    fn add_levels_keeps_progress() {
+        let mut experience = Experience::default();
+        experience.add_points(3);
+        experience.add_levels(5);
+        assert_eq!(experience.level, 5);
+        assert!(experience.progress > 0.0);
+
+        experience.add_levels(-3);
+        assert_eq!(experience.level, 2);
+
+        experience.add_levels(-10);
+        assert_eq!(experience, Experience::default());
+    }
+
+    #[test]
+    fn set_points_in_level() {
+        let mut experience = Experience {
+            level: 2,
+            ..Default::default()
+        };
+        assert!(experience.set_points_in_level(5));
+        assert_eq!(experience.points_in_level(), 5);
+        assert!(!experience.set_points_in_level(11));
+    }
+
+    #[test]
+    fn dropped_on_death() {
+        let experience = Experience {
+            level: 3,
+            ..Default::default()
+        };
+        assert_eq!(experience.dropped_on_death(), 21);
+        let experience = Experience {
+            level: 40,
+            ..Default::default()
+        };
+        assert_eq!(experience.dropped_on_death(), 100);
+    }
+
+    #[test]
+    fn split_into_orbs() {
+        assert_eq!(split_experience(0), Vec::<u32>::new());
+        assert_eq!(split_experience(10), vec![7, 3]);
+        assert_eq!(split_experience(100), vec![73, 17, 7, 3]);
+    }
+
+    #[test]
+    fn enchantment_costs() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let top = enchantment_cost(&mut rng, 0, 0);
+            assert!((1..=2).contains(&top));
+            // Bookshelves beyond the 15th don't count.
+            assert_eq!(enchantment_cost(&mut rng, 2, 30), 30);
+        }
+    }
+}
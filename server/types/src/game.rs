@@ -1,26 +1,33 @@
-use crate::{BlockUpdateCause, Network, ServerToWorkerMessage};
 use crate::{
-    BlockUpdateEvent, CanRespawn, Dead, EntityDeathEvent, EntityDespawnEvent, Health,
-    HealthUpdateEvent, Name, PlayerLeaveEvent,
+    AdvancementRegistry, BlockUpdateCause, Maps, Network, OnlinePlayers, PluginChannels,
+    RecipeRegistry, Scoreboard, ServerToWorkerMessage, Uuid,
+};
+use crate::{
+    BlockUpdateEvent, CanRespawn, CanTakeDamage, DamageSource, Dead, EntityDeathEvent,
+    EntityDespawnEvent, Health, HealthUpdateEvent, HurtTime, MaxHealth, Name, Player,
+    PlayerLeaveEvent, PreviousPosition, WeatherStrength, WorldChangeEvent, WorldCreateEvent,
+    WorldId, Worlds,
 };
 use ahash::AHashMap;
 use bumpalo::Bump;
 use feather_core::anvil::level::LevelData;
+use feather_core::biomes::Precipitation;
 use feather_core::blocks::BlockId;
-use feather_core::chunk_map::ChunkMap;
+use feather_core::dimension::DimensionTypes;
 use feather_core::game_rules::GameRules;
 use feather_core::network::{packets::DisconnectPlay, Packet};
 use feather_core::text::Text;
-use feather_core::util::{BlockPosition, ChunkPosition, Position};
+use feather_core::util::{BlockPosition, ChunkPosition, Dimension, Position};
 use feather_server_config::Config;
+use feather_server_worldgen::WorldGenerator;
 use fecs::{Entity, Event, EventHandlers, IntoQuery, OwnedResources, Read, RefResources, World};
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 use smallvec::SmallVec;
 use std::cell::{RefCell, RefMut};
 use std::fmt::Display;
-use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::ops::Deref;
+use std::path::PathBuf;
 use std::sync::Arc;
 use thread_local::CachedThreadLocal;
 
@@ -31,31 +38,23 @@ pub struct Shared {
     pub config: Arc<Config>,
     /// General-purpose, non-cryptographic random number generator
     pub rng: CachedThreadLocal<RefCell<SmallRng>>,
-    /// The server player count.
-    pub player_count: Arc<AtomicU32>, // fixme: double Arc
+    /// The players online on the server.
+    pub online_players: Arc<OnlinePlayers>,
 }
 
 /// The `Game` resource, which acts as a central bus to bind together
 /// the feather-server-* crates. Resources which are accessed frequently,
 /// such as the chunk map, are stored in here.
 pub struct Game {
-    pub chunk_map: ChunkMap,
+    /// The worlds running on the server.
+    pub worlds: Worlds,
     /// Number of ticks since the program started. Can be used
     /// to make a system which only runs at a fixed interval.
     pub tick_count: u64,
-    /// Stores entities which have a hold on chunks,
-    /// preventing the chunk from being unloaded.
-    pub chunk_holders: ChunkHolders,
-    /// Block entity map. Each `BlockPosition` may have a block
-    /// entity associated with it.
-    pub block_entities: AHashMap<BlockPosition, Entity>,
-    /// The level data.
+    /// The level data of the main world.
     pub level: LevelData,
-    /// Associates chunks with the entities that reside in them. Used
-    /// as an acceleration structure for spacial lookups.
-    pub chunk_entities: ChunkEntities,
-    /// World time, in the Minecraft way.
-    pub time: Time,
+    /// How strong the rain and thunder currently are.
+    pub weather_strength: WeatherStrength,
     /// The event handler map.
     pub event_handlers: Arc<EventHandlers>,
     /// Resources other than `Game`, used to run event handlers.
@@ -66,6 +65,18 @@ pub struct Game {
     pub shared: Arc<Shared>,
     /// Gamrules
     pub game_rules: GameRules,
+    /// Dimension types, including those loaded from data packs.
+    pub dimension_types: DimensionTypes,
+    /// The scoreboard's objectives, scores and teams.
+    pub scoreboard: Scoreboard,
+    /// The plugin channels the server listens on.
+    pub plugin_channels: PluginChannels,
+    /// The maps drawn by filled map items.
+    pub maps: Maps,
+    /// The advancements players can make.
+    pub advancements: AdvancementRegistry,
+    /// The recipes items can be crafted with.
+    pub recipes: RecipeRegistry,
 }
 
 impl Deref for Game {
@@ -87,14 +98,15 @@ impl Game {
         event_handlers.trigger(&resources, world, event);
     }
 
-    /// Retrieves the block at the given position.
+    /// Retrieves the block at the given position in a world.
     /// Returns `None` if the block's chunk is not loaded
     /// or the coordinates are out of bounds.
-    pub fn block_at(&self, pos: BlockPosition) -> Option<BlockId> {
-        self.chunk_map.block_at(pos)
+    pub fn block_at(&self, world_id: WorldId, pos: BlockPosition) -> Option<BlockId> {
+        self.worlds.get(world_id)?.block_at(pos)
     }
 
-    /// Sets the block at the given position.
+    /// Sets the block at the given position in a world.
+    /// The change is sent to clients at the end of the tick.
     ///
     /// Returns `false` if the block's chunk is not loaded
     /// or the coordinates are out of bounds;
@@ -102,20 +114,26 @@ impl Game {
     pub fn set_block_at(
         &mut self,
         world: &mut World,
+        world_id: WorldId,
         pos: BlockPosition,
         block: BlockId,
         cause: BlockUpdateCause,
     ) -> bool {
-        let old = match self.block_at(pos) {
+        let old = match self.block_at(world_id, pos) {
             Some(block) => block,
             None => return false,
         };
 
-        let result = self.chunk_map.set_block_at(pos, block);
+        let state = &mut self.worlds[world_id];
+        let result = state.chunk_map.set_block_at(pos, block);
+        if result {
+            state.dirty_chunks.mark_block(pos);
+        }
 
         self.handle(
             world,
             BlockUpdateEvent {
+                world: world_id,
                 pos,
                 old,
                 new: block,
@@ -126,6 +144,99 @@ impl Game {
         result
     }
 
+    /// Returns whether rain falls on the block at the given position:
+    /// it is raining, no block above stops the rain and the
+    /// block's biome has rain rather than snow.
+    pub fn is_raining_at(&self, world_id: WorldId, pos: BlockPosition) -> bool {
+        if !self.is_raining() {
+            return false;
+        }
+        let state = match self.worlds.get(world_id) {
+            Some(state) if state.dimension == Dimension::Overwold => state,
+            _ => return false,
+        };
+        let chunk = match state.chunk_map.chunk_at(pos.chunk()) {
+            Some(chunk) => chunk,
+            None => return false,
+        };
+        let (x, y, z) = match chunk.local_pos(pos) {
+            Some(local) => local,
+            None => return false,
+        };
+
+        y >= usize::from(chunk.heightmap(x, z).motion_blocking())
+            && chunk.biome_at_3d(x, y, z).precipitation() == Precipitation::Rain
+    }
+
+    /// Returns the world an entity is in. Entities
+    /// without a `WorldId` are in the main world.
+    pub fn world_of(&self, world: &World, entity: Entity) -> WorldId {
+        world
+            .try_get::<WorldId>(entity)
+            .map_or(WorldId::MAIN, |id| *id)
+    }
+
+    /// Returns the NBT-encoded type of a dimension, as
+    /// sent to clients joining or moving into a world.
+    pub fn dimension_type_nbt(&self, dimension: Dimension) -> Vec<u8> {
+        self.dimension_types
+            .get(dimension.type_id())
+            .expect("vanilla dimension types are always loaded")
+            .to_nbt()
+            .expect("failed to encode dimension type")
+    }
+
+    /// Creates a new, empty world and starts loading its chunks.
+    pub fn create_world(
+        &mut self,
+        world: &mut World,
+        name: impl Into<String>,
+        dimension: Dimension,
+        dir: impl Into<PathBuf>,
+        generator: Arc<dyn WorldGenerator>,
+    ) -> WorldId {
+        let id = self.worlds.add(name, dimension, dir, generator);
+        self.handle(world, WorldCreateEvent { world: id });
+        id
+    }
+
+    /// Moves an entity to the given position in another world.
+    /// If the entity is already in that world, it is just moved.
+    pub fn change_world(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        to: WorldId,
+        position: Position,
+    ) {
+        let from = self.world_of(world, entity);
+        if from == to {
+            *world.get_mut::<Position>(entity) = position;
+            return;
+        }
+
+        let old_position = std::mem::replace(&mut *world.get_mut::<Position>(entity), position);
+        match world.try_get_mut::<WorldId>(entity) {
+            Some(mut id) => *id = to,
+            None => world.add(entity, to).unwrap(),
+        }
+        // The entity didn't cross into the new chunk
+        // within its old world.
+        if let Some(mut previous) = world.try_get_mut::<PreviousPosition>(entity) {
+            previous.0 = Some(position);
+        }
+
+        self.handle(
+            world,
+            WorldChangeEvent {
+                entity,
+                old: from,
+                old_position,
+                new: to,
+            },
+        );
+    }
+
     /// Returns a bump allocator.
     pub fn bump(&self) -> &Bump {
         self.bump.get_or_default()
@@ -171,6 +282,7 @@ impl Game {
     ) {
         let name = world.get::<Name>(player);
         let network = world.get::<Network>(player);
+        let uuid = *world.get::<Uuid>(player);
 
         network.send(DisconnectPlay {
             reason: reason_client.to_string(),
@@ -182,7 +294,7 @@ impl Game {
         drop(name);
         drop(network);
 
-        self.player_count.fetch_sub(1, Ordering::AcqRel);
+        self.online_players.remove(uuid);
         self.handle(world, PlayerLeaveEvent { player });
         self.despawn(player, world);
     }
@@ -209,15 +321,36 @@ impl Game {
         }
     }
 
+    /// Broadcasts a packet to all players in a world.
+    pub fn broadcast_world(
+        &self,
+        world: &World,
+        packet: impl Packet,
+        world_id: WorldId,
+        neq: Option<Entity>,
+    ) {
+        let packet: Box<dyn Packet> = Box::new(packet);
+        for (entity, (network, id)) in
+            <(Read<Network>, Read<WorldId>)>::query().iter_entities(world.inner())
+        {
+            if *id != world_id || neq.map(|neq| neq == entity).unwrap_or(false) {
+                continue;
+            }
+
+            network.send_boxed(packet.box_clone());
+        }
+    }
+
     /// Broadcasts a packet to all players able to see a given chunk.
     pub fn broadcast_chunk_update(
         &self,
         world: &World,
         packet: impl Packet,
+        world_id: WorldId,
         chunk: ChunkPosition,
         neq: Option<Entity>,
     ) {
-        self.broadcast_chunk_update_boxed(world, Box::new(packet), chunk, neq);
+        self.broadcast_chunk_update_boxed(world, Box::new(packet), world_id, chunk, neq);
     }
 
     /// Broadcasts a boxed packet to all players able to see a given chunk.
@@ -225,11 +358,17 @@ impl Game {
         &self,
         world: &World,
         packet: Box<dyn Packet>,
+        world_id: WorldId,
         chunk: ChunkPosition,
         neq: Option<Entity>,
     ) {
+        let state = match self.worlds.get(world_id) {
+            Some(state) => state,
+            None => return,
+        };
+
         // we can use the chunk holders structure to accelerate this
-        for entity in self.chunk_holders.holders_for(chunk) {
+        for entity in state.chunk_holders.holders_for(chunk) {
             if neq.map(|neq| neq == *entity).unwrap_or(false) {
                 continue;
             }
@@ -261,17 +400,58 @@ impl Game {
     ) {
         // Send the packet to all players who have a hold on the entity's chunk.
         let entity_chunk = world.get::<Position>(entity).chunk();
-        self.broadcast_chunk_update_boxed(world, packet, entity_chunk, neq);
+        let world_id = self.world_of(world, entity);
+        self.broadcast_chunk_update_boxed(world, packet, world_id, entity_chunk, neq);
     }
 
-    /// Applies damage to the given entity. Handles all logic,
-    /// including killing the entity if its health drops below 1.
-    pub fn damage(&mut self, entity: Entity, damage: u32, world: &mut World) {
+    /// Applies damage from the given source to an entity. Handles all logic,
+    /// including blocking it with a shield, reducing the damage by the
+    /// entity's armor and killing the entity if its health drops below 1.
+    pub fn damage(&mut self, entity: Entity, damage: u32, source: DamageSource, world: &mut World) {
         if world.has::<Dead>(entity) {
             return;
         }
+        if world.has::<Player>(entity)
+            && !world.has::<CanTakeDamage>(entity)
+            && !source.bypasses_invulnerability()
+        {
+            return;
+        }
+
+        // Attacks blocked by a shield deal no damage.
+        if self.blocks_with_shield(world, entity, source) {
+            self.block_with_shield(world, entity, damage, source);
+            return;
+        }
+
+        // Shortly after being hurt, entities only take
+        // damage exceeding that of the last hit.
+        let tick = self.tick_count;
+        let damage = match world.try_get_mut::<HurtTime>(entity) {
+            Some(mut hurt_time) if tick < hurt_time.tick + HurtTime::INVULNERABLE_TICKS => {
+                if damage <= hurt_time.damage {
+                    return;
+                }
+                let extra = damage - hurt_time.damage;
+                hurt_time.damage = damage;
+                extra
+            }
+            Some(mut hurt_time) => {
+                *hurt_time = HurtTime { tick, damage };
+                damage
+            }
+            None => {
+                world.add(entity, HurtTime { tick, damage }).unwrap();
+                damage
+            }
+        };
+
+        let damage = crate::reduce_damage(world, entity, damage, source);
+        if damage == 0 {
+            return;
+        }
 
-        let (should_kill, old_health, new_health) =
+        let (mut should_kill, old_health, mut new_health) =
             if let Some(mut health) = world.try_get_mut::<Health>(entity) {
                 let old_health = health.0;
                 let should_kill = match health.0.checked_sub(damage) {
@@ -292,6 +472,14 @@ impl Game {
                 (false, None, 0)
             };
 
+        // A totem of undying saves the entity from death,
+        // unless the damage would kill even invulnerable players.
+        if should_kill && !source.bypasses_invulnerability() && self.use_totem(world, entity) {
+            world.get_mut::<Health>(entity).0 = 1;
+            should_kill = false;
+            new_health = 1;
+        }
+
         if let Some(old_health) = old_health {
             self.handle(
                 world,
@@ -304,18 +492,50 @@ impl Game {
         }
 
         if should_kill {
-            self.kill(entity, world);
+            self.kill(entity, source, world);
+        }
+    }
+
+    /// Heals the given entity, up to its `MaxHealth`.
+    pub fn heal(&mut self, entity: Entity, amount: u32, world: &mut World) {
+        if world.has::<Dead>(entity) {
+            return;
+        }
+
+        let max_health = world
+            .try_get::<MaxHealth>(entity)
+            .map(|max_health| max_health.0);
+        let (old_health, new_health) = match world.try_get_mut::<Health>(entity) {
+            Some(mut health) => {
+                let old_health = health.0;
+                health.0 = max_health.map_or(old_health + amount, |max_health| {
+                    (old_health + amount).min(max_health).max(old_health)
+                });
+                (old_health, health.0)
+            }
+            None => return,
+        };
+
+        if new_health != old_health {
+            self.handle(
+                world,
+                HealthUpdateEvent {
+                    old: old_health,
+                    new: new_health,
+                    entity,
+                },
+            );
         }
     }
 
     /// Kills an entity.
-    pub fn kill(&mut self, entity: Entity, world: &mut World) {
+    pub fn kill(&mut self, entity: Entity, source: DamageSource, world: &mut World) {
         // Don't kill if already on respawn screen
         if world.has::<Dead>(entity) {
             return;
         }
 
-        self.handle(world, EntityDeathEvent { entity });
+        self.handle(world, EntityDeathEvent { entity, source });
         if !world.has::<CanRespawn>(entity) {
             self.despawn(entity, world);
         }
@@ -381,34 +601,41 @@ impl ChunkEntities {
     }
 }
 
+/// The length of a Minecraft day in ticks.
+pub const DAY_LENGTH: u64 = 24_000;
+
 /// The current time of the world.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub struct Time(pub u64);
-
-impl Deref for Time {
-    type Target = u64;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl DerefMut for Time {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
+pub struct Time {
+    /// Ticks since the world was created.
+    pub age: u64,
+    /// Ticks since the first day began, which determine where
+    /// the sun and moon are. Unlike the age, this stands still
+    /// when `doDaylightCycle` is off and is changed by `/time`.
+    pub day_time: u64,
 }
 
 impl Time {
     /// Returns the time of day. This is calculated
-    /// as `time.0 % 24_000`.
+    /// as `day_time % 24_000`.
     pub fn time_of_day(self) -> u64 {
-        self.0 % 24_000
+        self.day_time % DAY_LENGTH
+    }
+
+    /// Returns the number of days which have passed.
+    pub fn day(self) -> u64 {
+        self.day_time / DAY_LENGTH
+    }
+
+    /// Sets the time of day, keeping the
+    /// number of days which have passed.
+    pub fn set_time_of_day(&mut self, time_of_day: u64) {
+        self.day_time = self.day() * DAY_LENGTH + time_of_day;
     }
 
-    /// Returns the age of the world in ticks. Equivalent to `time.0`.
+    /// Returns the age of the world in ticks.
     pub fn world_age(self) -> u64 {
-        self.0
+        self.age
     }
 }
 
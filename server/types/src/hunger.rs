@@ -0,0 +1,232 @@
+//! Food level, saturation and exhaustion of players.
+//!
+//! Actions such as sprinting, jumping, mining and fighting
+//! add exhaustion. Once exhaustion reaches 4.0, it is reset
+//! and a point of saturation is removed, or a point of food
+//! if the player has no saturation left. A well-fed player
+//! regenerates health, while a starving one takes damage.
+//!
+//! See https://minecraft.gamepedia.com/Hunger.
+
+/// Exhaustion added per meter sprinted.
+pub const SPRINT_EXHAUSTION: f32 = 0.1;
+/// Exhaustion added by jumping.
+pub const JUMP_EXHAUSTION: f32 = 0.05;
+/// Exhaustion added by jumping while sprinting.
+pub const SPRINT_JUMP_EXHAUSTION: f32 = 0.2;
+/// Exhaustion added by breaking a block.
+pub const BREAK_BLOCK_EXHAUSTION: f32 = 0.005;
+/// Exhaustion added by attacking an entity.
+pub const ATTACK_EXHAUSTION: f32 = 0.1;
+/// Exhaustion added by taking damage.
+pub const DAMAGE_EXHAUSTION: f32 = 0.1;
+/// Exhaustion added by regenerating a half-heart.
+pub const REGENERATION_EXHAUSTION: f32 = 6.0;
+
+/// What should happen to a player's health
+/// after ticking their hunger.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HungerEffect {
+    None,
+    /// The player regenerates the given amount of health.
+    Heal(u32),
+    /// The player is starving and takes the given amount of damage.
+    Starve(u32),
+}
+
+/// Hunger of a player.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hunger {
+    /// Food level, between 0 and 20.
+    pub food: u32,
+    /// Saturation, which is used up before the food level
+    /// and can never exceed it.
+    pub saturation: f32,
+    /// Exhaustion, which removes a point of saturation or
+    /// food each time it reaches 4.0.
+    pub exhaustion: f32,
+    /// Ticks since the player last regenerated or took starvation damage.
+    pub tick_timer: u32,
+}
+
+impl Default for Hunger {
+    fn default() -> Self {
+        Self {
+            food: Self::MAX_FOOD,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            tick_timer: 0,
+        }
+    }
+}
+
+impl Hunger {
+    pub const MAX_FOOD: u32 = 20;
+    /// Exhaustion at which a point of saturation or food is removed.
+    pub const EXHAUSTION_THRESHOLD: f32 = 4.0;
+    pub const MAX_EXHAUSTION: f32 = 40.0;
+
+    /// Food level at or above which players regenerate health.
+    const REGENERATION_FOOD: u32 = 18;
+    /// Ticks between regenerating health when food and saturation are full.
+    const FAST_REGENERATION_TICKS: u32 = 10;
+    /// Ticks between regenerating health or taking starvation damage.
+    const REGENERATION_TICKS: u32 = 80;
+
+    /// Adds exhaustion from an action.
+    pub fn exhaust(&mut self, exhaustion: f32) {
+        self.exhaustion = (self.exhaustion + exhaustion).min(Self::MAX_EXHAUSTION);
+    }
+
+    /// Returns whether the player is hungry enough to eat food.
+    pub fn can_eat(&self) -> bool {
+        self.food < Self::MAX_FOOD
+    }
+
+    /// Eats food, restoring `food` points and `2 * food * saturation_modifier`
+    /// saturation.
+    pub fn eat(&mut self, food: u32, saturation_modifier: f32) {
+        self.food = (self.food + food).min(Self::MAX_FOOD);
+        self.saturation =
+            (self.saturation + food as f32 * saturation_modifier * 2.0).min(self.food as f32);
+    }
+
+    /// Ticks hunger for a player with the given health, using up exhaustion
+    /// and returning whether the player should regenerate health or starve.
+    ///
+    /// Regeneration only happens if `natural_regeneration` is enabled.
+    pub fn tick(
+        &mut self,
+        health: u32,
+        max_health: u32,
+        natural_regeneration: bool,
+    ) -> HungerEffect {
+        if self.exhaustion >= Self::EXHAUSTION_THRESHOLD {
+            self.exhaustion -= Self::EXHAUSTION_THRESHOLD;
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.food = self.food.saturating_sub(1);
+            }
+        }
+
+        let can_heal = natural_regeneration && health < max_health;
+
+        if can_heal && self.food >= Self::MAX_FOOD && self.saturation > 0.0 {
+            self.tick_timer += 1;
+            if self.tick_timer >= Self::FAST_REGENERATION_TICKS {
+                self.tick_timer = 0;
+                // Fast regeneration uses up at most
+                // the player's remaining saturation.
+                self.exhaust(self.saturation.min(REGENERATION_EXHAUSTION));
+                return HungerEffect::Heal(1);
+            }
+        } else if can_heal && self.food >= Self::REGENERATION_FOOD {
+            self.tick_timer += 1;
+            if self.tick_timer >= Self::REGENERATION_TICKS {
+                self.tick_timer = 0;
+                self.exhaust(REGENERATION_EXHAUSTION);
+                return HungerEffect::Heal(1);
+            }
+        } else if self.food == 0 {
+            self.tick_timer += 1;
+            if self.tick_timer >= Self::REGENERATION_TICKS {
+                self.tick_timer = 0;
+                // On normal difficulty, starvation stops at half a heart.
+                if health > 1 {
+                    return HungerEffect::Starve(1);
+                }
+            }
+        } else {
+            self.tick_timer = 0;
+        }
+
+        HungerEffect::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eat_caps_saturation_at_food() {
+        let mut hunger = Hunger {
+            food: 10,
+            saturation: 0.0,
+            ..Default::default()
+        };
+        hunger.eat(8, 0.8);
+        assert_eq!(hunger.food, 18);
+        assert!((hunger.saturation - 12.8).abs() < 1e-4);
+
+        hunger.eat(8, 0.8);
+        assert_eq!(hunger.food, Hunger::MAX_FOOD);
+        assert!((hunger.saturation - 20.0).abs() < 1e-4);
+        assert!(!hunger.can_eat());
+    }
+
+    #[test]
+    fn exhaustion_uses_saturation_then_food() {
+        let mut hunger = Hunger {
+            food: 10,
+            saturation: 1.0,
+            ..Default::default()
+        };
+        hunger.exhaust(4.0);
+        assert_eq!(hunger.tick(20, 20, true), HungerEffect::None);
+        assert_eq!(hunger.saturation, 0.0);
+        assert_eq!(hunger.food, 10);
+
+        hunger.exhaust(4.5);
+        hunger.tick(20, 20, true);
+        assert_eq!(hunger.food, 9);
+        assert!((hunger.exhaustion - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn regenerates_when_well_fed() {
+        let mut hunger = Hunger {
+            food: 18,
+            saturation: 0.0,
+            ..Default::default()
+        };
+        for _ in 0..79 {
+            assert_eq!(hunger.tick(10, 20, true), HungerEffect::None);
+        }
+        assert_eq!(hunger.tick(10, 20, true), HungerEffect::Heal(1));
+        assert_eq!(hunger.exhaustion, REGENERATION_EXHAUSTION);
+
+        let mut hunger = Hunger::default();
+        for _ in 0..9 {
+            assert_eq!(hunger.tick(10, 20, true), HungerEffect::None);
+        }
+        assert_eq!(hunger.tick(10, 20, true), HungerEffect::Heal(1));
+    }
+
+    #[test]
+    fn no_regeneration_at_full_health_or_when_disabled() {
+        let mut hunger = Hunger::default();
+        for _ in 0..100 {
+            assert_eq!(hunger.tick(20, 20, true), HungerEffect::None);
+            assert_eq!(hunger.tick(10, 20, false), HungerEffect::None);
+        }
+    }
+
+    #[test]
+    fn starves_down_to_half_a_heart() {
+        let mut hunger = Hunger {
+            food: 0,
+            saturation: 0.0,
+            ..Default::default()
+        };
+        for _ in 0..79 {
+            assert_eq!(hunger.tick(2, 20, true), HungerEffect::None);
+        }
+        assert_eq!(hunger.tick(2, 20, true), HungerEffect::Starve(1));
+
+        for _ in 0..80 {
+            assert_eq!(hunger.tick(1, 20, true), HungerEffect::None);
+        }
+    }
+}
@@ -3,17 +3,65 @@
 
 extern crate nalgebra_glm as glm;
 
+mod advancements;
+mod block_ticks;
+mod border;
 mod components;
+mod damage;
+mod dirty;
+mod effects;
 mod events;
+mod experience;
 mod game;
+mod hunger;
+mod maps;
+mod mining;
 mod misc;
+mod plugin_channels;
+mod poi;
+mod recipes;
+mod resource_pack;
 mod resources;
+mod scoreboard;
+mod shield;
+mod sleeping;
+mod status;
+mod status_effects;
 pub mod task;
+mod tickets;
+mod title;
+mod totem;
+mod weather;
+mod worlds;
 
+pub use advancements::*;
+pub use block_ticks::*;
+pub use border::*;
 pub use components::*;
+pub use damage::*;
+pub use dirty::*;
+pub use effects::*;
 pub use events::*;
+pub use experience::*;
+pub use hunger::*;
+pub use maps::*;
+pub use mining::*;
 pub use misc::*;
+pub use plugin_channels::*;
+pub use poi::*;
+pub use recipes::*;
+pub use resource_pack::*;
 pub use resources::*;
+pub use scoreboard::*;
+pub use shield::*;
+pub use sleeping::*;
+pub use status::*;
+pub use status_effects::*;
+pub use tickets::*;
+pub use title::*;
+pub use totem::*;
+pub use weather::*;
+pub use worlds::*;
 
 // Constants
 /// The number of ticks executed per second.
@@ -23,3 +71,7 @@ pub const TICK_LENGTH: u64 = 1000 / TPS;
 
 /// Height from a player's position where the camera lies.
 pub const PLAYER_EYE_HEIGHT: f64 = 1.62;
+/// Width of a player's bounding box along the X and Z axes.
+pub const PLAYER_WIDTH: f64 = 0.6;
+/// Height of a player's bounding box.
+pub const PLAYER_HEIGHT: f64 = 1.8;
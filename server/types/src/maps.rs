@@ -0,0 +1,280 @@
+//! Maps, which filled map items show a drawing of the
+//! terrain around them on.
+//!
+//! Each map stores the colors of its 128x128 pixels. Pixels
+//! changed since the map was last sent to its holders are
+//! tracked in a dirty region, so that only that region is sent.
+
+use crate::WorldId;
+use feather_core::blocks::MapColor;
+use feather_core::network::packets::{MapData, MapIcon};
+
+/// The width and height of a map in pixels.
+pub const MAP_SIZE: usize = 128;
+/// The highest scale of a map, at which each pixel covers 16x16 blocks.
+pub const MAX_MAP_SCALE: u8 = 4;
+
+/// Shade of a map color, depending on the slope of the terrain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MapShade {
+    Dark = 0,
+    Normal = 1,
+    Light = 2,
+}
+
+/// Returns the ID of the given color and shade on maps.
+/// Pixels without a color are transparent.
+pub fn map_color_id(color: Option<MapColor>, shade: MapShade) -> u8 {
+    match color {
+        // Color IDs start from 1, after transparency.
+        Some(color) => (color as u8 + 1) * 4 + shade as u8,
+        None => 0,
+    }
+}
+
+/// A rectangle of pixels on a map, with inclusive bounds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MapRegion {
+    pub min_x: u8,
+    pub min_z: u8,
+    pub max_x: u8,
+    pub max_z: u8,
+}
+
+impl MapRegion {
+    /// The region covering the whole map.
+    pub const FULL: MapRegion = MapRegion {
+        min_x: 0,
+        min_z: 0,
+        max_x: MAP_SIZE as u8 - 1,
+        max_z: MAP_SIZE as u8 - 1,
+    };
+
+    fn pixel(x: u8, z: u8) -> Self {
+        Self {
+            min_x: x,
+            min_z: z,
+            max_x: x,
+            max_z: z,
+        }
+    }
+
+    fn include(&mut self, x: u8, z: u8) {
+        self.min_x = self.min_x.min(x);
+        self.min_z = self.min_z.min(z);
+        self.max_x = self.max_x.max(x);
+        self.max_z = self.max_z.max(z);
+    }
+}
+
+/// The state of a map.
+#[derive(Clone, Debug)]
+pub struct MapState {
+    /// The world drawn on the map.
+    pub world: WorldId,
+    /// Block coordinates of the center of the map.
+    pub center_x: i32,
+    pub center_z: i32,
+    /// Each pixel covers `2^scale` blocks along each axis.
+    pub scale: u8,
+    /// Whether the map has been locked in a cartography
+    /// table, after which it is no longer drawn.
+    pub locked: bool,
+    colors: Vec<u8>,
+    dirty: Option<MapRegion>,
+}
+
+impl MapState {
+    /// Creates an empty map of the given scale containing the
+    /// given position. As in vanilla, maps are aligned to a grid,
+    /// so that maps of the same scale don't overlap.
+    pub fn new(world: WorldId, x: f64, z: f64, scale: u8) -> Self {
+        let scale = scale.min(MAX_MAP_SCALE);
+        let width = (MAP_SIZE as i32) << scale;
+        let align = |coord: f64| {
+            ((coord + 64.0) / f64::from(width)).floor() as i32 * width + width / 2 - 64
+        };
+
+        Self {
+            world,
+            center_x: align(x),
+            center_z: align(z),
+            scale,
+            locked: false,
+            colors: vec![0; MAP_SIZE * MAP_SIZE],
+            dirty: None,
+        }
+    }
+
+    /// Returns the number of blocks along each axis covered by a pixel.
+    pub fn blocks_per_pixel(&self) -> i32 {
+        1 << self.scale
+    }
+
+    /// Returns the position of the given block coordinates on
+    /// the map in pixels, which may be outside of the map.
+    pub fn pixel_at(&self, x: f64, z: f64) -> (f64, f64) {
+        let blocks_per_pixel = f64::from(self.blocks_per_pixel());
+        (
+            (x - f64::from(self.center_x)) / blocks_per_pixel + (MAP_SIZE / 2) as f64,
+            (z - f64::from(self.center_z)) / blocks_per_pixel + (MAP_SIZE / 2) as f64,
+        )
+    }
+
+    /// Returns the block coordinates of the north-west
+    /// corner of the area covered by the given pixel.
+    pub fn block_at_pixel(&self, x: i32, z: i32) -> (i32, i32) {
+        let blocks_per_pixel = self.blocks_per_pixel();
+        (
+            (self.center_x / blocks_per_pixel + x - (MAP_SIZE / 2) as i32) * blocks_per_pixel,
+            (self.center_z / blocks_per_pixel + z - (MAP_SIZE / 2) as i32) * blocks_per_pixel,
+        )
+    }
+
+    pub fn color(&self, x: usize, z: usize) -> u8 {
+        self.colors[x + z * MAP_SIZE]
+    }
+
+    /// Sets the color of a pixel, marking it dirty if it changed.
+    pub fn set_color(&mut self, x: usize, z: usize, color: u8) {
+        let current = &mut self.colors[x + z * MAP_SIZE];
+        if *current == color {
+            return;
+        }
+        *current = color;
+
+        match &mut self.dirty {
+            Some(dirty) => dirty.include(x as u8, z as u8),
+            None => self.dirty = Some(MapRegion::pixel(x as u8, z as u8)),
+        }
+    }
+
+    /// Returns the region containing the pixels changed since
+    /// the dirty region was last taken.
+    pub fn dirty_region(&self) -> Option<MapRegion> {
+        self.dirty
+    }
+
+    /// Returns the dirty region and marks all pixels clean.
+    pub fn take_dirty_region(&mut self) -> Option<MapRegion> {
+        self.dirty.take()
+    }
+
+    /// Creates the `MapData` packet updating the given
+    /// region of pixels, if any, and showing the given icons.
+    pub fn packet(&self, id: i32, region: Option<MapRegion>, icons: Vec<MapIcon>) -> MapData {
+        let mut packet = MapData {
+            map_id: id,
+            scale: self.scale as i8,
+            tracking_position: true,
+            locked: self.locked,
+            icons,
+            ..Default::default()
+        };
+
+        if let Some(region) = region {
+            packet.columns = region.max_x - region.min_x + 1;
+            packet.rows = region.max_z - region.min_z + 1;
+            packet.x = region.min_x;
+            packet.z = region.min_z;
+            packet.data = (region.min_z..=region.max_z)
+                .flat_map(|z| {
+                    (region.min_x..=region.max_x).map(move |x| self.color(x as usize, z as usize))
+                })
+                .collect();
+        }
+
+        packet
+    }
+}
+
+/// The maps of the server, identified by their
+/// index, which filled map items refer to.
+#[derive(Default, Debug)]
+pub struct Maps {
+    maps: Vec<MapState>,
+}
+
+impl Maps {
+    /// Adds a map, returning its ID.
+    pub fn add(&mut self, map: MapState) -> i32 {
+        self.maps.push(map);
+        self.maps.len() as i32 - 1
+    }
+
+    pub fn get(&self, id: i32) -> Option<&MapState> {
+        if id < 0 {
+            return None;
+        }
+        self.maps.get(id as usize)
+    }
+
+    pub fn get_mut(&mut self, id: i32) -> Option<&mut MapState> {
+        if id < 0 {
+            return None;
+        }
+        self.maps.get_mut(id as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alignment() {
+        let map = MapState::new(WorldId::MAIN, 10.0, -70.0, 0);
+        assert_eq!((map.center_x, map.center_z), (0, -128));
+        assert_eq!(map.block_at_pixel(0, 0), (-64, -192));
+        assert_eq!(map.pixel_at(10.5, -70.0), (74.5, 122.0));
+
+        let map = MapState::new(WorldId::MAIN, 10.0, -70.0, 1);
+        assert_eq!((map.center_x, map.center_z), (64, -192));
+        assert_eq!(map.block_at_pixel(0, 0), (-64, -320));
+    }
+
+    #[test]
+    fn dirty_region() {
+        let mut map = MapState::new(WorldId::MAIN, 0.0, 0.0, 0);
+        let color = map_color_id(Some(MapColor::Grass), MapShade::Light);
+        assert_eq!(color, 6);
+
+        map.set_color(3, 5, color);
+        map.set_color(10, 2, color);
+        map.set_color(20, 20, 0);
+        assert_eq!(
+            map.take_dirty_region(),
+            Some(MapRegion {
+                min_x: 3,
+                min_z: 2,
+                max_x: 10,
+                max_z: 5
+            })
+        );
+        assert_eq!(map.dirty_region(), None);
+
+        let packet = map.packet(
+            0,
+            Some(MapRegion {
+                min_x: 3,
+                min_z: 5,
+                max_x: 4,
+                max_z: 6,
+            }),
+            vec![],
+        );
+        assert_eq!(
+            (packet.columns, packet.rows, packet.x, packet.z),
+            (2, 2, 3, 5)
+        );
+        assert_eq!(packet.data, vec![color, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,211 @@
+//! How fast players dig blocks, depending on the hardness of the
+//! block, the tool used, its enchantments, status effects and
+//! where the player is standing.
+//!
+//! See https://minecraft.gamepedia.com/Breaking#Speed.
+
+use feather_core::blocks::{BlockKind, SimplifiedBlockKind};
+use feather_core::items::{Enchantment, Item, ItemStack, Tool};
+
+/// Haste status effect of an entity, which makes it dig faster.
+/// Stores the level of the effect, e.g. 2 for Haste II.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Haste(pub u8);
+
+/// Mining Fatigue status effect of an entity, which makes it dig
+/// slower. Stores the level of the effect, e.g. 3 for Mining Fatigue III.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MiningFatigue(pub u8);
+
+/// Conditions affecting how fast a player digs,
+/// other than the tool they are holding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DigConditions {
+    /// Level of the player's Haste effect, or 0.
+    pub haste: u8,
+    /// Level of the player's Mining Fatigue effect, or 0.
+    pub mining_fatigue: u8,
+    /// Whether the player's head is underwater without
+    /// Aqua Affinity on their helmet.
+    pub underwater: bool,
+    pub on_ground: bool,
+}
+
+impl Default for DigConditions {
+    fn default() -> Self {
+        Self {
+            haste: 0,
+            mining_fatigue: 0,
+            underwater: false,
+            on_ground: true,
+        }
+    }
+}
+
+/// Returns whether a block drops items when dug with the given tool.
+pub fn can_harvest(block: BlockKind, tool: Option<Item>) -> bool {
+    if !block.best_tool_required() {
+        return true;
+    }
+
+    let kind = tool.and_then(Item::tool);
+    if block == BlockKind::Cobweb && kind == Some(Tool::Sword) {
+        return true;
+    }
+    if kind.is_none() || kind != block.best_tool() {
+        return false;
+    }
+
+    let harvest_level = tool
+        .and_then(Item::tool_material)
+        .map_or(0, |material| material.harvest_level());
+    harvest_level >= block.harvest_level().unwrap_or(0)
+}
+
+/// Returns the speed at which a tool digs a block, before
+/// enchantments and status effects. Digging with a hand
+/// or an unsuitable item has a speed of 1.
+pub fn tool_speed(block: BlockKind, tool: Option<Item>) -> f64 {
+    let tool = match tool {
+        Some(tool) => tool,
+        None => return 1.0,
+    };
+
+    match tool.tool() {
+        Some(Tool::Sword) => match block.to_simplified_kind() {
+            SimplifiedBlockKind::Cobweb => 15.0,
+            SimplifiedBlockKind::Leaves
+            | SimplifiedBlockKind::Melon
+            | SimplifiedBlockKind::Pumpkin
+            | SimplifiedBlockKind::Vine => 1.5,
+            _ => 1.0,
+        },
+        Some(Tool::Shears) => match block.to_simplified_kind() {
+            SimplifiedBlockKind::Cobweb | SimplifiedBlockKind::Leaves => 15.0,
+            SimplifiedBlockKind::Wool => 5.0,
+            _ => 1.0,
+        },
+        Some(kind) if Some(kind) == block.best_tool() => tool
+            .tool_material()
+            .map_or(1.0, |material| material.dig_multiplier()),
+        _ => 1.0,
+    }
+}
+
+/// Returns the fraction of a block dug by a player each tick.
+/// The block breaks once the total reaches 1, so values of
+/// 1 or more break the block instantly.
+pub fn dig_progress_per_tick(
+    block: BlockKind,
+    tool: Option<&ItemStack>,
+    conditions: DigConditions,
+) -> f64 {
+    let hardness = block.hardness();
+    if !block.diggable() || hardness < 0.0 {
+        return 0.0;
+    }
+
+    let item = tool.map(|stack| stack.ty);
+    let mut speed = tool_speed(block, item);
+
+    if speed > 1.0 {
+        let efficiency = tool
+            .and_then(|stack| stack.enchantments.level(Enchantment::Efficiency))
+            .unwrap_or(0)
+            .max(0) as f64;
+        if efficiency > 0.0 {
+            speed += efficiency * efficiency + 1.0;
+        }
+    }
+
+    if conditions.haste > 0 {
+        speed *= 1.0 + 0.2 * f64::from(conditions.haste);
+    }
+    speed *= match conditions.mining_fatigue {
+        0 => 1.0,
+        1 => 0.3,
+        2 => 0.09,
+        3 => 0.0027,
+        _ => 0.00081,
+    };
+
+    if conditions.underwater {
+        speed /= 5.0;
+    }
+    if !conditions.on_ground {
+        speed /= 5.0;
+    }
+
+    let divisor = if can_harvest(block, item) {
+        30.0
+    } else {
+        100.0
+    };
+    speed / hardness / divisor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks_to_dig(block: BlockKind, tool: Option<ItemStack>, conditions: DigConditions) -> u32 {
+        (1.0 / dig_progress_per_tick(block, tool.as_ref(), conditions)).ceil() as u32
+    }
+
+    #[test]
+    fn harvesting() {
+        assert!(can_harvest(BlockKind::Dirt, None));
+        assert!(!can_harvest(BlockKind::Stone, None));
+        assert!(can_harvest(BlockKind::Stone, Some(Item::WoodenPickaxe)));
+        assert!(!can_harvest(BlockKind::Stone, Some(Item::WoodenShovel)));
+        assert!(!can_harvest(BlockKind::IronOre, Some(Item::GoldenPickaxe)));
+        assert!(can_harvest(BlockKind::IronOre, Some(Item::StonePickaxe)));
+        assert!(!can_harvest(BlockKind::Obsidian, Some(Item::IronPickaxe)));
+        assert!(can_harvest(BlockKind::Obsidian, Some(Item::DiamondPickaxe)));
+        assert!(can_harvest(BlockKind::Cobweb, Some(Item::IronSword)));
+    }
+
+    #[test]
+    fn break_times() {
+        let conditions = DigConditions::default();
+        // 7.5 seconds to dig stone by hand
+        assert_eq!(ticks_to_dig(BlockKind::Stone, None, conditions), 150);
+        assert_eq!(
+            ticks_to_dig(
+                BlockKind::Stone,
+                Some(ItemStack::new(Item::WoodenPickaxe, 1)),
+                conditions
+            ),
+            23
+        );
+        assert_eq!(ticks_to_dig(BlockKind::Dirt, None, conditions), 15);
+        assert!(dig_progress_per_tick(BlockKind::Bedrock, None, conditions).abs() < 1e-9);
+        assert!(dig_progress_per_tick(BlockKind::Grass, None, conditions) >= 1.0);
+    }
+
+    #[test]
+    fn modifiers() {
+        let mut pickaxe = ItemStack::new(Item::DiamondPickaxe, 1);
+        pickaxe.enchantments.set(Enchantment::Efficiency, 5);
+
+        let conditions = DigConditions::default();
+        assert!(dig_progress_per_tick(BlockKind::Stone, Some(&pickaxe), conditions) < 1.0);
+        let hasted = DigConditions {
+            haste: 2,
+            ..conditions
+        };
+        assert!(dig_progress_per_tick(BlockKind::Stone, Some(&pickaxe), hasted) >= 1.0);
+
+        let underwater = DigConditions {
+            underwater: true,
+            ..conditions
+        };
+        assert_eq!(ticks_to_dig(BlockKind::Dirt, None, underwater), 75);
+        let in_air = DigConditions {
+            underwater: true,
+            on_ground: false,
+            ..conditions
+        };
+        assert_eq!(ticks_to_dig(BlockKind::Dirt, None, in_air), 375);
+    }
+}
@@ -0,0 +1,163 @@
+//! Plugin channels, over which plugins exchange custom
+//! payloads with client mods and proxies.
+
+use crate::{Game, Network};
+use feather_core::network::packets::PluginMessageClientbound;
+use fecs::World;
+use std::collections::BTreeSet;
+
+/// The channel on which the client and server send their brand.
+pub const BRAND_CHANNEL: &str = "minecraft:brand";
+/// The channel used to register channels with the other side.
+pub const REGISTER_CHANNEL: &str = "minecraft:register";
+/// The channel used to unregister channels with the other side.
+pub const UNREGISTER_CHANNEL: &str = "minecraft:unregister";
+
+/// The brand sent to clients, shown in the debug screen.
+pub const SERVER_BRAND: &str = "Feather";
+
+/// The plugin channels the server listens on.
+///
+/// Plugin messages received on these channels trigger a
+/// `PluginMessageEvent`; those received on other channels
+/// are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct PluginChannels {
+    channels: BTreeSet<String>,
+}
+
+impl PluginChannels {
+    /// Registers a channel. Returns `false` if it
+    /// was already registered.
+    pub fn register(&mut self, channel: impl Into<String>) -> bool {
+        self.channels.insert(channel.into())
+    }
+
+    /// Unregisters a channel. Returns `false` if it
+    /// was not registered.
+    pub fn unregister(&mut self, channel: &str) -> bool {
+        self.channels.remove(channel)
+    }
+
+    pub fn is_registered(&self, channel: &str) -> bool {
+        self.channels.contains(channel)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.channels.iter().map(String::as_str)
+    }
+
+    /// Returns the packet which registers all channels with
+    /// a client, or `None` if there are no channels.
+    pub fn register_packet(&self) -> Option<PluginMessageClientbound> {
+        if self.channels.is_empty() {
+            return None;
+        }
+
+        Some(PluginMessageClientbound {
+            channel: String::from(REGISTER_CHANNEL),
+            data: encode_channels(self.iter()),
+        })
+    }
+}
+
+/// The plugin channels a client has registered, on
+/// which it can receive plugin messages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientChannels(pub BTreeSet<String>);
+
+impl ClientChannels {
+    pub fn is_registered(&self, channel: &str) -> bool {
+        self.0.contains(channel)
+    }
+}
+
+/// The brand a client sent, such as `vanilla` or `fabric`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientBrand(pub String);
+
+/// Encodes channel names as the payload of a register or
+/// unregister message, in which they are separated by NUL.
+pub fn encode_channels<'a>(channels: impl IntoIterator<Item = &'a str>) -> Vec<u8> {
+    let mut data = Vec::new();
+    for channel in channels {
+        if !data.is_empty() {
+            data.push(0);
+        }
+        data.extend_from_slice(channel.as_bytes());
+    }
+    data
+}
+
+/// Decodes the channel names in the payload of
+/// a register or unregister message.
+pub fn decode_channels(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.split(|byte| *byte == 0)
+        .filter(|channel| !channel.is_empty())
+        .map(|channel| String::from_utf8_lossy(channel).into_owned())
+}
+
+impl Network {
+    /// Sends a plugin message to this player.
+    ///
+    /// Clients ignore messages on channels they don't know,
+    /// so check their `ClientChannels` first if the message
+    /// is for a client mod.
+    pub fn send_plugin_message(&self, channel: impl Into<String>, data: Vec<u8>) {
+        self.send(PluginMessageClientbound {
+            channel: channel.into(),
+            data,
+        });
+    }
+}
+
+impl Game {
+    /// Registers a plugin channel and registers it
+    /// with all online clients.
+    pub fn register_plugin_channel(&mut self, world: &World, channel: impl Into<String>) {
+        let channel = channel.into();
+        let packet = PluginMessageClientbound {
+            channel: String::from(REGISTER_CHANNEL),
+            data: encode_channels(std::iter::once(channel.as_str())),
+        };
+
+        if self.plugin_channels.register(channel) {
+            self.broadcast_global(world, packet, None);
+        }
+    }
+
+    /// Unregisters a plugin channel and unregisters it
+    /// with all online clients.
+    pub fn unregister_plugin_channel(&mut self, world: &World, channel: &str) {
+        if self.plugin_channels.unregister(channel) {
+            let packet = PluginMessageClientbound {
+                channel: String::from(UNREGISTER_CHANNEL),
+                data: encode_channels(std::iter::once(channel)),
+            };
+            self.broadcast_global(world, packet, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_payloads() {
+        let mut channels = PluginChannels::default();
+        assert!(channels.register_packet().is_none());
+
+        assert!(channels.register("feather:b"));
+        assert!(channels.register("feather:a"));
+        assert!(!channels.register("feather:a"));
+
+        let packet = channels.register_packet().unwrap();
+        assert_eq!(packet.channel, REGISTER_CHANNEL);
+        assert_eq!(packet.data, b"feather:a\0feather:b");
+
+        let decoded: Vec<String> = decode_channels(&packet.data).collect();
+        assert_eq!(decoded, vec!["feather:a", "feather:b"]);
+        assert_eq!(decode_channels(b"\0a:b\0").count(), 1);
+    }
+}
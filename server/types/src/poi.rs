@@ -0,0 +1,453 @@
+//! Points of interest: blocks which entities look for, such
+//! as beds and job sites for villagers, or nether portals
+//! to link with.
+//!
+//! Each world tracks the points of interest in its loaded
+//! chunks. They are saved alongside the chunks, and sections
+//! without saved points of interest are scanned when loaded.
+
+use ahash::AHashMap;
+use feather_core::anvil::poi::{PoiChunk, PoiRecord};
+use feather_core::blocks::{BlockId, BlockKind, Part, SimplifiedBlockKind};
+use feather_core::chunk::Chunk;
+use feather_core::util::{BlockPosition, ChunkPosition};
+use std::collections::BTreeMap;
+
+/// A type of point of interest.
+///
+/// The blocks of some types were added after 1.13.2. Points of
+/// interest of those types are never created from blocks, but
+/// are kept when loaded from worlds saved by newer versions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PoiType {
+    // Job sites
+    Armorer,
+    Butcher,
+    Cartographer,
+    Cleric,
+    Farmer,
+    Fisherman,
+    Fletcher,
+    Leatherworker,
+    Librarian,
+    Mason,
+    Shepherd,
+    Toolsmith,
+    Weaponsmith,
+    /// A bed.
+    Home,
+    /// A bell.
+    Meeting,
+    Beehive,
+    BeeNest,
+    NetherPortal,
+    Lodestone,
+}
+
+const ALL_TYPES: [PoiType; 19] = [
+    PoiType::Armorer,
+    PoiType::Butcher,
+    PoiType::Cartographer,
+    PoiType::Cleric,
+    PoiType::Farmer,
+    PoiType::Fisherman,
+    PoiType::Fletcher,
+    PoiType::Leatherworker,
+    PoiType::Librarian,
+    PoiType::Mason,
+    PoiType::Shepherd,
+    PoiType::Toolsmith,
+    PoiType::Weaponsmith,
+    PoiType::Home,
+    PoiType::Meeting,
+    PoiType::Beehive,
+    PoiType::BeeNest,
+    PoiType::NetherPortal,
+    PoiType::Lodestone,
+];
+
+impl PoiType {
+    /// Returns the namespaced identifier of this type, e.g. `minecraft:home`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PoiType::Armorer => "minecraft:armorer",
+            PoiType::Butcher => "minecraft:butcher",
+            PoiType::Cartographer => "minecraft:cartographer",
+            PoiType::Cleric => "minecraft:cleric",
+            PoiType::Farmer => "minecraft:farmer",
+            PoiType::Fisherman => "minecraft:fisherman",
+            PoiType::Fletcher => "minecraft:fletcher",
+            PoiType::Leatherworker => "minecraft:leatherworker",
+            PoiType::Librarian => "minecraft:librarian",
+            PoiType::Mason => "minecraft:mason",
+            PoiType::Shepherd => "minecraft:shepherd",
+            PoiType::Toolsmith => "minecraft:toolsmith",
+            PoiType::Weaponsmith => "minecraft:weaponsmith",
+            PoiType::Home => "minecraft:home",
+            PoiType::Meeting => "minecraft:meeting",
+            PoiType::Beehive => "minecraft:beehive",
+            PoiType::BeeNest => "minecraft:bee_nest",
+            PoiType::NetherPortal => "minecraft:nether_portal",
+            PoiType::Lodestone => "minecraft:lodestone",
+        }
+    }
+
+    /// Returns the type with the given namespaced identifier.
+    pub fn from_name(name: &str) -> Option<Self> {
+        ALL_TYPES.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    /// Returns the type of point of interest a block is, if any.
+    /// Only the head of a bed is a point of interest.
+    pub fn from_block(block: BlockId) -> Option<Self> {
+        if block.simplified_kind() == SimplifiedBlockKind::Bed {
+            return match block.part() {
+                Some(Part::Head) => Some(PoiType::Home),
+                _ => None,
+            };
+        }
+
+        match block.kind() {
+            BlockKind::BrewingStand => Some(PoiType::Cleric),
+            BlockKind::Cauldron => Some(PoiType::Leatherworker),
+            BlockKind::NetherPortal => Some(PoiType::NetherPortal),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this is the job site of a villager profession.
+    pub fn is_job_site(self) -> bool {
+        !matches!(
+            self,
+            PoiType::Home
+                | PoiType::Meeting
+                | PoiType::Beehive
+                | PoiType::BeeNest
+                | PoiType::NetherPortal
+                | PoiType::Lodestone
+        )
+    }
+
+    /// Returns the number of entities which can claim a
+    /// point of interest of this type at the same time.
+    pub fn max_claims(self) -> i32 {
+        match self {
+            PoiType::Meeting => 32,
+            PoiType::Beehive | PoiType::BeeNest | PoiType::NetherPortal | PoiType::Lodestone => 0,
+            _ => 1,
+        }
+    }
+}
+
+/// A point of interest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Poi {
+    pub pos: BlockPosition,
+    pub kind: PoiType,
+    /// The number of entities which can still claim this
+    /// point of interest.
+    pub free_claims: i32,
+}
+
+impl Poi {
+    /// Creates a point of interest which hasn't been claimed.
+    pub fn new(pos: BlockPosition, kind: PoiType) -> Self {
+        Self {
+            pos,
+            kind,
+            free_claims: kind.max_claims(),
+        }
+    }
+}
+
+/// The points of interest in the loaded chunks of a world.
+#[derive(Debug, Default)]
+pub struct PoiStorage {
+    chunks: AHashMap<ChunkPosition, Vec<Poi>>,
+}
+
+impl PoiStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the points of interest in a chunk which was loaded.
+    pub fn insert_chunk(&mut self, chunk: ChunkPosition, pois: Vec<Poi>) {
+        self.chunks.insert(chunk, pois);
+    }
+
+    /// Stops tracking the points of interest in a chunk
+    /// which was unloaded.
+    pub fn remove_chunk(&mut self, chunk: ChunkPosition) {
+        self.chunks.remove(&chunk);
+    }
+
+    /// Returns the points of interest in a chunk.
+    pub fn in_chunk(&self, chunk: ChunkPosition) -> &[Poi] {
+        self.chunks.get(&chunk).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the point of interest at the given position.
+    pub fn get(&self, pos: BlockPosition) -> Option<&Poi> {
+        self.in_chunk(pos.chunk()).iter().find(|poi| poi.pos == pos)
+    }
+
+    /// Adds a point of interest, replacing any existing
+    /// one at its position.
+    pub fn add(&mut self, poi: Poi) {
+        let pois = self.chunks.entry(poi.pos.chunk()).or_default();
+        pois.retain(|other| other.pos != poi.pos);
+        pois.push(poi);
+    }
+
+    /// Removes the point of interest at the given position.
+    pub fn remove(&mut self, pos: BlockPosition) -> Option<Poi> {
+        let pois = self.chunks.get_mut(&pos.chunk())?;
+        let index = pois.iter().position(|poi| poi.pos == pos)?;
+        Some(pois.swap_remove(index))
+    }
+
+    /// Returns the points of interest within `radius` blocks
+    /// of `center` which match the given filter.
+    pub fn in_radius<'a>(
+        &'a self,
+        center: BlockPosition,
+        radius: i32,
+        filter: impl Fn(&Poi) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Poi> + 'a {
+        let min = BlockPosition::new(center.x - radius, 0, center.z - radius).chunk();
+        let max = BlockPosition::new(center.x + radius, 0, center.z + radius).chunk();
+        let radius_squared = i64::from(radius) * i64::from(radius);
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.z..=max.z).map(move |z| ChunkPosition::new(x, z)))
+            .flat_map(move |chunk| self.in_chunk(chunk))
+            .filter(move |poi| distance_squared(poi.pos, center) <= radius_squared)
+            .filter(move |poi| filter(poi))
+    }
+
+    /// Returns the closest point of interest within `radius`
+    /// blocks of `center` which matches the given filter.
+    pub fn nearest<'a>(
+        &'a self,
+        center: BlockPosition,
+        radius: i32,
+        filter: impl Fn(&Poi) -> bool + 'a,
+    ) -> Option<&'a Poi> {
+        self.in_radius(center, radius, filter)
+            .min_by_key(|poi| distance_squared(poi.pos, center))
+    }
+
+    /// Claims the point of interest at the given position, e.g. for
+    /// a villager claiming a bed. Returns `false` if there is no
+    /// point of interest or it can't be claimed by more entities.
+    pub fn claim(&mut self, pos: BlockPosition) -> bool {
+        match self.get_mut(pos) {
+            Some(poi) if poi.free_claims > 0 => {
+                poi.free_claims -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Releases a claim on the point of interest at the given
+    /// position. Returns `false` if there is no point of
+    /// interest or it hasn't been claimed.
+    pub fn release(&mut self, pos: BlockPosition) -> bool {
+        match self.get_mut(pos) {
+            Some(poi) if poi.free_claims < poi.kind.max_claims() => {
+                poi.free_claims += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the points of interest in a chunk in their saved form.
+    pub fn to_stored(&self, chunk: ChunkPosition) -> PoiChunk {
+        // Sections are keyed by their Y coordinate, which is
+        // negative below Y 0 in worlds extending below it.
+        let mut sections: BTreeMap<i32, Vec<PoiRecord>> =
+            (0..16).map(|y| (y, Vec::new())).collect();
+        for poi in self.in_chunk(chunk) {
+            sections
+                .entry(poi.pos.y.div_euclid(16))
+                .or_default()
+                .push(PoiRecord {
+                    pos: poi.pos,
+                    kind: poi.kind.name().to_owned(),
+                    free_tickets: poi.free_claims,
+                });
+        }
+
+        let mut stored = PoiChunk::new();
+        for (y, records) in sections {
+            stored.set_section(y, records);
+        }
+        stored
+    }
+
+    fn get_mut(&mut self, pos: BlockPosition) -> Option<&mut Poi> {
+        self.chunks
+            .get_mut(&pos.chunk())?
+            .iter_mut()
+            .find(|poi| poi.pos == pos)
+    }
+}
+
+/// Returns the points of interest in a loaded chunk. They are read
+/// from the saved points of interest where possible; other sections
+/// of the chunk are scanned for blocks which are points of interest.
+pub fn load_chunk_pois(chunk: &Chunk, stored: Option<&PoiChunk>) -> Vec<Poi> {
+    let mut pois = Vec::new();
+    for index in 0..chunk.num_sections() {
+        let y = chunk.min_y().div_euclid(16) + index as i32;
+        match stored.and_then(|stored| stored.section(y)) {
+            Some(records) => pois.extend(records.iter().filter_map(|record| {
+                let kind = PoiType::from_name(&record.kind)?;
+                Some(Poi {
+                    pos: record.pos,
+                    kind,
+                    free_claims: record.free_tickets.clamp(0, kind.max_claims()),
+                })
+            })),
+            None => scan_section(chunk, index, &mut pois),
+        }
+    }
+    pois
+}
+
+/// Adds the blocks which are points of interest in the
+/// section with the given index in the chunk.
+fn scan_section(chunk: &Chunk, index: usize, pois: &mut Vec<Poi>) {
+    let section = match chunk.section(index) {
+        Some(section) => section,
+        None => return,
+    };
+    // Most sections have a palette without any point
+    // of interest, so they don't need to be scanned.
+    if let Some(palette) = section.palette() {
+        if palette
+            .iter()
+            .all(|&block| PoiType::from_block(block).is_none())
+        {
+            return;
+        }
+    }
+
+    let origin = BlockPosition::new(
+        chunk.position().x * 16,
+        chunk.min_y() + index as i32 * 16,
+        chunk.position().z * 16,
+    );
+    for x in 0..16 {
+        for y in 0..16 {
+            for z in 0..16 {
+                if let Some(kind) = PoiType::from_block(section.block_at(x, y, z)) {
+                    let pos = origin + BlockPosition::new(x as i32, y as i32, z as i32);
+                    pois.push(Poi::new(pos, kind));
+                }
+            }
+        }
+    }
+}
+
+fn distance_squared(a: BlockPosition, b: BlockPosition) -> i64 {
+    let (dx, dy, dz) = (
+        i64::from(a.x - b.x),
+        i64::from(a.y - b.y),
+        i64::from(a.z - b.z),
+    );
+    dx * dx + dy * dy + dz * dz
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_types() {
+        assert_eq!(
+            PoiType::from_block(BlockId::red_bed().with_part(Part::Head)),
+            Some(PoiType::Home)
+        );
+        assert_eq!(
+            PoiType::from_block(BlockId::red_bed().with_part(Part::Foot)),
+            None
+        );
+        assert_eq!(
+            PoiType::from_block(BlockId::nether_portal()),
+            Some(PoiType::NetherPortal)
+        );
+        assert_eq!(PoiType::from_block(BlockId::stone()), None);
+        assert_eq!(
+            PoiType::from_name("minecraft:lodestone"),
+            Some(PoiType::Lodestone)
+        );
+    }
+
+    #[test]
+    fn claims_and_queries() {
+        let mut storage = PoiStorage::new();
+        let bed = BlockPosition::new(5, 64, 5);
+        let portal = BlockPosition::new(-20, 70, 3);
+        storage.add(Poi::new(bed, PoiType::Home));
+        storage.add(Poi::new(portal, PoiType::NetherPortal));
+
+        assert!(storage.claim(bed));
+        assert!(!storage.claim(bed));
+        assert!(!storage.claim(portal));
+        assert!(storage.release(bed));
+        assert!(!storage.release(bed));
+
+        let center = BlockPosition::new(0, 64, 0);
+        assert_eq!(storage.in_radius(center, 30, |_| true).count(), 2);
+        assert_eq!(
+            storage.nearest(center, 30, |_| true).map(|poi| poi.pos),
+            Some(bed)
+        );
+        assert_eq!(
+            storage
+                .nearest(center, 30, |poi| poi.kind == PoiType::NetherPortal)
+                .map(|poi| poi.pos),
+            Some(portal)
+        );
+        assert_eq!(storage.in_radius(center, 8, |_| true).count(), 1);
+
+        assert_eq!(storage.remove(bed).map(|poi| poi.kind), Some(PoiType::Home));
+        assert!(storage.get(bed).is_none());
+    }
+
+    #[test]
+    fn load_chunk() {
+        let mut chunk = Chunk::new(ChunkPosition::new(1, -1));
+        chunk.set_block_at(3, 10, 4, BlockId::brewing_stand());
+        chunk.set_block_at(3, 40, 4, BlockId::cauldron());
+
+        // Without saved points of interest, every section is scanned.
+        let pois = load_chunk_pois(&chunk, None);
+        assert_eq!(
+            pois,
+            vec![
+                Poi::new(BlockPosition::new(19, 10, -12), PoiType::Cleric),
+                Poi::new(BlockPosition::new(19, 40, -12), PoiType::Leatherworker),
+            ]
+        );
+
+        // Saved sections are used as is.
+        let mut storage = PoiStorage::new();
+        storage.insert_chunk(chunk.position(), pois);
+        storage.claim(BlockPosition::new(19, 10, -12));
+        let mut stored = storage.to_stored(chunk.position());
+        stored.set_section(2, vec![]);
+        assert_eq!(
+            load_chunk_pois(&chunk, Some(&stored)),
+            vec![Poi {
+                pos: BlockPosition::new(19, 10, -12),
+                kind: PoiType::Cleric,
+                free_claims: 0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,866 @@
+//! Recipes, which turn ingredients into results.
+//!
+//! Definitions are loaded from the vanilla data directory along
+//! with the item tags their ingredients may refer to. Crafting
+//! recipes are matched against crafting grids and cooking recipes,
+//! such as smelting, against the ingredients of furnaces. Special
+//! crafting recipes, such as dyeing armor, depend on item data
+//! which isn't supported yet and are never matched.
+
+use ahash::AHashMap;
+use anyhow::Context;
+use feather_core::items::{Item, ItemStack};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Maximum depth of tags referring to other tags.
+const MAX_TAG_DEPTH: usize = 16;
+
+/// Named sets of items, such as `minecraft:planks`.
+#[derive(Default, Debug)]
+pub struct ItemTags {
+    tags: AHashMap<String, Vec<Item>>,
+}
+
+impl ItemTags {
+    /// Loads the vanilla item tags extracted
+    /// into the data directory.
+    pub fn vanilla() -> anyhow::Result<Self> {
+        let path = format!(
+            "{}/minecraft/data/minecraft/tags/items",
+            feather_data::minecraft::PATH
+        );
+        let mut raw = AHashMap::new();
+        load_tag_dir(Path::new(&path), "minecraft:", &mut raw)?;
+
+        let mut tags = Self::default();
+        for name in raw.keys() {
+            let items = resolve_tag(&raw, name, 0)
+                .with_context(|| format!("failed to resolve tag {}", name))?;
+            tags.insert(name.clone(), items);
+        }
+        Ok(tags)
+    }
+
+    /// Sets the items in a tag, replacing any it had.
+    pub fn insert(&mut self, name: impl Into<String>, items: Vec<Item>) {
+        self.tags.insert(name.into(), items);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[Item]> {
+        self.tags.get(name).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+/// Reads the values of the tags in the given directory and its
+/// subdirectories, which may name items or other tags.
+fn load_tag_dir(
+    dir: &Path,
+    prefix: &str,
+    raw: &mut AHashMap<String, Vec<String>>,
+) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        if path.is_dir() {
+            load_tag_dir(&path, &format!("{}{}/", prefix, name), raw)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "json")
+        {
+            let value: Value = serde_json::from_slice(&fs::read(&path)?)
+                .with_context(|| format!("failed to load tag {}{}", prefix, name))?;
+            let values = value
+                .get("values")
+                .and_then(Value::as_array)
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default();
+            raw.insert(format!("{}{}", prefix, name), values);
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve_tag(
+    raw: &AHashMap<String, Vec<String>>,
+    name: &str,
+    depth: usize,
+) -> anyhow::Result<Vec<Item>> {
+    anyhow::ensure!(depth < MAX_TAG_DEPTH, "tag {} refers to itself", name);
+    let values = raw
+        .get(name)
+        .with_context(|| format!("unknown tag {}", name))?;
+
+    let mut items = vec![];
+    for value in values {
+        if value.starts_with('#') {
+            items.extend(resolve_tag(raw, &value[1..], depth + 1)?);
+        } else if let Some(item) = Item::from_identifier(value) {
+            items.push(item);
+        }
+    }
+    Ok(items)
+}
+
+/// The items one slot of a recipe accepts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ingredient(pub Vec<Item>);
+
+impl Ingredient {
+    /// Parses an ingredient, which is an item, a tag
+    /// or an array of either, resolving tags.
+    pub fn from_json(value: &Value, tags: &ItemTags) -> anyhow::Result<Self> {
+        let mut items = vec![];
+        match value {
+            Value::Array(choices) => {
+                for choice in choices {
+                    items.extend(Self::from_json(choice, tags)?.0);
+                }
+            }
+            _ => {
+                if let Some(item) = value.get("item").and_then(Value::as_str) {
+                    items.push(parse_item(item)?);
+                } else if let Some(tag) = value.get("tag").and_then(Value::as_str) {
+                    let tag = tags
+                        .get(tag)
+                        .with_context(|| format!("unknown tag {}", tag))?;
+                    items.extend_from_slice(tag);
+                } else {
+                    anyhow::bail!("ingredient has neither item nor tag");
+                }
+            }
+        }
+        Ok(Self(items))
+    }
+
+    pub fn matches(&self, stack: Option<ItemStack>) -> bool {
+        stack.map_or(false, |stack| self.0.contains(&stack.ty))
+    }
+}
+
+/// A crafting recipe whose ingredients are laid out in a pattern,
+/// which may be placed anywhere in the grid and mirrored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShapedRecipe {
+    pub width: usize,
+    pub height: usize,
+    /// Ingredients of the pattern, row by row;
+    /// `None` for slots which must be empty.
+    pub ingredients: Vec<Option<Ingredient>>,
+    pub result: ItemStack,
+}
+
+impl ShapedRecipe {
+    fn from_json(value: &Value, tags: &ItemTags) -> anyhow::Result<Self> {
+        let mut keys = AHashMap::new();
+        if let Some(object) = value.get("key").and_then(Value::as_object) {
+            for (key, ingredient) in object {
+                let mut chars = key.chars();
+                let key = match (chars.next(), chars.next()) {
+                    (Some(key), None) if key != ' ' => key,
+                    _ => anyhow::bail!("invalid key {:?}", key),
+                };
+                keys.insert(key, Ingredient::from_json(ingredient, tags)?);
+            }
+        }
+
+        let rows: Vec<&str> = value
+            .get("pattern")
+            .and_then(Value::as_array)
+            .context("recipe has no pattern")?
+            .iter()
+            .map(|row| row.as_str().context("pattern row is not a string"))
+            .collect::<anyhow::Result<_>>()?;
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = rows.len();
+
+        let mut ingredients = Vec::with_capacity(width * height);
+        for row in &rows {
+            let mut chars = row.chars();
+            for _ in 0..width {
+                ingredients.push(match chars.next() {
+                    None | Some(' ') => None,
+                    Some(key) => Some(
+                        keys.get(&key)
+                            .cloned()
+                            .with_context(|| format!("pattern key {:?} is undefined", key))?,
+                    ),
+                });
+            }
+        }
+
+        // Empty rows and columns at the edges of the pattern are
+        // ignored, as the pattern may be placed anywhere.
+        let (x, y, trimmed_width, trimmed_height) =
+            bounds(width, height, |index| ingredients[index].is_some())
+                .context("pattern is empty")?;
+        let ingredients = (y..y + trimmed_height)
+            .flat_map(|row| ingredients[row * width + x..row * width + x + trimmed_width].to_vec())
+            .collect();
+
+        Ok(Self {
+            width: trimmed_width,
+            height: trimmed_height,
+            ingredients,
+            result: parse_result(value)?,
+        })
+    }
+
+    /// Returns whether the items in a crafting grid
+    /// with the given width match this recipe.
+    pub fn matches(&self, grid: &[Option<ItemStack>], width: usize) -> bool {
+        let height = grid.len() / width;
+        let (x, y, grid_width, grid_height) =
+            match bounds(width, height, |index| grid[index].is_some()) {
+                Some(bounds) => bounds,
+                None => return false,
+            };
+        if (grid_width, grid_height) != (self.width, self.height) {
+            return false;
+        }
+
+        [false, true].iter().any(|&mirrored| {
+            (0..self.height).all(|row| {
+                (0..self.width).all(|column| {
+                    let pattern_column = if mirrored {
+                        self.width - 1 - column
+                    } else {
+                        column
+                    };
+                    let stack = grid[(y + row) * width + x + column];
+                    match &self.ingredients[row * self.width + pattern_column] {
+                        Some(ingredient) => ingredient.matches(stack),
+                        None => stack.is_none(),
+                    }
+                })
+            })
+        })
+    }
+}
+
+/// A crafting recipe whose ingredients may be placed in any slots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShapelessRecipe {
+    pub ingredients: Vec<Ingredient>,
+    pub result: ItemStack,
+}
+
+impl ShapelessRecipe {
+    fn from_json(value: &Value, tags: &ItemTags) -> anyhow::Result<Self> {
+        let ingredients = value
+            .get("ingredients")
+            .and_then(Value::as_array)
+            .context("recipe has no ingredients")?
+            .iter()
+            .map(|ingredient| Ingredient::from_json(ingredient, tags))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        anyhow::ensure!(!ingredients.is_empty(), "recipe has no ingredients");
+
+        Ok(Self {
+            ingredients,
+            result: parse_result(value)?,
+        })
+    }
+
+    /// Returns whether the items in a crafting grid match this recipe.
+    pub fn matches(&self, grid: &[Option<ItemStack>]) -> bool {
+        let stacks: Vec<ItemStack> = grid.iter().copied().flatten().collect();
+        if stacks.len() != self.ingredients.len() {
+            return false;
+        }
+
+        let mut used = vec![false; stacks.len()];
+        assign_ingredients(&self.ingredients, &stacks, &mut used)
+    }
+}
+
+/// Returns whether each ingredient can be assigned its own stack.
+///
+/// Ingredients may accept the same items, so this backtracks
+/// rather than assigning the first matching stack.
+fn assign_ingredients(ingredients: &[Ingredient], stacks: &[ItemStack], used: &mut [bool]) -> bool {
+    let (ingredient, rest) = match ingredients.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+
+    for (index, stack) in stacks.iter().enumerate() {
+        if used[index] || !ingredient.matches(Some(*stack)) {
+            continue;
+        }
+
+        used[index] = true;
+        if assign_ingredients(rest, stacks, used) {
+            return true;
+        }
+        used[index] = false;
+    }
+    false
+}
+
+/// The blocks cooking recipes are cooked in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CookingKind {
+    /// Cooked in furnaces.
+    Smelting,
+    /// Cooked in blast furnaces.
+    Blasting,
+    /// Cooked in smokers.
+    Smoking,
+    /// Cooked on campfires.
+    CampfireCooking,
+}
+
+impl CookingKind {
+    /// Returns the cooking kind of a recipe type.
+    pub fn from_type(ty: &str) -> Option<Self> {
+        match ty {
+            "smelting" => Some(CookingKind::Smelting),
+            "blasting" => Some(CookingKind::Blasting),
+            "smoking" => Some(CookingKind::Smoking),
+            "campfire_cooking" => Some(CookingKind::CampfireCooking),
+            _ => None,
+        }
+    }
+
+    /// Returns the default number of ticks
+    /// recipes of this kind cook for.
+    pub fn default_cooking_time(self) -> u32 {
+        match self {
+            CookingKind::Smelting => 200,
+            _ => 100,
+        }
+    }
+
+    /// Returns the number of fuel ticks used up per tick.
+    /// Blast furnaces and smokers cook twice as fast as
+    /// furnaces, using fuel twice as fast.
+    pub fn fuel_rate(self) -> u32 {
+        match self {
+            CookingKind::Blasting | CookingKind::Smoking => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// A recipe cooking an item into another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CookingRecipe {
+    pub kind: CookingKind,
+    pub ingredient: Ingredient,
+    pub result: ItemStack,
+    /// Experience gained per item cooked.
+    pub experience: f32,
+    /// Ticks needed to cook one item.
+    pub cooking_time: u32,
+}
+
+impl CookingRecipe {
+    fn from_json(kind: CookingKind, value: &Value, tags: &ItemTags) -> anyhow::Result<Self> {
+        let ingredient = Ingredient::from_json(
+            value
+                .get("ingredient")
+                .context("recipe has no ingredient")?,
+            tags,
+        )?;
+
+        // The result of cooking recipes is just an item ID.
+        let result = match value.get("result") {
+            Some(Value::String(item)) => ItemStack::new(parse_item(item)?, 1),
+            _ => parse_result(value)?,
+        };
+
+        Ok(Self {
+            kind,
+            ingredient,
+            result,
+            experience: value
+                .get("experience")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as f32,
+            cooking_time: value
+                .get("cookingtime")
+                .and_then(Value::as_u64)
+                .map_or(kind.default_cooking_time(), |time| time as u32),
+        })
+    }
+}
+
+/// The kind of a recipe and its ingredients.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecipeKind {
+    Shaped(ShapedRecipe),
+    Shapeless(ShapelessRecipe),
+    /// A special crafting recipe, with the given
+    /// type, whose logic is hardcoded.
+    Special(String),
+    Cooking(CookingRecipe),
+}
+
+/// The definition of a recipe.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recipe {
+    pub id: String,
+    /// Recipes in the same group are shown
+    /// together in the recipe book.
+    pub group: Option<String>,
+    pub kind: RecipeKind,
+}
+
+impl Recipe {
+    /// Parses a recipe in the data pack JSON format.
+    ///
+    /// Returns `None` for recipes of unsupported types,
+    /// such as stonecutting recipes.
+    pub fn from_json(
+        id: impl Into<String>,
+        json: &[u8],
+        tags: &ItemTags,
+    ) -> anyhow::Result<Option<Self>> {
+        let value: Value = serde_json::from_slice(json)?;
+
+        let ty = value
+            .get("type")
+            .and_then(Value::as_str)
+            .context("recipe has no type")?;
+        // Types are namespaced since 1.14.
+        let ty = ty.trim_start_matches("minecraft:");
+        let kind = match ty {
+            "crafting_shaped" => RecipeKind::Shaped(ShapedRecipe::from_json(&value, tags)?),
+            "crafting_shapeless" => {
+                RecipeKind::Shapeless(ShapelessRecipe::from_json(&value, tags)?)
+            }
+            ty if ty.starts_with("crafting_special_") => RecipeKind::Special(ty.to_owned()),
+            ty => match CookingKind::from_type(ty) {
+                Some(kind) => RecipeKind::Cooking(CookingRecipe::from_json(kind, &value, tags)?),
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(Self {
+            id: id.into(),
+            group: value
+                .get("group")
+                .and_then(Value::as_str)
+                .filter(|group| !group.is_empty())
+                .map(str::to_owned),
+            kind,
+        }))
+    }
+
+    /// Returns the crafted or cooked item,
+    /// or `None` for special recipes.
+    pub fn result(&self) -> Option<ItemStack> {
+        match &self.kind {
+            RecipeKind::Shaped(recipe) => Some(recipe.result),
+            RecipeKind::Shapeless(recipe) => Some(recipe.result),
+            RecipeKind::Special(_) => None,
+            RecipeKind::Cooking(recipe) => Some(recipe.result),
+        }
+    }
+
+    /// Returns whether the items in a crafting grid
+    /// with the given width match this recipe.
+    pub fn matches(&self, grid: &[Option<ItemStack>], width: usize) -> bool {
+        match &self.kind {
+            RecipeKind::Shaped(recipe) => recipe.matches(grid, width),
+            RecipeKind::Shapeless(recipe) => recipe.matches(grid),
+            RecipeKind::Special(_) | RecipeKind::Cooking(_) => false,
+        }
+    }
+}
+
+fn parse_item(identifier: &str) -> anyhow::Result<Item> {
+    Item::from_identifier(identifier).with_context(|| format!("unknown item {}", identifier))
+}
+
+fn parse_result(value: &Value) -> anyhow::Result<ItemStack> {
+    let result = value.get("result").context("recipe has no result")?;
+    let item = result
+        .get("item")
+        .and_then(Value::as_str)
+        .context("result has no item")?;
+    let count = result.get("count").and_then(Value::as_u64).unwrap_or(1);
+    Ok(ItemStack::new(parse_item(item)?, count as u8))
+}
+
+/// Returns the bounding box `(x, y, width, height)` of the
+/// occupied slots in a grid, or `None` if none are occupied.
+fn bounds(
+    width: usize,
+    height: usize,
+    occupied: impl Fn(usize) -> bool,
+) -> Option<(usize, usize, usize, usize)> {
+    let mut min = (usize::MAX, usize::MAX);
+    let mut max = (0, 0);
+    for y in 0..height {
+        for x in 0..width {
+            if occupied(y * width + x) {
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+            }
+        }
+    }
+
+    if min.0 == usize::MAX {
+        None
+    } else {
+        Some((min.0, min.1, max.0 - min.0 + 1, max.1 - min.1 + 1))
+    }
+}
+
+/// Returns the item left in a crafting grid slot
+/// after an item in it is used as an ingredient.
+pub fn crafting_remainder(item: Item) -> Option<Item> {
+    match item {
+        Item::WaterBucket | Item::LavaBucket | Item::MilkBucket => Some(Item::Bucket),
+        Item::DragonBreath => Some(Item::GlassBottle),
+        _ => None,
+    }
+}
+
+/// The recipes of the server.
+#[derive(Default, Debug)]
+pub struct RecipeRegistry {
+    recipes: Vec<Recipe>,
+    by_id: AHashMap<String, usize>,
+}
+
+impl RecipeRegistry {
+    /// Loads the vanilla recipes extracted
+    /// into the data directory.
+    pub fn vanilla() -> anyhow::Result<Self> {
+        let tags = ItemTags::vanilla().context("failed to load item tags")?;
+        let mut registry = Self::default();
+        let path = format!(
+            "{}/minecraft/data/minecraft/recipes",
+            feather_data::minecraft::PATH
+        );
+        registry.load_dir(Path::new(&path), "minecraft:", &tags)?;
+        Ok(registry)
+    }
+
+    /// Loads the recipes in the given directory and its
+    /// subdirectories. Their IDs are their paths relative to
+    /// the directory, without the extension, after `prefix`.
+    pub fn load_dir(&mut self, dir: &Path, prefix: &str, tags: &ItemTags) -> anyhow::Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .collect::<Result<Vec<_>, _>>()?;
+        // Sorted so that the first of several
+        // matching recipes is consistent.
+        entries.sort_by_key(|entry| entry.path());
+
+        for entry in entries {
+            let path = entry.path();
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if path.is_dir() {
+                self.load_dir(&path, &format!("{}{}/", prefix, name), tags)?;
+            } else if path
+                .extension()
+                .map_or(false, |extension| extension == "json")
+            {
+                let id = format!("{}{}", prefix, name);
+                let recipe = Recipe::from_json(id.clone(), &fs::read(&path)?, tags)
+                    .with_context(|| format!("failed to load recipe {}", id))?;
+                if let Some(recipe) = recipe {
+                    self.add(recipe);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds a recipe, replacing any with the same ID.
+    pub fn add(&mut self, recipe: Recipe) {
+        match self.by_id.get(&recipe.id) {
+            Some(&index) => self.recipes[index] = recipe,
+            None => {
+                self.by_id.insert(recipe.id.clone(), self.recipes.len());
+                self.recipes.push(recipe);
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Recipe> {
+        self.by_id.get(id).map(|&index| &self.recipes[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Recipe> {
+        self.recipes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    /// Returns the recipe matching the items in a crafting grid,
+    /// given row by row, with the given width: 2 for a player's
+    /// inventory and 3 for a crafting table.
+    pub fn match_crafting(&self, grid: &[Option<ItemStack>], width: usize) -> Option<&Recipe> {
+        if width == 0 || grid.iter().all(Option::is_none) {
+            return None;
+        }
+        self.recipes
+            .iter()
+            .find(|recipe| recipe.matches(grid, width))
+    }
+
+    /// Returns the recipe of the given kind cooking an item.
+    pub fn match_cooking(&self, kind: CookingKind, item: ItemStack) -> Option<&CookingRecipe> {
+        self.recipes.iter().find_map(|recipe| match &recipe.kind {
+            RecipeKind::Cooking(recipe)
+                if recipe.kind == kind && recipe.ingredient.matches(Some(item)) =>
+            {
+                Some(recipe)
+            }
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> RecipeRegistry {
+        let mut tags = ItemTags::default();
+        tags.insert(
+            "minecraft:planks",
+            vec![Item::OakPlanks, Item::SprucePlanks],
+        );
+
+        let mut registry = RecipeRegistry::default();
+        let recipes: &[(&str, &[u8])] = &[
+            (
+                "minecraft:stick",
+                br###"{
+                    "type": "crafting_shaped",
+                    "group": "sticks",
+                    "pattern": ["#", "#"],
+                    "key": { "#": { "tag": "minecraft:planks" } },
+                    "result": { "item": "minecraft:stick", "count": 4 }
+                }"###,
+            ),
+            (
+                "minecraft:wooden_axe",
+                br###"{
+                    "type": "crafting_shaped",
+                    "pattern": ["##", "#/", " /"],
+                    "key": {
+                        "#": { "tag": "minecraft:planks" },
+                        "/": { "item": "minecraft:stick" }
+                    },
+                    "result": { "item": "minecraft:wooden_axe" }
+                }"###,
+            ),
+            (
+                "minecraft:flint_and_steel",
+                br#"{
+                    "type": "crafting_shapeless",
+                    "ingredients": [
+                        [{ "item": "minecraft:flint" }, { "item": "minecraft:iron_ingot" }],
+                        { "item": "minecraft:iron_ingot" }
+                    ],
+                    "result": { "item": "minecraft:flint_and_steel" }
+                }"#,
+            ),
+            (
+                "minecraft:armor_dye",
+                br#"{ "type": "crafting_special_armordye" }"#,
+            ),
+            (
+                "minecraft:iron_ingot",
+                br#"{
+                    "type": "smelting",
+                    "ingredient": { "item": "minecraft:iron_ore" },
+                    "result": "minecraft:iron_ingot",
+                    "experience": 0.7
+                }"#,
+            ),
+            (
+                "minecraft:iron_ingot_from_blasting",
+                br#"{
+                    "type": "minecraft:blasting",
+                    "ingredient": { "item": "minecraft:iron_ore" },
+                    "result": "minecraft:iron_ingot",
+                    "experience": 0.7,
+                    "cookingtime": 100
+                }"#,
+            ),
+        ];
+        for (id, json) in recipes {
+            registry.add(Recipe::from_json(*id, json, &tags).unwrap().unwrap());
+        }
+        registry
+    }
+
+    fn stack(item: Item) -> Option<ItemStack> {
+        Some(ItemStack::new(item, 1))
+    }
+
+    fn crafted(
+        registry: &RecipeRegistry,
+        grid: &[Option<ItemStack>],
+        width: usize,
+    ) -> Option<&str> {
+        registry
+            .match_crafting(grid, width)
+            .map(|recipe| recipe.id.as_str())
+    }
+
+    #[test]
+    fn parse() {
+        let registry = registry();
+        assert_eq!(registry.len(), 6);
+
+        let stick = registry.get("minecraft:stick").unwrap();
+        assert_eq!(stick.group.as_deref(), Some("sticks"));
+        assert_eq!(stick.result(), Some(ItemStack::new(Item::Stick, 4)));
+        match &stick.kind {
+            RecipeKind::Shaped(recipe) => {
+                assert_eq!((recipe.width, recipe.height), (1, 2));
+                assert_eq!(
+                    recipe.ingredients[0],
+                    Some(Ingredient(vec![Item::OakPlanks, Item::SprucePlanks]))
+                );
+            }
+            kind => panic!("{:?}", kind),
+        }
+
+        let dye = registry.get("minecraft:armor_dye").unwrap();
+        assert_eq!(
+            dye.kind,
+            RecipeKind::Special("crafting_special_armordye".to_owned())
+        );
+        assert_eq!(dye.result(), None);
+
+        let stonecutting = br#"{
+            "type": "minecraft:stonecutting",
+            "ingredient": { "item": "minecraft:stone" },
+            "result": "minecraft:stone_slab",
+            "count": 2
+        }"#;
+        assert_eq!(
+            Recipe::from_json("minecraft:stone_slab", stonecutting, &ItemTags::default()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn match_shaped() {
+        let registry = registry();
+
+        // Anywhere in the grid, with any planks in the tag.
+        let mut grid = vec![None; 9];
+        grid[4] = stack(Item::OakPlanks);
+        grid[7] = stack(Item::SprucePlanks);
+        assert_eq!(crafted(&registry, &grid, 3), Some("minecraft:stick"));
+        assert_eq!(crafted(&registry, &grid[..4], 2), None);
+
+        grid[7] = stack(Item::Stone);
+        assert_eq!(crafted(&registry, &grid, 3), None);
+
+        // Mirrored, but not too large for the grid.
+        let axe = |planks: [usize; 3], sticks: [usize; 2]| {
+            let mut grid = vec![None; 9];
+            for &slot in &planks {
+                grid[slot] = stack(Item::OakPlanks);
+            }
+            for &slot in &sticks {
+                grid[slot] = stack(Item::Stick);
+            }
+            grid
+        };
+        assert_eq!(
+            crafted(&registry, &axe([0, 1, 3], [4, 7]), 3),
+            Some("minecraft:wooden_axe")
+        );
+        assert_eq!(
+            crafted(&registry, &axe([1, 2, 5], [4, 7]), 3),
+            Some("minecraft:wooden_axe")
+        );
+        assert_eq!(crafted(&registry, &axe([1, 2, 5], [4, 8]), 3), None);
+    }
+
+    #[test]
+    fn match_shapeless() {
+        let registry = registry();
+
+        // The first ingredient accepts the iron too,
+        // but must take the flint.
+        let grid = [stack(Item::IronIngot), None, None, stack(Item::Flint)];
+        assert_eq!(
+            crafted(&registry, &grid, 2),
+            Some("minecraft:flint_and_steel")
+        );
+
+        let grid = [None, stack(Item::IronIngot), stack(Item::IronIngot), None];
+        assert_eq!(
+            crafted(&registry, &grid, 2),
+            Some("minecraft:flint_and_steel")
+        );
+
+        let grid = [stack(Item::Flint), stack(Item::Flint), None, None];
+        assert_eq!(crafted(&registry, &grid, 2), None);
+
+        let grid = [stack(Item::IronIngot), None, None, None];
+        assert_eq!(crafted(&registry, &grid, 2), None);
+    }
+
+    #[test]
+    fn match_cooking() {
+        let registry = registry();
+
+        let ore = ItemStack::new(Item::IronOre, 5);
+        let smelting = registry.match_cooking(CookingKind::Smelting, ore).unwrap();
+        assert_eq!(smelting.result, ItemStack::new(Item::IronIngot, 1));
+        assert_eq!(smelting.cooking_time, 200);
+        assert!((smelting.experience - 0.7).abs() < f32::EPSILON);
+
+        let blasting = registry.match_cooking(CookingKind::Blasting, ore).unwrap();
+        assert_eq!(blasting.cooking_time, 100);
+
+        assert!(registry.match_cooking(CookingKind::Smoking, ore).is_none());
+        assert!(registry
+            .match_cooking(CookingKind::Smelting, ItemStack::new(Item::Stone, 1))
+            .is_none());
+
+        // Cooking recipes aren't crafted.
+        let grid = [Some(ore.of_amount(1)), None, None, None];
+        assert!(registry.match_crafting(&grid, 2).is_none());
+    }
+}
@@ -0,0 +1,134 @@
+//! Resource packs sent to players.
+
+use feather_core::network::packets::ResourcePackSend;
+use feather_core::text::Text;
+use feather_server_config::ResourcePack as ResourcePackConfig;
+
+/// A resource pack which players are prompted to download.
+///
+/// Also used as a component storing the resource
+/// pack last sent to a player.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourcePack {
+    pub url: String,
+    /// SHA-1 hash of the pack, as a hex string. If empty,
+    /// clients download the pack each time it is sent.
+    pub hash: String,
+    /// Whether players who decline the pack or fail
+    /// to download it are kicked.
+    pub forced: bool,
+    /// Message shown in the prompt to download the pack.
+    /// Only shown to 1.17+ clients.
+    pub prompt: Option<Text>,
+}
+
+impl ResourcePack {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            hash: String::new(),
+            forced: false,
+            prompt: None,
+        }
+    }
+
+    /// Returns the resource pack configured in `feather.toml`,
+    /// or `None` if there is none.
+    pub fn from_config(config: &ResourcePackConfig) -> Option<Self> {
+        if config.url.is_empty() {
+            return None;
+        }
+
+        let mut pack = Self::new(config.url.clone()).hash(config.hash.clone());
+        pack.forced = config.forced;
+        if !config.prompt.is_empty() {
+            pack.prompt = Some(Text::from(config.prompt.clone()));
+        }
+        Some(pack)
+    }
+
+    pub fn hash(mut self, hash: impl Into<String>) -> Self {
+        self.hash = hash.into();
+        self
+    }
+
+    pub fn forced(mut self, forced: bool) -> Self {
+        self.forced = forced;
+        self
+    }
+
+    pub fn prompt(mut self, prompt: impl Into<Text>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    /// Returns the packet which prompts a player to download the pack.
+    pub fn packet(&self) -> ResourcePackSend {
+        ResourcePackSend {
+            url: self.url.clone(),
+            hash: self.hash.clone(),
+            forced: self.forced,
+            prompt: self.prompt.clone().map(String::from),
+        }
+    }
+}
+
+/// The status of a resource pack reported by a client.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResourcePackStatus {
+    /// The pack was downloaded and applied.
+    Loaded,
+    /// The player declined the pack.
+    Declined,
+    /// The pack could not be downloaded.
+    Failed,
+    /// The player accepted the pack, which is
+    /// now being downloaded.
+    Accepted,
+}
+
+impl ResourcePackStatus {
+    pub fn from_id(id: i32) -> Option<Self> {
+        Some(match id {
+            0 => ResourcePackStatus::Loaded,
+            1 => ResourcePackStatus::Declined,
+            2 => ResourcePackStatus::Failed,
+            3 => ResourcePackStatus::Accepted,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether the player won't use the pack.
+    pub fn is_rejected(self) -> bool {
+        matches!(
+            self,
+            ResourcePackStatus::Declined | ResourcePackStatus::Failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_from_config() {
+        let mut config = ResourcePackConfig {
+            url: String::new(),
+            hash: String::from("abc"),
+            forced: true,
+            prompt: String::new(),
+        };
+        assert_eq!(ResourcePack::from_config(&config), None);
+
+        config.url = String::from("https://example.com/pack.zip");
+        let pack = ResourcePack::from_config(&config).unwrap();
+        assert_eq!(
+            pack,
+            ResourcePack::new("https://example.com/pack.zip")
+                .hash("abc")
+                .forced(true)
+        );
+        assert_eq!(pack.packet().prompt, None);
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::{Arc, RwLock};
 
 pub use crate::game::*;
 pub use crate::task::*;
-pub use feather_server_config::{Ban, BanInfo, Config, ProxyMode};
+pub use feather_server_config::{Ban, BanInfo, Config, ProxyMode, RateLimit, RateLimitAction};
 pub type WrappedBanInfo = Arc<RwLock<BanInfo>>;
 
 pub use feather_server_packet_buffer::{PacketBuffer, PacketBuffers};
@@ -0,0 +1,599 @@
+//! The scoreboard, which tracks scores of players and other
+//! entities for a set of objectives, and groups them into teams.
+//!
+//! Scores and team members are identified by entries: the name
+//! of a player, or the UUID of another entity. Each method which
+//! changes the scoreboard returns the packet to broadcast to
+//! all players so that they see the change.
+
+use ahash::AHashMap;
+use feather_core::network::packets::{
+    DisplayScoreboard, ObjectiveAction, ScoreboardObjective, TeamInfo, Teams, TeamsAction,
+    UpdateScore,
+};
+use feather_core::network::Packet;
+use feather_core::text::{Color, Text};
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+pub use feather_core::network::packets::ObjectiveRenderType;
+
+/// The longest name of an objective or team.
+pub const MAX_NAME_LEN: usize = 16;
+/// The longest entry, as for a player name or UUID.
+pub const MAX_ENTRY_LEN: usize = 40;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ScoreboardError {
+    #[error("an objective named {0} already exists")]
+    ObjectiveExists(String),
+    #[error("unknown objective {0}")]
+    UnknownObjective(String),
+    #[error("a team named {0} already exists")]
+    TeamExists(String),
+    #[error("unknown team {0}")]
+    UnknownTeam(String),
+    #[error("{0} is longer than {1} characters")]
+    TooLong(String, usize),
+}
+
+/// A place in which an objective's scores can be displayed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DisplaySlot {
+    /// Next to players' names in the tab list.
+    List,
+    /// The sidebar, which shows the highest scores.
+    Sidebar,
+    /// Below players' name tags.
+    BelowName,
+}
+
+impl DisplaySlot {
+    pub fn id(self) -> i8 {
+        match self {
+            DisplaySlot::List => 0,
+            DisplaySlot::Sidebar => 1,
+            DisplaySlot::BelowName => 2,
+        }
+    }
+
+    /// Returns the slot with the given name, as used by the `/scoreboard` command.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "list" => Some(DisplaySlot::List),
+            "sidebar" => Some(DisplaySlot::Sidebar),
+            "belowName" => Some(DisplaySlot::BelowName),
+            _ => None,
+        }
+    }
+}
+
+/// An objective, which has a score for each of its entries.
+#[derive(Clone, Debug)]
+pub struct Objective {
+    pub display_name: Text,
+    pub render_type: ObjectiveRenderType,
+    scores: AHashMap<String, i32>,
+}
+
+impl Objective {
+    fn action(&self) -> (String, ObjectiveRenderType) {
+        (self.display_name.clone().into(), self.render_type)
+    }
+}
+
+/// When the name tags of a team's members are shown.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NameTagVisibility {
+    Always,
+    HideForOtherTeams,
+    HideForOwnTeam,
+    Never,
+}
+
+impl NameTagVisibility {
+    pub fn name(self) -> &'static str {
+        match self {
+            NameTagVisibility::Always => "always",
+            NameTagVisibility::HideForOtherTeams => "hideForOtherTeams",
+            NameTagVisibility::HideForOwnTeam => "hideForOwnTeam",
+            NameTagVisibility::Never => "never",
+        }
+    }
+}
+
+/// Which entities a team's members push.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CollisionRule {
+    Always,
+    PushOtherTeams,
+    PushOwnTeam,
+    Never,
+}
+
+impl CollisionRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            CollisionRule::Always => "always",
+            CollisionRule::PushOtherTeams => "pushOtherTeams",
+            CollisionRule::PushOwnTeam => "pushOwnTeam",
+            CollisionRule::Never => "never",
+        }
+    }
+}
+
+/// A team of entries, whose names are shown
+/// with the team's color, prefix and suffix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Team {
+    pub display_name: Text,
+    pub prefix: Text,
+    pub suffix: Text,
+    /// The color of members' names. Custom colors
+    /// are not supported and reset the color.
+    pub color: Option<Color>,
+    /// Whether members can damage each other.
+    pub friendly_fire: bool,
+    /// Whether members can see invisible members.
+    pub see_friendly_invisibles: bool,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+    members: BTreeSet<String>,
+}
+
+impl Team {
+    fn new(name: &str) -> Self {
+        Self {
+            display_name: Text::of(name.to_owned()),
+            prefix: Text::of(""),
+            suffix: Text::of(""),
+            color: None,
+            friendly_fire: true,
+            see_friendly_invisibles: true,
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            members: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the entries on the team.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(String::as_str)
+    }
+
+    fn info(&self) -> TeamInfo {
+        let mut friendly_flags = 0;
+        if self.friendly_fire {
+            friendly_flags |= 0x01;
+        }
+        if self.see_friendly_invisibles {
+            friendly_flags |= 0x02;
+        }
+
+        TeamInfo {
+            display_name: self.display_name.clone().into(),
+            friendly_flags,
+            name_tag_visibility: self.name_tag_visibility.name().to_owned(),
+            collision_rule: self.collision_rule.name().to_owned(),
+            color: self.color.as_ref().map(color_id).unwrap_or(RESET_COLOR),
+            prefix: self.prefix.clone().into(),
+            suffix: self.suffix.clone().into(),
+        }
+    }
+}
+
+/// The ID of the reset formatting code, used for teams without a color.
+const RESET_COLOR: i32 = 21;
+
+fn color_id(color: &Color) -> i32 {
+    match color {
+        Color::Black => 0,
+        Color::DarkBlue => 1,
+        Color::DarkGreen => 2,
+        Color::DarkAqua => 3,
+        Color::DarkRed => 4,
+        Color::DarkPurple => 5,
+        Color::Gold => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::Blue => 9,
+        Color::Green => 10,
+        Color::Aqua => 11,
+        Color::Red => 12,
+        Color::LightPurple => 13,
+        Color::Yellow => 14,
+        Color::White => 15,
+        Color::Custom(_) => RESET_COLOR,
+    }
+}
+
+fn check_len(name: &str, max: usize) -> Result<(), ScoreboardError> {
+    if name.chars().count() > max {
+        Err(ScoreboardError::TooLong(name.to_owned(), max))
+    } else {
+        Ok(())
+    }
+}
+
+/// The server's scoreboard, shared by all worlds.
+#[derive(Clone, Debug, Default)]
+pub struct Scoreboard {
+    objectives: AHashMap<String, Objective>,
+    display_slots: AHashMap<DisplaySlot, String>,
+    teams: AHashMap<String, Team>,
+    /// The team of each entry which is on one.
+    entry_teams: AHashMap<String, String>,
+}
+
+impl Scoreboard {
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives.get(name)
+    }
+
+    pub fn objectives(&self) -> impl Iterator<Item = (&str, &Objective)> {
+        self.objectives
+            .iter()
+            .map(|(name, objective)| (name.as_str(), objective))
+    }
+
+    pub fn add_objective(
+        &mut self,
+        name: &str,
+        display_name: Text,
+        render_type: ObjectiveRenderType,
+    ) -> Result<ScoreboardObjective, ScoreboardError> {
+        check_len(name, MAX_NAME_LEN)?;
+        if self.objectives.contains_key(name) {
+            return Err(ScoreboardError::ObjectiveExists(name.to_owned()));
+        }
+
+        let objective = Objective {
+            display_name,
+            render_type,
+            scores: AHashMap::new(),
+        };
+        let (display_name, render_type) = objective.action();
+        self.objectives.insert(name.to_owned(), objective);
+
+        Ok(ScoreboardObjective {
+            objective_name: name.to_owned(),
+            action: ObjectiveAction::Create(display_name, render_type),
+        })
+    }
+
+    /// Removes an objective, along with its scores.
+    /// Clients stop displaying it.
+    pub fn remove_objective(&mut self, name: &str) -> Result<ScoreboardObjective, ScoreboardError> {
+        if self.objectives.remove(name).is_none() {
+            return Err(ScoreboardError::UnknownObjective(name.to_owned()));
+        }
+        self.display_slots
+            .retain(|_, objective| objective.as_str() != name);
+
+        Ok(ScoreboardObjective {
+            objective_name: name.to_owned(),
+            action: ObjectiveAction::Remove,
+        })
+    }
+
+    /// Changes the display name and render type of an objective.
+    pub fn update_objective(
+        &mut self,
+        name: &str,
+        display_name: Text,
+        render_type: ObjectiveRenderType,
+    ) -> Result<ScoreboardObjective, ScoreboardError> {
+        let objective = self
+            .objectives
+            .get_mut(name)
+            .ok_or_else(|| ScoreboardError::UnknownObjective(name.to_owned()))?;
+        objective.display_name = display_name;
+        objective.render_type = render_type;
+        let (display_name, render_type) = objective.action();
+
+        Ok(ScoreboardObjective {
+            objective_name: name.to_owned(),
+            action: ObjectiveAction::Update(display_name, render_type),
+        })
+    }
+
+    /// Returns the objective displayed in a slot.
+    pub fn displayed(&self, slot: DisplaySlot) -> Option<&str> {
+        self.display_slots.get(&slot).map(String::as_str)
+    }
+
+    /// Displays an objective in a slot, or clears
+    /// the slot if `objective` is `None`.
+    pub fn set_displayed(
+        &mut self,
+        slot: DisplaySlot,
+        objective: Option<&str>,
+    ) -> Result<DisplayScoreboard, ScoreboardError> {
+        match objective {
+            Some(name) => {
+                if !self.objectives.contains_key(name) {
+                    return Err(ScoreboardError::UnknownObjective(name.to_owned()));
+                }
+                self.display_slots.insert(slot, name.to_owned());
+            }
+            None => {
+                self.display_slots.remove(&slot);
+            }
+        }
+
+        Ok(DisplayScoreboard {
+            position: slot.id(),
+            score_name: objective.unwrap_or_default().to_owned(),
+        })
+    }
+
+    /// Returns the score of an entry for an objective.
+    pub fn score(&self, objective: &str, entry: &str) -> Option<i32> {
+        self.objectives.get(objective)?.scores.get(entry).copied()
+    }
+
+    pub fn set_score(
+        &mut self,
+        objective: &str,
+        entry: &str,
+        score: i32,
+    ) -> Result<UpdateScore, ScoreboardError> {
+        check_len(entry, MAX_ENTRY_LEN)?;
+        let scores = &mut self
+            .objectives
+            .get_mut(objective)
+            .ok_or_else(|| ScoreboardError::UnknownObjective(objective.to_owned()))?
+            .scores;
+        scores.insert(entry.to_owned(), score);
+
+        Ok(UpdateScore {
+            entity_name: entry.to_owned(),
+            objective_name: objective.to_owned(),
+            value: Some(score),
+        })
+    }
+
+    /// Removes the score of an entry for an objective.
+    pub fn reset_score(
+        &mut self,
+        objective: &str,
+        entry: &str,
+    ) -> Result<UpdateScore, ScoreboardError> {
+        let scores = &mut self
+            .objectives
+            .get_mut(objective)
+            .ok_or_else(|| ScoreboardError::UnknownObjective(objective.to_owned()))?
+            .scores;
+        scores.remove(entry);
+
+        Ok(UpdateScore {
+            entity_name: entry.to_owned(),
+            objective_name: objective.to_owned(),
+            value: None,
+        })
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.get(name)
+    }
+
+    pub fn teams(&self) -> impl Iterator<Item = (&str, &Team)> {
+        self.teams.iter().map(|(name, team)| (name.as_str(), team))
+    }
+
+    /// Returns the name of the team an entry is on.
+    pub fn team_of(&self, entry: &str) -> Option<&str> {
+        self.entry_teams.get(entry).map(String::as_str)
+    }
+
+    /// Adds an empty team, with the default options.
+    pub fn add_team(&mut self, name: &str) -> Result<Teams, ScoreboardError> {
+        check_len(name, MAX_NAME_LEN)?;
+        if self.teams.contains_key(name) {
+            return Err(ScoreboardError::TeamExists(name.to_owned()));
+        }
+
+        let team = Team::new(name);
+        let info = team.info();
+        self.teams.insert(name.to_owned(), team);
+
+        Ok(Teams {
+            name: name.to_owned(),
+            action: TeamsAction::Create(info, vec![]),
+        })
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Result<Teams, ScoreboardError> {
+        let team = self
+            .teams
+            .remove(name)
+            .ok_or_else(|| ScoreboardError::UnknownTeam(name.to_owned()))?;
+        for member in &team.members {
+            self.entry_teams.remove(member);
+        }
+
+        Ok(Teams {
+            name: name.to_owned(),
+            action: TeamsAction::Remove,
+        })
+    }
+
+    /// Changes the options of a team.
+    pub fn update_team(
+        &mut self,
+        name: &str,
+        update: impl FnOnce(&mut Team),
+    ) -> Result<Teams, ScoreboardError> {
+        let team = self
+            .teams
+            .get_mut(name)
+            .ok_or_else(|| ScoreboardError::UnknownTeam(name.to_owned()))?;
+        update(team);
+
+        Ok(Teams {
+            name: name.to_owned(),
+            action: TeamsAction::UpdateInfo(team.info()),
+        })
+    }
+
+    /// Adds entries to a team. Entries on another
+    /// team leave it, as they do on clients.
+    pub fn join_team(&mut self, name: &str, entries: &[String]) -> Result<Teams, ScoreboardError> {
+        if !self.teams.contains_key(name) {
+            return Err(ScoreboardError::UnknownTeam(name.to_owned()));
+        }
+        for entry in entries {
+            check_len(entry, MAX_ENTRY_LEN)?;
+        }
+
+        for entry in entries {
+            if let Some(old) = self.entry_teams.insert(entry.clone(), name.to_owned()) {
+                if let Some(team) = self.teams.get_mut(&old) {
+                    team.members.remove(entry);
+                }
+            }
+            self.teams
+                .get_mut(name)
+                .expect("team exists")
+                .members
+                .insert(entry.clone());
+        }
+
+        Ok(Teams {
+            name: name.to_owned(),
+            action: TeamsAction::AddEntities(entries.to_vec()),
+        })
+    }
+
+    /// Removes entries from their teams, returning
+    /// a packet for each team which they left.
+    pub fn leave_team(&mut self, entries: &[String]) -> Vec<Teams> {
+        let mut left: AHashMap<String, Vec<String>> = AHashMap::new();
+        for entry in entries {
+            if let Some(name) = self.entry_teams.remove(entry) {
+                if let Some(team) = self.teams.get_mut(&name) {
+                    team.members.remove(entry);
+                }
+                left.entry(name).or_default().push(entry.clone());
+            }
+        }
+
+        left.into_iter()
+            .map(|(name, entries)| Teams {
+                name,
+                action: TeamsAction::RemoveEntities(entries),
+            })
+            .collect()
+    }
+
+    /// Returns the packets which send the whole scoreboard
+    /// to a player who joins.
+    pub fn initialize_packets(&self) -> Vec<Box<dyn Packet>> {
+        let mut packets: Vec<Box<dyn Packet>> = vec![];
+
+        for (name, objective) in &self.objectives {
+            let (display_name, render_type) = objective.action();
+            packets.push(Box::new(ScoreboardObjective {
+                objective_name: name.clone(),
+                action: ObjectiveAction::Create(display_name, render_type),
+            }));
+            for (entry, score) in &objective.scores {
+                packets.push(Box::new(UpdateScore {
+                    entity_name: entry.clone(),
+                    objective_name: name.clone(),
+                    value: Some(*score),
+                }));
+            }
+        }
+
+        for (slot, objective) in &self.display_slots {
+            packets.push(Box::new(DisplayScoreboard {
+                position: slot.id(),
+                score_name: objective.clone(),
+            }));
+        }
+
+        for (name, team) in &self.teams {
+            packets.push(Box::new(Teams {
+                name: name.clone(),
+                action: TeamsAction::Create(team.info(), team.members.iter().cloned().collect()),
+            }));
+        }
+
+        packets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objectives_and_scores() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard
+            .add_objective("kills", Text::of("Kills"), ObjectiveRenderType::Integer)
+            .unwrap();
+        assert!(matches!(
+            scoreboard.add_objective("kills", Text::of("Kills"), ObjectiveRenderType::Integer),
+            Err(ScoreboardError::ObjectiveExists(_))
+        ));
+
+        let packet = scoreboard.set_score("kills", "caelunshun", 5).unwrap();
+        assert_eq!(packet.value, Some(5));
+        assert_eq!(scoreboard.score("kills", "caelunshun"), Some(5));
+        assert!(scoreboard.set_score("deaths", "caelunshun", 1).is_err());
+
+        let packet = scoreboard
+            .set_displayed(DisplaySlot::Sidebar, Some("kills"))
+            .unwrap();
+        assert_eq!((packet.position, packet.score_name.as_str()), (1, "kills"));
+        assert_eq!(scoreboard.initialize_packets().len(), 3);
+
+        scoreboard.remove_objective("kills").unwrap();
+        assert_eq!(scoreboard.displayed(DisplaySlot::Sidebar), None);
+        assert_eq!(scoreboard.score("kills", "caelunshun"), None);
+    }
+
+    #[test]
+    fn teams() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.add_team("red").unwrap();
+        scoreboard.add_team("blue").unwrap();
+        assert!(scoreboard.add_team("a_very_long_team_name").is_err());
+
+        let packet = scoreboard
+            .update_team("red", |team| {
+                team.color = Some(Color::Red);
+                team.friendly_fire = false;
+            })
+            .unwrap();
+        match packet.action {
+            TeamsAction::UpdateInfo(info) => {
+                assert_eq!(info.color, 12);
+                assert_eq!(info.friendly_flags, 0x02);
+            }
+            action => panic!("unexpected action {:?}", action),
+        }
+
+        let entries = vec![String::from("a"), String::from("b")];
+        scoreboard.join_team("red", &entries).unwrap();
+        scoreboard.join_team("blue", &entries[..1]).unwrap();
+        assert_eq!(scoreboard.team_of("a"), Some("blue"));
+        assert_eq!(
+            scoreboard
+                .team("red")
+                .unwrap()
+                .members()
+                .collect::<Vec<_>>(),
+            vec!["b"]
+        );
+
+        let packets = scoreboard.leave_team(&entries);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(scoreboard.team_of("b"), None);
+
+        scoreboard.remove_team("red").unwrap();
+        assert!(scoreboard.team("red").is_none());
+    }
+}
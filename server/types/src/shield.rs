@@ -0,0 +1,263 @@
+//! Blocking attacks with shields, which stops the damage of
+//! attacks coming from in front of an entity at the cost of
+//! the shield's durability. Hitting a shield with an axe
+//! disables it for a while.
+//!
+//! See https://minecraft.gamepedia.com/Shield.
+
+use crate::{
+    CanTakeDamage, DamageSource, Game, HeldItem, ItemDamageEvent, Network, NetworkId, Player,
+};
+use feather_core::entitymeta::{
+    EntityMetadata, HandState, META_INDEX_LIVING_HAND_STATE, PLAYER_LAYOUT,
+};
+use feather_core::inventory::{slot, Area, Inventory, SlotIndex};
+use feather_core::items::{Item, ItemStack, Tool};
+use feather_core::network::packets::{EntityStatus, PacketEntityMetadata, SetCooldown};
+use feather_core::util::{Hand, Position, Vec3d};
+use fecs::{Entity, World};
+
+/// Ticks a shield must be raised for before it blocks attacks.
+const RAISE_TICKS: u64 = 5;
+/// Ticks a shield stays disabled for after being hit with an axe.
+const DISABLE_TICKS: u64 = 100;
+/// Damage below which a blocked attack doesn't wear down the shield.
+const MIN_SHIELD_DAMAGE: u32 = 3;
+
+/// Entity status which plays the shield block sound.
+const SHIELD_BLOCK_STATUS: i8 = 29;
+/// Entity status which plays the shield break sound.
+const SHIELD_DISABLE_STATUS: i8 = 30;
+
+/// Component of an entity raising a shield.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Blocking {
+    /// The tick on which the shield was raised.
+    pub tick_start: u64,
+    /// The hand holding the shield.
+    pub hand: Hand,
+}
+
+/// The tick until which an entity's shield is disabled
+/// after being hit with an axe.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ShieldCooldown(pub u64);
+
+/// Returns the durability lost by a shield blocking the given damage.
+pub fn shield_damage(damage: u32) -> u32 {
+    if damage >= MIN_SHIELD_DAMAGE {
+        damage + 1
+    } else {
+        0
+    }
+}
+
+/// Returns whether a shield raised while looking in the given
+/// direction blocks an attack coming from `from`, i.e. whether
+/// the attack comes from in front of the entity.
+pub fn faces_attack(look: Vec3d, from: Vec3d) -> bool {
+    look.x * from.x + look.z * from.z > 0.0
+}
+
+/// Returns the hand state of an entity using the item in
+/// the given hand, or of an entity using no item.
+pub fn hand_state(hand: Option<Hand>) -> HandState {
+    match hand {
+        Some(Hand::Main) => HandState::ACTIVE,
+        Some(Hand::Off) => HandState::ACTIVE | HandState::OFFHAND,
+        None => HandState::empty(),
+    }
+}
+
+/// Returns the slot of the shield an entity holds in the given hand.
+fn shield_slot(world: &World, entity: Entity, hand: Hand) -> Option<SlotIndex> {
+    let index = match hand {
+        Hand::Main => slot(
+            Area::Hotbar,
+            world.try_get::<HeldItem>(entity).map_or(0, |held| held.0),
+        ),
+        Hand::Off => slot(Area::Offhand, 0),
+    };
+    let inventory = world.try_get::<Inventory>(entity)?;
+    match inventory.item_at(index.area, index.slot) {
+        Ok(Some(stack)) if stack.ty == Item::Shield => Some(index),
+        _ => None,
+    }
+}
+
+/// Returns the item an entity holds in its main hand.
+fn item_in_main_hand(world: &World, entity: Entity) -> Option<ItemStack> {
+    let held = world.try_get::<HeldItem>(entity).map_or(0, |held| held.0);
+    world
+        .try_get::<Inventory>(entity)?
+        .item_at(Area::Hotbar, held)
+        .ok()
+        .flatten()
+}
+
+impl Game {
+    /// Raises the shield an entity holds in the given hand.
+    /// Returns whether it holds a shield there, even if
+    /// the shield is still disabled.
+    pub fn raise_shield(&mut self, world: &mut World, entity: Entity, hand: Hand) -> bool {
+        if shield_slot(world, entity, hand).is_none() {
+            return false;
+        }
+        let disabled = world
+            .try_get::<ShieldCooldown>(entity)
+            .map_or(false, |cooldown| self.tick_count < cooldown.0);
+        if disabled {
+            return true;
+        }
+
+        let blocking = Blocking {
+            tick_start: self.tick_count,
+            hand,
+        };
+        match world.try_get_mut::<Blocking>(entity) {
+            Some(mut old) => *old = blocking,
+            None => world.add(entity, blocking).unwrap(),
+        }
+        self.broadcast_hand_state(world, entity, hand_state(Some(hand)));
+        true
+    }
+
+    /// Lowers the shield raised by an entity, if any.
+    pub fn lower_shield(&mut self, world: &mut World, entity: Entity) {
+        if world.has::<Blocking>(entity) {
+            world.remove::<Blocking>(entity).unwrap();
+            self.broadcast_hand_state(world, entity, hand_state(None));
+        }
+    }
+
+    /// Returns whether a shield blocks the damage an entity takes from
+    /// the given source: the entity must have raised its shield for
+    /// long enough and face the attacker.
+    pub fn blocks_with_shield(&self, world: &World, entity: Entity, source: DamageSource) -> bool {
+        if world.has::<Player>(entity) && !world.has::<CanTakeDamage>(entity) {
+            return false;
+        }
+        let blocking = match world.try_get::<Blocking>(entity) {
+            Some(blocking) => *blocking,
+            None => return false,
+        };
+        if self.tick_count < blocking.tick_start + RAISE_TICKS
+            || shield_slot(world, entity, blocking.hand).is_none()
+        {
+            return false;
+        }
+
+        let attacker = match source {
+            DamageSource::Mob(attacker)
+            | DamageSource::Player(attacker)
+            | DamageSource::Arrow(Some(attacker)) => attacker,
+            _ => return false,
+        };
+        let pos = *world.get::<Position>(entity);
+        let attacker_pos = match world.try_get::<Position>(attacker) {
+            Some(pos) => *pos,
+            None => return false,
+        };
+        faces_attack(pos.direction(), attacker_pos.vec() - pos.vec())
+    }
+
+    /// Blocks damage with the shield an entity raised, wearing the
+    /// shield down. An attacker holding an axe disables the shield.
+    pub(crate) fn block_with_shield(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        damage: u32,
+        source: DamageSource,
+    ) {
+        let hand = world.get::<Blocking>(entity).hand;
+        let damage_taken = shield_damage(damage);
+        if damage_taken > 0 && world.has::<Network>(entity) {
+            if let Some(slot) = shield_slot(world, entity, hand) {
+                self.handle(
+                    world,
+                    ItemDamageEvent {
+                        player: entity,
+                        slot,
+                        damage_taken,
+                    },
+                );
+            }
+        }
+        self.broadcast_status(world, entity, SHIELD_BLOCK_STATUS);
+
+        let axe = source
+            .attacker()
+            .and_then(|attacker| item_in_main_hand(world, attacker))
+            .map_or(false, |stack| stack.ty.tool() == Some(Tool::Axe));
+        if axe {
+            self.disable_shield(world, entity);
+        }
+    }
+
+    /// Lowers the shield of an entity and prevents
+    /// it from raising it again for a while.
+    pub fn disable_shield(&mut self, world: &mut World, entity: Entity) {
+        self.lower_shield(world, entity);
+        let cooldown = ShieldCooldown(self.tick_count + DISABLE_TICKS);
+        match world.try_get_mut::<ShieldCooldown>(entity) {
+            Some(mut old) => *old = cooldown,
+            None => world.add(entity, cooldown).unwrap(),
+        }
+
+        if let Some(network) = world.try_get::<Network>(entity) {
+            network.send(SetCooldown {
+                item_id: Item::Shield.vanilla_id() as i32,
+                cooldown_ticks: DISABLE_TICKS as i32,
+            });
+        }
+        self.broadcast_status(world, entity, SHIELD_DISABLE_STATUS);
+    }
+
+    fn broadcast_status(&self, world: &World, entity: Entity, status: i8) {
+        if let Some(id) = world.try_get::<NetworkId>(entity).map(|id| id.0) {
+            let packet = EntityStatus {
+                entity_id: id,
+                entity_status: status,
+            };
+            self.broadcast_entity_update(world, packet, entity, None);
+        }
+    }
+
+    /// Shows other players whether an entity is using the item in its hands.
+    fn broadcast_hand_state(&self, world: &World, entity: Entity, state: HandState) {
+        if let Some(id) = world.try_get::<NetworkId>(entity).map(|id| id.0) {
+            let packet = PacketEntityMetadata {
+                entity_id: id,
+                metadata: EntityMetadata::new()
+                    .with_layout(PLAYER_LAYOUT)
+                    .with(META_INDEX_LIVING_HAND_STATE, state.bits()),
+            };
+            self.broadcast_entity_update(world, packet, entity, Some(entity));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::util::vec3;
+
+    #[test]
+    fn shield_durability_loss() {
+        assert_eq!(shield_damage(0), 0);
+        assert_eq!(shield_damage(2), 0);
+        assert_eq!(shield_damage(3), 4);
+        assert_eq!(shield_damage(9), 10);
+    }
+
+    #[test]
+    fn blocks_attacks_in_front() {
+        let look = vec3(0.0, 0.0, 1.0);
+        assert!(faces_attack(look, vec3(0.5, 2.0, 3.0)));
+        assert!(!faces_attack(look, vec3(1.0, 0.0, -0.5)));
+        // Attacks from right beside or above aren't blocked.
+        assert!(!faces_attack(look, vec3(2.0, 0.0, 0.0)));
+        assert!(!faces_attack(look, vec3(0.0, 3.0, 0.0)));
+    }
+}
@@ -0,0 +1,211 @@
+//! Players sleeping in beds and the spawn points they set.
+//!
+//! Players can only sleep at night or during thunderstorms.
+//! Sleeping sets a player's spawn point to the bed, and once
+//! enough players have slept for a while, the night is skipped.
+
+use crate::{BlockUpdateCause, Game, Network, NetworkId, Teleported, WorldId};
+use feather_core::anvil::player::SpawnData;
+use feather_core::blocks::{BlockId, Part, SimplifiedBlockKind};
+use feather_core::network::packets::{AnimationClientbound, ChatMessageClientbound};
+use feather_core::position;
+use feather_core::text::{Text, TextValue};
+use feather_core::util::{BlockPosition, ClientboundAnimation, Position};
+use fecs::{Entity, World};
+use std::ops::RangeInclusive;
+
+/// Times of day at which players may sleep.
+const NIGHT: RangeInclusive<u64> = 12_542..=23_459;
+
+/// Component for players sleeping in a bed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sleeping {
+    /// Position of the head of the bed.
+    pub bed: BlockPosition,
+    /// Ticks the player has been asleep for.
+    pub ticks: u32,
+}
+
+impl Sleeping {
+    /// Ticks a player needs to sleep before the night can be skipped.
+    pub const DEEP_SLEEP_TICKS: u32 = 100;
+
+    pub fn new(bed: BlockPosition) -> Self {
+        Self { bed, ticks: 0 }
+    }
+
+    /// Returns whether the player has slept long
+    /// enough for the night to be skipped.
+    pub fn is_deeply_asleep(self) -> bool {
+        self.ticks >= Self::DEEP_SLEEP_TICKS
+    }
+}
+
+/// Component storing the bed a player respawns at,
+/// which is always in the main world.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpawnPoint(pub BlockPosition);
+
+impl SpawnPoint {
+    /// Returns the spawn point stored in player data, if any.
+    pub fn from_data(data: SpawnData) -> Option<Self> {
+        match (data.x, data.y, data.z) {
+            (Some(x), Some(y), Some(z)) => Some(SpawnPoint(BlockPosition::new(x, y, z))),
+            _ => None,
+        }
+    }
+}
+
+impl From<SpawnPoint> for SpawnData {
+    fn from(spawn: SpawnPoint) -> Self {
+        Self {
+            x: Some(spawn.0.x),
+            y: Some(spawn.0.y),
+            z: Some(spawn.0.z),
+        }
+    }
+}
+
+/// Returns the other half of a bed.
+pub fn other_bed_half(bed: BlockId, pos: BlockPosition) -> Option<BlockPosition> {
+    let offset = bed.facing_cardinal()?.offset();
+    match bed.part()? {
+        Part::Head => Some(pos - offset),
+        Part::Foot => Some(pos + offset),
+    }
+}
+
+/// Sends a message to a player, shown above their hotbar.
+pub fn send_status_message(world: &World, player: Entity, key: &str) {
+    if let Some(network) = world.try_get::<Network>(player) {
+        network.send(ChatMessageClientbound {
+            json_data: Text::from(TextValue::translate(key)).to_string(),
+            position: 2,
+        });
+    }
+}
+
+impl Game {
+    /// Returns whether players may sleep in the given world.
+    pub fn is_sleeping_time(&self, world_id: WorldId) -> bool {
+        self.is_thundering()
+            || self
+                .worlds
+                .get(world_id)
+                .map_or(false, |state| NIGHT.contains(&state.time.time_of_day()))
+    }
+
+    /// Marks both halves of the bed whose head is
+    /// at the given position as occupied or not.
+    pub fn set_bed_occupied(
+        &mut self,
+        world: &mut World,
+        world_id: WorldId,
+        head: BlockPosition,
+        occupied: bool,
+    ) {
+        let bed = match self.block_at(world_id, head) {
+            Some(bed) if bed.simplified_kind() == SimplifiedBlockKind::Bed => bed,
+            _ => return,
+        };
+        let mut halves = vec![(head, bed)];
+        if let Some(foot) = other_bed_half(bed, head) {
+            if let Some(block) = self.block_at(world_id, foot) {
+                if block.kind() == bed.kind() {
+                    halves.push((foot, block));
+                }
+            }
+        }
+
+        for (pos, block) in halves {
+            if block.occupied() != Some(occupied) {
+                self.set_block_at(
+                    world,
+                    world_id,
+                    pos,
+                    block.with_occupied(occupied),
+                    BlockUpdateCause::Unknown,
+                );
+            }
+        }
+    }
+
+    /// Returns where a player getting out of the bed whose head
+    /// is at the given position stands: next to the bed if there
+    /// is room, or on top of it otherwise.
+    pub fn bed_exit_position(&self, world_id: WorldId, head: BlockPosition) -> Position {
+        let foot = self
+            .block_at(world_id, head)
+            .and_then(|bed| other_bed_half(bed, head));
+
+        let is_free = |pos: BlockPosition| {
+            self.block_at(world_id, pos)
+                .map_or(false, |block| !block.is_solid())
+        };
+        let can_stand = |pos: BlockPosition| {
+            self.block_at(world_id, pos.down())
+                .map_or(false, BlockId::is_full_block)
+                && is_free(pos)
+                && is_free(pos.up())
+        };
+
+        let exit = std::iter::once(head)
+            .chain(foot)
+            .flat_map(|half| {
+                (-1..=1)
+                    .flat_map(move |x| (-1..=1).map(move |z| half + BlockPosition::new(x, 0, z)))
+            })
+            .find(|pos| can_stand(*pos))
+            .unwrap_or_else(|| head.up());
+        exit.position() + glm::vec3(0.5, 0.0, 0.5)
+    }
+
+    /// Returns the world spawn, where players
+    /// without a bed spawn point respawn.
+    pub fn world_spawn(&self) -> Position {
+        let level = &self.level;
+        position!(
+            f64::from(level.spawn_x) + 0.5,
+            f64::from(level.spawn_y),
+            f64::from(level.spawn_z) + 0.5,
+        )
+    }
+
+    /// Returns where a player respawns next to the bed they
+    /// last slept in, or `None` if they have no spawn point
+    /// or the bed is gone.
+    pub fn bed_spawn(&self, world: &World, player: Entity) -> Option<Position> {
+        let bed = world.try_get::<SpawnPoint>(player)?.0;
+        match self.block_at(WorldId::MAIN, bed) {
+            Some(block) if block.simplified_kind() != SimplifiedBlockKind::Bed => None,
+            _ => Some(self.bed_exit_position(WorldId::MAIN, bed)),
+        }
+    }
+
+    /// Wakes a sleeping player, who gets out of their bed.
+    pub fn wake_up(&mut self, world: &mut World, player: Entity) {
+        let bed = match world.try_get::<Sleeping>(player) {
+            Some(sleeping) => sleeping.bed,
+            None => return,
+        };
+        let _ = world.remove::<Sleeping>(player);
+
+        let world_id = self.world_of(world, player);
+        self.set_bed_occupied(world, world_id, bed, false);
+
+        let packet = AnimationClientbound {
+            entity_id: world.get::<NetworkId>(player).0,
+            animation: ClientboundAnimation::LeaveBed,
+        };
+        self.broadcast_entity_update(world, packet, player, None);
+
+        let exit = self.bed_exit_position(world_id, bed);
+        let old = *world.get::<Position>(player);
+        *world.get_mut::<Position>(player) = Position {
+            yaw: old.yaw,
+            pitch: old.pitch,
+            ..exit
+        };
+        world.add(player, Teleported).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+//! The server's status, as shown in the server list.
+//!
+//! Server list pings are answered on the network threads
+//! with a `StatusResponse` built from the config and the
+//! `OnlinePlayers`. Plugins may change the response for
+//! each ping by registering a `StatusHook`.
+
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+/// A player shown in the list which appears when
+/// hovering over the player count in the server list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SamplePlayer {
+    pub name: String,
+    pub uuid: Uuid,
+}
+
+/// The players online on the server, shared with the
+/// network threads to answer server list pings.
+#[derive(Debug, Default)]
+pub struct OnlinePlayers {
+    players: RwLock<Vec<SamplePlayer>>,
+}
+
+impl OnlinePlayers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, uuid: Uuid, name: impl Into<String>) {
+        self.players.write().push(SamplePlayer {
+            name: name.into(),
+            uuid,
+        });
+    }
+
+    pub fn remove(&self, uuid: Uuid) {
+        self.players.write().retain(|player| player.uuid != uuid);
+    }
+
+    /// Returns the number of players online.
+    pub fn count(&self) -> u32 {
+        self.players.read().len() as u32
+    }
+
+    /// Returns the names of all online players, in the order they joined.
+    pub fn names(&self) -> Vec<String> {
+        self.players
+            .read()
+            .iter()
+            .map(|player| player.name.clone())
+            .collect()
+    }
+
+    /// Returns up to `max` random online players.
+    pub fn sample(&self, max: usize) -> Vec<SamplePlayer> {
+        self.players
+            .read()
+            .choose_multiple(&mut rand::thread_rng(), max)
+            .cloned()
+            .collect()
+    }
+}
+
+/// A response to a server list ping.
+///
+/// Built using the `with_*` functions, e.g.
+/// ```
+/// # use feather_server_types::StatusResponse;
+/// let response = StatusResponse::new("Feather 1.16.5", 754)
+///     .with_motd("&aA Feather server\n&7Running in a test")
+///     .with_players(1, 16);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StatusResponse {
+    /// The name of the server's version, shown to clients
+    /// whose protocol version differs from `protocol`.
+    pub version_name: String,
+    pub protocol: u32,
+    /// The message of the day, using `§` formatting codes.
+    /// It may span two lines.
+    pub motd: String,
+    pub online_players: u32,
+    pub max_players: i32,
+    /// The players shown when hovering over the player count.
+    pub sample: Vec<SamplePlayer>,
+    /// The server icon, as a data URL of a 64x64 PNG image.
+    pub favicon: Option<String>,
+}
+
+impl StatusResponse {
+    pub fn new(version_name: impl Into<String>, protocol: u32) -> Self {
+        Self {
+            version_name: version_name.into(),
+            protocol,
+            motd: String::new(),
+            online_players: 0,
+            max_players: 0,
+            sample: vec![],
+            favicon: None,
+        }
+    }
+
+    /// Sets the message of the day. Formatting codes may be
+    /// written with `&` in place of `§`, e.g. `&c` for red.
+    pub fn with_motd(mut self, motd: &str) -> Self {
+        self.motd = translate_color_codes(motd);
+        self
+    }
+
+    pub fn with_players(mut self, online: u32, max: i32) -> Self {
+        self.online_players = online;
+        self.max_players = max;
+        self
+    }
+
+    pub fn with_sample(mut self, sample: Vec<SamplePlayer>) -> Self {
+        self.sample = sample;
+        self
+    }
+
+    pub fn with_favicon(mut self, favicon: Option<String>) -> Self {
+        self.favicon = favicon;
+        self
+    }
+
+    /// Returns the JSON sent in the Response packet.
+    pub fn to_json(&self) -> serde_json::Value {
+        let sample: Vec<_> = self
+            .sample
+            .iter()
+            .map(|player| {
+                serde_json::json!({
+                    "name": player.name,
+                    "id": player.uuid.to_hyphenated_ref().to_string(),
+                })
+            })
+            .collect();
+
+        let mut json = serde_json::json!({
+            "version": {
+                "name": self.version_name,
+                "protocol": self.protocol,
+            },
+            "players": {
+                "max": self.max_players,
+                "online": self.online_players,
+                "sample": sample,
+            },
+            "description": {
+                "text": self.motd,
+            },
+        });
+
+        if let Some(favicon) = &self.favicon {
+            json["favicon"] = serde_json::Value::from(favicon.as_str());
+        }
+
+        json
+    }
+}
+
+/// Replaces `&` formatting codes with `§` codes.
+/// Other ampersands are left as they are.
+pub fn translate_color_codes(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(code) if c == '&' && matches!(*code, '0'..='9' | 'a'..='f' | 'k'..='o' | 'r') => {
+                result.push('§')
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Information about a server list ping.
+#[derive(Copy, Clone, Debug)]
+pub struct StatusPing {
+    pub address: SocketAddr,
+    /// The protocol version sent by the client,
+    /// which may not be supported by the server.
+    pub protocol_version: u32,
+}
+
+/// Customizes the response to server list pings.
+///
+/// Hooks are registered using
+/// `inventory::submit!(Box::new(MyHook) as Box<dyn StatusHook>)`,
+/// and run on the network threads for every ping, after the
+/// response has been filled in from the config.
+pub trait StatusHook: Send + Sync {
+    fn on_status(&self, ping: &StatusPing, response: &mut StatusResponse);
+}
+
+inventory::collect!(Box<dyn StatusHook>);
+
+/// Runs the registered `StatusHook`s on a response.
+pub fn run_status_hooks(ping: &StatusPing, response: &mut StatusResponse) {
+    for hook in inventory::iter::<Box<dyn StatusHook>> {
+        hook.on_status(ping, response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_codes() {
+        assert_eq!(
+            translate_color_codes("&aGreen &lbold\n&rFish & chips &z"),
+            "§aGreen §lbold\n§rFish & chips &z"
+        );
+    }
+
+    #[test]
+    fn response_json() {
+        let uuid = Uuid::from_u128(1);
+        let json = StatusResponse::new("Feather 1.13.2", 404)
+            .with_motd("&cHello")
+            .with_players(1, 16)
+            .with_sample(vec![SamplePlayer {
+                name: String::from("test"),
+                uuid,
+            }])
+            .to_json();
+
+        assert_eq!(json["version"]["protocol"], 404);
+        assert_eq!(json["description"]["text"], "§cHello");
+        assert_eq!(json["players"]["online"], 1);
+        assert_eq!(json["players"]["max"], 16);
+        assert_eq!(json["players"]["sample"][0]["name"], "test");
+        assert_eq!(
+            json["players"]["sample"][0]["id"],
+            "00000000-0000-0000-0000-000000000001"
+        );
+        assert!(json.get("favicon").is_none());
+    }
+
+    #[test]
+    fn online_players() {
+        let players = OnlinePlayers::new();
+        players.add(Uuid::from_u128(1), "a");
+        players.add(Uuid::from_u128(2), "b");
+        players.add(Uuid::from_u128(3), "c");
+        assert_eq!(players.count(), 3);
+        assert_eq!(players.sample(2).len(), 2);
+
+        players.remove(Uuid::from_u128(2));
+        assert_eq!(players.names(), vec!["a", "c"]);
+        let mut names: Vec<_> = players.sample(12).into_iter().map(|p| p.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["a", "c"]);
+    }
+}
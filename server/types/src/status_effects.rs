@@ -0,0 +1,286 @@
+//! Status effects of entities, such as those given by beacons.
+//!
+//! See https://minecraft.gamepedia.com/Status_effect.
+
+use crate::{FireResistance, Game, Network, NetworkId};
+use feather_core::network::packets::{EntityEffect, RemoveEntityEffect};
+use fecs::{Entity, World};
+
+/// Flags of Entity Effect packets.
+const FLAG_AMBIENT: i8 = 0x01;
+const FLAG_SHOW_PARTICLES: i8 = 0x02;
+const FLAG_SHOW_ICON: i8 = 0x04;
+
+/// A kind of status effect, numbered by its ID in the protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StatusEffectKind {
+    Speed = 1,
+    Slowness = 2,
+    Haste = 3,
+    MiningFatigue = 4,
+    Strength = 5,
+    InstantHealth = 6,
+    InstantDamage = 7,
+    JumpBoost = 8,
+    Nausea = 9,
+    Regeneration = 10,
+    Resistance = 11,
+    FireResistance = 12,
+    WaterBreathing = 13,
+    Invisibility = 14,
+    Blindness = 15,
+    NightVision = 16,
+    Hunger = 17,
+    Weakness = 18,
+    Poison = 19,
+    Wither = 20,
+    HealthBoost = 21,
+    Absorption = 22,
+    Saturation = 23,
+    Glowing = 24,
+    Levitation = 25,
+    Luck = 26,
+    Unluck = 27,
+    SlowFalling = 28,
+    ConduitPower = 29,
+    DolphinsGrace = 30,
+}
+
+impl StatusEffectKind {
+    /// All kinds of status effects, in the order of their IDs.
+    pub const ALL: [StatusEffectKind; 30] = [
+        StatusEffectKind::Speed,
+        StatusEffectKind::Slowness,
+        StatusEffectKind::Haste,
+        StatusEffectKind::MiningFatigue,
+        StatusEffectKind::Strength,
+        StatusEffectKind::InstantHealth,
+        StatusEffectKind::InstantDamage,
+        StatusEffectKind::JumpBoost,
+        StatusEffectKind::Nausea,
+        StatusEffectKind::Regeneration,
+        StatusEffectKind::Resistance,
+        StatusEffectKind::FireResistance,
+        StatusEffectKind::WaterBreathing,
+        StatusEffectKind::Invisibility,
+        StatusEffectKind::Blindness,
+        StatusEffectKind::NightVision,
+        StatusEffectKind::Hunger,
+        StatusEffectKind::Weakness,
+        StatusEffectKind::Poison,
+        StatusEffectKind::Wither,
+        StatusEffectKind::HealthBoost,
+        StatusEffectKind::Absorption,
+        StatusEffectKind::Saturation,
+        StatusEffectKind::Glowing,
+        StatusEffectKind::Levitation,
+        StatusEffectKind::Luck,
+        StatusEffectKind::Unluck,
+        StatusEffectKind::SlowFalling,
+        StatusEffectKind::ConduitPower,
+        StatusEffectKind::DolphinsGrace,
+    ];
+
+    /// Returns the kind of status effect with the given ID.
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.get((id as usize).wrapping_sub(1)).copied()
+    }
+
+    /// Returns the ID of this kind of status effect.
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+}
+
+/// A status effect an entity has.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// The level of the effect minus one.
+    pub amplifier: u8,
+    /// Ticks until the effect wears off.
+    pub duration: u32,
+    /// Whether the effect comes from the surroundings of the
+    /// entity, e.g. a beacon, which makes its particles fainter.
+    pub ambient: bool,
+    pub show_particles: bool,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusEffectKind, amplifier: u8, duration: u32) -> Self {
+        Self {
+            kind,
+            amplifier,
+            duration,
+            ambient: false,
+            show_particles: true,
+        }
+    }
+
+    /// Makes the effect ambient.
+    pub fn ambient(mut self) -> Self {
+        self.ambient = true;
+        self
+    }
+
+    fn flags(&self) -> i8 {
+        let mut flags = FLAG_SHOW_ICON;
+        if self.ambient {
+            flags |= FLAG_AMBIENT;
+        }
+        if self.show_particles {
+            flags |= FLAG_SHOW_PARTICLES;
+        }
+        flags
+    }
+}
+
+/// The status effects an entity has, at most one of each kind.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    /// Returns the effect of the given kind, if the entity has it.
+    pub fn get(&self, kind: StatusEffectKind) -> Option<&StatusEffect> {
+        self.0.iter().find(|effect| effect.kind == kind)
+    }
+
+    /// Returns whether the entity has an effect of the given kind.
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// Adds an effect, unless an effect of its kind with a
+    /// higher level, or with the same level and a longer
+    /// duration, is already there. Returns whether it was added.
+    pub fn add(&mut self, effect: StatusEffect) -> bool {
+        match self.0.iter_mut().find(|old| old.kind == effect.kind) {
+            Some(old) => {
+                if old.amplifier > effect.amplifier
+                    || (old.amplifier == effect.amplifier && old.duration >= effect.duration)
+                {
+                    return false;
+                }
+                *old = effect;
+            }
+            None => self.0.push(effect),
+        }
+        true
+    }
+
+    /// Removes the effect of the given kind,
+    /// returning whether there was one.
+    pub fn remove(&mut self, kind: StatusEffectKind) -> bool {
+        let len = self.0.len();
+        self.0.retain(|effect| effect.kind != kind);
+        self.0.len() != len
+    }
+}
+
+impl Game {
+    /// Gives a status effect to an entity, following the
+    /// rules of `StatusEffects::add`, and shows it to the
+    /// entity if it's a player.
+    pub fn add_status_effect(&mut self, world: &mut World, entity: Entity, effect: StatusEffect) {
+        let added = match world.try_get_mut::<StatusEffects>(entity) {
+            Some(mut effects) => effects.add(effect),
+            None => {
+                if world.add(entity, StatusEffects(vec![effect])).is_err() {
+                    return;
+                }
+                true
+            }
+        };
+        if !added {
+            return;
+        }
+
+        if effect.kind == StatusEffectKind::FireResistance {
+            let _ = world.add(entity, FireResistance);
+        }
+        if let (Some(network), Some(id)) = (
+            world.try_get::<Network>(entity),
+            world.try_get::<NetworkId>(entity),
+        ) {
+            network.send(EntityEffect {
+                entity_id: id.0,
+                effect_id: effect.kind.id() as i8,
+                amplifier: effect.amplifier as i8,
+                duration: effect.duration as i32,
+                flags: effect.flags(),
+            });
+        }
+    }
+
+    /// Removes the status effect of the given kind from an entity.
+    pub fn remove_status_effect(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        kind: StatusEffectKind,
+    ) {
+        let removed = match world.try_get_mut::<StatusEffects>(entity) {
+            Some(mut effects) => effects.remove(kind),
+            None => false,
+        };
+        if !removed {
+            return;
+        }
+
+        if kind == StatusEffectKind::FireResistance {
+            let _ = world.remove::<FireResistance>(entity);
+        }
+        if let (Some(network), Some(id)) = (
+            world.try_get::<Network>(entity),
+            world.try_get::<NetworkId>(entity),
+        ) {
+            network.send(RemoveEntityEffect {
+                entity_id: id.0,
+                effect_id: kind.id() as i8,
+            });
+        }
+    }
+
+    /// Removes all status effects of an entity.
+    pub fn clear_status_effects(&mut self, world: &mut World, entity: Entity) {
+        let kinds: Vec<StatusEffectKind> = match world.try_get::<StatusEffects>(entity) {
+            Some(effects) => effects.0.iter().map(|effect| effect.kind).collect(),
+            None => return,
+        };
+        for kind in kinds {
+            self.remove_status_effect(world, entity, kind);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids() {
+        for kind in StatusEffectKind::ALL.iter() {
+            assert_eq!(StatusEffectKind::from_id(kind.id()), Some(*kind));
+        }
+        assert_eq!(StatusEffectKind::from_id(0), None);
+        assert_eq!(StatusEffectKind::from_id(31), None);
+        assert_eq!(StatusEffectKind::from_id(-1), None);
+    }
+
+    #[test]
+    fn add_keeps_stronger_effects() {
+        let mut effects = StatusEffects::default();
+        assert!(effects.add(StatusEffect::new(StatusEffectKind::Speed, 0, 100)));
+        assert!(effects.add(StatusEffect::new(StatusEffectKind::Speed, 0, 200)));
+        assert!(!effects.add(StatusEffect::new(StatusEffectKind::Speed, 0, 150)));
+        assert!(effects.add(StatusEffect::new(StatusEffectKind::Speed, 1, 20)));
+        assert!(!effects.add(StatusEffect::new(StatusEffectKind::Speed, 0, 1000)));
+
+        assert_eq!(
+            effects.0,
+            vec![StatusEffect::new(StatusEffectKind::Speed, 1, 20)]
+        );
+        assert!(effects.remove(StatusEffectKind::Speed));
+        assert!(!effects.has(StatusEffectKind::Speed));
+    }
+}
@@ -0,0 +1,240 @@
+//! Chunk tickets, which determine the chunks that are loaded and ticked.
+//!
+//! A ticket is placed on a chunk with a level. Each chunk's level is the
+//! lowest value of `ticket level + distance` over all tickets, where the
+//! distance is counted in chunks along the longer axis. Lower levels
+//! mean more activity:
+//!
+//! * `ENTITY_TICKING_LEVEL` and below: blocks and entities are ticked.
+//! * `BLOCK_TICKING_LEVEL`: blocks are ticked, but entities are frozen.
+//! * `LOADED_LEVEL`: the chunk is loaded, but nothing in it is ticked.
+//! * Above `LOADED_LEVEL`: the chunk is unloaded.
+
+use ahash::AHashMap;
+use feather_core::util::ChunkPosition;
+use fecs::Entity;
+use smallvec::SmallVec;
+use std::collections::hash_map::Entry;
+
+/// Highest level at which entities in a chunk are ticked.
+pub const ENTITY_TICKING_LEVEL: u8 = 31;
+/// Highest level at which blocks in a chunk are ticked.
+pub const BLOCK_TICKING_LEVEL: u8 = 32;
+/// Highest level at which a chunk is loaded.
+pub const LOADED_LEVEL: u8 = 33;
+
+/// The reason a ticket exists.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TicketKind {
+    /// Keeps the chunks within a player's view loaded.
+    Player(Entity),
+    /// Keeps the spawn area loaded.
+    Spawn,
+    /// Keeps a chunk ticking until the ticket is removed.
+    Forced,
+    /// Keeps the area around the destination of an entity
+    /// travelling through a portal loaded until it arrives.
+    Portal(Entity),
+}
+
+/// A ticket on a chunk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ticket {
+    pub kind: TicketKind,
+    pub level: u8,
+}
+
+impl Ticket {
+    /// Creates a ticket which loads all chunks
+    /// within `radius` chunks of its own.
+    pub fn with_radius(kind: TicketKind, radius: u8) -> Self {
+        Self {
+            kind,
+            level: LOADED_LEVEL.saturating_sub(radius),
+        }
+    }
+
+    /// Creates a ticket for a forced chunk, which is fully
+    /// ticked, while its neighbours have their blocks ticked.
+    pub fn forced() -> Self {
+        Self {
+            kind: TicketKind::Forced,
+            level: ENTITY_TICKING_LEVEL,
+        }
+    }
+
+    /// Returns the radius of the area this ticket loads.
+    pub fn radius(self) -> u8 {
+        LOADED_LEVEL.saturating_sub(self.level)
+    }
+}
+
+/// The tickets on all chunks, and the resulting
+/// level of each loaded chunk.
+#[derive(Default, Clone, Debug)]
+pub struct ChunkTickets {
+    tickets: AHashMap<ChunkPosition, SmallVec<[Ticket; 2]>>,
+    /// The level of every chunk whose level is at most `LOADED_LEVEL`.
+    levels: AHashMap<ChunkPosition, u8>,
+}
+
+impl ChunkTickets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a ticket to a chunk, returning the
+    /// chunks which now have to be loaded.
+    pub fn add(&mut self, chunk: ChunkPosition, ticket: Ticket) -> Vec<ChunkPosition> {
+        self.tickets.entry(chunk).or_default().push(ticket);
+
+        let mut loaded = Vec::new();
+        for pos in area(chunk, ticket.radius()) {
+            let level = ticket.level.saturating_add(distance(chunk, pos));
+            match self.levels.entry(pos) {
+                Entry::Occupied(mut entry) => {
+                    if level < *entry.get() {
+                        entry.insert(level);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(level);
+                    loaded.push(pos);
+                }
+            }
+        }
+
+        loaded
+    }
+
+    /// Removes a ticket of the given kind from a chunk, returning
+    /// the chunks which should no longer be loaded. Does nothing
+    /// if the chunk has no such ticket.
+    pub fn remove(&mut self, chunk: ChunkPosition, kind: TicketKind) -> Vec<ChunkPosition> {
+        let tickets = match self.tickets.get_mut(&chunk) {
+            Some(tickets) => tickets,
+            None => return Vec::new(),
+        };
+        let ticket = match tickets.iter().position(|ticket| ticket.kind == kind) {
+            Some(index) => tickets.remove(index),
+            None => return Vec::new(),
+        };
+        if tickets.is_empty() {
+            self.tickets.remove(&chunk);
+        }
+
+        let mut unloaded = Vec::new();
+        for pos in area(chunk, ticket.radius()) {
+            match self.compute_level(pos) {
+                Some(level) => {
+                    self.levels.insert(pos, level);
+                }
+                None => {
+                    self.levels.remove(&pos);
+                    unloaded.push(pos);
+                }
+            }
+        }
+
+        unloaded
+    }
+
+    /// Returns the tickets on a chunk.
+    pub fn tickets_at(&self, chunk: ChunkPosition) -> &[Ticket] {
+        self.tickets
+            .get(&chunk)
+            .map(SmallVec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Returns the level of a chunk, or `None`
+    /// if it is above `LOADED_LEVEL`.
+    pub fn level(&self, chunk: ChunkPosition) -> Option<u8> {
+        self.levels.get(&chunk).copied()
+    }
+
+    pub fn is_loaded(&self, chunk: ChunkPosition) -> bool {
+        self.levels.contains_key(&chunk)
+    }
+
+    pub fn is_block_ticking(&self, chunk: ChunkPosition) -> bool {
+        self.level(chunk)
+            .map_or(false, |level| level <= BLOCK_TICKING_LEVEL)
+    }
+
+    pub fn is_entity_ticking(&self, chunk: ChunkPosition) -> bool {
+        self.level(chunk)
+            .map_or(false, |level| level <= ENTITY_TICKING_LEVEL)
+    }
+
+    /// Returns all chunks which should be loaded.
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = ChunkPosition> + '_ {
+        self.levels.keys().copied()
+    }
+
+    fn compute_level(&self, chunk: ChunkPosition) -> Option<u8> {
+        self.tickets
+            .iter()
+            .flat_map(|(&center, tickets)| {
+                let distance = distance(center, chunk);
+                tickets
+                    .iter()
+                    .map(move |ticket| ticket.level.saturating_add(distance))
+            })
+            .filter(|&level| level <= LOADED_LEVEL)
+            .min()
+    }
+}
+
+/// Returns the distance between two chunks along the longer axis.
+fn distance(a: ChunkPosition, b: ChunkPosition) -> u8 {
+    let distance = (a.x - b.x).abs().max((a.z - b.z).abs());
+    distance.min(i32::from(u8::MAX)) as u8
+}
+
+/// Returns the chunks within `radius` chunks of `center`.
+fn area(center: ChunkPosition, radius: u8) -> impl Iterator<Item = ChunkPosition> {
+    let radius = i32::from(radius);
+    (-radius..=radius).flat_map(move |x| {
+        (-radius..=radius).map(move |z| ChunkPosition::new(center.x + x, center.z + z))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticket_levels() {
+        let mut tickets = ChunkTickets::new();
+        let origin = ChunkPosition::new(0, 0);
+
+        let loaded = tickets.add(origin, Ticket::with_radius(TicketKind::Spawn, 2));
+        assert_eq!(loaded.len(), 25);
+        assert_eq!(tickets.level(origin), Some(31));
+        assert!(tickets.is_entity_ticking(origin));
+        assert!(tickets.is_block_ticking(ChunkPosition::new(1, -1)));
+        assert!(!tickets.is_entity_ticking(ChunkPosition::new(1, -1)));
+        assert!(tickets.is_loaded(ChunkPosition::new(-2, 2)));
+        assert!(!tickets.is_block_ticking(ChunkPosition::new(-2, 2)));
+        assert!(!tickets.is_loaded(ChunkPosition::new(3, 0)));
+
+        // A forced chunk overlapping the spawn area only
+        // loads the chunks which weren't loaded yet.
+        let forced = ChunkPosition::new(2, 0);
+        let loaded = tickets.add(forced, Ticket::forced());
+        assert_eq!(loaded.len(), 10);
+        assert!(tickets.is_entity_ticking(forced));
+        assert_eq!(tickets.tickets_at(forced), &[Ticket::forced()]);
+
+        let unloaded = tickets.remove(origin, TicketKind::Spawn);
+        assert_eq!(unloaded.len(), 10);
+        assert_eq!(tickets.level(ChunkPosition::new(1, 0)), Some(32));
+        assert_eq!(tickets.level(origin), Some(LOADED_LEVEL));
+        assert!(!tickets.is_loaded(ChunkPosition::new(-1, 0)));
+
+        assert!(tickets.remove(forced, TicketKind::Spawn).is_empty());
+        assert_eq!(tickets.remove(forced, TicketKind::Forced).len(), 25);
+        assert_eq!(tickets.loaded_chunks().count(), 0);
+    }
+}
@@ -0,0 +1,157 @@
+//! Titles and action bar messages shown to players.
+
+use crate::Network;
+use feather_core::network::packets::{
+    ActionBar, ClearTitles, SetTitleSubtitle, SetTitleText, SetTitleTimes, Title as TitlePacket,
+    TitleAction,
+};
+use feather_core::text::Text;
+
+/// A title shown in the middle of a player's screen,
+/// with an optional subtitle below it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Title {
+    pub title: Text,
+    pub subtitle: Option<Text>,
+    /// Time taken to fade in, in ticks.
+    pub fade_in: i32,
+    /// Time the title stays on screen, in ticks.
+    pub stay: i32,
+    /// Time taken to fade out, in ticks.
+    pub fade_out: i32,
+}
+
+impl Title {
+    /// Creates a title with vanilla's default times.
+    pub fn new(title: impl Into<Text>) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: None,
+            fade_in: 10,
+            stay: 70,
+            fade_out: 20,
+        }
+    }
+
+    pub fn subtitle(mut self, subtitle: impl Into<Text>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    pub fn times(mut self, fade_in: i32, stay: i32, fade_out: i32) -> Self {
+        self.fade_in = fade_in;
+        self.stay = stay;
+        self.fade_out = fade_out;
+        self
+    }
+}
+
+// Clients since 1.17 receive titles as separate packets instead
+// of the `Title` packet. Both are sent, and the codec drops
+// those which don't exist in a client's protocol version.
+impl Network {
+    /// Shows a title to this player.
+    pub fn send_title(&self, title: &Title) {
+        let (fade_in, stay, fade_out) = (title.fade_in, title.stay, title.fade_out);
+        // Clients keep the last subtitle, so an
+        // empty one is sent to clear it.
+        let subtitle: String = title.subtitle.clone().unwrap_or_else(Text::empty).into();
+        let text: String = title.title.clone().into();
+
+        self.send(TitlePacket {
+            action: TitleAction::SetTimes {
+                fade_in,
+                stay,
+                fade_out,
+            },
+        });
+        self.send(SetTitleTimes {
+            fade_in,
+            stay,
+            fade_out,
+        });
+        self.send(TitlePacket {
+            action: TitleAction::SetSubtitle(subtitle.clone()),
+        });
+        self.send(SetTitleSubtitle { text: subtitle });
+        // The title is shown once its text is received.
+        self.send(TitlePacket {
+            action: TitleAction::SetTitle(text.clone()),
+        });
+        self.send(SetTitleText { text });
+    }
+
+    /// Hides this player's title. If `reset` is true, their
+    /// client also forgets the title's text and times.
+    pub fn clear_title(&self, reset: bool) {
+        let action = if reset {
+            TitleAction::Reset
+        } else {
+            TitleAction::Hide
+        };
+        self.send(TitlePacket { action });
+        self.send(ClearTitles { reset });
+    }
+
+    /// Shows a message above this player's hotbar.
+    pub fn send_action_bar(&self, message: impl Into<Text>) {
+        let text: String = message.into().into();
+        self.send(TitlePacket {
+            action: TitleAction::SetActionBar(text.clone()),
+        });
+        self.send(ActionBar { text });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerToWorkerMessage;
+    use feather_core::network::Packet;
+    use parking_lot::Mutex;
+
+    #[test]
+    fn send_title() {
+        let (tx, rx) = flume::unbounded();
+        let network = Network {
+            tx,
+            rx: Mutex::new(flume::unbounded().1),
+        };
+
+        network.send_title(&Title::new("Hello").times(5, 40, 5));
+        let sent: Vec<Box<dyn Packet>> = rx
+            .try_iter()
+            .filter_map(|message| match message {
+                ServerToWorkerMessage::SendPacket(packet) => Some(packet),
+                ServerToWorkerMessage::SendPacketNow(packet) => Some(packet),
+                ServerToWorkerMessage::Flush => None,
+                ServerToWorkerMessage::Disconnect => panic!("player disconnected"),
+            })
+            .collect();
+        assert_eq!(sent.len(), 6);
+
+        let titles: Vec<&TitleAction> = sent
+            .iter()
+            .filter_map(|packet| packet.as_any().downcast_ref::<TitlePacket>())
+            .map(|packet| &packet.action)
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                &TitleAction::SetTimes {
+                    fade_in: 5,
+                    stay: 40,
+                    fade_out: 5
+                },
+                &TitleAction::SetSubtitle(Text::empty().into()),
+                &TitleAction::SetTitle(Text::of("Hello").into()),
+            ]
+        );
+
+        let text = sent
+            .last()
+            .and_then(|packet| packet.as_any().downcast_ref::<SetTitleText>())
+            .unwrap();
+        assert_eq!(text.text, String::from(Text::of("Hello")));
+    }
+}
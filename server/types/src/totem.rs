@@ -0,0 +1,77 @@
+//! Totems of undying, which save the entity holding
+//! them from death, giving it status effects instead.
+//!
+//! See https://minecraft.gamepedia.com/Totem_of_Undying.
+
+use crate::{Game, HeldItem, InventoryUpdateEvent, NetworkId, StatusEffect, StatusEffectKind};
+use feather_core::inventory::{slot, Area, Inventory, SlotIndex};
+use feather_core::items::Item;
+use feather_core::network::packets::EntityStatus;
+use fecs::{Entity, World};
+use smallvec::smallvec;
+
+/// Entity status which plays the totem animation and sound.
+const TOTEM_STATUS: i8 = 35;
+
+/// The effects given by a totem of undying.
+const TOTEM_EFFECTS: [(StatusEffectKind, u8, u32); 3] = [
+    (StatusEffectKind::Regeneration, 1, 900),
+    (StatusEffectKind::Absorption, 1, 100),
+    (StatusEffectKind::FireResistance, 0, 800),
+];
+
+/// Returns the slot of the totem of undying an entity holds
+/// in its main hand or, failing that, in its offhand.
+fn held_totem(world: &World, entity: Entity) -> Option<SlotIndex> {
+    let inventory = world.try_get::<Inventory>(entity)?;
+    let held = world.try_get::<HeldItem>(entity).map_or(0, |held| held.0);
+    [slot(Area::Hotbar, held), slot(Area::Offhand, 0)]
+        .iter()
+        .copied()
+        .find(|index| match inventory.item_at(index.area, index.slot) {
+            Ok(Some(item)) => item.ty == Item::TotemOfUndying,
+            _ => false,
+        })
+}
+
+impl Game {
+    /// Uses up the totem of undying held by an entity about to
+    /// die, replacing its status effects with those of the totem.
+    /// Returns whether the entity held a totem.
+    ///
+    /// The caller is responsible for leaving the entity alive.
+    pub fn use_totem(&mut self, world: &mut World, entity: Entity) -> bool {
+        let index = match held_totem(world, entity) {
+            Some(index) => index,
+            None => return false,
+        };
+        {
+            let inventory = world.get::<Inventory>(entity);
+            let totem = inventory.item_at(index.area, index.slot).unwrap().unwrap();
+            inventory
+                .set_item_at(index.area, index.slot, totem.of_amount(totem.amount - 1))
+                .unwrap();
+        }
+        self.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: smallvec![index],
+                entity,
+            },
+        );
+
+        self.clear_status_effects(world, entity);
+        for &(kind, amplifier, duration) in &TOTEM_EFFECTS {
+            self.add_status_effect(world, entity, StatusEffect::new(kind, amplifier, duration));
+        }
+
+        if let Some(id) = world.try_get::<NetworkId>(entity).map(|id| id.0) {
+            let packet = EntityStatus {
+                entity_id: id,
+                entity_status: TOTEM_STATUS,
+            };
+            self.broadcast_entity_update(world, packet, entity, None);
+        }
+        true
+    }
+}
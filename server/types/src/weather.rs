@@ -0,0 +1,184 @@
+//! The weather, which is shared by all worlds with a sky.
+//!
+//! Whether it's raining or thundering is stored in the level
+//! data along with the ticks until each changes. When the weather
+//! changes, rain and thunder fade in or out over a few seconds,
+//! and it only counts as raining once the rain is strong enough.
+
+use crate::{Game, Weather, WeatherChangeEvent, WorldId, DAY_LENGTH};
+use feather_core::anvil::level::LevelData;
+use feather_core::util::Dimension;
+use fecs::World;
+use rand::Rng;
+use std::f64::consts::PI;
+
+/// Rain strength above which it counts as raining.
+const RAINING_STRENGTH: f32 = 0.2;
+/// Thunder strength above which it counts as thundering.
+const THUNDERING_STRENGTH: f32 = 0.9;
+/// How much rain and thunder fade in or out each tick.
+const FADE_PER_TICK: f32 = 0.01;
+
+/// How strong the rain and thunder are, from 0 to 1.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WeatherStrength {
+    pub rain: f32,
+    pub thunder: f32,
+}
+
+impl WeatherStrength {
+    /// Returns the weather stored in level data at full strength,
+    /// as it is when the server starts.
+    pub fn of(level: &LevelData) -> Self {
+        let strength = |on: bool| if on { 1.0 } else { 0.0 };
+        Self {
+            rain: strength(level.raining),
+            thunder: strength(level.thundering),
+        }
+    }
+
+    /// Fades the rain and thunder a tick further
+    /// towards the weather in level data.
+    pub fn fade(&mut self, level: &LevelData) {
+        let fade = |strength: f32, on: bool| {
+            let delta = if on { FADE_PER_TICK } else { -FADE_PER_TICK };
+            (strength + delta).max(0.0).min(1.0)
+        };
+        self.rain = fade(self.rain, level.raining);
+        self.thunder = fade(self.thunder, level.thundering);
+    }
+}
+
+/// Returns the number of ticks until rain or thunder
+/// stops, if `active`, or starts otherwise.
+fn next_change(rng: &mut impl Rng, active: bool, thunder: bool) -> i32 {
+    match (active, thunder) {
+        (true, true) => rng.gen_range(3600, 15_600),
+        (true, false) => rng.gen_range(12_000, 24_000),
+        (false, _) => rng.gen_range(12_000, 180_000),
+    }
+}
+
+impl Game {
+    /// Returns the weather as stored in the level data.
+    pub fn weather(&self) -> Weather {
+        if self.level.raining && self.level.thundering {
+            Weather::Thunder
+        } else if self.level.raining {
+            Weather::Rain
+        } else {
+            Weather::Clear
+        }
+    }
+
+    /// Returns whether the rain is strong enough to count
+    /// as raining, so that it puts out fires, for example.
+    pub fn is_raining(&self) -> bool {
+        self.weather_strength.rain > RAINING_STRENGTH
+    }
+
+    /// Returns whether it's thundering strongly enough
+    /// for lightning to strike.
+    pub fn is_thundering(&self) -> bool {
+        self.weather_strength.rain * self.weather_strength.thunder > THUNDERING_STRENGTH
+    }
+
+    /// Returns how much the sky light in a world is reduced by the
+    /// time of day and the weather, from 0 during a clear day up
+    /// to 11 at midnight. Worlds without a sky are never darkened.
+    pub fn sky_darkness(&self, world_id: WorldId) -> u8 {
+        let state = match self.worlds.get(world_id) {
+            Some(state) if state.dimension == Dimension::Overwold => state,
+            _ => return 0,
+        };
+
+        // The angle of the sun, from 0 at noon to 0.5 at midnight.
+        let mut angle = state.time.time_of_day() as f64 / DAY_LENGTH as f64 - 0.25;
+        if angle < 0.0 {
+            angle += 1.0;
+        }
+        angle += (1.0 - ((angle * PI).cos() + 1.0) / 2.0 - angle) / 3.0;
+
+        let daylight = ((angle * 2.0 * PI).cos() * 2.0 + 0.5).max(0.0).min(1.0);
+        let daylight = daylight
+            * (1.0 - f64::from(self.weather_strength.rain) * 5.0 / 16.0)
+            * (1.0 - f64::from(self.weather_strength.thunder) * 5.0 / 16.0);
+        ((1.0 - daylight) * 11.0) as u8
+    }
+
+    /// Sets the weather for the given number of ticks,
+    /// like `/weather` does.
+    pub fn set_weather(&mut self, world: &mut World, weather: Weather, duration: i32) {
+        let from = self.weather();
+        let level = &mut self.level;
+        match weather {
+            Weather::Clear => {
+                level.clear_weather_time = duration;
+                level.rain_time = 0;
+                level.thunder_time = 0;
+            }
+            Weather::Rain | Weather::Thunder => {
+                level.clear_weather_time = 0;
+                level.rain_time = duration;
+                level.thunder_time = duration;
+            }
+        }
+        level.raining = weather != Weather::Clear;
+        level.thundering = weather == Weather::Thunder;
+
+        if weather != from {
+            self.handle(
+                world,
+                WeatherChangeEvent {
+                    from,
+                    to: weather,
+                    duration,
+                },
+            );
+        }
+    }
+
+    /// Advances the weather cycle by one tick, starting or
+    /// stopping rain and thunder once their time runs out.
+    pub fn cycle_weather(&mut self, world: &mut World) {
+        let from = self.weather();
+
+        if self.level.clear_weather_time > 0 {
+            // Clear weather set by a command holds until it runs
+            // out, stopping any rain or thunder right away.
+            self.level.clear_weather_time -= 1;
+            self.level.thunder_time = i32::from(self.level.thundering);
+            self.level.rain_time = i32::from(self.level.raining);
+        }
+
+        if self.level.thunder_time <= 0 {
+            let time = next_change(&mut *self.rng(), self.level.thundering, true);
+            self.level.thunder_time = time;
+        } else {
+            self.level.thunder_time -= 1;
+            if self.level.thunder_time <= 0 {
+                self.level.thundering = !self.level.thundering;
+            }
+        }
+
+        if self.level.rain_time <= 0 {
+            let time = next_change(&mut *self.rng(), self.level.raining, false);
+            self.level.rain_time = time;
+        } else {
+            self.level.rain_time -= 1;
+            if self.level.rain_time <= 0 {
+                self.level.raining = !self.level.raining;
+            }
+        }
+
+        let to = self.weather();
+        if to != from {
+            let duration = if to == Weather::Clear {
+                self.level.rain_time
+            } else {
+                self.level.rain_time.min(self.level.thunder_time)
+            };
+            self.handle(world, WeatherChangeEvent { from, to, duration });
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! The worlds running on the server.
+//!
+//! Each world has its own chunks, entities and time, and is
+//! displayed to clients as one of the three vanilla dimensions.
+//! The main world, the Nether and the End are created on startup;
+//! further worlds can be created at runtime with `Game::create_world`.
+//!
+//! Every entity in a world has a `WorldId` component naming it.
+
+use crate::{
+    BlockTicks, ChunkEntities, ChunkHolders, ChunkTickets, DirtyChunks, PoiStorage, Time,
+    WorldBorder,
+};
+use ahash::AHashMap;
+use feather_core::blocks::BlockId;
+use feather_core::chunk_map::ChunkMap;
+use feather_core::util::{BlockPosition, Dimension};
+use feather_server_worldgen::WorldGenerator;
+use fecs::Entity;
+use std::collections::BTreeMap;
+use std::ops::{Index, IndexMut, Range};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Identifies a world. Also used as a component
+/// storing the world an entity is in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorldId(pub u32);
+
+impl WorldId {
+    /// The main world, which players join
+    /// and respawn in by default.
+    pub const MAIN: WorldId = WorldId(0);
+}
+
+/// The state of a single world.
+pub struct WorldState {
+    pub id: WorldId,
+    /// The name of the world, unique across the server.
+    pub name: String,
+    /// The dimension clients see this world as.
+    pub dimension: Dimension,
+    /// The directory containing the world's region files.
+    pub dir: PathBuf,
+    pub chunk_map: ChunkMap,
+    /// Stores entities which have a hold on chunks,
+    /// preventing the chunk from being unloaded.
+    pub chunk_holders: ChunkHolders,
+    /// The tickets which determine which chunks
+    /// are loaded and ticked.
+    pub chunk_tickets: ChunkTickets,
+    /// Block entity map. Each `BlockPosition` may have a block
+    /// entity associated with it.
+    pub block_entities: AHashMap<BlockPosition, Entity>,
+    /// Associates chunks with the entities that reside in them. Used
+    /// as an acceleration structure for spacial lookups.
+    pub chunk_entities: ChunkEntities,
+    /// Chunks changed since they were last saved
+    /// or sent to clients.
+    pub dirty_chunks: DirtyChunks,
+    /// Points of interest in the loaded chunks.
+    pub poi: PoiStorage,
+    /// Block ticks scheduled in the loaded chunks.
+    pub block_ticks: BlockTicks,
+    /// World time, in the Minecraft way.
+    pub time: Time,
+    pub border: WorldBorder,
+    /// The generator used for new chunks.
+    pub generator: Arc<dyn WorldGenerator>,
+}
+
+impl WorldState {
+    /// Creates an empty world. Its chunks are loaded from
+    /// and saved to `dir`, or generated by `generator`.
+    pub fn new(
+        id: WorldId,
+        name: impl Into<String>,
+        dimension: Dimension,
+        dir: impl Into<PathBuf>,
+        generator: Arc<dyn WorldGenerator>,
+    ) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            dimension,
+            dir: dir.into(),
+            chunk_map: ChunkMap::new(),
+            chunk_holders: ChunkHolders::default(),
+            chunk_tickets: ChunkTickets::new(),
+            block_entities: AHashMap::new(),
+            chunk_entities: ChunkEntities::new(),
+            dirty_chunks: DirtyChunks::new(),
+            poi: PoiStorage::new(),
+            block_ticks: BlockTicks::new(),
+            time: Time::default(),
+            border: WorldBorder::default(),
+            generator,
+        }
+    }
+
+    /// Retrieves the block at the given position.
+    /// Returns `None` if the block's chunk is not loaded
+    /// or the coordinates are out of bounds.
+    pub fn block_at(&self, pos: BlockPosition) -> Option<BlockId> {
+        self.chunk_map.block_at(pos)
+    }
+
+    /// Returns the Y coordinates spanned by the column at the
+    /// given position, which depend on the world's dimension.
+    /// The range is empty if the column's chunk is not loaded.
+    pub fn vertical_bounds(&self, pos: BlockPosition) -> Range<i32> {
+        self.chunk_map
+            .chunk_at(pos.chunk())
+            .map_or(0..0, |chunk| chunk.min_y()..chunk.max_y())
+    }
+
+    /// Claims the point of interest at the given position.
+    /// See `PoiStorage::claim`.
+    pub fn claim_poi(&mut self, pos: BlockPosition) -> bool {
+        let claimed = self.poi.claim(pos);
+        if claimed {
+            self.dirty_chunks.mark_unsaved(pos.chunk());
+        }
+        claimed
+    }
+
+    /// Releases a claim on the point of interest at the
+    /// given position. See `PoiStorage::release`.
+    pub fn release_poi(&mut self, pos: BlockPosition) -> bool {
+        let released = self.poi.release(pos);
+        if released {
+            self.dirty_chunks.mark_unsaved(pos.chunk());
+        }
+        released
+    }
+}
+
+/// All worlds on the server, indexed by `WorldId`.
+#[derive(Default)]
+pub struct Worlds {
+    worlds: BTreeMap<WorldId, WorldState>,
+    next_id: u32,
+}
+
+impl Worlds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a world, returning its ID. The first
+    /// world added is the main world.
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        dimension: Dimension,
+        dir: impl Into<PathBuf>,
+        generator: Arc<dyn WorldGenerator>,
+    ) -> WorldId {
+        let id = WorldId(self.next_id);
+        self.next_id += 1;
+        self.worlds
+            .insert(id, WorldState::new(id, name, dimension, dir, generator));
+        id
+    }
+
+    pub fn get(&self, id: WorldId) -> Option<&WorldState> {
+        self.worlds.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: WorldId) -> Option<&mut WorldState> {
+        self.worlds.get_mut(&id)
+    }
+
+    /// Returns the world with the given name.
+    pub fn by_name(&self, name: &str) -> Option<WorldId> {
+        self.iter()
+            .find(|state| state.name == name)
+            .map(|state| state.id)
+    }
+
+    /// Returns the first world created with the
+    /// given dimension, e.g. the server's Nether.
+    pub fn by_dimension(&self, dimension: Dimension) -> Option<WorldId> {
+        self.iter()
+            .find(|state| state.dimension == dimension)
+            .map(|state| state.id)
+    }
+
+    /// Returns all worlds in order of creation.
+    pub fn iter(&self) -> impl Iterator<Item = &WorldState> {
+        self.worlds.values()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut WorldState> {
+        self.worlds.values_mut()
+    }
+
+    /// Returns the IDs of all worlds in order of creation.
+    pub fn ids(&self) -> impl Iterator<Item = WorldId> + '_ {
+        self.worlds.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.worlds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.worlds.is_empty()
+    }
+}
+
+impl Index<WorldId> for Worlds {
+    type Output = WorldState;
+
+    fn index(&self, id: WorldId) -> &Self::Output {
+        self.get(id).expect("world does not exist")
+    }
+}
+
+impl IndexMut<WorldId> for Worlds {
+    fn index_mut(&mut self, id: WorldId) -> &mut Self::Output {
+        self.get_mut(id).expect("world does not exist")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_worldgen::EmptyWorldGenerator;
+
+    #[test]
+    fn lookup() {
+        let mut worlds = Worlds::new();
+        let generator: Arc<dyn WorldGenerator> = Arc::new(EmptyWorldGenerator {});
+        let main = worlds.add(
+            "world",
+            Dimension::Overwold,
+            "world",
+            Arc::clone(&generator),
+        );
+        let nether = worlds.add(
+            "world_nether",
+            Dimension::Nether,
+            "world/DIM-1",
+            Arc::clone(&generator),
+        );
+        let other = worlds.add("other", Dimension::Overwold, "other", generator);
+
+        assert_eq!(main, WorldId::MAIN);
+        assert_eq!(worlds.len(), 3);
+        assert_eq!(worlds.by_name("other"), Some(other));
+        assert_eq!(worlds.by_dimension(Dimension::Nether), Some(nether));
+        assert_eq!(worlds.by_dimension(Dimension::Overwold), Some(main));
+        assert_eq!(worlds.by_dimension(Dimension::End), None);
+        assert_eq!(worlds[nether].dimension, Dimension::Nether);
+        assert_eq!(worlds.ids().collect::<Vec<_>>(), vec![main, nether, other]);
+    }
+}
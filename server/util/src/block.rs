@@ -19,9 +19,8 @@
 use crate::adjacent_blocks;
 use feather_core::blocks::categories::SupportType;
 use feather_core::blocks::{BlockId, BlockKind, Face};
-use feather_core::chunk_map::chunk_relative_pos;
 use feather_core::util::BlockPosition;
-use feather_server_types::{BlockUpdateEvent, Game};
+use feather_server_types::{BlockUpdateEvent, Game, WorldState};
 use fecs::{EntityBuilder, World};
 use std::cmp::max;
 use std::iter;
@@ -77,7 +76,7 @@ pub fn on_block_update_notify_adjacent(
         .into_iter()
         .chain(iter::once(event.pos))
         .filter_map(|adjacent_pos| {
-            if let Some(adjacent_block) = game.block_at(adjacent_pos) {
+            if let Some(adjacent_block) = game.block_at(event.world, adjacent_pos) {
                 Some((adjacent_block, adjacent_pos))
             } else {
                 None
@@ -87,17 +86,17 @@ pub fn on_block_update_notify_adjacent(
             notify_entity_for_block(adjacent_block, adjacent_pos)
         })
         .for_each(|builder| {
-            builder.build().spawn_in(world);
+            builder.with(event.world).build().spawn_in(world);
         })
 }
 
 /// This checks whether a block of a specific BlockId
-/// can be placed at a specific position in the world.
+/// can be placed at a specific position in a world.
 /// For example blocks like torches, snow, grass need
 /// supported blocks beneath/beside them.
-pub fn is_block_supported_at(block_id: BlockId, game: &Game, pos: BlockPosition) -> bool {
+pub fn is_block_supported_at(block_id: BlockId, state: &WorldState, pos: BlockPosition) -> bool {
     // return value of None means tried to check a block in an unloaded chunk TODO how to handle?
-    check_block_support_at(block_id, game, pos).unwrap_or(false)
+    check_block_support_at(block_id, state, pos).unwrap_or(false)
 }
 
 const NORTH: BlockPosition = BlockPosition { x: 0, y: 0, z: -1 };
@@ -117,11 +116,11 @@ fn face_facing_offset(id: BlockId) -> BlockPosition {
 
 use feather_core::blocks::SimplifiedBlockKind::*;
 
-fn check_block_support_at(id: BlockId, game: &Game, pos: BlockPosition) -> Option<bool> {
+fn check_block_support_at(id: BlockId, state: &WorldState, pos: BlockPosition) -> Option<bool> {
     // TODO leaves are technically a full block, but e.g. torches can't be placed on them https://minecraft.gamepedia.com/Opacity/Placement
-    let block_down = game.block_at(pos + DOWN);
+    let block_down = state.block_at(pos + DOWN);
     let block_facing = if id.has_facing_cardinal() {
-        game.block_at(pos + id.facing_cardinal().unwrap().opposite().offset())
+        state.block_at(pos + id.facing_cardinal().unwrap().opposite().offset())
     } else {
         None
     };
@@ -154,7 +153,7 @@ fn check_block_support_at(id: BlockId, game: &Game, pos: BlockPosition) -> Optio
             )),
 
             SupportType::OnOrFacingSolid => {
-                let block_face_facing = game.block_at(pos + face_facing_offset(id))?;
+                let block_face_facing = state.block_at(pos + face_facing_offset(id))?;
 
                 Some(block_face_facing.is_full_block())
             }
@@ -172,25 +171,25 @@ fn check_block_support_at(id: BlockId, game: &Game, pos: BlockPosition) -> Optio
                 Some(is_supported)
             }
 
-            SupportType::CactusLike => block_support_cactus_like(game, pos),
-            SupportType::ChorusFlowerLike => block_support_chorus_flower_like(game, pos),
-            SupportType::ChorusPlantLike => block_support_chorus_plant_like(game, pos),
-            SupportType::MushroomLike => block_support_mushroom_like(game, pos),
-            SupportType::SugarCaneLike => block_support_sugar_cane_like(game, pos),
-            SupportType::VineLike => block_support_vine_like(game, pos),
+            SupportType::CactusLike => block_support_cactus_like(state, pos),
+            SupportType::ChorusFlowerLike => block_support_chorus_flower_like(state, pos),
+            SupportType::ChorusPlantLike => block_support_chorus_plant_like(state, pos),
+            SupportType::MushroomLike => block_support_mushroom_like(state, pos),
+            SupportType::SugarCaneLike => block_support_sugar_cane_like(state, pos),
+            SupportType::VineLike => block_support_vine_like(state, pos),
         },
         None => Some(true),
     }
 }
 
-fn block_support_cactus_like(game: &Game, pos: BlockPosition) -> Option<bool> {
-    let north = game.block_at(pos + NORTH)?;
-    let east = game.block_at(pos + EAST)?;
-    let south = game.block_at(pos + SOUTH)?;
-    let west = game.block_at(pos + WEST)?;
+fn block_support_cactus_like(state: &WorldState, pos: BlockPosition) -> Option<bool> {
+    let north = state.block_at(pos + NORTH)?;
+    let east = state.block_at(pos + EAST)?;
+    let south = state.block_at(pos + SOUTH)?;
+    let west = state.block_at(pos + WEST)?;
 
     let is_supported = matches!(
-        game.block_at(pos + DOWN)?.simplified_kind(),
+        state.block_at(pos + DOWN)?.simplified_kind(),
         Cactus | Sand | RedSand
     ) && north.simplified_kind() != Cactus
         && !north.is_full_block()
@@ -204,11 +203,11 @@ fn block_support_cactus_like(game: &Game, pos: BlockPosition) -> Option<bool> {
     Some(is_supported)
 }
 
-fn block_support_chorus_flower_like(game: &Game, pos: BlockPosition) -> Option<bool> {
-    let north = game.block_at(pos + NORTH)?;
-    let east = game.block_at(pos + EAST)?;
-    let south = game.block_at(pos + SOUTH)?;
-    let west = game.block_at(pos + WEST)?;
+fn block_support_chorus_flower_like(state: &WorldState, pos: BlockPosition) -> Option<bool> {
+    let north = state.block_at(pos + NORTH)?;
+    let east = state.block_at(pos + EAST)?;
+    let south = state.block_at(pos + SOUTH)?;
+    let west = state.block_at(pos + WEST)?;
 
     let neighbours = [north, east, south, west];
     let neighbouring_chorus = neighbours
@@ -218,26 +217,26 @@ fn block_support_chorus_flower_like(game: &Game, pos: BlockPosition) -> Option<b
     let neighbouring_air = neighbours.iter().filter(|&id| id.is_air()).count();
 
     let is_supported = matches!(
-        game.block_at(pos + DOWN)?.simplified_kind(),
+        state.block_at(pos + DOWN)?.simplified_kind(),
         EndStone | ChorusPlant
     ) || (neighbouring_chorus == 1 && neighbouring_air == 3);
 
     Some(is_supported)
 }
 
-fn block_support_chorus_plant_like(game: &Game, pos: BlockPosition) -> Option<bool> {
-    let north = game.block_at(pos + NORTH)?;
-    let east = game.block_at(pos + EAST)?;
-    let south = game.block_at(pos + SOUTH)?;
-    let west = game.block_at(pos + WEST)?;
+fn block_support_chorus_plant_like(state: &WorldState, pos: BlockPosition) -> Option<bool> {
+    let north = state.block_at(pos + NORTH)?;
+    let east = state.block_at(pos + EAST)?;
+    let south = state.block_at(pos + SOUTH)?;
+    let west = state.block_at(pos + WEST)?;
 
-    let north_down = game.block_at(pos + NORTH + DOWN)?;
-    let east_down = game.block_at(pos + EAST + DOWN)?;
-    let south_down = game.block_at(pos + SOUTH + DOWN)?;
-    let west_down = game.block_at(pos + WEST + DOWN)?;
+    let north_down = state.block_at(pos + NORTH + DOWN)?;
+    let east_down = state.block_at(pos + EAST + DOWN)?;
+    let south_down = state.block_at(pos + SOUTH + DOWN)?;
+    let west_down = state.block_at(pos + WEST + DOWN)?;
 
-    let down = game.block_at(pos + DOWN)?;
-    let up = game.block_at(pos + UP)?;
+    let down = state.block_at(pos + DOWN)?;
+    let up = state.block_at(pos + UP)?;
 
     let horizontal = [north, east, south, west];
     let has_horizontal = horizontal
@@ -260,49 +259,49 @@ fn block_support_chorus_plant_like(game: &Game, pos: BlockPosition) -> Option<bo
     Some(is_supported)
 }
 
-fn block_support_mushroom_like(game: &Game, pos: BlockPosition) -> Option<bool> {
-    let chunk = game.chunk_map.chunk_at(pos.chunk())?;
-    let (x, y, z) = chunk_relative_pos(pos + DOWN);
+fn block_support_mushroom_like(state: &WorldState, pos: BlockPosition) -> Option<bool> {
+    let chunk = state.chunk_map.chunk_at(pos.chunk())?;
+    let (x, y, z) = chunk.local_pos(pos + DOWN)?;
 
-    let is_supported = game.block_at(pos + DOWN)?.is_full_block()
+    let is_supported = state.block_at(pos + DOWN)?.is_full_block()
         && max(chunk.sky_light_at(x, y, z), chunk.block_light_at(x, y, z)) < 13;
 
     Some(is_supported)
 }
 
-fn block_support_sugar_cane_like(game: &Game, pos: BlockPosition) -> Option<bool> {
-    let support = game.block_at(pos + DOWN)?.simplified_kind();
+fn block_support_sugar_cane_like(state: &WorldState, pos: BlockPosition) -> Option<bool> {
+    let support = state.block_at(pos + DOWN)?.simplified_kind();
 
     let is_supported = support == SugarCane
         || (matches!(
             support,
             GrassBlock | Dirt | CoarseDirt | Podzol | Sand | RedSand
         ) && (matches!(
-            game.block_at(pos + DOWN + NORTH)?.simplified_kind(),
+            state.block_at(pos + DOWN + NORTH)?.simplified_kind(),
             Water | FrostedIce
         ) || matches!(
-            game.block_at(pos + DOWN + EAST)?.simplified_kind(),
+            state.block_at(pos + DOWN + EAST)?.simplified_kind(),
             Water | FrostedIce
         ) || matches!(
-            game.block_at(pos + DOWN + SOUTH)?.simplified_kind(),
+            state.block_at(pos + DOWN + SOUTH)?.simplified_kind(),
             Water | FrostedIce
         ) || matches!(
-            game.block_at(pos + DOWN + WEST)?.simplified_kind(),
+            state.block_at(pos + DOWN + WEST)?.simplified_kind(),
             Water | FrostedIce
         )));
 
     Some(is_supported)
 }
 
-fn block_support_vine_like(game: &Game, pos: BlockPosition) -> Option<bool> {
-    let up = game.block_at(pos + UP)?;
+fn block_support_vine_like(state: &WorldState, pos: BlockPosition) -> Option<bool> {
+    let up = state.block_at(pos + UP)?;
 
     let is_supported = up.is_full_block()
         || up.simplified_kind() == Vine
-        || game.block_at(pos + NORTH)?.is_full_block()
-        || game.block_at(pos + EAST)?.is_full_block()
-        || game.block_at(pos + SOUTH)?.is_full_block()
-        || game.block_at(pos + WEST)?.is_full_block();
+        || state.block_at(pos + NORTH)?.is_full_block()
+        || state.block_at(pos + EAST)?.is_full_block()
+        || state.block_at(pos + SOUTH)?.is_full_block()
+        || state.block_at(pos + WEST)?.is_full_block();
 
     Some(is_supported)
 }
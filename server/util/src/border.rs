@@ -0,0 +1,74 @@
+//! Handles world borders.
+
+use feather_core::network::packets::WorldBorder as WorldBorderPacket;
+use feather_core::util::Position;
+use feather_server_types::{
+    CanTakeDamage, DamageSource, Dead, Game, Health, Network, Player, PlayerPreJoinEvent,
+    WorldChangeEvent, WorldId,
+};
+use fecs::{component, Entity, IntoQuery, Read, World};
+
+/// Number of ticks between two hits of border damage.
+const DAMAGE_INTERVAL: u64 = 10;
+
+/// System which moves world borders which are
+/// shrinking or growing.
+#[fecs::system]
+pub fn tick_world_borders(game: &mut Game) {
+    for state in game.worlds.iter_mut() {
+        state.border.tick();
+    }
+}
+
+/// System which damages players outside
+/// their world's border.
+#[fecs::system]
+pub fn damage_outside_border(game: &mut Game, world: &mut World) {
+    if game.tick_count % DAMAGE_INTERVAL != 0 {
+        return;
+    }
+
+    let damaged: Vec<(Entity, u32)> = <(Read<Position>, Read<WorldId>)>::query()
+        .filter(component::<Player>())
+        .filter(component::<Health>())
+        .filter(component::<CanTakeDamage>())
+        .filter(!component::<Dead>())
+        .iter_entities(world.inner())
+        .filter_map(|(entity, (pos, world_id))| {
+            let damage = game.worlds.get(*world_id)?.border.damage_at(*pos);
+            if damage > 0 {
+                Some((entity, damage))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for (entity, damage) in damaged {
+        game.damage(entity, damage, DamageSource::Suffocation, world);
+    }
+}
+
+/// Sends the border of a joining player's world.
+#[fecs::event_handler]
+pub fn on_player_join_send_border(event: &PlayerPreJoinEvent, game: &Game, world: &mut World) {
+    let world_id = game.world_of(world, event.player);
+    send_border(game, world, world_id, event.player);
+}
+
+/// Sends the border of a player's new world when it changes worlds.
+#[fecs::event_handler]
+pub fn on_world_change_send_border(event: &WorldChangeEvent, game: &Game, world: &mut World) {
+    send_border(game, world, event.new, event.entity);
+}
+
+fn send_border(game: &Game, world: &World, world_id: WorldId, player: Entity) {
+    let (network, state) = match (world.try_get::<Network>(player), game.worlds.get(world_id)) {
+        (Some(network), Some(state)) => (network, state),
+        _ => return,
+    };
+
+    network.send(WorldBorderPacket {
+        action: state.border.initialize_action(),
+    });
+}
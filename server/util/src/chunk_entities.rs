@@ -1,23 +1,26 @@
-use feather_core::util::Position;
-use feather_server_types::{ChunkCrossEvent, EntityDespawnEvent, EntitySpawnEvent, Game};
-use fecs::World;
+use feather_core::util::{ChunkPosition, Position};
+use feather_server_types::{
+    ChunkCrossEvent, ChunkEntities, EntityDespawnEvent, EntitySpawnEvent, Game, WorldChangeEvent,
+};
+use fecs::{Entity, World};
 use itertools::Itertools;
 
 /// System to update ChunkEntities when entities move into new chunks.
 #[fecs::event_handler]
-pub fn on_chunk_cross_update_chunk_entities(event: &ChunkCrossEvent, game: &mut Game) {
+pub fn on_chunk_cross_update_chunk_entities(
+    event: &ChunkCrossEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
     if let Some(old) = event.old {
-        if let Some(vec) = game.chunk_entities.0.get_mut(&old) {
-            let index = vec
-                .iter()
-                .find_position(|e| **e == event.entity)
-                .map(|(index, _)| index);
-            if let Some(index) = index {
-                vec.swap_remove(index);
-            }
-        }
+        let world_id = game.world_of(world, event.entity);
+        let chunk_entities = match game.worlds.get_mut(world_id) {
+            Some(state) => &mut state.chunk_entities,
+            None => return,
+        };
+        remove_entity(chunk_entities, old, event.entity);
 
-        game.chunk_entities
+        chunk_entities
             .0
             .entry(event.new)
             .or_default()
@@ -32,14 +35,9 @@ pub fn on_entity_despawn_update_chunk_entities(
     world: &mut World,
 ) {
     if let Some(pos) = world.try_get::<Position>(event.entity) {
-        if let Some(vec) = game.chunk_entities.0.get_mut(&pos.chunk()) {
-            let index = vec
-                .iter()
-                .find_position(|e| **e == event.entity)
-                .map(|(index, _)| index);
-            if let Some(index) = index {
-                vec.swap_remove(index);
-            }
+        let world_id = game.world_of(world, event.entity);
+        if let Some(state) = game.worlds.get_mut(world_id) {
+            remove_entity(&mut state.chunk_entities, pos.chunk(), event.entity);
         }
     }
 }
@@ -54,10 +52,53 @@ pub fn on_entity_spawn_update_chunk_entities(
         .try_get::<Position>(event.entity)
         .map(|pos| pos.chunk())
     {
-        game.chunk_entities
+        let world_id = game.world_of(world, event.entity);
+        if let Some(state) = game.worlds.get_mut(world_id) {
+            state
+                .chunk_entities
+                .0
+                .entry(chunk)
+                .or_default()
+                .push(event.entity);
+        }
+    }
+}
+
+/// Moves an entity which changed worlds to
+/// the chunk entities of its new world.
+#[fecs::event_handler]
+pub fn on_world_change_update_chunk_entities(
+    event: &WorldChangeEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if let Some(state) = game.worlds.get_mut(event.old) {
+        remove_entity(
+            &mut state.chunk_entities,
+            event.old_position.chunk(),
+            event.entity,
+        );
+    }
+
+    let chunk = world.get::<Position>(event.entity).chunk();
+    if let Some(state) = game.worlds.get_mut(event.new) {
+        state
+            .chunk_entities
             .0
             .entry(chunk)
             .or_default()
             .push(event.entity);
     }
 }
+
+fn remove_entity(chunk_entities: &mut ChunkEntities, chunk: ChunkPosition, entity: Entity) {
+    if let Some(vec) = chunk_entities.0.get_mut(&chunk) {
+        let index = vec
+            .iter()
+            .find_position(|e| **e == entity)
+            .map(|(index, _)| index);
+        if let Some(index) = index {
+            vec.swap_remove(index);
+        }
+    }
+}
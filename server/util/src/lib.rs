@@ -8,14 +8,18 @@ use nalgebra_glm::{vec3, DVec3};
 
 mod block;
 pub use block::*;
+mod border;
+pub use border::*;
 mod chunk_entities;
 pub use chunk_entities::*;
+mod scoreboard;
+pub use scoreboard::*;
 mod time;
 pub use time::*;
 mod load;
 pub use load::*;
 
-use feather_server_types::{Game, Uuid};
+use feather_server_types::{Game, Uuid, WorldId};
 use fecs::{Entity, World};
 use rand::Rng;
 use rand_distr::{Distribution, StandardNormal};
@@ -56,6 +60,10 @@ pub fn degrees_to_stops(degs: f32) -> u8 {
 }
 
 /// Returns the set of block positions adjacent to a given position.
+///
+/// Positions outside the vertical bounds of the world are included,
+/// as the bounds depend on the world; looking up blocks there
+/// returns `None`.
 pub fn adjacent_blocks(pos: BlockPosition) -> ArrayVec<[BlockPosition; 6]> {
     [
         pos + BlockPosition::new(1, 0, 0),
@@ -66,7 +74,6 @@ pub fn adjacent_blocks(pos: BlockPosition) -> ArrayVec<[BlockPosition; 6]> {
         pos + BlockPosition::new(0, 0, -1),
     ]
     .iter()
-    .filter(|pos| pos.y >= 0 && pos.y < 256)
     .copied()
     .collect()
 }
@@ -83,13 +90,14 @@ pub fn protocol_velocity(vel: DVec3) -> (i16, i16, i16) {
 }
 
 /// Returns all entities within the given distance of the given
-/// position.
+/// position in a world.
 ///
 /// # Panics
 /// Panics if either coordinate of the radius is negative.
 pub fn nearby_entities(
     world: &World,
     game: &Game,
+    world_id: WorldId,
     pos: Position,
     radius: DVec3,
 ) -> SmallVec<[Entity; 4]> {
@@ -98,9 +106,13 @@ pub fn nearby_entities(
     assert!(radius.z >= 0.0);
 
     let mut result = SmallVec::new();
+    let state = match game.worlds.get(world_id) {
+        Some(state) => state,
+        None => return result,
+    };
 
     for chunk in chunks_within_distance(pos, radius) {
-        let entities = game.chunk_entities.entities_in_chunk(chunk);
+        let entities = state.chunk_entities.entities_in_chunk(chunk);
         entities
             .iter()
             .copied()
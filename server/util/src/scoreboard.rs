@@ -0,0 +1,20 @@
+//! Sends the scoreboard to players.
+
+use feather_server_types::{Game, Network, PlayerJoinEvent};
+use fecs::World;
+
+/// Sends the scoreboard's objectives, scores and teams to a joining player.
+///
+/// Sent on join rather than pre-join so that scoreboard teams
+/// are created after the teams which sort the tab list.
+#[fecs::event_handler]
+pub fn on_player_join_send_scoreboard(event: &PlayerJoinEvent, game: &Game, world: &mut World) {
+    let network = match world.try_get::<Network>(event.player) {
+        Some(network) => network,
+        None => return,
+    };
+
+    for packet in game.scoreboard.initialize_packets() {
+        network.send_boxed(packet);
+    }
+}
@@ -1,25 +1,136 @@
 //! Handles world time.
 
 use feather_core::network::packets::TimeUpdate;
-use feather_server_types::{Game, Network, PlayerPreJoinEvent};
-use fecs::World;
+use feather_server_types::{
+    Game, Network, PlayerPreJoinEvent, Time, WorldChangeEvent, WorldId, WorldState,
+};
+use fecs::{Entity, World};
 
-/// System for incrementing time each tick.
+/// Ticks between each time update sent to players. Clients
+/// advance the time on their own in between.
+const TIME_UPDATE_INTERVAL: u64 = 20;
+
+/// System for incrementing the time of every world each tick.
+/// The time of day only advances if `doDaylightCycle` is on.
 #[fecs::system]
-pub fn increment_time(game: &mut Game) {
-    game.time.0 += 1;
+pub fn increment_time(game: &mut Game, world: &mut World) {
+    let daylight_cycle = game.game_rules.do_daylight_cycle;
+    for state in game.worlds.iter_mut() {
+        advance(&mut state.time, daylight_cycle);
+    }
+
+    if game.tick_count % TIME_UPDATE_INTERVAL == 0 {
+        broadcast_time(game, world);
+    }
+}
+
+/// Advances a world's time by one tick.
+fn advance(time: &mut Time, daylight_cycle: bool) {
+    time.age += 1;
+    if daylight_cycle {
+        time.day_time += 1;
+    }
+}
+
+/// Sends the time of each world to the players in it, e.g.
+/// after the time was changed by a command.
+pub fn broadcast_time(game: &Game, world: &World) {
+    for state in game.worlds.iter() {
+        game.broadcast_world(world, time_update(game, state), state.id, None);
+    }
 }
 
 /// Event handler for sending world time to players.
 #[fecs::event_handler]
 pub fn on_player_join_send_time(event: &PlayerPreJoinEvent, game: &Game, world: &mut World) {
-    let network = world.get::<Network>(event.player);
+    let world_id = game.world_of(world, event.player);
+    send_time(game, world, world_id, event.player);
+}
+
+/// Sends the time of a player's new world when it changes worlds.
+#[fecs::event_handler]
+pub fn on_world_change_send_time(event: &WorldChangeEvent, game: &Game, world: &mut World) {
+    send_time(game, world, event.new, event.entity);
+}
 
-    // Send time to player.
-    let packet = TimeUpdate {
-        world_age: game.time.world_age() as i64,
-        time_of_day: game.time.time_of_day() as i64,
+fn send_time(game: &Game, world: &World, world_id: WorldId, player: Entity) {
+    let (network, state) = match (world.try_get::<Network>(player), game.worlds.get(world_id)) {
+        (Some(network), Some(state)) => (network, state),
+        _ => return,
     };
 
-    network.send(packet);
+    network.send(time_update(game, state));
+}
+
+fn time_update(game: &Game, state: &WorldState) -> TimeUpdate {
+    TimeUpdate {
+        world_age: state.time.world_age() as i64,
+        time_of_day: encode_day_time(state.time.day_time, game.game_rules.do_daylight_cycle),
+    }
+}
+
+/// Encodes the day time sent to clients. The full day time is
+/// sent so that clients know the phase of the moon. A negative
+/// time stops them from advancing it; as zero can't be negated,
+/// -1 is sent instead.
+fn encode_day_time(day_time: u64, daylight_cycle: bool) -> i64 {
+    let day_time = day_time as i64;
+    if daylight_cycle {
+        day_time
+    } else if day_time == 0 {
+        -1
+    } else {
+        -day_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_server_types::DAY_LENGTH;
+
+    #[test]
+    fn day_time_stands_still_without_daylight_cycle() {
+        let mut time = Time::default();
+        advance(&mut time, true);
+        advance(&mut time, true);
+        assert_eq!(
+            time,
+            Time {
+                age: 2,
+                day_time: 2
+            }
+        );
+
+        advance(&mut time, false);
+        assert_eq!(
+            time,
+            Time {
+                age: 3,
+                day_time: 2
+            }
+        );
+        assert_eq!(time.world_age(), 3);
+    }
+
+    #[test]
+    fn set_time_of_day_keeps_day() {
+        let mut time = Time {
+            age: 100_000,
+            day_time: 3 * DAY_LENGTH + 18_000,
+        };
+        time.set_time_of_day(1000);
+        assert_eq!(time.day(), 3);
+        assert_eq!(time.time_of_day(), 1000);
+        assert_eq!(time.age, 100_000);
+    }
+
+    #[test]
+    fn day_time_sign() {
+        assert_eq!(encode_day_time(6000, true), 6000);
+        assert_eq!(encode_day_time(0, true), 0);
+        assert_eq!(encode_day_time(6000, false), -6000);
+        // Zero would keep the time advancing.
+        assert_eq!(encode_day_time(0, false), -1);
+    }
 }
@@ -1,129 +1,98 @@
+//! The weather cycle and sending the weather to players.
+//!
+//! Players only see the weather in worlds with a sky. Rain
+//! and thunder fade in and out gradually, so their strength
+//! is sent to players every tick while they change.
+
 use feather_core::network::packets::ChangeGameState;
-use feather_server_types::{Game, Network, PlayerPreJoinEvent, Weather, WeatherChangeEvent};
+use feather_core::util::Dimension;
+use feather_server_types::{Game, Network, PlayerPreJoinEvent, WorldChangeEvent, WorldId};
 use fecs::{Entity, World};
-use rand::Rng;
 
-const TICKS_DAY: i32 = 24_000;
-const TICKS_HALF_DAY: i32 = TICKS_DAY / 2;
-const TICKS_WEEK: i32 = TICKS_DAY * 7;
-// const THUNDER_FACTOR: i32 = 10;
+/// `ChangeGameState` reason for rain starting.
+const BEGIN_RAINING: u8 = 1;
+/// `ChangeGameState` reason for rain stopping.
+const END_RAINING: u8 = 2;
+/// `ChangeGameState` reason for a new rain strength.
+const RAIN_STRENGTH: u8 = 7;
+/// `ChangeGameState` reason for a new thunder strength.
+const THUNDER_STRENGTH: u8 = 8;
 
-#[allow(unused)]
-pub fn clear_weather(game: &mut Game) {
-    let duration = game
-        .rng()
-        .gen_range(TICKS_HALF_DAY, TICKS_WEEK + TICKS_HALF_DAY);
-    set_weather(game, Weather::Clear, duration);
+fn game_state(reason: u8, value: f32) -> ChangeGameState {
+    ChangeGameState { reason, value }
 }
 
-#[fecs::system]
-pub fn update_weather(game: &mut Game, world: &mut World) {
-    if game.level.clear_weather_time >= 0 {
-        game.level.clear_weather_time -= 1;
-        return;
-    }
-
-    let from = get_weather(game);
+fn has_weather(game: &Game, world_id: WorldId) -> bool {
+    game.worlds
+        .get(world_id)
+        .map_or(false, |state| state.dimension == Dimension::Overwold)
+}
 
-    game.level.rain_time -= 1;
-    let mut to = if game.level.rain_time <= 0 {
-        if game.level.raining {
-            Weather::Clear
-        } else {
-            Weather::Rain
+/// Sends a packet to all players in worlds with weather.
+fn broadcast_weather(game: &Game, world: &World, packet: ChangeGameState) {
+    for state in game.worlds.iter() {
+        if state.dimension == Dimension::Overwold {
+            game.broadcast_world(world, packet.clone(), state.id, None);
         }
-    } else {
-        from
-    };
+    }
+}
 
-    game.level.thunder_time -= 1;
-    to = if game.level.thunder_time <= 0 {
-        if game.level.thundering {
-            Weather::Clear
-        } else {
-            Weather::Thunder
-        }
-    } else {
-        to
-    };
+/// System which advances the weather cycle, fades rain
+/// and thunder in and out, and sends them to players.
+#[fecs::system]
+pub fn update_weather(game: &mut Game, world: &mut World) {
+    let was_raining = game.is_raining();
+    let old = game.weather_strength;
 
-    if from != to {
-        let duration = match to {
-            Weather::Clear => game
-                .rng()
-                .gen_range(TICKS_HALF_DAY, TICKS_WEEK + TICKS_HALF_DAY),
-            _ => game.rng().gen_range(TICKS_HALF_DAY, TICKS_DAY),
-        };
-        let event = WeatherChangeEvent { from, to, duration };
-        game.handle(world, event);
-        if event.to != from {
-            set_weather(game, event.to, event.duration);
-        }
+    if game.game_rules.do_weather_cycle {
+        game.cycle_weather(world);
     }
-}
+    game.weather_strength.fade(&game.level);
 
-pub fn get_weather(game: &Game) -> Weather {
-    if game.level.clear_weather_time > 0 {
-        Weather::Clear
-    } else if game.level.thundering {
-        Weather::Thunder
-    } else if game.level.raining {
-        Weather::Rain
-    } else {
-        Weather::Clear
+    let new = game.weather_strength;
+    #[allow(clippy::float_cmp)]
+    let (rain_changed, thunder_changed) = (new.rain != old.rain, new.thunder != old.thunder);
+    if rain_changed {
+        broadcast_weather(game, world, game_state(RAIN_STRENGTH, new.rain));
+    }
+    if thunder_changed {
+        broadcast_weather(game, world, game_state(THUNDER_STRENGTH, new.thunder));
     }
-}
 
-pub fn set_weather(game: &mut Game, weather: Weather, duration: i32) -> Weather {
-    let from = get_weather(game);
-    match weather {
-        Weather::Rain => {
-            game.level.raining = true;
-            game.level.rain_time = duration;
-        }
-        Weather::Thunder => {
-            game.level.thundering = true;
-            game.level.thunder_time = duration;
-        }
-        Weather::Clear => {
-            game.level.raining = false;
-            game.level.rain_time = 0;
-            game.level.thundering = false;
-            game.level.thunder_time = 0;
-            game.level.clear_weather_time = duration;
-        }
-    };
-    from
+    if game.is_raining() != was_raining {
+        let reason = if was_raining {
+            END_RAINING
+        } else {
+            BEGIN_RAINING
+        };
+        broadcast_weather(game, world, game_state(reason, 0.0));
+    }
 }
 
+/// Sends the weather to a player who is joining.
 #[fecs::event_handler]
 pub fn on_player_join_send_weather(event: &PlayerPreJoinEvent, game: &Game, world: &mut World) {
-    send_weather(world, event.player, get_weather(game));
+    let world_id = game.world_of(world, event.player);
+    send_weather(game, world, world_id, event.player);
 }
 
+/// Sends the weather to a player who moved into another world,
+/// since clients reset the weather when changing dimension.
 #[fecs::event_handler]
-pub fn on_weather_change_broadcast_weather(
-    event: &WeatherChangeEvent,
-    game: &mut Game,
-    world: &mut World,
-) {
-    game.broadcast_global(world, create_weather_packet(event.to), None);
-}
-
-pub fn send_weather(world: &mut World, player: Entity, to: Weather) {
-    let network = world.get::<Network>(player);
-
-    network.send(create_weather_packet(to));
+pub fn on_world_change_send_weather(event: &WorldChangeEvent, game: &Game, world: &mut World) {
+    send_weather(game, world, event.new, event.entity);
 }
 
-fn create_weather_packet(to: Weather) -> ChangeGameState {
-    let reason = match to {
-        Weather::Rain | Weather::Thunder => 2,
-        Weather::Clear => 1,
+fn send_weather(game: &Game, world: &World, world_id: WorldId, player: Entity) {
+    let network = match world.try_get::<Network>(player) {
+        Some(network) => network,
+        None => return,
     };
-
-    ChangeGameState {
-        reason,
-        value: 0f32,
+    if !has_weather(game, world_id) || !game.is_raining() {
+        return;
     }
+
+    network.send(game_state(BEGIN_RAINING, 0.0));
+    network.send(game_state(RAIN_STRENGTH, game.weather_strength.rain));
+    network.send(game_state(THUNDER_STRENGTH, game.weather_strength.thunder));
 }
@@ -0,0 +1,1166 @@
+//! Port of the vanilla biome layer system.
+//!
+//! Each layer maps integer values (usually biome IDs) over a
+//! rectangle of positions, sampling its parent layer(s) over a
+//! slightly larger or smaller rectangle. Every value depends only
+//! on the world seed and its position, so computing layers over
+//! whole areas gives the same results as vanilla's lazily evaluated
+//! layers while avoiding the need for caching.
+
+use crate::noise::ImprovedNoise;
+use crate::random::JavaRandom;
+use feather_core::biomes::{Biome, BiomeCategory, Precipitation, TempCategory};
+use std::sync::Arc;
+
+pub const OCEAN: i32 = 0;
+pub const PLAINS: i32 = 1;
+pub const DESERT: i32 = 2;
+pub const MOUNTAINS: i32 = 3;
+pub const FOREST: i32 = 4;
+pub const TAIGA: i32 = 5;
+pub const SWAMP: i32 = 6;
+pub const RIVER: i32 = 7;
+pub const FROZEN_OCEAN: i32 = 10;
+pub const FROZEN_RIVER: i32 = 11;
+pub const SNOWY_TUNDRA: i32 = 12;
+pub const SNOWY_MOUNTAINS: i32 = 13;
+pub const MUSHROOM_FIELDS: i32 = 14;
+pub const MUSHROOM_FIELD_SHORE: i32 = 15;
+pub const BEACH: i32 = 16;
+pub const DESERT_HILLS: i32 = 17;
+pub const WOODED_HILLS: i32 = 18;
+pub const TAIGA_HILLS: i32 = 19;
+pub const MOUNTAIN_EDGE: i32 = 20;
+pub const JUNGLE: i32 = 21;
+pub const JUNGLE_HILLS: i32 = 22;
+pub const JUNGLE_EDGE: i32 = 23;
+pub const DEEP_OCEAN: i32 = 24;
+pub const STONE_SHORE: i32 = 25;
+pub const SNOWY_BEACH: i32 = 26;
+pub const BIRCH_FOREST: i32 = 27;
+pub const BIRCH_FOREST_HILLS: i32 = 28;
+pub const DARK_FOREST: i32 = 29;
+pub const SNOWY_TAIGA: i32 = 30;
+pub const SNOWY_TAIGA_HILLS: i32 = 31;
+pub const GIANT_TREE_TAIGA: i32 = 32;
+pub const GIANT_TREE_TAIGA_HILLS: i32 = 33;
+pub const WOODED_MOUNTAINS: i32 = 34;
+pub const SAVANNA: i32 = 35;
+pub const SAVANNA_PLATEAU: i32 = 36;
+pub const BADLANDS: i32 = 37;
+pub const WOODED_BADLANDS_PLATEAU: i32 = 38;
+pub const BADLANDS_PLATEAU: i32 = 39;
+pub const WARM_OCEAN: i32 = 44;
+pub const LUKEWARM_OCEAN: i32 = 45;
+pub const COLD_OCEAN: i32 = 46;
+pub const DEEP_WARM_OCEAN: i32 = 47;
+pub const DEEP_LUKEWARM_OCEAN: i32 = 48;
+pub const DEEP_COLD_OCEAN: i32 = 49;
+pub const DEEP_FROZEN_OCEAN: i32 = 50;
+pub const SUNFLOWER_PLAINS: i32 = 129;
+
+/// A layer in the biome generation stack.
+pub trait Layer: Send + Sync {
+    /// Computes the values for a `width` by `height` area
+    /// starting at `(x, z)`. The returned vector is indexed
+    /// by `(z - start_z) * width + (x - start_x)`.
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32>;
+}
+
+/// Layers are reference counted since several
+/// branches of a stack may share the same parent.
+pub type LayerRef = Arc<dyn Layer>;
+
+/// Rectangle of values returned by a parent layer.
+struct Area {
+    x: i32,
+    z: i32,
+    width: i32,
+    values: Vec<i32>,
+}
+
+impl Area {
+    fn sample(layer: &dyn Layer, x: i32, z: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            z,
+            width,
+            values: layer.sample(x, z, width, height),
+        }
+    }
+
+    fn get(&self, x: i32, z: i32) -> i32 {
+        self.values[((z - self.z) * self.width + (x - self.x)) as usize]
+    }
+}
+
+/// Calls `f` for each position in the given area,
+/// collecting the results in layer order.
+fn map_area(
+    x: i32,
+    z: i32,
+    width: i32,
+    height: i32,
+    mut f: impl FnMut(i32, i32) -> i32,
+) -> Vec<i32> {
+    let mut result = Vec::with_capacity((width * height) as usize);
+    for pz in z..z + height {
+        for px in x..x + width {
+            result.push(f(px, pz));
+        }
+    }
+    result
+}
+
+fn mix(left: i64, right: i64) -> i64 {
+    left.wrapping_mul(
+        left.wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407),
+    )
+    .wrapping_add(right)
+}
+
+/// The position-seeded random generator used by layers.
+#[derive(Debug, Clone)]
+pub struct LayerRandom {
+    seed: i64,
+    state: i64,
+}
+
+impl LayerRandom {
+    /// Creates a generator for the layer with the given salt.
+    pub fn new(world_seed: i64, salt: i64) -> Self {
+        let mut base = salt;
+        for _ in 0..3 {
+            base = mix(base, salt);
+        }
+
+        let mut seed = world_seed;
+        for _ in 0..3 {
+            seed = mix(seed, base);
+        }
+
+        Self { seed, state: 0 }
+    }
+
+    /// Seeds the generator for the given position.
+    pub fn set_position(&mut self, x: i32, z: i32) {
+        let (x, z) = (i64::from(x), i64::from(z));
+        self.state = mix(mix(mix(mix(self.seed, x), z), x), z);
+    }
+
+    /// Returns a random integer in `[0, bound)`.
+    pub fn next_int(&mut self, bound: i32) -> i32 {
+        let result = (self.state >> 24).rem_euclid(i64::from(bound)) as i32;
+        self.state = mix(self.state, self.seed);
+        result
+    }
+
+    fn choose2(&mut self, a: i32, b: i32) -> i32 {
+        if self.next_int(2) == 0 {
+            a
+        } else {
+            b
+        }
+    }
+
+    fn choose4(&mut self, a: i32, b: i32, c: i32, d: i32) -> i32 {
+        match self.next_int(4) {
+            0 => a,
+            1 => b,
+            2 => c,
+            _ => d,
+        }
+    }
+
+    /// Returns the most common of the four values,
+    /// choosing randomly between ties.
+    fn mode_or_random(&mut self, a: i32, b: i32, c: i32, d: i32) -> i32 {
+        if b == c && c == d {
+            b
+        } else if (a == b && (a == c || a == d || c != d))
+            || (a == c && (a == d || b != d))
+            || (a == d && b != c)
+        {
+            a
+        } else if (b == c && a != d) || (b == d && a != c) {
+            b
+        } else if c == d && a != b {
+            c
+        } else {
+            self.choose4(a, b, c, d)
+        }
+    }
+}
+
+pub fn is_shallow_ocean(id: i32) -> bool {
+    matches!(
+        id,
+        WARM_OCEAN | LUKEWARM_OCEAN | OCEAN | COLD_OCEAN | FROZEN_OCEAN
+    )
+}
+
+pub fn is_ocean(id: i32) -> bool {
+    is_shallow_ocean(id)
+        || matches!(
+            id,
+            DEEP_WARM_OCEAN
+                | DEEP_LUKEWARM_OCEAN
+                | DEEP_OCEAN
+                | DEEP_COLD_OCEAN
+                | DEEP_FROZEN_OCEAN
+        )
+}
+
+fn is_mesa(id: i32) -> bool {
+    Biome::from_id(id).map(Biome::category) == Some(BiomeCategory::Mesa)
+}
+
+fn are_similar(a: i32, b: i32) -> bool {
+    if a == b {
+        return true;
+    }
+
+    match (Biome::from_id(a), Biome::from_id(b)) {
+        (Some(Biome::WoodedBadlandsPlateau), Some(other))
+        | (Some(Biome::BadlandsPlateau), Some(other)) => {
+            other == Biome::WoodedBadlandsPlateau || other == Biome::BadlandsPlateau
+        }
+        (Some(a), Some(b)) => a.category() != BiomeCategory::None && a.category() == b.category(),
+        _ => false,
+    }
+}
+
+fn can_be_neighbors(a: i32, b: i32) -> bool {
+    if are_similar(a, b) {
+        return true;
+    }
+
+    match (Biome::from_id(a), Biome::from_id(b)) {
+        (Some(a), Some(b)) => {
+            let (a, b) = (a.temp_category(), b.temp_category());
+            a == b || a == TempCategory::Medium || b == TempCategory::Medium
+        }
+        _ => false,
+    }
+}
+
+fn mutation_of(id: i32) -> Option<i32> {
+    Biome::from_id(id).and_then(Biome::mutation).map(Biome::id)
+}
+
+/// Initial layer: mostly ocean with random land (`1`) cells.
+pub struct IslandLayer {
+    rng: LayerRandom,
+}
+
+impl IslandLayer {
+    pub fn new(world_seed: i64, salt: i64) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+        }
+    }
+}
+
+impl Layer for IslandLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let mut rng = self.rng.clone();
+        map_area(x, z, width, height, |x, z| {
+            rng.set_position(x, z);
+            if (x == 0 && z == 0) || rng.next_int(10) == 0 {
+                1
+            } else {
+                0
+            }
+        })
+    }
+}
+
+/// Layer generating ocean temperatures from Perlin noise.
+pub struct OceanTemperatureLayer {
+    noise: ImprovedNoise,
+}
+
+impl OceanTemperatureLayer {
+    pub fn new(world_seed: i64) -> Self {
+        Self {
+            noise: ImprovedNoise::new(&mut JavaRandom::new(world_seed)),
+        }
+    }
+}
+
+impl Layer for OceanTemperatureLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        map_area(x, z, width, height, |x, z| {
+            let value = self
+                .noise
+                .sample(f64::from(x) / 8.0, f64::from(z) / 8.0, 0.0);
+            if value > 0.4 {
+                WARM_OCEAN
+            } else if value > 0.2 {
+                LUKEWARM_OCEAN
+            } else if value < -0.4 {
+                FROZEN_OCEAN
+            } else if value < -0.2 {
+                COLD_OCEAN
+            } else {
+                OCEAN
+            }
+        })
+    }
+}
+
+/// Layer doubling the resolution of its parent.
+pub struct ZoomLayer {
+    rng: LayerRandom,
+    fuzzy: bool,
+    parent: LayerRef,
+}
+
+impl ZoomLayer {
+    pub fn new(world_seed: i64, salt: i64, parent: LayerRef) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+            fuzzy: false,
+            parent,
+        }
+    }
+
+    /// Creates a zoom layer which picks corners randomly
+    /// instead of preferring the most common one.
+    pub fn fuzzy(world_seed: i64, salt: i64, parent: LayerRef) -> Self {
+        Self {
+            fuzzy: true,
+            ..Self::new(world_seed, salt, parent)
+        }
+    }
+
+    /// Applies `count` normal zoom layers with consecutive salts.
+    pub fn repeat(world_seed: i64, salt: i64, mut layer: LayerRef, count: u32) -> LayerRef {
+        for i in 0..count {
+            layer = Arc::new(Self::new(world_seed, salt + i64::from(i), layer));
+        }
+        layer
+    }
+}
+
+impl Layer for ZoomLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let (px, pz) = (x >> 1, z >> 1);
+        let parent = Area::sample(
+            self.parent.as_ref(),
+            px,
+            pz,
+            ((x + width - 1) >> 1) - px + 2,
+            ((z + height - 1) >> 1) - pz + 2,
+        );
+
+        let mut rng = self.rng.clone();
+        map_area(x, z, width, height, |x, z| {
+            let (px, pz) = (x >> 1, z >> 1);
+            let origin = parent.get(px, pz);
+
+            rng.set_position(x >> 1 << 1, z >> 1 << 1);
+            let (dx, dz) = (x & 1, z & 1);
+            if dx == 0 && dz == 0 {
+                return origin;
+            }
+
+            let south = parent.get(px, pz + 1);
+            let south_choice = rng.choose2(origin, south);
+            if dx == 0 && dz == 1 {
+                return south_choice;
+            }
+
+            let east = parent.get(px + 1, pz);
+            let east_choice = rng.choose2(origin, east);
+            if dx == 1 && dz == 0 {
+                return east_choice;
+            }
+
+            let south_east = parent.get(px + 1, pz + 1);
+            if self.fuzzy {
+                rng.choose4(origin, east, south, south_east)
+            } else {
+                rng.mode_or_random(origin, east, south, south_east)
+            }
+        })
+    }
+}
+
+/// Transformation applied to a single value.
+pub type PointFn = fn(&mut LayerRandom, i32) -> i32;
+
+/// Layer applying a function to each value of its parent.
+pub struct PointLayer {
+    rng: LayerRandom,
+    parent: LayerRef,
+    transform: PointFn,
+}
+
+impl PointLayer {
+    pub fn new(world_seed: i64, salt: i64, parent: LayerRef, transform: PointFn) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+            parent,
+            transform,
+        }
+    }
+}
+
+impl Layer for PointLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let parent = Area::sample(self.parent.as_ref(), x, z, width, height);
+        let mut rng = self.rng.clone();
+        map_area(x, z, width, height, |x, z| {
+            rng.set_position(x, z);
+            (self.transform)(&mut rng, parent.get(x, z))
+        })
+    }
+}
+
+/// Transformation applied to a value and its direct neighbors.
+/// Arguments are north, east, south, west, and center.
+pub type CrossFn = fn(&mut LayerRandom, i32, i32, i32, i32, i32) -> i32;
+
+/// Layer applying a function to each value and its four direct neighbors.
+pub struct CrossLayer {
+    rng: LayerRandom,
+    parent: LayerRef,
+    transform: CrossFn,
+}
+
+impl CrossLayer {
+    pub fn new(world_seed: i64, salt: i64, parent: LayerRef, transform: CrossFn) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+            parent,
+            transform,
+        }
+    }
+}
+
+impl Layer for CrossLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let parent = Area::sample(self.parent.as_ref(), x - 1, z - 1, width + 2, height + 2);
+        let mut rng = self.rng.clone();
+        map_area(x, z, width, height, |x, z| {
+            rng.set_position(x, z);
+            (self.transform)(
+                &mut rng,
+                parent.get(x, z - 1),
+                parent.get(x + 1, z),
+                parent.get(x, z + 1),
+                parent.get(x - 1, z),
+                parent.get(x, z),
+            )
+        })
+    }
+}
+
+/// Transformation applied to a value and its diagonal neighbors.
+/// Arguments are south-west, south-east, north-east, north-west, and center.
+pub type DiagonalFn = fn(&mut LayerRandom, i32, i32, i32, i32, i32) -> i32;
+
+/// Layer applying a function to each value and its four diagonal neighbors.
+pub struct DiagonalLayer {
+    rng: LayerRandom,
+    parent: LayerRef,
+    transform: DiagonalFn,
+}
+
+impl DiagonalLayer {
+    pub fn new(world_seed: i64, salt: i64, parent: LayerRef, transform: DiagonalFn) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+            parent,
+            transform,
+        }
+    }
+}
+
+impl Layer for DiagonalLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let parent = Area::sample(self.parent.as_ref(), x - 1, z - 1, width + 2, height + 2);
+        let mut rng = self.rng.clone();
+        map_area(x, z, width, height, |x, z| {
+            rng.set_position(x, z);
+            (self.transform)(
+                &mut rng,
+                parent.get(x - 1, z + 1),
+                parent.get(x + 1, z + 1),
+                parent.get(x + 1, z - 1),
+                parent.get(x - 1, z - 1),
+                parent.get(x, z),
+            )
+        })
+    }
+}
+
+/// Layer adding hills and mutated biomes, driven by the river noise.
+pub struct HillsLayer {
+    rng: LayerRandom,
+    biomes: LayerRef,
+    noise: LayerRef,
+}
+
+impl HillsLayer {
+    pub fn new(world_seed: i64, salt: i64, biomes: LayerRef, noise: LayerRef) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+            biomes,
+            noise,
+        }
+    }
+}
+
+impl Layer for HillsLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let biomes = Area::sample(self.biomes.as_ref(), x - 1, z - 1, width + 2, height + 2);
+        let noise = Area::sample(self.noise.as_ref(), x, z, width, height);
+        let mut rng = self.rng.clone();
+
+        map_area(x, z, width, height, |x, z| {
+            rng.set_position(x, z);
+
+            let biome = biomes.get(x, z);
+            let noise = noise.get(x, z);
+            let variant = (noise - 2) % 29;
+
+            if !is_shallow_ocean(biome) && noise >= 2 && variant == 1 {
+                let is_mutation = Biome::from_id(biome).map(Biome::is_mutation);
+                if is_mutation != Some(true) {
+                    return mutation_of(biome).unwrap_or(biome);
+                }
+            }
+
+            if rng.next_int(3) == 0 || variant == 0 {
+                let mut hills = match biome {
+                    DESERT => DESERT_HILLS,
+                    FOREST => WOODED_HILLS,
+                    BIRCH_FOREST => BIRCH_FOREST_HILLS,
+                    DARK_FOREST => PLAINS,
+                    TAIGA => TAIGA_HILLS,
+                    GIANT_TREE_TAIGA => GIANT_TREE_TAIGA_HILLS,
+                    SNOWY_TAIGA => SNOWY_TAIGA_HILLS,
+                    PLAINS => {
+                        if rng.next_int(3) == 0 {
+                            WOODED_HILLS
+                        } else {
+                            FOREST
+                        }
+                    }
+                    SNOWY_TUNDRA => SNOWY_MOUNTAINS,
+                    JUNGLE => JUNGLE_HILLS,
+                    OCEAN => DEEP_OCEAN,
+                    LUKEWARM_OCEAN => DEEP_LUKEWARM_OCEAN,
+                    COLD_OCEAN => DEEP_COLD_OCEAN,
+                    FROZEN_OCEAN => DEEP_FROZEN_OCEAN,
+                    MOUNTAINS => WOODED_MOUNTAINS,
+                    SAVANNA => SAVANNA_PLATEAU,
+                    _ if are_similar(biome, WOODED_BADLANDS_PLATEAU) => BADLANDS,
+                    DEEP_OCEAN | DEEP_LUKEWARM_OCEAN | DEEP_COLD_OCEAN | DEEP_FROZEN_OCEAN
+                        if rng.next_int(3) == 0 =>
+                    {
+                        if rng.next_int(2) == 0 {
+                            PLAINS
+                        } else {
+                            FOREST
+                        }
+                    }
+                    _ => biome,
+                };
+
+                if variant == 0 && hills != biome {
+                    hills = mutation_of(hills).unwrap_or(biome);
+                }
+
+                if hills != biome {
+                    let similar_neighbors = [
+                        biomes.get(x, z - 1),
+                        biomes.get(x + 1, z),
+                        biomes.get(x - 1, z),
+                        biomes.get(x, z + 1),
+                    ]
+                    .iter()
+                    .filter(|neighbor| are_similar(**neighbor, biome))
+                    .count();
+
+                    if similar_neighbors >= 3 {
+                        return hills;
+                    }
+                }
+            }
+
+            biome
+        })
+    }
+}
+
+/// Transformation merging the values of two layers at the same position.
+pub type MergeFn = fn(i32, i32) -> i32;
+
+/// Layer merging two parent layers point by point.
+pub struct MergeLayer {
+    first: LayerRef,
+    second: LayerRef,
+    merge: MergeFn,
+}
+
+impl MergeLayer {
+    pub fn new(first: LayerRef, second: LayerRef, merge: MergeFn) -> Self {
+        Self {
+            first,
+            second,
+            merge,
+        }
+    }
+}
+
+impl Layer for MergeLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let first = self.first.sample(x, z, width, height);
+        let second = self.second.sample(x, z, width, height);
+        first
+            .into_iter()
+            .zip(second)
+            .map(|(a, b)| (self.merge)(a, b))
+            .collect()
+    }
+}
+
+/// Layer replacing ocean biomes with those of the
+/// ocean temperature layer.
+pub struct MixOceansLayer {
+    land: LayerRef,
+    ocean: LayerRef,
+}
+
+impl MixOceansLayer {
+    pub fn new(land: LayerRef, ocean: LayerRef) -> Self {
+        Self { land, ocean }
+    }
+}
+
+impl Layer for MixOceansLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let land = Area::sample(self.land.as_ref(), x - 8, z - 8, width + 16, height + 16);
+        let ocean = Area::sample(self.ocean.as_ref(), x, z, width, height);
+
+        map_area(x, z, width, height, |x, z| {
+            let biome = land.get(x, z);
+            let temperature = ocean.get(x, z);
+
+            if !is_ocean(biome) {
+                return biome;
+            }
+
+            for dx in (-8..=8).step_by(4) {
+                for dz in (-8..=8).step_by(4) {
+                    if !is_ocean(land.get(x + dx, z + dz)) {
+                        if temperature == WARM_OCEAN {
+                            return LUKEWARM_OCEAN;
+                        }
+                        if temperature == FROZEN_OCEAN {
+                            return COLD_OCEAN;
+                        }
+                    }
+                }
+            }
+
+            if biome == DEEP_OCEAN {
+                match temperature {
+                    LUKEWARM_OCEAN => return DEEP_LUKEWARM_OCEAN,
+                    OCEAN => return DEEP_OCEAN,
+                    COLD_OCEAN => return DEEP_COLD_OCEAN,
+                    FROZEN_OCEAN => return DEEP_FROZEN_OCEAN,
+                    _ => (),
+                }
+            }
+
+            temperature
+        })
+    }
+}
+
+/// Final layer scaling biomes from 1:4 to block resolution,
+/// using a jittered Voronoi diagram.
+pub struct VoronoiZoomLayer {
+    rng: LayerRandom,
+    parent: LayerRef,
+}
+
+impl VoronoiZoomLayer {
+    pub fn new(world_seed: i64, salt: i64, parent: LayerRef) -> Self {
+        Self {
+            rng: LayerRandom::new(world_seed, salt),
+            parent,
+        }
+    }
+}
+
+impl Layer for VoronoiZoomLayer {
+    fn sample(&self, x: i32, z: i32, width: i32, height: i32) -> Vec<i32> {
+        let (px, pz) = ((x - 2) >> 2, (z - 2) >> 2);
+        let parent = Area::sample(
+            self.parent.as_ref(),
+            px,
+            pz,
+            ((x + width - 3) >> 2) - px + 2,
+            ((z + height - 3) >> 2) - pz + 2,
+        );
+
+        let mut rng = self.rng.clone();
+        let jitter = |rng: &mut LayerRandom, offset: f64| {
+            (f64::from(rng.next_int(1024)) / 1024.0 - 0.5) * 3.6 + offset
+        };
+
+        map_area(x, z, width, height, |x, z| {
+            let (x, z) = (x - 2, z - 2);
+            let (cell_x, cell_z) = (x >> 2 << 2, z >> 2 << 2);
+
+            rng.set_position(cell_x, cell_z);
+            let x0 = jitter(&mut rng, 0.0);
+            let z0 = jitter(&mut rng, 0.0);
+            rng.set_position(cell_x + 4, cell_z);
+            let x1 = jitter(&mut rng, 4.0);
+            let z1 = jitter(&mut rng, 0.0);
+            rng.set_position(cell_x, cell_z + 4);
+            let x2 = jitter(&mut rng, 0.0);
+            let z2 = jitter(&mut rng, 4.0);
+            rng.set_position(cell_x + 4, cell_z + 4);
+            let x3 = jitter(&mut rng, 4.0);
+            let z3 = jitter(&mut rng, 4.0);
+
+            let (lx, lz) = (f64::from(x & 3), f64::from(z & 3));
+            let dist = |cx: f64, cz: f64| (lz - cz) * (lz - cz) + (lx - cx) * (lx - cx);
+            let d0 = dist(x0, z0);
+            let d1 = dist(x1, z1);
+            let d2 = dist(x2, z2);
+            let d3 = dist(x3, z3);
+
+            let (cell_x, cell_z) = (cell_x >> 2, cell_z >> 2);
+            if d0 < d1 && d0 < d2 && d0 < d3 {
+                parent.get(cell_x, cell_z)
+            } else if d1 < d0 && d1 < d2 && d1 < d3 {
+                parent.get(cell_x + 1, cell_z)
+            } else if d2 < d0 && d2 < d1 && d2 < d3 {
+                parent.get(cell_x, cell_z + 1)
+            } else {
+                parent.get(cell_x + 1, cell_z + 1)
+            }
+        })
+    }
+}
+
+// Transformations used by the overworld layer stack.
+
+pub fn add_island(rng: &mut LayerRandom, sw: i32, se: i32, ne: i32, nw: i32, center: i32) -> i32 {
+    let neighbors = [nw, ne, sw, se];
+
+    if !is_shallow_ocean(center) || neighbors.iter().all(|n| is_shallow_ocean(*n)) {
+        if !is_shallow_ocean(center)
+            && neighbors.iter().any(|n| is_shallow_ocean(*n))
+            && rng.next_int(5) == 0
+        {
+            if let Some(ocean) = neighbors.iter().find(|n| is_shallow_ocean(**n)) {
+                return if center == 4 { 4 } else { *ocean };
+            }
+        }
+        return center;
+    }
+
+    let mut chance = 1;
+    let mut result = 1;
+    for neighbor in &neighbors {
+        if !is_shallow_ocean(*neighbor) {
+            if rng.next_int(chance) == 0 {
+                result = *neighbor;
+            }
+            chance += 1;
+        }
+    }
+
+    if rng.next_int(3) == 0 {
+        result
+    } else if result == 4 {
+        4
+    } else {
+        0
+    }
+}
+
+pub fn remove_too_much_ocean(
+    rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    if [north, east, south, west, center]
+        .iter()
+        .all(|v| is_shallow_ocean(*v))
+        && rng.next_int(2) == 0
+    {
+        1
+    } else {
+        center
+    }
+}
+
+pub fn add_snow(rng: &mut LayerRandom, value: i32) -> i32 {
+    if is_shallow_ocean(value) {
+        return value;
+    }
+
+    match rng.next_int(6) {
+        0 => 4,
+        1 => 3,
+        _ => 1,
+    }
+}
+
+pub fn cool_warm_edge(
+    _rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    let neighbors = [north, east, south, west];
+    if center == 1 && neighbors.iter().any(|n| *n == 3 || *n == 4) {
+        2
+    } else {
+        center
+    }
+}
+
+pub fn heat_ice_edge(
+    _rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    let neighbors = [north, east, south, west];
+    if center == 4 && neighbors.iter().any(|n| *n == 1 || *n == 2) {
+        3
+    } else {
+        center
+    }
+}
+
+pub fn add_special(rng: &mut LayerRandom, mut value: i32) -> i32 {
+    if !is_shallow_ocean(value) && rng.next_int(13) == 0 {
+        value |= ((1 + rng.next_int(15)) << 8) & 0xF00;
+    }
+    value
+}
+
+pub fn add_mushroom_island(
+    rng: &mut LayerRandom,
+    sw: i32,
+    se: i32,
+    ne: i32,
+    nw: i32,
+    center: i32,
+) -> i32 {
+    if [center, nw, sw, ne, se]
+        .iter()
+        .all(|v| is_shallow_ocean(*v))
+        && rng.next_int(100) == 0
+    {
+        MUSHROOM_FIELDS
+    } else {
+        center
+    }
+}
+
+pub fn deep_ocean(
+    _rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    if !is_shallow_ocean(center) {
+        return center;
+    }
+
+    let oceans = [north, east, south, west]
+        .iter()
+        .filter(|n| is_shallow_ocean(**n))
+        .count();
+    if oceans > 3 {
+        match center {
+            WARM_OCEAN => DEEP_WARM_OCEAN,
+            LUKEWARM_OCEAN => DEEP_LUKEWARM_OCEAN,
+            COLD_OCEAN => DEEP_COLD_OCEAN,
+            FROZEN_OCEAN => DEEP_FROZEN_OCEAN,
+            _ => DEEP_OCEAN,
+        }
+    } else {
+        center
+    }
+}
+
+pub fn start_river(rng: &mut LayerRandom, value: i32) -> i32 {
+    if is_shallow_ocean(value) {
+        value
+    } else {
+        rng.next_int(299_999) + 2
+    }
+}
+
+const WARM_BIOMES: [i32; 6] = [DESERT, DESERT, DESERT, SAVANNA, SAVANNA, PLAINS];
+const MEDIUM_BIOMES: [i32; 6] = [FOREST, DARK_FOREST, MOUNTAINS, PLAINS, BIRCH_FOREST, SWAMP];
+const COLD_BIOMES: [i32; 4] = [FOREST, MOUNTAINS, TAIGA, PLAINS];
+const ICY_BIOMES: [i32; 4] = [SNOWY_TUNDRA, SNOWY_TUNDRA, SNOWY_TUNDRA, SNOWY_TAIGA];
+
+pub fn select_biome(rng: &mut LayerRandom, value: i32) -> i32 {
+    let special = (value & 0xF00) >> 8;
+    let value = value & !0xF00;
+
+    if is_ocean(value) || value == MUSHROOM_FIELDS {
+        return value;
+    }
+
+    let pick =
+        |rng: &mut LayerRandom, biomes: &[i32]| biomes[rng.next_int(biomes.len() as i32) as usize];
+
+    match value {
+        1 if special > 0 => {
+            if rng.next_int(3) == 0 {
+                BADLANDS_PLATEAU
+            } else {
+                WOODED_BADLANDS_PLATEAU
+            }
+        }
+        1 => pick(rng, &WARM_BIOMES),
+        2 if special > 0 => JUNGLE,
+        2 => pick(rng, &MEDIUM_BIOMES),
+        3 if special > 0 => GIANT_TREE_TAIGA,
+        3 => pick(rng, &COLD_BIOMES),
+        4 => pick(rng, &ICY_BIOMES),
+        _ => MUSHROOM_FIELDS,
+    }
+}
+
+pub fn biome_edge(
+    _rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    let neighbors = [north, east, south, west];
+
+    if are_similar(center, MOUNTAINS) {
+        return if neighbors.iter().all(|n| can_be_neighbors(*n, MOUNTAINS)) {
+            center
+        } else {
+            MOUNTAIN_EDGE
+        };
+    }
+
+    for (plateau, edge) in &[
+        (WOODED_BADLANDS_PLATEAU, BADLANDS),
+        (BADLANDS_PLATEAU, BADLANDS),
+        (GIANT_TREE_TAIGA, TAIGA),
+    ] {
+        if center == *plateau {
+            return if neighbors.iter().all(|n| are_similar(*n, *plateau)) {
+                center
+            } else {
+                *edge
+            };
+        }
+    }
+
+    if center == DESERT && neighbors.contains(&SNOWY_TUNDRA) {
+        return WOODED_MOUNTAINS;
+    }
+
+    if center == SWAMP {
+        if neighbors
+            .iter()
+            .any(|n| *n == DESERT || *n == SNOWY_TAIGA || *n == SNOWY_TUNDRA)
+        {
+            return PLAINS;
+        }
+        if neighbors.contains(&JUNGLE) {
+            return JUNGLE_EDGE;
+        }
+    }
+
+    center
+}
+
+pub fn river(
+    _rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    let filter = |v: i32| if v >= 2 { 2 + (v & 1) } else { v };
+    let value = filter(center);
+    if [north, east, south, west]
+        .iter()
+        .all(|n| filter(*n) == value)
+    {
+        -1
+    } else {
+        RIVER
+    }
+}
+
+pub fn smooth(
+    rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    let horizontal = west == east;
+    let vertical = north == south;
+
+    match (horizontal, vertical) {
+        (true, true) => {
+            if rng.next_int(2) == 0 {
+                west
+            } else {
+                north
+            }
+        }
+        (true, false) => west,
+        (false, true) => north,
+        (false, false) => center,
+    }
+}
+
+pub fn rare_biome(rng: &mut LayerRandom, value: i32) -> i32 {
+    if rng.next_int(57) == 0 && value == PLAINS {
+        SUNFLOWER_PLAINS
+    } else {
+        value
+    }
+}
+
+fn is_jungle_compatible(id: i32) -> bool {
+    Biome::from_id(id).map(Biome::category) == Some(BiomeCategory::Jungle)
+        || matches!(id, JUNGLE_EDGE | JUNGLE | JUNGLE_HILLS | FOREST | TAIGA)
+        || is_ocean(id)
+}
+
+pub fn shore(
+    _rng: &mut LayerRandom,
+    north: i32,
+    east: i32,
+    south: i32,
+    west: i32,
+    center: i32,
+) -> i32 {
+    let neighbors = [north, east, south, west];
+    let any_ocean = neighbors.iter().any(|n| is_ocean(*n));
+    let biome = Biome::from_id(center);
+
+    if center == MUSHROOM_FIELDS {
+        if neighbors.iter().any(|n| is_shallow_ocean(*n)) {
+            return MUSHROOM_FIELD_SHORE;
+        }
+    } else if biome.map(Biome::category) == Some(BiomeCategory::Jungle) {
+        if !neighbors.iter().all(|n| is_jungle_compatible(*n)) {
+            return JUNGLE_EDGE;
+        }
+        if any_ocean {
+            return BEACH;
+        }
+    } else if center == MOUNTAINS || center == WOODED_MOUNTAINS || center == MOUNTAIN_EDGE {
+        if !is_ocean(center) && any_ocean {
+            return STONE_SHORE;
+        }
+    } else if biome.map(Biome::precipitation) == Some(Precipitation::Snow) {
+        if !is_ocean(center) && any_ocean {
+            return SNOWY_BEACH;
+        }
+    } else if center == BADLANDS || center == WOODED_BADLANDS_PLATEAU {
+        if !any_ocean && !neighbors.iter().all(|n| is_mesa(*n)) {
+            return DESERT;
+        }
+    } else if !is_ocean(center) && center != RIVER && center != SWAMP && any_ocean {
+        return BEACH;
+    }
+
+    center
+}
+
+pub fn mix_river(biome: i32, river: i32) -> i32 {
+    if is_ocean(biome) || river != RIVER {
+        return biome;
+    }
+
+    match biome {
+        SNOWY_TUNDRA => FROZEN_RIVER,
+        MUSHROOM_FIELDS | MUSHROOM_FIELD_SHORE => MUSHROOM_FIELD_SHORE,
+        _ => river & 0xFF,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_or_random() {
+        let mut rng = LayerRandom::new(0, 1);
+        rng.set_position(0, 0);
+        assert_eq!(rng.mode_or_random(1, 1, 2, 3), 1);
+        assert_eq!(rng.mode_or_random(1, 2, 2, 2), 2);
+        assert_eq!(rng.mode_or_random(1, 2, 3, 3), 3);
+    }
+
+    #[test]
+    fn zoom_preserves_even_positions() {
+        let seed = 1234;
+        let island = IslandLayer::new(seed, 1);
+        let parent = island.sample(-4, -4, 8, 8);
+        let zoom = ZoomLayer::new(seed, 2000, Arc::new(IslandLayer::new(seed, 1)));
+        let zoomed = zoom.sample(-8, -8, 16, 16);
+
+        for z in 0..8 {
+            for x in 0..8 {
+                assert_eq!(
+                    zoomed[(z * 2 * 16 + x * 2) as usize],
+                    parent[(z * 8 + x) as usize]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn area_independence() {
+        // Sampling a sub-area must yield the same values as
+        // sampling a larger area containing it.
+        let seed = -42;
+        let layer = ZoomLayer::fuzzy(seed, 2000, Arc::new(IslandLayer::new(seed, 1)));
+        let large = layer.sample(-10, -10, 20, 20);
+        let small = layer.sample(-3, 1, 5, 4);
+
+        for z in 0..4 {
+            for x in 0..5 {
+                assert_eq!(
+                    small[(z * 5 + x) as usize],
+                    large[((z + 11) * 20 + x + 7) as usize]
+                );
+            }
+        }
+    }
+}
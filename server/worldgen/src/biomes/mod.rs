@@ -1,7 +1,12 @@
 //! Biome grid creation.
 
 mod distorted_voronoi;
+mod layer;
+mod source;
 mod two_level;
 
 pub use distorted_voronoi::DistortedVoronoiBiomeGenerator;
+pub use source::{
+    BiomeSource, BiomeSourceGenerator, VanillaBiomeSource, DEFAULT_BIOME_SIZE, LARGE_BIOME_SIZE,
+};
 pub use two_level::TwoLevelBiomeGenerator;
@@ -0,0 +1,196 @@
+//! Biome sources: seed-deterministic biome layouts
+//! which can be queried at any position.
+
+use super::layer::*;
+use crate::{BiomeGenerator, ChunkBiomes};
+use feather_core::biomes::Biome;
+use feather_core::util::ChunkPosition;
+use std::sync::Arc;
+
+/// A source of biomes for a world.
+///
+/// Unlike `BiomeGenerator`s, a biome source may be queried
+/// for any area, not just whole chunks.
+pub trait BiomeSource: Send + Sync {
+    /// Returns the biomes for a `width` by `height` area of block
+    /// columns starting at `(x, z)`, indexed by
+    /// `(z - start_z) * width + (x - start_x)`.
+    fn biomes(&self, x: i32, z: i32, width: u32, height: u32) -> Vec<Biome>;
+
+    /// Returns the biome of the block column at `(x, z)`.
+    fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.biomes(x, z, 1, 1)[0]
+    }
+
+    /// Returns the biomes of the given chunk.
+    fn chunk_biomes(&self, chunk: ChunkPosition) -> ChunkBiomes {
+        let biomes = self.biomes(chunk.x * 16, chunk.z * 16, 16, 16);
+
+        let mut result = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+        for z in 0..16 {
+            for x in 0..16 {
+                result.set_biome_at(x, z, biomes[(z << 4) | x]);
+            }
+        }
+        result
+    }
+}
+
+/// Default size of biomes; each increment doubles their width.
+pub const DEFAULT_BIOME_SIZE: u32 = 4;
+/// Biome size used by the "large biomes" world type.
+pub const LARGE_BIOME_SIZE: u32 = 6;
+/// Size of rivers.
+const RIVER_SIZE: u32 = 4;
+
+/// Biome source producing the same biome layout
+/// as vanilla for a given seed.
+pub struct VanillaBiomeSource {
+    /// Biomes at 1:4 scale, as used by terrain generation.
+    noise: LayerRef,
+    /// Biomes at block scale.
+    block: LayerRef,
+}
+
+impl VanillaBiomeSource {
+    /// Creates a biome source with the default biome size.
+    pub fn new(seed: i64) -> Self {
+        Self::with_biome_size(seed, DEFAULT_BIOME_SIZE)
+    }
+
+    /// Creates a biome source with the given biome size.
+    pub fn with_biome_size(seed: i64, biome_size: u32) -> Self {
+        let noise = overworld_layers(seed, biome_size);
+        let block = Arc::new(VoronoiZoomLayer::new(seed, 10, Arc::clone(&noise)));
+
+        Self { noise, block }
+    }
+
+    /// Returns the biomes at 1:4 scale for the given area.
+    /// Each value covers a 4x4 area of block columns.
+    pub fn noise_biomes(&self, x: i32, z: i32, width: u32, height: u32) -> Vec<Biome> {
+        to_biomes(self.noise.sample(x, z, width as i32, height as i32))
+    }
+}
+
+impl BiomeSource for VanillaBiomeSource {
+    fn biomes(&self, x: i32, z: i32, width: u32, height: u32) -> Vec<Biome> {
+        to_biomes(self.block.sample(x, z, width as i32, height as i32))
+    }
+}
+
+fn to_biomes(ids: Vec<i32>) -> Vec<Biome> {
+    ids.into_iter()
+        .map(|id| Biome::from_id(id).unwrap_or(Biome::Ocean))
+        .collect()
+}
+
+/// Builds the overworld layer stack, which outputs biomes at 1:4 scale.
+fn overworld_layers(seed: i64, biome_size: u32) -> LayerRef {
+    // Continents and climate zones
+    let mut continents: LayerRef = Arc::new(IslandLayer::new(seed, 1));
+    continents = Arc::new(ZoomLayer::fuzzy(seed, 2000, continents));
+    continents = Arc::new(DiagonalLayer::new(seed, 1, continents, add_island));
+    continents = Arc::new(ZoomLayer::new(seed, 2001, continents));
+    for salt in &[2, 50, 70] {
+        continents = Arc::new(DiagonalLayer::new(seed, *salt, continents, add_island));
+    }
+    continents = Arc::new(CrossLayer::new(seed, 2, continents, remove_too_much_ocean));
+    continents = Arc::new(PointLayer::new(seed, 2, continents, add_snow));
+    continents = Arc::new(DiagonalLayer::new(seed, 3, continents, add_island));
+    continents = Arc::new(CrossLayer::new(seed, 2, continents, cool_warm_edge));
+    continents = Arc::new(CrossLayer::new(seed, 2, continents, heat_ice_edge));
+    continents = Arc::new(PointLayer::new(seed, 3, continents, add_special));
+    continents = Arc::new(ZoomLayer::new(seed, 2002, continents));
+    continents = Arc::new(ZoomLayer::new(seed, 2003, continents));
+    continents = Arc::new(DiagonalLayer::new(seed, 4, continents, add_island));
+    continents = Arc::new(DiagonalLayer::new(seed, 5, continents, add_mushroom_island));
+    continents = Arc::new(CrossLayer::new(seed, 4, continents, deep_ocean));
+
+    let ocean_temperatures =
+        ZoomLayer::repeat(seed, 2001, Arc::new(OceanTemperatureLayer::new(seed)), 6);
+
+    // Noise used for both rivers and hills
+    let river_noise: LayerRef = Arc::new(PointLayer::new(
+        seed,
+        100,
+        Arc::clone(&continents),
+        start_river,
+    ));
+
+    // Biomes
+    let mut biomes: LayerRef = Arc::new(PointLayer::new(seed, 200, continents, select_biome));
+    biomes = ZoomLayer::repeat(seed, 1000, biomes, 2);
+    biomes = Arc::new(CrossLayer::new(seed, 1000, biomes, biome_edge));
+    let hills_noise = ZoomLayer::repeat(seed, 1000, Arc::clone(&river_noise), 2);
+    biomes = Arc::new(HillsLayer::new(seed, 1000, biomes, hills_noise));
+    biomes = Arc::new(PointLayer::new(seed, 1001, biomes, rare_biome));
+
+    for i in 0..biome_size {
+        biomes = Arc::new(ZoomLayer::new(seed, 1000 + i64::from(i), biomes));
+        if i == 0 {
+            biomes = Arc::new(DiagonalLayer::new(seed, 3, biomes, add_island));
+        }
+        if i == 1 || biome_size == 1 {
+            biomes = Arc::new(CrossLayer::new(seed, 1000, biomes, shore));
+        }
+    }
+    biomes = Arc::new(CrossLayer::new(seed, 1000, biomes, smooth));
+
+    // Rivers
+    let mut rivers = ZoomLayer::repeat(seed, 1000, river_noise, 2);
+    rivers = ZoomLayer::repeat(seed, 1000, rivers, RIVER_SIZE);
+    rivers = Arc::new(CrossLayer::new(seed, 1, rivers, river));
+    rivers = Arc::new(CrossLayer::new(seed, 1000, rivers, smooth));
+
+    let mixed = Arc::new(MergeLayer::new(biomes, rivers, mix_river));
+    Arc::new(MixOceansLayer::new(mixed, ocean_temperatures))
+}
+
+/// Adapts a `BiomeSource` to the `ComposableGenerator` pipeline.
+///
+/// The source is seeded with the world seed on creation, so the
+/// seed passed by the pipeline is ignored.
+pub struct BiomeSourceGenerator<S> {
+    source: S,
+}
+
+impl<S: BiomeSource> BiomeSourceGenerator<S> {
+    pub fn new(source: S) -> Self {
+        Self { source }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S: BiomeSource> BiomeGenerator for BiomeSourceGenerator<S> {
+    fn generate_for_chunk(&self, chunk: ChunkPosition, _seed: u64) -> ChunkBiomes {
+        self.source.chunk_biomes(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let a = VanillaBiomeSource::new(1234);
+        let b = VanillaBiomeSource::new(1234);
+
+        let area_a = a.biomes(-100, 50, 32, 32);
+        assert_eq!(area_a, b.biomes(-100, 50, 32, 32));
+        assert_eq!(a.biome_at(-90, 60), area_a[10 * 32 + 10]);
+    }
+
+    #[test]
+    fn produces_land_and_ocean() {
+        let source = VanillaBiomeSource::new(0);
+        let biomes = source.noise_biomes(-128, -128, 256, 256);
+
+        assert!(biomes.iter().any(|biome| biome.is_ocean()));
+        assert!(biomes.iter().any(|biome| !biome.is_ocean()));
+    }
+}
@@ -28,15 +28,33 @@ static BIOME_GROUPS: Lazy<Vec<Vec<Biome>>> = Lazy::new(|| {
 /// and the second determines which biome inside that group
 /// to use. This technique allows similar biomes to be grouped
 /// together and prevents unrelated biomes from being neighbors.
-#[derive(Default)]
-pub struct TwoLevelBiomeGenerator;
+pub struct TwoLevelBiomeGenerator {
+    /// Multiplier for the width of biomes.
+    scale: u32,
+}
+
+impl Default for TwoLevelBiomeGenerator {
+    fn default() -> Self {
+        Self { scale: 1 }
+    }
+}
+
+impl TwoLevelBiomeGenerator {
+    /// Creates a generator whose biomes are `scale` times as wide
+    /// as the default. Large biomes worlds use a scale of 4.
+    pub fn with_scale(scale: u32) -> Self {
+        Self {
+            scale: scale.max(1),
+        }
+    }
+}
 
 impl BiomeGenerator for TwoLevelBiomeGenerator {
     fn generate_for_chunk(&self, chunk: ChunkPosition, seed: u64) -> ChunkBiomes {
         // Voronoi used to determine biome group
-        let mut group_voronoi = VoronoiGrid::new(1024, seed);
+        let mut group_voronoi = VoronoiGrid::new(1024 * self.scale, seed);
         // Voronoi used to determine biome within group
-        let mut local_voronoi = VoronoiGrid::new(256, seed + 1);
+        let mut local_voronoi = VoronoiGrid::new(256 * self.scale, seed + 1);
 
         let mut biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]); // Will be overridden
 
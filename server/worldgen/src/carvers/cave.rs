@@ -0,0 +1,178 @@
+use super::{for_each_source_chunk, CarveRegion, RANGE};
+use crate::random::JavaRandom;
+use crate::util::{cos, sin};
+use crate::{CarvingGenerator, ChunkBiomes};
+use feather_core::chunk::Chunk;
+use feather_core::util::ChunkPosition;
+use std::f32::consts::PI;
+
+/// Carver generating branching cave systems.
+#[derive(Default)]
+pub struct CaveCarver;
+
+impl CarvingGenerator for CaveCarver {
+    fn carve_chunk(&self, chunk: &mut Chunk, biomes: &ChunkBiomes, seed: u64) {
+        let target = chunk.position();
+        for_each_source_chunk(seed as i64, target, |rng, source_x, source_z| {
+            let mut ctx = Context {
+                chunk: &mut *chunk,
+                biomes,
+            };
+            ctx.start_caves(rng, source_x, source_z);
+        });
+    }
+}
+
+struct Context<'a> {
+    chunk: &'a mut Chunk,
+    biomes: &'a ChunkBiomes,
+}
+
+impl<'a> Context<'a> {
+    fn start_caves(&mut self, rng: &mut JavaRandom, source_x: i32, source_z: i32) {
+        let count = {
+            let bound = rng.next_int_bounded(15) + 1;
+            let bound = rng.next_int_bounded(bound) + 1;
+            rng.next_int_bounded(bound)
+        };
+        let count = if rng.next_int_bounded(7) != 0 {
+            0
+        } else {
+            count
+        };
+
+        for _ in 0..count {
+            let x = f64::from(source_x * 16 + rng.next_int_bounded(16));
+            let y = {
+                let bound = rng.next_int_bounded(120) + 8;
+                f64::from(rng.next_int_bounded(bound))
+            };
+            let z = f64::from(source_z * 16 + rng.next_int_bounded(16));
+
+            let mut tunnels = 1;
+            if rng.next_int_bounded(4) == 0 {
+                // Room
+                let room_seed = rng.next_long();
+                let width = 1.0 + rng.next_float() * 6.0;
+                self.tunnel(room_seed, x, y, z, width, 0.0, 0.0, -1, -1, 0.5);
+                tunnels += rng.next_int_bounded(4);
+            }
+
+            for _ in 0..tunnels {
+                let yaw = rng.next_float() * PI * 2.0;
+                let pitch = (rng.next_float() - 0.5) * 2.0 / 8.0;
+                let mut width = rng.next_float() * 2.0 + rng.next_float();
+                if rng.next_int_bounded(10) == 0 {
+                    width *= rng.next_float() * rng.next_float() * 3.0 + 1.0;
+                }
+
+                let tunnel_seed = rng.next_long();
+                self.tunnel(tunnel_seed, x, y, z, width, yaw, pitch, 0, 0, 1.0);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn tunnel(
+        &mut self,
+        seed: i64,
+        mut x: f64,
+        mut y: f64,
+        mut z: f64,
+        width: f32,
+        mut yaw: f32,
+        mut pitch: f32,
+        mut step: i32,
+        mut length: i32,
+        height_multiplier: f64,
+    ) {
+        let target = self.chunk.position();
+        let center_x = f64::from(target.x * 16 + 8);
+        let center_z = f64::from(target.z * 16 + 8);
+
+        let mut yaw_change = 0.0f32;
+        let mut pitch_change = 0.0f32;
+        let mut rng = JavaRandom::new(seed);
+
+        if length <= 0 {
+            let max_length = RANGE * 16 - 16;
+            length = max_length - rng.next_int_bounded(max_length / 4);
+        }
+
+        let mut is_room = false;
+        if step == -1 {
+            step = length / 2;
+            is_room = true;
+        }
+
+        let branch_step = rng.next_int_bounded(length / 2) + length / 4;
+        let steep = rng.next_int_bounded(6) == 0;
+
+        while step < length {
+            let horizontal_radius = 1.5 + f64::from(sin(step as f32 * PI / length as f32) * width);
+            let vertical_radius = horizontal_radius * height_multiplier;
+
+            let (pitch_cos, pitch_sin) = (cos(pitch), sin(pitch));
+            x += f64::from(cos(yaw) * pitch_cos);
+            y += f64::from(pitch_sin);
+            z += f64::from(sin(yaw) * pitch_cos);
+
+            pitch *= if steep { 0.92 } else { 0.7 };
+            pitch += pitch_change * 0.1;
+            yaw += yaw_change * 0.1;
+            pitch_change *= 0.9;
+            yaw_change *= 0.75;
+            pitch_change += (rng.next_float() - rng.next_float()) * rng.next_float() * 2.0;
+            yaw_change += (rng.next_float() - rng.next_float()) * rng.next_float() * 4.0;
+
+            if !is_room && step == branch_step && width > 1.0 && length > 0 {
+                for &direction in &[-1.0, 1.0] {
+                    let branch_seed = rng.next_long();
+                    let branch_width = rng.next_float() * 0.5 + 0.5;
+                    self.tunnel(
+                        branch_seed,
+                        x,
+                        y,
+                        z,
+                        branch_width,
+                        yaw + direction * PI / 2.0,
+                        pitch / 3.0,
+                        step,
+                        length,
+                        1.0,
+                    );
+                }
+                return;
+            }
+
+            if is_room || rng.next_int_bounded(4) != 0 {
+                let dx = x - center_x;
+                let dz = z - center_z;
+                let remaining = f64::from(length - step);
+                let max_distance = f64::from(width + 2.0 + 16.0);
+                if dx * dx + dz * dz - remaining * remaining > max_distance * max_distance {
+                    // The tunnel can no longer reach this chunk.
+                    return;
+                }
+
+                let region = CarveRegion {
+                    x,
+                    y,
+                    z,
+                    horizontal_radius,
+                    vertical_radius,
+                };
+                if region.is_near(target) {
+                    let carved = region.carve(self.chunk, self.biomes, |dx, dy, dz, _| {
+                        dy > -0.7 && dx * dx + dy * dy + dz * dz < 1.0
+                    });
+                    if carved && is_room {
+                        break;
+                    }
+                }
+            }
+
+            step += 1;
+        }
+    }
+}
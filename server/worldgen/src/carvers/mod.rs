@@ -0,0 +1,261 @@
+//! Carvers, which cut caves and ravines out of the terrain
+//! after composition and before finishing generators run.
+//!
+//! Carvers follow the vanilla algorithms: every chunk within
+//! `RANGE` chunks of the chunk being generated may start a
+//! tunnel system, and the parts of those tunnels which
+//! intersect the current chunk are carved out.
+
+mod cave;
+mod ravine;
+
+pub use cave::CaveCarver;
+pub use ravine::RavineCarver;
+
+use crate::composition::top_soil_block;
+use crate::random::{large_feature_seed, JavaRandom};
+use crate::ChunkBiomes;
+use feather_core::blocks::{BlockId, SimplifiedBlockKind};
+use feather_core::chunk::Chunk;
+use feather_core::util::ChunkPosition;
+
+/// Radius, in chunks, around a chunk in which
+/// tunnels affecting it may start.
+const RANGE: i32 = 8;
+
+/// Blocks at or below this height are filled with lava instead of air.
+const LAVA_LEVEL: usize = 10;
+
+/// Calls `f` for each chunk within `RANGE` chunks of `chunk`,
+/// passing a random number generator seeded for that chunk.
+fn for_each_source_chunk(
+    seed: i64,
+    chunk: ChunkPosition,
+    mut f: impl FnMut(&mut JavaRandom, i32, i32),
+) {
+    for source_x in chunk.x - RANGE..=chunk.x + RANGE {
+        for source_z in chunk.z - RANGE..=chunk.z + RANGE {
+            let source = ChunkPosition::new(source_x, source_z);
+            let mut rng = JavaRandom::new(large_feature_seed(seed, source));
+            f(&mut rng, source_x, source_z);
+        }
+    }
+}
+
+/// An ellipsoid-ish region to be carved out of a chunk.
+struct CarveRegion {
+    x: f64,
+    y: f64,
+    z: f64,
+    horizontal_radius: f64,
+    vertical_radius: f64,
+}
+
+impl CarveRegion {
+    /// Returns whether this region is close enough
+    /// to the given chunk to affect it.
+    fn is_near(&self, chunk: ChunkPosition) -> bool {
+        let center_x = f64::from(chunk.x * 16 + 8);
+        let center_z = f64::from(chunk.z * 16 + 8);
+        let extent = 16.0 + self.horizontal_radius * 2.0;
+
+        self.x >= center_x - extent
+            && self.z >= center_z - extent
+            && self.x <= center_x + extent
+            && self.z <= center_z + extent
+    }
+
+    /// Carves this region out of the chunk. `is_inside` is called
+    /// with the normalized offset of each block from the center
+    /// of the region, plus the block's Y coordinate.
+    ///
+    /// Returns `false` if nothing was carved because the region
+    /// would have breached water.
+    fn carve(
+        &self,
+        chunk: &mut Chunk,
+        biomes: &ChunkBiomes,
+        is_inside: impl Fn(f64, f64, f64, usize) -> bool,
+    ) -> bool {
+        let pos = chunk.position();
+        let (base_x, base_z) = (pos.x * 16, pos.z * 16);
+
+        let min_x = ((self.x - self.horizontal_radius).floor() as i32 - base_x - 1).max(0);
+        let max_x = ((self.x + self.horizontal_radius).floor() as i32 - base_x + 1).min(16);
+        let min_y = ((self.y - self.vertical_radius).floor() as i32 - 1).max(1);
+        let max_y = ((self.y + self.vertical_radius).floor() as i32 + 1).min(248);
+        let min_z = ((self.z - self.horizontal_radius).floor() as i32 - base_z - 1).max(0);
+        let max_z = ((self.z + self.horizontal_radius).floor() as i32 - base_z + 1).min(16);
+
+        if self.contains_water(chunk, (min_x, max_x), (min_y, max_y), (min_z, max_z)) {
+            return false;
+        }
+
+        for x in min_x..max_x {
+            let dx = (f64::from(x + base_x) + 0.5 - self.x) / self.horizontal_radius;
+            for z in min_z..max_z {
+                let dz = (f64::from(z + base_z) + 0.5 - self.z) / self.horizontal_radius;
+                if dx * dx + dz * dz >= 1.0 {
+                    continue;
+                }
+
+                let (x, z) = (x as usize, z as usize);
+                let mut found_top = false;
+                for y in ((min_y + 1)..=max_y).rev() {
+                    let dy = (f64::from(y - 1) + 0.5 - self.y) / self.vertical_radius;
+                    let y = y as usize;
+                    if !is_inside(dx, dy, dz, y) {
+                        continue;
+                    }
+
+                    let block = chunk.block_at(x, y, z);
+                    if matches!(
+                        block.simplified_kind(),
+                        SimplifiedBlockKind::GrassBlock | SimplifiedBlockKind::Mycelium
+                    ) {
+                        found_top = true;
+                    }
+
+                    let above = if y < 255 {
+                        chunk.block_at(x, y + 1, z)
+                    } else {
+                        BlockId::air()
+                    };
+                    if !can_replace(block, above) {
+                        continue;
+                    }
+
+                    if y - 1 < LAVA_LEVEL {
+                        chunk.set_block_at(x, y, z, BlockId::lava());
+                    } else {
+                        chunk.set_block_at(x, y, z, BlockId::cave_air());
+
+                        // Grass exposed by the carver would otherwise leave
+                        // dirt at the top of the column; restore the top soil.
+                        if found_top
+                            && chunk.block_at(x, y - 1, z).simplified_kind()
+                                == SimplifiedBlockKind::Dirt
+                        {
+                            let top = top_soil_block(biomes.biome_at(x, z));
+                            chunk.set_block_at(x, y - 1, z, top);
+                        }
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    fn contains_water(
+        &self,
+        chunk: &Chunk,
+        (min_x, max_x): (i32, i32),
+        (min_y, max_y): (i32, i32),
+        (min_z, max_z): (i32, i32),
+    ) -> bool {
+        for x in min_x..max_x {
+            for z in min_z..max_z {
+                let mut y = max_y + 1;
+                while y >= min_y - 1 {
+                    if y >= 0 && y < 256 {
+                        let block = chunk.block_at(x as usize, y as usize, z as usize);
+                        if block.simplified_kind() == SimplifiedBlockKind::Water {
+                            return true;
+                        }
+
+                        // Only the shell of the region needs to be checked.
+                        let on_edge = y == min_y - 1
+                            || x == min_x
+                            || x == max_x - 1
+                            || z == min_z
+                            || z == max_z - 1;
+                        if !on_edge {
+                            y = min_y;
+                        }
+                    }
+                    y -= 1;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Returns whether a carver may replace `block`, given the block above it.
+fn can_replace(block: BlockId, above: BlockId) -> bool {
+    match block.simplified_kind() {
+        SimplifiedBlockKind::Stone
+        | SimplifiedBlockKind::Granite
+        | SimplifiedBlockKind::Diorite
+        | SimplifiedBlockKind::Andesite
+        | SimplifiedBlockKind::Dirt
+        | SimplifiedBlockKind::CoarseDirt
+        | SimplifiedBlockKind::Podzol
+        | SimplifiedBlockKind::GrassBlock
+        | SimplifiedBlockKind::Terracotta
+        | SimplifiedBlockKind::Sandstone
+        | SimplifiedBlockKind::RedSandstone
+        | SimplifiedBlockKind::Mycelium
+        | SimplifiedBlockKind::Snow => true,
+        SimplifiedBlockKind::Sand | SimplifiedBlockKind::RedSand | SimplifiedBlockKind::Gravel => {
+            above.simplified_kind() != SimplifiedBlockKind::Water
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CarvingGenerator;
+    use feather_core::biomes::Biome;
+
+    fn stone_chunk(pos: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..64 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn source_chunks() {
+        let mut count = 0;
+        for_each_source_chunk(0, ChunkPosition::new(0, 0), |_, x, z| {
+            assert!(x.abs() <= RANGE && z.abs() <= RANGE);
+            count += 1;
+        });
+        assert_eq!(count, (RANGE * 2 + 1) * (RANGE * 2 + 1));
+    }
+
+    #[test]
+    fn carving_is_deterministic() {
+        let biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+        let carver = CaveCarver::default();
+
+        let mut carved_any = false;
+        for chunk_x in 0..8 {
+            let pos = ChunkPosition::new(chunk_x, 0);
+            let mut a = stone_chunk(pos);
+            let mut b = stone_chunk(pos);
+            carver.carve_chunk(&mut a, &biomes, 42);
+            carver.carve_chunk(&mut b, &biomes, 42);
+
+            for x in 0..16 {
+                for z in 0..16 {
+                    for y in 0..64 {
+                        assert_eq!(a.block_at(x, y, z), b.block_at(x, y, z));
+                        carved_any |= a.block_at(x, y, z) != BlockId::stone();
+                    }
+                }
+            }
+        }
+        assert!(carved_any);
+    }
+}
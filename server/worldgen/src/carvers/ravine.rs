@@ -0,0 +1,124 @@
+use super::{for_each_source_chunk, CarveRegion, RANGE};
+use crate::random::JavaRandom;
+use crate::util::{cos, sin};
+use crate::{CarvingGenerator, ChunkBiomes};
+use feather_core::chunk::Chunk;
+use feather_core::util::ChunkPosition;
+use std::f32::consts::PI;
+
+/// Carver generating ravines: long, narrow and deep canyons.
+#[derive(Default)]
+pub struct RavineCarver;
+
+impl CarvingGenerator for RavineCarver {
+    fn carve_chunk(&self, chunk: &mut Chunk, biomes: &ChunkBiomes, seed: u64) {
+        let target = chunk.position();
+        for_each_source_chunk(seed as i64, target, |rng, source_x, source_z| {
+            if rng.next_int_bounded(50) != 0 {
+                return;
+            }
+
+            let x = f64::from(source_x * 16 + rng.next_int_bounded(16));
+            let y = {
+                let bound = rng.next_int_bounded(40) + 8;
+                f64::from(rng.next_int_bounded(bound) + 20)
+            };
+            let z = f64::from(source_z * 16 + rng.next_int_bounded(16));
+
+            let yaw = rng.next_float() * PI * 2.0;
+            let pitch = (rng.next_float() - 0.5) * 2.0 / 8.0;
+            let width = (rng.next_float() * 2.0 + rng.next_float()) * 2.0;
+
+            let ravine_seed = rng.next_long();
+            carve_ravine(
+                chunk,
+                biomes,
+                ravine_seed,
+                (x, y, z),
+                width,
+                yaw,
+                pitch,
+                3.0,
+            );
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn carve_ravine(
+    chunk: &mut Chunk,
+    biomes: &ChunkBiomes,
+    seed: i64,
+    (mut x, mut y, mut z): (f64, f64, f64),
+    width: f32,
+    mut yaw: f32,
+    mut pitch: f32,
+    height_multiplier: f64,
+) {
+    let target: ChunkPosition = chunk.position();
+    let center_x = f64::from(target.x * 16 + 8);
+    let center_z = f64::from(target.z * 16 + 8);
+
+    let mut yaw_change = 0.0f32;
+    let mut pitch_change = 0.0f32;
+    let mut rng = JavaRandom::new(seed);
+
+    let max_length = RANGE * 16 - 16;
+    let length = max_length - rng.next_int_bounded(max_length / 4);
+
+    // Per-layer horizontal scale factors give
+    // ravine walls their uneven look.
+    let mut wall_factors = [0.0f32; 256];
+    let mut factor = 1.0f32;
+    for (y, wall_factor) in wall_factors.iter_mut().enumerate() {
+        if y == 0 || rng.next_int_bounded(3) == 0 {
+            factor = 1.0 + rng.next_float() * rng.next_float();
+        }
+        *wall_factor = factor * factor;
+    }
+
+    for step in 0..length {
+        let mut horizontal_radius = 1.5 + f64::from(sin(step as f32 * PI / length as f32) * width);
+        let mut vertical_radius = horizontal_radius * height_multiplier;
+        horizontal_radius *= f64::from(rng.next_float()) * 0.25 + 0.75;
+        vertical_radius *= f64::from(rng.next_float()) * 0.25 + 0.75;
+
+        let (pitch_cos, pitch_sin) = (cos(pitch), sin(pitch));
+        x += f64::from(cos(yaw) * pitch_cos);
+        y += f64::from(pitch_sin);
+        z += f64::from(sin(yaw) * pitch_cos);
+
+        pitch *= 0.7;
+        pitch += pitch_change * 0.05;
+        yaw += yaw_change * 0.05;
+        pitch_change *= 0.8;
+        yaw_change *= 0.5;
+        pitch_change += (rng.next_float() - rng.next_float()) * rng.next_float() * 2.0;
+        yaw_change += (rng.next_float() - rng.next_float()) * rng.next_float() * 4.0;
+
+        if rng.next_int_bounded(4) == 0 {
+            continue;
+        }
+
+        let dx = x - center_x;
+        let dz = z - center_z;
+        let remaining = f64::from(length - step);
+        let max_distance = f64::from(width + 2.0 + 16.0);
+        if dx * dx + dz * dz - remaining * remaining > max_distance * max_distance {
+            return;
+        }
+
+        let region = CarveRegion {
+            x,
+            y,
+            z,
+            horizontal_radius,
+            vertical_radius,
+        };
+        if region.is_near(target) {
+            region.carve(chunk, biomes, |dx, dy, dz, y| {
+                (dx * dx + dz * dz) * f64::from(wall_factors[y - 1]) + dy * dy / 6.0 < 1.0
+            });
+        }
+    }
+}
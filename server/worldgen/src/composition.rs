@@ -114,7 +114,7 @@ fn basic_composition_for_solid_biome(
 }
 
 /// Returns the top soil block for the given biome.
-fn top_soil_block(biome: Biome) -> BlockId {
+pub(crate) fn top_soil_block(biome: Biome) -> BlockId {
     match biome {
         Biome::SnowyTundra
         | Biome::IceSpikes
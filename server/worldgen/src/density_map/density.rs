@@ -3,7 +3,9 @@
 //! Over the 2D height map generator, this has the advantage that terrain
 //! is more interesting; overhangs and the like will be able to generate.
 
-use crate::{block_index, noise, DensityMapGenerator, NearbyBiomes, NoiseLerper};
+use crate::{
+    block_index, noise, DensityMapGenerator, NearbyBiomes, NoiseLerper, NoiseSettings, SEA_LEVEL,
+};
 use bitvec::order::Local;
 use bitvec::vec::BitVec;
 use feather_core::biomes::Biome;
@@ -24,7 +26,16 @@ use simdnoise::NoiseBuilder;
 /// * Depending on the density value from the noise, decide
 /// whether the position is solid or air.
 #[derive(Debug, Default)]
-pub struct DensityMapGeneratorImpl;
+pub struct DensityMapGeneratorImpl {
+    settings: NoiseSettings,
+}
+
+impl DensityMapGeneratorImpl {
+    /// Creates a generator with the given noise parameters.
+    pub fn new(settings: NoiseSettings) -> Self {
+        Self { settings }
+    }
+}
 
 impl DensityMapGenerator for DensityMapGeneratorImpl {
     fn generate_for_chunk(
@@ -35,7 +46,7 @@ impl DensityMapGenerator for DensityMapGeneratorImpl {
     ) -> BitVec<Local, u8> {
         let mut density = BitVec::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
 
-        let uninterpolated_densities = generate_density(chunk, &biomes, seed);
+        let uninterpolated_densities = generate_density(chunk, &biomes, seed, &self.settings);
         let noise = NoiseLerper::new(&uninterpolated_densities)
             .with_offset(chunk.x, chunk.z)
             .generate();
@@ -71,7 +82,12 @@ const DENSITY_HEIGHT: usize = 33;
 /// The density values emitted from this function should
 /// be considered solid if less than 0 and air if greater
 /// than 0. This is contrary to what might seem logical.
-fn generate_density(chunk: ChunkPosition, biomes: &NearbyBiomes, seed: u64) -> Vec<f32> {
+fn generate_density(
+    chunk: ChunkPosition,
+    biomes: &NearbyBiomes,
+    seed: u64,
+    settings: &NoiseSettings,
+) -> Vec<f32> {
     // TODO: generate based on biome
 
     let x_offset = (chunk.x * (DENSITY_WIDTH as i32 - 1)) as f32;
@@ -93,14 +109,14 @@ fn generate_density(chunk: ChunkPosition, biomes: &NearbyBiomes, seed: u64) -> V
         NoiseBuilder::fbm_3d_offset(x_offset, len, y_offset, height, z_offset, len)
             .with_seed(noise_seed + 1)
             .with_octaves(2)
-            .with_freq(0.2)
+            .with_freq(settings.density_frequency)
             .generate()
             .0;
     let density_noise_2 =
         NoiseBuilder::fbm_3d_offset(x_offset, len, y_offset, height, z_offset, len)
             .with_seed(noise_seed + 2)
             .with_octaves(2)
-            .with_freq(0.2)
+            .with_freq(settings.density_frequency)
             .generate()
             .0;
     // Additional 2D height noise for extra detail.
@@ -117,9 +133,9 @@ fn generate_density(chunk: ChunkPosition, biomes: &NearbyBiomes, seed: u64) -> V
     for subx in 0..DENSITY_WIDTH {
         for subz in 0..DENSITY_WIDTH {
             // TODO: average nearby biome parameters
-            let (amplitude, midpoint) = column_parameters(&biomes, subx, subz);
+            let (amplitude, midpoint) = column_parameters(&biomes, subx, subz, settings);
 
-            let height = height_noise[(subz * len) + subx] * 25.0;
+            let height = height_noise[(subz * len) + subx] * settings.height_scale;
 
             // Loop through Y axis of this subchunk column.
             for suby in 0..DENSITY_HEIGHT {
@@ -143,8 +159,8 @@ fn generate_density(chunk: ChunkPosition, biomes: &NearbyBiomes, seed: u64) -> V
                 let index = DENSITY_WIDTH * suby + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz;
 
                 let choice = choice_noise[index] * 100.0;
-                let density_1 = density_noise_1[index] * 50.0;
-                let density_2 = density_noise_2[index] * 50.0;
+                let density_1 = density_noise_1[index] * settings.density_scale;
+                let density_2 = density_noise_2[index] * settings.density_scale;
 
                 // Average between two density values based on choice weight.
                 result[index] = lerp(density_1, density_2, choice) + height_offset + height;
@@ -177,7 +193,12 @@ static ELEVATION_WEIGHT: Lazy<[[f32; 19]; 19]> = Lazy::new(|| {
 ///
 /// The X and Z parameters are the coordinates of the subchunk
 /// within the chunk, not the block coordinate.
-fn column_parameters(biomes: &NearbyBiomes, x: usize, z: usize) -> (f32, f32) {
+fn column_parameters(
+    biomes: &NearbyBiomes,
+    x: usize,
+    z: usize,
+    settings: &NoiseSettings,
+) -> (f32, f32) {
     let x = x as i32 * (DENSITY_WIDTH as i32 - 1);
     let z = z as i32 * (DENSITY_WIDTH as i32 - 1);
 
@@ -193,7 +214,7 @@ fn column_parameters(biomes: &NearbyBiomes, x: usize, z: usize) -> (f32, f32) {
             let abs_z = z + block_z;
 
             let biome = biomes.biome_at(abs_x, abs_z);
-            let (amplitude, midpoint) = biome_parameters(biome);
+            let (amplitude, midpoint) = scaled_parameters(biome_parameters(biome), settings);
 
             let weight = ELEVATION_WEIGHT[(block_x + 9) as usize][(block_z + 9) as usize];
 
@@ -209,6 +230,21 @@ fn column_parameters(biomes: &NearbyBiomes, x: usize, z: usize) -> (f32, f32) {
     (sum_amplitudes, sum_midpoints)
 }
 
+/// Applies the elevation and variation scales to the
+/// parameters of land biomes, as vanilla's amplified world
+/// type does. Oceans are left untouched.
+fn scaled_parameters((amplitude, midpoint): (f32, f32), settings: &NoiseSettings) -> (f32, f32) {
+    let shore_level = SEA_LEVEL as f32 - 4.0;
+    if midpoint < shore_level {
+        return (amplitude, midpoint);
+    }
+
+    (
+        amplitude / settings.variation_scale,
+        shore_level + (midpoint - shore_level) * settings.elevation_scale,
+    )
+}
+
 /// Returns the amplitude and midpoint for a given biome
 /// type as a tuple in that order.
 ///
@@ -260,3 +296,25 @@ fn lerp(a: f32, b: f32, weight: f32) -> f32 {
 
     a + (b - a) * weight
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amplified_raises_land_only() {
+        let default = NoiseSettings::default();
+        let amplified = NoiseSettings::amplified();
+
+        let plains = biome_parameters(Biome::Plains);
+        assert_eq!(scaled_parameters(plains, &default), plains);
+
+        let (amplitude, midpoint) =
+            scaled_parameters(biome_parameters(Biome::Mountains), &amplified);
+        assert!(amplitude < biome_parameters(Biome::Mountains).0);
+        assert!(midpoint > biome_parameters(Biome::Mountains).1);
+
+        let ocean = biome_parameters(Biome::DeepOcean);
+        assert_eq!(scaled_parameters(ocean, &amplified), ocean);
+    }
+}
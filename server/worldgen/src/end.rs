@@ -0,0 +1,190 @@
+//! Generator for the End: the main island
+//! and its ring of obsidian pillars.
+
+use crate::random::JavaRandom;
+use crate::WorldGenerator;
+use feather_core::biomes::Biome;
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use feather_core::util::ChunkPosition;
+use simdnoise::NoiseBuilder;
+use std::f64::consts::PI;
+
+/// Radius of the main island, in blocks.
+const ISLAND_RADIUS: f32 = 96.0;
+/// Y coordinate of the main island's surface at its center.
+const ISLAND_SURFACE: f32 = 60.0;
+/// Distance of the obsidian pillars from the world origin.
+const PILLAR_DISTANCE: f64 = 42.0;
+/// Number of obsidian pillars.
+const PILLAR_COUNT: i32 = 10;
+
+/// Generates the End.
+///
+/// Only the main island is generated; the void
+/// around it is left empty.
+pub struct EndGenerator {
+    seed: u64,
+    pillars: Vec<Pillar>,
+}
+
+impl EndGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pillars: Pillar::for_seed(seed as i64),
+        }
+    }
+
+    fn place_island(&self, chunk: &mut Chunk) {
+        let position = chunk.position();
+        let detail =
+            NoiseBuilder::fbm_2d_offset((position.x * 16) as f32, 16, (position.z * 16) as f32, 16)
+                .with_seed(self.seed as i32)
+                .with_octaves(3)
+                .with_freq(0.05)
+                .generate()
+                .0;
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let world_x = (position.x * 16 + x as i32) as f32;
+                let world_z = (position.z * 16 + z as i32) as f32;
+                let distance = (world_x * world_x + world_z * world_z).sqrt();
+
+                // 1 at the center of the island, 0 at its edge.
+                let falloff = 1.0 - distance / ISLAND_RADIUS;
+                if falloff <= 0.0 {
+                    continue;
+                }
+
+                let detail = detail[z * 16 + x] * 10.0;
+                let top = ISLAND_SURFACE + falloff * 4.0 + detail;
+                let bottom = ISLAND_SURFACE - falloff.sqrt() * 40.0 + detail;
+
+                for y in bottom.max(0.0) as usize..=top.max(0.0) as usize {
+                    chunk.set_block_at(x, y, z, BlockId::end_stone());
+                }
+            }
+        }
+    }
+}
+
+impl WorldGenerator for EndGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new_with_default_biome(position, Biome::TheEnd);
+
+        // Distance from the origin to the nearest block of the chunk, roughly.
+        let nearest = (position.x.abs().max(position.z.abs()) - 1) * 16;
+        if nearest as f32 <= ISLAND_RADIUS {
+            self.place_island(&mut chunk);
+        }
+
+        for pillar in &self.pillars {
+            pillar.place(&mut chunk);
+        }
+
+        chunk.recalculate_heightmap();
+        chunk
+    }
+}
+
+/// One of the obsidian pillars surrounding the main island.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pillar {
+    pub center_x: i32,
+    pub center_z: i32,
+    pub radius: i32,
+    /// Y coordinate of the bedrock block on top of the pillar.
+    pub height: i32,
+    /// Whether the top of the pillar is surrounded by iron bars.
+    pub guarded: bool,
+}
+
+impl Pillar {
+    /// Returns the pillars for the given world seed,
+    /// matching vanilla's pillar sizes and positions.
+    pub fn for_seed(seed: i64) -> Vec<Pillar> {
+        let mut rng = JavaRandom::new(JavaRandom::new(seed).next_long() & 0xFFFF);
+        let mut sizes: Vec<i32> = (0..PILLAR_COUNT).collect();
+        rng.shuffle(&mut sizes);
+
+        sizes
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let angle = 2.0 * (-PI + PI / f64::from(PILLAR_COUNT) * i as f64);
+                Pillar {
+                    center_x: (PILLAR_DISTANCE * angle.cos()).floor() as i32,
+                    center_z: (PILLAR_DISTANCE * angle.sin()).floor() as i32,
+                    radius: 2 + size / 3,
+                    height: 76 + size * 3,
+                    guarded: size == 1 || size == 2,
+                }
+            })
+            .collect()
+    }
+
+    /// Places the part of this pillar which lies within the given chunk.
+    fn place(&self, chunk: &mut Chunk) {
+        let position = chunk.position();
+        let radius_squared = self.radius * self.radius + 1;
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let dx = position.x * 16 + x as i32 - self.center_x;
+                let dz = position.z * 16 + z as i32 - self.center_z;
+
+                if dx * dx + dz * dz <= radius_squared {
+                    for y in 0..self.height as usize {
+                        chunk.set_block_at(x, y, z, BlockId::obsidian());
+                    }
+                }
+
+                if dx == 0 && dz == 0 {
+                    chunk.set_block_at(x, self.height as usize, z, BlockId::bedrock());
+                }
+
+                // The cage spans a 5x5x4 box above the pillar, open
+                // at the bottom.
+                if self.guarded && dx.abs() <= 2 && dz.abs() <= 2 {
+                    for dy in 0..=3 {
+                        if dx.abs() == 2 || dz.abs() == 2 || dy == 3 {
+                            let y = self.height as usize + dy;
+                            chunk.set_block_at(x, y, z, BlockId::iron_bars());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pillars() {
+        let pillars = Pillar::for_seed(0);
+        assert_eq!(pillars.len(), 10);
+        assert_eq!(pillars.iter().filter(|pillar| pillar.guarded).count(), 2);
+
+        let mut heights: Vec<i32> = pillars.iter().map(|pillar| pillar.height).collect();
+        heights.sort();
+        assert_eq!(heights, (0..10).map(|i| 76 + i * 3).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn main_island() {
+        let generator = EndGenerator::new(0);
+
+        let chunk = generator.generate_chunk(ChunkPosition::new(0, 0));
+        assert!((0..256).any(|y| chunk.block_at(0, y, 0) == BlockId::end_stone()));
+        assert_eq!(chunk.biome_at(0, 0), Biome::TheEnd);
+
+        // Outside the island, only the void remains.
+        let chunk = generator.generate_chunk(ChunkPosition::new(20, 20));
+        assert!((0..256).all(|y| chunk.block_at(8, y, 8).is_air()));
+    }
+}
@@ -0,0 +1,55 @@
+//! Features: small decorations such as trees, flowers,
+//! and mushrooms, placed on top of the terrain surface.
+
+mod placement;
+mod tree;
+
+pub use placement::BiomeFeatures;
+pub use tree::TreeKind;
+
+use feather_core::blocks::{BlockId, SimplifiedBlockKind};
+use feather_core::chunk::Chunk;
+
+/// Attempts to place a single plant block, such as a flower
+/// or mushroom, at the given chunk-local position.
+/// Returns whether the plant was placed.
+pub fn place_plant(chunk: &mut Chunk, x: usize, y: usize, z: usize, plant: BlockId) -> bool {
+    if y == 0 || y > crate::SKY_LIMIT || !can_replace(chunk.block_at(x, y, z)) {
+        return false;
+    }
+
+    let ground = chunk.block_at(x, y - 1, z);
+    let supported = match plant.simplified_kind() {
+        SimplifiedBlockKind::Mushroom => ground.is_opaque(),
+        _ => is_soil(ground),
+    };
+    if !supported {
+        return false;
+    }
+
+    chunk.set_block_at(x, y, z, plant);
+    true
+}
+
+/// Returns whether plants and trees can grow on the given block.
+fn is_soil(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::GrassBlock
+            | SimplifiedBlockKind::Dirt
+            | SimplifiedBlockKind::CoarseDirt
+            | SimplifiedBlockKind::Podzol
+    )
+}
+
+/// Returns whether features may overwrite the given block.
+fn can_replace(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::Air
+            | SimplifiedBlockKind::Snow
+            | SimplifiedBlockKind::Grass
+            | SimplifiedBlockKind::Fern
+            | SimplifiedBlockKind::Leaves
+    )
+}
@@ -0,0 +1,273 @@
+use super::TreeKind;
+use feather_core::biomes::Biome;
+use feather_core::blocks::BlockId;
+use rand::Rng;
+
+/// Describes which features are placed in a biome,
+/// and how often. Counts are per chunk.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BiomeFeatures {
+    /// Number of trees attempted.
+    pub trees: u32,
+    /// Chance of one additional tree attempt.
+    pub extra_tree_chance: f64,
+    /// Kinds of trees, with their weights.
+    pub tree_kinds: &'static [(TreeKind, u32)],
+    /// Number of grass or fern patches attempted.
+    pub grass: u32,
+    /// Chance that a grass block is a fern instead.
+    pub fern_chance: f64,
+    /// Number of flowers attempted.
+    pub flowers: u32,
+    /// Kinds of flowers.
+    pub flower_kinds: &'static [fn() -> BlockId],
+    /// Number of mushrooms attempted.
+    pub mushrooms: u32,
+}
+
+const OAK: &[(TreeKind, u32)] = &[(TreeKind::Oak, 1)];
+const FOREST: &[(TreeKind, u32)] = &[(TreeKind::Oak, 4), (TreeKind::Birch, 1)];
+const BIRCH: &[(TreeKind, u32)] = &[(TreeKind::Birch, 1)];
+const DARK_FOREST: &[(TreeKind, u32)] = &[(TreeKind::DarkOak, 3), (TreeKind::Oak, 1)];
+const SPRUCE: &[(TreeKind, u32)] = &[(TreeKind::Spruce, 1)];
+const JUNGLE: &[(TreeKind, u32)] = &[(TreeKind::Jungle, 2), (TreeKind::Oak, 1)];
+const SAVANNA: &[(TreeKind, u32)] = &[(TreeKind::Acacia, 4), (TreeKind::Oak, 1)];
+const MOUNTAINS: &[(TreeKind, u32)] = &[(TreeKind::Spruce, 2), (TreeKind::Oak, 1)];
+
+const DEFAULT_FLOWERS: &[fn() -> BlockId] = &[BlockId::dandelion, BlockId::poppy];
+const PLAINS_FLOWERS: &[fn() -> BlockId] = &[
+    BlockId::dandelion,
+    BlockId::poppy,
+    BlockId::azure_bluet,
+    BlockId::oxeye_daisy,
+    BlockId::red_tulip,
+    BlockId::orange_tulip,
+    BlockId::white_tulip,
+    BlockId::pink_tulip,
+];
+const FLOWER_FOREST_FLOWERS: &[fn() -> BlockId] = &[
+    BlockId::dandelion,
+    BlockId::poppy,
+    BlockId::allium,
+    BlockId::azure_bluet,
+    BlockId::oxeye_daisy,
+    BlockId::red_tulip,
+    BlockId::orange_tulip,
+    BlockId::white_tulip,
+    BlockId::pink_tulip,
+];
+const SWAMP_FLOWERS: &[fn() -> BlockId] = &[BlockId::blue_orchid];
+
+impl BiomeFeatures {
+    /// Returns the features placed in the given biome,
+    /// loosely following vanilla's biome decorators.
+    pub fn for_biome(biome: Biome) -> Self {
+        let default = Self {
+            extra_tree_chance: 0.1,
+            tree_kinds: OAK,
+            grass: 1,
+            flowers: 2,
+            flower_kinds: DEFAULT_FLOWERS,
+            ..Self::default()
+        };
+
+        match biome {
+            Biome::Plains | Biome::SunflowerPlains => Self {
+                extra_tree_chance: 0.05,
+                grass: 10,
+                flowers: 4,
+                flower_kinds: PLAINS_FLOWERS,
+                ..default
+            },
+            Biome::Forest | Biome::WoodedHills => Self {
+                trees: 10,
+                tree_kinds: FOREST,
+                grass: 2,
+                ..default
+            },
+            Biome::FlowerForest => Self {
+                trees: 6,
+                tree_kinds: FOREST,
+                grass: 2,
+                flowers: 100,
+                flower_kinds: FLOWER_FOREST_FLOWERS,
+                ..default
+            },
+            Biome::BirchForest
+            | Biome::BirchForestHills
+            | Biome::TallBirchForest
+            | Biome::TallBirchHills => Self {
+                trees: 10,
+                tree_kinds: BIRCH,
+                grass: 2,
+                ..default
+            },
+            Biome::DarkForest | Biome::DarkForestHills => Self {
+                trees: 16,
+                tree_kinds: DARK_FOREST,
+                grass: 2,
+                mushrooms: 2,
+                ..default
+            },
+            Biome::Taiga
+            | Biome::TaigaHills
+            | Biome::TaigaMountains
+            | Biome::SnowyTaiga
+            | Biome::SnowyTaigaHills
+            | Biome::SnowyTaigaMountains
+            | Biome::GiantTreeTaiga
+            | Biome::GiantTreeTaigaHills
+            | Biome::GiantSpruceTaiga
+            | Biome::GiantSpruceTaigaHills => Self {
+                trees: 10,
+                tree_kinds: SPRUCE,
+                grass: 1,
+                fern_chance: 0.8,
+                mushrooms: 1,
+                ..default
+            },
+            Biome::Jungle
+            | Biome::JungleHills
+            | Biome::ModifiedJungle
+            | Biome::JungleEdge
+            | Biome::ModifiedJungleEdge => Self {
+                trees: if biome == Biome::JungleEdge || biome == Biome::ModifiedJungleEdge {
+                    2
+                } else {
+                    30
+                },
+                tree_kinds: JUNGLE,
+                grass: 25,
+                fern_chance: 0.25,
+                flowers: 4,
+                ..default
+            },
+            Biome::Savanna
+            | Biome::SavannaPlateau
+            | Biome::ShatteredSavanna
+            | Biome::ShatteredSavannaPlateau => Self {
+                trees: 1,
+                tree_kinds: SAVANNA,
+                grass: 20,
+                flowers: 4,
+                ..default
+            },
+            Biome::Swamp | Biome::SwampHills => Self {
+                trees: 2,
+                grass: 5,
+                flowers: 1,
+                flower_kinds: SWAMP_FLOWERS,
+                mushrooms: 8,
+                ..default
+            },
+            Biome::Mountains
+            | Biome::GravellyMountains
+            | Biome::ModifiedGravellyMountains
+            | Biome::MountainEdge => Self {
+                tree_kinds: MOUNTAINS,
+                ..default
+            },
+            Biome::WoodedMountains => Self {
+                trees: 3,
+                tree_kinds: MOUNTAINS,
+                ..default
+            },
+            Biome::MushroomFields | Biome::MushroomFieldShore => Self {
+                mushrooms: 4,
+                ..Self::default()
+            },
+            Biome::SnowyTundra | Biome::SnowyMountains => Self {
+                extra_tree_chance: 0.1,
+                tree_kinds: SPRUCE,
+                ..Self::default()
+            },
+            Biome::Desert
+            | Biome::DesertHills
+            | Biome::DesertLakes
+            | Biome::Badlands
+            | Biome::BadlandsPlateau
+            | Biome::ErodedBadlands
+            | Biome::ModifiedBadlandsPlateau
+            | Biome::IceSpikes
+            | Biome::Beach
+            | Biome::SnowyBeach
+            | Biome::StoneShore => Self::default(),
+            Biome::WoodedBadlandsPlateau | Biome::ModifiedWoodedBadlandsPlateau => Self {
+                trees: 5,
+                ..default
+            },
+            _ if biome.is_ocean() => Self::default(),
+            _ => default,
+        }
+    }
+
+    /// Returns the number of tree attempts for a chunk.
+    pub fn tree_count(&self, rng: &mut impl Rng) -> u32 {
+        let extra = self.extra_tree_chance > 0.0 && rng.gen_bool(self.extra_tree_chance);
+        self.trees + u32::from(extra)
+    }
+
+    /// Chooses a kind of tree, or `None` if
+    /// this biome has no trees.
+    pub fn choose_tree(&self, rng: &mut impl Rng) -> Option<TreeKind> {
+        let total: u32 = self.tree_kinds.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut choice = rng.gen_range(0, total);
+        for (kind, weight) in self.tree_kinds {
+            if choice < *weight {
+                return Some(*kind);
+            }
+            choice -= weight;
+        }
+        None
+    }
+
+    /// Chooses the block of a grass patch.
+    pub fn choose_grass(&self, rng: &mut impl Rng) -> BlockId {
+        if self.fern_chance > 0.0 && rng.gen_bool(self.fern_chance) {
+            BlockId::fern()
+        } else {
+            BlockId::grass()
+        }
+    }
+
+    /// Chooses a kind of flower, or `None` if
+    /// this biome has no flowers.
+    pub fn choose_flower(&self, rng: &mut impl Rng) -> Option<BlockId> {
+        if self.flower_kinds.is_empty() {
+            return None;
+        }
+        let index = rng.gen_range(0, self.flower_kinds.len());
+        Some((self.flower_kinds[index])())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn biome_features() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+
+        let forest = BiomeFeatures::for_biome(Biome::Forest);
+        assert!(forest.tree_count(&mut rng) >= 10);
+        assert!(forest.choose_tree(&mut rng).is_some());
+
+        let taiga = BiomeFeatures::for_biome(Biome::Taiga);
+        assert_eq!(taiga.choose_tree(&mut rng), Some(TreeKind::Spruce));
+
+        let desert = BiomeFeatures::for_biome(Biome::Desert);
+        assert_eq!(desert.tree_count(&mut rng), 0);
+        assert_eq!(desert.choose_tree(&mut rng), None);
+        assert_eq!(desert.choose_flower(&mut rng), None);
+
+        let ocean = BiomeFeatures::for_biome(Biome::DeepOcean);
+        assert_eq!(ocean.grass, 0);
+    }
+}
@@ -0,0 +1,247 @@
+use super::{can_replace, is_soil};
+use crate::SKY_LIMIT;
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use rand::Rng;
+
+/// Maximum horizontal distance of leaves from the trunk.
+const MAX_RADIUS: usize = 2;
+
+/// A kind of tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeKind {
+    Oak,
+    Birch,
+    Spruce,
+    Jungle,
+    Acacia,
+    DarkOak,
+}
+
+impl TreeKind {
+    /// Returns the log block of this tree.
+    pub fn log(self) -> BlockId {
+        match self {
+            TreeKind::Oak => BlockId::oak_log(),
+            TreeKind::Birch => BlockId::birch_log(),
+            TreeKind::Spruce => BlockId::spruce_log(),
+            TreeKind::Jungle => BlockId::jungle_log(),
+            TreeKind::Acacia => BlockId::acacia_log(),
+            TreeKind::DarkOak => BlockId::dark_oak_log(),
+        }
+    }
+
+    /// Returns the leaves block of this tree, with
+    /// the given distance from the trunk.
+    pub fn leaves(self, distance: i32) -> BlockId {
+        let leaves = match self {
+            TreeKind::Oak => BlockId::oak_leaves(),
+            TreeKind::Birch => BlockId::birch_leaves(),
+            TreeKind::Spruce => BlockId::spruce_leaves(),
+            TreeKind::Jungle => BlockId::jungle_leaves(),
+            TreeKind::Acacia => BlockId::acacia_leaves(),
+            TreeKind::DarkOak => BlockId::dark_oak_leaves(),
+        };
+        leaves.with_distance(distance.clamp(1, 7))
+    }
+
+    fn trunk_height(self, rng: &mut impl Rng) -> usize {
+        match self {
+            TreeKind::Oak => rng.gen_range(4, 7),
+            TreeKind::Birch | TreeKind::Acacia => rng.gen_range(5, 8),
+            TreeKind::Spruce => rng.gen_range(6, 10),
+            TreeKind::Jungle => rng.gen_range(4, 11),
+            TreeKind::DarkOak => rng.gen_range(6, 9),
+        }
+    }
+
+    /// Attempts to grow a tree with its trunk starting at the given
+    /// chunk-local position. Returns whether the tree was placed.
+    ///
+    /// Trees are only placed if their leaves fit within the chunk.
+    pub fn place(
+        self,
+        chunk: &mut Chunk,
+        x: usize,
+        y: usize,
+        z: usize,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let height = self.trunk_height(rng);
+
+        if x < MAX_RADIUS
+            || z < MAX_RADIUS
+            || x + MAX_RADIUS > 15
+            || z + MAX_RADIUS > 15
+            || y == 0
+            || y + height + 1 > SKY_LIMIT
+        {
+            return false;
+        }
+
+        if !is_soil(chunk.block_at(x, y - 1, z)) {
+            return false;
+        }
+        if (y..y + height).any(|y| !can_replace(chunk.block_at(x, y, z))) {
+            return false;
+        }
+
+        match self {
+            TreeKind::Spruce => self.place_conical_leaves(chunk, x, y, z, height, rng),
+            _ => self.place_blob_leaves(chunk, x, y, z, height, rng),
+        }
+
+        for trunk_y in y..y + height {
+            chunk.set_block_at(x, trunk_y, z, self.log());
+        }
+        chunk.set_block_at(x, y - 1, z, BlockId::dirt());
+
+        true
+    }
+
+    /// Leaves in the shape of an oak tree: two wide
+    /// layers topped by two narrow layers.
+    fn place_blob_leaves(
+        self,
+        chunk: &mut Chunk,
+        x: usize,
+        y: usize,
+        z: usize,
+        height: usize,
+        rng: &mut impl Rng,
+    ) {
+        let top = y + height;
+        for leaves_y in top - 3..=top {
+            let radius = if leaves_y + 1 >= top { 1 } else { 2 };
+            let is_top = leaves_y == top;
+
+            self.place_layer(chunk, x, leaves_y, z, radius, is_top, |corner| {
+                !corner || (!is_top && rng.gen())
+            });
+        }
+    }
+
+    /// Leaves in the shape of a spruce tree: layers
+    /// alternating in radius, widening towards the bottom.
+    fn place_conical_leaves(
+        self,
+        chunk: &mut Chunk,
+        x: usize,
+        y: usize,
+        z: usize,
+        height: usize,
+        rng: &mut impl Rng,
+    ) {
+        let top = y + height;
+        let bottom = y + rng.gen_range(1, 3);
+
+        let mut radius = rng.gen_range(0, 2);
+        let mut max_radius = 1;
+        let mut reset_radius = 0;
+        for leaves_y in (bottom..=top).rev() {
+            self.place_layer(chunk, x, leaves_y, z, radius, leaves_y == top, |corner| {
+                !corner || radius == 0
+            });
+
+            if radius >= max_radius {
+                radius = reset_radius;
+                reset_radius = 1;
+                max_radius = (max_radius + 1).min(MAX_RADIUS);
+            } else {
+                radius += 1;
+            }
+        }
+    }
+
+    /// Places a square layer of leaves centered on the trunk.
+    /// `keep` is called for each position with whether it is a
+    /// corner of the layer, and returns whether to place leaves there.
+    #[allow(clippy::too_many_arguments)]
+    fn place_layer(
+        self,
+        chunk: &mut Chunk,
+        x: usize,
+        y: usize,
+        z: usize,
+        radius: usize,
+        above_trunk: bool,
+        mut keep: impl FnMut(bool) -> bool,
+    ) {
+        for leaves_x in x - radius..=x + radius {
+            for leaves_z in z - radius..=z + radius {
+                let dx = (leaves_x as i32 - x as i32).abs();
+                let dz = (leaves_z as i32 - z as i32).abs();
+                let corner = dx == radius as i32 && dz == radius as i32;
+
+                if !keep(corner) || !can_replace(chunk.block_at(leaves_x, y, leaves_z)) {
+                    continue;
+                }
+
+                let distance = dx + dz + i32::from(above_trunk);
+                chunk.set_block_at(leaves_x, y, leaves_z, self.leaves(distance));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::SimplifiedBlockKind;
+    use feather_core::util::ChunkPosition;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn grass_chunk() -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 63, z, BlockId::grass_block());
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn places_trees() {
+        let kinds = [
+            TreeKind::Oak,
+            TreeKind::Birch,
+            TreeKind::Spruce,
+            TreeKind::Jungle,
+            TreeKind::Acacia,
+            TreeKind::DarkOak,
+        ];
+
+        for kind in kinds.iter() {
+            let mut chunk = grass_chunk();
+            let mut rng = XorShiftRng::seed_from_u64(10);
+            assert!(kind.place(&mut chunk, 8, 64, 8, &mut rng));
+
+            assert_eq!(chunk.block_at(8, 63, 8), BlockId::dirt());
+            assert_eq!(chunk.block_at(8, 64, 8), kind.log());
+            assert!((64..80).any(|y| {
+                chunk.block_at(8, y, 8).simplified_kind() == SimplifiedBlockKind::Leaves
+            }));
+        }
+    }
+
+    #[test]
+    fn trees_stay_within_chunk() {
+        let mut chunk = grass_chunk();
+        let mut rng = XorShiftRng::seed_from_u64(10);
+
+        assert!(!TreeKind::Oak.place(&mut chunk, 1, 64, 8, &mut rng));
+        assert!(!TreeKind::Oak.place(&mut chunk, 8, 64, 14, &mut rng));
+        assert_eq!(chunk.block_at(1, 64, 8), BlockId::air());
+    }
+
+    #[test]
+    fn trees_need_soil() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        chunk.set_block_at(8, 63, 8, BlockId::stone());
+        let mut rng = XorShiftRng::seed_from_u64(10);
+
+        assert!(!TreeKind::Oak.place(&mut chunk, 8, 64, 8, &mut rng));
+    }
+}
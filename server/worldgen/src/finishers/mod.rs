@@ -1,9 +1,11 @@
-//! Various finishers for world generation, such as grass, snow, and trees.
+//! Various finishers for world generation, such as grass, snow, trees, and ores.
 
-mod clumped;
+mod ores;
 mod single;
 mod snow;
+mod vegetation;
 
-pub use clumped::ClumpedFoliageFinisher;
+pub use ores::OreFinisher;
 pub use single::SingleFoliageFinisher;
 pub use snow::SnowFinisher;
+pub use vegetation::VegetationFinisher;
@@ -0,0 +1,259 @@
+use crate::random::{population_seed, JavaRandom};
+use crate::settings::{HeightDistribution, OreConfig, OreSettings};
+use crate::util::{cos, sin};
+use crate::{ChunkBiomes, FinishingGenerator, TopBlocks};
+use feather_core::biomes::BiomeCategory;
+use feather_core::blocks::{BlockId, SimplifiedBlockKind};
+use feather_core::chunk::Chunk;
+use feather_core::util::ChunkPosition;
+use std::f32::consts::PI;
+
+/// Places ore veins and underground features
+/// such as dirt, gravel, and granite pockets.
+///
+/// Veins started in neighboring chunks are also placed,
+/// so veins cross chunk boundaries seamlessly.
+#[derive(Default)]
+pub struct OreFinisher {
+    settings: OreSettings,
+}
+
+impl OreFinisher {
+    pub fn new(settings: OreSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl FinishingGenerator for OreFinisher {
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        biomes: &ChunkBiomes,
+        _top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        // Veins are offset by eight blocks in the positive direction,
+        // so only this chunk and its negative neighbors can reach it.
+        let target = chunk.position();
+        for source_x in target.x - 1..=target.x {
+            for source_z in target.z - 1..=target.z {
+                let source = ChunkPosition::new(source_x, source_z);
+                let mut rng = JavaRandom::new(population_seed(seed as i64, source));
+
+                for ore in self.settings.veins().iter() {
+                    place_veins(chunk, source, &mut rng, ore);
+                }
+
+                // Emeralds never leave their source chunk.
+                if source == target && self.settings.emeralds {
+                    place_emeralds(chunk, biomes, &mut rng);
+                }
+            }
+        }
+    }
+}
+
+fn place_veins(chunk: &mut Chunk, source: ChunkPosition, rng: &mut JavaRandom, ore: &OreConfig) {
+    for _ in 0..ore.count {
+        let x = source.x * 16 + rng.next_int_bounded(16);
+        let y = vein_height(rng, ore.height);
+        let z = source.z * 16 + rng.next_int_bounded(16);
+
+        place_vein(chunk, rng, ore, x, y, z);
+    }
+}
+
+fn vein_height(rng: &mut JavaRandom, height: HeightDistribution) -> i32 {
+    match height {
+        HeightDistribution::Uniform { min, max } => {
+            let (mut min, mut max) = (min as i32, max as i32);
+            if max < min {
+                std::mem::swap(&mut min, &mut max);
+            } else if max == min {
+                if min < 255 {
+                    max += 1;
+                } else {
+                    min -= 1;
+                }
+            }
+            rng.next_int_bounded(max - min) + min
+        }
+        HeightDistribution::Triangular { center, spread } => {
+            let spread = (spread as i32).max(1);
+            rng.next_int_bounded(spread) + rng.next_int_bounded(spread) + center as i32 - spread
+        }
+    }
+}
+
+/// Places a single vein of ore, starting at the given
+/// position (offset by eight blocks, as in vanilla).
+fn place_vein(chunk: &mut Chunk, rng: &mut JavaRandom, ore: &OreConfig, x: i32, y: i32, z: i32) {
+    let size = ore.size as f32;
+    let angle = rng.next_float() * PI;
+    let offset_x = f64::from(sin(angle) * size / 8.0);
+    let offset_z = f64::from(cos(angle) * size / 8.0);
+
+    let (start_x, end_x) = (f64::from(x + 8) + offset_x, f64::from(x + 8) - offset_x);
+    let (start_z, end_z) = (f64::from(z + 8) + offset_z, f64::from(z + 8) - offset_z);
+    let start_y = f64::from(y + rng.next_int_bounded(3) - 2);
+    let end_y = f64::from(y + rng.next_int_bounded(3) - 2);
+
+    let pos = chunk.position();
+    let (base_x, base_z) = (pos.x * 16, pos.z * 16);
+
+    for i in 0..ore.size {
+        let progress = i as f32 / size;
+        let center_x = start_x + (end_x - start_x) * f64::from(progress);
+        let center_y = start_y + (end_y - start_y) * f64::from(progress);
+        let center_z = start_z + (end_z - start_z) * f64::from(progress);
+
+        let scale = rng.next_double() * f64::from(size) / 16.0;
+        let diameter = f64::from(sin(PI * progress) + 1.0) * scale + 1.0;
+        let radius = diameter / 2.0;
+
+        let min_x = (center_x - radius).floor() as i32;
+        let min_y = (center_y - radius).floor() as i32;
+        let min_z = (center_z - radius).floor() as i32;
+        let max_x = (center_x + radius).floor() as i32;
+        let max_y = (center_y + radius).floor() as i32;
+        let max_z = (center_z + radius).floor() as i32;
+
+        for block_x in min_x.max(base_x)..=max_x.min(base_x + 15) {
+            let dx = (f64::from(block_x) + 0.5 - center_x) / radius;
+            if dx * dx >= 1.0 {
+                continue;
+            }
+            for block_y in min_y.max(0)..=max_y.min(255) {
+                let dy = (f64::from(block_y) + 0.5 - center_y) / radius;
+                if dx * dx + dy * dy >= 1.0 {
+                    continue;
+                }
+                for block_z in min_z.max(base_z)..=max_z.min(base_z + 15) {
+                    let dz = (f64::from(block_z) + 0.5 - center_z) / radius;
+                    if dx * dx + dy * dy + dz * dz >= 1.0 {
+                        continue;
+                    }
+
+                    let (local_x, local_y, local_z) = (
+                        (block_x - base_x) as usize,
+                        block_y as usize,
+                        (block_z - base_z) as usize,
+                    );
+                    if is_natural_stone(chunk.block_at(local_x, local_y, local_z)) {
+                        chunk.set_block_at(local_x, local_y, local_z, ore.block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Places single emerald ores in mountain biomes.
+fn place_emeralds(chunk: &mut Chunk, biomes: &ChunkBiomes, rng: &mut JavaRandom) {
+    let count = 3 + rng.next_int_bounded(6);
+    for _ in 0..count {
+        let x = rng.next_int_bounded(16) as usize;
+        let y = (rng.next_int_bounded(28) + 4) as usize;
+        let z = rng.next_int_bounded(16) as usize;
+
+        if biomes.biome_at(x, z).category() == BiomeCategory::ExtremeHills
+            && chunk.block_at(x, y, z).simplified_kind() == SimplifiedBlockKind::Stone
+        {
+            chunk.set_block_at(x, y, z, BlockId::emerald_ore());
+        }
+    }
+}
+
+/// Returns whether ores may replace the given block.
+fn is_natural_stone(block: BlockId) -> bool {
+    matches!(
+        block.simplified_kind(),
+        SimplifiedBlockKind::Stone
+            | SimplifiedBlockKind::Granite
+            | SimplifiedBlockKind::Diorite
+            | SimplifiedBlockKind::Andesite
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::biomes::Biome;
+
+    fn stone_chunk(pos: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new(pos);
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..128 {
+                    chunk.set_block_at(x, y, z, BlockId::stone());
+                }
+            }
+        }
+        chunk
+    }
+
+    fn count(chunk: &Chunk, block: BlockId) -> usize {
+        let mut count = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..256 {
+                    if chunk.block_at(x, y, z) == block {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn places_ores() {
+        let biomes = ChunkBiomes::from_array([Biome::Mountains; 16 * 16]);
+        let finisher = OreFinisher::default();
+
+        let mut chunk = stone_chunk(ChunkPosition::new(3, -2));
+        finisher.generate_for_chunk(&mut chunk, &biomes, &TopBlocks::new(), 42);
+
+        assert!(count(&chunk, BlockId::coal_ore()) > 0);
+        assert!(count(&chunk, BlockId::iron_ore()) > 0);
+        assert!(count(&chunk, BlockId::emerald_ore()) > 0);
+        // Ores never replace air.
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(chunk.block_at(x, 200, z), BlockId::air());
+            }
+        }
+    }
+
+    #[test]
+    fn disabled_ores() {
+        let biomes = ChunkBiomes::from_array([Biome::Mountains; 16 * 16]);
+        let mut settings = OreSettings::default();
+        settings.emeralds = false;
+        settings.coal.count = 0;
+        let finisher = OreFinisher::new(settings);
+
+        let mut chunk = stone_chunk(ChunkPosition::new(0, 0));
+        finisher.generate_for_chunk(&mut chunk, &biomes, &TopBlocks::new(), 42);
+
+        assert_eq!(count(&chunk, BlockId::coal_ore()), 0);
+        assert_eq!(count(&chunk, BlockId::emerald_ore()), 0);
+    }
+
+    #[test]
+    fn height_distribution() {
+        let mut rng = JavaRandom::new(0);
+        for _ in 0..1000 {
+            let y = vein_height(&mut rng, HeightDistribution::Uniform { min: 0, max: 16 });
+            assert!((0..16).contains(&y));
+            let y = vein_height(
+                &mut rng,
+                HeightDistribution::Triangular {
+                    center: 16,
+                    spread: 16,
+                },
+            );
+            assert!((0..31).contains(&y));
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::features::{place_plant, BiomeFeatures};
+use crate::util::shuffle_seed_for_chunk;
+use crate::{ChunkBiomes, FinishingGenerator, TopBlocks};
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// Places trees, grass, flowers, and mushrooms
+/// according to each biome's `BiomeFeatures`.
+///
+/// The number of attempts is determined by the biome
+/// at the center of the chunk, while the kind of each feature
+/// is chosen from the biome of the column it is placed in.
+#[derive(Default)]
+pub struct VegetationFinisher;
+
+impl FinishingGenerator for VegetationFinisher {
+    fn generate_for_chunk(
+        &self,
+        chunk: &mut Chunk,
+        biomes: &ChunkBiomes,
+        top_blocks: &TopBlocks,
+        seed: u64,
+    ) {
+        let mut rng = XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(seed, chunk.position()));
+        let features = BiomeFeatures::for_biome(biomes.biome_at(8, 8));
+
+        for _ in 0..features.tree_count(&mut rng) {
+            let (x, z) = random_column(&mut rng);
+            let column = BiomeFeatures::for_biome(biomes.biome_at(x, z));
+            if let Some(tree) = column.choose_tree(&mut rng) {
+                tree.place(chunk, x, top_blocks.top_block_at(x, z) + 1, z, &mut rng);
+            }
+        }
+
+        for _ in 0..features.grass {
+            let (x, z) = random_column(&mut rng);
+            let column = BiomeFeatures::for_biome(biomes.biome_at(x, z));
+            if column.grass == 0 {
+                continue;
+            }
+
+            // Grass grows in small patches.
+            let block = column.choose_grass(&mut rng);
+            scatter(chunk, top_blocks, &mut rng, x, z, 8, block);
+        }
+
+        for _ in 0..features.flowers {
+            let (x, z) = random_column(&mut rng);
+            let column = BiomeFeatures::for_biome(biomes.biome_at(x, z));
+            if column.flowers == 0 {
+                continue;
+            }
+
+            if let Some(flower) = column.choose_flower(&mut rng) {
+                scatter(chunk, top_blocks, &mut rng, x, z, 4, flower);
+            }
+        }
+
+        for _ in 0..features.mushrooms {
+            let (x, z) = random_column(&mut rng);
+            let mushroom = if rng.gen_bool(0.5) {
+                BlockId::brown_mushroom()
+            } else {
+                BlockId::red_mushroom()
+            };
+            place_plant(chunk, x, top_blocks.top_block_at(x, z) + 1, z, mushroom);
+        }
+    }
+}
+
+fn random_column(rng: &mut impl Rng) -> (usize, usize) {
+    (rng.gen_range(0, 16), rng.gen_range(0, 16))
+}
+
+/// Places up to `count` copies of `block` in columns around `(x, z)`.
+/// Like other finishers, the patch is clamped to the chunk border.
+fn scatter(
+    chunk: &mut Chunk,
+    top_blocks: &TopBlocks,
+    rng: &mut impl Rng,
+    x: usize,
+    z: usize,
+    count: usize,
+    block: BlockId,
+) {
+    for _ in 0..count {
+        let pos_x = (x as i32 + rng.gen_range(-3, 4)).clamp(0, 15) as usize;
+        let pos_z = (z as i32 + rng.gen_range(-3, 4)).clamp(0, 15) as usize;
+
+        place_plant(
+            chunk,
+            pos_x,
+            top_blocks.top_block_at(pos_x, pos_z) + 1,
+            pos_z,
+            block,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::biomes::Biome;
+    use feather_core::blocks::SimplifiedBlockKind;
+    use feather_core::util::ChunkPosition;
+
+    fn grass_chunk() -> (Chunk, TopBlocks) {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        let mut top_blocks = TopBlocks::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_at(x, 63, z, BlockId::grass_block());
+                top_blocks.set_top_block_at(x, z, 63);
+            }
+        }
+        (chunk, top_blocks)
+    }
+
+    fn count(chunk: &Chunk, kind: SimplifiedBlockKind) -> usize {
+        let mut count = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 64..96 {
+                    if chunk.block_at(x, y, z).simplified_kind() == kind {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn forest_has_trees() {
+        let (mut chunk, top_blocks) = grass_chunk();
+        let biomes = ChunkBiomes::from_array([Biome::Forest; 16 * 16]);
+        VegetationFinisher.generate_for_chunk(&mut chunk, &biomes, &top_blocks, 5);
+
+        assert!(count(&chunk, SimplifiedBlockKind::Log) > 0);
+        assert!(count(&chunk, SimplifiedBlockKind::Leaves) > 0);
+    }
+
+    #[test]
+    fn plains_have_grass() {
+        let (mut chunk, top_blocks) = grass_chunk();
+        let biomes = ChunkBiomes::from_array([Biome::Plains; 16 * 16]);
+        VegetationFinisher.generate_for_chunk(&mut chunk, &biomes, &top_blocks, 5);
+
+        assert!(count(&chunk, SimplifiedBlockKind::Grass) > 0);
+    }
+
+    #[test]
+    fn desert_is_bare() {
+        let (mut chunk, top_blocks) = grass_chunk();
+        let biomes = ChunkBiomes::from_array([Biome::Desert; 16 * 16]);
+        VegetationFinisher.generate_for_chunk(&mut chunk, &biomes, &top_blocks, 5);
+
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(chunk.block_at(x, 64, z), BlockId::air());
+            }
+        }
+    }
+}
@@ -6,31 +6,50 @@
 //! which allows configuration of a world generator pipeline.
 
 mod biomes;
+mod carvers;
 mod composition;
 mod density_map;
+mod end;
+mod features;
 mod finishers;
+mod nether;
 pub mod noise;
+pub mod random;
+mod settings;
+mod structures;
 mod superflat;
 mod util;
 pub mod voronoi;
 
-pub use biomes::{DistortedVoronoiBiomeGenerator, TwoLevelBiomeGenerator};
+pub use biomes::{
+    BiomeSource, BiomeSourceGenerator, DistortedVoronoiBiomeGenerator, TwoLevelBiomeGenerator,
+    VanillaBiomeSource, DEFAULT_BIOME_SIZE, LARGE_BIOME_SIZE,
+};
 use bitvec::order::Local;
 use bitvec::slice::BitSlice;
 use bitvec::vec::BitVec;
+pub use carvers::{CaveCarver, RavineCarver};
 pub use composition::BasicCompositionGenerator;
 pub use density_map::{DensityMapGeneratorImpl, HeightMapGenerator};
+pub use end::{EndGenerator, Pillar};
 use feather_core::biomes::Biome;
-use feather_core::blocks::BlockId;
 use feather_core::chunk::Chunk;
-use feather_core::util::ChunkPosition;
-use finishers::{ClumpedFoliageFinisher, SingleFoliageFinisher, SnowFinisher};
+use feather_core::util::{BlockPosition, ChunkPosition, Dimension};
+pub use features::{BiomeFeatures, TreeKind};
+use finishers::{OreFinisher, SingleFoliageFinisher, SnowFinisher, VegetationFinisher};
+pub use nether::{NetherGenerator, LAVA_LEVEL, NETHER_HEIGHT};
 pub use noise::NoiseLerper;
 use num_traits::ToPrimitive;
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
+pub use settings::{HeightDistribution, NoiseSettings, OreConfig, OreSettings, WorldGenSettings};
 use smallvec::SmallVec;
 use std::fmt;
+use std::sync::Arc;
+pub use structures::{
+    BoundingBox, StructureGenerator, StructureKind, StructurePiece, StructurePlacement,
+    StructureStart, StructureTemplate, TemplateBlock, TemplateLoader, TemplatePiece, TerrainInfo,
+};
 pub use superflat::SuperflatWorldGenerator;
 
 /// Sea-level height.
@@ -39,10 +58,32 @@ pub const SEA_LEVEL: usize = 64;
 pub const SKY_LIMIT: usize = 255;
 /// Depth of an ocean.
 const OCEAN_DEPTH: usize = 30;
+/// Maximum distance, in placement regions, searched when locating structures.
+const MAX_LOCATE_REGIONS: i32 = 100;
 
 pub trait WorldGenerator: Send + Sync {
     /// Generates the chunk at the given position.
     fn generate_chunk(&self, position: ChunkPosition) -> Chunk;
+
+    /// Finds the nearest structure of the given kind to `near`.
+    /// Returns `None` if no such structure was found, or
+    /// if this generator doesn't generate structures.
+    fn locate_structure(
+        &self,
+        _kind: StructureKind,
+        _near: BlockPosition,
+    ) -> Option<BlockPosition> {
+        None
+    }
+}
+
+/// Returns the default generator for the given dimension.
+pub fn default_generator_for(dimension: Dimension, seed: u64) -> Arc<dyn WorldGenerator> {
+    match dimension {
+        Dimension::Overwold => Arc::new(ComposableGenerator::default_with_seed(seed)),
+        Dimension::Nether => Arc::new(NetherGenerator::new(seed)),
+        Dimension::End => Arc::new(EndGenerator::new(seed)),
+    }
 }
 
 pub struct EmptyWorldGenerator {}
@@ -63,6 +104,8 @@ impl WorldGenerator for EmptyWorldGenerator {
 /// * Biomes - generates a biome grid.
 /// * Terrain density - generates the terrain density values using Perlin noise.
 /// * Terrain composition - sets the correct block types based on the biome and terrain density.
+/// * Carvers - cut caves and ravines out of the composed terrain.
+/// * Structures - places the parts of villages, temples, etc. within the chunk.
 /// * Finishing generators - generates final elements, such as grass, snow, and trees.
 ///
 /// This generator is based on [this document](http://cuberite.xoft.cz/docs/Generator.html).
@@ -73,19 +116,25 @@ pub struct ComposableGenerator {
     density_map: Box<dyn DensityMapGenerator>,
     /// The composition generator.
     composition: Box<dyn CompositionGenerator>,
+    /// A vector of carvers used
+    /// by this composable generator.
+    carvers: SmallVec<[Box<dyn CarvingGenerator>; 2]>,
     /// A vector of finishing generators used
     /// by this composable generator.
     finishers: SmallVec<[Box<dyn FinishingGenerator>; 8]>,
+    /// The structure generator, if structures are enabled.
+    structures: Option<StructureGenerator>,
     /// The world seed.
     seed: u64,
 }
 
 impl ComposableGenerator {
     /// Creates a new `ComposableGenerator` with the given stages.
-    pub fn new<B, D, C, F>(
+    pub fn new<B, D, C, R, F>(
         biome: B,
         density_map: D,
         composition: C,
+        carvers: R,
         finishers: F,
         seed: u64,
     ) -> Self
@@ -93,42 +142,60 @@ impl ComposableGenerator {
         B: BiomeGenerator + 'static,
         D: DensityMapGenerator + 'static,
         C: CompositionGenerator + 'static,
+        R: IntoIterator<Item = Box<dyn CarvingGenerator>>,
         F: IntoIterator<Item = Box<dyn FinishingGenerator>>,
     {
         Self {
             biome: Box::new(biome),
             density_map: Box::new(density_map),
             composition: Box::new(composition),
+            carvers: carvers.into_iter().collect(),
             finishers: finishers.into_iter().collect(),
+            structures: None,
             seed,
         }
     }
 
+    /// Enables generation of structures using the given generator.
+    pub fn with_structures(mut self, structures: StructureGenerator) -> Self {
+        self.structures = Some(structures);
+        self
+    }
+
     /// A default composable generator, used
     /// for worlds with "default" world type.
     pub fn default_with_seed(seed: u64) -> Self {
+        Self::default_with_settings(seed, WorldGenSettings::default())
+    }
+
+    /// The default composable generator,
+    /// customized with the given settings.
+    pub fn default_with_settings(seed: u64, settings: WorldGenSettings) -> Self {
+        let carvers: Vec<Box<dyn CarvingGenerator>> = vec![
+            Box::new(CaveCarver::default()),
+            Box::new(RavineCarver::default()),
+        ];
         let finishers: Vec<Box<dyn FinishingGenerator>> = vec![
+            Box::new(OreFinisher::new(settings.ores)),
             Box::new(SnowFinisher::default()),
             Box::new(SingleFoliageFinisher::default()),
-            Box::new(ClumpedFoliageFinisher::default()),
+            Box::new(VegetationFinisher::default()),
         ];
+        let structures =
+            StructureGenerator::new(seed as i64, settings.structures, TemplateLoader::vanilla());
         Self::new(
-            TwoLevelBiomeGenerator::default(),
-            DensityMapGeneratorImpl::default(),
+            TwoLevelBiomeGenerator::with_scale(settings.biome_scale),
+            DensityMapGeneratorImpl::new(settings.noise),
             BasicCompositionGenerator::default(),
+            carvers,
             finishers,
             seed,
         )
+        .with_structures(structures)
     }
-}
-
-impl WorldGenerator for ComposableGenerator {
-    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
-        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
-
-        // Generate biomes for 3x3 grid of chunks around current chunk.
-        let biome_seed = seed_shuffler.gen();
 
+    /// Generates the biomes for the 3x3 grid of chunks around the given chunk.
+    fn nearby_biomes(&self, position: ChunkPosition, biome_seed: u64) -> NearbyBiomes {
         let mut biomes = vec![];
 
         for z in -1..=1 {
@@ -137,7 +204,49 @@ impl WorldGenerator for ComposableGenerator {
                 biomes.push(self.biome.generate_for_chunk(pos, biome_seed));
             }
         }
-        let biomes = NearbyBiomes::from_vec(biomes);
+        NearbyBiomes::from_vec(biomes)
+    }
+}
+
+/// Structures are laid out before the chunks they
+/// intersect are generated, so terrain information is
+/// computed from the biome and density stages directly.
+impl TerrainInfo for ComposableGenerator {
+    fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let biome_seed = XorShiftRng::seed_from_u64(self.seed).gen();
+        let chunk = ChunkPosition::new(x.div_euclid(16), z.div_euclid(16));
+        self.biome
+            .generate_for_chunk(chunk, biome_seed)
+            .biome_at(x.rem_euclid(16) as usize, z.rem_euclid(16) as usize)
+    }
+
+    fn height_at(&self, x: i32, z: i32) -> i32 {
+        // Seeds are drawn in the same order as in `generate_chunk`.
+        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
+        let biome_seed = seed_shuffler.gen();
+        let density_seed = seed_shuffler.gen();
+
+        let chunk = ChunkPosition::new(x.div_euclid(16), z.div_euclid(16));
+        let biomes = self.nearby_biomes(chunk, biome_seed);
+        let density = self
+            .density_map
+            .generate_for_chunk(chunk, &biomes, density_seed);
+
+        let (local_x, local_z) = (x.rem_euclid(16) as usize, z.rem_euclid(16) as usize);
+        (0..256)
+            .rev()
+            .find(|&y| density[block_index(local_x, y, local_z)])
+            .map_or(0, |y| y as i32)
+    }
+}
+
+impl WorldGenerator for ComposableGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut seed_shuffler = XorShiftRng::seed_from_u64(self.seed);
+
+        // Generate biomes for 3x3 grid of chunks around current chunk.
+        let biome_seed = seed_shuffler.gen();
+        let biomes = self.nearby_biomes(position, biome_seed);
 
         let density_map =
             self.density_map
@@ -159,22 +268,27 @@ impl WorldGenerator for ComposableGenerator {
             seed_shuffler.gen(),
         );
 
+        // Carvers use the world seed directly so that
+        // tunnels line up across chunk boundaries.
+        for carver in &self.carvers {
+            carver.carve_chunk(&mut chunk, &biomes.biomes[4], self.seed);
+        }
+
+        if let Some(structures) = &self.structures {
+            structures.place_structures(&mut chunk, self);
+        }
+
+        chunk.recalculate_heightmap();
+
         // Calculate top blocks in chunk.
-        // TODO: perhaps this should be moved to `Chunk`?
         let mut top_blocks = TopBlocks::new();
         for x in 0..16 {
             for z in 0..16 {
-                for y in (0..256).rev() {
-                    if chunk.block_at(x, y, z) != BlockId::air() {
-                        top_blocks.set_top_block_at(x, z, y);
-                        break;
-                    }
-                }
+                let height = chunk.heightmap(x, z).world_surface();
+                top_blocks.set_top_block_at(x, z, height.saturating_sub(1) as usize);
             }
         }
 
-        chunk.recalculate_heightmap();
-
         // Finishers.
         for finisher in &self.finishers {
             finisher.generate_for_chunk(
@@ -185,22 +299,13 @@ impl WorldGenerator for ComposableGenerator {
             );
         }
 
-        // TODO: correct lighting.
-        // Fill chunk with 15 light levels.
         chunk
-            .sections_mut()
-            .into_iter()
-            .filter(|section| section.is_some())
-            .map(|section| section.unwrap())
-            .for_each(|section| {
-                let sky_light = section.sky_light_mut();
-
-                (0..4096).for_each(|index| {
-                    sky_light.set(index, 15);
-                })
-            });
+    }
 
-        chunk
+    fn locate_structure(&self, kind: StructureKind, near: BlockPosition) -> Option<BlockPosition> {
+        self.structures
+            .as_ref()?
+            .locate(kind, near, MAX_LOCATE_REGIONS, self)
     }
 }
 
@@ -241,6 +346,15 @@ pub trait CompositionGenerator: Send + Sync {
     );
 }
 
+/// A generator, run after composition, which
+/// carves out parts of the terrain, such as caves.
+pub trait CarvingGenerator: Send + Sync {
+    /// Carves the given chunk. Implementations must be deterministic
+    /// with respect to the world seed, since carved features
+    /// span multiple chunks.
+    fn carve_chunk(&self, chunk: &mut Chunk, biomes: &ChunkBiomes, seed: u64);
+}
+
 /// A generator, run after composition,
 /// which can add finishing elements to chunks,
 /// such as grass, trees, and snow.
@@ -0,0 +1,250 @@
+//! Generator for the Nether: netherrack caverns above
+//! a sea of lava, enclosed by bedrock.
+
+use crate::util::shuffle_seed_for_chunk;
+use crate::{
+    block_index, noise, NoiseLerper, StructureGenerator, StructureKind, TerrainInfo, WorldGenerator,
+};
+use bitvec::order::Local;
+use bitvec::vec::BitVec;
+use feather_core::biomes::Biome;
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use feather_core::util::{BlockPosition, ChunkPosition};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use simdnoise::NoiseBuilder;
+use std::f32::consts::PI;
+
+/// Height of the Nether's bedrock ceiling.
+pub const NETHER_HEIGHT: usize = 128;
+/// Y coordinate of the surface of the lava sea.
+pub const LAVA_LEVEL: usize = 31;
+
+const DENSITY_WIDTH: usize = 5;
+const DENSITY_HEIGHT: usize = NETHER_HEIGHT / 8 + 1;
+
+/// Generates the Nether.
+///
+/// Terrain is shaped by 3D noise which is biased towards
+/// solid blocks near the floor and ceiling, so that the
+/// open space is concentrated around the middle of the dimension.
+pub struct NetherGenerator {
+    seed: u64,
+    /// The structure generator, if structures are enabled.
+    structures: Option<StructureGenerator>,
+}
+
+impl NetherGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            structures: None,
+        }
+    }
+
+    /// Enables generation of structures using the given generator.
+    pub fn with_structures(mut self, structures: StructureGenerator) -> Self {
+        self.structures = Some(structures);
+        self
+    }
+
+    /// Computes which blocks of the chunk are solid, indexable
+    /// by `block_index`. Blocks above `NETHER_HEIGHT` are always air.
+    fn generate_density(&self, chunk: ChunkPosition) -> BitVec<Local, u8> {
+        let x_offset = (chunk.x * (DENSITY_WIDTH as i32 - 1)) as f32;
+        let z_offset = (chunk.z * (DENSITY_WIDTH as i32 - 1)) as f32;
+
+        let noise = NoiseBuilder::fbm_3d_offset(
+            x_offset,
+            DENSITY_WIDTH,
+            0.0,
+            DENSITY_HEIGHT,
+            z_offset,
+            DENSITY_WIDTH,
+        )
+        .with_seed(self.seed as i32)
+        .with_octaves(3)
+        .with_freq(0.15)
+        .generate()
+        .0;
+
+        let mut densities = vec![0.0; DENSITY_WIDTH * DENSITY_HEIGHT * DENSITY_WIDTH];
+        for subx in 0..DENSITY_WIDTH {
+            for subz in 0..DENSITY_WIDTH {
+                for suby in 0..DENSITY_HEIGHT {
+                    let index = DENSITY_WIDTH * suby + subx + DENSITY_WIDTH * DENSITY_HEIGHT * subz;
+
+                    // +1 at the floor and ceiling, -1 halfway between.
+                    let gradient = (suby as f32 / (DENSITY_HEIGHT - 1) as f32 * 2.0 * PI).cos();
+                    let mut density = noise[index] * 50.0 - gradient * 25.0;
+
+                    // Close off the floor and ceiling.
+                    if suby == 0 || suby >= DENSITY_HEIGHT - 2 {
+                        density -= 100.0;
+                    }
+
+                    densities[index] = density;
+                }
+            }
+        }
+
+        let noise = NoiseLerper::new(&densities)
+            .with_size(16, NETHER_HEIGHT as u32)
+            .with_offset(chunk.x, chunk.z)
+            .generate();
+
+        let mut density = BitVec::from_vec(vec![0u8; 16 * 256 * 16 / 8]);
+        for x in 0..16 {
+            for y in 0..NETHER_HEIGHT {
+                for z in 0..16 {
+                    // As in the overworld, negative values are solid.
+                    density.set(block_index(x, y, z), noise[noise::index(x, y, z)] < 0.0);
+                }
+            }
+        }
+        density
+    }
+
+    /// Replaces netherrack around the lava sea with patches
+    /// of soul sand and gravel.
+    fn place_shores(&self, chunk: &mut Chunk) {
+        let position = chunk.position();
+        let (soul_sand, gravel) = (
+            shore_noise(position, (self.seed as i32).wrapping_add(1)),
+            shore_noise(position, (self.seed as i32).wrapping_add(2)),
+        );
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let block = if soul_sand[z * 16 + x] > 0.1 {
+                    BlockId::soul_sand()
+                } else if gravel[z * 16 + x] > 0.15 {
+                    BlockId::gravel()
+                } else {
+                    continue;
+                };
+
+                for y in LAVA_LEVEL - 3..=LAVA_LEVEL + 3 {
+                    if chunk.block_at(x, y, z) == BlockId::netherrack()
+                        && !chunk.block_at(x, y + 1, z).is_opaque()
+                    {
+                        chunk.set_block_at(x, y, z, block);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Places the bedrock floor and ceiling, which are
+    /// a few blocks thick with a jagged inner edge.
+    fn place_bedrock(&self, chunk: &mut Chunk) {
+        let mut rng =
+            XorShiftRng::seed_from_u64(shuffle_seed_for_chunk(self.seed, chunk.position()));
+
+        for x in 0..16 {
+            for z in 0..16 {
+                for depth in 0..5 {
+                    if depth <= rng.gen_range(0, 5) {
+                        chunk.set_block_at(x, depth, z, BlockId::bedrock());
+                    }
+                    if depth <= rng.gen_range(0, 5) {
+                        chunk.set_block_at(x, NETHER_HEIGHT - 1 - depth, z, BlockId::bedrock());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn shore_noise(chunk: ChunkPosition, seed: i32) -> Vec<f32> {
+    NoiseBuilder::fbm_2d_offset((chunk.x * 16) as f32, 16, (chunk.z * 16) as f32, 16)
+        .with_seed(seed)
+        .with_octaves(2)
+        .with_freq(0.05)
+        .generate()
+        .0
+}
+
+impl WorldGenerator for NetherGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new_with_default_biome(position, Biome::Nether);
+
+        let density = self.generate_density(position);
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..NETHER_HEIGHT {
+                    let block = if density[block_index(x, y, z)] {
+                        BlockId::netherrack()
+                    } else if y <= LAVA_LEVEL {
+                        BlockId::lava()
+                    } else {
+                        continue;
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        self.place_shores(&mut chunk);
+
+        if let Some(structures) = &self.structures {
+            structures.place_structures(&mut chunk, self);
+        }
+
+        self.place_bedrock(&mut chunk);
+
+        chunk.recalculate_heightmap();
+        chunk
+    }
+
+    fn locate_structure(&self, kind: StructureKind, near: BlockPosition) -> Option<BlockPosition> {
+        self.structures
+            .as_ref()?
+            .locate(kind, near, crate::MAX_LOCATE_REGIONS, self)
+    }
+}
+
+/// Nether structures, such as fortresses, are
+/// laid out relative to the lava sea.
+impl TerrainInfo for NetherGenerator {
+    fn biome_at(&self, _x: i32, _z: i32) -> Biome {
+        Biome::Nether
+    }
+
+    fn height_at(&self, _x: i32, _z: i32) -> i32 {
+        LAVA_LEVEL as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nether_is_enclosed() {
+        let chunk = NetherGenerator::new(42).generate_chunk(ChunkPosition::new(3, -2));
+
+        let mut netherrack = 0;
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(chunk.block_at(x, 0, z), BlockId::bedrock());
+                assert_eq!(chunk.block_at(x, NETHER_HEIGHT - 1, z), BlockId::bedrock());
+                assert_eq!(chunk.block_at(x, NETHER_HEIGHT, z), BlockId::air());
+                assert_eq!(chunk.biome_at(x, z), Biome::Nether);
+
+                for y in 5..NETHER_HEIGHT - 5 {
+                    let block = chunk.block_at(x, y, z);
+                    if block == BlockId::netherrack() {
+                        netherrack += 1;
+                    }
+                    // Open space above the lava sea is air.
+                    if y > LAVA_LEVEL {
+                        assert_ne!(block, BlockId::lava());
+                    }
+                }
+            }
+        }
+        assert!(netherrack > 0);
+    }
+}
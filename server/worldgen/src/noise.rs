@@ -1,3 +1,4 @@
+use crate::random::JavaRandom;
 use num_traits::ToPrimitive;
 
 /// Struct for applying linear interpolation to a 3D
@@ -209,6 +210,113 @@ pub fn index<N: ToPrimitive>(x: N, y: N, z: N) -> usize {
     ((y << 8) | z << 4) | x
 }
 
+/// Gradient vectors used by `ImprovedNoise`.
+const GRADIENTS: [[f64; 3]; 16] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0],
+    [0.0, -1.0, 1.0],
+    [-1.0, 1.0, 0.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Ken Perlin's improved noise, seeded from a `JavaRandom`
+/// the same way vanilla seeds it. Used by stages
+/// which need to match vanilla output.
+#[derive(Debug, Clone)]
+pub struct ImprovedNoise {
+    permutations: [u8; 256],
+    x_offset: f64,
+    y_offset: f64,
+    z_offset: f64,
+}
+
+impl ImprovedNoise {
+    pub fn new(rng: &mut JavaRandom) -> Self {
+        let x_offset = rng.next_double() * 256.0;
+        let y_offset = rng.next_double() * 256.0;
+        let z_offset = rng.next_double() * 256.0;
+
+        let mut permutations = [0u8; 256];
+        for (i, p) in permutations.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        for i in 0..256 {
+            let j = rng.next_int_bounded(256 - i as i32) as usize;
+            permutations.swap(i, i + j);
+        }
+
+        Self {
+            permutations,
+            x_offset,
+            y_offset,
+            z_offset,
+        }
+    }
+
+    /// Samples the noise at the given position.
+    pub fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        let x = x + self.x_offset;
+        let y = y + self.y_offset;
+        let z = z + self.z_offset;
+
+        let (fx, fy, fz) = (x.floor(), y.floor(), z.floor());
+        let (dx, dy, dz) = (x - fx, y - fy, z - fz);
+        let (ix, iy, iz) = (fx as i32, fy as i32, fz as i32);
+
+        let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+
+        let a = self.permutation(ix) + iy;
+        let aa = self.permutation(a) + iz;
+        let ab = self.permutation(a + 1) + iz;
+        let b = self.permutation(ix + 1) + iy;
+        let ba = self.permutation(b) + iz;
+        let bb = self.permutation(b + 1) + iz;
+
+        let d0 = grad(self.permutation(aa), dx, dy, dz);
+        let d1 = grad(self.permutation(ba), dx - 1.0, dy, dz);
+        let d2 = grad(self.permutation(ab), dx, dy - 1.0, dz);
+        let d3 = grad(self.permutation(bb), dx - 1.0, dy - 1.0, dz);
+        let d4 = grad(self.permutation(aa + 1), dx, dy, dz - 1.0);
+        let d5 = grad(self.permutation(ba + 1), dx - 1.0, dy, dz - 1.0);
+        let d6 = grad(self.permutation(ab + 1), dx, dy - 1.0, dz - 1.0);
+        let d7 = grad(self.permutation(bb + 1), dx - 1.0, dy - 1.0, dz - 1.0);
+
+        lerp(
+            w,
+            lerp(v, lerp(u, d0, d1), lerp(u, d2, d3)),
+            lerp(v, lerp(u, d4, d5), lerp(u, d6, d7)),
+        )
+    }
+
+    fn permutation(&self, i: i32) -> i32 {
+        i32::from(self.permutations[(i & 0xFF) as usize])
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: i32, x: f64, y: f64, z: f64) -> f64 {
+    let g = GRADIENTS[(hash & 15) as usize];
+    g[0] * x + g[1] * y + g[2] * z
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,293 @@
+//! Random number generators which produce the same
+//! sequences as the ones used by the vanilla server.
+//!
+//! Worldgen stages which aim to match vanilla output must use these
+//! instead of the `rand` crate's generators, seeded with the
+//! chunk seeding helpers below.
+
+use feather_core::util::ChunkPosition;
+
+const MULTIPLIER: i64 = 0x5_DEEC_E66D;
+const ADDEND: i64 = 0xB;
+const MASK: i64 = (1 << 48) - 1;
+
+/// A port of `java.util.Random`: a 48-bit linear congruential generator.
+#[derive(Debug, Clone)]
+pub struct JavaRandom {
+    seed: i64,
+}
+
+impl JavaRandom {
+    /// Creates a new generator with the given seed.
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed: (seed ^ MULTIPLIER) & MASK,
+        }
+    }
+
+    /// Resets the seed of this generator.
+    pub fn set_seed(&mut self, seed: i64) {
+        self.seed = (seed ^ MULTIPLIER) & MASK;
+    }
+
+    /// Returns the next `bits` random bits.
+    pub fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(MULTIPLIER).wrapping_add(ADDEND) & MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    /// Returns a random `i32`.
+    pub fn next_int(&mut self) -> i32 {
+        self.next(32)
+    }
+
+    /// Returns a random `i32` in `[0, bound)`.
+    ///
+    /// # Panics
+    /// Panics if `bound <= 0`.
+    pub fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "bound must be positive");
+
+        if (bound & -bound) == bound {
+            // Power of two
+            return ((i64::from(bound) * i64::from(self.next(31))) >> 31) as i32;
+        }
+
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+            if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+                return value;
+            }
+        }
+    }
+
+    /// Returns a random `i64`.
+    pub fn next_long(&mut self) -> i64 {
+        (i64::from(self.next(32)) << 32).wrapping_add(i64::from(self.next(32)))
+    }
+
+    /// Returns a random `bool`.
+    pub fn next_bool(&mut self) -> bool {
+        self.next(1) != 0
+    }
+
+    /// Returns a random `f32` in `[0, 1)`.
+    pub fn next_float(&mut self) -> f32 {
+        self.next(24) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a random `f64` in `[0, 1)`.
+    pub fn next_double(&mut self) -> f64 {
+        let high = i64::from(self.next(26)) << 27;
+        let low = i64::from(self.next(27));
+        (high + low) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Shuffles the slice in the same order
+    /// as `java.util.Collections.shuffle`.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.next_int_bounded(i as i32 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
+
+/// A port of vanilla's `XoroshiroRandomSource`, the
+/// xoroshiro128++ generator used by 1.18+ worldgen.
+#[derive(Debug, Clone)]
+pub struct Xoroshiro128PlusPlus {
+    low: u64,
+    high: u64,
+}
+
+impl Xoroshiro128PlusPlus {
+    /// Creates a new generator from a 64-bit seed,
+    /// expanding it to 128 bits as vanilla does.
+    pub fn new(seed: i64) -> Self {
+        let low = seed as u64 ^ 0x6A09_E667_F3BC_C909;
+        let high = low.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        Self::from_parts(mix_stafford13(low), mix_stafford13(high))
+    }
+
+    /// Creates a new generator from the two halves of its state.
+    /// An all-zero state is replaced, since it would only produce zeros.
+    pub fn from_parts(low: u64, high: u64) -> Self {
+        if low == 0 && high == 0 {
+            Self {
+                low: 0x9E37_79B9_7F4A_7C15,
+                high: 0x6A09_E667_F3BC_C909,
+            }
+        } else {
+            Self { low, high }
+        }
+    }
+
+    /// Returns a random `i64`.
+    pub fn next_long(&mut self) -> i64 {
+        let (low, mut high) = (self.low, self.high);
+        let result = low.wrapping_add(high).rotate_left(17).wrapping_add(low);
+
+        high ^= low;
+        self.low = low.rotate_left(49) ^ high ^ (high << 21);
+        self.high = high.rotate_left(28);
+        result as i64
+    }
+
+    /// Returns the `bits` most significant bits of the next `i64`.
+    fn next_bits(&mut self, bits: u32) -> u64 {
+        self.next_long() as u64 >> (64 - bits)
+    }
+
+    /// Returns a random `i32`.
+    pub fn next_int(&mut self) -> i32 {
+        self.next_long() as i32
+    }
+
+    /// Returns a random `i32` in `[0, bound)`, using
+    /// Lemire's method as vanilla does.
+    ///
+    /// # Panics
+    /// Panics if `bound <= 0`.
+    pub fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "bound must be positive");
+        let bound = bound as u64;
+
+        let mut product = u64::from(self.next_int() as u32) * bound;
+        if product & 0xFFFF_FFFF < bound {
+            let threshold = (bound.wrapping_neg() as u32 % bound as u32) as u64;
+            while product & 0xFFFF_FFFF < threshold {
+                product = u64::from(self.next_int() as u32) * bound;
+            }
+        }
+        (product >> 32) as i32
+    }
+
+    /// Returns a random `bool`.
+    pub fn next_bool(&mut self) -> bool {
+        self.next_long() & 1 != 0
+    }
+
+    /// Returns a random `f32` in `[0, 1)`.
+    pub fn next_float(&mut self) -> f32 {
+        self.next_bits(24) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a random `f64` in `[0, 1)`.
+    pub fn next_double(&mut self) -> f64 {
+        self.next_bits(53) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+/// The SplitMix64 finalizer ("Mix13" from David Stafford's variants).
+fn mix_stafford13(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+/// Returns the seed used to populate (decorate) the given chunk
+/// with ores and other features, as in vanilla's `populate`.
+pub fn population_seed(world_seed: i64, chunk: ChunkPosition) -> i64 {
+    let mut rng = JavaRandom::new(world_seed);
+    let x_multiplier = rng.next_long() / 2 * 2 + 1;
+    let z_multiplier = rng.next_long() / 2 * 2 + 1;
+
+    i64::from(chunk.x)
+        .wrapping_mul(x_multiplier)
+        .wrapping_add(i64::from(chunk.z).wrapping_mul(z_multiplier))
+        ^ world_seed
+}
+
+/// Returns the seed of the given chunk for large features,
+/// which are caves, ravines, and structure starts.
+pub fn large_feature_seed(world_seed: i64, chunk: ChunkPosition) -> i64 {
+    let mut rng = JavaRandom::new(world_seed);
+    let x_multiplier = rng.next_long();
+    let z_multiplier = rng.next_long();
+
+    i64::from(chunk.x).wrapping_mul(x_multiplier)
+        ^ i64::from(chunk.z).wrapping_mul(z_multiplier)
+        ^ world_seed
+}
+
+/// Returns the seed used to pick the position of a structure
+/// within a region of the structure grid. `salt` differs
+/// between structure kinds so that they don't line up.
+pub fn region_seed(world_seed: i64, region_x: i32, region_z: i32, salt: i64) -> i64 {
+    i64::from(region_x)
+        .wrapping_mul(341_873_128_712)
+        .wrapping_add(i64::from(region_z).wrapping_mul(132_897_987_541))
+        .wrapping_add(world_seed)
+        .wrapping_add(salt)
+}
+
+/// Returns whether slimes can spawn below Y=40 in the given chunk.
+pub fn is_slime_chunk(world_seed: i64, chunk: ChunkPosition) -> bool {
+    // Vanilla computes some of these products in 32-bit arithmetic,
+    // so they overflow before being widened.
+    let (x, z) = (chunk.x, chunk.z);
+    let seed = world_seed
+        .wrapping_add(i64::from(x.wrapping_mul(x).wrapping_mul(4_987_142)))
+        .wrapping_add(i64::from(x.wrapping_mul(5_947_611)))
+        .wrapping_add(i64::from(z.wrapping_mul(z)).wrapping_mul(4_392_871))
+        .wrapping_add(i64::from(z.wrapping_mul(389_711)))
+        ^ 987_234_911;
+
+    JavaRandom::new(seed).next_int_bounded(10) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_java() {
+        // Values obtained from `new java.util.Random(0)`.
+        let mut rng = JavaRandom::new(0);
+        assert_eq!(rng.next_int(), -1_155_484_576);
+        assert_eq!(rng.next_int_bounded(100), 48);
+
+        let mut rng = JavaRandom::new(0);
+        assert_eq!(rng.next_long(), -4_962_768_465_676_381_896);
+
+        let mut rng = JavaRandom::new(0);
+        assert!((rng.next_double() - 0.730_967_787_376_657).abs() < 1e-15);
+    }
+
+    #[test]
+    fn stafford_mix() {
+        // First output of SplitMix64 seeded with 0.
+        assert_eq!(mix_stafford13(0x9E37_79B9_7F4A_7C15), 0xE220_A839_7B1D_CDAF);
+    }
+
+    #[test]
+    fn xoroshiro_bounds() {
+        let mut a = Xoroshiro128PlusPlus::new(42);
+        let mut b = Xoroshiro128PlusPlus::new(42);
+        for bound in 1..1000 {
+            let value = a.next_int_bounded(bound);
+            assert!(value >= 0 && value < bound);
+            assert_eq!(value, b.next_int_bounded(bound));
+
+            let float = a.next_float();
+            assert!(float >= 0.0 && float < 1.0);
+            b.next_float();
+        }
+    }
+
+    #[test]
+    fn slime_chunks() {
+        let mut count = 0;
+        for x in -50..50 {
+            for z in -50..50 {
+                if is_slime_chunk(12345, ChunkPosition::new(x, z)) {
+                    count += 1;
+                }
+            }
+        }
+        // One in ten chunks, give or take.
+        assert!(count > 800 && count < 1200, "{}", count);
+    }
+}
@@ -0,0 +1,191 @@
+//! Settings which tweak the output of the `ComposableGenerator`.
+
+use crate::StructureKind;
+use feather_core::blocks::BlockId;
+
+/// Settings for the default world generator.
+#[derive(Debug, Clone)]
+pub struct WorldGenSettings {
+    /// Ore and underground feature placement.
+    pub ores: OreSettings,
+    /// The kinds of structures to generate.
+    pub structures: Vec<StructureKind>,
+    /// Parameters of the terrain noise.
+    pub noise: NoiseSettings,
+    /// Multiplier for the width of biomes.
+    pub biome_scale: u32,
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        Self {
+            ores: OreSettings::default(),
+            structures: StructureKind::values().to_vec(),
+            noise: NoiseSettings::default(),
+            biome_scale: 1,
+        }
+    }
+}
+
+impl WorldGenSettings {
+    /// Settings for the "amplified" world type, which
+    /// stretches land upwards into tall, steep mountains.
+    pub fn amplified() -> Self {
+        Self {
+            noise: NoiseSettings::amplified(),
+            ..Self::default()
+        }
+    }
+
+    /// Settings for the "large biomes" world type, whose
+    /// biomes are four times as wide, as in vanilla.
+    pub fn large_biomes() -> Self {
+        Self {
+            biome_scale: 4,
+            ..Self::default()
+        }
+    }
+}
+
+/// Parameters of the density noise which shapes the terrain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseSettings {
+    /// Frequency of the two main density noises.
+    pub density_frequency: f32,
+    /// Multiplier for the main density noises.
+    pub density_scale: f32,
+    /// Multiplier for the 2D height noise.
+    pub height_scale: f32,
+    /// Multiplier for how far above the shoreline the
+    /// midpoints of land biomes lie.
+    pub elevation_scale: f32,
+    /// Multiplier for the height variation of land biomes.
+    pub variation_scale: f32,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            density_frequency: 0.2,
+            density_scale: 50.0,
+            height_scale: 25.0,
+            elevation_scale: 1.0,
+            variation_scale: 1.0,
+        }
+    }
+}
+
+impl NoiseSettings {
+    /// Noise settings for amplified worlds.
+    pub fn amplified() -> Self {
+        Self {
+            elevation_scale: 2.0,
+            variation_scale: 4.0,
+            ..Self::default()
+        }
+    }
+}
+
+/// Describes how veins of a single ore are placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OreConfig {
+    /// The ore block.
+    pub block: BlockId,
+    /// Maximum number of blocks in a vein.
+    pub size: u32,
+    /// Number of veins attempted per chunk.
+    pub count: u32,
+    /// Distribution of vein heights.
+    pub height: HeightDistribution,
+}
+
+impl OreConfig {
+    /// Creates an ore config with uniformly distributed
+    /// heights in `min..max`.
+    pub fn uniform(block: BlockId, size: u32, count: u32, min: u32, max: u32) -> Self {
+        Self {
+            block,
+            size,
+            count,
+            height: HeightDistribution::Uniform { min, max },
+        }
+    }
+
+    /// Creates an ore config with heights distributed
+    /// around `center`, at most `spread` blocks away.
+    pub fn triangular(block: BlockId, size: u32, count: u32, center: u32, spread: u32) -> Self {
+        Self {
+            block,
+            size,
+            count,
+            height: HeightDistribution::Triangular { center, spread },
+        }
+    }
+}
+
+/// The distribution of heights at which veins are placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightDistribution {
+    /// Any height in `min..max` is equally likely.
+    Uniform { min: u32, max: u32 },
+    /// Heights near `center` are most likely.
+    Triangular { center: u32, spread: u32 },
+}
+
+/// Ore placement settings. Defaults match vanilla.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OreSettings {
+    pub dirt: OreConfig,
+    pub gravel: OreConfig,
+    pub diorite: OreConfig,
+    pub granite: OreConfig,
+    pub andesite: OreConfig,
+    pub coal: OreConfig,
+    pub iron: OreConfig,
+    pub gold: OreConfig,
+    pub redstone: OreConfig,
+    pub diamond: OreConfig,
+    pub lapis: OreConfig,
+    /// Whether single emerald ores are placed
+    /// in mountain biomes.
+    pub emeralds: bool,
+}
+
+impl OreSettings {
+    /// Returns the ore configs in the order
+    /// in which they are placed.
+    pub fn veins(&self) -> [&OreConfig; 11] {
+        [
+            &self.dirt,
+            &self.gravel,
+            &self.diorite,
+            &self.granite,
+            &self.andesite,
+            &self.coal,
+            &self.iron,
+            &self.gold,
+            &self.redstone,
+            &self.diamond,
+            &self.lapis,
+        ]
+    }
+}
+
+impl Default for OreSettings {
+    fn default() -> Self {
+        Self {
+            dirt: OreConfig::uniform(BlockId::dirt(), 33, 10, 0, 256),
+            gravel: OreConfig::uniform(BlockId::gravel(), 33, 8, 0, 256),
+            diorite: OreConfig::uniform(BlockId::diorite(), 33, 10, 0, 80),
+            granite: OreConfig::uniform(BlockId::granite(), 33, 10, 0, 80),
+            andesite: OreConfig::uniform(BlockId::andesite(), 33, 10, 0, 80),
+            coal: OreConfig::uniform(BlockId::coal_ore(), 17, 20, 0, 128),
+            iron: OreConfig::uniform(BlockId::iron_ore(), 9, 20, 0, 64),
+            gold: OreConfig::uniform(BlockId::gold_ore(), 9, 2, 0, 32),
+            redstone: OreConfig::uniform(BlockId::redstone_ore(), 8, 8, 0, 16),
+            diamond: OreConfig::uniform(BlockId::diamond_ore(), 8, 1, 0, 16),
+            lapis: OreConfig::triangular(BlockId::lapis_ore(), 7, 1, 16, 16),
+            emeralds: true,
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Desert pyramids: a procedurally built sandstone pyramid
+//! with a trapped treasure chamber beneath it.
+
+use super::{set_block, BoundingBox, StructurePiece, TerrainInfo};
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use feather_core::util::{BlockPosition, ChunkPosition};
+
+/// Width of the pyramid along both horizontal axes.
+const WIDTH: i32 = 21;
+/// Height of the pyramid above its floor.
+const HEIGHT: i32 = 10;
+/// Depth of the treasure chamber's TNT below the floor.
+const DEPTH: i32 = 16;
+
+pub fn create_pieces(
+    chunk: ChunkPosition,
+    terrain: &dyn TerrainInfo,
+) -> Vec<Box<dyn StructurePiece>> {
+    let x = chunk.x * 16;
+    let z = chunk.z * 16;
+    let y = terrain.height_at(x + WIDTH / 2, z + WIDTH / 2);
+
+    vec![Box::new(DesertPyramidPiece {
+        origin: BlockPosition::new(x, y, z),
+    })]
+}
+
+/// The pyramid. `origin` is the minimum corner of its floor.
+pub struct DesertPyramidPiece {
+    origin: BlockPosition,
+}
+
+impl DesertPyramidPiece {
+    fn set(&self, chunk: &mut Chunk, x: i32, y: i32, z: i32, block: BlockId) {
+        set_block(
+            chunk,
+            BlockPosition::new(self.origin.x + x, self.origin.y + y, self.origin.z + z),
+            block,
+        );
+    }
+
+    /// Fills the box between the given relative corners, inclusive.
+    fn fill(
+        &self,
+        chunk: &mut Chunk,
+        (x1, y1, z1): (i32, i32, i32),
+        (x2, y2, z2): (i32, i32, i32),
+        block: BlockId,
+    ) {
+        for x in x1..=x2 {
+            for y in y1..=y2 {
+                for z in z1..=z2 {
+                    self.set(chunk, x, y, z, block);
+                }
+            }
+        }
+    }
+
+    /// Builds the stepped body of the pyramid: a hollow shell
+    /// shrinking by one block on each side per layer.
+    fn place_body(&self, chunk: &mut Chunk) {
+        // Foundation, so that the pyramid doesn't overhang sloped terrain.
+        self.fill(
+            chunk,
+            (0, -4, 0),
+            (WIDTH - 1, -1, WIDTH - 1),
+            BlockId::sandstone(),
+        );
+        self.fill(
+            chunk,
+            (0, 0, 0),
+            (WIDTH - 1, 0, WIDTH - 1),
+            BlockId::sandstone(),
+        );
+
+        for layer in 1..=HEIGHT {
+            let min = layer;
+            let max = WIDTH - 1 - layer;
+            for x in min..=max {
+                for z in min..=max {
+                    let edge = x == min || x == max || z == min || z == max;
+                    let block = if edge {
+                        BlockId::sandstone()
+                    } else {
+                        BlockId::air()
+                    };
+                    self.set(chunk, x, layer, z, block);
+                }
+            }
+        }
+
+        // Clear the interior below the first step.
+        self.fill(chunk, (1, 1, 1), (WIDTH - 2, 4, WIDTH - 2), BlockId::air());
+    }
+
+    /// Builds the four corner towers and the entrance.
+    fn place_towers(&self, chunk: &mut Chunk) {
+        for &(x, z) in &[
+            (0, 0),
+            (WIDTH - 5, 0),
+            (0, WIDTH - 5),
+            (WIDTH - 5, WIDTH - 5),
+        ] {
+            self.fill(chunk, (x, 0, z), (x + 4, 9, z + 4), BlockId::sandstone());
+            self.fill(chunk, (x + 1, 1, z + 1), (x + 3, 9, z + 3), BlockId::air());
+            self.fill(
+                chunk,
+                (x, 6, z),
+                (x + 4, 6, z + 4),
+                BlockId::orange_terracotta(),
+            );
+            self.fill(chunk, (x + 1, 6, z + 1), (x + 3, 6, z + 3), BlockId::air());
+            self.set(chunk, x + 2, 10, z + 2, BlockId::chiseled_sandstone());
+        }
+
+        // Entrance on the north side.
+        let center = WIDTH / 2;
+        self.fill(
+            chunk,
+            (center - 1, 0, -1),
+            (center + 1, 0, -1),
+            BlockId::sandstone(),
+        );
+        self.fill(
+            chunk,
+            (center - 1, 1, 0),
+            (center + 1, 3, 4),
+            BlockId::air(),
+        );
+        self.fill(
+            chunk,
+            (center - 2, 4, 0),
+            (center + 2, 4, 0),
+            BlockId::cut_sandstone(),
+        );
+    }
+
+    /// Builds the decorated floor and the trapped chamber below it.
+    fn place_chamber(&self, chunk: &mut Chunk) {
+        let center = WIDTH / 2;
+
+        // Terracotta pattern around the center of the floor.
+        for &(dx, dz) in &[
+            (-2, 0),
+            (2, 0),
+            (0, -2),
+            (0, 2),
+            (-1, -1),
+            (1, 1),
+            (-1, 1),
+            (1, -1),
+        ] {
+            self.set(
+                chunk,
+                center + dx,
+                0,
+                center + dz,
+                BlockId::orange_terracotta(),
+            );
+        }
+        self.set(chunk, center, 0, center, BlockId::blue_terracotta());
+
+        // Shaft down to the chamber.
+        self.fill(
+            chunk,
+            (center, -10, center),
+            (center, -1, center),
+            BlockId::air(),
+        );
+
+        // The chamber itself, walled in sandstone.
+        let (min, max) = (center - 3, center + 3);
+        self.fill(
+            chunk,
+            (min, -DEPTH + 1, min),
+            (max, -10, max),
+            BlockId::sandstone(),
+        );
+        self.fill(
+            chunk,
+            (min + 1, -13, min + 1),
+            (max - 1, -11, max - 1),
+            BlockId::air(),
+        );
+        self.fill(
+            chunk,
+            (min + 1, -14, min + 1),
+            (max - 1, -14, max - 1),
+            BlockId::cut_sandstone(),
+        );
+
+        // One chest against each wall. Their contents are not generated.
+        for &(dx, dz) in &[(-2, 0), (2, 0), (0, -2), (0, 2)] {
+            self.set(chunk, center + dx, -13, center + dz, BlockId::chest());
+        }
+
+        // The trap: a pressure plate wired to TNT beneath the floor.
+        self.set(chunk, center, -13, center, BlockId::stone_pressure_plate());
+        self.fill(
+            chunk,
+            (center - 1, -DEPTH, center - 1),
+            (center + 1, -DEPTH, center + 1),
+            BlockId::tnt(),
+        );
+    }
+}
+
+impl StructurePiece for DesertPyramidPiece {
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            BlockPosition::new(self.origin.x, self.origin.y - DEPTH, self.origin.z - 1),
+            BlockPosition::new(
+                self.origin.x + WIDTH - 1,
+                self.origin.y + HEIGHT,
+                self.origin.z + WIDTH - 1,
+            ),
+        )
+    }
+
+    fn place(&self, chunk: &mut Chunk) {
+        self.place_body(chunk);
+        self.place_towers(chunk);
+        self.place_chamber(chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::biomes::Biome;
+
+    struct FlatTerrain;
+
+    impl TerrainInfo for FlatTerrain {
+        fn biome_at(&self, _x: i32, _z: i32) -> Biome {
+            Biome::Desert
+        }
+
+        fn height_at(&self, _x: i32, _z: i32) -> i32 {
+            64
+        }
+    }
+
+    #[test]
+    fn pyramid_spans_chunks() {
+        let pieces = create_pieces(ChunkPosition::new(0, 0), &FlatTerrain);
+        let bounds = pieces[0].bounds();
+        assert!(bounds.contains(BlockPosition::new(20, 64, 20)));
+
+        let mut chunk = Chunk::new(ChunkPosition::new(1, 1));
+        pieces[0].place(&mut chunk);
+        // Corner tower of the pyramid, in the neighbouring chunk.
+        assert_eq!(chunk.block_at(0, 64, 0), BlockId::sandstone());
+        assert_eq!(chunk.block_at(4, 70, 4), BlockId::orange_terracotta());
+
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        pieces[0].place(&mut chunk);
+        assert_eq!(chunk.block_at(10, 64, 10), BlockId::blue_terracotta());
+        assert_eq!(chunk.block_at(10, 51, 10), BlockId::stone_pressure_plate());
+        assert_eq!(chunk.block_at(10, 48, 10), BlockId::tnt());
+    }
+}
@@ -0,0 +1,485 @@
+//! Structures: large, multi-chunk features such as villages and temples.
+//!
+//! A structure is made of a `StructureStart`, which is determined
+//! deterministically from the world seed for a given chunk, and a list
+//! of `StructurePiece`s. Since a structure may span several chunks, each
+//! chunk being generated looks up the starts of all nearby structures and
+//! places only the parts of their pieces which lie within it.
+
+mod desert_pyramid;
+mod shipwreck;
+mod template;
+mod village;
+
+pub use template::{StructureTemplate, TemplateBlock, TemplateLoader, TemplatePiece};
+
+use crate::random::{large_feature_seed, region_seed, JavaRandom};
+use feather_core::biomes::Biome;
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use feather_core::util::{BlockPosition, ChunkPosition};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A kind of structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StructureKind {
+    Village,
+    DesertPyramid,
+    Shipwreck,
+}
+
+impl StructureKind {
+    /// Returns all structure kinds.
+    pub fn values() -> &'static [StructureKind] {
+        &[
+            StructureKind::Village,
+            StructureKind::DesertPyramid,
+            StructureKind::Shipwreck,
+        ]
+    }
+
+    /// Returns the name of this structure, as used by `/locate`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StructureKind::Village => "Village",
+            StructureKind::DesertPyramid => "Desert_Pyramid",
+            StructureKind::Shipwreck => "Shipwreck",
+        }
+    }
+
+    /// Returns the structure kind with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::values()
+            .iter()
+            .copied()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns how starts of this structure are spread across the world.
+    pub fn placement(self) -> StructurePlacement {
+        match self {
+            StructureKind::Village => StructurePlacement::new(32, 8, 10_387_312),
+            StructureKind::DesertPyramid => StructurePlacement::new(32, 8, 14_357_617),
+            StructureKind::Shipwreck => StructurePlacement::new(24, 4, 165_745_295),
+        }
+    }
+
+    /// Returns whether this structure may start in the given biome.
+    pub fn is_valid_biome(self, biome: Biome) -> bool {
+        match self {
+            StructureKind::Village => matches!(
+                biome,
+                Biome::Plains | Biome::Desert | Biome::Savanna | Biome::Taiga | Biome::SnowyTundra
+            ),
+            StructureKind::DesertPyramid => matches!(biome, Biome::Desert | Biome::DesertHills),
+            StructureKind::Shipwreck => biome.is_ocean() || biome == Biome::Beach,
+        }
+    }
+
+    /// Returns the maximum distance, in chunks, between the start
+    /// chunk of this structure and any chunk containing its pieces.
+    fn max_extent(self) -> i32 {
+        match self {
+            StructureKind::Village => 4,
+            StructureKind::DesertPyramid | StructureKind::Shipwreck => 2,
+        }
+    }
+
+    fn create_start(
+        self,
+        chunk: ChunkPosition,
+        rng: &mut JavaRandom,
+        terrain: &dyn TerrainInfo,
+        templates: &TemplateLoader,
+    ) -> Option<StructureStart> {
+        let pieces = match self {
+            StructureKind::Village => village::create_pieces(chunk, rng, terrain, templates),
+            StructureKind::DesertPyramid => desert_pyramid::create_pieces(chunk, terrain),
+            StructureKind::Shipwreck => shipwreck::create_pieces(chunk, rng, terrain, templates),
+        };
+
+        if pieces.is_empty() {
+            None
+        } else {
+            Some(StructureStart {
+                kind: self,
+                chunk,
+                pieces,
+            })
+        }
+    }
+}
+
+/// Determines the chunks in which structures of a kind may start.
+///
+/// The world is divided into square regions of `spacing` chunks,
+/// each of which contains at most one start. Starts are at least
+/// `separation` chunks from the edge of the next region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructurePlacement {
+    pub spacing: i32,
+    pub separation: i32,
+    pub salt: i64,
+}
+
+impl StructurePlacement {
+    pub fn new(spacing: i32, separation: i32, salt: i64) -> Self {
+        Self {
+            spacing,
+            separation,
+            salt,
+        }
+    }
+
+    /// Returns the region containing the given chunk.
+    pub fn region(&self, chunk: ChunkPosition) -> (i32, i32) {
+        (
+            chunk.x.div_euclid(self.spacing),
+            chunk.z.div_euclid(self.spacing),
+        )
+    }
+
+    /// Returns the chunk in which a structure may start
+    /// within the given region.
+    pub fn start_chunk(&self, world_seed: i64, (region_x, region_z): (i32, i32)) -> ChunkPosition {
+        let mut rng = JavaRandom::new(region_seed(world_seed, region_x, region_z, self.salt));
+
+        let range = self.spacing - self.separation;
+        let x = region_x * self.spacing + rng.next_int_bounded(range);
+        let z = region_z * self.spacing + rng.next_int_bounded(range);
+        ChunkPosition::new(x, z)
+    }
+}
+
+/// Information about the terrain used to lay out structures
+/// before the chunks they are placed in are generated.
+pub trait TerrainInfo {
+    /// Returns the biome of the column at the given block coordinates.
+    fn biome_at(&self, x: i32, z: i32) -> Biome;
+
+    /// Returns the Y coordinate of the highest solid block
+    /// in the column at the given block coordinates.
+    fn height_at(&self, x: i32, z: i32) -> i32;
+}
+
+/// An axis-aligned box of blocks. Both corners are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: BlockPosition,
+    pub max: BlockPosition,
+}
+
+impl BoundingBox {
+    pub fn new(min: BlockPosition, max: BlockPosition) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the box covering the given chunk.
+    pub fn for_chunk(chunk: ChunkPosition) -> Self {
+        Self::new(
+            BlockPosition::new(chunk.x * 16, 0, chunk.z * 16),
+            BlockPosition::new(chunk.x * 16 + 15, 255, chunk.z * 16 + 15),
+        )
+    }
+
+    pub fn contains(&self, pos: BlockPosition) -> bool {
+        pos.x >= self.min.x
+            && pos.y >= self.min.y
+            && pos.z >= self.min.z
+            && pos.x <= self.max.x
+            && pos.y <= self.max.y
+            && pos.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x
+            && self.min.y <= other.max.y
+            && self.min.z <= other.max.z
+            && self.max.x >= other.min.x
+            && self.max.y >= other.min.y
+            && self.max.z >= other.min.z
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(&self, other: &BoundingBox) -> Self {
+        Self::new(
+            BlockPosition::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            BlockPosition::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        )
+    }
+
+    /// Returns the center of the box.
+    pub fn center(&self) -> BlockPosition {
+        BlockPosition::new(
+            (self.min.x + self.max.x) / 2,
+            (self.min.y + self.max.y) / 2,
+            (self.min.z + self.max.z) / 2,
+        )
+    }
+}
+
+/// A piece of a structure.
+pub trait StructurePiece: Send + Sync {
+    /// Returns the box containing all blocks of this piece.
+    fn bounds(&self) -> BoundingBox;
+
+    /// Places the part of this piece which lies within the given chunk.
+    fn place(&self, chunk: &mut Chunk);
+}
+
+/// Sets the block at the given world position if it lies within the chunk.
+fn set_block(chunk: &mut Chunk, pos: BlockPosition, block: BlockId) {
+    let chunk_pos = chunk.position();
+    let x = pos.x - chunk_pos.x * 16;
+    let z = pos.z - chunk_pos.z * 16;
+    if (0..16).contains(&x) && (0..16).contains(&z) && (0..256).contains(&pos.y) {
+        chunk.set_block_at(x as usize, pos.y as usize, z as usize, block);
+    }
+}
+
+/// A structure generated for a start chunk.
+pub struct StructureStart {
+    kind: StructureKind,
+    chunk: ChunkPosition,
+    pieces: Vec<Box<dyn StructurePiece>>,
+}
+
+impl StructureStart {
+    pub fn kind(&self) -> StructureKind {
+        self.kind
+    }
+
+    /// Returns the chunk in which this structure starts.
+    pub fn chunk(&self) -> ChunkPosition {
+        self.chunk
+    }
+
+    pub fn pieces(&self) -> &[Box<dyn StructurePiece>] {
+        &self.pieces
+    }
+
+    /// Returns the box containing all pieces of this structure.
+    pub fn bounds(&self) -> BoundingBox {
+        self.pieces
+            .iter()
+            .map(|piece| piece.bounds())
+            .fold(self.pieces[0].bounds(), |a, b| a.union(&b))
+    }
+
+    /// Places the parts of this structure which lie within the given chunk.
+    pub fn place_in_chunk(&self, chunk: &mut Chunk) {
+        let area = BoundingBox::for_chunk(chunk.position());
+        for piece in &self.pieces {
+            if piece.bounds().intersects(&area) {
+                piece.place(chunk);
+            }
+        }
+    }
+}
+
+/// Computes the positions of structures and places them in chunks.
+///
+/// Structure starts are cached, since each is needed by
+/// every chunk the structure intersects.
+pub struct StructureGenerator {
+    seed: i64,
+    kinds: Vec<StructureKind>,
+    templates: TemplateLoader,
+    starts: Mutex<HashMap<(StructureKind, ChunkPosition), Option<Arc<StructureStart>>>>,
+}
+
+impl StructureGenerator {
+    pub fn new(
+        seed: i64,
+        kinds: impl IntoIterator<Item = StructureKind>,
+        templates: TemplateLoader,
+    ) -> Self {
+        Self {
+            seed,
+            kinds: kinds.into_iter().collect(),
+            templates,
+            starts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the structure kinds generated by this generator.
+    pub fn kinds(&self) -> &[StructureKind] {
+        &self.kinds
+    }
+
+    /// Returns the structure of the given kind starting in
+    /// the given chunk, if there is one.
+    pub fn start(
+        &self,
+        kind: StructureKind,
+        chunk: ChunkPosition,
+        terrain: &dyn TerrainInfo,
+    ) -> Option<Arc<StructureStart>> {
+        let placement = kind.placement();
+        if placement.start_chunk(self.seed, placement.region(chunk)) != chunk {
+            return None;
+        }
+
+        if let Some(start) = self.starts.lock().unwrap().get(&(kind, chunk)) {
+            return start.clone();
+        }
+
+        let start = if kind.is_valid_biome(terrain.biome_at(chunk.x * 16 + 8, chunk.z * 16 + 8)) {
+            let mut rng = JavaRandom::new(large_feature_seed(self.seed, chunk));
+            kind.create_start(chunk, &mut rng, terrain, &self.templates)
+                .map(Arc::new)
+        } else {
+            None
+        };
+
+        self.starts
+            .lock()
+            .unwrap()
+            .insert((kind, chunk), start.clone());
+        start
+    }
+
+    /// Places all structures intersecting the given chunk.
+    pub fn place_structures(&self, chunk: &mut Chunk, terrain: &dyn TerrainInfo) {
+        let position = chunk.position();
+        let area = BoundingBox::for_chunk(position);
+
+        for &kind in &self.kinds {
+            let placement = kind.placement();
+            let extent = kind.max_extent();
+
+            let (min_x, min_z) =
+                placement.region(ChunkPosition::new(position.x - extent, position.z - extent));
+            let (max_x, max_z) =
+                placement.region(ChunkPosition::new(position.x + extent, position.z + extent));
+
+            for region_x in min_x..=max_x {
+                for region_z in min_z..=max_z {
+                    let start_chunk = placement.start_chunk(self.seed, (region_x, region_z));
+                    if (start_chunk.x - position.x).abs() > extent
+                        || (start_chunk.z - position.z).abs() > extent
+                    {
+                        continue;
+                    }
+
+                    if let Some(start) = self.start(kind, start_chunk, terrain) {
+                        if start.bounds().intersects(&area) {
+                            start.place_in_chunk(chunk);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the nearest structure of the given kind, searching
+    /// up to `max_regions` placement regions away from `near`.
+    pub fn locate(
+        &self,
+        kind: StructureKind,
+        near: BlockPosition,
+        max_regions: i32,
+        terrain: &dyn TerrainInfo,
+    ) -> Option<BlockPosition> {
+        if !self.kinds.contains(&kind) {
+            return None;
+        }
+
+        let placement = kind.placement();
+        let (center_x, center_z) = placement.region(near.chunk());
+
+        for radius in 0..=max_regions {
+            let mut nearest: Option<(i64, BlockPosition)> = None;
+
+            for region_x in center_x - radius..=center_x + radius {
+                for region_z in center_z - radius..=center_z + radius {
+                    // Only visit the ring at this radius.
+                    if (region_x - center_x).abs() != radius
+                        && (region_z - center_z).abs() != radius
+                    {
+                        continue;
+                    }
+
+                    let chunk = placement.start_chunk(self.seed, (region_x, region_z));
+                    if self.start(kind, chunk, terrain).is_none() {
+                        continue;
+                    }
+
+                    let pos = BlockPosition::new(chunk.x * 16 + 8, near.y, chunk.z * 16 + 8);
+                    let dx = i64::from(pos.x - near.x);
+                    let dz = i64::from(pos.z - near.z);
+                    let distance = dx * dx + dz * dz;
+                    let closer = match nearest {
+                        Some((nearest, _)) => distance < nearest,
+                        None => true,
+                    };
+                    if closer {
+                        nearest = Some((distance, pos));
+                    }
+                }
+            }
+
+            if let Some((_, pos)) = nearest {
+                return Some(pos);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_chunks_stay_in_region() {
+        for kind in StructureKind::values() {
+            let placement = kind.placement();
+            for region_x in -3..3 {
+                for region_z in -3..3 {
+                    let chunk = placement.start_chunk(1234, (region_x, region_z));
+                    assert_eq!(placement.region(chunk), (region_x, region_z));
+
+                    let offset_x = chunk.x - region_x * placement.spacing;
+                    assert!(offset_x < placement.spacing - placement.separation);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kind_names() {
+        for kind in StructureKind::values() {
+            assert_eq!(StructureKind::from_name(kind.name()), Some(*kind));
+        }
+        assert_eq!(
+            StructureKind::from_name("desert_pyramid"),
+            Some(StructureKind::DesertPyramid)
+        );
+        assert_eq!(StructureKind::from_name("mansion"), None);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let a = BoundingBox::new(BlockPosition::new(0, 0, 0), BlockPosition::new(4, 4, 4));
+        let b = BoundingBox::new(BlockPosition::new(4, 4, 4), BlockPosition::new(8, 8, 8));
+        let c = BoundingBox::new(BlockPosition::new(5, 0, 0), BlockPosition::new(8, 8, 8));
+
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&c));
+        assert!(a.contains(BlockPosition::new(4, 0, 2)));
+        assert_eq!(a.union(&b).max, BlockPosition::new(8, 8, 8));
+        assert!(BoundingBox::for_chunk(ChunkPosition::new(-1, 0))
+            .contains(BlockPosition::new(-16, 10, 15)));
+    }
+}
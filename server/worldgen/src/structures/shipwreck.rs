@@ -0,0 +1,44 @@
+//! Shipwrecks: a single template resting on the ocean floor.
+
+use super::{StructurePiece, TemplateLoader, TemplatePiece, TerrainInfo};
+use crate::random::JavaRandom;
+use feather_core::util::{BlockPosition, ChunkPosition};
+
+/// Shipwreck templates, all of which may be chosen
+/// with equal probability.
+const TEMPLATES: &[&str] = &[
+    "shipwreck/rightsideup_full",
+    "shipwreck/rightsideup_full_degraded",
+    "shipwreck/rightsideup_fronthalf",
+    "shipwreck/rightsideup_fronthalf_degraded",
+    "shipwreck/rightsideup_backhalf",
+    "shipwreck/rightsideup_backhalf_degraded",
+    "shipwreck/sideways_full",
+    "shipwreck/sideways_full_degraded",
+    "shipwreck/upsidedown_full",
+    "shipwreck/upsidedown_full_degraded",
+    "shipwreck/with_mast",
+    "shipwreck/with_mast_degraded",
+];
+
+pub fn create_pieces(
+    chunk: ChunkPosition,
+    rng: &mut JavaRandom,
+    terrain: &dyn TerrainInfo,
+    templates: &TemplateLoader,
+) -> Vec<Box<dyn StructurePiece>> {
+    let name = TEMPLATES[rng.next_int_bounded(TEMPLATES.len() as i32) as usize];
+    let template = match templates.load(name) {
+        Some(template) => template,
+        None => return vec![],
+    };
+
+    // Sink the hull slightly into the floor so it doesn't float.
+    let x = chunk.x * 16;
+    let z = chunk.z * 16;
+    let size = template.size();
+    let floor = terrain.height_at(x + size.x / 2, z + size.z / 2);
+    let origin = BlockPosition::new(x, floor - 2, z);
+
+    vec![Box::new(TemplatePiece::new(template, origin))]
+}
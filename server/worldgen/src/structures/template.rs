@@ -0,0 +1,297 @@
+//! Loading of vanilla structure templates (`.nbt` files, as
+//! saved by structure blocks).
+
+use super::{set_block, BoundingBox, StructurePiece};
+use feather_core::blocks::BlockId;
+use feather_core::chunk::Chunk;
+use feather_core::util::BlockPosition;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Deserialize)]
+struct RawTemplate {
+    size: Vec<i32>,
+    #[serde(default)]
+    palette: Vec<RawBlockState>,
+    /// Some templates (e.g. shipwrecks) have several
+    /// palettes to choose from instead of one.
+    #[serde(default)]
+    palettes: Vec<Vec<RawBlockState>>,
+    blocks: Vec<RawBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawBlockState {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Properties", default)]
+    properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawBlock {
+    pos: Vec<i32>,
+    state: i32,
+}
+
+/// A block within a structure template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateBlock {
+    /// Position of the block relative to the template's origin.
+    pub offset: BlockPosition,
+    pub block: BlockId,
+}
+
+/// A structure template: a fixed arrangement of blocks.
+#[derive(Debug, Clone)]
+pub struct StructureTemplate {
+    size: BlockPosition,
+    blocks: Vec<TemplateBlock>,
+}
+
+impl StructureTemplate {
+    /// Loads a template from gzip-compressed NBT.
+    ///
+    /// Blocks unknown to Feather are left out of the template; when
+    /// a block's properties can't be parsed, its default state is used.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, nbt::Error> {
+        let raw: RawTemplate = nbt::from_gzip_reader(reader)?;
+
+        let palette = if raw.palette.is_empty() {
+            raw.palettes.into_iter().next().unwrap_or_default()
+        } else {
+            raw.palette
+        };
+        let palette: Vec<Option<BlockId>> = palette
+            .iter()
+            .map(|state| {
+                BlockId::from_identifier_and_properties(&state.name, &state.properties)
+                    .or_else(|| BlockId::from_identifier(&state.name))
+            })
+            .collect();
+
+        let blocks = raw
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                let state = palette.get(block.state as usize).copied().flatten()?;
+                match block.pos.as_slice() {
+                    [x, y, z] => Some(TemplateBlock {
+                        offset: BlockPosition::new(*x, *y, *z),
+                        block: state,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let size = match raw.size.as_slice() {
+            [x, y, z] => BlockPosition::new(*x, *y, *z),
+            _ => BlockPosition::default(),
+        };
+
+        Ok(Self { size, blocks })
+    }
+
+    /// Returns the size of this template along each axis.
+    pub fn size(&self) -> BlockPosition {
+        self.size
+    }
+
+    pub fn blocks(&self) -> &[TemplateBlock] {
+        &self.blocks
+    }
+}
+
+/// Loads structure templates by name, caching them once loaded.
+///
+/// Names are relative to the `structures` directory of a data pack,
+/// without the `.nbt` extension, e.g. `shipwreck/rightsideup_full`.
+pub struct TemplateLoader {
+    directories: Vec<PathBuf>,
+    cache: Mutex<HashMap<String, Option<Arc<StructureTemplate>>>>,
+}
+
+impl TemplateLoader {
+    /// Creates a loader searching the given directories in order.
+    pub fn new(directories: Vec<PathBuf>) -> Self {
+        Self {
+            directories,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a loader for the vanilla templates
+    /// extracted from the server JAR at build time.
+    pub fn vanilla() -> Self {
+        let path = feather_data::minecraft::PATH;
+        Self::new(vec![
+            PathBuf::from(format!("{}/minecraft-1.15/data/minecraft/structures", path)),
+            PathBuf::from(format!("{}/minecraft/data/minecraft/structures", path)),
+        ])
+    }
+
+    /// Loads the template with the given name. Returns `None`
+    /// if no such template exists or if it could not be parsed.
+    pub fn load(&self, name: &str) -> Option<Arc<StructureTemplate>> {
+        if let Some(template) = self.cache.lock().unwrap().get(name) {
+            return template.clone();
+        }
+
+        let template = self.load_uncached(name).map(Arc::new);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(name.to_owned(), template.clone());
+        template
+    }
+
+    fn load_uncached(&self, name: &str) -> Option<StructureTemplate> {
+        for directory in &self.directories {
+            let path = directory.join(format!("{}.nbt", name));
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+
+            match StructureTemplate::from_reader(file) {
+                Ok(template) => return Some(template),
+                Err(e) => {
+                    log::warn!("Failed to load structure template {}: {}", name, e);
+                    return None;
+                }
+            }
+        }
+
+        log::warn!("Structure template {} not found", name);
+        None
+    }
+}
+
+/// A structure piece consisting of a template.
+pub struct TemplatePiece {
+    template: Arc<StructureTemplate>,
+    origin: BlockPosition,
+}
+
+impl TemplatePiece {
+    /// Creates a piece placing the template with its
+    /// minimum corner at `origin`.
+    pub fn new(template: Arc<StructureTemplate>, origin: BlockPosition) -> Self {
+        Self { template, origin }
+    }
+}
+
+impl StructurePiece for TemplatePiece {
+    fn bounds(&self) -> BoundingBox {
+        let size = self.template.size();
+        BoundingBox::new(
+            self.origin,
+            BlockPosition::new(
+                self.origin.x + size.x - 1,
+                self.origin.y + size.y - 1,
+                self.origin.z + size.z - 1,
+            ),
+        )
+    }
+
+    fn place(&self, chunk: &mut Chunk) {
+        for block in self.template.blocks() {
+            set_block(chunk, self.origin + block.offset, block.block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::util::ChunkPosition;
+    use nbt::{Blob, Value};
+
+    fn compound(entries: Vec<(&str, Value)>) -> Value {
+        Value::Compound(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn test_template() -> Vec<u8> {
+        let mut blob = Blob::new();
+        blob.insert(
+            "size",
+            Value::List(vec![Value::Int(2), Value::Int(1), Value::Int(1)]),
+        )
+        .unwrap();
+        blob.insert(
+            "palette",
+            Value::List(vec![
+                compound(vec![("Name", Value::String("minecraft:stone".into()))]),
+                compound(vec![(
+                    "Name",
+                    Value::String("minecraft:not_a_block".into()),
+                )]),
+            ]),
+        )
+        .unwrap();
+        blob.insert(
+            "blocks",
+            Value::List(vec![
+                compound(vec![
+                    (
+                        "pos",
+                        Value::List(vec![Value::Int(0), Value::Int(0), Value::Int(0)]),
+                    ),
+                    ("state", Value::Int(0)),
+                ]),
+                compound(vec![
+                    (
+                        "pos",
+                        Value::List(vec![Value::Int(1), Value::Int(0), Value::Int(0)]),
+                    ),
+                    ("state", Value::Int(1)),
+                ]),
+            ]),
+        )
+        .unwrap();
+
+        let mut buf = vec![];
+        blob.to_gzip_writer(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn load_template() {
+        let template = StructureTemplate::from_reader(test_template().as_slice()).unwrap();
+
+        assert_eq!(template.size(), BlockPosition::new(2, 1, 1));
+        // The unknown block is skipped.
+        assert_eq!(
+            template.blocks(),
+            &[TemplateBlock {
+                offset: BlockPosition::new(0, 0, 0),
+                block: BlockId::stone(),
+            }]
+        );
+    }
+
+    #[test]
+    fn place_template_across_chunks() {
+        let template = StructureTemplate::from_reader(test_template().as_slice()).unwrap();
+        let piece = TemplatePiece::new(Arc::new(template), BlockPosition::new(-1, 64, 0));
+
+        let mut chunk = Chunk::new(ChunkPosition::new(-1, 0));
+        piece.place(&mut chunk);
+        assert_eq!(chunk.block_at(15, 64, 0), BlockId::stone());
+
+        // Nothing from the piece lies within this chunk.
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        piece.place(&mut chunk);
+        assert_eq!(chunk.block_at(0, 64, 0), BlockId::air());
+    }
+}
@@ -0,0 +1,255 @@
+//! Villages: a meeting point surrounded by houses,
+//! connected to it by paths.
+
+use super::{BoundingBox, StructurePiece, TemplateLoader, TemplatePiece, TerrainInfo};
+use crate::random::JavaRandom;
+use feather_core::biomes::Biome;
+use feather_core::blocks::{BlockId, SimplifiedBlockKind};
+use feather_core::chunk::Chunk;
+use feather_core::util::{BlockPosition, ChunkPosition};
+
+/// Offsets of the slots in which houses may be placed,
+/// relative to the village's meeting point.
+const HOUSE_SLOTS: [(i32, i32); 8] = [
+    (-24, -24),
+    (0, -24),
+    (24, -24),
+    (-24, 0),
+    (24, 0),
+    (-24, 24),
+    (0, 24),
+    (24, 24),
+];
+
+/// The style of a village, determined by its biome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VillageType {
+    Plains,
+    Desert,
+    Savanna,
+    Taiga,
+    Snowy,
+}
+
+impl VillageType {
+    fn for_biome(biome: Biome) -> Self {
+        match biome {
+            Biome::Desert => VillageType::Desert,
+            Biome::Savanna => VillageType::Savanna,
+            Biome::Taiga => VillageType::Taiga,
+            Biome::SnowyTundra => VillageType::Snowy,
+            _ => VillageType::Plains,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            VillageType::Plains => "plains",
+            VillageType::Desert => "desert",
+            VillageType::Savanna => "savanna",
+            VillageType::Taiga => "taiga",
+            VillageType::Snowy => "snowy",
+        }
+    }
+
+    /// Returns the number of meeting point templates of this type.
+    fn meeting_points(self) -> i32 {
+        match self {
+            VillageType::Plains | VillageType::Desert | VillageType::Snowy => 3,
+            VillageType::Savanna => 4,
+            VillageType::Taiga => 2,
+        }
+    }
+
+    /// Returns the number of small house templates of this type.
+    fn small_houses(self) -> i32 {
+        match self {
+            VillageType::Taiga => 5,
+            _ => 8,
+        }
+    }
+
+    fn meeting_point(self, rng: &mut JavaRandom) -> String {
+        format!(
+            "village/{0}/town_centers/{0}_meeting_point_{1}",
+            self.name(),
+            rng.next_int_bounded(self.meeting_points()) + 1
+        )
+    }
+
+    fn small_house(self, rng: &mut JavaRandom) -> String {
+        format!(
+            "village/{0}/houses/{0}_small_house_{1}",
+            self.name(),
+            rng.next_int_bounded(self.small_houses()) + 1
+        )
+    }
+}
+
+pub fn create_pieces(
+    chunk: ChunkPosition,
+    rng: &mut JavaRandom,
+    terrain: &dyn TerrainInfo,
+    templates: &TemplateLoader,
+) -> Vec<Box<dyn StructurePiece>> {
+    let x = chunk.x * 16;
+    let z = chunk.z * 16;
+    let village_type = VillageType::for_biome(terrain.biome_at(x + 8, z + 8));
+
+    let center = match templates.load(&village_type.meeting_point(rng)) {
+        Some(template) => template,
+        None => return vec![],
+    };
+    let size = center.size();
+    let center_x = x + size.x / 2;
+    let center_z = z + size.z / 2;
+    let origin = BlockPosition::new(x, terrain.height_at(center_x, center_z), z);
+
+    let mut roads: Vec<Box<dyn StructurePiece>> = vec![];
+    let mut houses: Vec<Box<dyn StructurePiece>> = vec![];
+
+    let mut slots = HOUSE_SLOTS;
+    rng.shuffle(&mut slots);
+    let count = 4 + rng.next_int_bounded(5) as usize;
+
+    for &(dx, dz) in &slots[..count] {
+        let template = match templates.load(&village_type.small_house(rng)) {
+            Some(template) => template,
+            None => continue,
+        };
+        let size = template.size();
+        let house_x = center_x + dx - size.x / 2;
+        let house_z = center_z + dz - size.z / 2;
+        let height = terrain.height_at(house_x + size.x / 2, house_z + size.z / 2);
+
+        // Houses which would end up underwater are left out.
+        if terrain.biome_at(house_x, house_z).is_ocean() {
+            continue;
+        }
+
+        roads.push(Box::new(RoadPiece::new(
+            (center_x, center_z),
+            (center_x + dx, center_z + dz),
+        )));
+        houses.push(Box::new(TemplatePiece::new(
+            template,
+            BlockPosition::new(house_x, height, house_z),
+        )));
+    }
+
+    // Roads are placed first so that buildings are built over them.
+    let mut pieces = roads;
+    pieces.push(Box::new(TemplatePiece::new(center, origin)));
+    pieces.extend(houses);
+    pieces
+}
+
+/// A path between two columns, laid along the X axis
+/// and then along the Z axis. The path follows the
+/// surface of the terrain in the chunk it is placed in.
+pub struct RoadPiece {
+    from: (i32, i32),
+    to: (i32, i32),
+}
+
+impl RoadPiece {
+    pub fn new(from: (i32, i32), to: (i32, i32)) -> Self {
+        Self { from, to }
+    }
+
+    fn columns(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (from_x, from_z) = self.from;
+        let (to_x, to_z) = self.to;
+
+        let along_x = range(from_x, to_x).map(move |x| (x, from_z));
+        let along_z = range(from_z, to_z).map(move |z| (to_x, z));
+        along_x.chain(along_z)
+    }
+}
+
+fn range(a: i32, b: i32) -> std::ops::RangeInclusive<i32> {
+    a.min(b)..=a.max(b)
+}
+
+impl StructurePiece for RoadPiece {
+    fn bounds(&self) -> BoundingBox {
+        let (from_x, from_z) = self.from;
+        let (to_x, to_z) = self.to;
+        BoundingBox::new(
+            BlockPosition::new(from_x.min(to_x) - 1, 0, from_z.min(to_z) - 1),
+            BlockPosition::new(from_x.max(to_x) + 1, 255, from_z.max(to_z) + 1),
+        )
+    }
+
+    fn place(&self, chunk: &mut Chunk) {
+        let chunk_pos = chunk.position();
+
+        for (x, z) in self.columns() {
+            // Paths are three blocks wide.
+            for (dx, dz) in &[(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let local_x = x + dx - chunk_pos.x * 16;
+                let local_z = z + dz - chunk_pos.z * 16;
+                if !(0..16).contains(&local_x) || !(0..16).contains(&local_z) {
+                    continue;
+                }
+
+                let (local_x, local_z) = (local_x as usize, local_z as usize);
+                if let Some(y) = surface(chunk, local_x, local_z) {
+                    chunk.set_block_at(local_x, y, local_z, BlockId::grass_path());
+                }
+            }
+        }
+    }
+}
+
+/// Returns the Y coordinate of the surface block of the
+/// given column, if it can be turned into a path.
+fn surface(chunk: &Chunk, x: usize, z: usize) -> Option<usize> {
+    let y = (0..256)
+        .rev()
+        .find(|&y| !chunk.block_at(x, y, z).is_air())?;
+    match chunk.block_at(x, y, z).simplified_kind() {
+        SimplifiedBlockKind::GrassBlock | SimplifiedBlockKind::Dirt => Some(y),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn road_follows_surface() {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for x in 0..16 {
+            for z in 0..16 {
+                let height = 60 + x / 4;
+                for y in 0..=height {
+                    chunk.set_block_at(x, y, z, BlockId::dirt());
+                }
+                chunk.set_block_at(x, height, z, BlockId::grass_block());
+            }
+        }
+
+        let road = RoadPiece::new((2, 4), (30, 8));
+        assert!(road
+            .bounds()
+            .intersects(&BoundingBox::for_chunk(chunk.position())));
+        road.place(&mut chunk);
+
+        assert_eq!(chunk.block_at(2, 60, 4), BlockId::grass_path());
+        assert_eq!(chunk.block_at(15, 63, 4), BlockId::grass_path());
+        assert_eq!(chunk.block_at(15, 63, 10), BlockId::grass_block());
+        assert_eq!(chunk.block_at(0, 60, 4), BlockId::grass_block());
+    }
+
+    #[test]
+    fn village_types() {
+        assert_eq!(VillageType::for_biome(Biome::Desert), VillageType::Desert);
+        assert_eq!(VillageType::for_biome(Biome::Plains), VillageType::Plains);
+
+        let mut rng = JavaRandom::new(0);
+        let name = VillageType::Taiga.small_house(&mut rng);
+        assert!(name.starts_with("village/taiga/houses/taiga_small_house_"));
+    }
+}
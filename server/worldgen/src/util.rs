@@ -1,6 +1,7 @@
 //! Utilities for world generation.
 
 use feather_core::util::ChunkPosition;
+use std::f64::consts::PI;
 
 /// Deterministically a seed for the given chunk. This allows
 /// different seeds to be used for different chunk.
@@ -16,3 +17,15 @@ pub fn shuffle_seed_for_column(seed: u64, chunk: ChunkPosition, col_x: usize, co
         .wrapping_mul(((col_x as u64) << 4) + 4)
         .wrapping_mul(col_z as u64 + 4)
 }
+
+/// Sine using the same lookup precision as vanilla.
+pub fn sin(value: f32) -> f32 {
+    let index = (value * 10430.378) as i32 & 0xFFFF;
+    (f64::from(index) * PI * 2.0 / 65536.0).sin() as f32
+}
+
+/// Cosine using the same lookup precision as vanilla.
+pub fn cos(value: f32) -> f32 {
+    let index = (value * 10430.378 + 16384.0) as i32 & 0xFFFF;
+    (f64::from(index) * PI * 2.0 / 65536.0).sin() as f32
+}